@@ -41,6 +41,10 @@ mod wire {
             max_seg_size: None,
             sack_permitted: false,
             sack_ranges: [None, None, None],
+            timestamp: None,
+            md5_signature: None,
+            authentication: None,
+            urgent_pointer: None,
             payload: &PAYLOAD_BYTES,
         };
         let mut bytes = vec![0xa5; repr.buffer_len()];
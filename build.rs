@@ -8,9 +8,14 @@ static CONFIGS: &[(&str, usize)] = &[
     // Generated by gen_config.py. DO NOT EDIT.
     ("IFACE_MAX_ADDR_COUNT", 2),
     ("IFACE_MAX_MULTICAST_GROUP_COUNT", 4),
+    ("IFACE_MAX_MULTICAST_SOURCE_COUNT", 4),
     ("IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT", 4),
     ("IFACE_NEIGHBOR_CACHE_COUNT", 4),
+    ("IFACE_DESTINATION_CACHE_COUNT", 4),
     ("IFACE_MAX_ROUTE_COUNT", 2),
+    ("IFACE_MAX_FILTER_RULE_COUNT", 4),
+    ("IFACE_LOCAL_BUFFER_SIZE", 0),
+    ("BRIDGE_MAC_TABLE_COUNT", 16),
     ("FRAGMENTATION_BUFFER_SIZE", 1500),
     ("ASSEMBLER_MAX_SEGMENT_COUNT", 4),
     ("REASSEMBLY_BUFFER_SIZE", 1500),
@@ -17,6 +17,11 @@ FEATURES_TEST=(
     "std,proto-ipv4"
     "std,medium-ethernet,phy-raw_socket,proto-ipv6,socket-udp,socket-dns"
     "std,medium-ethernet,phy-tuntap_interface,proto-ipv6,socket-udp"
+    "std,medium-ethernet,phy-xdp,proto-ipv4,socket-udp"
+    "std,medium-ip,phy-wintun,proto-ipv4,socket-udp"
+    "std,medium-ethernet,phy-npcap,proto-ipv4,socket-udp"
+    "std,medium-ethernet,phy-virtio_net,proto-ipv6,socket-tcp"
+    "std,medium-ip,phy-ppp,proto-ipv4,socket-tcp"
     "std,medium-ethernet,proto-ipv4,proto-ipv4-fragmentation,socket-raw,socket-dns"
     "std,medium-ethernet,proto-ipv4,proto-igmp,socket-raw,socket-dns"
     "std,medium-ethernet,proto-ipv4,socket-udp,socket-tcp,socket-dns"
@@ -30,13 +35,17 @@ FEATURES_TEST=(
     "std,medium-ieee802154,proto-rpl,proto-sixlowpan,proto-sixlowpan-fragmentation,socket-udp"
     "std,medium-ip,proto-ipv4,proto-ipv6,socket-tcp,socket-udp"
     "std,medium-ethernet,medium-ip,medium-ieee802154,proto-ipv4,proto-ipv6,socket-raw,socket-udp,socket-tcp,socket-icmp,socket-dns,async"
+    "std,medium-ip,proto-ipv4,socket-tcp,futures-io"
 )
 
 FEATURES_TEST_NIGHTLY=(
     "alloc,medium-ethernet,proto-ipv4,proto-ipv6,socket-raw,socket-udp,socket-tcp,socket-icmp"
+    "alloc,medium-ip,proto-ipv4,socket-tcp,embedded-io-async"
 )
 
 FEATURES_CHECK=(
+    "medium-ethernet,phy-virtio_net,proto-ipv4,socket-tcp"
+    "medium-ip,phy-ppp,proto-ipv6,socket-udp"
     "medium-ip,medium-ethernet,medium-ieee802154,proto-ipv6,proto-ipv6,proto-igmp,proto-dhcpv4,socket-raw,socket-udp,socket-tcp,socket-icmp,socket-dns,async"
     "defmt,medium-ip,medium-ethernet,proto-ipv6,proto-ipv6,proto-igmp,proto-dhcpv4,socket-raw,socket-udp,socket-tcp,socket-icmp,socket-dns,async"
     "defmt,alloc,medium-ip,medium-ethernet,proto-ipv6,proto-ipv6,proto-igmp,proto-dhcpv4,socket-raw,socket-udp,socket-tcp,socket-icmp,socket-dns,async"
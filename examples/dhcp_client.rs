@@ -80,6 +80,12 @@ fn main() {
                 set_ipv4_addr(&mut iface, Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0));
                 iface.routes_mut().remove_default_ipv4_route();
             }
+            Some(dhcpv4::Event::Renewing) => debug!("DHCP renewing lease"),
+            Some(dhcpv4::Event::Rebinding) => debug!("DHCP rebinding lease"),
+            Some(dhcpv4::Event::RenewFailed) => debug!("DHCP failed to renew lease"),
+            Some(dhcpv4::Event::LeaseExpiringSoon(expires_at)) => {
+                debug!("DHCP lease expires at {}", expires_at)
+            }
         }
 
         phy_wait(fd, iface.poll_delay(timestamp, &sockets)).expect("wait error");
@@ -80,15 +80,6 @@ fn main() {
     let udp_socket = udp::Socket::new(udp_rx_buffer, udp_tx_buffer);
     let udp_handle = sockets.add(udp_socket);
 
-    // Join a multicast group to receive mDNS traffic
-    iface
-        .join_multicast_group(
-            &mut device,
-            Ipv4Address::from_bytes(&MDNS_GROUP),
-            Instant::now(),
-        )
-        .unwrap();
-
     loop {
         let timestamp = Instant::now();
         iface.poll(timestamp, &mut device, &mut sockets);
@@ -112,7 +103,11 @@ fn main() {
 
         let socket = sockets.get_mut::<udp::Socket>(udp_handle);
         if !socket.is_open() {
-            socket.bind(MDNS_PORT).unwrap()
+            socket.bind(MDNS_PORT).unwrap();
+            // Join a multicast group to receive mDNS traffic
+            socket
+                .join_multicast_group(Ipv4Address::from_bytes(&MDNS_GROUP))
+                .unwrap();
         }
 
         if socket.can_recv() {
@@ -28,6 +28,15 @@ struct SixlowpanPacketFuzzer<'a> {
 
 fuzz_target!(|fuzz: SixlowpanPacketFuzzer| {
     match SixlowpanPacket::dispatch(fuzz.data) {
+        Ok(SixlowpanPacket::MeshHeader) => {
+            if let Ok(frame) = SixlowpanMeshPacket::new_checked(fuzz.data) {
+                if let Ok(repr) = SixlowpanMeshRepr::parse(&frame) {
+                    let mut buffer = vec![0; repr.buffer_len()];
+                    let mut frame = SixlowpanMeshPacket::new_unchecked(&mut buffer[..]);
+                    repr.emit(&mut frame);
+                }
+            }
+        }
         Ok(SixlowpanPacket::FragmentHeader) => {
             if let Ok(frame) = SixlowpanFragPacket::new_checked(fuzz.data) {
                 if let Ok(repr) = SixlowpanFragRepr::parse(&frame) {
@@ -29,9 +29,14 @@ def feature(name, default, min, max, pow2=None):
 
 feature("iface_max_addr_count", default=2, min=1, max=8)
 feature("iface_max_multicast_group_count", default=4, min=1, max=1024, pow2=8)
+feature("iface_max_multicast_source_count", default=4, min=1, max=1024, pow2=8)
 feature("iface_max_sixlowpan_address_context_count", default=4, min=1, max=1024, pow2=8)
 feature("iface_neighbor_cache_count", default=4, min=1, max=1024, pow2=8)
+feature("iface_destination_cache_count", default=4, min=1, max=1024, pow2=8)
 feature("iface_max_route_count", default=2, min=1, max=1024, pow2=8)
+feature("iface_max_filter_rule_count", default=4, min=1, max=1024, pow2=8)
+feature("iface_local_buffer_size", default=0, min=256, max=65536, pow2=True)
+feature("bridge_mac_table_count", default=16, min=1, max=1024, pow2=8)
 feature("fragmentation_buffer_size", default=1500, min=256, max=65536, pow2=True)
 feature("assembler_max_segment_count", default=4, min=1, max=32, pow2=4)
 feature("reassembly_buffer_size", default=1500, min=256, max=65536, pow2=True)
@@ -0,0 +1,430 @@
+use heapless::Vec;
+
+use crate::config::IFACE_MAX_FILTER_RULE_COUNT;
+use crate::iface::ip_packet::IpPacket;
+#[allow(unused)] // unused depending on which sockets are enabled
+use crate::iface::ip_packet::IpPayload;
+use crate::wire::{IpAddress, IpCidr, IpProtocol, IpRepr, IpVersion};
+#[cfg(feature = "proto-ipv4")]
+use crate::wire::{Ipv4Packet, Ipv4Repr};
+#[cfg(feature = "proto-ipv6")]
+use crate::wire::{Ipv6Packet, Ipv6Repr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FilterTableFull;
+
+impl core::fmt::Display for FilterTableFull {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Filter table full")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FilterTableFull {}
+
+/// Whether a packet is received or sent by the interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FilterDirection {
+    /// The packet was received, and is about to be processed by the sockets or forwarded.
+    Ingress,
+    /// The packet is about to be sent.
+    Egress,
+}
+
+/// What happens to a packet matched by a filter rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FilterAction {
+    /// The packet goes through.
+    Accept,
+    /// The packet is dropped silently.
+    Drop,
+    /// The packet is dropped, and its sender notified with an ICMP communication
+    /// administratively prohibited error. Packets sent by the interface itself are
+    /// dropped silently.
+    Reject,
+}
+
+/// A rule of a packet filter. Fields set to `None` match any packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FilterRule {
+    pub direction: Option<FilterDirection>,
+    pub src_cidr: Option<IpCidr>,
+    pub dst_cidr: Option<IpCidr>,
+    /// The protocol of the upper-layer header, after the IPv6 extension headers.
+    pub protocol: Option<IpProtocol>,
+    /// The source port of TCP, UDP and UDP-Lite packets. Other packets, and fragments
+    /// other than the first one, don't match rules with a port.
+    pub src_port: Option<u16>,
+    /// The destination port of TCP, UDP and UDP-Lite packets.
+    pub dst_port: Option<u16>,
+    pub action: FilterAction,
+}
+
+impl FilterRule {
+    /// Returns a rule applying `action` to every packet, to be narrowed down by setting
+    /// its other fields.
+    pub const fn new(action: FilterAction) -> Self {
+        Self {
+            direction: None,
+            src_cidr: None,
+            dst_cidr: None,
+            protocol: None,
+            src_port: None,
+            dst_port: None,
+            action,
+        }
+    }
+
+    fn matches(&self, direction: FilterDirection, packet: &FilterPacket) -> bool {
+        let ports_match = match (self.src_port, self.dst_port, packet.ports) {
+            (None, None, _) => true,
+            (_, _, None) => false,
+            (src_port, dst_port, Some((packet_src_port, packet_dst_port))) => {
+                src_port.map_or(true, |port| port == packet_src_port)
+                    && dst_port.map_or(true, |port| port == packet_dst_port)
+            }
+        };
+        self.direction.map_or(true, |d| d == direction)
+            && self
+                .src_cidr
+                .map_or(true, |cidr| cidr.contains_addr(&packet.src_addr))
+            && self
+                .dst_cidr
+                .map_or(true, |cidr| cidr.contains_addr(&packet.dst_addr))
+            && self.protocol.map_or(true, |p| p == packet.protocol)
+            && ports_match
+    }
+}
+
+/// A stateless packet filter.
+///
+/// Packets are checked against the rules in order, and the action of the first matching
+/// rule applies, or the default action if none matches. Received packets are checked
+/// before being processed by the sockets, and sent packets before being transmitted.
+/// Each interface has its own filter, so that rules only apply to its packets.
+///
+/// ARP is not filtered, but ICMPv6 Neighbor Discovery is, and must be accepted for IPv6 to
+/// work when the default action denies packets.
+#[derive(Debug)]
+pub struct Filter {
+    rules: Vec<FilterRule, IFACE_MAX_FILTER_RULE_COUNT>,
+    default_action: FilterAction,
+}
+
+impl Filter {
+    /// Creates a new filter without rules, accepting every packet.
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            default_action: FilterAction::Accept,
+        }
+    }
+
+    /// Update the rules of the filter.
+    pub fn update<F: FnOnce(&mut Vec<FilterRule, IFACE_MAX_FILTER_RULE_COUNT>)>(&mut self, f: F) {
+        f(&mut self.rules);
+    }
+
+    /// Add a rule after the existing ones.
+    pub fn add_rule(&mut self, rule: FilterRule) -> Result<(), FilterTableFull> {
+        self.rules.push(rule).map_err(|_| FilterTableFull)
+    }
+
+    /// Get the rules of the filter.
+    pub fn rules(&self) -> &[FilterRule] {
+        &self.rules
+    }
+
+    /// Get the action applied to the packets no rule matches.
+    pub fn default_action(&self) -> FilterAction {
+        self.default_action
+    }
+
+    /// Set the action applied to the packets no rule matches. The default is to accept
+    /// them.
+    pub fn set_default_action(&mut self, action: FilterAction) {
+        self.default_action = action;
+    }
+
+    /// Return the action applied to a packet.
+    pub(crate) fn action(&self, direction: FilterDirection, packet: &FilterPacket) -> FilterAction {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(direction, packet))
+            .map_or(self.default_action, |rule| rule.action)
+    }
+
+    /// Whether every packet is accepted, and parsing them can be skipped.
+    pub(crate) fn accepts_all(&self) -> bool {
+        self.rules.is_empty() && self.default_action == FilterAction::Accept
+    }
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The fields of a packet that filter rules match on.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FilterPacket {
+    src_addr: IpAddress,
+    dst_addr: IpAddress,
+    protocol: IpProtocol,
+    ports: Option<(u16, u16)>,
+}
+
+impl FilterPacket {
+    /// Return the fields of a packet with the IP header `ip_repr` and payload `payload`.
+    pub(crate) fn new(ip_repr: &IpRepr, payload: &[u8]) -> Self {
+        let (protocol, payload) = match ip_repr {
+            #[cfg(feature = "proto-ipv4")]
+            IpRepr::Ipv4(repr) => (repr.next_header, Some(payload)),
+            #[cfg(feature = "proto-ipv6")]
+            IpRepr::Ipv6(repr) => skip_ext_headers(repr.next_header, payload),
+        };
+        Self {
+            src_addr: ip_repr.src_addr(),
+            dst_addr: ip_repr.dst_addr(),
+            protocol,
+            ports: payload.and_then(|payload| ports(protocol, payload)),
+        }
+    }
+
+    /// Return the fields of a packet about to be sent.
+    pub(crate) fn from_ip_packet(packet: &IpPacket) -> Self {
+        let ip_repr = packet.ip_repr();
+        let mut filter_packet = Self {
+            src_addr: ip_repr.src_addr(),
+            dst_addr: ip_repr.dst_addr(),
+            protocol: ip_repr.next_header(),
+            ports: None,
+        };
+        filter_packet.ports = match packet.payload() {
+            #[cfg(feature = "socket-tcp")]
+            IpPayload::Tcp(repr) => Some((repr.src_port, repr.dst_port)),
+            #[cfg(any(
+                feature = "socket-udp",
+                feature = "socket-dns",
+                feature = "socket-dhcpv4-relay"
+            ))]
+            IpPayload::Udp(repr, _) => Some((repr.src_port, repr.dst_port)),
+            #[cfg(feature = "socket-udp")]
+            IpPayload::UdpNoChecksum(repr, _) => Some((repr.src_port, repr.dst_port)),
+            #[cfg(feature = "socket-udp")]
+            IpPayload::UdpLite(repr, _) => Some((repr.src_port, repr.dst_port)),
+            #[cfg(any(
+                feature = "socket-raw",
+                feature = "proto-rpl",
+                feature = "proto-ipv4-fragmentation"
+            ))]
+            IpPayload::Raw(payload) => {
+                return Self::new(&ip_repr, payload);
+            }
+            #[allow(unreachable_patterns)]
+            _ => None,
+        };
+        filter_packet
+    }
+
+    /// Return the fields of a raw IP packet, if it is valid.
+    #[allow(unused)] // only used for forwarding, which the 802.15.4 medium lacks
+    pub(crate) fn parse(packet: &[u8]) -> Option<Self> {
+        match IpVersion::of_packet(packet).ok()? {
+            #[cfg(feature = "proto-ipv4")]
+            IpVersion::Ipv4 => {
+                let packet = Ipv4Packet::new_checked(packet).ok()?;
+                let ip_repr = IpRepr::Ipv4(Ipv4Repr {
+                    src_addr: packet.src_addr(),
+                    dst_addr: packet.dst_addr(),
+                    next_header: packet.next_header(),
+                    payload_len: packet.payload().len(),
+                    hop_limit: packet.hop_limit(),
+                });
+                let mut filter_packet = Self::new(&ip_repr, packet.payload());
+                // Only the first fragment has the upper-layer header.
+                if packet.frag_offset() != 0 {
+                    filter_packet.ports = None;
+                }
+                Some(filter_packet)
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpVersion::Ipv6 => {
+                let packet = Ipv6Packet::new_checked(packet).ok()?;
+                let ip_repr = IpRepr::Ipv6(Ipv6Repr {
+                    src_addr: packet.src_addr(),
+                    dst_addr: packet.dst_addr(),
+                    next_header: packet.next_header(),
+                    payload_len: packet.payload().len(),
+                    hop_limit: packet.hop_limit(),
+                });
+                Some(Self::new(&ip_repr, packet.payload()))
+            }
+        }
+    }
+}
+
+/// Return the ports of a TCP, UDP or UDP-Lite packet.
+fn ports(protocol: IpProtocol, payload: &[u8]) -> Option<(u16, u16)> {
+    match protocol {
+        IpProtocol::Tcp | IpProtocol::Udp | IpProtocol::UdpLite if payload.len() >= 4 => Some((
+            u16::from_be_bytes([payload[0], payload[1]]),
+            u16::from_be_bytes([payload[2], payload[3]]),
+        )),
+        _ => None,
+    }
+}
+
+/// Skip the IPv6 extension headers of a payload, and return the upper-layer protocol
+/// along with its header, if it is in this packet.
+#[cfg(feature = "proto-ipv6")]
+fn skip_ext_headers(
+    mut next_header: IpProtocol,
+    mut payload: &[u8],
+) -> (IpProtocol, Option<&[u8]>) {
+    loop {
+        let len = match next_header {
+            IpProtocol::HopByHop | IpProtocol::Ipv6Opts | IpProtocol::Ipv6Route => {
+                match payload.get(1) {
+                    Some(&len) => (len as usize + 1) * 8,
+                    None => return (next_header, None),
+                }
+            }
+            IpProtocol::Ipv6Frag => {
+                // Only the first fragment has the upper-layer header.
+                match payload.get(2..4) {
+                    Some(offset) if u16::from_be_bytes([offset[0], offset[1]]) >> 3 == 0 => 8,
+                    _ => return (IpProtocol::from(payload[0]), None),
+                }
+            }
+            _ => return (next_header, Some(payload)),
+        };
+        if payload.len() < len {
+            return (next_header, None);
+        }
+        next_header = IpProtocol::from(payload[0]);
+        payload = &payload[len..];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(feature = "proto-ipv4")]
+    fn packet(protocol: IpProtocol, ports: Option<(u16, u16)>) -> FilterPacket {
+        FilterPacket {
+            src_addr: IpAddress::v4(192, 168, 1, 2),
+            dst_addr: IpAddress::v4(192, 168, 1, 1),
+            protocol,
+            ports,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_first_match() {
+        let mut filter = Filter::new();
+        filter
+            .add_rule(FilterRule {
+                protocol: Some(IpProtocol::Tcp),
+                dst_port: Some(22),
+                ..FilterRule::new(FilterAction::Reject)
+            })
+            .unwrap();
+        filter
+            .add_rule(FilterRule {
+                src_cidr: Some(IpCidr::new(IpAddress::v4(192, 168, 1, 0), 24)),
+                ..FilterRule::new(FilterAction::Accept)
+            })
+            .unwrap();
+        filter.set_default_action(FilterAction::Drop);
+
+        let ingress = FilterDirection::Ingress;
+        let ssh = packet(IpProtocol::Tcp, Some((40000, 22)));
+        assert_eq!(filter.action(ingress, &ssh), FilterAction::Reject);
+        let http = packet(IpProtocol::Tcp, Some((40000, 80)));
+        assert_eq!(filter.action(ingress, &http), FilterAction::Accept);
+        // Packets without ports don't match rules with a port.
+        let icmp = packet(IpProtocol::Icmp, None);
+        assert_eq!(filter.action(ingress, &icmp), FilterAction::Accept);
+
+        let other = FilterPacket {
+            src_addr: IpAddress::v4(10, 0, 0, 1),
+            ..http
+        };
+        assert_eq!(filter.action(ingress, &other), FilterAction::Drop);
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_direction() {
+        let mut filter = Filter::new();
+        filter
+            .add_rule(FilterRule {
+                direction: Some(FilterDirection::Egress),
+                protocol: Some(IpProtocol::Udp),
+                ..FilterRule::new(FilterAction::Drop)
+            })
+            .unwrap();
+
+        let udp = packet(IpProtocol::Udp, Some((53, 53)));
+        assert_eq!(
+            filter.action(FilterDirection::Ingress, &udp),
+            FilterAction::Accept
+        );
+        assert_eq!(
+            filter.action(FilterDirection::Egress, &udp),
+            FilterAction::Drop
+        );
+    }
+
+    #[test]
+    fn test_full() {
+        let mut filter = Filter::new();
+        for _ in 0..IFACE_MAX_FILTER_RULE_COUNT {
+            filter
+                .add_rule(FilterRule::new(FilterAction::Drop))
+                .unwrap();
+        }
+        assert_eq!(
+            filter.add_rule(FilterRule::new(FilterAction::Drop)),
+            Err(FilterTableFull)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv6")]
+    fn test_ext_headers() {
+        // A destination options header, followed by a UDP header.
+        let payload = [
+            0x11, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, 0x12, 0x34, 0x00, 0x35,
+        ];
+        assert_eq!(
+            skip_ext_headers(IpProtocol::Ipv6Opts, &payload),
+            (IpProtocol::Udp, Some(&payload[8..]))
+        );
+        let ip_repr = IpRepr::Ipv6(Ipv6Repr {
+            src_addr: crate::wire::Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 2),
+            dst_addr: crate::wire::Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1),
+            next_header: IpProtocol::Ipv6Opts,
+            payload_len: payload.len(),
+            hop_limit: 64,
+        });
+        let packet = FilterPacket::new(&ip_repr, &payload);
+        assert_eq!(packet.protocol, IpProtocol::Udp);
+        assert_eq!(packet.ports, Some((0x1234, 53)));
+
+        // A non-initial fragment.
+        let payload = [0x11, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(
+            skip_ext_headers(IpProtocol::Ipv6Frag, &payload),
+            (IpProtocol::Udp, None)
+        );
+    }
+}
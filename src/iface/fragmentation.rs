@@ -41,6 +41,25 @@ impl fmt::Display for AssemblerFullError {
 #[cfg(feature = "std")]
 impl std::error::Error for AssemblerFullError {}
 
+/// Statistics about packet reassembly.
+///
+/// This struct is marked as `#[non_exhaustive]`, so that new fields can be added without
+/// a breaking change.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct ReassemblyStats {
+    /// The number of packets reassembled.
+    pub reassembled: u32,
+    /// The number of incomplete packets dropped because their reassembly timed out.
+    pub timeouts: u32,
+    /// The number of incomplete packets dropped to make room for newer ones.
+    pub evictions: u32,
+    /// The number of fragments dropped because they were invalid, or did not fit within
+    /// the reassembly limits.
+    pub dropped_fragments: u32,
+}
+
 /// Holds different fragments of one packet, used for assembling fragmented packets.
 ///
 /// The buffer used for the `PacketAssembler` should either be dynamically sized (ex: Vec<u8>)
@@ -53,7 +72,11 @@ pub struct PacketAssembler<K> {
 
     assembler: Assembler,
     total_size: Option<usize>,
+    /// The end of the furthest fragment received.
+    extent: usize,
     expires_at: Instant,
+    /// The order in which the packet was started, the oldest first.
+    started: u32,
 }
 
 impl<K> PacketAssembler<K> {
@@ -69,7 +92,9 @@ impl<K> PacketAssembler<K> {
 
             assembler: Assembler::new(),
             total_size: None,
+            extent: 0,
             expires_at: Instant::ZERO,
+            started: 0,
         }
     }
 
@@ -77,9 +102,15 @@ impl<K> PacketAssembler<K> {
         self.key = None;
         self.assembler.clear();
         self.total_size = None;
+        self.extent = 0;
         self.expires_at = Instant::ZERO;
     }
 
+    /// Return the number of octets of the buffer the packet takes, as far as is known.
+    pub(crate) fn size(&self) -> usize {
+        self.total_size.unwrap_or(0).max(self.extent)
+    }
+
     /// Set the total size of the packet assembler.
     pub(crate) fn set_total_size(&mut self, size: usize) -> Result<(), AssemblerError> {
         if let Some(old_size) = self.total_size {
@@ -118,6 +149,7 @@ impl<K> PacketAssembler<K> {
 
         let len = f(&mut self.buffer[offset..])?;
         assert!(offset + len <= self.buffer.len());
+        self.extent = self.extent.max(offset + len);
 
         net_debug!(
             "frag assembler: receiving {} octets at offset {}",
@@ -148,6 +180,7 @@ impl<K> PacketAssembler<K> {
 
         let len = data.len();
         self.buffer[offset..][..len].copy_from_slice(data);
+        self.extent = self.extent.max(offset + len);
 
         net_debug!(
             "frag assembler: receiving {} octets at offset {}",
@@ -187,6 +220,7 @@ impl<K> PacketAssembler<K> {
 #[derive(Debug)]
 pub struct PacketAssemblerSet<K: Eq + Copy> {
     assemblers: [PacketAssembler<K>; REASSEMBLY_BUFFER_COUNT],
+    started: u32,
 }
 
 impl<K: Eq + Copy> PacketAssemblerSet<K> {
@@ -196,6 +230,7 @@ impl<K: Eq + Copy> PacketAssemblerSet<K> {
     pub fn new() -> Self {
         Self {
             assemblers: [Self::NEW_PA; REASSEMBLY_BUFFER_COUNT],
+            started: 0,
         }
     }
 
@@ -222,13 +257,52 @@ impl<K: Eq + Copy> PacketAssemblerSet<K> {
         let slot = empty_slot.ok_or(AssemblerFullError)?;
         slot.key = Some(*key);
         slot.expires_at = expires_at;
+        slot.started = self.started;
+        self.started = self.started.wrapping_add(1);
         Ok(slot)
     }
 
-    /// Remove all [`PacketAssembler`]s that are expired.
-    pub fn remove_expired(&mut self, timestamp: Instant) {
+    /// Return the number of [`PacketAssembler`]s in use for keys matching `f`.
+    pub(crate) fn count(&self, f: impl Fn(&K) -> bool) -> usize {
+        self.assemblers
+            .iter()
+            .filter(|slot| slot.key.as_ref().map_or(false, &f))
+            .count()
+    }
+
+    /// Return the number of octets taken by the [`PacketAssembler`]s in use for keys
+    /// matching `f`.
+    pub(crate) fn size(&self, f: impl Fn(&K) -> bool) -> usize {
+        self.assemblers
+            .iter()
+            .filter(|slot| slot.key.as_ref().map_or(false, &f))
+            .map(|slot| slot.size())
+            .sum()
+    }
+
+    /// Remove the oldest [`PacketAssembler`] in use for a key matching `f`, other than
+    /// `key`. Return whether one was removed.
+    pub(crate) fn remove_oldest(&mut self, key: &K, f: impl Fn(&K) -> bool) -> bool {
+        let started = self.started;
+        let oldest = self
+            .assemblers
+            .iter_mut()
+            .filter(|slot| slot.key.as_ref().map_or(false, |k| k != key && f(k)))
+            .max_by_key(|slot| started.wrapping_sub(slot.started));
+        match oldest {
+            Some(slot) => {
+                slot.reset();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove all [`PacketAssembler`]s that are expired, calling `f` with their keys.
+    pub fn remove_expired(&mut self, timestamp: Instant, mut f: impl FnMut(&K)) {
         for frag in &mut self.assemblers {
-            if !frag.is_free() && frag.expires_at < timestamp {
+            if let Some(key) = frag.key.filter(|_| frag.expires_at < timestamp) {
+                f(&key);
                 frag.reset();
             }
         }
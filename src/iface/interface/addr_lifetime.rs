@@ -0,0 +1,135 @@
+use heapless::Vec;
+
+use super::{Interface, InterfaceInner};
+use crate::config::IFACE_MAX_ADDR_COUNT;
+use crate::time::Instant;
+use crate::wire::IpAddress;
+
+/// The lifetimes of an address of the interface (RFC 4862 § 5.5.4).
+///
+/// A deprecated address, whose preferred lifetime ran out, is still accepted for inbound
+/// traffic, but it is not selected as the source address of new connections. The address
+/// is removed from the interface once its valid lifetime runs out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AddressLifetime {
+    /// The address is deprecated after this instant. `None` means "forever".
+    pub preferred_until: Option<Instant>,
+    /// The address is removed after this instant. `None` means "forever".
+    pub valid_until: Option<Instant>,
+}
+
+impl AddressLifetime {
+    /// Whether the address is deprecated at `timestamp`.
+    pub fn is_deprecated(&self, timestamp: Instant) -> bool {
+        self.preferred_until.map_or(false, |t| t <= timestamp)
+    }
+
+    /// Whether the address is to be removed at `timestamp`.
+    pub fn is_expired(&self, timestamp: Instant) -> bool {
+        self.valid_until.map_or(false, |t| t <= timestamp)
+    }
+}
+
+/// The lifetimes set for the addresses of the interface. Addresses without an entry last
+/// forever.
+#[derive(Debug, Default)]
+pub(crate) struct AddressLifetimes {
+    entries: Vec<(IpAddress, AddressLifetime), IFACE_MAX_ADDR_COUNT>,
+}
+
+impl Interface {
+    /// Get the lifetimes of an address of the interface.
+    pub fn ip_addr_lifetime<T: Into<IpAddress>>(&self, addr: T) -> AddressLifetime {
+        self.inner.ip_addr_lifetime(addr.into())
+    }
+
+    /// Set the lifetimes of an address of the interface, e.g. to renumber a network
+    /// gracefully.
+    ///
+    /// Lifetimes of addresses that are not assigned to the interface are ignored. The
+    /// lifetimes are forgotten when the address is removed.
+    pub fn set_ip_addr_lifetime<T: Into<IpAddress>>(&mut self, addr: T, lifetime: AddressLifetime) {
+        let addr = addr.into();
+        let inner = &mut self.inner;
+        let ip_addrs = &inner.ip_addrs;
+        let entries = &mut inner.addr_lifetimes.entries;
+        entries.retain(|(a, _)| *a != addr && ip_addrs.iter().any(|cidr| cidr.address() == *a));
+
+        if !ip_addrs.iter().any(|cidr| cidr.address() == addr) {
+            net_debug!("iface: ignoring lifetime of unassigned address {}", addr);
+            return;
+        }
+        if lifetime != AddressLifetime::default() {
+            // NOTE(unwrap): there are at most as many entries as addresses.
+            entries.push((addr, lifetime)).unwrap();
+        }
+    }
+}
+
+impl InterfaceInner {
+    fn ip_addr_lifetime(&self, addr: IpAddress) -> AddressLifetime {
+        self.addr_lifetimes
+            .entries
+            .iter()
+            .find(|(a, _)| *a == addr)
+            .map(|(_, lifetime)| *lifetime)
+            .unwrap_or_default()
+    }
+
+    /// Whether `addr` is deprecated, and not to be selected as a source address.
+    pub(super) fn ip_addr_deprecated(&self, addr: IpAddress) -> bool {
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        #[allow(irrefutable_let_patterns)] // if only ipv6 is enabled
+        if let IpAddress::Ipv6(addr) = addr {
+            if self.slaac_deprecated(addr) {
+                return true;
+            }
+        }
+        self.ip_addr_lifetime(addr).is_deprecated(self.now)
+    }
+
+    /// Move the lifetimes of `addr`, if any, to `new_addr`, which replaced it.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    pub(super) fn addr_lifetime_rename(&mut self, addr: IpAddress, new_addr: IpAddress) {
+        for (a, _) in self.addr_lifetimes.entries.iter_mut() {
+            if *a == addr {
+                *a = new_addr;
+            }
+        }
+    }
+
+    /// Remove the addresses whose valid lifetime ran out.
+    pub(super) fn addr_lifetime_expire(&mut self) {
+        let now = self.now;
+        let mut index = 0;
+        while index < self.addr_lifetimes.entries.len() {
+            let (addr, lifetime) = self.addr_lifetimes.entries[index];
+            if lifetime.is_expired(now) {
+                net_debug!("iface: address {} expired", addr);
+                self.addr_lifetimes.entries.swap_remove(index);
+                self.ip_addrs.retain(|cidr| cidr.address() != addr);
+                self.addrs_changed();
+            } else if !self.has_ip_addr(addr) {
+                // The address was removed by hand.
+                self.addr_lifetimes.entries.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    pub(super) fn addr_lifetime_poll_at(&self) -> Option<Instant> {
+        self.addr_lifetimes
+            .entries
+            .iter()
+            .filter_map(|(_, lifetime)| lifetime.valid_until)
+            .min()
+    }
+}
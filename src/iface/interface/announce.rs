@@ -0,0 +1,227 @@
+use heapless::Vec;
+
+#[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+use super::EthernetPacket;
+use super::{Interface, InterfaceInner};
+#[cfg(feature = "proto-ipv6")]
+use super::{IpPacket, IpPayload};
+use crate::config::IFACE_MAX_ADDR_COUNT;
+use crate::phy::Device;
+#[cfg(any(
+    all(feature = "proto-ipv4", feature = "medium-ethernet"),
+    feature = "medium-ip"
+))]
+use crate::phy::Medium;
+#[cfg(feature = "proto-ipv6")]
+use crate::phy::PacketMeta;
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// Number of announcements sent for an address by default (RFC 5227 § 1.1, ANNOUNCE_NUM).
+const ANNOUNCE_NUM: u8 = 2;
+
+/// Time between the announcements of an address (RFC 5227 § 1.1, ANNOUNCE_INTERVAL).
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// An address whose announcements are not all sent yet.
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+    addr: IpAddress,
+    /// Number of announcements still to send.
+    left: u8,
+    send_at: Instant,
+}
+
+/// Gratuitous ARP requests and unsolicited Neighbor Advertisements, which let the
+/// neighbors update their caches when an address is added or the hardware address
+/// changes (RFC 5227 § 2.3, RFC 4861 § 7.2.6).
+#[derive(Debug)]
+pub(crate) struct Announcements {
+    transmits: u8,
+    pending: Vec<Pending, IFACE_MAX_ADDR_COUNT>,
+}
+
+impl Default for Announcements {
+    fn default() -> Self {
+        Self {
+            transmits: ANNOUNCE_NUM,
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl Interface {
+    /// Get the number of announcements sent for a new address.
+    pub fn announce_transmits(&self) -> u8 {
+        self.inner.announce.transmits
+    }
+
+    /// Set the number of announcements sent for a new address.
+    ///
+    /// When an address is assigned to the interface, or when its hardware address changes,
+    /// the addresses are announced that many times, two seconds apart: IPv4 addresses with
+    /// gratuitous ARP requests, IPv6 addresses with unsolicited Neighbor Advertisements.
+    /// Neighbors update their caches right away instead of waiting for stale entries to
+    /// time out.
+    ///
+    /// The default is two. Zero disables the announcements.
+    pub fn set_announce_transmits(&mut self, transmits: u8) {
+        let announce = &mut self.inner.announce;
+        announce.transmits = transmits;
+        if transmits == 0 {
+            announce.pending.clear();
+        }
+    }
+
+    /// Send the pending announcement, if it is due.
+    pub(crate) fn announce_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        let Some(index) = self
+            .inner
+            .announce
+            .pending
+            .iter()
+            .position(|p| p.send_at <= now)
+        else {
+            return false;
+        };
+        let addr = self.inner.announce.pending[index].addr;
+        if !self.inner.has_ip_addr(addr) {
+            // The address was removed in the meantime.
+            self.inner.announce.pending.swap_remove(index);
+            return true;
+        }
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        match addr {
+            #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+            IpAddress::Ipv4(addr) => {
+                let arp_repr = ArpRepr::EthernetIpv4 {
+                    operation: ArpOperation::Request,
+                    source_hardware_addr: self.inner.hardware_addr.ethernet_or_panic(),
+                    source_protocol_addr: addr,
+                    target_hardware_addr: EthernetAddress::BROADCAST,
+                    target_protocol_addr: addr,
+                };
+                net_trace!("arp: announcing {}", addr);
+                // NOTE(unwrap): ARP packets are sent to a hardware address, and need no route.
+                self.inner
+                    .dispatch(
+                        tx_token,
+                        EthernetPacket::Arp(arp_repr),
+                        &mut self.fragmenter,
+                    )
+                    .unwrap();
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => {
+                let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+                    flags: NdiscNeighborFlags::OVERRIDE,
+                    target_addr: addr,
+                    lladdr: Some(self.inner.hardware_addr.into()),
+                });
+                let ip_repr = Ipv6Repr {
+                    src_addr: addr,
+                    dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+                    next_header: IpProtocol::Icmpv6,
+                    hop_limit: 0xff,
+                    payload_len: advert.buffer_len(),
+                };
+                net_trace!("ndisc: announcing {}", addr);
+                // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+                self.inner
+                    .dispatch_ip(
+                        tx_token,
+                        PacketMeta::default(),
+                        IpPacket::new_ipv6(ip_repr, IpPayload::Icmpv6(advert)),
+                        &mut self.fragmenter,
+                    )
+                    .unwrap();
+            }
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+
+        let pending = &mut self.inner.announce.pending[index];
+        pending.left -= 1;
+        pending.send_at = now + ANNOUNCE_INTERVAL;
+        if pending.left == 0 {
+            self.inner.announce.pending.swap_remove(index);
+        }
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Whether `addr` can be announced to the neighbors on the medium of the interface.
+    fn announceable(&self, addr: IpAddress) -> bool {
+        match addr {
+            #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+            IpAddress::Ipv4(addr) => {
+                self.caps.medium == Medium::Ethernet && addr.is_unicast() && !addr.is_loopback()
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => {
+                #[cfg(feature = "medium-ip")]
+                if self.caps.medium == Medium::Ip {
+                    return false;
+                }
+                addr.is_unicast() && !addr.is_loopback()
+            }
+            #[allow(unreachable_patterns)]
+            _ => false,
+        }
+    }
+
+    /// Start announcing `addr`, which was just assigned to the interface.
+    pub(super) fn announce_start(&mut self, addr: IpAddress) {
+        if self.announce.transmits == 0 || !self.announceable(addr) {
+            return;
+        }
+        let ip_addrs = &self.ip_addrs;
+        self.announce
+            .pending
+            .retain(|p| p.addr != addr && ip_addrs.iter().any(|cidr| cidr.address() == p.addr));
+        // NOTE(unwrap): there are at most as many pending announcements as addresses.
+        self.announce
+            .pending
+            .push(Pending {
+                addr,
+                left: self.announce.transmits,
+                send_at: self.now,
+            })
+            .unwrap();
+    }
+
+    /// Start announcing the addresses that are not in `previous`.
+    pub(super) fn announce_added(&mut self, previous: &[IpCidr]) {
+        for index in 0..self.ip_addrs.len() {
+            let addr = self.ip_addrs[index].address();
+            if !previous.iter().any(|cidr| cidr.address() == addr) {
+                self.announce_start(addr);
+            }
+        }
+    }
+
+    /// Start announcing all the addresses, e.g. with a new hardware address.
+    pub(super) fn announce_all(&mut self) {
+        for index in 0..self.ip_addrs.len() {
+            let addr = self.ip_addrs[index].address();
+            self.announce_start(addr);
+        }
+    }
+
+    /// Stop announcing the addresses, e.g. when the link goes down.
+    pub(super) fn announce_stop(&mut self) {
+        self.announce.pending.clear();
+    }
+
+    pub(super) fn announce_poll_at(&self) -> Option<Instant> {
+        self.announce.pending.iter().map(|p| p.send_at).min()
+    }
+}
@@ -0,0 +1,317 @@
+use super::{EthernetPacket, Interface, InterfaceInner};
+use crate::phy::{Device, Medium};
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// Upper bound of the random delay before the first probe (RFC 3927 § 9, PROBE_WAIT).
+const PROBE_WAIT: Duration = Duration::from_secs(1);
+
+/// Number of probes sent for an address (RFC 3927 § 9, PROBE_NUM).
+const PROBE_NUM: u8 = 3;
+
+/// Bounds of the random time between probes (RFC 3927 § 9, PROBE_MIN and PROBE_MAX).
+const PROBE_MIN: Duration = Duration::from_secs(1);
+const PROBE_MAX: Duration = Duration::from_secs(2);
+
+/// Time to wait after the last probe before using the address (RFC 3927 § 9,
+/// ANNOUNCE_WAIT).
+const ANNOUNCE_WAIT: Duration = Duration::from_secs(2);
+
+/// Number of conflicts after which addresses are tried at a slower rate (RFC 3927 § 9,
+/// MAX_CONFLICTS).
+const MAX_CONFLICTS: u8 = 10;
+
+/// Time between the addresses tried after too many conflicts (RFC 3927 § 9,
+/// RATE_LIMIT_INTERVAL).
+const RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Minimum time between the defenses of an address; the address is given up on a second
+/// conflict within it (RFC 3927 § 9, DEFEND_INTERVAL).
+const DEFEND_INTERVAL: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// No address is selected.
+    Idle,
+    /// Checking whether another host uses `addr`.
+    Probing {
+        addr: Ipv4Address,
+        /// Number of probes sent so far.
+        probes: u8,
+        /// When to send the next probe, or to use the address once all are sent.
+        retry_at: Instant,
+    },
+    /// `addr` is assigned to the interface.
+    Bound {
+        addr: Ipv4Address,
+        /// When the address was last defended against another host.
+        defended_at: Option<Instant>,
+    },
+}
+
+/// State of IPv4 link-local address autoconfiguration (RFC 3927).
+#[derive(Debug)]
+pub(crate) struct AutoIp {
+    enabled: bool,
+    state: State,
+    /// Number of addresses found to be used by another host in a row.
+    conflicts: u8,
+}
+
+impl Default for AutoIp {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            state: State::Idle,
+            conflicts: 0,
+        }
+    }
+}
+
+impl Interface {
+    /// Get whether IPv4 link-local address autoconfiguration is enabled.
+    pub fn autoip(&self) -> bool {
+        self.inner.autoip.enabled
+    }
+
+    /// Enable or disable IPv4 link-local address autoconfiguration (RFC 3927).
+    ///
+    /// While the interface has no other IPv4 address, an address of `169.254.0.0/16` is
+    /// selected at random, and assigned once ARP probes showed that no other host uses
+    /// it. The address is defended against other hosts claiming it, and replaced by
+    /// another one if they keep doing so. It is removed as soon as another IPv4 address is
+    /// assigned to the interface, e.g. by DHCP.
+    ///
+    /// This is meant as a fallback when no DHCP server answers, so that the device stays
+    /// reachable on isolated networks. Only Ethernet interfaces are supported.
+    ///
+    /// Disabled by default. Disabling it removes the link-local address.
+    pub fn set_autoip(&mut self, enabled: bool) {
+        if !enabled {
+            self.inner.autoip_stop();
+        }
+        self.inner.autoip.enabled = enabled;
+    }
+
+    /// Get the link-local address assigned by autoconfiguration, if any.
+    pub fn autoip_addr(&self) -> Option<Ipv4Address> {
+        match self.inner.autoip.state {
+            State::Bound { addr, .. } => Some(addr),
+            _ => None,
+        }
+    }
+
+    /// Send the pending ARP probe, and assign the probed address once all went
+    /// unanswered.
+    pub(crate) fn autoip_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let inner = &mut self.inner;
+        if !inner.autoip.enabled || inner.caps.medium != Medium::Ethernet {
+            return false;
+        }
+
+        // Link-local addresses are only used as long as there is no other one.
+        if inner.has_routable_ipv4_addr() {
+            if inner.autoip.state != State::Idle {
+                net_debug!("autoip: another address is assigned, stopping");
+                inner.autoip_stop();
+                return true;
+            }
+            return false;
+        }
+
+        let now = inner.now;
+        let (addr, probes) = match inner.autoip.state {
+            State::Idle if !inner.ip_addrs.is_full() => {
+                inner.autoip_select();
+                return false;
+            }
+            State::Probing {
+                addr,
+                probes,
+                retry_at,
+            } if retry_at <= now => (addr, probes),
+            _ => return false,
+        };
+
+        if probes == PROBE_NUM {
+            let cidr = Ipv4Cidr::new(addr, 16);
+            if inner.ip_addrs.push(IpCidr::Ipv4(cidr)).is_err() {
+                net_debug!("autoip: no room for {}", cidr);
+                inner.autoip.state = State::Idle;
+                return false;
+            }
+            net_debug!("autoip: assigned {}", cidr);
+            inner.addrs_changed();
+            inner.autoip.state = State::Bound {
+                addr,
+                defended_at: None,
+            };
+            inner.autoip.conflicts = 0;
+            inner.announce_start(addr.into());
+            return true;
+        }
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+        // Probes have an unspecified sender address, so that they don't pollute the ARP
+        // caches of other hosts.
+        let arp_repr = ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: inner.hardware_addr.ethernet_or_panic(),
+            source_protocol_addr: Ipv4Address::UNSPECIFIED,
+            target_hardware_addr: EthernetAddress::BROADCAST,
+            target_protocol_addr: addr,
+        };
+        net_trace!("autoip: probing {}", addr);
+        // NOTE(unwrap): ARP packets are sent to a hardware address, and need no route.
+        inner
+            .dispatch(
+                tx_token,
+                EthernetPacket::Arp(arp_repr),
+                &mut self.fragmenter,
+            )
+            .unwrap();
+
+        let probes = probes + 1;
+        let delay = if probes < PROBE_NUM {
+            inner.autoip_random_delay(PROBE_MIN, PROBE_MAX)
+        } else {
+            ANNOUNCE_WAIT
+        };
+        inner.autoip.state = State::Probing {
+            addr,
+            probes,
+            retry_at: now + delay,
+        };
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Whether an IPv4 address other than a link-local or loopback one is assigned.
+    fn has_routable_ipv4_addr(&self) -> bool {
+        self.ip_addrs.iter().any(|cidr| match cidr {
+            IpCidr::Ipv4(cidr) => !cidr.address().is_link_local() && !cidr.address().is_loopback(),
+            #[allow(unreachable_patterns)]
+            _ => false,
+        })
+    }
+
+    /// Return a random delay between `min` and `max`.
+    fn autoip_random_delay(&mut self, min: Duration, max: Duration) -> Duration {
+        let spread = (max - min).total_millis() as u32;
+        min + Duration::from_millis((self.rand.rand_u32() % spread) as u64)
+    }
+
+    /// Select a random address to probe, after a delay depending on the number of
+    /// conflicts so far.
+    fn autoip_select(&mut self) {
+        // The first and last 256 addresses are reserved (RFC 3927 § 2.1).
+        let n = self.rand.rand_u16() % (254 * 256) + 256;
+        let addr = Ipv4Address::new(169, 254, (n >> 8) as u8, n as u8);
+        let delay = if self.autoip.conflicts >= MAX_CONFLICTS {
+            RATE_LIMIT_INTERVAL
+        } else {
+            self.autoip_random_delay(Duration::ZERO, PROBE_WAIT)
+        };
+        net_trace!("autoip: selected {}", addr);
+        self.autoip.state = State::Probing {
+            addr,
+            probes: 0,
+            retry_at: self.now + delay,
+        };
+    }
+
+    /// Give up the selected address, if any.
+    fn autoip_stop(&mut self) {
+        if let State::Bound { addr, .. } = self.autoip.state {
+            self.ip_addrs
+                .retain(|cidr| cidr.address() != IpAddress::Ipv4(addr));
+            self.addrs_changed();
+        }
+        self.autoip.state = State::Idle;
+    }
+
+    /// Look for other hosts using the selected address in an incoming ARP packet, and
+    /// return the announcement defending it, if any.
+    pub(super) fn autoip_process_arp(&mut self, arp_repr: &ArpRepr) -> Option<ArpRepr> {
+        let ArpRepr::EthernetIpv4 {
+            operation,
+            source_hardware_addr,
+            source_protocol_addr,
+            target_protocol_addr,
+            ..
+        } = *arp_repr;
+        let our_hardware_addr = self.hardware_addr.ethernet_or_panic();
+        if source_hardware_addr == our_hardware_addr {
+            return None;
+        }
+
+        match self.autoip.state {
+            State::Idle => None,
+            State::Probing { addr, .. } => {
+                // Any packet from the address is a conflict, as is a probe for it from
+                // another host, since only one of us can have it (RFC 3927 § 2.2.1).
+                if source_protocol_addr == addr
+                    || (operation == ArpOperation::Request
+                        && source_protocol_addr.is_unspecified()
+                        && target_protocol_addr == addr)
+                {
+                    net_debug!("autoip: {} is used by {}", addr, source_hardware_addr);
+                    self.autoip.conflicts = self.autoip.conflicts.saturating_add(1);
+                    self.autoip_select();
+                }
+                None
+            }
+            State::Bound { addr, defended_at } => {
+                if source_protocol_addr != addr {
+                    return None;
+                }
+                // Defend the address once, and give it up if the other host insists
+                // (RFC 3927 § 2.5).
+                let now = self.now;
+                if defended_at.map_or(false, |t| now < t + DEFEND_INTERVAL) {
+                    net_debug!("autoip: {} is taken by {}", addr, source_hardware_addr);
+                    self.autoip_stop();
+                    self.autoip.conflicts = self.autoip.conflicts.saturating_add(1);
+                    self.autoip_select();
+                    return None;
+                }
+                net_debug!(
+                    "autoip: defending {} against {}",
+                    addr,
+                    source_hardware_addr
+                );
+                self.autoip.state = State::Bound {
+                    addr,
+                    defended_at: Some(now),
+                };
+                Some(ArpRepr::EthernetIpv4 {
+                    operation: ArpOperation::Request,
+                    source_hardware_addr: our_hardware_addr,
+                    source_protocol_addr: addr,
+                    target_hardware_addr: EthernetAddress::BROADCAST,
+                    target_protocol_addr: addr,
+                })
+            }
+        }
+    }
+
+    pub(super) fn autoip_poll_at(&self) -> Option<Instant> {
+        if !self.autoip.enabled || self.caps.medium != Medium::Ethernet {
+            return None;
+        }
+        match self.autoip.state {
+            // An address is to be selected.
+            State::Idle if !self.ip_addrs.is_full() && !self.has_routable_ipv4_addr() => {
+                Some(self.now)
+            }
+            State::Probing { retry_at, .. } => Some(retry_at),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use heapless::{Deque, Vec};
+
+use super::{check, Interface, InterfaceInner, IpPacket, IpPayload};
+use crate::config::IFACE_MAX_ADDR_COUNT;
+use crate::phy::{Device, PacketMeta};
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+use core::result::Result;
+
+/// Time to wait for an answer to a Neighbor Solicitation (RFC 4861 § 10, RETRANS_TIMER).
+pub(super) const RETRANS_TIMER: Duration = Duration::from_secs(1);
+
+/// An event of IPv6 Duplicate Address Detection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DadEvent {
+    /// No other node answered for the address, which is now assigned to the interface.
+    Assigned(Ipv6Cidr),
+    /// Another node uses the address, which was dropped.
+    Conflict(Ipv6Cidr),
+}
+
+/// An address that is not assigned until Duplicate Address Detection completes.
+#[derive(Debug, Clone, Copy)]
+struct Tentative {
+    cidr: Ipv6Cidr,
+    /// Number of Neighbor Solicitations still to send.
+    probes_left: u8,
+    /// When to send the next solicitation, or to assign the address once none is left.
+    retry_at: Instant,
+}
+
+/// State of IPv6 Duplicate Address Detection (RFC 4862 § 5.4).
+#[derive(Debug, Default)]
+pub(crate) struct Dad {
+    pub(super) transmits: u8,
+    tentative: Vec<Tentative, IFACE_MAX_ADDR_COUNT>,
+    events: Deque<DadEvent, IFACE_MAX_ADDR_COUNT>,
+}
+
+impl Interface {
+    /// Get the number of Neighbor Solicitations sent to detect duplicate addresses.
+    pub fn dad_transmits(&self) -> u8 {
+        self.inner.dad.transmits
+    }
+
+    /// Set the number of Neighbor Solicitations sent to detect duplicate addresses
+    /// (RFC 4862 § 5.4).
+    ///
+    /// When non-zero, the IPv6 unicast addresses added by [`update_ip_addrs`] or by
+    /// stateless autoconfiguration are tentative: they are only assigned to the interface
+    /// once that many solicitations, one second apart, went unanswered. Addresses that
+    /// another node turns out to use are dropped instead. Both outcomes are reported by
+    /// [`poll_dad_event`].
+    ///
+    /// Zero disables Duplicate Address Detection, which is the default.
+    ///
+    /// [`update_ip_addrs`]: Self::update_ip_addrs
+    /// [`poll_dad_event`]: Self::poll_dad_event
+    pub fn set_dad_transmits(&mut self, transmits: u8) {
+        self.inner.dad.transmits = transmits;
+    }
+
+    /// Get the addresses on which Duplicate Address Detection is in progress.
+    pub fn tentative_ipv6_addrs(&self) -> impl Iterator<Item = Ipv6Cidr> + '_ {
+        self.inner.dad.tentative.iter().map(|t| t.cidr)
+    }
+
+    /// Return the next Duplicate Address Detection event, if any.
+    ///
+    /// On a conflict, the application should pick another address, e.g. by changing
+    /// the interface identifier used by stateless autoconfiguration.
+    pub fn poll_dad_event(&mut self) -> Option<DadEvent> {
+        self.inner.dad.events.pop_front()
+    }
+
+    /// Send the Neighbor Solicitations for tentative addresses, and assign those that
+    /// went unanswered.
+    pub(crate) fn dad_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        let Some(index) = self
+            .inner
+            .dad
+            .tentative
+            .iter()
+            .position(|t| t.retry_at <= now)
+        else {
+            return false;
+        };
+        let tentative = self.inner.dad.tentative[index];
+
+        if tentative.probes_left == 0 {
+            self.inner.dad.tentative.swap_remove(index);
+            net_debug!("dad: assigned {}", tentative.cidr);
+            // NOTE(unwrap): tentative addresses are counted against the capacity.
+            self.inner
+                .ip_addrs
+                .push(IpCidr::Ipv6(tentative.cidr))
+                .unwrap();
+            self.inner.addrs_changed();
+            self.inner.dad_event(DadEvent::Assigned(tentative.cidr));
+            self.inner.announce_start(tentative.cidr.address().into());
+            return true;
+        }
+
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+        let target_addr = tentative.cidr.address();
+        let solicit = Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+            target_addr,
+            lladdr: None,
+        });
+        let ip_repr = Ipv6Repr {
+            src_addr: Ipv6Address::UNSPECIFIED,
+            dst_addr: target_addr.solicited_node(),
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 0xff,
+            payload_len: solicit.buffer_len(),
+        };
+        net_trace!("dad: probing {}", target_addr);
+        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+        self.inner
+            .dispatch_ip(
+                tx_token,
+                PacketMeta::default(),
+                IpPacket::new_ipv6(ip_repr, IpPayload::Icmpv6(solicit)),
+                &mut self.fragmenter,
+            )
+            .unwrap();
+
+        let tentative = &mut self.inner.dad.tentative[index];
+        tentative.probes_left -= 1;
+        tentative.retry_at = now + RETRANS_TIMER;
+        true
+    }
+}
+
+impl InterfaceInner {
+    /// Start detecting whether `cidr` is used by another node, or assign it right away
+    /// if Duplicate Address Detection is disabled.
+    pub(super) fn dad_start(&mut self, cidr: Ipv6Cidr) -> Result<(), ()> {
+        if self.ip_addrs.len() + self.dad.tentative.len() >= IFACE_MAX_ADDR_COUNT {
+            return Err(());
+        }
+        if self.dad.transmits == 0 {
+            self.ip_addrs.push(IpCidr::Ipv6(cidr)).map_err(|_| ())?;
+            self.addrs_changed();
+            self.announce_start(cidr.address().into());
+            Ok(())
+        } else {
+            self.dad
+                .tentative
+                .push(self.tentative(cidr))
+                .map_err(|_| ())
+        }
+    }
+
+    fn tentative(&self, cidr: Ipv6Cidr) -> Tentative {
+        Tentative {
+            cidr,
+            probes_left: self.dad.transmits,
+            retry_at: self.now,
+        }
+    }
+
+    /// Return the tentative address `addr`, if Duplicate Address Detection is in progress
+    /// for it.
+    pub(super) fn dad_tentative_mut(&mut self, addr: Ipv6Address) -> Option<&mut Ipv6Cidr> {
+        self.dad
+            .tentative
+            .iter_mut()
+            .find(|t| t.cidr.address() == addr)
+            .map(|t| &mut t.cidr)
+    }
+
+    /// Stop detecting whether `addr` is used by another node.
+    pub(super) fn dad_cancel(&mut self, addr: Ipv6Address) {
+        self.dad.tentative.retain(|t| t.cidr.address() != addr);
+    }
+
+    /// Move the tentative addresses into `ip_addrs`, returning the assigned ones, so that
+    /// they can be updated together.
+    pub(super) fn dad_merge(&mut self) -> Vec<IpCidr, IFACE_MAX_ADDR_COUNT> {
+        let assigned = self.ip_addrs.clone();
+        for tentative in &self.dad.tentative {
+            // NOTE(unwrap): tentative addresses are counted against the capacity.
+            self.ip_addrs.push(IpCidr::Ipv6(tentative.cidr)).unwrap();
+        }
+        assigned
+    }
+
+    /// Move the addresses that were not assigned before `dad_merge` back out of `ip_addrs`.
+    pub(super) fn dad_split(&mut self, assigned: &[IpCidr]) {
+        let previous = core::mem::take(&mut self.dad.tentative);
+        let transmits = self.dad.transmits;
+
+        let mut index = 0;
+        while index < self.ip_addrs.len() {
+            let cidr = match self.ip_addrs[index] {
+                IpCidr::Ipv6(cidr) => cidr,
+                #[allow(unreachable_patterns)]
+                _ => {
+                    index += 1;
+                    continue;
+                }
+            };
+            let addr = cidr.address();
+            let assigned = assigned.iter().any(|a| a.address() == addr.into());
+
+            let tentative = match previous.iter().find(|t| t.cidr.address() == addr) {
+                Some(previous) => Tentative { cidr, ..*previous },
+                None if transmits > 0 && !assigned && addr.is_unicast() && !addr.is_loopback() => {
+                    self.tentative(cidr)
+                }
+                None => {
+                    index += 1;
+                    continue;
+                }
+            };
+            self.ip_addrs.remove(index);
+            // NOTE(unwrap): the tentative address was just removed from `ip_addrs`.
+            self.dad.tentative.push(tentative).unwrap();
+        }
+    }
+
+    /// Handle a Neighbor Solicitation sent by a node performing Duplicate Address
+    /// Detection, from the unspecified address.
+    pub(super) fn process_dad_solicit<'frame>(
+        &mut self,
+        ip_repr: &Ipv6Repr,
+        ip_payload: &'frame [u8],
+    ) -> Option<IpPacket<'frame>> {
+        let icmp_packet = check!(Icmpv6Packet::new_checked(ip_payload));
+        let icmp_repr = check!(Icmpv6Repr::parse(
+            &ip_repr.src_addr.into(),
+            &ip_repr.dst_addr.into(),
+            &icmp_packet,
+            &self.caps.checksum,
+        ));
+        let Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+            target_addr,
+            lladdr: None,
+        }) = icmp_repr
+        else {
+            return None;
+        };
+        if ip_repr.hop_limit != 0xff || !ip_repr.dst_addr.is_multicast() {
+            return None;
+        }
+
+        // Another node is trying to use the same address.
+        if self.dad_conflict(target_addr) {
+            return None;
+        }
+
+        // Defend the addresses we own (RFC 4861 § 7.2.4).
+        if !self.has_ip_addr(target_addr) {
+            return None;
+        }
+        let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+            flags: NdiscNeighborFlags::OVERRIDE,
+            target_addr,
+            lladdr: Some(self.hardware_addr.into()),
+        });
+        let ip_repr = Ipv6Repr {
+            src_addr: target_addr,
+            dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 0xff,
+            payload_len: advert.buffer_len(),
+        };
+        Some(IpPacket::new_ipv6(ip_repr, IpPayload::Icmpv6(advert)))
+    }
+
+    /// Drop `addr` if it is tentative, because another node uses it.
+    pub(super) fn dad_conflict(&mut self, addr: Ipv6Address) -> bool {
+        let Some(index) = self
+            .dad
+            .tentative
+            .iter()
+            .position(|t| t.cidr.address() == addr)
+        else {
+            return false;
+        };
+        let tentative = self.dad.tentative.swap_remove(index);
+        net_debug!("dad: {} is used by another node", tentative.cidr);
+        self.dad_event(DadEvent::Conflict(tentative.cidr));
+        true
+    }
+
+    fn dad_event(&mut self, event: DadEvent) {
+        if self.dad.events.is_full() {
+            self.dad.events.pop_front();
+        }
+        // NOTE(unwrap): room was just made.
+        self.dad.events.push_back(event).unwrap();
+    }
+
+    pub(super) fn dad_poll_at(&self) -> Option<Instant> {
+        self.dad.tentative.iter().map(|t| t.retry_at).min()
+    }
+}
@@ -0,0 +1,184 @@
+use heapless::Vec;
+
+use super::{Interface, InterfaceInner};
+use crate::config::IFACE_MAX_ROUTE_COUNT;
+use crate::iface::Route;
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// Number of unanswered Neighbor Solicitations after which a router is deemed
+/// unreachable (RFC 4861 § 10, MAX_MULTICAST_SOLICIT).
+const MAX_MULTICAST_SOLICIT: u8 = 3;
+
+/// A router that advertised itself as a default router.
+#[derive(Debug, Clone, Copy)]
+struct DefaultRouter {
+    addr: Ipv6Address,
+    expires_at: Instant,
+    /// Neighbor Solicitations sent to the router since it was last heard from.
+    solicits: u8,
+}
+
+impl DefaultRouter {
+    fn reachable(&self) -> bool {
+        self.solicits < MAX_MULTICAST_SOLICIT
+    }
+}
+
+/// The Default Router List (RFC 4861 § 5.1).
+#[derive(Debug, Default)]
+pub(crate) struct DefaultRouters {
+    routers: Vec<DefaultRouter, IFACE_MAX_ROUTE_COUNT>,
+    /// The router that the default route goes through, if any.
+    current: Option<Ipv6Address>,
+}
+
+impl Interface {
+    /// Get the default routers learned from Router Advertisements, in the order they were
+    /// learned.
+    ///
+    /// The default route goes through one of them at a time, which is kept for as long as
+    /// its advertised lifetime lasts and it stays reachable. Once three Neighbor
+    /// Solicitations to it go unanswered, or its lifetime runs out, the default route
+    /// moves to the next router that is still reachable, or else to the next one in turn.
+    /// A router is deemed reachable again once it answers a solicitation or sends a new
+    /// Router Advertisement.
+    pub fn ipv6_default_routers(&self) -> impl Iterator<Item = Ipv6Address> + '_ {
+        self.inner.default_routers.routers.iter().map(|r| r.addr)
+    }
+}
+
+impl InterfaceInner {
+    /// Update the Default Router List from a Router Advertisement of `router`
+    /// (RFC 4861 § 6.3.4).
+    pub(super) fn default_router_update(&mut self, router: Ipv6Address, lifetime: Duration) {
+        let now = self.now;
+        let routers = &mut self.default_routers.routers;
+        match routers.iter().position(|r| r.addr == router) {
+            Some(index) if lifetime == Duration::ZERO => {
+                net_debug!("ndisc: removing default router {}", router);
+                routers.remove(index);
+            }
+            Some(index) => {
+                routers[index].expires_at = now + lifetime;
+                routers[index].solicits = 0;
+            }
+            None if lifetime == Duration::ZERO => return,
+            None => {
+                net_debug!("ndisc: adding default router {}", router);
+                let entry = DefaultRouter {
+                    addr: router,
+                    expires_at: now + lifetime,
+                    solicits: 0,
+                };
+                if routers.push(entry).is_err() {
+                    net_debug!("ndisc: default router list full, ignoring {}", router);
+                    return;
+                }
+            }
+        }
+        self.default_router_select();
+    }
+
+    /// Forget the routers whose advertised lifetime ran out.
+    pub(super) fn default_router_expire(&mut self) {
+        let now = self.now;
+        let routers = &mut self.default_routers.routers;
+        let len = routers.len();
+        routers.retain(|r| r.expires_at > now);
+        if routers.len() != len {
+            net_debug!("ndisc: default router expired");
+            self.default_router_select();
+        }
+    }
+
+    /// Count a Neighbor Solicitation sent to `addr`, and fail over to another router if it
+    /// is the current one and the previous solicitations went unanswered (RFC 4861 § 7.3.3).
+    pub(super) fn default_router_solicited(&mut self, addr: Ipv6Address) {
+        let list = &mut self.default_routers;
+        let Some(router) = list.routers.iter_mut().find(|r| r.addr == addr) else {
+            return;
+        };
+        if router.reachable() {
+            router.solicits += 1;
+        } else if list.current == Some(addr) {
+            net_debug!("ndisc: default router {} is unreachable", addr);
+            self.default_router_select();
+        }
+    }
+
+    /// Note that `addr` answered a Neighbor Solicitation.
+    pub(super) fn default_router_reachable(&mut self, addr: Ipv6Address) {
+        if let Some(router) = self
+            .default_routers
+            .routers
+            .iter_mut()
+            .find(|r| r.addr == addr)
+        {
+            router.solicits = 0;
+        }
+    }
+
+    /// Pick the router that the default route goes through, and install the route
+    /// (RFC 4861 § 6.3.6).
+    fn default_router_select(&mut self) {
+        let list = &mut self.default_routers;
+        let current = list
+            .current
+            .and_then(|addr| list.routers.iter().position(|r| r.addr == addr));
+        let len = list.routers.len();
+        let selected = match current {
+            Some(index) if list.routers[index].reachable() => Some(index),
+            _ if len == 0 => None,
+            _ => {
+                // Prefer the next reachable router, else try the next one in turn.
+                let start = current.map_or(0, |index| index + 1);
+                let index = (0..len)
+                    .map(|i| (start + i) % len)
+                    .find(|&i| list.routers[i].reachable())
+                    .unwrap_or(start % len);
+                list.routers[index].solicits = 0;
+                Some(index)
+            }
+        };
+
+        let router = selected.map(|index| list.routers[index]);
+        if router.map(|r| r.addr) != list.current {
+            if let Some(router) = router {
+                net_debug!("ndisc: default router is now {}", router.addr);
+            }
+            list.current = router.map(|r| r.addr);
+            self.routes_changed();
+        }
+
+        self.destinations.flush_next_hops();
+        self.routes.update(|routes| {
+            let default = Route::new_ipv6_gateway(Ipv6Address::UNSPECIFIED);
+            // Routes that never expire are static, and take precedence. Policy routes only
+            // apply to some packets, and leave the default route to the router.
+            let is_default = |route: &Route| route.cidr == default.cidr && !route.is_policy();
+            routes.retain(|route| !is_default(route) || route.expires_at.is_none());
+            if routes.iter().any(is_default) {
+                return;
+            }
+            let Some(router) = router else {
+                return;
+            };
+            let route = Route {
+                expires_at: Some(router.expires_at),
+                ..Route::new_ipv6_gateway(router.addr)
+            };
+            if routes.push(route).is_err() {
+                net_debug!("ndisc: route table full, ignoring router {}", router.addr);
+            }
+        });
+    }
+
+    pub(super) fn default_router_poll_at(&self) -> Option<Instant> {
+        self.default_routers
+            .routers
+            .iter()
+            .map(|r| r.expires_at)
+            .min()
+    }
+}
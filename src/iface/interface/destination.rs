@@ -0,0 +1,237 @@
+use heapless::Vec;
+
+use super::{Interface, InterfaceInner};
+use crate::config::IFACE_DESTINATION_CACHE_COUNT;
+use crate::iface::route::RouteKey;
+use crate::time::{Duration, Instant};
+use crate::wire::IpAddress;
+
+/// An entry of the destination cache, see [`Interface::destinations`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DestinationEntry {
+    pub dst_addr: IpAddress,
+    /// The router that the packets to the destination were last sent through, if any.
+    pub next_hop: Option<IpAddress>,
+    /// The MTU of the path towards the destination, if a Packet Too Big message reported
+    /// one smaller than the MTU of the interface.
+    pub path_mtu: Option<usize>,
+    /// The smoothed round-trip time of the TCP connections to the destination.
+    pub rtt: Option<Duration>,
+    /// The maximum segment size of the TCP connections to the destination.
+    pub mss: Option<u16>,
+}
+
+/// A router found by a route lookup.
+#[derive(Debug, Clone, Copy)]
+struct NextHop {
+    /// The properties of the packets that the lookup was made for.
+    key: RouteKey,
+    addr: IpAddress,
+    /// When the route or the redirect that the router came from expires, if ever.
+    expires_at: Option<Instant>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Destination {
+    dst_addr: IpAddress,
+    next_hop: Option<NextHop>,
+    path_mtu: Option<(usize, Instant)>,
+    rtt: Option<Duration>,
+    mss: Option<u16>,
+    used_at: Instant,
+}
+
+/// What the interface knows about the destinations it sent packets to (RFC 4861 § 5.1,
+/// Destination Cache), so that the packets of a flow don't all go through the routing
+/// table, and that new TCP connections start from what the previous ones learned.
+#[derive(Debug, Default)]
+pub(crate) struct DestinationCache {
+    entries: Vec<Destination, IFACE_DESTINATION_CACHE_COUNT>,
+}
+
+impl DestinationCache {
+    fn get(&self, addr: &IpAddress) -> Option<&Destination> {
+        self.entries.iter().find(|d| d.dst_addr == *addr)
+    }
+
+    /// Get the entry of `addr`, adding it in place of the least recently used one if
+    /// there is none.
+    fn get_mut(&mut self, addr: &IpAddress, timestamp: Instant) -> &mut Destination {
+        let index = match self.entries.iter().position(|d| d.dst_addr == *addr) {
+            Some(index) => index,
+            None => {
+                if self.entries.is_full() {
+                    let index = self
+                        .entries
+                        .iter()
+                        .enumerate()
+                        .min_by_key(|(_, d)| d.used_at)
+                        .map(|(index, _)| index)
+                        .unwrap();
+                    self.entries.swap_remove(index);
+                }
+                // NOTE(unwrap): room was just made.
+                self.entries
+                    .push(Destination {
+                        dst_addr: *addr,
+                        next_hop: None,
+                        path_mtu: None,
+                        rtt: None,
+                        mss: None,
+                        used_at: timestamp,
+                    })
+                    .unwrap();
+                self.entries.len() - 1
+            }
+        };
+        let entry = &mut self.entries[index];
+        entry.used_at = timestamp;
+        entry
+    }
+
+    /// Return the router that the packets to `addr` with the properties `key` go through,
+    /// if it was looked up already.
+    pub(super) fn next_hop(
+        &self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        timestamp: Instant,
+    ) -> Option<IpAddress> {
+        self.get(addr)?
+            .next_hop
+            .filter(|n| n.key == *key && n.expires_at.map_or(true, |t| t >= timestamp))
+            .map(|n| n.addr)
+    }
+
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    fn set_next_hop(
+        &mut self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        next_hop: IpAddress,
+        expires_at: Option<Instant>,
+        timestamp: Instant,
+    ) {
+        self.get_mut(addr, timestamp).next_hop = Some(NextHop {
+            key: *key,
+            addr: next_hop,
+            expires_at,
+        });
+    }
+
+    /// Forget the routers looked up so far, after the routes changed.
+    pub(super) fn flush_next_hops(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.next_hop = None;
+        }
+    }
+
+    /// Return the path MTU learned for `addr`, if any.
+    pub(super) fn path_mtu(&self, addr: &IpAddress, timestamp: Instant) -> Option<usize> {
+        self.get(addr)?
+            .path_mtu
+            .filter(|(_, expires_at)| *expires_at > timestamp)
+            .map(|(mtu, _)| mtu)
+    }
+
+    #[cfg(feature = "proto-ipv6")]
+    pub(super) fn set_path_mtu(
+        &mut self,
+        addr: &IpAddress,
+        mtu: usize,
+        expires_at: Instant,
+        timestamp: Instant,
+    ) {
+        self.get_mut(addr, timestamp).path_mtu = Some((mtu, expires_at));
+    }
+
+    fn entries(&self, timestamp: Instant) -> impl Iterator<Item = DestinationEntry> + '_ {
+        self.entries.iter().map(move |d| DestinationEntry {
+            dst_addr: d.dst_addr,
+            next_hop: d
+                .next_hop
+                .filter(|n| n.expires_at.map_or(true, |t| t >= timestamp))
+                .map(|n| n.addr),
+            path_mtu: self.path_mtu(&d.dst_addr, timestamp),
+            rtt: d.rtt,
+            mss: d.mss,
+        })
+    }
+}
+
+impl Interface {
+    /// Get the entries of the destination cache.
+    ///
+    /// The interface remembers the destinations it sends packets to, along with the
+    /// router they go through, so that the packets of a flow don't all go through the
+    /// routing table; the routers are looked up again whenever the routes change. It also
+    /// keeps the path MTUs learned from Packet Too Big messages, and the round-trip time
+    /// of the TCP connections to each destination, which new connections start from
+    /// instead of the default estimate. The least recently used destination makes room
+    /// for new ones once the cache holds `IFACE_DESTINATION_CACHE_COUNT` of them.
+    pub fn destinations(&self) -> impl Iterator<Item = DestinationEntry> + '_ {
+        self.inner.destinations.entries(self.inner.now)
+    }
+
+    /// Set hints for the TCP connections to `addr`: their initial round-trip time
+    /// estimate, and the maximum segment size they advertise, e.g. when a tunnel with a
+    /// small MTU is known to sit on the path. `None` leaves a hint unchanged.
+    ///
+    /// The round-trip time is then updated by the connections, see
+    /// [`destinations`](Self::destinations).
+    pub fn set_destination_hints(
+        &mut self,
+        addr: IpAddress,
+        rtt: Option<Duration>,
+        mss: Option<u16>,
+    ) {
+        let entry = self.inner.destinations.get_mut(&addr, self.inner.now);
+        if rtt.is_some() {
+            entry.rtt = rtt;
+        }
+        if mss.is_some() {
+            entry.mss = mss;
+        }
+    }
+
+    /// Empty the destination cache.
+    pub fn flush_destinations(&mut self) {
+        self.inner.destinations.entries.clear();
+    }
+}
+
+impl InterfaceInner {
+    /// Return the next hop towards `addr` for the packets with the properties `key`, and
+    /// remember the router it goes through for the next packets.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub(super) fn route_cached(&mut self, addr: &IpAddress, key: &RouteKey) -> Option<IpAddress> {
+        if let Some(next_hop) = self.route_direct(addr) {
+            return Some(next_hop);
+        }
+        if let Some(next_hop) = self.destinations.next_hop(addr, key, self.now) {
+            return Some(next_hop);
+        }
+        let (next_hop, expires_at) = self.route_via_router(addr, key, self.now)?;
+        self.destinations
+            .set_next_hop(addr, key, next_hop, expires_at, self.now);
+        Some(next_hop)
+    }
+
+    /// Return the round-trip time hint of the TCP connections to `addr`, if any.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn rtt_hint(&self, addr: &IpAddress) -> Option<Duration> {
+        self.destinations.get(addr)?.rtt
+    }
+
+    /// Remember the smoothed round-trip time of a TCP connection to `addr`.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn set_rtt_hint(&mut self, addr: &IpAddress, rtt: Duration) {
+        self.destinations.get_mut(addr, self.now).rtt = Some(rtt);
+    }
+
+    /// Return the maximum segment size hint of the TCP connections to `addr`, if any.
+    pub(super) fn mss_hint(&self, addr: &IpAddress) -> Option<u16> {
+        self.destinations.get(addr)?.mss
+    }
+}
@@ -0,0 +1,721 @@
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+use super::*;
+
+/// The outcome of forwarding a packet through another interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    not(any(feature = "medium-ethernet", feature = "medium-ip")),
+    allow(dead_code)
+)]
+pub(crate) enum Forwarded {
+    /// The packet was sent, or dropped without an error to report.
+    Done,
+    /// No other interface has a route to the destination.
+    NoRoute,
+    /// The packet is larger than the MTU of the outgoing interface, which is given, and
+    /// cannot be fragmented.
+    TooBig(usize),
+    /// The filter of the outgoing interface rejected the packet.
+    Prohibited,
+}
+
+/// The other interfaces of a router, through which the packets received by an interface
+/// are forwarded.
+#[cfg_attr(
+    not(any(feature = "medium-ethernet", feature = "medium-ip")),
+    allow(dead_code)
+)]
+pub(crate) trait Forward {
+    /// Send a packet received by the interface through the other interface with the most
+    /// specific route to its destination.
+    fn forward(&mut self, packet: &[u8]) -> Forwarded;
+
+    /// Return the highest rank of the routes of the other interfaces towards `addr`, if any,
+    /// see [`Interface::forward_rank`].
+    fn forward_rank(&self, addr: &crate::wire::IpAddress) -> Option<(u8, u8)>;
+
+    /// Return whether `addr` is an address of one of the other interfaces.
+    fn is_local(&self, addr: &crate::wire::IpAddress) -> bool;
+
+    /// Process a packet received by the interface for `addr`, an address of one of the
+    /// other interfaces, with the sockets of that interface, and return its response.
+    fn deliver<'f>(
+        &mut self,
+        addr: &crate::wire::IpAddress,
+        packet: &'f [u8],
+        fragments: &'f mut super::FragmentsBuffer,
+    ) -> Option<crate::iface::ip_packet::IpPacket<'f>>;
+}
+
+/// How the source address of the packets received by an interface is checked against the
+/// routes of the interface, or those of the other interfaces of its router, to drop spoofed
+/// packets (RFC 3704).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReversePathFilter {
+    /// The source address is not checked.
+    #[default]
+    Disabled,
+    /// The source address must be reachable through any interface.
+    Loose,
+    /// The best route to the source address must go through the interface the packet was
+    /// received on.
+    Strict,
+}
+
+/// Whether the interfaces of a router accept the packets for the addresses of the other
+/// interfaces (RFC 1122 § 3.3.4.2).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum HostModel {
+    /// The packets received for an address of another interface are dropped.
+    #[default]
+    Strong,
+    /// The packets received for an address of another interface are delivered to its
+    /// sockets, as if that interface had received them.
+    Weak,
+}
+
+/// The ICMP error sent back for a packet that cannot be forwarded.
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum ForwardError {
+    TimeExceeded,
+    NoRoute,
+    TooBig(usize),
+    Prohibited,
+}
+
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+impl Interface {
+    /// Return the rank of the route of the interface towards `addr` for the packets with
+    /// the properties `key`, if any, the highest being preferred. The networks of its
+    /// addresses come first, then its policy routes, then its other routes, the most
+    /// specific first. There is no route while the link of the interface is down.
+    pub(crate) fn forward_rank(
+        &self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        timestamp: Instant,
+    ) -> Option<(u8, u8)> {
+        self.inner.forward_rank(addr, key, timestamp)
+    }
+
+    /// Return whether `addr` is a unicast or anycast address of the interface, whose link
+    /// is up.
+    pub(crate) fn owns_addr(&self, addr: &IpAddress) -> bool {
+        if !self.inner.link_up() {
+            return false;
+        }
+        match *addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(addr) => self.inner.has_ip_addr(addr),
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => {
+                self.inner.has_ip_addr(addr) || self.inner.has_anycast_addr(addr)
+            }
+        }
+    }
+
+    /// Process a packet received by another interface for one of the addresses of this
+    /// one, with the sockets of this one, and return its response, which the receiving
+    /// interface sends.
+    pub(crate) fn deliver<'f>(
+        &mut self,
+        timestamp: Instant,
+        sockets: &mut SocketSet<'_>,
+        packet: &'f [u8],
+        fragments: &'f mut FragmentsBuffer,
+    ) -> Option<IpPacket<'f>> {
+        self.inner.now = timestamp;
+        self.inner
+            .process_ip(sockets, PacketMeta::default(), packet, fragments)
+    }
+
+    /// Send a packet received by another interface towards its destination, with its TTL
+    /// or hop limit decremented.
+    ///
+    /// IPv4 packets larger than the MTU of the interface are fragmented, unless they have
+    /// the don't fragment flag set or are fragments already. Packets whose destination is
+    /// not in the neighbor cache yet are dropped, while the address is resolved, and so are
+    /// directed broadcasts unless enabled.
+    pub(crate) fn forward_egress<D>(
+        &mut self,
+        timestamp: Instant,
+        device: &mut D,
+        packet: &[u8],
+    ) -> Forwarded
+    where
+        D: Device + ?Sized,
+    {
+        self.inner.now = timestamp;
+
+        let dst_addr: IpAddress = match IpVersion::of_packet(packet) {
+            #[cfg(feature = "proto-ipv4")]
+            Ok(IpVersion::Ipv4) => Ipv4PacketWire::new_unchecked(packet).dst_addr().into(),
+            #[cfg(feature = "proto-ipv6")]
+            Ok(IpVersion::Ipv6) => Ipv6PacketWire::new_unchecked(packet).dst_addr().into(),
+            _ => return Forwarded::Done,
+        };
+
+        match self.inner.filter_forwarded(FilterDirection::Egress, packet) {
+            FilterAction::Accept => (),
+            FilterAction::Drop => return Forwarded::Done,
+            FilterAction::Reject => return Forwarded::Prohibited,
+        }
+
+        #[cfg(feature = "proto-ipv4")]
+        if !self.inner.directed_broadcast && self.inner.is_broadcast(&dst_addr) {
+            net_debug!("forward: dropping directed broadcast to {}", dst_addr);
+            return Forwarded::Done;
+        }
+
+        let ip_mtu = self.inner.ip_mtu();
+        if packet.len() > ip_mtu {
+            #[cfg(feature = "proto-ipv4-fragmentation")]
+            if let Ok(IpVersion::Ipv4) = IpVersion::of_packet(packet) {
+                return self.forward_fragmented(device, packet);
+            }
+            return Forwarded::TooBig(ip_mtu);
+        }
+
+        let Some(tx_token) = device.transmit(timestamp) else {
+            net_debug!(
+                "forward: dropping packet for {}, device exhausted",
+                dst_addr
+            );
+            return Forwarded::Done;
+        };
+        let checksum_caps = self.inner.caps.checksum.clone();
+
+        match self.inner.caps.medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => {
+                let Some(src_addr) = self.inner.get_source_address(dst_addr) else {
+                    return Forwarded::NoRoute;
+                };
+                let (hardware_addr, tx_token) = match self.inner.lookup_hardware_addr(
+                    tx_token,
+                    &src_addr,
+                    &dst_addr,
+                    &forward_route_key(packet),
+                    &mut self.fragmenter,
+                ) {
+                    Ok(result) => result,
+                    Err(DispatchError::NoRoute) => return Forwarded::NoRoute,
+                    Err(DispatchError::NeighborPending) => {
+                        net_debug!(
+                            "forward: dropping packet for {}, neighbor pending",
+                            dst_addr
+                        );
+                        return Forwarded::Done;
+                    }
+                };
+                let ethertype = match dst_addr {
+                    #[cfg(feature = "proto-ipv4")]
+                    IpAddress::Ipv4(_) => EthernetProtocol::Ipv4,
+                    #[cfg(feature = "proto-ipv6")]
+                    IpAddress::Ipv6(_) => EthernetProtocol::Ipv6,
+                };
+                net_trace!("forward: sending packet for {}", dst_addr);
+                let result = self
+                    .inner
+                    .dispatch_ethernet(tx_token, packet.len(), |mut frame| {
+                        frame.set_dst_addr(hardware_addr.ethernet_or_panic());
+                        frame.set_ethertype(ethertype);
+                        let payload = frame.payload_mut();
+                        payload.copy_from_slice(packet);
+                        decrement_hop_limit(payload, &checksum_caps);
+                    });
+                if let Err(err) = result {
+                    net_debug!("forward: failed to send packet: {:?}", err);
+                }
+            }
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => {
+                net_trace!("forward: sending packet for {}", dst_addr);
+                self.inner.stats.count_tx(packet.len());
+                tx_token.consume(packet.len(), |buffer| {
+                    buffer.copy_from_slice(packet);
+                    decrement_hop_limit(buffer, &checksum_caps);
+                });
+            }
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => (),
+        }
+        Forwarded::Done
+    }
+
+    /// Send an IPv4 packet larger than the MTU of the interface in fragments, if it may be
+    /// fragmented.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    fn forward_fragmented<D>(&mut self, device: &mut D, packet: &[u8]) -> Forwarded
+    where
+        D: Device + ?Sized,
+    {
+        let ipv4_packet = Ipv4PacketWire::new_unchecked(packet);
+        if ipv4_packet.dont_frag() || ipv4_packet.more_frags() || ipv4_packet.frag_offset() != 0 {
+            return Forwarded::TooBig(self.inner.ip_mtu());
+        }
+        if !self.fragmenter.is_empty() {
+            net_debug!("forward: dropping packet, fragmenter busy");
+            return Forwarded::Done;
+        }
+        let Some(tx_token) = device.transmit(self.inner.now) else {
+            return Forwarded::Done;
+        };
+
+        // The fragments are sent without the options of the packet.
+        let ipv4_repr = Ipv4Repr {
+            src_addr: ipv4_packet.src_addr(),
+            dst_addr: ipv4_packet.dst_addr(),
+            next_header: ipv4_packet.next_header(),
+            payload_len: ipv4_packet.payload().len(),
+            hop_limit: ipv4_packet.hop_limit() - 1,
+        };
+        let mut packet = IpPacket::new_ipv4(ipv4_repr, IpPayload::Raw(ipv4_packet.payload()));
+        packet.set_traffic_class(ipv4_packet.dscp() << 2 | ipv4_packet.ecn());
+        packet.set_dont_frag(false);
+
+        net_trace!("forward: fragmenting packet for {}", ipv4_repr.dst_addr);
+        match self.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            packet,
+            &mut self.fragmenter,
+        ) {
+            Err(DispatchError::NoRoute) => Forwarded::NoRoute,
+            _ => Forwarded::Done,
+        }
+    }
+}
+
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+impl InterfaceInner {
+    fn forward_rank(
+        &self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        timestamp: Instant,
+    ) -> Option<(u8, u8)> {
+        if !self.link_up() {
+            return None;
+        }
+        let on_link = self
+            .ip_addrs
+            .iter()
+            .filter(|cidr| cidr.contains_addr(addr))
+            .map(|cidr| cidr.prefix_len())
+            .max();
+        match on_link {
+            Some(prefix_len) => Some((2, prefix_len)),
+            None => self
+                .routes
+                .lookup_rank(addr, key, timestamp)
+                .map(|(is_policy, prefix_len)| (is_policy as u8, prefix_len)),
+        }
+    }
+
+    /// Check the source address of a received frame with the reverse-path filter, given the
+    /// other interfaces of the router, if any, and return whether the frame is to be dropped.
+    ///
+    /// Only IP packets are checked, and not those from an unspecified or link-local address,
+    /// which are only valid on their link. Those from a broadcast or multicast address are
+    /// always dropped.
+    pub(super) fn reverse_path_drop(&self, frame: &[u8], forward: Option<&dyn Forward>) -> bool {
+        if self.reverse_path_filter == ReversePathFilter::Disabled {
+            return false;
+        }
+        let packet = match self.caps.medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => match EthernetFrame::new_checked(frame) {
+                Ok(eth_frame) => match eth_frame.ethertype() {
+                    #[cfg(feature = "proto-ipv4")]
+                    EthernetProtocol::Ipv4 => eth_frame.payload(),
+                    #[cfg(feature = "proto-ipv6")]
+                    EthernetProtocol::Ipv6 => eth_frame.payload(),
+                    _ => return false,
+                },
+                Err(_) => return false,
+            },
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => frame,
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => return false,
+        };
+
+        let (src_addr, link_local): (IpAddress, _) = match IpVersion::of_packet(packet) {
+            #[cfg(feature = "proto-ipv4")]
+            Ok(IpVersion::Ipv4) => match Ipv4PacketWire::new_checked(packet) {
+                Ok(ipv4_packet) => {
+                    let src_addr = ipv4_packet.src_addr();
+                    (src_addr.into(), src_addr.is_link_local())
+                }
+                Err(_) => return false,
+            },
+            #[cfg(feature = "proto-ipv6")]
+            Ok(IpVersion::Ipv6) => match Ipv6PacketWire::new_checked(packet) {
+                Ok(ipv6_packet) => {
+                    let src_addr = ipv6_packet.src_addr();
+                    (src_addr.into(), src_addr.is_link_local())
+                }
+                Err(_) => return false,
+            },
+            _ => return false,
+        };
+        if src_addr.is_unspecified() || link_local {
+            return false;
+        }
+        // No route leads back to a broadcast or multicast source.
+        if !src_addr.is_unicast() {
+            net_debug!(
+                "iface: dropping packet from {}, failing the reverse path filter",
+                src_addr
+            );
+            return true;
+        }
+
+        let rank = self.forward_rank(&src_addr, &RouteKey::default(), self.now);
+        let other_rank = forward.and_then(|forward| forward.forward_rank(&src_addr));
+        let accepted = match self.reverse_path_filter {
+            ReversePathFilter::Disabled => true,
+            ReversePathFilter::Loose => rank.is_some() || other_rank.is_some(),
+            ReversePathFilter::Strict => rank.is_some() && rank >= other_rank,
+        };
+        if !accepted {
+            net_debug!(
+                "iface: dropping packet from {}, failing the reverse path filter",
+                src_addr
+            );
+        }
+        !accepted
+    }
+
+    /// Return the IP packet of a received frame if it is to be routed through another
+    /// interface: a unicast packet for an address that is not one of ours, and which may
+    /// leave the link it was received on.
+    pub(super) fn forward_packet<'frame>(&self, frame: &'frame [u8]) -> Option<&'frame [u8]> {
+        let packet = match self.caps.medium {
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => {
+                let eth_frame = EthernetFrame::new_checked(frame).ok()?;
+                if eth_frame.dst_addr() != self.hardware_addr.ethernet_or_panic() {
+                    return None;
+                }
+                eth_frame.payload()
+            }
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => frame,
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => return None,
+        };
+
+        match IpVersion::of_packet(packet).ok()? {
+            #[cfg(feature = "proto-ipv4")]
+            IpVersion::Ipv4 => {
+                let ipv4_packet = Ipv4PacketWire::new_checked(packet).ok()?;
+                if self.caps.checksum.ipv4.rx() && !ipv4_packet.verify_checksum() {
+                    return None;
+                }
+                let src_addr = ipv4_packet.src_addr();
+                let dst_addr = ipv4_packet.dst_addr();
+                if !src_addr.is_unicast()
+                    || !self.is_unicast_v4(dst_addr)
+                    || dst_addr.is_loopback()
+                    || dst_addr.is_link_local()
+                    || self.has_ip_addr(dst_addr)
+                {
+                    return None;
+                }
+                Some(&packet[..ipv4_packet.total_len() as usize])
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpVersion::Ipv6 => {
+                let ipv6_packet = Ipv6PacketWire::new_checked(packet).ok()?;
+                let src_addr = ipv6_packet.src_addr();
+                let dst_addr = ipv6_packet.dst_addr();
+                // Link-local addresses are only valid on their link (RFC 4291 § 2.5.6).
+                if src_addr.is_unspecified()
+                    || src_addr.is_link_local()
+                    || !dst_addr.is_unicast()
+                    || dst_addr.is_loopback()
+                    || dst_addr.is_link_local()
+                    || self.has_ip_addr(dst_addr)
+                    || self.has_anycast_addr(dst_addr)
+                {
+                    return None;
+                }
+                Some(&packet[..ipv6_packet.total_len()])
+            }
+        }
+    }
+
+    /// Route a packet received by the interface through another one, and reply with an
+    /// ICMP error if its TTL or hop limit runs out, or if it cannot be forwarded.
+    ///
+    /// The packets for an address of another interface are not routed, but dropped or
+    /// delivered to that interface depending on the host model.
+    pub(super) fn forward<Tx: TxToken>(
+        &mut self,
+        tx_token: Tx,
+        packet: &[u8],
+        fragments: &mut FragmentsBuffer,
+        frag: &mut Fragmenter,
+        forward: &mut dyn Forward,
+    ) {
+        let (dst_addr, hop_limit): (IpAddress, _) = match IpVersion::of_packet(packet) {
+            #[cfg(feature = "proto-ipv4")]
+            Ok(IpVersion::Ipv4) => {
+                let packet = Ipv4PacketWire::new_unchecked(packet);
+                (packet.dst_addr().into(), packet.hop_limit())
+            }
+            #[cfg(feature = "proto-ipv6")]
+            Ok(IpVersion::Ipv6) => {
+                let packet = Ipv6PacketWire::new_unchecked(packet);
+                (packet.dst_addr().into(), packet.hop_limit())
+            }
+            _ => return,
+        };
+
+        let error = match self.filter_forwarded(FilterDirection::Ingress, packet) {
+            FilterAction::Accept if forward.is_local(&dst_addr) => {
+                let reply = match self.host_model {
+                    HostModel::Strong => {
+                        net_debug!(
+                            "forward: dropping packet for {}, an address of another interface",
+                            dst_addr
+                        );
+                        return;
+                    }
+                    HostModel::Weak => forward.deliver(&dst_addr, packet, fragments),
+                };
+                if let Some(reply) = reply {
+                    if let Err(err) = self.dispatch_ip(tx_token, PacketMeta::default(), reply, frag)
+                    {
+                        net_debug!("Failed to send response: {:?}", err);
+                    }
+                }
+                return;
+            }
+            FilterAction::Accept if hop_limit <= 1 => ForwardError::TimeExceeded,
+            FilterAction::Accept => match forward.forward(packet) {
+                Forwarded::Done => return,
+                Forwarded::NoRoute => {
+                    self.stats.count_no_route();
+                    ForwardError::NoRoute
+                }
+                Forwarded::TooBig(mtu) => ForwardError::TooBig(mtu),
+                Forwarded::Prohibited => ForwardError::Prohibited,
+            },
+            FilterAction::Drop => return,
+            FilterAction::Reject => ForwardError::Prohibited,
+        };
+        net_debug!("forward: cannot forward packet: {:?}", error);
+
+        let reply = match IpVersion::of_packet(packet) {
+            #[cfg(feature = "proto-ipv4")]
+            Ok(IpVersion::Ipv4) => {
+                self.forward_icmpv4_error(&Ipv4PacketWire::new_unchecked(packet), error)
+            }
+            #[cfg(feature = "proto-ipv6")]
+            Ok(IpVersion::Ipv6) => {
+                self.forward_icmpv6_error(&Ipv6PacketWire::new_unchecked(packet), error)
+            }
+            _ => None,
+        };
+        if let Some(reply) = reply {
+            if let Err(err) = self.dispatch_ip(tx_token, PacketMeta::default(), reply, frag) {
+                net_debug!("Failed to send response: {:?}", err);
+            }
+        }
+    }
+
+    /// Return the action of the filter on a packet being forwarded.
+    fn filter_forwarded(&self, direction: FilterDirection, packet: &[u8]) -> FilterAction {
+        if self.filter.accepts_all() {
+            return FilterAction::Accept;
+        }
+        match FilterPacket::parse(packet) {
+            Some(filter_packet) => {
+                let action = self.filter.action(direction, &filter_packet);
+                if action != FilterAction::Accept {
+                    net_debug!("filter: denying forwarded packet: {:?}", action);
+                }
+                action
+            }
+            None => FilterAction::Drop,
+        }
+    }
+
+    /// Return the ICMPv4 error for a packet that cannot be forwarded, unless it is about a
+    /// non-initial fragment or an ICMP error itself (RFC 1812 § 4.3.2.7).
+    #[cfg(feature = "proto-ipv4")]
+    fn forward_icmpv4_error<'a>(
+        &mut self,
+        packet: &Ipv4PacketWire<&'a [u8]>,
+        error: ForwardError,
+    ) -> Option<IpPacket<'a>> {
+        let payload = packet.payload();
+        if packet.frag_offset() != 0
+            || (packet.next_header() == IpProtocol::Icmp
+                && payload.first().map_or(true, |&msg_type| {
+                    !matches!(
+                        Icmpv4Message::from(msg_type),
+                        Icmpv4Message::EchoRequest | Icmpv4Message::EchoReply
+                    )
+                }))
+        {
+            return None;
+        }
+
+        let header = Ipv4Repr {
+            src_addr: packet.src_addr(),
+            dst_addr: packet.dst_addr(),
+            next_header: packet.next_header(),
+            payload_len: payload.len(),
+            hop_limit: packet.hop_limit(),
+        };
+        let payload_len = icmp_reply_payload_len(payload.len(), IPV4_MIN_MTU, header.buffer_len());
+        let data = &payload[..payload_len];
+        let icmp_repr = match error {
+            ForwardError::TimeExceeded => Icmpv4Repr::TimeExceeded {
+                reason: Icmpv4TimeExceeded::TtlExpired,
+                header,
+                data,
+            },
+            ForwardError::NoRoute => Icmpv4Repr::DstUnreachable {
+                reason: Icmpv4DstUnreachable::NetUnreachable,
+                header,
+                data,
+            },
+            ForwardError::TooBig(mtu) => Icmpv4Repr::PktTooBig {
+                mtu: mtu.min(u16::MAX as usize) as u16,
+                header,
+                data,
+            },
+            ForwardError::Prohibited => Icmpv4Repr::DstUnreachable {
+                reason: Icmpv4DstUnreachable::CommProhibited,
+                header,
+                data,
+            },
+        };
+
+        let src_addr = self.get_source_address_ipv4(header.src_addr)?;
+        Some(IpPacket::new_ipv4(
+            Ipv4Repr {
+                src_addr,
+                dst_addr: header.src_addr,
+                next_header: IpProtocol::Icmp,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: self.hop_limit,
+            },
+            IpPayload::Icmpv4(icmp_repr),
+        ))
+    }
+
+    /// Return the ICMPv6 error for a packet that cannot be forwarded, unless it is about
+    /// an ICMPv6 error itself (RFC 4443 § 2.4).
+    #[cfg(feature = "proto-ipv6")]
+    fn forward_icmpv6_error<'a>(
+        &mut self,
+        packet: &Ipv6PacketWire<&'a [u8]>,
+        error: ForwardError,
+    ) -> Option<IpPacket<'a>> {
+        let payload = packet.payload();
+        if packet.next_header() == IpProtocol::Icmpv6
+            && payload.first().map_or(true, |&msg_type| msg_type < 128)
+        {
+            return None;
+        }
+
+        let header = Ipv6Repr {
+            src_addr: packet.src_addr(),
+            dst_addr: packet.dst_addr(),
+            next_header: packet.next_header(),
+            payload_len: payload.len(),
+            hop_limit: packet.hop_limit(),
+        };
+        let payload_len = icmp_reply_payload_len(payload.len(), IPV6_MIN_MTU, header.buffer_len());
+        let data = &payload[..payload_len];
+        let icmp_repr = match error {
+            ForwardError::TimeExceeded => Icmpv6Repr::TimeExceeded {
+                reason: Icmpv6TimeExceeded::HopLimitExceeded,
+                header,
+                data,
+            },
+            ForwardError::NoRoute => Icmpv6Repr::DstUnreachable {
+                reason: Icmpv6DstUnreachable::NoRoute,
+                header,
+                data,
+            },
+            ForwardError::TooBig(mtu) => Icmpv6Repr::PktTooBig {
+                mtu: mtu as u32,
+                header,
+                data,
+            },
+            ForwardError::Prohibited => Icmpv6Repr::DstUnreachable {
+                reason: Icmpv6DstUnreachable::AdminProhibit,
+                header,
+                data,
+            },
+        };
+
+        let src_addr = self.get_source_address_ipv6(header.src_addr)?;
+        Some(IpPacket::new_ipv6(
+            Ipv6Repr {
+                src_addr,
+                dst_addr: header.src_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: self.ipv6_hop_limit(),
+            },
+            IpPayload::Icmpv6(icmp_repr),
+        ))
+    }
+}
+
+/// Return the properties of a packet being forwarded that policy routes select. Forwarded
+/// packets have no mark.
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+pub(crate) fn forward_route_key(packet: &[u8]) -> RouteKey {
+    let (src_addr, traffic_class): (IpAddress, u8) = match IpVersion::of_packet(packet) {
+        #[cfg(feature = "proto-ipv4")]
+        Ok(IpVersion::Ipv4) => {
+            let packet = Ipv4PacketWire::new_unchecked(packet);
+            (packet.src_addr().into(), packet.dscp() << 2 | packet.ecn())
+        }
+        #[cfg(feature = "proto-ipv6")]
+        Ok(IpVersion::Ipv6) => {
+            let packet = Ipv6PacketWire::new_unchecked(packet);
+            (packet.src_addr().into(), packet.traffic_class())
+        }
+        _ => return RouteKey::default(),
+    };
+    RouteKey {
+        src_addr: Some(src_addr),
+        dscp: traffic_class >> 2,
+        mark: None,
+    }
+}
+
+/// Decrement the TTL or hop limit of a packet being forwarded.
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+fn decrement_hop_limit(packet: &mut [u8], _checksum_caps: &ChecksumCapabilities) {
+    match IpVersion::of_packet(packet) {
+        #[cfg(feature = "proto-ipv4")]
+        Ok(IpVersion::Ipv4) => {
+            let mut ipv4_packet = Ipv4PacketWire::new_unchecked(packet);
+            ipv4_packet.set_hop_limit(ipv4_packet.hop_limit() - 1);
+            if _checksum_caps.ipv4.tx() {
+                ipv4_packet.fill_checksum();
+            }
+        }
+        #[cfg(feature = "proto-ipv6")]
+        Ok(IpVersion::Ipv6) => {
+            let mut ipv6_packet = Ipv6PacketWire::new_unchecked(packet);
+            ipv6_packet.set_hop_limit(ipv6_packet.hop_limit() - 1);
+        }
+        _ => (),
+    }
+}
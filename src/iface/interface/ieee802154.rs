@@ -49,6 +49,7 @@ impl InterfaceInner {
         frag: &mut Fragmenter,
     ) {
         let ll_src_a = self.hardware_addr.ieee802154_or_panic();
+        let (ll_dst_a, mesh_repr) = self.sixlowpan_mesh_header(ll_dst_a);
 
         // Create the IEEE802.15.4 header.
         let ieee_repr = Ieee802154Repr {
@@ -65,7 +66,7 @@ impl InterfaceInner {
             src_addr: Some(ll_src_a),
         };
 
-        self.dispatch_sixlowpan(tx_token, meta, packet, ieee_repr, frag);
+        self.dispatch_sixlowpan(tx_token, meta, packet, ieee_repr, mesh_repr, frag);
     }
 
     #[cfg(feature = "proto-sixlowpan-fragmentation")]
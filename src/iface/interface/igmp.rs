@@ -1,10 +1,29 @@
-use super::{check, IgmpReportState, Interface, InterfaceInner, IpPacket};
+use heapless::Vec;
+
+use super::{check, IgmpReportState, Interface, InterfaceInner, IpPacket, IpPayload};
+#[cfg(feature = "socket-udp")]
+use crate::config::IFACE_MAX_MULTICAST_GROUP_COUNT;
+use crate::config::IFACE_MAX_MULTICAST_SOURCE_COUNT;
+#[cfg(feature = "socket-udp")]
+use crate::iface::SocketSet;
 use crate::phy::{Device, PacketMeta};
+#[cfg(feature = "socket-udp")]
+use crate::socket::Socket;
 use crate::time::{Duration, Instant};
 use crate::wire::*;
 
 use core::result::Result;
 
+/// How long the interface keeps talking to an older version querier after its last query,
+/// the Older Version Querier Present Timeout of [RFC 3376 § 8.12].
+///
+/// [RFC 3376 § 8.12]: https://tools.ietf.org/html/rfc3376#section-8.12
+const OLDER_QUERIER_PRESENT_TIMEOUT: Duration = Duration::from_secs(2 * 125 + 10);
+
+/// The length of the buffer IGMPv3 reports are built in, which fits two group records
+/// with the most sources.
+pub(super) const IGMP_REPORT_BUFFER_LEN: usize = 2 * (8 + 4 * IFACE_MAX_MULTICAST_SOURCE_COUNT);
+
 /// Error type for `join_multicast_group`, `leave_multicast_group`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -13,6 +32,8 @@ pub enum MulticastError {
     Exhausted,
     /// The table of joined multicast groups is already full.
     GroupTableFull,
+    /// The source list of a multicast group is too long.
+    SourceTableFull,
     /// IPv6 multicast is not yet supported.
     Ipv6NotSupported,
 }
@@ -22,6 +43,7 @@ impl core::fmt::Display for MulticastError {
         match self {
             MulticastError::Exhausted => write!(f, "Exhausted"),
             MulticastError::GroupTableFull => write!(f, "GroupTableFull"),
+            MulticastError::SourceTableFull => write!(f, "SourceTableFull"),
             MulticastError::Ipv6NotSupported => write!(f, "Ipv6NotSupported"),
         }
     }
@@ -30,9 +52,70 @@ impl core::fmt::Display for MulticastError {
 #[cfg(feature = "std")]
 impl std::error::Error for MulticastError {}
 
+/// The filter mode of the source list of a multicast group, as described in
+/// [RFC 3376 § 2].
+///
+/// [RFC 3376 § 2]: https://tools.ietf.org/html/rfc3376#section-2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MulticastFilterMode {
+    /// Only the packets sent by the listed sources are received.
+    Include,
+    /// The packets sent by all the sources but the listed ones are received.
+    Exclude,
+}
+
+/// The sources whose packets are received for a joined IPv4 multicast group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SourceFilter {
+    mode: MulticastFilterMode,
+    sources: Vec<Ipv4Address, IFACE_MAX_MULTICAST_SOURCE_COUNT>,
+}
+
+impl SourceFilter {
+    /// The filter of a group that is not joined.
+    const NONE: SourceFilter = SourceFilter {
+        mode: MulticastFilterMode::Include,
+        sources: Vec::new(),
+    };
+
+    /// The filter of a group joined for all the sources.
+    pub(super) const ANY: SourceFilter = SourceFilter {
+        mode: MulticastFilterMode::Exclude,
+        sources: Vec::new(),
+    };
+
+    fn allows(&self, addr: Ipv4Address) -> bool {
+        self.sources.contains(&addr) == (self.mode == MulticastFilterMode::Include)
+    }
+
+    /// Return the sources of `self` that are not sources of `other`.
+    fn difference<'a>(&'a self, other: &'a SourceFilter) -> impl Iterator<Item = Ipv4Address> + 'a {
+        self.sources
+            .iter()
+            .copied()
+            .filter(|addr| !other.sources.contains(addr))
+    }
+}
+
+/// Until when older version queriers are known to be present on the network, so that
+/// the interface reports its memberships with their version of the protocol, as described in
+/// [RFC 3376 § 7.2.1].
+///
+/// [RFC 3376 § 7.2.1]: https://tools.ietf.org/html/rfc3376#section-7.2.1
+#[derive(Debug, Default)]
+pub(crate) struct OlderQueriers {
+    v1_until: Option<Instant>,
+    v2_until: Option<Instant>,
+}
+
 impl Interface {
     /// Add an address to a list of subscribed multicast IP addresses.
     ///
+    /// Packets sent to an IPv4 group by all the sources are received, which replaces the
+    /// source filter of a group already joined with
+    /// [set_multicast_source_filter](#method.set_multicast_source_filter).
+    ///
     /// Returns `Ok(announce_sent)` if the address was added successfully, where `annouce_sent`
     /// indicates whether an initial immediate announcement has been sent.
     pub fn join_multicast_group<D, T: Into<IpAddress>>(
@@ -47,32 +130,7 @@ impl Interface {
         self.inner.now = timestamp;
 
         match addr.into() {
-            IpAddress::Ipv4(addr) => {
-                let is_not_new = self
-                    .inner
-                    .ipv4_multicast_groups
-                    .insert(addr, ())
-                    .map_err(|_| MulticastError::GroupTableFull)?
-                    .is_some();
-                if is_not_new {
-                    Ok(false)
-                } else if let Some(pkt) = self.inner.igmp_report_packet(IgmpVersion::Version2, addr)
-                {
-                    // Send initial membership report
-                    let tx_token = device
-                        .transmit(timestamp)
-                        .ok_or(MulticastError::Exhausted)?;
-
-                    // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
-                    self.inner
-                        .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
-                        .unwrap();
-
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
-            }
+            IpAddress::Ipv4(addr) => self.ipv4_multicast_listen(device, addr, SourceFilter::ANY),
             // Multicast is not yet implemented for other address families
             #[allow(unreachable_patterns)]
             _ => Err(MulticastError::Ipv6NotSupported),
@@ -95,32 +153,111 @@ impl Interface {
         self.inner.now = timestamp;
 
         match addr.into() {
-            IpAddress::Ipv4(addr) => {
-                let was_not_present = self.inner.ipv4_multicast_groups.remove(&addr).is_none();
-                if was_not_present {
-                    Ok(false)
-                } else if let Some(pkt) = self.inner.igmp_leave_packet(addr) {
-                    // Send group leave packet
-                    let tx_token = device
-                        .transmit(timestamp)
-                        .ok_or(MulticastError::Exhausted)?;
-
-                    // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
-                    self.inner
-                        .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
-                        .unwrap();
-
-                    Ok(true)
-                } else {
-                    Ok(false)
-                }
-            }
+            IpAddress::Ipv4(addr) => self.ipv4_multicast_listen(device, addr, SourceFilter::NONE),
             // Multicast is not yet implemented for other address families
             #[allow(unreachable_patterns)]
             _ => Err(MulticastError::Ipv6NotSupported),
         }
     }
 
+    /// Set the sources whose packets are received for an IPv4 multicast group, joining or
+    /// leaving the group as needed, as described in [RFC 3376 § 2].
+    ///
+    /// A group is joined for specific sources, as required by Source-Specific Multicast
+    /// (232.0.0.0/8), with an `Include` filter listing them; it is left with an empty
+    /// `Include` filter. An `Exclude` filter receives the packets of all the sources but
+    /// the listed ones.
+    ///
+    /// Returns `Ok(report_sent)` if the filter was set successfully, where `report_sent`
+    /// indicates whether an immediate report of the change has been sent.
+    ///
+    /// [RFC 3376 § 2]: https://tools.ietf.org/html/rfc3376#section-2
+    pub fn set_multicast_source_filter<D>(
+        &mut self,
+        device: &mut D,
+        addr: Ipv4Address,
+        mode: MulticastFilterMode,
+        sources: &[Ipv4Address],
+        timestamp: Instant,
+    ) -> Result<bool, MulticastError>
+    where
+        D: Device + ?Sized,
+    {
+        self.inner.now = timestamp;
+
+        let mut filter = SourceFilter {
+            mode,
+            sources: Vec::new(),
+        };
+        for source in sources {
+            if !filter.sources.contains(source) {
+                filter
+                    .sources
+                    .push(*source)
+                    .map_err(|_| MulticastError::SourceTableFull)?;
+            }
+        }
+        self.ipv4_multicast_listen(device, addr, filter)
+    }
+
+    /// Get the source filter of a joined IPv4 multicast group.
+    pub fn multicast_source_filter(
+        &self,
+        addr: Ipv4Address,
+    ) -> Option<(MulticastFilterMode, &[Ipv4Address])> {
+        self.inner
+            .ipv4_multicast_groups
+            .get(&addr)
+            .map(|filter| (filter.mode, &filter.sources[..]))
+    }
+
+    fn ipv4_multicast_listen<D>(
+        &mut self,
+        device: &mut D,
+        addr: Ipv4Address,
+        filter: SourceFilter,
+    ) -> Result<bool, MulticastError>
+    where
+        D: Device + ?Sized,
+    {
+        // A group already joined by sockets is kept once they leave it.
+        #[cfg(feature = "socket-udp")]
+        self.inner.ipv4_socket_multicast_groups.remove(&addr);
+
+        let groups = &mut self.inner.ipv4_multicast_groups;
+        let old_filter = groups.get(&addr).cloned().unwrap_or(SourceFilter::NONE);
+        if old_filter == filter {
+            return Ok(false);
+        }
+        if filter == SourceFilter::NONE {
+            groups.remove(&addr);
+        } else {
+            groups
+                .insert(addr, filter.clone())
+                .map_err(|_| MulticastError::GroupTableFull)?;
+        }
+
+        let mut buffer = [0; IGMP_REPORT_BUFFER_LEN];
+        if let Some(pkt) = self
+            .inner
+            .igmp_change_packet(addr, &old_filter, &filter, &mut buffer)
+        {
+            // Send the report of the change
+            let tx_token = device
+                .transmit(self.inner.now)
+                .ok_or(MulticastError::Exhausted)?;
+
+            // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+            self.inner
+                .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+                .unwrap();
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
     /// Check whether the interface listens to given destination multicast IP address.
     pub fn has_multicast_group<T: Into<IpAddress>>(&self, addr: T) -> bool {
         self.inner.has_multicast_group(addr)
@@ -133,12 +270,9 @@ impl Interface {
         D: Device + ?Sized,
     {
         match self.inner.igmp_report_state {
-            IgmpReportState::ToSpecificQuery {
-                version,
-                timeout,
-                group,
-            } if self.inner.now >= timeout => {
-                if let Some(pkt) = self.inner.igmp_report_packet(version, group) {
+            IgmpReportState::ToSpecificQuery { timeout, group } if self.inner.now >= timeout => {
+                let mut buffer = [0; IGMP_REPORT_BUFFER_LEN];
+                if let Some(pkt) = self.inner.igmp_report_packet(group, &mut buffer) {
                     // Send initial membership report
                     if let Some(tx_token) = device.transmit(self.inner.now) {
                         // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
@@ -154,7 +288,6 @@ impl Interface {
                 true
             }
             IgmpReportState::ToGeneralQuery {
-                version,
                 timeout,
                 interval,
                 next_index,
@@ -164,11 +297,12 @@ impl Interface {
                     .ipv4_multicast_groups
                     .iter()
                     .nth(next_index)
-                    .map(|(addr, ())| *addr);
+                    .map(|(addr, _)| *addr);
 
                 match addr {
                     Some(addr) => {
-                        if let Some(pkt) = self.inner.igmp_report_packet(version, addr) {
+                        let mut buffer = [0; IGMP_REPORT_BUFFER_LEN];
+                        if let Some(pkt) = self.inner.igmp_report_packet(addr, &mut buffer) {
                             // Send initial membership report
                             if let Some(tx_token) = device.transmit(self.inner.now) {
                                 // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
@@ -187,7 +321,6 @@ impl Interface {
 
                         let next_timeout = (timeout + interval).max(self.inner.now);
                         self.inner.igmp_report_state = IgmpReportState::ToGeneralQuery {
-                            version,
                             timeout: next_timeout,
                             interval,
                             next_index: next_index + 1,
@@ -204,6 +337,104 @@ impl Interface {
             _ => false,
         }
     }
+
+    /// Send IGMP membership reports for the groups newly joined by sockets, and leave
+    /// the groups that were joined on behalf of sockets only, once none is a member anymore.
+    #[cfg(feature = "socket-udp")]
+    pub(crate) fn socket_multicast_egress<D>(&mut self, device: &mut D, sockets: &SocketSet) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let mut groups = heapless::Vec::<Ipv4Address, IFACE_MAX_MULTICAST_GROUP_COUNT>::new();
+        for item in sockets.items() {
+            let Socket::Udp(socket) = &item.socket else {
+                continue;
+            };
+            for addr in socket.multicast_groups() {
+                match addr {
+                    // Groups that do not fit in the table of the interface are never joined.
+                    IpAddress::Ipv4(addr) if !groups.contains(&addr) => {
+                        let _ = groups.push(addr);
+                    }
+                    // IPv6 groups are reported with MLD.
+                    _ => (),
+                }
+            }
+        }
+
+        let mut did_something = false;
+
+        for &addr in &groups {
+            if addr == Ipv4Address::MULTICAST_ALL_SYSTEMS
+                || self.inner.ipv4_multicast_groups.get(&addr).is_some()
+            {
+                continue;
+            }
+            if self.inner.ipv4_multicast_groups.len() == IFACE_MAX_MULTICAST_GROUP_COUNT {
+                break;
+            }
+            let mut buffer = [0; IGMP_REPORT_BUFFER_LEN];
+            let pkt = self.inner.igmp_change_packet(
+                addr,
+                &SourceFilter::NONE,
+                &SourceFilter::ANY,
+                &mut buffer,
+            );
+            if let Some(pkt) = pkt {
+                let Some(tx_token) = device.transmit(self.inner.now) else {
+                    return did_something;
+                };
+                // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+                self.inner
+                    .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+                    .unwrap();
+            }
+
+            // NOTE(unwrap): the tables were checked not to be full.
+            self.inner
+                .ipv4_multicast_groups
+                .insert(addr, SourceFilter::ANY)
+                .unwrap();
+            self.inner
+                .ipv4_socket_multicast_groups
+                .insert(addr, ())
+                .unwrap();
+            did_something = true;
+        }
+
+        loop {
+            let left = self
+                .inner
+                .ipv4_socket_multicast_groups
+                .keys()
+                .find(|addr| !groups.contains(addr));
+            let Some(&addr) = left else {
+                break;
+            };
+            let mut buffer = [0; IGMP_REPORT_BUFFER_LEN];
+            let pkt = self.inner.igmp_change_packet(
+                addr,
+                &SourceFilter::ANY,
+                &SourceFilter::NONE,
+                &mut buffer,
+            );
+            if let Some(pkt) = pkt {
+                let Some(tx_token) = device.transmit(self.inner.now) else {
+                    return did_something;
+                };
+                // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+                self.inner
+                    .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+                    .unwrap();
+            }
+
+            self.inner.ipv4_socket_multicast_groups.remove(&addr);
+            self.inner.ipv4_multicast_groups.remove(&addr);
+            did_something = true;
+        }
+
+        did_something
+    }
 }
 
 impl InterfaceInner {
@@ -222,7 +453,33 @@ impl InterfaceInner {
         }
     }
 
-    /// Host duties of the **IGMPv2** protocol.
+    /// Check whether the packets sent by `src_addr` to a joined IPv4 multicast group are
+    /// received, according to the source filter of the group.
+    pub(super) fn ipv4_multicast_source_allowed(
+        &self,
+        group_addr: Ipv4Address,
+        src_addr: Ipv4Address,
+    ) -> bool {
+        self.ipv4_multicast_groups
+            .get(&group_addr)
+            .map_or(true, |filter| filter.allows(src_addr))
+    }
+
+    /// Return the version of the older querier present on the network, if any, which
+    /// memberships are reported with instead of IGMPv3.
+    fn igmp_older_version(&self) -> Option<IgmpVersion> {
+        let present = |until: Option<Instant>| until.map_or(false, |t| self.now < t);
+        if present(self.igmp_older_queriers.v1_until) {
+            Some(IgmpVersion::Version1)
+        } else if present(self.igmp_older_queriers.v2_until) {
+            Some(IgmpVersion::Version2)
+        } else {
+            None
+        }
+    }
+
+    /// Host duties of the **IGMPv3** protocol, which falls back to **IGMPv1** and **IGMPv2**
+    /// while queriers of these versions are present.
     ///
     /// Sets up `igmp_report_state` for responding to IGMP general/specific membership queries.
     /// Membership must not be reported immediately in order to avoid flooding the network
@@ -236,54 +493,214 @@ impl InterfaceInner {
         let igmp_repr = check!(IgmpRepr::parse(&igmp_packet));
 
         // FIXME: report membership after a delay
-        match igmp_repr {
+        let (max_resp_time, group_addr, version) = match igmp_repr {
             IgmpRepr::MembershipQuery {
+                max_resp_time,
                 group_addr,
                 version,
-                max_resp_time,
             } => {
-                // General query
-                if group_addr.is_unspecified()
-                    && ipv4_repr.dst_addr == Ipv4Address::MULTICAST_ALL_SYSTEMS
-                {
-                    // Are we member in any groups?
-                    if self.ipv4_multicast_groups.iter().next().is_some() {
-                        let interval = match version {
-                            IgmpVersion::Version1 => Duration::from_millis(100),
-                            IgmpVersion::Version2 => {
-                                // No dependence on a random generator
-                                // (see [#24](https://github.com/m-labs/smoltcp/issues/24))
-                                // but at least spread reports evenly across max_resp_time.
-                                let intervals = self.ipv4_multicast_groups.len() as u32 + 1;
-                                max_resp_time / intervals
-                            }
-                        };
-                        self.igmp_report_state = IgmpReportState::ToGeneralQuery {
-                            version,
-                            timeout: self.now + interval,
-                            interval,
-                            next_index: 0,
-                        };
-                    }
-                } else {
-                    // Group-specific query
-                    if self.has_multicast_group(group_addr) && ipv4_repr.dst_addr == group_addr {
-                        // Don't respond immediately
-                        let timeout = max_resp_time / 4;
-                        self.igmp_report_state = IgmpReportState::ToSpecificQuery {
-                            version,
-                            timeout: self.now + timeout,
-                            group: group_addr,
-                        };
-                    }
+                let until = Some(self.now + OLDER_QUERIER_PRESENT_TIMEOUT);
+                match version {
+                    IgmpVersion::Version1 => self.igmp_older_queriers.v1_until = until,
+                    IgmpVersion::Version2 => self.igmp_older_queriers.v2_until = until,
                 }
+                (max_resp_time, group_addr, Some(version))
             }
+            // The sources of a group-and-source-specific query are not looked at: the
+            // current state of the whole group is reported, which covers them.
+            IgmpRepr::MembershipQueryV3 {
+                max_resp_time,
+                group_addr,
+                ..
+            } => (max_resp_time, group_addr, None),
             // Ignore membership reports
-            IgmpRepr::MembershipReport { .. } => (),
+            IgmpRepr::MembershipReport { .. } | IgmpRepr::MembershipReportV3 { .. } => return None,
             // Ignore hosts leaving groups
-            IgmpRepr::LeaveGroup { .. } => (),
+            IgmpRepr::LeaveGroup { .. } => return None,
+        };
+
+        // General query
+        if group_addr.is_unspecified() && ipv4_repr.dst_addr == Ipv4Address::MULTICAST_ALL_SYSTEMS {
+            // Are we member in any groups?
+            if self.ipv4_multicast_groups.iter().next().is_some() {
+                let interval = match version {
+                    Some(IgmpVersion::Version1) => Duration::from_millis(100),
+                    _ => {
+                        // No dependence on a random generator
+                        // (see [#24](https://github.com/m-labs/smoltcp/issues/24))
+                        // but at least spread reports evenly across max_resp_time.
+                        let intervals = self.ipv4_multicast_groups.len() as u32 + 1;
+                        max_resp_time / intervals
+                    }
+                };
+                self.igmp_report_state = IgmpReportState::ToGeneralQuery {
+                    timeout: self.now + interval,
+                    interval,
+                    next_index: 0,
+                };
+            }
+        } else {
+            // Group-specific query
+            if self.has_multicast_group(group_addr) && ipv4_repr.dst_addr == group_addr {
+                // Don't respond immediately
+                let timeout = max_resp_time / 4;
+                self.igmp_report_state = IgmpReportState::ToSpecificQuery {
+                    timeout: self.now + timeout,
+                    group: group_addr,
+                };
+            }
         }
 
         None
     }
+
+    /// Return a report of the current state of a joined group, in reply to a query.
+    pub(super) fn igmp_report_packet<'a>(
+        &self,
+        group_addr: Ipv4Address,
+        buffer: &'a mut [u8],
+    ) -> Option<IpPacket<'a>> {
+        let filter = self.ipv4_multicast_groups.get(&group_addr)?;
+        if let Some(version) = self.igmp_older_version() {
+            let igmp_repr = IgmpRepr::MembershipReport {
+                group_addr,
+                version,
+            };
+            // Send to the group being reported
+            return self.igmp_packet(group_addr, igmp_repr);
+        }
+
+        let record_type = match filter.mode {
+            MulticastFilterMode::Include => IgmpRecordType::ModeIsInclude,
+            MulticastFilterMode::Exclude => IgmpRecordType::ModeIsExclude,
+        };
+        let len = emit_group_record(
+            buffer,
+            record_type,
+            group_addr,
+            filter.sources.iter().copied(),
+        );
+        let igmp_repr = IgmpRepr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &buffer[..len],
+        };
+        self.igmp_packet(Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS, igmp_repr)
+    }
+
+    /// Return a report of the change of the source filter of a group, as described in
+    /// [RFC 3376 § 5.1], if the change is to be reported.
+    ///
+    /// [RFC 3376 § 5.1]: https://tools.ietf.org/html/rfc3376#section-5.1
+    pub(super) fn igmp_change_packet<'a>(
+        &self,
+        group_addr: Ipv4Address,
+        old_filter: &SourceFilter,
+        new_filter: &SourceFilter,
+        buffer: &'a mut [u8],
+    ) -> Option<IpPacket<'a>> {
+        // Source filters are not known to older versions, which only see groups being
+        // joined and left.
+        match self.igmp_older_version() {
+            Some(IgmpVersion::Version1) if *new_filter == SourceFilter::NONE => return None,
+            Some(IgmpVersion::Version2) if *new_filter == SourceFilter::NONE => {
+                let igmp_repr = IgmpRepr::LeaveGroup { group_addr };
+                return self.igmp_packet(Ipv4Address::MULTICAST_ALL_ROUTERS, igmp_repr);
+            }
+            Some(version) if *old_filter == SourceFilter::NONE => {
+                let igmp_repr = IgmpRepr::MembershipReport {
+                    group_addr,
+                    version,
+                };
+                // Send to the group being reported
+                return self.igmp_packet(group_addr, igmp_repr);
+            }
+            Some(_) => return None,
+            None => (),
+        }
+
+        let mut len = 0;
+        let mut num_group_records = 0;
+        if old_filter.mode != new_filter.mode {
+            let record_type = match new_filter.mode {
+                MulticastFilterMode::Include => IgmpRecordType::ChangeToInclude,
+                MulticastFilterMode::Exclude => IgmpRecordType::ChangeToExclude,
+            };
+            let sources = new_filter.sources.iter().copied();
+            len = emit_group_record(buffer, record_type, group_addr, sources);
+            num_group_records = 1;
+        } else {
+            let (allowed, blocked) = match new_filter.mode {
+                MulticastFilterMode::Include => (
+                    new_filter.difference(old_filter),
+                    old_filter.difference(new_filter),
+                ),
+                MulticastFilterMode::Exclude => (
+                    old_filter.difference(new_filter),
+                    new_filter.difference(old_filter),
+                ),
+            };
+            for (record_type, sources) in [
+                (IgmpRecordType::AllowNewSources, allowed),
+                (IgmpRecordType::BlockOldSources, blocked),
+            ] {
+                let mut sources = sources.peekable();
+                if sources.peek().is_some() {
+                    len += emit_group_record(&mut buffer[len..], record_type, group_addr, sources);
+                    num_group_records += 1;
+                }
+            }
+        }
+        if num_group_records == 0 {
+            return None;
+        }
+
+        let buffer: &'a [u8] = buffer;
+        let igmp_repr = IgmpRepr::MembershipReportV3 {
+            num_group_records,
+            data: &buffer[..len],
+        };
+        self.igmp_packet(Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS, igmp_repr)
+    }
+
+    fn igmp_packet<'a>(
+        &self,
+        dst_addr: Ipv4Address,
+        igmp_repr: IgmpRepr<'a>,
+    ) -> Option<IpPacket<'a>> {
+        let iface_addr = self.ipv4_addr()?;
+        let mut packet = IpPacket::new_ipv4(
+            Ipv4Repr {
+                src_addr: iface_addr,
+                dst_addr,
+                next_header: IpProtocol::Igmp,
+                payload_len: igmp_repr.buffer_len(),
+                hop_limit: 1,
+            },
+            IpPayload::Igmp(igmp_repr),
+        );
+        // IGMP messages carry the Router Alert option (RFC 2236 § 2, RFC 3376 § 4),
+        // [#183](https://github.com/m-labs/smoltcp/issues/183).
+        packet.set_router_alert();
+        Some(packet)
+    }
+}
+
+/// Emit an IGMPv3 group record into `buffer`, and return its length.
+fn emit_group_record(
+    buffer: &mut [u8],
+    record_type: IgmpRecordType,
+    group_addr: Ipv4Address,
+    sources: impl Iterator<Item = Ipv4Address>,
+) -> usize {
+    let mut record = IgmpGroupRecord::new_unchecked(buffer);
+    record.set_record_type(record_type);
+    record.set_aux_data_len(0);
+    record.set_mcast_addr(group_addr);
+    let mut num_srcs = 0;
+    for addr in sources {
+        record.set_source(num_srcs, addr);
+        num_srcs += 1;
+    }
+    record.set_num_srcs(num_srcs as u16);
+    record.record_len()
 }
@@ -2,10 +2,16 @@ use super::*;
 
 #[cfg(feature = "socket-dhcpv4")]
 use crate::socket::dhcpv4;
+#[cfg(feature = "socket-dhcpv4-relay")]
+use crate::socket::dhcpv4_relay;
+#[cfg(feature = "socket-dhcpv4-server")]
+use crate::socket::dhcpv4_server;
 #[cfg(feature = "socket-icmp")]
 use crate::socket::icmp;
 use crate::socket::AnySocket;
 
+#[cfg(feature = "proto-ipv4-fragmentation")]
+use crate::iface::fragmentation::AssemblerError;
 use crate::phy::{Medium, TxToken};
 use crate::time::Instant;
 use crate::wire::{Ipv4Packet as Ipv4PacketWire, *};
@@ -18,7 +24,15 @@ impl InterfaceInner {
         ipv4_packet: &Ipv4PacketWire<&'a [u8]>,
         frag: &'a mut FragmentsBuffer,
     ) -> Option<IpPacket<'a>> {
-        let ipv4_repr = check!(Ipv4Repr::parse(ipv4_packet, &self.caps.checksum));
+        let ipv4_repr = check!(
+            Ipv4Repr::parse(ipv4_packet, &self.caps.checksum),
+            if self.caps.checksum.ipv4.rx() && !ipv4_packet.verify_checksum() {
+                self.stats.ipv4.count_checksum_error();
+            }
+        );
+        self.stats
+            .ipv4
+            .count_rx(ipv4_repr.buffer_len() + ipv4_repr.payload_len);
         if !self.is_unicast_v4(ipv4_repr.src_addr) && !ipv4_repr.src_addr.is_unspecified() {
             // Discard packets with non-unicast source addresses but allow unspecified
             net_debug!("non-unicast or unspecified source address");
@@ -28,36 +42,25 @@ impl InterfaceInner {
         #[cfg(feature = "proto-ipv4-fragmentation")]
         let ip_payload = {
             if ipv4_packet.more_frags() || ipv4_packet.frag_offset() != 0 {
-                let key = FragKey::Ipv4(ipv4_packet.get_key());
-
-                let f = match frag.assembler.get(&key, self.now + frag.reassembly_timeout) {
-                    Ok(f) => f,
-                    Err(_) => {
-                        net_debug!("No available packet assembler for fragmented packet");
+                match self.ipv4_reassembly_add(ipv4_packet, frag) {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(e) => {
+                        net_debug!("fragmentation error: {:?}", e);
+                        let stats = &mut frag.ipv4_reassembly_stats;
+                        stats.dropped_fragments = stats.dropped_fragments.saturating_add(1);
                         return None;
                     }
-                };
-
-                if !ipv4_packet.more_frags() {
-                    // This is the last fragment, so we know the total size
-                    check!(f.set_total_size(
-                        ipv4_packet.total_len() as usize - ipv4_packet.header_len() as usize
-                            + ipv4_packet.frag_offset() as usize,
-                    ));
-                }
-
-                if let Err(e) = f.add(ipv4_packet.payload(), ipv4_packet.frag_offset() as usize) {
-                    net_debug!("fragmentation error: {:?}", e);
-                    return None;
                 }
 
                 // NOTE: according to the standard, the total length needs to be
                 // recomputed, as well as the checksum. However, we don't really use
                 // the IPv4 header after the packet is reassembled.
-                match f.assemble() {
-                    Some(payload) => payload,
-                    None => return None,
-                }
+                let key = FragKey::Ipv4(ipv4_packet.get_key());
+                let payload = frag.assembler.get(&key, self.now).ok()?.assemble()?;
+                let stats = &mut frag.ipv4_reassembly_stats;
+                stats.reassembled = stats.reassembled.saturating_add(1);
+                payload
             } else {
                 ipv4_packet.payload()
             }
@@ -68,6 +71,21 @@ impl InterfaceInner {
 
         let ip_repr = IpRepr::Ipv4(ipv4_repr);
 
+        if !self.filter.accepts_all() {
+            let filter_packet = FilterPacket::new(&ip_repr, ip_payload);
+            match self.filter.action(FilterDirection::Ingress, &filter_packet) {
+                FilterAction::Accept => (),
+                FilterAction::Drop => {
+                    net_debug!("filter: dropping packet from {}", ipv4_repr.src_addr);
+                    return None;
+                }
+                FilterAction::Reject => {
+                    net_debug!("filter: rejecting packet from {}", ipv4_repr.src_addr);
+                    return self.filter_reject_ipv4(ipv4_repr, ip_payload);
+                }
+            }
+        }
+
         #[cfg(feature = "socket-raw")]
         let handled_by_raw_socket = self.raw_socket_filter(sockets, &ip_repr, ip_payload);
         #[cfg(not(feature = "socket-raw"))]
@@ -89,12 +107,12 @@ impl InterfaceInner {
                         && udp_packet.dst_port() == dhcp_socket.client_port
                     {
                         let (src_addr, dst_addr) = (ip_repr.src_addr(), ip_repr.dst_addr());
-                        let udp_repr = check!(UdpRepr::parse(
+                        let udp_repr = self.parse_udp(
                             &udp_packet,
                             &src_addr,
                             &dst_addr,
-                            &self.caps.checksum
-                        ));
+                            self.checksum_caps(),
+                        )?;
                         let udp_payload = udp_packet.payload();
 
                         dhcp_socket.process(self, &ipv4_repr, &udp_repr, udp_payload);
@@ -104,6 +122,54 @@ impl InterfaceInner {
             }
         }
 
+        #[cfg(feature = "socket-dhcpv4-server")]
+        {
+            if ipv4_repr.next_header == IpProtocol::Udp
+                && matches!(self.caps.medium, Medium::Ethernet)
+                && (self.is_broadcast_v4(ipv4_repr.dst_addr)
+                    || self.has_ip_addr(ipv4_repr.dst_addr))
+            {
+                let udp_packet = check!(UdpPacket::new_checked(ip_payload));
+                if let Some(dhcp_socket) = sockets
+                    .items_mut()
+                    .filter_map(|i| dhcpv4_server::Socket::downcast_mut(&mut i.socket))
+                    .find(|s| s.server_port == udp_packet.dst_port())
+                {
+                    let (src_addr, dst_addr) = (ip_repr.src_addr(), ip_repr.dst_addr());
+                    let udp_repr =
+                        self.parse_udp(&udp_packet, &src_addr, &dst_addr, self.checksum_caps())?;
+                    let udp_payload = udp_packet.payload();
+
+                    dhcp_socket.process(self, &ipv4_repr, &udp_repr, udp_payload);
+                    return None;
+                }
+            }
+        }
+
+        #[cfg(feature = "socket-dhcpv4-relay")]
+        {
+            if ipv4_repr.next_header == IpProtocol::Udp
+                && matches!(self.caps.medium, Medium::Ethernet)
+                && (self.is_broadcast_v4(ipv4_repr.dst_addr)
+                    || self.has_ip_addr(ipv4_repr.dst_addr))
+            {
+                let udp_packet = check!(UdpPacket::new_checked(ip_payload));
+                if let Some(dhcp_socket) = sockets
+                    .items_mut()
+                    .filter_map(|i| dhcpv4_relay::Socket::downcast_mut(&mut i.socket))
+                    .find(|s| s.server_port == udp_packet.dst_port())
+                {
+                    let (src_addr, dst_addr) = (ip_repr.src_addr(), ip_repr.dst_addr());
+                    let udp_repr =
+                        self.parse_udp(&udp_packet, &src_addr, &dst_addr, self.checksum_caps())?;
+                    let udp_payload = udp_packet.payload();
+
+                    dhcp_socket.process(self, &ipv4_repr, &udp_repr, udp_payload);
+                    return None;
+                }
+            }
+        }
+
         if !self.has_ip_addr(ipv4_repr.dst_addr)
             && !self.has_multicast_group(ipv4_repr.dst_addr)
             && !self.is_broadcast_v4(ipv4_repr.dst_addr)
@@ -121,6 +187,18 @@ impl InterfaceInner {
             }
         }
 
+        // IGMP queries are sent to the groups by the routers, whatever the sources listened to.
+        #[cfg(feature = "proto-igmp")]
+        if ipv4_repr.next_header != IpProtocol::Igmp
+            && !self.ipv4_multicast_source_allowed(ipv4_repr.dst_addr, ipv4_repr.src_addr)
+        {
+            net_trace!(
+                "ipv4: dropping packet from filtered source {}",
+                ipv4_repr.src_addr
+            );
+            return None;
+        }
+
         match ipv4_repr.next_header {
             IpProtocol::Icmp => self.process_icmpv4(sockets, ip_repr, ip_payload),
 
@@ -130,12 +208,12 @@ impl InterfaceInner {
             #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
             IpProtocol::Udp => {
                 let udp_packet = check!(UdpPacket::new_checked(ip_payload));
-                let udp_repr = check!(UdpRepr::parse(
+                let udp_repr = self.parse_udp(
                     &udp_packet,
                     &ipv4_repr.src_addr.into(),
                     &ipv4_repr.dst_addr.into(),
-                    &self.checksum_caps(),
-                ));
+                    self.checksum_caps(),
+                )?;
 
                 self.process_udp(
                     sockets,
@@ -143,11 +221,17 @@ impl InterfaceInner {
                     ip_repr,
                     udp_repr,
                     handled_by_raw_socket,
+                    false,
                     udp_packet.payload(),
                     ip_payload,
                 )
             }
 
+            #[cfg(feature = "socket-udp")]
+            IpProtocol::UdpLite => {
+                self.process_udplite(sockets, meta, ip_repr, handled_by_raw_socket, ip_payload)
+            }
+
             #[cfg(feature = "socket-tcp")]
             IpProtocol::Tcp => self.process_tcp(sockets, ip_repr, ip_payload),
 
@@ -167,15 +251,90 @@ impl InterfaceInner {
         }
     }
 
+    /// Add an IPv4 fragment to the packet it belongs to, within the reassembly limits, and
+    /// return whether the packet is complete.
+    ///
+    /// The oldest incomplete packets are dropped to make room for new ones when their source
+    /// is over its limit, when all the buffers are in use, or when the memory limit is reached.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    fn ipv4_reassembly_add(
+        &mut self,
+        ipv4_packet: &Ipv4PacketWire<&[u8]>,
+        frag: &mut FragmentsBuffer,
+    ) -> core::result::Result<bool, AssemblerError> {
+        let ipv4_key = ipv4_packet.get_key();
+        let key = FragKey::Ipv4(ipv4_key);
+        let is_ipv4 = |k: &FragKey| matches!(k, FragKey::Ipv4(_));
+        let from_source =
+            |k: &FragKey| matches!(k, FragKey::Ipv4(k) if k.src_addr == ipv4_key.src_addr);
+        let assembler = &mut frag.assembler;
+        let stats = &mut frag.ipv4_reassembly_stats;
+
+        if assembler.count(|k| *k == key) == 0 {
+            while assembler.count(from_source) >= frag.ipv4_reassembly_limit {
+                if !assembler.remove_oldest(&key, from_source) {
+                    return Err(AssemblerError);
+                }
+                stats.evictions = stats.evictions.saturating_add(1);
+                self.stats.count_reassembly_failure();
+            }
+        }
+
+        let offset = ipv4_packet.frag_offset() as usize;
+        let payload = ipv4_packet.payload();
+        let total_size = (!ipv4_packet.more_frags()).then_some(offset + payload.len());
+        let size = assembler.size(|k| *k == key);
+        let new_size = size.max(offset + payload.len());
+        while assembler.size(is_ipv4) - size + new_size > frag.ipv4_reassembly_memory_limit {
+            if !assembler.remove_oldest(&key, is_ipv4) {
+                return Err(AssemblerError);
+            }
+            stats.evictions = stats.evictions.saturating_add(1);
+            self.stats.count_reassembly_failure();
+        }
+
+        let expires_at = self.now + frag.reassembly_timeout;
+        if assembler.get(&key, expires_at).is_err() {
+            if !assembler.remove_oldest(&key, is_ipv4) {
+                return Err(AssemblerError);
+            }
+            stats.evictions = stats.evictions.saturating_add(1);
+            self.stats.count_reassembly_failure();
+        }
+        let f = assembler
+            .get(&key, expires_at)
+            .map_err(|_| AssemblerError)?;
+        if let Some(total_size) = total_size {
+            f.set_total_size(total_size)?;
+        }
+        f.add(payload, offset)?;
+        Ok(f.is_complete())
+    }
+
     #[cfg(feature = "medium-ethernet")]
     pub(super) fn process_arp<'frame>(
         &mut self,
+        _sockets: &mut SocketSet,
         timestamp: Instant,
         eth_frame: &EthernetFrame<&'frame [u8]>,
     ) -> Option<EthernetPacket<'frame>> {
         let arp_packet = check!(ArpPacket::new_checked(eth_frame.payload()));
         let arp_repr = check!(ArpRepr::parse(&arp_packet));
 
+        // DHCP sockets probing an address look for any other host using it.
+        #[cfg(feature = "socket-dhcpv4")]
+        for dhcp_socket in _sockets
+            .items_mut()
+            .filter_map(|i| dhcpv4::Socket::downcast_mut(&mut i.socket))
+        {
+            dhcp_socket.process_arp(self, &arp_repr);
+        }
+
+        // So does link-local address autoconfiguration, which defends its address.
+        if let Some(defense) = self.autoip_process_arp(&arp_repr) {
+            return Some(EthernetPacket::Arp(defense));
+        }
+
         match arp_repr {
             ArpRepr::EthernetIpv4 {
                 operation,
@@ -240,7 +399,13 @@ impl InterfaceInner {
         ip_payload: &'frame [u8],
     ) -> Option<IpPacket<'frame>> {
         let icmp_packet = check!(Icmpv4Packet::new_checked(ip_payload));
-        let icmp_repr = check!(Icmpv4Repr::parse(&icmp_packet, &self.caps.checksum));
+        let icmp_repr = check!(
+            Icmpv4Repr::parse(&icmp_packet, &self.caps.checksum),
+            if self.caps.checksum.icmpv4.rx() && !icmp_packet.verify_checksum() {
+                self.stats.icmpv4.count_checksum_error();
+            }
+        );
+        self.stats.icmpv4.count_rx(ip_payload.len());
 
         #[cfg(feature = "socket-icmp")]
         let mut handled_by_icmp_socket = false;
@@ -289,6 +454,35 @@ impl InterfaceInner {
         }
     }
 
+    /// Return the ICMP error for a packet rejected by the filter, unless it was not sent to
+    /// one of our addresses, or is an ICMP error itself.
+    fn filter_reject_ipv4<'frame>(
+        &self,
+        ipv4_repr: Ipv4Repr,
+        ip_payload: &'frame [u8],
+    ) -> Option<IpPacket<'frame>> {
+        if !self.has_ip_addr(ipv4_repr.dst_addr)
+            || (ipv4_repr.next_header == IpProtocol::Icmp
+                && ip_payload.first().map_or(true, |&msg_type| {
+                    !matches!(
+                        Icmpv4Message::from(msg_type),
+                        Icmpv4Message::EchoRequest | Icmpv4Message::EchoReply
+                    )
+                }))
+        {
+            return None;
+        }
+
+        let payload_len =
+            icmp_reply_payload_len(ip_payload.len(), IPV4_MIN_MTU, ipv4_repr.buffer_len());
+        let icmp_reply_repr = Icmpv4Repr::DstUnreachable {
+            reason: Icmpv4DstUnreachable::CommProhibited,
+            header: ipv4_repr,
+            data: &ip_payload[0..payload_len],
+        };
+        self.icmpv4_reply(ipv4_repr, icmp_reply_repr)
+    }
+
     pub(super) fn icmpv4_reply<'frame, 'icmp: 'frame>(
         &self,
         ipv4_repr: Ipv4Repr,
@@ -304,7 +498,7 @@ impl InterfaceInner {
                 dst_addr: ipv4_repr.src_addr,
                 next_header: IpProtocol::Icmp,
                 payload_len: icmp_repr.buffer_len(),
-                hop_limit: 64,
+                hop_limit: self.hop_limit,
             };
             Some(IpPacket::new_ipv4(
                 ipv4_reply_repr,
@@ -320,7 +514,7 @@ impl InterfaceInner {
                             dst_addr: ipv4_repr.src_addr,
                             next_header: IpProtocol::Icmp,
                             payload_len: icmp_repr.buffer_len(),
-                            hop_limit: 64,
+                            hop_limit: self.hop_limit,
                         };
                         Some(IpPacket::new_ipv4(
                             ipv4_reply_repr,
@@ -341,10 +535,17 @@ impl InterfaceInner {
         let caps = self.caps.clone();
 
         let mtu_max = self.ip_mtu();
-        let ip_len = (frag.packet_len - frag.sent_bytes + frag.ipv4.repr.buffer_len()).min(mtu_max);
-        let payload_len = ip_len - frag.ipv4.repr.buffer_len();
+        let header_len = frag.ipv4.repr.buffer_len();
+        let remaining_len = frag.packet_len - frag.sent_bytes;
+        // The payload of all the fragments but the last one is a multiple of 8 octets.
+        let payload_len = if header_len + remaining_len <= mtu_max {
+            remaining_len
+        } else {
+            (mtu_max - header_len) & !7
+        };
+        let ip_len = header_len + payload_len;
 
-        let more_frags = (frag.packet_len - frag.sent_bytes) != payload_len;
+        let more_frags = remaining_len != payload_len;
         frag.ipv4.repr.payload_len = payload_len;
         frag.sent_bytes += payload_len;
 
@@ -371,6 +572,7 @@ impl InterfaceInner {
             }
         };
 
+        self.stats.count_tx(tx_len);
         tx_token.consume(tx_len, |mut tx_buffer| {
             #[cfg(feature = "medium-ethernet")]
             if matches!(self.caps.medium, Medium::Ethernet) {
@@ -385,6 +587,8 @@ impl InterfaceInner {
             packet.set_more_frags(more_frags);
             packet.set_dont_frag(false);
             packet.set_frag_offset(frag.ipv4.frag_offset);
+            packet.set_dscp(frag.ipv4.traffic_class >> 2);
+            packet.set_ecn(frag.ipv4.traffic_class & 0x03);
 
             if caps.checksum.ipv4.tx() {
                 packet.fill_checksum();
@@ -399,50 +603,4 @@ impl InterfaceInner {
             frag.ipv4.frag_offset += payload_len as u16;
         })
     }
-
-    #[cfg(feature = "proto-igmp")]
-    pub(super) fn igmp_report_packet<'any>(
-        &self,
-        version: IgmpVersion,
-        group_addr: Ipv4Address,
-    ) -> Option<IpPacket<'any>> {
-        let iface_addr = self.ipv4_addr()?;
-        let igmp_repr = IgmpRepr::MembershipReport {
-            group_addr,
-            version,
-        };
-        let pkt = IpPacket::new_ipv4(
-            Ipv4Repr {
-                src_addr: iface_addr,
-                // Send to the group being reported
-                dst_addr: group_addr,
-                next_header: IpProtocol::Igmp,
-                payload_len: igmp_repr.buffer_len(),
-                hop_limit: 1,
-                // [#183](https://github.com/m-labs/smoltcp/issues/183).
-            },
-            IpPayload::Igmp(igmp_repr),
-        );
-        Some(pkt)
-    }
-
-    #[cfg(feature = "proto-igmp")]
-    pub(super) fn igmp_leave_packet<'any>(
-        &self,
-        group_addr: Ipv4Address,
-    ) -> Option<IpPacket<'any>> {
-        self.ipv4_addr().map(|iface_addr| {
-            let igmp_repr = IgmpRepr::LeaveGroup { group_addr };
-            IpPacket::new_ipv4(
-                Ipv4Repr {
-                    src_addr: iface_addr,
-                    dst_addr: Ipv4Address::MULTICAST_ALL_ROUTERS,
-                    next_header: IpProtocol::Igmp,
-                    payload_len: igmp_repr.buffer_len(),
-                    hop_limit: 1,
-                },
-                IpPayload::Igmp(igmp_repr),
-            )
-        })
-    }
 }
@@ -2,8 +2,11 @@ use super::check;
 use super::icmp_reply_payload_len;
 use super::InterfaceInner;
 use super::SocketSet;
+use super::{FilterAction, FilterDirection, FilterPacket};
 use super::{IpPacket, IpPayload};
 
+#[cfg(feature = "socket-dhcpv6")]
+use crate::socket::dhcpv6;
 #[cfg(feature = "socket-icmp")]
 use crate::socket::icmp;
 use crate::socket::AnySocket;
@@ -20,6 +23,15 @@ impl InterfaceInner {
         ipv6_packet: &Ipv6Packet<&'frame [u8]>,
     ) -> Option<IpPacket<'frame>> {
         let ipv6_repr = check!(Ipv6Repr::parse(ipv6_packet));
+        self.stats
+            .ipv6
+            .count_rx(ipv6_repr.buffer_len() + ipv6_repr.payload_len);
+
+        // Duplicate Address Detection probes come from the unspecified address.
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        if ipv6_repr.src_addr.is_unspecified() && ipv6_repr.next_header == IpProtocol::Icmpv6 {
+            return self.process_dad_solicit(&ipv6_repr, ipv6_packet.payload());
+        }
 
         if !ipv6_repr.src_addr.is_unicast() {
             // Discard packets with non-unicast source addresses.
@@ -29,6 +41,59 @@ impl InterfaceInner {
 
         let ip_payload = ipv6_packet.payload();
 
+        if !self.filter.accepts_all() {
+            let filter_packet = FilterPacket::new(&ipv6_repr.into(), ip_payload);
+            match self.filter.action(FilterDirection::Ingress, &filter_packet) {
+                FilterAction::Accept => (),
+                FilterAction::Drop => {
+                    net_debug!("filter: dropping packet from {}", ipv6_repr.src_addr);
+                    return None;
+                }
+                FilterAction::Reject => {
+                    net_debug!("filter: rejecting packet from {}", ipv6_repr.src_addr);
+                    return self.filter_reject_ipv6(ipv6_repr, ip_payload);
+                }
+            }
+        }
+
+        // Pass on the packets that are routed through this node of the RPL DODAG.
+        #[cfg(feature = "proto-rpl")]
+        if self.rpl_forwards(&ipv6_repr) {
+            return self.rpl_forward(ipv6_repr, ip_payload);
+        }
+
+        #[cfg(feature = "socket-dhcpv6")]
+        {
+            use crate::phy::Medium;
+
+            if ipv6_repr.next_header == IpProtocol::Udp
+                && matches!(self.caps.medium, Medium::Ethernet)
+                && self.has_ip_addr(ipv6_repr.dst_addr)
+            {
+                let udp_packet = check!(UdpPacket::new_checked(ip_payload));
+                if let Some(dhcp_socket) = sockets
+                    .items_mut()
+                    .find_map(|i| dhcpv6::Socket::downcast_mut(&mut i.socket))
+                {
+                    // Check the ports before validating the checksum, as in `process_ipv4`.
+                    if udp_packet.src_port() == dhcp_socket.server_port
+                        && udp_packet.dst_port() == dhcp_socket.client_port
+                    {
+                        let udp_repr = self.parse_udp(
+                            &udp_packet,
+                            &ipv6_repr.src_addr.into(),
+                            &ipv6_repr.dst_addr.into(),
+                            self.checksum_caps(),
+                        )?;
+                        let udp_payload = udp_packet.payload();
+
+                        dhcp_socket.process(self, &ipv6_repr, &udp_repr, udp_payload);
+                        return None;
+                    }
+                }
+            }
+        }
+
         #[cfg(feature = "socket-raw")]
         let handled_by_raw_socket = self.raw_socket_filter(sockets, &ipv6_repr.into(), ip_payload);
         #[cfg(not(feature = "socket-raw"))]
@@ -41,12 +106,17 @@ impl InterfaceInner {
             ipv6_repr.next_header,
             handled_by_raw_socket,
             ip_payload,
+            0,
         )
     }
 
     /// Given the next header value forward the payload onto the correct process
     /// function.
+    ///
+    /// The header starts at `offset` in `ipv6_payload`, the payload of the IPv6 packet,
+    /// after the extension headers processed so far.
     #[cfg(feature = "proto-ipv6")]
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn process_nxt_hdr<'frame>(
         &mut self,
         sockets: &mut SocketSet,
@@ -54,20 +124,28 @@ impl InterfaceInner {
         ipv6_repr: Ipv6Repr,
         nxt_hdr: IpProtocol,
         handled_by_raw_socket: bool,
-        ip_payload: &'frame [u8],
+        ipv6_payload: &'frame [u8],
+        offset: usize,
     ) -> Option<IpPacket<'frame>> {
+        let ip_payload = &ipv6_payload[offset..];
         match nxt_hdr {
             IpProtocol::Icmpv6 => self.process_icmpv6(sockets, ipv6_repr.into(), ip_payload),
 
             #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
             IpProtocol::Udp => {
                 let udp_packet = check!(UdpPacket::new_checked(ip_payload));
-                let udp_repr = check!(UdpRepr::parse(
+                // A zero checksum is invalid, unless the receiving socket accepts it.
+                let mut checksum_caps = self.checksum_caps();
+                let zero_checksum = checksum_caps.udp.rx() && udp_packet.checksum() == 0;
+                if zero_checksum {
+                    checksum_caps.udp = crate::phy::Checksum::None;
+                }
+                let udp_repr = self.parse_udp(
                     &udp_packet,
                     &ipv6_repr.src_addr.into(),
                     &ipv6_repr.dst_addr.into(),
-                    &self.checksum_caps(),
-                ));
+                    checksum_caps,
+                )?;
 
                 self.process_udp(
                     sockets,
@@ -75,57 +153,92 @@ impl InterfaceInner {
                     ipv6_repr.into(),
                     udp_repr,
                     handled_by_raw_socket,
+                    zero_checksum,
                     udp_packet.payload(),
                     ip_payload,
                 )
             }
 
+            #[cfg(feature = "socket-udp")]
+            IpProtocol::UdpLite => self.process_udplite(
+                sockets,
+                meta,
+                ipv6_repr.into(),
+                handled_by_raw_socket,
+                ip_payload,
+            ),
+
             #[cfg(feature = "socket-tcp")]
             IpProtocol::Tcp => self.process_tcp(sockets, ipv6_repr.into(), ip_payload),
 
-            IpProtocol::HopByHop => {
-                self.process_hopbyhop(sockets, meta, ipv6_repr, handled_by_raw_socket, ip_payload)
-            }
+            // The Hop-by-Hop Options header is only allowed right after the IPv6 header
+            // (RFC 8200 § 4.1), and is unrecognized anywhere else.
+            IpProtocol::HopByHop if offset == 0 => self.process_ext_options(
+                sockets,
+                meta,
+                ipv6_repr,
+                handled_by_raw_socket,
+                ipv6_payload,
+                offset,
+            ),
+
+            IpProtocol::Ipv6Opts => self.process_ext_options(
+                sockets,
+                meta,
+                ipv6_repr,
+                handled_by_raw_socket,
+                ipv6_payload,
+                offset,
+            ),
+
+            IpProtocol::Ipv6Route => self.process_routing(
+                sockets,
+                meta,
+                ipv6_repr,
+                handled_by_raw_socket,
+                ipv6_payload,
+                offset,
+            ),
+
+            IpProtocol::Ipv6NoNxt => None,
 
             #[cfg(feature = "socket-raw")]
             _ if handled_by_raw_socket => None,
 
-            _ => {
-                // Send back as much of the original payload as we can.
-                let payload_len =
-                    icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
-                let icmp_reply_repr = Icmpv6Repr::ParamProblem {
-                    reason: Icmpv6ParamProblem::UnrecognizedNxtHdr,
-                    // The offending packet is after the IPv6 header.
-                    pointer: ipv6_repr.buffer_len() as u32,
-                    header: ipv6_repr,
-                    data: &ip_payload[0..payload_len],
-                };
-                self.icmpv6_reply(ipv6_repr, icmp_reply_repr)
-            }
+            // The offending header starts at `offset`.
+            _ => self.icmpv6_param_problem(
+                ipv6_repr,
+                ipv6_payload,
+                Icmpv6ParamProblem::UnrecognizedNxtHdr,
+                offset,
+                false,
+            ),
         }
     }
 
     #[cfg(feature = "proto-ipv6")]
     pub(super) fn process_icmpv6<'frame>(
         &mut self,
-        _sockets: &mut SocketSet,
+        sockets: &mut SocketSet,
         ip_repr: IpRepr,
         ip_payload: &'frame [u8],
     ) -> Option<IpPacket<'frame>> {
         let icmp_packet = check!(Icmpv6Packet::new_checked(ip_payload));
-        let icmp_repr = check!(Icmpv6Repr::parse(
-            &ip_repr.src_addr(),
-            &ip_repr.dst_addr(),
-            &icmp_packet,
-            &self.caps.checksum,
-        ));
+        let (src_addr, dst_addr) = (ip_repr.src_addr(), ip_repr.dst_addr());
+        let icmp_repr = check!(
+            Icmpv6Repr::parse(&src_addr, &dst_addr, &icmp_packet, &self.caps.checksum),
+            if self.caps.checksum.icmpv6.rx() && !icmp_packet.verify_checksum(&src_addr, &dst_addr)
+            {
+                self.stats.icmpv6.count_checksum_error();
+            }
+        );
+        self.stats.icmpv6.count_rx(ip_payload.len());
 
         #[cfg(feature = "socket-icmp")]
         let mut handled_by_icmp_socket = false;
 
         #[cfg(all(feature = "socket-icmp", feature = "proto-ipv6"))]
-        for icmp_socket in _sockets
+        for icmp_socket in sockets
             .items_mut()
             .filter_map(|i| icmp::Socket::downcast_mut(&mut i.socket))
         {
@@ -165,6 +278,31 @@ impl InterfaceInner {
                 _ => unreachable!(),
             },
 
+            // Forward any RPL control messages to the RPL handler
+            #[cfg(feature = "proto-rpl")]
+            Icmpv6Repr::Rpl(repr) if self.rpl.is_some() => match ip_repr {
+                IpRepr::Ipv6(ipv6_repr) => self.process_rpl(ipv6_repr, repr),
+                #[allow(unreachable_patterns)]
+                _ => unreachable!(),
+            },
+
+            // Report the multicast groups joined by sockets when asked for by a router.
+            #[cfg(feature = "socket-udp")]
+            Icmpv6Repr::Mld(MldRepr::Query { mcast_addr, .. }) => match ip_repr {
+                IpRepr::Ipv6(ipv6_repr) => {
+                    self.process_mld_query(ipv6_repr, mcast_addr);
+                    None
+                }
+                #[allow(unreachable_patterns)]
+                _ => unreachable!(),
+            },
+
+            // Lower the MTU of the path the packet was sent on.
+            Icmpv6Repr::PktTooBig { mtu, header, data } => {
+                self.process_pkt_too_big(sockets, mtu, &header, data);
+                None
+            }
+
             // Don't report an error if a packet with unknown type
             // has been handled by an ICMP socket
             #[cfg(feature = "socket-icmp")]
@@ -190,6 +328,11 @@ impl InterfaceInner {
                 target_addr,
                 flags,
             } => {
+                // Another node answered for one of our tentative addresses.
+                if self.dad_conflict(target_addr) {
+                    return None;
+                }
+
                 let ip_addr = ip_repr.src_addr.into();
                 if let Some(lladdr) = lladdr {
                     let lladdr = check!(lladdr.parse(self.caps.medium));
@@ -201,6 +344,7 @@ impl InterfaceInner {
                     {
                         self.neighbor_cache.fill(ip_addr, lladdr, self.now)
                     }
+                    self.default_router_reachable(ip_repr.src_addr);
                 }
                 None
             }
@@ -218,15 +362,23 @@ impl InterfaceInner {
                         .fill(ip_repr.src_addr.into(), lladdr, self.now);
                 }
 
-                if self.has_solicited_node(ip_repr.dst_addr) && self.has_ip_addr(target_addr) {
+                if self.has_solicited_node(ip_repr.dst_addr)
+                    && (self.has_ip_addr(target_addr) || self.has_anycast_addr(target_addr))
+                {
                     let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
                         flags: NdiscNeighborFlags::SOLICITED,
                         target_addr,
                         #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
                         lladdr: Some(self.hardware_addr.into()),
                     });
+                    // Anycast addresses are never used as source address.
+                    let src_addr = if self.has_anycast_addr(target_addr) {
+                        self.get_source_address_ipv6(ip_repr.src_addr)?
+                    } else {
+                        target_addr
+                    };
                     let ip_repr = Ipv6Repr {
-                        src_addr: target_addr,
+                        src_addr,
                         dst_addr: ip_repr.src_addr,
                         next_header: IpProtocol::Icmpv6,
                         hop_limit: 0xff,
@@ -237,68 +389,240 @@ impl InterfaceInner {
                     None
                 }
             }
+            NdiscRepr::RouterAdvert {
+                hop_limit,
+                flags,
+                router_lifetime,
+                prefix_info,
+                rdnss,
+                dnssl,
+                #[cfg(feature = "proto-sixlowpan")]
+                sixlowpan_context,
+                pref64,
+                ..
+            } => {
+                // Router Advertisements must come from a link-local address (RFC 4861 § 6.1.2).
+                if ip_repr.src_addr.is_link_local() {
+                    self.ipv6_router_flags = Some(flags);
+                    // A Cur Hop Limit of zero means that it is left unspecified.
+                    if hop_limit != 0 {
+                        self.ipv6_router_hop_limit = Some(hop_limit);
+                    }
+                    self.process_router_advert(ip_repr.src_addr, router_lifetime, prefix_info);
+                    self.process_router_dns(rdnss, dnssl);
+                    self.process_pref64(pref64);
+                    #[cfg(feature = "proto-sixlowpan")]
+                    self.process_sixlowpan_context(sixlowpan_context);
+                    self.router_solicit_done(router_lifetime);
+                }
+                None
+            }
+            NdiscRepr::Redirect {
+                target_addr,
+                dest_addr,
+                lladdr,
+                ..
+            } => {
+                self.process_redirect(&ip_repr, target_addr, dest_addr, lladdr);
+                None
+            }
             _ => None,
         }
     }
 
+    /// Process a Hop-by-Hop or Destination Options header, applying the action that the
+    /// type of unrecognized options asks for (RFC 8200 § 4.2).
     #[cfg(feature = "proto-ipv6")]
-    pub(super) fn process_hopbyhop<'frame>(
+    pub(super) fn process_ext_options<'frame>(
         &mut self,
         sockets: &mut SocketSet,
         meta: PacketMeta,
         ipv6_repr: Ipv6Repr,
         handled_by_raw_socket: bool,
-        ip_payload: &'frame [u8],
+        ipv6_payload: &'frame [u8],
+        offset: usize,
     ) -> Option<IpPacket<'frame>> {
-        let hbh_hdr = check!(Ipv6HopByHopHeader::new_checked(ip_payload));
-        let hbh_repr = check!(Ipv6HopByHopRepr::parse(&hbh_hdr));
+        let ext_hdr = check!(Ipv6ExtHeader::new_checked(&ipv6_payload[offset..]));
+        let ext_repr = check!(Ipv6ExtHeaderRepr::parse(&ext_hdr));
 
-        let hbh_options = Ipv6OptionsIterator::new(hbh_repr.data);
-        for opt_repr in hbh_options {
+        let mut opt_offset = offset + ext_repr.header_len();
+        for opt_repr in Ipv6OptionsIterator::new(ext_repr.data) {
             let opt_repr = check!(opt_repr);
             match opt_repr {
-                Ipv6OptionRepr::Pad1 | Ipv6OptionRepr::PadN(_) => (),
+                Ipv6OptionRepr::Pad1 | Ipv6OptionRepr::PadN(_) => {}
+                // The Router Alert option is only of interest to routers.
+                Ipv6OptionRepr::RouterAlert(_) => {}
                 #[cfg(feature = "proto-rpl")]
                 Ipv6OptionRepr::Rpl(_) => {}
 
-                Ipv6OptionRepr::Unknown { type_, .. } => {
-                    match Ipv6OptionFailureType::from(type_) {
-                        Ipv6OptionFailureType::Skip => (),
-                        Ipv6OptionFailureType::Discard => {
-                            return None;
-                        }
-                        _ => {
-                            // FIXME(dlrobertson): Send an ICMPv6 parameter problem message
-                            // here.
-                            return None;
-                        }
+                Ipv6OptionRepr::Unknown { type_, .. } => match Ipv6OptionFailureType::from(type_) {
+                    Ipv6OptionFailureType::Skip => (),
+                    Ipv6OptionFailureType::Discard => {
+                        return None;
+                    }
+                    Ipv6OptionFailureType::DiscardSendUnicast
+                        if ipv6_repr.dst_addr.is_multicast() =>
+                    {
+                        return None;
                     }
+                    _ => {
+                        return self.icmpv6_param_problem(
+                            ipv6_repr,
+                            ipv6_payload,
+                            Icmpv6ParamProblem::UnrecognizedOption,
+                            opt_offset,
+                            true,
+                        );
+                    }
+                },
+            }
+            opt_offset += opt_repr.buffer_len();
+        }
+        self.process_nxt_hdr(
+            sockets,
+            meta,
+            ipv6_repr,
+            ext_repr.next_header,
+            handled_by_raw_socket,
+            ipv6_payload,
+            offset + ext_repr.header_len() + ext_repr.data.len(),
+        )
+    }
+
+    /// Process a Routing header (RFC 8200 § 4.4).
+    ///
+    /// We don't forward packets, so only the routing headers with no segments left, i.e.
+    /// for which we are the final destination, can be processed.
+    #[cfg(feature = "proto-ipv6")]
+    pub(super) fn process_routing<'frame>(
+        &mut self,
+        sockets: &mut SocketSet,
+        meta: PacketMeta,
+        ipv6_repr: Ipv6Repr,
+        handled_by_raw_socket: bool,
+        ipv6_payload: &'frame [u8],
+        offset: usize,
+    ) -> Option<IpPacket<'frame>> {
+        let ext_hdr = check!(Ipv6ExtHeader::new_checked(&ipv6_payload[offset..]));
+        let ext_repr = check!(Ipv6ExtHeaderRepr::parse(&ext_hdr));
+        let routing_hdr = check!(Ipv6RoutingHeader::new_checked(ext_repr.data));
+
+        if routing_hdr.segments_left() != 0 {
+            match routing_hdr.routing_type() {
+                Ipv6RoutingType::Type2 | Ipv6RoutingType::Rpl => {
+                    net_debug!("IPv6 routing header with segments left, dropping");
+                    return None;
+                }
+                // Type 0 is deprecated, and processed as an unrecognized type (RFC 5095).
+                _ => {
+                    return self.icmpv6_param_problem(
+                        ipv6_repr,
+                        ipv6_payload,
+                        Icmpv6ParamProblem::ErroneousHdrField,
+                        // The Routing Type field follows the Next Header and length fields.
+                        offset + ext_repr.header_len(),
+                        false,
+                    );
                 }
             }
         }
+
         self.process_nxt_hdr(
             sockets,
             meta,
             ipv6_repr,
-            hbh_repr.next_header,
+            ext_repr.next_header,
             handled_by_raw_socket,
-            &ip_payload[hbh_repr.header_len() + hbh_repr.data.len()..],
+            ipv6_payload,
+            offset + ext_repr.header_len() + ext_repr.data.len(),
         )
     }
 
+    /// Report a problem with the packet made of `ipv6_repr` and `ipv6_payload`, at `offset`
+    /// in the payload.
+    ///
+    /// Errors about packets sent to a multicast address are only sent if `to_multicast` is
+    /// set (RFC 4443 § 2.4 (e)).
+    #[cfg(feature = "proto-ipv6")]
+    fn icmpv6_param_problem<'frame>(
+        &mut self,
+        ipv6_repr: Ipv6Repr,
+        ipv6_payload: &'frame [u8],
+        reason: Icmpv6ParamProblem,
+        offset: usize,
+        to_multicast: bool,
+    ) -> Option<IpPacket<'frame>> {
+        // Send back as much of the original payload as we can.
+        let payload_len =
+            icmp_reply_payload_len(ipv6_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
+        let icmp_reply_repr = Icmpv6Repr::ParamProblem {
+            reason,
+            // The pointer is counted from the start of the IPv6 header.
+            pointer: (ipv6_repr.buffer_len() + offset) as u32,
+            header: ipv6_repr,
+            data: &ipv6_payload[..payload_len],
+        };
+
+        if to_multicast && ipv6_repr.dst_addr.is_multicast() {
+            let ipv6_reply_repr = Ipv6Repr {
+                src_addr: self.get_source_address_ipv6(ipv6_repr.src_addr)?,
+                dst_addr: ipv6_repr.src_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_reply_repr.buffer_len(),
+                hop_limit: self.ipv6_hop_limit(),
+            };
+            return Some(IpPacket::new_ipv6(
+                ipv6_reply_repr,
+                IpPayload::Icmpv6(icmp_reply_repr),
+            ));
+        }
+        self.icmpv6_reply(ipv6_repr, icmp_reply_repr)
+    }
+
+    /// Return the ICMPv6 error for a packet rejected by the filter, unless it was not sent
+    /// to one of our addresses, or is an ICMPv6 error itself.
+    #[cfg(feature = "proto-ipv6")]
+    fn filter_reject_ipv6<'frame>(
+        &mut self,
+        ipv6_repr: Ipv6Repr,
+        ip_payload: &'frame [u8],
+    ) -> Option<IpPacket<'frame>> {
+        if !(self.has_ip_addr(ipv6_repr.dst_addr) || self.has_anycast_addr(ipv6_repr.dst_addr))
+            || (ipv6_repr.next_header == IpProtocol::Icmpv6
+                && ip_payload.first().map_or(true, |&msg_type| msg_type < 128))
+        {
+            return None;
+        }
+
+        let payload_len =
+            icmp_reply_payload_len(ip_payload.len(), IPV6_MIN_MTU, ipv6_repr.buffer_len());
+        let icmp_reply_repr = Icmpv6Repr::DstUnreachable {
+            reason: Icmpv6DstUnreachable::AdminProhibit,
+            header: ipv6_repr,
+            data: &ip_payload[0..payload_len],
+        };
+        self.icmpv6_reply(ipv6_repr, icmp_reply_repr)
+    }
+
     #[cfg(feature = "proto-ipv6")]
     pub(super) fn icmpv6_reply<'frame, 'icmp: 'frame>(
-        &self,
+        &mut self,
         ipv6_repr: Ipv6Repr,
         icmp_repr: Icmpv6Repr<'icmp>,
     ) -> Option<IpPacket<'frame>> {
         if ipv6_repr.dst_addr.is_unicast() {
+            // Anycast addresses are never used as source address.
+            let src_addr = if self.has_anycast_addr(ipv6_repr.dst_addr) {
+                self.get_source_address_ipv6(ipv6_repr.src_addr)?
+            } else {
+                ipv6_repr.dst_addr
+            };
             let ipv6_reply_repr = Ipv6Repr {
-                src_addr: ipv6_repr.dst_addr,
+                src_addr,
                 dst_addr: ipv6_repr.src_addr,
                 next_header: IpProtocol::Icmpv6,
                 payload_len: icmp_repr.buffer_len(),
-                hop_limit: 64,
+                hop_limit: self.ipv6_hop_limit(),
             };
             Some(IpPacket::new_ipv6(
                 ipv6_reply_repr,
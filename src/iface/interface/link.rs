@@ -0,0 +1,102 @@
+use super::{Interface, InterfaceInner, SocketSet};
+#[cfg(any(feature = "socket-dhcpv4", feature = "socket-dhcpv6"))]
+use crate::socket::Socket;
+
+/// A function called when the link of an interface goes up or down, with the sockets
+/// that use it, see [`Interface::set_link_callback`].
+pub type LinkCallback = fn(up: bool, sockets: &mut SocketSet<'_>);
+
+/// State of the link of an interface.
+#[derive(Debug)]
+pub(crate) struct Link {
+    up: bool,
+    callback: Option<LinkCallback>,
+}
+
+impl Default for Link {
+    fn default() -> Self {
+        Self {
+            up: true,
+            callback: None,
+        }
+    }
+}
+
+impl Interface {
+    /// Return whether the link of the interface is up, which it is until told otherwise.
+    pub fn link_up(&self) -> bool {
+        self.inner.link_up()
+    }
+
+    /// Set the function called when the link goes up or down.
+    pub fn set_link_callback(&mut self, callback: Option<LinkCallback>) {
+        self.inner.link.callback = callback;
+    }
+
+    /// Tell the interface that the link went up or down, e.g. because the carrier of the
+    /// device was gained or lost.
+    ///
+    /// While the link is down, [poll](Self::poll) neither receives nor sends packets,
+    /// [poll_at](Self::poll_at) returns `None`, and no packet is forwarded through the
+    /// interface by a [Router](crate::iface::Router). Going down flushes the learned
+    /// entries of the neighbor cache, since the neighbors may be different ones once
+    /// the link is back. Going up announces the addresses of the interface again,
+    /// solicits routers if stateless autoconfiguration is enabled, and restarts the
+    /// DHCP clients among `sockets`.
+    ///
+    /// The link callback, if any, is called with `sockets` after that, so that the
+    /// application can e.g. abort its TCP connections. Nothing happens if the link
+    /// already was in the given state.
+    pub fn set_link_up(&mut self, up: bool, sockets: &mut SocketSet<'_>) {
+        if self.inner.link.up == up {
+            return;
+        }
+        self.inner.link.up = up;
+
+        if up {
+            net_debug!("iface: link up");
+            #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+            self.inner.announce_all();
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            if self.slaac() {
+                self.inner.router_solicit_start();
+            }
+            #[cfg(any(feature = "socket-dhcpv4", feature = "socket-dhcpv6"))]
+            for (_, socket) in sockets.iter_mut() {
+                match socket {
+                    #[cfg(feature = "socket-dhcpv4")]
+                    Socket::Dhcpv4(socket) => socket.reset(),
+                    #[cfg(feature = "socket-dhcpv6")]
+                    Socket::Dhcpv6(socket) => socket.reset(),
+                    #[allow(unreachable_patterns)]
+                    _ => (),
+                }
+            }
+        } else {
+            net_debug!("iface: link down");
+            InterfaceInner::flush_cache(&mut self.inner);
+            #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+            self.inner.announce_stop();
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            self.inner.router_solicit_stop();
+            #[cfg(feature = "_proto-fragmentation")]
+            self.fragmenter.reset();
+        }
+
+        if let Some(callback) = self.inner.link.callback {
+            callback(up, sockets);
+        }
+    }
+}
+
+impl InterfaceInner {
+    pub(super) fn link_up(&self) -> bool {
+        self.link.up
+    }
+}
@@ -0,0 +1,170 @@
+use byteorder::{ByteOrder, NetworkEndian};
+
+use super::{Interface, InterfaceInner, IpPacket, SocketSet};
+use crate::config::IFACE_LOCAL_BUFFER_SIZE;
+use crate::phy::{ChecksumCapabilities, PacketMeta};
+use crate::wire::IpAddress;
+
+/// Number of octets before each queued packet, which hold its length.
+const LEN_SIZE: usize = 2;
+
+/// Packets sent by the interface to its own addresses, waiting to be processed as if they
+/// had been received.
+pub(crate) struct LocalQueue {
+    enabled: bool,
+    /// The queued packets, oldest first, each preceded by its length.
+    buffer: [u8; IFACE_LOCAL_BUFFER_SIZE],
+    /// The number of octets of `buffer` in use.
+    len: usize,
+}
+
+impl LocalQueue {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: false,
+            buffer: [0; IFACE_LOCAL_BUFFER_SIZE],
+            len: 0,
+        }
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Queue `packet` for local delivery if it is sent to an address of the interface.
+    ///
+    /// Return `None` if the packet is not for local delivery, `Some(true)` if it was queued
+    /// (or dropped), and `Some(false)` if the queue is full.
+    pub(super) fn push(&mut self, inner: &mut InterfaceInner, packet: &IpPacket) -> Option<bool> {
+        if !self.enabled || !inner.is_local_addr(packet.ip_repr().dst_addr()) {
+            return None;
+        }
+        match enqueue(inner, &mut self.buffer, self.len, packet) {
+            Some(len) => {
+                self.len = len;
+                Some(true)
+            }
+            None => Some(false),
+        }
+    }
+}
+
+/// Append `packet` to the packets queued in `buffer`, which take `len` octets, and return
+/// the number of octets they take now; or `None` if there is no room left for it.
+fn enqueue(
+    inner: &mut InterfaceInner,
+    buffer: &mut [u8],
+    len: usize,
+    packet: &IpPacket,
+) -> Option<usize> {
+    let ip_repr = packet.ip_repr();
+    let packet_len = ip_repr.buffer_len();
+    if LEN_SIZE + packet_len > IFACE_LOCAL_BUFFER_SIZE {
+        net_debug!(
+            "local: dropping packet to {}, larger than the buffer",
+            ip_repr.dst_addr()
+        );
+        return Some(len);
+    }
+    if LEN_SIZE + packet_len > buffer.len() - len {
+        return None;
+    }
+    if !inner.filter_egress(packet) {
+        return Some(len);
+    }
+
+    let buffer = &mut buffer[len..][..LEN_SIZE + packet_len];
+    NetworkEndian::write_u16(&mut buffer[..LEN_SIZE], packet_len as u16);
+    let buffer = &mut buffer[LEN_SIZE..];
+    // The packet does not go through the device, so its checksums are always computed,
+    // whatever the device offloads.
+    let mut caps = inner.caps.clone();
+    caps.checksum = ChecksumCapabilities::default();
+    let header_len = packet.emit_header(&ip_repr, buffer, &caps.checksum);
+    packet.emit_payload(&ip_repr, &mut buffer[header_len..], &caps);
+
+    inner.stats.count_ip_tx(&ip_repr);
+    Some(len + LEN_SIZE + packet_len)
+}
+
+impl Interface {
+    /// Return whether the packets sent to the addresses of the interface are delivered
+    /// locally.
+    ///
+    /// See also the [set_local_delivery](#method.set_local_delivery) method
+    pub fn local_delivery(&self) -> bool {
+        self.local.enabled
+    }
+
+    /// Enable or disable the local delivery of the packets sent to the addresses of the
+    /// interface.
+    ///
+    /// When enabled, the packets the sockets send to an address assigned to the interface,
+    /// e.g. to connect to a TCP socket listening on it, do not go through the device. They
+    /// are kept in an internal buffer, of `IFACE_LOCAL_BUFFER_SIZE` octets, and processed by
+    /// [poll](#method.poll) as if they had been received. Loopback addresses are handled the
+    /// same way once assigned, e.g. `127.0.0.1/8` or `::1/128`, which makes a separate
+    /// [Loopback](crate::phy::Loopback) device unnecessary.
+    ///
+    /// Local delivery is disabled by default, in which case such packets are sent to the
+    /// device. The packets queued when it gets disabled are dropped. It can't be enabled
+    /// when `IFACE_LOCAL_BUFFER_SIZE` is 0, the default, which leaves no room for the
+    /// buffer in the interface.
+    pub fn set_local_delivery(&mut self, enabled: bool) {
+        if enabled && IFACE_LOCAL_BUFFER_SIZE == 0 {
+            net_debug!("local: delivery not enabled, IFACE_LOCAL_BUFFER_SIZE is 0");
+            return;
+        }
+        self.local.enabled = enabled;
+        if !enabled {
+            self.local.len = 0;
+        }
+    }
+
+    /// Process the packets queued for local delivery, and queue their responses.
+    pub(super) fn local_ingress(&mut self, sockets: &mut SocketSet<'_>) -> bool {
+        // Only the packets queued so far are processed, so that the responses to
+        // the responses wait for the next call.
+        let mut pending = self.local.len;
+        let processed_any = pending > 0;
+
+        while pending > 0 {
+            let local = &mut self.local;
+            let packet_len = NetworkEndian::read_u16(&local.buffer[..LEN_SIZE]) as usize;
+            let size = LEN_SIZE + packet_len;
+            let (packet, rest) = local.buffer.split_at_mut(size);
+
+            if let Some(response) = self.inner.process_ip(
+                sockets,
+                PacketMeta::default(),
+                &packet[LEN_SIZE..],
+                &mut self.fragments,
+            ) {
+                if self.inner.is_local_addr(response.ip_repr().dst_addr()) {
+                    match enqueue(&mut self.inner, rest, local.len - size, &response) {
+                        Some(len) => local.len = size + len,
+                        None => net_debug!("local: dropping response, buffer full"),
+                    }
+                } else {
+                    net_debug!(
+                        "local: dropping response to {}",
+                        response.ip_repr().dst_addr()
+                    );
+                }
+            }
+
+            local.buffer.copy_within(size..local.len, 0);
+            local.len -= size;
+            pending -= size;
+        }
+
+        processed_any
+    }
+}
+
+impl InterfaceInner {
+    /// Check whether packets sent to `addr` are for the interface itself.
+    fn is_local_addr(&self, addr: IpAddress) -> bool {
+        self.has_ip_addr(addr)
+    }
+}
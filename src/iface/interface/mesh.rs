@@ -0,0 +1,189 @@
+use super::*;
+
+/// The number of hops the packets sent over a mesh route can be forwarded over.
+const MESH_HOPS_LEFT: u8 = 8;
+
+/// A route of a mesh-under 6LoWPAN network, as described in [RFC 4944 § 11].
+///
+/// Frames for the final destination are sent to the next hop, with a mesh header holding the
+/// link-layer addresses of the originator and of the final destination. Nodes without a route
+/// are expected to be neighbors.
+///
+/// [RFC 4944 § 11]: https://datatracker.ietf.org/doc/html/rfc4944#section-11
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SixlowpanMeshRoute {
+    /// The link-layer address of the final destination.
+    pub final_addr: Ieee802154Address,
+    /// The link-layer address of the neighbor frames are sent to.
+    pub next_hop: Ieee802154Address,
+}
+
+/// A frame to forward to another node of the mesh.
+pub(super) struct MeshForward {
+    next_hop: Ieee802154Address,
+    mesh_repr: SixlowpanMeshRepr,
+    /// Where the payload following the mesh header starts in the frame.
+    payload_start: usize,
+}
+
+impl Interface {
+    /// Get the mesh-under routes.
+    pub fn sixlowpan_mesh_routes(&self) -> &Vec<SixlowpanMeshRoute, IFACE_MAX_ROUTE_COUNT> {
+        &self.inner.sixlowpan_mesh_routes
+    }
+
+    /// Get a mutable reference to the mesh-under routes.
+    pub fn sixlowpan_mesh_routes_mut(
+        &mut self,
+    ) -> &mut Vec<SixlowpanMeshRoute, IFACE_MAX_ROUTE_COUNT> {
+        &mut self.inner.sixlowpan_mesh_routes
+    }
+}
+
+impl InterfaceInner {
+    /// Return the neighbor the frames for a node of the mesh are sent to, if it is not a neighbor.
+    fn sixlowpan_mesh_next_hop(&self, final_addr: Ieee802154Address) -> Option<Ieee802154Address> {
+        self.sixlowpan_mesh_routes
+            .iter()
+            .find(|route| route.final_addr == final_addr)
+            .map(|route| route.next_hop)
+    }
+
+    /// Return the link-layer destination and the mesh header of a packet sent to a node.
+    pub(super) fn sixlowpan_mesh_header(
+        &self,
+        ll_dst_addr: Ieee802154Address,
+    ) -> (Ieee802154Address, Option<SixlowpanMeshRepr>) {
+        match self.sixlowpan_mesh_next_hop(ll_dst_addr) {
+            Some(next_hop) if next_hop != ll_dst_addr => (
+                next_hop,
+                Some(SixlowpanMeshRepr {
+                    hops_left: MESH_HOPS_LEFT,
+                    originator_addr: self.hardware_addr.ieee802154_or_panic(),
+                    final_addr: ll_dst_addr,
+                }),
+            ),
+            _ => (ll_dst_addr, None),
+        }
+    }
+
+    /// Process a frame with a mesh header that is addressed to this node, using the addresses
+    /// of the originator and of the final destination as the link-layer addresses of the packet.
+    pub(super) fn process_sixlowpan_mesh<'output, 'payload: 'output>(
+        &mut self,
+        sockets: &mut SocketSet,
+        meta: PacketMeta,
+        ieee802154_repr: &Ieee802154Repr,
+        payload: &'payload [u8],
+        f: &'output mut FragmentsBuffer,
+    ) -> Option<IpPacket<'output>> {
+        let mesh = check!(SixlowpanMeshPacket::new_checked(payload));
+        let mesh_repr = check!(SixlowpanMeshRepr::parse(&mesh));
+
+        if mesh_repr.final_addr != self.hardware_addr.ieee802154_or_panic()
+            && !mesh_repr.final_addr.is_broadcast()
+        {
+            net_debug!("6LoWPAN: dropping mesh packet for {}", mesh_repr.final_addr);
+            return None;
+        }
+
+        // A mesh header cannot be nested.
+        let payload = mesh.payload();
+        if matches!(
+            SixlowpanPacket::dispatch(payload),
+            Ok(SixlowpanPacket::MeshHeader)
+        ) {
+            return None;
+        }
+
+        let ieee802154_repr = Ieee802154Repr {
+            src_addr: Some(mesh_repr.originator_addr),
+            dst_addr: Some(mesh_repr.final_addr),
+            ..*ieee802154_repr
+        };
+        self.process_sixlowpan(sockets, meta, &ieee802154_repr, payload, f)
+    }
+
+    /// Check whether a received frame has to be forwarded to another node of the mesh.
+    ///
+    /// Fragments are forwarded as they are, without being reassembled. Broadcast packets are
+    /// not forwarded.
+    pub(super) fn sixlowpan_mesh_forward(&self, frame: &[u8]) -> Option<MeshForward> {
+        let ieee802154_frame = Ieee802154Frame::new_checked(frame).ok()?;
+        let ieee_repr = Ieee802154Repr::parse(&ieee802154_frame).ok()?;
+        let our_addr = self.hardware_addr.ieee802154_or_panic();
+
+        if ieee_repr.frame_type != Ieee802154FrameType::Data
+            || ieee_repr.dst_addr != Some(our_addr)
+            || (self.pan_id.is_some() && ieee_repr.dst_pan_id != self.pan_id)
+        {
+            return None;
+        }
+
+        let payload = ieee802154_frame.payload()?;
+        let mesh = SixlowpanMeshPacket::new_checked(payload).ok()?;
+        let mesh_repr = SixlowpanMeshRepr::parse(&mesh).ok()?;
+        if mesh_repr.final_addr == our_addr || mesh_repr.final_addr.is_broadcast() {
+            return None;
+        }
+
+        // The hop count has to stay above zero once decremented, the packet is dropped
+        // otherwise.
+        if mesh_repr.hops_left <= 1 {
+            return None;
+        }
+
+        let next_hop = self
+            .sixlowpan_mesh_next_hop(mesh_repr.final_addr)
+            .unwrap_or(mesh_repr.final_addr);
+
+        Some(MeshForward {
+            next_hop,
+            mesh_repr: SixlowpanMeshRepr {
+                hops_left: mesh_repr.hops_left - 1,
+                ..mesh_repr
+            },
+            payload_start: frame.len() - mesh.payload().len(),
+        })
+    }
+
+    /// Send a frame on to the next hop towards its final destination.
+    pub(super) fn dispatch_sixlowpan_mesh_forward<Tx: TxToken>(
+        &mut self,
+        tx_token: Tx,
+        frame: &[u8],
+        forward: MeshForward,
+    ) {
+        let ieee_repr = Ieee802154Repr {
+            frame_type: Ieee802154FrameType::Data,
+            security_enabled: false,
+            frame_pending: false,
+            ack_request: false,
+            sequence_number: Some(self.get_sequence_number()),
+            pan_id_compression: true,
+            frame_version: Ieee802154FrameVersion::Ieee802154_2003,
+            dst_pan_id: self.pan_id,
+            dst_addr: Some(forward.next_hop),
+            src_pan_id: self.pan_id,
+            src_addr: Some(self.hardware_addr.ieee802154_or_panic()),
+        };
+        let payload = &frame[forward.payload_start..];
+        let ieee_len = ieee_repr.buffer_len();
+        let mesh_len = forward.mesh_repr.buffer_len();
+
+        net_trace!(
+            "6LoWPAN: forwarding mesh packet for {} to {}",
+            forward.mesh_repr.final_addr,
+            forward.next_hop
+        );
+
+        self.stats.count_tx(ieee_len + mesh_len + payload.len());
+        tx_token.consume(ieee_len + mesh_len + payload.len(), |tx_buf| {
+            ieee_repr.emit(&mut Ieee802154Frame::new_unchecked(&mut tx_buf[..ieee_len]));
+            let mut mesh_packet = SixlowpanMeshPacket::new_unchecked(&mut tx_buf[ieee_len..]);
+            forward.mesh_repr.emit(&mut mesh_packet);
+            mesh_packet.payload_mut()[..payload.len()].copy_from_slice(payload);
+        });
+    }
+}
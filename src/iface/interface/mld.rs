@@ -0,0 +1,215 @@
+use heapless::Vec;
+
+use super::{Interface, InterfaceInner, IpPacket, IpPayload};
+use crate::config::IFACE_MAX_MULTICAST_GROUP_COUNT;
+use crate::iface::SocketSet;
+use crate::phy::{Device, PacketMeta};
+use crate::socket::{udp, AnySocket};
+use crate::wire::*;
+
+/// The length of an MLDv2 address record without sources.
+const MLD_RECORD_LEN: usize = 20;
+
+/// The length of the buffer MLDv2 reports are built in, which fits a record for every group.
+const MLD_REPORT_BUFFER_LEN: usize = IFACE_MAX_MULTICAST_GROUP_COUNT * MLD_RECORD_LEN;
+
+/// Which memberships to report via MLD in answer to a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MldReportState {
+    Inactive,
+    ToGeneralQuery,
+    ToSpecificQuery { group: Ipv6Address },
+}
+
+impl Interface {
+    /// Send MLDv2 reports for the IPv6 groups newly joined and left by sockets, and for
+    /// the groups asked for by a query, as described in [RFC 3810 § 6].
+    ///
+    /// [RFC 3810 § 6]: https://tools.ietf.org/html/rfc3810#section-6
+    pub(crate) fn mld_egress<D>(&mut self, device: &mut D, sockets: &SocketSet) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        // 6LoWPAN nodes register their addresses with their router instead (RFC 6775),
+        // and the compressed headers do not carry the Router Alert option.
+        #[cfg(feature = "medium-ieee802154")]
+        if self.inner.caps.medium == crate::phy::Medium::Ieee802154 {
+            return false;
+        }
+
+        let mut groups = Vec::<Ipv6Address, IFACE_MAX_MULTICAST_GROUP_COUNT>::new();
+        for socket in sockets
+            .items()
+            .filter_map(|i| udp::Socket::downcast(&i.socket))
+        {
+            for addr in socket.multicast_groups() {
+                match addr {
+                    // Groups that do not fit in the table of the interface are never reported.
+                    IpAddress::Ipv6(addr) if is_reported(addr) && !groups.contains(&addr) => {
+                        let _ = groups.push(addr);
+                    }
+                    #[allow(unreachable_patterns)]
+                    _ => (),
+                }
+            }
+        }
+
+        let groups_table = &self.inner.ipv6_socket_multicast_groups;
+        let joined = groups
+            .iter()
+            .copied()
+            .filter(|addr| !groups_table.contains_key(addr))
+            .map(|addr| (MldRecordType::ChangeToExclude, addr));
+        let left = groups_table
+            .keys()
+            .copied()
+            .filter(|addr| !groups.contains(addr))
+            .map(|addr| (MldRecordType::ChangeToInclude, addr));
+        let mut changes = Vec::<_, IFACE_MAX_MULTICAST_GROUP_COUNT>::new();
+        for change in joined.chain(left) {
+            if changes.push(change).is_err() {
+                break;
+            }
+        }
+
+        let mut did_something = false;
+
+        if !changes.is_empty() {
+            let mut buffer = [0; MLD_REPORT_BUFFER_LEN];
+            let changes = changes.iter().copied();
+            if let Some(pkt) = self.inner.mld_report_packet(changes.clone(), &mut buffer) {
+                let Some(tx_token) = device.transmit(self.inner.now) else {
+                    return false;
+                };
+                // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+                self.inner
+                    .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+                    .unwrap();
+            }
+
+            for (record_type, addr) in changes {
+                if record_type == MldRecordType::ChangeToExclude {
+                    // NOTE(unwrap): the groups joined by sockets fit in the table.
+                    self.inner
+                        .ipv6_socket_multicast_groups
+                        .insert(addr, ())
+                        .unwrap();
+                } else {
+                    self.inner.ipv6_socket_multicast_groups.remove(&addr);
+                }
+            }
+            did_something = true;
+        }
+
+        if self.inner.mld_report_state != MldReportState::Inactive {
+            let groups_table = &self.inner.ipv6_socket_multicast_groups;
+            let mut groups = Vec::<_, IFACE_MAX_MULTICAST_GROUP_COUNT>::new();
+            match self.inner.mld_report_state {
+                MldReportState::ToSpecificQuery { group } if groups_table.contains_key(&group) => {
+                    let _ = groups.push((MldRecordType::ModeIsExclude, group));
+                }
+                MldReportState::ToGeneralQuery => {
+                    for &addr in groups_table.keys() {
+                        let _ = groups.push((MldRecordType::ModeIsExclude, addr));
+                    }
+                }
+                _ => (),
+            }
+
+            if !groups.is_empty() {
+                let mut buffer = [0; MLD_REPORT_BUFFER_LEN];
+                let groups = groups.iter().copied();
+                if let Some(pkt) = self.inner.mld_report_packet(groups, &mut buffer) {
+                    let Some(tx_token) = device.transmit(self.inner.now) else {
+                        return did_something;
+                    };
+                    // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+                    self.inner
+                        .dispatch_ip(tx_token, PacketMeta::default(), pkt, &mut self.fragmenter)
+                        .unwrap();
+                }
+            }
+
+            self.inner.mld_report_state = MldReportState::Inactive;
+            did_something = true;
+        }
+
+        did_something
+    }
+}
+
+impl InterfaceInner {
+    /// Set up `mld_report_state` for answering an MLD query about `group_addr`, or about all
+    /// the groups if it is unspecified.
+    ///
+    /// Memberships must not be reported immediately in order to avoid flooding the network
+    /// after a query is sent by a router; this is not currently done.
+    pub(super) fn process_mld_query(&mut self, ipv6_repr: Ipv6Repr, group_addr: Ipv6Address) {
+        // Queries are sent by routers on the link, with a link-local source address.
+        if ipv6_repr.hop_limit != 1 || !ipv6_repr.src_addr.is_link_local() {
+            return;
+        }
+
+        if group_addr.is_unspecified() {
+            if !self.ipv6_socket_multicast_groups.is_empty() {
+                self.mld_report_state = MldReportState::ToGeneralQuery;
+            }
+        } else if self.ipv6_socket_multicast_groups.contains_key(&group_addr)
+            && self.mld_report_state == MldReportState::Inactive
+        {
+            self.mld_report_state = MldReportState::ToSpecificQuery { group: group_addr };
+        }
+    }
+
+    /// Build an MLDv2 report with a record of the given type for every group, which is sent
+    /// from the link-local address of the interface, if it has one.
+    fn mld_report_packet<'a>(
+        &self,
+        records: impl Iterator<Item = (MldRecordType, Ipv6Address)>,
+        buffer: &'a mut [u8],
+    ) -> Option<IpPacket<'a>> {
+        let src_addr = self.ipv6_link_local_addr()?;
+
+        let mut len = 0;
+        let mut nr_mcast_addr_rcrds = 0;
+        for (record_type, addr) in records {
+            let mut record =
+                MldAddressRecord::new_unchecked(&mut buffer[len..len + MLD_RECORD_LEN]);
+            record.set_record_type(record_type);
+            record.set_aux_data_len(0);
+            record.set_num_srcs(0);
+            record.set_mcast_addr(addr);
+            len += MLD_RECORD_LEN;
+            nr_mcast_addr_rcrds += 1;
+        }
+
+        let buffer: &'a [u8] = buffer;
+        let icmp_repr = Icmpv6Repr::Mld(MldRepr::Report {
+            nr_mcast_addr_rcrds,
+            data: &buffer[..len],
+        });
+        let mut packet = IpPacket::new_ipv6(
+            Ipv6Repr {
+                src_addr,
+                dst_addr: Ipv6Address::LINK_LOCAL_ALL_MLDV2_ROUTERS,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: icmp_repr.buffer_len(),
+                hop_limit: 1,
+            },
+            IpPayload::Icmpv6(icmp_repr),
+        );
+        // MLD messages carry the Router Alert option (RFC 3810 § 5).
+        packet.set_router_alert();
+        Some(packet)
+    }
+}
+
+/// Check whether the membership of a group is reported, which is not the case for the
+/// link-local all-nodes group and the groups of the reserved and interface-local scopes,
+/// as described in [RFC 3810 § 6].
+///
+/// [RFC 3810 § 6]: https://tools.ietf.org/html/rfc3810#section-6
+fn is_reported(addr: Ipv6Address) -> bool {
+    let scope = addr.as_bytes()[1] & 0x0f;
+    addr != Ipv6Address::LINK_LOCAL_ALL_NODES && scope > 1
+}
@@ -19,28 +19,115 @@ mod sixlowpan;
 
 #[cfg(feature = "proto-igmp")]
 mod igmp;
+#[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+mod mld;
 
+pub use addr_lifetime::AddressLifetime;
+pub use destination::DestinationEntry;
 #[cfg(feature = "proto-igmp")]
-pub use igmp::MulticastError;
+pub use igmp::{MulticastError, MulticastFilterMode};
+pub use link::LinkCallback;
+pub use notify::{ConfigCallback, ConfigChange};
+#[cfg(feature = "medium-ethernet")]
+pub use wake::{WakeCallback, WakeError};
+
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+pub use dad::DadEvent;
+#[cfg(feature = "medium-ieee802154")]
+pub use mesh::SixlowpanMeshRoute;
+
+pub(crate) use forward::Forward;
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+pub(crate) use forward::{forward_route_key, Forwarded};
+pub use forward::{HostModel, ReversePathFilter};
+
+mod addr_lifetime;
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+mod announce;
+#[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+mod autoip;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod dad;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod default_router;
+mod destination;
+mod forward;
+mod link;
+mod local;
+#[cfg(feature = "medium-ieee802154")]
+mod mesh;
+#[cfg(feature = "proto-ipv6")]
+mod nat64;
+mod notify;
+#[cfg(feature = "proto-ipv6")]
+mod pmtu;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod rdnss;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod redirect;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod router_solicit;
+#[cfg(feature = "proto-rpl")]
+mod rpl;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+mod slaac;
+#[cfg(feature = "medium-ethernet")]
+mod wake;
 
 use super::ip_packet::*;
 
+use core::cmp::Reverse;
 use core::result::Result;
 use heapless::{LinearMap, Vec};
 
 #[cfg(any(feature = "proto-ipv4", feature = "proto-sixlowpan"))]
 use super::fragmentation::PacketAssemblerSet;
+#[cfg(feature = "proto-ipv4-fragmentation")]
+use super::fragmentation::ReassemblyStats;
 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
-use super::neighbor::{Answer as NeighborAnswer, Cache as NeighborCache};
+use super::neighbor::{
+    Answer as NeighborAnswer, Cache as NeighborCache, NeighborCacheFull, NeighborEntry,
+};
+use super::route::RouteKey;
+#[cfg(feature = "socket-tcp")]
+use super::socket_set::SocketHandle;
 use super::socket_set::SocketSet;
+#[cfg(feature = "medium-ieee802154")]
+use crate::config::IFACE_MAX_ROUTE_COUNT;
 use crate::config::{
     FRAGMENTATION_BUFFER_SIZE, IFACE_MAX_ADDR_COUNT, IFACE_MAX_MULTICAST_GROUP_COUNT,
     IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT,
 };
-use crate::iface::Routes;
+use crate::iface::filter::{FilterDirection, FilterPacket};
+#[cfg(feature = "proto-rpl")]
+use crate::iface::RplConfig;
+use crate::iface::{Filter, FilterAction, Routes, Stats};
 use crate::phy::PacketMeta;
 use crate::phy::{ChecksumCapabilities, Device, DeviceCapabilities, Medium, RxToken, TxToken};
 use crate::rand::Rand;
+#[cfg(feature = "proto-ipv6")]
+use crate::siphash::siphash;
 #[cfg(feature = "socket-dns")]
 use crate::socket::dns;
 use crate::socket::*;
@@ -72,6 +159,19 @@ pub(crate) struct FragmentsBuffer {
 
     #[cfg(feature = "_proto-fragmentation")]
     reassembly_timeout: Duration,
+
+    /// The number of 6LoWPAN packets from the same source that can be reassembled at once.
+    #[cfg(feature = "proto-sixlowpan-fragmentation")]
+    sixlowpan_reassembly_limit: usize,
+
+    /// The number of IPv4 packets from the same source that can be reassembled at once.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    ipv4_reassembly_limit: usize,
+    /// The number of octets that IPv4 packets being reassembled can take, in total.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    ipv4_reassembly_memory_limit: usize,
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    ipv4_reassembly_stats: ReassemblyStats,
 }
 
 #[cfg(not(feature = "_proto-fragmentation"))]
@@ -103,6 +203,8 @@ pub(crate) struct Fragmenter {
 pub(crate) struct Ipv4Fragmenter {
     /// The IPv4 representation.
     repr: Ipv4Repr,
+    /// The DSCP and ECN bits.
+    traffic_class: u8,
     /// The destination hardware address.
     #[cfg(feature = "medium-ethernet")]
     dst_hardware_addr: EthernetAddress,
@@ -127,6 +229,8 @@ pub(crate) struct SixlowpanFragmenter {
     ll_dst_addr: Ieee802154Address,
     /// The link layer IEEE802.15.4 source address.
     ll_src_addr: Ieee802154Address,
+    /// The mesh header sent before the fragmentation headers, if any.
+    mesh_repr: Option<SixlowpanMeshRepr>,
 }
 
 #[cfg(feature = "_proto-fragmentation")]
@@ -146,6 +250,7 @@ impl Fragmenter {
                     payload_len: 0,
                     hop_limit: 0,
                 },
+                traffic_class: 0,
                 #[cfg(feature = "medium-ethernet")]
                 dst_hardware_addr: EthernetAddress::default(),
                 frag_offset: 0,
@@ -160,6 +265,7 @@ impl Fragmenter {
                 fragn_size: 0,
                 ll_dst_addr: Ieee802154Address::Absent,
                 ll_src_addr: Ieee802154Address::Absent,
+                mesh_repr: None,
             },
         }
     }
@@ -190,6 +296,7 @@ impl Fragmenter {
                 payload_len: 0,
                 hop_limit: 0,
             };
+            self.ipv4.traffic_class = 0;
             #[cfg(feature = "medium-ethernet")]
             {
                 self.ipv4.dst_hardware_addr = EthernetAddress::default();
@@ -203,15 +310,21 @@ impl Fragmenter {
             self.sixlowpan.fragn_size = 0;
             self.sixlowpan.ll_dst_addr = Ieee802154Address::Absent;
             self.sixlowpan.ll_src_addr = Ieee802154Address::Absent;
+            self.sixlowpan.mesh_repr = None;
         }
     }
 }
 
 macro_rules! check {
     ($e:expr) => {
+        check!($e, ())
+    };
+    // Run `$on_err` before returning, e.g. to count the error.
+    ($e:expr, $on_err:expr) => {
         match $e {
             Ok(x) => x,
             Err(_) => {
+                $on_err;
                 // concat!/stringify! doesn't work with defmt macros
                 #[cfg(not(feature = "defmt"))]
                 net_trace!(concat!("iface: malformed ", stringify!($e)));
@@ -224,6 +337,10 @@ macro_rules! check {
 }
 use check;
 
+/// The default TTL or hop limit of the packets sent by an interface, recommended by
+/// [IANA](https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml).
+const DEFAULT_HOP_LIMIT: u8 = 64;
+
 /// A  network interface.
 ///
 /// The network interface logically owns a number of other data structures; to avoid
@@ -233,6 +350,7 @@ pub struct Interface {
     inner: InterfaceInner,
     fragments: FragmentsBuffer,
     fragmenter: Fragmenter,
+    local: local::LocalQueue,
 }
 
 /// The device independent part of an Ethernet network interface.
@@ -250,6 +368,10 @@ pub struct InterfaceInner {
     #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
     neighbor_cache: NeighborCache,
     hardware_addr: HardwareAddress,
+    link: link::Link,
+    notify: notify::Notify,
+    #[cfg(feature = "medium-ethernet")]
+    wake_callback: Option<WakeCallback>,
     #[cfg(feature = "medium-ieee802154")]
     sequence_no: u8,
     #[cfg(feature = "medium-ieee802154")]
@@ -259,17 +381,118 @@ pub struct InterfaceInner {
     #[cfg(feature = "proto-sixlowpan")]
     sixlowpan_address_context:
         Vec<SixlowpanAddressContext, IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT>,
+    #[cfg(feature = "proto-sixlowpan")]
+    sixlowpan_context_lifetimes:
+        [Option<sixlowpan::ContextLifetime>; IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT],
+    #[cfg(feature = "medium-ieee802154")]
+    sixlowpan_mesh_routes: Vec<SixlowpanMeshRoute, IFACE_MAX_ROUTE_COUNT>,
     #[cfg(feature = "proto-sixlowpan-fragmentation")]
     tag: u16,
     ip_addrs: Vec<IpCidr, IFACE_MAX_ADDR_COUNT>,
+    /// Lifetimes of the addresses that don't last forever.
+    addr_lifetimes: addr_lifetime::AddressLifetimes,
+    #[cfg(feature = "proto-ipv6")]
+    ipv6_anycast_addrs: Vec<Ipv6Address, IFACE_MAX_ADDR_COUNT>,
     #[cfg(feature = "proto-ipv4")]
     any_ip: bool,
+    #[cfg(feature = "proto-ipv4")]
+    directed_broadcast: bool,
+    reverse_path_filter: ReversePathFilter,
+    host_model: HostModel,
+    /// Default TTL or hop limit of the packets sent by the interface and its sockets.
+    hop_limit: u8,
     routes: Routes,
+    filter: Filter,
+    stats: Stats,
     #[cfg(feature = "proto-igmp")]
-    ipv4_multicast_groups: LinearMap<Ipv4Address, (), IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    ipv4_multicast_groups:
+        LinearMap<Ipv4Address, igmp::SourceFilter, IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    /// The multicast groups that were joined on behalf of sockets only, and are left
+    /// once no socket is a member anymore.
+    #[cfg(all(feature = "proto-igmp", feature = "socket-udp"))]
+    ipv4_socket_multicast_groups: LinearMap<Ipv4Address, (), IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    /// The IPv6 multicast groups joined by sockets, as last reported via MLD.
+    #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+    ipv6_socket_multicast_groups: LinearMap<Ipv6Address, (), IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    /// Which IPv6 multicast group memberships to report via MLD in answer to a query
+    #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+    mld_report_state: mld::MldReportState,
     /// When to report for (all or) the next multicast group membership via IGMP
     #[cfg(feature = "proto-igmp")]
     igmp_report_state: IgmpReportState,
+    #[cfg(feature = "proto-igmp")]
+    igmp_older_queriers: igmp::OlderQueriers,
+    /// Secret key used to generate TCP initial sequence numbers and SYN cookies.
+    #[cfg(feature = "socket-tcp")]
+    tcp_secret: [u64; 2],
+    /// Secret key used to generate IPv6 flow labels.
+    #[cfg(feature = "proto-ipv6")]
+    flow_label_secret: [u64; 2],
+    /// Whether flow labels are generated for the transport flows of sockets.
+    #[cfg(feature = "proto-ipv6")]
+    ipv6_flow_labels: bool,
+    /// Flags of the last Router Advertisement received.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    ipv6_router_flags: Option<NdiscRouterFlags>,
+    /// Cur Hop Limit of the last Router Advertisement that had one.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    ipv6_router_hop_limit: Option<u8>,
+    /// Addresses configured from Router Advertisements.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    slaac: slaac::Slaac,
+    /// DNS configuration learned from Router Advertisements.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    router_dns: rdnss::RouterDns,
+    /// Addresses to announce to the neighbors.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    announce: announce::Announcements,
+    /// IPv4 link-local address autoconfiguration.
+    #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+    autoip: autoip::AutoIp,
+    /// Addresses undergoing Duplicate Address Detection.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    dad: dad::Dad,
+    /// Routers that advertised themselves as default routers.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    default_routers: default_router::DefaultRouters,
+    /// NAT64 prefix, set by hand or learned from Router Advertisements.
+    #[cfg(feature = "proto-ipv6")]
+    nat64: nat64::Nat64,
+    /// Routers, path MTUs and TCP hints of the destinations packets were sent to.
+    destinations: destination::DestinationCache,
+    /// Next hops learned from Redirect messages.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    redirects: redirect::Redirects,
+    /// Pending Router Solicitations.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    router_solicit: router_solicit::RouterSolicit,
+    /// State of RPL, if enabled.
+    #[cfg(feature = "proto-rpl")]
+    rpl: Option<rpl::Rpl>,
 }
 
 /// Configuration structure used for creating a network interface.
@@ -294,6 +517,13 @@ pub struct Config {
     /// **NOTE**: we use the same PAN ID for destination and source.
     #[cfg(feature = "medium-ieee802154")]
     pub pan_id: Option<Ieee802154Pan>,
+
+    /// Enable RPL with the given configuration.
+    ///
+    /// The interface then joins a DODAG advertised on the link, or builds its own if it
+    /// is configured as a root, and routes the packets to global destinations along it.
+    #[cfg(feature = "proto-rpl")]
+    pub rpl_config: Option<RplConfig>,
 }
 
 impl Config {
@@ -303,6 +533,8 @@ impl Config {
             hardware_addr,
             #[cfg(feature = "medium-ieee802154")]
             pan_id: None,
+            #[cfg(feature = "proto-rpl")]
+            rpl_config: None,
         }
     }
 }
@@ -358,6 +590,18 @@ impl Interface {
             }
         }
 
+        #[cfg(feature = "socket-tcp")]
+        let tcp_secret = [
+            ((rand.rand_u32() as u64) << 32) | rand.rand_u32() as u64,
+            ((rand.rand_u32() as u64) << 32) | rand.rand_u32() as u64,
+        ];
+
+        #[cfg(feature = "proto-ipv6")]
+        let flow_label_secret = [
+            ((rand.rand_u32() as u64) << 32) | rand.rand_u32() as u64,
+            ((rand.rand_u32() as u64) << 32) | rand.rand_u32() as u64,
+        ];
+
         Interface {
             fragments: FragmentsBuffer {
                 #[cfg(feature = "proto-sixlowpan")]
@@ -367,22 +611,53 @@ impl Interface {
                 assembler: PacketAssemblerSet::new(),
                 #[cfg(feature = "_proto-fragmentation")]
                 reassembly_timeout: Duration::from_secs(60),
+                #[cfg(feature = "proto-sixlowpan-fragmentation")]
+                sixlowpan_reassembly_limit: crate::config::REASSEMBLY_BUFFER_COUNT,
+                #[cfg(feature = "proto-ipv4-fragmentation")]
+                ipv4_reassembly_limit: crate::config::REASSEMBLY_BUFFER_COUNT,
+                #[cfg(feature = "proto-ipv4-fragmentation")]
+                ipv4_reassembly_memory_limit: usize::MAX,
+                #[cfg(feature = "proto-ipv4-fragmentation")]
+                ipv4_reassembly_stats: ReassemblyStats::default(),
             },
             fragmenter: Fragmenter::new(),
+            local: local::LocalQueue::new(),
             inner: InterfaceInner {
                 now,
                 caps,
                 hardware_addr: config.hardware_addr,
                 ip_addrs: Vec::new(),
+                addr_lifetimes: addr_lifetime::AddressLifetimes::default(),
+                #[cfg(feature = "proto-ipv6")]
+                ipv6_anycast_addrs: Vec::new(),
                 #[cfg(feature = "proto-ipv4")]
                 any_ip: false,
+                #[cfg(feature = "proto-ipv4")]
+                directed_broadcast: false,
+                reverse_path_filter: ReversePathFilter::Disabled,
+                host_model: HostModel::Strong,
+                hop_limit: DEFAULT_HOP_LIMIT,
                 routes: Routes::new(),
+                filter: Filter::new(),
+                stats: Stats::default(),
+                link: link::Link::default(),
+                notify: notify::Notify::default(),
+                #[cfg(feature = "medium-ethernet")]
+                wake_callback: None,
                 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
                 neighbor_cache: NeighborCache::new(),
                 #[cfg(feature = "proto-igmp")]
                 ipv4_multicast_groups: LinearMap::new(),
+                #[cfg(all(feature = "proto-igmp", feature = "socket-udp"))]
+                ipv4_socket_multicast_groups: LinearMap::new(),
+                #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+                ipv6_socket_multicast_groups: LinearMap::new(),
+                #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+                mld_report_state: mld::MldReportState::Inactive,
                 #[cfg(feature = "proto-igmp")]
                 igmp_report_state: IgmpReportState::Inactive,
+                #[cfg(feature = "proto-igmp")]
+                igmp_older_queriers: igmp::OlderQueriers::default(),
                 #[cfg(feature = "medium-ieee802154")]
                 sequence_no,
                 #[cfg(feature = "medium-ieee802154")]
@@ -393,6 +668,67 @@ impl Interface {
                 ipv4_id,
                 #[cfg(feature = "proto-sixlowpan")]
                 sixlowpan_address_context: Vec::new(),
+                #[cfg(feature = "proto-sixlowpan")]
+                sixlowpan_context_lifetimes: [None; IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT],
+                #[cfg(feature = "medium-ieee802154")]
+                sixlowpan_mesh_routes: Vec::new(),
+                #[cfg(feature = "socket-tcp")]
+                tcp_secret,
+                #[cfg(feature = "proto-ipv6")]
+                flow_label_secret,
+                #[cfg(feature = "proto-ipv6")]
+                ipv6_flow_labels: true,
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                ipv6_router_flags: None,
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                ipv6_router_hop_limit: None,
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                slaac: slaac::Slaac::default(),
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                router_dns: rdnss::RouterDns::default(),
+                #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+                announce: announce::Announcements::default(),
+                #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+                autoip: autoip::AutoIp::default(),
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                dad: dad::Dad::default(),
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                default_routers: default_router::DefaultRouters::default(),
+                #[cfg(feature = "proto-ipv6")]
+                nat64: nat64::Nat64::default(),
+                destinations: destination::DestinationCache::default(),
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                redirects: redirect::Redirects::default(),
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                router_solicit: router_solicit::RouterSolicit::default(),
+                #[cfg(feature = "proto-rpl")]
+                rpl: config
+                    .rpl_config
+                    .map(|config| rpl::Rpl::new(config, now, &mut rand)),
                 rand,
             },
         }
@@ -427,6 +763,13 @@ impl Interface {
 
     /// Set the HardwareAddress address of the interface.
     ///
+    /// The hardware address can be changed at any time, e.g. to randomize it or to take
+    /// over the one of a failed device. The IPv6 addresses whose interface identifier was
+    /// derived from the previous hardware address, like the link-local address or those
+    /// configured by stateless autoconfiguration, are renumbered to use the new one. The
+    /// learned entries of the neighbor cache are flushed, and the addresses of the
+    /// interface announced again, so that the neighbors update their own caches.
+    ///
     /// # Panics
     /// This function panics if the address is not unicast, and if the medium is not Ethernet or
     /// Ieee802154.
@@ -444,7 +787,21 @@ impl Interface {
         );
 
         InterfaceInner::check_hardware_addr(&addr);
-        self.inner.hardware_addr = addr;
+        if self.inner.hardware_addr != addr {
+            let _old = core::mem::replace(&mut self.inner.hardware_addr, addr);
+            #[cfg(feature = "proto-ipv6")]
+            self.inner.renumber_interface_id(_old);
+            InterfaceInner::flush_cache(&mut self.inner);
+            self.inner.announce_all();
+
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            if self.slaac() {
+                self.inner.router_solicit_start();
+            }
+        }
     }
 
     /// Get the IP addresses of the interface.
@@ -464,14 +821,42 @@ impl Interface {
         self.inner.ipv6_addr()
     }
 
+    /// Get the flags of the last Router Advertisement received, if any.
+    ///
+    /// The managed and other configuration flags tell whether addresses and other
+    /// configuration parameters are available through DHCPv6.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    pub fn ipv6_router_flags(&self) -> Option<NdiscRouterFlags> {
+        self.inner.ipv6_router_flags()
+    }
+
     /// Update the IP addresses of the interface.
     ///
     /// # Panics
     /// This function panics if any of the addresses are not unicast.
     pub fn update_ip_addrs<F: FnOnce(&mut Vec<IpCidr, IFACE_MAX_ADDR_COUNT>)>(&mut self, f: F) {
+        let previous = self.inner.ip_addrs.clone();
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        let assigned = self.inner.dad_merge();
         f(&mut self.inner.ip_addrs);
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        self.inner.dad_split(&assigned);
         InterfaceInner::flush_cache(&mut self.inner);
-        InterfaceInner::check_ip_addrs(&self.inner.ip_addrs)
+        InterfaceInner::check_ip_addrs(&self.inner.ip_addrs);
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        self.inner.announce_added(&previous);
+        if self.inner.ip_addrs != previous {
+            self.inner.addrs_changed();
+        }
     }
 
     /// Check whether the interface has the given IP address assigned.
@@ -479,14 +864,153 @@ impl Interface {
         self.inner.has_ip_addr(addr)
     }
 
+    /// Install a permanent neighbor cache entry, e.g. for a peer with a fixed hardware
+    /// address.
+    ///
+    /// Permanent entries replace the learned entry for the same address, if any, and are
+    /// never replaced by ARP or Neighbor Discovery, expired, evicted, or flushed when the
+    /// IP addresses of the interface change. They are only removed by
+    /// [`remove_neighbor`](Self::remove_neighbor).
+    ///
+    /// # Panics
+    /// This function panics if either address is not unicast.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn add_static_neighbor<T: Into<IpAddress>>(
+        &mut self,
+        protocol_addr: T,
+        hardware_addr: HardwareAddress,
+    ) -> Result<(), NeighborCacheFull> {
+        let protocol_addr = protocol_addr.into();
+        assert!(
+            protocol_addr.is_unicast(),
+            "IP address {protocol_addr} is not unicast"
+        );
+        InterfaceInner::check_hardware_addr(&hardware_addr);
+        let now = self.inner.now;
+        self.inner
+            .neighbor_cache
+            .fill_permanent(protocol_addr, hardware_addr, now)
+    }
+
+    /// Lock the learned neighbor cache entry of `protocol_addr`, e.g. that of the default
+    /// router once resolved, and return its hardware address; or `None` if the address is
+    /// not in the cache.
+    ///
+    /// The entry becomes permanent, as if installed by
+    /// [`add_static_neighbor`](Self::add_static_neighbor): it no longer expires, and the
+    /// packets received later cannot change it.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn lock_neighbor<T: Into<IpAddress>>(
+        &mut self,
+        protocol_addr: T,
+    ) -> Option<HardwareAddress> {
+        let now = self.inner.now;
+        self.inner.neighbor_cache.lock(&protocol_addr.into(), now)
+    }
+
+    /// Set whether the neighbor cache only trusts the answers to its own requests.
+    ///
+    /// When enabled, the hardware address of a neighbor learned from an ARP packet or a
+    /// Neighbor Discovery message is only used to create or change its entry if the
+    /// interface asked for it recently, i.e. if it has packets waiting to be sent to the
+    /// neighbor. Otherwise, the entry of the neighbor is refreshed if it has the same
+    /// hardware address already, but never changed; and an entry is created only if no
+    /// other unsolicited one was in the last second. This makes poisoning the cache much
+    /// harder on a hostile link, at the cost of some more requests; critical entries can
+    /// also be [locked](Self::lock_neighbor).
+    ///
+    /// It is disabled by default.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn set_strict_neighbor_learning(&mut self, enabled: bool) {
+        self.inner.neighbor_cache.set_strict(enabled);
+    }
+
+    /// Get whether the neighbor cache only trusts the answers to its own requests.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn strict_neighbor_learning(&self) -> bool {
+        self.inner.neighbor_cache.strict()
+    }
+
+    /// Remove the neighbor cache entry of `protocol_addr`, permanent or not, and return
+    /// its hardware address.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn remove_neighbor<T: Into<IpAddress>>(
+        &mut self,
+        protocol_addr: T,
+    ) -> Option<HardwareAddress> {
+        self.inner.neighbor_cache.remove(&protocol_addr.into())
+    }
+
+    /// Get the entries of the neighbor cache, with their state and their age as of the
+    /// last call to [`poll`](Self::poll).
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub fn neighbors(&self) -> impl Iterator<Item = NeighborEntry> + '_ {
+        self.inner.neighbor_cache.entries(self.inner.now)
+    }
+
+    /// Get the IPv6 anycast addresses of the interface.
+    #[cfg(feature = "proto-ipv6")]
+    pub fn ipv6_anycast_addrs(&self) -> &[Ipv6Address] {
+        &self.inner.ipv6_anycast_addrs
+    }
+
+    /// Update the IPv6 anycast addresses of the interface.
+    ///
+    /// Anycast addresses are shared by several nodes, e.g. the Subnet-Router anycast
+    /// address of a prefix (see [`Ipv6Cidr::subnet_router_anycast`]). The interface
+    /// receives the packets sent to them, and answers Neighbor Solicitations for them
+    /// without overriding the answers of the other nodes (RFC 4861 § 7.2.7).
+    ///
+    /// Unlike the addresses of [`update_ip_addrs`](Self::update_ip_addrs), they don't go
+    /// through Duplicate Address Detection, and are never selected as source address:
+    /// ICMPv6 replies to them, and packets of sockets that aren't bound to them, come
+    /// from a unicast address of the interface.
+    ///
+    /// # Panics
+    /// This function panics if any of the addresses are not unicast.
+    #[cfg(feature = "proto-ipv6")]
+    pub fn update_ipv6_anycast_addrs<F: FnOnce(&mut Vec<Ipv6Address, IFACE_MAX_ADDR_COUNT>)>(
+        &mut self,
+        f: F,
+    ) {
+        f(&mut self.inner.ipv6_anycast_addrs);
+        for addr in &self.inner.ipv6_anycast_addrs {
+            if !addr.is_unicast() {
+                panic!("IP address {} is not unicast", addr)
+            }
+        }
+        self.inner.addrs_changed();
+    }
+
     pub fn routes(&self) -> &Routes {
         &self.inner.routes
     }
 
     pub fn routes_mut(&mut self) -> &mut Routes {
+        self.inner.routes_changed();
         &mut self.inner.routes
     }
 
+    /// Get the packet filter of the interface.
+    pub fn filter(&self) -> &Filter {
+        &self.inner.filter
+    }
+
+    /// Get the packet filter of the interface, to update its rules.
+    pub fn filter_mut(&mut self) -> &mut Filter {
+        &mut self.inner.filter
+    }
+
+    /// Get the counters of the packets handled by the interface.
+    pub fn stats(&self) -> Stats {
+        self.inner.stats
+    }
+
+    /// Reset the counters of the packets handled by the interface.
+    pub fn reset_stats(&mut self) {
+        self.inner.stats = Stats::default();
+    }
+
     /// Enable or disable the AnyIP capability.
     ///
     /// AnyIP allowins packets to be received
@@ -512,6 +1036,129 @@ impl Interface {
         self.inner.any_ip
     }
 
+    /// Enable or disable the forwarding of directed broadcasts through the interface.
+    ///
+    /// A directed broadcast is a packet from another network for the broadcast address of
+    /// the subnet of one of the addresses of the interface, e.g. `192.168.1.255` for
+    /// `192.168.1.1/24`. When a [Router](crate::iface::Router) forwards one through this
+    /// interface, it is sent as a link-layer broadcast if enabled, and dropped otherwise.
+    /// They are disabled by default, since they make smurf attacks possible (RFC 2644).
+    ///
+    /// The packets the interface sends to its subnet broadcast addresses, or receives
+    /// for them, are not affected.
+    #[cfg(feature = "proto-ipv4")]
+    pub fn set_directed_broadcast(&mut self, enabled: bool) {
+        self.inner.directed_broadcast = enabled;
+    }
+
+    /// Get whether directed broadcasts are forwarded through the interface.
+    ///
+    /// See [`set_directed_broadcast`](Self::set_directed_broadcast) for details.
+    #[cfg(feature = "proto-ipv4")]
+    pub fn directed_broadcast(&self) -> bool {
+        self.inner.directed_broadcast
+    }
+
+    /// Set how the source address of the received packets is checked against the routes.
+    ///
+    /// With the [Strict](ReversePathFilter::Strict) filter, the packets are dropped unless
+    /// the interface has a route back to their source, preferred over those of the other
+    /// interfaces of its [Router](crate::iface::Router), if any. With the
+    /// [Loose](ReversePathFilter::Loose) filter, any interface having a route back is enough.
+    /// The networks of the addresses of the interface, and its routes, including the
+    /// default ones, count as routes back.
+    ///
+    /// The filter is disabled by default. It drops spoofed packets early, e.g. on an
+    /// interface towards a network that should only send packets from its own addresses.
+    pub fn set_reverse_path_filter(&mut self, filter: ReversePathFilter) {
+        self.inner.reverse_path_filter = filter;
+    }
+
+    /// Get how the source address of the received packets is checked against the routes.
+    pub fn reverse_path_filter(&self) -> ReversePathFilter {
+        self.inner.reverse_path_filter
+    }
+
+    /// Set whether the interface accepts the packets for the addresses of the other
+    /// interfaces of its [Router](crate::iface::Router).
+    ///
+    /// With the [Strong](HostModel::Strong) host model, which is the default, such packets
+    /// are dropped: the addresses of an interface are only reachable through it. With the
+    /// [Weak](HostModel::Weak) host model, they are delivered to the sockets of the
+    /// interface the address is assigned to, as long as its link is up, without their TTL
+    /// or hop limit being decremented. The immediate responses, such as ICMP echo replies
+    /// or TCP resets, are sent back through this interface; the packets sent by the sockets
+    /// later are routed by the interface they are bound to.
+    pub fn set_host_model(&mut self, host_model: HostModel) {
+        self.inner.host_model = host_model;
+    }
+
+    /// Get whether the interface accepts the packets for the addresses of the other
+    /// interfaces of its router.
+    pub fn host_model(&self) -> HostModel {
+        self.inner.host_model
+    }
+
+    /// Set the default time-to-live (IPv4) or hop limit (IPv6) of the packets sent by the
+    /// interface, such as ICMP errors, and by the sockets that don't set their own.
+    ///
+    /// The default is 64. For IPv6, the Cur Hop Limit advertised by the routers of the link
+    /// in their Router Advertisements overrides it until it is set again (RFC 4861 § 6.3.4).
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a hop limit of 0 is given. See [RFC 1122 § 3.2.1.7].
+    ///
+    /// [RFC 1122 § 3.2.1.7]: https://tools.ietf.org/html/rfc1122#section-3.2.1.7
+    pub fn set_hop_limit(&mut self, hop_limit: u8) {
+        // A host MUST NOT send a datagram with a hop limit value of 0
+        if hop_limit == 0 {
+            panic!("the time-to-live value of a packet must not be zero")
+        }
+        self.inner.hop_limit = hop_limit;
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        {
+            self.inner.ipv6_router_hop_limit = None;
+        }
+    }
+
+    /// Get the default time-to-live (IPv4) or hop limit (IPv6) of the packets sent by the
+    /// interface, as set by [set_hop_limit](Self::set_hop_limit).
+    pub fn hop_limit(&self) -> u8 {
+        self.inner.hop_limit
+    }
+
+    /// Get the default hop limit of the IPv6 packets sent by the interface, which is the
+    /// one advertised by the routers of the link, if any.
+    #[cfg(feature = "proto-ipv6")]
+    pub fn ipv6_hop_limit(&self) -> u8 {
+        self.inner.ipv6_hop_limit()
+    }
+
+    /// Set whether flow labels are generated for the IPv6 packets sent by TCP and UDP sockets.
+    ///
+    /// When enabled, which is the default, the packets of a socket without an explicit flow
+    /// label are labeled with a keyed hash of the addresses, protocol and ports of their flow,
+    /// as described in [RFC 6437 § 3], so that load balancers can keep each flow on a single
+    /// path. When disabled, they are left unlabeled.
+    ///
+    /// [RFC 6437 § 3]: https://tools.ietf.org/html/rfc6437#section-3
+    #[cfg(feature = "proto-ipv6")]
+    pub fn set_ipv6_flow_labels(&mut self, enabled: bool) {
+        self.inner.ipv6_flow_labels = enabled;
+    }
+
+    /// Get whether flow labels are generated for the IPv6 packets sent by sockets.
+    ///
+    /// See [`set_ipv6_flow_labels`](Self::set_ipv6_flow_labels) for details.
+    #[cfg(feature = "proto-ipv6")]
+    pub fn ipv6_flow_labels(&self) -> bool {
+        self.inner.ipv6_flow_labels
+    }
+
     /// Get the 6LoWPAN address contexts.
     #[cfg(feature = "proto-sixlowpan")]
     pub fn sixlowpan_address_context(
@@ -521,6 +1168,9 @@ impl Interface {
     }
 
     /// Get a mutable reference to the 6LoWPAN address contexts.
+    ///
+    /// The context at index `n` has the Context Identifier `n`. Contexts set here are used
+    /// for both compression and decompression; contexts advertised by routers replace them.
     #[cfg(feature = "proto-sixlowpan")]
     pub fn sixlowpan_address_context_mut(
         &mut self,
@@ -543,6 +1193,63 @@ impl Interface {
         self.fragments.reassembly_timeout = timeout;
     }
 
+    /// Get the number of 6LoWPAN packets from the same source that can be reassembled at once.
+    #[cfg(feature = "proto-sixlowpan-fragmentation")]
+    pub fn sixlowpan_reassembly_limit(&self) -> usize {
+        self.fragments.sixlowpan_reassembly_limit
+    }
+
+    /// Set the number of 6LoWPAN packets from the same source that can be reassembled at once.
+    ///
+    /// This keeps a single neighbor, or a single originator in a mesh-under network, from using
+    /// all the reassembly buffers. By default, there is no limit besides the number of buffers.
+    #[cfg(feature = "proto-sixlowpan-fragmentation")]
+    pub fn set_sixlowpan_reassembly_limit(&mut self, limit: usize) {
+        self.fragments.sixlowpan_reassembly_limit = limit;
+    }
+
+    /// Get the number of IPv4 packets from the same source that can be reassembled at once.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    pub fn ipv4_reassembly_limit(&self) -> usize {
+        self.fragments.ipv4_reassembly_limit
+    }
+
+    /// Set the number of IPv4 packets from the same source that can be reassembled at once.
+    ///
+    /// When a source goes over the limit, its oldest incomplete packet is dropped. By default,
+    /// there is no limit besides the number of buffers.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    pub fn set_ipv4_reassembly_limit(&mut self, limit: usize) {
+        self.fragments.ipv4_reassembly_limit = limit;
+    }
+
+    /// Get the number of octets that IPv4 packets being reassembled can take, in total.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    pub fn ipv4_reassembly_memory_limit(&self) -> usize {
+        self.fragments.ipv4_reassembly_memory_limit
+    }
+
+    /// Set the number of octets that IPv4 packets being reassembled can take, in total.
+    ///
+    /// When a fragment does not fit, the oldest incomplete packets are dropped to make room
+    /// for it. By default, there is no limit besides the size of the buffers.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    pub fn set_ipv4_reassembly_memory_limit(&mut self, limit: usize) {
+        self.fragments.ipv4_reassembly_memory_limit = limit;
+    }
+
+    /// Get the statistics about the reassembly of IPv4 packets.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    pub fn ipv4_reassembly_stats(&self) -> ReassemblyStats {
+        self.fragments.ipv4_reassembly_stats
+    }
+
+    /// Reset the statistics about the reassembly of IPv4 packets.
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    pub fn reset_ipv4_reassembly_stats(&mut self) {
+        self.fragments.ipv4_reassembly_stats = ReassemblyStats::default();
+    }
+
     /// Transmit packets queued in the given sockets, and receive packets queued
     /// in the device.
     ///
@@ -555,13 +1262,59 @@ impl Interface {
         device: &mut D,
         sockets: &mut SocketSet<'_>,
     ) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        self.poll_forwarding(timestamp, device, sockets, None)
+    }
+
+    /// Like [poll](#method.poll), but received packets that are routed through another
+    /// interface are handed to `forward` instead of being dropped.
+    pub(crate) fn poll_forwarding<D>(
+        &mut self,
+        timestamp: Instant,
+        device: &mut D,
+        sockets: &mut SocketSet<'_>,
+        mut forward: Option<&mut (dyn Forward + '_)>,
+    ) -> bool
     where
         D: Device + ?Sized,
     {
         self.inner.now = timestamp;
 
         #[cfg(feature = "_proto-fragmentation")]
-        self.fragments.assembler.remove_expired(timestamp);
+        {
+            let fragments = &mut self.fragments;
+            #[cfg(feature = "proto-ipv4-fragmentation")]
+            let stats = &mut fragments.ipv4_reassembly_stats;
+            let iface_stats = &mut self.inner.stats;
+            fragments.assembler.remove_expired(timestamp, |_key| {
+                iface_stats.count_reassembly_failure();
+                #[cfg(feature = "proto-ipv4-fragmentation")]
+                if matches!(_key, FragKey::Ipv4(_)) {
+                    stats.timeouts = stats.timeouts.saturating_add(1);
+                }
+            });
+        }
+
+        self.inner.addr_lifetime_expire();
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        {
+            self.inner.slaac_expire();
+            self.inner.router_dns_expire();
+            self.inner.nat64_expire();
+            self.inner.default_router_expire();
+        }
+        #[cfg(feature = "proto-rpl")]
+        self.inner.rpl_expire();
+        self.config_notify(sockets);
+
+        if !self.link_up() {
+            return false;
+        }
 
         match self.inner.caps.medium {
             #[cfg(feature = "medium-ieee802154")]
@@ -586,13 +1339,57 @@ impl Interface {
 
         loop {
             let mut did_something = false;
-            did_something |= self.socket_ingress(device, sockets);
+            did_something |= self.socket_ingress(device, sockets, forward.as_deref_mut());
+            did_something |= self.local_ingress(sockets);
+            #[cfg(feature = "socket-tcp")]
+            {
+                did_something |= self.socket_splice(sockets);
+            }
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154"),
+                feature = "socket-dns"
+            ))]
+            self.inner.router_dns_sync(sockets);
             did_something |= self.socket_egress(device, sockets);
+            #[cfg(all(feature = "socket-dhcpv4", feature = "medium-ethernet"))]
+            {
+                did_something |= self.socket_arp_egress(device, sockets);
+            }
+
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            {
+                did_something |= self.dad_egress(device);
+                did_something |= self.router_solicit_egress(device);
+            }
+            #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+            {
+                did_something |= self.autoip_egress(device);
+            }
+            #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+            {
+                did_something |= self.announce_egress(device);
+            }
+            #[cfg(feature = "proto-rpl")]
+            {
+                did_something |= self.rpl_egress(device);
+            }
 
             #[cfg(feature = "proto-igmp")]
             {
                 did_something |= self.igmp_egress(device);
             }
+            #[cfg(all(feature = "proto-igmp", feature = "socket-udp"))]
+            {
+                did_something |= self.socket_multicast_egress(device, sockets);
+            }
+            #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+            {
+                did_something |= self.mld_egress(device, sockets);
+            }
 
             if did_something {
                 readiness_may_have_changed = true;
@@ -600,6 +1397,7 @@ impl Interface {
                 break;
             }
         }
+        self.config_notify(sockets);
 
         readiness_may_have_changed
     }
@@ -615,25 +1413,79 @@ impl Interface {
     pub fn poll_at(&mut self, timestamp: Instant, sockets: &SocketSet<'_>) -> Option<Instant> {
         self.inner.now = timestamp;
 
+        if !self.link_up() {
+            return None;
+        }
+
         #[cfg(feature = "_proto-fragmentation")]
         if !self.fragmenter.is_empty() {
             return Some(Instant::from_millis(0));
         }
+        if !self.local.is_empty() {
+            return Some(Instant::from_millis(0));
+        }
 
         let inner = &mut self.inner;
 
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        let ndisc_poll_at = [
+            inner.addr_lifetime_poll_at(),
+            inner.slaac_poll_at(),
+            inner.router_dns_poll_at(),
+            inner.nat64_poll_at(),
+            inner.dad_poll_at(),
+            inner.default_router_poll_at(),
+            inner.router_solicit_poll_at(),
+        ]
+        .into_iter()
+        .flatten()
+        .min();
+        #[cfg(not(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        )))]
+        let ndisc_poll_at = inner.addr_lifetime_poll_at();
+        #[cfg(feature = "proto-rpl")]
+        let rpl_poll_at = inner.rpl_poll_at();
+        #[cfg(not(feature = "proto-rpl"))]
+        let rpl_poll_at: Option<Instant> = None;
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        let announce_poll_at = inner.announce_poll_at();
+        #[cfg(not(any(feature = "medium-ethernet", feature = "medium-ieee802154")))]
+        let announce_poll_at: Option<Instant> = None;
+        #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+        let autoip_poll_at = inner.autoip_poll_at();
+        #[cfg(not(all(feature = "proto-ipv4", feature = "medium-ethernet")))]
+        let autoip_poll_at: Option<Instant> = None;
+
+        // Only the sockets whose poll time is not cached in the timer wheel are evaluated.
         sockets
-            .items()
-            .filter_map(move |item| {
+            .unscheduled_items()
+            .map(|item| {
                 let socket_poll_at = item.socket.poll_at(inner);
-                match item
-                    .meta
+                item.meta
                     .poll_at(socket_poll_at, |ip_addr| inner.has_neighbor(&ip_addr))
-                {
-                    PollAt::Ingress => None,
-                    PollAt::Time(instant) => Some(instant),
-                    PollAt::Now => Some(Instant::from_millis(0)),
-                }
+            })
+            .chain(core::iter::once(sockets.scheduled_poll_at()))
+            .chain(core::iter::once(
+                ndisc_poll_at.map_or(PollAt::Ingress, PollAt::Time),
+            ))
+            .chain(core::iter::once(
+                rpl_poll_at.map_or(PollAt::Ingress, PollAt::Time),
+            ))
+            .chain(core::iter::once(
+                announce_poll_at.map_or(PollAt::Ingress, PollAt::Time),
+            ))
+            .chain(core::iter::once(
+                autoip_poll_at.map_or(PollAt::Ingress, PollAt::Time),
+            ))
+            .filter_map(|poll_at| match poll_at {
+                PollAt::Ingress => None,
+                PollAt::Time(instant) => Some(instant),
+                PollAt::Now => Some(Instant::from_millis(0)),
             })
             .min()
     }
@@ -654,7 +1506,50 @@ impl Interface {
         }
     }
 
-    fn socket_ingress<D>(&mut self, device: &mut D, sockets: &mut SocketSet<'_>) -> bool
+    /// Move the data received by the TCP sockets into the sockets they are spliced to.
+    #[cfg(feature = "socket-tcp")]
+    fn socket_splice(&mut self, sockets: &mut SocketSet<'_>) -> bool {
+        fn splice(sockets: &mut SocketSet<'_>, src: SocketHandle, dst: SocketHandle) -> bool {
+            let (src_socket, dst_socket) =
+                sockets.get_pair_mut::<tcp::Socket, tcp::Socket>(src, dst);
+            let moved = src_socket.splice_into(dst_socket);
+            if src_socket.recv_queue() > 0 {
+                sockets.set_splice_src(dst, src);
+            }
+            moved > 0
+        }
+
+        // Only the sockets that may have changed can have data to move: those that received
+        // some, and those that may have made room for the data of the socket spliced into them.
+        let mut did_something = false;
+        let mut prev = None;
+        while let Some(index) = sockets.next_touched(prev) {
+            prev = Some(index);
+            let Some(item) = sockets.item_at_mut(index) else {
+                continue;
+            };
+            let handle = item.meta.handle;
+            let splice_src = item.meta.splice_src.take();
+            if let Some(dst) = tcp::Socket::downcast(&item.socket).and_then(|s| s.splice_to()) {
+                did_something |= splice(sockets, handle, dst);
+            }
+            if let Some(src) = splice_src {
+                did_something |= splice(sockets, src, handle);
+            }
+        }
+        did_something
+    }
+
+    #[cfg_attr(
+        not(any(feature = "medium-ethernet", feature = "medium-ip")),
+        allow(unused_mut, unused_variables)
+    )]
+    fn socket_ingress<D>(
+        &mut self,
+        device: &mut D,
+        sockets: &mut SocketSet<'_>,
+        mut forward: Option<&mut (dyn Forward + '_)>,
+    ) -> bool
     where
         D: Device + ?Sized,
     {
@@ -663,10 +1558,28 @@ impl Interface {
         while let Some((rx_token, tx_token)) = device.receive(self.inner.now) {
             let rx_meta = rx_token.meta();
             rx_token.consume(|frame| {
+                self.inner.stats.count_rx(frame.len());
                 match self.inner.caps.medium {
                     #[cfg(feature = "medium-ethernet")]
                     Medium::Ethernet => {
-                        if let Some(packet) = self.inner.process_ethernet(
+                        if self.inner.reverse_path_drop(frame, forward.as_deref()) {
+                            return;
+                        }
+                        let forward_packet = match forward {
+                            Some(_) => self.inner.forward_packet(frame),
+                            None => None,
+                        };
+                        if let (Some(forward), Some(packet)) =
+                            (forward.as_deref_mut(), forward_packet)
+                        {
+                            self.inner.forward(
+                                tx_token,
+                                packet,
+                                &mut self.fragments,
+                                &mut self.fragmenter,
+                                forward,
+                            );
+                        } else if let Some(packet) = self.inner.process_ethernet(
                             sockets,
                             rx_meta,
                             frame,
@@ -681,7 +1594,24 @@ impl Interface {
                     }
                     #[cfg(feature = "medium-ip")]
                     Medium::Ip => {
-                        if let Some(packet) =
+                        if self.inner.reverse_path_drop(frame, forward.as_deref()) {
+                            return;
+                        }
+                        let forward_packet = match forward {
+                            Some(_) => self.inner.forward_packet(frame),
+                            None => None,
+                        };
+                        if let (Some(forward), Some(packet)) =
+                            (forward.as_deref_mut(), forward_packet)
+                        {
+                            self.inner.forward(
+                                tx_token,
+                                packet,
+                                &mut self.fragments,
+                                &mut self.fragmenter,
+                                forward,
+                            );
+                        } else if let Some(packet) =
                             self.inner
                                 .process_ip(sockets, rx_meta, frame, &mut self.fragments)
                         {
@@ -697,7 +1627,10 @@ impl Interface {
                     }
                     #[cfg(feature = "medium-ieee802154")]
                     Medium::Ieee802154 => {
-                        if let Some(packet) = self.inner.process_ieee802154(
+                        if let Some(forward) = self.inner.sixlowpan_mesh_forward(frame) {
+                            self.inner
+                                .dispatch_sixlowpan_mesh_forward(tx_token, frame, forward);
+                        } else if let Some(packet) = self.inner.process_ieee802154(
                             sockets,
                             rx_meta,
                             frame,
@@ -733,73 +1666,166 @@ impl Interface {
         }
 
         let mut emitted_any = false;
-        for item in sockets.items_mut() {
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        if self.inner.neighbor_cache.take_filled() {
+            // The sockets waiting for a neighbor that was just discovered may send right away,
+            // rather than once their silence timer expires.
+            sockets.touch_matching(|item| {
+                item.meta
+                    .neighbor_discovered(|ip_addr| self.inner.has_neighbor(&ip_addr))
+            });
+        }
+        sockets.take_due(self.inner.now);
+        let mut prev = None;
+        while let Some(index) = sockets.next_due(prev) {
+            prev = Some(index);
+            #[cfg(feature = "socket-udp")]
+            let (item, mut others) = sockets.item_at_mut_with_others(index);
+            #[cfg(not(feature = "socket-udp"))]
+            let item = sockets.item_at_mut(index);
+            let item = item.unwrap();
             if !item
                 .meta
                 .egress_permitted(self.inner.now, |ip_addr| self.inner.has_neighbor(&ip_addr))
             {
+                let poll_at = item
+                    .meta
+                    .poll_at(item.socket.poll_at(&mut self.inner), |ip_addr| {
+                        self.inner.has_neighbor(&ip_addr)
+                    });
+                let handle = item.meta.handle;
+                sockets.schedule(handle, poll_at);
                 continue;
             }
 
             let mut neighbor_addr = None;
-            let mut respond = |inner: &mut InterfaceInner, meta: PacketMeta, response: IpPacket| {
-                neighbor_addr = Some(response.ip_repr().dst_addr());
-                let t = device.transmit(inner.now).ok_or_else(|| {
-                    net_debug!("failed to transmit IP: device exhausted");
-                    EgressError::Exhausted
-                })?;
+            let mark = item.meta.mark;
+            let mut respond =
+                |inner: &mut InterfaceInner, meta: PacketMeta, mut response: IpPacket| {
+                    // Wait until the fragments of the previous packet are sent.
+                    #[cfg(feature = "_proto-fragmentation")]
+                    if !self.fragmenter.is_empty() && !self.fragmenter.finished() {
+                        return Err(EgressError::Exhausted);
+                    }
 
-                inner
-                    .dispatch_ip(t, meta, response, &mut self.fragmenter)
-                    .map_err(EgressError::Dispatch)?;
+                    neighbor_addr = Some(response.ip_repr().dst_addr());
+                    response.set_mark(mark);
+                    match self.local.push(inner, &response) {
+                        Some(true) => {
+                            emitted_any = true;
+                            return Ok(());
+                        }
+                        Some(false) => return Err(EgressError::Exhausted),
+                        None => (),
+                    }
+                    let t = device.transmit(inner.now).ok_or_else(|| {
+                        net_debug!("failed to transmit IP: device exhausted");
+                        EgressError::Exhausted
+                    })?;
 
-                emitted_any = true;
+                    inner
+                        .dispatch_ip(t, meta, response, &mut self.fragmenter)
+                        .map_err(EgressError::Dispatch)?;
 
-                Ok(())
-            };
+                    emitted_any = true;
+
+                    Ok(())
+                };
 
             let result = match &mut item.socket {
                 #[cfg(feature = "socket-raw")]
-                Socket::Raw(socket) => socket.dispatch(&mut self.inner, |inner, (ip, raw)| {
-                    respond(
-                        inner,
-                        PacketMeta::default(),
-                        IpPacket::new(ip, IpPayload::Raw(raw)),
-                    )
-                }),
+                Socket::Raw(socket) => {
+                    let traffic_class = socket.traffic_class();
+                    socket.dispatch(&mut self.inner, |inner, (ip, raw)| {
+                        let mut packet = IpPacket::new(ip, IpPayload::Raw(raw));
+                        packet.set_traffic_class(traffic_class);
+                        respond(inner, PacketMeta::default(), packet)
+                    })
+                }
                 #[cfg(feature = "socket-icmp")]
                 Socket::Icmp(socket) => {
-                    socket.dispatch(&mut self.inner, |inner, response| match response {
-                        #[cfg(feature = "proto-ipv4")]
-                        (IpRepr::Ipv4(ipv4_repr), IcmpRepr::Ipv4(icmpv4_repr)) => respond(
-                            inner,
-                            PacketMeta::default(),
-                            IpPacket::new_ipv4(ipv4_repr, IpPayload::Icmpv4(icmpv4_repr)),
-                        ),
-                        #[cfg(feature = "proto-ipv6")]
-                        (IpRepr::Ipv6(ipv6_repr), IcmpRepr::Ipv6(icmpv6_repr)) => respond(
-                            inner,
-                            PacketMeta::default(),
-                            IpPacket::new_ipv6(ipv6_repr, IpPayload::Icmpv6(icmpv6_repr)),
-                        ),
-                        #[allow(unreachable_patterns)]
-                        _ => unreachable!(),
+                    let traffic_class = socket.traffic_class();
+                    socket.dispatch(&mut self.inner, |inner, response| {
+                        let mut packet = match response {
+                            #[cfg(feature = "proto-ipv4")]
+                            (IpRepr::Ipv4(ipv4_repr), IcmpRepr::Ipv4(icmpv4_repr)) => {
+                                IpPacket::new_ipv4(ipv4_repr, IpPayload::Icmpv4(icmpv4_repr))
+                            }
+                            #[cfg(feature = "proto-ipv6")]
+                            (IpRepr::Ipv6(ipv6_repr), IcmpRepr::Ipv6(icmpv6_repr)) => {
+                                IpPacket::new_ipv6(ipv6_repr, IpPayload::Icmpv6(icmpv6_repr))
+                            }
+                            #[allow(unreachable_patterns)]
+                            _ => unreachable!(),
+                        };
+                        packet.set_traffic_class(traffic_class);
+                        respond(inner, PacketMeta::default(), packet)
                     })
                 }
                 #[cfg(feature = "socket-udp")]
                 Socket::Udp(socket) => {
-                    socket.dispatch(&mut self.inner, |inner, meta, (ip, udp, payload)| {
-                        respond(inner, meta, IpPacket::new(ip, IpPayload::Udp(udp, payload)))
-                    })
+                    let checksum_coverage = socket.send_checksum_coverage();
+                    let send_checksum = socket.send_checksum_enabled();
+                    let dont_frag = socket.dont_fragment();
+                    let multicast_loop = socket.multicast_loop_enabled();
+                    let flow_label = socket.flow_label();
+                    // Send all the queued packets in one burst, until the device is exhausted.
+                    let mut result = Ok(());
+                    while result.is_ok() && socket.send_pending() {
+                        result = socket.dispatch(
+                            &mut self.inner,
+                            |inner, meta, traffic_class, (ip, udp, payload)| {
+                                let ip_payload = match ip.next_header() {
+                                    IpProtocol::UdpLite => {
+                                        let udplite = UdpLiteRepr {
+                                            src_port: udp.src_port,
+                                            dst_port: udp.dst_port,
+                                            checksum_coverage,
+                                        };
+                                        IpPayload::UdpLite(udplite, payload)
+                                    }
+                                    _ if !send_checksum => IpPayload::UdpNoChecksum(udp, payload),
+                                    _ => IpPayload::Udp(udp, payload),
+                                };
+                                let mut packet = IpPacket::new(ip.clone(), ip_payload);
+                                packet.set_traffic_class(traffic_class);
+                                packet.set_dont_frag(dont_frag);
+                                packet.set_flow_label(flow_label.unwrap_or_else(|| {
+                                    inner.flow_label(&ip, udp.src_port, udp.dst_port)
+                                }));
+                                respond(inner, meta, packet)?;
+
+                                // Deliver the multicast packets sent by the socket to the
+                                // other local sockets that joined their group.
+                                if multicast_loop && ip.dst_addr().is_multicast() {
+                                    for other in others
+                                        .items_mut()
+                                        .filter_map(|i| udp::Socket::downcast_mut(&mut i.socket))
+                                    {
+                                        if other.accepts(inner, &ip, &udp) {
+                                            other.process(inner, meta, &ip, &udp, payload);
+                                        }
+                                    }
+                                }
+                                Ok(())
+                            },
+                        );
+                    }
+                    result
                 }
                 #[cfg(feature = "socket-tcp")]
-                Socket::Tcp(socket) => socket.dispatch(&mut self.inner, |inner, (ip, tcp)| {
-                    respond(
-                        inner,
-                        PacketMeta::default(),
-                        IpPacket::new(ip, IpPayload::Tcp(tcp)),
-                    )
-                }),
+                Socket::Tcp(socket) => {
+                    let flow_label = socket.flow_label();
+                    let traffic_class = socket.traffic_class();
+                    socket.dispatch(&mut self.inner, |inner, (ip, tcp)| {
+                        let flow_label = flow_label
+                            .unwrap_or_else(|| inner.flow_label(&ip, tcp.src_port, tcp.dst_port));
+                        let mut packet = IpPacket::new(ip, IpPayload::Tcp(tcp));
+                        packet.set_flow_label(flow_label);
+                        packet.set_traffic_class(traffic_class);
+                        respond(inner, PacketMeta::default(), packet)
+                    })
+                }
                 #[cfg(feature = "socket-dhcpv4")]
                 Socket::Dhcpv4(socket) => {
                     socket.dispatch(&mut self.inner, |inner, (ip, udp, dhcp)| {
@@ -810,6 +1836,36 @@ impl Interface {
                         )
                     })
                 }
+                #[cfg(feature = "socket-dhcpv4-server")]
+                Socket::Dhcpv4Server(socket) => {
+                    socket.dispatch(&mut self.inner, |inner, (ip, udp, dhcp)| {
+                        respond(
+                            inner,
+                            PacketMeta::default(),
+                            IpPacket::new_ipv4(ip, IpPayload::Dhcpv4(udp, dhcp)),
+                        )
+                    })
+                }
+                #[cfg(feature = "socket-dhcpv4-relay")]
+                Socket::Dhcpv4Relay(socket) => {
+                    socket.dispatch(&mut self.inner, |inner, (ip, udp, payload)| {
+                        respond(
+                            inner,
+                            PacketMeta::default(),
+                            IpPacket::new_ipv4(ip, IpPayload::Udp(udp, payload)),
+                        )
+                    })
+                }
+                #[cfg(feature = "socket-dhcpv6")]
+                Socket::Dhcpv6(socket) => {
+                    socket.dispatch(&mut self.inner, |inner, (ip, udp, dhcp)| {
+                        respond(
+                            inner,
+                            PacketMeta::default(),
+                            IpPacket::new_ipv6(ip, IpPayload::Dhcpv6(udp, dhcp)),
+                        )
+                    })
+                }
                 #[cfg(feature = "socket-dns")]
                 Socket::Dns(socket) => socket.dispatch(&mut self.inner, |inner, (ip, udp, dns)| {
                     respond(
@@ -820,19 +1876,66 @@ impl Interface {
                 }),
             };
 
-            match result {
-                Err(EgressError::Exhausted) => break, // Device buffer full.
-                Err(EgressError::Dispatch(_)) => {
-                    // `NeighborCache` already takes care of rate limiting the neighbor discovery
-                    // requests from the socket. However, without an additional rate limiting
-                    // mechanism, we would spin on every socket that has yet to discover its
-                    // neighbor.
-                    item.meta.neighbor_missing(
-                        self.inner.now,
-                        neighbor_addr.expect("non-IP response packet"),
-                    );
-                }
-                Ok(()) => {}
+            match result {
+                Err(EgressError::Exhausted) => break, // Device buffer full.
+                Err(EgressError::Dispatch(_)) => {
+                    // `NeighborCache` already takes care of rate limiting the neighbor discovery
+                    // requests from the socket. However, without an additional rate limiting
+                    // mechanism, we would spin on every socket that has yet to discover its
+                    // neighbor.
+                    item.meta.neighbor_missing(
+                        self.inner.now,
+                        neighbor_addr.expect("non-IP response packet"),
+                    );
+                }
+                Ok(()) => {}
+            }
+
+            let poll_at = item
+                .meta
+                .poll_at(item.socket.poll_at(&mut self.inner), |ip_addr| {
+                    self.inner.has_neighbor(&ip_addr)
+                });
+            let handle = item.meta.handle;
+            sockets.schedule(handle, poll_at);
+        }
+        sockets.finish_due();
+        emitted_any
+    }
+
+    /// Send the ARP probes and announcements of DHCP sockets checking their address.
+    #[cfg(all(feature = "socket-dhcpv4", feature = "medium-ethernet"))]
+    fn socket_arp_egress<D>(&mut self, device: &mut D, sockets: &mut SocketSet<'_>) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        if self.inner.caps.medium != Medium::Ethernet {
+            return false;
+        }
+
+        let mut emitted_any = false;
+        for item in sockets.items_mut() {
+            let Socket::Dhcpv4(socket) = &mut item.socket else {
+                continue;
+            };
+            let result = socket.dispatch_arp(&mut self.inner, |inner, arp_repr| {
+                let Some(tx_token) = device.transmit(inner.now) else {
+                    net_debug!("failed to transmit ARP: device exhausted");
+                    return Err(());
+                };
+                // NOTE(unwrap): ARP packets are sent to a hardware address, and need no route.
+                inner
+                    .dispatch(
+                        tx_token,
+                        EthernetPacket::Arp(arp_repr),
+                        &mut self.fragmenter,
+                    )
+                    .unwrap();
+                emitted_any = true;
+                Ok(())
+            });
+            if result.is_err() {
+                break;
             }
         }
         emitted_any
@@ -921,51 +2024,127 @@ impl InterfaceInner {
         self.caps.ip_mtu()
     }
 
+    /// Return the MTU of the path towards `addr`: the IP MTU of the interface, unless a
+    /// Packet Too Big message reported a smaller one.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn path_mtu(&self, addr: &IpAddress) -> usize {
+        let ip_mtu = self.caps.ip_mtu();
+        self.destinations
+            .path_mtu(addr, self.now)
+            .map_or(ip_mtu, |mtu| mtu.min(ip_mtu))
+    }
+
+    /// Return the TCP maximum segment size configured on the route to `addr`, or hinted
+    /// for `addr` itself, whichever is smaller.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn route_mss(&self, addr: &IpAddress) -> Option<u16> {
+        let route_mss = self.routes.lookup_mss(addr, self.now);
+        match (route_mss, self.mss_hint(addr)) {
+            (Some(route_mss), Some(mss)) => Some(route_mss.min(mss)),
+            (route_mss, mss) => route_mss.or(mss),
+        }
+    }
+
     #[allow(unused)] // unused depending on which sockets are enabled, and in tests
     pub(crate) fn rand(&mut self) -> &mut Rand {
         &mut self.rand
     }
 
+    #[cfg(feature = "socket-tcp")]
+    pub(crate) fn tcp_secret(&self) -> &[u64; 2] {
+        &self.tcp_secret
+    }
+
+    /// Return the flow label of the packets of a transport flow whose socket has no explicit
+    /// one, derived from a keyed hash of the addresses, protocol and ports of the flow as
+    /// described in [RFC 6437 § 3], so that it is stable for the whole flow.
+    ///
+    /// [RFC 6437 § 3]: https://tools.ietf.org/html/rfc6437#section-3
     #[allow(unused)] // unused depending on which sockets are enabled
-    pub(crate) fn get_source_address(&mut self, dst_addr: IpAddress) -> Option<IpAddress> {
-        let v = dst_addr.version();
-        for cidr in self.ip_addrs.iter() {
-            let addr = cidr.address();
-            if addr.version() == v {
-                return Some(addr);
+    fn flow_label(&self, ip_repr: &IpRepr, src_port: u16, dst_port: u16) -> u32 {
+        match ip_repr {
+            #[cfg(feature = "proto-ipv6")]
+            IpRepr::Ipv6(repr) if self.ipv6_flow_labels => {
+                let mut data = [0; 37];
+                data[..16].copy_from_slice(repr.src_addr.as_bytes());
+                data[16..32].copy_from_slice(repr.dst_addr.as_bytes());
+                data[32] = repr.next_header.into();
+                data[33..35].copy_from_slice(&src_port.to_be_bytes());
+                data[35..].copy_from_slice(&dst_port.to_be_bytes());
+                // A zero flow label marks an unlabeled packet.
+                (siphash(&self.flow_label_secret, &data) as u32 & 0xf_ffff).max(1)
             }
+            _ => 0,
         }
-        None
     }
 
-    #[cfg(feature = "proto-ipv4")]
-    #[allow(unused)]
-    pub(crate) fn get_source_address_ipv4(
-        &mut self,
-        _dst_addr: Ipv4Address,
-    ) -> Option<Ipv4Address> {
-        for cidr in self.ip_addrs.iter() {
-            #[allow(irrefutable_let_patterns)] // if only ipv4 is enabled
-            if let IpCidr::Ipv4(cidr) = cidr {
-                return Some(cidr.address());
-            }
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn get_source_address(&mut self, dst_addr: IpAddress) -> Option<IpAddress> {
+        match dst_addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(addr) => self.get_source_address_ipv4(addr).map(Into::into),
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(addr) => self.get_source_address_ipv6(addr).map(Into::into),
         }
-        None
     }
 
+    /// Select the source address for `dst_addr` among ours (RFC 6724 § 5, rules 1, 2
+    /// and 8).
+    #[cfg(feature = "proto-ipv4")]
+    pub(crate) fn get_source_address_ipv4(&mut self, dst_addr: Ipv4Address) -> Option<Ipv4Address> {
+        self.ip_addrs
+            .iter()
+            .filter_map(|cidr| match cidr {
+                IpCidr::Ipv4(cidr) => Some(*cidr),
+                #[allow(unreachable_patterns)] // if only ipv4 is enabled
+                _ => None,
+            })
+            .min_by_key(|cidr| {
+                let addr = cidr.address();
+                (
+                    addr != dst_addr,
+                    scope_preference(addr.scope(), dst_addr.scope()),
+                    self.ip_addr_deprecated(addr.into()),
+                    Reverse(addr.common_prefix_len(&dst_addr).min(cidr.prefix_len())),
+                )
+            })
+            .map(|cidr| cidr.address())
+    }
+
+    /// Select the source address for `dst_addr` among ours (RFC 6724 § 5). Home addresses
+    /// and multiple interfaces are not supported, so rules 4, 5 and 5.5 don't apply.
     #[cfg(feature = "proto-ipv6")]
-    #[allow(unused)]
-    pub(crate) fn get_source_address_ipv6(
-        &mut self,
-        _dst_addr: Ipv6Address,
-    ) -> Option<Ipv6Address> {
-        for cidr in self.ip_addrs.iter() {
-            #[allow(irrefutable_let_patterns)] // if only ipv6 is enabled
-            if let IpCidr::Ipv6(cidr) = cidr {
-                return Some(cidr.address());
-            }
-        }
-        None
+    pub(crate) fn get_source_address_ipv6(&mut self, dst_addr: Ipv6Address) -> Option<Ipv6Address> {
+        self.ip_addrs
+            .iter()
+            .filter_map(|cidr| match cidr {
+                IpCidr::Ipv6(cidr) => Some(*cidr),
+                #[allow(unreachable_patterns)] // if only ipv6 is enabled
+                _ => None,
+            })
+            .min_by_key(|cidr| {
+                let addr = cidr.address();
+                #[cfg(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                ))]
+                let temporary = self.slaac_temporary(addr);
+                #[cfg(not(all(
+                    feature = "proto-ipv6",
+                    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+                )))]
+                let temporary = false;
+
+                (
+                    addr != dst_addr,
+                    scope_preference(addr.scope(), dst_addr.scope()),
+                    self.ip_addr_deprecated(addr.into()),
+                    policy_label(addr) != policy_label(dst_addr),
+                    !temporary,
+                    Reverse(addr.common_prefix_len(&dst_addr).min(cidr.prefix_len())),
+                )
+            })
+            .map(|cidr| cidr.address())
     }
 
     #[cfg(test)]
@@ -1006,11 +2185,82 @@ impl InterfaceInner {
                 )),
             ])
             .unwrap(),
+            addr_lifetimes: addr_lifetime::AddressLifetimes::default(),
             rand: Rand::new(1234),
             routes: Routes::new(),
+            filter: Filter::new(),
+            stats: Stats::default(),
+            link: link::Link::default(),
+            notify: notify::Notify::default(),
+            #[cfg(feature = "medium-ethernet")]
+            wake_callback: None,
+
+            #[cfg(feature = "socket-tcp")]
+            tcp_secret: [0x0706050403020100, 0x0f0e0d0c0b0a0908],
+            #[cfg(feature = "proto-ipv6")]
+            flow_label_secret: [0x0706050403020100, 0x0f0e0d0c0b0a0908],
+            #[cfg(feature = "proto-ipv6")]
+            ipv6_flow_labels: true,
+
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            ipv6_router_flags: None,
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            ipv6_router_hop_limit: None,
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            slaac: slaac::Slaac::default(),
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            router_dns: rdnss::RouterDns::default(),
+            #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+            announce: announce::Announcements::default(),
+            #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
+            autoip: autoip::AutoIp::default(),
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            dad: dad::Dad::default(),
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            default_routers: default_router::DefaultRouters::default(),
+            #[cfg(feature = "proto-ipv6")]
+            nat64: nat64::Nat64::default(),
+            destinations: destination::DestinationCache::default(),
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            redirects: redirect::Redirects::default(),
+            #[cfg(all(
+                feature = "proto-ipv6",
+                any(feature = "medium-ethernet", feature = "medium-ieee802154")
+            ))]
+            router_solicit: router_solicit::RouterSolicit::default(),
+            #[cfg(feature = "proto-rpl")]
+            rpl: None,
 
+            #[cfg(feature = "proto-ipv6")]
+            ipv6_anycast_addrs: Vec::new(),
             #[cfg(feature = "proto-ipv4")]
             any_ip: false,
+            #[cfg(feature = "proto-ipv4")]
+            directed_broadcast: false,
+            reverse_path_filter: ReversePathFilter::Disabled,
+            host_model: HostModel::Strong,
+            hop_limit: DEFAULT_HOP_LIMIT,
 
             #[cfg(feature = "medium-ieee802154")]
             pan_id: Some(crate::wire::Ieee802154Pan(0xabcd)),
@@ -1023,6 +2273,12 @@ impl InterfaceInner {
             #[cfg(feature = "proto-sixlowpan")]
             sixlowpan_address_context: Vec::new(),
 
+            #[cfg(feature = "proto-sixlowpan")]
+            sixlowpan_context_lifetimes: [None; IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT],
+
+            #[cfg(feature = "medium-ieee802154")]
+            sixlowpan_mesh_routes: Vec::new(),
+
             #[cfg(feature = "proto-ipv4-fragmentation")]
             ipv4_id: 1,
 
@@ -1055,7 +2311,15 @@ impl InterfaceInner {
             #[cfg(feature = "proto-igmp")]
             igmp_report_state: IgmpReportState::Inactive,
             #[cfg(feature = "proto-igmp")]
+            igmp_older_queriers: igmp::OlderQueriers::default(),
+            #[cfg(feature = "proto-igmp")]
             ipv4_multicast_groups: LinearMap::new(),
+            #[cfg(all(feature = "proto-igmp", feature = "socket-udp"))]
+            ipv4_socket_multicast_groups: LinearMap::new(),
+            #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+            ipv6_socket_multicast_groups: LinearMap::new(),
+            #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+            mld_report_state: mld::MldReportState::Inactive,
         }
     }
 
@@ -1065,6 +2329,23 @@ impl InterfaceInner {
         self.now = now
     }
 
+    #[cfg(all(
+        test,
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn set_ipv6_router_flags(&mut self, flags: Option<NdiscRouterFlags>) {
+        self.ipv6_router_flags = flags
+    }
+
+    #[cfg(any(test, feature = "socket-dhcpv4"))]
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn routes_mut(&mut self) -> &mut Routes {
+        self.routes_changed();
+        &mut self.routes
+    }
+
     #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
     fn check_hardware_addr(addr: &HardwareAddress) {
         if !addr.is_unicast() {
@@ -1108,16 +2389,18 @@ impl InterfaceInner {
     /// [RFC 4291 § 2.7.1]: https://tools.ietf.org/html/rfc4291#section-2.7.1
     #[cfg(feature = "proto-ipv6")]
     pub fn has_solicited_node(&self, addr: Ipv6Address) -> bool {
-        self.ip_addrs.iter().any(|cidr| {
-            match *cidr {
+        self.ip_addrs
+            .iter()
+            .filter_map(|cidr| match *cidr {
                 IpCidr::Ipv6(cidr) if cidr.address() != Ipv6Address::LOOPBACK => {
-                    // Take the lower order 24 bits of the IPv6 address and
-                    // append those bits to FF02:0:0:0:0:1:FF00::/104.
-                    addr.as_bytes()[14..] == cidr.address().as_bytes()[14..]
+                    Some(cidr.address())
                 }
-                _ => false,
-            }
-        })
+                _ => None,
+            })
+            .chain(self.ipv6_anycast_addrs.iter().copied())
+            // Take the lower order 24 bits of the IPv6 address and
+            // append those bits to FF02:0:0:0:0:1:FF00::/104.
+            .any(|ours| addr.as_bytes()[14..] == ours.as_bytes()[14..])
     }
 
     /// Check whether the interface has the given IP address assigned.
@@ -1126,6 +2409,12 @@ impl InterfaceInner {
         self.ip_addrs.iter().any(|probe| probe.address() == addr)
     }
 
+    /// Check whether the given address is one of the anycast addresses of the interface.
+    #[cfg(feature = "proto-ipv6")]
+    fn has_anycast_addr(&self, addr: Ipv6Address) -> bool {
+        self.ipv6_anycast_addrs.contains(&addr)
+    }
+
     /// Get the first IPv4 address of the interface.
     #[cfg(feature = "proto-ipv4")]
     pub fn ipv4_addr(&self) -> Option<Ipv4Address> {
@@ -1146,12 +2435,62 @@ impl InterfaceInner {
         })
     }
 
+    /// Get the flags of the last Router Advertisement received, if any.
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    pub fn ipv6_router_flags(&self) -> Option<NdiscRouterFlags> {
+        self.ipv6_router_flags
+    }
+
+    /// Get the default time-to-live of the IPv4 packets.
+    #[cfg(feature = "proto-ipv4")]
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn ipv4_hop_limit(&self) -> u8 {
+        self.hop_limit
+    }
+
+    /// Get the default hop limit of the IPv6 packets, learned from Router Advertisements
+    /// or configured.
+    #[cfg(feature = "proto-ipv6")]
+    pub(crate) fn ipv6_hop_limit(&self) -> u8 {
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        if let Some(hop_limit) = self.ipv6_router_hop_limit {
+            return hop_limit;
+        }
+        self.hop_limit
+    }
+
+    /// Get the default time-to-live or hop limit of the packets sent to `addr`.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    pub(crate) fn hop_limit(&self, addr: &IpAddress) -> u8 {
+        match addr {
+            #[cfg(feature = "proto-ipv4")]
+            IpAddress::Ipv4(_) => self.ipv4_hop_limit(),
+            #[cfg(feature = "proto-ipv6")]
+            IpAddress::Ipv6(_) => self.ipv6_hop_limit(),
+        }
+    }
+
+    /// Get the first link-local IPv6 address if present.
+    #[cfg(any(
+        feature = "socket-dhcpv6",
+        all(feature = "proto-ipv6", feature = "socket-udp")
+    ))]
+    pub(crate) fn ipv6_link_local_addr(&self) -> Option<Ipv6Address> {
+        self.ip_addrs.iter().find_map(|addr| match *addr {
+            IpCidr::Ipv6(cidr) if cidr.address().is_link_local() => Some(cidr.address()),
+            _ => None,
+        })
+    }
+
     #[cfg(not(feature = "proto-igmp"))]
     fn has_multicast_group<T: Into<IpAddress>>(&self, addr: T) -> bool {
         false
     }
 
-    #[cfg(feature = "medium-ip")]
+    #[cfg_attr(not(feature = "proto-ipv4"), allow(unused_variables))]
     fn process_ip<'frame>(
         &mut self,
         sockets: &mut SocketSet,
@@ -1233,6 +2572,30 @@ impl InterfaceInner {
         address.is_unicast() && !self.is_broadcast_v4(address)
     }
 
+    /// Parse a UDP datagram, counting it, or its checksum error.
+    #[allow(unused)] // unused depending on which sockets are enabled
+    fn parse_udp(
+        &mut self,
+        udp_packet: &UdpPacket<&[u8]>,
+        src_addr: &IpAddress,
+        dst_addr: &IpAddress,
+        checksum_caps: ChecksumCapabilities,
+    ) -> Option<UdpRepr> {
+        match UdpRepr::parse(udp_packet, src_addr, dst_addr, &checksum_caps) {
+            Ok(udp_repr) => {
+                self.stats.udp.count_rx(udp_packet.len() as usize);
+                Some(udp_repr)
+            }
+            Err(_) => {
+                if checksum_caps.udp.rx() && !udp_packet.verify_checksum(src_addr, dst_addr) {
+                    self.stats.udp.count_checksum_error();
+                }
+                net_trace!("iface: malformed UDP datagram");
+                None
+            }
+        }
+    }
+
     #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
     #[allow(clippy::too_many_arguments)]
     fn process_udp<'frame>(
@@ -1242,20 +2605,49 @@ impl InterfaceInner {
         ip_repr: IpRepr,
         udp_repr: UdpRepr,
         handled_by_raw_socket: bool,
+        zero_checksum: bool,
         udp_payload: &'frame [u8],
         ip_payload: &'frame [u8],
     ) -> Option<IpPacket<'frame>> {
+        // Magic packets are recognized whether a socket is bound to their port or not.
+        #[cfg(feature = "medium-ethernet")]
+        let magic = udp_repr.dst_port == WOL_PORT && self.wake_ingress(udp_payload);
+
+        // Multicast packets are delivered to every socket that joined their group, so that
+        // several sockets may share a port.
         #[cfg(feature = "socket-udp")]
-        for udp_socket in sockets
-            .items_mut()
-            .filter_map(|i| udp::Socket::downcast_mut(&mut i.socket))
         {
-            if udp_socket.accepts(self, &ip_repr, &udp_repr) {
-                udp_socket.process(self, meta, &ip_repr, &udp_repr, udp_payload);
+            let multicast = ip_repr.dst_addr().is_multicast();
+            let mut handled = false;
+            for udp_socket in sockets
+                .items_mut()
+                .filter_map(|i| udp::Socket::downcast_mut(&mut i.socket))
+            {
+                if !udp_socket.accepts(self, &ip_repr, &udp_repr) {
+                    continue;
+                }
+                handled = true;
+                if zero_checksum && !udp_socket.recv_zero_checksum_enabled() {
+                    net_debug!("UDP packet has a zero checksum, dropping");
+                } else {
+                    udp_socket.process(self, meta, &ip_repr, &udp_repr, udp_payload);
+                }
+                if !multicast {
+                    break;
+                }
+            }
+            if handled {
                 return None;
             }
         }
 
+        // Only the sockets that opted in accept UDP-over-IPv6 packets without a checksum,
+        // others are silently dropped as described in RFC 6936.
+        if zero_checksum {
+            net_debug!("UDP packet has a zero checksum, dropping");
+            return None;
+        }
+
         #[cfg(feature = "socket-dns")]
         for dns_socket in sockets
             .items_mut()
@@ -1267,7 +2659,68 @@ impl InterfaceInner {
             }
         }
 
+        #[cfg(feature = "medium-ethernet")]
+        if magic {
+            return None;
+        }
+
+        // The packet wasn't handled by a socket, send an ICMP port unreachable packet.
+        self.port_unreachable(ip_repr, handled_by_raw_socket, ip_payload)
+    }
+
+    #[cfg(feature = "socket-udp")]
+    pub(crate) fn process_udplite<'frame>(
+        &mut self,
+        sockets: &mut SocketSet,
+        meta: PacketMeta,
+        ip_repr: IpRepr,
+        handled_by_raw_socket: bool,
+        ip_payload: &'frame [u8],
+    ) -> Option<IpPacket<'frame>> {
+        let (src_addr, dst_addr) = (ip_repr.src_addr(), ip_repr.dst_addr());
+        let udplite_packet = check!(UdpLitePacket::new_checked(ip_payload));
+        let udplite_repr = check!(UdpLiteRepr::parse(&udplite_packet, &src_addr, &dst_addr));
+        let udp_repr = UdpRepr {
+            src_port: udplite_repr.src_port,
+            dst_port: udplite_repr.dst_port,
+        };
+
+        let multicast = ip_repr.dst_addr().is_multicast();
+        let mut handled = false;
+        for udp_socket in sockets
+            .items_mut()
+            .filter_map(|i| udp::Socket::downcast_mut(&mut i.socket))
+        {
+            if !udp_socket.accepts(self, &ip_repr, &udp_repr) {
+                continue;
+            }
+            handled = true;
+            if !udp_socket
+                .accepts_checksum_coverage(ip_payload.len(), udplite_repr.checksum_coverage)
+            {
+                net_debug!("UDP-Lite checksum coverage is too short, dropping");
+            } else {
+                udp_socket.process(self, meta, &ip_repr, &udp_repr, udplite_packet.payload());
+            }
+            if !multicast {
+                break;
+            }
+        }
+        if handled {
+            return None;
+        }
+
         // The packet wasn't handled by a socket, send an ICMP port unreachable packet.
+        self.port_unreachable(ip_repr, handled_by_raw_socket, ip_payload)
+    }
+
+    #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
+    fn port_unreachable<'frame>(
+        &mut self,
+        ip_repr: IpRepr,
+        handled_by_raw_socket: bool,
+        ip_payload: &'frame [u8],
+    ) -> Option<IpPacket<'frame>> {
         match ip_repr {
             #[cfg(feature = "proto-ipv4")]
             IpRepr::Ipv4(_) if handled_by_raw_socket => None,
@@ -1307,23 +2760,60 @@ impl InterfaceInner {
     ) -> Option<IpPacket<'frame>> {
         let (src_addr, dst_addr) = (ip_repr.src_addr(), ip_repr.dst_addr());
         let tcp_packet = check!(TcpPacket::new_checked(ip_payload));
-        let tcp_repr = check!(TcpRepr::parse(
-            &tcp_packet,
-            &src_addr,
-            &dst_addr,
-            &self.caps.checksum
-        ));
-
-        for tcp_socket in sockets
-            .items_mut()
-            .filter_map(|i| tcp::Socket::downcast_mut(&mut i.socket))
-        {
-            if tcp_socket.accepts(self, &ip_repr, &tcp_repr) {
-                return tcp_socket
+        let tcp_repr = check!(
+            TcpRepr::parse(&tcp_packet, &src_addr, &dst_addr, &self.caps.checksum),
+            if self.caps.checksum.tcp.rx() && !tcp_packet.verify_checksum(&src_addr, &dst_addr) {
+                self.stats.tcp.count_checksum_error();
+            }
+        );
+        self.stats.tcp.count_rx(ip_payload.len());
+
+        let accepting = sockets.items().find_map(|item| {
+            let tcp_socket = tcp::Socket::downcast(&item.socket)?;
+            tcp_socket
+                .accepts(self, &ip_repr, &tcp_repr)
+                .then_some(item.meta.handle)
+        });
+        if let Some(handle) = accepting {
+            let tcp_socket = sockets.get_mut::<tcp::Socket>(handle);
+            #[cfg(any(feature = "socket-tcp-md5", feature = "socket-tcp-ao"))]
+            if !tcp_socket.authenticate(&ip_repr, &tcp_packet, &tcp_repr) {
+                net_debug!("TCP segment authentication failed, dropping it");
+                return None;
+            }
+            let traffic_class = tcp_socket.traffic_class();
+            return tcp_socket
+                .process(self, &ip_repr, &tcp_repr)
+                .map(|(ip, tcp)| {
+                    let mut packet = IpPacket::new(ip, IpPayload::Tcp(tcp));
+                    packet.set_traffic_class(traffic_class);
+                    packet
+                });
+        }
+
+        // No socket accepted the segment, but a busy socket may still answer a SYN to its
+        // listen endpoint with a SYN cookie, and a listening one may reconstruct
+        // the connection once the cookie is acknowledged.
+        let mut accepted = None;
+        for item in sockets.items_mut() {
+            let Some(tcp_socket) = tcp::Socket::downcast_mut(&mut item.socket) else {
+                continue;
+            };
+            if let Some((ip, tcp)) = tcp_socket.syn_cookie_reply(self, &ip_repr, &tcp_repr) {
+                return Some(IpPacket::new(ip, IpPayload::Tcp(tcp)));
+            }
+            if tcp_socket.accept_syn_cookie(self, &ip_repr, &tcp_repr) {
+                let reply = tcp_socket
                     .process(self, &ip_repr, &tcp_repr)
                     .map(|(ip, tcp)| IpPacket::new(ip, IpPayload::Tcp(tcp)));
+                accepted = Some((item.meta.handle, reply));
+                break;
             }
         }
+        if let Some((handle, reply)) = accepted {
+            sockets.touch(handle);
+            return reply;
+        }
 
         if tcp_repr.control == TcpControl::Rst {
             // Never reply to a TCP RST packet with another TCP RST packet.
@@ -1374,6 +2864,30 @@ impl InterfaceInner {
     }
 
     fn route(&self, addr: &IpAddress, timestamp: Instant) -> Option<IpAddress> {
+        self.route_for(addr, &RouteKey::default(), timestamp)
+    }
+
+    /// Return the next hop towards `addr` for the packets with the properties `key`, which
+    /// policy routes select.
+    fn route_for(&self, addr: &IpAddress, key: &RouteKey, timestamp: Instant) -> Option<IpAddress> {
+        if let Some(next_hop) = self.route_direct(addr) {
+            return Some(next_hop);
+        }
+        if let Some(next_hop) = self.destinations.next_hop(addr, key, timestamp) {
+            return Some(next_hop);
+        }
+        self.route_via_router(addr, key, timestamp)
+            .map(|(next_hop, _)| next_hop)
+    }
+
+    /// Return the next hop towards `addr` if it is not a router.
+    fn route_direct(&self, addr: &IpAddress) -> Option<IpAddress> {
+        // Route along the RPL DODAG, whose nodes may share a prefix without being on-link.
+        #[cfg(feature = "proto-rpl")]
+        if let Some(next_hop) = self.rpl_route(addr) {
+            return Some(next_hop);
+        }
+
         // Send directly.
         // note: no need to use `self.is_broadcast()` to check for subnet-local broadcast addrs
         //       here because `in_same_network` will already return true.
@@ -1381,8 +2895,28 @@ impl InterfaceInner {
             return Some(*addr);
         }
 
+        None
+    }
+
+    /// Return the router towards `addr` for the packets with the properties `key`, along
+    /// with when the route or the redirect leading to it expires, if ever.
+    fn route_via_router(
+        &self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        timestamp: Instant,
+    ) -> Option<(IpAddress, Option<Instant>)> {
+        // Follow the redirects of routers.
+        #[cfg(all(
+            feature = "proto-ipv6",
+            any(feature = "medium-ethernet", feature = "medium-ieee802154")
+        ))]
+        if let Some((next_hop, expires_at)) = self.redirect_lookup(addr, timestamp) {
+            return Some((next_hop, Some(expires_at)));
+        }
+
         // Route via a router.
-        self.routes.lookup(addr, timestamp)
+        self.routes.lookup_expiry(addr, key, timestamp)
     }
 
     fn has_neighbor(&self, addr: &IpAddress) -> bool {
@@ -1405,6 +2939,7 @@ impl InterfaceInner {
         tx_token: Tx,
         src_addr: &IpAddress,
         dst_addr: &IpAddress,
+        route_key: &RouteKey,
         fragmenter: &mut Fragmenter,
     ) -> Result<(HardwareAddress, Tx), DispatchError>
     where
@@ -1456,14 +2991,18 @@ impl InterfaceInner {
             return Ok((hardware_addr, tx_token));
         }
 
-        let dst_addr = self
-            .route(dst_addr, self.now)
-            .ok_or(DispatchError::NoRoute)?;
+        let Some(dst_addr) = self.route_cached(dst_addr, route_key) else {
+            self.stats.count_no_route();
+            return Err(DispatchError::NoRoute);
+        };
 
         match self.neighbor_cache.lookup(&dst_addr, self.now) {
             NeighborAnswer::Found(hardware_addr) => return Ok((hardware_addr, tx_token)),
-            NeighborAnswer::RateLimited => return Err(DispatchError::NeighborPending),
-            _ => (), // XXX
+            NeighborAnswer::RateLimited => {
+                self.stats.count_neighbor_miss();
+                return Err(DispatchError::NeighborPending);
+            }
+            _ => self.stats.count_neighbor_miss(),
         }
 
         match (src_addr, dst_addr) {
@@ -1525,17 +3064,32 @@ impl InterfaceInner {
                     net_debug!("Failed to dispatch NDISC solicit: {:?}", e);
                     return Err(DispatchError::NeighborPending);
                 }
+                self.default_router_solicited(dst_addr);
             }
 
             #[allow(unreachable_patterns)]
             _ => (),
         }
 
-        // The request got dispatched, limit the rate on the cache.
+        // The request got dispatched, limit the rate on the cache, and wait for the answer.
         self.neighbor_cache.limit_rate(self.now);
+        self.neighbor_cache.solicit(dst_addr, self.now);
         Err(DispatchError::NeighborPending)
     }
 
+    /// Check whether the filter lets `packet` be sent. Rejected packets can only be
+    /// dropped, as the interface itself sent them.
+    fn filter_egress(&self, packet: &IpPacket) -> bool {
+        if !self.filter.accepts_all() {
+            let filter_packet = FilterPacket::from_ip_packet(packet);
+            if self.filter.action(FilterDirection::Egress, &filter_packet) != FilterAction::Accept {
+                net_debug!("filter: dropping packet to {}", packet.ip_repr().dst_addr());
+                return false;
+            }
+        }
+        true
+    }
+
     fn flush_cache(&mut self) {
         #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
         self.neighbor_cache.flush()
@@ -1553,6 +3107,17 @@ impl InterfaceInner {
         let mut ip_repr = packet.ip_repr();
         assert!(!ip_repr.dst_addr().is_unspecified());
 
+        if !self.filter_egress(&packet) {
+            return Ok(());
+        }
+
+        #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+        let route_key = RouteKey {
+            src_addr: Some(ip_repr.src_addr()),
+            dscp: packet.traffic_class() >> 2,
+            mark: packet.mark(),
+        };
+
         // Dispatch IEEE802.15.4:
 
         #[cfg(feature = "medium-ieee802154")]
@@ -1561,10 +3126,12 @@ impl InterfaceInner {
                 tx_token,
                 &ip_repr.src_addr(),
                 &ip_repr.dst_addr(),
+                &route_key,
                 frag,
             )?;
             let addr = addr.ieee802154_or_panic();
 
+            self.stats.count_ip_tx(&ip_repr);
             self.dispatch_ieee802154(addr, tx_token, meta, packet, frag);
             return Ok(());
         }
@@ -1593,6 +3160,7 @@ impl InterfaceInner {
                     tx_token,
                     &ip_repr.src_addr(),
                     &ip_repr.dst_addr(),
+                    &route_key,
                     frag,
                 )? {
                     (HardwareAddress::Ethernet(addr), tx_token) => (addr, tx_token),
@@ -1602,6 +3170,8 @@ impl InterfaceInner {
             _ => (EthernetAddress([0; 6]), tx_token),
         };
 
+        self.stats.count_ip_tx(&ip_repr);
+
         // Emit function for the Ethernet header.
         #[cfg(feature = "medium-ethernet")]
         let emit_ethernet = |repr: &IpRepr, tx_buffer: &mut [u8]| {
@@ -1622,10 +3192,15 @@ impl InterfaceInner {
         };
 
         // Emit function for the IP header and payload.
-        let emit_ip = |repr: &IpRepr, mut tx_buffer: &mut [u8]| {
-            repr.emit(&mut tx_buffer, &self.caps.checksum);
-
-            let payload = &mut tx_buffer[repr.header_len()..];
+        #[cfg(feature = "proto-ipv4-fragmentation")]
+        let traffic_class = packet.traffic_class();
+        #[cfg(feature = "proto-ipv6")]
+        let flow_label = packet.flow_label();
+        #[cfg(feature = "proto-ipv4")]
+        let dont_frag = packet.dont_frag();
+        let emit_ip = |repr: &IpRepr, tx_buffer: &mut [u8]| {
+            let header_len = packet.emit_header(repr, tx_buffer, &self.caps.checksum);
+            let payload = &mut tx_buffer[header_len..];
             packet.emit_payload(repr, payload, &caps)
         };
 
@@ -1640,11 +3215,13 @@ impl InterfaceInner {
                     {
                         net_debug!("start fragmentation");
 
-                        // Calculate how much we will send now (including the Ethernet header).
-                        let tx_len = self.caps.max_transmission_unit;
-
+                        // The payload of the first fragment is a multiple of 8 octets.
                         let ip_header_len = repr.buffer_len();
-                        let first_frag_ip_len = self.caps.ip_mtu();
+                        let first_frag_ip_len =
+                            ip_header_len + ((self.caps.ip_mtu() - ip_header_len) & !7);
+
+                        // Calculate how much we will send now (including the Ethernet header).
+                        let tx_len = total_len - total_ip_len + first_frag_ip_len;
 
                         if frag.buffer.len() < total_ip_len {
                             net_debug!(
@@ -1665,6 +3242,7 @@ impl InterfaceInner {
 
                         // Save the IP header for other fragments.
                         frag.ipv4.repr = *repr;
+                        frag.ipv4.traffic_class = traffic_class;
 
                         // Save how much bytes we will send now.
                         frag.sent_bytes = first_frag_ip_len;
@@ -1687,6 +3265,7 @@ impl InterfaceInner {
                         }
 
                         // Transmit the first packet.
+                        self.stats.count_tx(tx_len);
                         tx_token.consume(tx_len, |mut tx_buffer| {
                             #[cfg(feature = "medium-ethernet")]
                             if matches!(self.caps.medium, Medium::Ethernet) {
@@ -1714,6 +3293,7 @@ impl InterfaceInner {
                     tx_token.set_meta(meta);
 
                     // No fragmentation is required.
+                    self.stats.count_tx(total_len);
                     tx_token.consume(total_len, |mut tx_buffer| {
                         #[cfg(feature = "medium-ethernet")]
                         if matches!(self.caps.medium, Medium::Ethernet) {
@@ -1722,26 +3302,72 @@ impl InterfaceInner {
                         }
 
                         emit_ip(&ip_repr, tx_buffer);
+                        if !dont_frag {
+                            let mut ipv4_packet = Ipv4PacketWire::new_unchecked(tx_buffer);
+                            ipv4_packet.set_dont_frag(false);
+                            if caps.checksum.ipv4.tx() {
+                                ipv4_packet.fill_checksum();
+                            }
+                        }
                         Ok(())
                     })
                 }
             }
             // We don't support IPv6 fragmentation yet.
             #[cfg(feature = "proto-ipv6")]
-            IpRepr::Ipv6(_) => tx_token.consume(total_len, |mut tx_buffer| {
-                #[cfg(feature = "medium-ethernet")]
-                if matches!(self.caps.medium, Medium::Ethernet) {
-                    emit_ethernet(&ip_repr, tx_buffer)?;
-                    tx_buffer = &mut tx_buffer[EthernetFrame::<&[u8]>::header_len()..];
-                }
+            IpRepr::Ipv6(_) => {
+                self.stats.count_tx(total_len);
+                tx_token.consume(total_len, |mut tx_buffer| {
+                    #[cfg(feature = "medium-ethernet")]
+                    if matches!(self.caps.medium, Medium::Ethernet) {
+                        emit_ethernet(&ip_repr, tx_buffer)?;
+                        tx_buffer = &mut tx_buffer[EthernetFrame::<&[u8]>::header_len()..];
+                    }
 
-                emit_ip(&ip_repr, tx_buffer);
-                Ok(())
-            }),
+                    emit_ip(&ip_repr, tx_buffer);
+                    if flow_label != 0 {
+                        Ipv6PacketWire::new_unchecked(tx_buffer).set_flow_label(flow_label);
+                    }
+                    Ok(())
+                })
+            }
         }
     }
 }
 
+/// Rank a source address of scope `src_scope` for a destination of scope `dst_scope`,
+/// lower is better: the smallest scope that reaches the destination, or else the largest
+/// one (RFC 6724 § 5, rule 2).
+fn scope_preference(src_scope: u8, dst_scope: u8) -> (bool, u8) {
+    if src_scope >= dst_scope {
+        (false, src_scope)
+    } else {
+        (true, u8::MAX - src_scope)
+    }
+}
+
+/// Return the label of `addr` in the default policy table (RFC 6724 § 2.1). Source
+/// addresses with the same label as the destination are preferred.
+#[cfg(feature = "proto-ipv6")]
+fn policy_label(addr: Ipv6Address) -> u8 {
+    const POLICY_TABLE: [(Ipv6Address, u8, u8); 8] = [
+        (Ipv6Address::LOOPBACK, 128, 0),
+        (Ipv6Address::new(0, 0, 0, 0, 0, 0xffff, 0, 0), 96, 4),
+        (Ipv6Address::new(0, 0, 0, 0, 0, 0, 0, 0), 96, 3),
+        (Ipv6Address::new(0x2001, 0, 0, 0, 0, 0, 0, 0), 32, 5),
+        (Ipv6Address::new(0x2002, 0, 0, 0, 0, 0, 0, 0), 16, 2),
+        (Ipv6Address::new(0x3ffe, 0, 0, 0, 0, 0, 0, 0), 16, 12),
+        (Ipv6Address::new(0xfec0, 0, 0, 0, 0, 0, 0, 0), 10, 11),
+        (Ipv6Address::new(0xfc00, 0, 0, 0, 0, 0, 0, 0), 7, 13),
+    ];
+
+    // The prefixes are sorted by decreasing length, so the first match is the longest.
+    POLICY_TABLE
+        .iter()
+        .find(|(prefix, len, _)| Ipv6Cidr::new(*prefix, *len).contains_addr(&addr))
+        .map_or(1, |(_, _, label)| *label)
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 enum DispatchError {
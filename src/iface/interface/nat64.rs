@@ -0,0 +1,88 @@
+use super::Interface;
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+use super::InterfaceInner;
+use crate::time::Instant;
+use crate::wire::*;
+
+/// The NAT64 prefix, set through the API or learned from Router Advertisements.
+#[derive(Debug, Default)]
+pub(crate) struct Nat64 {
+    prefix: Option<Ipv6Cidr>,
+    /// `None` means "forever".
+    #[allow(unused)] // unused without Router Advertisements
+    prefix_until: Option<Instant>,
+}
+
+impl Interface {
+    /// Get the NAT64 prefix of the network.
+    ///
+    /// The prefix comes from the PREF64 option of the last Router Advertisement that
+    /// carried one (RFC 8781), and is forgotten once its lifetime runs out, unless it was
+    /// set with [set_nat64_prefix](#method.set_nat64_prefix).
+    pub fn nat64_prefix(&self) -> Option<Ipv6Cidr> {
+        self.inner.nat64.prefix
+    }
+
+    /// Set the NAT64 prefix of the network, e.g. as discovered by resolving
+    /// `ipv4only.arpa` with [`Ipv6Cidr::from_ipv4only_arpa`]. The prefix lasts until
+    /// replaced by another one, possibly from a Router Advertisement.
+    ///
+    /// # Panics
+    /// This function panics if the prefix length is not one of 32, 40, 48, 56, 64 or 96.
+    pub fn set_nat64_prefix(&mut self, prefix: Option<Ipv6Cidr>) {
+        if let Some(prefix) = prefix {
+            assert!(
+                matches!(prefix.prefix_len(), 32 | 40 | 48 | 56 | 64 | 96),
+                "invalid NAT64 prefix length {}",
+                prefix.prefix_len()
+            );
+        }
+        self.inner.nat64 = Nat64 {
+            prefix,
+            prefix_until: None,
+        };
+    }
+
+    /// Return the IPv6 address that reaches an IPv4 destination through NAT64, if a NAT64
+    /// prefix is known.
+    #[cfg(feature = "proto-ipv4")]
+    pub fn nat64_address(&self, addr: Ipv4Address) -> Option<Ipv6Address> {
+        self.inner.nat64.prefix?.embed_ipv4(addr)
+    }
+}
+
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+impl InterfaceInner {
+    pub(super) fn process_pref64(&mut self, pref64: Option<NdiscPref64>) {
+        let Some(pref64) = pref64 else {
+            return;
+        };
+        if pref64.lifetime == crate::time::Duration::ZERO {
+            // Only the advertised prefix is withdrawn.
+            if self.nat64.prefix == Some(Ipv6Cidr::new(pref64.prefix, pref64.prefix_len)) {
+                net_debug!("pref64: NAT64 prefix withdrawn");
+                self.nat64 = Nat64::default();
+            }
+            return;
+        }
+
+        let prefix = Ipv6Cidr::new(pref64.prefix, pref64.prefix_len);
+        net_debug!("pref64: NAT64 prefix {}", prefix);
+        self.nat64 = Nat64 {
+            prefix: Some(prefix),
+            prefix_until: Some(self.now + pref64.lifetime),
+        };
+    }
+
+    /// Forget the NAT64 prefix whose lifetime ran out.
+    pub(super) fn nat64_expire(&mut self) {
+        if self.nat64.prefix_until.map_or(false, |t| t <= self.now) {
+            net_debug!("pref64: NAT64 prefix expired");
+            self.nat64 = Nat64::default();
+        }
+    }
+
+    pub(super) fn nat64_poll_at(&self) -> Option<Instant> {
+        self.nat64.prefix_until
+    }
+}
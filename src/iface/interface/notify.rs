@@ -0,0 +1,84 @@
+use super::{Interface, InterfaceInner, SocketSet};
+
+/// What changed in the configuration of an interface, see
+/// [`Interface::set_config_callback`].
+///
+/// This struct is marked as `#[non_exhaustive]`, so that new fields can be added without
+/// a breaking change.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct ConfigChange {
+    /// IP addresses were added to the interface or removed from it, by the application,
+    /// by stateless autoconfiguration, or because their lifetime ran out.
+    pub addrs: bool,
+    /// The routes changed, e.g. because the default router learned from Router
+    /// Advertisements changed.
+    pub routes: bool,
+    /// The DNS servers or search list learned from Router Advertisements changed.
+    pub dns: bool,
+}
+
+impl ConfigChange {
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// A function called when the configuration of an interface changes, with the sockets
+/// that use it, see [`Interface::set_config_callback`].
+pub type ConfigCallback = fn(change: ConfigChange, sockets: &mut SocketSet<'_>);
+
+/// The configuration changes not reported yet.
+#[derive(Debug, Default)]
+pub(crate) struct Notify {
+    callback: Option<ConfigCallback>,
+    pending: ConfigChange,
+}
+
+impl Interface {
+    /// Set the function called when the addresses, the routes, or the DNS servers of the
+    /// interface change.
+    ///
+    /// The changes are reported by [poll](Self::poll), with its sockets, so that the
+    /// application can e.g. rebind them to a new address, or resolve names again with
+    /// other servers, without checking the configuration on every call. The changes made
+    /// since the previous call, by the application itself as well as by e.g. stateless
+    /// autoconfiguration, are reported together, once.
+    pub fn set_config_callback(&mut self, callback: Option<ConfigCallback>) {
+        self.inner.notify.callback = callback;
+    }
+
+    /// Report the configuration changes to the callback, if any.
+    pub(super) fn config_notify(&mut self, sockets: &mut SocketSet<'_>) {
+        let notify = &mut self.inner.notify;
+        if notify.pending.is_empty() {
+            return;
+        }
+        let change = core::mem::take(&mut notify.pending);
+        if let Some(callback) = notify.callback {
+            callback(change, sockets);
+        }
+    }
+}
+
+impl InterfaceInner {
+    pub(super) fn addrs_changed(&mut self) {
+        self.notify.pending.addrs = true;
+    }
+
+    /// Note that the routes changed, and forget the routers that the destinations went
+    /// through.
+    pub(super) fn routes_changed(&mut self) {
+        self.destinations.flush_next_hops();
+        self.notify.pending.routes = true;
+    }
+
+    #[cfg(all(
+        feature = "proto-ipv6",
+        any(feature = "medium-ethernet", feature = "medium-ieee802154")
+    ))]
+    pub(super) fn dns_changed(&mut self) {
+        self.notify.pending.dns = true;
+    }
+}
@@ -0,0 +1,68 @@
+use byteorder::{ByteOrder, NetworkEndian};
+
+use super::{InterfaceInner, SocketSet};
+#[cfg(any(feature = "socket-tcp", feature = "socket-udp"))]
+use crate::socket::Socket;
+use crate::time::Duration;
+use crate::wire::*;
+
+/// How long a reduced path MTU is kept for, after which larger packets are tried again
+/// (RFC 8201 § 4).
+const PATH_MTU_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+impl InterfaceInner {
+    /// Process a Packet Too Big message (RFC 8201 § 4), and let the sockets whose packet
+    /// was dropped send smaller ones.
+    pub(super) fn process_pkt_too_big(
+        &mut self,
+        _sockets: &mut SocketSet,
+        mtu: u32,
+        header: &Ipv6Repr,
+        data: &[u8],
+    ) {
+        if !self.has_ip_addr(header.src_addr) {
+            net_debug!("icmpv6: ignoring packet too big for a packet we did not send");
+            return;
+        }
+        // The path MTU is never reduced below the minimum link MTU.
+        let mtu = mtu.min(u16::MAX as u32) as usize;
+        if mtu < IPV6_MIN_MTU {
+            net_debug!("icmpv6: ignoring packet too big with mtu {}", mtu);
+            return;
+        }
+        if mtu >= self.path_mtu(&header.dst_addr.into()) {
+            return;
+        }
+
+        // The path MTUs are kept in the destination cache (RFC 8201 § 5.3).
+        net_debug!("icmpv6: path mtu to {} is {}", header.dst_addr, mtu);
+        self.destinations.set_path_mtu(
+            &header.dst_addr.into(),
+            mtu,
+            self.now + PATH_MTU_LIFETIME,
+            self.now,
+        );
+
+        // The quoted packet starts with the ports of its transport header.
+        if data.len() < 4 {
+            return;
+        }
+        let _local = IpEndpoint::new(header.src_addr.into(), NetworkEndian::read_u16(&data[0..2]));
+        let _remote = IpEndpoint::new(header.dst_addr.into(), NetworkEndian::read_u16(&data[2..4]));
+
+        // The sockets that have segments to send again are evaluated again.
+        #[cfg(any(feature = "socket-tcp", feature = "socket-udp"))]
+        _sockets.touch_matching(|item| match &mut item.socket {
+            #[cfg(feature = "socket-tcp")]
+            Socket::Tcp(socket) if header.next_header == IpProtocol::Tcp => {
+                socket.path_mtu_reduced(_local, _remote)
+            }
+            #[cfg(feature = "socket-udp")]
+            Socket::Udp(socket) if header.next_header == IpProtocol::Udp => {
+                socket.path_mtu_reduced(_local, _remote, mtu);
+                false
+            }
+            _ => false,
+        });
+    }
+}
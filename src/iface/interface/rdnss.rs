@@ -0,0 +1,155 @@
+use heapless::{String, Vec};
+
+use super::{Interface, InterfaceInner};
+use crate::config::{DNS_MAX_NAME_SIZE, DNS_MAX_SERVER_COUNT};
+#[cfg(feature = "socket-dns")]
+use crate::iface::SocketSet;
+#[cfg(feature = "socket-dns")]
+use crate::socket::{dns, AnySocket};
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// Lifetime that Router Advertisements use for "forever".
+const INFINITE_LIFETIME: Duration = Duration::from_secs(0xffff_ffff);
+
+/// DNS configuration learned from Router Advertisements (RFC 8106).
+#[derive(Debug, Default)]
+pub(crate) struct RouterDns {
+    servers: Vec<Ipv6Address, DNS_MAX_SERVER_COUNT>,
+    /// `None` means "forever".
+    servers_until: Option<Instant>,
+    search_list: Vec<String<DNS_MAX_NAME_SIZE>, DNS_MAX_SERVER_COUNT>,
+    /// `None` means "forever".
+    search_list_until: Option<Instant>,
+}
+
+fn deadline(now: Instant, lifetime: Duration) -> Option<Instant> {
+    (lifetime != INFINITE_LIFETIME).then(|| now + lifetime)
+}
+
+impl Interface {
+    /// Get the recursive DNS servers advertised by IPv6 routers.
+    ///
+    /// The servers come from the Recursive DNS Server option of the last Router
+    /// Advertisement that carried one, and are forgotten once its lifetime runs out.
+    /// Only the first `DNS_MAX_SERVER_COUNT` servers are kept.
+    ///
+    /// DNS sockets can also pick these servers up by themselves, see
+    /// [`dns::Socket::set_router_servers`](crate::socket::dns::Socket::set_router_servers).
+    pub fn ipv6_dns_servers(&self) -> &[Ipv6Address] {
+        &self.inner.router_dns.servers
+    }
+
+    /// Get the DNS search list advertised by IPv6 routers.
+    ///
+    /// The domains come from the DNS Search List option of the last Router Advertisement
+    /// that carried one, and are forgotten once its lifetime runs out. As many domains
+    /// as DNS servers are kept.
+    pub fn ipv6_dns_search_list(&self) -> impl Iterator<Item = &str> {
+        self.inner.router_dns.search_list.iter().map(|s| s.as_str())
+    }
+}
+
+impl InterfaceInner {
+    pub(super) fn process_router_dns(
+        &mut self,
+        rdnss: Option<NdiscRecursiveDnsServer>,
+        dnssl: Option<NdiscDnsSearchList>,
+    ) {
+        let now = self.now;
+        let dns = &mut self.router_dns;
+        let mut changed = false;
+
+        if let Some(rdnss) = rdnss {
+            let previous = dns.servers.clone();
+            dns.servers.clear();
+            if rdnss.lifetime != Duration::ZERO {
+                for addr in rdnss.addresses().filter(|addr| addr.is_unicast()) {
+                    if dns.servers.push(addr).is_err() {
+                        break;
+                    }
+                }
+            }
+            net_debug!("rdnss: DNS servers {:?}", dns.servers);
+            dns.servers_until = deadline(now, rdnss.lifetime);
+            changed |= dns.servers != previous;
+        }
+
+        if let Some(dnssl) = dnssl {
+            let previous = dns.search_list.clone();
+            dns.search_list.clear();
+            if dnssl.lifetime != Duration::ZERO {
+                for labels in dnssl.names() {
+                    let Some(name) = decode_name(labels) else {
+                        continue;
+                    };
+                    if dns.search_list.push(name).is_err() {
+                        break;
+                    }
+                }
+            }
+            dns.search_list_until = deadline(now, dnssl.lifetime);
+            changed |= dns.search_list != previous;
+        }
+
+        if changed {
+            self.dns_changed();
+        }
+    }
+
+    /// Forget the DNS configuration whose lifetime ran out.
+    pub(super) fn router_dns_expire(&mut self) {
+        let now = self.now;
+        let dns = &mut self.router_dns;
+        let mut changed = false;
+        if dns.servers_until.map_or(false, |t| t <= now) {
+            net_debug!("rdnss: DNS servers expired");
+            changed |= !dns.servers.is_empty();
+            dns.servers.clear();
+            dns.servers_until = None;
+        }
+        if dns.search_list_until.map_or(false, |t| t <= now) {
+            changed |= !dns.search_list.is_empty();
+            dns.search_list.clear();
+            dns.search_list_until = None;
+        }
+        if changed {
+            self.dns_changed();
+        }
+    }
+
+    pub(super) fn router_dns_poll_at(&self) -> Option<Instant> {
+        let dns = &self.router_dns;
+        let servers_until = dns.servers_until.filter(|_| !dns.servers.is_empty());
+        let search_list_until = dns
+            .search_list_until
+            .filter(|_| !dns.search_list.is_empty());
+        [servers_until, search_list_until]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Hand the advertised DNS servers to the sockets that asked for them.
+    #[cfg(feature = "socket-dns")]
+    pub(super) fn router_dns_sync(&self, sockets: &mut SocketSet) {
+        for socket in sockets
+            .items_mut()
+            .filter_map(|item| dns::Socket::downcast_mut(&mut item.socket))
+        {
+            socket.update_router_servers(&self.router_dns.servers);
+        }
+    }
+}
+
+/// Join the labels of a domain name with dots, if it is printable and fits.
+fn decode_name<'a>(labels: impl Iterator<Item = &'a [u8]>) -> Option<String<DNS_MAX_NAME_SIZE>> {
+    let mut name = String::new();
+    for label in labels {
+        if !name.is_empty() {
+            name.push('.').ok()?;
+        }
+        name.push_str(core::str::from_utf8(label).ok()?).ok()?;
+    }
+    (!name.is_empty()).then_some(name)
+}
@@ -0,0 +1,105 @@
+use heapless::Vec;
+
+use super::{check, InterfaceInner};
+use crate::config::IFACE_NEIGHBOR_CACHE_COUNT;
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// How long a Redirect is followed for. RFC 4861 leaves it to the implementation; the
+/// router sends a new one if the destination is still redirected afterwards.
+const REDIRECT_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+/// A destination that a router redirected to a better next hop.
+#[derive(Debug, Clone, Copy)]
+struct Redirect {
+    dest_addr: Ipv6Address,
+    /// The next hop: a better router, or the destination itself if it is on-link.
+    target_addr: Ipv6Address,
+    expires_at: Instant,
+}
+
+/// The next hops learned from Redirects, overriding the routes of their destinations
+/// (RFC 4861 § 5.1, Destination Cache).
+#[derive(Debug, Default)]
+pub(crate) struct Redirects {
+    entries: Vec<Redirect, IFACE_NEIGHBOR_CACHE_COUNT>,
+}
+
+impl InterfaceInner {
+    /// Process a Redirect message (RFC 4861 § 8.3).
+    pub(super) fn process_redirect(
+        &mut self,
+        ip_repr: &Ipv6Repr,
+        target_addr: Ipv6Address,
+        dest_addr: Ipv6Address,
+        lladdr: Option<RawHardwareAddress>,
+    ) {
+        // RFC 4861 § 8.1.
+        if !ip_repr.src_addr.is_link_local()
+            || !dest_addr.is_unicast()
+            || !(target_addr.is_link_local() || target_addr == dest_addr)
+        {
+            net_debug!("ndisc: ignoring invalid redirect for {}", dest_addr);
+            return;
+        }
+        // Only the current first hop towards the destination may redirect it.
+        if self.route(&dest_addr.into(), self.now) != Some(ip_repr.src_addr.into()) {
+            net_debug!(
+                "ndisc: ignoring redirect for {} from {}, which is not its first hop",
+                dest_addr,
+                ip_repr.src_addr
+            );
+            return;
+        }
+
+        if let Some(lladdr) = lladdr {
+            let lladdr = check!(lladdr.parse(self.caps.medium));
+            if lladdr.is_unicast() {
+                self.neighbor_cache
+                    .fill(target_addr.into(), lladdr, self.now);
+            }
+        }
+
+        net_debug!("ndisc: redirecting {} to {}", dest_addr, target_addr);
+        let redirect = Redirect {
+            dest_addr,
+            target_addr,
+            expires_at: self.now + REDIRECT_LIFETIME,
+        };
+        let entries = &mut self.redirects.entries;
+        let now = self.now;
+        entries.retain(|r| r.dest_addr != dest_addr && r.expires_at > now);
+        if entries.is_full() {
+            // Evict the oldest redirect.
+            let index = entries
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, r)| r.expires_at)
+                .map(|(index, _)| index)
+                .unwrap();
+            entries.swap_remove(index);
+        }
+        // NOTE(unwrap): room was just made.
+        entries.push(redirect).unwrap();
+        self.destinations.flush_next_hops();
+    }
+
+    /// Return the next hop that `addr` was redirected to, if any, along with when the
+    /// redirect expires.
+    pub(super) fn redirect_lookup(
+        &self,
+        addr: &IpAddress,
+        timestamp: Instant,
+    ) -> Option<(IpAddress, Instant)> {
+        let addr = match addr {
+            IpAddress::Ipv6(addr) => *addr,
+            #[allow(unreachable_patterns)]
+            _ => return None,
+        };
+        self.redirects
+            .entries
+            .iter()
+            .find(|r| r.dest_addr == addr && r.expires_at > timestamp)
+            .map(|r| (r.target_addr.into(), r.expires_at))
+    }
+}
@@ -0,0 +1,122 @@
+use super::{Interface, InterfaceInner, IpPacket, IpPayload};
+use crate::phy::{Device, PacketMeta};
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// Upper bound of the random delay before the first Router Solicitation
+/// (RFC 4861 § 10, MAX_RTR_SOLICITATION_DELAY).
+const MAX_RTR_SOLICITATION_DELAY: Duration = Duration::from_secs(1);
+
+/// Time between Router Solicitations (RFC 4861 § 10, RTR_SOLICITATION_INTERVAL).
+const RTR_SOLICITATION_INTERVAL: Duration = Duration::from_secs(4);
+
+/// Number of Router Solicitations sent before giving up (RFC 4861 § 10, MAX_RTR_SOLICITATIONS).
+const MAX_RTR_SOLICITATIONS: u8 = 3;
+
+/// State of Router Solicitation (RFC 4861 § 6.3.7).
+#[derive(Debug, Default)]
+pub(crate) struct RouterSolicit {
+    /// Number of solicitations still to send after the next one.
+    left: u8,
+    /// When to send the next solicitation, if any.
+    retry_at: Option<Instant>,
+}
+
+impl Interface {
+    /// Solicit Router Advertisements, instead of waiting for the next periodic one.
+    ///
+    /// Up to three Router Solicitations are sent, four seconds apart, after a random delay
+    /// of up to one second; they stop as soon as a router advertises itself as a default
+    /// router. This should be called when the link comes up. It is done automatically when
+    /// stateless autoconfiguration gets enabled, and when the hardware address changes
+    /// while it is enabled.
+    pub fn solicit_routers(&mut self) {
+        self.inner.router_solicit_start();
+    }
+
+    /// Send the pending Router Solicitation, if it is due.
+    pub(crate) fn router_solicit_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        if !self
+            .inner
+            .router_solicit
+            .retry_at
+            .map_or(false, |t| t <= now)
+        {
+            return false;
+        }
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        // Solicitations from the unspecified address must not carry a link-layer
+        // address (RFC 4861 § 4.1).
+        let src_addr = self.inner.link_local_ipv6_addr();
+        let solicit = Icmpv6Repr::Ndisc(NdiscRepr::RouterSolicit {
+            lladdr: src_addr.map(|_| self.inner.hardware_addr.into()),
+        });
+        let ip_repr = Ipv6Repr {
+            src_addr: src_addr.unwrap_or(Ipv6Address::UNSPECIFIED),
+            dst_addr: Ipv6Address::LINK_LOCAL_ALL_ROUTERS,
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 0xff,
+            payload_len: solicit.buffer_len(),
+        };
+        net_trace!("ndisc: soliciting routers");
+        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+        self.inner
+            .dispatch_ip(
+                tx_token,
+                PacketMeta::default(),
+                IpPacket::new_ipv6(ip_repr, IpPayload::Icmpv6(solicit)),
+                &mut self.fragmenter,
+            )
+            .unwrap();
+
+        let rs = &mut self.inner.router_solicit;
+        if rs.left == 0 {
+            rs.retry_at = None;
+        } else {
+            rs.left -= 1;
+            rs.retry_at = Some(now + RTR_SOLICITATION_INTERVAL);
+        }
+        true
+    }
+}
+
+impl InterfaceInner {
+    pub(super) fn router_solicit_start(&mut self) {
+        let delay = self.rand.rand_u32() % MAX_RTR_SOLICITATION_DELAY.total_millis() as u32;
+        self.router_solicit = RouterSolicit {
+            left: MAX_RTR_SOLICITATIONS - 1,
+            retry_at: Some(self.now + Duration::from_millis(delay.into())),
+        };
+    }
+
+    /// Stop soliciting once a default router advertised itself.
+    pub(super) fn router_solicit_done(&mut self, router_lifetime: Duration) {
+        if router_lifetime != Duration::ZERO {
+            self.router_solicit.retry_at = None;
+        }
+    }
+
+    /// Stop soliciting, e.g. when the link goes down.
+    pub(super) fn router_solicit_stop(&mut self) {
+        self.router_solicit.retry_at = None;
+    }
+
+    pub(super) fn router_solicit_poll_at(&self) -> Option<Instant> {
+        self.router_solicit.retry_at
+    }
+
+    /// Our assigned link-local address, if any.
+    fn link_local_ipv6_addr(&self) -> Option<Ipv6Address> {
+        self.ip_addrs.iter().find_map(|cidr| match cidr {
+            IpCidr::Ipv6(cidr) if cidr.address().is_link_local() => Some(cidr.address()),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,720 @@
+use super::{check, Interface, InterfaceInner, IpPacket, IpPayload};
+use crate::config::{IFACE_MAX_ADDR_COUNT, RPL_RELATIONS_BUFFER_COUNT};
+use crate::iface::rpl::*;
+use crate::phy::{Device, PacketMeta};
+use crate::rand::Rand;
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// Length of a RPL Target option for a single address.
+const TARGET_OPTION_LEN: usize = 20;
+
+/// Length of a Transit Information option without a parent address.
+const TRANSIT_OPTION_LEN: usize = 6;
+
+/// Length of a DODAG Configuration option.
+const DODAG_CONF_OPTION_LEN: usize = 16;
+
+/// Time to wait before sending a DAO again when it could not be sent.
+const DAO_RETRANSMIT_DELAY: Duration = Duration::from_secs(1);
+
+/// State of RPL (RFC 6550).
+#[derive(Debug)]
+pub(crate) struct Rpl {
+    config: RplConfig,
+    /// The DODAG that the node is part of, if any.
+    dodag: Option<Dodag>,
+}
+
+/// The parameters of a DODAG, from its DODAG Configuration option (RFC 6550 § 6.7.6).
+#[derive(Debug, Clone, Copy)]
+struct DodagConfiguration {
+    dio_interval_doublings: u8,
+    dio_interval_min: u8,
+    dio_redundancy_constant: u8,
+    max_rank_increase: u16,
+    min_hop_rank_increase: u16,
+    default_lifetime: u8,
+    lifetime_unit: u16,
+}
+
+impl Default for DodagConfiguration {
+    fn default() -> Self {
+        Self {
+            dio_interval_doublings: DEFAULT_DIO_INTERVAL_DOUBLINGS as u8,
+            dio_interval_min: DEFAULT_DIO_INTERVAL_MIN as u8,
+            dio_redundancy_constant: DEFAULT_DIO_REDUNDANCY_CONSTANT as u8,
+            max_rank_increase: DEFAULT_MAX_RANK_INCREASE,
+            min_hop_rank_increase: DEFAULT_MIN_HOP_RANK_INCREASE,
+            default_lifetime: DEFAULT_ROUTE_LIFETIME,
+            lifetime_unit: DEFAULT_LIFETIME_UNIT,
+        }
+    }
+}
+
+impl DodagConfiguration {
+    /// Return the DODAG configuration found in the options of a DIO, if any. DODAGs that
+    /// use another objective function than OF0 are not supported.
+    fn parse(options: &[u8]) -> Option<Self> {
+        RplOptionsIterator::new(options)
+            .flatten()
+            .find_map(|option| match option {
+                RplOptionRepr::DodagConfiguration {
+                    dio_interval_doublings,
+                    dio_interval_min,
+                    dio_redundancy_constant,
+                    max_rank_increase,
+                    minimum_hop_rank_increase,
+                    objective_code_point: 0,
+                    default_lifetime,
+                    lifetime_unit,
+                    ..
+                } if minimum_hop_rank_increase != 0
+                    && u32::from(dio_interval_min) + u32::from(dio_interval_doublings) < 32 =>
+                {
+                    Some(Self {
+                        dio_interval_doublings,
+                        dio_interval_min,
+                        dio_redundancy_constant,
+                        max_rank_increase,
+                        min_hop_rank_increase: minimum_hop_rank_increase,
+                        default_lifetime,
+                        lifetime_unit,
+                    })
+                }
+                _ => None,
+            })
+    }
+
+    fn emit(&self, buffer: &mut [u8]) {
+        let repr = RplOptionRepr::DodagConfiguration {
+            authentication_enabled: false,
+            path_control_size: 0,
+            dio_interval_doublings: self.dio_interval_doublings,
+            dio_interval_min: self.dio_interval_min,
+            dio_redundancy_constant: self.dio_redundancy_constant,
+            max_rank_increase: self.max_rank_increase,
+            minimum_hop_rank_increase: self.min_hop_rank_increase,
+            objective_code_point: 0,
+            default_lifetime: self.default_lifetime,
+            lifetime_unit: self.lifetime_unit,
+        };
+        repr.emit(&mut RplOptionPacket::new_unchecked(buffer));
+    }
+
+    fn dio_timer(&self, now: Instant, rand: &mut Rand) -> TrickleTimer {
+        let i_min = u32::from(self.dio_interval_min);
+        TrickleTimer::new(
+            i_min,
+            i_min + u32::from(self.dio_interval_doublings),
+            self.dio_redundancy_constant.into(),
+            now,
+            rand,
+        )
+    }
+
+    /// Return the duration of a lifetime expressed in lifetime units.
+    fn lifetime(&self, lifetime: u8) -> Duration {
+        Duration::from_secs(u64::from(lifetime) * u64::from(self.lifetime_unit))
+    }
+}
+
+/// A DODAG that the node is part of (RFC 6550 § 3.2).
+#[derive(Debug)]
+struct Dodag {
+    instance_id: RplInstanceId,
+    id: Ipv6Address,
+    version_number: SequenceCounter,
+    grounded: bool,
+    mode_of_operation: RplModeOfOperation,
+    preference: u8,
+    dtsn: SequenceCounter,
+    conf: DodagConfiguration,
+    rank: Rank,
+    dio_timer: TrickleTimer,
+    parent_set: ParentSet,
+    /// The preferred parent, which is the next hop of upward routes.
+    parent: Option<Ipv6Address>,
+    /// The last DTSN advertised by the preferred parent.
+    parent_dtsn: Option<SequenceCounter>,
+    dao_sequence: SequenceCounter,
+    /// When to send the next DAO, if any.
+    dao_at: Option<Instant>,
+    /// The downward routes learned from DAOs.
+    relations: Relations,
+}
+
+impl Dodag {
+    /// Schedule a DAO, if downward routes are maintained.
+    fn schedule_dao(&mut self, at: Instant) {
+        if self.mode_of_operation == RplModeOfOperation::StoringModeWithoutMulticast
+            && self.parent.is_some()
+        {
+            self.dao_at = Some(self.dao_at.map_or(at, |dao_at| dao_at.min(at)));
+        }
+    }
+}
+
+impl Rpl {
+    pub(super) fn new(config: RplConfig, now: Instant, rand: &mut Rand) -> Self {
+        let dodag = config.root.map(|root| {
+            let conf = DodagConfiguration::default();
+            Dodag {
+                instance_id: root.instance_id,
+                id: root.dodag_id,
+                version_number: SequenceCounter::default(),
+                grounded: root.grounded,
+                mode_of_operation: config.mode_of_operation,
+                preference: root.preference,
+                dtsn: SequenceCounter::default(),
+                conf,
+                rank: Rank::ROOT,
+                dio_timer: conf.dio_timer(now, rand),
+                parent_set: ParentSet::default(),
+                parent: None,
+                parent_dtsn: None,
+                dao_sequence: SequenceCounter::default(),
+                dao_at: None,
+                relations: Relations::default(),
+            }
+        });
+        Self { config, dodag }
+    }
+}
+
+impl Interface {
+    /// Get the ID of the RPL DODAG that the interface is part of, if any.
+    pub fn rpl_dodag_id(&self) -> Option<Ipv6Address> {
+        self.inner.rpl_dodag().map(|dodag| dodag.id)
+    }
+
+    /// Get the Rank of the interface in its RPL DODAG, if it is part of one.
+    pub fn rpl_rank(&self) -> Option<u16> {
+        self.inner.rpl_dodag().map(|dodag| dodag.rank.raw_value())
+    }
+
+    /// Get the preferred parent of the interface in its RPL DODAG, if any.
+    ///
+    /// Packets to global destinations that there is no downward route for go through it.
+    pub fn rpl_parent(&self) -> Option<Ipv6Address> {
+        self.inner.rpl_dodag().and_then(|dodag| dodag.parent)
+    }
+
+    /// Send the DAO or the DIO that is due, if any.
+    pub(crate) fn rpl_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        let Some(dodag) = self.inner.rpl.as_mut().and_then(|rpl| rpl.dodag.as_mut()) else {
+            return false;
+        };
+
+        if dodag.dao_at.map_or(false, |t| t <= now) {
+            return self.rpl_dao_egress(device);
+        }
+        if !dodag.dio_timer.poll(now, &mut self.inner.rand) {
+            return false;
+        }
+
+        let Some(src_addr) = self
+            .inner
+            .get_source_address_ipv6(Ipv6Address::LINK_LOCAL_ALL_RPL_NODES)
+            .filter(|addr| addr.is_link_local())
+        else {
+            net_debug!("rpl: no link-local address to send DIOs from");
+            return false;
+        };
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+
+        // NOTE(unwrap): the DODAG was found above.
+        let dodag = self.inner.rpl_dodag().unwrap();
+        let mut options = [0u8; DODAG_CONF_OPTION_LEN];
+        dodag.conf.emit(&mut options);
+        let dio = Icmpv6Repr::Rpl(RplRepr::DodagInformationObject {
+            rpl_instance_id: dodag.instance_id,
+            version_number: dodag.version_number.value(),
+            rank: dodag.rank.raw_value(),
+            grounded: dodag.grounded,
+            mode_of_operation: dodag.mode_of_operation,
+            dodag_preference: dodag.preference,
+            dtsn: dodag.dtsn.value(),
+            dodag_id: dodag.id,
+            options: &options,
+        });
+        let ip_repr = Ipv6Repr {
+            src_addr,
+            dst_addr: Ipv6Address::LINK_LOCAL_ALL_RPL_NODES,
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 64,
+            payload_len: dio.buffer_len(),
+        };
+        net_trace!("rpl: sending DIO with rank {}", dodag.rank);
+        // NOTE(unwrap): packet destination is multicast, which is always routable and doesn't require neighbor discovery.
+        self.inner
+            .dispatch_ip(
+                tx_token,
+                PacketMeta::default(),
+                IpPacket::new_ipv6(ip_repr, IpPayload::Icmpv6(dio)),
+                &mut self.fragmenter,
+            )
+            .unwrap();
+        true
+    }
+
+    /// Advertise the addresses of the interface and its sub-DODAG to the preferred parent
+    /// (RFC 6550 § 9.2).
+    fn rpl_dao_egress<D>(&mut self, device: &mut D) -> bool
+    where
+        D: Device + ?Sized,
+    {
+        let now = self.inner.now;
+        // NOTE(unwrap): DAOs are only scheduled for DODAGs with a preferred parent.
+        let dodag = self.inner.rpl_dodag().unwrap();
+        let parent = dodag.parent.unwrap();
+
+        let mut options = [0u8; TARGET_OPTION_LEN
+            * (IFACE_MAX_ADDR_COUNT + RPL_RELATIONS_BUFFER_COUNT)
+            + TRANSIT_OPTION_LEN];
+        let mut len = 0;
+        let own_addrs = self.inner.ip_addrs.iter().filter_map(|cidr| match cidr {
+            IpCidr::Ipv6(cidr) if !cidr.address().is_link_local() => Some(cidr.address()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        });
+        for target in own_addrs
+            .chain(dodag.relations.destinations())
+            .filter(|addr| addr.is_unicast() && !addr.is_loopback())
+        {
+            let target = RplOptionRepr::RplTarget {
+                prefix_length: 128,
+                prefix: target,
+            };
+            target.emit(&mut RplOptionPacket::new_unchecked(
+                &mut options[len..][..TARGET_OPTION_LEN],
+            ));
+            len += TARGET_OPTION_LEN;
+        }
+        if len == 0 {
+            // Nothing to advertise.
+            self.inner.rpl_dodag_mut().unwrap().dao_at = None;
+            return false;
+        }
+        let transit = RplOptionRepr::TransitInformation {
+            external: false,
+            path_control: 0,
+            path_sequence: dodag.dao_sequence.value(),
+            path_lifetime: dodag.conf.default_lifetime,
+            parent_address: None,
+        };
+        transit.emit(&mut RplOptionPacket::new_unchecked(
+            &mut options[len..][..TRANSIT_OPTION_LEN],
+        ));
+        len += TRANSIT_OPTION_LEN;
+
+        let dao = Icmpv6Repr::Rpl(RplRepr::DestinationAdvertisementObject {
+            rpl_instance_id: dodag.instance_id,
+            expect_ack: false,
+            sequence: dodag.dao_sequence.value(),
+            dodag_id: Some(dodag.id),
+            options: &options[..len],
+        });
+        let lifetime = dodag.conf.lifetime(dodag.conf.default_lifetime);
+
+        let Some(src_addr) = self
+            .inner
+            .get_source_address_ipv6(parent)
+            .filter(|addr| addr.is_link_local())
+        else {
+            net_debug!("rpl: no link-local address to send DAOs from");
+            // NOTE(unwrap): the DODAG was found above.
+            self.inner.rpl_dodag_mut().unwrap().dao_at = Some(now + DAO_RETRANSMIT_DELAY);
+            return false;
+        };
+        let Some(tx_token) = device.transmit(now) else {
+            return false;
+        };
+        let ip_repr = Ipv6Repr {
+            src_addr,
+            dst_addr: parent,
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 64,
+            payload_len: dao.buffer_len(),
+        };
+        net_trace!("rpl: sending DAO to {}", parent);
+        let sent = self.inner.dispatch_ip(
+            tx_token,
+            PacketMeta::default(),
+            IpPacket::new_ipv6(ip_repr, IpPayload::Icmpv6(dao)),
+            &mut self.fragmenter,
+        );
+
+        // NOTE(unwrap): the DODAG was found above.
+        let dodag = self.inner.rpl_dodag_mut().unwrap();
+        if sent.is_ok() {
+            dodag.dao_sequence.increment();
+            // Refresh the routes before they expire.
+            dodag.dao_at = Some(now + lifetime / 2);
+        } else {
+            dodag.dao_at = Some(now + DAO_RETRANSMIT_DELAY);
+        }
+        true
+    }
+}
+
+impl InterfaceInner {
+    fn rpl_dodag(&self) -> Option<&Dodag> {
+        self.rpl.as_ref().and_then(|rpl| rpl.dodag.as_ref())
+    }
+
+    fn rpl_dodag_mut(&mut self) -> Option<&mut Dodag> {
+        self.rpl.as_mut().and_then(|rpl| rpl.dodag.as_mut())
+    }
+
+    /// Process a RPL control message (RFC 6550 § 6).
+    pub(super) fn process_rpl<'frame>(
+        &mut self,
+        ip_repr: Ipv6Repr,
+        repr: RplRepr<'frame>,
+    ) -> Option<IpPacket<'frame>> {
+        if !ip_repr.src_addr.is_link_local() || self.has_ip_addr(ip_repr.src_addr) {
+            net_debug!("rpl: ignoring {} from {}", repr, ip_repr.src_addr);
+            return None;
+        }
+
+        match repr {
+            RplRepr::DodagInformationSolicitation { .. } => {
+                // Advertise the DODAG again soon (RFC 6550 § 8.3).
+                if let Some(dodag) = self.rpl.as_mut().and_then(|rpl| rpl.dodag.as_mut()) {
+                    dodag.dio_timer.hear_inconsistency(self.now, &mut self.rand);
+                }
+                None
+            }
+            RplRepr::DodagInformationObject { .. } => {
+                self.process_rpl_dio(ip_repr.src_addr, repr);
+                None
+            }
+            RplRepr::DestinationAdvertisementObject { .. } => self.process_rpl_dao(ip_repr, repr),
+            RplRepr::DestinationAdvertisementObjectAck { .. } => None,
+        }
+    }
+
+    /// Process a DIO, joining its DODAG and selecting a preferred parent (RFC 6550 § 8.2).
+    fn process_rpl_dio(&mut self, src_addr: Ipv6Address, repr: RplRepr) {
+        let RplRepr::DodagInformationObject {
+            rpl_instance_id,
+            version_number,
+            rank,
+            grounded,
+            mode_of_operation,
+            dodag_preference,
+            dtsn,
+            dodag_id,
+            options,
+        } = repr
+        else {
+            return;
+        };
+        let now = self.now;
+        // NOTE(unwrap): RPL control messages are only processed when RPL is enabled.
+        let rpl = self.rpl.as_mut().unwrap();
+        if mode_of_operation != rpl.config.mode_of_operation {
+            net_debug!(
+                "rpl: ignoring DODAG {} with mode of operation {:?}",
+                dodag_id,
+                mode_of_operation
+            );
+            return;
+        }
+        let version_number = SequenceCounter::new(version_number);
+
+        // Join the DODAG, unless already part of one.
+        let join = match &rpl.dodag {
+            None => true,
+            Some(dodag) => {
+                !rpl.config.is_root()
+                    && dodag.parent.is_none()
+                    && (dodag.instance_id != rpl_instance_id || dodag.id != dodag_id)
+            }
+        };
+        if join && rank != Rank::INFINITE.raw_value() {
+            let Some(conf) = DodagConfiguration::parse(options) else {
+                net_debug!("rpl: ignoring DODAG {} of unknown configuration", dodag_id);
+                return;
+            };
+            net_debug!("rpl: joining DODAG {}", dodag_id);
+            rpl.dodag = Some(Dodag {
+                instance_id: rpl_instance_id,
+                id: dodag_id,
+                version_number,
+                grounded,
+                mode_of_operation,
+                preference: dodag_preference,
+                dtsn: SequenceCounter::default(),
+                conf,
+                rank: Rank::new(Rank::INFINITE.raw_value(), conf.min_hop_rank_increase),
+                dio_timer: conf.dio_timer(now, &mut self.rand),
+                parent_set: ParentSet::default(),
+                parent: None,
+                parent_dtsn: None,
+                dao_sequence: SequenceCounter::default(),
+                dao_at: None,
+                relations: Relations::default(),
+            });
+        }
+
+        let Some(dodag) = &mut rpl.dodag else {
+            return;
+        };
+        if dodag.instance_id != rpl_instance_id || dodag.id != dodag_id {
+            return;
+        }
+
+        if rpl.config.is_root() {
+            // Only the root increments the version of its DODAG.
+            if version_number == dodag.version_number {
+                dodag.dio_timer.hear_consistent();
+            } else {
+                dodag.dio_timer.hear_inconsistency(now, &mut self.rand);
+            }
+            return;
+        }
+
+        if version_number > dodag.version_number {
+            // The root rebuilt the DODAG (RFC 6550 § 8.2.2.1).
+            net_debug!("rpl: DODAG {} has a new version", dodag_id);
+            dodag.version_number = version_number;
+            dodag.grounded = grounded;
+            dodag.preference = dodag_preference;
+            dodag.parent_set.clear();
+            dodag.dio_timer.hear_inconsistency(now, &mut self.rand);
+        } else if version_number != dodag.version_number {
+            // The neighbor is behind, let it know of the current version.
+            dodag.dio_timer.hear_inconsistency(now, &mut self.rand);
+            return;
+        }
+
+        if rank == Rank::INFINITE.raw_value() {
+            // The neighbor left the DODAG.
+            dodag.parent_set.remove(&src_addr);
+            return self.rpl_select_parent();
+        }
+        let rank = Rank::new(rank, dodag.conf.min_hop_rank_increase);
+        if dodag.parent.is_none() || rank < dodag.rank {
+            let parent = Parent::new(dodag_preference, rank, version_number, dodag_id);
+            dodag.parent_set.add(src_addr, parent);
+            dodag.dio_timer.hear_consistent();
+        } else {
+            // Only the neighbors closer to the root can be parents.
+            dodag.parent_set.remove(&src_addr);
+        }
+
+        // The preferred parent asks for new DAOs by incrementing its DTSN (RFC 6550 § 9.6).
+        if dodag.parent == Some(src_addr) {
+            let dtsn = SequenceCounter::new(dtsn);
+            if dodag
+                .parent_dtsn
+                .map_or(false, |parent_dtsn| dtsn > parent_dtsn)
+            {
+                dodag.schedule_dao(now);
+            }
+            dodag.parent_dtsn = Some(dtsn);
+        }
+
+        self.rpl_select_parent();
+    }
+
+    /// Select the preferred parent with Objective Function Zero (RFC 6552), and update the
+    /// Rank accordingly.
+    fn rpl_select_parent(&mut self) {
+        let now = self.now;
+        let Some(dodag) = self.rpl.as_mut().and_then(|rpl| rpl.dodag.as_mut()) else {
+            return;
+        };
+
+        let best = ObjectiveFunction0::preferred_parent(&dodag.parent_set)
+            .map(|(addr, parent)| (*addr, *parent.rank()));
+        let current = dodag
+            .parent
+            .and_then(|addr| Some((addr, *dodag.parent_set.find(&addr)?.rank())));
+        // Keep the current parent, unless another one is closer to the root.
+        let parent = match (current, best) {
+            (Some(current), Some(best)) if best.1 >= current.1 => Some(current),
+            (_, best) => best,
+        };
+
+        let parent_addr = parent.map(|(addr, _)| addr);
+        if parent_addr != dodag.parent {
+            match parent_addr {
+                Some(addr) => net_debug!("rpl: preferred parent is now {}", addr),
+                None => net_debug!("rpl: no parent left in DODAG {}", dodag.id),
+            }
+            dodag.parent = parent_addr;
+            dodag.parent_dtsn = None;
+            dodag.dao_at = None;
+            dodag.schedule_dao(now);
+        }
+
+        let infinite = Rank::new(Rank::INFINITE.raw_value(), dodag.conf.min_hop_rank_increase);
+        let rank = parent.map_or(infinite, |(_, rank)| {
+            ObjectiveFunction0::rank(dodag.rank, rank)
+        });
+        if rank.raw_value() != dodag.rank.raw_value() {
+            dodag.rank = rank;
+            dodag.dio_timer.hear_inconsistency(now, &mut self.rand);
+        }
+    }
+
+    /// Process a DAO, learning the downward routes to its targets (RFC 6550 § 9.7).
+    fn process_rpl_dao<'frame>(
+        &mut self,
+        ip_repr: Ipv6Repr,
+        repr: RplRepr<'frame>,
+    ) -> Option<IpPacket<'frame>> {
+        let RplRepr::DestinationAdvertisementObject {
+            rpl_instance_id,
+            expect_ack,
+            sequence,
+            dodag_id,
+            options,
+        } = repr
+        else {
+            return None;
+        };
+        let now = self.now;
+        let src_addr = ip_repr.src_addr;
+        let dodag = self.rpl.as_mut().and_then(|rpl| rpl.dodag.as_mut())?;
+        if dodag.mode_of_operation != RplModeOfOperation::StoringModeWithoutMulticast
+            || dodag.instance_id != rpl_instance_id
+            || dodag_id.map_or(false, |dodag_id| dodag_id != dodag.id)
+        {
+            return None;
+        }
+        if dodag.parent == Some(src_addr) {
+            net_debug!("rpl: ignoring DAO from parent {}", src_addr);
+            return None;
+        }
+
+        // The targets share the lifetime of the Transit Information option after them.
+        let mut path_lifetime = None;
+        for option in RplOptionsIterator::new(options) {
+            if let RplOptionRepr::TransitInformation {
+                path_lifetime: lifetime,
+                ..
+            } = check!(option)
+            {
+                path_lifetime = Some(lifetime);
+                break;
+            }
+        }
+        let Some(path_lifetime) = path_lifetime else {
+            net_debug!("rpl: ignoring DAO without transit information");
+            return None;
+        };
+
+        let expiration = now + dodag.conf.lifetime(path_lifetime);
+        for option in RplOptionsIterator::new(options).flatten() {
+            let RplOptionRepr::RplTarget {
+                prefix_length: 128,
+                prefix: target,
+            } = option
+            else {
+                continue;
+            };
+            if path_lifetime == 0 {
+                net_debug!("rpl: removing route to {}", target);
+                dodag.relations.remove_relation(target);
+            } else {
+                net_trace!("rpl: route to {} via {}", target, src_addr);
+                dodag.relations.add_relation(target, src_addr, expiration);
+            }
+        }
+        // Pass the routes on towards the root.
+        dodag.schedule_dao(now);
+
+        if !expect_ack {
+            return None;
+        }
+        let ack = Icmpv6Repr::Rpl(RplRepr::DestinationAdvertisementObjectAck {
+            rpl_instance_id,
+            sequence,
+            status: 0,
+            dodag_id,
+        });
+        self.icmpv6_reply(ip_repr, ack)
+    }
+
+    /// Forget the downward routes that expired.
+    pub(super) fn rpl_expire(&mut self) {
+        let now = self.now;
+        if let Some(dodag) = self.rpl_dodag_mut() {
+            dodag.relations.purge(now);
+        }
+    }
+
+    /// Return the next hop towards `addr` in the DODAG, if it is a global destination:
+    /// the downward route learned from DAOs if any, or else the preferred parent.
+    pub(super) fn rpl_route(&self, addr: &IpAddress) -> Option<IpAddress> {
+        let addr = match addr {
+            IpAddress::Ipv6(addr) => *addr,
+            #[allow(unreachable_patterns)]
+            _ => return None,
+        };
+        if !addr.is_unicast() || addr.is_link_local() || addr.is_loopback() {
+            return None;
+        }
+        let dodag = self.rpl_dodag()?;
+        dodag
+            .relations
+            .find_next_hop(addr)
+            .or(dodag.parent)
+            .map(Into::into)
+    }
+
+    /// Return whether a packet is to be forwarded along the DODAG, instead of being
+    /// processed.
+    pub(super) fn rpl_forwards(&self, ipv6_repr: &Ipv6Repr) -> bool {
+        let dst_addr = ipv6_repr.dst_addr;
+        self.rpl_dodag().is_some()
+            && dst_addr.is_unicast()
+            && !dst_addr.is_link_local()
+            && !dst_addr.is_loopback()
+            && !self.has_ip_addr(dst_addr)
+            && !self.has_anycast_addr(dst_addr)
+    }
+
+    /// Forward a packet to the next hop towards its destination.
+    pub(super) fn rpl_forward<'frame>(
+        &self,
+        ipv6_repr: Ipv6Repr,
+        ip_payload: &'frame [u8],
+    ) -> Option<IpPacket<'frame>> {
+        if ipv6_repr.hop_limit <= 1 {
+            net_debug!(
+                "rpl: hop limit exceeded, dropping packet to {}",
+                ipv6_repr.dst_addr
+            );
+            return None;
+        }
+        net_trace!("rpl: forwarding packet to {}", ipv6_repr.dst_addr);
+        let ipv6_repr = Ipv6Repr {
+            hop_limit: ipv6_repr.hop_limit - 1,
+            ..ipv6_repr
+        };
+        Some(IpPacket::new_ipv6(ipv6_repr, IpPayload::Raw(ip_payload)))
+    }
+
+    pub(super) fn rpl_poll_at(&self) -> Option<Instant> {
+        let dodag = self.rpl_dodag()?;
+        [
+            Some(dodag.dio_timer.poll_at()),
+            dodag.dao_at,
+            dodag.relations.expiration(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+}
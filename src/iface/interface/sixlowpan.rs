@@ -7,7 +7,73 @@ use crate::wire::{Ipv6Packet as Ipv6PacketWire, *};
 // TODO: lower. Should be (6lowpan mtu) - (min 6lowpan header size) + (max ipv6 header size)
 pub(crate) const MAX_DECOMPRESSED_LEN: usize = 1500;
 
+/// How long an address context learned from a router can be used for compression.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(super) struct ContextLifetime {
+    compression: bool,
+    expires_at: Instant,
+}
+
 impl InterfaceInner {
+    /// Learn an address context from the 6LoWPAN Context option of a Router Advertisement
+    /// (RFC 6775 § 5.4.3).
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub(super) fn process_sixlowpan_context(&mut self, context: Option<NdiscSixlowpanContext>) {
+        let Some(context) = context else {
+            return;
+        };
+
+        // Only the first 64 bits of a prefix are kept in a context, and new contexts can only
+        // be added after the ones already known.
+        let id = context.context_id as usize;
+        if context.context_len > 64
+            || id > self.sixlowpan_address_context.len()
+            || id >= self.sixlowpan_context_lifetimes.len()
+        {
+            net_debug!("ignoring 6LoWPAN context {}", context.context_id);
+            return;
+        }
+
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&context.prefix.as_bytes()[..8]);
+        let address_context = SixlowpanAddressContext(bytes);
+        if id == self.sixlowpan_address_context.len() {
+            // Cannot fail, the number of contexts is below the number of lifetimes.
+            let _ = self.sixlowpan_address_context.push(address_context);
+        } else {
+            self.sixlowpan_address_context[id] = address_context;
+        }
+
+        // An expired context is still used to decompress the addresses of packets sent before
+        // the router withdrew it.
+        self.sixlowpan_context_lifetimes[id] = Some(ContextLifetime {
+            compression: context.compression,
+            expires_at: self.now + context.valid_lifetime,
+        });
+    }
+
+    /// Return the identifier of the context the prefix of an address can be compressed with.
+    pub(super) fn sixlowpan_compression_context(&self, addr: &Ipv6Address) -> Option<u8> {
+        if addr.is_link_local() || addr.is_multicast() || addr.is_unspecified() {
+            return None;
+        }
+
+        self.sixlowpan_address_context
+            .iter()
+            .enumerate()
+            .find(|(id, context)| {
+                context.0 == addr.as_bytes()[..8]
+                    && match self.sixlowpan_context_lifetimes.get(*id) {
+                        Some(Some(lifetime)) => {
+                            lifetime.compression && lifetime.expires_at > self.now
+                        }
+                        _ => true,
+                    }
+            })
+            .map(|(id, _)| id as u8)
+    }
+
     pub(super) fn process_sixlowpan<'output, 'payload: 'output>(
         &mut self,
         sockets: &mut SocketSet,
@@ -17,6 +83,12 @@ impl InterfaceInner {
         f: &'output mut FragmentsBuffer,
     ) -> Option<IpPacket<'output>> {
         let payload = match check!(SixlowpanPacket::dispatch(payload)) {
+            #[cfg(feature = "medium-ieee802154")]
+            SixlowpanPacket::MeshHeader => {
+                return self.process_sixlowpan_mesh(sockets, meta, ieee802154_repr, payload, f);
+            }
+            #[cfg(not(feature = "medium-ieee802154"))]
+            SixlowpanPacket::MeshHeader => return None,
             #[cfg(not(feature = "proto-sixlowpan-fragmentation"))]
             SixlowpanPacket::FragmentHeader => {
                 net_debug!(
@@ -66,7 +138,18 @@ impl InterfaceInner {
 
         // The key specifies to which 6LoWPAN fragment it belongs too.
         // It is based on the link layer addresses, the tag and the size.
-        let key = FragKey::Sixlowpan(frag.get_key(ieee802154_repr));
+        let sixlowpan_key = frag.get_key(ieee802154_repr);
+        let key = FragKey::Sixlowpan(sixlowpan_key);
+
+        // Packets from a source only get a new assembler while it is below its limit.
+        let ll_src_addr = sixlowpan_key.ll_src_addr;
+        let from_source = f
+            .assembler
+            .count(|key| matches!(key, FragKey::Sixlowpan(key) if key.ll_src_addr == ll_src_addr));
+        if from_source >= f.sixlowpan_reassembly_limit && f.assembler.count(|k| *k == key) == 0 {
+            net_debug!("6LoWPAN: too many fragmented packets from {}", ll_src_addr);
+            return None;
+        }
 
         // The offset of this fragment in increments of 8 octets.
         let offset = frag.datagram_offset() as usize * 8;
@@ -232,6 +315,7 @@ impl InterfaceInner {
         meta: PacketMeta,
         packet: IpPacket,
         ieee_repr: Ieee802154Repr,
+        mesh_repr: Option<SixlowpanMeshRepr>,
         frag: &mut Fragmenter,
     ) {
         let ip_repr = packet.ip_repr();
@@ -244,18 +328,33 @@ impl InterfaceInner {
             }
         };
 
+        // With a mesh header, addresses are compressed against the addresses of the originator
+        // and of the final destination instead of the ones of the frame.
+        let (ll_src_addr, ll_dst_addr) = match mesh_repr {
+            Some(mesh_repr) => (Some(mesh_repr.originator_addr), Some(mesh_repr.final_addr)),
+            None => (ieee_repr.src_addr, ieee_repr.dst_addr),
+        };
+
         // Create the 6LoWPAN IPHC header.
         let iphc_repr = SixlowpanIphcRepr {
             src_addr,
-            ll_src_addr: ieee_repr.src_addr,
+            ll_src_addr,
             dst_addr,
-            ll_dst_addr: ieee_repr.dst_addr,
+            ll_dst_addr,
+            src_context_id: self.sixlowpan_compression_context(&src_addr),
+            dst_context_id: self.sixlowpan_compression_context(&dst_addr),
             next_header: match &packet.payload() {
                 IpPayload::Icmpv6(..) => SixlowpanNextHeader::Uncompressed(IpProtocol::Icmpv6),
                 #[cfg(feature = "socket-tcp")]
                 IpPayload::Tcp(..) => SixlowpanNextHeader::Uncompressed(IpProtocol::Tcp),
                 #[cfg(feature = "socket-udp")]
                 IpPayload::Udp(..) => SixlowpanNextHeader::Compressed,
+                #[cfg(any(
+                    feature = "socket-raw",
+                    feature = "proto-rpl",
+                    feature = "proto-ipv4-fragmentation"
+                ))]
+                IpPayload::Raw(..) => SixlowpanNextHeader::Uncompressed(ip_repr.next_header()),
                 #[allow(unreachable_patterns)]
                 _ => {
                     net_debug!("dispatch_ieee802154: dropping, unhandled protocol.");
@@ -291,11 +390,20 @@ impl InterfaceInner {
             IpPayload::Icmpv6(icmp_repr) => {
                 total_size += icmp_repr.buffer_len();
             }
+            #[cfg(any(
+                feature = "socket-raw",
+                feature = "proto-rpl",
+                feature = "proto-ipv4-fragmentation"
+            ))]
+            IpPayload::Raw(raw) => {
+                total_size += raw.len();
+            }
             #[allow(unreachable_patterns)]
             _ => unreachable!(),
         }
 
-        let ieee_len = ieee_repr.buffer_len();
+        let mesh_len = mesh_repr.map_or(0, |mesh_repr| mesh_repr.buffer_len());
+        let ieee_len = ieee_repr.buffer_len() + mesh_len;
 
         if total_size + ieee_len > 125 {
             #[cfg(feature = "proto-sixlowpan-fragmentation")]
@@ -321,6 +429,7 @@ impl InterfaceInner {
 
                 pkt.sixlowpan.ll_dst_addr = ieee_repr.dst_addr.unwrap();
                 pkt.sixlowpan.ll_src_addr = ieee_repr.src_addr.unwrap();
+                pkt.sixlowpan.mesh_repr = mesh_repr;
 
                 let mut iphc_packet =
                     SixlowpanIphcPacket::new_unchecked(&mut pkt.buffer[..iphc_repr.buffer_len()]);
@@ -365,6 +474,12 @@ impl InterfaceInner {
                             &self.caps.checksum,
                         );
                     }
+                    #[cfg(any(
+                        feature = "socket-raw",
+                        feature = "proto-rpl",
+                        feature = "proto-ipv4-fragmentation"
+                    ))]
+                    IpPayload::Raw(raw) => b[..raw.len()].copy_from_slice(raw),
                     #[allow(unreachable_patterns)]
                     _ => unreachable!(),
                 }
@@ -407,11 +522,11 @@ impl InterfaceInner {
                 pkt.sent_bytes = frag1_size;
                 pkt.sixlowpan.datagram_offset = frag1_size + header_diff;
 
+                self.stats
+                    .count_tx(ieee_len + frag1.buffer_len() + frag1_size);
                 tx_token.consume(ieee_len + frag1.buffer_len() + frag1_size, |mut tx_buf| {
-                    // Add the IEEE header.
-                    let mut ieee_packet = Ieee802154Frame::new_unchecked(&mut tx_buf[..ieee_len]);
-                    ieee_repr.emit(&mut ieee_packet);
-                    tx_buf = &mut tx_buf[ieee_len..];
+                    // Add the IEEE header, and the mesh header.
+                    tx_buf = emit_ieee802154_header(tx_buf, &ieee_repr, mesh_repr);
 
                     // Add the first fragment header
                     let mut frag1_packet = SixlowpanFragPacket::new_unchecked(&mut tx_buf);
@@ -434,10 +549,9 @@ impl InterfaceInner {
             tx_token.set_meta(meta);
 
             // We don't need fragmentation, so we emit everything to the TX token.
+            self.stats.count_tx(total_size + ieee_len);
             tx_token.consume(total_size + ieee_len, |mut tx_buf| {
-                let mut ieee_packet = Ieee802154Frame::new_unchecked(&mut tx_buf[..ieee_len]);
-                ieee_repr.emit(&mut ieee_packet);
-                tx_buf = &mut tx_buf[ieee_len..];
+                tx_buf = emit_ieee802154_header(tx_buf, &ieee_repr, mesh_repr);
 
                 let mut iphc_packet =
                     SixlowpanIphcPacket::new_unchecked(&mut tx_buf[..iphc_repr.buffer_len()]);
@@ -481,6 +595,12 @@ impl InterfaceInner {
                             &self.caps.checksum,
                         );
                     }
+                    #[cfg(any(
+                        feature = "socket-raw",
+                        feature = "proto-rpl",
+                        feature = "proto-ipv4-fragmentation"
+                    ))]
+                    IpPayload::Raw(raw) => tx_buf[..raw.len()].copy_from_slice(raw),
                     #[allow(unreachable_patterns)]
                     _ => unreachable!(),
                 }
@@ -502,27 +622,46 @@ impl InterfaceInner {
             offset: (frag.sixlowpan.datagram_offset / 8) as u8,
         };
 
-        let ieee_len = ieee_repr.buffer_len();
+        let mesh_repr = frag.sixlowpan.mesh_repr;
+        let mesh_len = mesh_repr.map_or(0, |mesh_repr| mesh_repr.buffer_len());
+        let ieee_len = ieee_repr.buffer_len() + mesh_len;
         let frag_size = (frag.packet_len - frag.sent_bytes).min(frag.sixlowpan.fragn_size);
 
-        tx_token.consume(
-            ieee_repr.buffer_len() + fragn.buffer_len() + frag_size,
-            |mut tx_buf| {
-                let mut ieee_packet = Ieee802154Frame::new_unchecked(&mut tx_buf[..ieee_len]);
-                ieee_repr.emit(&mut ieee_packet);
-                tx_buf = &mut tx_buf[ieee_len..];
+        self.stats
+            .count_tx(ieee_len + fragn.buffer_len() + frag_size);
+        tx_token.consume(ieee_len + fragn.buffer_len() + frag_size, |mut tx_buf| {
+            tx_buf = emit_ieee802154_header(tx_buf, &ieee_repr, mesh_repr);
 
-                let mut frag_packet =
-                    SixlowpanFragPacket::new_unchecked(&mut tx_buf[..fragn.buffer_len()]);
-                fragn.emit(&mut frag_packet);
-                tx_buf = &mut tx_buf[fragn.buffer_len()..];
+            let mut frag_packet =
+                SixlowpanFragPacket::new_unchecked(&mut tx_buf[..fragn.buffer_len()]);
+            fragn.emit(&mut frag_packet);
+            tx_buf = &mut tx_buf[fragn.buffer_len()..];
 
-                // Add the buffer part
-                tx_buf[..frag_size].copy_from_slice(&frag.buffer[frag.sent_bytes..][..frag_size]);
+            // Add the buffer part
+            tx_buf[..frag_size].copy_from_slice(&frag.buffer[frag.sent_bytes..][..frag_size]);
 
-                frag.sent_bytes += frag_size;
-                frag.sixlowpan.datagram_offset += frag_size;
-            },
-        );
+            frag.sent_bytes += frag_size;
+            frag.sixlowpan.datagram_offset += frag_size;
+        });
+    }
+}
+
+/// Emit the IEEE802.15.4 header of a frame, followed by its mesh header, and return the rest of
+/// the buffer.
+fn emit_ieee802154_header<'a>(
+    tx_buf: &'a mut [u8],
+    ieee_repr: &Ieee802154Repr,
+    mesh_repr: Option<SixlowpanMeshRepr>,
+) -> &'a mut [u8] {
+    let ieee_len = ieee_repr.buffer_len();
+    ieee_repr.emit(&mut Ieee802154Frame::new_unchecked(&mut tx_buf[..ieee_len]));
+    let tx_buf = &mut tx_buf[ieee_len..];
+
+    match mesh_repr {
+        Some(mesh_repr) => {
+            mesh_repr.emit(&mut SixlowpanMeshPacket::new_unchecked(&mut tx_buf[..]));
+            &mut tx_buf[mesh_repr.buffer_len()..]
+        }
+        None => tx_buf,
     }
 }
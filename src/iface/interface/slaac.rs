@@ -0,0 +1,515 @@
+use heapless::Vec;
+
+use super::dad::RETRANS_TIMER;
+use super::{Interface, InterfaceInner};
+use crate::config::IFACE_MAX_ADDR_COUNT;
+use crate::time::{Duration, Instant};
+use crate::wire::*;
+
+/// Lifetime that Router Advertisements use for "forever".
+const INFINITE_LIFETIME: Duration = Duration::from_secs(0xffff_ffff);
+
+/// Remaining lifetime below which an unauthenticated Router Advertisement can no longer
+/// shorten the lifetime of an address (RFC 4862 § 5.5.3 (e)).
+const MIN_VALID_LIFETIME: Duration = Duration::from_secs(2 * 60 * 60);
+
+/// Length of the prefixes we autoconfigure addresses from; the remaining 64 bits hold
+/// the interface identifier.
+const PREFIX_LEN: u8 = 64;
+
+/// Maximum valid lifetime of temporary addresses (RFC 8981 § 3.8).
+const TEMP_VALID_LIFETIME: Duration = Duration::from_secs(2 * 24 * 60 * 60);
+
+/// Maximum preferred lifetime of temporary addresses (RFC 8981 § 3.8).
+const TEMP_PREFERRED_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Upper bound of the random amount the preferred lifetime of temporary addresses is
+/// shortened by, so that the nodes of a link don't all regenerate them at once.
+const MAX_DESYNC_FACTOR: Duration = Duration::from_secs(24 * 60 * 60 * 2 / 5);
+
+/// Number of attempts at generating a random interface identifier that is not reserved.
+const TEMP_IDGEN_RETRIES: u8 = 3;
+
+/// An address configured from a Router Advertisement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SlaacAddress {
+    cidr: Ipv6Cidr,
+    /// `None` means "forever".
+    valid_until: Option<Instant>,
+    /// The address is deprecated afterwards. `None` means "forever".
+    preferred_until: Option<Instant>,
+    /// For temporary addresses, the upper bounds of their lifetimes.
+    temporary: Option<TemporaryBounds>,
+}
+
+/// Lifetimes that a temporary address can't exceed, whatever its prefix advertises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TemporaryBounds {
+    valid_until: Instant,
+    preferred_until: Instant,
+}
+
+impl TemporaryBounds {
+    /// Set the lifetimes of `temporary` to those of its `prefix`, within the bounds.
+    fn apply(&self, temporary: &mut SlaacAddress, prefix: &SlaacAddress) {
+        temporary.valid_until = Some(
+            prefix
+                .valid_until
+                .map_or(self.valid_until, |t| t.min(self.valid_until)),
+        );
+        temporary.preferred_until = Some(
+            prefix
+                .preferred_until
+                .map_or(self.preferred_until, |t| t.min(self.preferred_until)),
+        );
+    }
+}
+
+/// State of IPv6 stateless address autoconfiguration (RFC 4862).
+#[derive(Debug, Default)]
+pub(crate) struct Slaac {
+    enabled: bool,
+    temporary: bool,
+    interface_id: Option<[u8; 8]>,
+    addrs: Vec<SlaacAddress, IFACE_MAX_ADDR_COUNT>,
+}
+
+fn deadline(now: Instant, lifetime: Duration) -> Option<Instant> {
+    (lifetime != INFINITE_LIFETIME).then(|| now + lifetime)
+}
+
+fn remaining(now: Instant, deadline: Option<Instant>) -> Option<Duration> {
+    deadline.map(|t| if t > now { t - now } else { Duration::ZERO })
+}
+
+fn same_prefix(a: Ipv6Address, b: Ipv6Address) -> bool {
+    a.as_bytes()[..8] == b.as_bytes()[..8]
+}
+
+/// Whether `iid` is a reserved interface identifier (RFC 5453), which temporary addresses
+/// must not use.
+fn is_reserved_iid(iid: &[u8]) -> bool {
+    iid == [0; 8]
+        || iid[..4] == [0x02, 0x00, 0x5e, 0xfe]
+        || (iid[..7] == [0xfd, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff] && iid[7] >= 0x80)
+}
+
+impl Interface {
+    /// Get whether IPv6 stateless address autoconfiguration is enabled.
+    pub fn slaac(&self) -> bool {
+        self.inner.slaac.enabled
+    }
+
+    /// Enable or disable IPv6 stateless address autoconfiguration.
+    ///
+    /// When enabled, Router Advertisements configure an address for every autonomous
+    /// /64 prefix they carry, and add their sender to the default routers, see
+    /// [`ipv6_default_routers`](Self::ipv6_default_routers). Prefixes that are not
+    /// advertised as on-link get a /128 address, so that their traffic goes through the
+    /// routers. Addresses and routers are removed once their advertised lifetimes run
+    /// out; a static default route always takes precedence.
+    ///
+    /// Enabling autoconfiguration solicits Router Advertisements right away, see
+    /// [`solicit_routers`](Self::solicit_routers).
+    ///
+    /// New addresses go through Duplicate Address Detection if it is enabled, see
+    /// [`set_dad_transmits`](Self::set_dad_transmits). Disabling autoconfiguration keeps
+    /// the addresses and routes configured so far until they expire.
+    ///
+    /// Disabled by default.
+    pub fn set_slaac(&mut self, enabled: bool) {
+        if enabled && !self.inner.slaac.enabled {
+            self.inner.router_solicit_start();
+        }
+        self.inner.slaac.enabled = enabled;
+    }
+
+    /// Get the interface identifier of autoconfigured addresses, if set.
+    pub fn slaac_interface_id(&self) -> Option<[u8; 8]> {
+        self.inner.slaac.interface_id
+    }
+
+    /// Set the interface identifier of autoconfigured addresses.
+    ///
+    /// By default, or with `None`, it is derived from the hardware address (modified EUI-64).
+    /// Another identifier, e.g. a random one, can be picked when Duplicate Address Detection
+    /// reports a conflict; it applies from the next Router Advertisement.
+    pub fn set_slaac_interface_id(&mut self, interface_id: Option<[u8; 8]>) {
+        self.inner.slaac.interface_id = interface_id;
+    }
+
+    /// Get whether temporary addresses are generated by stateless autoconfiguration.
+    pub fn slaac_temporary_addrs(&self) -> bool {
+        self.inner.slaac.temporary
+    }
+
+    /// Enable or disable temporary addresses (RFC 8981).
+    ///
+    /// When enabled, stateless autoconfiguration also generates an address with a random
+    /// interface identifier for every prefix, and outgoing traffic to non link-local
+    /// destinations uses it as source address, so that the address derived from the
+    /// hardware address can't be used to track the device.
+    ///
+    /// Temporary addresses are valid for two days at most, and preferred for one day minus
+    /// a random amount; a new one is generated a few seconds before the current one is
+    /// deprecated. Deprecated addresses are kept for existing connections until they
+    /// expire, unless the address table is full, in which case they are replaced.
+    ///
+    /// Disabled by default.
+    pub fn set_slaac_temporary_addrs(&mut self, enabled: bool) {
+        self.inner.slaac.temporary = enabled;
+    }
+}
+
+impl InterfaceInner {
+    pub(super) fn process_router_advert(
+        &mut self,
+        router: Ipv6Address,
+        router_lifetime: Duration,
+        prefix_info: Option<NdiscPrefixInformation>,
+    ) {
+        if !self.slaac.enabled {
+            return;
+        }
+
+        self.default_router_update(router, router_lifetime);
+        if let Some(prefix_info) = prefix_info {
+            self.slaac_update_prefix(&prefix_info);
+            self.slaac_update_temporary();
+        }
+    }
+
+    fn slaac_update_prefix(&mut self, info: &NdiscPrefixInformation) {
+        // RFC 4862 § 5.5.3 (a) - (c).
+        if !info.flags.contains(NdiscPrefixInfoFlags::ADDRCONF)
+            || info.prefix.is_link_local()
+            || info.preferred_lifetime > info.valid_lifetime
+        {
+            return;
+        }
+        if info.prefix_len != PREFIX_LEN {
+            net_debug!(
+                "slaac: ignoring prefix {}/{} of unsupported length",
+                info.prefix,
+                info.prefix_len
+            );
+            return;
+        }
+        let Some(iid) = self.interface_identifier() else {
+            return;
+        };
+
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&info.prefix.as_bytes()[..8]);
+        bytes[8..].copy_from_slice(&iid);
+        let address = Ipv6Address::from_bytes(&bytes);
+        if !address.is_unicast() {
+            return;
+        }
+
+        // Destinations in an off-link prefix are reached through the routers.
+        let prefix_len = if info.flags.contains(NdiscPrefixInfoFlags::ON_LINK) {
+            PREFIX_LEN
+        } else {
+            128
+        };
+        let cidr = Ipv6Cidr::new(address, prefix_len);
+        let now = self.now;
+
+        if let Some(entry) = self
+            .slaac
+            .addrs
+            .iter_mut()
+            .find(|a| a.cidr.address() == address)
+        {
+            // RFC 4862 § 5.5.3 (e): don't let a forged advertisement expire the address.
+            let remaining = remaining(now, entry.valid_until);
+            if info.valid_lifetime > MIN_VALID_LIFETIME
+                || remaining.map_or(false, |r| info.valid_lifetime > r)
+            {
+                entry.valid_until = deadline(now, info.valid_lifetime);
+            } else if remaining.map_or(true, |r| r > MIN_VALID_LIFETIME) {
+                entry.valid_until = Some(now + MIN_VALID_LIFETIME);
+            }
+            entry.preferred_until = deadline(now, info.preferred_lifetime);
+            entry.cidr = cidr;
+
+            if let Some(addr) = self
+                .ip_addrs
+                .iter_mut()
+                .find(|addr| addr.address() == address.into())
+            {
+                *addr = IpCidr::Ipv6(cidr);
+            } else if let Some(tentative) = self.dad_tentative_mut(address) {
+                *tentative = cidr;
+            }
+            return;
+        }
+
+        if info.valid_lifetime == Duration::ZERO
+            || self.has_ip_addr(address)
+            || self.dad_tentative_mut(address).is_some()
+        {
+            return;
+        }
+        if self.slaac.addrs.is_full() || self.dad_start(cidr).is_err() {
+            net_debug!("slaac: address table full, ignoring {}", cidr);
+            return;
+        }
+        net_debug!("slaac: configured address {}", cidr);
+        self.slaac
+            .addrs
+            .push(SlaacAddress {
+                cidr,
+                valid_until: deadline(now, info.valid_lifetime),
+                preferred_until: deadline(now, info.preferred_lifetime),
+                temporary: None,
+            })
+            .unwrap();
+    }
+
+    /// Keep the temporary addresses within the lifetimes of their prefix, and generate a
+    /// new one for every prefix whose temporary addresses are about to be deprecated
+    /// (RFC 8981 § 3.4 and § 3.5).
+    fn slaac_update_temporary(&mut self) {
+        let now = self.now;
+        let stable: Vec<SlaacAddress, IFACE_MAX_ADDR_COUNT> = self
+            .slaac
+            .addrs
+            .iter()
+            .filter(|a| a.temporary.is_none())
+            .copied()
+            .collect();
+
+        for entry in self.slaac.addrs.iter_mut() {
+            let Some(bounds) = entry.temporary else {
+                continue;
+            };
+            let Some(prefix) = stable
+                .iter()
+                .find(|p| same_prefix(p.cidr.address(), entry.cidr.address()))
+            else {
+                continue;
+            };
+            bounds.apply(entry, prefix);
+        }
+
+        if !self.slaac.enabled || !self.slaac.temporary {
+            return;
+        }
+        let regen_advance = self.regen_advance();
+        for prefix in stable {
+            // A temporary address must be preferred for longer than it takes to replace it.
+            if remaining(now, prefix.preferred_until).map_or(false, |r| r <= regen_advance) {
+                continue;
+            }
+            let current = self.slaac.addrs.iter().any(|a| {
+                a.temporary.is_some()
+                    && same_prefix(a.cidr.address(), prefix.cidr.address())
+                    && remaining(now, a.preferred_until).map_or(true, |r| r > regen_advance)
+            });
+            if !current {
+                self.slaac_generate_temporary(prefix);
+            }
+        }
+    }
+
+    fn slaac_generate_temporary(&mut self, prefix: SlaacAddress) {
+        let now = self.now;
+        let Some(address) = self.temporary_address(prefix.cidr.address()) else {
+            return;
+        };
+        let cidr = Ipv6Cidr::new(address, prefix.cidr.prefix_len());
+
+        if self.slaac.addrs.is_full() || self.dad_start(cidr).is_err() {
+            // Make room by replacing a deprecated temporary address of the same prefix.
+            let Some(index) = self.slaac.addrs.iter().position(|a| {
+                a.temporary.is_some()
+                    && same_prefix(a.cidr.address(), address)
+                    && a.preferred_until.map_or(false, |t| t <= now)
+            }) else {
+                return;
+            };
+            self.slaac_remove(index);
+            if self.dad_start(cidr).is_err() {
+                return;
+            }
+        }
+
+        let desync =
+            Duration::from_secs(u64::from(self.rand.rand_u32()) % MAX_DESYNC_FACTOR.secs());
+        let bounds = TemporaryBounds {
+            valid_until: now + TEMP_VALID_LIFETIME,
+            preferred_until: now + TEMP_PREFERRED_LIFETIME - desync,
+        };
+        let mut entry = SlaacAddress {
+            cidr,
+            valid_until: None,
+            preferred_until: None,
+            temporary: Some(bounds),
+        };
+        bounds.apply(&mut entry, &prefix);
+        net_debug!("slaac: configured temporary address {}", cidr);
+        // NOTE(unwrap): there is room, see above.
+        self.slaac.addrs.push(entry).unwrap();
+    }
+
+    /// Generate an address with a random interface identifier in the /64 `prefix`.
+    fn temporary_address(&mut self, prefix: Ipv6Address) -> Option<Ipv6Address> {
+        for _ in 0..TEMP_IDGEN_RETRIES {
+            let mut bytes = [0; 16];
+            bytes[..8].copy_from_slice(&prefix.as_bytes()[..8]);
+            bytes[8..12].copy_from_slice(&self.rand.rand_u32().to_be_bytes());
+            bytes[12..].copy_from_slice(&self.rand.rand_u32().to_be_bytes());
+            let address = Ipv6Address::from_bytes(&bytes);
+            if !is_reserved_iid(&bytes[8..])
+                && !self.has_ip_addr(address)
+                && self.dad_tentative_mut(address).is_none()
+            {
+                return Some(address);
+            }
+        }
+        None
+    }
+
+    /// How long before a temporary address is deprecated its replacement is generated,
+    /// leaving time for Duplicate Address Detection (RFC 8981 § 3.8, REGEN_ADVANCE).
+    fn regen_advance(&self) -> Duration {
+        Duration::from_secs(2)
+            + RETRANS_TIMER * (u32::from(TEMP_IDGEN_RETRIES) * u32::from(self.dad.transmits))
+    }
+
+    /// Whether `addr` is an autoconfigured address whose preferred lifetime ran out.
+    pub(super) fn slaac_deprecated(&self, addr: Ipv6Address) -> bool {
+        self.slaac
+            .addrs
+            .iter()
+            .any(|a| a.cidr.address() == addr && a.preferred_until.map_or(false, |t| t <= self.now))
+    }
+
+    /// Whether `addr` is a temporary address, and those are enabled.
+    pub(super) fn slaac_temporary(&self, addr: Ipv6Address) -> bool {
+        self.slaac.temporary
+            && self
+                .slaac
+                .addrs
+                .iter()
+                .any(|a| a.cidr.address() == addr && a.temporary.is_some())
+    }
+
+    /// Remove the autoconfigured addresses whose valid lifetime ran out, and replace the
+    /// temporary addresses about to be deprecated.
+    pub(super) fn slaac_expire(&mut self) {
+        let now = self.now;
+        let mut index = 0;
+        while index < self.slaac.addrs.len() {
+            let entry = self.slaac.addrs[index];
+            let address = entry.cidr.address();
+            if entry.valid_until.map_or(false, |t| t <= now) {
+                net_debug!("slaac: address {} expired", address);
+                self.slaac_remove(index);
+            } else if !self.has_ip_addr(address) && self.dad_tentative_mut(address).is_none() {
+                // The address was removed by hand, or used by another node.
+                self.slaac.addrs.swap_remove(index);
+            } else {
+                index += 1;
+            }
+        }
+        self.slaac_update_temporary();
+    }
+
+    fn slaac_remove(&mut self, index: usize) {
+        let entry = self.slaac.addrs.swap_remove(index);
+        let address = entry.cidr.address();
+        self.ip_addrs
+            .retain(|addr| addr.address() != address.into());
+        self.dad_cancel(address);
+        self.addrs_changed();
+    }
+
+    pub(super) fn slaac_poll_at(&self) -> Option<Instant> {
+        let now = self.now;
+        let regen_advance = self.regen_advance();
+        self.slaac
+            .addrs
+            .iter()
+            .flat_map(|entry| {
+                // Temporary addresses are replaced before being deprecated, or when the
+                // address table was full.
+                let (regen_at, deprecated_at) = match entry.preferred_until {
+                    Some(t) if entry.temporary.is_some() && self.slaac.temporary => {
+                        (Some(t - regen_advance), Some(t))
+                    }
+                    _ => (None, None),
+                };
+                [
+                    entry.valid_until,
+                    regen_at.filter(|&t| t > now),
+                    deprecated_at.filter(|&t| t > now),
+                ]
+            })
+            .flatten()
+            .min()
+    }
+
+    /// The interface identifier of autoconfigured addresses, by default the modified EUI-64
+    /// derived from our hardware address.
+    fn interface_identifier(&self) -> Option<[u8; 8]> {
+        if let Some(interface_id) = self.slaac.interface_id {
+            return Some(interface_id);
+        }
+        eui_64(self.hardware_addr)
+    }
+
+    /// Replace the interface identifier derived from the previous hardware address `old`
+    /// with the one derived from the current hardware address, in the IPv6 addresses that
+    /// use it, e.g. the link-local one. The new addresses keep the prefix and lifetimes of
+    /// the ones they replace, and go through Duplicate Address Detection if it is enabled.
+    pub(super) fn renumber_interface_id(&mut self, old: HardwareAddress) {
+        let (Some(old_iid), Some(new_iid)) = (eui_64(old), eui_64(self.hardware_addr)) else {
+            return;
+        };
+        if old_iid == new_iid {
+            return;
+        }
+
+        let assigned = self.dad_merge();
+        for index in 0..self.ip_addrs.len() {
+            #[allow(irrefutable_let_patterns)] // if only ipv6 is enabled
+            let IpCidr::Ipv6(cidr) = self.ip_addrs[index] else {
+                continue;
+            };
+            let address = cidr.address();
+            if address.as_bytes()[8..] != old_iid {
+                continue;
+            }
+            let mut bytes = [0; 16];
+            bytes[..8].copy_from_slice(&address.as_bytes()[..8]);
+            bytes[8..].copy_from_slice(&new_iid);
+            let new_cidr = Ipv6Cidr::new(Ipv6Address::from_bytes(&bytes), cidr.prefix_len());
+            net_debug!("iface: renumbering {} to {}", cidr, new_cidr);
+
+            self.ip_addrs[index] = IpCidr::Ipv6(new_cidr);
+            self.addr_lifetime_rename(address.into(), new_cidr.address().into());
+            self.addrs_changed();
+            for entry in self.slaac.addrs.iter_mut() {
+                if entry.cidr == cidr {
+                    entry.cidr = new_cidr;
+                }
+            }
+        }
+        self.dad_split(&assigned);
+    }
+}
+
+/// The modified EUI-64 interface identifier derived from a hardware address, if any.
+fn eui_64(addr: HardwareAddress) -> Option<[u8; 8]> {
+    match addr {
+        #[cfg(feature = "medium-ethernet")]
+        HardwareAddress::Ethernet(addr) => Some(addr.as_eui_64()),
+        #[cfg(feature = "medium-ieee802154")]
+        HardwareAddress::Ieee802154(addr) => addr.as_eui_64(),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
@@ -168,6 +168,60 @@ fn test_local_subnet_broadcasts(#[case] medium: Medium) {
         .is_broadcast_v4(Ipv4Address([192, 255, 255, 255])));
 }
 
+#[test]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-udp"))]
+fn test_wake_on_lan_udp() {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    static WOKEN: AtomicBool = AtomicBool::new(false);
+
+    let (mut iface, mut sockets, _device) = setup(Medium::Ethernet);
+    iface.set_wake_callback(Some(|_password| WOKEN.store(true, Ordering::Relaxed)));
+
+    let wol_repr = WolRepr {
+        target_addr: iface.hardware_addr().ethernet_or_panic(),
+        password: &[],
+    };
+    let mut payload = vec![0; wol_repr.buffer_len()];
+    wol_repr.emit(&mut WolPacket::new_unchecked(&mut payload));
+    let udp_repr = UdpRepr {
+        src_port: 49152,
+        dst_port: WOL_PORT,
+    };
+    let ip_repr = IpRepr::Ipv4(Ipv4Repr {
+        src_addr: Ipv4Address([0x7f, 0x00, 0x00, 0x02]),
+        dst_addr: Ipv4Address([0x7f, 0x00, 0x00, 0x01]),
+        next_header: IpProtocol::Udp,
+        payload_len: udp_repr.header_len() + payload.len(),
+        hop_limit: 64,
+    });
+    let mut bytes = vec![0; udp_repr.header_len() + payload.len()];
+    udp_repr.emit(
+        &mut UdpPacket::new_unchecked(&mut bytes),
+        &ip_repr.src_addr(),
+        &ip_repr.dst_addr(),
+        payload.len(),
+        |buf| buf.copy_from_slice(&payload),
+        &ChecksumCapabilities::default(),
+    );
+
+    // No port unreachable error is sent back for a magic packet, even without a socket.
+    assert_eq!(
+        iface.inner.process_udp(
+            &mut sockets,
+            PacketMeta::default(),
+            ip_repr,
+            udp_repr,
+            false,
+            false,
+            &payload,
+            &bytes
+        ),
+        None
+    );
+    assert!(WOKEN.load(Ordering::Relaxed));
+}
+
 #[rstest]
 #[case(Medium::Ip)]
 #[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
@@ -242,6 +296,7 @@ fn test_icmp_error_port_unreachable(#[case] medium: Medium) {
             ip_repr,
             udp_repr,
             false,
+            false,
             &UDP_PAYLOAD,
             data
         ),
@@ -276,6 +331,7 @@ fn test_icmp_error_port_unreachable(#[case] medium: Medium) {
             ip_repr,
             udp_repr,
             false,
+            false,
             &UDP_PAYLOAD,
             packet_broadcast.into_inner(),
         ),
@@ -405,6 +461,7 @@ fn test_handle_valid_arp_request(#[case] medium: Medium) {
             MockTxToken,
             &IpAddress::Ipv4(local_ip_addr),
             &IpAddress::Ipv4(remote_ip_addr),
+            &RouteKey::default(),
             &mut iface.fragmenter,
         ),
         Ok((HardwareAddress::Ethernet(remote_hw_addr), MockTxToken))
@@ -454,6 +511,7 @@ fn test_handle_other_arp_request(#[case] medium: Medium) {
             MockTxToken,
             &IpAddress::Ipv4(Ipv4Address([0x7f, 0x00, 0x00, 0x01])),
             &IpAddress::Ipv4(remote_ip_addr),
+            &RouteKey::default(),
             &mut iface.fragmenter,
         ),
         Err(DispatchError::NeighborPending)
@@ -513,6 +571,7 @@ fn test_arp_flush_after_update_ip(#[case] medium: Medium) {
             MockTxToken,
             &IpAddress::Ipv4(local_ip_addr),
             &IpAddress::Ipv4(remote_ip_addr),
+            &RouteKey::default(),
             &mut iface.fragmenter,
         ),
         Ok((HardwareAddress::Ethernet(remote_hw_addr), MockTxToken))
@@ -609,13 +668,41 @@ fn test_icmpv4_socket(#[case] medium: Medium) {
     );
 }
 
+#[cfg(all(
+    feature = "proto-igmp",
+    feature = "medium-ip",
+    any(feature = "medium-ethernet", feature = "socket-udp")
+))]
+fn igmp_repr(payload: &[u8]) -> IgmpRepr<'_> {
+    IgmpRepr::parse(&IgmpPacket::new_checked(payload).unwrap()).unwrap()
+}
+
+/// Build an IGMPv3 group record.
+#[cfg(all(
+    feature = "proto-igmp",
+    feature = "medium-ip",
+    any(feature = "medium-ethernet", feature = "socket-udp")
+))]
+fn igmp_record(
+    record_type: IgmpRecordType,
+    group_addr: Ipv4Address,
+    sources: &[Ipv4Address],
+) -> Vec<u8> {
+    let mut bytes = vec![u8::from(record_type), 0, 0, sources.len() as u8];
+    bytes.extend_from_slice(group_addr.as_bytes());
+    for source in sources {
+        bytes.extend_from_slice(source.as_bytes());
+    }
+    bytes
+}
+
 #[rstest]
 #[case(Medium::Ip)]
 #[cfg(all(feature = "proto-igmp", feature = "medium-ip"))]
 #[case(Medium::Ethernet)]
 #[cfg(all(feature = "proto-igmp", feature = "medium-ethernet"))]
 fn test_handle_igmp(#[case] medium: Medium) {
-    fn recv_igmp(device: &mut Loopback, timestamp: Instant) -> Vec<(Ipv4Repr, IgmpRepr)> {
+    fn recv_igmp(device: &mut Loopback, timestamp: Instant) -> Vec<(Ipv4Repr, Vec<u8>)> {
         let caps = device.capabilities();
         let checksum_caps = &caps.checksum;
         recv_all(device, timestamp)
@@ -633,10 +720,9 @@ fn test_handle_igmp(#[case] medium: Medium) {
                     Medium::Ieee802154 => todo!(),
                 };
                 let ipv4_repr = Ipv4Repr::parse(&ipv4_packet, checksum_caps).ok()?;
-                let ip_payload = ipv4_packet.payload();
-                let igmp_packet = IgmpPacket::new_checked(ip_payload).ok()?;
-                let igmp_repr = IgmpRepr::parse(&igmp_packet).ok()?;
-                Some((ipv4_repr, igmp_repr))
+                // IGMP messages carry the Router Alert option.
+                assert_eq!(ipv4_packet.options(), &[0x94, 0x04, 0x00, 0x00]);
+                Some((ipv4_repr, ipv4_packet.payload().to_vec()))
             })
             .collect::<Vec<_>>()
     }
@@ -660,12 +746,15 @@ fn test_handle_igmp(#[case] medium: Medium) {
     assert_eq!(reports.len(), 2);
     for (i, group_addr) in groups.iter().enumerate() {
         assert_eq!(reports[i].0.next_header, IpProtocol::Igmp);
-        assert_eq!(reports[i].0.dst_addr, *group_addr);
         assert_eq!(
-            reports[i].1,
-            IgmpRepr::MembershipReport {
-                group_addr: *group_addr,
-                version: IgmpVersion::Version2,
+            reports[i].0.dst_addr,
+            Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS
+        );
+        assert_eq!(
+            igmp_repr(&reports[i].1),
+            IgmpRepr::MembershipReportV3 {
+                num_group_records: 1,
+                data: &igmp_record(IgmpRecordType::ChangeToExclude, *group_addr, &[]),
             }
         );
     }
@@ -689,7 +778,7 @@ fn test_handle_igmp(#[case] medium: Medium) {
     // loopback have been processed, including responses to
     // GENERAL_QUERY_BYTES. Therefore `recv_all()` would return 0
     // pkts that could be checked.
-    iface.socket_ingress(&mut device, &mut sockets);
+    iface.socket_ingress(&mut device, &mut sockets, None);
 
     // Leave multicast groups
     let timestamp = Instant::now();
@@ -703,8 +792,17 @@ fn test_handle_igmp(#[case] medium: Medium) {
     assert_eq!(leaves.len(), 2);
     for (i, group_addr) in groups.iter().cloned().enumerate() {
         assert_eq!(leaves[i].0.next_header, IpProtocol::Igmp);
-        assert_eq!(leaves[i].0.dst_addr, Ipv4Address::MULTICAST_ALL_ROUTERS);
-        assert_eq!(leaves[i].1, IgmpRepr::LeaveGroup { group_addr });
+        assert_eq!(
+            leaves[i].0.dst_addr,
+            Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS
+        );
+        assert_eq!(
+            igmp_repr(&leaves[i].1),
+            IgmpRepr::MembershipReportV3 {
+                num_group_records: 1,
+                data: &igmp_record(IgmpRecordType::ChangeToInclude, group_addr, &[]),
+            }
+        );
     }
 }
 
@@ -883,11 +981,10 @@ fn test_raw_socket_with_udp_socket(#[case] medium: Medium) {
     let socket = sockets.get_mut::<udp::Socket>(udp_socket_handle);
     assert!(socket.can_recv());
     assert_eq!(
-        socket.recv(),
-        Ok((
-            &UDP_PAYLOAD[..],
-            IpEndpoint::new(src_addr.into(), 67).into()
-        ))
+        socket
+            .recv()
+            .map(|(payload, meta)| (payload, meta.endpoint)),
+        Ok((&UDP_PAYLOAD[..], IpEndpoint::new(src_addr.into(), 67)))
     );
 }
 
@@ -957,6 +1054,7 @@ fn test_icmp_reply_size(#[case] medium: Medium) {
             ip_repr.into(),
             udp_repr,
             false,
+            false,
             &vec![0x2a; MAX_PAYLOAD_LEN],
             payload,
         ),
@@ -966,3 +1064,1899 @@ fn test_icmp_reply_size(#[case] medium: Medium) {
         ))
     );
 }
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp"))]
+fn test_tcp_splice() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let new_socket = || {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; 64]),
+        )
+    };
+    let client = sockets.add(new_socket());
+    let proxy_in = sockets.add(new_socket());
+    let proxy_out = sockets.add(new_socket());
+    let server = sockets.add(new_socket());
+
+    sockets
+        .get_mut::<tcp::Socket>(proxy_in)
+        .listen(1234)
+        .unwrap();
+    sockets.get_mut::<tcp::Socket>(server).listen(1235).unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(client)
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1234), 65000)
+        .unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(proxy_out)
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1235), 65001)
+        .unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(proxy_in)
+        .set_splice_to(Some(proxy_out));
+    sockets
+        .get_mut::<tcp::Socket>(proxy_out)
+        .set_splice_to(Some(proxy_in));
+
+    let (mut requested, mut replied) = (false, false);
+    for ms in 0..100 {
+        iface.poll(Instant::from_millis(ms), &mut device, &mut sockets);
+
+        let socket = sockets.get_mut::<tcp::Socket>(client);
+        if !requested && socket.can_send() {
+            socket.send_slice(b"hello").unwrap();
+            socket.close();
+            requested = true;
+        }
+        let socket = sockets.get_mut::<tcp::Socket>(server);
+        if !replied && socket.can_send() {
+            socket.send_slice(b"world").unwrap();
+            replied = true;
+        }
+    }
+
+    let mut buffer = [0; 16];
+    let socket = sockets.get_mut::<tcp::Socket>(server);
+    assert_eq!(socket.recv_slice(&mut buffer), Ok(5));
+    assert_eq!(&buffer[..5], b"hello");
+    // The end of the stream was forwarded too.
+    assert_eq!(
+        socket.recv_slice(&mut buffer),
+        Err(tcp::RecvError::Finished)
+    );
+
+    let socket = sockets.get_mut::<tcp::Socket>(client);
+    assert_eq!(socket.recv_slice(&mut buffer), Ok(5));
+    assert_eq!(&buffer[..5], b"world");
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp"))]
+fn test_tcp_splice_full() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let new_socket = |tx_len| {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; tx_len]),
+        )
+    };
+    let client = sockets.add(new_socket(64));
+    let proxy_in = sockets.add(new_socket(64));
+    // The data received by the proxy only fits in pieces in the socket it is spliced into.
+    let proxy_out = sockets.add(new_socket(8));
+    let server = sockets.add(new_socket(64));
+
+    sockets
+        .get_mut::<tcp::Socket>(proxy_in)
+        .listen(1234)
+        .unwrap();
+    sockets.get_mut::<tcp::Socket>(server).listen(1235).unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(client)
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1234), 65000)
+        .unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(proxy_out)
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1235), 65001)
+        .unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(proxy_in)
+        .set_splice_to(Some(proxy_out));
+
+    let data = [0x2a; 40];
+    let mut received = vec![];
+    let mut requested = false;
+    for ms in 0..100 {
+        iface.poll(Instant::from_millis(ms), &mut device, &mut sockets);
+
+        let socket = sockets.get_mut::<tcp::Socket>(client);
+        if !requested && socket.can_send() {
+            assert_eq!(socket.send_slice(&data), Ok(data.len()));
+            requested = true;
+        }
+        let mut buffer = [0; 64];
+        let socket = sockets.get_mut::<tcp::Socket>(server);
+        if let Ok(size) = socket.recv_slice(&mut buffer) {
+            received.extend_from_slice(&buffer[..size]);
+        }
+    }
+    assert_eq!(received, data);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp"))]
+fn test_tcp_simultaneous_open() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let new_socket = || {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; 64]),
+        )
+    };
+    let a = sockets.add(new_socket());
+    let b = sockets.add(new_socket());
+
+    sockets
+        .get_mut::<tcp::Socket>(a)
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1235), 1234)
+        .unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(b)
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1234), 1235)
+        .unwrap();
+
+    for ms in 0..100 {
+        iface.poll(Instant::from_millis(ms), &mut device, &mut sockets);
+    }
+
+    for handle in [a, b] {
+        assert_eq!(
+            sockets.get::<tcp::Socket>(handle).state(),
+            tcp::State::Established
+        );
+    }
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp"))]
+fn test_tcp_keep_alive_poll_at() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let new_socket = || {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; 64]),
+        )
+    };
+    let server = sockets.add(new_socket());
+    let client = sockets.add(new_socket());
+
+    sockets.get_mut::<tcp::Socket>(server).listen(1234).unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(client)
+        .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1234), 1235)
+        .unwrap();
+    for ms in 0..100 {
+        iface.poll(Instant::from_millis(ms), &mut device, &mut sockets);
+    }
+    assert_eq!(
+        sockets.get::<tcp::Socket>(client).state(),
+        tcp::State::Established
+    );
+    assert_eq!(iface.poll_at(Instant::from_millis(100), &sockets), None);
+
+    // Changing the socket through the set reschedules it.
+    sockets
+        .get_mut::<tcp::Socket>(client)
+        .set_keep_alive(Some(Duration::from_secs(10)));
+    iface.poll(Instant::from_millis(100), &mut device, &mut sockets);
+    let keep_alive_at = iface.poll_at(Instant::from_millis(100), &sockets).unwrap();
+    assert!(keep_alive_at > Instant::from_secs(9));
+    assert!(keep_alive_at <= Instant::from_secs(11));
+
+    // The keep-alive timer fires without any other activity and is rearmed.
+    iface.poll(keep_alive_at, &mut device, &mut sockets);
+    let next_at = iface.poll_at(keep_alive_at, &sockets).unwrap();
+    assert!(next_at > keep_alive_at + Duration::from_secs(9));
+}
+
+#[test]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-tcp"))]
+fn test_tcp_connect_neighbor_discovered() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    let local_ip_addr = Ipv4Address::new(127, 0, 0, 1);
+    let remote_ip_addr = Ipv4Address::new(127, 0, 0, 2);
+
+    let handle = sockets.add(tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; 64]),
+        tcp::SocketBuffer::new(vec![0; 64]),
+    ));
+    sockets
+        .get_mut::<tcp::Socket>(handle)
+        .connect(iface.context(), (remote_ip_addr, 80), 49500)
+        .unwrap();
+
+    // The SYN waits for the neighbor to be discovered.
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+    assert!(device.queue.is_empty());
+    assert_eq!(iface.stats().tcp.tx_packets, 0);
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(1))
+    );
+
+    // It is sent as soon as the neighbor answers, not once the socket stops waiting.
+    device.queue.push_back(emit_arp(ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Reply,
+        source_hardware_addr: EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x02]),
+        source_protocol_addr: remote_ip_addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: local_ip_addr,
+    }));
+    iface.poll(Instant::from_millis(1), &mut device, &mut sockets);
+    assert_eq!(iface.stats().tcp.tx_packets, 1);
+    assert_eq!(
+        sockets.get::<tcp::Socket>(handle).state(),
+        tcp::State::SynSent
+    );
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_send_metadata() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+    );
+    socket.bind(68).unwrap();
+    socket
+        .send_slice(
+            b"abcdef",
+            udp::UdpMetadata {
+                hop_limit: Some(1),
+                dscp: 46,
+                ecn: 1,
+                ..IpEndpoint::new(IpAddress::v4(127, 0, 0, 2), 67).into()
+            },
+        )
+        .unwrap();
+    sockets.add(socket);
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert!(packet.verify_checksum());
+    assert_eq!(packet.hop_limit(), 1);
+    assert_eq!(packet.dscp(), 46);
+    assert_eq!(packet.ecn(), 1);
+    assert_eq!(packet.src_addr(), Ipv4Address::new(127, 0, 0, 1));
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_default_hop_limit() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    iface.set_hop_limit(128);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 16]),
+    );
+    socket.bind(68).unwrap();
+    let endpoint = IpEndpoint::new(IpAddress::v4(127, 0, 0, 2), 67);
+    socket.send_slice(b"abcdef", endpoint).unwrap();
+    let handle = sockets.add(socket);
+
+    // Sockets without a hop limit of their own use the one of the interface.
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert_eq!(packet.hop_limit(), 128);
+
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.set_hop_limit(Some(10));
+    socket.send_slice(b"abcdef", endpoint).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert_eq!(packet.hop_limit(), 10);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "proto-igmp", feature = "socket-udp"))]
+fn test_udp_socket_multicast_group() {
+    fn recv_igmp(device: &mut Loopback) -> Vec<Vec<u8>> {
+        recv_all(device, Instant::ZERO)
+            .iter()
+            .map(|frame| {
+                let ipv4_packet = Ipv4PacketWire::new_checked(&frame[..]).unwrap();
+                assert_eq!(ipv4_packet.next_header(), IpProtocol::Igmp);
+                ipv4_packet.payload().to_vec()
+            })
+            .collect()
+    }
+
+    let group_addr = Ipv4Address::new(224, 0, 0, 123);
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+    );
+    socket.bind(5353).unwrap();
+    socket.join_multicast_group(group_addr).unwrap();
+    let handle = sockets.add(socket);
+
+    // The group joined by the socket is reported once.
+    assert!(iface.socket_multicast_egress(&mut device, &sockets));
+    let reports = recv_igmp(&mut device);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        igmp_repr(&reports[0]),
+        IgmpRepr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &igmp_record(IgmpRecordType::ChangeToExclude, group_addr, &[]),
+        }
+    );
+    assert!(iface.has_multicast_group(group_addr));
+    assert!(!iface.socket_multicast_egress(&mut device, &sockets));
+    assert!(recv_igmp(&mut device).is_empty());
+
+    // It is left once the socket leaves it.
+    sockets
+        .get_mut::<udp::Socket>(handle)
+        .leave_multicast_group(group_addr);
+    assert!(iface.socket_multicast_egress(&mut device, &sockets));
+    let reports = recv_igmp(&mut device);
+    assert_eq!(reports.len(), 1);
+    assert_eq!(
+        igmp_repr(&reports[0]),
+        IgmpRepr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &igmp_record(IgmpRecordType::ChangeToInclude, group_addr, &[]),
+        }
+    );
+    assert!(!iface.has_multicast_group(group_addr));
+
+    // Unless it was also joined on the interface itself.
+    sockets
+        .get_mut::<udp::Socket>(handle)
+        .join_multicast_group(group_addr)
+        .unwrap();
+    assert!(iface.socket_multicast_egress(&mut device, &sockets));
+    assert_eq!(recv_igmp(&mut device).len(), 1);
+    assert_eq!(
+        iface.join_multicast_group(&mut device, group_addr, Instant::ZERO),
+        Ok(false)
+    );
+    sockets.remove(handle);
+    assert!(!iface.socket_multicast_egress(&mut device, &sockets));
+    assert!(recv_igmp(&mut device).is_empty());
+    assert!(iface.has_multicast_group(group_addr));
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "proto-igmp", feature = "socket-udp"))]
+fn test_igmp_source_filter() {
+    use crate::iface::MulticastFilterMode::{Exclude, Include};
+
+    fn recv_report(device: &mut Loopback) -> Vec<u8> {
+        let packets = recv_all(device, Instant::ZERO);
+        assert_eq!(packets.len(), 1);
+        let ipv4_packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+        assert_eq!(ipv4_packet.next_header(), IpProtocol::Igmp);
+        assert_eq!(
+            ipv4_packet.dst_addr(),
+            Ipv4Address::MULTICAST_ALL_IGMPV3_ROUTERS
+        );
+        ipv4_packet.payload().to_vec()
+    }
+
+    fn ipv4_packet(
+        src_addr: Ipv4Address,
+        dst_addr: Ipv4Address,
+        next_header: IpProtocol,
+        payload: &[u8],
+    ) -> Vec<u8> {
+        let ipv4_repr = Ipv4Repr {
+            src_addr,
+            dst_addr,
+            next_header,
+            payload_len: payload.len(),
+            hop_limit: 1,
+        };
+        let mut bytes = vec![0; ipv4_repr.buffer_len() + payload.len()];
+        ipv4_repr.emit(
+            &mut Ipv4PacketWire::new_unchecked(&mut bytes),
+            &ChecksumCapabilities::default(),
+        );
+        bytes[ipv4_repr.buffer_len()..].copy_from_slice(payload);
+        bytes
+    }
+
+    let group_addr = Ipv4Address::new(232, 1, 1, 1);
+    let src_a = Ipv4Address::new(127, 0, 0, 2);
+    let src_b = Ipv4Address::new(127, 0, 0, 3);
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+    );
+    socket.bind(5000).unwrap();
+    socket.join_multicast_group(group_addr).unwrap();
+    let handle = sockets.add(socket);
+
+    let received = |iface: &mut Interface, sockets: &mut SocketSet, src_addr: Ipv4Address| {
+        let udp_repr = UdpRepr {
+            src_port: 5000,
+            dst_port: 5000,
+        };
+        let mut udp_bytes = vec![0; udp_repr.header_len() + 4];
+        udp_repr.emit(
+            &mut UdpPacket::new_unchecked(&mut udp_bytes),
+            &src_addr.into(),
+            &group_addr.into(),
+            4,
+            |buf| buf.copy_from_slice(b"abcd"),
+            &ChecksumCapabilities::default(),
+        );
+        let bytes = ipv4_packet(src_addr, group_addr, IpProtocol::Udp, &udp_bytes);
+        iface.inner.process_ipv4(
+            sockets,
+            PacketMeta::default(),
+            &Ipv4PacketWire::new_checked(&bytes[..]).unwrap(),
+            &mut iface.fragments,
+        );
+        sockets.get_mut::<udp::Socket>(handle).recv().is_ok()
+    };
+
+    // Joining for a source of a Source-Specific Multicast group allows it.
+    assert_eq!(
+        iface.set_multicast_source_filter(
+            &mut device,
+            group_addr,
+            Include,
+            &[src_a],
+            Instant::ZERO
+        ),
+        Ok(true)
+    );
+    assert_eq!(
+        igmp_repr(&recv_report(&mut device)),
+        IgmpRepr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &igmp_record(IgmpRecordType::AllowNewSources, group_addr, &[src_a]),
+        }
+    );
+    assert_eq!(
+        iface.multicast_source_filter(group_addr),
+        Some((Include, &[src_a][..]))
+    );
+    assert!(received(&mut iface, &mut sockets, src_a));
+    assert!(!received(&mut iface, &mut sockets, src_b));
+
+    // Changing the filter mode reports the new source list.
+    assert_eq!(
+        iface.set_multicast_source_filter(
+            &mut device,
+            group_addr,
+            Exclude,
+            &[src_a],
+            Instant::ZERO
+        ),
+        Ok(true)
+    );
+    assert_eq!(
+        igmp_repr(&recv_report(&mut device)),
+        IgmpRepr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &igmp_record(IgmpRecordType::ChangeToExclude, group_addr, &[src_a]),
+        }
+    );
+    assert!(!received(&mut iface, &mut sockets, src_a));
+    assert!(received(&mut iface, &mut sockets, src_b));
+
+    // Changing the source list reports the sources allowed and blocked.
+    assert_eq!(
+        iface.set_multicast_source_filter(
+            &mut device,
+            group_addr,
+            Exclude,
+            &[src_b],
+            Instant::ZERO
+        ),
+        Ok(true)
+    );
+    let mut data = igmp_record(IgmpRecordType::AllowNewSources, group_addr, &[src_a]);
+    data.extend(igmp_record(
+        IgmpRecordType::BlockOldSources,
+        group_addr,
+        &[src_b],
+    ));
+    assert_eq!(
+        igmp_repr(&recv_report(&mut device)),
+        IgmpRepr::MembershipReportV3 {
+            num_group_records: 2,
+            data: &data,
+        }
+    );
+    assert_eq!(
+        iface.set_multicast_source_filter(
+            &mut device,
+            group_addr,
+            Exclude,
+            &[src_b],
+            Instant::ZERO
+        ),
+        Ok(false)
+    );
+    let too_many = (0..=crate::config::IFACE_MAX_MULTICAST_SOURCE_COUNT)
+        .map(|i| Ipv4Address::new(127, 0, 1, i as u8))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        iface.set_multicast_source_filter(
+            &mut device,
+            group_addr,
+            Include,
+            &too_many,
+            Instant::ZERO
+        ),
+        Err(MulticastError::SourceTableFull)
+    );
+
+    // Queries are answered with the current state of the group.
+    let query = IgmpRepr::MembershipQueryV3 {
+        max_resp_time: Duration::from_secs(1),
+        group_addr,
+        s_flag: false,
+        qrv: 2,
+        qqic: 125,
+        num_srcs: 0,
+        data: &[],
+    };
+    let mut query_bytes = vec![0; query.buffer_len()];
+    query.emit(&mut IgmpPacket::new_unchecked(&mut query_bytes));
+    let bytes = ipv4_packet(
+        Ipv4Address::new(127, 0, 0, 254),
+        group_addr,
+        IpProtocol::Igmp,
+        &query_bytes,
+    );
+    iface.inner.process_ipv4(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv4PacketWire::new_checked(&bytes[..]).unwrap(),
+        &mut iface.fragments,
+    );
+    iface.inner.now = Instant::from_secs(1);
+    assert!(iface.igmp_egress(&mut device));
+    assert_eq!(
+        igmp_repr(&recv_report(&mut device)),
+        IgmpRepr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &igmp_record(IgmpRecordType::ModeIsExclude, group_addr, &[src_b]),
+        }
+    );
+
+    // An IGMPv2 querier makes the interface fall back to IGMPv2, without source filters.
+    let query = IgmpRepr::MembershipQuery {
+        max_resp_time: Duration::from_secs(1),
+        group_addr: Ipv4Address::UNSPECIFIED,
+        version: IgmpVersion::Version2,
+    };
+    let mut query_bytes = vec![0; query.buffer_len()];
+    query.emit(&mut IgmpPacket::new_unchecked(&mut query_bytes));
+    let bytes = ipv4_packet(
+        Ipv4Address::new(127, 0, 0, 254),
+        Ipv4Address::MULTICAST_ALL_SYSTEMS,
+        IpProtocol::Igmp,
+        &query_bytes,
+    );
+    iface.inner.process_ipv4(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv4PacketWire::new_checked(&bytes[..]).unwrap(),
+        &mut iface.fragments,
+    );
+    assert_eq!(
+        iface.leave_multicast_group(&mut device, group_addr, Instant::from_secs(1)),
+        Ok(true)
+    );
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let ipv4_packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert_eq!(ipv4_packet.dst_addr(), Ipv4Address::MULTICAST_ALL_ROUTERS);
+    assert_eq!(
+        igmp_repr(ipv4_packet.payload()),
+        IgmpRepr::LeaveGroup { group_addr }
+    );
+
+    // It goes back to IGMPv3 once the querier is gone.
+    let timestamp = Instant::from_secs(1 + 2 * 125 + 10);
+    assert_eq!(
+        iface.join_multicast_group(&mut device, group_addr, timestamp),
+        Ok(true)
+    );
+    assert_eq!(
+        igmp_repr(&recv_report(&mut device)),
+        IgmpRepr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &igmp_record(IgmpRecordType::ChangeToExclude, group_addr, &[]),
+        }
+    );
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_send_batch() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 3], vec![0; 24]),
+    );
+    socket.bind(68).unwrap();
+    let remote = IpEndpoint::new(IpAddress::v4(127, 0, 0, 2), 67);
+    assert_eq!(
+        socket.send_batch([
+            (&b"abc"[..], remote),
+            (&b"defg"[..], remote),
+            (&b"hijkl"[..], remote)
+        ]),
+        Ok(3)
+    );
+    sockets.add(socket);
+
+    // All the queued packets are sent at once.
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 3);
+    for (packet, payload) in packets.iter().zip([&b"abc"[..], b"defg", b"hijkl"]) {
+        let packet = Ipv4PacketWire::new_checked(&packet[..]).unwrap();
+        let udp_packet = UdpPacket::new_checked(packet.payload()).unwrap();
+        assert_eq!(udp_packet.payload(), payload);
+    }
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_lite() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+    );
+    socket.set_udp_lite_enabled(true);
+    socket.set_send_checksum_coverage(8);
+    socket.bind(5000).unwrap();
+    socket
+        .send_slice(
+            b"abcdef",
+            IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5000),
+        )
+        .unwrap();
+    let handle = sockets.add(socket);
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let mut packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert_eq!(packet.next_header(), IpProtocol::UdpLite);
+    let udplite_packet = UdpLitePacket::new_checked(packet.payload()).unwrap();
+    assert_eq!(udplite_packet.checksum_coverage(), 8);
+    assert!(udplite_packet.verify_checksum(&packet.src_addr().into(), &packet.dst_addr().into()));
+
+    // Damage to the payload, which is not covered by the checksum, goes undetected.
+    let len = packets[0].len();
+    packets[0][len - 1] = b'z';
+    let frame = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &frame,
+            &mut iface.fragments
+        ),
+        None
+    );
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    assert_eq!(socket.recv().unwrap().0, b"abcdez");
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_send_checksum_disabled() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+    );
+    socket.set_send_checksum_enabled(false);
+    socket.bind(5000).unwrap();
+    socket
+        .send_slice(
+            b"abcdef",
+            IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5000),
+        )
+        .unwrap();
+    let handle = sockets.add(socket);
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    let udp_packet = UdpPacket::new_checked(packet.payload()).unwrap();
+    assert_eq!(udp_packet.checksum(), 0);
+
+    // UDP-over-IPv4 packets without a checksum are always accepted.
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &packet,
+            &mut iface.fragments
+        ),
+        None
+    );
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    assert_eq!(socket.recv().unwrap().0, b"abcdef");
+}
+
+#[test]
+#[cfg(all(
+    feature = "medium-ip",
+    feature = "socket-udp",
+    feature = "proto-ipv4-fragmentation"
+))]
+fn test_udp_fragmentation() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    iface.inner.caps.max_transmission_unit = 576;
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 1000]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 2000]),
+    );
+    socket.bind(5000).unwrap();
+    let endpoint = IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5000);
+    socket.send_slice(&[0x2a; 1000], endpoint).unwrap();
+    let handle = sockets.add(socket);
+
+    // Datagrams larger than the MTU are dropped by default.
+    assert!(!iface.socket_egress(&mut device, &mut sockets));
+    assert!(recv_all(&mut device, Instant::ZERO).is_empty());
+
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.set_fragmentation_enabled(true);
+    socket.send_slice(&[0x2a; 1000], endpoint).unwrap();
+    socket.send_slice(&[0x2b; 1000], endpoint).unwrap();
+
+    // The second datagram waits until all the fragments of the first one are sent.
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    while iface.ipv4_egress(&mut device) {}
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 2);
+    assert!(sockets.get_mut::<udp::Socket>(handle).send_pending());
+
+    for (i, packet) in packets.iter().enumerate() {
+        let packet = Ipv4PacketWire::new_checked(&packet[..]).unwrap();
+        assert!(packet.total_len() <= 576);
+        assert!(!packet.dont_frag());
+        assert_eq!(packet.more_frags(), i == 0);
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &packet,
+            &mut iface.fragments,
+        );
+    }
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    assert_eq!(socket.recv().unwrap().0, &[0x2a; 1000][..]);
+}
+
+/// Build a fragment of an IPv4 packet sent to us.
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv4-fragmentation"))]
+fn ipv4_fragment(
+    src_addr: Ipv4Address,
+    ident: u16,
+    offset: u16,
+    more_frags: bool,
+    payload: &[u8],
+) -> std::vec::Vec<u8> {
+    let repr = Ipv4Repr {
+        src_addr,
+        dst_addr: Ipv4Address::new(127, 0, 0, 1),
+        next_header: IpProtocol::Unknown(0xfe),
+        payload_len: payload.len(),
+        hop_limit: 64,
+    };
+    let mut bytes = vec![0u8; repr.buffer_len() + payload.len()];
+    let mut packet = Ipv4PacketWire::new_unchecked(&mut bytes[..]);
+    repr.emit(&mut packet, &ChecksumCapabilities::default());
+    packet.set_ident(ident);
+    packet.set_more_frags(more_frags);
+    packet.set_frag_offset(offset);
+    packet.fill_checksum();
+    packet.payload_mut().copy_from_slice(payload);
+    bytes
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "proto-ipv4-fragmentation"))]
+fn test_ipv4_reassembly_limits() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    let src_a = Ipv4Address::new(127, 0, 0, 2);
+    let src_b = Ipv4Address::new(127, 0, 0, 3);
+    let process = |iface: &mut Interface, sockets: &mut SocketSet, data: &[u8]| {
+        iface.inner.process_ipv4(
+            sockets,
+            PacketMeta::default(),
+            &Ipv4PacketWire::new_checked(data).unwrap(),
+            &mut iface.fragments,
+        );
+    };
+
+    // The oldest incomplete packet is dropped when all the buffers are in use.
+    for ident in 0..=crate::config::REASSEMBLY_BUFFER_COUNT as u16 {
+        process(
+            &mut iface,
+            &mut sockets,
+            &ipv4_fragment(src_a, ident, 0, true, &[0; 8]),
+        );
+    }
+    assert_eq!(iface.ipv4_reassembly_stats().evictions, 1);
+    let ident = crate::config::REASSEMBLY_BUFFER_COUNT as u16;
+    process(
+        &mut iface,
+        &mut sockets,
+        &ipv4_fragment(src_a, ident, 8, false, &[0; 8]),
+    );
+    assert_eq!(iface.ipv4_reassembly_stats().reassembled, 1);
+    process(
+        &mut iface,
+        &mut sockets,
+        &ipv4_fragment(src_a, 0, 8, false, &[0; 8]),
+    );
+    assert_eq!(iface.ipv4_reassembly_stats().reassembled, 1);
+
+    // Incomplete packets time out.
+    iface.poll(Instant::from_secs(61), &mut device, &mut sockets);
+    assert_eq!(
+        iface.ipv4_reassembly_stats().timeouts,
+        crate::config::REASSEMBLY_BUFFER_COUNT as u32
+    );
+
+    // So is the oldest packet of a source over its limit.
+    iface.reset_ipv4_reassembly_stats();
+    iface.set_ipv4_reassembly_limit(1);
+    process(
+        &mut iface,
+        &mut sockets,
+        &ipv4_fragment(src_b, 10, 0, true, &[0; 8]),
+    );
+    process(
+        &mut iface,
+        &mut sockets,
+        &ipv4_fragment(src_b, 11, 0, true, &[0; 8]),
+    );
+    assert_eq!(iface.ipv4_reassembly_stats().evictions, 1);
+
+    // Fragments past the memory limit are dropped.
+    iface.reset_ipv4_reassembly_stats();
+    iface.set_ipv4_reassembly_memory_limit(8);
+    process(
+        &mut iface,
+        &mut sockets,
+        &ipv4_fragment(src_b, 11, 8, true, &[0; 8]),
+    );
+    assert_eq!(iface.ipv4_reassembly_stats().dropped_fragments, 1);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_dont_fragment() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 12]),
+    );
+    socket.bind(5000).unwrap();
+    let endpoint = IpEndpoint::new(IpAddress::v4(127, 0, 0, 2), 5000);
+    socket.send_slice(b"abcdef", endpoint).unwrap();
+    let handle = sockets.add(socket);
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket.set_dont_fragment(false);
+    socket.send_slice(b"abcdef", endpoint).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 2);
+    let packet = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert!(packet.dont_frag());
+    let packet = Ipv4PacketWire::new_checked(&packets[1][..]).unwrap();
+    assert!(!packet.dont_frag());
+    assert!(packet.verify_checksum());
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_shared_port() {
+    let (mut iface, mut sockets, _device) = setup(Medium::Ip);
+
+    let group_addr = Ipv4Address::new(239, 255, 255, 250);
+    let handles: Vec<_> = (0..3)
+        .map(|i| {
+            let mut socket = udp::Socket::new(
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 32]),
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+            );
+            socket.bind(1900).unwrap();
+            if i < 2 {
+                socket.join_multicast_group(group_addr).unwrap();
+            }
+            sockets.add(socket)
+        })
+        .collect();
+
+    let udp_repr = UdpRepr {
+        src_port: 1900,
+        dst_port: 1900,
+    };
+    let mut process = |dst_addr| {
+        let ip_repr = IpRepr::Ipv4(Ipv4Repr {
+            src_addr: Ipv4Address::new(127, 0, 0, 2),
+            dst_addr,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + 6,
+            hop_limit: 64,
+        });
+        assert_eq!(
+            iface.inner.process_udp(
+                &mut sockets,
+                PacketMeta::default(),
+                ip_repr,
+                udp_repr,
+                false,
+                false,
+                b"abcdef",
+                &[],
+            ),
+            None
+        );
+    };
+
+    // Multicast packets are delivered to every socket that joined the group.
+    process(group_addr);
+    // Unicast packets only to the first socket.
+    process(Ipv4Address::new(127, 0, 0, 1));
+
+    let mut recv_count = |handle| {
+        let socket = sockets.get_mut::<udp::Socket>(handle);
+        let mut count = 0;
+        while socket.recv().is_ok() {
+            count += 1;
+        }
+        count
+    };
+    assert_eq!(recv_count(handles[0]), 2);
+    assert_eq!(recv_count(handles[1]), 1);
+    assert_eq!(recv_count(handles[2]), 0);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_multicast_loop() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let group_addr = Ipv4Address::new(239, 255, 255, 250);
+    let receivers: Vec<_> = (0..2)
+        .map(|i| {
+            let mut socket = udp::Socket::new(
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 32]),
+                udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+            );
+            socket.bind(1900).unwrap();
+            if i == 0 {
+                socket.join_multicast_group(group_addr).unwrap();
+            }
+            sockets.add(socket)
+        })
+        .collect();
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 32]),
+    );
+    socket.bind(5000).unwrap();
+    socket.join_multicast_group(group_addr).unwrap();
+    let endpoint = IpEndpoint::new(group_addr.into(), 1900);
+    socket.send_slice(b"abcdef", endpoint).unwrap();
+    let sender = sockets.add(socket);
+
+    // Multicast packets are not delivered locally by default.
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    assert_eq!(recv_all(&mut device, Instant::ZERO).len(), 1);
+    assert!(!sockets.get_mut::<udp::Socket>(receivers[0]).can_recv());
+
+    let socket = sockets.get_mut::<udp::Socket>(sender);
+    socket.set_multicast_loop_enabled(true);
+    socket.send_slice(b"ghijkl", endpoint).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    assert_eq!(recv_all(&mut device, Instant::ZERO).len(), 1);
+
+    // Only to the sockets that joined their group, and not to the sender.
+    let (payload, meta) = sockets.get_mut::<udp::Socket>(receivers[0]).recv().unwrap();
+    assert_eq!(payload, b"ghijkl");
+    assert_eq!(
+        meta.endpoint,
+        IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5000)
+    );
+    assert!(!sockets.get_mut::<udp::Socket>(receivers[1]).can_recv());
+    assert!(!sockets.get_mut::<udp::Socket>(sender).can_recv());
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_source_address_selection(#[case] medium: Medium) {
+    let (mut iface, _, _) = setup(medium);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v4(192, 168, 1, 1), 24))
+            .unwrap();
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v4(10, 0, 0, 1), 8))
+            .unwrap();
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v4(169, 254, 0, 1), 16))
+            .unwrap();
+    });
+
+    let source = |iface: &mut Interface, dst| iface.inner.get_source_address_ipv4(dst);
+    // Our own address.
+    assert_eq!(
+        source(&mut iface, Ipv4Address::new(10, 0, 0, 1)),
+        Some(Ipv4Address::new(10, 0, 0, 1))
+    );
+    // Same scope.
+    assert_eq!(
+        source(&mut iface, Ipv4Address::new(169, 254, 3, 4)),
+        Some(Ipv4Address::new(169, 254, 0, 1))
+    );
+    assert_eq!(
+        source(&mut iface, Ipv4Address::new(127, 0, 0, 2)),
+        Some(Ipv4Address::new(127, 0, 0, 1))
+    );
+    // Longest matching prefix.
+    assert_eq!(
+        source(&mut iface, Ipv4Address::new(10, 1, 2, 3)),
+        Some(Ipv4Address::new(10, 0, 0, 1))
+    );
+    assert_eq!(
+        source(&mut iface, Ipv4Address::new(192, 168, 1, 7)),
+        Some(Ipv4Address::new(192, 168, 1, 1))
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_source_address_deprecated(#[case] medium: Medium) {
+    let (mut iface, _, _) = setup(medium);
+    let old = Ipv4Address::new(10, 0, 0, 1);
+    let new = Ipv4Address::new(10, 0, 0, 2);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.clear();
+        ip_addrs.push(IpCidr::new(old.into(), 8)).unwrap();
+        ip_addrs.push(IpCidr::new(new.into(), 8)).unwrap();
+    });
+    iface.set_ip_addr_lifetime(
+        old,
+        AddressLifetime {
+            preferred_until: Some(Instant::ZERO),
+            valid_until: None,
+        },
+    );
+
+    // A deprecated address is only used when there is no other one.
+    let remote = Ipv4Address::new(10, 0, 0, 3);
+    assert_eq!(iface.inner.get_source_address_ipv4(remote), Some(new));
+    assert_eq!(iface.inner.get_source_address_ipv4(old), Some(old));
+    assert!(iface.has_ip_addr(old));
+}
+
+#[cfg(feature = "medium-ethernet")]
+/// Receive the next ARP packet, skipping the other frames.
+fn recv_arp(device: &mut Loopback) -> Option<(EthernetAddress, ArpRepr)> {
+    loop {
+        let (rx, _tx) = device.receive(Instant::ZERO)?;
+        let arp = rx.consume(|frame| {
+            let frame = EthernetFrame::new_checked(&*frame).unwrap();
+            if frame.ethertype() != EthernetProtocol::Arp {
+                return None;
+            }
+            let packet = ArpPacket::new_checked(frame.payload()).unwrap();
+            Some((frame.dst_addr(), ArpRepr::parse(&packet).unwrap()))
+        });
+        if arp.is_some() {
+            return arp;
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_gratuitous_arp() {
+    let (mut iface, sockets, mut device) = setup(Medium::Ethernet);
+    let addr = Ipv4Address::new(192, 168, 1, 1);
+    let mac = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    let announcement = |mac| ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: mac,
+        source_protocol_addr: addr,
+        target_hardware_addr: EthernetAddress::BROADCAST,
+        target_protocol_addr: addr,
+    };
+    iface.set_announce_transmits(2);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.push(IpCidr::new(addr.into(), 24)).unwrap();
+    });
+
+    // A new address is announced right away, then once more two seconds later.
+    assert_eq!(iface.poll_at(Instant::ZERO, &sockets), Some(Instant::ZERO));
+    assert!(iface.announce_egress(&mut device));
+    assert_eq!(
+        recv_arp(&mut device),
+        Some((
+            EthernetAddress::BROADCAST,
+            announcement(EthernetAddress::default())
+        ))
+    );
+    assert!(!iface.announce_egress(&mut device));
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(2))
+    );
+    iface.inner.now = Instant::from_secs(2);
+    assert!(iface.announce_egress(&mut device));
+    assert!(recv_arp(&mut device).is_some());
+    assert!(!iface.announce_egress(&mut device));
+    assert_eq!(iface.poll_at(Instant::from_secs(2), &sockets), None);
+
+    // Addresses that were already assigned are not announced again.
+    iface.update_ip_addrs(|_| {});
+    assert!(!iface.announce_egress(&mut device));
+
+    // All addresses are announced with a new hardware address, except for the loopback one.
+    iface.set_hardware_addr(HardwareAddress::Ethernet(mac));
+    while iface.announce_egress(&mut device) {}
+    assert_eq!(
+        recv_arp(&mut device),
+        Some((EthernetAddress::BROADCAST, announcement(mac)))
+    );
+    assert!(recv_arp(&mut device).is_none());
+
+    // Removed addresses are not announced anymore.
+    iface.update_ip_addrs(|ip_addrs| ip_addrs.retain(|cidr| cidr.address() != addr.into()));
+    iface.inner.now = Instant::from_secs(4);
+    while iface.announce_egress(&mut device) {}
+    assert!(recv_arp(&mut device).is_none());
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn emit_arp(repr: ArpRepr) -> std::vec::Vec<u8> {
+    let mut bytes = vec![0u8; 14 + repr.buffer_len()];
+    let mut frame = EthernetFrame::new_unchecked(&mut bytes[..]);
+    frame.set_dst_addr(EthernetAddress::BROADCAST);
+    frame.set_src_addr(EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]));
+    frame.set_ethertype(EthernetProtocol::Arp);
+    repr.emit(&mut ArpPacket::new_unchecked(frame.payload_mut()));
+    bytes
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_autoip() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]);
+    iface.set_autoip(true);
+
+    // Probe for an address, and return it along with the time the probes were done.
+    let probe = |iface: &mut Interface, sockets: &SocketSet, device: &mut Loopback| {
+        let mut addr = None;
+        for _ in 0..3 {
+            let now = iface.poll_at(iface.inner.now, sockets).unwrap();
+            iface.inner.now = now;
+            while !iface.autoip_egress(device) {
+                iface.inner.now = iface.poll_at(iface.inner.now, sockets).unwrap();
+            }
+            let Some((
+                _,
+                ArpRepr::EthernetIpv4 {
+                    operation: ArpOperation::Request,
+                    source_protocol_addr,
+                    target_protocol_addr,
+                    ..
+                },
+            )) = recv_arp(device)
+            else {
+                panic!("expected an ARP probe");
+            };
+            assert!(source_protocol_addr.is_unspecified());
+            assert!(addr.is_none() || addr == Some(target_protocol_addr));
+            addr = Some(target_protocol_addr);
+        }
+        addr.unwrap()
+    };
+
+    // A link-local address is assigned if nobody answers the probes.
+    let probes_at = iface.inner.now;
+    let addr = probe(&mut iface, &sockets, &mut device);
+    assert!(iface.inner.now - probes_at <= Duration::from_secs(5));
+    assert!(addr.is_link_local());
+    assert!(addr.0[2] != 0 && addr.0[2] != 255);
+    assert_eq!(iface.autoip_addr(), None);
+    let assign_at = iface.poll_at(iface.inner.now, &sockets).unwrap();
+    assert_eq!(assign_at, iface.inner.now + Duration::from_secs(2));
+    iface.inner.now = assign_at;
+    assert!(iface.autoip_egress(&mut device));
+    assert_eq!(iface.autoip_addr(), Some(addr));
+    assert!(iface.has_ip_addr(addr));
+
+    // The address is defended once, and given up if the other host insists.
+    let claim = emit_arp(ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: remote_hw_addr,
+        source_protocol_addr: addr,
+        target_hardware_addr: EthernetAddress::default(),
+        target_protocol_addr: addr,
+    });
+    assert_eq!(
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &claim[..],
+            &mut iface.fragments
+        ),
+        Some(EthernetPacket::Arp(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: EthernetAddress::default(),
+            source_protocol_addr: addr,
+            target_hardware_addr: EthernetAddress::BROADCAST,
+            target_protocol_addr: addr,
+        }))
+    );
+    assert_eq!(iface.autoip_addr(), Some(addr));
+    iface.inner.now += Duration::from_secs(5);
+    assert_eq!(
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &claim[..],
+            &mut iface.fragments
+        ),
+        None
+    );
+    assert_eq!(iface.autoip_addr(), None);
+    assert!(!iface.has_ip_addr(addr));
+
+    // Another address is selected, and a probe for it from another host is a conflict.
+    let other = probe(&mut iface, &sockets, &mut device);
+    assert_ne!(other, addr);
+    iface.inner.process_ethernet(
+        &mut sockets,
+        PacketMeta::default(),
+        &emit_arp(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: remote_hw_addr,
+            source_protocol_addr: Ipv4Address::UNSPECIFIED,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: other,
+        })[..],
+        &mut iface.fragments,
+    );
+    let addr = probe(&mut iface, &sockets, &mut device);
+    assert_ne!(addr, other);
+    iface.inner.now = iface.poll_at(iface.inner.now, &sockets).unwrap();
+    assert!(iface.autoip_egress(&mut device));
+    assert_eq!(iface.autoip_addr(), Some(addr));
+
+    // The link-local address is removed once another address is assigned.
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v4(192, 168, 1, 1), 24))
+            .unwrap();
+    });
+    assert!(iface.autoip_egress(&mut device));
+    assert_eq!(iface.autoip_addr(), None);
+    assert!(!iface.has_ip_addr(addr));
+    assert_eq!(iface.poll_at(iface.inner.now, &sockets), None);
+    assert!(!iface.autoip_egress(&mut device));
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_static_neighbor() {
+    use crate::iface::{NeighborEntry, NeighborState};
+
+    let (mut iface, mut sockets, _device) = setup(Medium::Ethernet);
+    let remote_ip_addr = Ipv4Address::new(127, 0, 0, 2);
+    let static_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x02]);
+    iface
+        .add_static_neighbor(remote_ip_addr, static_hw_addr.into())
+        .unwrap();
+
+    // ARP packets from another host claiming the address don't replace the entry.
+    iface.inner.now = Instant::from_secs(10);
+    iface.inner.process_ethernet(
+        &mut sockets,
+        PacketMeta::default(),
+        &emit_arp(ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x01]),
+            source_protocol_addr: remote_ip_addr,
+            target_hardware_addr: EthernetAddress::default(),
+            target_protocol_addr: Ipv4Address::new(127, 0, 0, 1),
+        })[..],
+        &mut iface.fragments,
+    );
+    // Nor do changes of the addresses of the interface.
+    iface.update_ip_addrs(|_| {});
+    assert_eq!(
+        iface.neighbors().collect::<std::vec::Vec<_>>(),
+        [NeighborEntry {
+            protocol_addr: remote_ip_addr.into(),
+            hardware_addr: static_hw_addr.into(),
+            state: NeighborState::Permanent,
+            age: Duration::from_secs(10),
+        }]
+    );
+
+    assert_eq!(
+        iface.remove_neighbor(remote_ip_addr),
+        Some(static_hw_addr.into())
+    );
+    assert_eq!(iface.neighbors().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_strict_neighbor_learning() {
+    use crate::iface::NeighborState;
+
+    let (mut iface, mut sockets, _device) = setup(Medium::Ethernet);
+    iface.set_strict_neighbor_learning(true);
+    let local_ip_addr = Ipv4Address::new(127, 0, 0, 1);
+    let remote_ip_addr = Ipv4Address::new(127, 0, 0, 2);
+    let remote_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x02]);
+    let attacker_hw_addr = EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x66]);
+
+    let mut recv_arp = |iface: &mut Interface, operation, source_hardware_addr| {
+        iface.inner.process_ethernet(
+            &mut sockets,
+            PacketMeta::default(),
+            &emit_arp(ArpRepr::EthernetIpv4 {
+                operation,
+                source_hardware_addr,
+                source_protocol_addr: remote_ip_addr,
+                target_hardware_addr: EthernetAddress::default(),
+                target_protocol_addr: local_ip_addr,
+            })[..],
+            &mut iface.fragments,
+        );
+    };
+    let lookup = |iface: &mut Interface| {
+        iface.inner.lookup_hardware_addr(
+            MockTxToken,
+            &local_ip_addr.into(),
+            &remote_ip_addr.into(),
+            &RouteKey::default(),
+            &mut iface.fragmenter,
+        )
+    };
+
+    // An unsolicited reply does not change the entry learned from our request.
+    assert_eq!(lookup(&mut iface), Err(DispatchError::NeighborPending));
+    recv_arp(&mut iface, ArpOperation::Reply, remote_hw_addr);
+    recv_arp(&mut iface, ArpOperation::Reply, attacker_hw_addr);
+    assert_eq!(
+        lookup(&mut iface),
+        Ok((HardwareAddress::Ethernet(remote_hw_addr), MockTxToken))
+    );
+
+    // Once the entry expired, only the answer to the next request is trusted.
+    iface.inner.now += Duration::from_secs(120);
+    assert_eq!(lookup(&mut iface), Err(DispatchError::NeighborPending));
+    recv_arp(&mut iface, ArpOperation::Request, remote_hw_addr);
+    recv_arp(&mut iface, ArpOperation::Request, attacker_hw_addr);
+    assert_eq!(
+        lookup(&mut iface),
+        Ok((HardwareAddress::Ethernet(remote_hw_addr), MockTxToken))
+    );
+
+    // A locked entry is not changed by the answers to our requests either.
+    assert_eq!(
+        iface.lock_neighbor(remote_ip_addr),
+        Some(remote_hw_addr.into())
+    );
+    assert_eq!(
+        iface.neighbors().next().map(|entry| entry.state),
+        Some(NeighborState::Permanent)
+    );
+    iface.inner.now += Duration::from_secs(120);
+    recv_arp(&mut iface, ArpOperation::Reply, attacker_hw_addr);
+    assert_eq!(
+        lookup(&mut iface),
+        Ok((HardwareAddress::Ethernet(remote_hw_addr), MockTxToken))
+    );
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_filter() {
+    use crate::iface::FilterRule;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+    );
+    socket.bind(5000).unwrap();
+    socket
+        .send_slice(
+            b"abcdef",
+            IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5000),
+        )
+        .unwrap();
+    let handle = sockets.add(socket);
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let frame = Ipv4PacketWire::new_checked(&packets[0][..]).unwrap();
+
+    // Dropped packets don't reach the sockets.
+    let rule = FilterRule {
+        direction: Some(FilterDirection::Ingress),
+        protocol: Some(IpProtocol::Udp),
+        dst_port: Some(5000),
+        ..FilterRule::new(FilterAction::Drop)
+    };
+    iface.filter_mut().add_rule(rule).unwrap();
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &frame,
+            &mut iface.fragments
+        ),
+        None
+    );
+    assert!(!sockets.get_mut::<udp::Socket>(handle).can_recv());
+
+    // Rejected packets are answered with an ICMP error.
+    iface.filter_mut().update(|rules| {
+        rules[0].action = FilterAction::Reject;
+    });
+    let reply = iface
+        .inner
+        .process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &frame,
+            &mut iface.fragments,
+        )
+        .unwrap();
+    assert!(matches!(
+        reply.payload(),
+        IpPayload::Icmpv4(Icmpv4Repr::DstUnreachable {
+            reason: Icmpv4DstUnreachable::CommProhibited,
+            ..
+        })
+    ));
+    assert!(!sockets.get_mut::<udp::Socket>(handle).can_recv());
+
+    // Packets to other ports go through.
+    iface.filter_mut().update(|rules| {
+        rules[0].dst_port = Some(5001);
+    });
+    assert_eq!(
+        iface.inner.process_ipv4(
+            &mut sockets,
+            PacketMeta::default(),
+            &frame,
+            &mut iface.fragments
+        ),
+        None
+    );
+    assert_eq!(
+        sockets.get_mut::<udp::Socket>(handle).recv().unwrap().0,
+        b"abcdef"
+    );
+
+    // Packets denied on egress are not sent.
+    iface.filter_mut().update(|rules| {
+        rules[0].direction = Some(FilterDirection::Egress);
+        rules[0].dst_port = Some(5000);
+    });
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    socket
+        .send_slice(
+            b"abcdef",
+            IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5000),
+        )
+        .unwrap();
+    iface.socket_egress(&mut device, &mut sockets);
+    assert!(recv_all(&mut device, Instant::ZERO).is_empty());
+}
+
+#[test]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-udp"))]
+fn test_policy_route() {
+    use crate::iface::Route;
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    let gateway_a = Ipv4Address::new(127, 0, 0, 2);
+    let gateway_b = Ipv4Address::new(127, 0, 0, 3);
+    let gateway_c = Ipv4Address::new(127, 0, 0, 4);
+    let hw_addr = |n| EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, n]);
+    iface
+        .add_static_neighbor(gateway_a, hw_addr(2).into())
+        .unwrap();
+    iface
+        .add_static_neighbor(gateway_b, hw_addr(3).into())
+        .unwrap();
+    iface
+        .add_static_neighbor(gateway_c, hw_addr(4).into())
+        .unwrap();
+    iface.routes_mut().update(|routes| {
+        routes.push(Route::new_ipv4_gateway(gateway_a)).unwrap();
+        routes
+            .push(Route {
+                mark: Some(1),
+                ..Route::new_ipv4_gateway(gateway_b)
+            })
+            .unwrap();
+        routes
+            .push(Route {
+                dscp: Some(46),
+                ..Route::new_ipv4_gateway(gateway_c)
+            })
+            .unwrap();
+    });
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 3], vec![0; 48]),
+    );
+    socket.bind(68).unwrap();
+    let handle = sockets.add(socket);
+
+    // Returns the hardware address the packets sent by the socket go to.
+    let mut send = |iface: &mut Interface, sockets: &mut SocketSet, dscp| {
+        let endpoint = IpEndpoint::new(IpAddress::v4(10, 0, 0, 1), 67);
+        let meta = udp::UdpMetadata {
+            dscp,
+            ..endpoint.into()
+        };
+        let socket = sockets.get_mut::<udp::Socket>(handle);
+        socket.send_slice(b"abcdef", meta).unwrap();
+        assert!(iface.socket_egress(&mut device, sockets));
+        let (rx, _) = device.receive(Instant::ZERO).unwrap();
+        rx.consume(|frame| EthernetFrame::new_checked(&frame[..]).unwrap().dst_addr())
+    };
+
+    assert_eq!(send(&mut iface, &mut sockets, 0), hw_addr(2));
+    assert_eq!(send(&mut iface, &mut sockets, 46), hw_addr(4));
+    sockets.set_mark(handle, Some(1));
+    assert_eq!(sockets.mark(handle), Some(1));
+    assert_eq!(send(&mut iface, &mut sockets, 0), hw_addr(3));
+
+    // Policy routes are kept when the default route is replaced.
+    iface.routes_mut().remove_default_ipv4_route();
+    assert_eq!(send(&mut iface, &mut sockets, 0), hw_addr(3));
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_stats() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 32]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 2], vec![0; 32]),
+    );
+    socket.bind(5000).unwrap();
+    let endpoint = IpEndpoint::new(IpAddress::v4(127, 0, 0, 1), 5000);
+    socket.send_slice(b"abcdef", endpoint).unwrap();
+    let handle = sockets.add(socket);
+
+    // The datagram is sent, then received back through the loopback device.
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let stats = iface.stats();
+    assert_eq!((stats.tx_packets, stats.tx_bytes), (1, 34));
+    assert_eq!((stats.ipv4.tx_packets, stats.ipv4.tx_bytes), (1, 34));
+    assert_eq!((stats.udp.tx_packets, stats.udp.tx_bytes), (1, 14));
+    assert_eq!(stats.rx_packets, 0);
+
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert!(sockets.get_mut::<udp::Socket>(handle).can_recv());
+    let stats = iface.stats();
+    assert_eq!((stats.rx_packets, stats.rx_bytes), (1, 34));
+    assert_eq!((stats.ipv4.rx_packets, stats.ipv4.rx_bytes), (1, 34));
+    assert_eq!((stats.udp.rx_packets, stats.udp.rx_bytes), (1, 14));
+    assert_eq!(stats.tcp, crate::iface::ProtocolStats::default());
+
+    // A datagram with a wrong checksum is counted, and dropped.
+    sockets
+        .get_mut::<udp::Socket>(handle)
+        .send_slice(b"abcdef", endpoint)
+        .unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let mut packet = device.queue.pop_front().unwrap();
+    packet[26] ^= 0xff;
+    device.queue.push_back(packet);
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    let stats = iface.stats();
+    assert_eq!(stats.rx_packets, 2);
+    assert_eq!(stats.ipv4.rx_packets, 2);
+    assert_eq!(stats.udp.rx_packets, 1);
+    assert_eq!(stats.udp.checksum_errors, 1);
+
+    iface.reset_stats();
+    assert_eq!(iface.stats(), Stats::default());
+}
+
+#[test]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-udp"))]
+fn test_link_down() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static LINK_CHANGES: AtomicUsize = AtomicUsize::new(0);
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    iface.set_link_callback(Some(|_up, _sockets| {
+        LINK_CHANGES.fetch_add(1, Ordering::Relaxed);
+    }));
+    let remote_ip_addr = Ipv4Address::new(127, 0, 0, 2);
+    iface.inner.neighbor_cache.fill(
+        remote_ip_addr.into(),
+        EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x02]).into(),
+        Instant::ZERO,
+    );
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 32]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 32]),
+    );
+    socket.bind(5000).unwrap();
+    socket
+        .send_slice(b"abcdef", IpEndpoint::new(remote_ip_addr.into(), 5000))
+        .unwrap();
+    let handle = sockets.add(socket);
+
+    // Nothing is sent while the link is down, and the neighbors are forgotten.
+    iface.set_link_up(false, &mut sockets);
+    assert!(!iface.link_up());
+    assert_eq!(LINK_CHANGES.load(Ordering::Relaxed), 1);
+    assert_eq!(iface.neighbors().count(), 0);
+    assert_eq!(iface.poll_at(Instant::ZERO, &sockets), None);
+    assert!(!iface.poll(Instant::ZERO, &mut device, &mut sockets));
+    assert!(device.queue.is_empty());
+    assert!(!sockets.get_mut::<udp::Socket>(handle).can_send());
+
+    // Setting the same state again does nothing.
+    iface.set_link_up(false, &mut sockets);
+    assert_eq!(LINK_CHANGES.load(Ordering::Relaxed), 1);
+
+    // Once the link is up, the neighbor is resolved again.
+    iface.set_link_up(true, &mut sockets);
+    assert!(iface.link_up());
+    assert_eq!(LINK_CHANGES.load(Ordering::Relaxed), 2);
+    iface.socket_egress(&mut device, &mut sockets);
+    let frame = device.queue.pop_front().unwrap();
+    let frame = EthernetFrame::new_checked(&frame[..]).unwrap();
+    assert_eq!(frame.ethertype(), EthernetProtocol::Arp);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_config_callback() {
+    use core::sync::atomic::{AtomicU8, Ordering};
+
+    // The last change reported, as bits: 1 for the addresses, 2 for the routes.
+    static CHANGES: AtomicU8 = AtomicU8::new(0);
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    // The addresses set up before the callback are not reported to it.
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    iface.set_config_callback(Some(|change, _sockets| {
+        let bits = change.addrs as u8 | (change.routes as u8) << 1;
+        CHANGES.store(bits, Ordering::Relaxed);
+    }));
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(CHANGES.load(Ordering::Relaxed), 0);
+
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(IpAddress::v4(192, 168, 1, 1), 24))
+            .unwrap();
+    });
+    iface
+        .routes_mut()
+        .add_default_ipv4_route(Ipv4Address::new(192, 168, 1, 254))
+        .unwrap();
+    assert_eq!(CHANGES.load(Ordering::Relaxed), 0);
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(CHANGES.swap(0, Ordering::Relaxed), 0b11);
+
+    // Changes are only reported once, and updates that change nothing are not reported.
+    iface.update_ip_addrs(|_addrs| {});
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(CHANGES.load(Ordering::Relaxed), 0);
+
+    iface.update_ip_addrs(|addrs| {
+        addrs.pop();
+    });
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(CHANGES.load(Ordering::Relaxed), 0b01);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-icmp"))]
+fn test_icmpv4_socket_dscp() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = icmp::Socket::new(
+        icmp::PacketBuffer::new(vec![icmp::PacketMetadata::EMPTY], vec![0; 24]),
+        icmp::PacketBuffer::new(vec![icmp::PacketMetadata::EMPTY], vec![0; 24]),
+    );
+    socket.bind(icmp::Endpoint::Ident(0x1234)).unwrap();
+    socket.set_dscp(46);
+    socket.set_ecn(1);
+
+    let echo_repr = Icmpv4Repr::EchoRequest {
+        ident: 0x1234,
+        seq_no: 1,
+        data: &[0xff; 8],
+    };
+    let mut bytes = [0; 16];
+    echo_repr.emit(
+        &mut Icmpv4Packet::new_unchecked(&mut bytes[..]),
+        &ChecksumCapabilities::default(),
+    );
+    socket
+        .send_slice(&bytes, IpAddress::v4(127, 0, 0, 1))
+        .unwrap();
+    sockets.add(socket);
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packet = device.queue.pop_front().unwrap();
+    let packet = Ipv4PacketWire::new_checked(&packet[..]).unwrap();
+    assert_eq!((packet.dscp(), packet.ecn()), (46, 1));
+    assert!(packet.verify_checksum());
+}
+
+#[test]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-tcp"))]
+fn test_local_delivery() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    iface.set_local_delivery(true);
+    assert!(iface.local_delivery());
+
+    let tcp_socket = || {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 64]),
+            tcp::SocketBuffer::new(vec![0; 64]),
+        )
+    };
+    let mut server = tcp_socket();
+    server.listen(8080).unwrap();
+    let server = sockets.add(server);
+    let mut client = tcp_socket();
+    client
+        .connect(
+            iface.context(),
+            (Ipv4Address::new(127, 0, 0, 1), 8080),
+            49152,
+        )
+        .unwrap();
+    let client = sockets.add(client);
+
+    // The connection is established, and data exchanged, without going through the device.
+    let mut timestamp = Instant::ZERO;
+    while iface.poll(timestamp, &mut device, &mut sockets) {
+        timestamp += Duration::from_millis(1);
+    }
+    assert!(device.queue.is_empty());
+    assert!(sockets.get::<tcp::Socket>(client).may_send());
+    assert!(sockets.get::<tcp::Socket>(server).may_recv());
+
+    sockets
+        .get_mut::<tcp::Socket>(client)
+        .send_slice(b"hello")
+        .unwrap();
+    iface.poll(timestamp, &mut device, &mut sockets);
+    assert!(device.queue.is_empty());
+    let mut buf = [0; 8];
+    assert_eq!(
+        sockets.get_mut::<tcp::Socket>(server).recv_slice(&mut buf),
+        Ok(5)
+    );
+    assert_eq!(&buf[..5], b"hello");
+}
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_destination_cache(#[case] medium: Medium) {
+    let (mut iface, _sockets, _device) = setup(medium);
+
+    let local_ip_addr = IpAddress::v4(127, 0, 0, 1);
+    let remote_ip_addr = IpAddress::v4(10, 0, 0, 1);
+    let router_1 = Ipv4Address::new(127, 0, 0, 2);
+    let router_2 = Ipv4Address::new(127, 0, 0, 3);
+    iface.routes_mut().add_default_ipv4_route(router_1).unwrap();
+
+    let lookup = |iface: &mut Interface| {
+        iface.inner.lookup_hardware_addr(
+            MockTxToken,
+            &local_ip_addr,
+            &remote_ip_addr,
+            &RouteKey::default(),
+            &mut iface.fragmenter,
+        )
+    };
+
+    // The router is remembered for the next packets.
+    assert!(lookup(&mut iface).is_err());
+    let entry = iface.destinations().next().unwrap();
+    assert_eq!(entry.dst_addr, remote_ip_addr);
+    assert_eq!(entry.next_hop, Some(router_1.into()));
+    assert_eq!(entry.path_mtu, None);
+    assert_eq!(iface.destinations().count(), 1);
+
+    // Changing the routes makes the router be looked up again.
+    iface.routes_mut().add_default_ipv4_route(router_2).unwrap();
+    assert_eq!(iface.destinations().next().unwrap().next_hop, None);
+    assert!(lookup(&mut iface).is_err());
+    assert_eq!(
+        iface.destinations().next().unwrap().next_hop,
+        Some(router_2.into())
+    );
+
+    // The hints apply to the TCP connections to the destination.
+    iface.set_destination_hints(remote_ip_addr, Some(Duration::from_millis(40)), Some(1000));
+    assert_eq!(
+        iface.inner.rtt_hint(&remote_ip_addr),
+        Some(Duration::from_millis(40))
+    );
+    assert_eq!(iface.inner.route_mss(&remote_ip_addr), Some(1000));
+
+    iface.flush_destinations();
+    assert_eq!(iface.destinations().count(), 0);
+    assert_eq!(iface.inner.route_mss(&remote_ip_addr), None);
+}
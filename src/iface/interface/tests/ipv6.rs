@@ -10,6 +10,7 @@ fn parse_ipv6(data: &[u8]) -> crate::wire::Result<IpPacket<'_>> {
         IpProtocol::Igmp => todo!(),
         IpProtocol::Tcp => todo!(),
         IpProtocol::Udp => todo!(),
+        IpProtocol::UdpLite => todo!(),
         IpProtocol::Ipv6Route => todo!(),
         IpProtocol::Ipv6Frag => todo!(),
         IpProtocol::Icmpv6 => {
@@ -382,6 +383,267 @@ fn unknown_proto(#[case] medium: Medium) {
     );
 }
 
+/// Build an IPv6 packet from fdbe::2 to `dst_addr`, made of the `ext_hdrs` extension
+/// headers, starting with a `next_header` one, and of an ICMPv6 echo request.
+#[cfg(feature = "medium-ethernet")]
+fn ext_hdr_packet(
+    dst_addr: Ipv6Address,
+    next_header: IpProtocol,
+    ext_hdrs: &[u8],
+) -> (Ipv6Repr, std::vec::Vec<u8>) {
+    let src_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    let icmp_repr = Icmpv6Repr::EchoRequest {
+        ident: 42,
+        seq_no: 420,
+        data: b"Lorem Ipsum",
+    };
+    let ip_repr = Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header,
+        payload_len: ext_hdrs.len() + icmp_repr.buffer_len(),
+        hop_limit: 64,
+    };
+    let mut bytes = vec![0u8; ip_repr.buffer_len() + ip_repr.payload_len];
+    ip_repr.emit(&mut Ipv6PacketWire::new_unchecked(&mut bytes));
+    bytes[ip_repr.buffer_len()..][..ext_hdrs.len()].copy_from_slice(ext_hdrs);
+    icmp_repr.emit(
+        &src_addr.into(),
+        &dst_addr.into(),
+        &mut Icmpv6Packet::new_unchecked(&mut bytes[ip_repr.buffer_len() + ext_hdrs.len()..]),
+        &ChecksumCapabilities::default(),
+    );
+    (ip_repr, bytes)
+}
+
+#[cfg(all(
+    feature = "medium-ip",
+    feature = "medium-ethernet",
+    feature = "medium-ieee802154"
+))]
+fn echo_reply() -> IpPacket<'static> {
+    IpPacket::new_ipv6(
+        Ipv6Repr {
+            src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+            dst_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2),
+            hop_limit: 64,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: 19,
+        },
+        IpPayload::Icmpv6(Icmpv6Repr::EchoReply {
+            ident: 42,
+            seq_no: 420,
+            data: b"Lorem Ipsum",
+        }),
+    )
+}
+
+#[cfg(all(
+    feature = "medium-ip",
+    feature = "medium-ethernet",
+    feature = "medium-ieee802154"
+))]
+fn param_problem<'a>(
+    ip_repr: Ipv6Repr,
+    data: &'a [u8],
+    reason: Icmpv6ParamProblem,
+    pointer: u32,
+) -> IpPacket<'a> {
+    let icmp_repr = Icmpv6Repr::ParamProblem {
+        reason,
+        pointer,
+        header: ip_repr,
+        data: &data[ip_repr.buffer_len()..],
+    };
+    IpPacket::new_ipv6(
+        Ipv6Repr {
+            src_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+            dst_addr: ip_repr.src_addr,
+            hop_limit: 64,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+        },
+        IpPayload::Icmpv6(icmp_repr),
+    )
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn destination_options(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+    let unicast = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    // Unknown options that can be skipped.
+    let (_, data) = ext_hdr_packet(
+        unicast,
+        IpProtocol::Ipv6Opts,
+        &[0x3a, 0x00, 0x01, 0x00, 0x0f, 0x00, 0x01, 0x00],
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(echo_reply())
+    );
+
+    // Unknown options to report, after a PadN one.
+    let opts = [0x3a, 0x00, 0x01, 0x00, 0xc5, 0x00, 0x01, 0x00];
+    let (ip_repr, data) = ext_hdr_packet(unicast, IpProtocol::Ipv6Opts, &opts);
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(param_problem(
+            ip_repr,
+            &data,
+            Icmpv6ParamProblem::UnrecognizedOption,
+            44
+        ))
+    );
+
+    // Packets sent to a multicast address are only reported for some option types.
+    let (_, data) = ext_hdr_packet(
+        Ipv6Address::LINK_LOCAL_ALL_NODES,
+        IpProtocol::Ipv6Opts,
+        &opts,
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    let opts = [0x3a, 0x00, 0x01, 0x00, 0x85, 0x00, 0x01, 0x00];
+    let (ip_repr, data) = ext_hdr_packet(
+        Ipv6Address::LINK_LOCAL_ALL_NODES,
+        IpProtocol::Ipv6Opts,
+        &opts,
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(param_problem(
+            ip_repr,
+            &data,
+            Icmpv6ParamProblem::UnrecognizedOption,
+            44
+        ))
+    );
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn routing_header(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+    let dst_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    // We are the final destination.
+    let (_, data) = ext_hdr_packet(
+        dst_addr,
+        IpProtocol::Ipv6Route,
+        &[0x3a, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(echo_reply())
+    );
+
+    // The deprecated type 0 routing header is rejected, after a Destination Options header.
+    let (ip_repr, data) = ext_hdr_packet(
+        dst_addr,
+        IpProtocol::Ipv6Opts,
+        &[
+            0x2b, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, // Destination Options
+            0x3a, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // Routing
+        ],
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(param_problem(
+            ip_repr,
+            &data,
+            Icmpv6ParamProblem::ErroneousHdrField,
+            50
+        ))
+    );
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn misplaced_hop_by_hop(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    // The Hop-by-Hop Options header must come first.
+    let (ip_repr, data) = ext_hdr_packet(
+        Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+        IpProtocol::Ipv6Opts,
+        &[
+            0x00, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, // Destination Options
+            0x3a, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00, // Hop-by-Hop Options
+        ],
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(param_problem(
+            ip_repr,
+            &data,
+            Icmpv6ParamProblem::UnrecognizedNxtHdr,
+            48
+        ))
+    );
+
+    // Nothing follows a No Next Header.
+    let (_, data) = ext_hdr_packet(
+        Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+        IpProtocol::Ipv6Opts,
+        &[0x3b, 0x00, 0x01, 0x04, 0x00, 0x00, 0x00, 0x00],
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+}
+
 #[rstest]
 #[case::ethernet(Medium::Ethernet)]
 #[cfg(feature = "medium-ethernet")]
@@ -615,6 +877,7 @@ fn test_handle_valid_ndisc_request(#[case] medium: Medium) {
             MockTxToken,
             &IpAddress::Ipv6(local_ip_addr),
             &IpAddress::Ipv6(remote_ip_addr),
+            &RouteKey::default(),
             &mut iface.fragmenter,
         ),
         Ok((HardwareAddress::Ethernet(remote_hw_addr), MockTxToken))
@@ -622,114 +885,2112 @@ fn test_handle_valid_ndisc_request(#[case] medium: Medium) {
 }
 
 #[rstest]
-#[case(Medium::Ip)]
-#[cfg(feature = "medium-ip")]
 #[case(Medium::Ethernet)]
 #[cfg(feature = "medium-ethernet")]
-#[case(Medium::Ieee802154)]
-#[cfg(feature = "medium-ieee802154")]
-fn test_solicited_node_addrs(#[case] medium: Medium) {
-    let (mut iface, _, _) = setup(medium);
-    let mut new_addrs = heapless::Vec::<IpCidr, IFACE_MAX_ADDR_COUNT>::new();
-    new_addrs
-        .push(IpCidr::new(IpAddress::v6(0xfe80, 0, 0, 0, 1, 2, 0, 2), 64))
-        .unwrap();
-    new_addrs
-        .push(IpCidr::new(
-            IpAddress::v6(0xfe80, 0, 0, 0, 3, 4, 0, 0xffff),
-            64,
-        ))
-        .unwrap();
-    iface.update_ip_addrs(|addrs| {
-        new_addrs.extend(addrs.to_vec());
-        *addrs = new_addrs;
-    });
-    assert!(iface
-        .inner
-        .has_solicited_node(Ipv6Address::new(0xff02, 0, 0, 0, 0, 1, 0xff00, 0x0002)));
-    assert!(iface
-        .inner
-        .has_solicited_node(Ipv6Address::new(0xff02, 0, 0, 0, 0, 1, 0xff00, 0xffff)));
-    assert!(!iface
-        .inner
-        .has_solicited_node(Ipv6Address::new(0xff02, 0, 0, 0, 0, 1, 0xff00, 0x0003)));
-}
+fn test_router_advertisement_flags(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
 
-#[rstest]
-#[case(Medium::Ip)]
-#[cfg(all(feature = "socket-udp", feature = "medium-ip"))]
-#[case(Medium::Ethernet)]
-#[cfg(all(feature = "socket-udp", feature = "medium-ethernet"))]
-#[case(Medium::Ieee802154)]
-#[cfg(all(feature = "socket-udp", feature = "medium-ieee802154"))]
-fn test_icmp_reply_size(#[case] medium: Medium) {
-    use crate::wire::Icmpv6DstUnreachable;
-    use crate::wire::IPV6_MIN_MTU as MIN_MTU;
-    const MAX_PAYLOAD_LEN: usize = 1192;
+    let advert = |src_addr: Ipv6Address, flags: NdiscRouterFlags| {
+        let advert = Icmpv6Repr::Ndisc(NdiscRepr::RouterAdvert {
+            hop_limit: 64,
+            flags,
+            router_lifetime: Duration::from_secs(1800),
+            reachable_time: Duration::ZERO,
+            retrans_time: Duration::ZERO,
+            lladdr: None,
+            mtu: None,
+            prefix_info: None,
+            rdnss: None,
+            dnssl: None,
+            sixlowpan_context: None,
+            pref64: None,
+        });
+        let ip_repr = Ipv6Repr {
+            src_addr,
+            dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 0xff,
+            payload_len: advert.buffer_len(),
+        };
+        let mut bytes = vec![0u8; ip_repr.buffer_len() + advert.buffer_len()];
+        ip_repr.emit(&mut Ipv6PacketWire::new_unchecked(&mut bytes));
+        advert.emit(
+            &src_addr.into(),
+            &Ipv6Address::LINK_LOCAL_ALL_NODES.into(),
+            &mut Icmpv6Packet::new_unchecked(&mut bytes[ip_repr.buffer_len()..]),
+            &ChecksumCapabilities::default(),
+        );
+        bytes
+    };
 
-    let (mut iface, mut sockets, _device) = setup(medium);
+    assert_eq!(iface.ipv6_router_flags(), None);
 
-    let src_addr = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
-    let dst_addr = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    let data = advert(
+        Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+        NdiscRouterFlags::MANAGED,
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(iface.ipv6_router_flags(), Some(NdiscRouterFlags::MANAGED));
 
-    // UDP packet that if not tructated will cause a icmp port unreachable reply
-    // to exeed the minimum mtu bytes in length.
-    let udp_repr = UdpRepr {
-        src_port: 67,
-        dst_port: 68,
+    // Router Advertisements from global addresses are invalid.
+    let data = advert(
+        Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2),
+        NdiscRouterFlags::OTHER,
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(iface.ipv6_router_flags(), Some(NdiscRouterFlags::MANAGED));
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_router_advertisement_hop_limit() {
+    let (mut iface, mut sockets, _device) = setup(Medium::Ethernet);
+
+    let advert = |hop_limit: u8| {
+        emit_router_advert(NdiscRepr::RouterAdvert {
+            hop_limit,
+            flags: NdiscRouterFlags::empty(),
+            router_lifetime: Duration::from_secs(1800),
+            reachable_time: Duration::ZERO,
+            retrans_time: Duration::ZERO,
+            lladdr: None,
+            mtu: None,
+            prefix_info: None,
+            rdnss: None,
+            dnssl: None,
+            sixlowpan_context: None,
+            pref64: None,
+        })
     };
-    let mut bytes = vec![0xff; udp_repr.header_len() + MAX_PAYLOAD_LEN];
-    let mut packet = UdpPacket::new_unchecked(&mut bytes[..]);
-    udp_repr.emit(
-        &mut packet,
-        &src_addr.into(),
-        &dst_addr.into(),
-        MAX_PAYLOAD_LEN,
-        |buf| fill_slice(buf, 0x2a),
-        &ChecksumCapabilities::default(),
+    // The hop limit of the echo reply sent by the interface.
+    let echo_hop_limit = |iface: &mut Interface, sockets: &mut SocketSet| {
+        let (_, data) = ext_hdr_packet(
+            Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+            IpProtocol::Icmpv6,
+            &[],
+        );
+        iface
+            .inner
+            .process_ipv6(
+                sockets,
+                PacketMeta::default(),
+                &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+            )
+            .map(|packet| packet.ip_repr().hop_limit())
+    };
+
+    assert_eq!(iface.hop_limit(), 64);
+    assert_eq!(iface.ipv6_hop_limit(), 64);
+    iface.set_hop_limit(100);
+    assert_eq!(iface.ipv6_hop_limit(), 100);
+    assert_eq!(echo_hop_limit(&mut iface, &mut sockets), Some(100));
+
+    // A Cur Hop Limit of zero is unspecified.
+    let data = advert(0);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
     );
+    assert_eq!(iface.ipv6_hop_limit(), 100);
 
-    let ip_repr = Ipv6Repr {
-        src_addr,
-        dst_addr,
-        next_header: IpProtocol::Udp,
+    let data = advert(200);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.ipv6_hop_limit(), 200);
+    assert_eq!(iface.hop_limit(), 100);
+    let remote_addr = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2).into();
+    assert_eq!(iface.inner.hop_limit(&remote_addr), 200);
+    assert_eq!(echo_hop_limit(&mut iface, &mut sockets), Some(200));
+
+    // Setting the hop limit again overrides the advertised one.
+    iface.set_hop_limit(32);
+    assert_eq!(iface.ipv6_hop_limit(), 32);
+    assert_eq!(echo_hop_limit(&mut iface, &mut sockets), Some(32));
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn router_advert(
+    router_lifetime: Duration,
+    prefix_info: Option<NdiscPrefixInformation>,
+) -> std::vec::Vec<u8> {
+    emit_router_advert(NdiscRepr::RouterAdvert {
         hop_limit: 64,
-        payload_len: udp_repr.header_len() + MAX_PAYLOAD_LEN,
-    };
-    let payload = packet.into_inner();
+        flags: NdiscRouterFlags::empty(),
+        router_lifetime,
+        reachable_time: Duration::ZERO,
+        retrans_time: Duration::ZERO,
+        lladdr: None,
+        mtu: None,
+        prefix_info,
+        rdnss: None,
+        dnssl: None,
+        sixlowpan_context: None,
+        pref64: None,
+    })
+}
 
-    let expected_icmp_repr = Icmpv6Repr::DstUnreachable {
-        reason: Icmpv6DstUnreachable::PortUnreachable,
-        header: ip_repr,
-        data: &payload[..MAX_PAYLOAD_LEN],
-    };
+#[cfg(feature = "medium-ethernet")]
+fn emit_router_advert(advert: NdiscRepr) -> std::vec::Vec<u8> {
+    emit_ndisc(
+        Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+        Ipv6Address::LINK_LOCAL_ALL_NODES,
+        advert,
+    )
+}
 
-    let expected_ip_repr = Ipv6Repr {
-        src_addr: dst_addr,
-        dst_addr: src_addr,
+#[cfg(feature = "medium-ethernet")]
+fn emit_ndisc(src_addr: Ipv6Address, dst_addr: Ipv6Address, repr: NdiscRepr) -> std::vec::Vec<u8> {
+    let repr = Icmpv6Repr::Ndisc(repr);
+    let ip_repr = Ipv6Repr {
+        src_addr,
+        dst_addr,
         next_header: IpProtocol::Icmpv6,
-        hop_limit: 64,
-        payload_len: expected_icmp_repr.buffer_len(),
+        hop_limit: 0xff,
+        payload_len: repr.buffer_len(),
     };
+    let mut bytes = vec![0u8; ip_repr.buffer_len() + repr.buffer_len()];
+    ip_repr.emit(&mut Ipv6PacketWire::new_unchecked(&mut bytes));
+    repr.emit(
+        &src_addr.into(),
+        &dst_addr.into(),
+        &mut Icmpv6Packet::new_unchecked(&mut bytes[ip_repr.buffer_len()..]),
+        &ChecksumCapabilities::default(),
+    );
+    bytes
+}
 
+#[cfg(feature = "medium-ethernet")]
+fn prefix_info(flags: NdiscPrefixInfoFlags, valid_lifetime: Duration) -> NdiscPrefixInformation {
+    NdiscPrefixInformation {
+        prefix_len: 64,
+        flags,
+        valid_lifetime,
+        preferred_lifetime: valid_lifetime,
+        prefix: Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0),
+    }
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn setup_slaac() -> (Interface, SocketSet<'static>, Loopback) {
+    let (mut iface, sockets, device) = setup(Medium::Ethernet);
+    iface.update_ip_addrs(|addrs| addrs.clear());
+    iface.set_slaac(true);
+    (iface, sockets, device)
+}
+
+#[cfg(feature = "medium-ethernet")]
+const SLAAC_ADDR: Ipv6Address = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0x200, 0xff, 0xfe00, 0);
+
+#[rstest]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+fn test_slaac(#[case] medium: Medium) {
+    let (mut iface, mut sockets, mut device) = setup(medium);
+    iface.update_ip_addrs(|addrs| addrs.clear());
+    assert!(!iface.slaac());
+    iface.set_slaac(true);
+
+    let router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    let data = router_advert(
+        Duration::from_secs(1800),
+        Some(prefix_info(
+            NdiscPrefixInfoFlags::ON_LINK | NdiscPrefixInfoFlags::ADDRCONF,
+            Duration::from_secs(3600),
+        )),
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(iface.ip_addrs(), &[IpCidr::new(SLAAC_ADDR.into(), 64)]);
+    assert_eq!(
+        iface.inner.routes.lookup(
+            &Ipv6Address::new(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1).into(),
+            Instant::ZERO
+        ),
+        Some(router.into())
+    );
+    assert_eq!(
+        iface.inner.routes.lookup(
+            &Ipv6Address::new(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1).into(),
+            Instant::from_secs(1801)
+        ),
+        None
+    );
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(1800))
+    );
+    iface.poll(Instant::from_secs(1800), &mut device, &mut sockets);
+    assert_eq!(iface.ipv6_default_routers().count(), 0);
+    assert_eq!(
+        iface.poll_at(Instant::from_secs(1800), &sockets),
+        Some(Instant::from_secs(3600))
+    );
+
+    iface.poll(Instant::from_secs(3599), &mut device, &mut sockets);
+    assert!(iface.has_ip_addr(SLAAC_ADDR));
+    iface.poll(Instant::from_secs(3600), &mut device, &mut sockets);
+    assert_eq!(iface.ip_addrs(), &[]);
+    assert_eq!(iface.poll_at(Instant::from_secs(3600), &sockets), None);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_slaac_disabled() {
+    let (mut iface, mut sockets, _device) = setup_slaac();
+    iface.set_slaac(false);
+
+    let data = router_advert(
+        Duration::from_secs(1800),
+        Some(prefix_info(
+            NdiscPrefixInfoFlags::ON_LINK | NdiscPrefixInfoFlags::ADDRCONF,
+            Duration::from_secs(3600),
+        )),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.ip_addrs(), &[]);
+    assert!(iface.routes_mut().remove_default_ipv6_route().is_none());
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_slaac_off_link_prefix() {
+    let (mut iface, mut sockets, _device) = setup_slaac();
+
+    let data = router_advert(
+        Duration::ZERO,
+        Some(prefix_info(
+            NdiscPrefixInfoFlags::ADDRCONF,
+            Duration::from_secs(3600),
+        )),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.ip_addrs(), &[IpCidr::new(SLAAC_ADDR.into(), 128)]);
+
+    // Prefixes that are not meant for autoconfiguration are ignored.
+    iface.update_ip_addrs(|addrs| addrs.clear());
+    let data = router_advert(
+        Duration::ZERO,
+        Some(prefix_info(
+            NdiscPrefixInfoFlags::ON_LINK,
+            Duration::from_secs(3600),
+        )),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.ip_addrs(), &[]);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_slaac_lifetimes() {
+    let (mut iface, mut sockets, mut device) = setup_slaac();
+    let router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    let flags = NdiscPrefixInfoFlags::ON_LINK | NdiscPrefixInfoFlags::ADDRCONF;
+
+    let data = router_advert(
+        Duration::from_secs(1800),
+        Some(prefix_info(flags, Duration::from_secs(0xffff_ffff))),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert!(iface.has_ip_addr(SLAAC_ADDR));
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(1800))
+    );
+
+    // A short lifetime doesn't expire the address right away (RFC 4862 § 5.5.3 (e)).
+    let data = router_advert(
+        Duration::ZERO,
+        Some(prefix_info(flags, Duration::from_secs(60))),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(2 * 60 * 60))
+    );
+
+    // A router lifetime of zero removes the default router.
+    assert!(iface.routes_mut().remove_default_ipv6_route().is_none());
+
+    iface.poll(Instant::from_secs(2 * 60 * 60), &mut device, &mut sockets);
+    assert_eq!(iface.ip_addrs(), &[]);
+
+    // Static default routes take precedence.
+    iface.routes_mut().add_default_ipv6_route(router).unwrap();
+    let data = router_advert(Duration::from_secs(1800), None);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    let route = iface.routes_mut().remove_default_ipv6_route().unwrap();
+    assert_eq!(route.via_router, router.into());
+    assert_eq!(route.expires_at, None);
+    assert!(iface.routes_mut().remove_default_ipv6_route().is_none());
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_slaac_temporary() {
+    let (mut iface, mut sockets, mut device) = setup_slaac();
+    assert!(!iface.slaac_temporary_addrs());
+    iface.set_slaac_temporary_addrs(true);
+    let remote = Ipv6Address::new(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1);
+
+    let data = router_advert(
+        Duration::from_secs(1800),
+        Some(prefix_info(
+            NdiscPrefixInfoFlags::ON_LINK | NdiscPrefixInfoFlags::ADDRCONF,
+            Duration::from_secs(0xffff_ffff),
+        )),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.ip_addrs().len(), 2);
+    assert_eq!(iface.ip_addrs()[0], IpCidr::new(SLAAC_ADDR.into(), 64));
+    #[allow(irrefutable_let_patterns)] // if only ipv6 is enabled
+    let IpCidr::Ipv6(temporary) = iface.ip_addrs()[1] else {
+        unreachable!()
+    };
+    assert_eq!(temporary.prefix_len(), 64);
+    let temporary = temporary.address();
+    assert_ne!(temporary, SLAAC_ADDR);
+    assert_eq!(temporary.as_bytes()[..8], SLAAC_ADDR.as_bytes()[..8]);
+
+    // Forget the router, so that only the addresses need polling.
+    let data = router_advert(Duration::ZERO, None);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+
+    // The temporary address is used for new connections.
+    assert_eq!(iface.inner.get_source_address_ipv6(remote), Some(temporary));
+
+    // It is replaced a bit before being deprecated, after at most a day.
+    let regen_at = iface.poll_at(Instant::ZERO, &sockets).unwrap();
+    assert!(regen_at > Instant::from_secs(24 * 60 * 60 * 3 / 5 - 2));
+    assert!(regen_at <= Instant::from_secs(24 * 60 * 60 - 2));
+    iface.poll(regen_at, &mut device, &mut sockets);
+    // If the address table is full, the old address is only replaced once deprecated.
+    let deprecated_at = regen_at + Duration::from_secs(2);
+    assert_eq!(iface.poll_at(regen_at, &sockets), Some(deprecated_at));
+    iface.poll(deprecated_at, &mut device, &mut sockets);
+    assert!(iface.has_ip_addr(SLAAC_ADDR));
+    let source = iface.inner.get_source_address_ipv6(remote).unwrap();
+    assert_ne!(source, SLAAC_ADDR);
+    assert_ne!(source, temporary);
+
+    // Disabling temporary addresses makes the stable one the preferred source again.
+    iface.set_slaac_temporary_addrs(false);
+    assert_eq!(
+        iface.inner.get_source_address_ipv6(remote),
+        Some(SLAAC_ADDR)
+    );
+}
+
+#[test]
+#[cfg(all(feature = "medium-ethernet", feature = "socket-dns"))]
+fn test_router_advertisement_dns() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    static DNS_CHANGES: AtomicUsize = AtomicUsize::new(0);
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    let server = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0x53);
+    iface.set_config_callback(Some(|change, _sockets| {
+        if change.dns {
+            DNS_CHANGES.fetch_add(1, Ordering::Relaxed);
+        }
+    }));
+
+    let mut dns_socket = dns::Socket::new(&[], vec![]);
+    dns_socket.set_router_servers(true);
+    let handle = sockets.add(dns_socket);
+
+    let data = emit_router_advert(NdiscRepr::RouterAdvert {
+        hop_limit: 64,
+        flags: NdiscRouterFlags::empty(),
+        router_lifetime: Duration::ZERO,
+        reachable_time: Duration::ZERO,
+        retrans_time: Duration::ZERO,
+        lladdr: None,
+        mtu: None,
+        prefix_info: None,
+        rdnss: Some(NdiscRecursiveDnsServer {
+            lifetime: Duration::from_secs(600),
+            servers: server.as_bytes(),
+        }),
+        dnssl: Some(NdiscDnsSearchList {
+            lifetime: Duration::from_secs(1200),
+            domains: b"\x07example\x03com\x00",
+        }),
+        sixlowpan_context: None,
+        pref64: None,
+    });
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(iface.ipv6_dns_servers(), &[server]);
+    assert_eq!(
+        iface.ipv6_dns_search_list().collect::<std::vec::Vec<_>>(),
+        ["example.com"]
+    );
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(600))
+    );
+
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(DNS_CHANGES.load(Ordering::Relaxed), 1);
+    assert!(sockets.get::<dns::Socket>(handle).accepts(
+        &IpRepr::Ipv6(Ipv6Repr {
+            src_addr: server,
+            dst_addr: Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1),
+            next_header: IpProtocol::Udp,
+            payload_len: 8,
+            hop_limit: 64,
+        }),
+        &UdpRepr {
+            src_port: 53,
+            dst_port: 49152,
+        }
+    ));
+
+    iface.poll(Instant::from_secs(600), &mut device, &mut sockets);
+    assert_eq!(iface.ipv6_dns_servers(), &[]);
+    assert_eq!(iface.ipv6_dns_search_list().count(), 1);
+    assert_eq!(DNS_CHANGES.load(Ordering::Relaxed), 2);
+    iface.poll(Instant::from_secs(1200), &mut device, &mut sockets);
+    assert_eq!(iface.ipv6_dns_search_list().count(), 0);
+    assert_eq!(DNS_CHANGES.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ethernet", feature = "proto-ipv4"))]
+fn test_router_advertisement_pref64() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    let prefix = Ipv6Cidr::new(Ipv6Address::new(0x2001, 0xdb8, 0x64, 0, 0, 0, 0, 0), 64);
+    let pref64_advert = |lifetime| {
+        emit_router_advert(NdiscRepr::RouterAdvert {
+            hop_limit: 64,
+            flags: NdiscRouterFlags::empty(),
+            router_lifetime: Duration::ZERO,
+            reachable_time: Duration::ZERO,
+            retrans_time: Duration::ZERO,
+            lladdr: None,
+            mtu: None,
+            prefix_info: None,
+            rdnss: None,
+            dnssl: None,
+            sixlowpan_context: None,
+            pref64: Some(NdiscPref64 {
+                lifetime,
+                prefix_len: prefix.prefix_len(),
+                prefix: prefix.address(),
+            }),
+        })
+    };
+
+    // A prefix set by hand is replaced by the advertised one.
+    iface.set_nat64_prefix(Some(Ipv6Cidr::NAT64_WELL_KNOWN_PREFIX));
+    let data = pref64_advert(Duration::from_secs(1800));
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.nat64_prefix(), Some(prefix));
+    assert_eq!(
+        iface.nat64_address(Ipv4Address::new(192, 0, 2, 33)),
+        Some(Ipv6Address::new(
+            0x2001, 0xdb8, 0x64, 0, 0xc0, 0x2, 0x2100, 0
+        ))
+    );
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(1800))
+    );
+
+    // The prefix is forgotten once its lifetime runs out.
+    iface.poll(Instant::from_secs(1800), &mut device, &mut sockets);
+    assert_eq!(iface.nat64_prefix(), None);
+    assert_eq!(iface.nat64_address(Ipv4Address::new(192, 0, 2, 33)), None);
+
+    // Or once it is advertised with a lifetime of zero.
+    let data = pref64_advert(Duration::from_secs(1800));
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.nat64_prefix(), Some(prefix));
+    let data = pref64_advert(Duration::ZERO);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.nat64_prefix(), None);
+}
+
+/// Receive the Neighbor Solicitation sent for Duplicate Address Detection, and return its
+/// destination and target addresses.
+#[cfg(feature = "medium-ethernet")]
+fn recv_dad_solicit(device: &mut Loopback) -> Option<(Ipv6Address, Ipv6Address)> {
+    let (rx, _tx) = device.receive(Instant::ZERO)?;
+    rx.consume(|frame| {
+        let frame = EthernetFrame::new_checked(&*frame).unwrap();
+        let packet = Ipv6PacketWire::new_checked(frame.payload()).unwrap();
+        let ip_repr = Ipv6Repr::parse(&packet).unwrap();
+        assert_eq!(ip_repr.src_addr, Ipv6Address::UNSPECIFIED);
+        let icmp_repr = Icmpv6Repr::parse(
+            &ip_repr.src_addr.into(),
+            &ip_repr.dst_addr.into(),
+            &Icmpv6Packet::new_checked(packet.payload()).unwrap(),
+            &ChecksumCapabilities::default(),
+        )
+        .unwrap();
+        match icmp_repr {
+            Icmpv6Repr::Ndisc(NdiscRepr::NeighborSolicit {
+                target_addr,
+                lladdr: None,
+            }) => Some((ip_repr.dst_addr, target_addr)),
+            repr => panic!("unexpected packet {repr:?}"),
+        }
+    })
+}
+
+#[cfg(feature = "medium-ethernet")]
+const TENTATIVE_ADDR: Ipv6Address = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+
+#[cfg(feature = "medium-ethernet")]
+fn setup_dad() -> (Interface, SocketSet<'static>, Loopback) {
+    let (mut iface, sockets, device) = setup(Medium::Ethernet);
+    iface.update_ip_addrs(|addrs| {
+        addrs.retain(|addr| addr.address() == Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1).into())
+    });
+    iface.set_dad_transmits(2);
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(TENTATIVE_ADDR, 64)))
+            .unwrap()
+    });
+    (iface, sockets, device)
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_dad() {
+    let (mut iface, sockets, mut device) = setup_dad();
+
+    // Addresses assigned before are not checked again.
+    assert_eq!(
+        iface.ip_addrs(),
+        &[IpCidr::new(IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 1), 64)]
+    );
+    assert_eq!(
+        iface.tentative_ipv6_addrs().collect::<std::vec::Vec<_>>(),
+        [Ipv6Cidr::new(TENTATIVE_ADDR, 64)]
+    );
+    assert_eq!(iface.poll_at(Instant::ZERO, &sockets), Some(Instant::ZERO));
+
+    assert!(iface.dad_egress(&mut device));
+    assert_eq!(
+        recv_dad_solicit(&mut device),
+        Some((TENTATIVE_ADDR.solicited_node(), TENTATIVE_ADDR))
+    );
+    assert!(!iface.dad_egress(&mut device));
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(1))
+    );
+
+    iface.inner.now = Instant::from_secs(1);
+    assert!(iface.dad_egress(&mut device));
+    assert_eq!(
+        recv_dad_solicit(&mut device),
+        Some((TENTATIVE_ADDR.solicited_node(), TENTATIVE_ADDR))
+    );
+    assert!(!iface.has_ip_addr(TENTATIVE_ADDR));
+    assert_eq!(iface.poll_dad_event(), None);
+
+    iface.inner.now = Instant::from_secs(2);
+    assert!(iface.dad_egress(&mut device));
+    assert_eq!(recv_dad_solicit(&mut device), None);
+    assert!(iface.has_ip_addr(TENTATIVE_ADDR));
+    assert_eq!(iface.tentative_ipv6_addrs().count(), 0);
+    assert_eq!(
+        iface.poll_dad_event(),
+        Some(DadEvent::Assigned(Ipv6Cidr::new(TENTATIVE_ADDR, 64)))
+    );
+    assert_eq!(iface.poll_dad_event(), None);
+
+    // Tentative addresses can be removed by hand.
+    iface.update_ip_addrs(|addrs| addrs.clear());
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(TENTATIVE_ADDR, 64)))
+            .unwrap()
+    });
+    assert_eq!(iface.tentative_ipv6_addrs().count(), 1);
+    iface.update_ip_addrs(|addrs| addrs.clear());
+    assert_eq!(iface.tentative_ipv6_addrs().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_dad_conflict_advert() {
+    let (mut iface, mut sockets, mut device) = setup_dad();
+    assert!(iface.dad_egress(&mut device));
+
+    let data = emit_ndisc(
+        TENTATIVE_ADDR,
+        Ipv6Address::LINK_LOCAL_ALL_NODES,
+        NdiscRepr::NeighborAdvert {
+            flags: NdiscNeighborFlags::OVERRIDE,
+            target_addr: TENTATIVE_ADDR,
+            lladdr: Some(EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x02]).into()),
+        },
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(
+        iface.poll_dad_event(),
+        Some(DadEvent::Conflict(Ipv6Cidr::new(TENTATIVE_ADDR, 64)))
+    );
+    assert_eq!(iface.tentative_ipv6_addrs().count(), 0);
+    assert!(!iface.has_ip_addr(TENTATIVE_ADDR));
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_dad_conflict_solicit() {
+    let (mut iface, mut sockets, _device) = setup_dad();
+    let assigned = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    // Another node is probing for one of our addresses.
+    let data = emit_ndisc(
+        Ipv6Address::UNSPECIFIED,
+        assigned.solicited_node(),
+        NdiscRepr::NeighborSolicit {
+            target_addr: assigned,
+            lladdr: None,
+        },
+    );
+    let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+        flags: NdiscNeighborFlags::OVERRIDE,
+        target_addr: assigned,
+        lladdr: Some(EthernetAddress::default().into()),
+    });
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(IpPacket::new_ipv6(
+            Ipv6Repr {
+                src_addr: assigned,
+                dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+                next_header: IpProtocol::Icmpv6,
+                hop_limit: 0xff,
+                payload_len: advert.buffer_len(),
+            },
+            IpPayload::Icmpv6(advert)
+        ))
+    );
+    assert_eq!(iface.poll_dad_event(), None);
+
+    // Another node is probing for our tentative address.
+    let data = emit_ndisc(
+        Ipv6Address::UNSPECIFIED,
+        TENTATIVE_ADDR.solicited_node(),
+        NdiscRepr::NeighborSolicit {
+            target_addr: TENTATIVE_ADDR,
+            lladdr: None,
+        },
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert_eq!(
+        iface.poll_dad_event(),
+        Some(DadEvent::Conflict(Ipv6Cidr::new(TENTATIVE_ADDR, 64)))
+    );
+    assert_eq!(iface.tentative_ipv6_addrs().count(), 0);
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_dad_slaac() {
+    let (mut iface, mut sockets, mut device) = setup_slaac();
+    iface.set_dad_transmits(1);
+
+    let data = router_advert(
+        Duration::ZERO,
+        Some(prefix_info(
+            NdiscPrefixInfoFlags::ON_LINK | NdiscPrefixInfoFlags::ADDRCONF,
+            Duration::from_secs(3600),
+        )),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.ip_addrs(), &[]);
+    assert_eq!(
+        iface.tentative_ipv6_addrs().collect::<std::vec::Vec<_>>(),
+        [Ipv6Cidr::new(SLAAC_ADDR, 64)]
+    );
+    assert!(iface.dad_egress(&mut device));
+    assert_eq!(
+        recv_dad_solicit(&mut device),
+        Some((SLAAC_ADDR.solicited_node(), SLAAC_ADDR))
+    );
+    assert!(iface.inner.dad_conflict(SLAAC_ADDR));
+    assert_eq!(
+        iface.poll_dad_event(),
+        Some(DadEvent::Conflict(Ipv6Cidr::new(SLAAC_ADDR, 64)))
+    );
+
+    // Pick another interface identifier.
+    iface.set_slaac_interface_id(Some([0, 0, 0, 0, 0, 0, 0, 0x42]));
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    let addr = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x42);
+    assert_eq!(
+        iface.tentative_ipv6_addrs().collect::<std::vec::Vec<_>>(),
+        [Ipv6Cidr::new(addr, 64)]
+    );
+    assert!(iface.dad_egress(&mut device));
+    iface.inner.now = Instant::from_secs(1);
+    assert!(iface.dad_egress(&mut device));
+    assert_eq!(iface.ip_addrs(), &[IpCidr::new(addr.into(), 64)]);
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn test_solicited_node_addrs(#[case] medium: Medium) {
+    let (mut iface, _, _) = setup(medium);
+    let mut new_addrs = heapless::Vec::<IpCidr, IFACE_MAX_ADDR_COUNT>::new();
+    new_addrs
+        .push(IpCidr::new(IpAddress::v6(0xfe80, 0, 0, 0, 1, 2, 0, 2), 64))
+        .unwrap();
+    new_addrs
+        .push(IpCidr::new(
+            IpAddress::v6(0xfe80, 0, 0, 0, 3, 4, 0, 0xffff),
+            64,
+        ))
+        .unwrap();
+    iface.update_ip_addrs(|addrs| {
+        new_addrs.extend(addrs.to_vec());
+        *addrs = new_addrs;
+    });
+    assert!(iface
+        .inner
+        .has_solicited_node(Ipv6Address::new(0xff02, 0, 0, 0, 0, 1, 0xff00, 0x0002)));
+    assert!(iface
+        .inner
+        .has_solicited_node(Ipv6Address::new(0xff02, 0, 0, 0, 0, 1, 0xff00, 0xffff)));
+    assert!(!iface
+        .inner
+        .has_solicited_node(Ipv6Address::new(0xff02, 0, 0, 0, 0, 1, 0xff00, 0x0003)));
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(all(feature = "socket-udp", feature = "medium-ip"))]
+#[case(Medium::Ethernet)]
+#[cfg(all(feature = "socket-udp", feature = "medium-ethernet"))]
+#[case(Medium::Ieee802154)]
+#[cfg(all(feature = "socket-udp", feature = "medium-ieee802154"))]
+fn test_icmp_reply_size(#[case] medium: Medium) {
+    use crate::wire::Icmpv6DstUnreachable;
+    use crate::wire::IPV6_MIN_MTU as MIN_MTU;
+    const MAX_PAYLOAD_LEN: usize = 1192;
+
+    let (mut iface, mut sockets, _device) = setup(medium);
+
+    let src_addr = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let dst_addr = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+
+    // UDP packet that if not tructated will cause a icmp port unreachable reply
+    // to exeed the minimum mtu bytes in length.
+    let udp_repr = UdpRepr {
+        src_port: 67,
+        dst_port: 68,
+    };
+    let mut bytes = vec![0xff; udp_repr.header_len() + MAX_PAYLOAD_LEN];
+    let mut packet = UdpPacket::new_unchecked(&mut bytes[..]);
+    udp_repr.emit(
+        &mut packet,
+        &src_addr.into(),
+        &dst_addr.into(),
+        MAX_PAYLOAD_LEN,
+        |buf| fill_slice(buf, 0x2a),
+        &ChecksumCapabilities::default(),
+    );
+
+    let ip_repr = Ipv6Repr {
+        src_addr,
+        dst_addr,
+        next_header: IpProtocol::Udp,
+        hop_limit: 64,
+        payload_len: udp_repr.header_len() + MAX_PAYLOAD_LEN,
+    };
+    let payload = packet.into_inner();
+
+    let expected_icmp_repr = Icmpv6Repr::DstUnreachable {
+        reason: Icmpv6DstUnreachable::PortUnreachable,
+        header: ip_repr,
+        data: &payload[..MAX_PAYLOAD_LEN],
+    };
+
+    let expected_ip_repr = Ipv6Repr {
+        src_addr: dst_addr,
+        dst_addr: src_addr,
+        next_header: IpProtocol::Icmpv6,
+        hop_limit: 64,
+        payload_len: expected_icmp_repr.buffer_len(),
+    };
+
+    assert_eq!(
+        expected_ip_repr.buffer_len() + expected_icmp_repr.buffer_len(),
+        MIN_MTU
+    );
+
+    assert_eq!(
+        iface.inner.process_udp(
+            &mut sockets,
+            PacketMeta::default(),
+            ip_repr.into(),
+            udp_repr,
+            false,
+            false,
+            &vec![0x2a; MAX_PAYLOAD_LEN],
+            payload,
+        ),
+        Some(IpPacket::new_ipv6(
+            expected_ip_repr,
+            IpPayload::Icmpv6(expected_icmp_repr)
+        ))
+    );
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_zero_checksum() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+    );
+    socket.set_send_checksum_enabled(false);
+    socket.bind(5000).unwrap();
+    socket
+        .send_slice(
+            b"abcdef",
+            IpEndpoint::new(IpAddress::v6(0, 0, 0, 0, 0, 0, 0, 1), 5000),
+        )
+        .unwrap();
+    let handle = sockets.add(socket);
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let packet = Ipv6PacketWire::new_checked(&packets[0][..]).unwrap();
+    let udp_packet = UdpPacket::new_checked(packet.payload()).unwrap();
+    assert_eq!(udp_packet.checksum(), 0);
+
+    // The packet is silently dropped, unless the socket accepts zero checksums.
+    assert_eq!(
+        iface
+            .inner
+            .process_ipv6(&mut sockets, PacketMeta::default(), &packet),
+        None
+    );
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    assert!(!socket.can_recv());
+
+    socket.set_recv_zero_checksum_enabled(true);
+    assert_eq!(
+        iface
+            .inner
+            .process_ipv6(&mut sockets, PacketMeta::default(), &packet),
+        None
+    );
+    let socket = sockets.get_mut::<udp::Socket>(handle);
+    assert_eq!(socket.recv().unwrap().0, b"abcdef");
+}
+
+/// Receive a Router Solicitation, and return its source address and link-layer address
+/// option.
+#[cfg(feature = "medium-ethernet")]
+fn recv_router_solicit(device: &mut Loopback) -> Option<(Ipv6Address, Option<RawHardwareAddress>)> {
+    let (rx, _tx) = device.receive(Instant::ZERO)?;
+    rx.consume(|frame| {
+        let frame = EthernetFrame::new_checked(&*frame).unwrap();
+        let packet = Ipv6PacketWire::new_checked(frame.payload()).unwrap();
+        let ip_repr = Ipv6Repr::parse(&packet).unwrap();
+        assert_eq!(ip_repr.dst_addr, Ipv6Address::LINK_LOCAL_ALL_ROUTERS);
+        assert_eq!(ip_repr.hop_limit, 0xff);
+        let icmp_repr = Icmpv6Repr::parse(
+            &ip_repr.src_addr.into(),
+            &ip_repr.dst_addr.into(),
+            &Icmpv6Packet::new_checked(packet.payload()).unwrap(),
+            &ChecksumCapabilities::default(),
+        )
+        .unwrap();
+        match icmp_repr {
+            Icmpv6Repr::Ndisc(NdiscRepr::RouterSolicit { lladdr }) => {
+                Some((ip_repr.src_addr, lladdr))
+            }
+            repr => panic!("unexpected packet {repr:?}"),
+        }
+    })
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_router_solicit() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    assert!(!iface.router_solicit_egress(&mut device));
+
+    // Without a link-local address, solicitations come from the unspecified address.
+    iface.solicit_routers();
+    let first = iface.poll_at(Instant::ZERO, &sockets).unwrap();
+    assert!(first < Instant::from_secs(1));
+    assert!(!iface.router_solicit_egress(&mut device));
+    for i in 0..3 {
+        iface.inner.now = first + Duration::from_secs(4 * i);
+        assert!(iface.router_solicit_egress(&mut device));
+        assert_eq!(
+            recv_router_solicit(&mut device),
+            Some((Ipv6Address::UNSPECIFIED, None))
+        );
+    }
+    iface.inner.now = first + Duration::from_secs(12);
+    assert!(!iface.router_solicit_egress(&mut device));
+    assert_eq!(iface.poll_at(iface.inner.now, &sockets), None);
+
+    // Enabling autoconfiguration solicits routers again, until one answers.
+    let link_local = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    iface.update_ip_addrs(|addrs| {
+        addrs.clear();
+        addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(link_local, 64)))
+            .unwrap();
+    });
+    iface.inner.now = Instant::ZERO;
+    iface.set_slaac(true);
+    iface.inner.now = Instant::from_secs(1);
+    assert!(iface.router_solicit_egress(&mut device));
+    assert_eq!(
+        recv_router_solicit(&mut device),
+        Some((link_local, Some(iface.hardware_addr().into())))
+    );
+
+    let data = router_advert(Duration::from_secs(1800), None);
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(iface.inner.router_solicit_poll_at(), None);
+    iface.inner.now = Instant::from_secs(5);
+    assert!(!iface.router_solicit_egress(&mut device));
+
+    // Setting the same hardware address does not solicit routers again, but changing it does.
+    iface.set_hardware_addr(iface.hardware_addr());
+    assert_eq!(iface.inner.router_solicit_poll_at(), None);
+    iface.set_hardware_addr(HardwareAddress::Ethernet(EthernetAddress([
+        0x02, 0x02, 0x02, 0x02, 0x02, 0x03,
+    ])));
+    assert!(iface.inner.router_solicit_poll_at().is_some());
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn test_source_address_selection(#[case] medium: Medium) {
+    let (mut iface, _, _) = setup(medium);
+    let link_local = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let global_a = Ipv6Address::new(0x2001, 0xdb8, 0xa, 0, 0, 0, 0, 1);
+    let global_b = Ipv6Address::new(0x2001, 0xdb8, 0xb, 0, 0, 0, 0, 1);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(link_local, 64)))
+            .unwrap();
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(global_a, 64)))
+            .unwrap();
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(global_b, 64)))
+            .unwrap();
+    });
+
+    let source = |iface: &mut Interface, dst| iface.inner.get_source_address_ipv6(dst);
+    // Our own address.
+    assert_eq!(source(&mut iface, global_b), Some(global_b));
+    // Same scope.
+    assert_eq!(
+        source(&mut iface, Ipv6Address::LOOPBACK),
+        Some(Ipv6Address::LOOPBACK)
+    );
+    assert_eq!(
+        source(&mut iface, Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2)),
+        Some(link_local)
+    );
+    assert_eq!(
+        source(&mut iface, Ipv6Address::LINK_LOCAL_ALL_NODES),
+        Some(link_local)
+    );
+    // Unique local addresses are labelled differently from other global ones.
+    assert_eq!(
+        source(&mut iface, Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1)),
+        Some(Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1))
+    );
+    assert_eq!(
+        source(&mut iface, Ipv6Address::new(0x2a00, 0, 0, 0, 0, 0, 0, 1)),
+        Some(global_a)
+    );
+    // Longest matching prefix.
+    assert_eq!(
+        source(
+            &mut iface,
+            Ipv6Address::new(0x2001, 0xdb8, 0xb, 1, 0, 0, 0, 1)
+        ),
+        Some(global_b)
+    );
+    // Larger scope when none matches.
+    assert_eq!(
+        source(&mut iface, Ipv6Address::new(0xff0e, 0, 0, 0, 0, 0, 0, 1)),
+        Some(global_a)
+    );
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_source_address_deprecated() {
+    let (mut iface, mut sockets, _) = setup_slaac();
+    let remote = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    let other = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x100);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(other, 64)))
+            .unwrap();
+    });
+
+    // A deprecated address is only used when there is no other one.
+    let data = router_advert(
+        Duration::from_secs(1800),
+        Some(NdiscPrefixInformation {
+            preferred_lifetime: Duration::ZERO,
+            ..prefix_info(
+                NdiscPrefixInfoFlags::ON_LINK | NdiscPrefixInfoFlags::ADDRCONF,
+                Duration::from_secs(3600),
+            )
+        }),
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert!(iface.has_ip_addr(SLAAC_ADDR));
+    assert!(iface.inner.slaac_deprecated(SLAAC_ADDR));
+    assert_eq!(iface.inner.get_source_address_ipv6(remote), Some(other));
+    assert_eq!(
+        iface.inner.get_source_address_ipv6(SLAAC_ADDR),
+        Some(SLAAC_ADDR)
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn test_addr_lifetime(#[case] medium: Medium) {
+    let (mut iface, mut sockets, mut device) = setup(medium);
+    let old = Ipv6Address::new(0x2001, 0xdb8, 0xa, 0, 0, 0, 0, 1);
+    let new = Ipv6Address::new(0x2001, 0xdb8, 0xb, 0, 0, 0, 0, 1);
+    let remote = Ipv6Address::new(0x2001, 0xdb8, 0xa, 0, 0, 0, 0, 2);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.clear();
+        ip_addrs.push(IpCidr::Ipv6(Ipv6Cidr::new(old, 64))).unwrap();
+        ip_addrs.push(IpCidr::Ipv6(Ipv6Cidr::new(new, 64))).unwrap();
+    });
+    assert_eq!(iface.inner.get_source_address_ipv6(remote), Some(old));
+
+    let lifetime = AddressLifetime {
+        preferred_until: Some(Instant::ZERO),
+        valid_until: Some(Instant::from_secs(60)),
+    };
+    iface.set_ip_addr_lifetime(old, lifetime);
+    assert_eq!(iface.ip_addr_lifetime(old), lifetime);
+    assert_eq!(iface.ip_addr_lifetime(new), AddressLifetime::default());
+
+    // A deprecated address is still ours, but not used for new connections.
+    assert!(iface.has_ip_addr(old));
+    assert_eq!(iface.inner.get_source_address_ipv6(remote), Some(new));
+    assert_eq!(
+        iface.poll_at(Instant::ZERO, &sockets),
+        Some(Instant::from_secs(60))
+    );
+
+    // It is removed once its valid lifetime runs out.
+    iface.poll(Instant::from_secs(60), &mut device, &mut sockets);
+    assert!(!iface.has_ip_addr(old));
+    assert_eq!(iface.ip_addr_lifetime(old), AddressLifetime::default());
+    assert_eq!(iface.poll_at(Instant::from_secs(60), &sockets), None);
+
+    // Lifetimes of addresses that are not ours are ignored.
+    iface.set_ip_addr_lifetime(old, lifetime);
+    assert_eq!(iface.ip_addr_lifetime(old), AddressLifetime::default());
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_default_router_failover() {
+    let (mut iface, mut sockets, mut device) = setup_slaac();
+    let link_local = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let router_a = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    let router_b = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 3);
+    let remote = IpAddress::v6(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(link_local, 64)))
+            .unwrap();
+    });
+
+    for (router, lifetime) in [(router_a, 1800), (router_b, 600)] {
+        let data = emit_ndisc(
+            router,
+            Ipv6Address::LINK_LOCAL_ALL_NODES,
+            NdiscRepr::RouterAdvert {
+                hop_limit: 64,
+                flags: NdiscRouterFlags::empty(),
+                router_lifetime: Duration::from_secs(lifetime),
+                reachable_time: Duration::ZERO,
+                retrans_time: Duration::ZERO,
+                lladdr: None,
+                mtu: None,
+                prefix_info: None,
+                rdnss: None,
+                dnssl: None,
+                sixlowpan_context: None,
+                pref64: None,
+            },
+        );
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+        );
+    }
     assert_eq!(
-        expected_ip_repr.buffer_len() + expected_icmp_repr.buffer_len(),
-        MIN_MTU
+        iface.ipv6_default_routers().collect::<std::vec::Vec<_>>(),
+        [router_a, router_b]
+    );
+    assert_eq!(
+        iface.inner.routes.lookup(&remote, Instant::ZERO),
+        Some(router_a.into())
     );
 
+    // The router doesn't answer its Neighbor Solicitations.
+    for secs in 0..4 {
+        iface.inner.set_now(Instant::from_secs(secs));
+        assert_eq!(
+            iface.inner.routes.lookup(&remote, iface.inner.now),
+            Some(router_a.into())
+        );
+        assert_eq!(
+            iface.inner.lookup_hardware_addr(
+                MockTxToken,
+                &link_local.into(),
+                &remote,
+                &RouteKey::default(),
+                &mut iface.fragmenter,
+            ),
+            Err(DispatchError::NeighborPending)
+        );
+    }
     assert_eq!(
-        iface.inner.process_udp(
+        iface.inner.routes.lookup(&remote, iface.inner.now),
+        Some(router_b.into())
+    );
+
+    // Once it answers again, it is only used after the other router goes away.
+    let data = emit_ndisc(
+        router_a,
+        link_local,
+        NdiscRepr::NeighborAdvert {
+            flags: NdiscNeighborFlags::SOLICITED,
+            target_addr: router_a,
+            lladdr: Some(EthernetAddress([0x02, 0, 0, 0, 0, 2]).into()),
+        },
+    );
+    iface.inner.process_ipv6(
+        &mut sockets,
+        PacketMeta::default(),
+        &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+    );
+    assert_eq!(
+        iface.inner.routes.lookup(&remote, iface.inner.now),
+        Some(router_b.into())
+    );
+
+    assert_eq!(
+        iface.poll_at(Instant::from_secs(4), &sockets),
+        Some(Instant::from_secs(600))
+    );
+    iface.poll(Instant::from_secs(600), &mut device, &mut sockets);
+    assert_eq!(
+        iface.ipv6_default_routers().collect::<std::vec::Vec<_>>(),
+        [router_a]
+    );
+    assert_eq!(
+        iface.inner.routes.lookup(&remote, Instant::from_secs(600)),
+        Some(router_a.into())
+    );
+
+    iface.poll(Instant::from_secs(1800), &mut device, &mut sockets);
+    assert_eq!(iface.ipv6_default_routers().count(), 0);
+    assert_eq!(
+        iface.inner.routes.lookup(&remote, Instant::from_secs(1800)),
+        None
+    );
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_redirect() {
+    let (mut iface, mut sockets, _device) = setup(Medium::Ethernet);
+    let link_local = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    let router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    let better_router = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 3);
+    let remote = Ipv6Address::new(0x2001, 0xdb8, 1, 0, 0, 0, 0, 1);
+    let neighbor = Ipv6Address::new(0x2001, 0xdb8, 2, 0, 0, 0, 0, 1);
+    let lladdr = EthernetAddress([0x02, 0, 0, 0, 0, 3]);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs.clear();
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(link_local, 64)))
+            .unwrap();
+    });
+    iface.routes_mut().add_default_ipv6_route(router).unwrap();
+
+    let mut redirect = |iface: &mut Interface, src_addr, target_addr, dest_addr| {
+        let data = emit_ndisc(
+            src_addr,
+            link_local,
+            NdiscRepr::Redirect {
+                target_addr,
+                dest_addr,
+                lladdr: Some(lladdr.into()),
+                redirected_hdr: None,
+            },
+        );
+        iface.inner.process_ipv6(
             &mut sockets,
             PacketMeta::default(),
-            ip_repr.into(),
-            udp_repr,
-            false,
-            &vec![0x2a; MAX_PAYLOAD_LEN],
-            payload,
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap(),
+        );
+    };
+    let route = |iface: &Interface, addr: Ipv6Address, timestamp| {
+        iface.inner.route(&addr.into(), timestamp)
+    };
+
+    // Only the first hop towards the destination may redirect it.
+    redirect(&mut iface, better_router, better_router, remote);
+    assert_eq!(route(&iface, remote, Instant::ZERO), Some(router.into()));
+    // The target must be a router on the link, or the destination itself.
+    redirect(&mut iface, router, neighbor, remote);
+    assert_eq!(route(&iface, remote, Instant::ZERO), Some(router.into()));
+
+    redirect(&mut iface, router, better_router, remote);
+    assert_eq!(
+        route(&iface, remote, Instant::ZERO),
+        Some(better_router.into())
+    );
+    assert_eq!(
+        iface
+            .inner
+            .neighbor_cache
+            .lookup(&better_router.into(), Instant::ZERO),
+        NeighborAnswer::Found(lladdr.into())
+    );
+    // Only the new first hop may redirect it again.
+    redirect(&mut iface, router, router, remote);
+    assert_eq!(
+        route(&iface, remote, Instant::ZERO),
+        Some(better_router.into())
+    );
+
+    // A destination that is on the link.
+    redirect(&mut iface, router, neighbor, neighbor);
+    assert_eq!(
+        route(&iface, neighbor, Instant::ZERO),
+        Some(neighbor.into())
+    );
+
+    // Redirects expire.
+    assert_eq!(
+        route(&iface, remote, Instant::from_secs(600)),
+        Some(router.into())
+    );
+}
+
+#[rstest]
+#[case(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn anycast_echo_request(#[case] medium: Medium) {
+    let (mut iface, mut sockets, _device) = setup(medium);
+    let anycast =
+        Ipv6Cidr::new(Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1), 64).subnet_router_anycast();
+    iface.update_ipv6_anycast_addrs(|addrs| addrs.push(anycast).unwrap());
+    assert_eq!(iface.ipv6_anycast_addrs(), &[anycast]);
+    assert!(!iface.has_ip_addr(anycast));
+
+    // The reply comes from a unicast address.
+    let (_, data) = ext_hdr_packet(anycast, IpProtocol::Icmpv6, &[]);
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(echo_reply())
+    );
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_anycast_neighbor_solicit() {
+    let (mut iface, mut sockets, _device) = setup(Medium::Ethernet);
+    iface.set_dad_transmits(1);
+    let unicast = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+    let anycast = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0);
+    let remote = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    iface.update_ipv6_anycast_addrs(|addrs| addrs.push(anycast).unwrap());
+    // Anycast addresses don't go through Duplicate Address Detection.
+    assert_eq!(iface.tentative_ipv6_addrs().count(), 0);
+
+    let data = emit_ndisc(
+        remote,
+        anycast.solicited_node(),
+        NdiscRepr::NeighborSolicit {
+            target_addr: anycast,
+            lladdr: Some(EthernetAddress([0x52, 0x54, 0, 0, 0, 0]).into()),
+        },
+    );
+    let advert = Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+        flags: NdiscNeighborFlags::SOLICITED,
+        target_addr: anycast,
+        lladdr: Some(iface.hardware_addr().into()),
+    });
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
         ),
         Some(IpPacket::new_ipv6(
-            expected_ip_repr,
-            IpPayload::Icmpv6(expected_icmp_repr)
+            Ipv6Repr {
+                src_addr: unicast,
+                dst_addr: remote,
+                next_header: IpProtocol::Icmpv6,
+                hop_limit: 0xff,
+                payload_len: advert.buffer_len(),
+            },
+            IpPayload::Icmpv6(advert)
+        ))
+    );
+
+    // Nodes probing for the address aren't told it is in use.
+    let data = emit_ndisc(
+        Ipv6Address::UNSPECIFIED,
+        anycast.solicited_node(),
+        NdiscRepr::NeighborSolicit {
+            target_addr: anycast,
+            lladdr: None,
+        },
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_flow_label() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY; 4], vec![0; 64]),
+    );
+    let handle = sockets.add(socket);
+    sockets.get_mut::<udp::Socket>(handle).bind(5000).unwrap();
+
+    let mut send = |iface: &mut Interface, sockets: &mut SocketSet, port| {
+        let remote = IpEndpoint::new(IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 2), port);
+        let socket = sockets.get_mut::<udp::Socket>(handle);
+        socket.send_slice(b"abcdef", remote).unwrap();
+        assert!(iface.socket_egress(&mut device, sockets));
+        let packets = recv_all(&mut device, Instant::ZERO);
+        assert_eq!(packets.len(), 1);
+        Ipv6PacketWire::new_checked(&packets[0][..])
+            .unwrap()
+            .flow_label()
+    };
+
+    // The packets of a flow share a label, which differs from the one of other flows.
+    let label = send(&mut iface, &mut sockets, 6000);
+    assert_ne!(label, 0);
+    assert_eq!(send(&mut iface, &mut sockets, 6000), label);
+    assert_ne!(send(&mut iface, &mut sockets, 6001), label);
+
+    // A label set on the socket takes precedence.
+    sockets
+        .get_mut::<udp::Socket>(handle)
+        .set_flow_label(Some(0x12345));
+    assert_eq!(send(&mut iface, &mut sockets, 6000), 0x12345);
+
+    sockets.get_mut::<udp::Socket>(handle).set_flow_label(None);
+    iface.set_ipv6_flow_labels(false);
+    assert_eq!(send(&mut iface, &mut sockets, 6000), 0);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp"))]
+fn test_tcp_flow_label() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let mut socket = tcp::Socket::new(
+        tcp::SocketBuffer::new(vec![0; 64]),
+        tcp::SocketBuffer::new(vec![0; 64]),
+    );
+    socket.set_flow_label(Some(0xabcde));
+    let handle = sockets.add(socket);
+    sockets
+        .get_mut::<tcp::Socket>(handle)
+        .connect(
+            iface.context(),
+            (IpAddress::v6(0xfdbe, 0, 0, 0, 0, 0, 0, 2), 80),
+            49500,
+        )
+        .unwrap();
+
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    let packet = Ipv6PacketWire::new_checked(&packets[0][..]).unwrap();
+    assert_eq!(packet.next_header(), IpProtocol::Tcp);
+    assert_eq!(packet.flow_label(), 0xabcde);
+}
+
+/// Emit the Packet Too Big message a router sends for a packet it dropped.
+#[cfg(all(
+    feature = "medium-ip",
+    any(feature = "socket-udp", feature = "socket-tcp")
+))]
+fn emit_pkt_too_big(mtu: u32, dropped: &[u8]) -> std::vec::Vec<u8> {
+    let dropped = Ipv6PacketWire::new_checked(dropped).unwrap();
+    let header = Ipv6Repr::parse(&dropped).unwrap();
+    let data = &dropped.payload()[..dropped.payload().len().min(64)];
+    let repr = Icmpv6Repr::PktTooBig { mtu, header, data };
+    let router = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0xfe);
+    let ip_repr = Ipv6Repr {
+        src_addr: router,
+        dst_addr: header.src_addr,
+        next_header: IpProtocol::Icmpv6,
+        hop_limit: 64,
+        payload_len: repr.buffer_len(),
+    };
+    let mut bytes = vec![0u8; ip_repr.buffer_len() + repr.buffer_len()];
+    ip_repr.emit(&mut Ipv6PacketWire::new_unchecked(&mut bytes));
+    repr.emit(
+        &router.into(),
+        &header.src_addr.into(),
+        &mut Icmpv6Packet::new_unchecked(&mut bytes[ip_repr.buffer_len()..]),
+        &ChecksumCapabilities::default(),
+    );
+    bytes
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_pkt_too_big_udp() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 1500]),
+    );
+    let handle = sockets.add(socket);
+    sockets.get_mut::<udp::Socket>(handle).bind(5000).unwrap();
+
+    let remote = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 2);
+    sockets
+        .get_mut::<udp::Socket>(handle)
+        .send_slice(&[0; 1400], (remote, 6000))
+        .unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+
+    let process = |iface: &mut Interface, sockets: &mut SocketSet, data: &[u8]| {
+        let packet = Ipv6PacketWire::new_checked(data).unwrap();
+        assert!(iface
+            .inner
+            .process_ipv6(sockets, PacketMeta::default(), &packet)
+            .is_none());
+    };
+    let ip_mtu = iface.inner.ip_mtu();
+
+    // Path MTUs below the IPv6 minimum are ignored.
+    process(
+        &mut iface,
+        &mut sockets,
+        &emit_pkt_too_big(1000, &packets[0]),
+    );
+    assert_eq!(iface.inner.path_mtu(&remote.into()), ip_mtu);
+    assert_eq!(sockets.get::<udp::Socket>(handle).path_mtu(), None);
+
+    process(
+        &mut iface,
+        &mut sockets,
+        &emit_pkt_too_big(1280, &packets[0]),
+    );
+    assert_eq!(iface.inner.path_mtu(&remote.into()), 1280);
+    assert_eq!(
+        sockets.get::<udp::Socket>(handle).path_mtu(),
+        Some((remote.into(), 1280))
+    );
+
+    // The path MTU is tried again after ten minutes.
+    iface.inner.now = Instant::from_secs(10 * 60 + 1);
+    assert_eq!(iface.inner.path_mtu(&remote.into()), ip_mtu);
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-tcp"))]
+fn test_pkt_too_big_tcp() {
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+
+    let new_socket = || {
+        tcp::Socket::new(
+            tcp::SocketBuffer::new(vec![0; 4096]),
+            tcp::SocketBuffer::new(vec![0; 4096]),
+        )
+    };
+    let client = sockets.add(new_socket());
+    let server = sockets.add(new_socket());
+    let local = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1);
+
+    sockets.get_mut::<tcp::Socket>(server).listen(80).unwrap();
+    sockets
+        .get_mut::<tcp::Socket>(client)
+        .connect(iface.context(), (local, 80), 49500)
+        .unwrap();
+    for _ in 0..4 {
+        iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    }
+    assert_eq!(
+        sockets.get::<tcp::Socket>(client).state(),
+        tcp::State::Established
+    );
+
+    // A router drops a segment that is too big for the next link.
+    let socket = sockets.get_mut::<tcp::Socket>(client);
+    socket.set_nagle_enabled(false);
+    socket.send_slice(&[0xaa; 2000]).unwrap();
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 1);
+    assert!(packets[0].len() > 2000);
+
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&emit_pkt_too_big(1280, &packets[0])[..]).unwrap(),
+        ),
+        None
+    );
+
+    // The data is sent again right away, in segments that fit the path MTU.
+    assert!(iface.socket_egress(&mut device, &mut sockets));
+    while iface.socket_egress(&mut device, &mut sockets) {}
+    let packets = recv_all(&mut device, Instant::ZERO);
+    assert_eq!(packets.len(), 2);
+    assert!(packets.iter().all(|packet| packet.len() <= 1280));
+}
+
+#[cfg(feature = "medium-ethernet")]
+fn recv_neighbor_advert(
+    device: &mut Loopback,
+) -> Option<(Ipv6Address, NdiscNeighborFlags, Option<RawHardwareAddress>)> {
+    let (rx, _tx) = device.receive(Instant::ZERO)?;
+    rx.consume(|frame| {
+        let frame = EthernetFrame::new_checked(&*frame).unwrap();
+        let packet = Ipv6PacketWire::new_checked(frame.payload()).unwrap();
+        let ip_repr = Ipv6Repr::parse(&packet).unwrap();
+        assert_eq!(ip_repr.dst_addr, Ipv6Address::LINK_LOCAL_ALL_NODES);
+        assert_eq!(ip_repr.hop_limit, 0xff);
+        let icmp_repr = Icmpv6Repr::parse(
+            &ip_repr.src_addr.into(),
+            &ip_repr.dst_addr.into(),
+            &Icmpv6Packet::new_checked(packet.payload()).unwrap(),
+            &ChecksumCapabilities::default(),
+        )
+        .unwrap();
+        match icmp_repr {
+            Icmpv6Repr::Ndisc(NdiscRepr::NeighborAdvert {
+                flags,
+                target_addr,
+                lladdr,
+            }) => {
+                assert_eq!(ip_repr.src_addr, target_addr);
+                Some((target_addr, flags, lladdr))
+            }
+            repr => panic!("unexpected packet {repr:?}"),
+        }
+    })
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_unsolicited_neighbor_advert() {
+    let (mut iface, sockets, mut device) = setup(Medium::Ethernet);
+    let addr = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    let mac = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    iface.set_announce_transmits(1);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(addr, 64)))
+            .unwrap();
+    });
+
+    assert_eq!(iface.poll_at(Instant::ZERO, &sockets), Some(Instant::ZERO));
+    assert!(iface.announce_egress(&mut device));
+    assert_eq!(
+        recv_neighbor_advert(&mut device),
+        Some((
+            addr,
+            NdiscNeighborFlags::OVERRIDE,
+            Some(EthernetAddress::default().into())
         ))
     );
+    assert!(!iface.announce_egress(&mut device));
+    assert_eq!(iface.poll_at(Instant::ZERO, &sockets), None);
+
+    // A new hardware address is announced for every address, except for the loopback one.
+    iface.set_hardware_addr(HardwareAddress::Ethernet(mac));
+    let mut announced = std::vec::Vec::new();
+    while iface.announce_egress(&mut device) {
+        let (target_addr, _, lladdr) = recv_neighbor_advert(&mut device).unwrap();
+        assert_eq!(lladdr, Some(mac.into()));
+        announced.push(target_addr);
+    }
+    announced.sort();
+    assert_eq!(
+        announced,
+        [addr, Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 1)]
+    );
+
+    // Tentative addresses are only announced once assigned.
+    let tentative = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2);
+    iface.set_dad_transmits(1);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::Ipv6(Ipv6Cidr::new(tentative, 64)))
+            .unwrap();
+    });
+    assert!(!iface.announce_egress(&mut device));
+    assert!(iface.dad_egress(&mut device));
+    device.receive(Instant::ZERO).unwrap();
+    iface.inner.now = Instant::from_secs(1);
+    assert!(iface.dad_egress(&mut device));
+    assert!(iface.announce_egress(&mut device));
+    assert_eq!(
+        recv_neighbor_advert(&mut device).map(|(target_addr, ..)| target_addr),
+        Some(tentative)
+    );
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_hardware_addr_renumber() {
+    use crate::iface::AddressLifetime;
+
+    fn with_iid(prefix: Ipv6Address, mac: EthernetAddress) -> Ipv6Address {
+        let mut bytes = [0; 16];
+        bytes[..8].copy_from_slice(&prefix.as_bytes()[..8]);
+        bytes[8..].copy_from_slice(&mac.as_eui_64());
+        Ipv6Address::from_bytes(&bytes)
+    }
+
+    let (mut iface, _, mut device) = setup(Medium::Ethernet);
+    let old_mac = EthernetAddress::default();
+    let new_mac = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    let link_local = with_iid(Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0), old_mac);
+    let global = with_iid(Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), old_mac);
+    let static_addr = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    iface.update_ip_addrs(|ip_addrs| {
+        for addr in [link_local, global, static_addr] {
+            ip_addrs
+                .push(IpCidr::Ipv6(Ipv6Cidr::new(addr, 64)))
+                .unwrap();
+        }
+    });
+    let lifetime = AddressLifetime {
+        preferred_until: Some(Instant::from_secs(100)),
+        valid_until: Some(Instant::from_secs(200)),
+    };
+    iface.set_ip_addr_lifetime(global, lifetime);
+    let neighbor = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2);
+    iface.inner.neighbor_cache.fill(
+        neighbor.into(),
+        EthernetAddress([0x52, 0x54, 0x00, 0x00, 0x00, 0x02]).into(),
+        Instant::ZERO,
+    );
+
+    // The addresses derived from the hardware address follow it, with their lifetimes.
+    iface.set_hardware_addr(HardwareAddress::Ethernet(new_mac));
+    let new_link_local = with_iid(link_local, new_mac);
+    let new_global = with_iid(global, new_mac);
+    assert!(iface.has_ip_addr(new_link_local));
+    assert!(iface.has_ip_addr(new_global));
+    assert!(iface.has_ip_addr(static_addr));
+    assert!(!iface.has_ip_addr(link_local));
+    assert!(!iface.has_ip_addr(global));
+    assert_eq!(iface.ip_addr_lifetime(new_global), lifetime);
+    assert!(!iface
+        .inner
+        .neighbor_cache
+        .lookup(&neighbor.into(), Instant::ZERO)
+        .found());
+
+    // The new addresses are announced.
+    let mut announced = std::vec::Vec::new();
+    iface.set_announce_transmits(1);
+    iface.set_hardware_addr(HardwareAddress::Ethernet(old_mac));
+    while iface.announce_egress(&mut device) {
+        let (target_addr, _, lladdr) = recv_neighbor_advert(&mut device).unwrap();
+        assert_eq!(lladdr, Some(old_mac.into()));
+        announced.push(target_addr);
+    }
+    assert!(announced.contains(&link_local));
+    assert!(announced.contains(&global));
+
+    // With Duplicate Address Detection, the new addresses are tentative again.
+    iface.set_dad_transmits(1);
+    iface.set_hardware_addr(HardwareAddress::Ethernet(new_mac));
+    assert!(!iface.has_ip_addr(new_link_local));
+    let mut tentative: std::vec::Vec<_> = iface
+        .tentative_ipv6_addrs()
+        .map(|cidr| cidr.address())
+        .collect();
+    tentative.sort();
+    let mut expected = [new_link_local, new_global];
+    expected.sort();
+    assert_eq!(tentative, expected);
+}
+
+#[rstest]
+#[case::ip(Medium::Ip)]
+#[cfg(feature = "medium-ip")]
+#[case::ethernet(Medium::Ethernet)]
+#[cfg(feature = "medium-ethernet")]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn filter_reject(#[case] medium: Medium) {
+    use crate::iface::FilterRule;
+
+    // The ICMP echo request of `hop_by_hop_skip_with_icmp`, after a hop-by-hop header.
+    let data = [
+        0x60, 0x0, 0x0, 0x0, 0x0, 0x1b, 0x0, 0x40, 0xfd, 0xbe, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x2, 0xfd, 0xbe, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0,
+        0x0, 0x0, 0x0, 0x0, 0x1, 0x3a, 0x0, 0x1, 0x0, 0xf, 0x0, 0x1, 0x0, 0x80, 0x0, 0x2c, 0x88,
+        0x0, 0x2a, 0x1, 0xa4, 0x4c, 0x6f, 0x72, 0x65, 0x6d, 0x20, 0x49, 0x70, 0x73, 0x75, 0x6d,
+    ];
+
+    let (mut iface, mut sockets, _device) = setup(medium);
+    let rule = FilterRule {
+        src_cidr: Some(IpCidr::new(
+            IpAddress::Ipv6(Ipv6Address::from_parts(&[0xfdbe, 0, 0, 0, 0, 0, 0, 0x0002])),
+            128,
+        )),
+        protocol: Some(IpProtocol::Icmpv6),
+        ..FilterRule::new(FilterAction::Reject)
+    };
+    iface.filter_mut().add_rule(rule).unwrap();
+
+    let header = Ipv6Repr {
+        src_addr: Ipv6Address::from_parts(&[0xfdbe, 0, 0, 0, 0, 0, 0, 0x0002]),
+        dst_addr: Ipv6Address::from_parts(&[0xfdbe, 0, 0, 0, 0, 0, 0, 0x0001]),
+        hop_limit: 64,
+        next_header: IpProtocol::HopByHop,
+        payload_len: 27,
+    };
+    let icmp_repr = Icmpv6Repr::DstUnreachable {
+        reason: Icmpv6DstUnreachable::AdminProhibit,
+        header,
+        data: &data[40..],
+    };
+    let response = Some(IpPacket::new_ipv6(
+        Ipv6Repr {
+            src_addr: header.dst_addr,
+            dst_addr: header.src_addr,
+            hop_limit: 64,
+            next_header: IpProtocol::Icmpv6,
+            payload_len: icmp_repr.buffer_len(),
+        },
+        IpPayload::Icmpv6(icmp_repr),
+    ));
+
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        response
+    );
+
+    // Dropped packets are not answered.
+    iface.filter_mut().update(|rules| {
+        rules[0].action = FilterAction::Drop;
+    });
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+}
+
+#[test]
+#[cfg(all(feature = "medium-ip", feature = "socket-udp"))]
+fn test_udp_socket_multicast_group_mld() {
+    fn recv_mld(device: &mut Loopback) -> Vec<(MldRecordType, Ipv6Address)> {
+        let mut records = Vec::new();
+        for frame in recv_all(device, Instant::ZERO) {
+            let ipv6_packet = Ipv6PacketWire::new_checked(&frame[..]).unwrap();
+            let (src_addr, dst_addr) = (ipv6_packet.src_addr(), ipv6_packet.dst_addr());
+            assert_eq!(src_addr, Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1));
+            assert_eq!(dst_addr, Ipv6Address::LINK_LOCAL_ALL_MLDV2_ROUTERS);
+            assert_eq!(ipv6_packet.hop_limit(), 1);
+
+            // The report is preceded by a Hop-by-Hop Options header with a Router Alert option.
+            assert_eq!(ipv6_packet.next_header(), IpProtocol::HopByHop);
+            let header = Ipv6HopByHopHeader::new_checked(ipv6_packet.payload()).unwrap();
+            let header = Ipv6HopByHopRepr::parse(&header).unwrap();
+            assert_eq!(header.next_header, IpProtocol::Icmpv6);
+            let options: Vec<_> = Ipv6OptionsIterator::new(header.data)
+                .map(Result::unwrap)
+                .collect();
+            assert_eq!(
+                options,
+                [Ipv6OptionRepr::RouterAlert(0), Ipv6OptionRepr::PadN(0)]
+            );
+
+            let icmp_packet = Icmpv6Packet::new_checked(&ipv6_packet.payload()[8..]).unwrap();
+            let icmp_repr = Icmpv6Repr::parse(
+                &src_addr.into(),
+                &dst_addr.into(),
+                &icmp_packet,
+                &ChecksumCapabilities::default(),
+            );
+            let Ok(Icmpv6Repr::Mld(MldRepr::Report {
+                nr_mcast_addr_rcrds,
+                data,
+            })) = icmp_repr
+            else {
+                panic!("not an MLD report: {icmp_repr:?}");
+            };
+            for chunk in data.chunks(20).take(nr_mcast_addr_rcrds as usize) {
+                let record = MldAddressRecord::new_checked(chunk).unwrap();
+                assert_eq!(record.num_srcs(), 0);
+                records.push((record.record_type(), record.mcast_addr()));
+            }
+        }
+        records
+    }
+
+    let group_addr = Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb);
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ip);
+    iface.update_ip_addrs(|ip_addrs| {
+        ip_addrs
+            .push(IpCidr::new(IpAddress::v6(0xfe80, 0, 0, 0, 0, 0, 0, 1), 64))
+            .unwrap();
+    });
+
+    let mut socket = udp::Socket::new(
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![0; 16]),
+        udp::PacketBuffer::new(vec![udp::PacketMetadata::EMPTY], vec![]),
+    );
+    socket.bind(5353).unwrap();
+    socket.join_multicast_group(group_addr).unwrap();
+    // The link-local all-nodes group is never reported.
+    socket
+        .join_multicast_group(Ipv6Address::LINK_LOCAL_ALL_NODES)
+        .unwrap();
+    let handle = sockets.add(socket);
+
+    // The group joined by the socket is reported once.
+    assert!(iface.mld_egress(&mut device, &sockets));
+    assert_eq!(
+        recv_mld(&mut device),
+        vec![(MldRecordType::ChangeToExclude, group_addr)]
+    );
+    assert!(!iface.mld_egress(&mut device, &sockets));
+    assert!(recv_mld(&mut device).is_empty());
+
+    // It is reported again when a router asks for it.
+    let router_addr = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    let query = Icmpv6Repr::Mld(MldRepr::Query {
+        max_resp_code: 1000,
+        mcast_addr: Ipv6Address::UNSPECIFIED,
+        s_flag: false,
+        qrv: 2,
+        qqic: 125,
+        num_srcs: 0,
+        data: &[],
+    });
+    let ipv6_repr = Ipv6Repr {
+        src_addr: router_addr,
+        dst_addr: Ipv6Address::LINK_LOCAL_ALL_NODES,
+        next_header: IpProtocol::Icmpv6,
+        payload_len: query.buffer_len(),
+        hop_limit: 1,
+    };
+    let mut data = vec![0; ipv6_repr.buffer_len() + query.buffer_len()];
+    ipv6_repr.emit(&mut Ipv6PacketWire::new_unchecked(&mut data[..]));
+    query.emit(
+        &router_addr.into(),
+        &Ipv6Address::LINK_LOCAL_ALL_NODES.into(),
+        &mut Icmpv6Packet::new_unchecked(&mut data[ipv6_repr.buffer_len()..]),
+        &ChecksumCapabilities::default(),
+    );
+    assert_eq!(
+        iface.inner.process_ipv6(
+            &mut sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+    assert!(iface.mld_egress(&mut device, &sockets));
+    assert_eq!(
+        recv_mld(&mut device),
+        vec![(MldRecordType::ModeIsExclude, group_addr)]
+    );
+
+    // It is left once the socket leaves it.
+    sockets
+        .get_mut::<udp::Socket>(handle)
+        .leave_multicast_group(group_addr);
+    assert!(iface.mld_egress(&mut device, &sockets));
+    assert_eq!(
+        recv_mld(&mut device),
+        vec![(MldRecordType::ChangeToInclude, group_addr)]
+    );
+    assert!(!iface.mld_egress(&mut device, &sockets));
+    assert!(recv_mld(&mut device).is_empty());
 }
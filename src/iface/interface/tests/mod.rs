@@ -2,10 +2,18 @@
 mod ipv4;
 #[cfg(feature = "proto-ipv6")]
 mod ipv6;
+#[cfg(all(feature = "proto-rpl", feature = "medium-ieee802154"))]
+mod rpl;
 #[cfg(feature = "proto-sixlowpan")]
 mod sixlowpan;
 
-#[cfg(feature = "proto-igmp")]
+#[cfg(any(
+    feature = "proto-igmp",
+    all(
+        feature = "medium-ip",
+        any(feature = "socket-udp", feature = "socket-tcp")
+    )
+))]
 use std::vec::Vec;
 
 use rstest::*;
@@ -36,6 +44,9 @@ fn setup<'a>(medium: Medium) -> (Interface, SocketSet<'a>, Loopback) {
     });
 
     let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+    // Address announcements are tested on their own.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    iface.set_announce_transmits(0);
 
     #[cfg(feature = "proto-ipv4")]
     {
@@ -61,7 +72,13 @@ fn setup<'a>(medium: Medium) -> (Interface, SocketSet<'a>, Loopback) {
     (iface, SocketSet::new(vec![]), device)
 }
 
-#[cfg(feature = "proto-igmp")]
+#[cfg(any(
+    feature = "proto-igmp",
+    all(
+        feature = "medium-ip",
+        any(feature = "socket-udp", feature = "socket-tcp")
+    )
+))]
 fn recv_all(device: &mut Loopback, timestamp: Instant) -> Vec<Vec<u8>> {
     let mut pkts = Vec::new();
     while let Some((rx, _tx)) = device.receive(timestamp) {
@@ -143,6 +160,8 @@ fn test_handle_udp_broadcast(
         hop_limit: 0x40,
     });
 
+    let dst_addr = ip_repr.dst_addr();
+
     // Bind the socket to port 68
     let socket = sockets.get_mut::<udp::Socket>(socket_handle);
     assert_eq!(socket.bind(68), Ok(()));
@@ -166,6 +185,7 @@ fn test_handle_udp_broadcast(
             ip_repr,
             udp_repr,
             false,
+            false,
             &UDP_PAYLOAD,
             packet.into_inner(),
         ),
@@ -176,8 +196,44 @@ fn test_handle_udp_broadcast(
     // appended to the bound sockets rx_buffer
     let socket = sockets.get_mut::<udp::Socket>(socket_handle);
     assert!(socket.can_recv());
+    let (payload, meta) = socket.recv().unwrap();
+    assert_eq!(payload, &UDP_PAYLOAD[..]);
+    assert_eq!(meta.endpoint, IpEndpoint::new(src_ip.into(), 67));
+    // The destination address tells that the packet was broadcast.
+    assert_eq!(meta.local_address, Some(dst_addr));
+    assert_eq!(meta.hop_limit, Some(0x40));
+}
+
+#[test]
+#[cfg(feature = "medium-ethernet")]
+fn test_wake_on_lan() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    // The length of the password of the last magic packet received, plus one.
+    static WOKEN: AtomicUsize = AtomicUsize::new(0);
+
+    let (mut iface, mut sockets, mut device) = setup(Medium::Ethernet);
+    iface.set_wake_callback(Some(|password| {
+        WOKEN.store(password.len() + 1, Ordering::Relaxed);
+    }));
+    let own_addr = iface.hardware_addr().ethernet_or_panic();
+    let other_addr = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+
+    // The loopback device receives the packets the interface sends.
+    iface
+        .send_magic_packet(&mut device, other_addr, &[], Instant::ZERO)
+        .unwrap();
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(WOKEN.load(Ordering::Relaxed), 0);
+
+    iface
+        .send_magic_packet(&mut device, own_addr, &[1, 2, 3, 4], Instant::ZERO)
+        .unwrap();
+    iface.poll(Instant::ZERO, &mut device, &mut sockets);
+    assert_eq!(WOKEN.load(Ordering::Relaxed), 5);
+
     assert_eq!(
-        socket.recv(),
-        Ok((&UDP_PAYLOAD[..], IpEndpoint::new(src_ip.into(), 67).into()))
+        iface.send_magic_packet(&mut device, own_addr, &[1, 2, 3], Instant::ZERO),
+        Err(WakeError::InvalidPassword)
     );
 }
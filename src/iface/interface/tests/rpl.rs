@@ -0,0 +1,206 @@
+use super::*;
+
+use crate::iface::{RplConfig, RplRootConfig};
+
+const PAN_ID: Ieee802154Pan = Ieee802154Pan(0xbeef);
+
+fn hardware_addr(n: u8) -> Ieee802154Address {
+    Ieee802154Address::Extended([0x02, 0, 0, 0, 0, 0, 0, n])
+}
+
+fn link_local_addr(n: u8) -> Ipv6Address {
+    Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, n.into())
+}
+
+fn global_addr(n: u8) -> Ipv6Address {
+    Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, n.into())
+}
+
+fn root_config() -> RplConfig {
+    RplConfig::new(RplModeOfOperation::StoringModeWithoutMulticast)
+        .add_root_config(RplRootConfig::new(RplInstanceId::from(30), global_addr(1)))
+}
+
+fn node_config() -> RplConfig {
+    RplConfig::new(RplModeOfOperation::StoringModeWithoutMulticast)
+}
+
+/// Create the interface of node `n` of a PAN.
+fn setup_rpl(n: u8, rpl_config: RplConfig) -> (Interface, SocketSet<'static>, Loopback) {
+    let mut device = Loopback::new(Medium::Ieee802154);
+    let mut config = Config::new(HardwareAddress::Ieee802154(hardware_addr(n)));
+    config.pan_id = Some(PAN_ID);
+    config.rpl_config = Some(rpl_config);
+    let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(link_local_addr(n).into(), 64))
+            .unwrap();
+        addrs.push(IpCidr::new(global_addr(n).into(), 64)).unwrap();
+    });
+    (iface, SocketSet::new(vec![]), device)
+}
+
+/// Pass the frames sent by one interface on to another one.
+fn deliver(device: &mut Loopback, iface: &mut Interface, sockets: &mut SocketSet) -> usize {
+    let mut count = 0;
+    while let Some((rx, _tx)) = device.receive(iface.inner.now) {
+        let frame = rx.consume(|frame| frame.to_vec());
+        iface.inner.process_ieee802154(
+            sockets,
+            PacketMeta::default(),
+            &frame,
+            &mut iface.fragments,
+        );
+        count += 1;
+    }
+    count
+}
+
+/// Let node 2 join the DODAG of root 1 from its first DIO.
+fn join() -> (
+    (Interface, SocketSet<'static>, Loopback),
+    (Interface, SocketSet<'static>, Loopback),
+) {
+    let (mut root, root_sockets, mut root_device) = setup_rpl(1, root_config());
+    let (mut node, mut node_sockets, node_device) = setup_rpl(2, node_config());
+
+    let dio_at = root.inner.rpl_poll_at().unwrap();
+    root.inner.now = dio_at;
+    assert!(root.rpl_egress(&mut root_device));
+    node.inner.now = dio_at;
+    assert_eq!(deliver(&mut root_device, &mut node, &mut node_sockets), 1);
+
+    (
+        (root, root_sockets, root_device),
+        (node, node_sockets, node_device),
+    )
+}
+
+#[test]
+fn test_rpl_root() {
+    let (mut root, sockets, _device) = setup_rpl(1, root_config());
+    assert_eq!(root.rpl_dodag_id(), Some(global_addr(1)));
+    assert_eq!(root.rpl_rank(), Some(256));
+    assert_eq!(root.rpl_parent(), None);
+    assert!(root.poll_at(Instant::ZERO, &sockets).is_some());
+}
+
+#[test]
+fn test_rpl_join() {
+    let (_, (node, _, _)) = join();
+    assert_eq!(node.rpl_dodag_id(), Some(global_addr(1)));
+    assert_eq!(node.rpl_parent(), Some(link_local_addr(1)));
+    // OF0 adds 3 times the MinHopRankIncrease to the Rank of the parent.
+    assert_eq!(node.rpl_rank(), Some(256 + 3 * 256));
+    // Global destinations are routed through the parent.
+    assert_eq!(
+        node.inner.route(&global_addr(3).into(), node.inner.now),
+        Some(link_local_addr(1).into())
+    );
+}
+
+#[test]
+fn test_rpl_ignore_other_mode_of_operation() {
+    let (mut root, _, mut root_device) = setup_rpl(1, root_config());
+    let (mut node, mut node_sockets, _) = setup_rpl(
+        2,
+        RplConfig::new(RplModeOfOperation::NoDownwardRoutesMaintained),
+    );
+
+    let dio_at = root.inner.rpl_poll_at().unwrap();
+    root.inner.now = dio_at;
+    assert!(root.rpl_egress(&mut root_device));
+    assert_eq!(deliver(&mut root_device, &mut node, &mut node_sockets), 1);
+    assert_eq!(node.rpl_dodag_id(), None);
+    assert_eq!(node.rpl_parent(), None);
+}
+
+#[test]
+fn test_rpl_dao() {
+    let ((mut root, mut root_sockets, _), (mut node, _, mut node_device)) = join();
+    root.inner.now = node.inner.now;
+    node.inner.neighbor_cache.fill(
+        link_local_addr(1).into(),
+        HardwareAddress::Ieee802154(hardware_addr(1)),
+        node.inner.now,
+    );
+
+    // The node advertises its global address to its parent right away.
+    assert!(node.rpl_egress(&mut node_device));
+    assert_eq!(deliver(&mut node_device, &mut root, &mut root_sockets), 1);
+    assert_eq!(
+        root.inner.route(&global_addr(2).into(), root.inner.now),
+        Some(link_local_addr(2).into())
+    );
+
+    // The route lasts for the default lifetime of 30 minutes, unless refreshed.
+    let now = root.inner.now;
+    root.inner.now = now + Duration::from_secs(29 * 60);
+    root.inner.rpl_expire();
+    assert_eq!(
+        root.inner.rpl_route(&global_addr(2).into()),
+        Some(link_local_addr(2).into())
+    );
+    root.inner.now = now + Duration::from_secs(31 * 60);
+    root.inner.rpl_expire();
+    assert_eq!(root.inner.rpl_route(&global_addr(2).into()), None);
+}
+
+fn forwarded_packet(hop_limit: u8) -> std::vec::Vec<u8> {
+    let payload = [0x01, 0x02, 0x03, 0x04];
+    let ip_repr = Ipv6Repr {
+        src_addr: global_addr(3),
+        dst_addr: global_addr(2),
+        next_header: IpProtocol::Unknown(0xfe),
+        hop_limit,
+        payload_len: payload.len(),
+    };
+    let mut bytes = vec![0u8; ip_repr.buffer_len() + payload.len()];
+    ip_repr.emit(&mut Ipv6PacketWire::new_unchecked(&mut bytes));
+    bytes[ip_repr.buffer_len()..].copy_from_slice(&payload);
+    bytes
+}
+
+#[test]
+fn test_rpl_forward() {
+    let ((mut root, mut root_sockets, _), (mut node, _, mut node_device)) = join();
+    root.inner.now = node.inner.now;
+    node.inner.neighbor_cache.fill(
+        link_local_addr(1).into(),
+        HardwareAddress::Ieee802154(hardware_addr(1)),
+        node.inner.now,
+    );
+    assert!(node.rpl_egress(&mut node_device));
+    assert_eq!(deliver(&mut node_device, &mut root, &mut root_sockets), 1);
+
+    let data = forwarded_packet(64);
+    assert_eq!(
+        root.inner.process_ipv6(
+            &mut root_sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        Some(IpPacket::new_ipv6(
+            Ipv6Repr {
+                src_addr: global_addr(3),
+                dst_addr: global_addr(2),
+                next_header: IpProtocol::Unknown(0xfe),
+                hop_limit: 63,
+                payload_len: 4,
+            },
+            IpPayload::Raw(&[0x01, 0x02, 0x03, 0x04]),
+        ))
+    );
+
+    // Packets are not forwarded past their hop limit.
+    let data = forwarded_packet(1);
+    assert_eq!(
+        root.inner.process_ipv6(
+            &mut root_sockets,
+            PacketMeta::default(),
+            &Ipv6PacketWire::new_checked(&data[..]).unwrap()
+        ),
+        None
+    );
+}
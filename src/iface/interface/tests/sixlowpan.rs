@@ -343,7 +343,9 @@ fn test_sixlowpan_udp_with_fragmentation() {
     let udp_data = b"Lorem ipsum dolor sit amet, consectetur adipiscing elit. \
 In at rhoncus tortor. Cras blandit tellus diam, varius vestibulum nibh commodo nec.";
     assert_eq!(
-        socket.recv(),
+        socket
+            .recv()
+            .map(|(payload, meta)| (payload, meta.endpoint)),
         Ok((
             &udp_data[..],
             IpEndpoint {
@@ -353,7 +355,6 @@ In at rhoncus tortor. Cras blandit tellus diam, varius vestibulum nibh commodo n
                 ])),
                 port: 54217,
             }
-            .into()
         ))
     );
 
@@ -411,3 +412,288 @@ In at rhoncus tortor. Cras blandit tellus diam, varius vestibulum nibh commodo n
         ]
     );
 }
+
+#[rstest]
+#[case::ieee802154(Medium::Ieee802154)]
+#[cfg(feature = "medium-ieee802154")]
+fn test_sixlowpan_context_from_router_advert(#[case] medium: Medium) {
+    let (mut iface, _sockets, _device) = setup(medium);
+    let addr = Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1);
+    let context = |context_id, compression| NdiscSixlowpanContext {
+        context_len: 64,
+        compression,
+        context_id,
+        valid_lifetime: Duration::from_secs(600),
+        prefix: Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 0),
+    };
+
+    // Contexts have to be learned in order.
+    iface
+        .inner
+        .process_sixlowpan_context(Some(context(1, true)));
+    assert!(iface.sixlowpan_address_context().is_empty());
+
+    iface
+        .inner
+        .process_sixlowpan_context(Some(context(0, true)));
+    assert_eq!(
+        iface.sixlowpan_address_context()[..],
+        [SixlowpanAddressContext([0xfd, 0, 0, 0, 0, 0, 0, 0])]
+    );
+    assert_eq!(iface.inner.sixlowpan_compression_context(&addr), Some(0));
+    assert_eq!(
+        iface
+            .inner
+            .sixlowpan_compression_context(&Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+        None
+    );
+
+    // Expired contexts are kept for decompression only.
+    iface.inner.now += Duration::from_secs(600);
+    assert_eq!(iface.inner.sixlowpan_compression_context(&addr), None);
+    assert_eq!(iface.sixlowpan_address_context().len(), 1);
+
+    iface
+        .inner
+        .process_sixlowpan_context(Some(context(0, false)));
+    assert_eq!(iface.inner.sixlowpan_compression_context(&addr), None);
+
+    // Contexts set through the API are always used for compression.
+    iface
+        .sixlowpan_address_context_mut()
+        .push(SixlowpanAddressContext([0xfd, 0, 0, 0, 0, 0, 0, 0]))
+        .unwrap();
+    assert_eq!(iface.inner.sixlowpan_compression_context(&addr), Some(1));
+}
+
+#[cfg(feature = "medium-ieee802154")]
+fn mesh_hardware_addr(n: u8) -> Ieee802154Address {
+    Ieee802154Address::Extended([0x02, 0, 0, 0, 0, 0, 0, n])
+}
+
+#[cfg(feature = "medium-ieee802154")]
+fn mesh_link_local_addr(n: u8) -> Ipv6Address {
+    mesh_hardware_addr(n).as_link_local_address().unwrap()
+}
+
+/// Create the interface of node `n` of a mesh-under network.
+#[cfg(feature = "medium-ieee802154")]
+fn setup_mesh_node(n: u8) -> (Interface, SocketSet<'static>, Loopback) {
+    let mut device = Loopback::new(Medium::Ieee802154);
+    let mut config = Config::new(HardwareAddress::Ieee802154(mesh_hardware_addr(n)));
+    config.pan_id = Some(Ieee802154Pan(0xbeef));
+    let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+    iface.update_ip_addrs(|addrs| {
+        addrs
+            .push(IpCidr::new(mesh_link_local_addr(n).into(), 64))
+            .unwrap();
+    });
+    (iface, SocketSet::new(vec![]), device)
+}
+
+#[cfg(feature = "medium-ieee802154")]
+fn mesh_frames(device: &mut Loopback) -> std::vec::Vec<std::vec::Vec<u8>> {
+    let mut frames = vec![];
+    while let Some((rx, _tx)) = device.receive(Instant::ZERO) {
+        rx.consume(|frame| frames.push(frame.to_vec()));
+    }
+    frames
+}
+
+#[rstest]
+#[case::single_frame(16)]
+#[cfg_attr(feature = "proto-sixlowpan-fragmentation", case::fragmented(300))]
+#[cfg(feature = "medium-ieee802154")]
+fn test_sixlowpan_mesh_under(#[case] data_len: usize) {
+    // Node 1 reaches node 3 through node 2.
+    let (mut iface1, _, mut device1) = setup_mesh_node(1);
+    let (mut iface2, _, mut device2) = setup_mesh_node(2);
+    let (mut iface3, mut sockets3, _) = setup_mesh_node(3);
+    iface1
+        .sixlowpan_mesh_routes_mut()
+        .push(SixlowpanMeshRoute {
+            final_addr: mesh_hardware_addr(3),
+            next_hop: mesh_hardware_addr(2),
+        })
+        .unwrap();
+
+    let data = vec![0x2a; data_len];
+    let request = IpPacket::new_ipv6(
+        Ipv6Repr {
+            src_addr: mesh_link_local_addr(1),
+            dst_addr: mesh_link_local_addr(3),
+            next_header: IpProtocol::Icmpv6,
+            hop_limit: 64,
+            payload_len: 8 + data_len,
+        },
+        IpPayload::Icmpv6(Icmpv6Repr::EchoRequest {
+            ident: 1,
+            seq_no: 1,
+            data: &data,
+        }),
+    );
+    let tx_token = device1.transmit(Instant::ZERO).unwrap();
+    iface1.inner.dispatch_ieee802154(
+        mesh_hardware_addr(3),
+        tx_token,
+        PacketMeta::default(),
+        request,
+        &mut iface1.fragmenter,
+    );
+    #[cfg(feature = "proto-sixlowpan-fragmentation")]
+    while !iface1.fragmenter.is_empty() && !iface1.fragmenter.finished() {
+        let tx_token = device1.transmit(Instant::ZERO).unwrap();
+        iface1
+            .inner
+            .dispatch_ieee802154_frag(tx_token, &mut iface1.fragmenter);
+    }
+
+    let mut response = None;
+    for frame in mesh_frames(&mut device1) {
+        // The frames are sent to the next hop, with the mesh header.
+        let ieee_frame = Ieee802154Frame::new_checked(&frame[..]).unwrap();
+        assert_eq!(ieee_frame.dst_addr(), Some(mesh_hardware_addr(2)));
+        let mesh_repr = SixlowpanMeshRepr::parse(&SixlowpanMeshPacket::new_unchecked(
+            ieee_frame.payload().unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(mesh_repr.originator_addr, mesh_hardware_addr(1));
+        assert_eq!(mesh_repr.final_addr, mesh_hardware_addr(3));
+
+        // The next hop does not process the packet, but forwards it as it is.
+        assert_eq!(
+            iface2.inner.process_ieee802154(
+                &mut SocketSet::new(vec![]),
+                PacketMeta::default(),
+                &frame,
+                &mut iface2.fragments
+            ),
+            None
+        );
+        let forward = iface2.inner.sixlowpan_mesh_forward(&frame).unwrap();
+        let tx_token = device2.transmit(Instant::ZERO).unwrap();
+        iface2
+            .inner
+            .dispatch_sixlowpan_mesh_forward(tx_token, &frame, forward);
+        let forwarded = mesh_frames(&mut device2).pop().unwrap();
+
+        let ieee_frame = Ieee802154Frame::new_checked(&forwarded[..]).unwrap();
+        assert_eq!(ieee_frame.src_addr(), Some(mesh_hardware_addr(2)));
+        assert_eq!(ieee_frame.dst_addr(), Some(mesh_hardware_addr(3)));
+        let forwarded_mesh = SixlowpanMeshPacket::new_unchecked(ieee_frame.payload().unwrap());
+        assert_eq!(forwarded_mesh.hops_left(), mesh_repr.hops_left - 1);
+
+        response = iface3
+            .inner
+            .process_ieee802154(
+                &mut sockets3,
+                PacketMeta::default(),
+                &forwarded,
+                &mut iface3.fragments,
+            )
+            .map(|packet| (packet.ip_repr().src_addr(), packet.ip_repr().dst_addr()));
+    }
+
+    // The final destination answers the originator.
+    assert_eq!(
+        response,
+        Some((
+            mesh_link_local_addr(3).into(),
+            mesh_link_local_addr(1).into()
+        ))
+    );
+}
+
+#[test]
+#[cfg(feature = "medium-ieee802154")]
+fn test_sixlowpan_mesh_no_hops_left() {
+    let (iface, _, _) = setup_mesh_node(2);
+
+    let mesh_repr = SixlowpanMeshRepr {
+        hops_left: 1,
+        originator_addr: mesh_hardware_addr(1),
+        final_addr: mesh_hardware_addr(3),
+    };
+    let ieee_repr = Ieee802154Repr {
+        frame_type: Ieee802154FrameType::Data,
+        security_enabled: false,
+        frame_pending: false,
+        ack_request: false,
+        sequence_number: Some(1),
+        pan_id_compression: true,
+        frame_version: Ieee802154FrameVersion::Ieee802154_2003,
+        dst_pan_id: Some(Ieee802154Pan(0xbeef)),
+        dst_addr: Some(mesh_hardware_addr(2)),
+        src_pan_id: None,
+        src_addr: Some(mesh_hardware_addr(1)),
+    };
+    let mut frame = vec![0; ieee_repr.buffer_len() + mesh_repr.buffer_len() + 2];
+    ieee_repr.emit(&mut Ieee802154Frame::new_unchecked(&mut frame[..]));
+    let mut mesh_packet = SixlowpanMeshPacket::new_unchecked(&mut frame[ieee_repr.buffer_len()..]);
+    mesh_repr.emit(&mut mesh_packet);
+    mesh_packet.payload_mut().copy_from_slice(&[0x7a, 0x33]);
+
+    assert!(iface.inner.sixlowpan_mesh_forward(&frame).is_none());
+
+    let mut mesh_packet = SixlowpanMeshPacket::new_unchecked(&mut frame[ieee_repr.buffer_len()..]);
+    SixlowpanMeshRepr {
+        hops_left: 2,
+        ..mesh_repr
+    }
+    .emit(&mut mesh_packet);
+    assert!(iface.inner.sixlowpan_mesh_forward(&frame).is_some());
+}
+
+#[test]
+#[cfg(all(
+    feature = "medium-ieee802154",
+    feature = "proto-sixlowpan-fragmentation"
+))]
+fn test_sixlowpan_reassembly_limit() {
+    let (mut iface1, _, mut device1) = setup_mesh_node(1);
+    let (mut iface2, mut sockets2, _) = setup_mesh_node(2);
+    iface2.set_sixlowpan_reassembly_limit(1);
+    assert_eq!(iface2.sixlowpan_reassembly_limit(), 1);
+
+    // Send the first fragments of two large packets.
+    let data = vec![0x2a; 300];
+    let mut frames = vec![];
+    for seq_no in 0..2 {
+        let request = IpPacket::new_ipv6(
+            Ipv6Repr {
+                src_addr: mesh_link_local_addr(1),
+                dst_addr: mesh_link_local_addr(2),
+                next_header: IpProtocol::Icmpv6,
+                hop_limit: 64,
+                payload_len: 8 + data.len(),
+            },
+            IpPayload::Icmpv6(Icmpv6Repr::EchoRequest {
+                ident: 1,
+                seq_no,
+                data: &data,
+            }),
+        );
+        let tx_token = device1.transmit(Instant::ZERO).unwrap();
+        iface1.inner.dispatch_ieee802154(
+            mesh_hardware_addr(2),
+            tx_token,
+            PacketMeta::default(),
+            request,
+            &mut iface1.fragmenter,
+        );
+        frames.append(&mut mesh_frames(&mut device1));
+        iface1.fragmenter.reset();
+    }
+    assert_eq!(frames.len(), 2);
+
+    // Only one of them is reassembled at once.
+    for frame in &frames {
+        iface2.inner.process_ieee802154(
+            &mut sockets2,
+            PacketMeta::default(),
+            frame,
+            &mut iface2.fragments,
+        );
+    }
+    assert_eq!(iface2.fragments.assembler.count(|_| true), 1);
+}
@@ -0,0 +1,114 @@
+use super::{Interface, InterfaceInner};
+use crate::phy::{Device, Medium};
+use crate::time::Instant;
+use crate::wire::{EthernetAddress, EthernetProtocol, HardwareAddress, WolPacket, WolRepr};
+
+/// Error type for [`Interface::send_magic_packet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum WakeError {
+    /// The hardware device transmit buffer is full. Try again later.
+    Exhausted,
+    /// The password is not four or six octets long.
+    InvalidPassword,
+    /// The interface is not an Ethernet one.
+    Unsupported,
+}
+
+impl core::fmt::Display for WakeError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            WakeError::Exhausted => write!(f, "Exhausted"),
+            WakeError::InvalidPassword => write!(f, "InvalidPassword"),
+            WakeError::Unsupported => write!(f, "Unsupported"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for WakeError {}
+
+/// A function called when a Wake-on-LAN magic packet for an interface is received, with
+/// its SecureOn password, see [`Interface::set_wake_callback`].
+pub type WakeCallback = fn(password: &[u8]);
+
+impl Interface {
+    /// Send a Wake-on-LAN magic packet to the host with the hardware address `target`,
+    /// with a SecureOn `password` of four or six octets, or an empty one.
+    ///
+    /// The packet is broadcast on the link, in an Ethernet frame of EtherType `0x0842`.
+    /// A host on another network can be woken up with a UDP socket instead, by sending a
+    /// [WolRepr] to the broadcast address of its network, on port
+    /// [WOL_PORT](crate::wire::WOL_PORT).
+    pub fn send_magic_packet<D>(
+        &mut self,
+        device: &mut D,
+        target: EthernetAddress,
+        password: &[u8],
+        timestamp: Instant,
+    ) -> Result<(), WakeError>
+    where
+        D: Device + ?Sized,
+    {
+        self.inner.now = timestamp;
+
+        if self.inner.caps.medium != Medium::Ethernet {
+            return Err(WakeError::Unsupported);
+        }
+        if !matches!(password.len(), 0 | 4 | 6) {
+            return Err(WakeError::InvalidPassword);
+        }
+        let tx_token = device
+            .transmit(self.inner.now)
+            .ok_or(WakeError::Exhausted)?;
+
+        let repr = WolRepr {
+            target_addr: target,
+            password,
+        };
+        net_debug!("wake: sending magic packet for {}", target);
+        // NOTE(unwrap): an Ethernet frame is always sent.
+        self.inner
+            .dispatch_ethernet(tx_token, repr.buffer_len(), |mut frame| {
+                frame.set_dst_addr(EthernetAddress::BROADCAST);
+                frame.set_ethertype(EthernetProtocol::WakeOnLan);
+                repr.emit(&mut WolPacket::new_unchecked(frame.payload_mut()));
+            })
+            .unwrap();
+        Ok(())
+    }
+
+    /// Set the function called when a Wake-on-LAN magic packet for the hardware address
+    /// of the interface is received.
+    ///
+    /// Magic packets are recognized in the Ethernet frames of EtherType `0x0842`, and in
+    /// the UDP datagrams to port [WOL_PORT](crate::wire::WOL_PORT), whether a socket is
+    /// bound to it or not, so that e.g. power management firmware can be told about them
+    /// while the sockets are idle. The callback is called with the SecureOn password of
+    /// the packet, which is empty if there is none, and which it is up to the callback to
+    /// check.
+    pub fn set_wake_callback(&mut self, callback: Option<WakeCallback>) {
+        self.inner.wake_callback = callback;
+    }
+}
+
+impl InterfaceInner {
+    /// Check whether `payload` is a magic packet for the interface, and call the wake
+    /// callback, if any, if it is.
+    pub(super) fn wake_ingress(&self, payload: &[u8]) -> bool {
+        let Ok(packet) = WolPacket::new_checked(payload) else {
+            return false;
+        };
+        let Ok(repr) = WolRepr::parse(&packet) else {
+            return false;
+        };
+        if HardwareAddress::Ethernet(repr.target_addr) != self.hardware_addr {
+            return false;
+        }
+        net_debug!("wake: received magic packet");
+        if let Some(callback) = self.wake_callback {
+            callback(repr.password);
+        }
+        true
+    }
+}
@@ -1,6 +1,11 @@
-use crate::phy::DeviceCapabilities;
+use crate::phy::{ChecksumCapabilities, DeviceCapabilities};
 use crate::wire::*;
 
+/// The length of the Hop-by-Hop Options header carrying the Router Alert option of an
+/// IPv6 packet, padded to 8 octets.
+#[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+const IPV6_ROUTER_ALERT_LEN: usize = 8;
+
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -34,6 +39,10 @@ impl<'p> IpPacket<'p> {
     pub(crate) fn new_ipv4(ip_repr: Ipv4Repr, payload: IpPayload<'p>) -> Self {
         Self::Ipv4(Ipv4Packet {
             header: ip_repr,
+            traffic_class: 0,
+            dont_frag: true,
+            router_alert: false,
+            mark: None,
             payload,
         })
     }
@@ -42,6 +51,10 @@ impl<'p> IpPacket<'p> {
     pub(crate) fn new_ipv6(ip_repr: Ipv6Repr, payload: IpPayload<'p>) -> Self {
         Self::Ipv6(Ipv6Packet {
             header: ip_repr,
+            traffic_class: 0,
+            flow_label: 0,
+            router_alert: false,
+            mark: None,
             #[cfg(feature = "proto-ipv6-hbh")]
             hop_by_hop: None,
             #[cfg(feature = "proto-ipv6-fragmentation")]
@@ -61,6 +74,127 @@ impl<'p> IpPacket<'p> {
         }
     }
 
+    /// Return the DSCP and ECN bits of the packet, i.e. the IPv4 type of service or the IPv6
+    /// traffic class.
+    #[cfg(any(
+        feature = "medium-ethernet",
+        feature = "medium-ieee802154",
+        feature = "proto-ipv4-fragmentation"
+    ))]
+    pub(crate) fn traffic_class(&self) -> u8 {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => p.traffic_class,
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(p) => p.traffic_class,
+        }
+    }
+
+    /// Set the DSCP and ECN bits of the packet. By default, they are zero.
+    pub(crate) fn set_traffic_class(&mut self, traffic_class: u8) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => p.traffic_class = traffic_class,
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(p) => p.traffic_class = traffic_class,
+        }
+    }
+
+    /// Return the flow label of the packet. IPv4 packets have no flow label.
+    #[cfg(feature = "proto-ipv6")]
+    pub(crate) fn flow_label(&self) -> u32 {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(_) => 0,
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(p) => p.flow_label,
+        }
+    }
+
+    /// Set the flow label of the packet. By default, it is zero. IPv4 packets have no
+    /// such field.
+    #[cfg(any(feature = "socket-udp", feature = "socket-tcp"))]
+    pub(crate) fn set_flow_label(&mut self, _flow_label: u32) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(_) => (),
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(p) => p.flow_label = _flow_label,
+        }
+    }
+
+    /// Return whether the don't fragment flag is set in the packet, if it is not fragmented.
+    #[cfg(feature = "proto-ipv4")]
+    pub(crate) fn dont_frag(&self) -> bool {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => p.dont_frag,
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(_) => true,
+        }
+    }
+
+    /// Set whether the don't fragment flag is set in the packet, if it is not fragmented.
+    /// By default, it is set. IPv6 packets have no such flag.
+    #[cfg(any(feature = "socket-udp", feature = "proto-ipv4-fragmentation"))]
+    pub(crate) fn set_dont_frag(&mut self, _dont_frag: bool) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => p.dont_frag = _dont_frag,
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(_) => (),
+        }
+    }
+
+    /// Add the Router Alert option to the header of the packet, which routers must then
+    /// examine even if it is not addressed to them. It is carried in a Hop-by-Hop Options
+    /// header in IPv6 packets.
+    #[cfg(any(
+        feature = "proto-igmp",
+        all(feature = "proto-ipv6", feature = "socket-udp")
+    ))]
+    pub(crate) fn set_router_alert(&mut self) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => {
+                if !p.router_alert {
+                    p.router_alert = true;
+                    p.header.payload_len += IPV4_ROUTER_ALERT_LEN;
+                }
+            }
+            #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+            IpPacket::Ipv6(p) => {
+                if !p.router_alert {
+                    p.router_alert = true;
+                    p.header.payload_len += IPV6_ROUTER_ALERT_LEN;
+                }
+            }
+            #[cfg(all(feature = "proto-ipv6", not(feature = "socket-udp")))]
+            IpPacket::Ipv6(_) => (),
+        }
+    }
+
+    /// Return the mark of the socket that sent the packet, if any.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub(crate) fn mark(&self) -> Option<u32> {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => p.mark,
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(p) => p.mark,
+        }
+    }
+
+    /// Set the mark of the socket that sent the packet, which selects its policy routes.
+    pub(crate) fn set_mark(&mut self, mark: Option<u32>) {
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => p.mark = mark,
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(p) => p.mark = mark,
+        }
+    }
+
     pub(crate) fn payload(&self) -> &IpPayload<'p> {
         match self {
             #[cfg(feature = "proto-ipv4")]
@@ -70,6 +204,71 @@ impl<'p> IpPacket<'p> {
         }
     }
 
+    /// Emit the IP header of the packet from `ip_repr`, which is its header or the one of
+    /// one of its fragments, and return its length, i.e. where the payload starts.
+    ///
+    /// The IP representations always emit the DSCP and ECN bits as zero, and no IPv4 options,
+    /// so they are set here.
+    pub(crate) fn emit_header(
+        &self,
+        ip_repr: &IpRepr,
+        buffer: &mut [u8],
+        checksum_caps: &ChecksumCapabilities,
+    ) -> usize {
+        ip_repr.emit(&mut *buffer, checksum_caps);
+        match self {
+            #[cfg(feature = "proto-ipv4")]
+            IpPacket::Ipv4(p) => {
+                let mut header_len = ip_repr.header_len();
+                if p.traffic_class == 0 && !p.router_alert {
+                    return header_len;
+                }
+                if p.router_alert {
+                    let option = &mut buffer[header_len..header_len + IPV4_ROUTER_ALERT_LEN];
+                    Ipv4OptionRepr::RouterAlert(0).emit(&mut Ipv4Option::new_unchecked(option));
+                    header_len += IPV4_ROUTER_ALERT_LEN;
+                }
+                let mut packet = crate::wire::Ipv4Packet::new_unchecked(buffer);
+                packet.set_header_len(header_len as u8);
+                packet.set_dscp(p.traffic_class >> 2);
+                packet.set_ecn(p.traffic_class & 0x03);
+                if checksum_caps.ipv4.tx() {
+                    packet.fill_checksum();
+                }
+                header_len
+            }
+            #[cfg(feature = "proto-ipv6")]
+            IpPacket::Ipv6(p) => {
+                let header_len = ip_repr.header_len();
+                if p.traffic_class != 0 {
+                    crate::wire::Ipv6Packet::new_unchecked(&mut *buffer)
+                        .set_traffic_class(p.traffic_class);
+                }
+                #[cfg(all(feature = "proto-ipv6", feature = "socket-udp"))]
+                if p.router_alert {
+                    let mut packet = crate::wire::Ipv6Packet::new_unchecked(&mut *buffer);
+                    let next_header = packet.next_header();
+                    packet.set_next_header(IpProtocol::HopByHop);
+
+                    let header = &mut buffer[header_len..header_len + IPV6_ROUTER_ALERT_LEN];
+                    let mut header = Ipv6HopByHopHeader::new_unchecked(header);
+                    let repr = Ipv6HopByHopRepr {
+                        next_header,
+                        length: 0,
+                        data: &[],
+                    };
+                    repr.emit(&mut header);
+                    // A value of 0 tells routers that the packet contains an MLD message.
+                    let (option, padding) = header.payload_mut().split_at_mut(4);
+                    Ipv6OptionRepr::RouterAlert(0).emit(&mut Ipv6Option::new_unchecked(option));
+                    Ipv6OptionRepr::PadN(0).emit(&mut Ipv6Option::new_unchecked(padding));
+                    return header_len + IPV6_ROUTER_ALERT_LEN;
+                }
+                header_len
+            }
+        }
+    }
+
     pub(crate) fn emit_payload(
         &self,
         _ip_repr: &IpRepr,
@@ -90,9 +289,17 @@ impl<'p> IpPacket<'p> {
                 &mut Icmpv6Packet::new_unchecked(payload),
                 &caps.checksum,
             ),
-            #[cfg(feature = "socket-raw")]
-            IpPayload::Raw(raw_packet) => payload.copy_from_slice(raw_packet),
-            #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
+            #[cfg(any(
+                feature = "socket-raw",
+                feature = "proto-rpl",
+                feature = "proto-ipv4-fragmentation"
+            ))]
+            IpPayload::Raw(raw_packet) => payload[..raw_packet.len()].copy_from_slice(raw_packet),
+            #[cfg(any(
+                feature = "socket-udp",
+                feature = "socket-dns",
+                feature = "socket-dhcpv4-relay"
+            ))]
             IpPayload::Udp(udp_repr, inner_payload) => udp_repr.emit(
                 &mut UdpPacket::new_unchecked(payload),
                 &_ip_repr.src_addr(),
@@ -101,6 +308,28 @@ impl<'p> IpPacket<'p> {
                 |buf| buf.copy_from_slice(inner_payload),
                 &caps.checksum,
             ),
+            #[cfg(feature = "socket-udp")]
+            IpPayload::UdpNoChecksum(udp_repr, inner_payload) => udp_repr.emit(
+                &mut UdpPacket::new_unchecked(payload),
+                &_ip_repr.src_addr(),
+                &_ip_repr.dst_addr(),
+                inner_payload.len(),
+                |buf| buf.copy_from_slice(inner_payload),
+                &ChecksumCapabilities {
+                    udp: crate::phy::Checksum::None,
+                    ..caps.checksum.clone()
+                },
+            ),
+            #[cfg(feature = "socket-udp")]
+            IpPayload::UdpLite(udplite_repr, inner_payload) => {
+                let packet_len = udplite_repr.header_len() + inner_payload.len();
+                udplite_repr.emit(
+                    &mut UdpLitePacket::new_unchecked(&mut payload[..packet_len]),
+                    &_ip_repr.src_addr(),
+                    &_ip_repr.dst_addr(),
+                    |buf| buf.copy_from_slice(inner_payload),
+                )
+            }
             #[cfg(feature = "socket-tcp")]
             IpPayload::Tcp(mut tcp_repr) => {
                 // This is a terrible hack to make TCP performance more acceptable on systems
@@ -137,6 +366,19 @@ impl<'p> IpPacket<'p> {
                 |buf| dhcp_repr.emit(&mut DhcpPacket::new_unchecked(buf)).unwrap(),
                 &caps.checksum,
             ),
+            #[cfg(feature = "socket-dhcpv6")]
+            IpPayload::Dhcpv6(udp_repr, dhcp_repr) => udp_repr.emit(
+                &mut UdpPacket::new_unchecked(payload),
+                &_ip_repr.src_addr(),
+                &_ip_repr.dst_addr(),
+                dhcp_repr.buffer_len(),
+                |buf| {
+                    dhcp_repr
+                        .emit(&mut Dhcpv6Packet::new_unchecked(buf))
+                        .unwrap()
+                },
+                &caps.checksum,
+            ),
         }
     }
 }
@@ -146,6 +388,12 @@ impl<'p> IpPacket<'p> {
 #[cfg(feature = "proto-ipv4")]
 pub(crate) struct Ipv4Packet<'p> {
     header: Ipv4Repr,
+    traffic_class: u8,
+    dont_frag: bool,
+    /// Whether the header has a Router Alert option, which the payload length of `header`
+    /// accounts for.
+    router_alert: bool,
+    mark: Option<u32>,
     payload: IpPayload<'p>,
 }
 
@@ -154,6 +402,12 @@ pub(crate) struct Ipv4Packet<'p> {
 #[cfg(feature = "proto-ipv6")]
 pub(crate) struct Ipv6Packet<'p> {
     header: Ipv6Repr,
+    traffic_class: u8,
+    flow_label: u32,
+    /// Whether the header is followed by a Hop-by-Hop Options header with a Router Alert
+    /// option, which the payload length of `header` accounts for.
+    router_alert: bool,
+    mark: Option<u32>,
     #[cfg(feature = "proto-ipv6-hbh")]
     hop_by_hop: Option<Ipv6HopByHopRepr<'p>>,
     #[cfg(feature = "proto-ipv6-fragmentation")]
@@ -169,17 +423,31 @@ pub(crate) enum IpPayload<'p> {
     #[cfg(feature = "proto-ipv4")]
     Icmpv4(Icmpv4Repr<'p>),
     #[cfg(feature = "proto-igmp")]
-    Igmp(IgmpRepr),
+    Igmp(IgmpRepr<'p>),
     #[cfg(feature = "proto-ipv6")]
     Icmpv6(Icmpv6Repr<'p>),
-    #[cfg(feature = "socket-raw")]
+    #[cfg(any(
+        feature = "socket-raw",
+        feature = "proto-rpl",
+        feature = "proto-ipv4-fragmentation"
+    ))]
     Raw(&'p [u8]),
-    #[cfg(any(feature = "socket-udp", feature = "socket-dns"))]
+    #[cfg(any(
+        feature = "socket-udp",
+        feature = "socket-dns",
+        feature = "socket-dhcpv4-relay"
+    ))]
     Udp(UdpRepr, &'p [u8]),
+    #[cfg(feature = "socket-udp")]
+    UdpNoChecksum(UdpRepr, &'p [u8]),
+    #[cfg(feature = "socket-udp")]
+    UdpLite(UdpLiteRepr, &'p [u8]),
     #[cfg(feature = "socket-tcp")]
     Tcp(TcpRepr<'p>),
     #[cfg(feature = "socket-dhcpv4")]
     Dhcpv4(UdpRepr, DhcpRepr<'p>),
+    #[cfg(feature = "socket-dhcpv6")]
+    Dhcpv6(UdpRepr, Dhcpv6Repr<'p>),
 }
 
 #[cfg(any(feature = "proto-ipv4", feature = "proto-ipv6"))]
@@ -199,13 +467,11 @@ pub(crate) fn icmp_reply_payload_len(len: usize, mtu: usize, header_len: usize)
 pub(crate) enum IgmpReportState {
     Inactive,
     ToGeneralQuery {
-        version: IgmpVersion,
         timeout: crate::time::Instant,
         interval: crate::time::Duration,
         next_index: usize,
     },
     ToSpecificQuery {
-        version: IgmpVersion,
         timeout: crate::time::Instant,
         group: Ipv4Address,
     },
@@ -4,22 +4,49 @@ The `iface` module deals with the *network interfaces*. It filters incoming fram
 provides lookup and caching of hardware addresses, and handles management packets.
 */
 
+mod filter;
 #[cfg(any(feature = "proto-ipv4", feature = "proto-sixlowpan"))]
 mod fragmentation;
 mod interface;
 #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
 mod neighbor;
 mod route;
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+mod router;
 #[cfg(feature = "proto-rpl")]
 mod rpl;
 mod socket_meta;
 mod socket_set;
+mod stats;
+mod timer_wheel;
 
 mod ip_packet;
 
+pub use self::filter::{Filter, FilterAction, FilterDirection, FilterRule, FilterTableFull};
+#[cfg(feature = "proto-ipv4-fragmentation")]
+pub use self::fragmentation::ReassemblyStats;
+#[cfg(all(
+    feature = "proto-ipv6",
+    any(feature = "medium-ethernet", feature = "medium-ieee802154")
+))]
+pub use self::interface::DadEvent;
+#[cfg(feature = "medium-ieee802154")]
+pub use self::interface::SixlowpanMeshRoute;
+pub use self::interface::{
+    AddressLifetime, Config, ConfigCallback, ConfigChange, DestinationEntry, HostModel, Interface,
+    InterfaceInner as Context, LinkCallback, ReversePathFilter,
+};
 #[cfg(feature = "proto-igmp")]
-pub use self::interface::MulticastError;
-pub use self::interface::{Config, Interface, InterfaceInner as Context};
+pub use self::interface::{MulticastError, MulticastFilterMode};
+#[cfg(feature = "medium-ethernet")]
+pub use self::interface::{WakeCallback, WakeError};
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+pub use self::neighbor::{NeighborCacheFull, NeighborEntry, NeighborState};
 
 pub use self::route::{Route, RouteTableFull, Routes};
+#[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+pub use self::router::{Port, Router};
+#[cfg(feature = "proto-rpl")]
+pub use self::rpl::{RplConfig, RplRootConfig};
 pub use self::socket_set::{SocketHandle, SocketSet, SocketStorage};
+pub use self::stats::{ProtocolStats, Stats};
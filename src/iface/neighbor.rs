@@ -15,9 +15,48 @@ use crate::wire::{HardwareAddress, IpAddress};
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Neighbor {
     hardware_addr: HardwareAddress,
-    expires_at: Instant,
+    /// `None` means "forever", for permanent entries.
+    expires_at: Option<Instant>,
+    filled_at: Instant,
 }
 
+/// The state of a neighbor cache entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum NeighborState {
+    /// The entry was installed by hand, and never expires nor gets evicted.
+    Permanent,
+    /// The entry was learned from the network, and is used.
+    Reachable,
+    /// The entry was learned from the network, but expired; it is only kept until its
+    /// room is needed.
+    Stale,
+}
+
+/// An entry of the neighbor cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NeighborEntry {
+    pub protocol_addr: IpAddress,
+    pub hardware_addr: HardwareAddress,
+    pub state: NeighborState,
+    /// Time since the entry was installed or last learned.
+    pub age: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct NeighborCacheFull;
+
+impl core::fmt::Display for NeighborCacheFull {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "Neighbor cache full")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for NeighborCacheFull {}
+
 /// An answer to a neighbor cache lookup.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -46,6 +85,14 @@ impl Answer {
 pub struct Cache {
     storage: LinearMap<IpAddress, Neighbor, IFACE_NEIGHBOR_CACHE_COUNT>,
     silent_until: Instant,
+    /// The addresses whose hardware address was asked for, and when the question expires.
+    solicited: LinearMap<IpAddress, Instant, IFACE_NEIGHBOR_CACHE_COUNT>,
+    /// Whether entries are only created or changed by answers to our questions.
+    strict: bool,
+    /// When the next unsolicited entry may be created, in strict mode.
+    unsolicited_until: Instant,
+    /// Whether an address became known since `take_filled` was last called.
+    filled: bool,
 }
 
 impl Cache {
@@ -55,11 +102,102 @@ impl Cache {
     /// Neighbor entry lifetime, in milliseconds.
     pub(crate) const ENTRY_LIFETIME: Duration = Duration::from_millis(60_000);
 
+    /// How long the answers to a discovery request are waited for, in milliseconds.
+    pub(crate) const SOLICIT_LIFETIME: Duration = Duration::from_millis(3_000);
+
+    /// Minimum delay between the unsolicited entries created in strict mode, in
+    /// milliseconds.
+    pub(crate) const UNSOLICITED_INTERVAL: Duration = Duration::from_millis(1_000);
+
     /// Create a cache.
     pub fn new() -> Self {
         Self {
             storage: LinearMap::new(),
             silent_until: Instant::from_millis(0),
+            solicited: LinearMap::new(),
+            strict: false,
+            unsolicited_until: Instant::from_millis(0),
+            filled: false,
+        }
+    }
+
+    /// Return whether an address became known since this function was last called.
+    pub(crate) fn take_filled(&mut self) -> bool {
+        core::mem::replace(&mut self.filled, false)
+    }
+
+    /// Return whether entries are only created or changed by answers to our questions.
+    pub(crate) fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Set whether entries are only created or changed by answers to our questions.
+    ///
+    /// In strict mode, the information received about an address is only trusted if a
+    /// discovery request for it was sent recently, i.e. if packets are waiting for it.
+    /// Otherwise it may refresh an entry with the same hardware address, or create an
+    /// entry, at most once per `UNSOLICITED_INTERVAL`, but never change an entry.
+    pub(crate) fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Remember that a discovery request for `protocol_addr` was sent.
+    pub(crate) fn solicit(&mut self, protocol_addr: IpAddress, timestamp: Instant) {
+        let expires_at = timestamp + Self::SOLICIT_LIFETIME;
+        if let Err((protocol_addr, expires_at)) = self.solicited.insert(protocol_addr, expires_at) {
+            // NOTE(unwrap): the map is full, so not empty.
+            let oldest = self
+                .solicited
+                .iter()
+                .min_by_key(|(_, expires_at)| **expires_at)
+                .map(|(protocol_addr, _)| *protocol_addr)
+                .unwrap();
+            self.solicited.remove(&oldest);
+            // NOTE(unwrap): room was just made.
+            self.solicited.insert(protocol_addr, expires_at).unwrap();
+        }
+    }
+
+    /// Return whether the information received about `protocol_addr` may be used to
+    /// create or change its entry, consuming the discovery request for it, if any.
+    fn accepts(
+        &mut self,
+        protocol_addr: &IpAddress,
+        hardware_addr: &HardwareAddress,
+        timestamp: Instant,
+    ) -> bool {
+        let solicited = self
+            .solicited
+            .remove(protocol_addr)
+            .map_or(false, |expires_at| timestamp < expires_at);
+        if !self.strict || solicited {
+            return true;
+        }
+
+        match self.storage.get(protocol_addr) {
+            Some(neighbor) if neighbor.expires_at.map_or(false, |t| timestamp < t) => {
+                if neighbor.hardware_addr != *hardware_addr {
+                    net_debug!(
+                        "not replacing entry of {} with unsolicited {}",
+                        protocol_addr,
+                        hardware_addr
+                    );
+                    return false;
+                }
+                true
+            }
+            _ => {
+                if timestamp < self.unsolicited_until {
+                    net_debug!(
+                        "not filling {} => {}, unsolicited entries are rate limited",
+                        protocol_addr,
+                        hardware_addr
+                    );
+                    return false;
+                }
+                self.unsolicited_until = timestamp + Self::UNSOLICITED_INTERVAL;
+                true
+            }
         }
     }
 
@@ -72,12 +210,27 @@ impl Cache {
         debug_assert!(protocol_addr.is_unicast());
         debug_assert!(hardware_addr.is_unicast());
 
+        if let Some(Neighbor {
+            expires_at: None, ..
+        }) = self.storage.get(&protocol_addr)
+        {
+            net_trace!("not replacing permanent entry of {}", protocol_addr);
+            return;
+        }
+        if !self.accepts(&protocol_addr, &hardware_addr, timestamp) {
+            return;
+        }
+
         let neighbor = Neighbor {
-            expires_at: timestamp + Self::ENTRY_LIFETIME,
             hardware_addr,
+            expires_at: Some(timestamp + Self::ENTRY_LIFETIME),
+            filled_at: timestamp,
         };
         match self.storage.insert(protocol_addr, neighbor) {
             Ok(Some(old_neighbor)) => {
+                if old_neighbor.expires_at.map_or(false, |t| t <= timestamp) {
+                    self.filled = true;
+                }
                 if old_neighbor.hardware_addr != hardware_addr {
                     net_trace!(
                         "replaced {} => {} (was {})",
@@ -89,19 +242,22 @@ impl Cache {
             }
             Ok(None) => {
                 net_trace!("filled {} => {} (was empty)", protocol_addr, hardware_addr);
+                self.filled = true;
             }
             Err((protocol_addr, neighbor)) => {
                 // If we're going down this branch, it means the cache is full, and we need to evict an entry.
-                let old_protocol_addr = *self
-                    .storage
-                    .iter()
-                    .min_by_key(|(_, neighbor)| neighbor.expires_at)
-                    .expect("empty neighbor cache storage")
-                    .0;
+                let Some(old_protocol_addr) = self.oldest() else {
+                    net_debug!(
+                        "neighbor cache full of permanent entries, dropping {}",
+                        protocol_addr
+                    );
+                    return;
+                };
 
                 let _old_neighbor = self.storage.remove(&old_protocol_addr).unwrap();
                 match self.storage.insert(protocol_addr, neighbor) {
                     Ok(None) => {
+                        self.filled = true;
                         net_trace!(
                             "filled {} => {} (evicted {} => {})",
                             protocol_addr,
@@ -117,15 +273,91 @@ impl Cache {
         }
     }
 
+    /// Install a permanent entry, which replaces any learned one for the same address.
+    ///
+    /// The entry that expires first is evicted if the cache is full; permanent entries
+    /// never are.
+    pub fn fill_permanent(
+        &mut self,
+        protocol_addr: IpAddress,
+        hardware_addr: HardwareAddress,
+        timestamp: Instant,
+    ) -> Result<(), NeighborCacheFull> {
+        let neighbor = Neighbor {
+            hardware_addr,
+            expires_at: None,
+            filled_at: timestamp,
+        };
+        if let Err((protocol_addr, neighbor)) = self.storage.insert(protocol_addr, neighbor) {
+            let old_protocol_addr = self.oldest().ok_or(NeighborCacheFull)?;
+            self.storage.remove(&old_protocol_addr);
+            // NOTE(unwrap): room was just made.
+            self.storage.insert(protocol_addr, neighbor).unwrap();
+        }
+        net_trace!("filled {} => {} (permanent)", protocol_addr, hardware_addr);
+        self.filled = true;
+        Ok(())
+    }
+
+    /// Make the current learned entry of `protocol_addr` permanent, and return its
+    /// hardware address; or `None` if there is none.
+    pub fn lock(
+        &mut self,
+        protocol_addr: &IpAddress,
+        timestamp: Instant,
+    ) -> Option<HardwareAddress> {
+        let neighbor = self.storage.get_mut(protocol_addr)?;
+        if !neighbor.expires_at.map_or(false, |t| timestamp < t) {
+            return None;
+        }
+        neighbor.expires_at = None;
+        net_trace!("locked {} => {}", protocol_addr, neighbor.hardware_addr);
+        Some(neighbor.hardware_addr)
+    }
+
+    /// Remove the entry of `protocol_addr`, permanent or not, and return its hardware
+    /// address.
+    pub fn remove(&mut self, protocol_addr: &IpAddress) -> Option<HardwareAddress> {
+        self.storage
+            .remove(protocol_addr)
+            .map(|neighbor| neighbor.hardware_addr)
+    }
+
+    /// Return the entries of the cache, in no particular order.
+    pub fn entries(&self, timestamp: Instant) -> impl Iterator<Item = NeighborEntry> + '_ {
+        self.storage
+            .iter()
+            .map(move |(protocol_addr, neighbor)| NeighborEntry {
+                protocol_addr: *protocol_addr,
+                hardware_addr: neighbor.hardware_addr,
+                state: match neighbor.expires_at {
+                    None => NeighborState::Permanent,
+                    Some(expires_at) if timestamp < expires_at => NeighborState::Reachable,
+                    Some(_) => NeighborState::Stale,
+                },
+                age: timestamp - neighbor.filled_at.min(timestamp),
+            })
+    }
+
+    /// Return the address of the learned entry that expires first, if any.
+    fn oldest(&self) -> Option<IpAddress> {
+        self.storage
+            .iter()
+            .filter_map(|(protocol_addr, neighbor)| Some((protocol_addr, neighbor.expires_at?)))
+            .min_by_key(|(_, expires_at)| *expires_at)
+            .map(|(protocol_addr, _)| *protocol_addr)
+    }
+
     pub(crate) fn lookup(&self, protocol_addr: &IpAddress, timestamp: Instant) -> Answer {
         assert!(protocol_addr.is_unicast());
 
         if let Some(&Neighbor {
             expires_at,
             hardware_addr,
+            ..
         }) = self.storage.get(protocol_addr)
         {
-            if timestamp < expires_at {
+            if expires_at.map_or(true, |expires_at| timestamp < expires_at) {
                 return Answer::Found(hardware_addr);
             }
         }
@@ -141,8 +373,18 @@ impl Cache {
         self.silent_until = timestamp + Self::SILENT_TIME;
     }
 
+    /// Remove the learned entries, keeping the permanent ones, and forget the discovery
+    /// requests sent.
     pub(crate) fn flush(&mut self) {
-        self.storage.clear()
+        self.solicited.clear();
+        while let Some(protocol_addr) = self
+            .storage
+            .iter()
+            .find(|(_, neighbor)| neighbor.expires_at.is_some())
+            .map(|(protocol_addr, _)| *protocol_addr)
+        {
+            self.storage.remove(&protocol_addr);
+        }
     }
 }
 
@@ -191,6 +433,27 @@ mod test {
             .found());
     }
 
+    #[test]
+    fn test_take_filled() {
+        let mut cache = Cache::new();
+        assert!(!cache.take_filled());
+
+        cache.fill(MOCK_IP_ADDR_1, HADDR_A, Instant::from_millis(0));
+        assert!(cache.take_filled());
+        assert!(!cache.take_filled());
+
+        // Refreshing a known address does not make it known again.
+        cache.fill(MOCK_IP_ADDR_1, HADDR_B, Instant::from_millis(1000));
+        assert!(!cache.take_filled());
+
+        cache.fill(
+            MOCK_IP_ADDR_1,
+            HADDR_B,
+            Instant::from_millis(0) + Cache::ENTRY_LIFETIME * 2,
+        );
+        assert!(cache.take_filled());
+    }
+
     #[test]
     fn test_expire() {
         let mut cache = Cache::new();
@@ -290,4 +553,180 @@ mod test {
             .lookup(&MOCK_IP_ADDR_1, Instant::from_millis(0))
             .found());
     }
+
+    #[test]
+    fn test_permanent() {
+        let mut cache = Cache::new();
+
+        cache.fill(MOCK_IP_ADDR_1, HADDR_A, Instant::from_millis(0));
+        assert_eq!(
+            cache.fill_permanent(MOCK_IP_ADDR_1, HADDR_B, Instant::from_millis(0)),
+            Ok(())
+        );
+        // Permanent entries are not replaced by learned ones, nor do they expire.
+        cache.fill(MOCK_IP_ADDR_1, HADDR_C, Instant::from_millis(0));
+        assert_eq!(
+            cache.lookup(
+                &MOCK_IP_ADDR_1,
+                Instant::from_millis(0) + Cache::ENTRY_LIFETIME * 2
+            ),
+            Answer::Found(HADDR_B)
+        );
+
+        // Nor are they flushed or evicted.
+        cache.flush();
+        cache.fill(MOCK_IP_ADDR_2, HADDR_B, Instant::from_millis(0));
+        cache.fill(MOCK_IP_ADDR_3, HADDR_C, Instant::from_millis(50));
+        cache.fill(MOCK_IP_ADDR_4, HADDR_D, Instant::from_millis(100));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1, Instant::from_millis(0)),
+            Answer::Found(HADDR_B)
+        );
+        assert!(!cache
+            .lookup(&MOCK_IP_ADDR_2, Instant::from_millis(0))
+            .found());
+
+        // Once the cache is full of permanent entries, nothing else fits.
+        for (addr, haddr) in [(MOCK_IP_ADDR_3, HADDR_C), (MOCK_IP_ADDR_4, HADDR_D)] {
+            assert_eq!(
+                cache.fill_permanent(addr, haddr, Instant::from_millis(0)),
+                Ok(())
+            );
+        }
+        assert_eq!(
+            cache.fill_permanent(MOCK_IP_ADDR_2, HADDR_A, Instant::from_millis(0)),
+            Err(NeighborCacheFull)
+        );
+        cache.fill(MOCK_IP_ADDR_2, HADDR_A, Instant::from_millis(0));
+        assert!(!cache
+            .lookup(&MOCK_IP_ADDR_2, Instant::from_millis(0))
+            .found());
+
+        assert_eq!(cache.remove(&MOCK_IP_ADDR_1), Some(HADDR_B));
+        assert_eq!(cache.remove(&MOCK_IP_ADDR_1), None);
+        assert!(!cache
+            .lookup(&MOCK_IP_ADDR_1, Instant::from_millis(0))
+            .found());
+    }
+
+    #[test]
+    fn test_strict() {
+        let mut cache = Cache::new();
+        cache.set_strict(true);
+
+        // Answers to a discovery request are trusted.
+        cache.solicit(MOCK_IP_ADDR_1, Instant::from_millis(0));
+        cache.fill(MOCK_IP_ADDR_1, HADDR_A, Instant::from_millis(100));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1, Instant::from_millis(100)),
+            Answer::Found(HADDR_A)
+        );
+
+        // Unsolicited information refreshes an entry, but does not change it.
+        cache.fill(MOCK_IP_ADDR_1, HADDR_B, Instant::from_millis(200));
+        cache.fill(MOCK_IP_ADDR_1, HADDR_A, Instant::from_millis(50_000));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1, Instant::from_millis(100_000)),
+            Answer::Found(HADDR_A)
+        );
+
+        // Nor does it create entries more than once per interval.
+        cache.fill(MOCK_IP_ADDR_2, HADDR_B, Instant::from_millis(200));
+        cache.fill(MOCK_IP_ADDR_3, HADDR_C, Instant::from_millis(300));
+        assert!(cache
+            .lookup(&MOCK_IP_ADDR_2, Instant::from_millis(300))
+            .found());
+        assert!(!cache
+            .lookup(&MOCK_IP_ADDR_3, Instant::from_millis(300))
+            .found());
+        cache.fill(MOCK_IP_ADDR_3, HADDR_C, Instant::from_millis(1_200));
+        assert!(cache
+            .lookup(&MOCK_IP_ADDR_3, Instant::from_millis(1_200))
+            .found());
+
+        // Late answers are not trusted.
+        cache.solicit(MOCK_IP_ADDR_1, Instant::from_millis(2_000));
+        cache.fill(
+            MOCK_IP_ADDR_1,
+            HADDR_B,
+            Instant::from_millis(2_000) + Cache::SOLICIT_LIFETIME,
+        );
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1, Instant::from_millis(6_000)),
+            Answer::Found(HADDR_A)
+        );
+        cache.solicit(MOCK_IP_ADDR_1, Instant::from_millis(6_000));
+        cache.fill(MOCK_IP_ADDR_1, HADDR_B, Instant::from_millis(6_000));
+        assert_eq!(
+            cache.lookup(&MOCK_IP_ADDR_1, Instant::from_millis(6_000)),
+            Answer::Found(HADDR_B)
+        );
+    }
+
+    #[test]
+    fn test_lock() {
+        let mut cache = Cache::new();
+
+        assert_eq!(cache.lock(&MOCK_IP_ADDR_1, Instant::from_millis(0)), None);
+        cache.fill(MOCK_IP_ADDR_1, HADDR_A, Instant::from_millis(0));
+        assert_eq!(
+            cache.lock(
+                &MOCK_IP_ADDR_1,
+                Instant::from_millis(0) + Cache::ENTRY_LIFETIME
+            ),
+            None
+        );
+        assert_eq!(
+            cache.lock(&MOCK_IP_ADDR_1, Instant::from_millis(100)),
+            Some(HADDR_A)
+        );
+
+        // A locked entry neither expires nor changes.
+        cache.solicit(MOCK_IP_ADDR_1, Instant::from_millis(100));
+        cache.fill(MOCK_IP_ADDR_1, HADDR_B, Instant::from_millis(100));
+        assert_eq!(
+            cache.lookup(
+                &MOCK_IP_ADDR_1,
+                Instant::from_millis(0) + Cache::ENTRY_LIFETIME * 2
+            ),
+            Answer::Found(HADDR_A)
+        );
+    }
+
+    #[test]
+    fn test_entries() {
+        let mut cache = Cache::new();
+
+        cache.fill(MOCK_IP_ADDR_1, HADDR_A, Instant::from_millis(0));
+        cache.fill(MOCK_IP_ADDR_2, HADDR_B, Instant::from_millis(50_000));
+        cache
+            .fill_permanent(MOCK_IP_ADDR_3, HADDR_C, Instant::from_millis(0))
+            .unwrap();
+
+        let mut entries: std::vec::Vec<_> = cache.entries(Instant::from_millis(70_000)).collect();
+        entries.sort_by_key(|entry| entry.age);
+        assert_eq!(
+            entries,
+            [
+                NeighborEntry {
+                    protocol_addr: MOCK_IP_ADDR_2,
+                    hardware_addr: HADDR_B,
+                    state: NeighborState::Reachable,
+                    age: Duration::from_secs(20),
+                },
+                NeighborEntry {
+                    protocol_addr: MOCK_IP_ADDR_1,
+                    hardware_addr: HADDR_A,
+                    state: NeighborState::Stale,
+                    age: Duration::from_secs(70),
+                },
+                NeighborEntry {
+                    protocol_addr: MOCK_IP_ADDR_3,
+                    hardware_addr: HADDR_C,
+                    state: NeighborState::Permanent,
+                    age: Duration::from_secs(70),
+                },
+            ]
+        );
+    }
 }
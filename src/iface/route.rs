@@ -31,6 +31,16 @@ pub struct Route {
     pub preferred_until: Option<Instant>,
     /// `None` means "forever".
     pub expires_at: Option<Instant>,
+    /// The maximum segment size of TCP connections to this prefix, e.g. when a tunnel or
+    /// another hop with a small MTU sits behind the router. `None` means no clamping.
+    pub mss: Option<u16>,
+    /// Only route the packets from source addresses in this prefix. `None` means any.
+    pub src_cidr: Option<IpCidr>,
+    /// Only route the packets with this DSCP. `None` means any.
+    pub dscp: Option<u8>,
+    /// Only route the packets of the sockets with this mark, set with
+    /// [`SocketSet::set_mark`](crate::iface::SocketSet::set_mark). `None` means any.
+    pub mark: Option<u32>,
 }
 
 #[cfg(feature = "proto-ipv4")]
@@ -48,6 +58,10 @@ impl Route {
             via_router: gateway.into(),
             preferred_until: None,
             expires_at: None,
+            mss: None,
+            src_cidr: None,
+            dscp: None,
+            mark: None,
         }
     }
 
@@ -59,11 +73,49 @@ impl Route {
             via_router: gateway.into(),
             preferred_until: None,
             expires_at: None,
+            mss: None,
+            src_cidr: None,
+            dscp: None,
+            mark: None,
         }
     }
+
+    /// Return whether the route only applies to some packets towards its prefix, depending
+    /// on their source address, DSCP or mark.
+    pub fn is_policy(&self) -> bool {
+        self.src_cidr.is_some() || self.dscp.is_some() || self.mark.is_some()
+    }
+
+    fn matches(&self, addr: &IpAddress, key: &RouteKey, timestamp: Instant) -> bool {
+        if let Some(expires_at) = self.expires_at {
+            if timestamp > expires_at {
+                return false;
+            }
+        }
+        self.cidr.contains_addr(addr)
+            && self.src_cidr.map_or(true, |cidr| {
+                key.src_addr
+                    .map_or(false, |src_addr| cidr.contains_addr(&src_addr))
+            })
+            && self.dscp.map_or(true, |dscp| dscp == key.dscp)
+            && self.mark.map_or(true, |mark| Some(mark) == key.mark)
+    }
+}
+
+/// The properties of a packet, besides its destination, that policy routes select.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct RouteKey {
+    pub src_addr: Option<IpAddress>,
+    pub dscp: u8,
+    pub mark: Option<u32>,
 }
 
 /// A routing table.
+///
+/// The most specific route to a destination is used. Policy routes, which only apply to
+/// some packets, take precedence over the others when they match: for example, a default
+/// route for the packets of some source address or mark sends them through another gateway
+/// than the default route of the other packets.
 #[derive(Debug)]
 pub struct Routes {
     storage: Vec<Route, IFACE_MAX_ROUTE_COUNT>,
@@ -121,7 +173,7 @@ impl Routes {
             .storage
             .iter()
             .enumerate()
-            .find(|(_, r)| r.cidr == IPV4_DEFAULT)
+            .find(|(_, r)| r.cidr == IPV4_DEFAULT && !r.is_policy())
         {
             Some(self.storage.remove(i))
         } else {
@@ -138,7 +190,7 @@ impl Routes {
             .storage
             .iter()
             .enumerate()
-            .find(|(_, r)| r.cidr == IPV6_DEFAULT)
+            .find(|(_, r)| r.cidr == IPV6_DEFAULT && !r.is_policy())
         {
             Some(self.storage.remove(i))
         } else {
@@ -146,23 +198,63 @@ impl Routes {
         }
     }
 
+    #[allow(unused)] // only used by AnyIP, for IPv4
     pub(crate) fn lookup(&self, addr: &IpAddress, timestamp: Instant) -> Option<IpAddress> {
+        self.lookup_for(addr, &RouteKey::default(), timestamp)
+    }
+
+    /// Return the router towards `addr` for the packets with the properties `key`.
+    pub(crate) fn lookup_for(
+        &self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        timestamp: Instant,
+    ) -> Option<IpAddress> {
+        self.lookup_route(addr, key, timestamp)
+            .map(|route| route.via_router)
+    }
+
+    /// Return the router towards `addr` for the packets with the properties `key`, along
+    /// with when its route expires, if ever.
+    pub(crate) fn lookup_expiry(
+        &self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        timestamp: Instant,
+    ) -> Option<(IpAddress, Option<Instant>)> {
+        self.lookup_route(addr, key, timestamp)
+            .map(|route| (route.via_router, route.expires_at))
+    }
+
+    /// Return the TCP maximum segment size configured on the route to `addr`, if any.
+    pub(crate) fn lookup_mss(&self, addr: &IpAddress, timestamp: Instant) -> Option<u16> {
+        self.lookup_route(addr, &RouteKey::default(), timestamp)
+            .and_then(|route| route.mss)
+    }
+
+    /// Return whether the route to `addr` is a policy route, along with its prefix length,
+    /// if there is one.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ip"))]
+    pub(crate) fn lookup_rank(
+        &self,
+        addr: &IpAddress,
+        key: &RouteKey,
+        timestamp: Instant,
+    ) -> Option<(bool, u8)> {
+        self.lookup_route(addr, key, timestamp)
+            .map(|route| (route.is_policy(), route.cidr.prefix_len()))
+    }
+
+    fn lookup_route(&self, addr: &IpAddress, key: &RouteKey, timestamp: Instant) -> Option<&Route> {
         assert!(addr.is_unicast());
 
         self.storage
             .iter()
             // Keep only matching routes
-            .filter(|route| {
-                if let Some(expires_at) = route.expires_at {
-                    if timestamp > expires_at {
-                        return false;
-                    }
-                }
-                route.cidr.contains_addr(addr)
-            })
-            // pick the most specific one (highest prefix_len)
-            .max_by_key(|route| route.cidr.prefix_len())
-            .map(|route| route.via_router)
+            .filter(|route| route.matches(addr, key, timestamp))
+            // pick the matching policy routes first, then the most specific one (highest
+            // prefix_len)
+            .max_by_key(|route| (route.is_policy(), route.cidr.prefix_len()))
     }
 }
 
@@ -246,6 +338,10 @@ mod test {
             via_router: ADDR_1A.into(),
             preferred_until: None,
             expires_at: None,
+            mss: None,
+            src_cidr: None,
+            dscp: None,
+            mark: None,
         };
         routes.update(|storage| {
             storage.push(route).unwrap();
@@ -277,6 +373,10 @@ mod test {
             via_router: ADDR_2A.into(),
             preferred_until: Some(Instant::from_millis(10)),
             expires_at: Some(Instant::from_millis(10)),
+            mss: None,
+            src_cidr: None,
+            dscp: None,
+            mark: None,
         };
         routes.update(|storage| {
             storage.push(route2).unwrap();
@@ -324,4 +424,69 @@ mod test {
             Some(ADDR_2A.into())
         );
     }
+
+    #[test]
+    fn test_policy() {
+        let mut routes = Routes::new();
+        let route = |via_router: IpAddress| Route {
+            cidr: IpCidr::new(ADDR_2A.into(), 0),
+            via_router,
+            preferred_until: None,
+            expires_at: None,
+            mss: None,
+            src_cidr: None,
+            dscp: None,
+            mark: None,
+        };
+        routes.update(|storage| {
+            storage.push(route(ADDR_1A.into())).unwrap();
+            storage
+                .push(Route {
+                    src_cidr: Some(cidr_2().into()),
+                    ..route(ADDR_1B.into())
+                })
+                .unwrap();
+            storage
+                .push(Route {
+                    mark: Some(7),
+                    ..route(ADDR_1C.into())
+                })
+                .unwrap();
+            storage
+                .push(Route {
+                    cidr: cidr_1().into(),
+                    dscp: Some(46),
+                    ..route(ADDR_2B.into())
+                })
+                .unwrap();
+        });
+
+        let lookup = |addr: IpAddress, key| routes.lookup_for(&addr, &key, Instant::ZERO);
+        let key = RouteKey::default();
+        assert_eq!(lookup(ADDR_1C.into(), key), Some(ADDR_1A.into()));
+        let key = RouteKey {
+            src_addr: Some(ADDR_2B.into()),
+            ..RouteKey::default()
+        };
+        assert_eq!(lookup(ADDR_1C.into(), key), Some(ADDR_1B.into()));
+        let key = RouteKey {
+            src_addr: Some(ADDR_1A.into()),
+            mark: Some(7),
+            ..RouteKey::default()
+        };
+        assert_eq!(lookup(ADDR_1C.into(), key), Some(ADDR_1C.into()));
+        let key = RouteKey {
+            mark: Some(8),
+            ..RouteKey::default()
+        };
+        assert_eq!(lookup(ADDR_1C.into(), key), Some(ADDR_1A.into()));
+
+        // Policy routes are only used for their prefix.
+        let key = RouteKey {
+            dscp: 46,
+            ..RouteKey::default()
+        };
+        assert_eq!(lookup(ADDR_1C.into(), key), Some(ADDR_2B.into()));
+        assert_eq!(lookup(ADDR_2A.into(), key), Some(ADDR_1A.into()));
+    }
 }
@@ -0,0 +1,728 @@
+use managed::ManagedSlice;
+
+use super::interface::{forward_route_key, Forward, Forwarded, FragmentsBuffer};
+use super::ip_packet::IpPacket;
+use super::route::RouteKey;
+use super::{Interface, SocketSet};
+use crate::phy::Device;
+use crate::time::Instant;
+use crate::wire::*;
+
+/// An interface of a [Router], with its device and the sockets bound to it.
+pub struct Port<'a, D: Device> {
+    pub iface: Interface,
+    pub device: D,
+    /// The sockets that send and receive through this interface only.
+    pub sockets: SocketSet<'a>,
+}
+
+impl<'a, D: Device> Port<'a, D> {
+    /// Create a port from an interface, its device and its sockets.
+    pub fn new(iface: Interface, device: D, sockets: SocketSet<'a>) -> Self {
+        Self {
+            iface,
+            device,
+            sockets,
+        }
+    }
+}
+
+/// Several interfaces, and the routing of IP packets between them.
+///
+/// Each interface is polled with its own sockets, which only send and receive through it.
+/// Unicast packets received by an interface for an address that is not one of its own are
+/// forwarded through the interface with the most specific route to their destination:
+/// the network of one of its addresses, or else one of its routes. Their TTL or hop limit
+/// is decremented, and the ICMP error sent back when it runs out, when no other interface
+/// has a route, or when the packet is too large for the MTU of the outgoing interface and
+/// cannot be fragmented.
+///
+/// Packets are never sent back through the interface they were received on. Packets for
+/// an address of another interface are dropped, unless the receiving interface uses the
+/// [weak host model](Interface::set_host_model). Only Ethernet and IP interfaces forward
+/// packets.
+pub struct Router<'a, D: Device> {
+    ports: ManagedSlice<'a, Port<'a, D>>,
+}
+
+impl<'a, D: Device> Router<'a, D> {
+    /// Create a router from its ports.
+    ///
+    /// # Examples
+    ///
+    /// Creating a router with owned storage:
+    ///
+    /// ```rust,ignore
+    /// let router = Router::new(vec![
+    ///     Port::new(lan_iface, lan_device, lan_sockets),
+    ///     Port::new(wan_iface, wan_device, wan_sockets),
+    /// ]);
+    /// ```
+    pub fn new<P>(ports: P) -> Self
+    where
+        P: Into<ManagedSlice<'a, Port<'a, D>>>,
+    {
+        Self {
+            ports: ports.into(),
+        }
+    }
+
+    /// Get the ports of the router.
+    pub fn ports(&self) -> &[Port<'a, D>] {
+        &self.ports
+    }
+
+    /// Get the ports of the router mutably, e.g. to use their sockets.
+    pub fn ports_mut(&mut self) -> &mut [Port<'a, D>] {
+        &mut self.ports
+    }
+
+    /// Poll every interface of the router, forwarding the packets they receive for other
+    /// networks.
+    ///
+    /// This function returns a boolean value indicating whether any packets were
+    /// processed or emitted, and thus, whether the readiness of any socket might
+    /// have changed.
+    pub fn poll(&mut self, timestamp: Instant) -> bool {
+        let mut readiness_may_have_changed = false;
+        for index in 0..self.ports.len() {
+            let (before, rest) = self.ports.split_at_mut(index);
+            let (port, after) = rest.split_first_mut().unwrap();
+            let mut others = OtherPorts {
+                before,
+                after,
+                timestamp,
+            };
+            readiness_may_have_changed |= port.iface.poll_forwarding(
+                timestamp,
+                &mut port.device,
+                &mut port.sockets,
+                Some(&mut others),
+            );
+        }
+        readiness_may_have_changed
+    }
+
+    /// Return a _soft deadline_ for calling [poll] the next time, the earliest one of the
+    /// interfaces.
+    ///
+    /// [poll]: #method.poll
+    pub fn poll_at(&mut self, timestamp: Instant) -> Option<Instant> {
+        self.ports
+            .iter_mut()
+            .filter_map(|port| port.iface.poll_at(timestamp, &port.sockets))
+            .min()
+    }
+}
+
+/// The ports of a router other than the one being polled.
+struct OtherPorts<'p, 'a, D: Device> {
+    before: &'p mut [Port<'a, D>],
+    after: &'p mut [Port<'a, D>],
+    timestamp: Instant,
+}
+
+impl<'p, 'a, D: Device> Forward for OtherPorts<'p, 'a, D> {
+    /// Send a packet through the port, among the ones it was not received by, with the
+    /// most specific route to its destination.
+    fn forward(&mut self, packet: &[u8]) -> Forwarded {
+        let dst_addr: IpAddress = match IpVersion::of_packet(packet) {
+            #[cfg(feature = "proto-ipv4")]
+            Ok(IpVersion::Ipv4) => Ipv4Packet::new_unchecked(packet).dst_addr().into(),
+            #[cfg(feature = "proto-ipv6")]
+            Ok(IpVersion::Ipv6) => Ipv6Packet::new_unchecked(packet).dst_addr().into(),
+            _ => return Forwarded::Done,
+        };
+
+        let timestamp = self.timestamp;
+        let route_key = forward_route_key(packet);
+        let port = self
+            .before
+            .iter_mut()
+            .chain(self.after.iter_mut())
+            .filter_map(|port| {
+                let rank = port.iface.forward_rank(&dst_addr, &route_key, timestamp)?;
+                Some((rank, port))
+            })
+            .max_by_key(|(rank, _)| *rank);
+        match port {
+            Some((_, port)) => port
+                .iface
+                .forward_egress(timestamp, &mut port.device, packet),
+            None => {
+                net_debug!("forward: no route to {}", dst_addr);
+                Forwarded::NoRoute
+            }
+        }
+    }
+
+    fn forward_rank(&self, addr: &IpAddress) -> Option<(u8, u8)> {
+        self.before
+            .iter()
+            .chain(self.after.iter())
+            .filter_map(|port| {
+                port.iface
+                    .forward_rank(addr, &RouteKey::default(), self.timestamp)
+            })
+            .max()
+    }
+
+    fn is_local(&self, addr: &IpAddress) -> bool {
+        self.before
+            .iter()
+            .chain(self.after.iter())
+            .any(|port| port.iface.owns_addr(addr))
+    }
+
+    fn deliver<'f>(
+        &mut self,
+        addr: &IpAddress,
+        packet: &'f [u8],
+        fragments: &'f mut FragmentsBuffer,
+    ) -> Option<IpPacket<'f>> {
+        let port = self
+            .before
+            .iter_mut()
+            .chain(self.after.iter_mut())
+            .find(|port| port.iface.owns_addr(addr))?;
+        port.iface
+            .deliver(self.timestamp, &mut port.sockets, packet, fragments)
+    }
+}
+
+#[cfg(all(test, feature = "medium-ip"))]
+mod test {
+    use std::collections::VecDeque;
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::iface::Config;
+    use crate::phy::{self, ChecksumCapabilities, DeviceCapabilities, Medium};
+
+    /// A device whose received and sent packets are queued apart.
+    struct TestDevice {
+        rx: VecDeque<Vec<u8>>,
+        tx: VecDeque<Vec<u8>>,
+        mtu: usize,
+    }
+
+    struct RxToken(Vec<u8>);
+
+    impl phy::RxToken for RxToken {
+        fn consume<R, F>(mut self, f: F) -> R
+        where
+            F: FnOnce(&mut [u8]) -> R,
+        {
+            f(&mut self.0)
+        }
+    }
+
+    struct TxToken<'a>(&'a mut VecDeque<Vec<u8>>);
+
+    impl<'a> phy::TxToken for TxToken<'a> {
+        fn consume<R, F>(self, len: usize, f: F) -> R
+        where
+            F: FnOnce(&mut [u8]) -> R,
+        {
+            let mut buffer = vec![0; len];
+            let result = f(&mut buffer);
+            self.0.push_back(buffer);
+            result
+        }
+    }
+
+    impl Device for TestDevice {
+        type RxToken<'a> = RxToken;
+        type TxToken<'a> = TxToken<'a>;
+
+        fn capabilities(&self) -> DeviceCapabilities {
+            DeviceCapabilities {
+                max_transmission_unit: self.mtu,
+                medium: Medium::Ip,
+                ..DeviceCapabilities::default()
+            }
+        }
+
+        fn receive(&mut self, _timestamp: Instant) -> Option<(RxToken, TxToken<'_>)> {
+            let buffer = self.rx.pop_front()?;
+            Some((RxToken(buffer), TxToken(&mut self.tx)))
+        }
+
+        fn transmit(&mut self, _timestamp: Instant) -> Option<TxToken<'_>> {
+            Some(TxToken(&mut self.tx))
+        }
+    }
+
+    fn port(mtu: usize, cidrs: &[IpCidr]) -> Port<'static, TestDevice> {
+        let mut device = TestDevice {
+            rx: VecDeque::new(),
+            tx: VecDeque::new(),
+            mtu,
+        };
+        let mut iface =
+            Interface::new(Config::new(HardwareAddress::Ip), &mut device, Instant::ZERO);
+        iface.update_ip_addrs(|ip_addrs| {
+            for cidr in cidrs {
+                ip_addrs.push(*cidr).unwrap();
+            }
+        });
+        Port::new(iface, device, SocketSet::new(vec![]))
+    }
+
+    #[cfg(feature = "proto-ipv4")]
+    fn setup_ipv4(mtu: usize) -> Router<'static, TestDevice> {
+        Router::new(vec![
+            port(1500, &[IpCidr::new(IpAddress::v4(10, 0, 0, 1), 24)]),
+            port(mtu, &[IpCidr::new(IpAddress::v4(10, 0, 1, 1), 24)]),
+        ])
+    }
+
+    #[cfg(feature = "proto-ipv4")]
+    fn ipv4_packet(
+        dst_addr: Ipv4Address,
+        hop_limit: u8,
+        payload_len: usize,
+        dont_frag: bool,
+    ) -> Vec<u8> {
+        let repr = Ipv4Repr {
+            src_addr: Ipv4Address::new(10, 0, 0, 2),
+            dst_addr,
+            next_header: IpProtocol::Udp,
+            payload_len,
+            hop_limit,
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len() + payload_len];
+        let mut packet = Ipv4Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet, &ChecksumCapabilities::default());
+        packet.set_dont_frag(dont_frag);
+        packet.fill_checksum();
+        bytes
+    }
+
+    /// Receive a packet on the first port, and return the ICMPv4 error it sent back, if any.
+    #[cfg(feature = "proto-ipv4")]
+    fn recv_icmpv4_error(
+        router: &mut Router<'static, TestDevice>,
+    ) -> Option<(Ipv4Address, Vec<u8>)> {
+        let bytes = router.ports_mut()[0].device.tx.pop_front()?;
+        let packet = Ipv4Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(packet.dst_addr(), Ipv4Address::new(10, 0, 0, 2));
+        assert_eq!(packet.next_header(), IpProtocol::Icmp);
+        Some((packet.src_addr(), packet.payload().to_vec()))
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_forward_ipv4() {
+        let mut router = setup_ipv4(1500);
+        let dst_addr = Ipv4Address::new(10, 0, 1, 2);
+        let sent = ipv4_packet(dst_addr, 64, 32, true);
+        router.ports_mut()[0].device.rx.push_back(sent.clone());
+        assert!(router.poll(Instant::ZERO));
+
+        let forwarded = router.ports_mut()[1].device.tx.pop_front().unwrap();
+        let packet = Ipv4Packet::new_checked(&forwarded[..]).unwrap();
+        assert!(packet.verify_checksum());
+        assert_eq!(packet.dst_addr(), dst_addr);
+        assert_eq!(packet.hop_limit(), 63);
+        assert_eq!(
+            packet.payload(),
+            Ipv4Packet::new_unchecked(&sent[..]).payload()
+        );
+        assert!(router.ports()[1].device.tx.is_empty());
+        assert!(router.ports()[0].device.tx.is_empty());
+
+        // Packets for the interface itself are not forwarded.
+        let sent = ipv4_packet(Ipv4Address::new(10, 0, 0, 1), 64, 32, true);
+        router.ports_mut()[0].device.rx.push_back(sent);
+        router.poll(Instant::ZERO);
+        assert!(router.ports()[1].device.tx.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_forward_ipv4_directed_broadcast() {
+        let mut router = setup_ipv4(1500);
+        let dst_addr = Ipv4Address::new(10, 0, 1, 255);
+
+        // Directed broadcasts are dropped by default, without an error.
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv4_packet(dst_addr, 64, 32, true));
+        router.poll(Instant::ZERO);
+        assert!(router.ports()[1].device.tx.is_empty());
+        assert!(router.ports()[0].device.tx.is_empty());
+
+        router.ports_mut()[1].iface.set_directed_broadcast(true);
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv4_packet(dst_addr, 64, 32, true));
+        router.poll(Instant::ZERO);
+        let forwarded = router.ports_mut()[1].device.tx.pop_front().unwrap();
+        let packet = Ipv4Packet::new_checked(&forwarded[..]).unwrap();
+        assert_eq!(packet.dst_addr(), dst_addr);
+        assert_eq!(packet.hop_limit(), 63);
+
+        // The subnet broadcasts of the receiving interface are for the interface itself.
+        router.ports_mut()[1].iface.set_directed_broadcast(false);
+        router.ports_mut()[1]
+            .device
+            .rx
+            .push_back(ipv4_packet(dst_addr, 64, 32, true));
+        router.poll(Instant::ZERO);
+        assert!(router.ports()[0].device.tx.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_forward_ipv4_reverse_path_filter() {
+        use crate::iface::ReversePathFilter;
+
+        fn recv_forwarded(router: &mut Router<'static, TestDevice>, src_addr: Ipv4Address) -> bool {
+            let mut sent = ipv4_packet(Ipv4Address::new(10, 0, 1, 2), 64, 32, true);
+            let mut packet = Ipv4Packet::new_unchecked(&mut sent);
+            packet.set_src_addr(src_addr);
+            packet.fill_checksum();
+            router.ports_mut()[0].device.rx.push_back(sent);
+            router.poll(Instant::ZERO);
+            router.ports_mut()[1].device.tx.pop_front().is_some()
+        }
+
+        let mut router = setup_ipv4(1500);
+        let spoofed = Ipv4Address::new(10, 0, 1, 3);
+        let unroutable = Ipv4Address::new(192, 168, 1, 1);
+        assert!(recv_forwarded(&mut router, spoofed));
+        assert!(recv_forwarded(&mut router, unroutable));
+
+        // Any interface may have a route back to the source.
+        router.ports_mut()[0]
+            .iface
+            .set_reverse_path_filter(ReversePathFilter::Loose);
+        assert!(recv_forwarded(&mut router, Ipv4Address::new(10, 0, 0, 2)));
+        assert!(recv_forwarded(&mut router, spoofed));
+        assert!(!recv_forwarded(&mut router, unroutable));
+
+        // The receiving interface must have the best route back to the source.
+        router.ports_mut()[0]
+            .iface
+            .set_reverse_path_filter(ReversePathFilter::Strict);
+        assert!(recv_forwarded(&mut router, Ipv4Address::new(10, 0, 0, 2)));
+        assert!(!recv_forwarded(&mut router, spoofed));
+        assert!(!recv_forwarded(&mut router, unroutable));
+
+        // A default route leads back to any source not routed elsewhere.
+        router.ports_mut()[0]
+            .iface
+            .routes_mut()
+            .add_default_ipv4_route(Ipv4Address::new(10, 0, 0, 254))
+            .unwrap();
+        assert!(recv_forwarded(&mut router, unroutable));
+        assert!(!recv_forwarded(&mut router, spoofed));
+        assert!(router.ports()[0].device.tx.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_forward_ipv4_errors() {
+        let mut router = setup_ipv4(576);
+
+        // The TTL runs out.
+        let sent = ipv4_packet(Ipv4Address::new(10, 0, 1, 2), 1, 32, true);
+        router.ports_mut()[0].device.rx.push_back(sent);
+        router.poll(Instant::ZERO);
+        let (src_addr, icmp) = recv_icmpv4_error(&mut router).unwrap();
+        assert_eq!(src_addr, Ipv4Address::new(10, 0, 0, 1));
+        let icmp_packet = Icmpv4Packet::new_checked(&icmp[..]).unwrap();
+        assert!(matches!(
+            Icmpv4Repr::parse(&icmp_packet, &ChecksumCapabilities::default()),
+            Ok(Icmpv4Repr::TimeExceeded {
+                reason: Icmpv4TimeExceeded::TtlExpired,
+                ..
+            })
+        ));
+
+        // No interface has a route to the destination.
+        let sent = ipv4_packet(Ipv4Address::new(192, 168, 1, 1), 64, 32, true);
+        router.ports_mut()[0].device.rx.push_back(sent);
+        router.poll(Instant::ZERO);
+        let (_, icmp) = recv_icmpv4_error(&mut router).unwrap();
+        let icmp_packet = Icmpv4Packet::new_checked(&icmp[..]).unwrap();
+        assert!(matches!(
+            Icmpv4Repr::parse(&icmp_packet, &ChecksumCapabilities::default()),
+            Ok(Icmpv4Repr::DstUnreachable {
+                reason: Icmpv4DstUnreachable::NetUnreachable,
+                ..
+            })
+        ));
+
+        // The packet is too large for the outgoing interface, and may not be fragmented.
+        let sent = ipv4_packet(Ipv4Address::new(10, 0, 1, 2), 64, 1000, true);
+        router.ports_mut()[0].device.rx.push_back(sent);
+        router.poll(Instant::ZERO);
+        let (_, icmp) = recv_icmpv4_error(&mut router).unwrap();
+        // The quoted packet is truncated, only the header is checked.
+        let icmp_packet = Icmpv4Packet::new_checked(&icmp[..]).unwrap();
+        assert_eq!(icmp_packet.msg_type(), Icmpv4Message::DstUnreachable);
+        assert_eq!(
+            icmp_packet.msg_code(),
+            u8::from(Icmpv4DstUnreachable::FragRequired)
+        );
+        assert_eq!(icmp_packet.next_hop_mtu(), 576);
+        assert!(router.ports()[1].device.tx.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_forward_ipv4_host_model() {
+        use crate::iface::HostModel;
+
+        fn echo_request(router: &mut Router<'static, TestDevice>) {
+            let icmp_repr = Icmpv4Repr::EchoRequest {
+                ident: 0x1234,
+                seq_no: 1,
+                data: &[0xa5; 8],
+            };
+            let mut sent = ipv4_packet(
+                Ipv4Address::new(10, 0, 1, 1),
+                64,
+                icmp_repr.buffer_len(),
+                true,
+            );
+            let mut packet = Ipv4Packet::new_unchecked(&mut sent);
+            packet.set_next_header(IpProtocol::Icmp);
+            packet.fill_checksum();
+            icmp_repr.emit(
+                &mut Icmpv4Packet::new_unchecked(packet.payload_mut()),
+                &ChecksumCapabilities::default(),
+            );
+            router.ports_mut()[0].device.rx.push_back(sent);
+            router.poll(Instant::ZERO);
+        }
+
+        // The address of the second interface is not reachable through the first one.
+        let mut router = setup_ipv4(1500);
+        echo_request(&mut router);
+        assert!(router.ports()[0].device.tx.is_empty());
+        assert!(router.ports()[1].device.tx.is_empty());
+
+        // The second interface answers, through the first one.
+        router.ports_mut()[0].iface.set_host_model(HostModel::Weak);
+        echo_request(&mut router);
+        assert!(router.ports()[1].device.tx.is_empty());
+        let reply = router.ports_mut()[0].device.tx.pop_front().unwrap();
+        let packet = Ipv4Packet::new_checked(&reply[..]).unwrap();
+        assert_eq!(packet.src_addr(), Ipv4Address::new(10, 0, 1, 1));
+        assert_eq!(packet.dst_addr(), Ipv4Address::new(10, 0, 0, 2));
+        let icmp_packet = Icmpv4Packet::new_checked(packet.payload()).unwrap();
+        assert!(matches!(
+            Icmpv4Repr::parse(&icmp_packet, &ChecksumCapabilities::default()),
+            Ok(Icmpv4Repr::EchoReply { seq_no: 1, .. })
+        ));
+
+        // Not while its link is down, when there is no route to it either.
+        let mut sockets = SocketSet::new(vec![]);
+        router.ports_mut()[1].iface.set_link_up(false, &mut sockets);
+        echo_request(&mut router);
+        let (_, icmp) = recv_icmpv4_error(&mut router).unwrap();
+        let icmp_packet = Icmpv4Packet::new_checked(&icmp[..]).unwrap();
+        assert!(matches!(
+            Icmpv4Repr::parse(&icmp_packet, &ChecksumCapabilities::default()),
+            Ok(Icmpv4Repr::DstUnreachable {
+                reason: Icmpv4DstUnreachable::NetUnreachable,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_forward_ipv4_policy() {
+        use crate::iface::Route;
+
+        let mut router = Router::new(vec![
+            port(1500, &[IpCidr::new(IpAddress::v4(10, 0, 0, 1), 24)]),
+            port(1500, &[IpCidr::new(IpAddress::v4(10, 0, 1, 1), 24)]),
+            port(1500, &[IpCidr::new(IpAddress::v4(10, 0, 2, 1), 24)]),
+        ]);
+        router.ports_mut()[1]
+            .iface
+            .routes_mut()
+            .add_default_ipv4_route(Ipv4Address::new(10, 0, 1, 254))
+            .unwrap();
+        let route = Route {
+            src_cidr: Some(IpCidr::new(IpAddress::v4(10, 0, 0, 0), 24)),
+            ..Route::new_ipv4_gateway(Ipv4Address::new(10, 0, 2, 254))
+        };
+        router.ports_mut()[2]
+            .iface
+            .routes_mut()
+            .update(|routes| routes.push(route).unwrap());
+
+        // The packets from the first network leave through the third interface.
+        let dst_addr = Ipv4Address::new(192, 168, 1, 1);
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv4_packet(dst_addr, 64, 32, true));
+        router.poll(Instant::ZERO);
+        assert!(router.ports()[1].device.tx.is_empty());
+        assert_eq!(router.ports_mut()[2].device.tx.len(), 1);
+
+        // The networks of the interfaces still come first.
+        let dst_addr = Ipv4Address::new(10, 0, 1, 2);
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv4_packet(dst_addr, 64, 32, true));
+        router.poll(Instant::ZERO);
+        assert_eq!(router.ports_mut()[1].device.tx.len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_forward_ipv4_filter() {
+        use crate::iface::{FilterAction, FilterDirection, FilterRule};
+
+        let mut router = setup_ipv4(1500);
+        let dst_addr = Ipv4Address::new(10, 0, 1, 2);
+
+        // The incoming interface rejects the packet.
+        let rule = FilterRule {
+            direction: Some(FilterDirection::Ingress),
+            dst_cidr: Some(IpCidr::new(dst_addr.into(), 32)),
+            ..FilterRule::new(FilterAction::Reject)
+        };
+        router.ports_mut()[0]
+            .iface
+            .filter_mut()
+            .add_rule(rule)
+            .unwrap();
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv4_packet(dst_addr, 64, 32, true));
+        router.poll(Instant::ZERO);
+        let (_, icmp) = recv_icmpv4_error(&mut router).unwrap();
+        let icmp_packet = Icmpv4Packet::new_checked(&icmp[..]).unwrap();
+        assert!(matches!(
+            Icmpv4Repr::parse(&icmp_packet, &ChecksumCapabilities::default()),
+            Ok(Icmpv4Repr::DstUnreachable {
+                reason: Icmpv4DstUnreachable::CommProhibited,
+                ..
+            })
+        ));
+        assert!(router.ports()[1].device.tx.is_empty());
+
+        // The outgoing interface drops it.
+        router.ports_mut()[0]
+            .iface
+            .filter_mut()
+            .update(|rules| rules.clear());
+        let rule = FilterRule {
+            direction: Some(FilterDirection::Egress),
+            protocol: Some(IpProtocol::Udp),
+            ..FilterRule::new(FilterAction::Drop)
+        };
+        router.ports_mut()[1]
+            .iface
+            .filter_mut()
+            .add_rule(rule)
+            .unwrap();
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv4_packet(dst_addr, 64, 32, true));
+        router.poll(Instant::ZERO);
+        assert!(router.ports()[0].device.tx.is_empty());
+        assert!(router.ports()[1].device.tx.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4-fragmentation")]
+    fn test_forward_ipv4_fragmented() {
+        let mut router = setup_ipv4(576);
+        let sent = ipv4_packet(Ipv4Address::new(10, 0, 1, 2), 64, 1000, false);
+        router.ports_mut()[0].device.rx.push_back(sent);
+        router.poll(Instant::ZERO);
+        router.poll(Instant::ZERO);
+        assert!(recv_icmpv4_error(&mut router).is_none());
+
+        let mut payload_len = 0;
+        let fragments = &router.ports()[1].device.tx;
+        assert!(fragments.len() > 1);
+        for (index, bytes) in fragments.iter().enumerate() {
+            assert!(bytes.len() <= 576);
+            let packet = Ipv4Packet::new_checked(&bytes[..]).unwrap();
+            assert_eq!(packet.hop_limit(), 63);
+            assert_eq!(packet.frag_offset() as usize, payload_len);
+            assert_eq!(packet.more_frags(), index + 1 < fragments.len());
+            payload_len += packet.payload().len();
+        }
+        assert_eq!(payload_len, 1000);
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv6")]
+    fn test_forward_ipv6() {
+        let mut router = Router::new(vec![
+            port(
+                1500,
+                &[IpCidr::new(IpAddress::v6(0xfd00, 0, 0, 0, 0, 0, 0, 1), 64)],
+            ),
+            port(
+                1280,
+                &[IpCidr::new(IpAddress::v6(0xfd00, 0, 0, 1, 0, 0, 0, 1), 64)],
+            ),
+        ]);
+        let ipv6_packet = |payload_len: usize, hop_limit: u8| {
+            let repr = Ipv6Repr {
+                src_addr: Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 2),
+                dst_addr: Ipv6Address::new(0xfd00, 0, 0, 1, 0, 0, 0, 2),
+                next_header: IpProtocol::Udp,
+                payload_len,
+                hop_limit,
+            };
+            let mut bytes = vec![0xa5; repr.buffer_len() + payload_len];
+            repr.emit(&mut Ipv6Packet::new_unchecked(&mut bytes));
+            bytes
+        };
+
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv6_packet(32, 64));
+        router.poll(Instant::ZERO);
+        let forwarded = router.ports_mut()[1].device.tx.pop_front().unwrap();
+        assert_eq!(
+            Ipv6Packet::new_checked(&forwarded[..]).unwrap().hop_limit(),
+            63
+        );
+
+        // The packet is too large for the outgoing interface.
+        router.ports_mut()[0]
+            .device
+            .rx
+            .push_back(ipv6_packet(1400, 64));
+        router.poll(Instant::ZERO);
+        assert!(router.ports()[1].device.tx.is_empty());
+        let bytes = router.ports_mut()[0].device.tx.pop_front().unwrap();
+        let packet = Ipv6Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(
+            packet.src_addr(),
+            Ipv6Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1)
+        );
+        let icmp_packet = Icmpv6Packet::new_checked(packet.payload()).unwrap();
+        assert!(matches!(
+            Icmpv6Repr::parse(
+                &packet.src_addr().into(),
+                &packet.dst_addr().into(),
+                &icmp_packet,
+                &ChecksumCapabilities::default()
+            ),
+            Ok(Icmpv6Repr::PktTooBig { mtu: 1280, .. })
+        ));
+    }
+}
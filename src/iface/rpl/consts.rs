@@ -6,3 +6,11 @@ pub const DEFAULT_DIO_INTERVAL_MIN: u32 = 12;
 pub const DEFAULT_DIO_REDUNDANCY_CONSTANT: usize = 10;
 /// This is 20 in the standard, but in Contiki they use:
 pub const DEFAULT_DIO_INTERVAL_DOUBLINGS: u32 = 8;
+
+/// Not specified by the standard, Contiki uses:
+pub const DEFAULT_MAX_RANK_INCREASE: u16 = 7 * DEFAULT_MIN_HOP_RANK_INCREASE;
+
+/// Lifetime of the routes, in lifetime units, as in Contiki.
+pub const DEFAULT_ROUTE_LIFETIME: u8 = 30;
+/// Unit of the route lifetimes, in seconds.
+pub const DEFAULT_LIFETIME_UNIT: u16 = 60;
@@ -7,3 +7,92 @@ mod parents;
 mod rank;
 mod relations;
 mod trickle;
+
+pub(crate) use self::consts::*;
+pub(crate) use self::lollipop::SequenceCounter;
+pub(crate) use self::of0::{ObjectiveFunction, ObjectiveFunction0};
+pub(crate) use self::parents::{Parent, ParentSet};
+pub(crate) use self::rank::Rank;
+pub(crate) use self::relations::Relations;
+pub(crate) use self::trickle::TrickleTimer;
+
+use crate::wire::{Ipv6Address, RplInstanceId, RplModeOfOperation};
+
+/// The RPL configuration of an interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RplConfig {
+    pub(crate) mode_of_operation: RplModeOfOperation,
+    pub(crate) root: Option<RplRootConfig>,
+}
+
+impl RplConfig {
+    /// Create a RPL configuration for a node that joins the DODAGs advertised with the
+    /// given mode of operation.
+    ///
+    /// # Panics
+    /// This function panics if the mode of operation is not supported. Only storing mode
+    /// without multicast, and no downward routes at all, are.
+    pub fn new(mode_of_operation: RplModeOfOperation) -> Self {
+        assert!(
+            matches!(
+                mode_of_operation,
+                RplModeOfOperation::NoDownwardRoutesMaintained
+                    | RplModeOfOperation::StoringModeWithoutMulticast
+            ),
+            "unsupported RPL mode of operation"
+        );
+        Self {
+            mode_of_operation,
+            root: None,
+        }
+    }
+
+    /// Make the node the root of a DODAG, instead of joining one.
+    pub fn add_root_config(mut self, root: RplRootConfig) -> Self {
+        self.root = Some(root);
+        self
+    }
+
+    /// Return whether the node is the root of a DODAG.
+    pub fn is_root(&self) -> bool {
+        self.root.is_some()
+    }
+}
+
+/// The configuration of the DODAG that a RPL root advertises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RplRootConfig {
+    pub(crate) instance_id: RplInstanceId,
+    pub(crate) dodag_id: Ipv6Address,
+    pub(crate) preference: u8,
+    pub(crate) grounded: bool,
+}
+
+impl RplRootConfig {
+    /// Create the configuration of a DODAG of the given RPL instance.
+    ///
+    /// The DODAG ID must be a routable address of the root.
+    pub fn new(instance_id: RplInstanceId, dodag_id: Ipv6Address) -> Self {
+        Self {
+            instance_id,
+            dodag_id,
+            preference: 0,
+            grounded: false,
+        }
+    }
+
+    /// Set the preference of the DODAG over the other DODAGs of the instance, from 0 (the
+    /// least preferred, and the default) to 7.
+    pub fn with_preference(mut self, preference: u8) -> Self {
+        self.preference = preference.min(7);
+        self
+    }
+
+    /// Set whether the DODAG can reach the goal of the instance, e.g. a larger network.
+    pub fn with_grounded(mut self, grounded: bool) -> Self {
+        self.grounded = grounded;
+        self
+    }
+}
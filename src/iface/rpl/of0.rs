@@ -1,5 +1,6 @@
 use super::parents::*;
 use super::rank::Rank;
+use crate::wire::Ipv6Address;
 
 pub struct ObjectiveFunction0;
 
@@ -9,8 +10,8 @@ pub(crate) trait ObjectiveFunction {
     /// Return the new calculated Rank, based on information from the parent.
     fn rank(current_rank: Rank, parent_rank: Rank) -> Rank;
 
-    /// Return the preferred parent from a given parent set.
-    fn preferred_parent(parent_set: &ParentSet) -> Option<&Parent>;
+    /// Return the preferred parent, and its address, from a given parent set.
+    fn preferred_parent(parent_set: &ParentSet) -> Option<(&Ipv6Address, &Parent)>;
 }
 
 impl ObjectiveFunction0 {
@@ -22,7 +23,7 @@ impl ObjectiveFunction0 {
 
     fn rank_increase(parent_rank: Rank) -> u16 {
         (Self::RANK_FACTOR * Self::RANK_STEP + Self::RANK_STRETCH)
-            * parent_rank.min_hop_rank_increase
+            .saturating_mul(parent_rank.min_hop_rank_increase)
     }
 }
 
@@ -33,17 +34,19 @@ impl ObjectiveFunction for ObjectiveFunction0 {
         assert_ne!(parent_rank, Rank::INFINITE);
 
         Rank::new(
-            parent_rank.value + Self::rank_increase(parent_rank),
+            parent_rank
+                .value
+                .saturating_add(Self::rank_increase(parent_rank)),
             parent_rank.min_hop_rank_increase,
         )
     }
 
-    fn preferred_parent(parent_set: &ParentSet) -> Option<&Parent> {
-        let mut pref_parent: Option<&Parent> = None;
+    fn preferred_parent(parent_set: &ParentSet) -> Option<(&Ipv6Address, &Parent)> {
+        let mut pref_parent: Option<(&Ipv6Address, &Parent)> = None;
 
-        for (_, parent) in parent_set.parents() {
-            if pref_parent.is_none() || parent.rank() < pref_parent.unwrap().rank() {
-                pref_parent = Some(parent);
+        for (address, parent) in parent_set.parents() {
+            if pref_parent.is_none() || parent.rank() < pref_parent.unwrap().1.rank() {
+                pref_parent = Some((address, parent));
             }
         }
 
@@ -94,8 +97,6 @@ mod tests {
 
     #[test]
     fn non_empty_set() {
-        use crate::wire::Ipv6Address;
-
         let mut parents = ParentSet::default();
 
         parents.add(
@@ -118,11 +119,9 @@ mod tests {
 
         assert_eq!(
             ObjectiveFunction0::preferred_parent(&parents),
-            Some(&Parent::new(
-                0,
-                Rank::ROOT,
-                Default::default(),
-                Ipv6Address::default(),
+            Some((
+                &Ipv6Address::default(),
+                &Parent::new(0, Rank::ROOT, Default::default(), Ipv6Address::default())
             ))
         );
     }
@@ -68,6 +68,16 @@ impl ParentSet {
         self.parents.get_mut(address)
     }
 
+    /// Remove a parent from the parent set.
+    pub(crate) fn remove(&mut self, address: &Ipv6Address) {
+        self.parents.remove(address);
+    }
+
+    /// Remove all the parents from the parent set.
+    pub(crate) fn clear(&mut self) {
+        self.parents.clear();
+    }
+
     /// Return a slice to the parent set.
     pub(crate) fn parents(&self) -> impl Iterator<Item = (&Ipv6Address, &Parent)> {
         self.parents.iter()
@@ -50,7 +50,7 @@ impl Relations {
     }
 
     /// Return the next hop for a specific IPv6 address, if there is one.
-    pub fn find_next_hop(&mut self, destination: Ipv6Address) -> Option<Ipv6Address> {
+    pub fn find_next_hop(&self, destination: Ipv6Address) -> Option<Ipv6Address> {
         self.relations.iter().find_map(|r| {
             if r.destination == destination {
                 Some(r.next_hop)
@@ -60,6 +60,16 @@ impl Relations {
         })
     }
 
+    /// Return the destinations that there is a relation for.
+    pub fn destinations(&self) -> impl Iterator<Item = Ipv6Address> + '_ {
+        self.relations.iter().map(|r| r.destination)
+    }
+
+    /// Return the earliest expiration of the relations, if any.
+    pub fn expiration(&self) -> Option<Instant> {
+        self.relations.iter().map(|r| r.expiration).min()
+    }
+
     /// Purge expired relations.
     pub fn purge(&mut self, now: Instant) {
         self.relations.retain(|r| r.expiration > now)
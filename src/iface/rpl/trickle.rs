@@ -79,9 +79,11 @@ impl TrickleTimer {
     /// Poll the Trickle timer. Returns `true` when the Trickle timer singals that a message can be
     /// transmitted. This happens when the Trickle timer expires.
     pub(crate) fn poll(&mut self, now: Instant, rand: &mut Rand) -> bool {
-        let can_transmit = self.can_transmit() && self.t_expired(now);
+        let t_expired = self.t_expired(now);
+        let can_transmit = self.can_transmit() && t_expired;
 
-        if can_transmit {
+        // A suppressed transmission is not retried until the timer expires again.
+        if t_expired {
             self.set_t(now, rand);
         }
 
@@ -1,3 +1,4 @@
+use super::timer_wheel;
 use super::SocketHandle;
 use crate::{
     socket::PollAt,
@@ -36,6 +37,14 @@ pub(crate) struct Meta {
     pub(crate) handle: SocketHandle,
     /// See [NeighborState](struct.NeighborState.html).
     neighbor_state: NeighborState,
+    /// Position of this socket in the timer wheel of its enclosing `SocketSet`.
+    pub(crate) timer: timer_wheel::Entry,
+    /// Mark of the packets sent by this socket, which selects their policy routes.
+    pub(crate) mark: Option<u32>,
+    /// Handle of the TCP socket spliced into this one, whose received data is waiting
+    /// for room in this socket.
+    #[cfg(feature = "socket-tcp")]
+    pub(crate) splice_src: Option<SocketHandle>,
 }
 
 impl Meta {
@@ -88,6 +97,18 @@ impl Meta {
         }
     }
 
+    /// Return whether the socket is waiting for a neighbor that is now known.
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub(crate) fn neighbor_discovered<F>(&self, has_neighbor: F) -> bool
+    where
+        F: Fn(IpAddress) -> bool,
+    {
+        match self.neighbor_state {
+            NeighborState::Active => false,
+            NeighborState::Waiting { neighbor, .. } => has_neighbor(neighbor),
+        }
+    }
+
     pub(crate) fn neighbor_missing(&mut self, timestamp: Instant, neighbor: IpAddress) {
         net_trace!(
             "{}: neighbor {} missing, silencing until t+{}",
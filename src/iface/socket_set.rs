@@ -2,7 +2,9 @@ use core::fmt;
 use managed::ManagedSlice;
 
 use super::socket_meta::Meta;
-use crate::socket::{AnySocket, Socket};
+use super::timer_wheel::{Entries, Entry, TimerWheel};
+use crate::socket::{AnySocket, PollAt, Socket};
+use crate::time::Instant;
 
 /// Opaque struct with space for storing one socket.
 ///
@@ -24,6 +26,24 @@ pub(crate) struct Item<'a> {
     pub(crate) socket: Socket<'a>,
 }
 
+/// The items of a socket set other than the one being evaluated.
+#[cfg(feature = "socket-udp")]
+pub(crate) struct OtherItems<'s, 'a> {
+    before: &'s mut [SocketStorage<'a>],
+    after: &'s mut [SocketStorage<'a>],
+}
+
+#[cfg(feature = "socket-udp")]
+impl<'s, 'a> OtherItems<'s, 'a> {
+    /// Iterate the other sockets of the set.
+    pub(crate) fn items_mut(&mut self) -> impl Iterator<Item = &mut Item<'a>> + '_ {
+        self.before
+            .iter_mut()
+            .chain(self.after.iter_mut())
+            .filter_map(|x| x.inner.as_mut())
+    }
+}
+
 /// A handle, identifying a socket in an Interface.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -38,9 +58,35 @@ impl fmt::Display for SocketHandle {
 /// An extensible set of sockets.
 ///
 /// The lifetime `'a` is used when storing a `Socket<'a>`.
+///
+/// The set keeps track of when its TCP sockets need to be polled in a timer wheel, so that
+/// the cost of [Interface::poll_at](crate::iface::Interface::poll_at) does not grow with
+/// the number of idle connections. Accessing a socket mutably through the set schedules it
+/// to be evaluated again.
 #[derive(Debug)]
 pub struct SocketSet<'a> {
     sockets: ManagedSlice<'a, SocketStorage<'a>>,
+    timers: TimerWheel,
+}
+
+impl<'a> Entries for [SocketStorage<'a>] {
+    fn entry(&self, index: usize) -> &Entry {
+        &self[index].inner.as_ref().unwrap().meta.timer
+    }
+
+    fn entry_mut(&mut self, index: usize) -> &mut Entry {
+        &mut self[index].inner.as_mut().unwrap().meta.timer
+    }
+}
+
+/// Whether the poll time of a socket is cached in the timer wheel.
+fn is_scheduled(socket: &Socket) -> bool {
+    match socket {
+        #[cfg(feature = "socket-tcp")]
+        Socket::Tcp(_) => true,
+        #[allow(unreachable_patterns)]
+        _ => false,
+    }
 }
 
 impl<'a> SocketSet<'a> {
@@ -50,7 +96,10 @@ impl<'a> SocketSet<'a> {
         SocketsT: Into<ManagedSlice<'a, SocketStorage<'a>>>,
     {
         let sockets = sockets.into();
-        SocketSet { sockets }
+        SocketSet {
+            sockets,
+            timers: TimerWheel::new(),
+        }
     }
 
     /// Add a socket to the set, and return its handle.
@@ -70,22 +119,27 @@ impl<'a> SocketSet<'a> {
         }
 
         let socket = socket.upcast();
+        let scheduled = is_scheduled(&socket);
 
-        for (index, slot) in self.sockets.iter_mut().enumerate() {
-            if slot.inner.is_none() {
-                return put(index, slot, socket);
-            }
-        }
+        let handle = match self.sockets.iter().position(|slot| slot.inner.is_none()) {
+            Some(index) => put(index, &mut self.sockets[index], socket),
+            None => match &mut self.sockets {
+                ManagedSlice::Borrowed(_) => panic!("adding a socket to a full SocketSet"),
+                #[cfg(feature = "alloc")]
+                ManagedSlice::Owned(sockets) => {
+                    sockets.push(SocketStorage { inner: None });
+                    let index = sockets.len() - 1;
+                    put(index, &mut sockets[index], socket)
+                }
+            },
+        };
 
-        match &mut self.sockets {
-            ManagedSlice::Borrowed(_) => panic!("adding a socket to a full SocketSet"),
-            #[cfg(feature = "alloc")]
-            ManagedSlice::Owned(sockets) => {
-                sockets.push(SocketStorage { inner: None });
-                let index = sockets.len() - 1;
-                put(index, &mut sockets[index], socket)
-            }
+        if scheduled {
+            self.timers.touch(&mut self.sockets[..], handle.0);
+        } else {
+            self.timers.add_unmanaged(&mut self.sockets[..], handle.0);
         }
+        handle
     }
 
     /// Get a socket from the set by its handle, as mutable.
@@ -108,6 +162,7 @@ impl<'a> SocketSet<'a> {
     /// This function may panic if the handle does not belong to this socket set
     /// or the socket has the wrong type.
     pub fn get_mut<T: AnySocket<'a>>(&mut self, handle: SocketHandle) -> &mut T {
+        self.touch(handle);
         match self.sockets[handle.0].inner.as_mut() {
             Some(item) => T::downcast_mut(&mut item.socket)
                 .expect("handle refers to a socket of a wrong type"),
@@ -115,12 +170,67 @@ impl<'a> SocketSet<'a> {
         }
     }
 
+    /// Get two distinct sockets from the set by their handles, as mutable.
+    ///
+    /// # Panics
+    /// This function may panic if the handles are equal or do not belong to this socket set,
+    /// or if the sockets have the wrong type.
+    pub fn get_pair_mut<T: AnySocket<'a>, U: AnySocket<'a>>(
+        &mut self,
+        first: SocketHandle,
+        second: SocketHandle,
+    ) -> (&mut T, &mut U) {
+        fn downcast<'a, 'b, T: AnySocket<'a>>(slot: &'b mut SocketStorage<'a>) -> &'b mut T {
+            match slot.inner.as_mut() {
+                Some(item) => T::downcast_mut(&mut item.socket)
+                    .expect("handle refers to a socket of a wrong type"),
+                None => panic!("handle does not refer to a valid socket"),
+            }
+        }
+
+        assert!(first != second, "handles refer to the same socket");
+        self.touch(first);
+        self.touch(second);
+        let (low, high) = self.sockets.split_at_mut(first.0.max(second.0));
+        if first.0 < second.0 {
+            (downcast(&mut low[first.0]), downcast(&mut high[0]))
+        } else {
+            (downcast(&mut high[0]), downcast(&mut low[second.0]))
+        }
+    }
+
+    /// Set the mark of a socket, which selects the policy routes of the packets it sends.
+    /// See [`Route::mark`](crate::iface::Route::mark).
+    ///
+    /// # Panics
+    /// This function may panic if the handle does not belong to this socket set.
+    pub fn set_mark(&mut self, handle: SocketHandle, mark: Option<u32>) {
+        match self.sockets[handle.0].inner.as_mut() {
+            Some(item) => item.meta.mark = mark,
+            None => panic!("handle does not refer to a valid socket"),
+        }
+    }
+
+    /// Get the mark of a socket.
+    ///
+    /// # Panics
+    /// This function may panic if the handle does not belong to this socket set.
+    pub fn mark(&self, handle: SocketHandle) -> Option<u32> {
+        match self.sockets[handle.0].inner.as_ref() {
+            Some(item) => item.meta.mark,
+            None => panic!("handle does not refer to a valid socket"),
+        }
+    }
+
     /// Remove a socket from the set, without changing its state.
     ///
     /// # Panics
     /// This function may panic if the handle does not belong to this socket set.
     pub fn remove(&mut self, handle: SocketHandle) -> Socket<'a> {
         net_trace!("[{}]: removing", handle.0);
+        if self.sockets[handle.0].inner.is_some() {
+            self.timers.remove(&mut self.sockets[..], handle.0);
+        }
         match self.sockets[handle.0].inner.take() {
             Some(item) => item.socket,
             None => panic!("handle does not refer to a valid socket"),
@@ -134,6 +244,11 @@ impl<'a> SocketSet<'a> {
 
     /// Get a mutable iterator to the inner sockets.
     pub fn iter_mut(&mut self) -> impl Iterator<Item = (SocketHandle, &mut Socket<'a>)> {
+        for index in 0..self.sockets.len() {
+            if self.sockets[index].inner.is_some() {
+                self.timers.touch(&mut self.sockets[..], index);
+            }
+        }
         self.items_mut().map(|i| (i.meta.handle, &mut i.socket))
     }
 
@@ -142,6 +257,113 @@ impl<'a> SocketSet<'a> {
         self.sockets.iter().filter_map(|x| x.inner.as_ref())
     }
 
+    /// Get the socket in the slot at `index`, if any, as mutable.
+    ///
+    /// Unlike [get_mut](#method.get_mut), this does not schedule the socket to be
+    /// evaluated again.
+    #[cfg(any(feature = "socket-tcp", not(feature = "socket-udp")))]
+    pub(crate) fn item_at_mut(&mut self, index: usize) -> Option<&mut Item<'a>> {
+        self.sockets[index].inner.as_mut()
+    }
+
+    /// Get the socket in the slot at `index`, if any, as mutable, along with the other
+    /// sockets of the set.
+    #[cfg(feature = "socket-udp")]
+    pub(crate) fn item_at_mut_with_others(
+        &mut self,
+        index: usize,
+    ) -> (Option<&mut Item<'a>>, OtherItems<'_, 'a>) {
+        let (before, rest) = self.sockets.split_at_mut(index);
+        let (slot, after) = rest.split_first_mut().unwrap();
+        (slot.inner.as_mut(), OtherItems { before, after })
+    }
+
+    /// Remember that the data received by the TCP socket `src` is waiting for room in
+    /// the socket `dst`, to move it again once `dst` may have changed.
+    #[cfg(feature = "socket-tcp")]
+    pub(crate) fn set_splice_src(&mut self, dst: SocketHandle, src: SocketHandle) {
+        if let Some(item) = self.sockets[dst.0].inner.as_mut() {
+            item.meta.splice_src = Some(src);
+        }
+    }
+
+    /// Schedule a socket to be evaluated again, because it may have changed.
+    pub(crate) fn touch(&mut self, handle: SocketHandle) {
+        if self.sockets[handle.0].inner.is_some() {
+            self.timers.touch(&mut self.sockets[..], handle.0);
+        }
+    }
+
+    /// Schedule a socket to be evaluated again at `poll_at`.
+    pub(crate) fn schedule(&mut self, handle: SocketHandle, poll_at: PollAt) {
+        self.timers
+            .schedule(&mut self.sockets[..], handle.0, poll_at)
+    }
+
+    /// Gather the sockets that must be evaluated at `now`, as returned by
+    /// [is_due](#method.is_due).
+    pub(crate) fn take_due(&mut self, now: Instant) {
+        self.timers.take_due(&mut self.sockets[..], now)
+    }
+
+    /// Return the slot of the next socket that must be evaluated, after the slot `prev`
+    /// returned by the previous call, if any: either because its poll time has come, it may
+    /// have changed, or it is not scheduled by the timer wheel.
+    pub(crate) fn next_due(&mut self, prev: Option<usize>) -> Option<usize> {
+        self.timers.next_due(&mut self.sockets[..], prev)
+    }
+
+    /// Return the slot of the next socket that may have changed since it was last evaluated,
+    /// after the slot `prev` returned by the previous call, if any.
+    #[cfg(feature = "socket-tcp")]
+    pub(crate) fn next_touched(&self, prev: Option<usize>) -> Option<usize> {
+        self.timers.next_touched(&self.sockets[..], prev)
+    }
+
+    /// Schedule the sockets for which `f` returns true to be evaluated again.
+    ///
+    /// This visits every socket, and is meant for events that may change many of them,
+    /// such as a neighbor becoming known.
+    #[cfg(any(
+        feature = "medium-ethernet",
+        feature = "medium-ieee802154",
+        all(
+            feature = "proto-ipv6",
+            any(feature = "socket-tcp", feature = "socket-udp")
+        )
+    ))]
+    pub(crate) fn touch_matching<F>(&mut self, mut f: F)
+    where
+        F: FnMut(&mut Item<'a>) -> bool,
+    {
+        for index in 0..self.sockets.len() {
+            let changed = match self.sockets[index].inner.as_mut() {
+                Some(item) => f(item),
+                None => false,
+            };
+            if changed {
+                self.timers.touch(&mut self.sockets[..], index);
+            }
+        }
+    }
+
+    /// Mark the sockets that were due but not evaluated as needing to be evaluated again.
+    pub(crate) fn finish_due(&mut self) {
+        self.timers.finish_batch(&mut self.sockets[..])
+    }
+
+    /// Iterate the sockets whose poll time is not known.
+    pub(crate) fn unscheduled_items(&self) -> impl Iterator<Item = &Item<'a>> + '_ {
+        self.timers
+            .unscheduled(&self.sockets[..])
+            .filter_map(|index| self.sockets[index].inner.as_ref())
+    }
+
+    /// Return the earliest poll time of the scheduled sockets.
+    pub(crate) fn scheduled_poll_at(&self) -> PollAt {
+        self.timers.poll_at(&self.sockets[..])
+    }
+
     /// Iterate every socket in this set.
     pub(crate) fn items_mut(&mut self) -> impl Iterator<Item = &mut Item<'a>> + '_ {
         self.sockets.iter_mut().filter_map(|x| x.inner.as_mut())
@@ -0,0 +1,175 @@
+use crate::wire::{IpProtocol, IpRepr, IpVersion};
+
+/// Counters of the packets of one protocol handled by an interface.
+///
+/// This struct is marked as `#[non_exhaustive]`, so that new fields can be added without
+/// a breaking change.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct ProtocolStats {
+    /// The number of packets received and successfully parsed.
+    pub rx_packets: u64,
+    /// The number of octets in the packets received, including the protocol header.
+    pub rx_bytes: u64,
+    /// The number of packets sent.
+    pub tx_packets: u64,
+    /// The number of octets in the packets sent, including the protocol header.
+    pub tx_bytes: u64,
+    /// The number of packets received with an invalid checksum, and dropped.
+    pub checksum_errors: u64,
+}
+
+impl ProtocolStats {
+    pub(crate) fn count_rx(&mut self, len: usize) {
+        self.rx_packets = self.rx_packets.saturating_add(1);
+        self.rx_bytes = self.rx_bytes.saturating_add(len as u64);
+    }
+
+    pub(crate) fn count_tx(&mut self, len: usize) {
+        self.tx_packets = self.tx_packets.saturating_add(1);
+        self.tx_bytes = self.tx_bytes.saturating_add(len as u64);
+    }
+
+    pub(crate) fn count_checksum_error(&mut self) {
+        self.checksum_errors = self.checksum_errors.saturating_add(1);
+    }
+}
+
+/// Counters of the packets handled by an interface.
+///
+/// The frames exchanged with the device are counted as a whole, and the packets of each
+/// protocol apart, so that a single frame can be counted several times, e.g. as a frame,
+/// as an IPv4 packet, and as a TCP segment.
+///
+/// This struct is marked as `#[non_exhaustive]`, so that new fields can be added without
+/// a breaking change.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct Stats {
+    /// The number of frames received from the device.
+    pub rx_packets: u64,
+    /// The number of octets in the frames received from the device.
+    pub rx_bytes: u64,
+    /// The number of frames sent to the device.
+    pub tx_packets: u64,
+    /// The number of octets in the frames sent to the device.
+    pub tx_bytes: u64,
+    /// The number of packets that could not be sent or forwarded, because no route led
+    /// to their destination.
+    pub no_route: u64,
+    /// The number of packets that could not be sent yet, because the hardware address of
+    /// their next hop was not in the neighbor cache.
+    pub neighbor_misses: u64,
+    /// The number of packets whose reassembly failed, because it timed out or because they
+    /// were dropped to make room for newer ones.
+    pub reassembly_failures: u64,
+    /// IPv4 packets, including their fragments.
+    pub ipv4: ProtocolStats,
+    /// IPv6 packets.
+    pub ipv6: ProtocolStats,
+    /// ICMPv4 messages.
+    pub icmpv4: ProtocolStats,
+    /// ICMPv6 messages.
+    pub icmpv6: ProtocolStats,
+    /// TCP segments.
+    pub tcp: ProtocolStats,
+    /// UDP datagrams.
+    pub udp: ProtocolStats,
+}
+
+impl Stats {
+    pub(crate) fn count_rx(&mut self, len: usize) {
+        self.rx_packets = self.rx_packets.saturating_add(1);
+        self.rx_bytes = self.rx_bytes.saturating_add(len as u64);
+    }
+
+    pub(crate) fn count_tx(&mut self, len: usize) {
+        self.tx_packets = self.tx_packets.saturating_add(1);
+        self.tx_bytes = self.tx_bytes.saturating_add(len as u64);
+    }
+
+    pub(crate) fn count_no_route(&mut self) {
+        self.no_route = self.no_route.saturating_add(1);
+    }
+
+    #[cfg(any(feature = "medium-ethernet", feature = "medium-ieee802154"))]
+    pub(crate) fn count_neighbor_miss(&mut self) {
+        self.neighbor_misses = self.neighbor_misses.saturating_add(1);
+    }
+
+    #[cfg(feature = "_proto-fragmentation")]
+    pub(crate) fn count_reassembly_failure(&mut self) {
+        self.reassembly_failures = self.reassembly_failures.saturating_add(1);
+    }
+
+    /// Count an IP packet being sent, along with its payload.
+    pub(crate) fn count_ip_tx(&mut self, ip_repr: &IpRepr) {
+        self.ip_mut(ip_repr.version())
+            .count_tx(ip_repr.buffer_len());
+        if let Some(stats) = self.protocol_mut(ip_repr.next_header()) {
+            stats.count_tx(ip_repr.payload_len());
+        }
+    }
+
+    /// Return the counters of a version of IP.
+    pub(crate) fn ip_mut(&mut self, version: IpVersion) -> &mut ProtocolStats {
+        match version {
+            #[cfg(feature = "proto-ipv4")]
+            IpVersion::Ipv4 => &mut self.ipv4,
+            #[cfg(feature = "proto-ipv6")]
+            IpVersion::Ipv6 => &mut self.ipv6,
+        }
+    }
+
+    /// Return the counters of a protocol carried by IP, if it has any.
+    pub(crate) fn protocol_mut(&mut self, protocol: IpProtocol) -> Option<&mut ProtocolStats> {
+        match protocol {
+            IpProtocol::Icmp => Some(&mut self.icmpv4),
+            IpProtocol::Icmpv6 => Some(&mut self.icmpv6),
+            IpProtocol::Tcp => Some(&mut self.tcp),
+            IpProtocol::Udp => Some(&mut self.udp),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[cfg(feature = "proto-ipv4")]
+    use crate::wire::{Ipv4Address, Ipv4Repr};
+
+    #[test]
+    fn test_saturating() {
+        let mut stats = Stats {
+            rx_bytes: u64::MAX - 1,
+            ..Stats::default()
+        };
+        stats.count_rx(10);
+        stats.count_rx(10);
+        assert_eq!(stats.rx_packets, 2);
+        assert_eq!(stats.rx_bytes, u64::MAX);
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_count_ip_tx() {
+        let mut stats = Stats::default();
+        stats.count_ip_tx(&IpRepr::Ipv4(Ipv4Repr {
+            src_addr: Ipv4Address::new(192, 168, 1, 1),
+            dst_addr: Ipv4Address::new(192, 168, 1, 2),
+            next_header: IpProtocol::Udp,
+            payload_len: 12,
+            hop_limit: 64,
+        }));
+        assert_eq!(stats.ipv4.tx_packets, 1);
+        assert_eq!(stats.ipv4.tx_bytes, 32);
+        assert_eq!(stats.udp.tx_packets, 1);
+        assert_eq!(stats.udp.tx_bytes, 12);
+        assert_eq!(stats.tcp, ProtocolStats::default());
+        // Only the packets sent to the device are counted as frames.
+        assert_eq!(stats.tx_packets, 0);
+    }
+}
@@ -0,0 +1,507 @@
+// A hierarchical timer wheel, scheduling the sockets of a socket set.
+//
+// Computing the poll time of a socket is not free, and with many sockets, scanning all of them
+// in every call to `Interface::poll_at` dominates the cost of an otherwise idle interface.
+// Instead, the poll time of a scheduled socket is cached in the wheel, and only recomputed
+// once the socket was polled, or may have been changed by processing a packet or through
+// the socket set.
+//
+// The wheel has `LEVELS` levels of `SLOTS` slots, each slot of level `n` spanning `SLOTS^n`
+// ticks of about a millisecond. An entry is stored at the lowest level where its deadline
+// shares all the higher-order slot indices with the current time, so that the slots of
+// every level are ordered from the current time onwards; entries too far in the future
+// are kept in an overflow list. Advancing the time visits every slot that elapsed, at most
+// `SLOTS` per level, and moves its entries to a lower level or to the ready list.
+//
+// The entries are linked in intrusive lists through the socket set, and the wheel itself
+// only holds the list heads, so that it does not need any allocation.
+
+use crate::socket::PollAt;
+use crate::time::Instant;
+
+const SLOT_BITS: u32 = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 4;
+
+/// Number of microseconds in a tick, as a power of two.
+const TICK_BITS: u32 = 10;
+
+/// Identifiers of the lists, the first of which are the slots of the wheel.
+const READY: u16 = (LEVELS * SLOTS) as u16;
+const DIRTY: u16 = READY + 1;
+const BATCH: u16 = READY + 2;
+const OVERFLOW: u16 = READY + 3;
+const UNMANAGED: u16 = READY + 4;
+const LISTS: usize = UNMANAGED as usize + 1;
+
+const NO_LIST: u16 = u16::MAX;
+const NIL: u32 = u32::MAX;
+
+/// The position of a socket in the timer wheel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(crate) struct Entry {
+    list: u16,
+    prev: u32,
+    next: u32,
+    /// The poll time of the socket, in microseconds, if it is in a slot or overflowing.
+    at: u64,
+}
+
+impl Default for Entry {
+    fn default() -> Entry {
+        Entry {
+            list: NO_LIST,
+            prev: NIL,
+            next: NIL,
+            at: 0,
+        }
+    }
+}
+
+/// Storage of the entries, indexed by socket slot.
+pub(crate) trait Entries {
+    fn entry(&self, index: usize) -> &Entry;
+    fn entry_mut(&mut self, index: usize) -> &mut Entry;
+}
+
+#[derive(Debug)]
+pub(crate) struct TimerWheel {
+    heads: [u32; LISTS],
+    /// The current time, in microseconds.
+    now: u64,
+}
+
+fn micros(instant: Instant) -> u64 {
+    instant.total_micros().max(0) as u64
+}
+
+fn tick(at: u64) -> u64 {
+    at >> TICK_BITS
+}
+
+impl TimerWheel {
+    pub(crate) const fn new() -> TimerWheel {
+        TimerWheel {
+            heads: [NIL; LISTS],
+            now: 0,
+        }
+    }
+
+    fn link<E: Entries + ?Sized>(&mut self, entries: &mut E, index: usize, list: u16) {
+        self.unlink(entries, index);
+        let head = self.heads[list as usize];
+        if head != NIL {
+            entries.entry_mut(head as usize).prev = index as u32;
+        }
+        let entry = entries.entry_mut(index);
+        entry.list = list;
+        entry.prev = NIL;
+        entry.next = head;
+        self.heads[list as usize] = index as u32;
+    }
+
+    fn unlink<E: Entries + ?Sized>(&mut self, entries: &mut E, index: usize) {
+        let Entry {
+            list, prev, next, ..
+        } = *entries.entry(index);
+        if list == NO_LIST {
+            return;
+        }
+        if prev == NIL {
+            self.heads[list as usize] = next;
+        } else {
+            entries.entry_mut(prev as usize).next = next;
+        }
+        if next != NIL {
+            entries.entry_mut(next as usize).prev = prev;
+        }
+        *entries.entry_mut(index) = Entry::default();
+    }
+
+    /// Move all the entries of list `from` to list `to`.
+    fn move_all<E: Entries + ?Sized>(&mut self, entries: &mut E, from: u16, to: u16) {
+        while self.heads[from as usize] != NIL {
+            let index = self.heads[from as usize] as usize;
+            self.link(entries, index, to);
+        }
+    }
+
+    /// Store an entry according to its poll time.
+    fn place<E: Entries + ?Sized>(&mut self, entries: &mut E, index: usize, at: u64) {
+        if at <= self.now {
+            return self.link(entries, index, READY);
+        }
+
+        let diff = tick(at) ^ tick(self.now);
+        let level = ((u64::BITS - diff.leading_zeros()).saturating_sub(1) / SLOT_BITS) as usize;
+        let list = if level < LEVELS {
+            let slot = (tick(at) >> (SLOT_BITS as usize * level)) as usize % SLOTS;
+            (level * SLOTS + slot) as u16
+        } else {
+            OVERFLOW
+        };
+        self.link(entries, index, list);
+        entries.entry_mut(index).at = at;
+    }
+
+    /// Add an entry that is never scheduled, and always needs to be evaluated.
+    pub(crate) fn add_unmanaged<E: Entries + ?Sized>(&mut self, entries: &mut E, index: usize) {
+        self.link(entries, index, UNMANAGED)
+    }
+
+    /// Remove an entry from the wheel.
+    pub(crate) fn remove<E: Entries + ?Sized>(&mut self, entries: &mut E, index: usize) {
+        self.unlink(entries, index)
+    }
+
+    /// Mark an entry as needing to be evaluated again, because its socket may have changed.
+    pub(crate) fn touch<E: Entries + ?Sized>(&mut self, entries: &mut E, index: usize) {
+        if !matches!(entries.entry(index).list, DIRTY | UNMANAGED) {
+            self.link(entries, index, DIRTY)
+        }
+    }
+
+    /// Schedule an entry according to the poll time of its socket.
+    pub(crate) fn schedule<E: Entries + ?Sized>(
+        &mut self,
+        entries: &mut E,
+        index: usize,
+        poll_at: PollAt,
+    ) {
+        if entries.entry(index).list == UNMANAGED {
+            return;
+        }
+        match poll_at {
+            PollAt::Now => self.link(entries, index, READY),
+            PollAt::Time(instant) => self.place(entries, index, micros(instant)),
+            PollAt::Ingress => self.unlink(entries, index),
+        }
+    }
+
+    /// Advance the time, and gather the entries that must be evaluated into a batch: those
+    /// whose poll time has come, and those that may have changed.
+    pub(crate) fn take_due<E: Entries + ?Sized>(&mut self, entries: &mut E, now: Instant) {
+        let now = micros(now);
+        if now > self.now {
+            let (old_tick, new_tick) = (tick(self.now), tick(now));
+            self.now = now;
+
+            for level in 0..LEVELS {
+                let shift = SLOT_BITS as usize * level;
+                let (first, last) = (old_tick >> shift, new_tick >> shift);
+                let count = (last - first + 1).min(SLOTS as u64);
+                for k in first..first + count {
+                    let list = (level * SLOTS + k as usize % SLOTS) as u16;
+                    self.replace_all(entries, list);
+                }
+            }
+            if old_tick >> (SLOT_BITS as usize * LEVELS)
+                != new_tick >> (SLOT_BITS as usize * LEVELS)
+            {
+                self.replace_all(entries, OVERFLOW);
+            }
+        }
+
+        self.move_all(entries, READY, BATCH);
+        self.move_all(entries, DIRTY, BATCH);
+    }
+
+    /// Place again all the entries of a list, relative to the current time.
+    fn replace_all<E: Entries + ?Sized>(&mut self, entries: &mut E, list: u16) {
+        let mut index = self.heads[list as usize];
+        self.heads[list as usize] = NIL;
+        while index != NIL {
+            let Entry { next, at, .. } = *entries.entry(index as usize);
+            *entries.entry_mut(index as usize) = Entry::default();
+            self.place(entries, index as usize, at);
+            index = next;
+        }
+    }
+
+    /// Return the next entry whose socket must be evaluated in the current batch, after
+    /// the entry `prev` returned by the previous call, if any.
+    ///
+    /// The unmanaged entries are returned first. The others are taken out of the batch,
+    /// and need to be evaluated again until they are scheduled.
+    pub(crate) fn next_due<E: Entries + ?Sized>(
+        &mut self,
+        entries: &mut E,
+        prev: Option<usize>,
+    ) -> Option<usize> {
+        let next = match prev {
+            None => self.heads[UNMANAGED as usize],
+            Some(index) if entries.entry(index).list == UNMANAGED => entries.entry(index).next,
+            Some(_) => NIL,
+        };
+        if next != NIL {
+            return Some(next as usize);
+        }
+
+        let index = self.heads[BATCH as usize];
+        if index == NIL {
+            return None;
+        }
+        self.link(entries, index as usize, DIRTY);
+        Some(index as usize)
+    }
+
+    /// Return the next entry that may have changed since it was last evaluated, after
+    /// the entry `prev` returned by the previous call, if any.
+    ///
+    /// Entries touched meanwhile are not returned.
+    #[cfg(feature = "socket-tcp")]
+    pub(crate) fn next_touched<E: Entries + ?Sized>(
+        &self,
+        entries: &E,
+        prev: Option<usize>,
+    ) -> Option<usize> {
+        let next = match prev {
+            None => self.heads[DIRTY as usize],
+            Some(index) => entries.entry(index).next,
+        };
+        (next != NIL).then_some(next as usize)
+    }
+
+    /// Mark the entries left in the current batch as needing to be evaluated again.
+    pub(crate) fn finish_batch<E: Entries + ?Sized>(&mut self, entries: &mut E) {
+        self.move_all(entries, BATCH, DIRTY)
+    }
+
+    /// Return the indices of the entries whose poll time is not known.
+    pub(crate) fn unscheduled<'e, E: Entries + ?Sized>(
+        &self,
+        entries: &'e E,
+    ) -> impl Iterator<Item = usize> + 'e {
+        [DIRTY, BATCH, UNMANAGED]
+            .map(|list| self.heads[list as usize])
+            .into_iter()
+            .flat_map(move |head| {
+                core::iter::successors((head != NIL).then_some(head), move |&index| {
+                    let next = entries.entry(index as usize).next;
+                    (next != NIL).then_some(next)
+                })
+            })
+            .map(|index| index as usize)
+    }
+
+    /// Return the earliest poll time of the scheduled entries.
+    pub(crate) fn poll_at<E: Entries + ?Sized>(&self, entries: &E) -> PollAt {
+        if self.heads[READY as usize] != NIL {
+            return PollAt::Now;
+        }
+
+        let min_at = |head: u32| {
+            let mut index = head;
+            let mut min_at = None;
+            while index != NIL {
+                let entry = entries.entry(index as usize);
+                min_at = Some(min_at.map_or(entry.at, |at: u64| at.min(entry.at)));
+                index = entry.next;
+            }
+            min_at
+        };
+
+        // The slots of a level are ordered from the current time onwards, and the entries
+        // of a level are all earlier than those of the levels above it.
+        let now_tick = tick(self.now);
+        let at = (0..LEVELS)
+            .find_map(|level| {
+                let first = (now_tick >> (SLOT_BITS as usize * level)) as usize % SLOTS;
+                (first..SLOTS)
+                    .map(|slot| self.heads[level * SLOTS + slot])
+                    .find(|&head| head != NIL)
+                    .and_then(min_at)
+            })
+            .or_else(|| min_at(self.heads[OVERFLOW as usize]));
+
+        match at {
+            Some(at) => PollAt::Time(Instant::from_micros(at as i64)),
+            None => PollAt::Ingress,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::time::Duration;
+
+    impl Entries for [Entry] {
+        fn entry(&self, index: usize) -> &Entry {
+            &self[index]
+        }
+
+        fn entry_mut(&mut self, index: usize) -> &mut Entry {
+            &mut self[index]
+        }
+    }
+
+    fn due(wheel: &mut TimerWheel, entries: &mut [Entry], now: Instant) -> Vec<usize> {
+        wheel.take_due(entries, now);
+        let mut due = vec![];
+        let mut prev = None;
+        while let Some(index) = wheel.next_due(entries, prev) {
+            wheel.schedule(entries, index, PollAt::Ingress);
+            due.push(index);
+            prev = Some(index);
+        }
+        due.sort();
+        due
+    }
+
+    #[test]
+    fn test_schedule() {
+        let mut wheel = TimerWheel::new();
+        let mut entries = [Entry::default(); 4];
+        assert_eq!(wheel.poll_at(&entries[..]), PollAt::Ingress);
+
+        wheel.schedule(&mut entries[..], 0, PollAt::Time(Instant::from_millis(100)));
+        wheel.schedule(&mut entries[..], 1, PollAt::Time(Instant::from_secs(10)));
+        wheel.schedule(
+            &mut entries[..],
+            2,
+            PollAt::Time(Instant::from_secs(100_000)),
+        );
+        assert_eq!(
+            wheel.poll_at(&entries[..]),
+            PollAt::Time(Instant::from_millis(100))
+        );
+        wheel.schedule(
+            &mut entries[..],
+            3,
+            PollAt::Time(Instant::from_micros(2500)),
+        );
+        assert_eq!(
+            wheel.poll_at(&entries[..]),
+            PollAt::Time(Instant::from_micros(2500))
+        );
+        wheel.schedule(&mut entries[..], 3, PollAt::Now);
+        assert_eq!(wheel.poll_at(&entries[..]), PollAt::Now);
+        wheel.remove(&mut entries[..], 3);
+        assert_eq!(
+            wheel.poll_at(&entries[..]),
+            PollAt::Time(Instant::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn test_expiry() {
+        let mut wheel = TimerWheel::new();
+        let mut entries = [Entry::default(); 4];
+        let times = [
+            Instant::from_millis(100),
+            Instant::from_secs(10),
+            Instant::from_secs(20_000),
+            Instant::from_micros(10_500),
+        ];
+        for (index, &at) in times.iter().enumerate() {
+            wheel.schedule(&mut entries[..], index, PollAt::Time(at));
+        }
+
+        assert_eq!(due(&mut wheel, &mut entries, Instant::from_millis(10)), []);
+        assert_eq!(due(&mut wheel, &mut entries, Instant::from_millis(11)), [3]);
+        assert_eq!(
+            wheel.poll_at(&entries[..]),
+            PollAt::Time(Instant::from_millis(100))
+        );
+        assert_eq!(due(&mut wheel, &mut entries, Instant::from_secs(9)), [0]);
+        assert_eq!(
+            wheel.poll_at(&entries[..]),
+            PollAt::Time(Instant::from_secs(10))
+        );
+        assert_eq!(due(&mut wheel, &mut entries, Instant::from_secs(10)), [1]);
+        assert_eq!(
+            wheel.poll_at(&entries[..]),
+            PollAt::Time(Instant::from_secs(20_000))
+        );
+        assert_eq!(
+            due(&mut wheel, &mut entries, Instant::from_secs(19_999)),
+            []
+        );
+        assert_eq!(
+            due(&mut wheel, &mut entries, Instant::from_secs(20_000)),
+            [2]
+        );
+        assert_eq!(wheel.poll_at(&entries[..]), PollAt::Ingress);
+    }
+
+    #[test]
+    fn test_expiry_every_tick() {
+        let mut wheel = TimerWheel::new();
+        let mut entries = [Entry::default(); 3];
+        let times = [
+            Instant::from_millis(70),
+            Instant::from_millis(4200),
+            Instant::from_millis(5000),
+        ];
+        for (index, &at) in times.iter().enumerate() {
+            wheel.schedule(&mut entries[..], index, PollAt::Time(at));
+        }
+
+        let mut expired = vec![];
+        let mut now = Instant::ZERO;
+        while now <= Instant::from_secs(6) {
+            for index in due(&mut wheel, &mut entries, now) {
+                assert!(now >= times[index]);
+                assert!(now < times[index] + Duration::from_millis(1));
+                expired.push(index);
+            }
+            now += Duration::from_micros(300);
+        }
+        assert_eq!(expired, [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_touch() {
+        let mut wheel = TimerWheel::new();
+        let mut entries = [Entry::default(); 3];
+        wheel.add_unmanaged(&mut entries[..], 0);
+        wheel.schedule(&mut entries[..], 1, PollAt::Time(Instant::from_secs(1)));
+        wheel.schedule(&mut entries[..], 2, PollAt::Time(Instant::from_secs(2)));
+        wheel.touch(&mut entries[..], 0);
+        wheel.touch(&mut entries[..], 2);
+
+        let mut unscheduled: Vec<usize> = wheel.unscheduled(&entries[..]).collect();
+        unscheduled.sort();
+        assert_eq!(unscheduled, [0, 2]);
+        assert_eq!(
+            wheel.poll_at(&entries[..]),
+            PollAt::Time(Instant::from_secs(1))
+        );
+
+        // Touched and unmanaged entries are always due.
+        wheel.take_due(&mut entries[..], Instant::from_millis(1));
+        assert_eq!(wheel.next_due(&mut entries[..], None), Some(0));
+        assert_eq!(wheel.next_due(&mut entries[..], Some(0)), Some(2));
+        assert_eq!(wheel.next_due(&mut entries[..], Some(2)), None);
+
+        // Entries that were not evaluated stay unscheduled.
+        wheel.finish_batch(&mut entries[..]);
+        let mut unscheduled: Vec<usize> = wheel.unscheduled(&entries[..]).collect();
+        unscheduled.sort();
+        assert_eq!(unscheduled, [0, 2]);
+    }
+
+    #[cfg(feature = "socket-tcp")]
+    #[test]
+    fn test_next_touched() {
+        let mut wheel = TimerWheel::new();
+        let mut entries = [Entry::default(); 4];
+        wheel.add_unmanaged(&mut entries[..], 0);
+        wheel.schedule(&mut entries[..], 1, PollAt::Time(Instant::from_secs(1)));
+        wheel.touch(&mut entries[..], 2);
+        wheel.touch(&mut entries[..], 3);
+
+        let mut touched = vec![];
+        let mut prev = None;
+        while let Some(index) = wheel.next_touched(&entries[..], prev) {
+            // Entries touched meanwhile are not visited.
+            wheel.touch(&mut entries[..], 1);
+            touched.push(index);
+            prev = Some(index);
+        }
+        touched.sort();
+        assert_eq!(touched, [2, 3]);
+    }
+}
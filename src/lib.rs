@@ -106,10 +106,11 @@ compile_error!("You must enable at least one of the following features: proto-ip
         feature = "socket-tcp",
         feature = "socket-icmp",
         feature = "socket-dhcpv4",
+        feature = "socket-dhcpv6",
         feature = "socket-dns",
     ))
 ))]
-compile_error!("If you enable the socket feature, you must enable at least one of the following features: socket-raw, socket-udp, socket-tcp, socket-icmp, socket-dhcpv4, socket-dns");
+compile_error!("If you enable the socket feature, you must enable at least one of the following features: socket-raw, socket-udp, socket-tcp, socket-icmp, socket-dhcpv4, socket-dhcpv6, socket-dns");
 
 #[cfg(all(
     feature = "socket",
@@ -126,19 +127,28 @@ compile_error!("You must enable at most one of the following features: defmt, lo
 
 #[macro_use]
 mod macros;
+#[cfg(any(feature = "socket-tcp-md5", feature = "phy-ppp"))]
+mod md5;
 mod parsers;
 mod rand;
+#[cfg(any(feature = "socket-tcp", feature = "proto-ipv6"))]
+mod siphash;
 
 #[cfg(test)]
 mod config {
     #![allow(unused)]
     pub const ASSEMBLER_MAX_SEGMENT_COUNT: usize = 4;
+    pub const BRIDGE_MAC_TABLE_COUNT: usize = 4;
     pub const DNS_MAX_NAME_SIZE: usize = 255;
     pub const DNS_MAX_RESULT_COUNT: usize = 1;
     pub const DNS_MAX_SERVER_COUNT: usize = 1;
     pub const FRAGMENTATION_BUFFER_SIZE: usize = 1500;
+    pub const IFACE_DESTINATION_CACHE_COUNT: usize = 4;
+    pub const IFACE_LOCAL_BUFFER_SIZE: usize = 3072;
     pub const IFACE_MAX_ADDR_COUNT: usize = 8;
+    pub const IFACE_MAX_FILTER_RULE_COUNT: usize = 4;
     pub const IFACE_MAX_MULTICAST_GROUP_COUNT: usize = 4;
+    pub const IFACE_MAX_MULTICAST_SOURCE_COUNT: usize = 4;
     pub const IFACE_MAX_ROUTE_COUNT: usize = 4;
     pub const IFACE_MAX_SIXLOWPAN_ADDRESS_CONTEXT_COUNT: usize = 4;
     pub const IFACE_NEIGHBOR_CACHE_COUNT: usize = 3;
@@ -0,0 +1,148 @@
+//! The MD5 digest, as described in RFC 1321, used by TCP MD5 signatures and PPP CHAP
+//! authentication.
+
+use byteorder::{ByteOrder, LittleEndian};
+
+const SHIFTS: [u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const TABLE: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// An incremental MD5 digest computation.
+pub(crate) struct Md5 {
+    state: [u32; 4],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Md5 {
+    pub(crate) fn new() -> Md5 {
+        Md5 {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476],
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub(crate) fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        while !data.is_empty() {
+            let size = data.len().min(64 - self.block_len);
+            self.block[self.block_len..self.block_len + size].copy_from_slice(&data[..size]);
+            self.block_len += size;
+            data = &data[size..];
+            if self.block_len == 64 {
+                self.compress();
+                self.block_len = 0;
+            }
+        }
+    }
+
+    pub(crate) fn finalize(mut self) -> [u8; 16] {
+        let bit_len = self.total_len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0]);
+        }
+        let mut len_bytes = [0; 8];
+        LittleEndian::write_u64(&mut len_bytes, bit_len);
+        self.update(&len_bytes);
+
+        let mut digest = [0; 16];
+        LittleEndian::write_u32_into(&self.state, &mut digest);
+        digest
+    }
+
+    fn compress(&mut self) {
+        let mut words = [0; 16];
+        LittleEndian::read_u32_into(&self.block, &mut words);
+
+        let [mut a, mut b, mut c, mut d] = self.state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f
+                .wrapping_add(a)
+                .wrapping_add(TABLE[i])
+                .wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFTS[(i / 16) * 4 + i % 4]));
+        }
+
+        self.state[0] = self.state[0].wrapping_add(a);
+        self.state[1] = self.state[1].wrapping_add(b);
+        self.state[2] = self.state[2].wrapping_add(c);
+        self.state[3] = self.state[3].wrapping_add(d);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn digest(data: &[u8]) -> [u8; 16] {
+        let mut md5 = Md5::new();
+        md5.update(data);
+        md5.finalize()
+    }
+
+    #[test]
+    fn test_md5_rfc1321() {
+        assert_eq!(
+            digest(b""),
+            [
+                0xd4, 0x1d, 0x8c, 0xd9, 0x8f, 0x00, 0xb2, 0x04, 0xe9, 0x80, 0x09, 0x98, 0xec, 0xf8,
+                0x42, 0x7e
+            ]
+        );
+        assert_eq!(
+            digest(b"abc"),
+            [
+                0x90, 0x01, 0x50, 0x98, 0x3c, 0xd2, 0x4f, 0xb0, 0xd6, 0x96, 0x3f, 0x7d, 0x28, 0xe1,
+                0x7f, 0x72
+            ]
+        );
+        assert_eq!(
+            digest(b"message digest"),
+            [
+                0xf9, 0x6b, 0x69, 0x7d, 0x7c, 0xb7, 0x93, 0x8d, 0x52, 0x5a, 0x2f, 0x31, 0xaa, 0xf1,
+                0x61, 0xd0
+            ]
+        );
+        assert_eq!(
+            digest(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            ),
+            [
+                0x57, 0xed, 0xf4, 0xa2, 0x2b, 0xe3, 0xc9, 0x55, 0xac, 0x49, 0xda, 0x2e, 0x21, 0x07,
+                0xb6, 0x7a
+            ]
+        );
+    }
+
+    #[test]
+    fn test_md5_incremental() {
+        let data = [0x5a; 200];
+        let mut md5 = Md5::new();
+        for chunk in data.chunks(7) {
+            md5.update(chunk);
+        }
+        assert_eq!(md5.finalize(), digest(&data));
+    }
+}
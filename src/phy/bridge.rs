@@ -0,0 +1,567 @@
+use heapless::LinearMap;
+use managed::ManagedSlice;
+
+use crate::config::BRIDGE_MAC_TABLE_COUNT;
+use crate::phy::{self, Device, DeviceCapabilities, Medium, PacketMeta};
+use crate::time::{Duration, Instant};
+use crate::wire::{EthernetAddress, EthernetFrame};
+
+/// An entry of the MAC learning table of a [Bridge].
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    port: usize,
+    expires_at: Instant,
+}
+
+/// What became of a frame received by a port.
+enum Received {
+    Nothing,
+    Forwarded,
+    Local(usize, PacketMeta),
+}
+
+/// A learning bridge between Ethernet devices.
+///
+/// A bridge forwards the frames received by each of its ports, learning the port behind
+/// each source hardware address. Frames for a known unicast address are only sent through
+/// the port it was last seen on, and dropped if that is the port they came from; all other
+/// frames are flooded to every other port. Entries of the learning table expire after the
+/// [ageing time](#method.set_ageing_time), 5 minutes by default.
+///
+/// Frames can also be delivered to the stack: once a [local address](#method.set_local_addr)
+/// is set, the bridge itself is a device to be polled by an interface with that hardware
+/// address. Frames for that address, as well as broadcast and multicast frames, are then
+/// received by the interface, and frames sent by the interface are forwarded like the
+/// others. Without a local address, the bridge is only a switch, driven by [poll].
+///
+/// [poll]: #method.poll
+pub struct Bridge<'a, D: Device> {
+    ports: ManagedSlice<'a, D>,
+    table: LinearMap<EthernetAddress, Entry, BRIDGE_MAC_TABLE_COUNT>,
+    local_addr: Option<EthernetAddress>,
+    ageing_time: Duration,
+    rx_buffer: ManagedSlice<'a, u8>,
+    tx_buffer: ManagedSlice<'a, u8>,
+    next_port: usize,
+}
+
+impl<'a, D: Device> Bridge<'a, D> {
+    /// Create a bridge between the given devices.
+    ///
+    /// The buffers hold a frame being forwarded, or being received or sent by the
+    /// stack; frames larger than the receive buffer are dropped.
+    ///
+    /// # Panics
+    /// This function panics if the medium of one of the devices is not Ethernet.
+    ///
+    /// # Examples
+    ///
+    /// Creating a two-port bridge with owned storage:
+    ///
+    /// ```rust,ignore
+    /// let bridge = Bridge::new(vec![eth0, eth1], vec![0; 1514], vec![0; 1514]);
+    /// ```
+    pub fn new<P, B>(ports: P, rx_buffer: B, tx_buffer: B) -> Bridge<'a, D>
+    where
+        P: Into<ManagedSlice<'a, D>>,
+        B: Into<ManagedSlice<'a, u8>>,
+    {
+        let ports = ports.into();
+        for port in ports.iter() {
+            assert_eq!(port.capabilities().medium, Medium::Ethernet);
+        }
+        Bridge {
+            ports,
+            table: LinearMap::new(),
+            local_addr: None,
+            ageing_time: Duration::from_secs(300),
+            rx_buffer: rx_buffer.into(),
+            tx_buffer: tx_buffer.into(),
+            next_port: 0,
+        }
+    }
+
+    /// Get the ports of the bridge.
+    pub fn ports(&self) -> &[D] {
+        &self.ports
+    }
+
+    /// Get the ports of the bridge mutably.
+    ///
+    /// It is inadvisable to directly read from the devices as doing so will circumvent
+    /// the bridge.
+    pub fn ports_mut(&mut self) -> &mut [D] {
+        &mut self.ports
+    }
+
+    /// Return the hardware address of the stack, if frames are delivered to it.
+    pub fn local_addr(&self) -> Option<EthernetAddress> {
+        self.local_addr
+    }
+
+    /// Set the hardware address of the stack, delivering the frames for it, as well as
+    /// broadcast and multicast frames, to the interface polling the bridge; or stop
+    /// delivering frames to the stack.
+    pub fn set_local_addr(&mut self, addr: Option<EthernetAddress>) {
+        self.local_addr = addr;
+    }
+
+    /// Return the time after which an address that was not seen is forgotten.
+    pub fn ageing_time(&self) -> Duration {
+        self.ageing_time
+    }
+
+    /// Set the time after which an address that was not seen is forgotten.
+    pub fn set_ageing_time(&mut self, ageing_time: Duration) {
+        self.ageing_time = ageing_time;
+    }
+
+    /// Return the port behind a hardware address, if it was learned and has not expired.
+    pub fn lookup(&self, addr: &EthernetAddress, timestamp: Instant) -> Option<usize> {
+        match self.table.get(addr) {
+            Some(entry) if entry.expires_at > timestamp => Some(entry.port),
+            _ => None,
+        }
+    }
+
+    /// Forget every learned address.
+    pub fn flush(&mut self) {
+        self.table.clear();
+    }
+
+    /// Forward every frame received by the ports.
+    ///
+    /// Frames which would be delivered to the stack are dropped; when a local address
+    /// is set, poll the interface using the bridge instead.
+    ///
+    /// This function returns a boolean value indicating whether any frames were
+    /// processed.
+    pub fn poll(&mut self, timestamp: Instant) -> bool {
+        let mut processed_any = false;
+        while self.process_all(timestamp).is_some() {
+            processed_any = true;
+        }
+        processed_any
+    }
+
+    /// Receive one frame from each port in turn, until one is to be delivered to the
+    /// stack or none was received.
+    fn process_all(&mut self, timestamp: Instant) -> Option<Received> {
+        if self.ports.is_empty() {
+            return None;
+        }
+        let mut received = None;
+        for _ in 0..self.ports.len() {
+            let index = self.next_port;
+            self.next_port = (index + 1) % self.ports.len();
+            match self.process(index, timestamp) {
+                Received::Nothing => (),
+                Received::Forwarded => received = Some(Received::Forwarded),
+                local @ Received::Local(..) => return Some(local),
+            }
+        }
+        received
+    }
+
+    /// Receive a frame from a port, learn its source address, and forward it.
+    fn process(&mut self, index: usize, timestamp: Instant) -> Received {
+        let (len, meta) = {
+            let rx_buffer = &mut self.rx_buffer[..];
+            let Some((rx_token, _tx_token)) = self.ports[index].receive(timestamp) else {
+                return Received::Nothing;
+            };
+            let meta = phy::RxToken::meta(&rx_token);
+            let len = phy::RxToken::consume(rx_token, |frame| {
+                if frame.len() > rx_buffer.len() {
+                    net_debug!("bridge: frame too large for buffer, dropping");
+                    return None;
+                }
+                rx_buffer[..frame.len()].copy_from_slice(frame);
+                Some(frame.len())
+            });
+            match len {
+                Some(len) => (len, meta),
+                None => return Received::Forwarded,
+            }
+        };
+
+        let Ok(frame) = EthernetFrame::new_checked(&self.rx_buffer[..len]) else {
+            return Received::Forwarded;
+        };
+        let (src_addr, dst_addr) = (frame.src_addr(), frame.dst_addr());
+        if src_addr.is_unicast() && Some(src_addr) != self.local_addr {
+            self.learn(src_addr, index, timestamp);
+        }
+
+        if Some(dst_addr) == self.local_addr {
+            return Received::Local(len, meta);
+        }
+        forward(
+            &mut self.ports,
+            &self.table,
+            timestamp,
+            Some(index),
+            &self.rx_buffer[..len],
+        );
+        if self.local_addr.is_some() && dst_addr.is_multicast() {
+            Received::Local(len, meta)
+        } else {
+            Received::Forwarded
+        }
+    }
+
+    /// Record the port behind a hardware address, evicting the entry closest to expiry
+    /// if the table is full.
+    fn learn(&mut self, addr: EthernetAddress, port: usize, timestamp: Instant) {
+        let entry = Entry {
+            port,
+            expires_at: timestamp + self.ageing_time,
+        };
+        if let Some(existing) = self.table.get(&addr) {
+            if existing.port != port {
+                net_debug!("bridge: {} moved to port {}", addr, port);
+            }
+        } else if self.table.len() == self.table.capacity() {
+            let oldest = self
+                .table
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(addr, _)| *addr);
+            if let Some(oldest) = oldest {
+                self.table.remove(&oldest);
+            }
+        }
+        let _ = self.table.insert(addr, entry);
+    }
+}
+
+/// Send a frame through the port behind its destination, or through every port but the
+/// one it was received on if the destination is unknown or not unicast.
+fn forward<D: Device>(
+    ports: &mut [D],
+    table: &LinearMap<EthernetAddress, Entry, BRIDGE_MAC_TABLE_COUNT>,
+    timestamp: Instant,
+    ingress: Option<usize>,
+    frame: &[u8],
+) {
+    let Ok(eth_frame) = EthernetFrame::new_checked(frame) else {
+        return;
+    };
+    let dst_addr = eth_frame.dst_addr();
+    let known = match table.get(&dst_addr) {
+        Some(entry) if dst_addr.is_unicast() && entry.expires_at > timestamp => Some(entry.port),
+        _ => None,
+    };
+
+    match known {
+        Some(port) if Some(port) == ingress => (),
+        Some(port) => transmit(&mut ports[port], timestamp, frame),
+        None => {
+            for (index, port) in ports.iter_mut().enumerate() {
+                if Some(index) != ingress {
+                    transmit(port, timestamp, frame);
+                }
+            }
+        }
+    }
+}
+
+fn transmit<D: Device>(port: &mut D, timestamp: Instant, frame: &[u8]) {
+    if frame.len() > port.capabilities().max_transmission_unit {
+        net_debug!("bridge: frame too large for port, dropping");
+        return;
+    }
+    match port.transmit(timestamp) {
+        Some(token) => {
+            phy::TxToken::consume(token, frame.len(), |buffer| buffer.copy_from_slice(frame))
+        }
+        None => net_debug!("bridge: port exhausted, dropping"),
+    }
+}
+
+impl<'a, D: Device> Device for Bridge<'a, D> {
+    type RxToken<'t> = RxToken<'t>
+    where
+        Self: 't;
+    type TxToken<'t> = TxToken<'t, 'a, D>
+    where
+        Self: 't;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mtu = self
+            .ports
+            .iter()
+            .map(|port| port.capabilities().max_transmission_unit)
+            .chain([self.rx_buffer.len(), self.tx_buffer.len()])
+            .min()
+            .unwrap_or(0);
+        DeviceCapabilities {
+            medium: Medium::Ethernet,
+            max_transmission_unit: mtu,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let (len, meta) = loop {
+            match self.process_all(timestamp)? {
+                Received::Local(len, meta) => break (len, meta),
+                _ => continue,
+            }
+        };
+        let rx = RxToken {
+            buffer: &mut self.rx_buffer[..len],
+            meta,
+        };
+        let tx = TxToken {
+            ports: &mut self.ports,
+            table: &self.table,
+            buffer: &mut self.tx_buffer,
+            timestamp,
+        };
+        Some((rx, tx))
+    }
+
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxToken {
+            ports: &mut self.ports,
+            table: &self.table,
+            buffer: &mut self.tx_buffer,
+            timestamp,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken<'b> {
+    buffer: &'b mut [u8],
+    meta: PacketMeta,
+}
+
+impl<'b> phy::RxToken for RxToken<'b> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(self.buffer)
+    }
+
+    fn meta(&self) -> PacketMeta {
+        self.meta
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<'b, 'a, D: Device> {
+    ports: &'b mut ManagedSlice<'a, D>,
+    table: &'b LinearMap<EthernetAddress, Entry, BRIDGE_MAC_TABLE_COUNT>,
+    buffer: &'b mut ManagedSlice<'a, u8>,
+    timestamp: Instant,
+}
+
+impl<'b, 'a, D: Device> phy::TxToken for TxToken<'b, 'a, D> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let buffer = &mut self.buffer[..len];
+        let result = f(buffer);
+        forward(self.ports, self.table, self.timestamp, None, buffer);
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::phy::{RxToken as _, TxToken as _};
+    use crate::wire::{EthernetProtocol, EthernetRepr};
+
+    const LOCAL: EthernetAddress = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    const HOST_A: EthernetAddress = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x0a]);
+    const HOST_B: EthernetAddress = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x0b]);
+
+    /// A device whose received and sent frames are queued apart.
+    #[derive(Default)]
+    struct TestDevice {
+        rx: VecDeque<Vec<u8>>,
+        tx: VecDeque<Vec<u8>>,
+    }
+
+    struct TestRxToken(Vec<u8>);
+
+    impl phy::RxToken for TestRxToken {
+        fn consume<R, F>(mut self, f: F) -> R
+        where
+            F: FnOnce(&mut [u8]) -> R,
+        {
+            f(&mut self.0)
+        }
+    }
+
+    struct TestTxToken<'a>(&'a mut VecDeque<Vec<u8>>);
+
+    impl<'a> phy::TxToken for TestTxToken<'a> {
+        fn consume<R, F>(self, len: usize, f: F) -> R
+        where
+            F: FnOnce(&mut [u8]) -> R,
+        {
+            let mut buffer = vec![0; len];
+            let result = f(&mut buffer);
+            self.0.push_back(buffer);
+            result
+        }
+    }
+
+    impl Device for TestDevice {
+        type RxToken<'a> = TestRxToken;
+        type TxToken<'a> = TestTxToken<'a>;
+
+        fn capabilities(&self) -> DeviceCapabilities {
+            DeviceCapabilities {
+                max_transmission_unit: 1514,
+                medium: Medium::Ethernet,
+                ..DeviceCapabilities::default()
+            }
+        }
+
+        fn receive(&mut self, _timestamp: Instant) -> Option<(TestRxToken, TestTxToken<'_>)> {
+            let frame = self.rx.pop_front()?;
+            Some((TestRxToken(frame), TestTxToken(&mut self.tx)))
+        }
+
+        fn transmit(&mut self, _timestamp: Instant) -> Option<TestTxToken<'_>> {
+            Some(TestTxToken(&mut self.tx))
+        }
+    }
+
+    fn frame(src_addr: EthernetAddress, dst_addr: EthernetAddress) -> Vec<u8> {
+        let repr = EthernetRepr {
+            src_addr,
+            dst_addr,
+            ethertype: EthernetProtocol::Ipv4,
+        };
+        let mut buffer = vec![0; repr.buffer_len() + 4];
+        repr.emit(&mut EthernetFrame::new_unchecked(&mut buffer));
+        buffer
+    }
+
+    fn bridge() -> Bridge<'static, TestDevice> {
+        let ports = vec![
+            TestDevice::default(),
+            TestDevice::default(),
+            TestDevice::default(),
+        ];
+        Bridge::new(ports, vec![0; 1514], vec![0; 1514])
+    }
+
+    fn sent(bridge: &mut Bridge<TestDevice>) -> Vec<usize> {
+        let mut ports = vec![];
+        for (index, port) in bridge.ports_mut().iter_mut().enumerate() {
+            if !port.tx.is_empty() {
+                ports.push(index);
+                port.tx.clear();
+            }
+        }
+        ports
+    }
+
+    #[test]
+    fn test_learning() {
+        let mut bridge = bridge();
+        let now = Instant::ZERO;
+
+        // Unknown destination: flooded.
+        bridge.ports_mut()[0].rx.push_back(frame(HOST_A, HOST_B));
+        assert!(bridge.poll(now));
+        assert_eq!(sent(&mut bridge), vec![1, 2]);
+        assert_eq!(bridge.lookup(&HOST_A, now), Some(0));
+
+        // Learned destination: only sent through its port.
+        bridge.ports_mut()[1].rx.push_back(frame(HOST_B, HOST_A));
+        assert!(bridge.poll(now));
+        assert_eq!(sent(&mut bridge), vec![0]);
+        bridge.ports_mut()[0].rx.push_back(frame(HOST_A, HOST_B));
+        assert!(bridge.poll(now));
+        assert_eq!(sent(&mut bridge), vec![1]);
+        assert!(!bridge.poll(now));
+
+        // Destination behind the port the frame came from: dropped.
+        bridge.ports_mut()[1].rx.push_back(frame(HOST_B, HOST_B));
+        assert!(bridge.poll(now));
+        assert_eq!(sent(&mut bridge), Vec::<usize>::new());
+
+        // Broadcast: flooded.
+        bridge.ports_mut()[2]
+            .rx
+            .push_back(frame(HOST_A, EthernetAddress::BROADCAST));
+        assert!(bridge.poll(now));
+        assert_eq!(sent(&mut bridge), vec![0, 1]);
+        assert_eq!(bridge.lookup(&HOST_A, now), Some(2));
+
+        // Expired entry: flooded.
+        let later = now + bridge.ageing_time();
+        assert_eq!(bridge.lookup(&HOST_B, later), None);
+        bridge.ports_mut()[2].rx.push_back(frame(HOST_A, HOST_B));
+        assert!(bridge.poll(later));
+        assert_eq!(sent(&mut bridge), vec![0, 1]);
+
+        bridge.flush();
+        assert_eq!(bridge.lookup(&HOST_A, later), None);
+    }
+
+    #[test]
+    fn test_table_full() {
+        let mut bridge = bridge();
+        for i in 0..BRIDGE_MAC_TABLE_COUNT as u8 + 1 {
+            let src_addr = EthernetAddress([0x02, 0, 0, 0, 0x01, i]);
+            let timestamp = Instant::from_secs(i as i64);
+            bridge.ports_mut()[0].rx.push_back(frame(src_addr, HOST_B));
+            bridge.poll(timestamp);
+        }
+        let now = Instant::from_secs(BRIDGE_MAC_TABLE_COUNT as i64);
+        let first = EthernetAddress([0x02, 0, 0, 0, 0x01, 0]);
+        let last = EthernetAddress([0x02, 0, 0, 0, 0x01, BRIDGE_MAC_TABLE_COUNT as u8]);
+        assert_eq!(bridge.lookup(&first, now), None);
+        assert_eq!(bridge.lookup(&last, now), Some(0));
+    }
+
+    #[test]
+    fn test_local_delivery() {
+        let mut bridge = bridge();
+        bridge.set_local_addr(Some(LOCAL));
+        let now = Instant::ZERO;
+
+        // Frames for the stack are only delivered to it.
+        bridge.ports_mut()[0].rx.push_back(frame(HOST_A, LOCAL));
+        let (rx, tx) = bridge.receive(now).unwrap();
+        assert_eq!(rx.consume(|buffer| buffer.to_vec()), frame(HOST_A, LOCAL));
+
+        // Replies are sent through the port behind their destination.
+        tx.consume(18, |buffer| buffer.copy_from_slice(&frame(LOCAL, HOST_A)));
+        assert_eq!(sent(&mut bridge), vec![0]);
+
+        // Broadcast frames are both delivered and flooded.
+        bridge.ports_mut()[1]
+            .rx
+            .push_back(frame(HOST_B, EthernetAddress::BROADCAST));
+        assert!(bridge.receive(now).is_some());
+        assert_eq!(sent(&mut bridge), vec![0, 2]);
+
+        // Other frames are forwarded without being delivered.
+        bridge.ports_mut()[1].rx.push_back(frame(HOST_B, HOST_A));
+        assert!(bridge.receive(now).is_none());
+        assert_eq!(sent(&mut bridge), vec![0]);
+
+        // Frames with an unknown destination sent by the stack are flooded.
+        let tx = bridge.transmit(now).unwrap();
+        tx.consume(18, |buffer| {
+            buffer.copy_from_slice(&frame(LOCAL, EthernetAddress::BROADCAST))
+        });
+        assert_eq!(sent(&mut bridge), vec![0, 1, 2]);
+    }
+}
@@ -7,9 +7,19 @@ and implementations of it:
   * the [_loopback_](struct.Loopback.html), for zero dependency testing;
   * _middleware_ [Tracer](struct.Tracer.html) and
     [FaultInjector](struct.FaultInjector.html), to facilitate debugging;
+  * the [VlanDevice](struct.VlanDevice.html), to run one interface per
+    IEEE 802.1Q VLAN over a single Ethernet device;
+  * the [Bridge](struct.Bridge.html), a learning switch between Ethernet devices;
+  * the [VirtioNetDevice](struct.VirtioNetDevice.html), to drive a virtio-net device
+    from a `no_std` guest of a hypervisor;
+  * the [PppDevice](struct.PppDevice.html), to run PPP over a serial line, such as
+    the one of a cellular modem;
   * _adapters_ [RawSocket](struct.RawSocket.html) and
     [TunTapInterface](struct.TunTapInterface.html), to transmit and receive frames
-    on the host OS.
+    on the host OS, and [XdpDevice](struct.XdpDevice.html), to do so at high rates
+    on Linux;
+  * _adapters_ [WintunInterface](struct.WintunInterface.html) and
+    [NpcapDevice](struct.NpcapDevice.html), to do so on Windows.
 */
 #![cfg_attr(
     feature = "medium-ethernet",
@@ -90,45 +100,90 @@ impl<'a> phy::TxToken for StmPhyTxToken<'a> {
 
 use crate::time::Instant;
 
-#[cfg(all(
-    any(feature = "phy-raw_socket", feature = "phy-tuntap_interface"),
-    unix
+#[cfg(any(
+    all(
+        any(
+            feature = "phy-raw_socket",
+            feature = "phy-tuntap_interface",
+            feature = "phy-xdp"
+        ),
+        unix
+    ),
+    all(any(feature = "phy-wintun", feature = "phy-npcap"), windows)
 ))]
 mod sys;
 
+#[cfg(feature = "medium-ethernet")]
+mod bridge;
 mod fault_injector;
 mod fuzz_injector;
 #[cfg(feature = "alloc")]
 mod loopback;
+#[cfg(all(feature = "phy-npcap", windows))]
+mod npcap_device;
 mod pcap_writer;
+#[cfg(feature = "phy-ppp")]
+mod ppp;
 #[cfg(all(feature = "phy-raw_socket", unix))]
 mod raw_socket;
 mod tracer;
 #[cfg(all(
     feature = "phy-tuntap_interface",
-    any(target_os = "linux", target_os = "android")
+    any(target_os = "linux", target_os = "android", target_os = "macos")
 ))]
 mod tuntap_interface;
+#[cfg(feature = "phy-virtio_net")]
+mod virtio_net;
+#[cfg(feature = "medium-ethernet")]
+mod vlan;
+#[cfg(all(feature = "phy-wintun", windows))]
+mod wintun_interface;
+#[cfg(all(feature = "phy-xdp", target_os = "linux", target_pointer_width = "64"))]
+mod xdp;
 
 #[cfg(all(
-    any(feature = "phy-raw_socket", feature = "phy-tuntap_interface"),
+    any(
+        feature = "phy-raw_socket",
+        feature = "phy-tuntap_interface",
+        feature = "phy-xdp"
+    ),
     unix
 ))]
 pub use self::sys::wait;
 
+#[cfg(feature = "medium-ethernet")]
+pub use self::bridge::Bridge;
 pub use self::fault_injector::FaultInjector;
 pub use self::fuzz_injector::{FuzzInjector, Fuzzer};
 #[cfg(feature = "alloc")]
 pub use self::loopback::Loopback;
+#[cfg(all(feature = "phy-npcap", windows))]
+pub use self::npcap_device::NpcapDevice;
 pub use self::pcap_writer::{PcapLinkType, PcapMode, PcapSink, PcapWriter};
+#[cfg(all(feature = "phy-ppp", feature = "proto-ipv4"))]
+pub use self::ppp::PppIpv4Config;
+#[cfg(all(feature = "phy-ppp", feature = "proto-ipv6"))]
+pub use self::ppp::PppIpv6Config;
+#[cfg(feature = "phy-ppp")]
+pub use self::ppp::{PppDevice, PppEvent, SerialPort, PPP_MAX_CREDENTIAL_LEN};
 #[cfg(all(feature = "phy-raw_socket", unix))]
 pub use self::raw_socket::RawSocket;
 pub use self::tracer::Tracer;
 #[cfg(all(
     feature = "phy-tuntap_interface",
-    any(target_os = "linux", target_os = "android")
+    any(target_os = "linux", target_os = "android", target_os = "macos")
 ))]
 pub use self::tuntap_interface::TunTapInterface;
+#[cfg(feature = "phy-virtio_net")]
+pub use self::virtio_net::{
+    VirtioNetDevice, VirtioNetError, VirtioNetMemory, VirtioTransport, VIRTIO_NET_BUFFER_LEN,
+};
+#[cfg(feature = "medium-ethernet")]
+pub use self::vlan::{Vlan, VlanDevice};
+#[cfg(all(feature = "phy-wintun", windows))]
+pub use self::wintun_interface::WintunInterface;
+#[cfg(all(feature = "phy-xdp", target_os = "linux", target_pointer_width = "64"))]
+pub use self::xdp::XdpDevice;
 
 /// Metadata associated to a packet.
 ///
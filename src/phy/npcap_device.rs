@@ -0,0 +1,129 @@
+use std::cell::RefCell;
+use std::io;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use crate::phy::{self, sys, Device, DeviceCapabilities, Medium};
+use crate::time::{Duration, Instant};
+
+/// A capture of an Ethernet interface with Npcap, the packet capture driver of Nmap for
+/// Windows, that receives and transmits the complete frames, like a raw socket.
+///
+/// `wpcap.dll` is loaded from the `Npcap` directory of the system directory, where
+/// the Npcap installer puts it, when the device is created.
+#[derive(Debug)]
+pub struct NpcapDevice {
+    lower: Rc<RefCell<sys::NpcapDesc>>,
+    mtu: usize,
+}
+
+impl NpcapDevice {
+    /// Opens a capture of the interface called `name`, which is the name of its
+    /// Npcap device, e.g. `\Device\NPF_{5B2A53E8-1B6F-4A2C-8CC4-5E8AE7C96C5C}`, with
+    /// the GUID of the interface, as listed by `getmac /v` or `pktmon list`.
+    ///
+    /// The interface is put in promiscuous mode. The MTU of the device is the one of
+    /// an Ethernet interface, 1500 octets plus the Ethernet header; see
+    /// [set_mtu](Self::set_mtu) for interfaces configured otherwise.
+    ///
+    /// This requires administrator privileges, unless Npcap was installed without the
+    /// option to restrict its access to administrators.
+    pub fn new(name: &str) -> io::Result<NpcapDevice> {
+        let mtu = 1500 + crate::wire::EthernetFrame::<&[u8]>::header_len();
+        let lower = sys::NpcapDesc::new(name, mtu)?;
+        Ok(NpcapDevice {
+            lower: Rc::new(RefCell::new(lower)),
+            mtu,
+        })
+    }
+
+    /// Set the MTU of the device, including the Ethernet header, which should be the
+    /// one configured on the interface.
+    pub fn set_mtu(&mut self, mtu: usize) {
+        self.mtu = mtu;
+    }
+
+    /// Wait until a frame is received, but no longer than given timeout.
+    ///
+    /// This takes the place of `phy::wait` on Windows, where the device has
+    /// no file descriptor.
+    pub fn wait(&self, duration: Option<Duration>) -> io::Result<()> {
+        self.lower.borrow().wait(duration)
+    }
+}
+
+impl Device for NpcapDevice {
+    type RxToken<'a>
+        = RxToken
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken
+    where
+        Self: 'a;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            max_transmission_unit: self.mtu,
+            medium: Medium::Ethernet,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let mut lower = self.lower.borrow_mut();
+        let mut buffer = vec![0; self.mtu];
+        match lower.recv(&mut buffer[..]) {
+            Ok(size) => {
+                buffer.resize(size, 0);
+                let rx = RxToken { buffer };
+                let tx = TxToken {
+                    lower: self.lower.clone(),
+                };
+                Some((rx, tx))
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => None,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxToken {
+            lower: self.lower.clone(),
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken {
+    buffer: Vec<u8>,
+}
+
+impl phy::RxToken for RxToken {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(&mut self.buffer[..])
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken {
+    lower: Rc<RefCell<sys::NpcapDesc>>,
+}
+
+impl phy::TxToken for TxToken {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut lower = self.lower.borrow_mut();
+        let mut buffer = vec![0; len];
+        let result = f(&mut buffer);
+        if let Err(err) = lower.send(&buffer[..]) {
+            panic!("{}", err);
+        }
+        result
+    }
+}
@@ -0,0 +1,85 @@
+// The authentication of this end of the link to the peer, with PAP, as described in
+// RFC 1334, or CHAP with MD5, as described in RFC 1994.
+
+use super::fsm::HEADER_LEN;
+use crate::md5::Md5;
+
+pub(super) const PAP_AUTHENTICATE_REQUEST: u8 = 1;
+pub(super) const PAP_AUTHENTICATE_ACK: u8 = 2;
+pub(super) const PAP_AUTHENTICATE_NAK: u8 = 3;
+
+pub(super) const CHAP_CHALLENGE: u8 = 1;
+pub(super) const CHAP_RESPONSE: u8 = 2;
+pub(super) const CHAP_SUCCESS: u8 = 3;
+pub(super) const CHAP_FAILURE: u8 = 4;
+
+/// Write the data of a PAP Authenticate-Request after the header in `buffer`, and return
+/// its length.
+pub(super) fn pap_request(buffer: &mut [u8], name: &[u8], secret: &[u8]) -> usize {
+    let data = &mut buffer[HEADER_LEN..];
+    data[0] = name.len() as u8;
+    data[1..][..name.len()].copy_from_slice(name);
+    data[1 + name.len()] = secret.len() as u8;
+    data[2 + name.len()..][..secret.len()].copy_from_slice(secret);
+    2 + name.len() + secret.len()
+}
+
+/// Write the data of the CHAP Response to the challenge in `challenge`, the data of a
+/// CHAP Challenge, after the header in `buffer`, and return its length.
+pub(super) fn chap_response(
+    buffer: &mut [u8],
+    id: u8,
+    challenge: &[u8],
+    name: &[u8],
+    secret: &[u8],
+) -> Option<usize> {
+    let (&value_len, challenge) = challenge.split_first()?;
+    let value = challenge.get(..value_len as usize)?;
+
+    let mut md5 = Md5::new();
+    md5.update(&[id]);
+    md5.update(secret);
+    md5.update(value);
+
+    let data = &mut buffer[HEADER_LEN..];
+    data[0] = 16;
+    data[1..17].copy_from_slice(&md5.finalize());
+    data[17..][..name.len()].copy_from_slice(name);
+    Some(17 + name.len())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_pap_request() {
+        let mut buffer = [0; 32];
+        let len = pap_request(&mut buffer, b"user", b"pass");
+        assert_eq!(&buffer[HEADER_LEN..][..len], b"\x04user\x04pass");
+    }
+
+    #[test]
+    fn test_chap_response() {
+        let mut buffer = [0; 64];
+        let challenge = [
+            16, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, b'p', b'e', b'e', b'r',
+        ];
+        let len = chap_response(&mut buffer, 1, &challenge, b"user", b"secret").unwrap();
+        assert_eq!(len, 21);
+        // MD5(0x01 || "secret" || 0x00..0x0f)
+        assert_eq!(
+            &buffer[HEADER_LEN..][..len],
+            &[
+                16, 0x74, 0x0e, 0x86, 0x46, 0x3b, 0xda, 0x3a, 0x4d, 0x70, 0x17, 0xd6, 0xe0, 0xfb,
+                0xa0, 0x69, 0x9d, b'u', b's', b'e', b'r'
+            ]
+        );
+
+        assert_eq!(
+            chap_response(&mut buffer, 1, &[16, 0], b"user", b"secret"),
+            None
+        );
+    }
+}
@@ -0,0 +1,591 @@
+// The option negotiation automaton of the control protocols of PPP, as described in
+// RFC 1661, which is shared by LCP and the network control protocols.
+
+use crate::time::{Duration, Instant};
+
+pub(super) const CONFIGURE_REQUEST: u8 = 1;
+pub(super) const CONFIGURE_ACK: u8 = 2;
+pub(super) const CONFIGURE_NAK: u8 = 3;
+pub(super) const CONFIGURE_REJECT: u8 = 4;
+pub(super) const TERMINATE_REQUEST: u8 = 5;
+pub(super) const TERMINATE_ACK: u8 = 6;
+pub(super) const CODE_REJECT: u8 = 7;
+
+/// The length of the code, identifier and length fields of a control packet.
+pub(super) const HEADER_LEN: usize = 4;
+/// The largest control packet sent.
+pub(super) const MAX_PACKET_LEN: usize = 256;
+
+const RESTART_INTERVAL: Duration = Duration::from_secs(3);
+const MAX_TERMINATE: u8 = 2;
+const MAX_CONFIGURE: u8 = 10;
+const MAX_FAILURE: u8 = 5;
+
+/// A sink of the control packets of a protocol.
+pub(super) trait Output {
+    fn send(&mut self, protocol: u16, packet: &[u8]);
+}
+
+/// Write the header of a control packet, whose data follows it in `buffer`, and return
+/// the packet.
+pub(super) fn emit(buffer: &mut [u8], code: u8, id: u8, data_len: usize) -> &[u8] {
+    let len = HEADER_LEN + data_len;
+    buffer[0] = code;
+    buffer[1] = id;
+    buffer[2..4].copy_from_slice(&(len as u16).to_be_bytes());
+    &buffer[..len]
+}
+
+/// Split a control packet into its code, identifier and data.
+pub(super) fn parse(packet: &[u8]) -> Option<(u8, u8, &[u8])> {
+    if packet.len() < HEADER_LEN {
+        return None;
+    }
+    let len = u16::from_be_bytes([packet[2], packet[3]]) as usize;
+    // The octets beyond the length field are padding.
+    if len < HEADER_LEN || len > packet.len() {
+        return None;
+    }
+    Some((packet[0], packet[1], &packet[HEADER_LEN..len]))
+}
+
+/// Iterate over the configuration options in `data`, as types and values.
+pub(super) fn parse_options(data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    let mut rest = data;
+    core::iter::from_fn(move || match *rest {
+        [kind, len, ..] if len >= 2 && len as usize <= rest.len() => {
+            let (option, next) = rest.split_at(len as usize);
+            rest = next;
+            Some((kind, &option[2..]))
+        }
+        _ => None,
+    })
+}
+
+/// Check that `data` is a sequence of well-formed configuration options.
+fn valid_options(data: &[u8]) -> bool {
+    parse_options(data)
+        .map(|(_, value)| value.len() + 2)
+        .sum::<usize>()
+        == data.len()
+}
+
+/// Append a configuration option to the ones in `buffer[..len]`, if it fits, and return
+/// their new length.
+pub(super) fn push_option(buffer: &mut [u8], len: usize, kind: u8, value: &[u8]) -> usize {
+    let end = len + 2 + value.len();
+    if end > buffer.len() {
+        return len;
+    }
+    buffer[len] = kind;
+    buffer[len + 1] = (2 + value.len()) as u8;
+    buffer[len + 2..end].copy_from_slice(value);
+    end
+}
+
+/// Whether a configuration option requested by the peer is acceptable.
+pub(super) enum Verdict {
+    Ack,
+    /// Acceptable with the value written in the buffer given, of that length.
+    Nak(usize),
+    Reject,
+}
+
+/// The configuration options of a control protocol.
+pub(super) trait Options {
+    /// Start the negotiation anew.
+    fn reset(&mut self);
+
+    /// Write the options of the next Configure-Request in `buffer`, and return their length.
+    fn request(&self, buffer: &mut [u8]) -> usize;
+
+    /// Check an option requested by the peer, writing an acceptable value in `nak`.
+    fn check(&self, kind: u8, value: &[u8], nak: &mut [u8]) -> Verdict;
+
+    /// Use the options of a Configure-Request of the peer, which was acknowledged.
+    fn apply(&mut self, options: &[u8]);
+
+    /// Use the value suggested by the peer for a requested option.
+    fn nak(&mut self, kind: u8, value: &[u8]);
+
+    /// Stop requesting an option rejected by the peer.
+    fn reject(&mut self, kind: u8);
+}
+
+/// The state of the automaton; the Starting state is absent, as the lower layer of PPP,
+/// the serial link, is always up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(super) enum State {
+    Initial,
+    Closed,
+    Stopped,
+    Closing,
+    Stopping,
+    ReqSent,
+    AckRcvd,
+    AckSent,
+    Opened,
+}
+
+/// A change of the layer above the automaton.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(super) enum Transition {
+    /// This-Layer-Up: the options are negotiated.
+    Up,
+    /// This-Layer-Down: the options are negotiated again, or the link is terminated.
+    Down,
+    /// This-Layer-Finished: the negotiation failed, or the link is terminated.
+    Finished,
+}
+
+#[derive(Debug)]
+pub(super) struct Fsm {
+    protocol: u16,
+    state: State,
+    /// Identifier of the last Configure-Request or Terminate-Request sent.
+    id: u8,
+    /// Identifier of the last Code-Reject sent.
+    reject_id: u8,
+    restarts: u8,
+    failures: u8,
+    deadline: Option<Instant>,
+}
+
+impl Fsm {
+    pub(super) const fn new(protocol: u16) -> Fsm {
+        Fsm {
+            protocol,
+            state: State::Initial,
+            id: 0,
+            reject_id: 0,
+            restarts: 0,
+            failures: 0,
+            deadline: None,
+        }
+    }
+
+    pub(super) fn state(&self) -> State {
+        self.state
+    }
+
+    pub(super) fn is_opened(&self) -> bool {
+        self.state == State::Opened
+    }
+
+    /// Return the identifier of a new packet that rejects a received one.
+    pub(super) fn next_reject_id(&mut self) -> u8 {
+        self.reject_id = self.reject_id.wrapping_add(1);
+        self.reject_id
+    }
+
+    /// Return the instant of the next retransmission.
+    pub(super) fn poll_at(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Start the negotiation.
+    pub(super) fn open<O: Options>(&mut self, options: &mut O, now: Instant, out: &mut dyn Output) {
+        match self.state {
+            State::Initial | State::Closed | State::Stopped => {
+                options.reset();
+                self.failures = MAX_FAILURE;
+                self.restart(options, now, out);
+                self.state = State::ReqSent;
+            }
+            State::Closing => self.state = State::Stopping,
+            _ => (),
+        }
+    }
+
+    /// Terminate the link.
+    pub(super) fn close(&mut self, now: Instant, out: &mut dyn Output) -> Option<Transition> {
+        let transition = match self.state {
+            State::Stopped => {
+                self.state = State::Closed;
+                return None;
+            }
+            State::Stopping => {
+                self.state = State::Closing;
+                return None;
+            }
+            State::Opened => Some(Transition::Down),
+            State::ReqSent | State::AckRcvd | State::AckSent => None,
+            _ => return None,
+        };
+        self.restarts = MAX_TERMINATE;
+        self.send_terminate_request(now, out);
+        self.state = State::Closing;
+        transition
+    }
+
+    /// Stop the automaton without a termination exchange, as the link below it is down.
+    pub(super) fn down(&mut self) -> Option<Transition> {
+        let opened = self.is_opened();
+        self.state = State::Initial;
+        self.deadline = None;
+        opened.then_some(Transition::Down)
+    }
+
+    /// Retransmit the last request if its time has come.
+    pub(super) fn poll<O: Options>(
+        &mut self,
+        options: &mut O,
+        now: Instant,
+        out: &mut dyn Output,
+    ) -> Option<Transition> {
+        match self.deadline {
+            Some(deadline) if deadline <= now => (),
+            _ => return None,
+        }
+        if self.restarts > 0 {
+            match self.state {
+                State::Closing | State::Stopping => self.send_terminate_request(now, out),
+                State::ReqSent | State::AckRcvd => {
+                    self.send_configure_request(options, now, out);
+                    self.state = State::ReqSent;
+                }
+                State::AckSent => self.send_configure_request(options, now, out),
+                _ => self.deadline = None,
+            }
+            return None;
+        }
+
+        net_debug!("ppp: protocol {:04x} timed out", self.protocol);
+        self.deadline = None;
+        self.state = match self.state {
+            State::Closing => State::Closed,
+            State::Stopping | State::ReqSent | State::AckRcvd | State::AckSent => State::Stopped,
+            _ => return None,
+        };
+        Some(Transition::Finished)
+    }
+
+    /// Process a received packet with one of the codes common to all the protocols.
+    pub(super) fn input<O: Options>(
+        &mut self,
+        options: &mut O,
+        packet: &[u8],
+        now: Instant,
+        out: &mut dyn Output,
+    ) -> Option<Transition> {
+        let (code, id, data) = parse(packet)?;
+        if self.state == State::Initial {
+            return None;
+        }
+        match code {
+            CONFIGURE_REQUEST
+                if valid_options(data) && data.len() <= MAX_PACKET_LEN - HEADER_LEN =>
+            {
+                self.receive_configure_request(options, id, data, now, out)
+            }
+            CONFIGURE_ACK if id == self.id => self.receive_configure_ack(options, now, out),
+            CONFIGURE_NAK | CONFIGURE_REJECT if id == self.id && valid_options(data) => {
+                for (kind, value) in parse_options(data) {
+                    if code == CONFIGURE_NAK {
+                        options.nak(kind, value);
+                    } else {
+                        options.reject(kind);
+                    }
+                }
+                self.receive_configure_nak(options, now, out)
+            }
+            CONFIGURE_REQUEST | CONFIGURE_ACK | CONFIGURE_NAK | CONFIGURE_REJECT => {
+                net_debug!(
+                    "ppp: protocol {:04x} dropping unexpected packet",
+                    self.protocol
+                );
+                None
+            }
+            TERMINATE_REQUEST => self.receive_terminate_request(id, now, out),
+            TERMINATE_ACK => self.receive_terminate_ack(options, now, out),
+            CODE_REJECT => {
+                // Only the rejection of a code of the automaton is fatal.
+                match data.first() {
+                    Some(CONFIGURE_REQUEST..=CODE_REJECT) => self.receive_fatal_reject(now, out),
+                    _ => None,
+                }
+            }
+            _ => {
+                let mut buffer = [0; MAX_PACKET_LEN];
+                let len = packet.len().min(MAX_PACKET_LEN - HEADER_LEN);
+                buffer[HEADER_LEN..][..len].copy_from_slice(&packet[..len]);
+                let id = self.next_reject_id();
+                out.send(self.protocol, emit(&mut buffer, CODE_REJECT, id, len));
+                None
+            }
+        }
+    }
+
+    fn receive_configure_request<O: Options>(
+        &mut self,
+        options: &mut O,
+        id: u8,
+        data: &[u8],
+        now: Instant,
+        out: &mut dyn Output,
+    ) -> Option<Transition> {
+        match self.state {
+            State::Closed => {
+                self.send_terminate_ack(id, out);
+                return None;
+            }
+            State::Closing | State::Stopping => return None,
+            _ => (),
+        }
+
+        let mut buffer = [0; MAX_PACKET_LEN];
+        let (code, len) = self.check_request(options, data, &mut buffer[HEADER_LEN..]);
+        let mut transition = None;
+        match self.state {
+            State::Opened => {
+                transition = Some(Transition::Down);
+                self.restart(options, now, out);
+            }
+            State::Stopped => self.restart(options, now, out),
+            _ => (),
+        }
+        out.send(self.protocol, emit(&mut buffer, code, id, len));
+
+        self.state = match (self.state, code == CONFIGURE_ACK) {
+            (State::AckRcvd, true) => {
+                options.apply(data);
+                self.deadline = None;
+                transition = Some(Transition::Up);
+                State::Opened
+            }
+            (_, true) => {
+                options.apply(data);
+                State::AckSent
+            }
+            (State::AckRcvd, false) => State::AckRcvd,
+            (_, false) => State::ReqSent,
+        };
+        transition
+    }
+
+    /// Return the response to the options requested by the peer, and the length of the
+    /// options it contains.
+    fn check_request<O: Options>(
+        &mut self,
+        options: &O,
+        data: &[u8],
+        buffer: &mut [u8],
+    ) -> (u8, usize) {
+        let mut code = CONFIGURE_ACK;
+        let mut len = 0;
+        for (kind, value) in parse_options(data) {
+            let mut nak = [0; 16];
+            match options.check(kind, value, &mut nak) {
+                Verdict::Ack => (),
+                Verdict::Nak(nak_len) if code != CONFIGURE_REJECT && self.failures > 0 => {
+                    if code == CONFIGURE_ACK {
+                        code = CONFIGURE_NAK;
+                        len = 0;
+                    }
+                    len = push_option(buffer, len, kind, &nak[..nak_len]);
+                }
+                // After too many Configure-Naks, the options are rejected instead, in case
+                // the peer cannot use any other value.
+                Verdict::Nak(_) | Verdict::Reject => {
+                    if code != CONFIGURE_REJECT {
+                        code = CONFIGURE_REJECT;
+                        len = 0;
+                    }
+                    len = push_option(buffer, len, kind, value);
+                }
+            }
+        }
+        match code {
+            CONFIGURE_ACK => {
+                buffer[..data.len()].copy_from_slice(data);
+                (code, data.len())
+            }
+            CONFIGURE_NAK => {
+                self.failures -= 1;
+                (code, len)
+            }
+            _ => (code, len),
+        }
+    }
+
+    fn receive_configure_ack<O: Options>(
+        &mut self,
+        options: &mut O,
+        now: Instant,
+        out: &mut dyn Output,
+    ) -> Option<Transition> {
+        match self.state {
+            State::ReqSent => {
+                self.restarts = MAX_CONFIGURE;
+                self.state = State::AckRcvd;
+                None
+            }
+            State::AckRcvd => {
+                // A crossed connection, or a duplicate.
+                self.send_configure_request(options, now, out);
+                self.state = State::ReqSent;
+                None
+            }
+            State::AckSent => {
+                self.deadline = None;
+                self.state = State::Opened;
+                Some(Transition::Up)
+            }
+            State::Opened => {
+                self.restart(options, now, out);
+                self.state = State::ReqSent;
+                Some(Transition::Down)
+            }
+            State::Closed | State::Stopped => {
+                self.send_terminate_ack(self.id, out);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn receive_configure_nak<O: Options>(
+        &mut self,
+        options: &mut O,
+        now: Instant,
+        out: &mut dyn Output,
+    ) -> Option<Transition> {
+        match self.state {
+            State::ReqSent | State::AckSent => {
+                self.restart(options, now, out);
+                None
+            }
+            State::AckRcvd => {
+                self.send_configure_request(options, now, out);
+                self.state = State::ReqSent;
+                None
+            }
+            State::Opened => {
+                self.restart(options, now, out);
+                self.state = State::ReqSent;
+                Some(Transition::Down)
+            }
+            State::Closed | State::Stopped => {
+                self.send_terminate_ack(self.id, out);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn receive_terminate_request(
+        &mut self,
+        id: u8,
+        now: Instant,
+        out: &mut dyn Output,
+    ) -> Option<Transition> {
+        self.send_terminate_ack(id, out);
+        match self.state {
+            State::Opened => {
+                // Wait for a restart interval before finishing, for the peer to receive
+                // the Terminate-Ack.
+                self.restarts = 0;
+                self.deadline = Some(now + RESTART_INTERVAL);
+                self.state = State::Stopping;
+                Some(Transition::Down)
+            }
+            State::AckRcvd | State::AckSent => {
+                self.state = State::ReqSent;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn receive_terminate_ack<O: Options>(
+        &mut self,
+        options: &mut O,
+        now: Instant,
+        out: &mut dyn Output,
+    ) -> Option<Transition> {
+        match self.state {
+            State::Closing | State::Stopping => {
+                self.deadline = None;
+                self.state = match self.state {
+                    State::Closing => State::Closed,
+                    _ => State::Stopped,
+                };
+                Some(Transition::Finished)
+            }
+            State::AckRcvd => {
+                self.state = State::ReqSent;
+                None
+            }
+            State::Opened => {
+                self.restart(options, now, out);
+                self.state = State::ReqSent;
+                Some(Transition::Down)
+            }
+            _ => None,
+        }
+    }
+
+    fn receive_fatal_reject(&mut self, now: Instant, out: &mut dyn Output) -> Option<Transition> {
+        net_debug!("ppp: protocol {:04x} rejected by the peer", self.protocol);
+        match self.state {
+            State::Opened => {
+                self.restarts = MAX_TERMINATE;
+                self.send_terminate_request(now, out);
+                self.state = State::Stopping;
+                Some(Transition::Down)
+            }
+            State::Closing => {
+                self.deadline = None;
+                self.state = State::Closed;
+                Some(Transition::Finished)
+            }
+            State::Stopping | State::ReqSent | State::AckRcvd | State::AckSent => {
+                self.deadline = None;
+                self.state = State::Stopped;
+                Some(Transition::Finished)
+            }
+            _ => None,
+        }
+    }
+
+    /// Send a new Configure-Request, with the full count of retransmissions.
+    fn restart<O: Options>(&mut self, options: &O, now: Instant, out: &mut dyn Output) {
+        self.restarts = MAX_CONFIGURE;
+        self.send_configure_request(options, now, out);
+    }
+
+    fn send_configure_request<O: Options>(
+        &mut self,
+        options: &O,
+        now: Instant,
+        out: &mut dyn Output,
+    ) {
+        let mut buffer = [0; MAX_PACKET_LEN];
+        let len = options.request(&mut buffer[HEADER_LEN..]);
+        self.id = self.id.wrapping_add(1);
+        out.send(
+            self.protocol,
+            emit(&mut buffer, CONFIGURE_REQUEST, self.id, len),
+        );
+        self.restarts = self.restarts.saturating_sub(1);
+        self.deadline = Some(now + RESTART_INTERVAL);
+    }
+
+    fn send_terminate_request(&mut self, now: Instant, out: &mut dyn Output) {
+        let mut buffer = [0; HEADER_LEN];
+        self.id = self.id.wrapping_add(1);
+        out.send(
+            self.protocol,
+            emit(&mut buffer, TERMINATE_REQUEST, self.id, 0),
+        );
+        self.restarts = self.restarts.saturating_sub(1);
+        self.deadline = Some(now + RESTART_INTERVAL);
+    }
+
+    fn send_terminate_ack(&self, id: u8, out: &mut dyn Output) {
+        let mut buffer = [0; HEADER_LEN];
+        out.send(self.protocol, emit(&mut buffer, TERMINATE_ACK, id, 0));
+    }
+}
@@ -0,0 +1,265 @@
+// HDLC-like framing of PPP over asynchronous serial links, as described in RFC 1662.
+
+const FLAG: u8 = 0x7e;
+const ESCAPE: u8 = 0x7d;
+const ALL_STATIONS: u8 = 0xff;
+const UI: u8 = 0x03;
+
+const INITIAL_FCS: u16 = 0xffff;
+/// The FCS of a frame followed by its own FCS.
+const GOOD_FCS: u16 = 0xf0b8;
+
+/// The Async-Control-Character-Map used before LCP negotiates another one, with all the
+/// control characters escaped.
+pub(super) const DEFAULT_ACCM: u32 = 0xffff_ffff;
+
+/// The length of the address, control and protocol fields, and of the FCS, which
+/// surround the information field of an uncompressed frame.
+pub(super) const FRAME_OVERHEAD: usize = 6;
+
+/// Return the length of the encoding of a frame with an information field of `len`
+/// octets, if every octet has to be escaped.
+pub(super) const fn max_encoded_len(len: usize) -> usize {
+    2 * (len + FRAME_OVERHEAD) + 2
+}
+
+fn fcs16(mut fcs: u16, data: &[u8]) -> u16 {
+    for byte in data {
+        fcs ^= *byte as u16;
+        for _ in 0..8 {
+            fcs = if fcs & 1 != 0 {
+                (fcs >> 1) ^ 0x8408
+            } else {
+                fcs >> 1
+            };
+        }
+    }
+    fcs
+}
+
+/// A receiver of the octets of a serial link, that delimits frames and removes their
+/// transparency escapes.
+#[derive(Debug, Default)]
+pub(super) struct Decoder {
+    len: usize,
+    escaped: bool,
+    /// Whether the frame being received is aborted, or does not fit the buffer.
+    discard: bool,
+}
+
+impl Decoder {
+    /// Add an octet to the frame being received in `buffer`.
+    ///
+    /// When it ends a frame with a good FCS, return the length of the frame, without
+    /// its FCS. The control characters in `accm` are discarded, as they may have been
+    /// inserted by the DCE.
+    pub(super) fn push(&mut self, byte: u8, accm: u32, buffer: &mut [u8]) -> Option<usize> {
+        match byte {
+            FLAG => {
+                let len = core::mem::replace(&mut self.len, 0);
+                let discard = core::mem::replace(&mut self.discard, false)
+                    || core::mem::replace(&mut self.escaped, false);
+                if discard || len < 4 || fcs16(INITIAL_FCS, &buffer[..len]) != GOOD_FCS {
+                    // Consecutive flags delimit no frame.
+                    if len > 0 {
+                        net_debug!("ppp: dropping invalid frame");
+                    }
+                    return None;
+                }
+                Some(len - 2)
+            }
+            ESCAPE => {
+                self.escaped = true;
+                None
+            }
+            byte if byte < 0x20 && accm & (1 << byte) != 0 => None,
+            byte => {
+                let byte = if core::mem::replace(&mut self.escaped, false) {
+                    byte ^ 0x20
+                } else {
+                    byte
+                };
+                if self.len < buffer.len() {
+                    buffer[self.len] = byte;
+                    self.len += 1;
+                } else {
+                    self.discard = true;
+                }
+                None
+            }
+        }
+    }
+}
+
+/// Return the protocol of a received frame, and the offset of its information field,
+/// whether its address, control and protocol fields are compressed or not.
+pub(super) fn parse(frame: &[u8]) -> Option<(u16, usize)> {
+    let offset = match frame {
+        [ALL_STATIONS, UI, ..] => 2,
+        [ALL_STATIONS, ..] => return None,
+        _ => 0,
+    };
+    // The least significant bit of the last octet of the protocol field is set.
+    match frame[offset..] {
+        [protocol, ..] if protocol & 1 != 0 => Some((protocol as u16, offset + 1)),
+        [high, low, ..] if low & 1 != 0 => Some((u16::from_be_bytes([high, low]), offset + 2)),
+        _ => None,
+    }
+}
+
+/// A writer of the encoding of a frame, with the flags, the escapes and the FCS.
+pub(super) struct Encoder {
+    pos: usize,
+    fcs: u16,
+    accm: u32,
+}
+
+impl Encoder {
+    /// Start a frame of `protocol` at `pos` in `buffer`, escaping the control characters
+    /// in `accm`, and the address and control fields uncompressed.
+    pub(super) fn new(buffer: &mut [u8], pos: usize, accm: u32, protocol: u16) -> Encoder {
+        buffer[pos] = FLAG;
+        let mut encoder = Encoder {
+            pos: pos + 1,
+            fcs: INITIAL_FCS,
+            accm,
+        };
+        let [high, low] = protocol.to_be_bytes();
+        for byte in [ALL_STATIONS, UI, high, low] {
+            encoder.push(buffer, byte);
+        }
+        encoder
+    }
+
+    fn write(&mut self, buffer: &mut [u8], byte: u8) {
+        let escape = byte == FLAG || byte == ESCAPE || byte < 0x20 && self.accm & (1 << byte) != 0;
+        if escape {
+            buffer[self.pos] = ESCAPE;
+            buffer[self.pos + 1] = byte ^ 0x20;
+            self.pos += 2;
+        } else {
+            buffer[self.pos] = byte;
+            self.pos += 1;
+        }
+    }
+
+    /// Add an octet of the information field.
+    pub(super) fn push(&mut self, buffer: &mut [u8], byte: u8) {
+        self.fcs = fcs16(self.fcs, &[byte]);
+        self.write(buffer, byte);
+    }
+
+    /// End the frame, and return the position of its end.
+    pub(super) fn finish(mut self, buffer: &mut [u8]) -> usize {
+        let [low, high] = (!self.fcs).to_le_bytes();
+        self.write(buffer, low);
+        self.write(buffer, high);
+        buffer[self.pos] = FLAG;
+        self.pos + 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // An LCP Configure-Request with an Async-Control-Character-Map of zero.
+    static INFO: [u8; 10] = [0x01, 0x01, 0x00, 0x0a, 0x02, 0x06, 0x00, 0x00, 0x00, 0x00];
+
+    static ENCODED: [u8; 29] = [
+        0x7e, 0xff, 0x7d, 0x23, 0xc0, 0x21, 0x7d, 0x21, 0x7d, 0x21, 0x7d, 0x20, 0x7d, 0x2a, 0x7d,
+        0x22, 0x7d, 0x26, 0x7d, 0x20, 0x7d, 0x20, 0x7d, 0x20, 0x7d, 0x20, 0x58, 0x7b, 0x7e,
+    ];
+
+    fn encode(accm: u32, protocol: u16, info: &[u8]) -> std::vec::Vec<u8> {
+        let mut buffer = vec![0; max_encoded_len(info.len())];
+        let mut encoder = Encoder::new(&mut buffer, 0, accm, protocol);
+        for byte in info {
+            encoder.push(&mut buffer, *byte);
+        }
+        let len = encoder.finish(&mut buffer);
+        buffer.truncate(len);
+        buffer
+    }
+
+    fn decode(accm: u32, data: &[u8]) -> std::vec::Vec<std::vec::Vec<u8>> {
+        let mut decoder = Decoder::default();
+        let mut buffer = [0; 64];
+        let mut frames = vec![];
+        for byte in data {
+            if let Some(len) = decoder.push(*byte, accm, &mut buffer) {
+                frames.push(buffer[..len].to_vec());
+            }
+        }
+        frames
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(DEFAULT_ACCM, 0xc021, &INFO), &ENCODED[..]);
+        // With an empty map, only the flag and escape characters are escaped.
+        let encoded = encode(0, 0x0021, &[0x45, 0x7e, 0x11]);
+        assert_eq!(
+            &encoded[..8],
+            &[0x7e, 0xff, 0x03, 0x00, 0x21, 0x45, 0x7d, 0x5e]
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let frames = decode(DEFAULT_ACCM, &ENCODED);
+        assert_eq!(frames.len(), 1);
+        assert_eq!(&frames[0][..4], &[0xff, 0x03, 0xc0, 0x21]);
+        assert_eq!(&frames[0][4..], &INFO);
+        assert_eq!(parse(&frames[0]), Some((0xc021, 4)));
+    }
+
+    #[test]
+    fn test_decode_roundtrip() {
+        let info = [0x45, 0x00, 0x7e, 0x7d, 0x11, 0x13, 0xff];
+        for accm in [0, DEFAULT_ACCM] {
+            let frames = decode(accm, &encode(accm, 0x0021, &info));
+            assert_eq!(
+                frames,
+                vec![[&[0xff, 0x03, 0x00, 0x21], &info[..]].concat()]
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        // A corrupted octet fails the FCS.
+        let mut data = ENCODED;
+        data[4] ^= 1;
+        assert!(decode(DEFAULT_ACCM, &data).is_empty());
+
+        // An escape followed by a flag aborts the frame.
+        let mut data = ENCODED.to_vec();
+        data.insert(28, ESCAPE);
+        assert!(decode(DEFAULT_ACCM, &data).is_empty());
+
+        // A frame longer than the buffer is dropped, and the next one received.
+        let mut data = encode(DEFAULT_ACCM, 0x0021, &[0x55; 100]);
+        data.extend_from_slice(&ENCODED[1..]);
+        assert_eq!(decode(DEFAULT_ACCM, &data).len(), 1);
+    }
+
+    #[test]
+    fn test_decode_accm() {
+        // XON and XOFF inserted by a modem are discarded.
+        let mut data = ENCODED.to_vec();
+        data.insert(3, 0x11);
+        data.insert(10, 0x13);
+        assert_eq!(decode(DEFAULT_ACCM, &data).len(), 1);
+        assert!(decode(0, &data).is_empty());
+    }
+
+    #[test]
+    fn test_parse_compressed() {
+        assert_eq!(parse(&[0x21, 0x45]), Some((0x0021, 1)));
+        assert_eq!(parse(&[0xc0, 0x21, 0x01]), Some((0xc021, 2)));
+        assert_eq!(parse(&[0xff, 0x03, 0x57, 0x60]), Some((0x0057, 3)));
+        assert_eq!(parse(&[0xff, 0x05, 0x00, 0x21]), None);
+        assert_eq!(parse(&[0xc0, 0x20]), None);
+    }
+}
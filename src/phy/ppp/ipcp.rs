@@ -0,0 +1,192 @@
+// The configuration options of the network control protocols: IPCP, as described in
+// RFC 1332 and RFC 1877, and IPV6CP, as described in RFC 5072.
+
+use super::fsm::{parse_options, push_option, Options, Verdict};
+#[cfg(feature = "proto-ipv4")]
+use crate::wire::Ipv4Address;
+#[cfg(feature = "proto-ipv6")]
+use crate::wire::Ipv6Address;
+
+#[cfg(feature = "proto-ipv4")]
+const IP_ADDRESS: u8 = 3;
+#[cfg(feature = "proto-ipv4")]
+const PRIMARY_DNS_SERVER: u8 = 129;
+#[cfg(feature = "proto-ipv4")]
+const SECONDARY_DNS_SERVER: u8 = 131;
+
+#[cfg(feature = "proto-ipv6")]
+const INTERFACE_IDENTIFIER: u8 = 1;
+
+#[cfg(feature = "proto-ipv4")]
+#[derive(Debug)]
+pub(super) struct Ipcp {
+    /// The address requested, unspecified to have the peer assign one.
+    pub(super) address: Ipv4Address,
+    request_address: bool,
+    /// The primary and secondary DNS servers, unspecified to have the peer give them.
+    pub(super) dns_servers: [Ipv4Address; 2],
+    request_dns_servers: [bool; 2],
+
+    pub(super) peer_address: Option<Ipv4Address>,
+}
+
+#[cfg(feature = "proto-ipv4")]
+impl Ipcp {
+    pub(super) const fn new() -> Ipcp {
+        Ipcp {
+            address: Ipv4Address::UNSPECIFIED,
+            request_address: true,
+            dns_servers: [Ipv4Address::UNSPECIFIED; 2],
+            request_dns_servers: [true; 2],
+            peer_address: None,
+        }
+    }
+}
+
+#[cfg(feature = "proto-ipv4")]
+impl Options for Ipcp {
+    fn reset(&mut self) {
+        *self = Ipcp::new();
+    }
+
+    fn request(&self, buffer: &mut [u8]) -> usize {
+        let mut len = 0;
+        if self.request_address {
+            len = push_option(buffer, len, IP_ADDRESS, self.address.as_bytes());
+        }
+        for (i, kind) in [PRIMARY_DNS_SERVER, SECONDARY_DNS_SERVER]
+            .into_iter()
+            .enumerate()
+        {
+            if self.request_dns_servers[i] {
+                len = push_option(buffer, len, kind, self.dns_servers[i].as_bytes());
+            }
+        }
+        len
+    }
+
+    fn check(&self, kind: u8, value: &[u8], _nak: &mut [u8]) -> Verdict {
+        match (kind, value.len()) {
+            // No address can be assigned to the peer.
+            (IP_ADDRESS, 4) if value != [0; 4] => Verdict::Ack,
+            _ => Verdict::Reject,
+        }
+    }
+
+    fn apply(&mut self, options: &[u8]) {
+        self.peer_address = parse_options(options)
+            .find(|(kind, _)| *kind == IP_ADDRESS)
+            .map(|(_, value)| Ipv4Address::from_bytes(value));
+    }
+
+    fn nak(&mut self, kind: u8, value: &[u8]) {
+        if value.len() != 4 {
+            return;
+        }
+        let address = Ipv4Address::from_bytes(value);
+        match kind {
+            IP_ADDRESS => self.address = address,
+            PRIMARY_DNS_SERVER => self.dns_servers[0] = address,
+            SECONDARY_DNS_SERVER => self.dns_servers[1] = address,
+            _ => (),
+        }
+    }
+
+    fn reject(&mut self, kind: u8) {
+        match kind {
+            IP_ADDRESS => self.request_address = false,
+            PRIMARY_DNS_SERVER => self.request_dns_servers[0] = false,
+            SECONDARY_DNS_SERVER => self.request_dns_servers[1] = false,
+            _ => (),
+        }
+    }
+}
+
+#[cfg(feature = "proto-ipv6")]
+#[derive(Debug)]
+pub(super) struct Ipv6cp {
+    /// The interface identifier requested, zero to have the peer suggest one.
+    identifier: [u8; 8],
+    request_identifier: bool,
+
+    peer_identifier: [u8; 8],
+}
+
+#[cfg(feature = "proto-ipv6")]
+impl Ipv6cp {
+    pub(super) const fn new() -> Ipv6cp {
+        Ipv6cp {
+            identifier: [0; 8],
+            request_identifier: true,
+            peer_identifier: [0; 8],
+        }
+    }
+
+    fn link_local_address(identifier: &[u8; 8]) -> Ipv6Address {
+        let mut address = [0; 16];
+        address[..2].copy_from_slice(&[0xfe, 0x80]);
+        address[8..].copy_from_slice(identifier);
+        Ipv6Address(address)
+    }
+
+    pub(super) fn address(&self) -> Ipv6Address {
+        Self::link_local_address(&self.identifier)
+    }
+
+    pub(super) fn peer_address(&self) -> Ipv6Address {
+        Self::link_local_address(&self.peer_identifier)
+    }
+}
+
+#[cfg(feature = "proto-ipv6")]
+impl Options for Ipv6cp {
+    fn reset(&mut self) {
+        // The identifier is kept, to keep the same address on every negotiation.
+        self.request_identifier = true;
+        self.peer_identifier = [0; 8];
+    }
+
+    fn request(&self, buffer: &mut [u8]) -> usize {
+        match self.request_identifier {
+            true => push_option(buffer, 0, INTERFACE_IDENTIFIER, &self.identifier),
+            false => 0,
+        }
+    }
+
+    fn check(&self, kind: u8, value: &[u8], nak: &mut [u8]) -> Verdict {
+        match (kind, value.len()) {
+            (INTERFACE_IDENTIFIER, 8) if value == [0; 8] || value == self.identifier => {
+                // Suggest an identifier different from this one.
+                let mut suggestion = self.identifier;
+                suggestion[7] ^= 0xff;
+                if suggestion == [0; 8] {
+                    suggestion[7] = 1;
+                }
+                nak[..8].copy_from_slice(&suggestion);
+                Verdict::Nak(8)
+            }
+            (INTERFACE_IDENTIFIER, 8) => Verdict::Ack,
+            _ => Verdict::Reject,
+        }
+    }
+
+    fn apply(&mut self, options: &[u8]) {
+        for (kind, value) in parse_options(options) {
+            if kind == INTERFACE_IDENTIFIER {
+                self.peer_identifier.copy_from_slice(value);
+            }
+        }
+    }
+
+    fn nak(&mut self, kind: u8, value: &[u8]) {
+        if kind == INTERFACE_IDENTIFIER && value.len() == 8 && value != [0; 8] {
+            self.identifier.copy_from_slice(value);
+        }
+    }
+
+    fn reject(&mut self, kind: u8) {
+        if kind == INTERFACE_IDENTIFIER {
+            self.request_identifier = false;
+        }
+    }
+}
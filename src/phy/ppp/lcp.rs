@@ -0,0 +1,155 @@
+// The configuration options of LCP, as described in RFC 1661, and of the authentication
+// protocols it negotiates, PAP (RFC 1334) and CHAP with MD5 (RFC 1994).
+
+use super::fsm::{parse_options, push_option, Options, Verdict};
+use super::hdlc::DEFAULT_ACCM;
+use super::{PROTOCOL_CHAP, PROTOCOL_PAP};
+
+const MAXIMUM_RECEIVE_UNIT: u8 = 1;
+const ASYNC_CONTROL_CHARACTER_MAP: u8 = 2;
+const AUTHENTICATION_PROTOCOL: u8 = 3;
+const MAGIC_NUMBER: u8 = 5;
+const PROTOCOL_FIELD_COMPRESSION: u8 = 7;
+const ADDRESS_AND_CONTROL_FIELD_COMPRESSION: u8 = 8;
+
+/// The algorithm of CHAP with MD5.
+const CHAP_MD5: u8 = 5;
+
+/// The MRU used unless another one is negotiated.
+pub(super) const DEFAULT_MRU: u16 = 1500;
+
+/// An authentication protocol requested by the peer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub(super) enum Authentication {
+    Pap,
+    Chap,
+}
+
+#[derive(Debug)]
+pub(super) struct Lcp {
+    /// The largest frame that can be received, requested if lower than the default.
+    mru: u16,
+    request_mru: bool,
+    /// The control characters to be escaped by the peer.
+    accm: u32,
+    request_accm: bool,
+    /// Whether the peer can be authenticated with, if it requests it.
+    pub(super) can_authenticate: bool,
+
+    pub(super) peer_mru: u16,
+    pub(super) peer_accm: u32,
+    pub(super) authentication: Option<Authentication>,
+}
+
+impl Lcp {
+    pub(super) const fn new(mru: u16) -> Lcp {
+        Lcp {
+            mru,
+            request_mru: mru < DEFAULT_MRU,
+            accm: 0,
+            request_accm: true,
+            can_authenticate: false,
+            peer_mru: DEFAULT_MRU,
+            peer_accm: DEFAULT_ACCM,
+            authentication: None,
+        }
+    }
+
+    /// Return the control characters discarded when received, once LCP is opened.
+    pub(super) fn rx_accm(&self) -> u32 {
+        match self.request_accm {
+            true => self.accm,
+            false => DEFAULT_ACCM,
+        }
+    }
+}
+
+impl Options for Lcp {
+    fn reset(&mut self) {
+        *self = Lcp {
+            can_authenticate: self.can_authenticate,
+            ..Lcp::new(self.mru)
+        };
+    }
+
+    fn request(&self, buffer: &mut [u8]) -> usize {
+        let mut len = 0;
+        if self.request_mru {
+            len = push_option(buffer, len, MAXIMUM_RECEIVE_UNIT, &self.mru.to_be_bytes());
+        }
+        if self.request_accm {
+            len = push_option(
+                buffer,
+                len,
+                ASYNC_CONTROL_CHARACTER_MAP,
+                &self.accm.to_be_bytes(),
+            );
+        }
+        len
+    }
+
+    fn check(&self, kind: u8, value: &[u8], nak: &mut [u8]) -> Verdict {
+        match (kind, value.len()) {
+            (MAXIMUM_RECEIVE_UNIT, 2) | (ASYNC_CONTROL_CHARACTER_MAP, 4) | (MAGIC_NUMBER, 4) => {
+                Verdict::Ack
+            }
+            // The fields of the frames sent are never compressed.
+            (PROTOCOL_FIELD_COMPRESSION, 0) | (ADDRESS_AND_CONTROL_FIELD_COMPRESSION, 0) => {
+                Verdict::Ack
+            }
+            (AUTHENTICATION_PROTOCOL, _) if self.can_authenticate => {
+                let pap = PROTOCOL_PAP.to_be_bytes();
+                let [high, low] = PROTOCOL_CHAP.to_be_bytes();
+                let chap_md5 = [high, low, CHAP_MD5];
+                if value == pap || value == chap_md5 {
+                    Verdict::Ack
+                } else {
+                    nak[..3].copy_from_slice(&chap_md5);
+                    Verdict::Nak(3)
+                }
+            }
+            _ => Verdict::Reject,
+        }
+    }
+
+    fn apply(&mut self, options: &[u8]) {
+        self.peer_mru = DEFAULT_MRU;
+        self.peer_accm = DEFAULT_ACCM;
+        self.authentication = None;
+        for (kind, value) in parse_options(options) {
+            match kind {
+                MAXIMUM_RECEIVE_UNIT => self.peer_mru = u16::from_be_bytes([value[0], value[1]]),
+                ASYNC_CONTROL_CHARACTER_MAP => {
+                    self.peer_accm = u32::from_be_bytes([value[0], value[1], value[2], value[3]])
+                }
+                AUTHENTICATION_PROTOCOL => {
+                    self.authentication = match u16::from_be_bytes([value[0], value[1]]) {
+                        PROTOCOL_PAP => Some(Authentication::Pap),
+                        _ => Some(Authentication::Chap),
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn nak(&mut self, kind: u8, value: &[u8]) {
+        match (kind, value.len()) {
+            // A larger MRU could not be received, so receive the default one instead.
+            (MAXIMUM_RECEIVE_UNIT, 2) => self.request_mru = false,
+            (ASYNC_CONTROL_CHARACTER_MAP, 4) => {
+                self.accm |= u32::from_be_bytes([value[0], value[1], value[2], value[3]])
+            }
+            _ => (),
+        }
+    }
+
+    fn reject(&mut self, kind: u8) {
+        match kind {
+            MAXIMUM_RECEIVE_UNIT => self.request_mru = false,
+            ASYNC_CONTROL_CHARACTER_MAP => self.request_accm = false,
+            _ => (),
+        }
+    }
+}
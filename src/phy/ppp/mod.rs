@@ -0,0 +1,1395 @@
+use heapless::{Deque, Vec};
+use managed::ManagedSlice;
+
+use crate::phy::{self, Device, DeviceCapabilities, Medium};
+use crate::time::{Duration, Instant};
+#[cfg(feature = "proto-ipv4")]
+use crate::wire::Ipv4Address;
+#[cfg(feature = "proto-ipv6")]
+use crate::wire::Ipv6Address;
+
+mod auth;
+mod fsm;
+mod hdlc;
+mod ipcp;
+mod lcp;
+
+use self::fsm::{Fsm, Output, Transition, HEADER_LEN, MAX_PACKET_LEN};
+use self::hdlc::{Decoder, Encoder, DEFAULT_ACCM};
+#[cfg(feature = "proto-ipv4")]
+use self::ipcp::Ipcp;
+#[cfg(feature = "proto-ipv6")]
+use self::ipcp::Ipv6cp;
+use self::lcp::{Authentication, Lcp, DEFAULT_MRU};
+
+const PROTOCOL_IPV4: u16 = 0x0021;
+const PROTOCOL_IPV6: u16 = 0x0057;
+#[cfg(feature = "proto-ipv4")]
+const PROTOCOL_IPCP: u16 = 0x8021;
+#[cfg(feature = "proto-ipv6")]
+const PROTOCOL_IPV6CP: u16 = 0x8057;
+const PROTOCOL_LCP: u16 = 0xc021;
+const PROTOCOL_PAP: u16 = 0xc023;
+const PROTOCOL_CHAP: u16 = 0xc223;
+
+// The codes of LCP besides the ones of the automaton.
+const PROTOCOL_REJECT: u8 = 8;
+const ECHO_REQUEST: u8 = 9;
+const ECHO_REPLY: u8 = 10;
+const DISCARD_REQUEST: u8 = 11;
+
+const AUTHENTICATE_INTERVAL: Duration = Duration::from_secs(3);
+const MAX_AUTHENTICATE: u8 = 10;
+
+/// Maximum length of the name and of the secret used to authenticate, in octets.
+pub const PPP_MAX_CREDENTIAL_LEN: usize = 64;
+
+/// A serial link, such as the UART of a cellular modem in data mode, over which
+/// a [PppDevice] runs.
+pub trait SerialPort {
+    /// Read the octets received into `buffer`, without blocking, and return their count.
+    fn read(&mut self, buffer: &mut [u8]) -> usize;
+
+    /// Write the octets of `buffer` that can be sent without blocking, and return
+    /// their count.
+    fn write(&mut self, buffer: &[u8]) -> usize;
+}
+
+/// The IPv4 configuration negotiated with IPCP.
+#[cfg(feature = "proto-ipv4")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PppIpv4Config {
+    /// The address assigned to this end of the link.
+    pub address: Ipv4Address,
+    /// The address of the peer, if it gave one.
+    pub peer_address: Option<Ipv4Address>,
+    /// The DNS servers given by the peer.
+    pub dns_servers: Vec<Ipv4Address, 2>,
+}
+
+/// The IPv6 configuration negotiated with IPV6CP.
+#[cfg(feature = "proto-ipv6")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PppIpv6Config {
+    /// The link-local address of this end of the link, from its interface identifier.
+    pub address: Ipv6Address,
+    /// The link-local address of the peer.
+    pub peer_address: Ipv6Address,
+}
+
+/// Return value for the `PppDevice::poll_event` function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PppEvent {
+    /// IPCP is opened, and IPv4 packets can be exchanged with the given configuration.
+    #[cfg(feature = "proto-ipv4")]
+    Ipv4Up(PppIpv4Config),
+    /// IPCP is closed, or negotiated again, and its configuration is lost.
+    #[cfg(feature = "proto-ipv4")]
+    Ipv4Down,
+    /// IPV6CP is opened, and IPv6 packets can be exchanged with the given configuration.
+    #[cfg(feature = "proto-ipv6")]
+    Ipv6Up(PppIpv6Config),
+    /// IPV6CP is closed, or negotiated again, and its configuration is lost.
+    #[cfg(feature = "proto-ipv6")]
+    Ipv6Down,
+    /// The link was terminated by either end, or could not be established. It stays
+    /// down until [PppDevice::open] is called.
+    Terminated,
+}
+
+/// The phase of the link, as described in RFC 1661.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Phase {
+    Dead,
+    Establish,
+    Authenticate,
+    Network,
+}
+
+/// The end of the serial link that sends frames.
+#[derive(Debug)]
+struct Lower<'a, S: SerialPort> {
+    serial: S,
+    /// The encoded frames, between `start` and `end`, waiting to be written.
+    buffer: ManagedSlice<'a, u8>,
+    start: usize,
+    end: usize,
+    /// The control characters escaped for the peer, except in LCP frames.
+    accm: u32,
+}
+
+impl<'a, S: SerialPort> Lower<'a, S> {
+    /// Make room for `len` octets after the frames waiting to be written, if possible.
+    fn reserve(&mut self, len: usize) -> bool {
+        if self.buffer.len() - self.end < len && self.start > 0 {
+            self.buffer.copy_within(self.start..self.end, 0);
+            self.end -= self.start;
+            self.start = 0;
+        }
+        self.buffer.len() - self.end >= len
+    }
+
+    fn flush(&mut self) {
+        while self.start < self.end {
+            match self.serial.write(&self.buffer[self.start..self.end]) {
+                0 => break,
+                len => self.start += len,
+            }
+        }
+        if self.start == self.end {
+            self.start = 0;
+            self.end = 0;
+        }
+    }
+
+    /// Send the packet written at the end of the buffer, from `packet_start`.
+    ///
+    /// The packet is encoded in place: the reserved room, for the encoding of the
+    /// longest packet, ensures that each octet is read before being overwritten.
+    fn send_in_place(&mut self, protocol: u16, packet_start: usize) {
+        let mut encoder = Encoder::new(&mut self.buffer, self.end, self.accm, protocol);
+        for i in packet_start..self.buffer.len() {
+            let byte = self.buffer[i];
+            encoder.push(&mut self.buffer, byte);
+        }
+        self.end = encoder.finish(&mut self.buffer);
+        self.flush();
+    }
+}
+
+impl<'a, S: SerialPort> Output for Lower<'a, S> {
+    fn send(&mut self, protocol: u16, packet: &[u8]) {
+        net_trace!("ppp: sending protocol {:04x} packet {:?}", protocol, packet);
+        if !self.reserve(hdlc::max_encoded_len(packet.len())) {
+            net_debug!("ppp: tx buffer full, dropping control packet");
+            return;
+        }
+        // LCP frames are always sent with the default map, in case the link is renegotiated.
+        let accm = match protocol {
+            PROTOCOL_LCP => DEFAULT_ACCM,
+            _ => self.accm,
+        };
+        let mut encoder = Encoder::new(&mut self.buffer, self.end, accm, protocol);
+        for byte in packet {
+            encoder.push(&mut self.buffer, *byte);
+        }
+        self.end = encoder.finish(&mut self.buffer);
+        self.flush();
+    }
+}
+
+/// The end of the serial link that receives frames.
+#[derive(Debug)]
+struct Upper<'a> {
+    /// The frame being received, and then the last frame received.
+    buffer: ManagedSlice<'a, u8>,
+    decoder: Decoder,
+    /// The octets read from the serial link, and not decoded yet.
+    input: [u8; 64],
+    input_start: usize,
+    input_end: usize,
+}
+
+impl<'a> Upper<'a> {
+    /// Read and decode octets until a frame is received, and return its length.
+    fn receive<S: SerialPort>(&mut self, serial: &mut S, accm: u32) -> Option<usize> {
+        loop {
+            if self.input_start == self.input_end {
+                self.input_start = 0;
+                self.input_end = serial.read(&mut self.input);
+                if self.input_end == 0 {
+                    return None;
+                }
+            }
+            let byte = self.input[self.input_start];
+            self.input_start += 1;
+            if let Some(len) = self.decoder.push(byte, accm, &mut self.buffer) {
+                return Some(len);
+            }
+        }
+    }
+}
+
+/// The control protocols of the link.
+#[derive(Debug)]
+struct Link {
+    phase: Phase,
+    /// Whether the link is to be opened, or closed, at the next poll.
+    open_request: Option<bool>,
+    lcp: Fsm,
+    lcp_options: Lcp,
+    credentials: Option<(
+        Vec<u8, PPP_MAX_CREDENTIAL_LEN>,
+        Vec<u8, PPP_MAX_CREDENTIAL_LEN>,
+    )>,
+    /// Identifier, retransmissions left and retransmission time of the last
+    /// PAP Authenticate-Request.
+    pap_id: u8,
+    pap_restarts: u8,
+    pap_deadline: Option<Instant>,
+    #[cfg(feature = "proto-ipv4")]
+    ipcp: Fsm,
+    #[cfg(feature = "proto-ipv4")]
+    ipcp_options: Ipcp,
+    #[cfg(feature = "proto-ipv6")]
+    ipv6cp: Fsm,
+    #[cfg(feature = "proto-ipv6")]
+    ipv6cp_options: Ipv6cp,
+    events: Deque<PppEvent, 4>,
+}
+
+impl Link {
+    fn push_event(&mut self, event: PppEvent) {
+        if self.events.push_back(event).is_err() {
+            net_debug!("ppp: event queue full, dropping event");
+        }
+    }
+
+    /// Return the control characters discarded when received.
+    fn rx_accm(&self) -> u32 {
+        match self.lcp.is_opened() {
+            true => self.lcp_options.rx_accm(),
+            false => DEFAULT_ACCM,
+        }
+    }
+
+    /// Whether the packets of a network protocol can be exchanged.
+    fn is_up(&self, protocol: u16) -> bool {
+        match protocol {
+            #[cfg(feature = "proto-ipv4")]
+            PROTOCOL_IPV4 => self.ipcp.is_opened(),
+            #[cfg(feature = "proto-ipv6")]
+            PROTOCOL_IPV6 => self.ipv6cp.is_opened(),
+            _ => false,
+        }
+    }
+
+    fn is_any_up(&self) -> bool {
+        self.is_up(PROTOCOL_IPV4) || self.is_up(PROTOCOL_IPV6)
+    }
+
+    fn poll_at(&self) -> Option<Instant> {
+        let deadlines = [
+            self.lcp.poll_at(),
+            self.pap_deadline,
+            #[cfg(feature = "proto-ipv4")]
+            self.ipcp.poll_at(),
+            #[cfg(feature = "proto-ipv6")]
+            self.ipv6cp.poll_at(),
+        ];
+        deadlines.into_iter().flatten().min()
+    }
+
+    fn poll<S: SerialPort>(&mut self, now: Instant, lower: &mut Lower<S>) {
+        match self.open_request.take() {
+            Some(true) if self.phase == Phase::Dead => {
+                self.phase = Phase::Establish;
+                self.lcp.open(&mut self.lcp_options, now, lower);
+            }
+            Some(false) => {
+                let transition = self.lcp.close(now, lower);
+                self.lcp_transition(transition, now, lower);
+            }
+            _ => (),
+        }
+
+        let transition = self.lcp.poll(&mut self.lcp_options, now, lower);
+        self.lcp_transition(transition, now, lower);
+        if matches!(self.pap_deadline, Some(deadline) if deadline <= now) {
+            match self.pap_restarts {
+                0 => self.authentication_failed(now, lower),
+                _ => self.send_pap_request(now, lower),
+            }
+        }
+        #[cfg(feature = "proto-ipv4")]
+        {
+            let transition = self.ipcp.poll(&mut self.ipcp_options, now, lower);
+            self.ipcp_transition(transition, now, lower);
+        }
+        #[cfg(feature = "proto-ipv6")]
+        {
+            let transition = self.ipv6cp.poll(&mut self.ipv6cp_options, now, lower);
+            self.ipv6cp_transition(transition, now, lower);
+        }
+        lower.flush();
+    }
+
+    /// Process a received control packet.
+    fn input<S: SerialPort>(
+        &mut self,
+        protocol: u16,
+        packet: &[u8],
+        now: Instant,
+        lower: &mut Lower<S>,
+    ) {
+        net_trace!(
+            "ppp: received protocol {:04x} packet {:?}",
+            protocol,
+            packet
+        );
+        match (protocol, self.phase) {
+            (PROTOCOL_LCP, _) => self.input_lcp(packet, now, lower),
+            (PROTOCOL_PAP, Phase::Authenticate) => self.input_pap(packet, now, lower),
+            (PROTOCOL_CHAP, Phase::Authenticate | Phase::Network) => {
+                self.input_chap(packet, now, lower)
+            }
+            #[cfg(feature = "proto-ipv4")]
+            (PROTOCOL_IPCP, Phase::Network) => {
+                let transition = self.ipcp.input(&mut self.ipcp_options, packet, now, lower);
+                self.ipcp_transition(transition, now, lower);
+            }
+            #[cfg(feature = "proto-ipv6")]
+            (PROTOCOL_IPV6CP, Phase::Network) => {
+                let transition = self
+                    .ipv6cp
+                    .input(&mut self.ipv6cp_options, packet, now, lower);
+                self.ipv6cp_transition(transition, now, lower);
+            }
+            (PROTOCOL_IPV4 | PROTOCOL_IPV6, _) => {
+                net_debug!("ppp: dropping packet of a network protocol that is down")
+            }
+            (_, Phase::Authenticate | Phase::Network) => {
+                // Reject the protocol, echoing the start of the packet.
+                let mut buffer = [0; MAX_PACKET_LEN];
+                buffer[HEADER_LEN..][..2].copy_from_slice(&protocol.to_be_bytes());
+                let len = packet.len().min(MAX_PACKET_LEN - HEADER_LEN - 2);
+                buffer[HEADER_LEN + 2..][..len].copy_from_slice(&packet[..len]);
+                let id = self.lcp.next_reject_id();
+                lower.send(
+                    PROTOCOL_LCP,
+                    fsm::emit(&mut buffer, PROTOCOL_REJECT, id, 2 + len),
+                );
+            }
+            _ => net_debug!("ppp: dropping protocol {:04x} packet", protocol),
+        }
+    }
+
+    fn input_lcp<S: SerialPort>(&mut self, packet: &[u8], now: Instant, lower: &mut Lower<S>) {
+        match fsm::parse(packet) {
+            Some((PROTOCOL_REJECT, _, [high, low, ..])) if self.lcp.is_opened() => {
+                match u16::from_be_bytes([*high, *low]) {
+                    #[cfg(feature = "proto-ipv4")]
+                    PROTOCOL_IPCP => {
+                        let transition = self.ipcp.down();
+                        self.ipcp_transition(transition, now, lower);
+                        self.ncp_finished(now, lower);
+                    }
+                    #[cfg(feature = "proto-ipv6")]
+                    PROTOCOL_IPV6CP => {
+                        let transition = self.ipv6cp.down();
+                        self.ipv6cp_transition(transition, now, lower);
+                        self.ncp_finished(now, lower);
+                    }
+                    protocol => net_debug!("ppp: protocol {:04x} rejected", protocol),
+                }
+            }
+            Some((ECHO_REQUEST, id, _)) if self.lcp.is_opened() => {
+                // The magic number is zero, as none is negotiated.
+                let mut buffer = [0; HEADER_LEN + 4];
+                lower.send(PROTOCOL_LCP, fsm::emit(&mut buffer, ECHO_REPLY, id, 4));
+            }
+            Some((PROTOCOL_REJECT..=DISCARD_REQUEST, _, _)) => (),
+            _ => {
+                let transition = self.lcp.input(&mut self.lcp_options, packet, now, lower);
+                self.lcp_transition(transition, now, lower);
+            }
+        }
+    }
+
+    fn lcp_transition<S: SerialPort>(
+        &mut self,
+        transition: Option<Transition>,
+        now: Instant,
+        lower: &mut Lower<S>,
+    ) {
+        match transition {
+            Some(Transition::Up) => {
+                net_debug!("ppp: link established");
+                lower.accm = self.lcp_options.peer_accm;
+                match self.lcp_options.authentication {
+                    Some(Authentication::Pap) => {
+                        self.phase = Phase::Authenticate;
+                        self.pap_restarts = MAX_AUTHENTICATE;
+                        self.send_pap_request(now, lower);
+                    }
+                    // The peer sends a challenge.
+                    Some(Authentication::Chap) => self.phase = Phase::Authenticate,
+                    None => self.start_network(now, lower),
+                }
+            }
+            Some(Transition::Down) => {
+                net_debug!("ppp: link down");
+                self.phase = Phase::Establish;
+                lower.accm = DEFAULT_ACCM;
+                self.pap_deadline = None;
+                #[cfg(feature = "proto-ipv4")]
+                {
+                    let transition = self.ipcp.down();
+                    self.ipcp_transition(transition, now, lower);
+                }
+                #[cfg(feature = "proto-ipv6")]
+                {
+                    let transition = self.ipv6cp.down();
+                    self.ipv6cp_transition(transition, now, lower);
+                }
+            }
+            Some(Transition::Finished) => {
+                net_debug!("ppp: link terminated");
+                self.phase = Phase::Dead;
+                self.push_event(PppEvent::Terminated);
+            }
+            None => (),
+        }
+    }
+
+    fn start_network<S: SerialPort>(&mut self, now: Instant, lower: &mut Lower<S>) {
+        self.phase = Phase::Network;
+        #[cfg(feature = "proto-ipv4")]
+        self.ipcp.open(&mut self.ipcp_options, now, lower);
+        #[cfg(feature = "proto-ipv6")]
+        self.ipv6cp.open(&mut self.ipv6cp_options, now, lower);
+    }
+
+    /// Close the link if no network protocol can be negotiated anymore.
+    fn ncp_finished<S: SerialPort>(&mut self, now: Instant, lower: &mut Lower<S>) {
+        let finished = |fsm: &Fsm| {
+            matches!(
+                fsm.state(),
+                fsm::State::Initial | fsm::State::Closed | fsm::State::Stopped
+            )
+        };
+        #[cfg(feature = "proto-ipv4")]
+        if !finished(&self.ipcp) {
+            return;
+        }
+        #[cfg(feature = "proto-ipv6")]
+        if !finished(&self.ipv6cp) {
+            return;
+        }
+        net_debug!("ppp: no network protocol negotiated");
+        let transition = self.lcp.close(now, lower);
+        self.lcp_transition(transition, now, lower);
+    }
+
+    #[cfg(feature = "proto-ipv4")]
+    fn ipcp_transition<S: SerialPort>(
+        &mut self,
+        transition: Option<Transition>,
+        now: Instant,
+        lower: &mut Lower<S>,
+    ) {
+        match transition {
+            Some(Transition::Up) => {
+                let options = &self.ipcp_options;
+                let config = PppIpv4Config {
+                    address: options.address,
+                    peer_address: options.peer_address,
+                    dns_servers: options
+                        .dns_servers
+                        .iter()
+                        .filter(|address| !address.is_unspecified())
+                        .copied()
+                        .collect(),
+                };
+                net_debug!("ppp: IPv4 up with {:?}", config);
+                self.push_event(PppEvent::Ipv4Up(config));
+            }
+            Some(Transition::Down) => self.push_event(PppEvent::Ipv4Down),
+            Some(Transition::Finished) => self.ncp_finished(now, lower),
+            None => (),
+        }
+    }
+
+    #[cfg(feature = "proto-ipv6")]
+    fn ipv6cp_transition<S: SerialPort>(
+        &mut self,
+        transition: Option<Transition>,
+        now: Instant,
+        lower: &mut Lower<S>,
+    ) {
+        match transition {
+            Some(Transition::Up) => {
+                let config = PppIpv6Config {
+                    address: self.ipv6cp_options.address(),
+                    peer_address: self.ipv6cp_options.peer_address(),
+                };
+                net_debug!("ppp: IPv6 up with {:?}", config);
+                self.push_event(PppEvent::Ipv6Up(config));
+            }
+            Some(Transition::Down) => self.push_event(PppEvent::Ipv6Down),
+            Some(Transition::Finished) => self.ncp_finished(now, lower),
+            None => (),
+        }
+    }
+
+    fn send_pap_request<S: SerialPort>(&mut self, now: Instant, lower: &mut Lower<S>) {
+        let Some((name, secret)) = &self.credentials else {
+            return;
+        };
+        let mut buffer = [0; MAX_PACKET_LEN];
+        let len = auth::pap_request(&mut buffer, name, secret);
+        self.pap_id = self.pap_id.wrapping_add(1);
+        lower.send(
+            PROTOCOL_PAP,
+            fsm::emit(
+                &mut buffer,
+                auth::PAP_AUTHENTICATE_REQUEST,
+                self.pap_id,
+                len,
+            ),
+        );
+        self.pap_restarts -= 1;
+        self.pap_deadline = Some(now + AUTHENTICATE_INTERVAL);
+    }
+
+    fn input_pap<S: SerialPort>(&mut self, packet: &[u8], now: Instant, lower: &mut Lower<S>) {
+        match fsm::parse(packet) {
+            Some((auth::PAP_AUTHENTICATE_ACK, id, _)) if id == self.pap_id => {
+                net_debug!("ppp: authenticated");
+                self.pap_deadline = None;
+                self.start_network(now, lower);
+            }
+            Some((auth::PAP_AUTHENTICATE_NAK, id, _)) if id == self.pap_id => {
+                self.authentication_failed(now, lower)
+            }
+            _ => net_debug!("ppp: dropping unexpected PAP packet"),
+        }
+    }
+
+    fn input_chap<S: SerialPort>(&mut self, packet: &[u8], now: Instant, lower: &mut Lower<S>) {
+        match fsm::parse(packet) {
+            // The peer may challenge again at any time.
+            Some((auth::CHAP_CHALLENGE, id, challenge)) => {
+                let Some((name, secret)) = &self.credentials else {
+                    return;
+                };
+                let mut buffer = [0; MAX_PACKET_LEN];
+                let Some(len) = auth::chap_response(&mut buffer, id, challenge, name, secret)
+                else {
+                    net_debug!("ppp: dropping invalid CHAP challenge");
+                    return;
+                };
+                lower.send(
+                    PROTOCOL_CHAP,
+                    fsm::emit(&mut buffer, auth::CHAP_RESPONSE, id, len),
+                );
+            }
+            Some((auth::CHAP_SUCCESS, _, _)) if self.phase == Phase::Authenticate => {
+                net_debug!("ppp: authenticated");
+                self.start_network(now, lower);
+            }
+            Some((auth::CHAP_FAILURE, _, _)) => self.authentication_failed(now, lower),
+            _ => net_debug!("ppp: dropping unexpected CHAP packet"),
+        }
+    }
+
+    fn authentication_failed<S: SerialPort>(&mut self, now: Instant, lower: &mut Lower<S>) {
+        net_debug!("ppp: authentication failed");
+        self.pap_deadline = None;
+        let transition = self.lcp.close(now, lower);
+        self.lcp_transition(transition, now, lower);
+    }
+}
+
+/// A PPP link over a serial line, such as the one of a cellular modem in data mode.
+///
+/// The device frames the packets as described in RFC 1662, and negotiates the link with
+/// LCP, authenticating to the peer with PAP or CHAP if it requires it and
+/// [credentials](#method.set_credentials) are set. It then negotiates an IPv4 address and
+/// DNS servers with IPCP, and an IPv6 link-local address with IPV6CP, depending on the
+/// features enabled. Only the packets of the network protocols opened are received and
+/// sent by the interface.
+///
+/// The configurations negotiated are reported by [poll_event], and are to be applied to
+/// the interface, with the medium of the device being IP. The device drives the
+/// negotiation whenever it is polled by the interface; the instant of its next
+/// retransmission is returned by [poll_at].
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let mut device = PppDevice::new(uart, vec![0; 1506], vec![0; 4096]);
+/// device.set_credentials(Some((b"user", b"pass")));
+/// let config = Config::new(HardwareAddress::Ip);
+/// let mut iface = Interface::new(config, &mut device, Instant::now());
+/// loop {
+///     iface.poll(Instant::now(), &mut device, &mut sockets);
+///     while let Some(event) = device.poll_event() {
+///         match event {
+///             PppEvent::Ipv4Up(config) => {
+///                 iface.update_ip_addrs(|addrs| {
+///                     addrs.clear();
+///                     addrs.push(IpCidr::new(config.address.into(), 32)).unwrap();
+///                 });
+///                 // Any address is a gateway over a point-to-point link.
+///                 let gateway = config.peer_address.unwrap_or(config.address);
+///                 iface.routes_mut().add_default_ipv4_route(gateway).unwrap();
+///             }
+///             PppEvent::Terminated => device.open(),
+///             _ => {}
+///         }
+///     }
+/// }
+/// ```
+///
+/// [poll_event]: #method.poll_event
+/// [poll_at]: #method.poll_at
+#[derive(Debug)]
+pub struct PppDevice<'a, S: SerialPort> {
+    lower: Lower<'a, S>,
+    upper: Upper<'a>,
+    link: Link,
+}
+
+impl<'a, S: SerialPort> PppDevice<'a, S> {
+    /// Create a PPP link over the given serial line, which is opened when the device is
+    /// first polled.
+    ///
+    /// The receive buffer holds a frame being received; a MRU lower than the default,
+    /// 1500 octets, is requested from the peer if it cannot hold such a frame with its
+    /// 6 octets of framing. The transmit buffer holds the frames waiting to be written
+    /// to the serial line; as their encoding takes up to twice their size, the MTU of
+    /// the device is lowered if it cannot hold twice a frame of 1500 octets.
+    ///
+    /// # Panics
+    /// This function panics if the transmit buffer cannot hold the longest control
+    /// packet, of 256 octets.
+    pub fn new<B>(serial: S, rx_buffer: B, tx_buffer: B) -> PppDevice<'a, S>
+    where
+        B: Into<ManagedSlice<'a, u8>>,
+    {
+        let rx_buffer = rx_buffer.into();
+        let tx_buffer = tx_buffer.into();
+        assert!(tx_buffer.len() >= hdlc::max_encoded_len(MAX_PACKET_LEN));
+        let mru = rx_buffer.len().saturating_sub(hdlc::FRAME_OVERHEAD);
+        PppDevice {
+            lower: Lower {
+                serial,
+                buffer: tx_buffer,
+                start: 0,
+                end: 0,
+                accm: DEFAULT_ACCM,
+            },
+            upper: Upper {
+                buffer: rx_buffer,
+                decoder: Decoder::default(),
+                input: [0; 64],
+                input_start: 0,
+                input_end: 0,
+            },
+            link: Link {
+                phase: Phase::Dead,
+                open_request: Some(true),
+                lcp: Fsm::new(PROTOCOL_LCP),
+                lcp_options: Lcp::new(mru.min(DEFAULT_MRU as usize) as u16),
+                credentials: None,
+                pap_id: 0,
+                pap_restarts: 0,
+                pap_deadline: None,
+                #[cfg(feature = "proto-ipv4")]
+                ipcp: Fsm::new(PROTOCOL_IPCP),
+                #[cfg(feature = "proto-ipv4")]
+                ipcp_options: Ipcp::new(),
+                #[cfg(feature = "proto-ipv6")]
+                ipv6cp: Fsm::new(PROTOCOL_IPV6CP),
+                #[cfg(feature = "proto-ipv6")]
+                ipv6cp_options: Ipv6cp::new(),
+                events: Deque::new(),
+            },
+        }
+    }
+
+    /// Get a reference to the serial line.
+    pub fn serial(&self) -> &S {
+        &self.lower.serial
+    }
+
+    /// Get a mutable reference to the serial line.
+    ///
+    /// It is inadvisable to directly read from or write to the serial line as doing so
+    /// will corrupt the frames of the link.
+    pub fn serial_mut(&mut self) -> &mut S {
+        &mut self.lower.serial
+    }
+
+    /// Set the name and the secret used to authenticate to the peer.
+    ///
+    /// Without credentials, the link is only established with a peer that does not
+    /// require authentication. The credentials must be set before the link is opened.
+    ///
+    /// # Panics
+    /// This function panics if the name or the secret is longer than 64 octets.
+    pub fn set_credentials(&mut self, credentials: Option<(&[u8], &[u8])>) {
+        self.link.credentials = credentials.map(|(name, secret)| {
+            (
+                Vec::from_slice(name).expect("PPP name too long"),
+                Vec::from_slice(secret).expect("PPP secret too long"),
+            )
+        });
+        self.link.lcp_options.can_authenticate = self.link.credentials.is_some();
+    }
+
+    /// Open the link again, after it was terminated or closed.
+    ///
+    /// This takes effect at the next poll.
+    pub fn open(&mut self) {
+        self.link.open_request = Some(true);
+    }
+
+    /// Terminate the link.
+    ///
+    /// This takes effect at the next poll, after which [PppEvent::Terminated] is
+    /// returned once the peer acknowledges it.
+    pub fn close(&mut self) {
+        self.link.open_request = Some(false);
+    }
+
+    /// Query the device for changes of the link.
+    ///
+    /// The events are returned in the order they happened.
+    pub fn poll_event(&mut self) -> Option<PppEvent> {
+        self.link.events.pop_front()
+    }
+
+    /// Return the instant at which the device should be polled again, to retransmit
+    /// a control packet, if any.
+    pub fn poll_at(&self) -> Option<Instant> {
+        self.link.poll_at()
+    }
+
+    fn mtu(&self) -> usize {
+        let mru = match self.link.lcp.is_opened() {
+            true => self.link.lcp_options.peer_mru,
+            false => DEFAULT_MRU,
+        };
+        let buffer_mtu = (self.lower.buffer.len() - 2) / 2 - hdlc::FRAME_OVERHEAD;
+        buffer_mtu.min(mru as usize)
+    }
+}
+
+impl<'a, S: SerialPort> Device for PppDevice<'a, S> {
+    type RxToken<'b>
+        = RxToken<'b>
+    where
+        Self: 'b;
+    type TxToken<'b>
+        = TxToken<'b, 'a, S>
+    where
+        Self: 'b;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            max_transmission_unit: self.mtu(),
+            medium: Medium::Ip,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        self.link.poll(timestamp, &mut self.lower);
+
+        // Leave the frames in the serial line until the response to one can be sent.
+        let mtu = self.mtu();
+        while self.lower.reserve(hdlc::max_encoded_len(mtu)) {
+            let len = self
+                .upper
+                .receive(&mut self.lower.serial, self.link.rx_accm())?;
+            let Some((protocol, offset)) = hdlc::parse(&self.upper.buffer[..len]) else {
+                net_debug!("ppp: dropping invalid frame");
+                continue;
+            };
+            if self.link.is_up(protocol) {
+                let rx = RxToken {
+                    buffer: &mut self.upper.buffer[offset..len],
+                };
+                let tx = TxToken {
+                    lower: &mut self.lower,
+                };
+                return Some((rx, tx));
+            }
+            self.link.input(
+                protocol,
+                &self.upper.buffer[offset..len],
+                timestamp,
+                &mut self.lower,
+            );
+        }
+        None
+    }
+
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        self.link.poll(timestamp, &mut self.lower);
+
+        let mtu = self.mtu();
+        if self.link.is_any_up() && self.lower.reserve(hdlc::max_encoded_len(mtu)) {
+            Some(TxToken {
+                lower: &mut self.lower,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken<'a> {
+    buffer: &'a mut [u8],
+}
+
+impl<'a> phy::RxToken for RxToken<'a> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(self.buffer)
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<'b, 'a, S: SerialPort> {
+    lower: &'b mut Lower<'a, S>,
+}
+
+impl<'b, 'a, S: SerialPort> phy::TxToken for TxToken<'b, 'a, S> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // The token is only given with room for the encoding of a packet of the MTU,
+        // and the packet is written at the end of that room.
+        let packet_start = self.lower.buffer.len() - len;
+        let result = f(&mut self.lower.buffer[packet_start..]);
+        match self.lower.buffer.get(packet_start).map(|byte| byte >> 4) {
+            Some(4) => self.lower.send_in_place(PROTOCOL_IPV4, packet_start),
+            Some(6) => self.lower.send_in_place(PROTOCOL_IPV6, packet_start),
+            _ => net_debug!("ppp: dropping packet of unknown IP version"),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+
+    use super::fsm::{
+        CONFIGURE_ACK, CONFIGURE_NAK, CONFIGURE_REJECT, CONFIGURE_REQUEST, TERMINATE_ACK,
+        TERMINATE_REQUEST,
+    };
+    use super::*;
+    use crate::phy::{RxToken as _, TxToken as _};
+
+    type Bytes = std::vec::Vec<u8>;
+
+    static LCP_REQUEST: [u8; 6] = [2, 6, 0, 0, 0, 0];
+
+    #[derive(Debug, Default)]
+    struct Pipe {
+        rx: VecDeque<u8>,
+        tx: Bytes,
+    }
+
+    impl SerialPort for Pipe {
+        fn read(&mut self, buffer: &mut [u8]) -> usize {
+            let len = buffer.len().min(self.rx.len());
+            for (byte, rx) in buffer.iter_mut().zip(self.rx.drain(..len)) {
+                *byte = rx;
+            }
+            len
+        }
+
+        fn write(&mut self, buffer: &[u8]) -> usize {
+            self.tx.extend_from_slice(buffer);
+            buffer.len()
+        }
+    }
+
+    fn new_device() -> PppDevice<'static, Pipe> {
+        PppDevice::new(Pipe::default(), vec![0; 1506], vec![0; 4096])
+    }
+
+    fn control(code: u8, id: u8, data: &[u8]) -> Bytes {
+        let len = (HEADER_LEN + data.len()) as u16;
+        [&[code, id], &len.to_be_bytes()[..], data].concat()
+    }
+
+    /// Send a frame from the peer.
+    fn send(device: &mut PppDevice<Pipe>, protocol: u16, packet: &[u8]) {
+        let mut buffer = vec![0; hdlc::max_encoded_len(packet.len())];
+        let mut encoder = Encoder::new(&mut buffer, 0, DEFAULT_ACCM, protocol);
+        for byte in packet {
+            encoder.push(&mut buffer, *byte);
+        }
+        let len = encoder.finish(&mut buffer);
+        device.serial_mut().rx.extend(&buffer[..len]);
+    }
+
+    /// Return the frames sent to the peer.
+    fn recv(device: &mut PppDevice<Pipe>) -> std::vec::Vec<(u16, Bytes)> {
+        let mut decoder = Decoder::default();
+        let mut buffer = [0; 1506];
+        let mut frames = vec![];
+        for byte in core::mem::take(&mut device.serial_mut().tx) {
+            if let Some(len) = decoder.push(byte, 0, &mut buffer) {
+                let (protocol, offset) = hdlc::parse(&buffer[..len]).unwrap();
+                frames.push((protocol, buffer[offset..len].to_vec()));
+            }
+        }
+        frames
+    }
+
+    fn poll(device: &mut PppDevice<Pipe>, now: Instant) {
+        assert!(device.receive(now).is_none());
+    }
+
+    /// Establish the link with the options requested by the peer, and return the frames
+    /// sent by the device after the Configure-Ack of these options.
+    fn establish(device: &mut PppDevice<Pipe>, options: &[u8]) -> std::vec::Vec<(u16, Bytes)> {
+        let now = Instant::from_secs(0);
+        poll(device, now);
+        assert_eq!(
+            recv(device),
+            vec![(PROTOCOL_LCP, control(CONFIGURE_REQUEST, 1, &LCP_REQUEST))]
+        );
+        send(
+            device,
+            PROTOCOL_LCP,
+            &control(CONFIGURE_ACK, 1, &LCP_REQUEST),
+        );
+        send(
+            device,
+            PROTOCOL_LCP,
+            &control(CONFIGURE_REQUEST, 1, options),
+        );
+        poll(device, now);
+        let mut frames = recv(device);
+        assert_eq!(
+            frames.remove(0),
+            (PROTOCOL_LCP, control(CONFIGURE_ACK, 1, options))
+        );
+        frames
+    }
+
+    /// Return the Configure-Requests of the network control protocols.
+    fn ncp_requests() -> std::vec::Vec<(u16, Bytes)> {
+        vec![
+            #[cfg(feature = "proto-ipv4")]
+            (
+                PROTOCOL_IPCP,
+                control(
+                    CONFIGURE_REQUEST,
+                    1,
+                    &[3, 6, 0, 0, 0, 0, 129, 6, 0, 0, 0, 0, 131, 6, 0, 0, 0, 0],
+                ),
+            ),
+            #[cfg(feature = "proto-ipv6")]
+            (
+                PROTOCOL_IPV6CP,
+                control(CONFIGURE_REQUEST, 1, &[1, 10, 0, 0, 0, 0, 0, 0, 0, 0]),
+            ),
+        ]
+    }
+
+    /// Reject a network control protocol, as a peer that does not support it.
+    #[allow(unused)]
+    fn reject_protocol(device: &mut PppDevice<Pipe>, protocol: u16, request: &[u8]) {
+        let data = [&protocol.to_be_bytes()[..], request].concat();
+        send(device, PROTOCOL_LCP, &control(PROTOCOL_REJECT, 1, &data));
+        poll(device, Instant::from_secs(0));
+    }
+
+    /// Establish the link, and negotiate IPCP only.
+    #[cfg(feature = "proto-ipv4")]
+    fn establish_ipv4(device: &mut PppDevice<Pipe>) {
+        let now = Instant::from_secs(0);
+        assert_eq!(
+            establish(device, &[5, 6, 0x12, 0x34, 0x56, 0x78]),
+            ncp_requests()
+        );
+        #[cfg(feature = "proto-ipv6")]
+        reject_protocol(device, PROTOCOL_IPV6CP, &ncp_requests()[1].1);
+
+        send(
+            device,
+            PROTOCOL_IPCP,
+            &control(
+                CONFIGURE_NAK,
+                1,
+                &[3, 6, 10, 0, 0, 2, 129, 6, 8, 8, 8, 8, 131, 6, 8, 8, 4, 4],
+            ),
+        );
+        poll(device, now);
+        let request = [3, 6, 10, 0, 0, 2, 129, 6, 8, 8, 8, 8, 131, 6, 8, 8, 4, 4];
+        assert_eq!(
+            recv(device),
+            vec![(PROTOCOL_IPCP, control(CONFIGURE_REQUEST, 2, &request))]
+        );
+        send(device, PROTOCOL_IPCP, &control(CONFIGURE_ACK, 2, &request));
+        send(
+            device,
+            PROTOCOL_IPCP,
+            &control(CONFIGURE_REQUEST, 1, &[3, 6, 10, 0, 0, 1]),
+        );
+        poll(device, now);
+        assert_eq!(
+            recv(device),
+            vec![(
+                PROTOCOL_IPCP,
+                control(CONFIGURE_ACK, 1, &[3, 6, 10, 0, 0, 1])
+            )]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_establish_ipv4() {
+        let mut device = new_device();
+        establish_ipv4(&mut device);
+        assert_eq!(
+            device.poll_event(),
+            Some(PppEvent::Ipv4Up(PppIpv4Config {
+                address: Ipv4Address([10, 0, 0, 2]),
+                peer_address: Some(Ipv4Address([10, 0, 0, 1])),
+                dns_servers: Vec::from_slice(&[
+                    Ipv4Address([8, 8, 8, 8]),
+                    Ipv4Address([8, 8, 4, 4])
+                ])
+                .unwrap(),
+            }))
+        );
+        assert_eq!(device.poll_event(), None);
+        assert_eq!(device.poll_at(), None);
+
+        let now = Instant::from_secs(1);
+        send(&mut device, PROTOCOL_IPV4, &[0x45, 0x7e, 0x7d, 0x01]);
+        let (rx, _tx) = device.receive(now).unwrap();
+        assert_eq!(
+            rx.consume(|packet| packet.to_vec()),
+            vec![0x45, 0x7e, 0x7d, 0x01]
+        );
+
+        let tx = device.transmit(now).unwrap();
+        tx.consume(4, |packet| {
+            packet.copy_from_slice(&[0x45, 0x00, 0x11, 0x7e])
+        });
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_IPV4, vec![0x45, 0x00, 0x11, 0x7e])]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv6")]
+    fn test_establish_ipv6() {
+        let mut device = new_device();
+        let now = Instant::from_secs(0);
+        assert_eq!(establish(&mut device, &[]), ncp_requests());
+        #[cfg(feature = "proto-ipv4")]
+        reject_protocol(&mut device, PROTOCOL_IPCP, &ncp_requests()[0].1);
+        assert!(device.transmit(now).is_none());
+
+        // The peer suggests an interface identifier, and requests the same one.
+        let identifier = [1, 10, 0x02, 0, 0, 0, 0, 0, 0, 0x01];
+        send(
+            &mut device,
+            PROTOCOL_IPV6CP,
+            &control(CONFIGURE_NAK, 1, &identifier),
+        );
+        send(
+            &mut device,
+            PROTOCOL_IPV6CP,
+            &control(CONFIGURE_REQUEST, 1, &identifier),
+        );
+        poll(&mut device, now);
+        assert_eq!(
+            recv(&mut device),
+            vec![
+                (PROTOCOL_IPV6CP, control(CONFIGURE_REQUEST, 2, &identifier)),
+                (
+                    PROTOCOL_IPV6CP,
+                    control(CONFIGURE_NAK, 1, &[1, 10, 0x02, 0, 0, 0, 0, 0, 0, 0xfe])
+                ),
+            ]
+        );
+
+        let peer_identifier = [1, 10, 0x02, 0, 0, 0, 0, 0, 0, 0xfe];
+        send(
+            &mut device,
+            PROTOCOL_IPV6CP,
+            &control(CONFIGURE_ACK, 2, &identifier),
+        );
+        send(
+            &mut device,
+            PROTOCOL_IPV6CP,
+            &control(CONFIGURE_REQUEST, 2, &peer_identifier),
+        );
+        poll(&mut device, now);
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_IPV6CP, control(CONFIGURE_ACK, 2, &peer_identifier))]
+        );
+        assert_eq!(
+            device.poll_event(),
+            Some(PppEvent::Ipv6Up(PppIpv6Config {
+                address: Ipv6Address::new(0xfe80, 0, 0, 0, 0x0200, 0, 0, 0x0001),
+                peer_address: Ipv6Address::new(0xfe80, 0, 0, 0, 0x0200, 0, 0, 0x00fe),
+            }))
+        );
+        assert!(device.transmit(now).is_some());
+    }
+
+    #[test]
+    fn test_reject_options() {
+        let mut device = new_device();
+        let now = Instant::from_secs(0);
+        poll(&mut device, now);
+        recv(&mut device);
+
+        // Without credentials, the authentication cannot be done.
+        let options = [1, 4, 0x05, 0xdc, 3, 4, 0xc0, 0x23, 13, 3, 6];
+        send(
+            &mut device,
+            PROTOCOL_LCP,
+            &control(CONFIGURE_REQUEST, 1, &options),
+        );
+        poll(&mut device, now);
+        assert_eq!(
+            recv(&mut device),
+            vec![(
+                PROTOCOL_LCP,
+                control(CONFIGURE_REJECT, 1, &[3, 4, 0xc0, 0x23, 13, 3, 6])
+            )]
+        );
+
+        // With credentials, CHAP with MD5 is suggested instead of EAP.
+        device.set_credentials(Some((b"user", b"secret")));
+        let options = [1, 4, 0x05, 0xdc, 3, 4, 0xc2, 0x27];
+        send(
+            &mut device,
+            PROTOCOL_LCP,
+            &control(CONFIGURE_REQUEST, 2, &options),
+        );
+        poll(&mut device, now);
+        assert_eq!(
+            recv(&mut device),
+            vec![(
+                PROTOCOL_LCP,
+                control(CONFIGURE_NAK, 2, &[3, 5, 0xc2, 0x23, 5])
+            )]
+        );
+
+        // The ACCM rejected by the peer is not requested anymore.
+        send(
+            &mut device,
+            PROTOCOL_LCP,
+            &control(CONFIGURE_REJECT, 1, &LCP_REQUEST),
+        );
+        poll(&mut device, now);
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_LCP, control(CONFIGURE_REQUEST, 2, &[]))]
+        );
+    }
+
+    #[test]
+    fn test_mru() {
+        let mut device = PppDevice::new(Pipe::default(), vec![0; 1006], vec![0; 1500]);
+        poll(&mut device, Instant::from_secs(0));
+        assert_eq!(
+            recv(&mut device),
+            vec![(
+                PROTOCOL_LCP,
+                control(CONFIGURE_REQUEST, 1, &[1, 4, 0x03, 0xe8, 2, 6, 0, 0, 0, 0])
+            )]
+        );
+        assert_eq!(device.capabilities().max_transmission_unit, 743);
+    }
+
+    #[test]
+    fn test_pap() {
+        let mut device = new_device();
+        device.set_credentials(Some((b"user", b"pass")));
+        let frames = establish(&mut device, &[3, 4, 0xc0, 0x23]);
+        let request = (
+            PROTOCOL_PAP,
+            control(auth::PAP_AUTHENTICATE_REQUEST, 1, b"\x04user\x04pass"),
+        );
+        assert_eq!(frames, vec![request.clone()]);
+
+        // The request is sent again until it is acknowledged.
+        poll(&mut device, Instant::from_secs(3));
+        let request = (
+            PROTOCOL_PAP,
+            control(auth::PAP_AUTHENTICATE_REQUEST, 2, b"\x04user\x04pass"),
+        );
+        assert_eq!(recv(&mut device), vec![request]);
+        send(
+            &mut device,
+            PROTOCOL_PAP,
+            &control(auth::PAP_AUTHENTICATE_ACK, 2, &[0]),
+        );
+        poll(&mut device, Instant::from_secs(3));
+        assert_eq!(recv(&mut device), ncp_requests());
+    }
+
+    #[test]
+    fn test_chap() {
+        let mut device = new_device();
+        device.set_credentials(Some((b"user", b"secret")));
+        assert_eq!(establish(&mut device, &[3, 5, 0xc2, 0x23, 5]), vec![]);
+
+        let now = Instant::from_secs(0);
+        let challenge = [
+            16, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f, b'p', b'e', b'e', b'r',
+        ];
+        send(
+            &mut device,
+            PROTOCOL_CHAP,
+            &control(auth::CHAP_CHALLENGE, 1, &challenge),
+        );
+        poll(&mut device, now);
+        let response = [
+            16, 0x74, 0x0e, 0x86, 0x46, 0x3b, 0xda, 0x3a, 0x4d, 0x70, 0x17, 0xd6, 0xe0, 0xfb, 0xa0,
+            0x69, 0x9d, b'u', b's', b'e', b'r',
+        ];
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_CHAP, control(auth::CHAP_RESPONSE, 1, &response))]
+        );
+        send(
+            &mut device,
+            PROTOCOL_CHAP,
+            &control(auth::CHAP_SUCCESS, 1, b""),
+        );
+        poll(&mut device, now);
+        assert_eq!(recv(&mut device), ncp_requests());
+    }
+
+    #[test]
+    fn test_chap_failure() {
+        let mut device = new_device();
+        device.set_credentials(Some((b"user", b"secret")));
+        establish(&mut device, &[3, 5, 0xc2, 0x23, 5]);
+
+        let now = Instant::from_secs(0);
+        send(
+            &mut device,
+            PROTOCOL_CHAP,
+            &control(auth::CHAP_FAILURE, 1, b""),
+        );
+        poll(&mut device, now);
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_LCP, control(TERMINATE_REQUEST, 2, &[]))]
+        );
+        send(&mut device, PROTOCOL_LCP, &control(TERMINATE_ACK, 2, &[]));
+        poll(&mut device, now);
+        assert_eq!(device.poll_event(), Some(PppEvent::Terminated));
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_terminated_by_peer() {
+        let mut device = new_device();
+        establish_ipv4(&mut device);
+        device.poll_event();
+
+        send(
+            &mut device,
+            PROTOCOL_LCP,
+            &control(TERMINATE_REQUEST, 7, &[]),
+        );
+        poll(&mut device, Instant::from_secs(1));
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_LCP, control(TERMINATE_ACK, 7, &[]))]
+        );
+        assert_eq!(device.poll_event(), Some(PppEvent::Ipv4Down));
+        assert!(device.transmit(Instant::from_secs(1)).is_none());
+
+        // The link is finished after a restart interval.
+        assert_eq!(device.poll_at(), Some(Instant::from_secs(4)));
+        poll(&mut device, Instant::from_secs(4));
+        assert_eq!(device.poll_event(), Some(PppEvent::Terminated));
+
+        device.open();
+        poll(&mut device, Instant::from_secs(5));
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_LCP, control(CONFIGURE_REQUEST, 2, &LCP_REQUEST))]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_close() {
+        let mut device = new_device();
+        establish_ipv4(&mut device);
+        device.poll_event();
+
+        device.close();
+        poll(&mut device, Instant::from_secs(1));
+        assert_eq!(
+            recv(&mut device),
+            vec![(PROTOCOL_LCP, control(TERMINATE_REQUEST, 2, &[]))]
+        );
+        assert_eq!(device.poll_event(), Some(PppEvent::Ipv4Down));
+        send(&mut device, PROTOCOL_LCP, &control(TERMINATE_ACK, 2, &[]));
+        poll(&mut device, Instant::from_secs(1));
+        assert_eq!(device.poll_event(), Some(PppEvent::Terminated));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut device = new_device();
+        for i in 0..10 {
+            poll(&mut device, Instant::from_secs(i * 3));
+            assert_eq!(
+                recv(&mut device),
+                vec![(
+                    PROTOCOL_LCP,
+                    control(CONFIGURE_REQUEST, i as u8 + 1, &LCP_REQUEST)
+                )]
+            );
+            assert_eq!(device.poll_at(), Some(Instant::from_secs(i * 3 + 3)));
+        }
+        poll(&mut device, Instant::from_secs(30));
+        assert_eq!(recv(&mut device), vec![]);
+        assert_eq!(device.poll_event(), Some(PppEvent::Terminated));
+        assert_eq!(device.poll_at(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_lcp_packets() {
+        let mut device = new_device();
+        establish_ipv4(&mut device);
+
+        let now = Instant::from_secs(1);
+        send(
+            &mut device,
+            PROTOCOL_LCP,
+            &control(ECHO_REQUEST, 3, &[0x12, 0x34, 0x56, 0x78]),
+        );
+        // The Compression Control Protocol is not supported.
+        send(&mut device, 0x80fd, &control(CONFIGURE_REQUEST, 1, &[]));
+        send(&mut device, PROTOCOL_LCP, &control(42, 1, &[]));
+        poll(&mut device, now);
+        assert_eq!(
+            recv(&mut device),
+            vec![
+                (PROTOCOL_LCP, control(ECHO_REPLY, 3, &[0, 0, 0, 0])),
+                (
+                    PROTOCOL_LCP,
+                    control(PROTOCOL_REJECT, 1, &[0x80, 0xfd, 1, 1, 0, 4])
+                ),
+                (PROTOCOL_LCP, control(7, 2, &[42, 1, 0, 4])),
+            ]
+        );
+    }
+}
@@ -29,7 +29,31 @@ impl RawSocket {
     pub fn new(name: &str, medium: Medium) -> io::Result<RawSocket> {
         let mut lower = sys::RawSocketDesc::new(name, medium)?;
         lower.bind_interface()?;
+        Self::from_desc(lower, medium)
+    }
+
+    /// Creates a raw socket, bound to the interface called `name`, that receives and sends
+    /// the frames through rings memory-mapped with the kernel (`PACKET_MMAP`, in the
+    /// `TPACKET_V3` format), rather than with a system call each.
+    ///
+    /// The received frames are handed over by the kernel in blocks, once a block is full or
+    /// after a millisecond. The frames to send are queued in the ring, and sent together
+    /// once enough of them are, or once no received frame is left, i.e. at the end of each
+    /// [poll](crate::iface::Interface::poll). This requires Linux 4.11 or later.
+    ///
+    /// This requires superuser privileges or a corresponding capability bit
+    /// set on the executable.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn new_mmap(name: &str, medium: Medium) -> io::Result<RawSocket> {
+        let mut lower = sys::RawSocketDesc::new(name, medium)?;
+        // Room for the link-layer header.
+        let frame_len = lower.interface_mtu()? + 64;
+        lower.map_rings(frame_len)?;
+        lower.bind_interface()?;
+        Self::from_desc(lower, medium)
+    }
 
+    fn from_desc(mut lower: sys::RawSocketDesc, medium: Medium) -> io::Result<RawSocket> {
         let mut mtu = lower.interface_mtu()?;
 
         // FIXME(thvdveld): this is a workaround for https://github.com/smoltcp-rs/smoltcp/issues/622
@@ -54,10 +78,12 @@ impl RawSocket {
 }
 
 impl Device for RawSocket {
-    type RxToken<'a> = RxToken
+    type RxToken<'a>
+        = RxToken
     where
         Self: 'a;
-    type TxToken<'a> = TxToken
+    type TxToken<'a>
+        = TxToken
     where
         Self: 'a;
 
@@ -9,3 +9,32 @@ pub const TUNSETIFF: libc::c_ulong = 0x400454CA;
 pub const IFF_TUN: libc::c_int = 0x0001;
 pub const IFF_TAP: libc::c_int = 0x0002;
 pub const IFF_NO_PI: libc::c_int = 0x1000;
+
+pub const AF_XDP: libc::c_int = 44;
+pub const SOL_XDP: libc::c_int = 283;
+pub const XDP_MMAP_OFFSETS: libc::c_int = 1;
+pub const XDP_RX_RING: libc::c_int = 2;
+pub const XDP_TX_RING: libc::c_int = 3;
+pub const XDP_UMEM_REG: libc::c_int = 4;
+pub const XDP_UMEM_FILL_RING: libc::c_int = 5;
+pub const XDP_UMEM_COMPLETION_RING: libc::c_int = 6;
+pub const XDP_OPTIONS: libc::c_int = 8;
+pub const XDP_OPTIONS_ZEROCOPY: u32 = 1 << 0;
+pub const XDP_USE_NEED_WAKEUP: u16 = 1 << 3;
+pub const XDP_RING_NEED_WAKEUP: u32 = 1 << 0;
+pub const XDP_PACKET_HEADROOM: usize = 256;
+pub const XDP_PGOFF_RX_RING: u64 = 0;
+pub const XDP_PGOFF_TX_RING: u64 = 0x80000000;
+pub const XDP_UMEM_PGOFF_FILL_RING: u64 = 0x100000000;
+pub const XDP_UMEM_PGOFF_COMPLETION_RING: u64 = 0x180000000;
+
+pub const SOL_PACKET: libc::c_int = 263;
+pub const PACKET_RX_RING: libc::c_int = 5;
+pub const PACKET_VERSION: libc::c_int = 10;
+pub const PACKET_TX_RING: libc::c_int = 13;
+pub const TPACKET_V3: libc::c_int = 2;
+pub const TP_STATUS_KERNEL: u32 = 0;
+pub const TP_STATUS_USER: u32 = 1 << 0;
+pub const TP_STATUS_AVAILABLE: u32 = 0;
+pub const TP_STATUS_SEND_REQUEST: u32 = 1 << 0;
+pub const TP_STATUS_WRONG_FORMAT: u32 = 1 << 2;
@@ -1,7 +1,10 @@
 #![allow(unsafe_code)]
 
+#[cfg(unix)]
 use crate::time::Duration;
+#[cfg(unix)]
 use std::os::unix::io::RawFd;
+#[cfg(unix)]
 use std::{io, mem, ptr};
 
 #[cfg(any(target_os = "linux", target_os = "android"))]
@@ -24,6 +27,17 @@ pub mod raw_socket;
     any(target_os = "linux", target_os = "android")
 ))]
 pub mod tuntap_interface;
+#[cfg(all(feature = "phy-tuntap_interface", target_os = "macos"))]
+pub mod utun;
+#[cfg(all(feature = "phy-xdp", target_os = "linux", target_pointer_width = "64"))]
+pub mod xdp;
+
+#[cfg(all(feature = "phy-npcap", windows))]
+pub mod npcap;
+#[cfg(windows)]
+mod windows;
+#[cfg(all(feature = "phy-wintun", windows))]
+pub mod wintun;
 
 #[cfg(all(
     feature = "phy-raw_socket",
@@ -41,8 +55,18 @@ pub use self::raw_socket::RawSocketDesc;
     any(target_os = "linux", target_os = "android")
 ))]
 pub use self::tuntap_interface::TunTapInterfaceDesc;
+#[cfg(all(feature = "phy-tuntap_interface", target_os = "macos"))]
+pub use self::utun::TunTapInterfaceDesc;
+#[cfg(all(feature = "phy-xdp", target_os = "linux", target_pointer_width = "64"))]
+pub use self::xdp::{XdpRxQueue, XdpSocketDesc, XdpTxQueue};
+
+#[cfg(all(feature = "phy-npcap", windows))]
+pub use self::npcap::NpcapDesc;
+#[cfg(all(feature = "phy-wintun", windows))]
+pub use self::wintun::{WintunDesc, WintunRxPacket};
 
 /// Wait until given file descriptor becomes readable, but no longer than given timeout.
+#[cfg(unix)]
 pub fn wait(fd: RawFd, duration: Option<Duration>) -> io::Result<()> {
     unsafe {
         let mut readfds = {
@@ -91,7 +115,14 @@ pub fn wait(fd: RawFd, duration: Option<Duration>) -> io::Result<()> {
 }
 
 #[cfg(all(
-    any(feature = "phy-tuntap_interface", feature = "phy-raw_socket"),
+    any(
+        all(
+            feature = "phy-tuntap_interface",
+            any(target_os = "linux", target_os = "android")
+        ),
+        feature = "phy-raw_socket",
+        feature = "phy-xdp"
+    ),
     unix
 ))]
 #[repr(C)]
@@ -102,7 +133,14 @@ struct ifreq {
 }
 
 #[cfg(all(
-    any(feature = "phy-tuntap_interface", feature = "phy-raw_socket"),
+    any(
+        all(
+            feature = "phy-tuntap_interface",
+            any(target_os = "linux", target_os = "android")
+        ),
+        feature = "phy-raw_socket",
+        feature = "phy-xdp"
+    ),
     unix
 ))]
 fn ifreq_for(name: &str) -> ifreq {
@@ -118,7 +156,11 @@ fn ifreq_for(name: &str) -> ifreq {
 
 #[cfg(all(
     any(target_os = "linux", target_os = "android"),
-    any(feature = "phy-tuntap_interface", feature = "phy-raw_socket")
+    any(
+        feature = "phy-tuntap_interface",
+        feature = "phy-raw_socket",
+        feature = "phy-xdp"
+    )
 ))]
 fn ifreq_ioctl(
     lower: libc::c_int,
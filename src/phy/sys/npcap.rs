@@ -0,0 +1,125 @@
+use super::windows::{wait_event, Handle, Library};
+use crate::time::Duration;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::{io, ptr, slice};
+
+const PCAP_ERRBUF_SIZE: usize = 256;
+
+#[repr(C)]
+struct PcapPkthdr {
+    // A `struct timeval`, whose fields are 32-bit `long`s on Windows.
+    tv_sec: i32,
+    tv_usec: i32,
+    caplen: u32,
+    len: u32,
+}
+
+type OpenLiveFn = unsafe extern "C" fn(
+    device: *const c_char,
+    snaplen: c_int,
+    promisc: c_int,
+    to_ms: c_int,
+    errbuf: *mut c_char,
+) -> *mut c_void;
+type SetNonblockFn =
+    unsafe extern "C" fn(pcap: *mut c_void, nonblock: c_int, errbuf: *mut c_char) -> c_int;
+type SetMinToCopyFn = unsafe extern "C" fn(pcap: *mut c_void, size: c_int) -> c_int;
+type GetEventFn = unsafe extern "C" fn(pcap: *mut c_void) -> Handle;
+type NextExFn = unsafe extern "C" fn(
+    pcap: *mut c_void,
+    header: *mut *mut PcapPkthdr,
+    data: *mut *const u8,
+) -> c_int;
+type SendPacketFn = unsafe extern "C" fn(pcap: *mut c_void, buf: *const u8, size: c_int) -> c_int;
+type GetErrFn = unsafe extern "C" fn(pcap: *mut c_void) -> *const c_char;
+type CloseFn = unsafe extern "C" fn(pcap: *mut c_void);
+
+#[derive(Debug)]
+pub struct NpcapDesc {
+    next_ex: NextExFn,
+    send_packet: SendPacketFn,
+    get_err: GetErrFn,
+    close: CloseFn,
+    pcap: *mut c_void,
+    event: Handle,
+    // Unloaded after the capture is closed.
+    _library: Library,
+}
+
+fn error(message: &CStr) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, message.to_string_lossy().into_owned())
+}
+
+impl NpcapDesc {
+    pub fn new(name: &str, snaplen: usize) -> io::Result<NpcapDesc> {
+        let library = Library::load_system("Npcap\\wpcap.dll")?;
+        let name = CString::new(name).map_err(|_| io::Error::from(io::ErrorKind::InvalidInput))?;
+        let mut errbuf = [0 as c_char; PCAP_ERRBUF_SIZE];
+        unsafe {
+            let open_live: OpenLiveFn = library.symbol(b"pcap_open_live\0")?;
+            let set_nonblock: SetNonblockFn = library.symbol(b"pcap_setnonblock\0")?;
+            let set_min_to_copy: SetMinToCopyFn = library.symbol(b"pcap_setmintocopy\0")?;
+            let get_event: GetEventFn = library.symbol(b"pcap_getevent\0")?;
+            let close: CloseFn = library.symbol(b"pcap_close\0")?;
+
+            // Capture in promiscuous mode, to receive the frames for all the hardware
+            // addresses of the interfaces.
+            let pcap = open_live(name.as_ptr(), snaplen as c_int, 1, 0, errbuf.as_mut_ptr());
+            if pcap.is_null() {
+                return Err(error(CStr::from_ptr(errbuf.as_ptr())));
+            }
+            // Deliver every frame as soon as it is captured, rather than in batches.
+            if set_nonblock(pcap, 1, errbuf.as_mut_ptr()) < 0 || set_min_to_copy(pcap, 0) < 0 {
+                let err = error(CStr::from_ptr(errbuf.as_ptr()));
+                close(pcap);
+                return Err(err);
+            }
+
+            Ok(NpcapDesc {
+                next_ex: library.symbol(b"pcap_next_ex\0")?,
+                send_packet: library.symbol(b"pcap_sendpacket\0")?,
+                get_err: library.symbol(b"pcap_geterr\0")?,
+                close,
+                pcap,
+                event: get_event(pcap),
+                _library: library,
+            })
+        }
+    }
+
+    fn last_error(&self) -> io::Error {
+        unsafe { error(CStr::from_ptr((self.get_err)(self.pcap))) }
+    }
+
+    pub fn recv(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut header = ptr::null_mut();
+        let mut data = ptr::null();
+        match unsafe { (self.next_ex)(self.pcap, &mut header, &mut data) } {
+            1 => {
+                let frame = unsafe { slice::from_raw_parts(data, (*header).caplen as usize) };
+                let len = frame.len().min(buffer.len());
+                buffer[..len].copy_from_slice(&frame[..len]);
+                Ok(len)
+            }
+            0 => Err(io::ErrorKind::WouldBlock.into()),
+            _ => Err(self.last_error()),
+        }
+    }
+
+    pub fn send(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if unsafe { (self.send_packet)(self.pcap, buffer.as_ptr(), buffer.len() as c_int) } < 0 {
+            return Err(self.last_error());
+        }
+        Ok(buffer.len())
+    }
+
+    pub fn wait(&self, duration: Option<Duration>) -> io::Result<()> {
+        wait_event(self.event, duration)
+    }
+}
+
+impl Drop for NpcapDesc {
+    fn drop(&mut self) {
+        unsafe { (self.close)(self.pcap) }
+    }
+}
@@ -1,13 +1,259 @@
 use super::*;
 use crate::phy::Medium;
 use std::os::unix::io::{AsRawFd, RawFd};
-use std::{io, mem};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::{io, mem, ptr};
+
+/// The size of the receive ring.
+const RX_RING_LEN: usize = 8 << 20;
+/// The size of the send ring.
+const TX_RING_LEN: usize = 2 << 20;
+/// The smallest size of the blocks of the rings.
+const BLOCK_SIZE: usize = 128 << 10;
+/// The smallest size of the frames of the rings.
+const FRAME_SIZE: usize = 2048;
+/// How long the kernel waits for a receive block to fill up before handing it over, in
+/// milliseconds.
+const RX_BLOCK_TIMEOUT: u32 = 1;
+/// The number of frames queued for sending before the kernel is asked to send them.
+const TX_BATCH: usize = 32;
+
+// Offsets in `struct tpacket_block_desc`.
+const BLOCK_STATUS: usize = 8;
+const BLOCK_NUM_PKTS: usize = 12;
+const BLOCK_OFFSET_TO_FIRST_PKT: usize = 16;
+// Offsets in `struct tpacket3_hdr`.
+const PKT_NEXT_OFFSET: usize = 0;
+const PKT_SNAPLEN: usize = 12;
+const PKT_LEN: usize = 16;
+const PKT_STATUS: usize = 20;
+const PKT_MAC: usize = 24;
+/// The offset of the frames in the send ring, after their `struct tpacket3_hdr`.
+const PKT_DATA: usize = 48;
+
+#[repr(C)]
+#[derive(Debug)]
+struct TpacketReq3 {
+    tp_block_size: u32,
+    tp_block_nr: u32,
+    tp_frame_size: u32,
+    tp_frame_nr: u32,
+    tp_retire_blk_tov: u32,
+    tp_sizeof_priv: u32,
+    tp_feature_req_word: u32,
+}
+
+unsafe fn read_u32(ptr: *const u8) -> u32 {
+    (ptr as *const u32).read_volatile()
+}
+
+unsafe fn status<'a>(ptr: *mut u8) -> &'a AtomicU32 {
+    &*(ptr as *const AtomicU32)
+}
+
+/// Receive and send rings memory-mapped with the kernel (`PACKET_MMAP`), in the
+/// `TPACKET_V3` format, so that frames are received and sent in batches rather than with a
+/// system call each.
+#[derive(Debug)]
+struct PacketRings {
+    map: *mut u8,
+    map_len: usize,
+    rx_block_size: usize,
+    rx_block_nr: usize,
+    /// The block being read, and the number and offset of the next packet in it.
+    rx_block: usize,
+    rx_packet: u32,
+    rx_offset: usize,
+    tx_frame_size: usize,
+    tx_frame_nr: usize,
+    /// The next frame to fill, and the number of frames queued since the last flush.
+    tx_frame: usize,
+    tx_pending: usize,
+}
+
+impl PacketRings {
+    fn new(lower: libc::c_int, frame_len: usize) -> io::Result<PacketRings> {
+        let setsockopt = |name: libc::c_int, value: *const libc::c_void, len: usize| unsafe {
+            let res = libc::setsockopt(lower, imp::SOL_PACKET, name, value, len as libc::socklen_t);
+            if res == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        };
+        let ring_req = |block_size: usize, len: usize, frame_size: usize, tov: u32| {
+            let block_nr = (len / block_size).max(1);
+            TpacketReq3 {
+                tp_block_size: block_size as u32,
+                tp_block_nr: block_nr as u32,
+                tp_frame_size: frame_size as u32,
+                tp_frame_nr: (block_size / frame_size * block_nr) as u32,
+                tp_retire_blk_tov: tov,
+                tp_sizeof_priv: 0,
+                tp_feature_req_word: 0,
+            }
+        };
+
+        let version = imp::TPACKET_V3;
+        setsockopt(
+            imp::PACKET_VERSION,
+            &version as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>(),
+        )?;
+
+        // Blocks hold several received frames of any size; frames hold a single frame to
+        // send, after its header.
+        let rx_block_size = BLOCK_SIZE.max((frame_len + 256).next_power_of_two());
+        let rx_req = ring_req(rx_block_size, RX_RING_LEN, FRAME_SIZE, RX_BLOCK_TIMEOUT);
+        let tx_frame_size = FRAME_SIZE.max((frame_len + PKT_DATA).next_power_of_two());
+        let tx_block_size = BLOCK_SIZE.max(tx_frame_size);
+        let tx_req = ring_req(tx_block_size, TX_RING_LEN, tx_frame_size, 0);
+        for (name, req) in [
+            (imp::PACKET_RX_RING, &rx_req),
+            (imp::PACKET_TX_RING, &tx_req),
+        ] {
+            setsockopt(
+                name,
+                req as *const TpacketReq3 as *const libc::c_void,
+                mem::size_of::<TpacketReq3>(),
+            )?;
+        }
+
+        let rx_len = rx_block_size * rx_req.tp_block_nr as usize;
+        let map_len = rx_len + tx_block_size * tx_req.tp_block_nr as usize;
+        let map = unsafe {
+            let map = libc::mmap(
+                ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                lower,
+                0,
+            );
+            if map == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            map as *mut u8
+        };
+
+        Ok(PacketRings {
+            map,
+            map_len,
+            rx_block_size,
+            rx_block_nr: rx_req.tp_block_nr as usize,
+            rx_block: 0,
+            rx_packet: 0,
+            rx_offset: 0,
+            tx_frame_size,
+            tx_frame_nr: tx_req.tp_frame_nr as usize,
+            tx_frame: 0,
+            tx_pending: 0,
+        })
+    }
+
+    fn recv(&mut self, lower: libc::c_int, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            unsafe {
+                let block = self.map.add(self.rx_block * self.rx_block_size);
+                let block_status = status(block.add(BLOCK_STATUS));
+                if block_status.load(Ordering::Acquire) & imp::TP_STATUS_USER == 0 {
+                    // Nothing was received: send the frames queued so far.
+                    self.flush(lower);
+                    return Err(io::ErrorKind::WouldBlock.into());
+                }
+
+                let num_pkts = read_u32(block.add(BLOCK_NUM_PKTS));
+                if self.rx_packet == 0 {
+                    self.rx_offset = read_u32(block.add(BLOCK_OFFSET_TO_FIRST_PKT)) as usize;
+                }
+                let mut len = None;
+                if self.rx_packet < num_pkts {
+                    let packet = block.add(self.rx_offset);
+                    let snaplen = read_u32(packet.add(PKT_SNAPLEN)) as usize;
+                    let mac = (packet.add(PKT_MAC) as *const u16).read_volatile() as usize;
+                    let copied = snaplen.min(buffer.len());
+                    ptr::copy_nonoverlapping(packet.add(mac), buffer.as_mut_ptr(), copied);
+                    len = Some(copied);
+                    self.rx_packet += 1;
+                    self.rx_offset += read_u32(packet.add(PKT_NEXT_OFFSET)) as usize;
+                }
+                if self.rx_packet >= num_pkts {
+                    // Give the block back to the kernel.
+                    block_status.store(imp::TP_STATUS_KERNEL, Ordering::Release);
+                    self.rx_block = (self.rx_block + 1) % self.rx_block_nr;
+                    self.rx_packet = 0;
+                }
+                if let Some(len) = len {
+                    return Ok(len);
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, lower: libc::c_int, buffer: &[u8]) -> io::Result<usize> {
+        unsafe {
+            let frame = self
+                .map
+                .add(self.rx_block_size * self.rx_block_nr + self.tx_frame * self.tx_frame_size);
+            let frame_status = status(frame.add(PKT_STATUS));
+            let mut value = frame_status.load(Ordering::Acquire);
+            if !matches!(
+                value,
+                imp::TP_STATUS_AVAILABLE | imp::TP_STATUS_WRONG_FORMAT
+            ) {
+                // The ring is full of frames that were not sent yet.
+                self.flush(lower);
+                value = frame_status.load(Ordering::Acquire);
+            }
+            match value {
+                imp::TP_STATUS_AVAILABLE => (),
+                imp::TP_STATUS_WRONG_FORMAT => {
+                    net_debug!("phy: frame rejected by the kernel");
+                }
+                _ => return Err(io::ErrorKind::WouldBlock.into()),
+            }
+
+            let len = buffer.len().min(self.tx_frame_size - PKT_DATA);
+            ptr::copy_nonoverlapping(buffer.as_ptr(), frame.add(PKT_DATA), len);
+            (frame.add(PKT_LEN) as *mut u32).write_volatile(len as u32);
+            (frame.add(PKT_SNAPLEN) as *mut u32).write_volatile(len as u32);
+            frame_status.store(imp::TP_STATUS_SEND_REQUEST, Ordering::Release);
+            self.tx_frame = (self.tx_frame + 1) % self.tx_frame_nr;
+            self.tx_pending += 1;
+            if self.tx_pending >= TX_BATCH.min(self.tx_frame_nr) {
+                self.flush(lower);
+            }
+            Ok(len)
+        }
+    }
+
+    /// Ask the kernel to send the frames queued in the send ring.
+    fn flush(&mut self, lower: libc::c_int) {
+        if self.tx_pending == 0 {
+            return;
+        }
+        self.tx_pending = 0;
+        let res = unsafe { libc::send(lower, ptr::null(), 0, libc::MSG_DONTWAIT) };
+        if res == -1 {
+            // The frames stay in the ring, and are sent by the next flush.
+            net_debug!("phy: tx failed: {}", io::Error::last_os_error());
+        }
+    }
+}
+
+impl Drop for PacketRings {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.map as *mut libc::c_void, self.map_len);
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct RawSocketDesc {
     protocol: libc::c_short,
     lower: libc::c_int,
     ifreq: ifreq,
+    rings: Option<PacketRings>,
 }
 
 impl AsRawFd for RawSocketDesc {
@@ -43,9 +289,17 @@ impl RawSocketDesc {
             protocol,
             lower,
             ifreq: ifreq_for(name),
+            rings: None,
         })
     }
 
+    /// Receive and send the frames through memory-mapped rings, sized for frames of up to
+    /// `frame_len` octets.
+    pub fn map_rings(&mut self, frame_len: usize) -> io::Result<()> {
+        self.rings = Some(PacketRings::new(self.lower, frame_len)?);
+        Ok(())
+    }
+
     pub fn interface_mtu(&mut self) -> io::Result<usize> {
         ifreq_ioctl(self.lower, &mut self.ifreq, imp::SIOCGIFMTU).map(|mtu| mtu as usize)
     }
@@ -76,6 +330,9 @@ impl RawSocketDesc {
     }
 
     pub fn recv(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if let Some(rings) = &mut self.rings {
+            return rings.recv(self.lower, buffer);
+        }
         unsafe {
             let len = libc::recv(
                 self.lower,
@@ -91,6 +348,9 @@ impl RawSocketDesc {
     }
 
     pub fn send(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        if let Some(rings) = &mut self.rings {
+            return rings.send(self.lower, buffer);
+        }
         unsafe {
             let len = libc::send(
                 self.lower,
@@ -108,6 +368,9 @@ impl RawSocketDesc {
 
 impl Drop for RawSocketDesc {
     fn drop(&mut self) {
+        if let Some(rings) = &mut self.rings {
+            rings.flush(self.lower);
+        }
         unsafe {
             libc::close(self.lower);
         }
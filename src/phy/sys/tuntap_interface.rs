@@ -35,7 +35,7 @@ impl TunTapInterfaceDesc {
         Ok(TunTapInterfaceDesc { lower, mtu })
     }
 
-    pub fn from_fd(fd: RawFd, mtu: usize) -> io::Result<TunTapInterfaceDesc> {
+    pub fn from_fd(fd: RawFd, _medium: Medium, mtu: usize) -> io::Result<TunTapInterfaceDesc> {
         Ok(TunTapInterfaceDesc { lower: fd, mtu })
     }
 
@@ -0,0 +1,317 @@
+use crate::phy::Medium;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::vec::Vec;
+use std::{io, mem};
+
+/// get interface MTU
+const SIOCGIFMTU: libc::c_ulong = 0xc0206933;
+/// The size of the buffer of the BPF device, which receives several frames at once.
+const BPF_BUFFER_LEN: libc::c_uint = 65536;
+
+fn last_error<T>() -> io::Result<T> {
+    Err(io::Error::last_os_error())
+}
+
+macro_rules! try_ioctl {
+    ($fd:expr,$cmd:expr,$req:expr) => {
+        if libc::ioctl($fd, $cmd, $req) == -1 {
+            let err = io::Error::last_os_error();
+            libc::close($fd);
+            return Err(err);
+        }
+    };
+}
+
+/// A utun interface, for [Medium::Ip], or a BPF device bound to a feth interface, for
+/// [Medium::Ethernet].
+///
+/// utun interfaces are the TUN interfaces of macOS: each one is created by connecting a
+/// `PF_SYSTEM` socket, and destroyed when the socket is closed. The packets are read and
+/// written after a 4-octet header, with their address family.
+///
+/// macOS has no TAP interfaces; the closest are feth interfaces, pairs of fake Ethernet
+/// interfaces connected to each other, e.g. created with `ifconfig feth0 create`,
+/// `ifconfig feth1 create` and `ifconfig feth0 peer feth1`. The frames are received and
+/// sent on one end of the pair with BPF, with the other end configured on the host.
+#[derive(Debug)]
+pub struct TunTapInterfaceDesc {
+    lower: libc::c_int,
+    medium: Medium,
+    mtu: usize,
+    // The frames read from the BPF device but not received yet.
+    buffer: Vec<u8>,
+    offset: usize,
+}
+
+impl AsRawFd for TunTapInterfaceDesc {
+    fn as_raw_fd(&self) -> RawFd {
+        self.lower
+    }
+}
+
+impl TunTapInterfaceDesc {
+    pub fn new(name: &str, medium: Medium) -> io::Result<TunTapInterfaceDesc> {
+        let (lower, buffer_len) = match medium {
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => (Self::open_utun(name)?, 0),
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => Self::open_bpf(name)?,
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => todo!(),
+        };
+        let mtu = match Self::mtu_ifreq(medium, name) {
+            Ok(mtu) => mtu,
+            Err(err) => {
+                unsafe { libc::close(lower) };
+                return Err(err);
+            }
+        };
+        Ok(TunTapInterfaceDesc {
+            lower,
+            medium,
+            mtu,
+            buffer: Vec::with_capacity(buffer_len),
+            offset: 0,
+        })
+    }
+
+    pub fn from_fd(fd: RawFd, medium: Medium, mtu: usize) -> io::Result<TunTapInterfaceDesc> {
+        // A BPF device must be read with a buffer of its own size.
+        let mut buffer_len: libc::c_uint = 0;
+        #[cfg(feature = "medium-ethernet")]
+        if medium == Medium::Ethernet
+            && unsafe { libc::ioctl(fd, libc::BIOCGBLEN, &mut buffer_len as *mut libc::c_uint) }
+                == -1
+        {
+            return last_error();
+        }
+        Ok(TunTapInterfaceDesc {
+            lower: fd,
+            medium,
+            mtu,
+            buffer: Vec::with_capacity(buffer_len as usize),
+            offset: 0,
+        })
+    }
+
+    /// Create the utun interface called `name`, which must be `utun` followed by its unit
+    /// number.
+    #[cfg(feature = "medium-ip")]
+    fn open_utun(name: &str) -> io::Result<libc::c_int> {
+        let unit = name
+            .strip_prefix("utun")
+            .and_then(|unit| unit.parse::<u32>().ok())
+            .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?;
+
+        unsafe {
+            let lower = libc::socket(libc::PF_SYSTEM, libc::SOCK_DGRAM, libc::SYSPROTO_CONTROL);
+            if lower == -1 {
+                return last_error();
+            }
+
+            let mut info: libc::ctl_info = mem::zeroed();
+            for (i, byte) in b"com.apple.net.utun_control".iter().enumerate() {
+                info.ctl_name[i] = *byte as libc::c_char;
+            }
+            try_ioctl!(lower, libc::CTLIOCGINFO, &mut info as *mut libc::ctl_info);
+
+            let mut addr: libc::sockaddr_ctl = mem::zeroed();
+            addr.sc_len = mem::size_of::<libc::sockaddr_ctl>() as u8;
+            addr.sc_family = libc::AF_SYSTEM as u8;
+            addr.ss_sysaddr = libc::AF_SYS_CONTROL as u16;
+            addr.sc_id = info.ctl_id;
+            // Unit numbers are offset by one, unit 0 letting the kernel choose one.
+            addr.sc_unit = unit + 1;
+            if libc::connect(
+                lower,
+                &addr as *const libc::sockaddr_ctl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_ctl>() as libc::socklen_t,
+            ) == -1
+                || libc::fcntl(lower, libc::F_SETFL, libc::O_NONBLOCK) == -1
+            {
+                let err = io::Error::last_os_error();
+                libc::close(lower);
+                return Err(err);
+            }
+            Ok(lower)
+        }
+    }
+
+    /// Open a BPF device bound to the interface called `name`, and return it with the size
+    /// of its buffer.
+    #[cfg(feature = "medium-ethernet")]
+    fn open_bpf(name: &str) -> io::Result<(libc::c_int, usize)> {
+        unsafe {
+            let mut lower = -1;
+            for i in 0..256 {
+                let dev = format!("/dev/bpf{}\0", i);
+                lower = libc::open(
+                    dev.as_ptr() as *const libc::c_char,
+                    libc::O_RDWR | libc::O_NONBLOCK,
+                );
+                if lower != -1 {
+                    break;
+                }
+            }
+            if lower == -1 {
+                return last_error();
+            }
+
+            // The buffer size must be set before the interface.
+            let mut value = BPF_BUFFER_LEN;
+            try_ioctl!(lower, libc::BIOCSBLEN, &mut value as *mut libc::c_uint);
+            let mut ifreq: libc::ifreq = mem::zeroed();
+            for (i, byte) in name.as_bytes().iter().take(libc::IFNAMSIZ - 1).enumerate() {
+                ifreq.ifr_name[i] = *byte as libc::c_char;
+            }
+            try_ioctl!(lower, libc::BIOCSETIF, &mut ifreq as *mut libc::ifreq);
+            let mut buffer_len: libc::c_uint = 0;
+            try_ioctl!(lower, libc::BIOCGBLEN, &mut buffer_len as *mut libc::c_uint);
+            // Return the frames as soon as they are received, keep the source addresses of
+            // the frames sent, and do not receive them back.
+            let mut value: libc::c_uint = 1;
+            try_ioctl!(lower, libc::BIOCIMMEDIATE, &mut value as *mut libc::c_uint);
+            try_ioctl!(lower, libc::BIOCSHDRCMPLT, &mut value as *mut libc::c_uint);
+            let mut value: libc::c_uint = 0;
+            try_ioctl!(lower, libc::BIOCSSEESENT, &mut value as *mut libc::c_uint);
+
+            Ok((lower, buffer_len as usize))
+        }
+    }
+
+    fn mtu_ifreq(medium: Medium, name: &str) -> io::Result<usize> {
+        let ip_mtu = unsafe {
+            let lower = libc::socket(libc::AF_INET, libc::SOCK_DGRAM, libc::IPPROTO_IP);
+            if lower == -1 {
+                return last_error();
+            }
+            let mut ifreq: libc::ifreq = mem::zeroed();
+            for (i, byte) in name.as_bytes().iter().take(libc::IFNAMSIZ - 1).enumerate() {
+                ifreq.ifr_name[i] = *byte as libc::c_char;
+            }
+            try_ioctl!(lower, SIOCGIFMTU, &mut ifreq as *mut libc::ifreq);
+            libc::close(lower);
+            ifreq.ifr_ifru.ifru_mtu as usize
+        };
+
+        // SIOCGIFMTU returns the IP MTU (typically 1500 bytes.)
+        // smoltcp counts the entire Ethernet packet in the MTU, so add the Ethernet header size to it.
+        let mtu = match medium {
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => ip_mtu,
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => ip_mtu + crate::wire::EthernetFrame::<&[u8]>::header_len(),
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => todo!(),
+        };
+
+        Ok(mtu)
+    }
+
+    pub fn interface_mtu(&self) -> io::Result<usize> {
+        Ok(self.mtu)
+    }
+
+    pub fn recv(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        match self.medium {
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => {
+                let mut family = [0u8; 4];
+                let iovecs = [
+                    iovec(family.as_mut_ptr(), family.len()),
+                    iovec(buffer.as_mut_ptr(), buffer.len()),
+                ];
+                let len = unsafe { libc::readv(self.lower, iovecs.as_ptr(), 2) };
+                if len == -1 {
+                    return last_error();
+                }
+                Ok((len as usize).saturating_sub(family.len()))
+            }
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => {
+                if self.offset >= self.buffer.len() {
+                    let len = unsafe {
+                        libc::read(
+                            self.lower,
+                            self.buffer.as_mut_ptr() as *mut libc::c_void,
+                            self.buffer.capacity(),
+                        )
+                    };
+                    if len == -1 {
+                        return last_error();
+                    }
+                    unsafe { self.buffer.set_len(len as usize) };
+                    self.offset = 0;
+                }
+
+                // Each frame follows a `struct bpf_hdr`, and is followed by padding to
+                // align the next header to 32 bits.
+                let header = &self.buffer[self.offset..];
+                let caplen = u32::from_ne_bytes([header[8], header[9], header[10], header[11]]);
+                let hdrlen = u16::from_ne_bytes([header[16], header[17]]) as usize;
+                let frame = &header[hdrlen..][..caplen as usize];
+                let len = frame.len().min(buffer.len());
+                buffer[..len].copy_from_slice(&frame[..len]);
+                self.offset += (hdrlen + caplen as usize + 3) & !3;
+                Ok(len)
+            }
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => todo!(),
+        }
+    }
+
+    pub fn send(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self.medium {
+            #[cfg(feature = "medium-ip")]
+            Medium::Ip => {
+                let family = match buffer.first().map(|byte| byte >> 4) {
+                    Some(6) => libc::AF_INET6,
+                    _ => libc::AF_INET,
+                };
+                let family = (family as u32).to_be_bytes();
+                // writev() does not write through the pointers.
+                let iovecs = [
+                    iovec(family.as_ptr() as *mut u8, family.len()),
+                    iovec(buffer.as_ptr() as *mut u8, buffer.len()),
+                ];
+                let len = unsafe { libc::writev(self.lower, iovecs.as_ptr(), 2) };
+                if len == -1 {
+                    return last_error();
+                }
+                Ok((len as usize).saturating_sub(family.len()))
+            }
+            #[cfg(feature = "medium-ethernet")]
+            Medium::Ethernet => {
+                let len = unsafe {
+                    libc::write(
+                        self.lower,
+                        buffer.as_ptr() as *const libc::c_void,
+                        buffer.len(),
+                    )
+                };
+                if len == -1 {
+                    return last_error();
+                }
+                Ok(len as usize)
+            }
+            #[cfg(feature = "medium-ieee802154")]
+            Medium::Ieee802154 => todo!(),
+        }
+    }
+}
+
+#[cfg(feature = "medium-ip")]
+fn iovec(base: *mut u8, len: usize) -> libc::iovec {
+    libc::iovec {
+        iov_base: base as *mut libc::c_void,
+        iov_len: len,
+    }
+}
+
+impl Drop for TunTapInterfaceDesc {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.lower);
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::time::Duration;
+use std::ffi::c_void;
+use std::vec::Vec;
+use std::{io, iter, mem};
+
+pub type Handle = *mut c_void;
+
+#[cfg(feature = "phy-npcap")]
+const LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR: u32 = 0x00000100;
+const LOAD_LIBRARY_SEARCH_DEFAULT_DIRS: u32 = 0x00001000;
+const INFINITE: u32 = 0xffffffff;
+const WAIT_FAILED: u32 = 0xffffffff;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn LoadLibraryExW(name: *const u16, file: Handle, flags: u32) -> Handle;
+    fn GetProcAddress(module: Handle, name: *const u8) -> *mut c_void;
+    fn FreeLibrary(module: Handle) -> i32;
+    #[cfg(feature = "phy-npcap")]
+    fn GetSystemDirectoryW(buffer: *mut u16, size: u32) -> u32;
+    fn WaitForSingleObject(handle: Handle, milliseconds: u32) -> u32;
+}
+
+/// Return `s` as a NUL-terminated UTF-16 string.
+pub fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(iter::once(0)).collect()
+}
+
+/// A DLL, unloaded when dropped.
+#[derive(Debug)]
+pub struct Library {
+    module: Handle,
+}
+
+impl Library {
+    /// Load the DLL called `name` from the directory of the application or from the
+    /// system directory.
+    #[cfg(feature = "phy-wintun")]
+    pub fn load(name: &str) -> io::Result<Library> {
+        Self::load_with_flags(name, LOAD_LIBRARY_SEARCH_DEFAULT_DIRS)
+    }
+
+    /// Load the DLL at `path` in the system directory, along with the DLLs next to it that
+    /// it depends on.
+    #[cfg(feature = "phy-npcap")]
+    pub fn load_system(path: &str) -> io::Result<Library> {
+        let mut dir = [0u16; 260];
+        let len = unsafe { GetSystemDirectoryW(dir.as_mut_ptr(), dir.len() as u32) } as usize;
+        if len == 0 || len > dir.len() {
+            return Err(io::Error::last_os_error());
+        }
+        let path = format!("{}\\{}", String::from_utf16_lossy(&dir[..len]), path);
+        Self::load_with_flags(
+            &path,
+            LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR | LOAD_LIBRARY_SEARCH_DEFAULT_DIRS,
+        )
+    }
+
+    fn load_with_flags(name: &str, flags: u32) -> io::Result<Library> {
+        let module = unsafe { LoadLibraryExW(wide(name).as_ptr(), std::ptr::null_mut(), flags) };
+        if module.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Library { module })
+    }
+
+    /// Return the function called `name`, which must be NUL-terminated.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be a function pointer type matching the signature of the function.
+    pub unsafe fn symbol<T: Copy>(&self, name: &[u8]) -> io::Result<T> {
+        debug_assert_eq!(mem::size_of::<T>(), mem::size_of::<*mut c_void>());
+        debug_assert_eq!(name.last(), Some(&0));
+        let symbol = GetProcAddress(self.module, name.as_ptr());
+        if symbol.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(mem::transmute_copy(&symbol))
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        unsafe {
+            FreeLibrary(self.module);
+        }
+    }
+}
+
+/// Wait until given event becomes signaled, but no longer than given timeout.
+pub fn wait_event(event: Handle, duration: Option<Duration>) -> io::Result<()> {
+    let milliseconds = match duration {
+        Some(duration) => duration.total_millis().min(INFINITE as u64 - 1) as u32,
+        None => INFINITE,
+    };
+    if unsafe { WaitForSingleObject(event, milliseconds) } == WAIT_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,164 @@
+use super::windows::{wait_event, wide, Handle, Library};
+use crate::time::Duration;
+use std::{io, ptr, slice};
+
+/// The size of the rings shared with the driver.
+const RING_CAPACITY: u32 = 0x400000;
+const ERROR_NO_MORE_ITEMS: i32 = 259;
+const ERROR_BUFFER_OVERFLOW: i32 = 111;
+
+type CreateAdapterFn =
+    unsafe extern "system" fn(name: *const u16, tunnel_type: *const u16, guid: *const u8) -> Handle;
+type OpenAdapterFn = unsafe extern "system" fn(name: *const u16) -> Handle;
+type CloseAdapterFn = unsafe extern "system" fn(adapter: Handle);
+type StartSessionFn = unsafe extern "system" fn(adapter: Handle, capacity: u32) -> Handle;
+type EndSessionFn = unsafe extern "system" fn(session: Handle);
+type GetReadWaitEventFn = unsafe extern "system" fn(session: Handle) -> Handle;
+type ReceivePacketFn = unsafe extern "system" fn(session: Handle, size: *mut u32) -> *mut u8;
+type ReleaseReceivePacketFn = unsafe extern "system" fn(session: Handle, packet: *const u8);
+type AllocateSendPacketFn = unsafe extern "system" fn(session: Handle, size: u32) -> *mut u8;
+type SendPacketFn = unsafe extern "system" fn(session: Handle, packet: *const u8);
+
+#[derive(Debug)]
+pub struct WintunDesc {
+    close_adapter: CloseAdapterFn,
+    end_session: EndSessionFn,
+    receive_packet: ReceivePacketFn,
+    release_receive_packet: ReleaseReceivePacketFn,
+    allocate_send_packet: AllocateSendPacketFn,
+    send_packet: SendPacketFn,
+    adapter: Handle,
+    session: Handle,
+    read_event: Handle,
+    // Unloaded after the session and the adapter are closed.
+    _library: Library,
+}
+
+impl WintunDesc {
+    pub fn new(name: &str) -> io::Result<WintunDesc> {
+        let library = Library::load("wintun.dll")?;
+        unsafe {
+            let create_adapter: CreateAdapterFn = library.symbol(b"WintunCreateAdapter\0")?;
+            let open_adapter: OpenAdapterFn = library.symbol(b"WintunOpenAdapter\0")?;
+            let close_adapter: CloseAdapterFn = library.symbol(b"WintunCloseAdapter\0")?;
+            let start_session: StartSessionFn = library.symbol(b"WintunStartSession\0")?;
+            let end_session: EndSessionFn = library.symbol(b"WintunEndSession\0")?;
+            let get_read_wait_event: GetReadWaitEventFn =
+                library.symbol(b"WintunGetReadWaitEvent\0")?;
+
+            let name = wide(name);
+            let mut adapter = open_adapter(name.as_ptr());
+            if adapter.is_null() {
+                adapter = create_adapter(name.as_ptr(), wide("smoltcp").as_ptr(), ptr::null());
+            }
+            if adapter.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let session = start_session(adapter, RING_CAPACITY);
+            if session.is_null() {
+                let err = io::Error::last_os_error();
+                close_adapter(adapter);
+                return Err(err);
+            }
+
+            Ok(WintunDesc {
+                close_adapter,
+                end_session,
+                receive_packet: library.symbol(b"WintunReceivePacket\0")?,
+                release_receive_packet: library.symbol(b"WintunReleaseReceivePacket\0")?,
+                allocate_send_packet: library.symbol(b"WintunAllocateSendPacket\0")?,
+                send_packet: library.symbol(b"WintunSendPacket\0")?,
+                adapter,
+                session,
+                read_event: get_read_wait_event(session),
+                _library: library,
+            })
+        }
+    }
+
+    /// Return the next received packet, in place in the receive ring, if any.
+    pub fn recv(&self) -> io::Result<WintunRxPacket<'_>> {
+        let mut len = 0;
+        let packet = unsafe { (self.receive_packet)(self.session, &mut len) };
+        if packet.is_null() {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(ERROR_NO_MORE_ITEMS) {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            return Err(err);
+        }
+        Ok(WintunRxPacket {
+            desc: self,
+            packet,
+            len: len as usize,
+        })
+    }
+
+    /// Return room for a packet of `len` octets in the send ring.
+    pub fn allocate(&self, len: usize) -> io::Result<WintunTxPacket<'_>> {
+        let packet = unsafe { (self.allocate_send_packet)(self.session, len as u32) };
+        if packet.is_null() {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(ERROR_BUFFER_OVERFLOW) {
+                return Err(io::ErrorKind::WouldBlock.into());
+            }
+            return Err(err);
+        }
+        Ok(WintunTxPacket {
+            desc: self,
+            packet,
+            len,
+        })
+    }
+
+    pub fn wait(&self, duration: Option<Duration>) -> io::Result<()> {
+        wait_event(self.read_event, duration)
+    }
+}
+
+impl Drop for WintunDesc {
+    fn drop(&mut self) {
+        unsafe {
+            (self.end_session)(self.session);
+            (self.close_adapter)(self.adapter);
+        }
+    }
+}
+
+/// A received packet, released from the receive ring when dropped.
+pub struct WintunRxPacket<'a> {
+    desc: &'a WintunDesc,
+    packet: *mut u8,
+    len: usize,
+}
+
+impl<'a> WintunRxPacket<'a> {
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.packet, self.len) }
+    }
+}
+
+impl<'a> Drop for WintunRxPacket<'a> {
+    fn drop(&mut self) {
+        unsafe { (self.desc.release_receive_packet)(self.desc.session, self.packet) }
+    }
+}
+
+/// A packet to send, sent from the send ring when dropped.
+pub struct WintunTxPacket<'a> {
+    desc: &'a WintunDesc,
+    packet: *mut u8,
+    len: usize,
+}
+
+impl<'a> WintunTxPacket<'a> {
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.packet, self.len) }
+    }
+}
+
+impl<'a> Drop for WintunTxPacket<'a> {
+    fn drop(&mut self) {
+        unsafe { (self.desc.send_packet)(self.desc.session, self.packet) }
+    }
+}
@@ -0,0 +1,495 @@
+use super::*;
+use crate::wire::EthernetFrame;
+use std::ffi::CString;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::vec::Vec;
+use std::{io, mem, ptr, slice};
+
+/// The number of entries of each ring, and of the frames used for receiving and for
+/// sending each.
+const RING_SIZE: u32 = 2048;
+/// The size of the UMEM frames, which hold one Ethernet frame each.
+const FRAME_SIZE: usize = 4096;
+/// The number of UMEM frames, the first half of which are used for receiving.
+const FRAME_COUNT: usize = 2 * RING_SIZE as usize;
+
+#[repr(C)]
+#[derive(Debug)]
+struct SockaddrXdp {
+    sxdp_family: u16,
+    sxdp_flags: u16,
+    sxdp_ifindex: u32,
+    sxdp_queue_id: u32,
+    sxdp_shared_umem_fd: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct XdpRingOffset {
+    producer: u64,
+    consumer: u64,
+    desc: u64,
+    flags: u64,
+}
+
+#[repr(C)]
+#[derive(Debug, Default)]
+struct XdpMmapOffsets {
+    rx: XdpRingOffset,
+    tx: XdpRingOffset,
+    fr: XdpRingOffset,
+    cr: XdpRingOffset,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+struct XdpUmemReg {
+    addr: u64,
+    len: u64,
+    chunk_size: u32,
+    headroom: u32,
+    flags: u32,
+    tx_metadata_len: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct XdpDesc {
+    addr: u64,
+    len: u32,
+    options: u32,
+}
+
+fn setsockopt<T>(lower: libc::c_int, name: libc::c_int, value: &T) -> io::Result<()> {
+    unsafe {
+        let res = libc::setsockopt(
+            lower,
+            imp::SOL_XDP,
+            name,
+            value as *const T as *const libc::c_void,
+            mem::size_of::<T>() as libc::socklen_t,
+        );
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+fn getsockopt<T: Default>(lower: libc::c_int, name: libc::c_int) -> io::Result<T> {
+    let mut value = T::default();
+    let mut len = mem::size_of::<T>() as libc::socklen_t;
+    unsafe {
+        let res = libc::getsockopt(
+            lower,
+            imp::SOL_XDP,
+            name,
+            &mut value as *mut T as *mut libc::c_void,
+            &mut len,
+        );
+        if res == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    // Kernels older than 5.4 don't have the flags of the rings.
+    if len as usize != mem::size_of::<T>() {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "AF_XDP socket option too short",
+        ));
+    }
+    Ok(value)
+}
+
+/// Wake the kernel up after the ring it was waiting on was produced to, with an empty
+/// `sendto` for the TX ring, or an empty `recvfrom` for the fill ring.
+fn wakeup(lower: libc::c_int, tx: bool) {
+    let res = unsafe {
+        if tx {
+            libc::sendto(lower, ptr::null(), 0, libc::MSG_DONTWAIT, ptr::null(), 0)
+        } else {
+            libc::recvfrom(
+                lower,
+                ptr::null_mut(),
+                0,
+                libc::MSG_DONTWAIT,
+                ptr::null_mut(),
+                ptr::null_mut(),
+            )
+        }
+    };
+    if res == -1 {
+        // The kernel is busy with the ring already, or the link is down.
+        net_trace!("phy: AF_XDP wakeup failed: {}", io::Error::last_os_error());
+    }
+}
+
+/// A single-producer, single-consumer ring shared with the kernel.
+#[derive(Debug)]
+struct Ring<T> {
+    producer: *const AtomicU32,
+    consumer: *const AtomicU32,
+    flags: *const AtomicU32,
+    descs: *mut T,
+    map: *mut libc::c_void,
+    map_len: usize,
+}
+
+impl<T: Copy> Ring<T> {
+    fn map(lower: libc::c_int, offsets: &XdpRingOffset, pgoff: u64) -> io::Result<Ring<T>> {
+        let map_len = offsets.desc as usize + RING_SIZE as usize * mem::size_of::<T>();
+        unsafe {
+            let map = libc::mmap(
+                ptr::null_mut(),
+                map_len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                lower,
+                pgoff as libc::off_t,
+            );
+            if map == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            let at = |offset: u64| (map as *mut u8).add(offset as usize);
+            Ok(Ring {
+                producer: at(offsets.producer) as *const AtomicU32,
+                consumer: at(offsets.consumer) as *const AtomicU32,
+                flags: at(offsets.flags) as *const AtomicU32,
+                descs: at(offsets.desc) as *mut T,
+                map,
+                map_len,
+            })
+        }
+    }
+
+    fn producer(&self) -> &AtomicU32 {
+        unsafe { &*self.producer }
+    }
+
+    fn consumer(&self) -> &AtomicU32 {
+        unsafe { &*self.consumer }
+    }
+
+    /// Return whether the kernel has to be woken up to process the ring.
+    fn needs_wakeup(&self) -> bool {
+        unsafe { (*self.flags).load(Ordering::Relaxed) & imp::XDP_RING_NEED_WAKEUP != 0 }
+    }
+
+    /// Consume the next entry produced by the kernel, if any.
+    fn pop(&mut self) -> Option<T> {
+        let consumer = self.consumer().load(Ordering::Relaxed);
+        if self.producer().load(Ordering::Acquire) == consumer {
+            return None;
+        }
+        let entry = unsafe { self.descs.add((consumer % RING_SIZE) as usize).read() };
+        self.consumer()
+            .store(consumer.wrapping_add(1), Ordering::Release);
+        Some(entry)
+    }
+
+    /// Produce an entry for the kernel, if there is room for it.
+    fn push(&mut self, entry: T) -> bool {
+        let producer = self.producer().load(Ordering::Relaxed);
+        if producer.wrapping_sub(self.consumer().load(Ordering::Acquire)) == RING_SIZE {
+            return false;
+        }
+        unsafe { self.descs.add((producer % RING_SIZE) as usize).write(entry) };
+        self.producer()
+            .store(producer.wrapping_add(1), Ordering::Release);
+        true
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.map, self.map_len);
+        }
+    }
+}
+
+/// The memory area the frames are received into and sent from, registered with the kernel.
+#[derive(Debug)]
+struct Umem {
+    area: *mut u8,
+    len: usize,
+}
+
+impl Umem {
+    fn new() -> io::Result<Umem> {
+        let len = FRAME_COUNT * FRAME_SIZE;
+        unsafe {
+            let area = libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            );
+            if area == libc::MAP_FAILED {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Umem {
+                area: area as *mut u8,
+                len,
+            })
+        }
+    }
+}
+
+impl Drop for Umem {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.area as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+/// The receiving half of an AF_XDP socket.
+#[derive(Debug)]
+pub struct XdpRxQueue {
+    lower: libc::c_int,
+    rx: Ring<XdpDesc>,
+    fill: Ring<u64>,
+    area: *mut u8,
+}
+
+impl XdpRxQueue {
+    /// Return the address and length in the UMEM of the next received frame, if any.
+    pub fn recv(&mut self) -> Option<(u64, usize)> {
+        match self.rx.pop() {
+            Some(desc) => Some((desc.addr, desc.len as usize)),
+            None => {
+                if self.fill.needs_wakeup() {
+                    wakeup(self.lower, false);
+                }
+                None
+            }
+        }
+    }
+
+    /// Return a received frame, in place.
+    pub fn frame_mut(&mut self, addr: u64, len: usize) -> &mut [u8] {
+        // The frame is owned by the application from its reception until it is recycled.
+        unsafe { slice::from_raw_parts_mut(self.area.add(addr as usize), len) }
+    }
+
+    /// Give a received frame back to the kernel, for it to receive another one into.
+    pub fn recycle(&mut self, addr: u64) {
+        // NOTE: the fill ring has room for all the receive frames.
+        let chunk = addr - addr % FRAME_SIZE as u64;
+        let pushed = self.fill.push(chunk);
+        debug_assert!(pushed);
+    }
+}
+
+/// The sending half of an AF_XDP socket.
+#[derive(Debug)]
+pub struct XdpTxQueue {
+    lower: libc::c_int,
+    tx: Ring<XdpDesc>,
+    completion: Ring<u64>,
+    area: *mut u8,
+    /// The frames that are not queued for sending.
+    free: Vec<u64>,
+}
+
+impl XdpTxQueue {
+    /// Take back the frames that the kernel is done sending.
+    fn reclaim(&mut self) {
+        while let Some(addr) = self.completion.pop() {
+            self.free.push(addr);
+        }
+    }
+
+    /// Return whether a frame can be sent.
+    pub fn can_send(&mut self) -> bool {
+        self.reclaim();
+        !self.free.is_empty()
+    }
+
+    /// Take a frame to send, if any is free, and return its address in the UMEM.
+    pub fn reserve(&mut self) -> Option<u64> {
+        self.reclaim();
+        self.free.pop()
+    }
+
+    /// Return a reserved frame, to be filled in place.
+    pub fn frame_mut(&mut self, addr: u64, len: usize) -> &mut [u8] {
+        // The frame is owned by the application from its reservation until it is sent.
+        unsafe { slice::from_raw_parts_mut(self.area.add(addr as usize), len) }
+    }
+
+    /// Queue the first `len` octets of a reserved frame for sending.
+    pub fn send(&mut self, addr: u64, len: usize) {
+        // NOTE: the TX ring has room for all the send frames.
+        let pushed = self.tx.push(XdpDesc {
+            addr,
+            len: len as u32,
+            options: 0,
+        });
+        debug_assert!(pushed);
+        if self.tx.needs_wakeup() {
+            wakeup(self.lower, true);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct XdpSocketDesc {
+    pub rx: XdpRxQueue,
+    pub tx: XdpTxQueue,
+    lower: libc::c_int,
+    mtu: usize,
+    // Unmapped after the socket is closed.
+    _umem: Umem,
+}
+
+impl AsRawFd for XdpSocketDesc {
+    fn as_raw_fd(&self) -> RawFd {
+        self.lower
+    }
+}
+
+impl XdpSocketDesc {
+    pub fn new(name: &str, queue_id: u32) -> io::Result<XdpSocketDesc> {
+        let cname = CString::new(name)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid interface name"))?;
+        let ifindex = unsafe { libc::if_nametoindex(cname.as_ptr()) };
+        if ifindex == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mtu = Self::mtu_ifreq(&mut ifreq_for(name))?;
+
+        let lower = unsafe {
+            let lower = libc::socket(imp::AF_XDP, libc::SOCK_RAW | libc::SOCK_CLOEXEC, 0);
+            if lower == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            lower
+        };
+
+        let desc = Self::setup(lower, ifindex, queue_id, mtu);
+        if desc.is_err() {
+            unsafe {
+                libc::close(lower);
+            }
+        }
+        desc
+    }
+
+    fn setup(
+        lower: libc::c_int,
+        ifindex: u32,
+        queue_id: u32,
+        mtu: usize,
+    ) -> io::Result<XdpSocketDesc> {
+        let umem = Umem::new()?;
+        setsockopt(
+            lower,
+            imp::XDP_UMEM_REG,
+            &XdpUmemReg {
+                addr: umem.area as u64,
+                len: umem.len as u64,
+                chunk_size: FRAME_SIZE as u32,
+                headroom: 0,
+                flags: 0,
+                tx_metadata_len: 0,
+            },
+        )?;
+        setsockopt(lower, imp::XDP_UMEM_FILL_RING, &RING_SIZE)?;
+        setsockopt(lower, imp::XDP_UMEM_COMPLETION_RING, &RING_SIZE)?;
+        setsockopt(lower, imp::XDP_RX_RING, &RING_SIZE)?;
+        setsockopt(lower, imp::XDP_TX_RING, &RING_SIZE)?;
+
+        let offsets: XdpMmapOffsets = getsockopt(lower, imp::XDP_MMAP_OFFSETS)?;
+        let mut rx = XdpRxQueue {
+            lower,
+            rx: Ring::map(lower, &offsets.rx, imp::XDP_PGOFF_RX_RING)?,
+            fill: Ring::map(lower, &offsets.fr, imp::XDP_UMEM_PGOFF_FILL_RING)?,
+            area: umem.area,
+        };
+        let tx = XdpTxQueue {
+            lower,
+            tx: Ring::map(lower, &offsets.tx, imp::XDP_PGOFF_TX_RING)?,
+            completion: Ring::map(lower, &offsets.cr, imp::XDP_UMEM_PGOFF_COMPLETION_RING)?,
+            area: umem.area,
+            free: (RING_SIZE as usize..FRAME_COUNT)
+                .map(|frame| (frame * FRAME_SIZE) as u64)
+                .collect(),
+        };
+        for frame in 0..RING_SIZE as usize {
+            rx.recycle((frame * FRAME_SIZE) as u64);
+        }
+
+        // The kernel uses zero-copy mode if the driver supports it, and copy mode otherwise.
+        let sockaddr = SockaddrXdp {
+            sxdp_family: imp::AF_XDP as u16,
+            sxdp_flags: imp::XDP_USE_NEED_WAKEUP,
+            sxdp_ifindex: ifindex,
+            sxdp_queue_id: queue_id,
+            sxdp_shared_umem_fd: 0,
+        };
+        unsafe {
+            let res = libc::bind(
+                lower,
+                &sockaddr as *const SockaddrXdp as *const libc::sockaddr,
+                mem::size_of::<SockaddrXdp>() as libc::socklen_t,
+            );
+            if res == -1 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(XdpSocketDesc {
+            rx,
+            tx,
+            lower,
+            mtu,
+            _umem: umem,
+        })
+    }
+
+    fn mtu_ifreq(ifr: &mut ifreq) -> io::Result<usize> {
+        let lower = unsafe {
+            let lower = libc::socket(libc::AF_INET, libc::SOCK_DGRAM, libc::IPPROTO_IP);
+            if lower == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            lower
+        };
+
+        let ip_mtu = ifreq_ioctl(lower, ifr, imp::SIOCGIFMTU).map(|mtu| mtu as usize);
+
+        unsafe {
+            libc::close(lower);
+        }
+
+        // SIOCGIFMTU returns the IP MTU (typically 1500 bytes.)
+        // smoltcp counts the entire Ethernet packet in the MTU, so add the Ethernet header size to it.
+        let mtu = ip_mtu? + EthernetFrame::<&[u8]>::header_len();
+
+        // In copy mode, the kernel receives the frames after some headroom.
+        Ok(mtu.min(FRAME_SIZE - imp::XDP_PACKET_HEADROOM))
+    }
+
+    pub fn interface_mtu(&self) -> usize {
+        self.mtu
+    }
+
+    /// Return whether the frames are received and sent without being copied by the kernel.
+    pub fn zero_copy(&self) -> io::Result<bool> {
+        let flags: u32 = getsockopt(self.lower, imp::XDP_OPTIONS)?;
+        Ok(flags & imp::XDP_OPTIONS_ZEROCOPY != 0)
+    }
+}
+
+impl Drop for XdpSocketDesc {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.lower);
+        }
+    }
+}
@@ -8,6 +8,9 @@ use crate::phy::{self, sys, Device, DeviceCapabilities, Medium};
 use crate::time::Instant;
 
 /// A virtual TUN (IP) or TAP (Ethernet) interface.
+///
+/// On macOS, a utun interface is used as TUN interface, and one end of a pair of feth
+/// interfaces as TAP interface.
 #[derive(Debug)]
 pub struct TunTapInterface {
     lower: Rc<RefCell<sys::TunTapInterfaceDesc>>,
@@ -27,6 +30,10 @@ impl TunTapInterface {
     /// If `name` is a persistent interface configured with UID of the current user,
     /// no special privileges are needed. Otherwise, this requires superuser privileges
     /// or a corresponding capability set on the executable.
+    ///
+    /// On macOS, `name` is either `utunN`, the utun interface created for a [Medium::Ip]
+    /// device, or the name of an existing feth interface for a [Medium::Ethernet] device,
+    /// whose peer is configured on the host. This requires superuser privileges.
     pub fn new(name: &str, medium: Medium) -> io::Result<TunTapInterface> {
         let lower = sys::TunTapInterfaceDesc::new(name, medium)?;
         let mtu = lower.interface_mtu()?;
@@ -42,7 +49,7 @@ impl TunTapInterface {
     /// On platforms like Android, a file descriptor to a tun interface is exposed.
     /// On these platforms, a TunTapInterface cannot be instantiated with a name.
     pub fn from_fd(fd: RawFd, medium: Medium, mtu: usize) -> io::Result<TunTapInterface> {
-        let lower = sys::TunTapInterfaceDesc::from_fd(fd, mtu)?;
+        let lower = sys::TunTapInterfaceDesc::from_fd(fd, medium, mtu)?;
         Ok(TunTapInterface {
             lower: Rc::new(RefCell::new(lower)),
             mtu,
@@ -0,0 +1,995 @@
+use core::fmt;
+use core::sync::atomic::{fence, AtomicU16, Ordering};
+
+use byteorder::{ByteOrder, LittleEndian, NetworkEndian};
+
+use crate::phy::{self, Checksum, Device, DeviceCapabilities, Medium};
+use crate::time::Instant;
+use crate::wire::ip::checksum;
+use crate::wire::{EthernetAddress, EthernetFrame, EthernetProtocol, IpAddress, IpProtocol};
+
+/// The size of each buffer of a [VirtioNetMemory], including the virtio-net header.
+pub const VIRTIO_NET_BUFFER_LEN: usize = 2048;
+
+/// The size of `struct virtio_net_hdr_v1`, which precedes each frame.
+const HEADER_LEN: usize = 12;
+const ETHERNET_HEADER_LEN: usize = 14;
+/// The MTU of the device when it does not tell it.
+const DEFAULT_MTU: usize = 1500;
+
+const QUEUE_RX: u16 = 0;
+const QUEUE_TX: u16 = 1;
+
+mod status {
+    pub const ACKNOWLEDGE: u8 = 1;
+    pub const DRIVER: u8 = 2;
+    pub const DRIVER_OK: u8 = 4;
+    pub const FEATURES_OK: u8 = 8;
+    pub const FAILED: u8 = 128;
+}
+
+mod feature {
+    pub const CSUM: u64 = 1 << 0;
+    pub const GUEST_CSUM: u64 = 1 << 1;
+    pub const MTU: u64 = 1 << 3;
+    pub const MAC: u64 = 1 << 5;
+    pub const VERSION_1: u64 = 1 << 32;
+    pub const ACCESS_PLATFORM: u64 = 1 << 33;
+}
+
+const VIRTQ_DESC_F_WRITE: u16 = 2;
+const VIRTQ_AVAIL_F_NO_INTERRUPT: u16 = 1;
+const VIRTQ_USED_F_NO_NOTIFY: u16 = 1;
+
+const VIRTIO_NET_HDR_F_NEEDS_CSUM: u8 = 1;
+
+/// Error type for [`VirtioNetDevice::new`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum VirtioNetError {
+    /// The device does not implement version 1.0 or later of the virtio specification.
+    Unsupported,
+    /// The device did not accept the negotiated features.
+    FeaturesRejected,
+    /// The receive or the transmit virtqueue of the device is not available.
+    QueueUnavailable,
+}
+
+impl fmt::Display for VirtioNetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VirtioNetError::Unsupported => write!(f, "Unsupported"),
+            VirtioNetError::FeaturesRejected => write!(f, "FeaturesRejected"),
+            VirtioNetError::QueueUnavailable => write!(f, "QueueUnavailable"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VirtioNetError {}
+
+/// The transport of a virtio device, e.g. virtio over MMIO or over PCI, through which a
+/// [VirtioNetDevice] accesses the registers of the device.
+///
+/// The transport is implemented by the user for the platform, on top of the memory-mapped
+/// registers or of the PCI capabilities of the device. Interrupts are left to the user as
+/// well: the device only needs to be polled once the receive queue raised one.
+pub trait VirtioTransport {
+    /// Return the features offered by the device.
+    fn device_features(&mut self) -> u64;
+
+    /// Set the features accepted by the driver.
+    fn set_driver_features(&mut self, features: u64);
+
+    /// Return the device status.
+    fn status(&mut self) -> u8;
+
+    /// Set the device status, where 0 resets the device.
+    fn set_status(&mut self, status: u8);
+
+    /// Return the maximum size of the virtqueue `queue`, or 0 if it is not available.
+    fn max_queue_size(&mut self, queue: u16) -> u16;
+
+    /// Set the size of the virtqueue `queue`, and the addresses of its descriptor table,
+    /// driver area and device area, then enable it.
+    fn set_queue(
+        &mut self,
+        queue: u16,
+        size: u16,
+        descriptors: u64,
+        driver_area: u64,
+        device_area: u64,
+    );
+
+    /// Notify the device that buffers were made available in the virtqueue `queue`.
+    fn notify(&mut self, queue: u16);
+
+    /// Return the octet at `offset` in the device-specific configuration space.
+    fn read_config(&mut self, offset: usize) -> u8;
+}
+
+#[repr(C, align(16))]
+#[derive(Debug)]
+struct Descriptor {
+    addr: u64,
+    len: u32,
+    flags: u16,
+    next: u16,
+}
+
+impl Descriptor {
+    const EMPTY: Descriptor = Descriptor {
+        addr: 0,
+        len: 0,
+        flags: 0,
+        next: 0,
+    };
+}
+
+#[repr(C)]
+#[derive(Debug)]
+struct AvailRing<const N: usize> {
+    flags: u16,
+    idx: AtomicU16,
+    ring: [u16; N],
+    used_event: u16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct UsedElem {
+    id: u32,
+    len: u32,
+}
+
+#[repr(C, align(4))]
+#[derive(Debug)]
+struct UsedRing<const N: usize> {
+    flags: AtomicU16,
+    idx: AtomicU16,
+    ring: [UsedElem; N],
+    avail_event: u16,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+struct Virtqueue<const N: usize> {
+    descriptors: [Descriptor; N],
+    avail: AvailRing<N>,
+    used: UsedRing<N>,
+}
+
+impl<const N: usize> Virtqueue<N> {
+    const fn new() -> Self {
+        Virtqueue {
+            descriptors: [Descriptor::EMPTY; N],
+            avail: AvailRing {
+                flags: 0,
+                idx: AtomicU16::new(0),
+                ring: [0; N],
+                used_event: 0,
+            },
+            used: UsedRing {
+                flags: AtomicU16::new(0),
+                idx: AtomicU16::new(0),
+                ring: [UsedElem { id: 0, len: 0 }; N],
+                avail_event: 0,
+            },
+        }
+    }
+}
+
+/// The memory shared by a [VirtioNetDevice] with the device: its receive and transmit
+/// virtqueues of `N` entries, and a buffer of [VIRTIO_NET_BUFFER_LEN] octets for each.
+///
+/// The memory must be accessible to the device, at an address known to the driver, e.g.
+/// in a `static` of an identity-mapped unikernel. `N` must be a power of two.
+#[repr(C, align(4096))]
+#[derive(Debug)]
+pub struct VirtioNetMemory<const N: usize> {
+    rx: Virtqueue<N>,
+    tx: Virtqueue<N>,
+    rx_buffers: [[u8; VIRTIO_NET_BUFFER_LEN]; N],
+    tx_buffers: [[u8; VIRTIO_NET_BUFFER_LEN]; N],
+}
+
+impl<const N: usize> VirtioNetMemory<N> {
+    pub const fn new() -> Self {
+        VirtioNetMemory {
+            rx: Virtqueue::new(),
+            tx: Virtqueue::new(),
+            rx_buffers: [[0; VIRTIO_NET_BUFFER_LEN]; N],
+            tx_buffers: [[0; VIRTIO_NET_BUFFER_LEN]; N],
+        }
+    }
+}
+
+impl<const N: usize> Default for VirtioNetMemory<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+struct Queue<'a, const N: usize> {
+    index: u16,
+    size: u16,
+    queue: &'a mut Virtqueue<N>,
+    buffers: &'a mut [[u8; VIRTIO_NET_BUFFER_LEN]; N],
+    avail_idx: u16,
+    last_used_idx: u16,
+    // Whether buffers were made available since the device was last notified.
+    pending: bool,
+}
+
+impl<'a, const N: usize> Queue<'a, N> {
+    /// Make the buffer `id` available to the device, with `len` octets.
+    fn push(&mut self, id: u16, len: usize) {
+        self.queue.descriptors[id as usize].len = len as u32;
+        self.queue.avail.ring[(self.avail_idx % self.size) as usize] = id;
+        self.avail_idx = self.avail_idx.wrapping_add(1);
+        // The ring entry must be written before the index.
+        self.queue
+            .avail
+            .idx
+            .store(self.avail_idx, Ordering::Release);
+        self.pending = true;
+    }
+
+    /// Return the next buffer used by the device, with the number of octets it wrote to it.
+    fn pop_used(&mut self) -> Option<(u16, usize)> {
+        if self.queue.used.idx.load(Ordering::Acquire) == self.last_used_idx {
+            return None;
+        }
+        let elem = self.queue.used.ring[(self.last_used_idx % self.size) as usize];
+        self.last_used_idx = self.last_used_idx.wrapping_add(1);
+        Some((elem.id as u16, elem.len as usize))
+    }
+
+    /// Notify the device of the buffers made available since it was last notified, if it
+    /// wants to be.
+    fn notify<T: VirtioTransport>(&mut self, transport: &mut T) {
+        if !self.pending {
+            return;
+        }
+        self.pending = false;
+        // The index must be written before the flags are read.
+        fence(Ordering::SeqCst);
+        if self.queue.used.flags.load(Ordering::Acquire) & VIRTQ_USED_F_NO_NOTIFY == 0 {
+            transport.notify(self.index);
+        }
+    }
+}
+
+/// A virtio-net device, driven through a [VirtioTransport] and a [VirtioNetMemory], for
+/// unikernels and other `no_std` guests of a hypervisor.
+///
+/// The device offloads the computation of the TCP checksums of the frames sent, if it
+/// supports it; UDP checksums are still computed, as those of fragmented datagrams cannot
+/// be offloaded. The partial checksums of the frames received from other guests of the
+/// host, if any, are completed before the frames are handed over.
+#[derive(Debug)]
+pub struct VirtioNetDevice<'a, T: VirtioTransport, const N: usize> {
+    transport: T,
+    rx: Queue<'a, N>,
+    tx: Queue<'a, N>,
+    tx_free: [u16; N],
+    tx_free_len: usize,
+    features: u64,
+    hardware_addr: Option<EthernetAddress>,
+    mtu: usize,
+}
+
+impl<'a, T: VirtioTransport, const N: usize> VirtioNetDevice<'a, T, N> {
+    /// Initialize the virtio-net device behind `transport`, with its virtqueues and buffers
+    /// in `memory`, at the physical address `phys_addr`, i.e. the address at which the
+    /// device accesses it.
+    ///
+    /// # Panics
+    /// This function panics if `N` is not a power of two, or larger than 32768.
+    pub fn new(
+        mut transport: T,
+        memory: &'a mut VirtioNetMemory<N>,
+        phys_addr: u64,
+    ) -> Result<Self, VirtioNetError> {
+        assert!(N.is_power_of_two() && N <= 32768);
+
+        transport.set_status(0);
+        transport.set_status(status::ACKNOWLEDGE | status::DRIVER);
+
+        match Self::negotiate(&mut transport) {
+            Ok(features) => {
+                let base = memory as *const VirtioNetMemory<N> as usize;
+                let phys = |addr: usize| phys_addr + (addr - base) as u64;
+                let VirtioNetMemory {
+                    rx,
+                    tx,
+                    rx_buffers,
+                    tx_buffers,
+                } = memory;
+
+                let rx = Self::setup_queue(&mut transport, QUEUE_RX, rx, rx_buffers, &phys);
+                let tx = Self::setup_queue(&mut transport, QUEUE_TX, tx, tx_buffers, &phys);
+                let (Some(mut rx), Some(tx)) = (rx, tx) else {
+                    transport.set_status(status::FAILED);
+                    return Err(VirtioNetError::QueueUnavailable);
+                };
+
+                for id in 0..rx.size {
+                    rx.queue.descriptors[id as usize].flags = VIRTQ_DESC_F_WRITE;
+                    rx.push(id, VIRTIO_NET_BUFFER_LEN);
+                }
+                // The transmitted buffers are reclaimed when sending, not on interrupts.
+                tx.queue.avail.flags = VIRTQ_AVAIL_F_NO_INTERRUPT;
+                let mut tx_free = [0; N];
+                for (id, free) in tx_free.iter_mut().enumerate() {
+                    *free = id as u16;
+                }
+
+                let hardware_addr = (features & feature::MAC != 0).then(|| {
+                    let mut addr = [0; 6];
+                    for (offset, octet) in addr.iter_mut().enumerate() {
+                        *octet = transport.read_config(offset);
+                    }
+                    EthernetAddress(addr)
+                });
+                let mtu = if features & feature::MTU != 0 {
+                    Self::read_mtu(&mut transport)
+                } else {
+                    DEFAULT_MTU
+                };
+
+                transport.set_status(
+                    status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK | status::DRIVER_OK,
+                );
+                let mut device = VirtioNetDevice {
+                    transport,
+                    rx,
+                    tx_free_len: tx.size as usize,
+                    tx,
+                    tx_free,
+                    features,
+                    hardware_addr,
+                    mtu,
+                };
+                device.rx.notify(&mut device.transport);
+                Ok(device)
+            }
+            Err(err) => {
+                transport.set_status(status::FAILED);
+                Err(err)
+            }
+        }
+    }
+
+    fn negotiate(transport: &mut T) -> Result<u64, VirtioNetError> {
+        let offered = transport.device_features();
+        if offered & feature::VERSION_1 == 0 {
+            return Err(VirtioNetError::Unsupported);
+        }
+
+        let mut features = offered
+            & (feature::VERSION_1
+                | feature::ACCESS_PLATFORM
+                | feature::MAC
+                | feature::MTU
+                | feature::CSUM
+                | feature::GUEST_CSUM);
+        // Frames larger than the buffers can not be received.
+        if features & feature::MTU != 0
+            && Self::read_mtu(transport) + ETHERNET_HEADER_LEN > VIRTIO_NET_BUFFER_LEN - HEADER_LEN
+        {
+            features &= !feature::MTU;
+        }
+
+        transport.set_driver_features(features);
+        transport.set_status(status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK);
+        if transport.status() & status::FEATURES_OK == 0 {
+            return Err(VirtioNetError::FeaturesRejected);
+        }
+        Ok(features)
+    }
+
+    fn read_mtu(transport: &mut T) -> usize {
+        u16::from_le_bytes([transport.read_config(10), transport.read_config(11)]) as usize
+    }
+
+    fn setup_queue(
+        transport: &mut T,
+        index: u16,
+        queue: &'a mut Virtqueue<N>,
+        buffers: &'a mut [[u8; VIRTIO_NET_BUFFER_LEN]; N],
+        phys: &impl Fn(usize) -> u64,
+    ) -> Option<Queue<'a, N>> {
+        let max_size = transport.max_queue_size(index);
+        if max_size == 0 {
+            return None;
+        }
+        // The largest power of two that both the device and the memory allow.
+        let size = (N as u16).min(1 << (15 - max_size.leading_zeros()));
+
+        for (descriptor, buffer) in queue.descriptors.iter_mut().zip(buffers.iter()) {
+            descriptor.addr = phys(buffer.as_ptr() as usize);
+        }
+        transport.set_queue(
+            index,
+            size,
+            phys(&queue.descriptors as *const _ as usize),
+            phys(&queue.avail as *const _ as usize),
+            phys(&queue.used as *const _ as usize),
+        );
+
+        Some(Queue {
+            index,
+            size,
+            queue,
+            buffers,
+            avail_idx: 0,
+            last_used_idx: 0,
+            pending: false,
+        })
+    }
+
+    /// Return the hardware address of the device, if it has one.
+    pub fn hardware_addr(&self) -> Option<EthernetAddress> {
+        self.hardware_addr
+    }
+
+    /// Return the features negotiated with the device.
+    pub fn features(&self) -> u64 {
+        self.features
+    }
+
+    /// Return a reference to the transport, e.g. to acknowledge interrupts.
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Return a mutable reference to the transport, e.g. to acknowledge interrupts.
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Return the transmitted buffers to the free list.
+    fn reclaim_tx(&mut self) {
+        while let Some((id, _)) = self.tx.pop_used() {
+            self.tx_free[self.tx_free_len] = id;
+            self.tx_free_len += 1;
+        }
+    }
+}
+
+impl<'a, T: VirtioTransport, const N: usize> Drop for VirtioNetDevice<'a, T, N> {
+    fn drop(&mut self) {
+        // Stop the device from accessing the memory.
+        self.transport.set_status(0);
+    }
+}
+
+impl<'a, T: VirtioTransport, const N: usize> Device for VirtioNetDevice<'a, T, N> {
+    type RxToken<'b>
+        = RxToken<'b, 'a, N>
+    where
+        Self: 'b;
+    type TxToken<'b>
+        = TxToken<'b, 'a, T, N>
+    where
+        Self: 'b;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities {
+            max_transmission_unit: self.mtu + ETHERNET_HEADER_LEN,
+            medium: Medium::Ethernet,
+            ..DeviceCapabilities::default()
+        };
+        if self.features & feature::CSUM != 0 {
+            caps.checksum.tcp = Checksum::Rx;
+        }
+        caps
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        // Give the buffers received since the last time back to the device.
+        self.rx.notify(&mut self.transport);
+        self.reclaim_tx();
+        if self.tx_free_len == 0 {
+            return None;
+        }
+        let (id, len) = self.rx.pop_used()?;
+        let rx = RxToken {
+            queue: &mut self.rx,
+            id,
+            len: len.clamp(HEADER_LEN, VIRTIO_NET_BUFFER_LEN),
+        };
+        let tx = TxToken {
+            queue: &mut self.tx,
+            transport: &mut self.transport,
+            id: self.tx_free[self.tx_free_len - 1],
+            free_len: &mut self.tx_free_len,
+            offload: self.features & feature::CSUM != 0,
+        };
+        Some((rx, tx))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        self.reclaim_tx();
+        if self.tx_free_len == 0 {
+            return None;
+        }
+        Some(TxToken {
+            queue: &mut self.tx,
+            transport: &mut self.transport,
+            id: self.tx_free[self.tx_free_len - 1],
+            free_len: &mut self.tx_free_len,
+            offload: self.features & feature::CSUM != 0,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken<'b, 'a, const N: usize> {
+    queue: &'b mut Queue<'a, N>,
+    id: u16,
+    len: usize,
+}
+
+impl<'b, 'a, const N: usize> phy::RxToken for RxToken<'b, 'a, N> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let buffer = &mut self.queue.buffers[self.id as usize][..self.len];
+        let (header, frame) = buffer.split_at_mut(HEADER_LEN);
+        if header[0] & VIRTIO_NET_HDR_F_NEEDS_CSUM != 0 {
+            let start = LittleEndian::read_u16(&header[6..8]) as usize;
+            let offset = LittleEndian::read_u16(&header[8..10]) as usize;
+            complete_checksum(frame, start, offset);
+        }
+        f(frame)
+    }
+}
+
+impl<'b, 'a, const N: usize> Drop for RxToken<'b, 'a, N> {
+    fn drop(&mut self) {
+        self.queue.push(self.id, VIRTIO_NET_BUFFER_LEN);
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<'b, 'a, T: VirtioTransport, const N: usize> {
+    queue: &'b mut Queue<'a, N>,
+    transport: &'b mut T,
+    id: u16,
+    free_len: &'b mut usize,
+    offload: bool,
+}
+
+impl<'b, 'a, T: VirtioTransport, const N: usize> phy::TxToken for TxToken<'b, 'a, T, N> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        *self.free_len -= 1;
+        let buffer = &mut self.queue.buffers[self.id as usize][..HEADER_LEN + len];
+        let (header, frame) = buffer.split_at_mut(HEADER_LEN);
+        header.fill(0);
+        let result = f(frame);
+        if self.offload {
+            offload_checksum(header, frame);
+        }
+        self.queue.push(self.id, HEADER_LEN + len);
+        self.queue.notify(self.transport);
+        result
+    }
+}
+
+/// Complete the partial checksum at `offset` in the data starting at `start` in `frame`,
+/// which holds the checksum of the pseudo header.
+fn complete_checksum(frame: &mut [u8], start: usize, offset: usize) {
+    if start + offset + 2 > frame.len() {
+        return;
+    }
+    let checksum = !checksum::data(&frame[start..]);
+    NetworkEndian::write_u16(&mut frame[start + offset..], checksum);
+}
+
+/// Leave the computation of the TCP checksum of `frame`, if any, to the device: set the
+/// checksum to the one of the pseudo header, and tell the device where the data to checksum
+/// starts in `header`.
+fn offload_checksum(header: &mut [u8], frame: &mut [u8]) {
+    let Ok(ethernet) = EthernetFrame::new_checked(&*frame) else {
+        return;
+    };
+    let (src_addr, dst_addr, next_header, ip_header_len, payload_len): (
+        IpAddress,
+        IpAddress,
+        IpProtocol,
+        usize,
+        usize,
+    ) = match ethernet.ethertype() {
+        #[cfg(feature = "proto-ipv4")]
+        EthernetProtocol::Ipv4 => {
+            let Ok(packet) = crate::wire::Ipv4Packet::new_checked(ethernet.payload()) else {
+                return;
+            };
+            let header_len = packet.header_len() as usize;
+            (
+                packet.src_addr().into(),
+                packet.dst_addr().into(),
+                packet.next_header(),
+                header_len,
+                packet.total_len() as usize - header_len,
+            )
+        }
+        #[cfg(feature = "proto-ipv6")]
+        EthernetProtocol::Ipv6 => {
+            let Ok(packet) = crate::wire::Ipv6Packet::new_checked(ethernet.payload()) else {
+                return;
+            };
+            (
+                packet.src_addr().into(),
+                packet.dst_addr().into(),
+                packet.next_header(),
+                packet.header_len(),
+                packet.payload_len() as usize,
+            )
+        }
+        _ => return,
+    };
+    if next_header != IpProtocol::Tcp {
+        return;
+    }
+
+    // The offset of the checksum in the TCP header.
+    const CHECKSUM_OFFSET: usize = 16;
+    let start = ETHERNET_HEADER_LEN + ip_header_len;
+    if start + CHECKSUM_OFFSET + 2 > frame.len() {
+        return;
+    }
+    let checksum = checksum::pseudo_header(&src_addr, &dst_addr, next_header, payload_len as u32);
+    NetworkEndian::write_u16(&mut frame[start + CHECKSUM_OFFSET..], checksum);
+
+    header[0] = VIRTIO_NET_HDR_F_NEEDS_CSUM;
+    LittleEndian::write_u16(&mut header[6..8], start as u16);
+    LittleEndian::write_u16(&mut header[8..10], CHECKSUM_OFFSET as u16);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::phy::{RxToken as _, TxToken as _};
+
+    const PHYS_ADDR: u64 = 0x8000_0000;
+    const MAC: [u8; 6] = [0x52, 0x54, 0x00, 0x12, 0x34, 0x56];
+
+    #[derive(Debug)]
+    struct MockTransport {
+        features: u64,
+        driver_features: u64,
+        status: u8,
+        reject_features: bool,
+        max_queue_size: u16,
+        queues: Vec<(u16, u16, u64, u64, u64)>,
+        notified: Vec<u16>,
+        mtu: u16,
+    }
+
+    impl MockTransport {
+        fn new(features: u64) -> MockTransport {
+            MockTransport {
+                features,
+                driver_features: 0,
+                status: 0,
+                reject_features: false,
+                max_queue_size: 256,
+                queues: Vec::new(),
+                notified: Vec::new(),
+                mtu: 1500,
+            }
+        }
+    }
+
+    impl VirtioTransport for MockTransport {
+        fn device_features(&mut self) -> u64 {
+            self.features
+        }
+
+        fn set_driver_features(&mut self, features: u64) {
+            self.driver_features = features;
+        }
+
+        fn status(&mut self) -> u8 {
+            self.status
+        }
+
+        fn set_status(&mut self, status: u8) {
+            self.status = status;
+            if self.reject_features {
+                self.status &= !status::FEATURES_OK;
+            }
+        }
+
+        fn max_queue_size(&mut self, _queue: u16) -> u16 {
+            self.max_queue_size
+        }
+
+        fn set_queue(
+            &mut self,
+            queue: u16,
+            size: u16,
+            descriptors: u64,
+            driver_area: u64,
+            device_area: u64,
+        ) {
+            self.queues
+                .push((queue, size, descriptors, driver_area, device_area));
+        }
+
+        fn notify(&mut self, queue: u16) {
+            self.notified.push(queue);
+        }
+
+        fn read_config(&mut self, offset: usize) -> u8 {
+            let mut config = [0; 12];
+            config[..6].copy_from_slice(&MAC);
+            config[10..].copy_from_slice(&self.mtu.to_le_bytes());
+            config[offset]
+        }
+    }
+
+    const FEATURES: u64 = feature::VERSION_1 | feature::MAC | feature::MTU;
+
+    fn new_device<const N: usize>(
+        transport: MockTransport,
+        memory: &mut VirtioNetMemory<N>,
+    ) -> VirtioNetDevice<'_, MockTransport, N> {
+        VirtioNetDevice::new(transport, memory, PHYS_ADDR).unwrap()
+    }
+
+    // Act as the device: use the next available buffer of `queue`, writing `data` to it
+    // for the receive queue.
+    fn use_buffer<const N: usize>(queue: &mut Queue<'_, N>, data: &[u8]) {
+        let used_idx = queue.queue.used.idx.load(Ordering::Relaxed);
+        let id = queue.queue.avail.ring[(used_idx % queue.size) as usize];
+        queue.buffers[id as usize][..data.len()].copy_from_slice(data);
+        let len = if queue.index == QUEUE_RX {
+            data.len()
+        } else {
+            queue.queue.descriptors[id as usize].len as usize
+        };
+        queue.queue.used.ring[(used_idx % queue.size) as usize] = UsedElem {
+            id: id as u32,
+            len: len as u32,
+        };
+        queue
+            .queue
+            .used
+            .idx
+            .store(used_idx.wrapping_add(1), Ordering::Release);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let mut memory = Box::new(VirtioNetMemory::<8>::new());
+        let offered = FEATURES | feature::CSUM | feature::GUEST_CSUM | (1 << 22);
+        let device = new_device(MockTransport::new(offered), &mut memory);
+
+        assert_eq!(
+            device.transport().driver_features,
+            FEATURES | feature::CSUM | feature::GUEST_CSUM
+        );
+        assert_eq!(
+            device.transport().status,
+            status::ACKNOWLEDGE | status::DRIVER | status::FEATURES_OK | status::DRIVER_OK
+        );
+        assert_eq!(device.hardware_addr(), Some(EthernetAddress(MAC)));
+
+        let caps = device.capabilities();
+        assert_eq!(caps.medium, Medium::Ethernet);
+        assert_eq!(caps.max_transmission_unit, 1514);
+        assert!(caps.checksum.tcp.rx() && !caps.checksum.tcp.tx());
+        assert!(caps.checksum.udp.rx() && caps.checksum.udp.tx());
+    }
+
+    #[test]
+    fn test_negotiate_mtu() {
+        let mut memory = Box::new(VirtioNetMemory::<8>::new());
+        let mut transport = MockTransport::new(FEATURES);
+        transport.mtu = 1280;
+        let device = new_device(transport, &mut memory);
+        assert_eq!(device.capabilities().max_transmission_unit, 1294);
+        drop(device);
+
+        // Frames of a jumbo MTU do not fit in the buffers.
+        let mut transport = MockTransport::new(FEATURES);
+        transport.mtu = 9000;
+        let device = new_device(transport, &mut memory);
+        assert_eq!(device.features() & feature::MTU, 0);
+        assert_eq!(device.capabilities().max_transmission_unit, 1514);
+        assert!(device.capabilities().checksum.tcp.tx());
+    }
+
+    #[test]
+    fn test_negotiate_failed() {
+        let mut memory = Box::new(VirtioNetMemory::<8>::new());
+
+        let result = VirtioNetDevice::new(MockTransport::new(feature::MAC), &mut memory, PHYS_ADDR);
+        assert_eq!(result.err(), Some(VirtioNetError::Unsupported));
+
+        let mut transport = MockTransport::new(FEATURES);
+        transport.reject_features = true;
+        let result = VirtioNetDevice::new(transport, &mut memory, PHYS_ADDR);
+        assert_eq!(result.err(), Some(VirtioNetError::FeaturesRejected));
+
+        let mut transport = MockTransport::new(FEATURES);
+        transport.max_queue_size = 0;
+        let result = VirtioNetDevice::new(transport, &mut memory, PHYS_ADDR);
+        assert_eq!(result.err(), Some(VirtioNetError::QueueUnavailable));
+    }
+
+    #[test]
+    fn test_queue_setup() {
+        let mut memory = Box::new(VirtioNetMemory::<8>::new());
+        let mut transport = MockTransport::new(FEATURES);
+        transport.max_queue_size = 6;
+        let device = new_device(transport, &mut memory);
+
+        let rx = &device.rx;
+        let base = PHYS_ADDR;
+        let offset = |addr: usize| addr as u64 - device.rx.queue as *const _ as u64;
+        assert_eq!(
+            device.transport().queues[0],
+            (
+                QUEUE_RX,
+                4,
+                base,
+                base + offset(&rx.queue.avail as *const _ as usize),
+                base + offset(&rx.queue.used as *const _ as usize)
+            )
+        );
+        assert_eq!(device.transport().queues[1].0, QUEUE_TX);
+        assert_eq!(device.transport().queues[1].1, 4);
+        assert_eq!(
+            rx.queue.descriptors[1].addr,
+            base + offset(rx.buffers[1].as_ptr() as usize)
+        );
+        assert_eq!(rx.queue.descriptors[1].flags, VIRTQ_DESC_F_WRITE);
+        assert_eq!(rx.queue.avail.idx.load(Ordering::Relaxed), 4);
+        assert_eq!(device.tx.queue.avail.flags, VIRTQ_AVAIL_F_NO_INTERRUPT);
+        assert_eq!(device.transport().notified, vec![QUEUE_RX]);
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut memory = Box::new(VirtioNetMemory::<8>::new());
+        let mut device = new_device(MockTransport::new(FEATURES), &mut memory);
+        assert!(device.receive(Instant::ZERO).is_none());
+
+        let mut data = vec![0; HEADER_LEN];
+        data.extend_from_slice(&[0xaa; 60]);
+        use_buffer(&mut device.rx, &data);
+        let (rx, _tx) = device.receive(Instant::ZERO).unwrap();
+        assert_eq!(rx.consume(|frame| frame.to_vec()), vec![0xaa; 60]);
+
+        // The buffer is given back to the device, which is notified on the next receive.
+        assert_eq!(device.rx.queue.avail.idx.load(Ordering::Relaxed), 9);
+        assert!(device.receive(Instant::ZERO).is_none());
+        assert_eq!(device.transport().notified, vec![QUEUE_RX, QUEUE_RX]);
+
+        // Unless it does not want to be.
+        device
+            .rx
+            .queue
+            .used
+            .flags
+            .store(VIRTQ_USED_F_NO_NOTIFY, Ordering::Relaxed);
+        use_buffer(&mut device.rx, &data);
+        drop(device.receive(Instant::ZERO).unwrap());
+        assert!(device.receive(Instant::ZERO).is_none());
+        assert_eq!(device.transport().notified, vec![QUEUE_RX, QUEUE_RX]);
+    }
+
+    #[test]
+    fn test_transmit() {
+        let mut memory = Box::new(VirtioNetMemory::<2>::new());
+        let mut device = new_device(MockTransport::new(FEATURES), &mut memory);
+
+        for i in 0..2 {
+            let tx = device.transmit(Instant::ZERO).unwrap();
+            tx.consume(60, |frame| frame.fill(i));
+        }
+        assert!(device.transmit(Instant::ZERO).is_none());
+        assert_eq!(
+            device.transport().notified,
+            vec![QUEUE_RX, QUEUE_TX, QUEUE_TX]
+        );
+
+        let tx = &device.tx;
+        assert_eq!(tx.queue.avail.idx.load(Ordering::Relaxed), 2);
+        let id = tx.queue.avail.ring[0] as usize;
+        assert_eq!(tx.queue.descriptors[id].len as usize, HEADER_LEN + 60);
+        assert_eq!(tx.buffers[id][..HEADER_LEN], [0; HEADER_LEN]);
+        assert_eq!(tx.buffers[id][HEADER_LEN..HEADER_LEN + 60], [0; 60]);
+
+        // Sent buffers are reused.
+        use_buffer(&mut device.tx, &[]);
+        assert!(device.transmit(Instant::ZERO).is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_checksum_offload() {
+        use crate::phy::ChecksumCapabilities;
+        use crate::wire::{EthernetRepr, Ipv4Address, Ipv4Packet, Ipv4Repr, TcpPacket};
+
+        const SRC_ADDR: Ipv4Address = Ipv4Address::new(192, 168, 1, 1);
+        const DST_ADDR: Ipv4Address = Ipv4Address::new(192, 168, 1, 2);
+
+        fn emit(frame: &mut [u8]) {
+            EthernetRepr {
+                src_addr: EthernetAddress(MAC),
+                dst_addr: EthernetAddress::BROADCAST,
+                ethertype: EthernetProtocol::Ipv4,
+            }
+            .emit(&mut EthernetFrame::new_unchecked(&mut *frame));
+            Ipv4Repr {
+                src_addr: SRC_ADDR,
+                dst_addr: DST_ADDR,
+                next_header: IpProtocol::Tcp,
+                payload_len: 23,
+                hop_limit: 64,
+            }
+            .emit(
+                &mut Ipv4Packet::new_unchecked(&mut frame[14..]),
+                &ChecksumCapabilities::default(),
+            );
+            let mut packet = TcpPacket::new_unchecked(&mut frame[34..]);
+            packet.set_src_port(1234);
+            packet.set_header_len(20);
+            frame[54..].copy_from_slice(b"abc");
+        }
+
+        fn checksum_valid(frame: &[u8]) -> bool {
+            TcpPacket::new_unchecked(&frame[34..])
+                .verify_checksum(&IpAddress::Ipv4(SRC_ADDR), &IpAddress::Ipv4(DST_ADDR))
+        }
+
+        let mut memory = Box::new(VirtioNetMemory::<8>::new());
+        let mut device = new_device(
+            MockTransport::new(FEATURES | feature::CSUM | feature::GUEST_CSUM),
+            &mut memory,
+        );
+
+        let tx = device.transmit(Instant::ZERO).unwrap();
+        tx.consume(57, emit);
+        let id = device.tx.queue.avail.ring[0] as usize;
+        let buffer = &mut device.tx.buffers[id][..HEADER_LEN + 57];
+        assert_eq!(
+            buffer[..HEADER_LEN],
+            [
+                VIRTIO_NET_HDR_F_NEEDS_CSUM,
+                0,
+                0,
+                0,
+                0,
+                0,
+                34,
+                0,
+                16,
+                0,
+                0,
+                0
+            ]
+        );
+        let mut data = buffer.to_vec();
+        assert!(!checksum_valid(&data[HEADER_LEN..]));
+
+        // As received from another guest of the host, with the same partial checksum.
+        use_buffer(&mut device.rx, &data);
+        let (rx, _tx) = device.receive(Instant::ZERO).unwrap();
+        assert!(rx.consume(|frame| checksum_valid(frame)));
+
+        complete_checksum(&mut data[HEADER_LEN..], 34, 16);
+        assert!(checksum_valid(&data[HEADER_LEN..]));
+    }
+}
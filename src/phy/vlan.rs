@@ -0,0 +1,350 @@
+use managed::ManagedSlice;
+
+use crate::phy::{self, Device, DeviceCapabilities, Medium, PacketMeta};
+use crate::time::Instant;
+use crate::wire::{EthernetFrame, EthernetProtocol, VlanPacket, VlanRepr, VLAN_HEADER_LEN};
+
+/// The length of the destination and source addresses of an Ethernet II frame.
+const ADDRS_LEN: usize = 12;
+
+/// A device demultiplexing IEEE 802.1Q VLANs.
+///
+/// A VLAN device wraps an Ethernet device and splits it into one sub-device
+/// per VLAN, returned by [vlan](#method.vlan), each of which can be polled by
+/// its own interface. Incoming frames are untagged before being passed to the
+/// interface, and outgoing frames are tagged with the VLAN identifier and
+/// priority code point of the sub-device.
+///
+/// Frames are received from the underlying device in order. A frame destined
+/// to a VLAN other than the one being polled is kept in a buffer until that
+/// VLAN is polled, so every VLAN listed when creating the device must be
+/// polled regularly. Frames for VLANs which are not listed are dropped.
+/// VLAN identifier 0 stands for untagged and priority-tagged frames.
+pub struct VlanDevice<'a, D: Device> {
+    inner: D,
+    vids: ManagedSlice<'a, u16>,
+    buffer: ManagedSlice<'a, u8>,
+    pending: Option<(u16, usize, PacketMeta)>,
+}
+
+impl<'a, D: Device> VlanDevice<'a, D> {
+    /// Create a VLAN device accepting the given VLAN identifiers.
+    ///
+    /// The buffer must be large enough to hold an untagged frame of the
+    /// maximum size of the underlying device; larger frames are dropped.
+    ///
+    /// # Panics
+    /// This function panics if the medium of the underlying device is not Ethernet.
+    pub fn new<V, B>(inner: D, vids: V, buffer: B) -> VlanDevice<'a, D>
+    where
+        V: Into<ManagedSlice<'a, u16>>,
+        B: Into<ManagedSlice<'a, u8>>,
+    {
+        assert_eq!(inner.capabilities().medium, Medium::Ethernet);
+        VlanDevice {
+            inner,
+            vids: vids.into(),
+            buffer: buffer.into(),
+            pending: None,
+        }
+    }
+
+    /// Return the sub-device for the given VLAN identifier, tagging outgoing frames
+    /// with the given priority code point.
+    ///
+    /// Frames sent through the sub-device for VLAN 0 are left untagged, unless
+    /// the priority code point is not 0.
+    ///
+    /// # Panics
+    /// This function panics if the VLAN identifier is not a valid 12-bit identifier,
+    /// or if the priority code point does not fit in 3 bits.
+    pub fn vlan(&mut self, vid: u16, pcp: u8) -> Vlan<'_, 'a, D> {
+        assert!(vid <= 0xffe);
+        assert!(pcp < 8);
+        Vlan {
+            device: self,
+            vid,
+            pcp,
+        }
+    }
+
+    /// Return the accepted VLAN identifiers.
+    pub fn vids(&self) -> &[u16] {
+        &self.vids
+    }
+
+    /// Get a reference to the underlying device.
+    pub fn get_ref(&self) -> &D {
+        &self.inner
+    }
+
+    /// Get a mutable reference to the underlying device.
+    ///
+    /// It is inadvisable to directly read from the device as doing so will circumvent
+    /// the VLAN demultiplexing.
+    pub fn get_mut(&mut self) -> &mut D {
+        &mut self.inner
+    }
+
+    /// Return the underlying device, consuming the VLAN device.
+    pub fn into_inner(self) -> D {
+        self.inner
+    }
+}
+
+/// The sub-device of a [VlanDevice] for a single VLAN.
+pub struct Vlan<'v, 'a, D: Device> {
+    device: &'v mut VlanDevice<'a, D>,
+    vid: u16,
+    pcp: u8,
+}
+
+impl<'v, 'a, D: Device> Vlan<'v, 'a, D> {
+    fn tagged(&self) -> bool {
+        self.vid != 0 || self.pcp != 0
+    }
+}
+
+impl<'v, 'a, D: Device> Device for Vlan<'v, 'a, D> {
+    type RxToken<'t> = RxToken<'t>
+    where
+        Self: 't;
+    type TxToken<'t> = TxToken<D::TxToken<'t>>
+    where
+        Self: 't;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = self.device.inner.capabilities();
+        if self.tagged() {
+            caps.max_transmission_unit -= VLAN_HEADER_LEN;
+        }
+        caps
+    }
+
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let (vid, pcp) = (self.vid, self.pcp);
+        let VlanDevice {
+            inner,
+            vids,
+            buffer,
+            pending,
+        } = &mut *self.device;
+
+        if pending.is_none() {
+            let (rx_token, tx_token) = inner.receive(timestamp)?;
+            let meta = phy::RxToken::meta(&rx_token);
+            let received = phy::RxToken::consume(rx_token, |frame| untag(frame, buffer));
+            match received {
+                Some((frame_vid, len)) if frame_vid == vid => {
+                    let rx = RxToken {
+                        buffer: &mut buffer[..len],
+                        meta,
+                    };
+                    let tx = TxToken {
+                        token: tx_token,
+                        vid,
+                        pcp,
+                    };
+                    return Some((rx, tx));
+                }
+                Some((frame_vid, len)) if vids.contains(&frame_vid) => {
+                    *pending = Some((frame_vid, len, meta));
+                }
+                _ => (),
+            }
+            return None;
+        }
+
+        match *pending {
+            Some((pending_vid, len, meta)) if pending_vid == vid => {
+                let tx_token = inner.transmit(timestamp)?;
+                *pending = None;
+                let rx = RxToken {
+                    buffer: &mut buffer[..len],
+                    meta,
+                };
+                let tx = TxToken {
+                    token: tx_token,
+                    vid,
+                    pcp,
+                };
+                Some((rx, tx))
+            }
+            _ => None,
+        }
+    }
+
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        let (vid, pcp) = (self.vid, self.pcp);
+        self.device
+            .inner
+            .transmit(timestamp)
+            .map(|token| TxToken { token, vid, pcp })
+    }
+}
+
+/// Copy a received frame into `buffer` without its 802.1Q tag, returning the VLAN
+/// identifier of the frame and the length of the untagged frame.
+fn untag(frame: &[u8], buffer: &mut [u8]) -> Option<(u16, usize)> {
+    let eth_frame = EthernetFrame::new_checked(frame).ok()?;
+    let (vid, header_len) = if eth_frame.ethertype() == EthernetProtocol::Vlan {
+        let tag = VlanPacket::new_checked(eth_frame.payload()).ok()?;
+        let repr = VlanRepr::parse(&tag).ok()?;
+        (repr.vid, VLAN_HEADER_LEN)
+    } else {
+        (0, 0)
+    };
+
+    let len = frame.len() - header_len;
+    if len > buffer.len() {
+        net_debug!("vlan: frame too large for buffer, dropping");
+        return None;
+    }
+    buffer[..ADDRS_LEN].copy_from_slice(&frame[..ADDRS_LEN]);
+    buffer[ADDRS_LEN..len].copy_from_slice(&frame[ADDRS_LEN + header_len..]);
+    Some((vid, len))
+}
+
+#[doc(hidden)]
+pub struct RxToken<'b> {
+    buffer: &'b mut [u8],
+    meta: PacketMeta,
+}
+
+impl<'b> phy::RxToken for RxToken<'b> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(self.buffer)
+    }
+
+    fn meta(&self) -> PacketMeta {
+        self.meta
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<Tx: phy::TxToken> {
+    token: Tx,
+    vid: u16,
+    pcp: u8,
+}
+
+impl<Tx: phy::TxToken> phy::TxToken for TxToken<Tx> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        if self.vid == 0 && self.pcp == 0 {
+            return self.token.consume(len, f);
+        }
+
+        let (vid, pcp) = (self.vid, self.pcp);
+        self.token.consume(len + VLAN_HEADER_LEN, |buffer| {
+            let result = f(&mut buffer[VLAN_HEADER_LEN..]);
+
+            // Move the addresses in front of the tag, leaving the EtherType of
+            // the payload in place right after it.
+            buffer.copy_within(VLAN_HEADER_LEN..VLAN_HEADER_LEN + ADDRS_LEN, 0);
+            let mut frame = EthernetFrame::new_unchecked(&mut buffer[..]);
+            frame.set_ethertype(EthernetProtocol::Vlan);
+            let mut tag = VlanPacket::new_unchecked(frame.payload_mut());
+            tag.set_pcp(pcp);
+            tag.set_dei(false);
+            tag.set_vid(vid);
+            result
+        })
+    }
+
+    fn set_meta(&mut self, meta: PacketMeta) {
+        self.token.set_meta(meta)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::phy::{Loopback, RxToken as _, TxToken as _};
+    use crate::wire::{EthernetAddress, EthernetRepr};
+
+    const SRC: EthernetAddress = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+    const DST: EthernetAddress = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x02]);
+
+    fn send<D: Device>(device: &mut D, payload: u8) {
+        let repr = EthernetRepr {
+            src_addr: SRC,
+            dst_addr: DST,
+            ethertype: EthernetProtocol::Ipv4,
+        };
+        let token = device.transmit(Instant::ZERO).unwrap();
+        token.consume(repr.buffer_len() + 1, |buffer| {
+            repr.emit(&mut EthernetFrame::new_unchecked(&mut buffer[..]));
+            buffer[repr.buffer_len()] = payload;
+        });
+    }
+
+    fn recv<D: Device>(device: &mut D) -> Option<Vec<u8>> {
+        let (rx, _tx) = device.receive(Instant::ZERO)?;
+        Some(rx.consume(|buffer| buffer.to_vec()))
+    }
+
+    fn untagged(payload: u8) -> Vec<u8> {
+        let mut frame = vec![0; 15];
+        frame[0..6].copy_from_slice(DST.as_bytes());
+        frame[6..12].copy_from_slice(SRC.as_bytes());
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+        frame[14] = payload;
+        frame
+    }
+
+    #[test]
+    fn test_tag() {
+        let mut device = VlanDevice::new(Loopback::new(Medium::Ethernet), vec![0, 10], vec![0; 64]);
+
+        send(&mut device.vlan(10, 3), 0xaa);
+        send(&mut device.vlan(0, 0), 0xbb);
+        send(&mut device.vlan(0, 2), 0xcc);
+
+        let queue = &device.get_ref().queue;
+        assert_eq!(
+            queue[0],
+            [
+                0x02, 0x00, 0x00, 0x00, 0x00, 0x02, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x81, 0x00,
+                0x60, 0x0a, 0x08, 0x00, 0xaa,
+            ]
+        );
+        assert_eq!(queue[1], untagged(0xbb));
+        assert_eq!(&queue[2][12..16], &[0x81, 0x00, 0x40, 0x00]);
+
+        assert_eq!(
+            device.vlan(0, 0).capabilities().max_transmission_unit,
+            65535
+        );
+        assert_eq!(
+            device.vlan(10, 0).capabilities().max_transmission_unit,
+            65531
+        );
+    }
+
+    #[test]
+    fn test_untag() {
+        let mut device = VlanDevice::new(Loopback::new(Medium::Ethernet), vec![0, 10], vec![0; 64]);
+
+        send(&mut device.vlan(20, 0), 0x11);
+        send(&mut device.vlan(10, 0), 0xaa);
+        send(&mut device.vlan(0, 5), 0xbb);
+        send(&mut device.vlan(10, 0), 0xcc);
+
+        // A frame for an unknown VLAN is dropped.
+        assert_eq!(recv(&mut device.vlan(10, 0)), None);
+        assert_eq!(recv(&mut device.vlan(10, 0)), Some(untagged(0xaa)));
+
+        // A frame for another VLAN is held until that VLAN is polled.
+        assert_eq!(recv(&mut device.vlan(10, 0)), None);
+        assert_eq!(recv(&mut device.vlan(10, 0)), None);
+        assert_eq!(recv(&mut device.vlan(0, 0)), Some(untagged(0xbb)));
+        assert_eq!(recv(&mut device.vlan(10, 0)), Some(untagged(0xcc)));
+        assert_eq!(recv(&mut device.vlan(10, 0)), None);
+    }
+}
@@ -0,0 +1,111 @@
+use std::io;
+
+use crate::phy::{self, sys, Device, DeviceCapabilities, Medium};
+use crate::time::{Duration, Instant};
+
+/// A Wintun adapter, the layer 3 virtual network interface of WireGuard for Windows, that
+/// receives and sends IP packets in place, in rings shared with the driver.
+///
+/// `wintun.dll` is loaded when the device is created, from the directory of the application
+/// or from the system directory.
+#[derive(Debug)]
+pub struct WintunInterface {
+    lower: sys::WintunDesc,
+    mtu: usize,
+}
+
+impl WintunInterface {
+    /// Opens the Wintun adapter called `name`, or creates it if there is none.
+    ///
+    /// The MTU of the device is the default one of a new adapter, 1500 octets; see
+    /// [set_mtu](Self::set_mtu) for adapters configured otherwise.
+    ///
+    /// This requires administrator privileges.
+    pub fn new(name: &str) -> io::Result<WintunInterface> {
+        let lower = sys::WintunDesc::new(name)?;
+        Ok(WintunInterface { lower, mtu: 1500 })
+    }
+
+    /// Set the MTU of the device, which should be the one configured on the adapter.
+    pub fn set_mtu(&mut self, mtu: usize) {
+        self.mtu = mtu;
+    }
+
+    /// Wait until a packet is received, but no longer than given timeout.
+    ///
+    /// This takes the place of `phy::wait` on Windows, where the device has
+    /// no file descriptor.
+    pub fn wait(&self, duration: Option<Duration>) -> io::Result<()> {
+        self.lower.wait(duration)
+    }
+}
+
+impl Device for WintunInterface {
+    type RxToken<'a>
+        = RxToken<'a>
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken<'a>
+    where
+        Self: 'a;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            max_transmission_unit: self.mtu,
+            medium: Medium::Ip,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        match self.lower.recv() {
+            Ok(packet) => {
+                let rx = RxToken { packet };
+                let tx = TxToken { lower: &self.lower };
+                Some((rx, tx))
+            }
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => None,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxToken { lower: &self.lower })
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken<'a> {
+    packet: sys::WintunRxPacket<'a>,
+}
+
+impl<'a> phy::RxToken for RxToken<'a> {
+    fn consume<R, F>(mut self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(self.packet.as_mut_slice())
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<'a> {
+    lower: &'a sys::WintunDesc,
+}
+
+impl<'a> phy::TxToken for TxToken<'a> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        match self.lower.allocate(len) {
+            Ok(mut packet) => f(packet.as_mut_slice()),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                net_debug!("phy: tx failed due to full Wintun ring");
+                f(&mut vec![0; len])
+            }
+            Err(err) => panic!("{}", err),
+        }
+    }
+}
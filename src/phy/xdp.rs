@@ -0,0 +1,131 @@
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+use crate::phy::{self, sys, Device, DeviceCapabilities, Medium};
+use crate::time::Instant;
+
+/// An AF_XDP socket, that receives and sends the Ethernet frames of a queue of an interface
+/// in place, in memory shared with the kernel.
+///
+/// With a driver supporting zero-copy mode, the frames are not copied at all between the
+/// network card and the interface, which is needed to handle millions of packets per second;
+/// other drivers copy them once, like a raw socket does.
+///
+/// The device does not load an XDP program: one that redirects the frames received on the
+/// queue to the socket must be attached to the interface, e.g. with `libxdp`, with the file
+/// descriptor of the device at the index of the queue in its `XSKMAP`. The frames that the
+/// program passes to the kernel instead are not seen by the device.
+#[derive(Debug)]
+pub struct XdpDevice {
+    lower: sys::XdpSocketDesc,
+}
+
+impl AsRawFd for XdpDevice {
+    fn as_raw_fd(&self) -> RawFd {
+        self.lower.as_raw_fd()
+    }
+}
+
+impl XdpDevice {
+    /// Creates an AF_XDP socket, bound to the queue `queue_id` of the interface called `name`.
+    ///
+    /// This requires superuser privileges or a corresponding capability bit
+    /// set on the executable.
+    pub fn new(name: &str, queue_id: u32) -> io::Result<XdpDevice> {
+        let lower = sys::XdpSocketDesc::new(name, queue_id)?;
+        Ok(XdpDevice { lower })
+    }
+
+    /// Return whether the driver of the interface receives and sends the frames in place,
+    /// rather than copying them.
+    pub fn zero_copy(&self) -> io::Result<bool> {
+        self.lower.zero_copy()
+    }
+}
+
+impl Device for XdpDevice {
+    type RxToken<'a>
+        = RxToken<'a>
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TxToken<'a>
+    where
+        Self: 'a;
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        DeviceCapabilities {
+            max_transmission_unit: self.lower.interface_mtu(),
+            medium: Medium::Ethernet,
+            ..DeviceCapabilities::default()
+        }
+    }
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let (addr, len) = self.lower.rx.recv()?;
+        let rx = RxToken {
+            queue: &mut self.lower.rx,
+            addr,
+            len,
+        };
+        let tx = TxToken {
+            queue: &mut self.lower.tx,
+        };
+        Some((rx, tx))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        if !self.lower.tx.can_send() {
+            return None;
+        }
+        Some(TxToken {
+            queue: &mut self.lower.tx,
+        })
+    }
+}
+
+#[doc(hidden)]
+pub struct RxToken<'a> {
+    queue: &'a mut sys::XdpRxQueue,
+    addr: u64,
+    len: usize,
+}
+
+impl<'a> phy::RxToken for RxToken<'a> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        f(self.queue.frame_mut(self.addr, self.len))
+    }
+}
+
+impl<'a> Drop for RxToken<'a> {
+    fn drop(&mut self) {
+        self.queue.recycle(self.addr);
+    }
+}
+
+#[doc(hidden)]
+pub struct TxToken<'a> {
+    queue: &'a mut sys::XdpTxQueue,
+}
+
+impl<'a> phy::TxToken for TxToken<'a> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        match self.queue.reserve() {
+            Some(addr) => {
+                let result = f(self.queue.frame_mut(addr, len));
+                self.queue.send(addr, len);
+                result
+            }
+            None => {
+                net_debug!("phy: tx failed due to full AF_XDP ring");
+                f(&mut vec![0; len])
+            }
+        }
+    }
+}
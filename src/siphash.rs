@@ -0,0 +1,64 @@
+//! SipHash-2-4, used to derive values that cannot be guessed off-path from a secret key,
+//! such as TCP initial sequence numbers and IPv6 flow labels.
+
+use byteorder::{ByteOrder, LittleEndian};
+
+fn sip_round(v: &mut [u64; 4]) {
+    v[0] = v[0].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(13) ^ v[0];
+    v[0] = v[0].rotate_left(32);
+    v[2] = v[2].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(16) ^ v[2];
+    v[0] = v[0].wrapping_add(v[3]);
+    v[3] = v[3].rotate_left(21) ^ v[0];
+    v[2] = v[2].wrapping_add(v[1]);
+    v[1] = v[1].rotate_left(17) ^ v[2];
+    v[2] = v[2].rotate_left(32);
+}
+
+fn sip_compress(v: &mut [u64; 4], m: u64) {
+    v[3] ^= m;
+    sip_round(v);
+    sip_round(v);
+    v[0] ^= m;
+}
+
+/// Compute the SipHash-2-4 of `data` with a 128-bit key.
+pub(crate) fn siphash(key: &[u64; 2], data: &[u8]) -> u64 {
+    let mut v = [
+        key[0] ^ 0x736f6d6570736575,
+        key[1] ^ 0x646f72616e646f6d,
+        key[0] ^ 0x6c7967656e657261,
+        key[1] ^ 0x7465646279746573,
+    ];
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+        sip_compress(&mut v, LittleEndian::read_u64(chunk));
+    }
+    let mut last = [0; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = data.len() as u8;
+    sip_compress(&mut v, LittleEndian::read_u64(&last));
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        sip_round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const KEY: [u64; 2] = [0x0706050403020100, 0x0f0e0d0c0b0a0908];
+
+    #[test]
+    fn test_siphash_vectors() {
+        let data: [u8; 15] = core::array::from_fn(|i| i as u8);
+        assert_eq!(siphash(&KEY, &data[..0]), 0x726fdb47dd0e0e31);
+        assert_eq!(siphash(&KEY, &data[..8]), 0x93f5f5799a932462);
+        assert_eq!(siphash(&KEY, &data[..15]), 0xa129ca6149be45e5);
+    }
+}
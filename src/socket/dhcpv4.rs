@@ -1,15 +1,17 @@
 #[cfg(feature = "async")]
 use core::task::Waker;
 
-use crate::iface::Context;
+use crate::config::IFACE_MAX_ROUTE_COUNT;
+use crate::iface::{Context, Route};
 use crate::time::{Duration, Instant};
 use crate::wire::dhcpv4::field as dhcpv4_field;
 use crate::wire::{
-    DhcpMessageType, DhcpPacket, DhcpRepr, IpAddress, IpProtocol, Ipv4Address, Ipv4Cidr, Ipv4Repr,
-    UdpRepr, DHCP_CLIENT_PORT, DHCP_MAX_DNS_SERVER_COUNT, DHCP_SERVER_PORT, UDP_HEADER_LEN,
+    ArpOperation, ArpRepr, DhcpMessageType, DhcpPacket, DhcpRepr, EthernetAddress, IpAddress,
+    IpProtocol, Ipv4Address, Ipv4Cidr, Ipv4Repr, UdpRepr, DHCP_CLIENT_PORT,
+    DHCP_MAX_DNS_SERVER_COUNT, DHCP_SERVER_PORT, UDP_HEADER_LEN,
 };
 use crate::wire::{DhcpOption, HardwareAddress};
-use heapless::Vec;
+use heapless::{Deque, Vec};
 
 #[cfg(feature = "async")]
 use super::WakerRegistration;
@@ -18,6 +20,15 @@ use super::PollAt;
 
 const DEFAULT_LEASE_DURATION: Duration = Duration::from_secs(120);
 
+/// Number of ARP probes sent before using an address, from RFC 5227.
+const PROBE_NUM: u8 = 3;
+/// Delay between ARP probes.
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+/// Delay between the last ARP probe and using the address, from RFC 5227.
+const ANNOUNCE_WAIT: Duration = Duration::from_secs(2);
+/// Delay before restarting discovery after declining an address, from RFC 2131.
+const DECLINE_BACKOFF: Duration = Duration::from_secs(10);
+
 const DEFAULT_PARAMETER_REQUEST_LIST: &[u8] = &[
     dhcpv4_field::OPT_SUBNET_MASK,
     dhcpv4_field::OPT_ROUTER,
@@ -38,10 +49,78 @@ pub struct Config<'a> {
     pub router: Option<Ipv4Address>,
     /// DNS servers
     pub dns_servers: Vec<Ipv4Address, DHCP_MAX_DNS_SERVER_COUNT>,
+    /// Classless static routes, when enabled with [`Socket::set_install_routes`].
+    /// They are installed in the interface routing table while the lease is valid.
+    pub routes: Vec<StaticRoute, IFACE_MAX_ROUTE_COUNT>,
     /// Received DHCP packet
     pub packet: Option<DhcpPacket<&'a [u8]>>,
 }
 
+impl Config<'_> {
+    /// Return an iterator over the options of the received DHCP packet, such as those
+    /// requested with [`Socket::set_requested_options`].
+    ///
+    /// The packet is only available when a buffer is set with
+    /// [`Socket::set_receive_packet_buffer`]; otherwise, there are no options.
+    pub fn options(&self) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
+        self.packet.iter().flat_map(|packet| packet.options())
+    }
+
+    /// Return the data of the first option with the given code in the received
+    /// DHCP packet, if any.
+    pub fn option(&self, kind: u8) -> Option<&[u8]> {
+        self.options()
+            .find(|option| option.kind == kind)
+            .map(|option| option.data)
+    }
+
+    /// Return an iterator over the sub-options of the vendor-specific information
+    /// in the received DHCP packet, usually sent in response to the vendor class
+    /// identifier set with [`Socket::set_vendor_class_id`]. Their codes are defined
+    /// by the vendor.
+    pub fn vendor_options(&self) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
+        let data = self
+            .option(dhcpv4_field::OPT_VENDOR_SPECIFIC_INFO)
+            .unwrap_or(&[]);
+        DhcpOption {
+            kind: dhcpv4_field::OPT_VENDOR_SPECIFIC_INFO,
+            data,
+        }
+        .sub_options()
+    }
+}
+
+/// A route provided by the DHCP server in a classless static route option.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StaticRoute {
+    /// Destination prefix
+    pub cidr: Ipv4Cidr,
+    /// Router to reach the destination through
+    pub via_router: Ipv4Address,
+}
+
+impl StaticRoute {
+    fn matches(&self, route: &Route) -> bool {
+        route.cidr == self.cidr.into() && route.via_router == self.via_router.into()
+    }
+}
+
+impl From<StaticRoute> for Route {
+    fn from(route: StaticRoute) -> Route {
+        Route {
+            cidr: route.cidr.into(),
+            via_router: route.via_router.into(),
+            preferred_until: None,
+            expires_at: None,
+            mss: None,
+            src_cidr: None,
+            dscp: None,
+            mark: None,
+        }
+    }
+}
+
 /// Information on how to reach a DHCP server.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -73,7 +152,7 @@ struct RequestState {
     requested_ip: Ipv4Address,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 struct RenewState {
     /// Active network config
@@ -92,14 +171,51 @@ struct RenewState {
     /// equal to `expires_at`.
     rebind_at: Instant,
 
+    /// Whether the T1 time has elapsed
+    renewing: bool,
+
     /// Whether the T2 time has elapsed
     rebinding: bool,
 
+    /// Whether the lease has been reported as expiring soon
+    expiry_warned: bool,
+
     /// Expiration timer. When reached, this lease is no longer valid, so it must be
     /// thrown away and the ethernet interface deconfigured.
     expires_at: Instant,
 }
 
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct ProbeState {
+    /// Lease to use once the address is known to be free
+    lease: RenewState,
+    /// When to send next probe
+    retry_at: Instant,
+    /// How many probes have been sent
+    probes: u8,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct DeclineState {
+    /// Server that offered the address
+    server: ServerInfo,
+    /// Address in use by another host
+    address: Ipv4Address,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct InformState {
+    /// When to send next inform
+    retry_at: Instant,
+    /// Statically configured address
+    address: Ipv4Cidr,
+    /// Network config, once acknowledged by a server
+    config: Option<Config<'static>>,
+}
+
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 enum ClientState {
@@ -107,8 +223,25 @@ enum ClientState {
     Discovering(DiscoverState),
     /// Requesting an address
     Requesting(RequestState),
+    /// Checking that the acknowledged address isn't used by another host.
+    Probing(ProbeState),
+    /// Declining an address used by another host.
+    Declining(DeclineState),
     /// Having an address, refresh it periodically.
     Renewing(RenewState),
+    /// Having a static address, asking for the other parameters.
+    Informing(InformState),
+}
+
+impl ClientState {
+    /// Return the active network config, if any.
+    fn config(&self) -> Option<&Config<'static>> {
+        match self {
+            ClientState::Renewing(state) => Some(&state.config),
+            ClientState::Informing(state) => state.config.as_ref(),
+            _ => None,
+        }
+    }
 }
 
 /// Timeout and retry configuration.
@@ -141,6 +274,29 @@ pub enum Event<'a> {
     Deconfigured,
     /// Configuration has been newly acquired, or modified.
     Configured(Config<'a>),
+    /// The renew timer (T1) has elapsed, and the lease is being renewed with the
+    /// server that granted it. The configuration is still valid.
+    Renewing,
+    /// The rebind timer (T2) has elapsed without an answer from that server, and the
+    /// lease is being renewed with any server. The configuration is still valid.
+    Rebinding,
+    /// The lease has been refused by the server, or has expired before it could be
+    /// renewed. It is followed by [`Event::Deconfigured`].
+    RenewFailed,
+    /// The lease expires at the given instant, and has not been renewed yet. It is
+    /// emitted once per lease, ahead of the expiration by the lead time set with
+    /// [`Socket::set_expiry_warning`].
+    LeaseExpiringSoon(Instant),
+}
+
+/// An event of [`Event`] not related to a configuration change, waiting to be polled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Notification {
+    Renewing,
+    Rebinding,
+    RenewFailed,
+    LeaseExpiringSoon(Instant),
 }
 
 #[derive(Debug)]
@@ -149,6 +305,8 @@ pub struct Socket<'a> {
     state: ClientState,
     /// Set to true on config/state change, cleared back to false by the `config` function.
     config_changed: bool,
+    /// Events reported by `poll` before the configuration changes.
+    notifications: Deque<Notification, 4>,
     /// xid of the last sent message.
     transaction_id: u32,
 
@@ -161,6 +319,9 @@ pub struct Socket<'a> {
     /// Ignore NAKs.
     ignore_naks: bool,
 
+    /// How long before the lease expires to report it as expiring soon.
+    expiry_warning: Option<Duration>,
+
     /// Server port config
     pub(crate) server_port: u16,
 
@@ -172,10 +333,33 @@ pub struct Socket<'a> {
     outgoing_options: &'a [DhcpOption<'a>],
     /// A buffer containing all requested parameters.
     parameter_request_list: Option<&'a [u8]>,
+    /// Client identifier sent to the server, instead of the hardware address.
+    client_identifier: Option<&'a [u8]>,
+    /// Host name sent to the server.
+    hostname: Option<&'a str>,
+    /// Fully qualified domain name sent to the server.
+    fqdn: Option<&'a str>,
+    /// Vendor class identifier sent to the server.
+    vendor_class_id: Option<&'a [u8]>,
+    /// Option codes to request in addition to the parameter request list, and
+    /// whose data is kept in the configuration.
+    requested_options: &'a [u8],
 
     /// Incoming DHCP packets are copied into this buffer, overwriting the previous.
     receive_packet_buffer: Option<&'a mut [u8]>,
 
+    /// Whether to accept an ACK in response to a DISCOVER.
+    rapid_commit: bool,
+    /// Whether to probe acknowledged addresses with ARP before using them.
+    conflict_detection: bool,
+    /// Static address to send DHCPINFORM messages from, instead of acquiring a lease.
+    inform_address: Option<Ipv4Cidr>,
+
+    /// Whether to request classless static routes and install them in the interface.
+    install_routes: bool,
+    /// Routes currently installed in the interface routing table by this socket.
+    installed_routes: Vec<StaticRoute, IFACE_MAX_ROUTE_COUNT>,
+
     /// Waker registration
     #[cfg(feature = "async")]
     waker: WakerRegistration,
@@ -195,13 +379,25 @@ impl<'a> Socket<'a> {
                 retry_at: Instant::from_millis(0),
             }),
             config_changed: true,
+            notifications: Deque::new(),
             transaction_id: 1,
             max_lease_duration: None,
             retry_config: RetryConfig::default(),
             ignore_naks: false,
+            expiry_warning: None,
             outgoing_options: &[],
             parameter_request_list: None,
+            client_identifier: None,
+            hostname: None,
+            fqdn: None,
+            vendor_class_id: None,
+            requested_options: &[],
             receive_packet_buffer: None,
+            rapid_commit: false,
+            conflict_detection: false,
+            inform_address: None,
+            install_routes: false,
+            installed_routes: Vec::new(),
             #[cfg(feature = "async")]
             waker: WakerRegistration::new(),
             server_port: DHCP_SERVER_PORT,
@@ -232,6 +428,198 @@ impl<'a> Socket<'a> {
         self.parameter_request_list = Some(parameter_request_list);
     }
 
+    /// Set additional option codes to request from the server.
+    ///
+    /// The codes are appended to the parameter request list, and the data of
+    /// the options returned by the server for them, for example NTP servers (`42`)
+    /// or vendor-specific information (`43`), is available through [`Config::option`]
+    /// when a receive packet buffer is set with
+    /// [`set_receive_packet_buffer`](Self::set_receive_packet_buffer).
+    pub fn set_requested_options(&mut self, options: &'a [u8]) {
+        self.requested_options = options;
+    }
+
+    /// Get the client identifier sent to the server.
+    ///
+    /// See also [`Self::set_client_identifier()`]
+    pub fn client_identifier(&self) -> Option<&'a [u8]> {
+        self.client_identifier
+    }
+
+    /// Set the client identifier sent to the server (option 61).
+    ///
+    /// The identifier is a type octet followed by the identifier itself, for example
+    /// `0` followed by a serial number, or `255` followed by an IAID and a DUID as
+    /// described in RFC 4361. Servers key leases on it, so that they survive a change
+    /// of hardware address. If `None`, the client is identified by its hardware address.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the identifier is shorter than 2 octets or longer than
+    /// 255 octets.
+    pub fn set_client_identifier(&mut self, client_identifier: Option<&'a [u8]>) {
+        if let Some(id) = client_identifier {
+            assert!(
+                (2..=u8::MAX as usize).contains(&id.len()),
+                "invalid client identifier"
+            );
+        }
+        self.client_identifier = client_identifier;
+    }
+
+    /// Get the host name sent to the server.
+    ///
+    /// See also [`Self::set_hostname()`]
+    pub fn hostname(&self) -> Option<&'a str> {
+        self.hostname
+    }
+
+    /// Set the host name sent to the server (option 12).
+    ///
+    /// Servers commonly show it in their lease table, and may register it in DNS.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the host name is empty or longer than 255 octets.
+    pub fn set_hostname(&mut self, hostname: Option<&'a str>) {
+        if let Some(hostname) = hostname {
+            assert!(
+                !hostname.is_empty() && hostname.len() <= u8::MAX as usize,
+                "invalid host name"
+            );
+        }
+        self.hostname = hostname;
+    }
+
+    /// Get the fully qualified domain name sent to the server.
+    ///
+    /// See also [`Self::set_fqdn()`]
+    pub fn fqdn(&self) -> Option<&'a str> {
+        self.fqdn
+    }
+
+    /// Set the fully qualified domain name sent to the server (option 81, RFC 4702),
+    /// which asks the server to register it in DNS on behalf of the client.
+    ///
+    /// As required by RFC 4702, the host name option is not sent when this is set.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the name has an empty label or a label longer than
+    /// 63 octets, or if it is too long to fit in the option.
+    pub fn set_fqdn(&mut self, fqdn: Option<&'a str>) {
+        if let Some(fqdn) = fqdn {
+            let name = fqdn.trim_end_matches('.');
+            assert!(
+                name.len() + 5 <= u8::MAX as usize
+                    && name.split('.').all(|label| (1..=63).contains(&label.len())),
+                "invalid FQDN"
+            );
+        }
+        self.fqdn = fqdn;
+    }
+
+    /// Get the vendor class identifier sent to the server.
+    ///
+    /// See also [`Self::set_vendor_class_id()`]
+    pub fn vendor_class_id(&self) -> Option<&'a [u8]> {
+        self.vendor_class_id
+    }
+
+    /// Set the vendor class identifier sent to the server (option 60, RFC 2132).
+    ///
+    /// When set, the vendor-specific information option is also requested, and servers
+    /// may use it to provide the device with the address of its controller or configuration
+    /// server. It is available through [`Config::vendor_options`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the identifier is empty or longer than 255 octets.
+    pub fn set_vendor_class_id(&mut self, vendor_class_id: Option<&'a [u8]>) {
+        if let Some(id) = vendor_class_id {
+            assert!(
+                (1..=u8::MAX as usize).contains(&id.len()),
+                "invalid vendor class identifier"
+            );
+        }
+        self.vendor_class_id = vendor_class_id;
+    }
+
+    /// Get whether rapid commit is enabled.
+    ///
+    /// See also [`Self::set_rapid_commit()`]
+    pub fn rapid_commit(&self) -> bool {
+        self.rapid_commit
+    }
+
+    /// Set whether rapid commit is enabled.
+    ///
+    /// When enabled, the socket includes the rapid commit option in DHCPDISCOVER messages,
+    /// and servers supporting it assign an address with a DHCPACK straight away, instead of
+    /// the four message exchange, as described in RFC 4039. Offers are still accepted from
+    /// other servers.
+    pub fn set_rapid_commit(&mut self, rapid_commit: bool) {
+        self.rapid_commit = rapid_commit;
+    }
+
+    /// Get whether address conflict detection is enabled.
+    ///
+    /// See also [`Self::set_conflict_detection()`]
+    pub fn conflict_detection(&self) -> bool {
+        self.conflict_detection
+    }
+
+    /// Set whether address conflict detection is enabled.
+    ///
+    /// When enabled, the socket probes the address acknowledged by the server with ARP
+    /// before reporting it, as described in RFC 5227. If another host answers, the address
+    /// is declined with a DHCPDECLINE and discovery restarts.
+    pub fn set_conflict_detection(&mut self, conflict_detection: bool) {
+        self.conflict_detection = conflict_detection;
+    }
+
+    /// Get the static address the socket sends DHCPINFORM messages from.
+    ///
+    /// See also [`Self::set_inform_address()`]
+    pub fn inform_address(&self) -> Option<Ipv4Cidr> {
+        self.inform_address
+    }
+
+    /// Set a static address to send DHCPINFORM messages from, instead of acquiring a lease.
+    ///
+    /// When set, the socket asks the DHCP servers for the other configuration parameters,
+    /// such as the router, DNS servers and requested options, as described in RFC 2131
+    /// § 3.4. The configuration of the first acknowledgment is reported with the static
+    /// address, and does not expire. Informs are retransmitted every `discover_timeout`
+    /// until then.
+    ///
+    /// This resets the socket.
+    pub fn set_inform_address(&mut self, address: Option<Ipv4Cidr>) {
+        self.inform_address = address;
+        self.reset();
+    }
+
+    /// Get whether classless static routes are installed in the interface.
+    ///
+    /// See also [`Self::set_install_routes()`]
+    pub fn install_routes(&self) -> bool {
+        self.install_routes
+    }
+
+    /// Set whether classless static routes are installed in the interface.
+    ///
+    /// When enabled, the socket requests classless static routes (option 121,
+    /// and its pre-standard code 249) from the server, adds the routes it returns
+    /// to the interface routing table, and removes them when the lease is lost.
+    /// As required by RFC 3442, the router option is ignored when the server
+    /// provides such routes, so [`Config::router`] is `None` in that case.
+    ///
+    /// Routes to on-link destinations (with a router address of `0.0.0.0`) are
+    /// not supported, and are ignored.
+    pub fn set_install_routes(&mut self, install_routes: bool) {
+        self.install_routes = install_routes;
+    }
+
     /// Get the configured max lease duration.
     ///
     /// See also [`Self::set_max_lease_duration()`]
@@ -267,6 +655,21 @@ impl<'a> Socket<'a> {
         self.ignore_naks = ignore_naks;
     }
 
+    /// Get how long before the lease expires it is reported as expiring soon.
+    ///
+    /// See also [`Self::set_expiry_warning()`]
+    pub fn expiry_warning(&self) -> Option<Duration> {
+        self.expiry_warning
+    }
+
+    /// Set how long before the lease expires it is reported as expiring soon, with
+    /// [`Event::LeaseExpiringSoon`], if it has not been renewed by then.
+    ///
+    /// By default, no such event is reported.
+    pub fn set_expiry_warning(&mut self, lead_time: Option<Duration>) {
+        self.expiry_warning = lead_time;
+    }
+
     /// Set the server/client port
     ///
     /// Allows you to specify the ports used by DHCP.
@@ -278,14 +681,24 @@ impl<'a> Socket<'a> {
 
     pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
         let t = match &self.state {
+            ClientState::Informing(state) if state.config.is_some() => return PollAt::Ingress,
+            ClientState::Informing(state) => state.retry_at,
             ClientState::Discovering(state) => state.retry_at,
             ClientState::Requesting(state) => state.retry_at,
-            ClientState::Renewing(state) => if state.rebinding {
-                state.rebind_at
-            } else {
-                state.renew_at.min(state.rebind_at)
+            ClientState::Probing(state) => state.retry_at,
+            ClientState::Declining(_) => Instant::from_millis(0),
+            ClientState::Renewing(state) => {
+                let t = if state.rebinding {
+                    state.rebind_at
+                } else {
+                    state.renew_at.min(state.rebind_at)
+                }
+                .min(state.expires_at);
+                match self.expiry_warning {
+                    Some(lead_time) if !state.expiry_warned => t.min(state.expires_at - lead_time),
+                    _ => t,
+                }
             }
-            .min(state.expires_at),
         };
         PollAt::Time(t)
     }
@@ -369,18 +782,55 @@ impl<'a> Socket<'a> {
                     requested_ip: dhcp_repr.your_ip, // use the offered ip
                 });
             }
+            (ClientState::Discovering(_), DhcpMessageType::Ack)
+                if self.rapid_commit && dhcp_repr.rapid_commit =>
+            {
+                if let Some((config, renew_at, rebind_at, expires_at)) = Self::parse_ack(
+                    cx.now(),
+                    &dhcp_packet,
+                    &dhcp_repr,
+                    self.install_routes,
+                    self.max_lease_duration,
+                    ServerInfo {
+                        address: src_ip,
+                        identifier: server_identifier,
+                    },
+                ) {
+                    self.bind(
+                        cx.now(),
+                        RenewState {
+                            config,
+                            renew_at,
+                            rebind_at,
+                            expires_at,
+                            renewing: false,
+                            rebinding: false,
+                            expiry_warned: false,
+                        },
+                    );
+                }
+            }
             (ClientState::Requesting(state), DhcpMessageType::Ack) => {
-                if let Some((config, renew_at, rebind_at, expires_at)) =
-                    Self::parse_ack(cx.now(), &dhcp_repr, self.max_lease_duration, state.server)
-                {
-                    self.state = ClientState::Renewing(RenewState {
-                        config,
-                        renew_at,
-                        rebind_at,
-                        expires_at,
-                        rebinding: false,
-                    });
-                    self.config_changed();
+                if let Some((config, renew_at, rebind_at, expires_at)) = Self::parse_ack(
+                    cx.now(),
+                    &dhcp_packet,
+                    &dhcp_repr,
+                    self.install_routes,
+                    self.max_lease_duration,
+                    state.server,
+                ) {
+                    self.bind(
+                        cx.now(),
+                        RenewState {
+                            config,
+                            renew_at,
+                            rebind_at,
+                            expires_at,
+                            renewing: false,
+                            rebinding: false,
+                            expiry_warned: false,
+                        },
+                    );
                 }
             }
             (ClientState::Requesting(_), DhcpMessageType::Nak) => {
@@ -391,13 +841,17 @@ impl<'a> Socket<'a> {
             (ClientState::Renewing(state), DhcpMessageType::Ack) => {
                 if let Some((config, renew_at, rebind_at, expires_at)) = Self::parse_ack(
                     cx.now(),
+                    &dhcp_packet,
                     &dhcp_repr,
+                    self.install_routes,
                     self.max_lease_duration,
                     state.config.server,
                 ) {
                     state.renew_at = renew_at;
                     state.rebind_at = rebind_at;
+                    state.renewing = false;
                     state.rebinding = false;
+                    state.expiry_warned = false;
                     state.expires_at = expires_at;
                     // The `receive_packet_buffer` field isn't populated until
                     // the client asks for the state, but receiving any packet
@@ -417,6 +871,23 @@ impl<'a> Socket<'a> {
             (ClientState::Renewing(_), DhcpMessageType::Nak) => {
                 if !self.ignore_naks {
                     self.reset();
+                    self.notify(Notification::RenewFailed);
+                }
+            }
+            (ClientState::Informing(state), DhcpMessageType::Ack) => {
+                let config = Self::parse_config(
+                    &dhcp_packet,
+                    &dhcp_repr,
+                    self.install_routes,
+                    ServerInfo {
+                        address: src_ip,
+                        identifier: server_identifier,
+                    },
+                    state.address,
+                );
+                if state.config.is_none() {
+                    state.config = Some(config);
+                    self.config_changed();
                 }
             }
             _ => {
@@ -426,11 +897,66 @@ impl<'a> Socket<'a> {
                 );
             }
         }
+
+        self.update_routes(cx);
+    }
+
+    /// Start using a newly acknowledged lease, after probing its address if enabled.
+    fn bind(&mut self, now: Instant, lease: RenewState) {
+        if self.conflict_detection {
+            self.state = ClientState::Probing(ProbeState {
+                lease,
+                retry_at: now,
+                probes: 0,
+            });
+        } else {
+            self.state = ClientState::Renewing(lease);
+            self.config_changed();
+        }
+    }
+
+    /// Process an incoming ARP packet, looking for other users of the probed address.
+    pub(crate) fn process_arp(&mut self, cx: &mut Context, arp_repr: &ArpRepr) {
+        let ClientState::Probing(state) = &self.state else {
+            return;
+        };
+        let ArpRepr::EthernetIpv4 {
+            operation,
+            source_hardware_addr,
+            source_protocol_addr,
+            target_protocol_addr,
+            ..
+        } = *arp_repr;
+
+        if HardwareAddress::Ethernet(source_hardware_addr) == cx.hardware_addr() {
+            return;
+        }
+
+        // Any packet from the address is a conflict, as is a probe for it from
+        // another host, since only one of us can have it.
+        let address = state.lease.config.address.address();
+        if source_protocol_addr == address
+            || (operation == ArpOperation::Request
+                && source_protocol_addr.is_unspecified()
+                && target_protocol_addr == address)
+        {
+            net_debug!(
+                "DHCP address {} is in use by {}, declining",
+                address,
+                source_hardware_addr
+            );
+            self.state = ClientState::Declining(DeclineState {
+                server: state.lease.config.server,
+                address,
+            });
+        }
     }
 
     fn parse_ack(
         now: Instant,
+        dhcp_packet: &DhcpPacket<&[u8]>,
         dhcp_repr: &DhcpRepr,
+        install_routes: bool,
         max_lease_duration: Option<Duration>,
         server: ServerInfo,
     ) -> Option<(Config<'static>, Instant, Instant, Instant)> {
@@ -463,28 +989,13 @@ impl<'a> Socket<'a> {
             lease_duration = lease_duration.min(max_lease_duration);
         }
 
-        // Cleanup the DNS servers list, keeping only unicasts/
-        // TP-Link TD-W8970 sends 0.0.0.0 as second DNS server if there's only one configured :(
-        let mut dns_servers = Vec::new();
-
-        dhcp_repr
-            .dns_servers
-            .iter()
-            .flatten()
-            .filter(|s| s.is_unicast())
-            .for_each(|a| {
-                // This will never produce an error, as both the arrays and `dns_servers`
-                // have length DHCP_MAX_DNS_SERVER_COUNT
-                dns_servers.push(*a).ok();
-            });
-
-        let config = Config {
+        let config = Self::parse_config(
+            dhcp_packet,
+            dhcp_repr,
+            install_routes,
             server,
-            address: Ipv4Cidr::new(dhcp_repr.your_ip, prefix_len),
-            router: dhcp_repr.router,
-            dns_servers,
-            packet: None,
-        };
+            Ipv4Cidr::new(dhcp_repr.your_ip, prefix_len),
+        );
 
         // Set renew and rebind times as per RFC 2131:
         // Times T1 and T2 are configurable by the server through
@@ -526,6 +1037,115 @@ impl<'a> Socket<'a> {
         Some((config, renew_at, rebind_at, expires_at))
     }
 
+    /// Build the configuration provided by the server in an ACK.
+    fn parse_config(
+        dhcp_packet: &DhcpPacket<&[u8]>,
+        dhcp_repr: &DhcpRepr,
+        install_routes: bool,
+        server: ServerInfo,
+        address: Ipv4Cidr,
+    ) -> Config<'static> {
+        // Cleanup the DNS servers list, keeping only unicasts/
+        // TP-Link TD-W8970 sends 0.0.0.0 as second DNS server if there's only one configured :(
+        let mut dns_servers = Vec::new();
+
+        dhcp_repr
+            .dns_servers
+            .iter()
+            .flatten()
+            .filter(|s| s.is_unicast())
+            .for_each(|a| {
+                // This will never produce an error, as both the arrays and `dns_servers`
+                // have length DHCP_MAX_DNS_SERVER_COUNT
+                dns_servers.push(*a).ok();
+            });
+
+        let mut router = dhcp_repr.router;
+        let mut routes = Vec::new();
+        if install_routes {
+            let option = dhcp_packet
+                .options()
+                .find(|option| option.kind == dhcpv4_field::OPT_CLASSLESS_STATIC_ROUTE)
+                .or_else(|| {
+                    dhcp_packet
+                        .options()
+                        .find(|option| option.kind == dhcpv4_field::OPT_MS_CLASSLESS_STATIC_ROUTE)
+                });
+            if let Some(option) = option {
+                match Self::parse_classless_static_routes(option.data) {
+                    Some(parsed) => {
+                        routes = parsed;
+                        router = None;
+                    }
+                    None => net_debug!("DHCP ignoring malformed classless static routes"),
+                }
+            }
+        }
+
+        Config {
+            server,
+            address,
+            router,
+            dns_servers,
+            routes,
+            packet: None,
+        }
+    }
+
+    /// Parse the data of a classless static route option, as described in RFC 3442.
+    fn parse_classless_static_routes(
+        mut data: &[u8],
+    ) -> Option<Vec<StaticRoute, IFACE_MAX_ROUTE_COUNT>> {
+        let mut routes = Vec::new();
+        while let Some((&prefix_len, rest)) = data.split_first() {
+            if prefix_len > 32 {
+                return None;
+            }
+            let octets = (prefix_len as usize + 7) / 8;
+            if rest.len() < octets + 4 {
+                return None;
+            }
+            let mut destination = [0; 4];
+            destination[..octets].copy_from_slice(&rest[..octets]);
+            let via_router = Ipv4Address::from_bytes(&rest[octets..octets + 4]);
+            data = &rest[octets + 4..];
+
+            let cidr = Ipv4Cidr::new(Ipv4Address(destination), prefix_len).network();
+            if via_router.is_unspecified() {
+                net_debug!("DHCP ignoring on-link route to {}", cidr);
+                continue;
+            }
+            if routes.push(StaticRoute { cidr, via_router }).is_err() {
+                net_debug!("DHCP ignoring route to {}: too many routes", cidr);
+            }
+        }
+        Some(routes)
+    }
+
+    /// Make the routes installed in the interface match those of the current lease.
+    fn update_routes(&mut self, cx: &mut Context) {
+        let routes: &[StaticRoute] = match self.state.config() {
+            Some(config) => &config.routes,
+            None => &[],
+        };
+        if self.installed_routes == routes {
+            return;
+        }
+
+        let installed_routes = &mut self.installed_routes;
+        cx.routes_mut().update(|table| {
+            table.retain(|route| !installed_routes.iter().any(|r| r.matches(route)));
+            installed_routes.clear();
+            for route in routes {
+                if table.push((*route).into()).is_err() {
+                    net_debug!("DHCP cannot install route to {}: table full", route.cidr);
+                    break;
+                }
+                installed_routes.push(*route).ok();
+            }
+        });
+    }
+
     #[cfg(not(test))]
     fn random_transaction_id(cx: &mut Context) -> u32 {
         cx.rand().rand_u32()
@@ -540,6 +1160,22 @@ impl<'a> Socket<'a> {
     where
         F: FnOnce(&mut Context, (Ipv4Repr, UdpRepr, DhcpRepr)) -> Result<(), E>,
     {
+        // The lease may have been lost through `reset`.
+        self.update_routes(cx);
+
+        // Report a lease about to expire, whether or not a request is due.
+        if let (ClientState::Renewing(state), Some(lead_time)) =
+            (&mut self.state, self.expiry_warning)
+        {
+            let now = cx.now();
+            if !state.expiry_warned && now >= state.expires_at - lead_time && now < state.expires_at
+            {
+                state.expiry_warned = true;
+                let expires_at = state.expires_at;
+                self.notify(Notification::LeaseExpiringSoon(expires_at));
+            }
+        }
+
         // note: Dhcpv4Socket is only usable in ethernet mediums, so the
         // unwrap can never fail.
         let HardwareAddress::Ethernet(ethernet_addr) = cx.hardware_addr() else {
@@ -554,6 +1190,31 @@ impl<'a> Socket<'a> {
         // may fail. We only want to update state after succesfully sending.
         let next_transaction_id = Self::random_transaction_id(cx);
 
+        let mut parameter_request_list = Vec::<u8, { u8::MAX as usize }>::new();
+        for &code in self
+            .parameter_request_list
+            .unwrap_or(DEFAULT_PARAMETER_REQUEST_LIST)
+            .iter()
+            .chain(self.requested_options)
+            .chain(if self.install_routes {
+                &[
+                    dhcpv4_field::OPT_CLASSLESS_STATIC_ROUTE,
+                    dhcpv4_field::OPT_MS_CLASSLESS_STATIC_ROUTE,
+                ][..]
+            } else {
+                &[]
+            })
+            .chain(if self.vendor_class_id.is_some() {
+                &[dhcpv4_field::OPT_VENDOR_SPECIFIC_INFO][..]
+            } else {
+                &[]
+            })
+        {
+            if !parameter_request_list.contains(&code) {
+                parameter_request_list.push(code).ok();
+            }
+        }
+
         let mut dhcp_repr = DhcpRepr {
             message_type: DhcpMessageType::Discover,
             transaction_id: next_transaction_id,
@@ -567,17 +1228,22 @@ impl<'a> Socket<'a> {
             relay_agent_ip: Ipv4Address::UNSPECIFIED,
             broadcast: false,
             requested_ip: None,
-            client_identifier: Some(ethernet_addr),
+            client_identifier: match self.client_identifier {
+                Some(_) => None,
+                None => Some(ethernet_addr),
+            },
+            raw_client_identifier: self.client_identifier,
             server_identifier: None,
-            parameter_request_list: Some(
-                self.parameter_request_list
-                    .unwrap_or(DEFAULT_PARAMETER_REQUEST_LIST),
-            ),
+            parameter_request_list: Some(&parameter_request_list),
             max_size: Some((cx.ip_mtu() - MAX_IPV4_HEADER_LEN - UDP_HEADER_LEN) as u16),
             lease_duration: None,
             renew_duration: None,
             rebind_duration: None,
             dns_servers: None,
+            rapid_commit: false,
+            hostname: self.hostname.filter(|_| self.fqdn.is_none()),
+            fqdn: self.fqdn,
+            vendor_class_id: self.vendor_class_id,
             additional_options: self.outgoing_options,
         };
 
@@ -591,7 +1257,7 @@ impl<'a> Socket<'a> {
             dst_addr: Ipv4Address::BROADCAST,
             next_header: IpProtocol::Udp,
             payload_len: 0, // filled right before emit
-            hop_limit: 64,
+            hop_limit: cx.ipv4_hop_limit(),
         };
 
         match &mut self.state {
@@ -600,6 +1266,8 @@ impl<'a> Socket<'a> {
                     return Ok(());
                 }
 
+                dhcp_repr.rapid_commit = self.rapid_commit;
+
                 // send packet
                 net_debug!(
                     "DHCP send DISCOVER to {}: {:?}",
@@ -645,24 +1313,70 @@ impl<'a> Socket<'a> {
                 self.transaction_id = next_transaction_id;
                 Ok(())
             }
-            ClientState::Renewing(state) => {
-                let now = cx.now();
-                if state.expires_at <= now {
-                    net_debug!("DHCP lease expired");
-                    self.reset();
-                    // return Ok so we get polled again
-                    return Ok(());
-                }
+            ClientState::Probing(_) => Ok(()),
+            ClientState::Declining(state) => {
+                dhcp_repr.message_type = DhcpMessageType::Decline;
+                dhcp_repr.requested_ip = Some(state.address);
+                dhcp_repr.server_identifier = Some(state.server.identifier);
+                dhcp_repr.parameter_request_list = None;
+                dhcp_repr.max_size = None;
+                dhcp_repr.vendor_class_id = None;
 
-                if now < state.renew_at || state.rebinding && now < state.rebind_at {
-                    return Ok(());
+                net_debug!(
+                    "DHCP send decline to {}: {:?}",
+                    ipv4_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv4_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+                self.state = ClientState::Discovering(DiscoverState {
+                    retry_at: cx.now() + DECLINE_BACKOFF,
+                });
+                self.transaction_id = next_transaction_id;
+                Ok(())
+            }
+            ClientState::Informing(state) => {
+                if state.config.is_some() || cx.now() < state.retry_at {
+                    return Ok(());
                 }
 
-                state.rebinding |= now >= state.rebind_at;
+                ipv4_repr.src_addr = state.address.address();
+                dhcp_repr.message_type = DhcpMessageType::Inform;
+                dhcp_repr.client_ip = state.address.address();
+
+                net_debug!(
+                    "DHCP send inform to {}: {:?}",
+                    ipv4_repr.dst_addr,
+                    dhcp_repr
+                );
+                ipv4_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
+                emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+                state.retry_at = cx.now() + self.retry_config.discover_timeout;
+                self.transaction_id = next_transaction_id;
+                Ok(())
+            }
+            ClientState::Renewing(state) => {
+                let now = cx.now();
+                if state.expires_at <= now {
+                    net_debug!("DHCP lease expired");
+                    self.reset();
+                    self.notify(Notification::RenewFailed);
+                    self.update_routes(cx);
+                    // return Ok so we get polled again
+                    return Ok(());
+                }
+
+                if now < state.renew_at || state.rebinding && now < state.rebind_at {
+                    return Ok(());
+                }
+
+                let rebinding = state.rebinding || now >= state.rebind_at;
 
                 ipv4_repr.src_addr = state.config.address.address();
                 // Renewing is unicast to the original server, rebinding is broadcast
-                if !state.rebinding {
+                if !rebinding {
                     ipv4_repr.dst_addr = state.config.server.address;
                 }
                 dhcp_repr.message_type = DhcpMessageType::Request;
@@ -672,6 +1386,17 @@ impl<'a> Socket<'a> {
                 ipv4_repr.payload_len = udp_repr.header_len() + dhcp_repr.buffer_len();
                 emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
 
+                // Only report the first request of each phase, and not the retransmissions.
+                let notification = if rebinding && !state.rebinding {
+                    Some(Notification::Rebinding)
+                } else if !state.renewing {
+                    Some(Notification::Renewing)
+                } else {
+                    None
+                };
+                state.renewing = true;
+                state.rebinding = rebinding;
+
                 // In both RENEWING and REBINDING states, if the client receives no
                 // response to its DHCPREQUEST message, the client SHOULD wait one-half
                 // of the remaining time until T2 (in RENEWING state) and one-half of
@@ -693,39 +1418,132 @@ impl<'a> Socket<'a> {
                 }
 
                 self.transaction_id = next_transaction_id;
+                if let Some(notification) = notification {
+                    self.notify(notification);
+                }
                 Ok(())
             }
         }
     }
 
+    pub(crate) fn dispatch_arp<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, ArpRepr) -> Result<(), E>,
+    {
+        let ClientState::Probing(state) = &mut self.state else {
+            return Ok(());
+        };
+        if cx.now() < state.retry_at {
+            return Ok(());
+        }
+
+        let HardwareAddress::Ethernet(ethernet_addr) = cx.hardware_addr() else {
+            panic!("using DHCPv4 socket with a non-ethernet hardware address.");
+        };
+        let address = state.lease.config.address.address();
+
+        if state.probes < PROBE_NUM {
+            // Probes have an unspecified sender address, so that they don't
+            // pollute the ARP caches of other hosts.
+            let arp_repr = ArpRepr::EthernetIpv4 {
+                operation: ArpOperation::Request,
+                source_hardware_addr: ethernet_addr,
+                source_protocol_addr: Ipv4Address::UNSPECIFIED,
+                target_hardware_addr: EthernetAddress::BROADCAST,
+                target_protocol_addr: address,
+            };
+            net_debug!("DHCP send ARP probe for {}", address);
+            emit(cx, arp_repr)?;
+
+            state.probes += 1;
+            state.retry_at = cx.now()
+                + if state.probes < PROBE_NUM {
+                    PROBE_INTERVAL
+                } else {
+                    ANNOUNCE_WAIT
+                };
+            return Ok(());
+        }
+
+        // Nobody answered the probes: announce the address and start using it.
+        let arp_repr = ArpRepr::EthernetIpv4 {
+            operation: ArpOperation::Request,
+            source_hardware_addr: ethernet_addr,
+            source_protocol_addr: address,
+            target_hardware_addr: EthernetAddress::BROADCAST,
+            target_protocol_addr: address,
+        };
+        net_debug!("DHCP send ARP announcement for {}", address);
+        emit(cx, arp_repr)?;
+
+        self.state = ClientState::Renewing(state.lease.clone());
+        self.config_changed();
+        Ok(())
+    }
+
     /// Reset state and restart discovery phase.
     ///
     /// Use this to speed up acquisition of an address in a new
     /// network if a link was down and it is now back up.
     pub fn reset(&mut self) {
         net_trace!("DHCP reset");
-        if let ClientState::Renewing(_) = &self.state {
+        if self.state.config().is_some() {
             self.config_changed();
         }
-        self.state = ClientState::Discovering(DiscoverState {
-            retry_at: Instant::from_millis(0),
-        });
+        self.notifications.clear();
+        self.state = match self.inform_address {
+            Some(address) => ClientState::Informing(InformState {
+                retry_at: Instant::from_millis(0),
+                address,
+                config: None,
+            }),
+            None => ClientState::Discovering(DiscoverState {
+                retry_at: Instant::from_millis(0),
+            }),
+        };
+    }
+
+    /// Renew the lease right away, without waiting for the renew timer.
+    ///
+    /// Use this when the network may have changed, for example after the link was
+    /// briefly down. It has no effect if the socket holds no lease.
+    pub fn force_renew(&mut self) {
+        if let ClientState::Renewing(state) = &mut self.state {
+            net_trace!("DHCP force renew");
+            if state.rebinding {
+                state.rebind_at = Instant::from_millis(0);
+            } else {
+                state.renew_at = Instant::from_millis(0);
+            }
+        }
     }
 
     /// Query the socket for configuration changes.
     ///
     /// The socket has an internal "configuration changed" flag. If
     /// set, this function returns the configuration and resets the flag.
+    /// Events about the lease, such as [`Event::Renewing`], are returned
+    /// before the configuration change that follows them.
     pub fn poll(&mut self) -> Option<Event> {
+        if let Some(notification) = self.notifications.pop_front() {
+            return Some(match notification {
+                Notification::Renewing => Event::Renewing,
+                Notification::Rebinding => Event::Rebinding,
+                Notification::RenewFailed => Event::RenewFailed,
+                Notification::LeaseExpiringSoon(expires_at) => Event::LeaseExpiringSoon(expires_at),
+            });
+        }
+
         if !self.config_changed {
             None
-        } else if let ClientState::Renewing(state) = &self.state {
+        } else if let Some(config) = self.state.config() {
             self.config_changed = false;
             Some(Event::Configured(Config {
-                server: state.config.server,
-                address: state.config.address,
-                router: state.config.router,
-                dns_servers: state.config.dns_servers.clone(),
+                server: config.server,
+                address: config.address,
+                router: config.router,
+                dns_servers: config.dns_servers.clone(),
+                routes: config.routes.clone(),
                 packet: self
                     .receive_packet_buffer
                     .as_deref()
@@ -746,6 +1564,17 @@ impl<'a> Socket<'a> {
         self.waker.wake();
     }
 
+    /// Queue an event about the lease to be returned by `poll`, and wake a possible waker.
+    fn notify(&mut self, notification: Notification) {
+        // The oldest events are dropped if the application doesn't poll.
+        if self.notifications.is_full() {
+            self.notifications.pop_front();
+        }
+        let _ = self.notifications.push_back(notification);
+        #[cfg(feature = "async")]
+        self.waker.wake();
+    }
+
     /// Register a waker.
     ///
     /// The waker is woken on state changes that might affect the return value
@@ -846,6 +1675,32 @@ mod test {
         assert_eq!(i, reprs.len());
     }
 
+    fn recv_arp(s: &mut TestSocket, timestamp: Instant, reprs: &[ArpRepr]) {
+        s.cx.set_now(timestamp);
+
+        let mut i = 0;
+
+        loop {
+            let mut emitted = false;
+            let _ = s.socket.dispatch_arp(&mut s.cx, |_, arp_repr| {
+                net_trace!("recv: {:?}", arp_repr);
+
+                match reprs.get(i) {
+                    Some(want_repr) => assert_eq!(want_repr, &arp_repr),
+                    None => panic!("Too many reprs emitted"),
+                }
+                i += 1;
+                emitted = true;
+                Ok::<_, ()>(())
+            });
+            if !emitted {
+                break;
+            }
+        }
+
+        assert_eq!(i, reprs.len());
+    }
+
     macro_rules! send {
         ($socket:ident, $repr:expr) =>
             (send!($socket, time 0, $repr));
@@ -953,6 +1808,7 @@ mod test {
         broadcast: false,
         requested_ip: None,
         client_identifier: None,
+        raw_client_identifier: None,
         server_identifier: None,
         parameter_request_list: None,
         dns_servers: None,
@@ -960,9 +1816,39 @@ mod test {
         renew_duration: None,
         rebind_duration: None,
         lease_duration: None,
+        rapid_commit: false,
+        hostname: None,
+        fqdn: None,
+        vendor_class_id: None,
         additional_options: &[],
     };
 
+    const DHCP_DECLINE: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Decline,
+        client_identifier: Some(MY_MAC),
+        requested_ip: Some(MY_IP),
+        server_identifier: Some(SERVER_IP),
+        ..DHCP_DEFAULT
+    };
+
+    const OTHER_MAC: EthernetAddress = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x03]);
+
+    const ARP_PROBE: ArpRepr = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: MY_MAC,
+        source_protocol_addr: Ipv4Address::UNSPECIFIED,
+        target_hardware_addr: EthernetAddress::BROADCAST,
+        target_protocol_addr: MY_IP,
+    };
+
+    const ARP_ANNOUNCE: ArpRepr = ArpRepr::EthernetIpv4 {
+        operation: ArpOperation::Request,
+        source_hardware_addr: MY_MAC,
+        source_protocol_addr: MY_IP,
+        target_hardware_addr: EthernetAddress::BROADCAST,
+        target_protocol_addr: MY_IP,
+    };
+
     const DHCP_DISCOVER: DhcpRepr = DhcpRepr {
         message_type: DhcpMessageType::Discover,
         client_identifier: Some(MY_MAC),
@@ -1079,11 +1965,14 @@ mod test {
                 address: Ipv4Cidr::new(MY_IP, 24),
                 dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
                 router: Some(SERVER_IP),
+                routes: Vec::new(),
                 packet: None,
             },
             renew_at: Instant::from_secs(500),
             rebind_at: Instant::from_secs(875),
+            renewing: false,
             rebinding: false,
+            expiry_warned: false,
             expires_at: Instant::from_secs(1000),
         });
 
@@ -1112,6 +2001,7 @@ mod test {
                 address: Ipv4Cidr::new(MY_IP, 24),
                 dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
                 router: Some(SERVER_IP),
+                routes: Vec::new(),
                 packet: None,
             }))
         );
@@ -1148,6 +2038,7 @@ mod test {
                 address: Ipv4Cidr::new(MY_IP, 24),
                 dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
                 router: Some(SERVER_IP),
+                routes: Vec::new(),
                 packet: None,
             }))
         );
@@ -1242,6 +2133,7 @@ mod test {
         recv!(s, []);
         assert_eq!(s.poll(), None);
         recv!(s, time 500_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+        assert_eq!(s.poll(), Some(Event::Renewing));
         assert_eq!(s.poll(), None);
 
         match &s.state {
@@ -1306,6 +2198,510 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_requested_options() {
+        let mut s = socket();
+        s.set_receive_packet_buffer(vec![0; 1500].leak());
+        s.set_requested_options(&[42, 3, 43]);
+
+        const NTP_SERVER: &[u8] = &[192, 168, 1, 2];
+        const VENDOR_INFO: &[u8] = &[1, 2, 0xab, 0xcd];
+        const ACK_OPTIONS: &[DhcpOption] = &[
+            DhcpOption {
+                kind: 42,
+                data: NTP_SERVER,
+            },
+            DhcpOption {
+                kind: 66,
+                data: b"tftp",
+            },
+            DhcpOption {
+                kind: 43,
+                data: VENDOR_INFO,
+            },
+        ];
+
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 42, 43]),
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 42, 43]),
+                    ..DHCP_REQUEST
+                }
+            )]
+        );
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    additional_options: ACK_OPTIONS,
+                    ..dhcp_ack()
+                }
+            )
+        );
+
+        let Some(Event::Configured(config)) = s.poll() else {
+            panic!("Expected a configuration");
+        };
+        assert_eq!(config.option(42), Some(NTP_SERVER));
+        assert_eq!(config.option(43), Some(VENDOR_INFO));
+        assert_eq!(config.option(3), Some(&SERVER_IP.0[..]));
+        assert_eq!(config.option(66), Some(&b"tftp"[..]));
+        assert_eq!(config.option(125), None);
+    }
+
+    #[test]
+    fn test_requested_options_without_buffer() {
+        let mut s = socket();
+        s.set_requested_options(&[42]);
+
+        const ACK_OPTIONS: &[DhcpOption] = &[DhcpOption {
+            kind: 42,
+            data: &[192, 168, 1, 2],
+        }];
+
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 42]),
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 42]),
+                    ..DHCP_REQUEST
+                }
+            )]
+        );
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    additional_options: ACK_OPTIONS,
+                    ..dhcp_ack()
+                }
+            )
+        );
+
+        // Without a receive packet buffer, the options are not kept.
+        let Some(Event::Configured(config)) = s.poll() else {
+            panic!("Expected a configuration");
+        };
+        assert_eq!(config.option(42), None);
+        assert_eq!(config.options().count(), 0);
+    }
+
+    #[test]
+    fn test_parse_classless_static_routes() {
+        let routes = Socket::parse_classless_static_routes(&[
+            0, 192, 168, 1, 1, // default route
+            8, 10, 192, 168, 1, 2, // 10.0.0.0/8
+            25, 172, 16, 1, 128, 192, 168, 1, 3, // 172.16.1.128/25
+            32, 1, 2, 3, 4, 0, 0, 0, 0, // on-link route, ignored
+        ])
+        .unwrap();
+        assert_eq!(
+            &routes[..],
+            &[
+                StaticRoute {
+                    cidr: Ipv4Cidr::new(Ipv4Address::UNSPECIFIED, 0),
+                    via_router: Ipv4Address([192, 168, 1, 1]),
+                },
+                StaticRoute {
+                    cidr: Ipv4Cidr::new(Ipv4Address([10, 0, 0, 0]), 8),
+                    via_router: Ipv4Address([192, 168, 1, 2]),
+                },
+                StaticRoute {
+                    cidr: Ipv4Cidr::new(Ipv4Address([172, 16, 1, 128]), 25),
+                    via_router: Ipv4Address([192, 168, 1, 3]),
+                },
+            ]
+        );
+
+        assert_eq!(
+            Socket::parse_classless_static_routes(&[33, 0, 0, 0, 0]),
+            None
+        );
+        assert_eq!(
+            Socket::parse_classless_static_routes(&[8, 10, 192, 168, 1]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_install_routes() {
+        let mut s = socket();
+        s.set_install_routes(true);
+
+        const ROUTES: &[u8] = &[8, 10, 192, 168, 1, 2, 0, 192, 168, 1, 1];
+        const ACK_OPTIONS: &[DhcpOption] = &[DhcpOption {
+            kind: 121,
+            data: ROUTES,
+        }];
+        const OTHER_ROUTER: Ipv4Address = Ipv4Address([192, 168, 1, 2]);
+
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 121, 249]),
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 121, 249]),
+                    ..DHCP_REQUEST
+                }
+            )]
+        );
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    additional_options: ACK_OPTIONS,
+                    ..dhcp_ack()
+                }
+            )
+        );
+
+        // The router option is ignored in favor of the routes.
+        let Some(Event::Configured(config)) = s.poll() else {
+            panic!("Expected a configuration");
+        };
+        assert_eq!(config.router, None);
+        assert_eq!(config.routes.len(), 2);
+
+        let now = s.cx.now();
+        let routes = s.cx.routes_mut();
+        assert_eq!(
+            routes.lookup(&IpAddress::v4(10, 1, 2, 3), now),
+            Some(OTHER_ROUTER.into())
+        );
+        assert_eq!(
+            routes.lookup(&IpAddress::v4(8, 8, 8, 8), now),
+            Some(SERVER_IP.into())
+        );
+
+        // The routes are removed when the lease is lost.
+        s.reset();
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 121, 249]),
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+        assert_eq!(
+            s.cx.routes_mut().lookup(&IpAddress::v4(10, 1, 2, 3), now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_client_identifier() {
+        let mut s = socket();
+        s.set_client_identifier(Some(b"\x00serial-42"));
+
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    client_identifier: None,
+                    raw_client_identifier: Some(b"\x00serial-42"),
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+
+        // Replies echoing the identifier (RFC 6842) are accepted.
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    raw_client_identifier: Some(b"\x00serial-42"),
+                    ..dhcp_offer()
+                }
+            )
+        );
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    client_identifier: None,
+                    raw_client_identifier: Some(b"\x00serial-42"),
+                    ..DHCP_REQUEST
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_rapid_commit() {
+        let mut s = socket();
+        s.set_rapid_commit(true);
+
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    rapid_commit: true,
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+
+        // Acks without the rapid commit option are ignored.
+        send!(s, (IP_RECV, UDP_RECV, dhcp_ack()));
+        assert_eq!(s.poll(), None);
+
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    rapid_commit: true,
+                    ..dhcp_ack()
+                }
+            )
+        );
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: ServerInfo {
+                    address: SERVER_IP,
+                    identifier: SERVER_IP,
+                },
+                address: Ipv4Cidr::new(MY_IP, 24),
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+                router: Some(SERVER_IP),
+                routes: Vec::new(),
+                packet: None,
+            }))
+        );
+        recv!(s, time 499_000, []);
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+    }
+
+    #[test]
+    fn test_inform() {
+        let mut s = socket();
+        s.set_inform_address(Some(Ipv4Cidr::new(MY_IP, 24)));
+
+        const DHCP_INFORM: DhcpRepr = DhcpRepr {
+            message_type: DhcpMessageType::Inform,
+            client_identifier: Some(MY_MAC),
+            client_ip: MY_IP,
+            max_size: Some(1432),
+            parameter_request_list: Some(&[1, 3, 6]),
+            ..DHCP_DEFAULT
+        };
+
+        recv!(s, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_INFORM)]);
+        recv!(s, time 5_000, []);
+        recv!(s, time 10_000, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_INFORM)]);
+
+        // The acknowledgment has no lease, nor address.
+        send!(
+            s,
+            time 10_000,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    your_ip: Ipv4Address::UNSPECIFIED,
+                    lease_duration: None,
+                    ..dhcp_ack()
+                }
+            )
+        );
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: ServerInfo {
+                    address: SERVER_IP,
+                    identifier: SERVER_IP,
+                },
+                address: Ipv4Cidr::new(MY_IP, 24),
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+                router: Some(SERVER_IP),
+                routes: Vec::new(),
+                packet: None,
+            }))
+        );
+
+        // No more informs are sent.
+        recv!(s, time 1_000_000, []);
+        assert_eq!(s.poll(), None);
+
+        // Leaving the mode drops the configuration.
+        s.set_inform_address(None);
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        recv!(s, time 1_000_000, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+    }
+
+    #[test]
+    fn test_hostname() {
+        let mut s = socket();
+        s.set_hostname(Some("sensor-12"));
+
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    hostname: Some("sensor-12"),
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+
+        // The FQDN replaces the host name.
+        s.set_fqdn(Some("sensor-12.example.com"));
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    fqdn: Some("sensor-12.example.com"),
+                    ..DHCP_REQUEST
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_vendor_class_id() {
+        let mut s = socket();
+        s.set_receive_packet_buffer(vec![0; 1500].leak());
+        s.set_vendor_class_id(Some(b"Cisco AP c2700"));
+
+        const VENDOR_INFO: &[u8] = &[0xf1, 0x04, 192, 168, 1, 10];
+        const ACK_OPTIONS: &[DhcpOption] = &[DhcpOption {
+            kind: 43,
+            data: VENDOR_INFO,
+        }];
+
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 43]),
+                    vendor_class_id: Some(b"Cisco AP c2700"),
+                    ..DHCP_DISCOVER
+                }
+            )]
+        );
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(
+            s,
+            [(
+                IP_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    parameter_request_list: Some(&[1, 3, 6, 43]),
+                    vendor_class_id: Some(b"Cisco AP c2700"),
+                    ..DHCP_REQUEST
+                }
+            )]
+        );
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                DhcpRepr {
+                    additional_options: ACK_OPTIONS,
+                    ..dhcp_ack()
+                }
+            )
+        );
+
+        let Some(Event::Configured(config)) = s.poll() else {
+            panic!("Expected a configuration");
+        };
+        assert_eq!(config.option(43), Some(VENDOR_INFO));
+        let mut vendor_options = config.vendor_options();
+        assert_eq!(
+            vendor_options.next(),
+            Some(DhcpOption {
+                kind: 0xf1,
+                data: &[192, 168, 1, 10]
+            })
+        );
+        assert_eq!(vendor_options.next(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid vendor class identifier")]
+    fn test_vendor_class_id_invalid() {
+        let mut s = socket();
+        s.set_vendor_class_id(Some(b""));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid FQDN")]
+    fn test_fqdn_invalid() {
+        let mut s = socket();
+        s.set_fqdn(Some("sensor-12..example.com"));
+    }
+
     #[test]
     fn test_renew_rebind_timeout() {
         let mut s = socket_bound();
@@ -1342,4 +2738,179 @@ mod test {
         send!(s, time 500_000, (IP_SERVER_BROADCAST, UDP_RECV, DHCP_NAK));
         recv!(s, time 500_000, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
     }
+
+    #[test]
+    fn test_renew_events() {
+        let mut s = socket_bound();
+
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+        assert_eq!(s.poll(), Some(Event::Renewing));
+        // Retransmissions are not reported.
+        recv!(s, time 687_500, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+        assert_eq!(s.poll(), None);
+        recv!(s, time 875_000, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_REBIND)]);
+        assert_eq!(s.poll(), Some(Event::Rebinding));
+        recv!(s, time 937_500, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_REBIND)]);
+        assert_eq!(s.poll(), None);
+
+        recv!(s, time 1_000_000, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+        assert_eq!(s.poll(), Some(Event::RenewFailed));
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        assert_eq!(s.poll(), None);
+    }
+
+    #[test]
+    fn test_renew_events_after_ack() {
+        let mut s = socket_bound();
+
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+        assert_eq!(s.poll(), Some(Event::Renewing));
+        send!(s, time 500_000, (IP_RECV, UDP_RECV, dhcp_ack()));
+        assert_eq!(s.poll(), None);
+
+        // The next renewal is reported again.
+        recv!(s, time 1_000_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+        assert_eq!(s.poll(), Some(Event::Renewing));
+    }
+
+    #[test]
+    fn test_renew_nak_event() {
+        let mut s = socket_bound();
+
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+        send!(s, time 500_000, (IP_SERVER_BROADCAST, UDP_RECV, DHCP_NAK));
+        assert_eq!(s.poll(), Some(Event::RenewFailed));
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        assert_eq!(s.poll(), None);
+    }
+
+    #[test]
+    fn test_expiry_warning() {
+        let mut s = socket_bound();
+        s.set_expiry_warning(Some(Duration::from_secs(100)));
+
+        recv!(s, time 899_000, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_REBIND)]);
+        assert_eq!(s.poll(), Some(Event::Rebinding));
+        assert_eq!(s.poll(), None);
+        recv!(s, time 900_000, []);
+        assert_eq!(
+            s.poll(),
+            Some(Event::LeaseExpiringSoon(Instant::from_secs(1000)))
+        );
+        // It is only reported once.
+        recv!(s, time 959_000, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_REBIND)]);
+        assert_eq!(s.poll(), None);
+
+        // Renewing the lease rearms the warning.
+        send!(s, time 960_000, (IP_RECV, UDP_RECV, dhcp_ack()));
+        assert_eq!(s.poll(), None);
+        recv!(s, time 1_859_000, [(IP_BROADCAST_ADDRESSED, UDP_SEND, DHCP_REBIND)]);
+        assert_eq!(s.poll(), Some(Event::Rebinding));
+        recv!(s, time 1_860_000, []);
+        assert_eq!(
+            s.poll(),
+            Some(Event::LeaseExpiringSoon(Instant::from_secs(1960)))
+        );
+    }
+
+    #[test]
+    fn test_force_renew() {
+        let mut s = socket_bound();
+
+        recv!(s, time 100_000, []);
+        s.force_renew();
+        recv!(s, time 100_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+        assert_eq!(s.poll(), Some(Event::Renewing));
+
+        // Without a lease, there is nothing to renew.
+        let mut s = socket();
+        s.force_renew();
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+    }
+
+    fn socket_probing() -> TestSocket {
+        let mut s = socket();
+        s.set_conflict_detection(true);
+
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_offer()));
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_REQUEST)]);
+        send!(s, (IP_RECV, UDP_RECV, dhcp_ack()));
+        assert_eq!(s.poll(), None);
+
+        s
+    }
+
+    #[test]
+    fn test_conflict_detection() {
+        let mut s = socket_probing();
+
+        recv_arp(&mut s, Instant::from_millis(0), &[ARP_PROBE]);
+        recv_arp(&mut s, Instant::from_millis(999), &[]);
+        recv_arp(&mut s, Instant::from_millis(1_000), &[ARP_PROBE]);
+        recv_arp(&mut s, Instant::from_millis(2_000), &[ARP_PROBE]);
+        recv_arp(&mut s, Instant::from_millis(3_999), &[]);
+        assert_eq!(s.poll(), None);
+
+        // Our own probes, and packets about other addresses, are no conflict.
+        s.socket.process_arp(&mut s.cx, &ARP_PROBE);
+        s.socket.process_arp(
+            &mut s.cx,
+            &ArpRepr::EthernetIpv4 {
+                operation: ArpOperation::Reply,
+                source_hardware_addr: OTHER_MAC,
+                source_protocol_addr: SERVER_IP,
+                target_hardware_addr: MY_MAC,
+                target_protocol_addr: Ipv4Address::UNSPECIFIED,
+            },
+        );
+
+        recv_arp(&mut s, Instant::from_millis(4_000), &[ARP_ANNOUNCE]);
+        assert!(matches!(s.poll(), Some(Event::Configured(_))));
+        recv!(s, time 499_000, []);
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, DHCP_RENEW)]);
+    }
+
+    #[test]
+    fn test_conflict_detection_reply() {
+        let mut s = socket_probing();
+
+        recv_arp(&mut s, Instant::from_millis(0), &[ARP_PROBE]);
+        s.socket.process_arp(
+            &mut s.cx,
+            &ArpRepr::EthernetIpv4 {
+                operation: ArpOperation::Reply,
+                source_hardware_addr: OTHER_MAC,
+                source_protocol_addr: MY_IP,
+                target_hardware_addr: MY_MAC,
+                target_protocol_addr: Ipv4Address::UNSPECIFIED,
+            },
+        );
+        recv_arp(&mut s, Instant::from_millis(1_000), &[]);
+
+        recv!(s, time 1_000, [(IP_BROADCAST, UDP_SEND, DHCP_DECLINE)]);
+        assert_eq!(s.poll(), None);
+        recv!(s, time 10_999, []);
+        recv!(s, time 11_000, [(IP_BROADCAST, UDP_SEND, DHCP_DISCOVER)]);
+    }
+
+    #[test]
+    fn test_conflict_detection_probe() {
+        let mut s = socket_probing();
+
+        recv_arp(&mut s, Instant::from_millis(0), &[ARP_PROBE]);
+        s.socket.process_arp(
+            &mut s.cx,
+            &ArpRepr::EthernetIpv4 {
+                operation: ArpOperation::Request,
+                source_hardware_addr: OTHER_MAC,
+                source_protocol_addr: Ipv4Address::UNSPECIFIED,
+                target_hardware_addr: EthernetAddress::BROADCAST,
+                target_protocol_addr: MY_IP,
+            },
+        );
+
+        recv!(s, [(IP_BROADCAST, UDP_SEND, DHCP_DECLINE)]);
+        assert_eq!(s.poll(), None);
+    }
 }
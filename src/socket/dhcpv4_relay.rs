@@ -0,0 +1,670 @@
+use crate::iface::Context;
+use crate::storage::Empty;
+use crate::wire::dhcpv4::{field as dhcpv4_field, OpCode};
+use crate::wire::{
+    DhcpOption, DhcpPacket, IpProtocol, Ipv4Address, Ipv4Repr, UdpRepr, DHCP_CLIENT_PORT,
+    DHCP_SERVER_PORT,
+};
+
+use super::PollAt;
+
+/// Maximum value of the hops field of the requests forwarded to the server (RFC 1542 § 4.1.1).
+const MAX_HOPS: u8 = 16;
+
+/// A relayed message metadata, holding its destination.
+pub type PacketMetadata = crate::storage::PacketMetadata<Ipv4Address>;
+
+/// A buffer of the messages waiting to be relayed.
+pub type PacketBuffer<'a> = crate::storage::PacketBuffer<'a, Ipv4Address>;
+
+/// DHCP relay agent configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config<'a> {
+    /// Address of the relay agent on the network of the clients, given to the server as
+    /// `giaddr`. The server uses it to select the subnet of the addresses it assigns, and
+    /// sends its replies to it.
+    pub agent_address: Ipv4Address,
+    /// Address of the DHCP server the client messages are forwarded to.
+    pub server: Ipv4Address,
+    /// Agent Circuit ID sub-option of the Relay Agent Information option, identifying the
+    /// circuit the client messages were received on.
+    pub circuit_id: Option<&'a [u8]>,
+    /// Agent Remote ID sub-option of the Relay Agent Information option, identifying the
+    /// remote host end of the circuit.
+    pub remote_id: Option<&'a [u8]>,
+}
+
+impl<'a> Config<'a> {
+    /// Create a configuration forwarding client messages to `server`, without relay agent
+    /// information.
+    pub fn new(agent_address: Ipv4Address, server: Ipv4Address) -> Self {
+        Config {
+            agent_address,
+            server,
+            circuit_id: None,
+            remote_id: None,
+        }
+    }
+}
+
+/// DHCP relay agent socket.
+///
+/// The socket forwards the messages broadcast by the clients on the network of the interface
+/// to a DHCP server, as described in RFC 1542, so that a single server can serve several
+/// networks. It sets the `giaddr` field to the address of the agent, and adds the Relay Agent
+/// Information option (RFC 3046) when a circuit or remote ID is configured. The replies of
+/// the server are forwarded back to the clients without this option.
+///
+/// Replies to clients without an address are broadcast, since the hardware address of the
+/// client can't be resolved before it is configured.
+///
+/// Messages are relayed unmodified otherwise, including options unknown to smoltcp. They are
+/// queued in the buffer given to [`Socket::new`], and dropped when it is full.
+#[derive(Debug)]
+pub struct Socket<'a> {
+    config: Config<'a>,
+    buffer: PacketBuffer<'a>,
+
+    /// Server port config
+    pub(crate) server_port: u16,
+
+    /// Client port config
+    pub(crate) client_port: u16,
+}
+
+impl<'a> Socket<'a> {
+    /// Create a DHCP relay agent socket with the given configuration and buffer.
+    pub fn new(config: Config<'a>, buffer: PacketBuffer<'a>) -> Socket<'a> {
+        Socket {
+            config,
+            buffer,
+            server_port: DHCP_SERVER_PORT,
+            client_port: DHCP_CLIENT_PORT,
+        }
+    }
+
+    /// Return the configuration of the relay agent.
+    pub fn config(&self) -> &Config<'a> {
+        &self.config
+    }
+
+    /// Set the configuration of the relay agent.
+    pub fn set_config(&mut self, config: Config<'a>) {
+        self.config = config;
+    }
+
+    /// Set the server/client port
+    ///
+    /// Allows you to specify the ports used by DHCP.
+    pub fn set_ports(&mut self, server_port: u16, client_port: u16) {
+        self.server_port = server_port;
+        self.client_port = client_port;
+    }
+
+    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+        if self.buffer.is_empty() {
+            PollAt::Ingress
+        } else {
+            PollAt::Now
+        }
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        _cx: &mut Context,
+        ip_repr: &Ipv4Repr,
+        repr: &UdpRepr,
+        payload: &[u8],
+    ) {
+        let src_ip = ip_repr.src_addr;
+
+        // This is enforced in interface.rs.
+        assert!(repr.dst_port == self.server_port);
+
+        let dhcp_packet = match DhcpPacket::new_checked(payload) {
+            Ok(dhcp_packet) => dhcp_packet,
+            Err(e) => {
+                net_debug!("DHCP relay invalid pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+
+        match dhcp_packet.opcode() {
+            OpCode::Request => self.relay_request(&dhcp_packet),
+            OpCode::Reply => self.relay_reply(&dhcp_packet),
+            OpCode::Unknown(_) => {}
+        }
+    }
+
+    /// Forward a client message to the server.
+    fn relay_request(&mut self, packet: &DhcpPacket<&[u8]>) {
+        let hops = packet.hops();
+        if hops >= MAX_HOPS {
+            net_debug!("DHCP relay dropping request: too many hops");
+            return;
+        }
+
+        let has_agent_information = packet
+            .options()
+            .any(|option| option.kind == dhcpv4_field::OPT_RELAY_AGENT_INFORMATION);
+        let mut agent_information = [0; 255];
+        let (relay_agent_ip, agent_information) = if packet.relay_agent_ip().is_unspecified() {
+            // Clients don't send relay agent information: it can't be trusted (RFC 3046 § 2.1).
+            if has_agent_information {
+                net_debug!("DHCP relay dropping request with untrusted agent information");
+                return;
+            }
+            let len = self.agent_information(&mut agent_information);
+            (self.config.agent_address, &agent_information[..len])
+        } else {
+            // Already relayed by another agent, which did set the agent information.
+            (packet.relay_agent_ip(), &[][..])
+        };
+
+        let option = (!agent_information.is_empty()).then_some(DhcpOption {
+            kind: dhcpv4_field::OPT_RELAY_AGENT_INFORMATION,
+            data: agent_information,
+        });
+        let server = self.config.server;
+        let Some(buf) = self.enqueue(packet, server, |_| true, option) else {
+            return;
+        };
+        let mut relayed = DhcpPacket::new_unchecked(buf);
+        relayed.set_hops(hops + 1);
+        relayed.set_relay_agent_ip(relay_agent_ip);
+    }
+
+    /// Forward a server reply to the client.
+    fn relay_reply(&mut self, packet: &DhcpPacket<&[u8]>) {
+        if packet.relay_agent_ip() != self.config.agent_address {
+            net_debug!("DHCP relay ignoring reply for another agent");
+            return;
+        }
+
+        let dst_addr = if packet.client_ip().is_unspecified() {
+            Ipv4Address::BROADCAST
+        } else {
+            packet.client_ip()
+        };
+        // The relay agent information is only meant for the agent (RFC 3046 § 2.2).
+        self.enqueue(
+            packet,
+            dst_addr,
+            |option| option.kind != dhcpv4_field::OPT_RELAY_AGENT_INFORMATION,
+            None,
+        );
+    }
+
+    /// Encode the relay agent information sub-options into `buffer`, returning their length.
+    fn agent_information(&self, buffer: &mut [u8; 255]) -> usize {
+        let mut len = 0;
+        for (kind, data) in [
+            (dhcpv4_field::RELAY_AGENT_CIRCUIT_ID, self.config.circuit_id),
+            (dhcpv4_field::RELAY_AGENT_REMOTE_ID, self.config.remote_id),
+        ] {
+            let Some(data) = data else {
+                continue;
+            };
+            if len + 2 + data.len() > buffer.len() {
+                net_debug!("DHCP relay agent information too long, truncating");
+                break;
+            }
+            buffer[len] = kind;
+            buffer[len + 1] = data.len() as u8;
+            buffer[len + 2..len + 2 + data.len()].copy_from_slice(data);
+            len += 2 + data.len();
+        }
+        len
+    }
+
+    /// Queue a copy of `packet` to `dst_addr`, with the options matching `filter`, and
+    /// `extra_option` if any. Return the copy, to be modified further.
+    fn enqueue<F>(
+        &mut self,
+        packet: &DhcpPacket<&[u8]>,
+        dst_addr: Ipv4Address,
+        filter: F,
+        extra_option: Option<DhcpOption>,
+    ) -> Option<&mut [u8]>
+    where
+        F: Fn(&DhcpOption) -> bool,
+    {
+        let options = || packet.options().filter(|option| filter(option));
+        let header_len = dhcpv4_field::OPTIONS.start;
+        let options_len: usize = options()
+            .chain(extra_option)
+            .map(|option| 2 + option.data.len())
+            .sum();
+
+        let Ok(buf) = self.buffer.enqueue(header_len + options_len + 1, dst_addr) else {
+            net_debug!("DHCP relay dropping message: buffer full");
+            return None;
+        };
+        buf[..header_len].copy_from_slice(&packet.into_inner()[..header_len]);
+        let mut relayed = DhcpPacket::new_unchecked(&mut buf[..]);
+        let mut writer = relayed.options_mut();
+        // The buffer was sized for the options, so they can't fail to be emitted.
+        for option in options().chain(extra_option) {
+            writer.emit(option).unwrap();
+        }
+        writer.end().unwrap();
+        Some(buf)
+    }
+
+    pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (Ipv4Repr, UdpRepr, &[u8])) -> Result<(), E>,
+    {
+        let src_addr = self.config.agent_address;
+        let (server_port, client_port) = (self.server_port, self.client_port);
+
+        let res = self.buffer.dequeue_with(|dst_addr, payload| {
+            // Requests go to the server, and replies to the client.
+            let dst_port = match DhcpPacket::new_unchecked(&*payload).opcode() {
+                OpCode::Request => server_port,
+                _ => client_port,
+            };
+            let udp_repr = UdpRepr {
+                src_port: server_port,
+                dst_port,
+            };
+            let ipv4_repr = Ipv4Repr {
+                src_addr,
+                dst_addr: *dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: udp_repr.header_len() + payload.len(),
+                hop_limit: cx.ipv4_hop_limit(),
+            };
+
+            net_debug!("DHCP relay send {} octets to {}", payload.len(), dst_addr);
+            emit(cx, (ipv4_repr, udp_repr, payload))
+        });
+        match res {
+            Err(Empty) => Ok(()),
+            Ok(Err(e)) => Err(e),
+            Ok(Ok(())) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::{Deref, DerefMut};
+
+    use super::*;
+    use crate::wire::{DhcpMessageType, DhcpRepr, EthernetAddress};
+
+    // =========================================================================================//
+    // Helper functions
+
+    struct TestSocket {
+        socket: Socket<'static>,
+        cx: Context,
+    }
+
+    impl Deref for TestSocket {
+        type Target = Socket<'static>;
+        fn deref(&self) -> &Self::Target {
+            &self.socket
+        }
+    }
+
+    impl DerefMut for TestSocket {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.socket
+        }
+    }
+
+    fn send(s: &mut TestSocket, (ip_repr, udp_repr, dhcp_repr): (Ipv4Repr, UdpRepr, DhcpRepr)) {
+        net_trace!("send: {:?}", ip_repr);
+        net_trace!("      {:?}", udp_repr);
+        net_trace!("      {:?}", dhcp_repr);
+
+        let mut payload = vec![0; dhcp_repr.buffer_len()];
+        dhcp_repr
+            .emit(&mut DhcpPacket::new_unchecked(&mut payload))
+            .unwrap();
+
+        s.socket.process(&mut s.cx, &ip_repr, &udp_repr, &payload)
+    }
+
+    /// Return the messages relayed by the socket.
+    fn recv(s: &mut TestSocket) -> std::vec::Vec<(Ipv4Repr, UdpRepr, std::vec::Vec<u8>)> {
+        let mut relayed = vec![];
+        while s.socket.poll_at(&mut s.cx) == PollAt::Now {
+            s.socket
+                .dispatch(&mut s.cx, |_, (ip_repr, udp_repr, payload)| {
+                    assert_eq!(ip_repr.next_header, IpProtocol::Udp);
+                    assert_eq!(ip_repr.payload_len, udp_repr.header_len() + payload.len());
+                    relayed.push((ip_repr, udp_repr, payload.to_vec()));
+                    Ok::<_, ()>(())
+                })
+                .unwrap();
+        }
+        relayed
+    }
+
+    fn agent_information(payload: &[u8]) -> Option<std::vec::Vec<u8>> {
+        DhcpPacket::new_checked(payload)
+            .unwrap()
+            .options()
+            .find(|option| option.kind == dhcpv4_field::OPT_RELAY_AGENT_INFORMATION)
+            .map(|option| option.data.to_vec())
+    }
+
+    // =========================================================================================//
+    // Constants
+
+    const AGENT_IP: Ipv4Address = Ipv4Address([192, 168, 2, 1]);
+    const OTHER_AGENT_IP: Ipv4Address = Ipv4Address([192, 168, 3, 1]);
+    const SERVER_IP: Ipv4Address = Ipv4Address([10, 0, 0, 1]);
+    const CLIENT_IP: Ipv4Address = Ipv4Address([192, 168, 2, 42]);
+
+    const CLIENT_MAC: EthernetAddress = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+
+    const CIRCUIT_ID: &[u8] = b"eth1";
+
+    const IP_CLIENT_BROADCAST: Ipv4Repr = Ipv4Repr {
+        src_addr: Ipv4Address::UNSPECIFIED,
+        dst_addr: Ipv4Address::BROADCAST,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const IP_SERVER: Ipv4Repr = Ipv4Repr {
+        src_addr: SERVER_IP,
+        dst_addr: AGENT_IP,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const UDP_FROM_CLIENT: UdpRepr = UdpRepr {
+        src_port: DHCP_CLIENT_PORT,
+        dst_port: DHCP_SERVER_PORT,
+    };
+
+    const UDP_FROM_SERVER: UdpRepr = UdpRepr {
+        src_port: DHCP_SERVER_PORT,
+        dst_port: DHCP_SERVER_PORT,
+    };
+
+    const DHCP_DISCOVER: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Discover,
+        transaction_id: 0x12345678,
+        secs: 0,
+        client_hardware_address: CLIENT_MAC,
+        client_ip: Ipv4Address::UNSPECIFIED,
+        your_ip: Ipv4Address::UNSPECIFIED,
+        server_ip: Ipv4Address::UNSPECIFIED,
+        router: None,
+        subnet_mask: None,
+        relay_agent_ip: Ipv4Address::UNSPECIFIED,
+        broadcast: false,
+        requested_ip: None,
+        client_identifier: Some(CLIENT_MAC),
+        raw_client_identifier: None,
+        server_identifier: None,
+        parameter_request_list: Some(&[1, 3, 6]),
+        dns_servers: None,
+        max_size: None,
+        renew_duration: None,
+        rebind_duration: None,
+        lease_duration: None,
+        rapid_commit: false,
+        hostname: None,
+        fqdn: None,
+        vendor_class_id: None,
+        additional_options: &[],
+    };
+
+    const AGENT_INFORMATION: &[u8] = &[1, 4, b'e', b't', b'h', b'1'];
+
+    fn dhcp_reply(message_type: DhcpMessageType) -> DhcpRepr<'static> {
+        DhcpRepr {
+            message_type,
+            your_ip: CLIENT_IP,
+            relay_agent_ip: AGENT_IP,
+            server_identifier: Some(SERVER_IP),
+            lease_duration: Some(1000),
+            client_identifier: None,
+            parameter_request_list: None,
+            additional_options: &[DhcpOption {
+                kind: dhcpv4_field::OPT_RELAY_AGENT_INFORMATION,
+                data: AGENT_INFORMATION,
+            }],
+            ..DHCP_DISCOVER
+        }
+    }
+
+    fn socket() -> TestSocket {
+        let config = Config {
+            circuit_id: Some(CIRCUIT_ID),
+            ..Config::new(AGENT_IP, SERVER_IP)
+        };
+        let buffer = PacketBuffer::new(vec![PacketMetadata::EMPTY; 2], vec![0; 1024]);
+        TestSocket {
+            socket: Socket::new(config, buffer),
+            cx: Context::mock(),
+        }
+    }
+
+    // =========================================================================================//
+    // Tests
+
+    #[test]
+    fn test_relay_request() {
+        let mut s = socket();
+
+        send(
+            &mut s,
+            (IP_CLIENT_BROADCAST, UDP_FROM_CLIENT, DHCP_DISCOVER),
+        );
+
+        let relayed = recv(&mut s);
+        assert_eq!(relayed.len(), 1);
+        let (ip_repr, udp_repr, payload) = &relayed[0];
+        assert_eq!(ip_repr.src_addr, AGENT_IP);
+        assert_eq!(ip_repr.dst_addr, SERVER_IP);
+        assert_eq!(udp_repr, &UDP_FROM_SERVER);
+
+        let packet = DhcpPacket::new_checked(&payload[..]).unwrap();
+        assert_eq!(packet.hops(), 1);
+        assert_eq!(
+            DhcpRepr::parse(&packet).unwrap(),
+            DhcpRepr {
+                relay_agent_ip: AGENT_IP,
+                ..DHCP_DISCOVER
+            }
+        );
+        assert_eq!(
+            agent_information(payload).as_deref(),
+            Some(AGENT_INFORMATION)
+        );
+    }
+
+    #[test]
+    fn test_relay_request_without_agent_information() {
+        let mut s = socket();
+        s.set_config(Config::new(AGENT_IP, SERVER_IP));
+
+        send(
+            &mut s,
+            (IP_CLIENT_BROADCAST, UDP_FROM_CLIENT, DHCP_DISCOVER),
+        );
+
+        let relayed = recv(&mut s);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(agent_information(&relayed[0].2), None);
+    }
+
+    #[test]
+    fn test_relay_relayed_request() {
+        let mut s = socket();
+
+        // The agent information set by the first agent is kept.
+        let mut payload = vec![0; DHCP_DISCOVER.buffer_len() + 8];
+        let discover = DhcpRepr {
+            relay_agent_ip: OTHER_AGENT_IP,
+            additional_options: &[DhcpOption {
+                kind: dhcpv4_field::OPT_RELAY_AGENT_INFORMATION,
+                data: &[1, 2, b'e', b'0'],
+            }],
+            ..DHCP_DISCOVER
+        };
+        let mut packet = DhcpPacket::new_unchecked(&mut payload);
+        discover.emit(&mut packet).unwrap();
+        packet.set_hops(3);
+        s.socket.process(
+            &mut s.cx,
+            &Ipv4Repr {
+                src_addr: OTHER_AGENT_IP,
+                dst_addr: AGENT_IP,
+                ..IP_CLIENT_BROADCAST
+            },
+            &UDP_FROM_SERVER,
+            &payload,
+        );
+
+        let relayed = recv(&mut s);
+        assert_eq!(relayed.len(), 1);
+        let packet = DhcpPacket::new_checked(&relayed[0].2[..]).unwrap();
+        assert_eq!(packet.hops(), 4);
+        assert_eq!(packet.relay_agent_ip(), OTHER_AGENT_IP);
+        assert_eq!(
+            agent_information(&relayed[0].2).as_deref(),
+            Some(&[1, 2, b'e', b'0'][..])
+        );
+    }
+
+    #[test]
+    fn test_untrusted_agent_information() {
+        let mut s = socket();
+
+        send(
+            &mut s,
+            (
+                IP_CLIENT_BROADCAST,
+                UDP_FROM_CLIENT,
+                DhcpRepr {
+                    additional_options: &[DhcpOption {
+                        kind: dhcpv4_field::OPT_RELAY_AGENT_INFORMATION,
+                        data: AGENT_INFORMATION,
+                    }],
+                    ..DHCP_DISCOVER
+                },
+            ),
+        );
+
+        assert!(recv(&mut s).is_empty());
+    }
+
+    #[test]
+    fn test_max_hops() {
+        let mut s = socket();
+
+        let mut payload = vec![0; DHCP_DISCOVER.buffer_len()];
+        let mut packet = DhcpPacket::new_unchecked(&mut payload);
+        DHCP_DISCOVER.emit(&mut packet).unwrap();
+        packet.set_hops(MAX_HOPS);
+        s.socket
+            .process(&mut s.cx, &IP_CLIENT_BROADCAST, &UDP_FROM_CLIENT, &payload);
+
+        assert!(recv(&mut s).is_empty());
+    }
+
+    #[test]
+    fn test_relay_reply() {
+        let mut s = socket();
+
+        send(
+            &mut s,
+            (
+                IP_SERVER,
+                UDP_FROM_SERVER,
+                dhcp_reply(DhcpMessageType::Offer),
+            ),
+        );
+
+        let relayed = recv(&mut s);
+        assert_eq!(relayed.len(), 1);
+        let (ip_repr, udp_repr, payload) = &relayed[0];
+        assert_eq!(ip_repr.src_addr, AGENT_IP);
+        assert_eq!(ip_repr.dst_addr, Ipv4Address::BROADCAST);
+        assert_eq!(
+            udp_repr,
+            &UdpRepr {
+                src_port: DHCP_SERVER_PORT,
+                dst_port: DHCP_CLIENT_PORT,
+            }
+        );
+
+        // The agent information is removed.
+        assert_eq!(agent_information(payload), None);
+        let packet = DhcpPacket::new_checked(&payload[..]).unwrap();
+        assert_eq!(
+            DhcpRepr::parse(&packet).unwrap(),
+            DhcpRepr {
+                additional_options: &[],
+                ..dhcp_reply(DhcpMessageType::Offer)
+            }
+        );
+    }
+
+    #[test]
+    fn test_relay_reply_unicast() {
+        let mut s = socket();
+
+        // Clients renewing their lease can receive unicast replies.
+        send(
+            &mut s,
+            (
+                IP_SERVER,
+                UDP_FROM_SERVER,
+                DhcpRepr {
+                    client_ip: CLIENT_IP,
+                    ..dhcp_reply(DhcpMessageType::Ack)
+                },
+            ),
+        );
+
+        let relayed = recv(&mut s);
+        assert_eq!(relayed.len(), 1);
+        assert_eq!(relayed[0].0.dst_addr, CLIENT_IP);
+    }
+
+    #[test]
+    fn test_reply_for_other_agent() {
+        let mut s = socket();
+
+        send(
+            &mut s,
+            (
+                IP_SERVER,
+                UDP_FROM_SERVER,
+                DhcpRepr {
+                    relay_agent_ip: OTHER_AGENT_IP,
+                    ..dhcp_reply(DhcpMessageType::Offer)
+                },
+            ),
+        );
+
+        assert!(recv(&mut s).is_empty());
+    }
+
+    #[test]
+    fn test_buffer_full() {
+        let mut s = socket();
+
+        for _ in 0..3 {
+            send(
+                &mut s,
+                (IP_CLIENT_BROADCAST, UDP_FROM_CLIENT, DHCP_DISCOVER),
+            );
+        }
+
+        assert_eq!(recv(&mut s).len(), 2);
+    }
+}
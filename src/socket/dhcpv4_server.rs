@@ -0,0 +1,1096 @@
+use heapless::{Deque, Vec};
+use managed::ManagedSlice;
+
+use crate::iface::Context;
+use crate::time::{Duration, Instant};
+use crate::wire::{
+    DhcpMessageType, DhcpOption, DhcpPacket, DhcpRepr, EthernetAddress, IpProtocol, Ipv4Address,
+    Ipv4Cidr, Ipv4Repr, UdpRepr, DHCP_CLIENT_PORT, DHCP_MAX_DNS_SERVER_COUNT, DHCP_SERVER_PORT,
+};
+
+use super::PollAt;
+
+/// How long an offered address is held for the client it was offered to.
+const OFFER_DURATION: Duration = Duration::from_secs(60);
+
+/// Maximum number of replies waiting to be sent.
+const MAX_PENDING_REPLIES: usize = 4;
+
+/// An address reserved for a client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Reservation {
+    /// Hardware address of the client
+    pub hardware_address: EthernetAddress,
+    /// Address always assigned to the client
+    pub address: Ipv4Address,
+}
+
+/// DHCP server configuration.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config<'a> {
+    /// Address of the server on the served network, with the prefix length of the network.
+    pub address: Ipv4Cidr,
+    /// First address of the pool handed out to clients.
+    pub pool_start: Ipv4Address,
+    /// Last address of the pool handed out to clients, inclusive.
+    pub pool_end: Ipv4Address,
+    /// Duration of the leases.
+    pub lease_duration: Duration,
+    /// Router address given to clients.
+    pub router: Option<Ipv4Address>,
+    /// DNS servers given to clients. Only the first `DHCP_MAX_DNS_SERVER_COUNT` are sent.
+    pub dns_servers: &'a [Ipv4Address],
+    /// Addresses reserved for given clients. They may be outside of the pool.
+    pub reservations: &'a [Reservation],
+    /// Additional options sent in offers and acknowledgments.
+    pub options: &'a [DhcpOption<'a>],
+}
+
+impl<'a> Config<'a> {
+    /// Create a configuration handing out addresses from `pool_start` to `pool_end`,
+    /// with one day leases, and no router nor DNS servers.
+    pub fn new(address: Ipv4Cidr, pool_start: Ipv4Address, pool_end: Ipv4Address) -> Self {
+        Config {
+            address,
+            pool_start,
+            pool_end,
+            lease_duration: Duration::from_secs(24 * 60 * 60),
+            router: None,
+            dns_servers: &[],
+            reservations: &[],
+            options: &[],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum LeaseState {
+    /// The address was offered to the client, which has yet to request it.
+    Offered,
+    /// The address is assigned to the client.
+    Bound,
+    /// The client reported the address to be in use by another host.
+    Declined,
+}
+
+/// An address lease.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Lease {
+    /// Hardware address of the client
+    pub hardware_address: EthernetAddress,
+    /// Address assigned to the client
+    pub address: Ipv4Address,
+    /// Expiration time of the lease
+    pub expires_at: Instant,
+    state: LeaseState,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Reply {
+    message_type: DhcpMessageType,
+    transaction_id: u32,
+    client_hardware_address: EthernetAddress,
+    client_ip: Ipv4Address,
+    your_ip: Ipv4Address,
+    relay_agent_ip: Ipv4Address,
+    broadcast: bool,
+    /// Whether the reply assigns `your_ip` for the lease duration.
+    lease: bool,
+    dst_addr: Ipv4Address,
+    dst_port: u16,
+}
+
+/// DHCP server socket.
+///
+/// The socket hands out addresses from a pool to the clients on the network of the
+/// interface, along with the router, DNS servers and other options of its configuration.
+/// Leases are kept in the storage given to [`Socket::new`], and clients are refused an
+/// address when it is full.
+///
+/// Replies are broadcast, unless they are addressed to a configured client, or to a relay
+/// agent.
+#[derive(Debug)]
+pub struct Socket<'a> {
+    config: Config<'a>,
+    leases: ManagedSlice<'a, Option<Lease>>,
+    replies: Deque<Reply, MAX_PENDING_REPLIES>,
+
+    /// Server port config
+    pub(crate) server_port: u16,
+
+    /// Client port config
+    pub(crate) client_port: u16,
+}
+
+impl<'a> Socket<'a> {
+    /// Create a DHCPv4 server socket with the given configuration and lease storage.
+    pub fn new<L>(config: Config<'a>, leases: L) -> Socket<'a>
+    where
+        L: Into<ManagedSlice<'a, Option<Lease>>>,
+    {
+        Socket {
+            config,
+            leases: leases.into(),
+            replies: Deque::new(),
+            server_port: DHCP_SERVER_PORT,
+            client_port: DHCP_CLIENT_PORT,
+        }
+    }
+
+    /// Return the configuration of the server.
+    pub fn config(&self) -> &Config<'a> {
+        &self.config
+    }
+
+    /// Set the configuration of the server.
+    ///
+    /// Existing leases are kept until they expire, even if they do not match the new
+    /// configuration.
+    pub fn set_config(&mut self, config: Config<'a>) {
+        self.config = config;
+    }
+
+    /// Set the server/client port
+    ///
+    /// Allows you to specify the ports used by DHCP.
+    pub fn set_ports(&mut self, server_port: u16, client_port: u16) {
+        self.server_port = server_port;
+        self.client_port = client_port;
+    }
+
+    /// Return an iterator over the addresses currently assigned to clients.
+    ///
+    /// Leases may have expired since the last time the socket was polled.
+    pub fn leases(&self) -> impl Iterator<Item = &Lease> {
+        self.leases
+            .iter()
+            .flatten()
+            .filter(|lease| lease.state == LeaseState::Bound)
+    }
+
+    pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
+        if self.replies.is_empty() {
+            PollAt::Ingress
+        } else {
+            PollAt::Now
+        }
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &Ipv4Repr,
+        repr: &UdpRepr,
+        payload: &[u8],
+    ) {
+        let src_ip = ip_repr.src_addr;
+
+        // This is enforced in interface.rs.
+        assert!(repr.dst_port == self.server_port);
+
+        let dhcp_packet = match DhcpPacket::new_checked(payload) {
+            Ok(dhcp_packet) => dhcp_packet,
+            Err(e) => {
+                net_debug!("DHCP server invalid pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+        let dhcp_repr = match DhcpRepr::parse(&dhcp_packet) {
+            Ok(dhcp_repr) => dhcp_repr,
+            Err(e) => {
+                net_debug!("DHCP server error parsing pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+
+        net_debug!(
+            "DHCP server recv {:?} from {}: {:?}",
+            dhcp_repr.message_type,
+            src_ip,
+            dhcp_repr
+        );
+
+        let now = cx.now();
+        let client = dhcp_repr.client_hardware_address;
+        let server_address = self.config.address.address();
+        let for_us = dhcp_repr
+            .server_identifier
+            .map_or(true, |id| id == server_address);
+
+        match dhcp_repr.message_type {
+            DhcpMessageType::Discover => {
+                let Some(address) = self.select_address(now, client, dhcp_repr.requested_ip) else {
+                    net_debug!("DHCP server has no address available for {}", client);
+                    return;
+                };
+                if self.set_lease(
+                    now,
+                    client,
+                    address,
+                    LeaseState::Offered,
+                    now + OFFER_DURATION,
+                ) {
+                    self.reply(&dhcp_repr, DhcpMessageType::Offer, address);
+                }
+            }
+            DhcpMessageType::Request if !for_us => {
+                // The client selected another server.
+                self.remove_lease(client, LeaseState::Offered);
+            }
+            DhcpMessageType::Request => {
+                let address = dhcp_repr.requested_ip.unwrap_or(dhcp_repr.client_ip);
+                let expires_at = now + self.config.lease_duration;
+                if self.can_assign(now, client, address)
+                    && self.set_lease(now, client, address, LeaseState::Bound, expires_at)
+                {
+                    self.reply(&dhcp_repr, DhcpMessageType::Ack, address);
+                } else if dhcp_repr.server_identifier.is_some()
+                    || self.lease_of(client).is_some()
+                    || self.reservation_of(client).is_some()
+                {
+                    net_debug!("DHCP server refusing {} to {}", address, client);
+                    self.reply(&dhcp_repr, DhcpMessageType::Nak, Ipv4Address::UNSPECIFIED);
+                } else {
+                    // RFC 2131 § 4.3.2: a client in INIT-REBOOT, RENEWING or REBINDING
+                    // state may be bound by another server, which is the one to answer.
+                    net_debug!(
+                        "DHCP server ignoring request from unknown client {}",
+                        client
+                    );
+                }
+            }
+            DhcpMessageType::Decline if for_us => {
+                let Some(address) = dhcp_repr.requested_ip else {
+                    return;
+                };
+                net_debug!("DHCP server: {} declined {}", client, address);
+                self.remove_lease(client, LeaseState::Offered);
+                self.remove_lease(client, LeaseState::Bound);
+                // Keep the address out of the pool for a while.
+                let expires_at = now + self.config.lease_duration;
+                if let Some(slot) = self.free_slot(now) {
+                    *slot = Some(Lease {
+                        hardware_address: client,
+                        address,
+                        expires_at,
+                        state: LeaseState::Declined,
+                    });
+                }
+            }
+            DhcpMessageType::Release
+                if for_us
+                    && self.lease_of(client).map(|lease| lease.address)
+                        == Some(dhcp_repr.client_ip) =>
+            {
+                self.remove_lease(client, LeaseState::Bound);
+            }
+            DhcpMessageType::Inform => {
+                self.reply(&dhcp_repr, DhcpMessageType::Ack, Ipv4Address::UNSPECIFIED);
+            }
+            _ => {}
+        }
+    }
+
+    /// Return whether `address` is in the pool.
+    fn in_pool(&self, address: Ipv4Address) -> bool {
+        let address = u32::from_be_bytes(address.0);
+        (u32::from_be_bytes(self.config.pool_start.0)..=u32::from_be_bytes(self.config.pool_end.0))
+            .contains(&address)
+    }
+
+    /// Return the address reserved for `client`, if any.
+    fn reservation_of(&self, client: EthernetAddress) -> Option<Ipv4Address> {
+        self.config
+            .reservations
+            .iter()
+            .find(|r| r.hardware_address == client)
+            .map(|r| r.address)
+    }
+
+    /// Return the offered or bound lease of `client`, if any.
+    fn lease_of(&self, client: EthernetAddress) -> Option<&Lease> {
+        self.leases
+            .iter()
+            .flatten()
+            .find(|lease| lease.hardware_address == client && lease.state != LeaseState::Declined)
+    }
+
+    /// Return whether `address` may be given to `client`, as far as other clients are
+    /// concerned.
+    fn is_available(&self, now: Instant, client: EthernetAddress, address: Ipv4Address) -> bool {
+        address != self.config.address.address()
+            && !self
+                .config
+                .reservations
+                .iter()
+                .any(|r| r.address == address && r.hardware_address != client)
+            && !self.leases.iter().flatten().any(|lease| {
+                lease.address == address
+                    && lease.expires_at > now
+                    && (lease.hardware_address != client || lease.state == LeaseState::Declined)
+            })
+    }
+
+    /// Return whether `address` may be assigned to `client`.
+    fn can_assign(&self, now: Instant, client: EthernetAddress, address: Ipv4Address) -> bool {
+        match self.reservation_of(client) {
+            Some(reserved) => reserved == address,
+            None => self.in_pool(address) && self.is_available(now, client, address),
+        }
+    }
+
+    /// Select the address to offer to `client`.
+    fn select_address(
+        &self,
+        now: Instant,
+        client: EthernetAddress,
+        requested: Option<Ipv4Address>,
+    ) -> Option<Ipv4Address> {
+        if let Some(address) = self.reservation_of(client) {
+            return Some(address);
+        }
+        if let Some(lease) = self.lease_of(client) {
+            if self.can_assign(now, client, lease.address) {
+                return Some(lease.address);
+            }
+        }
+        if let Some(address) = requested {
+            if self.can_assign(now, client, address) {
+                return Some(address);
+            }
+        }
+
+        // At most one address per lease, per reservation and for the server is taken,
+        // so there is no need to look any further into the pool.
+        let max_taken = self.leases.len() + self.config.reservations.len() + 1;
+        (u32::from_be_bytes(self.config.pool_start.0)..=u32::from_be_bytes(self.config.pool_end.0))
+            .take(max_taken + 1)
+            .map(|address| Ipv4Address(address.to_be_bytes()))
+            .find(|&address| self.can_assign(now, client, address))
+    }
+
+    /// Return a free lease slot, reclaiming an expired lease if needed.
+    fn free_slot(&mut self, now: Instant) -> Option<&mut Option<Lease>> {
+        let index = self
+            .leases
+            .iter()
+            .position(|slot| slot.map_or(true, |lease| lease.expires_at <= now));
+
+        match index {
+            Some(index) => Some(&mut self.leases[index]),
+            None => match &mut self.leases {
+                ManagedSlice::Borrowed(_) => None,
+                #[cfg(feature = "alloc")]
+                ManagedSlice::Owned(leases) => {
+                    leases.push(None);
+                    leases.last_mut()
+                }
+            },
+        }
+    }
+
+    /// Record a lease of `address` for `client`, replacing its previous one.
+    fn set_lease(
+        &mut self,
+        now: Instant,
+        client: EthernetAddress,
+        address: Ipv4Address,
+        state: LeaseState,
+        expires_at: Instant,
+    ) -> bool {
+        let lease = Lease {
+            hardware_address: client,
+            address,
+            expires_at,
+            state,
+        };
+
+        if let Some(slot) = self.leases.iter_mut().find(|slot| {
+            slot.map_or(false, |lease| {
+                lease.hardware_address == client && lease.state != LeaseState::Declined
+            })
+        }) {
+            *slot = Some(lease);
+            return true;
+        }
+
+        match self.free_slot(now) {
+            Some(slot) => {
+                *slot = Some(lease);
+                true
+            }
+            None => {
+                net_debug!("DHCP server lease storage is full");
+                false
+            }
+        }
+    }
+
+    /// Remove the lease of `client` in the given state, if any.
+    fn remove_lease(&mut self, client: EthernetAddress, state: LeaseState) {
+        for slot in self.leases.iter_mut() {
+            if slot.map_or(false, |lease| {
+                lease.hardware_address == client && lease.state == state
+            }) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Queue a reply to a client message.
+    fn reply(&mut self, request: &DhcpRepr, message_type: DhcpMessageType, your_ip: Ipv4Address) {
+        // Replies go to the relay agent, if any, or to the client if it already has an
+        // address. Otherwise they are broadcast, since the client can't receive unicast
+        // packets before being configured.
+        let (dst_addr, dst_port) = if !request.relay_agent_ip.is_unspecified() {
+            (request.relay_agent_ip, self.server_port)
+        } else if message_type != DhcpMessageType::Nak && !request.client_ip.is_unspecified() {
+            (request.client_ip, self.client_port)
+        } else {
+            (Ipv4Address::BROADCAST, self.client_port)
+        };
+
+        let reply = Reply {
+            message_type,
+            transaction_id: request.transaction_id,
+            client_hardware_address: request.client_hardware_address,
+            client_ip: match message_type {
+                DhcpMessageType::Ack => request.client_ip,
+                _ => Ipv4Address::UNSPECIFIED,
+            },
+            your_ip,
+            relay_agent_ip: request.relay_agent_ip,
+            broadcast: request.broadcast,
+            lease: !your_ip.is_unspecified(),
+            dst_addr,
+            dst_port,
+        };
+        if self.replies.push_back(reply).is_err() {
+            net_debug!("DHCP server dropping reply: too many pending replies");
+        }
+    }
+
+    pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (Ipv4Repr, UdpRepr, DhcpRepr)) -> Result<(), E>,
+    {
+        let Some(reply) = self.replies.front() else {
+            return Ok(());
+        };
+
+        let configures = reply.message_type != DhcpMessageType::Nak;
+        let dns_servers = &self.config.dns_servers
+            [..self.config.dns_servers.len().min(DHCP_MAX_DNS_SERVER_COUNT)];
+        let dhcp_repr = DhcpRepr {
+            message_type: reply.message_type,
+            transaction_id: reply.transaction_id,
+            secs: 0,
+            client_hardware_address: reply.client_hardware_address,
+            client_ip: reply.client_ip,
+            your_ip: reply.your_ip,
+            server_ip: Ipv4Address::UNSPECIFIED,
+            router: self.config.router.filter(|_| configures),
+            subnet_mask: Some(self.config.address.netmask()).filter(|_| configures),
+            relay_agent_ip: reply.relay_agent_ip,
+            broadcast: reply.broadcast,
+            requested_ip: None,
+            client_identifier: None,
+            raw_client_identifier: None,
+            server_identifier: Some(self.config.address.address()),
+            parameter_request_list: None,
+            dns_servers: Some(Vec::from_slice(dns_servers).unwrap())
+                .filter(|servers| configures && !servers.is_empty()),
+            max_size: None,
+            lease_duration: Some(self.config.lease_duration.secs().min(u32::MAX as u64) as u32)
+                .filter(|_| reply.lease),
+            renew_duration: None,
+            rebind_duration: None,
+            rapid_commit: false,
+            hostname: None,
+            fqdn: None,
+            vendor_class_id: None,
+            additional_options: if configures { self.config.options } else { &[] },
+        };
+
+        let udp_repr = UdpRepr {
+            src_port: self.server_port,
+            dst_port: reply.dst_port,
+        };
+
+        let ipv4_repr = Ipv4Repr {
+            src_addr: self.config.address.address(),
+            dst_addr: reply.dst_addr,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + dhcp_repr.buffer_len(),
+            hop_limit: cx.ipv4_hop_limit(),
+        };
+
+        net_debug!(
+            "DHCP server send {:?} to {}: {:?}",
+            dhcp_repr.message_type,
+            ipv4_repr.dst_addr,
+            dhcp_repr
+        );
+        emit(cx, (ipv4_repr, udp_repr, dhcp_repr))?;
+
+        self.replies.pop_front();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::{Deref, DerefMut};
+
+    use super::*;
+
+    // =========================================================================================//
+    // Helper functions
+
+    struct TestSocket {
+        socket: Socket<'static>,
+        cx: Context,
+    }
+
+    impl Deref for TestSocket {
+        type Target = Socket<'static>;
+        fn deref(&self) -> &Self::Target {
+            &self.socket
+        }
+    }
+
+    impl DerefMut for TestSocket {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.socket
+        }
+    }
+
+    fn send(
+        s: &mut TestSocket,
+        timestamp: Instant,
+        (ip_repr, udp_repr, dhcp_repr): (Ipv4Repr, UdpRepr, DhcpRepr),
+    ) {
+        s.cx.set_now(timestamp);
+
+        net_trace!("send: {:?}", ip_repr);
+        net_trace!("      {:?}", udp_repr);
+        net_trace!("      {:?}", dhcp_repr);
+
+        let mut payload = vec![0; dhcp_repr.buffer_len()];
+        dhcp_repr
+            .emit(&mut DhcpPacket::new_unchecked(&mut payload))
+            .unwrap();
+
+        s.socket.process(&mut s.cx, &ip_repr, &udp_repr, &payload)
+    }
+
+    fn recv(s: &mut TestSocket, timestamp: Instant, reprs: &[(Ipv4Repr, UdpRepr, DhcpRepr)]) {
+        s.cx.set_now(timestamp);
+
+        let mut i = 0;
+
+        while s.socket.poll_at(&mut s.cx) <= PollAt::Time(timestamp) {
+            let _ = s
+                .socket
+                .dispatch(&mut s.cx, |_, (mut ip_repr, udp_repr, dhcp_repr)| {
+                    assert_eq!(ip_repr.next_header, IpProtocol::Udp);
+                    assert_eq!(
+                        ip_repr.payload_len,
+                        udp_repr.header_len() + dhcp_repr.buffer_len()
+                    );
+
+                    // We validated the payload len, change it to 0 to make equality testing easier
+                    ip_repr.payload_len = 0;
+
+                    net_trace!("recv: {:?}", ip_repr);
+                    net_trace!("      {:?}", udp_repr);
+                    net_trace!("      {:?}", dhcp_repr);
+
+                    let got_repr = (ip_repr, udp_repr, dhcp_repr);
+                    match reprs.get(i) {
+                        Some(want_repr) => assert_eq!(want_repr, &got_repr),
+                        None => panic!("Too many reprs emitted"),
+                    }
+                    i += 1;
+                    Ok::<_, ()>(())
+                });
+        }
+
+        assert_eq!(i, reprs.len());
+    }
+
+    macro_rules! send {
+        ($socket:ident, $repr:expr) =>
+            (send!($socket, time 0, $repr));
+        ($socket:ident, time $time:expr, $repr:expr) =>
+            (send(&mut $socket, Instant::from_millis($time), $repr));
+    }
+
+    macro_rules! recv {
+        ($socket:ident, $reprs:expr) => ({
+            recv!($socket, time 0, $reprs);
+        });
+        ($socket:ident, time $time:expr, $reprs:expr) => ({
+            recv(&mut $socket, Instant::from_millis($time), &$reprs);
+        });
+    }
+
+    // =========================================================================================//
+    // Constants
+
+    const TXID: u32 = 0x12345678;
+
+    const SERVER_IP: Ipv4Address = Ipv4Address([192, 168, 1, 1]);
+    const POOL_START: Ipv4Address = Ipv4Address([192, 168, 1, 100]);
+    const POOL_END: Ipv4Address = Ipv4Address([192, 168, 1, 101]);
+    const RESERVED_IP: Ipv4Address = Ipv4Address([192, 168, 1, 10]);
+    const RELAY_IP: Ipv4Address = Ipv4Address([10, 0, 0, 1]);
+    const DNS_IPS: &[Ipv4Address] = &[Ipv4Address([1, 1, 1, 1])];
+
+    const MASK_24: Ipv4Address = Ipv4Address([255, 255, 255, 0]);
+
+    const CLIENT_MAC: EthernetAddress = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x02]);
+    const OTHER_MAC: EthernetAddress = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x03]);
+    const THIRD_MAC: EthernetAddress = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x04]);
+    const FOURTH_MAC: EthernetAddress = EthernetAddress([0x02, 0x02, 0x02, 0x02, 0x02, 0x05]);
+
+    const RESERVATIONS: &[Reservation] = &[Reservation {
+        hardware_address: OTHER_MAC,
+        address: RESERVED_IP,
+    }];
+
+    const IP_RECV: Ipv4Repr = Ipv4Repr {
+        src_addr: Ipv4Address::UNSPECIFIED,
+        dst_addr: Ipv4Address::BROADCAST,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const IP_SEND_BROADCAST: Ipv4Repr = Ipv4Repr {
+        src_addr: SERVER_IP,
+        dst_addr: Ipv4Address::BROADCAST,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const UDP_RECV: UdpRepr = UdpRepr {
+        src_port: DHCP_CLIENT_PORT,
+        dst_port: DHCP_SERVER_PORT,
+    };
+    const UDP_SEND: UdpRepr = UdpRepr {
+        src_port: DHCP_SERVER_PORT,
+        dst_port: DHCP_CLIENT_PORT,
+    };
+
+    const DHCP_DEFAULT: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Unknown(99),
+        transaction_id: TXID,
+        secs: 0,
+        client_hardware_address: CLIENT_MAC,
+        client_ip: Ipv4Address::UNSPECIFIED,
+        your_ip: Ipv4Address::UNSPECIFIED,
+        server_ip: Ipv4Address::UNSPECIFIED,
+        router: None,
+        subnet_mask: None,
+        relay_agent_ip: Ipv4Address::UNSPECIFIED,
+        broadcast: false,
+        requested_ip: None,
+        client_identifier: None,
+        raw_client_identifier: None,
+        server_identifier: None,
+        parameter_request_list: None,
+        dns_servers: None,
+        max_size: None,
+        renew_duration: None,
+        rebind_duration: None,
+        lease_duration: None,
+        rapid_commit: false,
+        hostname: None,
+        fqdn: None,
+        vendor_class_id: None,
+        additional_options: &[],
+    };
+
+    const DHCP_DISCOVER: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Discover,
+        client_identifier: Some(CLIENT_MAC),
+        parameter_request_list: Some(&[1, 3, 6]),
+        ..DHCP_DEFAULT
+    };
+
+    fn dhcp_request(address: Ipv4Address) -> DhcpRepr<'static> {
+        DhcpRepr {
+            message_type: DhcpMessageType::Request,
+            client_identifier: Some(CLIENT_MAC),
+            server_identifier: Some(SERVER_IP),
+            requested_ip: Some(address),
+            parameter_request_list: Some(&[1, 3, 6]),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_reply(message_type: DhcpMessageType, address: Ipv4Address) -> DhcpRepr<'static> {
+        DhcpRepr {
+            message_type,
+            server_identifier: Some(SERVER_IP),
+            your_ip: address,
+            router: Some(SERVER_IP),
+            subnet_mask: Some(MASK_24),
+            dns_servers: Some(Vec::from_slice(DNS_IPS).unwrap()),
+            lease_duration: Some(1000),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    const DHCP_NAK: DhcpRepr = DhcpRepr {
+        message_type: DhcpMessageType::Nak,
+        server_identifier: Some(SERVER_IP),
+        ..DHCP_DEFAULT
+    };
+
+    fn socket() -> TestSocket {
+        let config = Config {
+            router: Some(SERVER_IP),
+            dns_servers: DNS_IPS,
+            reservations: RESERVATIONS,
+            lease_duration: Duration::from_secs(1000),
+            ..Config::new(Ipv4Cidr::new(SERVER_IP, 24), POOL_START, POOL_END)
+        };
+        let s = Socket::new(config, vec![]);
+        TestSocket {
+            socket: s,
+            cx: Context::mock(),
+        }
+    }
+
+    fn bind(s: &mut TestSocket, client: EthernetAddress) -> Ipv4Address {
+        let discover = DhcpRepr {
+            client_hardware_address: client,
+            ..DHCP_DISCOVER
+        };
+        send(s, Instant::ZERO, (IP_RECV, UDP_RECV, discover));
+        let address = s.replies.front().unwrap().your_ip;
+        s.replies.clear();
+
+        let request = DhcpRepr {
+            client_hardware_address: client,
+            ..dhcp_request(address)
+        };
+        send(s, Instant::ZERO, (IP_RECV, UDP_RECV, request));
+        s.replies.clear();
+        address
+    }
+
+    // =========================================================================================//
+    // Tests
+
+    #[test]
+    fn test_bind() {
+        let mut s = socket();
+
+        send!(s, (IP_RECV, UDP_RECV, DHCP_DISCOVER));
+        recv!(
+            s,
+            [(
+                IP_SEND_BROADCAST,
+                UDP_SEND,
+                dhcp_reply(DhcpMessageType::Offer, POOL_START)
+            )]
+        );
+        assert_eq!(s.leases().count(), 0);
+
+        send!(s, (IP_RECV, UDP_RECV, dhcp_request(POOL_START)));
+        recv!(
+            s,
+            [(
+                IP_SEND_BROADCAST,
+                UDP_SEND,
+                dhcp_reply(DhcpMessageType::Ack, POOL_START)
+            )]
+        );
+        assert_eq!(
+            s.leases().collect::<std::vec::Vec<_>>(),
+            [&Lease {
+                hardware_address: CLIENT_MAC,
+                address: POOL_START,
+                expires_at: Instant::from_secs(1000),
+                state: LeaseState::Bound,
+            }]
+        );
+
+        // Discovering again offers the same address.
+        send!(s, time 1_000, (IP_RECV, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, time 1_000, [(
+            IP_SEND_BROADCAST,
+            UDP_SEND,
+            dhcp_reply(DhcpMessageType::Offer, POOL_START)
+        )]);
+    }
+
+    #[test]
+    fn test_renew() {
+        let mut s = socket();
+        bind(&mut s, CLIENT_MAC);
+
+        let ip_renew = Ipv4Repr {
+            src_addr: POOL_START,
+            dst_addr: SERVER_IP,
+            ..IP_RECV
+        };
+        let renew = DhcpRepr {
+            message_type: DhcpMessageType::Request,
+            client_ip: POOL_START,
+            ..DHCP_DEFAULT
+        };
+        send!(s, time 500_000, (ip_renew, UDP_RECV, renew));
+        recv!(s, time 500_000, [(
+            Ipv4Repr {
+                dst_addr: POOL_START,
+                ..IP_SEND_BROADCAST
+            },
+            UDP_SEND,
+            DhcpRepr {
+                client_ip: POOL_START,
+                ..dhcp_reply(DhcpMessageType::Ack, POOL_START)
+            }
+        )]);
+        assert_eq!(
+            s.leases().next().unwrap().expires_at,
+            Instant::from_secs(1500)
+        );
+    }
+
+    #[test]
+    fn test_reservation() {
+        let mut s = socket();
+
+        // The reserved address is offered to its client, even though it is not in the pool.
+        let discover = DhcpRepr {
+            client_hardware_address: OTHER_MAC,
+            requested_ip: Some(POOL_START),
+            ..DHCP_DISCOVER
+        };
+        send!(s, (IP_RECV, UDP_RECV, discover));
+        recv!(
+            s,
+            [(
+                IP_SEND_BROADCAST,
+                UDP_SEND,
+                DhcpRepr {
+                    client_hardware_address: OTHER_MAC,
+                    ..dhcp_reply(DhcpMessageType::Offer, RESERVED_IP)
+                }
+            )]
+        );
+
+        // Other clients can't get it.
+        send!(s, (IP_RECV, UDP_RECV, dhcp_request(RESERVED_IP)));
+        recv!(s, [(IP_SEND_BROADCAST, UDP_SEND, DHCP_NAK)]);
+    }
+
+    #[test]
+    fn test_request_unavailable() {
+        let mut s = socket();
+        bind(&mut s, OTHER_MAC);
+        bind(&mut s, THIRD_MAC);
+
+        // Outside of the pool.
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                dhcp_request(Ipv4Address([192, 168, 1, 42]))
+            )
+        );
+        recv!(s, [(IP_SEND_BROADCAST, UDP_SEND, DHCP_NAK)]);
+
+        // Assigned to another client.
+        send!(s, (IP_RECV, UDP_RECV, dhcp_request(POOL_START)));
+        recv!(s, [(IP_SEND_BROADCAST, UDP_SEND, DHCP_NAK)]);
+    }
+
+    #[test]
+    fn test_request_unknown_client() {
+        let mut s = socket();
+
+        // Requests without a server identifier from clients without a lease are
+        // ignored, since they may be bound by another server.
+        let request = DhcpRepr {
+            server_identifier: None,
+            ..dhcp_request(Ipv4Address([10, 0, 0, 42]))
+        };
+        send!(s, (IP_RECV, UDP_RECV, request.clone()));
+        recv!(s, []);
+
+        // Once the client has a lease, they are refused.
+        bind(&mut s, CLIENT_MAC);
+        send!(s, (IP_RECV, UDP_RECV, request));
+        recv!(s, [(IP_SEND_BROADCAST, UDP_SEND, DHCP_NAK)]);
+    }
+
+    #[test]
+    fn test_pool_exhausted() {
+        let mut s = socket();
+        assert_eq!(bind(&mut s, THIRD_MAC), POOL_START);
+        assert_eq!(bind(&mut s, CLIENT_MAC), POOL_END);
+
+        let discover = DhcpRepr {
+            client_hardware_address: FOURTH_MAC,
+            ..DHCP_DISCOVER
+        };
+        send!(s, (IP_RECV, UDP_RECV, discover.clone()));
+        recv!(s, []);
+
+        // Expired leases are reclaimed.
+        send!(s, time 1_000_000, (IP_RECV, UDP_RECV, discover));
+        recv!(s, time 1_000_000, [(
+            IP_SEND_BROADCAST,
+            UDP_SEND,
+            DhcpRepr {
+                client_hardware_address: FOURTH_MAC,
+                ..dhcp_reply(DhcpMessageType::Offer, POOL_START)
+            }
+        )]);
+    }
+
+    #[test]
+    fn test_storage_full() {
+        let config = Config::new(Ipv4Cidr::new(SERVER_IP, 24), POOL_START, POOL_END);
+        let leases: &'static mut [Option<Lease>] = Box::leak(Box::new([None]));
+        let mut s = TestSocket {
+            socket: Socket::new(config, leases),
+            cx: Context::mock(),
+        };
+        bind(&mut s, THIRD_MAC);
+
+        send!(s, (IP_RECV, UDP_RECV, DHCP_DISCOVER));
+        recv!(s, []);
+    }
+
+    #[test]
+    fn test_other_server_selected() {
+        let mut s = socket();
+
+        send!(s, (IP_RECV, UDP_RECV, DHCP_DISCOVER));
+        s.replies.clear();
+
+        let request = DhcpRepr {
+            server_identifier: Some(Ipv4Address([192, 168, 1, 2])),
+            ..dhcp_request(POOL_START)
+        };
+        send!(s, (IP_RECV, UDP_RECV, request));
+        recv!(s, []);
+        assert!(s.lease_of(CLIENT_MAC).is_none());
+    }
+
+    #[test]
+    fn test_release() {
+        let mut s = socket();
+        bind(&mut s, CLIENT_MAC);
+
+        let release = DhcpRepr {
+            message_type: DhcpMessageType::Release,
+            client_ip: POOL_START,
+            server_identifier: Some(SERVER_IP),
+            ..DHCP_DEFAULT
+        };
+        send!(s, (IP_RECV, UDP_RECV, release));
+        recv!(s, []);
+        assert_eq!(s.leases().count(), 0);
+    }
+
+    #[test]
+    fn test_decline() {
+        let mut s = socket();
+        bind(&mut s, CLIENT_MAC);
+
+        let decline = DhcpRepr {
+            message_type: DhcpMessageType::Decline,
+            requested_ip: Some(POOL_START),
+            server_identifier: Some(SERVER_IP),
+            ..DHCP_DEFAULT
+        };
+        send!(s, (IP_RECV, UDP_RECV, decline));
+        recv!(s, []);
+        assert_eq!(s.leases().count(), 0);
+
+        // The declined address is not offered again.
+        send!(s, (IP_RECV, UDP_RECV, DHCP_DISCOVER));
+        recv!(
+            s,
+            [(
+                IP_SEND_BROADCAST,
+                UDP_SEND,
+                dhcp_reply(DhcpMessageType::Offer, POOL_END)
+            )]
+        );
+    }
+
+    #[test]
+    fn test_inform() {
+        let mut s = socket();
+
+        let ip_inform = Ipv4Repr {
+            src_addr: Ipv4Address([192, 168, 1, 42]),
+            ..IP_RECV
+        };
+        let inform = DhcpRepr {
+            message_type: DhcpMessageType::Inform,
+            client_ip: Ipv4Address([192, 168, 1, 42]),
+            ..DHCP_DEFAULT
+        };
+        send!(s, (ip_inform, UDP_RECV, inform));
+        recv!(
+            s,
+            [(
+                Ipv4Repr {
+                    dst_addr: Ipv4Address([192, 168, 1, 42]),
+                    ..IP_SEND_BROADCAST
+                },
+                UDP_SEND,
+                DhcpRepr {
+                    client_ip: Ipv4Address([192, 168, 1, 42]),
+                    lease_duration: None,
+                    ..dhcp_reply(DhcpMessageType::Ack, Ipv4Address::UNSPECIFIED)
+                }
+            )]
+        );
+        assert_eq!(s.leases().count(), 0);
+    }
+
+    #[test]
+    fn test_relay() {
+        let mut s = socket();
+
+        let discover = DhcpRepr {
+            relay_agent_ip: RELAY_IP,
+            ..DHCP_DISCOVER
+        };
+        send!(s, (IP_RECV, UDP_RECV, discover));
+        recv!(
+            s,
+            [(
+                Ipv4Repr {
+                    dst_addr: RELAY_IP,
+                    ..IP_SEND_BROADCAST
+                },
+                UdpRepr {
+                    src_port: DHCP_SERVER_PORT,
+                    dst_port: DHCP_SERVER_PORT,
+                },
+                DhcpRepr {
+                    relay_agent_ip: RELAY_IP,
+                    ..dhcp_reply(DhcpMessageType::Offer, POOL_START)
+                }
+            )]
+        );
+    }
+}
@@ -0,0 +1,1728 @@
+#[cfg(feature = "async")]
+use core::task::Waker;
+
+use crate::iface::Context;
+use crate::time::{Duration, Instant};
+use crate::wire::dhcpv6::field as dhcpv6_field;
+use crate::wire::{
+    Dhcpv6IaAddress, Dhcpv6IaNa, Dhcpv6IaPd, Dhcpv6IaPrefix, Dhcpv6MessageType, Dhcpv6Packet,
+    Dhcpv6Repr, Dhcpv6StatusCode, HardwareAddress, IpProtocol, Ipv6Address, Ipv6Cidr, Ipv6Repr,
+    NdiscRouterFlags, UdpRepr, DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS, DHCPV6_CLIENT_PORT,
+    DHCPV6_MAX_DNS_SERVER_COUNT, DHCPV6_MAX_DUID_LEN, DHCPV6_SERVER_PORT,
+};
+use heapless::Vec;
+
+#[cfg(feature = "async")]
+use super::WakerRegistration;
+
+use super::PollAt;
+
+/// Information refresh time used when the server does not give one (IRT_DEFAULT).
+const DEFAULT_INFORMATION_REFRESH_TIME: Duration = Duration::from_secs(86400);
+
+/// Minimum information refresh time (IRT_MINIMUM).
+const MIN_INFORMATION_REFRESH_TIME: Duration = Duration::from_secs(600);
+
+/// Options requested in every message.
+const REQUESTED_OPTIONS: &[u16] = &[dhcpv6_field::OPT_DNS_SERVERS];
+
+/// Options requested in Information-request messages.
+const INFORMATION_REQUESTED_OPTIONS: &[u16] = &[
+    dhcpv6_field::OPT_DNS_SERVERS,
+    dhcpv6_field::OPT_INFORMATION_REFRESH_TIME,
+];
+
+/// IPv6 configuration data provided by the DHCPv6 server.
+#[derive(Debug, Eq, PartialEq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    /// Information on the DHCPv6 server that provided the configuration.
+    pub server: ServerInfo,
+    /// IPv6 address assigned by the server. It is `None` when only the other configuration
+    /// parameters are requested.
+    ///
+    /// DHCPv6 does not provide the prefix length of the link: the on-link prefixes are
+    /// given by the Router Advertisements, and are usually 64 bits long.
+    pub address: Option<Ipv6Address>,
+    /// Prefix delegated by the server, when enabled with [`Socket::set_prefix_delegation`].
+    pub prefix: Option<DelegatedPrefix>,
+    /// DNS servers
+    pub dns_servers: Vec<Ipv6Address, DHCPV6_MAX_DNS_SERVER_COUNT>,
+}
+
+/// Prefix delegated by a DHCPv6 server, as described in RFC 8415 § 6.3.
+///
+/// A router can number its downstream links from it, and advertise them in its Router
+/// Advertisements with the remaining lifetimes. Extending the lifetimes of the prefix yields
+/// a new [`Event::Configured`].
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DelegatedPrefix {
+    pub prefix: Ipv6Cidr,
+    /// When the prefix stops being preferred.
+    pub preferred_until: Instant,
+    /// When the prefix becomes invalid.
+    pub valid_until: Instant,
+}
+
+/// Information on a DHCPv6 server.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ServerInfo {
+    /// Link-local address the server, or a relay agent, replied from.
+    pub address: Ipv6Address,
+    /// DUID of the server.
+    pub identifier: Vec<u8, DHCPV6_MAX_DUID_LEN>,
+}
+
+/// How the socket obtains the configuration.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Mode {
+    /// Follow the flags of the Router Advertisements received by the interface: acquire an
+    /// address when the managed address configuration flag is set, or only the other
+    /// configuration parameters when the other configuration flag is set. Nothing is sent
+    /// until a Router Advertisement with either flag is received.
+    Auto,
+    /// Acquire an address, along with the other configuration parameters.
+    Stateful,
+    /// Only acquire the other configuration parameters, such as DNS servers, as described
+    /// in RFC 8415 § 6.1.
+    Stateless,
+}
+
+/// Retransmission state of a message exchange.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Exchange {
+    /// When to send the next message
+    retry_at: Instant,
+    /// Timeout after the next message
+    timeout: Duration,
+    /// When the first message of the exchange was sent, if any
+    started_at: Option<Instant>,
+}
+
+impl Exchange {
+    fn new(retry_at: Instant, timeout: Duration) -> Self {
+        Exchange {
+            retry_at,
+            timeout,
+            started_at: None,
+        }
+    }
+
+    /// Update the state after sending a message, doubling the timeout up to `max_timeout`.
+    fn sent(&mut self, now: Instant, max_timeout: Duration) {
+        self.started_at.get_or_insert(now);
+        self.retry_at = now + self.timeout;
+        self.timeout = (self.timeout * 2).min(max_timeout);
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct SolicitState {
+    exchange: Exchange,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct RequestState {
+    exchange: Exchange,
+    /// How many retries have been done
+    retry: u16,
+    /// Server we're trying to request from
+    server: ServerInfo,
+    /// Address that we're trying to request.
+    address: Option<Ipv6Address>,
+    /// Prefix that we're trying to request.
+    prefix: Option<Ipv6Cidr>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct RenewState {
+    /// Active network config
+    config: Config,
+    /// Renew or rebind exchange, whose first message is sent at the renew time.
+    exchange: Exchange,
+    /// Rebind timer. When reached, we will start asking any server to extend the lease.
+    rebind_at: Instant,
+    /// Whether the rebind time has elapsed
+    rebinding: bool,
+    /// Expiration timer. When reached, the address is no longer valid, so it must be
+    /// thrown away.
+    expires_at: Instant,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct InformState {
+    /// Information-request exchange, whose first message is sent at the refresh time once
+    /// configured.
+    exchange: Exchange,
+    /// Network config, once provided by a server
+    config: Option<Config>,
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[allow(clippy::large_enum_variant)]
+enum ClientState {
+    /// Waiting for a Router Advertisement asking for DHCPv6
+    Idle,
+    /// Looking for a DHCPv6 server
+    Soliciting(SolicitState),
+    /// Requesting an address
+    Requesting(RequestState),
+    /// Having an address, refresh it periodically.
+    Renewing(RenewState),
+    /// Asking for the other configuration parameters only.
+    Informing(InformState),
+}
+
+impl ClientState {
+    /// Return the active network config, if any.
+    fn config(&self) -> Option<&Config> {
+        match self {
+            ClientState::Renewing(state) => Some(&state.config),
+            ClientState::Informing(state) => state.config.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Timeout and retry configuration.
+///
+/// The defaults are those of RFC 8415 § 7.6.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RetryConfig {
+    /// The SOLICIT and Information-request timeout doubles on every try, up to
+    /// `max_solicit_timeout`.
+    pub initial_solicit_timeout: Duration,
+    pub max_solicit_timeout: Duration,
+    /// The REQUEST timeout doubles on every try, up to `max_request_timeout`.
+    pub initial_request_timeout: Duration,
+    pub max_request_timeout: Duration,
+    pub request_retries: u16,
+    /// The RENEW and REBIND timeout doubles on every try, up to `max_renew_timeout`.
+    pub initial_renew_timeout: Duration,
+    pub max_renew_timeout: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            initial_solicit_timeout: Duration::from_secs(1),
+            max_solicit_timeout: Duration::from_secs(3600),
+            initial_request_timeout: Duration::from_secs(1),
+            max_request_timeout: Duration::from_secs(30),
+            request_retries: 10,
+            initial_renew_timeout: Duration::from_secs(10),
+            max_renew_timeout: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Return value for the `Dhcpv6Socket::poll` function
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[allow(clippy::large_enum_variant)]
+pub enum Event {
+    /// Configuration has been lost (for example, the lease has expired)
+    Deconfigured,
+    /// Configuration has been newly acquired, or modified.
+    Configured(Config),
+}
+
+#[derive(Debug)]
+pub struct Socket<'a> {
+    /// State of the DHCPv6 client.
+    state: ClientState,
+    /// Set to true on config/state change, cleared back to false by the `config` function.
+    config_changed: bool,
+    /// Transaction ID of the current exchange.
+    transaction_id: u32,
+
+    mode: Mode,
+    retry_config: RetryConfig,
+
+    /// DUID identifying the client, instead of the one derived from the hardware address.
+    duid: Option<&'a [u8]>,
+    /// Whether to request a delegated prefix.
+    prefix_delegation: bool,
+
+    /// Server port config
+    pub(crate) server_port: u16,
+
+    /// Client port config
+    pub(crate) client_port: u16,
+
+    /// Waker registration
+    #[cfg(feature = "async")]
+    waker: WakerRegistration,
+}
+
+/// DHCPv6 client socket.
+///
+/// The socket acquires an IPv6 address, and other configuration parameters such as
+/// DNS servers, through DHCPv6 autonomously, as described in RFC 8415. By default, it
+/// follows the managed and other configuration flags of the Router Advertisements received
+/// by the interface, see [`Mode::Auto`].
+///
+/// You must query the configuration with `.poll()` after every call to `Interface::poll()`,
+/// and apply the configuration to the `Interface`. The default route is not provided by
+/// DHCPv6, but by the Router Advertisements.
+///
+/// The interface must have a link-local address, from which messages are sent.
+impl<'a> Socket<'a> {
+    /// Create a DHCPv6 socket
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        Socket {
+            state: ClientState::Idle,
+            config_changed: false,
+            transaction_id: 1,
+            mode: Mode::Auto,
+            retry_config: RetryConfig::default(),
+            duid: None,
+            prefix_delegation: false,
+            #[cfg(feature = "async")]
+            waker: WakerRegistration::new(),
+            server_port: DHCPV6_SERVER_PORT,
+            client_port: DHCPV6_CLIENT_PORT,
+        }
+    }
+
+    /// Set the retry/timeouts configuration.
+    pub fn set_retry_config(&mut self, config: RetryConfig) {
+        self.retry_config = config;
+    }
+
+    /// Get how the socket obtains the configuration.
+    ///
+    /// See also [`Self::set_mode()`]
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Set how the socket obtains the configuration.
+    ///
+    /// This resets the socket.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.reset();
+    }
+
+    /// Get the DUID identifying the client.
+    ///
+    /// See also [`Self::set_duid()`]
+    pub fn duid(&self) -> Option<&'a [u8]> {
+        self.duid
+    }
+
+    /// Set the DUID identifying the client to the servers, as described in RFC 8415 § 11.
+    ///
+    /// Servers key leases on it, so it should be stable across reboots, for example a
+    /// DUID-EN based on a serial number, or a DUID-UUID. If `None`, a DUID-LL based on the
+    /// hardware address of the interface is used.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the DUID is shorter than 3 octets or longer than 130 octets.
+    pub fn set_duid(&mut self, duid: Option<&'a [u8]>) {
+        if let Some(duid) = duid {
+            assert!(
+                (3..=DHCPV6_MAX_DUID_LEN).contains(&duid.len()),
+                "invalid DUID"
+            );
+        }
+        self.duid = duid;
+    }
+
+    /// Get whether a delegated prefix is requested.
+    ///
+    /// See also [`Self::set_prefix_delegation()`]
+    pub fn prefix_delegation(&self) -> bool {
+        self.prefix_delegation
+    }
+
+    /// Set whether to request a delegated prefix along with the address, for example to
+    /// number the downstream link of a router.
+    ///
+    /// A prefix is only requested with stateful configuration. A server may then provide
+    /// either an address, a prefix, or both.
+    ///
+    /// This resets the socket.
+    pub fn set_prefix_delegation(&mut self, enabled: bool) {
+        self.prefix_delegation = enabled;
+        self.reset();
+    }
+
+    /// Set the server/client port
+    ///
+    /// Allows you to specify the ports used by DHCPv6.
+    pub fn set_ports(&mut self, server_port: u16, client_port: u16) {
+        self.server_port = server_port;
+        self.client_port = client_port;
+    }
+
+    pub(crate) fn poll_at(&self, cx: &mut Context) -> PollAt {
+        let t = match &self.state {
+            ClientState::Idle if self.wanted_state(cx).is_some() => return PollAt::Now,
+            ClientState::Idle => return PollAt::Ingress,
+            ClientState::Informing(_) if self.wanted_state(cx) == Some(Mode::Stateful) => {
+                return PollAt::Now
+            }
+            ClientState::Soliciting(state) => state.exchange.retry_at,
+            ClientState::Requesting(state) => state.exchange.retry_at,
+            ClientState::Renewing(state) => state.exchange.retry_at.min(state.expires_at),
+            ClientState::Informing(state) => state.exchange.retry_at,
+        };
+        PollAt::Time(t)
+    }
+
+    /// Return the mode the socket should be operating in, if any.
+    fn wanted_state(&self, cx: &Context) -> Option<Mode> {
+        match self.mode {
+            Mode::Auto => {
+                let flags = cx.ipv6_router_flags()?;
+                if flags.contains(NdiscRouterFlags::MANAGED) {
+                    Some(Mode::Stateful)
+                } else if flags.contains(NdiscRouterFlags::OTHER) {
+                    Some(Mode::Stateless)
+                } else {
+                    None
+                }
+            }
+            mode => Some(mode),
+        }
+    }
+
+    /// Start acquiring the configuration as required by the router flags, when following them.
+    fn update_state(&mut self, cx: &mut Context) {
+        let stateful = match (&self.state, self.wanted_state(cx)) {
+            (ClientState::Idle, Some(Mode::Stateful)) => true,
+            (ClientState::Idle, Some(Mode::Stateless)) => false,
+            // Upgrade to stateful configuration, but never downgrade.
+            (ClientState::Informing(_), Some(Mode::Stateful)) => true,
+            _ => return,
+        };
+
+        if self.state.config().is_some() {
+            self.config_changed();
+        }
+        let exchange = Exchange::new(cx.now(), self.retry_config.initial_solicit_timeout);
+        self.state = if stateful {
+            net_debug!("DHCPv6 starting stateful configuration");
+            ClientState::Soliciting(SolicitState { exchange })
+        } else {
+            net_debug!("DHCPv6 starting stateless configuration");
+            ClientState::Informing(InformState {
+                exchange,
+                config: None,
+            })
+        };
+    }
+
+    /// Return the DUID of the client, using `buffer` to store the default one.
+    fn client_duid<'b>(&'b self, cx: &Context, buffer: &'b mut [u8; 10]) -> &'b [u8] {
+        if let Some(duid) = self.duid {
+            return duid;
+        }
+        let HardwareAddress::Ethernet(ethernet_addr) = cx.hardware_addr() else {
+            panic!("using DHCPv6 socket with a non-ethernet hardware address.");
+        };
+        // DUID-LL, with the Ethernet hardware type.
+        buffer[..4].copy_from_slice(&[0, 3, 0, 1]);
+        buffer[4..].copy_from_slice(ethernet_addr.as_bytes());
+        &buffer[..]
+    }
+
+    /// Return the identifier of the identity association of the address.
+    fn iaid(cx: &Context) -> u32 {
+        let HardwareAddress::Ethernet(ethernet_addr) = cx.hardware_addr() else {
+            panic!("using DHCPv6 socket with a non-ethernet hardware address.");
+        };
+        let bytes = ethernet_addr.as_bytes();
+        u32::from_be_bytes([bytes[2], bytes[3], bytes[4], bytes[5]])
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &Ipv6Repr,
+        repr: &UdpRepr,
+        payload: &[u8],
+    ) {
+        let src_ip = ip_repr.src_addr;
+
+        // This is enforced in interface.rs.
+        assert!(repr.src_port == self.server_port && repr.dst_port == self.client_port);
+
+        let dhcp_packet = match Dhcpv6Packet::new_checked(payload) {
+            Ok(dhcp_packet) => dhcp_packet,
+            Err(e) => {
+                net_debug!("DHCPv6 invalid pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+        let dhcp_repr = match Dhcpv6Repr::parse(&dhcp_packet) {
+            Ok(dhcp_repr) => dhcp_repr,
+            Err(e) => {
+                net_debug!("DHCPv6 error parsing pkt from {}: {:?}", src_ip, e);
+                return;
+            }
+        };
+
+        if dhcp_repr.transaction_id != self.transaction_id {
+            return;
+        }
+        let mut duid_buffer = [0; 10];
+        if dhcp_repr.client_id != Some(self.client_duid(cx, &mut duid_buffer)) {
+            return;
+        }
+        let Some(server_identifier) = dhcp_repr.server_id.and_then(|id| Vec::from_slice(id).ok())
+        else {
+            net_debug!(
+                "DHCPv6 ignoring {:?} because of a missing or invalid server ID",
+                dhcp_repr.message_type
+            );
+            return;
+        };
+
+        net_debug!(
+            "DHCPv6 recv {:?} from {}: {:?}",
+            dhcp_repr.message_type,
+            src_ip,
+            dhcp_repr
+        );
+
+        let status_code = dhcp_repr.status_code.unwrap_or(Dhcpv6StatusCode::Success);
+        if status_code != Dhcpv6StatusCode::Success {
+            net_debug!(
+                "DHCPv6 ignoring {:?} with status {:?}",
+                dhcp_repr.message_type,
+                status_code
+            );
+            return;
+        }
+
+        let server = ServerInfo {
+            address: src_ip,
+            identifier: server_identifier,
+        };
+        let now = cx.now();
+
+        match (&mut self.state, dhcp_repr.message_type) {
+            (ClientState::Soliciting(_), Dhcpv6MessageType::Advertise) => {
+                let address = Self::parse_ia_na(&dhcp_repr).map(|(_, address)| address.address);
+                let prefix = Self::parse_ia_pd(&dhcp_repr)
+                    .filter(|_| self.prefix_delegation)
+                    .map(|(_, prefix)| Ipv6Cidr::new(prefix.prefix, prefix.prefix_len));
+                if address.is_none() && prefix.is_none() {
+                    net_debug!("DHCPv6 ignoring ADVERTISE without address or prefix");
+                    return;
+                }
+                self.state = ClientState::Requesting(RequestState {
+                    exchange: Exchange::new(now, self.retry_config.initial_request_timeout),
+                    retry: 0,
+                    server,
+                    address,
+                    prefix,
+                });
+            }
+            (ClientState::Requesting(_), Dhcpv6MessageType::Reply)
+            | (ClientState::Renewing(_), Dhcpv6MessageType::Reply) => {
+                let Some((config, renew_at, rebind_at, expires_at)) =
+                    Self::parse_reply(now, &dhcp_repr, server, self.prefix_delegation)
+                else {
+                    net_debug!("DHCPv6 nothing assigned, restarting solicitation");
+                    self.reset();
+                    return;
+                };
+                let exchange = Exchange::new(renew_at, self.retry_config.initial_renew_timeout);
+                match &mut self.state {
+                    ClientState::Renewing(state) => {
+                        state.exchange = exchange;
+                        state.rebind_at = rebind_at;
+                        state.rebinding = false;
+                        state.expires_at = expires_at;
+                        if state.config != config {
+                            state.config = config;
+                            self.config_changed();
+                        }
+                    }
+                    _ => {
+                        self.state = ClientState::Renewing(RenewState {
+                            config,
+                            exchange,
+                            rebind_at,
+                            rebinding: false,
+                            expires_at,
+                        });
+                        self.config_changed();
+                    }
+                }
+            }
+            (ClientState::Informing(state), Dhcpv6MessageType::Reply) => {
+                let config = Config {
+                    server,
+                    address: None,
+                    prefix: None,
+                    dns_servers: Self::parse_dns_servers(&dhcp_repr),
+                };
+                let refresh_time = dhcp_repr
+                    .information_refresh_time
+                    .map(|t| Duration::from_secs(t as u64).max(MIN_INFORMATION_REFRESH_TIME))
+                    .unwrap_or(DEFAULT_INFORMATION_REFRESH_TIME);
+                state.exchange = Exchange::new(
+                    now + refresh_time,
+                    self.retry_config.initial_solicit_timeout,
+                );
+                if state.config.as_ref() != Some(&config) {
+                    state.config = Some(config);
+                    self.config_changed();
+                }
+            }
+            _ => {
+                net_debug!(
+                    "DHCPv6 ignoring {:?}: unexpected in current state",
+                    dhcp_repr.message_type
+                );
+            }
+        }
+    }
+
+    /// Return the address of the identity association of a message, if it was assigned.
+    fn parse_ia_na(dhcp_repr: &Dhcpv6Repr) -> Option<(Dhcpv6IaNa, Dhcpv6IaAddress)> {
+        let ia_na = dhcp_repr.ia_na?;
+        if ia_na
+            .status_code
+            .map_or(false, |s| s != Dhcpv6StatusCode::Success)
+        {
+            return None;
+        }
+        let address = ia_na.address?;
+        if !address.address.is_unicast() || address.valid_lifetime == 0 {
+            return None;
+        }
+        Some((ia_na, address))
+    }
+
+    /// Return the prefix of the identity association for prefix delegation of a message,
+    /// if it was delegated.
+    fn parse_ia_pd(dhcp_repr: &Dhcpv6Repr) -> Option<(Dhcpv6IaPd, Dhcpv6IaPrefix)> {
+        let ia_pd = dhcp_repr.ia_pd?;
+        if ia_pd
+            .status_code
+            .map_or(false, |s| s != Dhcpv6StatusCode::Success)
+        {
+            return None;
+        }
+        let prefix = ia_pd.prefix?;
+        if prefix.valid_lifetime == 0 {
+            return None;
+        }
+        Some((ia_pd, prefix))
+    }
+
+    /// Return the renew, rebind and valid durations of an identity association.
+    fn lease_durations(
+        renew_time: u32,
+        rebind_time: u32,
+        preferred_lifetime: u32,
+        valid_lifetime: u32,
+    ) -> (Duration, Duration, Duration) {
+        let valid_lifetime = Duration::from_secs(valid_lifetime as u64);
+        let preferred_lifetime = Duration::from_secs(preferred_lifetime as u64).min(valid_lifetime);
+
+        // When the server leaves T1 and T2 to the client, use 0.5 and 0.8 times the
+        // preferred lifetime, as recommended by RFC 8415 § 21.4.
+        let (renew_duration, rebind_duration) = match (renew_time, rebind_time) {
+            (0, 0) => (preferred_lifetime / 2, preferred_lifetime * 4 / 5),
+            (t1, 0) => {
+                let t1 = Duration::from_secs(t1 as u64);
+                (t1, t1.max(preferred_lifetime * 4 / 5))
+            }
+            (0, t2) => {
+                let t2 = Duration::from_secs(t2 as u64);
+                ((preferred_lifetime / 2).min(t2), t2)
+            }
+            (t1, t2) => (
+                Duration::from_secs(t1 as u64),
+                Duration::from_secs(t2 as u64),
+            ),
+        };
+        let rebind_duration = rebind_duration.min(valid_lifetime);
+        let renew_duration = renew_duration.min(rebind_duration);
+        (renew_duration, rebind_duration, valid_lifetime)
+    }
+
+    /// Build the configuration, and the renew, rebind and expiration times of a reply
+    /// assigning an address or delegating a prefix.
+    ///
+    /// When both are given, the lease is renewed as soon as either needs to be.
+    fn parse_reply(
+        now: Instant,
+        dhcp_repr: &Dhcpv6Repr,
+        server: ServerInfo,
+        prefix_delegation: bool,
+    ) -> Option<(Config, Instant, Instant, Instant)> {
+        let ia_na = Self::parse_ia_na(dhcp_repr);
+        let ia_pd = Self::parse_ia_pd(dhcp_repr).filter(|_| prefix_delegation);
+
+        let na_durations = ia_na.map(|(ia, address)| {
+            Self::lease_durations(
+                ia.renew_time,
+                ia.rebind_time,
+                address.preferred_lifetime,
+                address.valid_lifetime,
+            )
+        });
+        let pd_durations = ia_pd.map(|(ia, prefix)| {
+            Self::lease_durations(
+                ia.renew_time,
+                ia.rebind_time,
+                prefix.preferred_lifetime,
+                prefix.valid_lifetime,
+            )
+        });
+        let (renew_duration, rebind_duration, valid_lifetime) = match (na_durations, pd_durations) {
+            (Some(na), Some(pd)) => (na.0.min(pd.0), na.1.min(pd.1), na.2.min(pd.2)),
+            (Some(durations), None) | (None, Some(durations)) => durations,
+            (None, None) => return None,
+        };
+
+        let config = Config {
+            server,
+            address: ia_na.map(|(_, address)| address.address),
+            prefix: ia_pd.map(|(_, prefix)| {
+                let valid_lifetime = Duration::from_secs(prefix.valid_lifetime as u64);
+                let preferred_lifetime =
+                    Duration::from_secs(prefix.preferred_lifetime as u64).min(valid_lifetime);
+                DelegatedPrefix {
+                    prefix: Ipv6Cidr::new(prefix.prefix, prefix.prefix_len),
+                    preferred_until: now + preferred_lifetime,
+                    valid_until: now + valid_lifetime,
+                }
+            }),
+            dns_servers: Self::parse_dns_servers(dhcp_repr),
+        };
+        Some((
+            config,
+            now + renew_duration,
+            now + rebind_duration,
+            now + valid_lifetime,
+        ))
+    }
+
+    fn parse_dns_servers(dhcp_repr: &Dhcpv6Repr) -> Vec<Ipv6Address, DHCPV6_MAX_DNS_SERVER_COUNT> {
+        dhcp_repr
+            .dns_servers
+            .iter()
+            .flatten()
+            .filter(|s| s.is_unicast())
+            .copied()
+            .collect()
+    }
+
+    #[cfg(not(test))]
+    fn random_transaction_id(cx: &mut Context) -> u32 {
+        cx.rand().rand_u32() & 0xff_ffff
+    }
+
+    #[cfg(test)]
+    fn random_transaction_id(_cx: &mut Context) -> u32 {
+        0x123456
+    }
+
+    pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
+    where
+        F: FnOnce(&mut Context, (Ipv6Repr, UdpRepr, Dhcpv6Repr)) -> Result<(), E>,
+    {
+        self.update_state(cx);
+
+        let now = cx.now();
+        let exchange = match &mut self.state {
+            ClientState::Idle => return Ok(()),
+            ClientState::Soliciting(state) => &mut state.exchange,
+            ClientState::Requesting(state) => &mut state.exchange,
+            ClientState::Renewing(state) => {
+                if state.expires_at <= now {
+                    net_debug!("DHCPv6 lease expired");
+                    self.reset();
+                    // return Ok so we get polled again
+                    return Ok(());
+                }
+                if !state.rebinding && state.rebind_at <= now {
+                    // Start a new exchange with any server.
+                    state.rebinding = true;
+                    state.exchange = Exchange::new(now, self.retry_config.initial_renew_timeout);
+                }
+                &mut state.exchange
+            }
+            ClientState::Informing(state) => &mut state.exchange,
+        };
+        if now < exchange.retry_at {
+            return Ok(());
+        }
+        let started_at = exchange.started_at;
+
+        if let ClientState::Requesting(state) = &self.state {
+            if state.retry >= self.retry_config.request_retries {
+                net_debug!("DHCPv6 request retries exceeded, restarting solicitation");
+                self.reset();
+                return Ok(());
+            }
+        }
+
+        let Some(src_addr) = cx.ipv6_link_local_addr() else {
+            net_trace!("DHCPv6 no link-local address to send from");
+            return Ok(());
+        };
+
+        // The transaction ID is kept for the retransmissions of a message.
+        let (transaction_id, started_at) = match started_at {
+            Some(started_at) => (self.transaction_id, started_at),
+            None => (Self::random_transaction_id(cx), now),
+        };
+        let elapsed_time = ((now - started_at).total_millis() / 10).min(u16::MAX as u64) as u16;
+
+        let mut duid_buffer = [0; 10];
+        let iaid = Self::iaid(cx);
+        let mut dhcp_repr = Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Solicit,
+            transaction_id,
+            client_id: Some(self.client_duid(cx, &mut duid_buffer)),
+            server_id: None,
+            ia_na: Some(Dhcpv6IaNa {
+                iaid,
+                renew_time: 0,
+                rebind_time: 0,
+                address: None,
+                status_code: None,
+            }),
+            ia_pd: self.prefix_delegation.then_some(Dhcpv6IaPd {
+                iaid,
+                renew_time: 0,
+                rebind_time: 0,
+                prefix: None,
+                status_code: None,
+            }),
+            requested_options: Some(REQUESTED_OPTIONS),
+            elapsed_time: Some(elapsed_time),
+            preference: None,
+            status_code: None,
+            rapid_commit: false,
+            dns_servers: None,
+            information_refresh_time: None,
+            additional_options: &[],
+        };
+
+        let requested_address = |address| Dhcpv6IaAddress {
+            address,
+            preferred_lifetime: 0,
+            valid_lifetime: 0,
+        };
+        let requested_prefix = |prefix: Ipv6Cidr| Dhcpv6IaPrefix {
+            prefix: prefix.address(),
+            prefix_len: prefix.prefix_len(),
+            preferred_lifetime: 0,
+            valid_lifetime: 0,
+        };
+        let max_timeout = match &self.state {
+            ClientState::Idle => unreachable!(),
+            ClientState::Soliciting(_) => self.retry_config.max_solicit_timeout,
+            ClientState::Requesting(state) => {
+                dhcp_repr.message_type = Dhcpv6MessageType::Request;
+                dhcp_repr.server_id = Some(&state.server.identifier);
+                dhcp_repr.ia_na.as_mut().unwrap().address = state.address.map(requested_address);
+                if let Some(ia_pd) = &mut dhcp_repr.ia_pd {
+                    ia_pd.prefix = state.prefix.map(requested_prefix);
+                }
+                self.retry_config.max_request_timeout
+            }
+            ClientState::Renewing(state) => {
+                // Renewing is with the original server, rebinding with any server.
+                if state.rebinding {
+                    dhcp_repr.message_type = Dhcpv6MessageType::Rebind;
+                } else {
+                    dhcp_repr.message_type = Dhcpv6MessageType::Renew;
+                    dhcp_repr.server_id = Some(&state.config.server.identifier);
+                }
+                dhcp_repr.ia_na.as_mut().unwrap().address =
+                    state.config.address.map(requested_address);
+                if let Some(ia_pd) = &mut dhcp_repr.ia_pd {
+                    ia_pd.prefix = state.config.prefix.map(|p| requested_prefix(p.prefix));
+                }
+                self.retry_config.max_renew_timeout
+            }
+            ClientState::Informing(_) => {
+                dhcp_repr.message_type = Dhcpv6MessageType::InformationRequest;
+                dhcp_repr.ia_na = None;
+                dhcp_repr.ia_pd = None;
+                dhcp_repr.requested_options = Some(INFORMATION_REQUESTED_OPTIONS);
+                self.retry_config.max_solicit_timeout
+            }
+        };
+
+        let udp_repr = UdpRepr {
+            src_port: self.client_port,
+            dst_port: self.server_port,
+        };
+
+        let ipv6_repr = Ipv6Repr {
+            src_addr,
+            dst_addr: DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS,
+            next_header: IpProtocol::Udp,
+            payload_len: udp_repr.header_len() + dhcp_repr.buffer_len(),
+            hop_limit: cx.ipv6_hop_limit(),
+        };
+
+        net_debug!(
+            "DHCPv6 send {:?} to {}: {:?}",
+            dhcp_repr.message_type,
+            ipv6_repr.dst_addr,
+            dhcp_repr
+        );
+        emit(cx, (ipv6_repr, udp_repr, dhcp_repr))?;
+
+        // Update state AFTER the packet has been successfully sent.
+        self.transaction_id = transaction_id;
+        match &mut self.state {
+            ClientState::Idle => {}
+            ClientState::Soliciting(state) => state.exchange.sent(now, max_timeout),
+            ClientState::Requesting(state) => {
+                state.exchange.sent(now, max_timeout);
+                state.retry += 1;
+            }
+            ClientState::Renewing(state) => {
+                state.exchange.sent(now, max_timeout);
+                let deadline = if state.rebinding {
+                    state.expires_at
+                } else {
+                    state.rebind_at
+                };
+                state.exchange.retry_at = state.exchange.retry_at.min(deadline);
+            }
+            ClientState::Informing(state) => state.exchange.sent(now, max_timeout),
+        }
+        Ok(())
+    }
+
+    /// Reset state and restart acquiring the configuration.
+    ///
+    /// Use this to speed up acquisition of an address in a new
+    /// network if a link was down and it is now back up.
+    pub fn reset(&mut self) {
+        net_trace!("DHCPv6 reset");
+        if self.state.config().is_some() {
+            self.config_changed();
+        }
+        self.state = ClientState::Idle;
+    }
+
+    /// Query the socket for configuration changes.
+    ///
+    /// The socket has an internal "configuration changed" flag. If
+    /// set, this function returns the configuration and resets the flag.
+    pub fn poll(&mut self) -> Option<Event> {
+        if !self.config_changed {
+            None
+        } else if let Some(config) = self.state.config() {
+            self.config_changed = false;
+            Some(Event::Configured(config.clone()))
+        } else {
+            self.config_changed = false;
+            Some(Event::Deconfigured)
+        }
+    }
+
+    /// This function _must_ be called when the configuration provided to the
+    /// interface, by this DHCPv6 socket, changes. It will update the `config_changed` field
+    /// so that a subsequent call to `poll` will yield an event, and wake a possible waker.
+    pub(crate) fn config_changed(&mut self) {
+        self.config_changed = true;
+        #[cfg(feature = "async")]
+        self.waker.wake();
+    }
+
+    /// Register a waker.
+    ///
+    /// The waker is woken on state changes that might affect the return value
+    /// of `poll` method calls, which indicates a new state in the DHCPv6 configuration
+    /// provided by this DHCPv6 socket.
+    ///
+    /// Notes:
+    ///
+    /// - Only one waker can be registered at a time. If another waker was previously registered,
+    ///   it is overwritten and will no longer be woken.
+    /// - The Waker is woken only once. Once woken, you must register it again to receive more wakes.
+    #[cfg(feature = "async")]
+    pub fn register_waker(&mut self, waker: &Waker) {
+        self.waker.register(waker)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::ops::{Deref, DerefMut};
+
+    use super::*;
+
+    // =========================================================================================//
+    // Helper functions
+
+    struct TestSocket {
+        socket: Socket<'static>,
+        cx: Context,
+    }
+
+    impl Deref for TestSocket {
+        type Target = Socket<'static>;
+        fn deref(&self) -> &Self::Target {
+            &self.socket
+        }
+    }
+
+    impl DerefMut for TestSocket {
+        fn deref_mut(&mut self) -> &mut Self::Target {
+            &mut self.socket
+        }
+    }
+
+    fn send(
+        s: &mut TestSocket,
+        timestamp: Instant,
+        (ip_repr, udp_repr, dhcp_repr): (Ipv6Repr, UdpRepr, Dhcpv6Repr),
+    ) {
+        s.cx.set_now(timestamp);
+
+        net_trace!("send: {:?}", ip_repr);
+        net_trace!("      {:?}", udp_repr);
+        net_trace!("      {:?}", dhcp_repr);
+
+        let mut payload = vec![0; dhcp_repr.buffer_len()];
+        dhcp_repr
+            .emit(&mut Dhcpv6Packet::new_unchecked(&mut payload))
+            .unwrap();
+
+        s.socket.process(&mut s.cx, &ip_repr, &udp_repr, &payload)
+    }
+
+    fn recv(s: &mut TestSocket, timestamp: Instant, reprs: &[(Ipv6Repr, UdpRepr, Dhcpv6Repr)]) {
+        s.cx.set_now(timestamp);
+
+        let mut i = 0;
+
+        while s.socket.poll_at(&mut s.cx) <= PollAt::Time(timestamp) {
+            let _ = s
+                .socket
+                .dispatch(&mut s.cx, |_, (mut ip_repr, udp_repr, dhcp_repr)| {
+                    assert_eq!(ip_repr.next_header, IpProtocol::Udp);
+                    assert_eq!(
+                        ip_repr.payload_len,
+                        udp_repr.header_len() + dhcp_repr.buffer_len()
+                    );
+
+                    // We validated the payload len, change it to 0 to make equality testing easier
+                    ip_repr.payload_len = 0;
+
+                    net_trace!("recv: {:?}", ip_repr);
+                    net_trace!("      {:?}", udp_repr);
+                    net_trace!("      {:?}", dhcp_repr);
+
+                    let got_repr = (ip_repr, udp_repr, dhcp_repr);
+                    match reprs.get(i) {
+                        Some(want_repr) => assert_eq!(want_repr, &got_repr),
+                        None => panic!("Too many reprs emitted"),
+                    }
+                    i += 1;
+                    Ok::<_, ()>(())
+                });
+        }
+
+        assert_eq!(i, reprs.len());
+    }
+
+    macro_rules! send {
+        ($socket:ident, $repr:expr) =>
+            (send!($socket, time 0, $repr));
+        ($socket:ident, time $time:expr, $repr:expr) =>
+            (send(&mut $socket, Instant::from_millis($time), $repr));
+    }
+
+    macro_rules! recv {
+        ($socket:ident, $reprs:expr) => ({
+            recv!($socket, time 0, $reprs);
+        });
+        ($socket:ident, time $time:expr, $reprs:expr) => ({
+            recv(&mut $socket, Instant::from_millis($time), &$reprs);
+        });
+    }
+
+    // =========================================================================================//
+    // Constants
+
+    const TXID: u32 = 0x123456;
+
+    const MY_LL: Ipv6Address = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 1);
+    const SERVER_LL: Ipv6Address = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 2);
+    const MY_ADDR: Ipv6Address = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x42);
+    const DNS_IP_1: Ipv6Address = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x53);
+    const DNS_IP_2: Ipv6Address = Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x54);
+    const DNS_IPS: &[Ipv6Address] = &[DNS_IP_1, DNS_IP_2];
+
+    const MY_DUID: &[u8] = &[0, 3, 0, 1, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02];
+    const MY_IAID: u32 = 0x02020202;
+    const SERVER_DUID: &[u8] = &[0, 3, 0, 1, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04];
+
+    const IP_SEND: Ipv6Repr = Ipv6Repr {
+        src_addr: MY_LL,
+        dst_addr: DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const IP_RECV: Ipv6Repr = Ipv6Repr {
+        src_addr: SERVER_LL,
+        dst_addr: MY_LL,
+        next_header: IpProtocol::Udp,
+        payload_len: 0,
+        hop_limit: 64,
+    };
+
+    const UDP_SEND: UdpRepr = UdpRepr {
+        src_port: DHCPV6_CLIENT_PORT,
+        dst_port: DHCPV6_SERVER_PORT,
+    };
+    const UDP_RECV: UdpRepr = UdpRepr {
+        src_port: DHCPV6_SERVER_PORT,
+        dst_port: DHCPV6_CLIENT_PORT,
+    };
+
+    const IA_ADDRESS: Dhcpv6IaAddress = Dhcpv6IaAddress {
+        address: MY_ADDR,
+        preferred_lifetime: 1000,
+        valid_lifetime: 2000,
+    };
+
+    const IA_REQUESTED: Dhcpv6IaNa = Dhcpv6IaNa {
+        iaid: MY_IAID,
+        renew_time: 0,
+        rebind_time: 0,
+        address: Some(Dhcpv6IaAddress {
+            address: MY_ADDR,
+            preferred_lifetime: 0,
+            valid_lifetime: 0,
+        }),
+        status_code: None,
+    };
+
+    const IA_ASSIGNED: Dhcpv6IaNa = Dhcpv6IaNa {
+        iaid: MY_IAID,
+        renew_time: 500,
+        rebind_time: 800,
+        address: Some(IA_ADDRESS),
+        status_code: None,
+    };
+
+    const DHCP_DEFAULT: Dhcpv6Repr = Dhcpv6Repr {
+        message_type: Dhcpv6MessageType::Solicit,
+        transaction_id: TXID,
+        client_id: Some(MY_DUID),
+        server_id: None,
+        ia_na: None,
+        ia_pd: None,
+        requested_options: None,
+        elapsed_time: None,
+        preference: None,
+        status_code: None,
+        rapid_commit: false,
+        dns_servers: None,
+        information_refresh_time: None,
+        additional_options: &[],
+    };
+
+    fn dhcp_solicit(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Solicit,
+            ia_na: Some(Dhcpv6IaNa {
+                address: None,
+                ..IA_REQUESTED
+            }),
+            requested_options: Some(REQUESTED_OPTIONS),
+            elapsed_time: Some(elapsed_time),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_advertise() -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Advertise,
+            server_id: Some(SERVER_DUID),
+            ia_na: Some(IA_ASSIGNED),
+            dns_servers: Some(Vec::from_slice(DNS_IPS).unwrap()),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_request(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Request,
+            server_id: Some(SERVER_DUID),
+            ia_na: Some(IA_REQUESTED),
+            requested_options: Some(REQUESTED_OPTIONS),
+            elapsed_time: Some(elapsed_time),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_reply() -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Reply,
+            server_id: Some(SERVER_DUID),
+            ia_na: Some(IA_ASSIGNED),
+            dns_servers: Some(Vec::from_slice(DNS_IPS).unwrap()),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_renew(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Renew,
+            ..dhcp_request(elapsed_time)
+        }
+    }
+
+    fn dhcp_rebind(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Rebind,
+            server_id: None,
+            ..dhcp_request(elapsed_time)
+        }
+    }
+
+    fn dhcp_information_request(elapsed_time: u16) -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::InformationRequest,
+            requested_options: Some(INFORMATION_REQUESTED_OPTIONS),
+            elapsed_time: Some(elapsed_time),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn dhcp_information_reply() -> Dhcpv6Repr<'static> {
+        Dhcpv6Repr {
+            message_type: Dhcpv6MessageType::Reply,
+            server_id: Some(SERVER_DUID),
+            dns_servers: Some(Vec::from_slice(DNS_IPS).unwrap()),
+            information_refresh_time: Some(3600),
+            ..DHCP_DEFAULT
+        }
+    }
+
+    fn server_info() -> ServerInfo {
+        ServerInfo {
+            address: SERVER_LL,
+            identifier: Vec::from_slice(SERVER_DUID).unwrap(),
+        }
+    }
+
+    fn socket(mode: Mode) -> TestSocket {
+        let mut s = Socket::new();
+        s.set_mode(mode);
+        assert_eq!(s.poll(), None);
+        TestSocket {
+            socket: s,
+            cx: Context::mock(),
+        }
+    }
+
+    fn socket_bound() -> TestSocket {
+        let mut s = socket(Mode::Stateful);
+        bind(&mut s);
+        s
+    }
+
+    fn bind(s: &mut TestSocket) {
+        recv(s, Instant::ZERO, &[(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        assert_eq!(s.poll(), None);
+        send(s, Instant::ZERO, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        assert_eq!(s.poll(), None);
+        recv(s, Instant::ZERO, &[(IP_SEND, UDP_SEND, dhcp_request(0))]);
+        assert_eq!(s.poll(), None);
+        send(s, Instant::ZERO, (IP_RECV, UDP_RECV, dhcp_reply()));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: server_info(),
+                address: Some(MY_ADDR),
+                prefix: None,
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+            }))
+        );
+
+        match &s.state {
+            ClientState::Renewing(r) => {
+                assert_eq!(r.exchange.retry_at, Instant::from_secs(500));
+                assert_eq!(r.rebind_at, Instant::from_secs(800));
+                assert_eq!(r.expires_at, Instant::from_secs(2000));
+            }
+            _ => panic!("Invalid state"),
+        }
+    }
+
+    // =========================================================================================//
+    // Tests
+
+    #[test]
+    fn test_bind() {
+        let mut s = socket(Mode::Stateful);
+        bind(&mut s);
+    }
+
+    #[test]
+    fn test_solicit_retransmit() {
+        let mut s = socket(Mode::Stateful);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_solicit(100))]);
+        recv!(s, time 2_000, []);
+        recv!(s, time 3_000, [(IP_SEND, UDP_SEND, dhcp_solicit(300))]);
+        recv!(s, time 6_000, []);
+        recv!(s, time 7_000, [(IP_SEND, UDP_SEND, dhcp_solicit(700))]);
+
+        // check after retransmits it still works
+        send!(s, time 7_500, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        recv!(s, time 7_500, [(IP_SEND, UDP_SEND, dhcp_request(0))]);
+    }
+
+    #[test]
+    fn test_ignore_invalid() {
+        let mut s = socket(Mode::Stateful);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+
+        // wrong transaction ID
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                Dhcpv6Repr {
+                    transaction_id: TXID + 1,
+                    ..dhcp_advertise()
+                }
+            )
+        );
+        // wrong client ID
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                Dhcpv6Repr {
+                    client_id: Some(SERVER_DUID),
+                    ..dhcp_advertise()
+                }
+            )
+        );
+        // no server ID
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                Dhcpv6Repr {
+                    server_id: None,
+                    ..dhcp_advertise()
+                }
+            )
+        );
+        // no address available
+        send!(
+            s,
+            (
+                IP_RECV,
+                UDP_RECV,
+                Dhcpv6Repr {
+                    ia_na: Some(Dhcpv6IaNa {
+                        address: None,
+                        status_code: Some(Dhcpv6StatusCode::NoAddrsAvail),
+                        ..IA_ASSIGNED
+                    }),
+                    ..dhcp_advertise()
+                }
+            )
+        );
+        // unexpected reply
+        send!(s, (IP_RECV, UDP_RECV, dhcp_reply()));
+
+        assert!(matches!(s.state, ClientState::Soliciting(_)));
+        assert_eq!(s.poll(), None);
+    }
+
+    #[test]
+    fn test_request_retries() {
+        let mut s = socket(Mode::Stateful);
+        s.set_retry_config(RetryConfig {
+            request_retries: 3,
+            ..RetryConfig::default()
+        });
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_request(0))]);
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_request(100))]);
+        recv!(s, time 3_000, [(IP_SEND, UDP_SEND, dhcp_request(300))]);
+
+        // After exceeding the retries, restart soliciting.
+        recv!(s, time 7_000, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        assert_eq!(s.poll(), None);
+    }
+
+    #[test]
+    fn test_request_not_on_link() {
+        let mut s = socket(Mode::Stateful);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_request(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_na: Some(Dhcpv6IaNa {
+                address: None,
+                status_code: Some(Dhcpv6StatusCode::NotOnLink),
+                ..IA_ASSIGNED
+            }),
+            ..dhcp_reply()
+        }));
+
+        // restart soliciting
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        assert_eq!(s.poll(), None);
+    }
+
+    #[test]
+    fn test_renew() {
+        let mut s = socket_bound();
+
+        recv!(s, time 499_000, []);
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, dhcp_renew(0))]);
+        recv!(s, time 510_000, [(IP_SEND, UDP_SEND, dhcp_renew(1000))]);
+        send!(s, time 511_000, (IP_RECV, UDP_RECV, dhcp_reply()));
+
+        // Same config, no event.
+        assert_eq!(s.poll(), None);
+        match &s.state {
+            ClientState::Renewing(r) => {
+                assert_eq!(r.exchange.retry_at, Instant::from_secs(1011));
+                assert_eq!(r.rebind_at, Instant::from_secs(1311));
+                assert_eq!(r.expires_at, Instant::from_secs(2511));
+            }
+            _ => panic!("Invalid state"),
+        }
+    }
+
+    #[test]
+    fn test_rebind() {
+        let mut s = socket_bound();
+
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, dhcp_renew(0))]);
+        recv!(s, time 510_000, [(IP_SEND, UDP_SEND, dhcp_renew(1000))]);
+        recv!(s, time 530_000, [(IP_SEND, UDP_SEND, dhcp_renew(3000))]);
+        recv!(s, time 570_000, [(IP_SEND, UDP_SEND, dhcp_renew(7000))]);
+        recv!(s, time 650_000, [(IP_SEND, UDP_SEND, dhcp_renew(15000))]);
+        // Retransmissions are capped at the rebind time.
+        recv!(s, time 799_000, []);
+        recv!(s, time 800_000, [(IP_SEND, UDP_SEND, dhcp_rebind(0))]);
+        recv!(s, time 810_000, [(IP_SEND, UDP_SEND, dhcp_rebind(1000))]);
+
+        send!(s, time 811_000, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            dns_servers: Some(Vec::from_slice(&[DNS_IP_2]).unwrap()),
+            ..dhcp_reply()
+        }));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: server_info(),
+                address: Some(MY_ADDR),
+                prefix: None,
+                dns_servers: Vec::from_slice(&[DNS_IP_2]).unwrap(),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_expires() {
+        let mut s = socket_bound();
+        s.set_retry_config(RetryConfig {
+            max_renew_timeout: Duration::from_secs(400),
+            ..RetryConfig::default()
+        });
+
+        recv!(s, time 500_000, [(IP_SEND, UDP_SEND, dhcp_renew(0))]);
+        recv!(s, time 510_000, [(IP_SEND, UDP_SEND, dhcp_renew(1000))]);
+        recv!(s, time 530_000, [(IP_SEND, UDP_SEND, dhcp_renew(3000))]);
+        recv!(s, time 570_000, [(IP_SEND, UDP_SEND, dhcp_renew(7000))]);
+        recv!(s, time 650_000, [(IP_SEND, UDP_SEND, dhcp_renew(15000))]);
+        recv!(s, time 800_000, [(IP_SEND, UDP_SEND, dhcp_rebind(0))]);
+        recv!(s, time 810_000, [(IP_SEND, UDP_SEND, dhcp_rebind(1000))]);
+        recv!(s, time 830_000, [(IP_SEND, UDP_SEND, dhcp_rebind(3000))]);
+        recv!(s, time 870_000, [(IP_SEND, UDP_SEND, dhcp_rebind(7000))]);
+        recv!(s, time 950_000, [(IP_SEND, UDP_SEND, dhcp_rebind(15000))]);
+        recv!(s, time 1_110_000, [(IP_SEND, UDP_SEND, dhcp_rebind(31000))]);
+        recv!(s, time 1_430_000, [(IP_SEND, UDP_SEND, dhcp_rebind(63000))]);
+        recv!(s, time 1_830_000, [(IP_SEND, UDP_SEND, dhcp_rebind(65535))]);
+        recv!(s, time 1_999_000, []);
+
+        // Lease expired, restart soliciting.
+        recv!(s, time 2_000_000, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+    }
+
+    #[test]
+    fn test_stateless() {
+        let mut s = socket(Mode::Stateless);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_information_request(0))]);
+        recv!(s, time 1_000, [(IP_SEND, UDP_SEND, dhcp_information_request(100))]);
+        send!(s, time 1_500, (IP_RECV, UDP_RECV, dhcp_information_reply()));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: server_info(),
+                address: None,
+                prefix: None,
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+            }))
+        );
+
+        // Refresh after the information refresh time, with a new exchange.
+        recv!(s, time 3_601_000, []);
+        recv!(s, time 3_601_500, [(IP_SEND, UDP_SEND, dhcp_information_request(0))]);
+        send!(s, time 3_601_600, (IP_RECV, UDP_RECV, dhcp_information_reply()));
+        assert_eq!(s.poll(), None);
+    }
+
+    #[test]
+    fn test_auto() {
+        let mut s = socket(Mode::Auto);
+
+        // No Router Advertisement yet.
+        recv!(s, time 0, []);
+        s.cx.set_ipv6_router_flags(Some(NdiscRouterFlags::empty()));
+        recv!(s, time 0, []);
+
+        s.cx.set_ipv6_router_flags(Some(NdiscRouterFlags::OTHER));
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_information_request(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, dhcp_information_reply()));
+        assert!(matches!(s.poll(), Some(Event::Configured(_))));
+
+        // Switch to stateful configuration when the routers ask for it.
+        s.cx.set_ipv6_router_flags(Some(NdiscRouterFlags::MANAGED | NdiscRouterFlags::OTHER));
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+        send!(s, time 0, (IP_RECV, UDP_RECV, dhcp_advertise()));
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_request(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, dhcp_reply()));
+        assert!(matches!(s.poll(), Some(Event::Configured(_))));
+
+        // But never go back.
+        s.cx.set_ipv6_router_flags(Some(NdiscRouterFlags::OTHER));
+        recv!(s, time 0, []);
+        assert!(matches!(s.state, ClientState::Renewing(_)));
+    }
+
+    const PREFIX: Ipv6Address = Ipv6Address::new(0x2001, 0xdb8, 0xff00, 0, 0, 0, 0, 0);
+
+    const IA_PD_SOLICIT: Dhcpv6IaPd = Dhcpv6IaPd {
+        iaid: MY_IAID,
+        renew_time: 0,
+        rebind_time: 0,
+        prefix: None,
+        status_code: None,
+    };
+
+    const IA_PD_REQUESTED: Dhcpv6IaPd = Dhcpv6IaPd {
+        prefix: Some(Dhcpv6IaPrefix {
+            prefix: PREFIX,
+            prefix_len: 56,
+            preferred_lifetime: 0,
+            valid_lifetime: 0,
+        }),
+        ..IA_PD_SOLICIT
+    };
+
+    const IA_PD_DELEGATED: Dhcpv6IaPd = Dhcpv6IaPd {
+        iaid: MY_IAID,
+        renew_time: 300,
+        rebind_time: 600,
+        prefix: Some(Dhcpv6IaPrefix {
+            prefix: PREFIX,
+            prefix_len: 56,
+            preferred_lifetime: 1000,
+            valid_lifetime: 1500,
+        }),
+        status_code: None,
+    };
+
+    const IA_NO_ADDRS: Dhcpv6IaNa = Dhcpv6IaNa {
+        renew_time: 0,
+        rebind_time: 0,
+        address: None,
+        status_code: Some(Dhcpv6StatusCode::NoAddrsAvail),
+        ..IA_ASSIGNED
+    };
+
+    fn delegated_prefix(at: Instant) -> DelegatedPrefix {
+        DelegatedPrefix {
+            prefix: Ipv6Cidr::new(PREFIX, 56),
+            preferred_until: at + Duration::from_secs(1000),
+            valid_until: at + Duration::from_secs(1500),
+        }
+    }
+
+    #[test]
+    fn test_prefix_delegation() {
+        let mut s = socket(Mode::Stateful);
+        s.set_prefix_delegation(true);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_SOLICIT),
+            ..dhcp_solicit(0)
+        })]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_DELEGATED),
+            ..dhcp_advertise()
+        }));
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_REQUESTED),
+            ..dhcp_request(0)
+        })]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_DELEGATED),
+            ..dhcp_reply()
+        }));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: server_info(),
+                address: Some(MY_ADDR),
+                prefix: Some(delegated_prefix(Instant::ZERO)),
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+            }))
+        );
+
+        // The lease is renewed as soon as the prefix needs to be, and all the identity
+        // associations are renewed together.
+        recv!(s, time 299_000, []);
+        recv!(s, time 300_000, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_REQUESTED),
+            ..dhcp_renew(0)
+        })]);
+        send!(s, time 301_000, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_DELEGATED),
+            ..dhcp_reply()
+        }));
+
+        // The extended lifetimes are reported.
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: server_info(),
+                address: Some(MY_ADDR),
+                prefix: Some(delegated_prefix(Instant::from_secs(301))),
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+            }))
+        );
+        match &s.state {
+            ClientState::Renewing(r) => {
+                assert_eq!(r.exchange.retry_at, Instant::from_secs(601));
+                assert_eq!(r.rebind_at, Instant::from_secs(901));
+                assert_eq!(r.expires_at, Instant::from_secs(1801));
+            }
+            _ => panic!("Invalid state"),
+        }
+    }
+
+    #[test]
+    fn test_prefix_delegation_without_address() {
+        let mut s = socket(Mode::Stateful);
+        s.set_prefix_delegation(true);
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_SOLICIT),
+            ..dhcp_solicit(0)
+        })]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_na: Some(IA_NO_ADDRS),
+            ia_pd: Some(IA_PD_DELEGATED),
+            ..dhcp_advertise()
+        }));
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            ia_na: Some(Dhcpv6IaNa {
+                address: None,
+                ..IA_REQUESTED
+            }),
+            ia_pd: Some(IA_PD_REQUESTED),
+            ..dhcp_request(0)
+        })]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_na: Some(IA_NO_ADDRS),
+            ia_pd: Some(IA_PD_DELEGATED),
+            ..dhcp_reply()
+        }));
+
+        assert_eq!(
+            s.poll(),
+            Some(Event::Configured(Config {
+                server: server_info(),
+                address: None,
+                prefix: Some(delegated_prefix(Instant::ZERO)),
+                dns_servers: Vec::from_slice(DNS_IPS).unwrap(),
+            }))
+        );
+
+        // The prefix expires.
+        recv!(s, time 1_500_000, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            ia_pd: Some(IA_PD_SOLICIT),
+            ..dhcp_solicit(0)
+        })]);
+        assert_eq!(s.poll(), Some(Event::Deconfigured));
+    }
+
+    #[test]
+    fn test_prefix_delegation_disabled() {
+        let mut s = socket(Mode::Stateful);
+
+        // A prefix that was not requested is ignored.
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, dhcp_solicit(0))]);
+        send!(s, time 0, (IP_RECV, UDP_RECV, Dhcpv6Repr {
+            ia_na: Some(IA_NO_ADDRS),
+            ia_pd: Some(IA_PD_DELEGATED),
+            ..dhcp_advertise()
+        }));
+        assert!(matches!(s.state, ClientState::Soliciting(_)));
+    }
+
+    #[test]
+    fn test_custom_duid() {
+        let mut s = socket(Mode::Stateful);
+        s.set_duid(Some(b"\x00\x04custom-duid"));
+
+        recv!(s, time 0, [(IP_SEND, UDP_SEND, Dhcpv6Repr {
+            client_id: Some(b"\x00\x04custom-duid"),
+            ..dhcp_solicit(0)
+        })]);
+    }
+}
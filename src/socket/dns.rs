@@ -142,6 +142,10 @@ pub struct Socket<'a> {
 
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+
+    /// Whether the servers advertised by IPv6 routers replace `servers`.
+    #[cfg(feature = "proto-ipv6")]
+    router_servers: bool,
 }
 
 impl<'a> Socket<'a> {
@@ -158,6 +162,8 @@ impl<'a> Socket<'a> {
             servers: Vec::from_slice(servers).unwrap(),
             queries: queries.into(),
             hop_limit: None,
+            #[cfg(feature = "proto-ipv6")]
+            router_servers: false,
         }
     }
 
@@ -170,6 +176,40 @@ impl<'a> Socket<'a> {
         self.servers = Vec::from_slice(servers).unwrap();
     }
 
+    /// Return whether the DNS servers advertised by IPv6 routers are used.
+    ///
+    /// See also the [set_router_servers](#method.set_router_servers) method
+    #[cfg(feature = "proto-ipv6")]
+    pub fn router_servers(&self) -> bool {
+        self.router_servers
+    }
+
+    /// Set whether the DNS servers advertised by IPv6 routers are used.
+    ///
+    /// When enabled, the interface replaces the list of servers of this socket with the
+    /// servers from the Recursive DNS Server option of Router Advertisements (RFC 8106),
+    /// and empties it once they expire. Disabled by default.
+    #[cfg(feature = "proto-ipv6")]
+    pub fn set_router_servers(&mut self, enabled: bool) {
+        self.router_servers = enabled;
+    }
+
+    #[cfg(feature = "proto-ipv6")]
+    pub(crate) fn update_router_servers(&mut self, servers: &[wire::Ipv6Address]) {
+        if !self.router_servers
+            || self
+                .servers
+                .iter()
+                .copied()
+                .eq(servers.iter().map(|addr| IpAddress::Ipv6(*addr)))
+        {
+            return;
+        }
+
+        net_debug!("dns: using router advertised servers {:?}", servers);
+        self.servers = servers.iter().map(|addr| IpAddress::Ipv6(*addr)).collect();
+    }
+
     /// Return the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
     /// See also the [set_hop_limit](#method.set_hop_limit) method
@@ -179,14 +219,14 @@ impl<'a> Socket<'a> {
 
     /// Set the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
-    /// A socket without an explicitly set hop limit value uses the default [IANA recommended]
-    /// value (64).
+    /// A socket without an explicitly set hop limit value uses the default of the interface
+    /// it sends the packets through, see [Interface::set_hop_limit].
     ///
     /// # Panics
     ///
     /// This function panics if a hop limit value of 0 is given. See [RFC 1122 § 3.2.1.7].
     ///
-    /// [IANA recommended]: https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml
+    /// [Interface::set_hop_limit]: crate::iface::Interface::set_hop_limit
     /// [RFC 1122 § 3.2.1.7]: https://tools.ietf.org/html/rfc1122#section-3.2.1.7
     pub fn set_hop_limit(&mut self, hop_limit: Option<u8>) {
         // A host MUST NOT send a datagram with a hop limit value of 0
@@ -529,7 +569,7 @@ impl<'a> Socket<'a> {
     where
         F: FnOnce(&mut Context, (IpRepr, UdpRepr, &[u8])) -> Result<(), E>,
     {
-        let hop_limit = self.hop_limit.unwrap_or(64);
+        let hop_limit = self.hop_limit;
 
         for q in self.queries.iter_mut().flatten() {
             if let State::Pending(pq) = &mut q.state {
@@ -616,7 +656,7 @@ impl<'a> Socket<'a> {
                     dst_addr,
                     IpProtocol::Udp,
                     udp_repr.header_len() + payload.len(),
-                    hop_limit,
+                    hop_limit.unwrap_or_else(|| cx.hop_limit(&dst_addr)),
                 );
 
                 net_trace!(
@@ -120,6 +120,8 @@ pub struct Socket<'a> {
     endpoint: Endpoint,
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+    /// The DSCP and ECN codepoint of outgoing packets.
+    traffic_class: u8,
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
     #[cfg(feature = "async")]
@@ -134,6 +136,7 @@ impl<'a> Socket<'a> {
             tx_buffer: tx_buffer,
             endpoint: Default::default(),
             hop_limit: None,
+            traffic_class: 0,
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
             #[cfg(feature = "async")]
@@ -185,14 +188,14 @@ impl<'a> Socket<'a> {
 
     /// Set the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
-    /// A socket without an explicitly set hop limit value uses the default [IANA recommended]
-    /// value (64).
+    /// A socket without an explicitly set hop limit value uses the default of the interface
+    /// it sends the packets through, see [Interface::set_hop_limit].
     ///
     /// # Panics
     ///
     /// This function panics if a hop limit value of 0 is given. See [RFC 1122 § 3.2.1.7].
     ///
-    /// [IANA recommended]: https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml
+    /// [Interface::set_hop_limit]: crate::iface::Interface::set_hop_limit
     /// [RFC 1122 § 3.2.1.7]: https://tools.ietf.org/html/rfc1122#section-3.2.1.7
     pub fn set_hop_limit(&mut self, hop_limit: Option<u8>) {
         // A host MUST NOT send a datagram with a hop limit value of 0
@@ -203,6 +206,56 @@ impl<'a> Socket<'a> {
         self.hop_limit = hop_limit
     }
 
+    /// Return the differentiated services code point of outgoing packets.
+    ///
+    /// See also the [set_dscp](#method.set_dscp) method
+    pub fn dscp(&self) -> u8 {
+        self.traffic_class >> 2
+    }
+
+    /// Set the differentiated services code point of outgoing packets, carried in the IPv4
+    /// type of service or IPv6 traffic class field, as described in [RFC 2474].
+    ///
+    /// A socket sends its packets with the default code point (0, best effort) unless
+    /// another one is set.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the code point does not fit in 6 bits.
+    ///
+    /// [RFC 2474]: https://tools.ietf.org/html/rfc2474
+    pub fn set_dscp(&mut self, dscp: u8) {
+        assert!(dscp <= 0x3f, "the DSCP must fit in 6 bits");
+        self.traffic_class = (dscp << 2) | (self.traffic_class & 0x03)
+    }
+
+    /// Return the explicit congestion notification codepoint of outgoing packets.
+    ///
+    /// See also the [set_ecn](#method.set_ecn) method
+    pub fn ecn(&self) -> u8 {
+        self.traffic_class & 0x03
+    }
+
+    /// Set the explicit congestion notification codepoint of outgoing packets, as described
+    /// in [RFC 3168].
+    ///
+    /// A socket sends its packets as not ECN-capable (0) unless another codepoint is set.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the codepoint does not fit in 2 bits.
+    ///
+    /// [RFC 3168]: https://tools.ietf.org/html/rfc3168
+    pub fn set_ecn(&mut self, ecn: u8) {
+        assert!(ecn <= 0x03, "the ECN codepoint must fit in 2 bits");
+        self.traffic_class = (self.traffic_class & !0x03) | ecn
+    }
+
+    /// Return the DSCP and ECN codepoint of outgoing packets, as a traffic class.
+    pub(crate) fn traffic_class(&self) -> u8 {
+        self.traffic_class
+    }
+
     /// Bind the socket to the given endpoint.
     ///
     /// This function returns `Err(Error::Illegal)` if the socket was open
@@ -415,6 +468,7 @@ impl<'a> Socket<'a> {
                 &Endpoint::Udp(endpoint),
                 &IcmpRepr::Ipv4(
                     Icmpv4Repr::DstUnreachable { data, header, .. }
+                    | Icmpv4Repr::PktTooBig { data, header, .. }
                     | Icmpv4Repr::TimeExceeded { data, header, .. },
                 ),
             ) if endpoint.addr.is_none() || endpoint.addr == Some(ip_repr.dst_addr()) => {
@@ -519,7 +573,7 @@ impl<'a> Socket<'a> {
     where
         F: FnOnce(&mut Context, (IpRepr, IcmpRepr)) -> Result<(), E>,
     {
-        let hop_limit = self.hop_limit.unwrap_or(64);
+        let hop_limit = self.hop_limit;
         let res = self.tx_buffer.dequeue_with(|remote_endpoint, packet_buf| {
             net_trace!(
                 "icmp:{}: sending {} octets",
@@ -555,7 +609,7 @@ impl<'a> Socket<'a> {
                         dst_addr,
                         next_header: IpProtocol::Icmp,
                         payload_len: repr.buffer_len(),
-                        hop_limit: hop_limit,
+                        hop_limit: hop_limit.unwrap_or_else(|| cx.hop_limit(remote_endpoint)),
                     });
                     emit(cx, (ip_repr, IcmpRepr::Ipv4(repr)))
                 }
@@ -592,7 +646,7 @@ impl<'a> Socket<'a> {
                         dst_addr,
                         next_header: IpProtocol::Icmpv6,
                         payload_len: repr.buffer_len(),
-                        hop_limit: hop_limit,
+                        hop_limit: hop_limit.unwrap_or_else(|| cx.hop_limit(remote_endpoint)),
                     });
                     emit(cx, (ip_repr, IcmpRepr::Ipv6(repr)))
                 }
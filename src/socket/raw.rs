@@ -86,6 +86,8 @@ pub struct Socket<'a> {
     ip_protocol: IpProtocol,
     rx_buffer: PacketBuffer<'a>,
     tx_buffer: PacketBuffer<'a>,
+    /// The DSCP and ECN codepoint of outgoing packets.
+    traffic_class: u8,
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
     #[cfg(feature = "async")]
@@ -106,6 +108,7 @@ impl<'a> Socket<'a> {
             ip_protocol,
             rx_buffer,
             tx_buffer,
+            traffic_class: 0,
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
             #[cfg(feature = "async")]
@@ -160,6 +163,58 @@ impl<'a> Socket<'a> {
         self.ip_protocol
     }
 
+    /// Return the differentiated services code point of outgoing packets.
+    ///
+    /// See also the [set_dscp](#method.set_dscp) method
+    pub fn dscp(&self) -> u8 {
+        self.traffic_class >> 2
+    }
+
+    /// Set the differentiated services code point of outgoing packets, carried in the IPv4
+    /// type of service or IPv6 traffic class field, as described in [RFC 2474].
+    ///
+    /// A socket sends its packets with the default code point (0, best effort) unless
+    /// another one is set. It replaces the code point of the IP header
+    /// written by the application.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the code point does not fit in 6 bits.
+    ///
+    /// [RFC 2474]: https://tools.ietf.org/html/rfc2474
+    pub fn set_dscp(&mut self, dscp: u8) {
+        assert!(dscp <= 0x3f, "the DSCP must fit in 6 bits");
+        self.traffic_class = (dscp << 2) | (self.traffic_class & 0x03)
+    }
+
+    /// Return the explicit congestion notification codepoint of outgoing packets.
+    ///
+    /// See also the [set_ecn](#method.set_ecn) method
+    pub fn ecn(&self) -> u8 {
+        self.traffic_class & 0x03
+    }
+
+    /// Set the explicit congestion notification codepoint of outgoing packets, as described
+    /// in [RFC 3168].
+    ///
+    /// A socket sends its packets as not ECN-capable (0) unless another codepoint is set. It replaces the codepoint of the IP header
+    /// written by the application.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the codepoint does not fit in 2 bits.
+    ///
+    /// [RFC 3168]: https://tools.ietf.org/html/rfc3168
+    pub fn set_ecn(&mut self, ecn: u8) {
+        assert!(ecn <= 0x03, "the ECN codepoint must fit in 2 bits");
+        self.traffic_class = (self.traffic_class & !0x03) | ecn
+    }
+
+    /// Return the DSCP and ECN codepoint of outgoing packets, as a traffic class.
+    pub(crate) fn traffic_class(&self) -> u8 {
+        self.traffic_class
+    }
+
     /// Check whether the transmit buffer is full.
     #[inline]
     pub fn can_send(&self) -> bool {
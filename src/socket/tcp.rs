@@ -7,16 +7,39 @@ use core::fmt::Display;
 use core::task::Waker;
 use core::{cmp, fmt, mem};
 
+use crate::iface::SocketHandle;
 #[cfg(feature = "async")]
 use crate::socket::WakerRegistration;
 use crate::socket::{Context, PollAt};
 use crate::storage::{Assembler, RingBuffer};
 use crate::time::{Duration, Instant};
+#[cfg(any(feature = "socket-tcp-md5", feature = "socket-tcp-ao"))]
+use crate::wire::TcpPacket;
 use crate::wire::{
     IpAddress, IpEndpoint, IpListenEndpoint, IpProtocol, IpRepr, TcpControl, TcpRepr, TcpSeqNumber,
-    TCP_HEADER_LEN,
+    TcpTimestampOption, TCP_HEADER_LEN,
 };
 
+#[cfg(feature = "socket-tcp-ao")]
+mod ao;
+mod cookie;
+#[cfg(feature = "socket-tcp-ao")]
+mod crypto;
+#[cfg(feature = "std")]
+mod io;
+#[cfg(feature = "socket-tcp-md5")]
+mod md5;
+#[cfg(feature = "std")]
+pub use self::io::Stream;
+
+#[cfg(any(feature = "futures-io", feature = "embedded-io-async"))]
+mod async_io;
+#[cfg(any(feature = "futures-io", feature = "embedded-io-async"))]
+pub use self::async_io::AsyncStream;
+
+#[cfg(feature = "socket-tcp-ao")]
+pub use self::ao::{AoAlgorithm, AoKey, AoKeyError};
+
 macro_rules! tcp_trace {
     ($($arg:expr),*) => (net_log!(trace, $($arg),*));
 }
@@ -99,6 +122,54 @@ impl Display for RecvError {
 #[cfg(feature = "std")]
 impl std::error::Error for RecvError {}
 
+/// Which halves of the connection [`Socket::shutdown`] closes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Shutdown {
+    /// Stop receiving; data already received or arriving later is discarded.
+    Read,
+    /// Stop sending; a FIN is sent once the transmit buffer is drained.
+    Write,
+    /// Both of the above.
+    Both,
+}
+
+/// The reason a connection was closed, as returned by [`Socket::close_reason`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CloseReason {
+    /// Both endpoints closed their transmit half of the connection.
+    Graceful,
+    /// The connection was aborted locally, with [`Socket::abort`].
+    Aborted,
+    /// The remote endpoint reset the connection.
+    Reset,
+    /// The remote endpoint did not respond within the [timeout](Socket::set_timeout), or
+    /// did not acknowledge data within the [user timeout](Socket::set_user_timeout).
+    Timeout,
+    /// The [retransmission limit](Socket::set_max_retransmissions) was exceeded.
+    RetransmissionLimit,
+    /// The remote endpoint did not answer the [keep-alive probes](Socket::set_keep_alive_probes).
+    KeepAliveTimeout,
+    /// The transmitted data was not acknowledged within the [linger](Socket::set_linger)
+    /// interval after closing.
+    LingerTimeout,
+}
+
+impl Display for CloseReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CloseReason::Graceful => write!(f, "graceful close"),
+            CloseReason::Aborted => write!(f, "aborted"),
+            CloseReason::Reset => write!(f, "reset by peer"),
+            CloseReason::Timeout => write!(f, "timeout"),
+            CloseReason::RetransmissionLimit => write!(f, "retransmission limit exceeded"),
+            CloseReason::KeepAliveTimeout => write!(f, "keep-alive timeout"),
+            CloseReason::LingerTimeout => write!(f, "linger timeout"),
+        }
+    }
+}
+
 /// A TCP socket ring buffer.
 pub type SocketBuffer<'a> = RingBuffer<'a, u8>;
 
@@ -139,6 +210,62 @@ impl fmt::Display for State {
     }
 }
 
+/// Statistics about a TCP connection, as returned by [`Socket::info`].
+///
+/// This struct is marked as `#[non_exhaustive]`, so that new fields can be added without
+/// a breaking change.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct Info {
+    /// The connection state.
+    pub state: State,
+    /// The smoothed round-trip time estimate (SRTT).
+    pub rtt: Duration,
+    /// The round-trip time deviation estimate (RTTVAR).
+    pub rtt_deviation: Duration,
+    /// The current retransmission timeout.
+    pub rto: Duration,
+    /// The maximum segment size the remote endpoint is willing to receive.
+    pub remote_mss: usize,
+    /// The window advertised by the remote endpoint, in octets.
+    pub send_window: usize,
+    /// The window advertised to the remote endpoint, in octets.
+    pub recv_window: usize,
+    /// The amount of sequence space sent but not yet acknowledged.
+    pub bytes_in_flight: usize,
+    /// The amount of octets queued in the transmit buffer.
+    pub send_queue: usize,
+    /// The amount of octets queued in the receive buffer.
+    pub recv_queue: usize,
+    /// The total number of retransmissions on this connection.
+    pub retransmissions: u32,
+    /// The number of retransmissions since new data was last acknowledged.
+    pub consecutive_retransmissions: u8,
+    /// The number of segments received out of order on this connection.
+    pub out_of_order_segments: u32,
+    /// The number of segments dropped on this connection because the receive assembler
+    /// could not track any more holes (see `Socket::set_reassembly_capacity`).
+    pub reassembly_drops: u32,
+    /// The number of consecutive duplicate ACKs received.
+    pub duplicate_acks: u8,
+    /// The time at which the connection reached the `ESTABLISHED` state.
+    pub established_at: Option<Instant>,
+    /// The time at which the last segment was received.
+    pub last_received_at: Option<Instant>,
+    /// The time at which the last segment was sent.
+    pub last_sent_at: Option<Instant>,
+    /// The congestion window, in octets, if congestion control is enabled
+    /// (see [`Socket::set_initial_window`]).
+    pub congestion_window: Option<usize>,
+    /// The number of duplicate selective acknowledgements (DSACK, RFC 2883) received,
+    /// each reporting data that the remote endpoint received twice.
+    pub duplicate_sacks: u32,
+    /// The number of retransmission episodes, after a timeout or duplicate ACKs, that
+    /// were found to be spurious, and whose congestion window reduction was undone.
+    pub spurious_retransmissions: u32,
+}
+
 // Conservative initial RTT estimate.
 const RTTE_INITIAL_RTT: u32 = 300;
 const RTTE_INITIAL_DEV: u32 = 100;
@@ -159,6 +286,8 @@ struct RttEstimator {
     timestamp: Option<(Instant, TcpSeqNumber)>,
     max_seq_sent: Option<TcpSeqNumber>,
     rto_count: u8,
+    min_rto: u32,
+    max_rto: u32,
 }
 
 impl Default for RttEstimator {
@@ -169,14 +298,32 @@ impl Default for RttEstimator {
             timestamp: None,
             max_seq_sent: None,
             rto_count: 0,
+            min_rto: RTTE_MIN_RTO,
+            max_rto: RTTE_MAX_RTO,
         }
     }
 }
 
 impl RttEstimator {
+    /// Create an estimator in its initial state, keeping the RTO bounds of `self`.
+    fn reset(&self) -> Self {
+        Self {
+            min_rto: self.min_rto,
+            max_rto: self.max_rto,
+            ..Self::default()
+        }
+    }
+
+    /// Start from the round-trip time of previous connections instead of the default
+    /// estimate, as if it were the first sample (RFC 6298 § 2.2).
+    fn seed(&mut self, rtt: Duration) {
+        self.rtt = rtt.total_millis().clamp(1, self.max_rto as u64) as u32;
+        self.deviation = self.rtt / 2;
+    }
+
     fn retransmission_timeout(&self) -> Duration {
         let margin = RTTE_MIN_MARGIN.max(self.deviation * 4);
-        let ms = (self.rtt + margin).clamp(RTTE_MIN_RTO, RTTE_MAX_RTO);
+        let ms = (self.rtt + margin).clamp(self.min_rto, self.max_rto);
         Duration::from_millis(ms as u64)
     }
 
@@ -212,13 +359,17 @@ impl RttEstimator {
         }
     }
 
-    fn on_ack(&mut self, timestamp: Instant, seq: TcpSeqNumber) {
+    /// Take a sample if `seq` acknowledges the segment being timed, and return whether
+    /// one was taken.
+    fn on_ack(&mut self, timestamp: Instant, seq: TcpSeqNumber) -> bool {
         if let Some((sent_timestamp, sent_seq)) = self.timestamp {
             if seq >= sent_seq {
                 self.sample((timestamp - sent_timestamp).total_millis() as u32);
                 self.timestamp = None;
+                return true;
             }
         }
+        false
     }
 
     fn on_retransmit(&mut self) {
@@ -235,7 +386,7 @@ impl RttEstimator {
             // all packets sent would incur a retransmit. To avoid this, force an estimate
             // increase if we see 3 consecutive retransmissions without any successful sample.
             self.rto_count = 0;
-            self.rtt = RTTE_MAX_RTO.min(self.rtt * 2);
+            self.rtt = self.max_rto.min(self.rtt * 2);
             let rto = self.retransmission_timeout().total_millis();
             tcp_trace!(
                 "rtte: too many retransmissions, increasing: rtt={:?} dev={:?} rto={:?}",
@@ -356,9 +507,9 @@ impl Timer {
         *self = Timer::FastRetransmit
     }
 
-    fn set_for_close(&mut self, timestamp: Instant) {
+    fn set_for_close(&mut self, timestamp: Instant, delay: Duration) {
         *self = Timer::Close {
-            expires_at: timestamp + CLOSE_DELAY,
+            expires_at: timestamp + delay,
         }
     }
 
@@ -370,6 +521,24 @@ impl Timer {
     }
 }
 
+/// The congestion window before a retransmission episode, restored if it turns out
+/// to be spurious.
+///
+/// As described in RFC 3708, an episode is spurious if the remote endpoint reports
+/// every octet retransmitted during it in a DSACK block.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct CwndUndo {
+    cwnd: usize,
+    ssthresh: usize,
+    /// The range of sequence space unacknowledged when the episode started.
+    start: TcpSeqNumber,
+    end: TcpSeqNumber,
+    /// The amount of octets of that range retransmitted so far.
+    retransmitted: usize,
+    /// The amount of octets of that range reported in DSACK blocks so far.
+    duplicated: usize,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 enum AckDelayTimer {
     Idle,
@@ -404,13 +573,83 @@ pub struct Socket<'a> {
     assembler: Assembler,
     rx_buffer: SocketBuffer<'a>,
     rx_fin_received: bool,
+    /// Whether the receive half has been shut down locally, in which case incoming data
+    /// is acknowledged and discarded.
+    rx_shutdown: bool,
+    /// Whether urgent data is left in the stream, instead of being delivered out of band.
+    urgent_inline: bool,
+    /// Sequence number of the received out-of-band octet, until it is removed from the stream.
+    rx_urgent_seq: Option<TcpSeqNumber>,
+    /// Whether the out-of-band octet at `rx_urgent_seq` has been received.
+    rx_urgent_received: bool,
+    /// The last received out-of-band octet, until it is read.
+    rx_urgent_byte: Option<u8>,
+    /// Sequence number following the urgent data in the transmit buffer, until it is
+    /// acknowledged.
+    tx_urgent_seq: Option<TcpSeqNumber>,
+    /// Sequence number following the data explicitly pushed, until it is acknowledged.
+    tx_push_seq: Option<TcpSeqNumber>,
     tx_buffer: SocketBuffer<'a>,
+    /// Borrowed data sent without copying it into the transmit buffer, until it is
+    /// acknowledged. It follows the first `tx_borrowed_at` octets of the transmit buffer
+    /// in the stream.
+    tx_borrowed: &'a [u8],
+    tx_borrowed_at: usize,
     /// Interval after which, if no inbound packets are received, the connection is aborted.
     timeout: Option<Duration>,
+    /// Interval after which, if transmitted data remains unacknowledged, the connection
+    /// is aborted (RFC 5482 user timeout).
+    user_timeout: Option<Duration>,
+    /// Interval after closing the transmit half during which unacknowledged data may drain,
+    /// after which the connection is reset.
+    linger: Option<Duration>,
+    /// Whether the transmit half was closed with a linger interval set.
+    lingering: bool,
+    /// The time at which the linger interval started.
+    linger_since: Option<Instant>,
+    /// Number of consecutive retransmissions after which the connection is aborted.
+    max_retransmissions: Option<u8>,
+    /// Number of consecutive retransmissions since the last acknowledgement of new data.
+    retransmissions: u8,
+    /// The time at which the oldest currently unacknowledged segment was sent.
+    unacked_since: Option<Instant>,
+    /// Total number of retransmissions on this connection.
+    total_retransmissions: u32,
+    /// Total number of segments received out of order on this connection.
+    out_of_order_segments: u32,
+    /// Total number of segments dropped because the assembler was full on this connection.
+    reassembly_drops: u32,
+    /// Total number of DSACK blocks received on this connection.
+    duplicate_sacks: u32,
+    /// Total number of spurious retransmission episodes on this connection.
+    spurious_retransmissions: u32,
+    /// The time at which the connection was established.
+    established_at: Option<Instant>,
+    /// The time at which the last segment was received.
+    last_rx_at: Option<Instant>,
+    /// The time at which the last segment was sent.
+    last_tx_at: Option<Instant>,
     /// Interval at which keep-alive packets will be sent.
     keep_alive: Option<Duration>,
+    /// Interval between unanswered keep-alive packets, if different from `keep_alive`.
+    keep_alive_probe_interval: Option<Duration>,
+    /// Number of unanswered keep-alive packets after which the connection is aborted.
+    keep_alive_probes: Option<u8>,
+    /// Number of keep-alive packets sent since the last segment was received.
+    keep_alive_unanswered: u8,
+    /// Why the last connection was closed.
+    close_reason: Option<CloseReason>,
+    /// Duration of the TIME-WAIT state.
+    time_wait: Duration,
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+    /// The flow label of outgoing IPv6 packets, if set explicitly.
+    flow_label: Option<u32>,
+    /// The DSCP and ECN codepoint of outgoing packets.
+    traffic_class: u8,
+    /// Local address to which the socket is bound, used by listen() and connect() when
+    /// they are not given one.
+    bound_addr: Option<IpAddress>,
     /// Address passed to listen(). Listen address is set when listen() is called and
     /// used every time the socket is reset back to the LISTEN state.
     listen_endpoint: IpListenEndpoint,
@@ -423,7 +662,7 @@ pub struct Socket<'a> {
     /// I.e. userspace reading n bytes adds n to remote_seq_no.
     remote_seq_no: TcpSeqNumber,
     /// The last sequence number sent.
-    /// I.e. in an idle socket, local_seq_no+tx_buffer.len().
+    /// I.e. in an idle socket, local_seq_no+tx_len().
     remote_last_seq: TcpSeqNumber,
     /// The last acknowledgement number sent.
     /// I.e. in an idle socket, remote_seq_no+rx_buffer.len().
@@ -440,8 +679,19 @@ pub struct Socket<'a> {
     remote_win_scale: Option<u8>,
     /// Whether or not the remote supports selective ACK as described in RFC 2018.
     remote_has_sack: bool,
+    /// The most recent timestamp value received from the remote, if RFC 7323 timestamps
+    /// are used on the connection.
+    remote_tsval: Option<u32>,
     /// The maximum number of data octets that the remote side may receive.
     remote_mss: usize,
+    /// The initial congestion window, in segments; `None` if congestion control is disabled.
+    initial_window: Option<usize>,
+    /// The congestion window, in octets.
+    cwnd: usize,
+    /// The slow start threshold, in octets.
+    ssthresh: usize,
+    /// How to undo the last reduction of the congestion window.
+    cwnd_undo: Option<CwndUndo>,
     /// The timestamp of the last packet received.
     remote_last_ts: Option<Instant>,
     /// The sequence number of the last packet received, used for sACK
@@ -463,6 +713,43 @@ pub struct Socket<'a> {
 
     /// Nagle's Algorithm enabled.
     nagle: bool,
+    /// Whether segments smaller than MSS are held back until the data is pushed.
+    cork: bool,
+    /// Whether PSH is set on the segment that empties the transmit buffer.
+    auto_push: bool,
+
+    /// Pacing enabled.
+    pacing: bool,
+    /// With pacing enabled, no new data segment will be sent until this instant.
+    pacing_next_at: Option<Instant>,
+
+    /// Whether an incoming connection is only established once data is received.
+    defer_accept: bool,
+
+    /// Whether SYN cookies are sent while the socket is not listening.
+    syn_cookies: bool,
+
+    /// Whether RFC 7323 timestamps are offered when establishing a connection.
+    timestamps: bool,
+
+    /// Socket to which the received data is forwarded when polling the interface.
+    splice_to: Option<SocketHandle>,
+
+    /// Whether the advertised window grows with the measured delivery rate.
+    rx_window_auto: bool,
+    /// With window auto-tuning enabled, the largest window advertised so far.
+    rx_window_limit: usize,
+    /// With window auto-tuning enabled, the start time and sequence number of the current
+    /// delivery rate measurement.
+    rx_window_measure: Option<(Instant, TcpSeqNumber)>,
+
+    /// Key used to sign and verify every segment (RFC 2385).
+    #[cfg(feature = "socket-tcp-md5")]
+    md5_key: Option<&'a [u8]>,
+
+    /// Keys and connection state used to authenticate every segment (RFC 5925).
+    #[cfg(feature = "socket-tcp-ao")]
+    ao: ao::AoState<'a>,
 
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
@@ -472,6 +759,9 @@ pub struct Socket<'a> {
 
 const DEFAULT_MSS: usize = 536;
 
+/// Initial receive window with window auto-tuning enabled, in segments.
+const RECV_WINDOW_INITIAL_SEGMENTS: usize = 10;
+
 impl<'a> Socket<'a> {
     #[allow(unused_comparisons)] // small usize platforms always pass rx_capacity check
     /// Create a socket using the given buffers.
@@ -497,11 +787,43 @@ impl<'a> Socket<'a> {
             rtte: RttEstimator::default(),
             assembler: Assembler::new(),
             tx_buffer,
+            tx_borrowed: &[],
+            tx_borrowed_at: 0,
             rx_buffer,
             rx_fin_received: false,
+            rx_shutdown: false,
+            urgent_inline: false,
+            rx_urgent_seq: None,
+            rx_urgent_received: false,
+            rx_urgent_byte: None,
+            tx_urgent_seq: None,
+            tx_push_seq: None,
             timeout: None,
+            user_timeout: None,
+            linger: None,
+            lingering: false,
+            linger_since: None,
+            max_retransmissions: None,
+            retransmissions: 0,
+            unacked_since: None,
+            total_retransmissions: 0,
+            out_of_order_segments: 0,
+            reassembly_drops: 0,
+            duplicate_sacks: 0,
+            spurious_retransmissions: 0,
+            established_at: None,
+            last_rx_at: None,
+            last_tx_at: None,
             keep_alive: None,
+            keep_alive_probe_interval: None,
+            keep_alive_probes: None,
+            keep_alive_unanswered: 0,
+            close_reason: None,
+            time_wait: CLOSE_DELAY,
             hop_limit: None,
+            flow_label: None,
+            traffic_class: 0,
+            bound_addr: None,
             listen_endpoint: IpListenEndpoint::default(),
             tuple: None,
             local_seq_no: TcpSeqNumber::default(),
@@ -513,7 +835,12 @@ impl<'a> Socket<'a> {
             remote_win_shift: rx_cap_log2.saturating_sub(16) as u8,
             remote_win_scale: None,
             remote_has_sack: false,
+            remote_tsval: None,
             remote_mss: DEFAULT_MSS,
+            initial_window: None,
+            cwnd: usize::MAX,
+            ssthresh: usize::MAX,
+            cwnd_undo: None,
             remote_last_ts: None,
             local_rx_last_ack: None,
             local_rx_last_seq: None,
@@ -522,6 +849,23 @@ impl<'a> Socket<'a> {
             ack_delay_timer: AckDelayTimer::Idle,
             challenge_ack_timer: Instant::from_secs(0),
             nagle: true,
+            cork: false,
+            auto_push: true,
+            pacing: false,
+            pacing_next_at: None,
+            defer_accept: false,
+            syn_cookies: false,
+            timestamps: false,
+            splice_to: None,
+            rx_window_auto: false,
+            rx_window_limit: 0,
+            rx_window_measure: None,
+
+            #[cfg(feature = "socket-tcp-md5")]
+            md5_key: None,
+
+            #[cfg(feature = "socket-tcp-ao")]
+            ao: ao::AoState::new(),
 
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
@@ -586,6 +930,148 @@ impl<'a> Socket<'a> {
         self.nagle
     }
 
+    /// Return whether the socket is corked.
+    ///
+    /// See also the [set_cork_enabled](#method.set_cork_enabled) method.
+    pub fn cork_enabled(&self) -> bool {
+        self.cork
+    }
+
+    /// Return whether PSH is set when the transmit buffer is emptied.
+    ///
+    /// See also the [set_auto_push_enabled](#method.set_auto_push_enabled) method.
+    pub fn auto_push_enabled(&self) -> bool {
+        self.auto_push
+    }
+
+    /// Return whether pacing is enabled.
+    ///
+    /// See also the [set_pacing_enabled](#method.set_pacing_enabled) method.
+    pub fn pacing_enabled(&self) -> bool {
+        self.pacing
+    }
+
+    /// Return whether urgent data is received inline.
+    ///
+    /// See also the [set_urgent_inline](#method.set_urgent_inline) method.
+    pub fn urgent_inline(&self) -> bool {
+        self.urgent_inline
+    }
+
+    /// Return whether accepting connections is deferred until data is received.
+    ///
+    /// See also the [set_defer_accept](#method.set_defer_accept) method.
+    pub fn defer_accept(&self) -> bool {
+        self.defer_accept
+    }
+
+    /// Return whether SYN cookies are enabled.
+    ///
+    /// See also the [set_syn_cookies_enabled](#method.set_syn_cookies_enabled) method.
+    pub fn syn_cookies_enabled(&self) -> bool {
+        self.syn_cookies
+    }
+
+    /// Return whether RFC 7323 timestamps are enabled.
+    ///
+    /// See also the [set_timestamps_enabled](#method.set_timestamps_enabled) method.
+    pub fn timestamps_enabled(&self) -> bool {
+        self.timestamps
+    }
+
+    /// Return the socket to which the received data is forwarded, if any.
+    ///
+    /// See also the [set_splice_to](#method.set_splice_to) method.
+    pub fn splice_to(&self) -> Option<SocketHandle> {
+        self.splice_to
+    }
+
+    /// Return the largest window that may be advertised, if auto-tuning is enabled.
+    fn recv_window_limit(&self) -> Option<usize> {
+        if !self.rx_window_auto {
+            return None;
+        }
+        let initial = RECV_WINDOW_INITIAL_SEGMENTS * self.remote_mss;
+        Some(
+            self.rx_window_limit
+                .max(initial)
+                .min(self.rx_buffer.capacity()),
+        )
+    }
+
+    /// Return the receive window, in octets, before scaling.
+    fn rx_window(&self) -> usize {
+        match self.recv_window_limit() {
+            Some(limit) => cmp::min(
+                self.rx_buffer.window(),
+                limit.saturating_sub(self.rx_buffer.len()),
+            ),
+            None => self.rx_buffer.window(),
+        }
+    }
+
+    /// Grow the window limit if the remote end delivered more than half of it within
+    /// a round-trip time.
+    fn tune_recv_window(&mut self, now: Instant) {
+        let Some(limit) = self.recv_window_limit() else {
+            return;
+        };
+        let delivered_seq = self.remote_seq_no + self.rx_buffer.len();
+        match self.rx_window_measure {
+            Some((start_at, start_seq))
+                if now >= start_at + Duration::from_millis(self.rtte.rtt as u64) =>
+            {
+                let delivered = delivered_seq - start_seq;
+                if delivered > limit / 2 {
+                    let limit = cmp::min(2 * delivered, self.rx_buffer.capacity());
+                    tcp_trace!("rx window: growing to {}", limit);
+                    self.rx_window_limit = self.rx_window_limit.max(limit);
+                }
+                self.rx_window_measure = Some((now, delivered_seq));
+            }
+            Some(_) => (),
+            None => self.rx_window_measure = Some((now, delivered_seq)),
+        }
+    }
+
+    /// Return the amount of octets queued for transmission, including borrowed data.
+    fn tx_len(&self) -> usize {
+        self.tx_buffer.len() + self.tx_borrowed.len()
+    }
+
+    /// Return the contiguous octets queued for transmission at `offset`, up to `size`.
+    ///
+    /// This takes the fields separately, so that the rest of the socket can still be updated
+    /// while the octets are borrowed.
+    fn tx_get<'b>(
+        tx_buffer: &'b SocketBuffer<'a>,
+        borrowed: &'a [u8],
+        borrowed_at: usize,
+        offset: usize,
+        size: usize,
+    ) -> &'b [u8] {
+        if offset < borrowed_at {
+            tx_buffer.get_allocated(offset, size.min(borrowed_at - offset))
+        } else if offset < borrowed_at + borrowed.len() {
+            let data = &borrowed[offset - borrowed_at..];
+            &data[..size.min(data.len())]
+        } else {
+            tx_buffer.get_allocated(offset - borrowed.len(), size)
+        }
+    }
+
+    /// Remove `size` acknowledged octets from the front of the queued data.
+    fn tx_dequeue(&mut self, size: usize) {
+        let before = size.min(self.tx_borrowed_at);
+        let borrowed = (size - before).min(self.tx_borrowed.len());
+        self.tx_buffer.dequeue_allocated(size - borrowed);
+        self.tx_borrowed_at -= before;
+        self.tx_borrowed = &self.tx_borrowed[borrowed..];
+        if self.tx_borrowed.is_empty() {
+            self.tx_borrowed_at = 0;
+        }
+    }
+
     /// Return the current window field value, including scaling according to RFC 1323.
     ///
     /// Used in internal calculations as well as packet generation.
@@ -593,7 +1079,7 @@ impl<'a> Socket<'a> {
     #[inline]
     fn scaled_window(&self) -> u16 {
         cmp::min(
-            self.rx_buffer.window() >> self.remote_win_shift as usize,
+            self.rx_window() >> self.remote_win_shift as usize,
             (1 << 16) - 1,
         ) as u16
     }
@@ -613,6 +1099,90 @@ impl<'a> Socket<'a> {
         self.timeout = duration
     }
 
+    /// Return the user timeout duration.
+    ///
+    /// See also the [set_user_timeout](#method.set_user_timeout) method.
+    pub fn user_timeout(&self) -> Option<Duration> {
+        self.user_timeout
+    }
+
+    /// Set the user timeout duration, as described in [RFC 5482].
+    ///
+    /// A socket with a user timeout set will abort the connection if transmitted data
+    /// remains unacknowledged for longer than the specified duration. Unlike the
+    /// [timeout](#method.set_timeout), this is not reset by incoming packets that don't
+    /// acknowledge new data, such as duplicate ACKs or window probes.
+    ///
+    /// [RFC 5482]: https://tools.ietf.org/html/rfc5482
+    pub fn set_user_timeout(&mut self, duration: Option<Duration>) {
+        self.user_timeout = duration
+    }
+
+    /// Return the linger interval.
+    ///
+    /// See also the [set_linger](#method.set_linger) method.
+    pub fn linger(&self) -> Option<Duration> {
+        self.linger
+    }
+
+    /// Set the linger interval, which determines what [close](#method.close) does with
+    /// the data that was not yet acknowledged, similarly to the `SO_LINGER` socket option.
+    ///
+    ///   * With no linger interval, which is the default, closing sends a FIN after the data
+    ///     in the transmit buffer, and the socket keeps retransmitting it until it is
+    ///     acknowledged or another timeout expires.
+    ///   * With a zero interval, closing [aborts](#method.abort) the connection: the data in
+    ///     the transmit buffer is discarded, and a RST is sent instead of a FIN.
+    ///   * With a non-zero interval, closing sends a FIN, but resets the connection if the data
+    ///     and the FIN are still unacknowledged once the interval elapses.
+    pub fn set_linger(&mut self, duration: Option<Duration>) {
+        self.linger = duration
+    }
+
+    /// Return the maximum number of consecutive retransmissions.
+    ///
+    /// See also the [set_max_retransmissions](#method.set_max_retransmissions) method.
+    pub fn max_retransmissions(&self) -> Option<u8> {
+        self.max_retransmissions
+    }
+
+    /// Set the maximum number of consecutive retransmissions.
+    ///
+    /// A socket with a retransmission limit set will abort the connection when the
+    /// retransmit timer expires after that many retransmissions of the same data without
+    /// any of it being acknowledged. By default, there is no limit.
+    pub fn set_max_retransmissions(&mut self, count: Option<u8>) {
+        self.max_retransmissions = count
+    }
+
+    /// Return the lower and upper bounds of the retransmission timeout.
+    ///
+    /// See also the [set_rto_bounds](#method.set_rto_bounds) method.
+    pub fn rto_bounds(&self) -> (Duration, Duration) {
+        (
+            Duration::from_millis(self.rtte.min_rto as u64),
+            Duration::from_millis(self.rtte.max_rto as u64),
+        )
+    }
+
+    /// Set the lower and upper bounds of the retransmission timeout.
+    ///
+    /// The retransmission timeout computed from the round-trip time estimate is clamped
+    /// to these bounds. By default, they are 10ms and 10s.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `min` is zero or greater than `max`.
+    pub fn set_rto_bounds(&mut self, min: Duration, max: Duration) {
+        if min == Duration::ZERO || min > max {
+            panic!("invalid retransmission timeout bounds")
+        }
+
+        self.rtte.min_rto = min.total_millis().min(u32::MAX as u64) as u32;
+        self.rtte.max_rto = max.total_millis().min(u32::MAX as u64) as u32;
+        self.rtte.rtt = self.rtte.rtt.min(self.rtte.max_rto);
+    }
+
     /// Set the ACK delay duration.
     ///
     /// By default, the ACK delay is set to 10ms.
@@ -636,6 +1206,190 @@ impl<'a> Socket<'a> {
         self.nagle = enabled
     }
 
+    /// Enable or disable corking.
+    ///
+    /// By default, it is disabled. This is equivalent to Linux's TCP_CORK flag.
+    ///
+    /// When enabled, only full-sized segments are sent, regardless of the data in flight,
+    /// until the socket is uncorked, the data is [pushed](#method.push), or the socket is
+    /// closed. It lets an application that issues many small writes, e.g. a header followed
+    /// by a body, have them coalesced into as few segments as possible.
+    pub fn set_cork_enabled(&mut self, enabled: bool) {
+        self.cork = enabled
+    }
+
+    /// Enable or disable setting PSH automatically.
+    ///
+    /// By default, it is enabled, and the PSH flag is set on every segment that empties
+    /// the transmit buffer. When disabled, it is only set on the segments that end data
+    /// explicitly [pushed](#method.push), so that the remote endpoint can wait for a complete
+    /// message before delivering it to the application.
+    pub fn set_auto_push_enabled(&mut self, enabled: bool) {
+        self.auto_push = enabled
+    }
+
+    /// Enable or disable deferring the acceptance of incoming connections.
+    ///
+    /// By default, an incoming connection is established as soon as the handshake completes.
+    /// When enabled, a listening socket that received a SYN stays in the `SYN-RECEIVED` state,
+    /// and is not [active](#method.is_active), until the first data segment (or a FIN)
+    /// arrives, ignoring the bare ACK that completes the handshake. This is equivalent to
+    /// Linux's TCP_DEFER_ACCEPT flag, and avoids waking up the application for port scanners
+    /// and health checks that never send any data.
+    ///
+    /// Meanwhile, the SYN|ACK is retransmitted as usual, so the connection is eventually
+    /// aborted after the [timeout](#method.set_timeout) or the
+    /// [maximum number of retransmissions](#method.set_max_retransmissions), if any.
+    pub fn set_defer_accept(&mut self, enabled: bool) {
+        self.defer_accept = enabled
+    }
+
+    /// Enable or disable SYN cookies.
+    ///
+    /// A listening socket handles a single connection at a time; once it has accepted one,
+    /// further connection attempts to its endpoint are reset, unless another socket is
+    /// listening on it. When SYN cookies are enabled, such a connection attempt is answered
+    /// instead with a SYN|ACK whose sequence number encodes the connection, without keeping
+    /// any state. If a socket with SYN cookies enabled is listening on the endpoint once
+    /// the final ACK of the handshake arrives, the connection is reconstructed in it.
+    /// This way, a flood of SYNs cannot exhaust the sockets, as only completed handshakes
+    /// use one.
+    ///
+    /// By default, SYN cookies are disabled. A connection reconstructed from a SYN cookie
+    /// supports neither window scaling nor selective acknowledgements, and the remote MSS
+    /// is rounded down to one of a few common values. SYN cookies are not sent by sockets
+    /// using TCP MD5 signatures or TCP-AO.
+    pub fn set_syn_cookies_enabled(&mut self, enabled: bool) {
+        self.syn_cookies = enabled
+    }
+
+    /// Enable or disable RFC 7323 timestamps.
+    ///
+    /// By default, timestamps are disabled. When enabled, the Timestamps option is offered
+    /// when establishing a connection, and if the remote endpoint agrees, every segment
+    /// of the connection carries the value of a millisecond clock and echoes the most
+    /// recent value received. A connection in the `TIME-WAIT` state that used timestamps
+    /// can then be reopened by a SYN with a newer timestamp, as described in
+    /// [RFC 6191], whatever its sequence number.
+    ///
+    /// [RFC 6191]: https://tools.ietf.org/html/rfc6191
+    pub fn set_timestamps_enabled(&mut self, enabled: bool) {
+        self.timestamps = enabled
+    }
+
+    /// Return the maximum number of holes in the received data that can be tracked.
+    ///
+    /// See also the [set_reassembly_capacity](#method.set_reassembly_capacity) method.
+    pub fn reassembly_capacity(&self) -> usize {
+        self.assembler.capacity()
+    }
+
+    /// Set the maximum number of holes in the received data that can be tracked.
+    ///
+    /// By default, `ASSEMBLER_MAX_SEGMENT_COUNT` holes can be tracked; once they are all
+    /// in use, segments received out of order that would open another one are dropped,
+    /// and counted in [Info::reassembly_drops]. On lossy links, raising the capacity avoids
+    /// retransmitting these segments. The storage is allocated on the heap, and ranges
+    /// already received out of order are discarded if they do not fit in it.
+    ///
+    /// # Panics
+    /// This function panics if `capacity` is zero.
+    #[cfg(feature = "alloc")]
+    pub fn set_reassembly_capacity(&mut self, capacity: usize) {
+        self.assembler.set_capacity(capacity)
+    }
+
+    /// Set the socket to which the received data is forwarded.
+    ///
+    /// When set, every [Interface::poll](crate::iface::Interface::poll) moves the data
+    /// received by this socket into the transmit buffer of the TCP socket `dst`, in the same
+    /// socket set, as with [splice_into](#method.splice_into). Setting each socket of
+    /// a pair as the destination of the other one forwards a connection in both directions,
+    /// as a TCP proxy would, without involving the application.
+    ///
+    /// # Panics
+    /// Polling the interface panics if `dst` does not refer to another TCP socket in
+    /// the socket set.
+    pub fn set_splice_to(&mut self, dst: Option<SocketHandle>) {
+        self.splice_to = dst
+    }
+
+    /// Return whether the receive window is tuned automatically.
+    ///
+    /// See also the [set_recv_window_auto_tuning](#method.set_recv_window_auto_tuning) method.
+    pub fn recv_window_auto_tuning(&self) -> bool {
+        self.rx_window_auto
+    }
+
+    /// Enable or disable automatic tuning of the receive window.
+    ///
+    /// By default, the socket advertises all the free space of its receive buffer. With
+    /// auto-tuning enabled, it starts by advertising at most 10 segments, and grows
+    /// the window up to the buffer capacity once the remote end delivers more than half of it
+    /// within a round-trip time, so that the window follows the delivery rate of
+    /// the connection. This allows many connections to share receive buffers sized for
+    /// the fastest of them, e.g. when the buffers are allocated lazily from a common pool.
+    pub fn set_recv_window_auto_tuning(&mut self, enabled: bool) {
+        self.rx_window_auto = enabled
+    }
+
+    /// Enable or disable pacing.
+    ///
+    /// By default, pacing is disabled, and the socket sends as many segments as the windows
+    /// allow in a single burst. When enabled, the transmission of data segments is spread
+    /// over the round-trip time instead, at a rate derived from the smoothed round-trip time
+    /// and the congestion window (see [set_initial_window](#method.set_initial_window)), or
+    /// the remote window if congestion control is disabled. During slow start, the rate
+    /// is doubled so that the congestion window can keep growing.
+    ///
+    /// Bursts can overflow the queue of a slow link behind the first router, causing losses;
+    /// pacing avoids them, at the cost of polling the socket more often.
+    pub fn set_pacing_enabled(&mut self, enabled: bool) {
+        self.pacing = enabled;
+        if !enabled {
+            self.pacing_next_at = None;
+        }
+    }
+
+    /// Enable or disable receiving urgent data inline.
+    ///
+    /// By default, the last octet of the urgent data signaled by the remote endpoint
+    /// (following RFC 6093, the octet preceding the urgent pointer) is removed from the stream
+    /// and can only be read with [recv_urgent](#method.recv_urgent), as with BSD sockets;
+    /// reading the stream stops at this octet until it has been passed. When enabled,
+    /// the urgent pointer is ignored and all data is received in the stream.
+    pub fn set_urgent_inline(&mut self, enabled: bool) {
+        self.urgent_inline = enabled;
+        if enabled {
+            self.rx_urgent_seq = None;
+            self.rx_urgent_received = false;
+        }
+    }
+
+    /// Return the initial congestion window, in segments.
+    ///
+    /// See also the [set_initial_window](#method.set_initial_window) method.
+    pub fn initial_window(&self) -> Option<usize> {
+        self.initial_window
+    }
+
+    /// Set the initial congestion window, in segments.
+    ///
+    /// By default, congestion control is disabled, and the socket sends as much data as
+    /// the remote window allows. Setting an initial window enables congestion control as
+    /// described in [RFC 5681]: once the connection is established, the socket sends at most
+    /// this many segments before receiving an acknowledgement, then grows the congestion window
+    /// with slow start and congestion avoidance, and shrinks it when segments are lost.
+    /// [RFC 6928] recommends an initial window of 10 segments.
+    ///
+    /// The setting takes effect when the next connection is established.
+    ///
+    /// [RFC 5681]: https://tools.ietf.org/html/rfc5681
+    /// [RFC 6928]: https://tools.ietf.org/html/rfc6928
+    pub fn set_initial_window(&mut self, segments: Option<usize>) {
+        self.initial_window = segments
+    }
+
     /// Return the keep-alive interval.
     ///
     /// See also the [set_keep_alive](#method.set_keep_alive) method.
@@ -653,7 +1407,8 @@ impl<'a> Socket<'a> {
     ///   * The remote endpoint has rebooted and answers with an RST packet.
     ///   * The remote endpoint has crashed and does not answer.
     ///
-    /// The keep-alive functionality together with the timeout functionality allows to react
+    /// The keep-alive functionality together with the timeout functionality, or with
+    /// a [keep-alive probe count](#method.set_keep_alive_probes), allows to react
     /// to these error conditions.
     pub fn set_keep_alive(&mut self, interval: Option<Duration>) {
         self.keep_alive = interval;
@@ -664,6 +1419,78 @@ impl<'a> Socket<'a> {
         }
     }
 
+    /// Return the interval between unanswered keep-alive packets.
+    ///
+    /// See also the [set_keep_alive_probe_interval](#method.set_keep_alive_probe_interval)
+    /// method.
+    pub fn keep_alive_probe_interval(&self) -> Option<Duration> {
+        self.keep_alive_probe_interval
+    }
+
+    /// Set the interval between unanswered keep-alive packets.
+    ///
+    /// Once a keep-alive packet has been sent, further ones are sent at this interval until
+    /// the remote endpoint answers. By default, this is the [keep-alive interval](#method.set_keep_alive)
+    /// itself. This is equivalent to Linux's TCP_KEEPINTVL option, the keep-alive interval
+    /// being equivalent to TCP_KEEPIDLE.
+    pub fn set_keep_alive_probe_interval(&mut self, interval: Option<Duration>) {
+        self.keep_alive_probe_interval = interval
+    }
+
+    /// Return the maximum number of unanswered keep-alive packets.
+    ///
+    /// See also the [set_keep_alive_probes](#method.set_keep_alive_probes) method.
+    pub fn keep_alive_probes(&self) -> Option<u8> {
+        self.keep_alive_probes
+    }
+
+    /// Set the maximum number of unanswered keep-alive packets.
+    ///
+    /// A socket with a keep-alive probe count set will abort the connection when the
+    /// keep-alive timer expires after that many keep-alive packets were sent without any
+    /// answer from the remote endpoint; [close_reason](#method.close_reason) then returns
+    /// [CloseReason::KeepAliveTimeout]. By default, there is no limit.
+    /// This is equivalent to Linux's TCP_KEEPCNT option.
+    pub fn set_keep_alive_probes(&mut self, count: Option<u8>) {
+        self.keep_alive_probes = count
+    }
+
+    /// Return the reason the last connection was closed.
+    ///
+    /// This returns `None` if the socket never had a connection, or if the current
+    /// connection is not closed yet. Once a connection is closed, its reason is kept
+    /// until the socket is reused with [listen](#method.listen) or [connect](#method.connect).
+    pub fn close_reason(&self) -> Option<CloseReason> {
+        self.close_reason
+    }
+
+    /// Return the TIME-WAIT duration.
+    ///
+    /// See also the [set_time_wait](#method.set_time_wait) method.
+    pub fn time_wait(&self) -> Duration {
+        self.time_wait
+    }
+
+    /// Set the TIME-WAIT duration.
+    ///
+    /// After both sides have closed the connection, the side that closed first lingers in
+    /// the `TIME-WAIT` state for this duration, acknowledging retransmitted FINs and
+    /// absorbing delayed segments, so that they cannot be mistaken for segments belonging
+    /// to a new connection with the same local and remote endpoints. RFC 793 specifies
+    /// twice the maximum segment lifetime (2MSL, 4 minutes); by default, it is 10 seconds.
+    ///
+    /// A listening socket in the `TIME-WAIT` state that has no unread data will be reopened
+    /// early by a new SYN from the same remote endpoint, if its sequence number is past
+    /// the end of the previous connection, as described in [RFC 1122 § 4.2.2.13], or if
+    /// both connections use [timestamps](#method.set_timestamps_enabled) and the timestamp
+    /// of the SYN is newer than the last one received, as described in [RFC 6191].
+    ///
+    /// [RFC 1122 § 4.2.2.13]: https://tools.ietf.org/html/rfc1122#section-4.2.2.13
+    /// [RFC 6191]: https://tools.ietf.org/html/rfc6191
+    pub fn set_time_wait(&mut self, duration: Duration) {
+        self.time_wait = duration
+    }
+
     /// Return the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
     /// See also the [set_hop_limit](#method.set_hop_limit) method
@@ -673,14 +1500,14 @@ impl<'a> Socket<'a> {
 
     /// Set the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
-    /// A socket without an explicitly set hop limit value uses the default [IANA recommended]
-    /// value (64).
+    /// A socket without an explicitly set hop limit value uses the default of the interface
+    /// it sends the packets through, see [Interface::set_hop_limit].
     ///
     /// # Panics
     ///
     /// This function panics if a hop limit value of 0 is given. See [RFC 1122 § 3.2.1.7].
     ///
-    /// [IANA recommended]: https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml
+    /// [Interface::set_hop_limit]: crate::iface::Interface::set_hop_limit
     /// [RFC 1122 § 3.2.1.7]: https://tools.ietf.org/html/rfc1122#section-3.2.1.7
     pub fn set_hop_limit(&mut self, hop_limit: Option<u8>) {
         // A host MUST NOT send a datagram with a hop limit value of 0
@@ -691,58 +1518,305 @@ impl<'a> Socket<'a> {
         self.hop_limit = hop_limit
     }
 
-    /// Return the local endpoint, or None if not connected.
-    #[inline]
-    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
-        Some(self.tuple?.local)
+    /// Return the flow label set in outgoing IPv6 packets, if it was set explicitly.
+    ///
+    /// See also the [set_flow_label](#method.set_flow_label) method
+    pub fn flow_label(&self) -> Option<u32> {
+        self.flow_label
     }
 
-    /// Return the remote endpoint, or None if not connected.
-    #[inline]
-    pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
-        Some(self.tuple?.remote)
-    }
+    /// Set the flow label set in outgoing IPv6 packets.
+    ///
+    /// A socket without an explicitly set flow label uses the one the interface derives from
+    /// the addresses, protocol and ports of the flow, as described in [RFC 6437], if flow
+    /// label generation is enabled, and zero otherwise. A value of zero leaves the packets
+    /// unlabeled.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the flow label does not fit in 20 bits.
+    ///
+    /// [RFC 6437]: https://tools.ietf.org/html/rfc6437
+    pub fn set_flow_label(&mut self, flow_label: Option<u32>) {
+        if let Some(flow_label) = flow_label {
+            assert!(flow_label <= 0xf_ffff, "the flow label must fit in 20 bits");
+        }
 
-    /// Return the connection state, in terms of the TCP state machine.
-    #[inline]
-    pub fn state(&self) -> State {
-        self.state
+        self.flow_label = flow_label
     }
 
-    fn reset(&mut self) {
-        let rx_cap_log2 =
-            mem::size_of::<usize>() * 8 - self.rx_buffer.capacity().leading_zeros() as usize;
+    /// Return the differentiated services code point of outgoing packets.
+    ///
+    /// See also the [set_dscp](#method.set_dscp) method
+    pub fn dscp(&self) -> u8 {
+        self.traffic_class >> 2
+    }
 
-        self.state = State::Closed;
-        self.timer = Timer::new();
-        self.rtte = RttEstimator::default();
-        self.assembler = Assembler::new();
-        self.tx_buffer.clear();
-        self.rx_buffer.clear();
-        self.rx_fin_received = false;
-        self.listen_endpoint = IpListenEndpoint::default();
-        self.tuple = None;
-        self.local_seq_no = TcpSeqNumber::default();
-        self.remote_seq_no = TcpSeqNumber::default();
-        self.remote_last_seq = TcpSeqNumber::default();
-        self.remote_last_ack = None;
-        self.remote_last_win = 0;
-        self.remote_win_len = 0;
-        self.remote_win_scale = None;
-        self.remote_win_shift = rx_cap_log2.saturating_sub(16) as u8;
-        self.remote_mss = DEFAULT_MSS;
-        self.remote_last_ts = None;
-        self.ack_delay_timer = AckDelayTimer::Idle;
-        self.challenge_ack_timer = Instant::from_secs(0);
+    /// Set the differentiated services code point of outgoing packets, carried in the IPv4
+    /// type of service or IPv6 traffic class field, as described in [RFC 2474].
+    ///
+    /// A socket sends its packets with the default code point (0, best effort) unless
+    /// another one is set.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the code point does not fit in 6 bits.
+    ///
+    /// [RFC 2474]: https://tools.ietf.org/html/rfc2474
+    pub fn set_dscp(&mut self, dscp: u8) {
+        assert!(dscp <= 0x3f, "the DSCP must fit in 6 bits");
+        self.traffic_class = (dscp << 2) | (self.traffic_class & 0x03)
+    }
 
-        #[cfg(feature = "async")]
-        {
-            self.rx_waker.wake();
-            self.tx_waker.wake();
-        }
+    /// Return the explicit congestion notification codepoint of outgoing packets.
+    ///
+    /// See also the [set_ecn](#method.set_ecn) method
+    pub fn ecn(&self) -> u8 {
+        self.traffic_class & 0x03
     }
 
-    /// Start listening on the given endpoint.
+    /// Set the explicit congestion notification codepoint of outgoing packets, as described
+    /// in [RFC 3168].
+    ///
+    /// A socket sends its packets as not ECN-capable (0) unless another codepoint is set. TCP sockets do not negotiate ECN with their
+    /// peer, nor react to congestion experienced marks, so the codepoint should only be
+    /// set when the application handles this on its own.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the codepoint does not fit in 2 bits.
+    ///
+    /// [RFC 3168]: https://tools.ietf.org/html/rfc3168
+    pub fn set_ecn(&mut self, ecn: u8) {
+        assert!(ecn <= 0x03, "the ECN codepoint must fit in 2 bits");
+        self.traffic_class = (self.traffic_class & !0x03) | ecn
+    }
+
+    /// Return the DSCP and ECN codepoint of outgoing packets, as a traffic class.
+    pub(crate) fn traffic_class(&self) -> u8 {
+        self.traffic_class
+    }
+
+    /// Return the local address to which the socket is bound.
+    ///
+    /// See also the [set_bound_addr](#method.set_bound_addr) method.
+    pub fn bound_addr(&self) -> Option<IpAddress> {
+        self.bound_addr
+    }
+
+    /// Bind the socket to a local address.
+    ///
+    /// A bound socket only accepts connections to that address when [listening](#method.listen),
+    /// and uses it as the source address of the connections it [opens](#method.connect), instead
+    /// of choosing one according to the remote address. Listening on or connecting from an
+    /// explicitly given address other than the bound one returns `Err(Unaddressable)`.
+    ///
+    /// This does not bind the socket to an interface: it sends and receives through the
+    /// interface it is polled with. On a multihomed device, the uplink used by a connection
+    /// is selected by polling the socket with that interface, e.g. by adding it to the
+    /// sockets of the corresponding [Port](crate::iface::Port) of a
+    /// [Router](crate::iface::Router).
+    ///
+    /// The binding takes effect on the next `listen` or `connect` call, and is kept when
+    /// the socket is closed. By default, the socket is not bound.
+    ///
+    /// # Panics
+    /// This function panics if the address is unspecified.
+    pub fn set_bound_addr(&mut self, addr: Option<IpAddress>) {
+        if let Some(addr) = addr {
+            if addr.is_unspecified() {
+                panic!("cannot bind a socket to the unspecified address")
+            }
+        }
+
+        self.bound_addr = addr
+    }
+
+    /// Return the TCP MD5 signature key.
+    ///
+    /// See also the [set_md5_key](#method.set_md5_key) method.
+    #[cfg(feature = "socket-tcp-md5")]
+    pub fn md5_key(&self) -> Option<&'a [u8]> {
+        self.md5_key
+    }
+
+    /// Set the TCP MD5 signature key.
+    ///
+    /// A socket with a key set adds an MD5 signature option (RFC 2385) to every segment
+    /// it sends, and silently drops every received segment that doesn't carry a valid
+    /// signature. This is typically required for BGP sessions. The key must be set before
+    /// calling [listen](#method.listen) or [connect](#method.connect), and is kept across
+    /// connections.
+    ///
+    /// # Panics
+    /// This function panics if the key is longer than 80 octets.
+    #[cfg(feature = "socket-tcp-md5")]
+    pub fn set_md5_key(&mut self, key: Option<&'a [u8]>) {
+        if let Some(key) = key {
+            assert!(
+                key.len() <= md5::MAX_KEY_LEN,
+                "TCP MD5 key must not exceed 80 octets"
+            );
+        }
+        self.md5_key = key
+    }
+
+    /// Return the TCP-AO master key tuples.
+    ///
+    /// See also the [set_ao_keys](#method.set_ao_keys) method.
+    #[cfg(feature = "socket-tcp-ao")]
+    pub fn ao_keys(&self) -> &'a [AoKey<'a>] {
+        self.ao.keys()
+    }
+
+    /// Set the TCP-AO master key tuples.
+    ///
+    /// A socket with at least one key adds a TCP Authentication Option (RFC 5925) to every
+    /// segment it sends, and silently drops every received segment that isn't authenticated
+    /// with one of its keys. Keys must be set before calling [listen](#method.listen) or
+    /// [connect](#method.connect), and are kept across connections. If a TCP MD5 key is
+    /// also set, it is ignored.
+    ///
+    /// The first key is used to authenticate sent segments, until either
+    /// [set_ao_current_key](#method.set_ao_current_key) is called, or the remote requests
+    /// another key. When the keys are replaced, the current key is kept if it is among
+    /// the new keys.
+    ///
+    /// This function returns `Err(AoKeyError::Duplicate)` if two keys have the same send or
+    /// receive identifier, in which case the keys are left unchanged.
+    #[cfg(feature = "socket-tcp-ao")]
+    pub fn set_ao_keys(&mut self, keys: &'a [AoKey<'a>]) -> Result<(), AoKeyError> {
+        self.ao.set_keys(keys)
+    }
+
+    /// Return the TCP-AO master key tuple used to authenticate sent segments.
+    #[cfg(feature = "socket-tcp-ao")]
+    pub fn ao_current_key(&self) -> Option<&'a AoKey<'a>> {
+        self.ao.current_key()
+    }
+
+    /// Use the TCP-AO master key tuple with the given send identifier to authenticate
+    /// sent segments.
+    ///
+    /// This function returns `Err(AoKeyError::NotFound)` if there is no such key.
+    #[cfg(feature = "socket-tcp-ao")]
+    pub fn set_ao_current_key(&mut self, send_id: u8) -> Result<(), AoKeyError> {
+        self.ao.set_current_key(send_id)
+    }
+
+    /// Return the local endpoint, or None if not connected.
+    #[inline]
+    pub fn local_endpoint(&self) -> Option<IpEndpoint> {
+        Some(self.tuple?.local)
+    }
+
+    /// Return the remote endpoint, or None if not connected.
+    #[inline]
+    pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
+        Some(self.tuple?.remote)
+    }
+
+    /// Return the connection state, in terms of the TCP state machine.
+    #[inline]
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Return statistics about the connection.
+    ///
+    /// These are intended for telemetry and for adapting application behavior to
+    /// network conditions; see [Info] for the available values.
+    pub fn info(&self) -> Info {
+        Info {
+            state: self.state,
+            rtt: Duration::from_millis(self.rtte.rtt as u64),
+            rtt_deviation: Duration::from_millis(self.rtte.deviation as u64),
+            rto: self.rtte.retransmission_timeout(),
+            remote_mss: self.remote_mss,
+            send_window: self.remote_win_len,
+            recv_window: self.rx_window(),
+            bytes_in_flight: self.remote_last_seq - self.local_seq_no,
+            send_queue: self.tx_len(),
+            recv_queue: self.rx_buffer.len(),
+            retransmissions: self.total_retransmissions,
+            out_of_order_segments: self.out_of_order_segments,
+            reassembly_drops: self.reassembly_drops,
+            consecutive_retransmissions: self.retransmissions,
+            duplicate_acks: self.local_rx_dup_acks,
+            established_at: self.established_at,
+            last_received_at: self.last_rx_at,
+            last_sent_at: self.last_tx_at,
+            congestion_window: self.initial_window.map(|_| self.cwnd),
+            duplicate_sacks: self.duplicate_sacks,
+            spurious_retransmissions: self.spurious_retransmissions,
+        }
+    }
+
+    fn reset(&mut self) {
+        let rx_cap_log2 =
+            mem::size_of::<usize>() * 8 - self.rx_buffer.capacity().leading_zeros() as usize;
+
+        self.state = State::Closed;
+        self.timer = Timer::new();
+        self.rtte = self.rtte.reset();
+        self.retransmissions = 0;
+        self.unacked_since = None;
+        self.total_retransmissions = 0;
+        self.out_of_order_segments = 0;
+        self.reassembly_drops = 0;
+        self.duplicate_sacks = 0;
+        self.spurious_retransmissions = 0;
+        self.established_at = None;
+        self.last_rx_at = None;
+        self.last_tx_at = None;
+        self.assembler.clear();
+        self.tx_buffer.clear();
+        self.tx_borrowed = &[];
+        self.tx_borrowed_at = 0;
+        self.rx_buffer.clear();
+        self.rx_fin_received = false;
+        self.rx_shutdown = false;
+        self.rx_urgent_seq = None;
+        self.rx_urgent_received = false;
+        self.rx_urgent_byte = None;
+        self.tx_urgent_seq = None;
+        self.tx_push_seq = None;
+        self.listen_endpoint = IpListenEndpoint::default();
+        self.tuple = None;
+        self.local_seq_no = TcpSeqNumber::default();
+        self.remote_seq_no = TcpSeqNumber::default();
+        self.remote_last_seq = TcpSeqNumber::default();
+        self.remote_last_ack = None;
+        self.remote_last_win = 0;
+        self.remote_win_len = 0;
+        self.remote_win_scale = None;
+        self.remote_win_shift = rx_cap_log2.saturating_sub(16) as u8;
+        self.remote_mss = DEFAULT_MSS;
+        self.cwnd = usize::MAX;
+        self.ssthresh = usize::MAX;
+        self.cwnd_undo = None;
+        self.pacing_next_at = None;
+        self.remote_last_ts = None;
+        self.remote_tsval = None;
+        self.keep_alive_unanswered = 0;
+        self.close_reason = None;
+        self.ack_delay_timer = AckDelayTimer::Idle;
+        self.challenge_ack_timer = Instant::from_secs(0);
+        self.lingering = false;
+        self.linger_since = None;
+        self.rx_window_limit = 0;
+        self.rx_window_measure = None;
+        #[cfg(feature = "socket-tcp-ao")]
+        self.ao.reset();
+
+        #[cfg(feature = "async")]
+        {
+            self.rx_waker.wake();
+            self.tx_waker.wake();
+        }
+    }
+
+    /// Start listening on the given endpoint.
     ///
     /// This function returns `Err(Error::Illegal)` if the socket was already open
     /// (see [is_open](#method.is_open)), and `Err(Error::Unaddressable)`
@@ -751,10 +1825,17 @@ impl<'a> Socket<'a> {
     where
         T: Into<IpListenEndpoint>,
     {
-        let local_endpoint = local_endpoint.into();
+        let mut local_endpoint = local_endpoint.into();
         if local_endpoint.port == 0 {
             return Err(ListenError::Unaddressable);
         }
+        match (local_endpoint.addr, self.bound_addr) {
+            (Some(addr), Some(bound_addr)) if addr != bound_addr => {
+                return Err(ListenError::Unaddressable);
+            }
+            (None, bound_addr) => local_endpoint.addr = bound_addr,
+            _ => (),
+        }
 
         if self.is_open() {
             return Err(ListenError::InvalidState);
@@ -817,7 +1898,7 @@ impl<'a> Socket<'a> {
         U: Into<IpListenEndpoint>,
     {
         let remote_endpoint: IpEndpoint = remote_endpoint.into();
-        let local_endpoint: IpListenEndpoint = local_endpoint.into();
+        let mut local_endpoint: IpListenEndpoint = local_endpoint.into();
 
         if self.is_open() {
             return Err(ConnectError::InvalidState);
@@ -828,6 +1909,13 @@ impl<'a> Socket<'a> {
         if local_endpoint.port == 0 {
             return Err(ConnectError::Unaddressable);
         }
+        match (local_endpoint.addr, self.bound_addr) {
+            (Some(addr), Some(bound_addr)) if addr != bound_addr => {
+                return Err(ConnectError::Unaddressable);
+            }
+            (None, bound_addr) => local_endpoint.addr = bound_addr,
+            _ => (),
+        }
 
         // If local address is not provided, choose it automatically.
         let local_endpoint = IpEndpoint {
@@ -849,35 +1937,70 @@ impl<'a> Socket<'a> {
             return Err(ConnectError::Unaddressable);
         }
 
-        self.reset();
-        self.tuple = Some(Tuple {
+        let tuple = Tuple {
             local: local_endpoint,
             remote: remote_endpoint,
-        });
+        };
+
+        // Reusing the 4-tuple of a connection in TIME-WAIT could let delayed segments
+        // of the old connection corrupt the new one.
+        if self.state == State::TimeWait && self.tuple == Some(tuple) {
+            return Err(ConnectError::InvalidState);
+        }
+
+        self.reset();
+        self.tuple = Some(tuple);
+        self.seed_rtt(cx);
         self.set_state(State::SynSent);
 
-        let seq = Self::random_seq_no(cx);
+        let seq = Self::initial_seq_no(cx, &tuple);
         self.local_seq_no = seq;
         self.remote_last_seq = seq;
+        #[cfg(feature = "socket-tcp-ao")]
+        self.ao.set_local_isn(seq);
         Ok(())
     }
 
+    /// Start the round-trip time estimate of a new connection from the one of the previous
+    /// connections to the same destination, if any.
+    fn seed_rtt(&mut self, cx: &Context) {
+        if let Some(rtt) = cx.rtt_hint(&self.tuple.unwrap().remote.addr) {
+            self.rtte.seed(rtt);
+        }
+    }
+
     #[cfg(test)]
-    fn random_seq_no(_cx: &mut Context) -> TcpSeqNumber {
+    fn initial_seq_no(_cx: &mut Context, _tuple: &Tuple) -> TcpSeqNumber {
         TcpSeqNumber(10000)
     }
 
     #[cfg(not(test))]
-    fn random_seq_no(cx: &mut Context) -> TcpSeqNumber {
-        TcpSeqNumber(cx.rand().rand_u32() as i32)
+    fn initial_seq_no(cx: &mut Context, tuple: &Tuple) -> TcpSeqNumber {
+        cookie::initial_seq_no(cx.tcp_secret(), cx.now(), tuple.local, tuple.remote)
     }
 
     /// Close the transmit half of the full-duplex connection.
     ///
-    /// Note that there is no corresponding function for the receive half of the full-duplex
-    /// connection; only the remote end can close it. If you no longer wish to receive any
-    /// data and would like to reuse the socket right away, use [abort](#method.abort).
+    /// Data can still be received until the remote endpoint closes its own transmit half.
+    /// To also stop receiving, use [shutdown](#method.shutdown); if you would like to reuse
+    /// the socket right away, use [abort](#method.abort). The [linger](#method.set_linger)
+    /// interval determines what happens to the data that was not yet acknowledged.
     pub fn close(&mut self) {
+        if self.linger == Some(Duration::ZERO)
+            && !matches!(
+                self.state,
+                State::Listen | State::SynSent | State::TimeWait | State::Closed
+            )
+        {
+            self.abort();
+            return;
+        }
+
+        self.lingering |= self.linger.is_some()
+            && matches!(
+                self.state,
+                State::SynReceived | State::Established | State::CloseWait
+            );
         match self.state {
             // In the LISTEN state there is no established connection.
             State::Listen => self.set_state(State::Closed),
@@ -900,6 +2023,36 @@ impl<'a> Socket<'a> {
         }
     }
 
+    /// Shut down the receive half, the transmit half, or both halves of the connection.
+    ///
+    /// Shutting down the transmit half is the same as calling [close](#method.close).
+    ///
+    /// Shutting down the receive half discards the contents of the receive buffer, and any
+    /// data received afterwards is acknowledged and discarded as well, as with BSD sockets;
+    /// receiving then returns `Err(RecvError::Finished)`. The remote endpoint is not notified,
+    /// and the connection is only closed once both endpoints have closed their transmit half.
+    pub fn shutdown(&mut self, how: Shutdown) {
+        if matches!(how, Shutdown::Read | Shutdown::Both) {
+            self.rx_shutdown = true;
+            self.rx_urgent_seq = None;
+            self.rx_urgent_received = false;
+            self.discard_rx_buffer();
+        }
+        if matches!(how, Shutdown::Write | Shutdown::Both) {
+            self.close();
+        }
+    }
+
+    /// Dequeue and drop all the contiguous data in the receive buffer.
+    fn discard_rx_buffer(&mut self) {
+        let size = self.rx_buffer.len();
+        if size > 0 {
+            tcp_trace!("rx buffer: discarding {} octets", size);
+            self.rx_buffer.dequeue_allocated(size);
+            self.remote_seq_no += size;
+        }
+    }
+
     /// Aborts the connection, if any.
     ///
     /// This function instantly closes the socket. One reset packet will be sent to the remote
@@ -908,6 +2061,9 @@ impl<'a> Socket<'a> {
     /// In terms of the TCP state machine, the socket may be in any state and is moved to
     /// the `CLOSED` state.
     pub fn abort(&mut self) {
+        if self.tuple.is_some() && self.state != State::Closed {
+            self.close_reason = Some(CloseReason::Aborted);
+        }
         self.set_state(State::Closed);
     }
 
@@ -950,13 +2106,15 @@ impl<'a> Socket<'a> {
     /// the remote endpoint.
     ///
     /// In terms of the TCP state machine, the socket must not be in the `CLOSED`, `TIME-WAIT`,
-    /// or `LISTEN` state.
+    /// or `LISTEN` state, nor in the `SYN-RECEIVED` state if accepting the connection is
+    /// [deferred](#method.set_defer_accept).
     #[inline]
     pub fn is_active(&self) -> bool {
         match self.state {
             State::Closed => false,
             State::TimeWait => false,
             State::Listen => false,
+            State::SynReceived if self.defer_accept => false,
             _ => true,
         }
     }
@@ -989,8 +2147,13 @@ impl<'a> Socket<'a> {
     ///
     /// In terms of the TCP state machine, the socket must be in the `ESTABLISHED`,
     /// `FIN-WAIT-1`, or `FIN-WAIT-2` state, or have data in the receive buffer instead.
+    /// It must also not have had its receive half [shut down](#method.shutdown).
     #[inline]
     pub fn may_recv(&self) -> bool {
+        if self.rx_shutdown {
+            return false;
+        }
+
         match self.state {
             State::Established => true,
             // In FIN-WAIT-1/2, we have closed our transmit half of the connection but
@@ -1047,7 +2210,7 @@ impl<'a> Socket<'a> {
         // The connection might have been idle for a long time, and so remote_last_ts
         // would be far in the past. Unless we clear it here, we'll abort the connection
         // down over in dispatch() by erroneously detecting it as timed out.
-        if self.tx_buffer.is_empty() {
+        if self.tx_len() == 0 {
             self.remote_last_ts = None
         }
 
@@ -1089,12 +2252,91 @@ impl<'a> Socket<'a> {
         })
     }
 
+    /// Enqueue a sequence of octets gathered from several slices to be sent.
+    ///
+    /// The slices are enqueued in order, as if they were concatenated, which allows e.g.
+    /// sending a header and a payload held in separate buffers without an intermediate copy.
+    /// This function returns the amount of octets actually enqueued, which is limited
+    /// by the amount of free space in the transmit buffer; down to zero.
+    ///
+    /// See also [send_slice](#method.send_slice).
+    pub fn send_vectored(&mut self, data: &[&[u8]]) -> Result<usize, SendError> {
+        self.send_impl(|tx_buffer| {
+            let mut size = 0;
+            for slice in data {
+                let enqueued = tx_buffer.enqueue_slice(slice);
+                size += enqueued;
+                if enqueued < slice.len() {
+                    break;
+                }
+            }
+            (size, size)
+        })
+    }
+
+    /// Enqueue borrowed data to be sent, without copying it into the transmit buffer.
+    ///
+    /// The data is sent after the octets already in the transmit buffer, and is referred to
+    /// until it is acknowledged, which allows e.g. sending large constant blobs, or buffers
+    /// that the caller keeps alive until [send_borrowed_queue](#method.send_borrowed_queue)
+    /// returns zero. Octets enqueued later with [send_slice](#method.send_slice) and similar
+    /// methods are sent after it.
+    ///
+    /// Only one borrowed buffer can be queued at a time. This function returns the amount of
+    /// octets enqueued, which is either the length of `data`, or zero if the previous
+    /// borrowed buffer is not yet acknowledged.
+    pub fn send_borrowed(&mut self, data: &'a [u8]) -> Result<usize, SendError> {
+        if !self.may_send() {
+            return Err(SendError::InvalidState);
+        }
+        if !self.tx_borrowed.is_empty() {
+            return Ok(0);
+        }
+
+        // See send_impl().
+        if self.tx_len() == 0 {
+            self.remote_last_ts = None
+        }
+
+        tcp_trace!("tx buffer: borrowing {} octets", data.len());
+        self.tx_borrowed = data;
+        self.tx_borrowed_at = self.tx_buffer.len();
+        Ok(data.len())
+    }
+
+    /// Enqueue a sequence of octets to be sent as urgent data, and fill it from a slice.
+    ///
+    /// The enqueued octets are sent in the stream as usual, with the urgent pointer of
+    /// the segments set until all of them are acknowledged; with BSD sockets, the remote
+    /// endpoint receives the last one out of band. A new call moves the urgent pointer past
+    /// the newly enqueued octets.
+    ///
+    /// This function otherwise behaves identically to [send_slice](#method.send_slice).
+    pub fn send_urgent(&mut self, data: &[u8]) -> Result<usize, SendError> {
+        let size = self.send_slice(data)?;
+        if size > 0 {
+            self.tx_urgent_seq = Some(self.local_seq_no + self.tx_len());
+        }
+        Ok(size)
+    }
+
+    /// Push the octets enqueued in the transmit buffer.
+    ///
+    /// They are sent without waiting for a full-sized segment even if the socket is
+    /// [corked](#method.set_cork_enabled), and the PSH flag is set on the segment containing
+    /// the last of them. Nagle's Algorithm still applies.
+    pub fn push(&mut self) {
+        if self.tx_len() > 0 {
+            self.tx_push_seq = Some(self.local_seq_no + self.tx_len());
+        }
+    }
+
     fn recv_error_check(&mut self) -> Result<(), RecvError> {
         // We may have received some data inside the initial SYN, but until the connection
         // is fully open we must not dequeue any data, as it may be overwritten by e.g.
         // another (stale) SYN. (We do not support TCP Fast Open.)
         if !self.may_recv() {
-            if self.rx_fin_received {
+            if self.rx_fin_received || self.rx_shutdown {
                 return Err(RecvError::Finished);
             }
             return Err(RecvError::InvalidState);
@@ -1103,14 +2345,65 @@ impl<'a> Socket<'a> {
         Ok(())
     }
 
+    /// Record the out-of-band octet at `seq`, signaled by the urgent pointer of
+    /// an incoming segment.
+    fn set_rx_urgent(&mut self, seq: TcpSeqNumber) {
+        // Ignore retransmissions, and urgent data that has already been passed.
+        if seq < self.remote_seq_no || self.rx_urgent_seq.map_or(false, |mark| seq <= mark) {
+            return;
+        }
+
+        // A new urgent pointer supersedes the previous one, whose octet (if any) is then
+        // left in the stream.
+        net_debug!("urgent data up to seq {}", seq + 1);
+        self.rx_urgent_seq = Some(seq);
+        self.rx_urgent_received = false;
+        self.update_rx_urgent();
+    }
+
+    /// Capture the out-of-band octet once it has been received, and remove it from
+    /// the receive buffer once all the data in front of it has been read.
+    fn update_rx_urgent(&mut self) {
+        let Some(seq) = self.rx_urgent_seq else {
+            return;
+        };
+        let offset = seq - self.remote_seq_no;
+        if offset >= self.rx_buffer.len() {
+            return;
+        }
+
+        if !self.rx_urgent_received {
+            self.rx_urgent_byte = Some(self.rx_buffer.get_allocated(offset, 1)[0]);
+            self.rx_urgent_received = true;
+        }
+        if offset == 0 {
+            tcp_trace!("rx buffer: skipping out-of-band octet");
+            self.rx_buffer.dequeue_allocated(1);
+            self.remote_seq_no += 1;
+            self.rx_urgent_seq = None;
+            self.rx_urgent_received = false;
+        }
+    }
+
+    /// Return the amount of octets in the receive buffer that may be read, i.e. the ones
+    /// in front of the out-of-band octet, if any.
+    fn rx_readable_len(&self) -> usize {
+        match self.rx_urgent_seq {
+            Some(seq) => (seq - self.remote_seq_no).min(self.rx_buffer.len()),
+            None => self.rx_buffer.len(),
+        }
+    }
+
     fn recv_impl<'b, F, R>(&'b mut self, f: F) -> Result<R, RecvError>
     where
-        F: FnOnce(&'b mut SocketBuffer<'a>) -> (usize, R),
+        F: FnOnce(&'b mut SocketBuffer<'a>, usize) -> (usize, R),
     {
+        self.update_rx_urgent();
         self.recv_error_check()?;
 
         let _old_length = self.rx_buffer.len();
-        let (size, result) = f(&mut self.rx_buffer);
+        let readable_len = self.rx_readable_len();
+        let (size, result) = f(&mut self.rx_buffer, readable_len);
         self.remote_seq_no += size;
         if size > 0 {
             #[cfg(any(test, feature = "verbose"))]
@@ -1137,7 +2430,12 @@ impl<'a> Socket<'a> {
     where
         F: FnOnce(&'b mut [u8]) -> (usize, R),
     {
-        self.recv_impl(|rx_buffer| rx_buffer.dequeue_many_with(f))
+        self.recv_impl(|rx_buffer, readable_len| {
+            rx_buffer.dequeue_many_with(|data| {
+                let size = data.len().min(readable_len);
+                f(&mut data[..size])
+            })
+        })
     }
 
     /// Dequeue a sequence of received octets, and fill a slice from it.
@@ -1147,8 +2445,9 @@ impl<'a> Socket<'a> {
     ///
     /// See also [recv](#method.recv).
     pub fn recv_slice(&mut self, data: &mut [u8]) -> Result<usize, RecvError> {
-        self.recv_impl(|rx_buffer| {
-            let size = rx_buffer.dequeue_slice(data);
+        self.recv_impl(|rx_buffer, readable_len| {
+            let size = data.len().min(readable_len);
+            let size = rx_buffer.dequeue_slice(&mut data[..size]);
             (size, size)
         })
     }
@@ -1158,9 +2457,12 @@ impl<'a> Socket<'a> {
     ///
     /// This function otherwise behaves identically to [recv](#method.recv).
     pub fn peek(&mut self, size: usize) -> Result<&[u8], RecvError> {
+        self.update_rx_urgent();
         self.recv_error_check()?;
 
-        let buffer = self.rx_buffer.get_allocated(0, size);
+        let buffer = self
+            .rx_buffer
+            .get_allocated(0, size.min(self.rx_readable_len()));
         if !buffer.is_empty() {
             #[cfg(any(test, feature = "verbose"))]
             tcp_trace!("rx buffer: peeking at {} octets", buffer.len());
@@ -1179,11 +2481,97 @@ impl<'a> Socket<'a> {
         Ok(buffer.len())
     }
 
-    /// Return the amount of octets queued in the transmit buffer.
+    /// Return the received octets as an iterator over contiguous chunks of the receive
+    /// buffer, without removing them from it.
+    ///
+    /// The receive buffer is a ring, so the queued octets are split in at most two chunks
+    /// at the point where it wraps around. This allows parsing data in place across the
+    /// wrap point; once done, the parsed octets may be removed using
+    /// [consume](#method.consume).
+    ///
+    /// This function otherwise behaves identically to [peek](#method.peek).
+    pub fn recv_chunks(&mut self) -> Result<impl Iterator<Item = &[u8]> + '_, RecvError> {
+        self.update_rx_urgent();
+        self.recv_error_check()?;
+
+        let readable_len = self.rx_readable_len();
+        let first = self.rx_buffer.get_allocated(0, readable_len);
+        let second = self
+            .rx_buffer
+            .get_allocated(first.len(), readable_len - first.len());
+        Ok([first, second]
+            .into_iter()
+            .filter(|chunk| !chunk.is_empty()))
+    }
+
+    /// Dequeue up to `size` received octets without copying them anywhere.
+    ///
+    /// This function returns the amount of octets actually dequeued, which is limited
+    /// by the amount of occupied space in the receive buffer; down to zero. It is meant
+    /// to be used together with [recv_chunks](#method.recv_chunks).
+    ///
+    /// This function otherwise behaves identically to [recv](#method.recv).
+    pub fn consume(&mut self, size: usize) -> Result<usize, RecvError> {
+        self.recv_impl(|rx_buffer, readable_len| {
+            let size = size.min(readable_len);
+            rx_buffer.dequeue_allocated(size);
+            (size, size)
+        })
+    }
+
+    /// Move as many received octets as possible into the transmit buffer of `dst`.
+    ///
+    /// The octets are copied directly between the buffers of both sockets, without going
+    /// through the application. This function returns the amount of octets actually moved,
+    /// which is limited by the amount of octets in the receive buffer of this socket and
+    /// by the amount of free space in the transmit buffer of `dst`; down to zero, which
+    /// is also returned if this socket may not receive or `dst` may not send.
+    ///
+    /// Once the remote endpoint of this socket has closed its transmit half and all of its
+    /// data has been moved, the transmit half of `dst` is closed as well, forwarding the end
+    /// of the stream. Resets are not forwarded.
+    pub fn splice_into(&mut self, dst: &mut Socket) -> usize {
+        let mut size = 0;
+        // The received data may wrap around the end of the receive buffer.
+        for _ in 0..2 {
+            match self.recv(|data| match dst.send_slice(data) {
+                Ok(moved) => (moved, moved),
+                Err(_) => (0, 0),
+            }) {
+                Ok(moved) if moved > 0 => size += moved,
+                _ => break,
+            }
+        }
+
+        if self.rx_fin_received && !self.may_recv() && dst.may_send() {
+            tcp_trace!("splice: forwarding end of stream");
+            dst.close();
+        }
+        size
+    }
+
+    /// Dequeue the last out-of-band octet received from the remote endpoint, if any.
+    ///
+    /// The out-of-band octet is available as soon as it has been received, even if there is
+    /// unread data in front of it; [recv](#method.recv) stops at its position in the stream
+    /// until that data has been read. A new out-of-band octet replaces one that has not
+    /// been read yet. See also [set_urgent_inline](#method.set_urgent_inline).
+    pub fn recv_urgent(&mut self) -> Option<u8> {
+        self.rx_urgent_byte.take()
+    }
+
+    /// Return the amount of octets queued in the transmit buffer, including borrowed data.
     ///
     /// Note that the Berkeley sockets interface does not have an equivalent of this API.
     pub fn send_queue(&self) -> usize {
-        self.tx_buffer.len()
+        self.tx_len()
+    }
+
+    /// Return the amount of octets of borrowed data that are not yet acknowledged.
+    ///
+    /// See also the [send_borrowed](#method.send_borrowed) method.
+    pub fn send_borrowed_queue(&self) -> usize {
+        self.tx_borrowed.len()
     }
 
     /// Return the amount of octets queued in the receive buffer. This value can be larger than
@@ -1202,8 +2590,9 @@ impl<'a> Socket<'a> {
 
         self.state = state;
 
+        // A deferred connection has nothing to report yet.
         #[cfg(feature = "async")]
-        {
+        if !(state == State::SynReceived && self.defer_accept) {
             // Wake all tasks waiting. Even if we haven't received/sent data, this
             // is needed because return values of functions may change depending on the state.
             // For example, a pending read has to fail with an error if the socket is closed.
@@ -1224,6 +2613,10 @@ impl<'a> Socket<'a> {
             max_seg_size: None,
             sack_permitted: false,
             sack_ranges: [None, None, None],
+            timestamp: None,
+            md5_signature: None,
+            authentication: None,
+            urgent_pointer: None,
             payload: &[],
         };
         let ip_reply_repr = IpRepr::new(
@@ -1252,8 +2645,14 @@ impl<'a> Socket<'a> {
         (ip_reply_repr, reply_repr)
     }
 
-    fn ack_reply(&mut self, ip_repr: &IpRepr, repr: &TcpRepr) -> (IpRepr, TcpRepr<'static>) {
+    fn ack_reply(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        repr: &TcpRepr,
+    ) -> (IpRepr, TcpRepr<'static>) {
         let (mut ip_reply_repr, mut reply_repr) = Self::reply(ip_repr, repr);
+        reply_repr.timestamp = self.timestamp(cx);
 
         // From RFC 793:
         // [...] an empty acknowledgment segment containing the current send-sequence number
@@ -1322,7 +2721,29 @@ impl<'a> Socket<'a> {
         // Rate-limit to 1 per second max.
         self.challenge_ack_timer = cx.now() + Duration::from_secs(1);
 
-        return Some(self.ack_reply(ip_repr, repr));
+        return Some(self.ack_reply(cx, ip_repr, repr));
+    }
+
+    /// Handle a Packet Too Big message quoting a segment of the connection, once the
+    /// interface lowered the MTU of the path to the remote endpoint.
+    ///
+    /// The segments in flight are sent again right away, split with the smaller MSS of
+    /// the path, instead of waiting for the retransmission timer. The congestion window
+    /// is left alone, since they were not lost to congestion (RFC 8201 § 5.4).
+    ///
+    /// Returns whether there are segments to send again.
+    #[cfg(feature = "proto-ipv6")]
+    pub(crate) fn path_mtu_reduced(&mut self, local: IpEndpoint, remote: IpEndpoint) -> bool {
+        if self.tuple != Some(Tuple { local, remote })
+            || matches!(self.state, State::SynSent | State::SynReceived)
+            || self.remote_last_seq == self.local_seq_no
+        {
+            return false;
+        }
+        net_debug!("path mtu reduced, retransmitting");
+        self.remote_last_seq = self.local_seq_no;
+        self.rtte.on_retransmit();
+        true
     }
 
     pub(crate) fn accepts(&self, _cx: &mut Context, ip_repr: &IpRepr, repr: &TcpRepr) -> bool {
@@ -1345,39 +2766,225 @@ impl<'a> Socket<'a> {
                 && repr.src_port == tuple.remote.port
         } else {
             // We're listening, reject packets not matching the listen endpoint.
-            let addr_ok = match self.listen_endpoint.addr {
-                Some(addr) => ip_repr.dst_addr() == addr,
-                None => true,
-            };
-            addr_ok && repr.dst_port != 0 && repr.dst_port == self.listen_endpoint.port
+            self.listens_on(ip_repr, repr)
         }
     }
 
-    pub(crate) fn process(
-        &mut self,
+    fn listens_on(&self, ip_repr: &IpRepr, repr: &TcpRepr) -> bool {
+        let addr_ok = match self.listen_endpoint.addr {
+            Some(addr) => ip_repr.dst_addr() == addr,
+            None => true,
+        };
+        addr_ok && repr.dst_port != 0 && repr.dst_port == self.listen_endpoint.port
+    }
+
+    /// Whether SYN cookies may be used for a segment to the listen endpoint of this socket.
+    fn may_use_syn_cookie(&self, ip_repr: &IpRepr, repr: &TcpRepr) -> bool {
+        #[cfg(feature = "socket-tcp-md5")]
+        if self.md5_key.is_some() {
+            return false;
+        }
+        #[cfg(feature = "socket-tcp-ao")]
+        if self.ao.is_enabled() {
+            return false;
+        }
+        self.syn_cookies && self.listens_on(ip_repr, repr)
+    }
+
+    /// Answer a SYN to the listen endpoint of this socket with a SYN cookie, if this socket
+    /// is busy with another connection and has SYN cookies enabled.
+    pub(crate) fn syn_cookie_reply(
+        &self,
         cx: &mut Context,
         ip_repr: &IpRepr,
         repr: &TcpRepr,
     ) -> Option<(IpRepr, TcpRepr<'static>)> {
-        debug_assert!(self.accepts(cx, ip_repr, repr));
+        if repr.control != TcpControl::Syn
+            || repr.ack_number.is_some()
+            || repr.max_seg_size == Some(0)
+            || self.state == State::Listen
+            || !self.may_use_syn_cookie(ip_repr, repr)
+        {
+            return None;
+        }
 
-        // Consider how much the sequence number space differs from the transmit buffer space.
-        let (sent_syn, sent_fin) = match self.state {
-            // In SYN-SENT or SYN-RECEIVED, we've just sent a SYN.
-            State::SynSent | State::SynReceived => (true, false),
-            // In FIN-WAIT-1, LAST-ACK, or CLOSING, we've just sent a FIN.
-            State::FinWait1 | State::LastAck | State::Closing => (false, true),
-            // In all other states we've already got acknowledgements for
-            // all of the control flags we sent.
-            _ => (false, false),
+        let tuple = Tuple {
+            local: IpEndpoint::new(ip_repr.dst_addr(), repr.dst_port),
+            remote: IpEndpoint::new(ip_repr.src_addr(), repr.src_port),
         };
-        let control_len = (sent_syn as usize) + (sent_fin as usize);
+        let (mut ip_reply_repr, mut reply_repr) = Self::reply(ip_repr, repr);
+        reply_repr.control = TcpControl::Syn;
+        reply_repr.seq_number = cookie::generate(
+            cx.tcp_secret(),
+            cx.now(),
+            tuple.local,
+            tuple.remote,
+            repr.seq_number,
+            repr.max_seg_size.unwrap_or(DEFAULT_MSS as u16),
+        );
+        reply_repr.ack_number = Some(repr.seq_number + 1);
+        reply_repr.window_len = self.rx_buffer.capacity().min((1 << 16) - 1) as u16;
+        reply_repr.max_seg_size = Some(Self::path_mss(cx, &tuple) as u16);
+        ip_reply_repr.set_payload_len(reply_repr.buffer_len());
 
-        // Reject unacceptable acknowledgements.
-        match (self.state, repr.control, repr.ack_number) {
-            // An RST received in response to initial SYN is acceptable if it acknowledges
-            // the initial SYN.
-            (State::SynSent, TcpControl::Rst, None) => {
+        net_debug!("sending SYN cookie to {}", tuple.remote);
+        Some((ip_reply_repr, reply_repr))
+    }
+
+    /// Reconstruct a connection from the SYN cookie acknowledged by the final ACK of
+    /// a handshake, if this socket is listening and has SYN cookies enabled.
+    ///
+    /// If this function returns true, the socket is in the `SYN-RECEIVED` state, and
+    /// accepts the segment.
+    pub(crate) fn accept_syn_cookie(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        repr: &TcpRepr,
+    ) -> bool {
+        let Some(ack_number) = repr.ack_number else {
+            return false;
+        };
+        if matches!(repr.control, TcpControl::Syn | TcpControl::Rst)
+            || self.state != State::Listen
+            || !self.may_use_syn_cookie(ip_repr, repr)
+        {
+            return false;
+        }
+
+        let tuple = Tuple {
+            local: IpEndpoint::new(ip_repr.dst_addr(), repr.dst_port),
+            remote: IpEndpoint::new(ip_repr.src_addr(), repr.src_port),
+        };
+        let Some(remote_mss) = cookie::validate(
+            cx.tcp_secret(),
+            cx.now(),
+            tuple.local,
+            tuple.remote,
+            repr.seq_number - 1,
+            ack_number - 1,
+        ) else {
+            return false;
+        };
+
+        net_debug!("accepting SYN cookie from {}", tuple.remote);
+        self.tuple = Some(tuple);
+        self.local_seq_no = ack_number - 1;
+        self.remote_seq_no = repr.seq_number;
+        self.remote_last_seq = ack_number;
+        self.remote_mss = remote_mss as usize;
+        self.remote_has_sack = false;
+        self.remote_tsval = None;
+        self.remote_win_scale = None;
+        self.remote_win_shift = 0;
+        self.seed_rtt(cx);
+        self.set_state(State::SynReceived);
+        self.timer.set_for_idle(cx.now(), self.keep_alive);
+        true
+    }
+
+    /// Check the TCP-AO MAC or the MD5 signature of a segment accepted by this socket,
+    /// if a key is set.
+    #[cfg(any(feature = "socket-tcp-md5", feature = "socket-tcp-ao"))]
+    pub(crate) fn authenticate(
+        &mut self,
+        ip_repr: &IpRepr,
+        packet: &TcpPacket<&[u8]>,
+        repr: &TcpRepr,
+    ) -> bool {
+        #[cfg(feature = "socket-tcp-ao")]
+        if self.ao.is_enabled() {
+            return self.ao.verify(ip_repr, packet, repr);
+        }
+        #[cfg(feature = "socket-tcp-md5")]
+        if let Some(key) = &self.md5_key {
+            return md5::verify(key, ip_repr, packet, repr);
+        }
+        true
+    }
+
+    pub(crate) fn process(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        repr: &TcpRepr,
+    ) -> Option<(IpRepr, TcpRepr<'static>)> {
+        let reply = self.process_segment(cx, ip_repr, repr);
+        #[cfg(any(feature = "socket-tcp-md5", feature = "socket-tcp-ao"))]
+        let reply = reply.map(|(mut ip_reply_repr, mut reply_repr)| {
+            #[cfg(feature = "socket-tcp-ao")]
+            self.ao.sign(&mut ip_reply_repr, &mut reply_repr);
+            #[cfg(feature = "socket-tcp-md5")]
+            if let (Some(key), None) = (&self.md5_key, reply_repr.authentication) {
+                md5::sign(key, &mut ip_reply_repr, &mut reply_repr);
+            }
+            (ip_reply_repr, reply_repr)
+        });
+        reply
+    }
+
+    fn process_segment(
+        &mut self,
+        cx: &mut Context,
+        ip_repr: &IpRepr,
+        repr: &TcpRepr,
+    ) -> Option<(IpRepr, TcpRepr<'static>)> {
+        debug_assert!(self.accepts(cx, ip_repr, repr));
+
+        // RFC 1122 § 4.2.2.13: a connection in TIME-WAIT may be reopened by a new SYN,
+        // as long as its sequence number is greater than the last one of the previous
+        // incarnation of the connection. RFC 6191: if both incarnations use timestamps,
+        // the timestamp of the SYN has to be newer instead. Only do this if we were
+        // listening for it.
+        let newer_syn = match (self.remote_tsval, repr.timestamp) {
+            (Some(tsval), Some(timestamp)) => timestamp.tsval.wrapping_sub(tsval) as i32 > 0,
+            _ => repr.seq_number >= self.remote_seq_no,
+        };
+        if self.state == State::TimeWait
+            && repr.control == TcpControl::Syn
+            && repr.ack_number.is_none()
+            && self.listen_endpoint.port != 0
+            && self.rx_buffer.is_empty()
+            && newer_syn
+        {
+            tcp_trace!("reopening connection in TIME-WAIT");
+            let listen_endpoint = self.listen_endpoint;
+            self.reset();
+            self.listen_endpoint = listen_endpoint;
+            self.set_state(State::Listen);
+        }
+
+        // Consider how much the sequence number space differs from the transmit buffer space.
+        let (sent_syn, sent_fin) = match self.state {
+            // In SYN-SENT or SYN-RECEIVED, we've just sent a SYN.
+            State::SynSent | State::SynReceived => (true, false),
+            // In FIN-WAIT-1, LAST-ACK, or CLOSING, we've just sent a FIN.
+            State::FinWait1 | State::LastAck | State::Closing => (false, true),
+            // In all other states we've already got acknowledgements for
+            // all of the control flags we sent.
+            _ => (false, false),
+        };
+        let control_len = (sent_syn as usize) + (sent_fin as usize);
+
+        // RFC 5961 § 4.2: a SYN received in a synchronized state, regardless of its sequence
+        // number, may be an attempt to blindly reset the connection. Instead of acting on it,
+        // send a challenge ACK; if the remote has really restarted, it will answer with a
+        // RST carrying the exact sequence number we expect.
+        if repr.control == TcpControl::Syn
+            && !matches!(
+                self.state,
+                State::Listen | State::SynSent | State::SynReceived
+            )
+        {
+            net_debug!("received SYN in a synchronized state, will send challenge ACK");
+            return self.challenge_ack_reply(cx, ip_repr, repr);
+        }
+
+        // Reject unacceptable acknowledgements.
+        match (self.state, repr.control, repr.ack_number) {
+            // An RST received in response to initial SYN is acceptable if it acknowledges
+            // the initial SYN.
+            (State::SynSent, TcpControl::Rst, None) => {
                 net_debug!("unacceptable RST (expecting RST|ACK) in response to initial SYN");
                 return None;
             }
@@ -1393,6 +3000,8 @@ impl<'a> Socket<'a> {
             (State::Listen, _, None) => (),
             // This case is handled in `accepts()`.
             (State::Listen, _, Some(_)) => unreachable!(),
+            // A SYN without an acknowledgement in the SYN-SENT state is a simultaneous open.
+            (State::SynSent, TcpControl::Syn, None) => (),
             // Every packet after the initial SYN must be an acknowledgement.
             (_, _, None) => {
                 net_debug!("expecting an ACK");
@@ -1437,7 +3046,7 @@ impl<'a> Socket<'a> {
             }
             // Every acknowledgement must be for transmitted but unacknowledged data.
             (_, _, Some(ack_number)) => {
-                let unacknowledged = self.tx_buffer.len() + control_len;
+                let unacknowledged = self.tx_len() + control_len;
 
                 // Acceptable ACK range (both inclusive)
                 let mut ack_min = self.local_seq_no;
@@ -1478,6 +3087,25 @@ impl<'a> Socket<'a> {
         let (payload, payload_offset) = match self.state {
             // In LISTEN and SYN-SENT states, we have not yet synchronized with the remote end.
             State::Listen | State::SynSent => (&[][..], 0),
+            // In a simultaneous open, the SYN|ACK of the remote end repeats the SYN that moved
+            // us to the SYN-RECEIVED state; its acknowledgement completes the handshake.
+            State::SynReceived
+                if repr.control == TcpControl::Syn && segment_end == window_start =>
+            {
+                (&[][..], 0)
+            }
+            // RFC 5961 § 3.2: only a RST with exactly the next expected sequence number
+            // resets the connection. A RST elsewhere in the window elicits a challenge ACK,
+            // and one outside of the window is silently dropped.
+            _ if repr.control == TcpControl::Rst && segment_start != window_start => {
+                if window_start < segment_start && segment_start < window_end {
+                    net_debug!("RST not at the start of the window, will send challenge ACK");
+                    return self.challenge_ack_reply(cx, ip_repr, repr);
+                } else {
+                    net_debug!("RST not in receive window, dropping");
+                    return None;
+                }
+            }
             _ => {
                 // https://www.rfc-editor.org/rfc/rfc9293.html#name-segment-acceptability-tests
                 let segment_in_window = match (
@@ -1550,7 +3178,7 @@ impl<'a> Socket<'a> {
                     // If we're in the TIME-WAIT state, restart the TIME-WAIT timeout, since
                     // the remote end may not have realized we've closed the connection.
                     if self.state == State::TimeWait {
-                        self.timer.set_for_close(cx.now());
+                        self.timer.set_for_close(cx.now(), self.time_wait);
                     }
 
                     return self.challenge_ack_reply(cx, ip_repr, repr);
@@ -1575,7 +3203,7 @@ impl<'a> Socket<'a> {
 
                     // We could've sent data before the FIN, so only remove FIN from the sequence
                     // space if all of that data is acknowledged.
-                    if sent_fin && self.tx_buffer.len() + 1 == ack_len {
+                    if sent_fin && self.tx_len() + 1 == ack_len {
                         ack_len -= 1;
                         tcp_trace!("received ACK of FIN");
                         ack_of_fin = true;
@@ -1584,7 +3212,13 @@ impl<'a> Socket<'a> {
                     ack_all = self.remote_last_seq == ack_number
                 }
 
-                self.rtte.on_ack(cx.now(), ack_number);
+                // Later connections to the same destination start from this estimate.
+                if self.rtte.on_ack(cx.now(), ack_number) {
+                    if let Some(tuple) = self.tuple {
+                        let rtt = Duration::from_millis(self.rtte.rtt as u64);
+                        cx.set_rtt_hint(&tuple.remote.addr, rtt);
+                    }
+                }
             }
         }
 
@@ -1611,9 +3245,17 @@ impl<'a> Socket<'a> {
                 return None;
             }
 
+            // RSTs in TIME-WAIT are ignored, to avoid the hazards described in RFC 1337
+            // (TIME-WAIT assassination).
+            (State::TimeWait, TcpControl::Rst) => {
+                net_debug!("ignoring RST in TIME-WAIT");
+                return None;
+            }
+
             // RSTs in any other state close the socket.
             (_, TcpControl::Rst) => {
                 tcp_trace!("received RST");
+                self.close_reason = Some(CloseReason::Reset);
                 self.set_state(State::Closed);
                 self.tuple = None;
                 return None;
@@ -1634,22 +3276,44 @@ impl<'a> Socket<'a> {
                     local: IpEndpoint::new(ip_repr.dst_addr(), repr.dst_port),
                     remote: IpEndpoint::new(ip_repr.src_addr(), repr.src_port),
                 });
-                self.local_seq_no = Self::random_seq_no(cx);
+                self.local_seq_no = Self::initial_seq_no(cx, &self.tuple.unwrap());
                 self.remote_seq_no = repr.seq_number + 1;
                 self.remote_last_seq = self.local_seq_no;
+                #[cfg(feature = "socket-tcp-ao")]
+                {
+                    self.ao.set_local_isn(self.local_seq_no);
+                    self.ao.set_remote_isn(repr.seq_number);
+                }
                 self.remote_has_sack = repr.sack_permitted;
+                self.remote_tsval = repr
+                    .timestamp
+                    .filter(|_| self.timestamps)
+                    .map(|timestamp| timestamp.tsval);
                 self.remote_win_scale = repr.window_scale;
                 // Remote doesn't support window scaling, don't do it.
                 if self.remote_win_scale.is_none() {
                     self.remote_win_shift = 0;
                 }
+                self.seed_rtt(cx);
                 self.set_state(State::SynReceived);
                 self.timer.set_for_idle(cx.now(), self.keep_alive);
             }
 
-            // ACK packets in the SYN-RECEIVED state change it to ESTABLISHED.
-            (State::SynReceived, TcpControl::None) => {
+            // When accepting is deferred, the ACK completing the handshake is ignored, and
+            // the SYN|ACK will be retransmitted until data is received.
+            (State::SynReceived, TcpControl::None)
+                if self.defer_accept && repr.payload.is_empty() =>
+            {
+                tcp_trace!("deferring accept until data is received");
+                return None;
+            }
+
+            // ACK packets in the SYN-RECEIVED state change it to ESTABLISHED, and so do
+            // SYN|ACK packets in a simultaneous open.
+            (State::SynReceived, TcpControl::None | TcpControl::Syn) => {
                 self.set_state(State::Established);
+                self.established_at = Some(cx.now());
+                self.reset_congestion_window();
                 self.timer.set_for_idle(cx.now(), self.keep_alive);
             }
 
@@ -1663,6 +3327,36 @@ impl<'a> Socket<'a> {
                 self.timer.set_for_idle(cx.now(), self.keep_alive);
             }
 
+            // SYN packets in the SYN-SENT state change it to SYN-RECEIVED (RFC 9293 § 3.5,
+            // simultaneous open). Our SYN is sent again, this time with an acknowledgement.
+            (State::SynSent, TcpControl::Syn) if repr.ack_number.is_none() => {
+                tcp_trace!("received SYN, simultaneous open");
+                if let Some(max_seg_size) = repr.max_seg_size {
+                    if max_seg_size == 0 {
+                        tcp_trace!("received SYN with zero MSS, ignoring");
+                        return None;
+                    }
+                    self.remote_mss = max_seg_size as usize;
+                }
+
+                self.remote_seq_no = repr.seq_number + 1;
+                self.remote_last_seq = self.local_seq_no;
+                #[cfg(feature = "socket-tcp-ao")]
+                self.ao.set_remote_isn(repr.seq_number);
+                self.remote_has_sack = repr.sack_permitted;
+                self.remote_tsval = repr
+                    .timestamp
+                    .filter(|_| self.timestamps)
+                    .map(|timestamp| timestamp.tsval);
+                self.remote_win_scale = repr.window_scale;
+                // Remote doesn't support window scaling, don't do it.
+                if self.remote_win_scale.is_none() {
+                    self.remote_win_shift = 0;
+                }
+                self.set_state(State::SynReceived);
+                self.timer.set_for_idle(cx.now(), self.keep_alive);
+            }
+
             // SYN|ACK packets in the SYN-SENT state change it to ESTABLISHED.
             (State::SynSent, TcpControl::Syn) => {
                 tcp_trace!("received SYN|ACK");
@@ -1677,6 +3371,12 @@ impl<'a> Socket<'a> {
                 self.remote_seq_no = repr.seq_number + 1;
                 self.remote_last_seq = self.local_seq_no + 1;
                 self.remote_last_ack = Some(repr.seq_number);
+                #[cfg(feature = "socket-tcp-ao")]
+                self.ao.set_remote_isn(repr.seq_number);
+                self.remote_tsval = repr
+                    .timestamp
+                    .filter(|_| self.timestamps)
+                    .map(|timestamp| timestamp.tsval);
                 self.remote_win_scale = repr.window_scale;
                 // Remote doesn't support window scaling, don't do it.
                 if self.remote_win_scale.is_none() {
@@ -1684,6 +3384,8 @@ impl<'a> Socket<'a> {
                 }
 
                 self.set_state(State::Established);
+                self.established_at = Some(cx.now());
+                self.reset_congestion_window();
                 self.timer.set_for_idle(cx.now(), self.keep_alive);
             }
 
@@ -1720,8 +3422,9 @@ impl<'a> Socket<'a> {
                 self.remote_seq_no += 1;
                 self.rx_fin_received = true;
                 if ack_of_fin {
+                    self.close_reason = Some(CloseReason::Graceful);
                     self.set_state(State::TimeWait);
-                    self.timer.set_for_close(cx.now());
+                    self.timer.set_for_close(cx.now(), self.time_wait);
                 } else {
                     self.set_state(State::Closing);
                     self.timer.set_for_idle(cx.now(), self.keep_alive);
@@ -1737,15 +3440,17 @@ impl<'a> Socket<'a> {
             (State::FinWait2, TcpControl::Fin) => {
                 self.remote_seq_no += 1;
                 self.rx_fin_received = true;
+                self.close_reason = Some(CloseReason::Graceful);
                 self.set_state(State::TimeWait);
-                self.timer.set_for_close(cx.now());
+                self.timer.set_for_close(cx.now(), self.time_wait);
             }
 
             // ACK packets in CLOSING state change it to TIME-WAIT.
             (State::Closing, TcpControl::None) => {
                 if ack_of_fin {
+                    self.close_reason = Some(CloseReason::Graceful);
                     self.set_state(State::TimeWait);
-                    self.timer.set_for_close(cx.now());
+                    self.timer.set_for_close(cx.now(), self.time_wait);
                 } else {
                     self.timer.set_for_idle(cx.now(), self.keep_alive);
                 }
@@ -1760,6 +3465,7 @@ impl<'a> Socket<'a> {
             (State::LastAck, TcpControl::None) => {
                 if ack_of_fin {
                     // Clear the remote endpoint, or we'll send an RST there.
+                    self.close_reason = Some(CloseReason::Graceful);
                     self.set_state(State::Closed);
                     self.tuple = None;
                 } else {
@@ -1775,6 +3481,14 @@ impl<'a> Socket<'a> {
 
         // Update remote state.
         self.remote_last_ts = Some(cx.now());
+        // RFC 7323 § 4.3: keep the most recent timestamp, to be echoed in our segments.
+        if let (Some(tsval), Some(timestamp)) = (self.remote_tsval, repr.timestamp) {
+            if timestamp.tsval.wrapping_sub(tsval) as i32 > 0 {
+                self.remote_tsval = Some(timestamp.tsval);
+            }
+        }
+        self.last_rx_at = Some(cx.now());
+        self.keep_alive_unanswered = 0;
 
         // RFC 1323: The window field (SEG.WND) in the header of every incoming segment, with the
         // exception of SYN segments, is left-shifted by Snd.Wind.Scale bits before updating SND.WND.
@@ -1788,13 +3502,14 @@ impl<'a> Socket<'a> {
 
         if ack_len > 0 {
             // Dequeue acknowledged octets.
-            debug_assert!(self.tx_buffer.len() >= ack_len);
+            debug_assert!(self.tx_len() >= ack_len);
             tcp_trace!(
                 "tx buffer: dequeueing {} octets (now {})",
                 ack_len,
-                self.tx_buffer.len() - ack_len
+                self.tx_len() - ack_len
             );
-            self.tx_buffer.dequeue_allocated(ack_len);
+            self.tx_dequeue(ack_len);
+            self.grow_congestion_window(ack_len);
 
             // There's new room available in tx_buffer, wake the waiting task if any.
             #[cfg(feature = "async")]
@@ -1802,6 +3517,23 @@ impl<'a> Socket<'a> {
         }
 
         if let Some(ack_number) = repr.ack_number {
+            // RFC 2883: a first SACK block below the cumulative ACK, or within the second
+            // block, reports data that the remote endpoint received twice.
+            let dsack = repr.sack_ranges[0]
+                .filter(|_| self.remote_has_sack)
+                .map(|(left, right)| (TcpSeqNumber(left as i32), TcpSeqNumber(right as i32)))
+                .filter(|&(left, right)| {
+                    left < right
+                        && (right <= ack_number
+                            || repr.sack_ranges[1].map_or(false, |(outer_left, outer_right)| {
+                                left >= TcpSeqNumber(outer_left as i32)
+                                    && right <= TcpSeqNumber(outer_right as i32)
+                            }))
+                });
+            if let Some((left, right)) = dsack {
+                self.process_dsack(left, right);
+            }
+
             // TODO: When flow control is implemented,
             // refractor the following block within that implementation
 
@@ -1812,12 +3544,14 @@ impl<'a> Socket<'a> {
             match self.local_rx_last_ack {
                 // Duplicate ACK if payload empty and ACK doesn't move send window ->
                 // Increment duplicate ACK count and set for retransmit if we just received
-                // the third duplicate ACK
+                // the third duplicate ACK. An ACK carrying a DSACK block reports a duplicate
+                // segment rather than a missing one, and is not counted.
                 Some(last_rx_ack)
                     if repr.payload.is_empty()
                         && last_rx_ack == ack_number
                         && ack_number < self.remote_last_seq
-                        && !is_window_update =>
+                        && !is_window_update
+                        && dsack.is_none() =>
                 {
                     // Increment duplicate ACK count
                     self.local_rx_dup_acks = self.local_rx_dup_acks.saturating_add(1);
@@ -1836,6 +3570,8 @@ impl<'a> Socket<'a> {
                     if self.local_rx_dup_acks == 3 {
                         self.timer.set_for_fast_retransmit();
                         net_debug!("started fast retransmit");
+                        // RFC 5681 § 3.2: halve the congestion window.
+                        self.shrink_congestion_window(false);
                     }
                 }
                 // No duplicate ACK -> Reset state and update last received ACK
@@ -1847,9 +3583,25 @@ impl<'a> Socket<'a> {
                     self.local_rx_last_ack = Some(ack_number);
                 }
             };
+            // New data (or a control flag) was acknowledged, so the connection is making
+            // progress; restart the retransmission count and user timeout.
+            if ack_number > self.local_seq_no {
+                self.retransmissions = 0;
+                self.unacked_since = if ack_number >= self.remote_last_seq {
+                    None
+                } else {
+                    Some(cx.now())
+                };
+            }
             // We've processed everything in the incoming segment, so advance the local
             // sequence number past it.
             self.local_seq_no = ack_number;
+            if self.tx_urgent_seq.map_or(false, |seq| ack_number >= seq) {
+                self.tx_urgent_seq = None;
+            }
+            if self.tx_push_seq.map_or(false, |seq| ack_number >= seq) {
+                self.tx_push_seq = None;
+            }
             // During retransmission, if an earlier segment got lost but later was
             // successfully received, self.local_seq_no can move past self.remote_last_seq.
             // Do not attempt to retransmit the latter segments; not only this is pointless
@@ -1860,21 +3612,45 @@ impl<'a> Socket<'a> {
             }
         }
 
+        // RFC 6093: the urgent pointer points to the octet following the urgent data.
+        // The urgent pointer of a SYN segment would be relative to the SYN itself.
+        match repr.urgent_pointer {
+            Some(urgent_pointer)
+                if urgent_pointer > 0
+                    && repr.control != TcpControl::Syn
+                    && !self.urgent_inline
+                    && !self.rx_shutdown
+                    && !matches!(self.state, State::Listen | State::SynSent) =>
+            {
+                self.set_rx_urgent(repr.seq_number + urgent_pointer as usize - 1)
+            }
+            _ => (),
+        }
+
         let payload_len = payload.len();
         if payload_len == 0 {
             return None;
         }
 
         let assembler_was_empty = self.assembler.is_empty();
+        if payload_offset != 0 {
+            self.out_of_order_segments = self.out_of_order_segments.saturating_add(1);
+        }
 
         // Try adding payload octets to the assembler.
-        let Ok(contig_len) = self.assembler.add_then_remove_front(payload_offset, payload_len) else {
+        let Ok(contig_len) = self
+            .assembler
+            .add_then_remove_front(payload_offset, payload_len)
+        else {
             net_debug!(
                 "assembler: too many holes to add {} octets at offset {}",
                 payload_len,
                 payload_offset
             );
-            return None;
+            self.reassembly_drops = self.reassembly_drops.saturating_add(1);
+            // Still send a duplicate ACK, so that the remote endpoint can detect the loss
+            // that caused the holes and retransmit the missing data quickly.
+            return Some(self.ack_reply(cx, ip_repr, repr));
         };
 
         // Place payload octets into the buffer.
@@ -1895,9 +3671,17 @@ impl<'a> Socket<'a> {
             );
             self.rx_buffer.enqueue_unallocated(contig_len);
 
-            // There's new data in rx_buffer, notify waiting task if any.
-            #[cfg(feature = "async")]
-            self.rx_waker.wake();
+            if self.rx_shutdown {
+                // Nobody is going to read this data, but it has to be acknowledged
+                // all the same.
+                self.discard_rx_buffer();
+            } else {
+                self.update_rx_urgent();
+                self.tune_recv_window(cx.now());
+                // There's new data in rx_buffer, notify waiting task if any.
+                #[cfg(feature = "async")]
+                self.rx_waker.wake();
+            }
         }
 
         if !self.assembler.is_empty() {
@@ -1937,29 +3721,228 @@ impl<'a> Socket<'a> {
             // This is fine because smoltcp assumes that it can always transmit zero or one
             // packets for every packet it receives.
             tcp_trace!("ACKing incoming segment");
-            Some(self.ack_reply(ip_repr, repr))
+            Some(self.ack_reply(cx, ip_repr, repr))
         } else {
             None
         }
     }
 
     fn timed_out(&self, timestamp: Instant) -> bool {
-        match (self.remote_last_ts, self.timeout) {
+        let timed_out = match (self.remote_last_ts, self.timeout) {
             (Some(remote_last_ts), Some(timeout)) => timestamp >= remote_last_ts + timeout,
             (_, _) => false,
+        };
+        let user_timed_out = match (self.unacked_since, self.user_timeout) {
+            (Some(unacked_since), Some(user_timeout)) => timestamp >= unacked_since + user_timeout,
+            (_, _) => false,
+        };
+        timed_out || user_timed_out
+    }
+
+    fn linger_deadline(&self) -> Option<Instant> {
+        match (self.state, self.linger_since, self.linger) {
+            // Only states where our FIN is unacknowledged linger.
+            (State::FinWait1 | State::Closing | State::LastAck, Some(since), Some(linger)) => {
+                Some(since + linger)
+            }
+            _ => None,
         }
     }
 
-    fn seq_to_transmit(&self, cx: &mut Context) -> bool {
-        let ip_header_len = match self.tuple.unwrap().local.addr {
+    fn linger_expired(&self, timestamp: Instant) -> bool {
+        match self.linger_deadline() {
+            Some(deadline) => timestamp >= deadline,
+            None => false,
+        }
+    }
+
+    fn retransmissions_exceeded(&self) -> bool {
+        match self.max_retransmissions {
+            Some(max_retransmissions) => self.retransmissions >= max_retransmissions,
+            None => false,
+        }
+    }
+
+    /// Return the largest segment the path to the remote endpoint can carry, not counting
+    /// options, due to MTU limitations or to the MSS configured on the route to it.
+    fn local_mss(&self, cx: &mut Context) -> usize {
+        Self::path_mss(cx, &self.tuple.unwrap())
+    }
+
+    fn path_mss(cx: &mut Context, tuple: &Tuple) -> usize {
+        let ip_header_len = match tuple.local.addr {
             #[cfg(feature = "proto-ipv4")]
             IpAddress::Ipv4(_) => crate::wire::IPV4_HEADER_LEN,
             #[cfg(feature = "proto-ipv6")]
             IpAddress::Ipv6(_) => crate::wire::IPV6_HEADER_LEN,
         };
+        let mss = cx.path_mtu(&tuple.remote.addr) - ip_header_len - TCP_HEADER_LEN;
+
+        match cx.route_mss(&tuple.remote.addr) {
+            Some(route_mss) => mss.min(route_mss as usize),
+            None => mss,
+        }
+    }
+
+    /// Return the amount of sequence space we may send past `local_seq_no`.
+    fn send_window(&self) -> usize {
+        self.remote_win_len.min(self.cwnd)
+    }
+
+    fn reset_congestion_window(&mut self) {
+        if let Some(initial_window) = self.initial_window {
+            self.cwnd = initial_window.max(1).saturating_mul(self.remote_mss);
+            self.ssthresh = usize::MAX;
+        }
+    }
+
+    fn grow_congestion_window(&mut self, acked: usize) {
+        if self.initial_window.is_none() {
+            return;
+        }
+
+        let increase = if self.cwnd < self.ssthresh {
+            // Slow start.
+            acked.min(self.remote_mss)
+        } else {
+            // Congestion avoidance, about one segment per round-trip time.
+            (self.remote_mss * self.remote_mss / self.cwnd).max(1)
+        };
+        self.cwnd = self.cwnd.saturating_add(increase);
+    }
+
+    fn shrink_congestion_window(&mut self, timed_out: bool) {
+        // Several reductions without progress belong to the same retransmission episode.
+        if self
+            .cwnd_undo
+            .map_or(true, |undo| undo.start != self.local_seq_no)
+        {
+            self.cwnd_undo = Some(CwndUndo {
+                cwnd: self.cwnd,
+                ssthresh: self.ssthresh,
+                start: self.local_seq_no,
+                end: self.remote_last_seq,
+                retransmitted: 0,
+                duplicated: 0,
+            });
+        }
+
+        if self.initial_window.is_none() {
+            return;
+        }
+
+        let in_flight = self.remote_last_seq - self.local_seq_no;
+        self.ssthresh = (in_flight / 2).max(2 * self.remote_mss);
+        self.cwnd = if timed_out {
+            self.remote_mss
+        } else {
+            self.ssthresh
+        };
+        tcp_trace!(
+            "congestion window: {}, threshold {}",
+            self.cwnd,
+            self.ssthresh
+        );
+    }
+
+    /// Account for a DSACK block, and undo the reduction of the congestion window if every
+    /// octet retransmitted since was received twice.
+    fn process_dsack(&mut self, left: TcpSeqNumber, right: TcpSeqNumber) {
+        self.duplicate_sacks = self.duplicate_sacks.saturating_add(1);
+
+        let Some(undo) = self.cwnd_undo.as_mut() else {
+            return;
+        };
+        let left = left.max(undo.start);
+        let right = right.min(undo.end);
+        if left < right {
+            undo.duplicated += right - left;
+        }
+        if undo.retransmitted == 0 || undo.duplicated < undo.retransmitted {
+            return;
+        }
+
+        let undo = self.cwnd_undo.take().unwrap();
+        net_debug!("retransmissions from seq {} were spurious", undo.start);
+        self.spurious_retransmissions = self.spurious_retransmissions.saturating_add(1);
+        if self.initial_window.is_some() {
+            self.cwnd = cmp::max(self.cwnd, undo.cwnd);
+            self.ssthresh = cmp::max(self.ssthresh, undo.ssthresh);
+            tcp_trace!(
+                "congestion window: {}, threshold {}",
+                self.cwnd,
+                self.ssthresh
+            );
+        }
+    }
+
+    /// Return whether pacing prevents sending new data at `timestamp`.
+    fn pacing_delayed(&self, timestamp: Instant) -> bool {
+        match self.pacing_next_at {
+            Some(pacing_next_at) => timestamp < pacing_next_at,
+            None => false,
+        }
+    }
+
+    /// Return when the segment following one of `size` octets sent at `timestamp` is due,
+    /// if pacing is enabled.
+    fn next_paced_at(&self, timestamp: Instant, size: usize) -> Option<Instant> {
+        if !self.pacing {
+            return None;
+        }
+
+        let (window, factor) = match self.initial_window {
+            Some(_) if self.cwnd < self.ssthresh => (self.cwnd, 2),
+            Some(_) => (self.cwnd, 1),
+            None => (self.remote_win_len, 1),
+        };
+        if window == 0 {
+            return None;
+        }
+
+        // Send one window every round-trip time (or two, during slow start).
+        let rtt = self.rtte.rtt as u64 * 1000;
+        let interval = rtt * size as u64 / (window as u64 * factor);
+        Some(timestamp + Duration::from_micros(interval))
+    }
+
+    fn keep_alive_probes_exceeded(&self) -> bool {
+        match self.keep_alive_probes {
+            Some(keep_alive_probes) => self.keep_alive_unanswered >= keep_alive_probes,
+            None => false,
+        }
+    }
+
+    /// Return the length of the options added to every outgoing segment, which reduce
+    /// the space available for data.
+    fn options_len(&self) -> usize {
+        // The Timestamps option, padded to a multiple of 4 octets.
+        let timestamp_len = if self.remote_tsval.is_some() { 12 } else { 0 };
 
-        // Max segment size we're able to send due to MTU limitations.
-        let local_mss = cx.ip_mtu() - ip_header_len - TCP_HEADER_LEN;
+        #[cfg(feature = "socket-tcp-ao")]
+        if self.ao.is_enabled() {
+            return ao::OPTION_LEN + timestamp_len;
+        }
+        #[cfg(feature = "socket-tcp-md5")]
+        if self.md5_key.is_some() {
+            // The MD5 signature option, padded to a multiple of 4 octets.
+            return 20 + timestamp_len;
+        }
+
+        timestamp_len
+    }
+
+    /// Return the Timestamps option of the segments of the connection, if it uses them.
+    fn timestamp(&self, cx: &mut Context) -> Option<TcpTimestampOption> {
+        self.remote_tsval.map(|tsecr| TcpTimestampOption {
+            tsval: cx.now().total_millis() as u32,
+            tsecr,
+        })
+    }
+
+    fn seq_to_transmit(&self, cx: &mut Context) -> bool {
+        // Max segment size we're able to send due to MTU or route limitations.
+        let local_mss = self.local_mss(cx) - self.options_len();
 
         // The effective max segment size, taking into account our and remote's limits.
         let effective_mss = local_mss.min(self.remote_mss);
@@ -1973,8 +3956,7 @@ impl<'a> Socket<'a> {
         }
 
         // max sequence number we can send.
-        let max_send_seq =
-            self.local_seq_no + core::cmp::min(self.remote_win_len, self.tx_buffer.len());
+        let max_send_seq = self.local_seq_no + core::cmp::min(self.send_window(), self.tx_len());
 
         // Max amount of octets we can send.
         let max_send = if max_send_seq >= self.remote_last_seq {
@@ -1996,6 +3978,11 @@ impl<'a> Socket<'a> {
             _ => false,
         };
 
+        // If we're pacing transmissions, wait until the next segment is due.
+        if self.pacing_delayed(cx.now()) {
+            can_send = false;
+        }
+
         // If we're applying the Nagle algorithm we don't want to send more
         // until one of:
         // * There's no data in flight
@@ -2005,11 +3992,20 @@ impl<'a> Socket<'a> {
             can_send = false;
         }
 
+        // If we're corked, we don't want to send a partial segment unless it ends
+        // data that was pushed, or we're closing send.
+        let want_push = self
+            .tx_push_seq
+            .map_or(false, |seq| seq > self.remote_last_seq);
+        if self.cork && !can_send_full && !want_fin && !want_push {
+            can_send = false;
+        }
+
         // Can we actually send the FIN? We can send it if:
         // 1. We have unsent data that fits in the remote window.
         // 2. We have no unsent data.
         // This condition matches only if #2, because #1 is already covered by can_data and we're ORing them.
-        let can_fin = want_fin && self.remote_last_seq == self.local_seq_no + self.tx_buffer.len();
+        let can_fin = want_fin && self.remote_last_seq == self.local_seq_no + self.tx_len();
 
         can_send || can_fin
     }
@@ -2060,29 +4056,59 @@ impl<'a> Socket<'a> {
             self.remote_last_ts = Some(cx.now());
         }
 
+        if self.lingering && self.linger_since.is_none() {
+            // The transmit half was just closed, start the linger interval.
+            self.linger_since = Some(cx.now());
+        }
+
         // Check if any state needs to be changed because of a timer.
         if self.timed_out(cx.now()) {
             // If a timeout expires, we should abort the connection.
             net_debug!("timeout exceeded");
+            self.close_reason = Some(CloseReason::Timeout);
+            self.set_state(State::Closed);
+        } else if self.linger_expired(cx.now()) {
+            // If the data did not drain in time after closing, reset the connection.
+            net_debug!("linger timeout exceeded");
+            self.close_reason = Some(CloseReason::LingerTimeout);
+            self.set_state(State::Closed);
+        } else if self.timer.should_keep_alive(cx.now()) && self.keep_alive_probes_exceeded() {
+            // If the remote endpoint did not answer our keep-alive packets, it is likely dead.
+            net_debug!("keep-alive probes unanswered");
+            self.close_reason = Some(CloseReason::KeepAliveTimeout);
             self.set_state(State::Closed);
         } else if !self.seq_to_transmit(cx) {
             if let Some(retransmit_delta) = self.timer.should_retransmit(cx.now()) {
-                // If a retransmit timer expired, we should resend data starting at the last ACK.
-                net_debug!("retransmitting at t+{}", retransmit_delta);
-
-                // Rewind "last sequence number sent", as if we never
-                // had sent them. This will cause all data in the queue
-                // to be sent again.
-                self.remote_last_seq = self.local_seq_no;
-
-                // Clear the `should_retransmit` state. If we can't retransmit right
-                // now for whatever reason (like zero window), this avoids an
-                // infinite polling loop where `poll_at` returns `Now` but `dispatch`
-                // can't actually do anything.
-                self.timer.set_for_idle(cx.now(), self.keep_alive);
+                if self.retransmissions_exceeded() {
+                    // If we've retransmitted too many times without progress, we should
+                    // abort the connection.
+                    net_debug!("retransmission limit exceeded");
+                    self.close_reason = Some(CloseReason::RetransmissionLimit);
+                    self.set_state(State::Closed);
+                } else {
+                    // If a retransmit timer expired, we should resend data starting at the last ACK.
+                    net_debug!("retransmitting at t+{}", retransmit_delta);
+                    if self.timer != Timer::FastRetransmit {
+                        // RFC 5681 § 3.1: restart from the loss window after a timeout.
+                        self.shrink_congestion_window(true);
+                    }
+                    self.retransmissions = self.retransmissions.saturating_add(1);
+                    self.total_retransmissions = self.total_retransmissions.saturating_add(1);
+
+                    // Rewind "last sequence number sent", as if we never
+                    // had sent them. This will cause all data in the queue
+                    // to be sent again.
+                    self.remote_last_seq = self.local_seq_no;
+
+                    // Clear the `should_retransmit` state. If we can't retransmit right
+                    // now for whatever reason (like zero window), this avoids an
+                    // infinite polling loop where `poll_at` returns `Now` but `dispatch`
+                    // can't actually do anything.
+                    self.timer.set_for_idle(cx.now(), self.keep_alive);
 
-                // Inform RTTE, so that it can avoid bogus measurements.
-                self.rtte.on_retransmit();
+                    // Inform RTTE, so that it can avoid bogus measurements.
+                    self.rtte.on_retransmit();
+                }
             }
         }
 
@@ -2105,7 +4131,9 @@ impl<'a> Socket<'a> {
         } else if self.timer.should_close(cx.now()) {
             // If we have spent enough time in the TIME-WAIT state, close the socket.
             tcp_trace!("TIME-WAIT timer expired");
+            let close_reason = self.close_reason;
             self.reset();
+            self.close_reason = close_reason;
             return Ok(());
         } else {
             return Ok(());
@@ -2121,7 +4149,8 @@ impl<'a> Socket<'a> {
             tuple.remote.addr,
             IpProtocol::Tcp,
             0,
-            self.hop_limit.unwrap_or(64),
+            self.hop_limit
+                .unwrap_or_else(|| cx.hop_limit(&tuple.remote.addr)),
         );
 
         // Construct the basic TCP representation, an empty ACK packet.
@@ -2137,6 +4166,10 @@ impl<'a> Socket<'a> {
             max_seg_size: None,
             sack_permitted: false,
             sack_ranges: [None, None, None],
+            timestamp: self.timestamp(cx),
+            md5_signature: None,
+            authentication: None,
+            urgent_pointer: None,
             payload: &[],
         };
 
@@ -2145,6 +4178,7 @@ impl<'a> Socket<'a> {
             // with a specified endpoint, it means that the socket was aborted.
             State::Closed => {
                 repr.control = TcpControl::Rst;
+                repr.timestamp = None;
             }
 
             // We never transmit anything in the LISTEN state.
@@ -2155,11 +4189,17 @@ impl<'a> Socket<'a> {
             State::SynSent | State::SynReceived => {
                 repr.control = TcpControl::Syn;
                 // window len must NOT be scaled in SYNs.
-                repr.window_len = self.rx_buffer.window().min((1 << 16) - 1) as u16;
+                repr.window_len = self.rx_window().min((1 << 16) - 1) as u16;
                 if self.state == State::SynSent {
                     repr.ack_number = None;
                     repr.window_scale = Some(self.remote_win_shift);
                     repr.sack_permitted = true;
+                    if self.timestamps {
+                        repr.timestamp = Some(TcpTimestampOption {
+                            tsval: cx.now().total_millis() as u32,
+                            tsecr: 0,
+                        });
+                    }
                 } else {
                     repr.sack_permitted = self.remote_has_sack;
                     repr.window_scale = self.remote_win_scale.map(|_| self.remote_win_shift);
@@ -2177,7 +4217,7 @@ impl<'a> Socket<'a> {
                 // from the transmit buffer.
 
                 // Right edge of window, ie the max sequence number we're allowed to send.
-                let win_right_edge = self.local_seq_no + self.remote_win_len;
+                let win_right_edge = self.local_seq_no + self.send_window();
 
                 // Max amount of octets we're allowed to send according to the remote window.
                 let win_limit = if win_right_edge >= self.remote_last_seq {
@@ -2192,28 +4232,49 @@ impl<'a> Socket<'a> {
                 };
 
                 // Maximum size we're allowed to send. This can be limited by 3 factors:
-                // 1. remote window
+                // 1. remote and congestion windows
                 // 2. MSS the remote is willing to accept, probably determined by their MTU
-                // 3. MSS we can send, determined by our MTU.
+                // 3. MSS we can send, determined by our MTU or the route.
                 let size = win_limit
                     .min(self.remote_mss)
-                    .min(cx.ip_mtu() - ip_repr.header_len() - TCP_HEADER_LEN);
+                    .min(self.local_mss(cx) - self.options_len());
 
                 let offset = self.remote_last_seq - self.local_seq_no;
-                repr.payload = self.tx_buffer.get_allocated(offset, size);
+                repr.payload = Self::tx_get(
+                    &self.tx_buffer,
+                    self.tx_borrowed,
+                    self.tx_borrowed_at,
+                    offset,
+                    size,
+                );
+
+                if let Some(urgent_seq) = self.tx_urgent_seq {
+                    if urgent_seq > repr.seq_number {
+                        // RFC 6093: an urgent pointer too large for the header is clamped,
+                        // and updated in the following segments.
+                        let urgent_pointer = (urgent_seq - repr.seq_number).min(0xffff);
+                        repr.urgent_pointer = Some(urgent_pointer as u16);
+                    }
+                }
 
                 // If we've sent everything we had in the buffer, follow it with the PSH or FIN
                 // flags, depending on whether the transmit half of the connection is open.
-                if offset + repr.payload.len() == self.tx_buffer.len() {
-                    match self.state {
-                        State::FinWait1 | State::LastAck | State::Closing => {
-                            repr.control = TcpControl::Fin
-                        }
-                        State::Established | State::CloseWait if !repr.payload.is_empty() => {
-                            repr.control = TcpControl::Psh
-                        }
-                        _ => (),
+                // Data explicitly pushed is followed by the PSH flag regardless.
+                let sent_all = offset + repr.payload.len() == self.tx_len();
+                let pushed = self.tx_push_seq.map_or(false, |seq| {
+                    seq > repr.seq_number && seq <= repr.seq_number + repr.payload.len()
+                });
+                match self.state {
+                    State::FinWait1 | State::LastAck | State::Closing if sent_all => {
+                        repr.control = TcpControl::Fin
                     }
+                    State::Established | State::CloseWait
+                        if sent_all && self.auto_push && !repr.payload.is_empty() =>
+                    {
+                        repr.control = TcpControl::Psh
+                    }
+                    _ if pushed => repr.control = TcpControl::Psh,
+                    _ => (),
                 }
             }
 
@@ -2259,7 +4320,7 @@ impl<'a> Socket<'a> {
 
         if repr.control == TcpControl::Syn {
             // Fill the MSS option. See RFC 6691 for an explanation of this calculation.
-            let max_segment_size = cx.ip_mtu() - ip_repr.header_len() - TCP_HEADER_LEN;
+            let max_segment_size = self.local_mss(cx);
             repr.max_seg_size = Some(max_segment_size as u16);
         }
 
@@ -2271,11 +4332,27 @@ impl<'a> Socket<'a> {
         // to not waste time waiting for the retransmit timer on packets that we know
         // for sure will not be successfully transmitted.
         ip_repr.set_payload_len(repr.buffer_len());
+        #[cfg(feature = "socket-tcp-ao")]
+        self.ao.sign(&mut ip_repr, &mut repr);
+        #[cfg(feature = "socket-tcp-md5")]
+        if let (Some(key), None) = (&self.md5_key, repr.authentication) {
+            md5::sign(key, &mut ip_repr, &mut repr);
+        }
         emit(cx, (ip_repr, repr))?;
+        self.last_tx_at = Some(cx.now());
 
         // We've sent something, whether useful data or a keep-alive packet, so rewind
-        // the keep-alive timer.
-        self.timer.rewind_keep_alive(cx.now(), self.keep_alive);
+        // the keep-alive timer. Once we're waiting for an answer to a keep-alive packet,
+        // probe at the shorter interval.
+        if is_keep_alive {
+            self.keep_alive_unanswered = self.keep_alive_unanswered.saturating_add(1);
+        }
+        let keep_alive = if self.keep_alive_unanswered > 0 && self.keep_alive.is_some() {
+            self.keep_alive_probe_interval.or(self.keep_alive)
+        } else {
+            self.keep_alive
+        };
+        self.timer.rewind_keep_alive(cx.now(), keep_alive);
 
         // Reset delayed-ack timer
         match self.ack_delay_timer {
@@ -2296,13 +4373,26 @@ impl<'a> Socket<'a> {
         }
 
         // We've sent a packet successfully, so we can update the internal state now.
+        if let Some(undo) = self.cwnd_undo.as_mut() {
+            if repr.seq_number < undo.end && !repr.payload.is_empty() {
+                let end = (repr.seq_number + repr.payload.len()).min(undo.end);
+                undo.retransmitted += end - repr.seq_number;
+            }
+        }
         self.remote_last_seq = repr.seq_number + repr.segment_len();
         self.remote_last_ack = repr.ack_number;
         self.remote_last_win = repr.window_len;
 
+        if !repr.payload.is_empty() {
+            self.pacing_next_at = self.next_paced_at(cx.now(), repr.payload.len());
+        }
+
         if repr.segment_len() > 0 {
             self.rtte
                 .on_send(cx.now(), repr.seq_number + repr.segment_len());
+            if self.unacked_since.is_none() {
+                self.unacked_since = Some(cx.now());
+            }
         }
 
         if !self.seq_to_transmit(cx) && repr.segment_len() > 0 {
@@ -2334,6 +4424,9 @@ impl<'a> Socket<'a> {
         } else if self.remote_last_ts.is_none() {
             // Socket stopped being quiet recently, we need to acquire a timestamp.
             PollAt::Now
+        } else if self.lingering && self.linger_since.is_none() {
+            // Socket was closed recently, we need to start the linger interval.
+            PollAt::Now
         } else if self.state == State::Closed {
             // Socket was aborted, we have an RST packet to transmit.
             PollAt::Now
@@ -2358,11 +4451,45 @@ impl<'a> Socket<'a> {
                 (_, _) => PollAt::Ingress,
             };
 
+            let pacing_poll_at = match self.pacing_next_at {
+                // If pacing delays sending data, we need to poll when the next segment is due.
+                Some(pacing_next_at)
+                    if self.pacing_delayed(cx.now())
+                        && self.remote_last_seq < self.local_seq_no + self.tx_len() =>
+                {
+                    PollAt::Time(pacing_next_at)
+                }
+                _ => PollAt::Ingress,
+            };
+
+            let user_timeout_poll_at = match (self.unacked_since, self.user_timeout) {
+                // If we have unacknowledged data, we need to poll at the moment when
+                // the user timeout would expire.
+                (Some(unacked_since), Some(user_timeout)) => {
+                    PollAt::Time(unacked_since + user_timeout)
+                }
+                (_, _) => PollAt::Ingress,
+            };
+
+            let linger_poll_at = match self.linger_deadline() {
+                // If the transmit half is closed, we need to poll at the moment when
+                // the linger interval would expire.
+                Some(deadline) => PollAt::Time(deadline),
+                None => PollAt::Ingress,
+            };
+
             // We wait for the earliest of our timers to fire.
-            *[self.timer.poll_at(), timeout_poll_at, delayed_ack_poll_at]
-                .iter()
-                .min()
-                .unwrap_or(&PollAt::Ingress)
+            *[
+                self.timer.poll_at(),
+                timeout_poll_at,
+                linger_poll_at,
+                user_timeout_poll_at,
+                delayed_ack_poll_at,
+                pacing_poll_at,
+            ]
+            .iter()
+            .min()
+            .unwrap_or(&PollAt::Ingress)
         }
     }
 }
@@ -2459,6 +4586,10 @@ mod test {
         max_seg_size: None,
         sack_permitted: false,
         sack_ranges: [None, None, None],
+        timestamp: None,
+        md5_signature: None,
+        authentication: None,
+        urgent_pointer: None,
         payload: &[],
     };
     const _RECV_IP_TEMPL: IpRepr = IpReprIpvX(IpvXRepr {
@@ -2479,6 +4610,10 @@ mod test {
         max_seg_size: None,
         sack_permitted: false,
         sack_ranges: [None, None, None],
+        timestamp: None,
+        md5_signature: None,
+        authentication: None,
+        urgent_pointer: None,
         payload: &[],
     };
 
@@ -2901,12 +5036,32 @@ mod test {
     }
 
     #[test]
-    fn test_listen_twice() {
+    fn test_listen_bound_addr() {
         let mut s = socket();
-        assert_eq!(s.listen(80), Ok(()));
-        assert_eq!(s.listen(80), Err(ListenError::InvalidState));
-    }
-
+        s.set_bound_addr(Some(OTHER_ADDR.into()));
+        assert_eq!(
+            s.listen((LOCAL_ADDR, LOCAL_PORT)),
+            Err(ListenError::Unaddressable)
+        );
+        s.listen(LOCAL_PORT).unwrap();
+        assert_eq!(s.listen_endpoint.addr, Some(OTHER_ADDR.into()));
+
+        let tcp_repr = TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: None,
+            ..SEND_TEMPL
+        };
+        assert!(!s.socket.accepts(&mut s.cx, &SEND_IP_TEMPL, &tcp_repr));
+    }
+
+    #[test]
+    fn test_listen_twice() {
+        let mut s = socket();
+        assert_eq!(s.listen(80), Ok(()));
+        assert_eq!(s.listen(80), Err(ListenError::InvalidState));
+    }
+
     #[test]
     fn test_listen_syn() {
         let mut s = socket_listen();
@@ -2988,6 +5143,103 @@ mod test {
         sanity!(s, socket_established());
     }
 
+    #[test]
+    fn test_syn_received_timestamps() {
+        let mut s = socket_listen();
+        s.set_timestamps_enabled(true);
+        send!(s, time 1_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: None,
+            timestamp: Some(TcpTimestampOption { tsval: 500, tsecr: 0 }),
+            ..SEND_TEMPL
+        });
+        recv!(s, time 1_000, Ok(TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            max_seg_size: Some(BASE_MSS),
+            timestamp: Some(TcpTimestampOption { tsval: 1_000, tsecr: 500 }),
+            ..RECV_TEMPL
+        }));
+        send!(s, time 1_100, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            timestamp: Some(TcpTimestampOption { tsval: 600, tsecr: 1_000 }),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.remote_tsval, Some(600));
+    }
+
+    #[test]
+    fn test_syn_received_timestamps_disabled() {
+        let mut s = socket_listen();
+        send!(s, time 1_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: None,
+            timestamp: Some(TcpTimestampOption { tsval: 500, tsecr: 0 }),
+            ..SEND_TEMPL
+        });
+        recv!(s, time 1_000, Ok(TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            max_seg_size: Some(BASE_MSS),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.remote_tsval, None);
+    }
+
+    #[test]
+    fn test_syn_received_defer_accept() {
+        let mut s = socket_syn_received();
+        s.set_defer_accept(true);
+        assert!(!s.is_active());
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::SynReceived);
+        assert!(!s.is_active());
+
+        // The SYN|ACK is retransmitted until data is received.
+        recv!(s, time 1000, Ok(TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            max_seg_size: Some(BASE_MSS),
+            ..RECV_TEMPL
+        }));
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+        assert!(s.is_active());
+        assert_eq!(s.rx_buffer.dequeue_many(6), &b"abcdef"[..]);
+    }
+
     #[test]
     fn test_syn_received_ack_too_low() {
         let mut s = socket_syn_received();
@@ -3265,6 +5517,42 @@ mod test {
         assert_eq!(s.tuple, Some(TUPLE));
     }
 
+    #[test]
+    fn test_connect_timestamps() {
+        let mut s = socket();
+        s.local_seq_no = LOCAL_SEQ;
+        s.set_timestamps_enabled(true);
+        s.socket
+            .connect(&mut s.cx, REMOTE_END, LOCAL_END.port)
+            .unwrap();
+        recv!(s, time 1_000, Ok(TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: LOCAL_SEQ,
+            ack_number: None,
+            max_seg_size: Some(BASE_MSS),
+            window_scale: Some(0),
+            sack_permitted: true,
+            timestamp: Some(TcpTimestampOption { tsval: 1_000, tsecr: 0 }),
+            ..RECV_TEMPL
+        }));
+        send!(s, time 1_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: Some(LOCAL_SEQ + 1),
+            max_seg_size: Some(BASE_MSS),
+            timestamp: Some(TcpTimestampOption { tsval: 500, tsecr: 1_000 }),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.remote_tsval, Some(500));
+        recv!(s, time 1_000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            timestamp: Some(TcpTimestampOption { tsval: 1_000, tsecr: 500 }),
+            ..RECV_TEMPL
+        }));
+    }
+
     #[test]
     fn test_connect_unspecified_local() {
         let mut s = socket();
@@ -3280,6 +5568,21 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_connect_bound_addr() {
+        let mut s = socket();
+        s.set_bound_addr(Some(OTHER_ADDR.into()));
+        assert_eq!(
+            s.socket.connect(&mut s.cx, REMOTE_END, (LOCAL_ADDR, 80)),
+            Err(ConnectError::Unaddressable)
+        );
+        assert_eq!(s.socket.connect(&mut s.cx, REMOTE_END, 80), Ok(()));
+        assert_eq!(
+            s.tuple.unwrap().local,
+            IpEndpoint::new(OTHER_ADDR.into(), 80)
+        );
+    }
+
     #[test]
     fn test_connect_twice() {
         let mut s = socket();
@@ -3337,6 +5640,96 @@ mod test {
         sanity!(s, socket_established());
     }
 
+    #[test]
+    fn test_syn_sent_syn_simultaneous_open() {
+        let mut s = socket_syn_sent();
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: None,
+                max_seg_size: Some(BASE_MSS),
+                window_scale: Some(0),
+                sack_permitted: true,
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                max_seg_size: Some(BASE_MSS - 80),
+                window_scale: Some(0),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::SynReceived);
+        assert_eq!(s.remote_mss, BASE_MSS as usize - 80);
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                window_scale: Some(0),
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: Some(LOCAL_SEQ + 1),
+                max_seg_size: Some(BASE_MSS - 80),
+                window_scale: Some(0),
+                ..SEND_TEMPL
+            }
+        );
+        recv_nothing!(s, time 1000);
+        assert_eq!(s.state, State::Established);
+        sanity!(s, socket_established());
+    }
+
+    #[test]
+    fn test_syn_sent_syn_simultaneous_open_ack() {
+        let mut s = socket_syn_sent();
+        s.remote_last_seq = LOCAL_SEQ + 1;
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                ..RECV_TEMPL
+            }]
+        );
+        // The remote end may also have received our SYN|ACK first, and only acknowledge it.
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+    }
+
     #[test]
     fn test_syn_sent_syn_ack_not_incremented() {
         let mut s = socket_syn_sent();
@@ -3873,46 +6266,150 @@ mod test {
     }
 
     #[test]
-    fn test_established_send_buf_gt_win() {
-        let mut data = [0; 32];
-        for (i, elem) in data.iter_mut().enumerate() {
-            *elem = i as u8
-        }
-
+    fn test_established_send_vectored() {
         let mut s = socket_established();
-        s.remote_win_len = 16;
-        s.send_slice(&data[..]).unwrap();
+        assert_eq!(s.send_vectored(&[b"abc", b"", b"def"]), Ok(6));
         recv!(
             s,
             [TcpRepr {
                 seq_number: LOCAL_SEQ + 1,
                 ack_number: Some(REMOTE_SEQ + 1),
-                payload: &data[0..16],
+                payload: &b"abcdef"[..],
                 ..RECV_TEMPL
             }]
         );
     }
 
     #[test]
-    fn test_established_send_window_shrink() {
-        let mut s = socket_established();
-
-        // 6 octets fit on the remote side's window, so we send them.
-        s.send_slice(b"abcdef").unwrap();
-        recv!(
-            s,
-            [TcpRepr {
-                seq_number: LOCAL_SEQ + 1,
-                ack_number: Some(REMOTE_SEQ + 1),
-                payload: &b"abcdef"[..],
-                ..RECV_TEMPL
-            }]
-        );
-        assert_eq!(s.tx_buffer.len(), 6);
+    fn test_send_vectored_buffer_full() {
+        let mut s = socket_established_with_buffer_sizes(8, 64);
+        assert_eq!(s.send_vectored(&[b"abc", b"defgh", b"ijk"]), Ok(8));
+        assert_eq!(s.send_vectored(&[b"lmn"]), Ok(0));
+        assert_eq!(s.tx_buffer.len(), 8);
+    }
 
-        println!(
-            "local_seq_no={} remote_win_len={} remote_last_seq={}",
-            s.local_seq_no, s.remote_win_len, s.remote_last_seq
+    #[test]
+    fn test_send_vectored_invalid_state() {
+        let mut s = socket_fin_wait_1();
+        assert_eq!(
+            s.send_vectored(&[b"abc", b"def"]),
+            Err(SendError::InvalidState)
+        );
+    }
+
+    #[test]
+    fn test_send_borrowed() {
+        let mut s = socket_established();
+        s.set_nagle_enabled(false);
+        assert_eq!(s.send_slice(b"abc"), Ok(3));
+        assert_eq!(s.send_borrowed(b"0123456789"), Ok(10));
+        assert_eq!(s.send_borrowed(b"0123456789"), Ok(0));
+        assert_eq!(s.send_slice(b"xyz"), Ok(3));
+        assert_eq!(s.send_queue(), 16);
+        assert_eq!(s.tx_buffer.len(), 6);
+        recv!(
+            s,
+            [
+                TcpRepr {
+                    seq_number: LOCAL_SEQ + 1,
+                    ack_number: Some(REMOTE_SEQ + 1),
+                    payload: &b"abc"[..],
+                    ..RECV_TEMPL
+                },
+                TcpRepr {
+                    seq_number: LOCAL_SEQ + 1 + 3,
+                    ack_number: Some(REMOTE_SEQ + 1),
+                    payload: &b"0123456789"[..],
+                    ..RECV_TEMPL
+                },
+                TcpRepr {
+                    seq_number: LOCAL_SEQ + 1 + 13,
+                    ack_number: Some(REMOTE_SEQ + 1),
+                    payload: &b"xyz"[..],
+                    ..RECV_TEMPL
+                }
+            ]
+        );
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1 + 5),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.send_borrowed_queue(), 8);
+        assert_eq!(s.send_queue(), 11);
+
+        // Retransmit from the middle of the borrowed data.
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 5,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &b"23456789"[..],
+            ..RECV_TEMPL
+        }));
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1 + 16),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.send_borrowed_queue(), 0);
+        assert_eq!(s.send_queue(), 0);
+        assert_eq!(s.send_borrowed(b"0123456789"), Ok(10));
+    }
+
+    #[test]
+    fn test_send_borrowed_invalid_state() {
+        let mut s = socket_fin_wait_1();
+        assert_eq!(s.send_borrowed(b"abc"), Err(SendError::InvalidState));
+    }
+
+    #[test]
+    fn test_established_send_buf_gt_win() {
+        let mut data = [0; 32];
+        for (i, elem) in data.iter_mut().enumerate() {
+            *elem = i as u8
+        }
+
+        let mut s = socket_established();
+        s.remote_win_len = 16;
+        s.send_slice(&data[..]).unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &data[0..16],
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_established_send_window_shrink() {
+        let mut s = socket_established();
+
+        // 6 octets fit on the remote side's window, so we send them.
+        s.send_slice(b"abcdef").unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..RECV_TEMPL
+            }]
+        );
+        assert_eq!(s.tx_buffer.len(), 6);
+
+        println!(
+            "local_seq_no={} remote_win_len={} remote_last_seq={}",
+            s.local_seq_no, s.remote_win_len, s.remote_last_seq
         );
 
         // - Peer doesn't ack them yet
@@ -3949,6 +6446,48 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_established_recv_chunks() {
+        let mut s = socket_established_with_buffer_sizes(64, 6);
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcd"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(
+            s.recv_chunks().unwrap().collect::<Vec<_>>(),
+            vec![&b"abcd"[..]]
+        );
+        assert_eq!(s.consume(3), Ok(3));
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 4,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"efgh"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(
+            s.recv_chunks().unwrap().collect::<Vec<_>>(),
+            vec![&b"def"[..], &b"gh"[..]]
+        );
+        assert_eq!(s.consume(10), Ok(5));
+        assert_eq!(s.recv_chunks().unwrap().count(), 0);
+        assert_eq!(s.remote_seq_no, REMOTE_SEQ + 1 + 8);
+    }
+
+    #[test]
+    fn test_recv_chunks_invalid_state() {
+        let mut s = socket_syn_received();
+        assert!(matches!(s.recv_chunks(), Err(RecvError::InvalidState)));
+        assert_eq!(s.consume(1), Err(RecvError::InvalidState));
+    }
+
     #[test]
     fn test_established_receive_partially_outside_window() {
         let mut s = socket_established();
@@ -4193,6 +6732,7 @@ mod test {
             }
         );
         assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::Reset));
     }
 
     #[test]
@@ -4218,11 +6758,113 @@ mod test {
         sanity!(s, socket_fin_wait_1());
     }
 
+    #[test]
+    fn test_established_shutdown_write() {
+        let mut s = socket_established();
+        s.shutdown(Shutdown::Write);
+        assert_eq!(s.state, State::FinWait1);
+        assert!(s.may_recv());
+        sanity!(s, socket_fin_wait_1());
+    }
+
+    #[test]
+    fn test_established_shutdown_read() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        s.shutdown(Shutdown::Read);
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.recv_queue(), 0);
+        assert!(!s.may_recv());
+        assert!(s.may_send());
+        assert_eq!(s.recv_slice(&mut [0; 8]), Err(RecvError::Finished));
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 3,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"def"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.recv_queue(), 0);
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 64,
+                ..RECV_TEMPL
+            }]
+        );
+
+        s.send_slice(b"xyz").unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                payload: &b"xyz"[..],
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_established_shutdown_both() {
+        let mut s = socket_established();
+        s.shutdown(Shutdown::Both);
+        assert_eq!(s.state, State::FinWait1);
+        assert!(!s.may_recv());
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.recv_queue(), 0);
+        assert_eq!(s.recv_slice(&mut [0; 8]), Err(RecvError::Finished));
+
+        s.reset();
+        assert!(!s.rx_shutdown);
+    }
+
     #[test]
     fn test_established_abort() {
         let mut s = socket_established();
         s.abort();
         assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::Aborted));
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Rst,
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_established_close_linger_zero() {
+        let mut s = socket_established();
+        s.set_linger(Some(Duration::ZERO));
+        s.send_slice(b"abcdef").unwrap();
+        s.close();
+        assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::Aborted));
         recv!(
             s,
             [TcpRepr {
@@ -4234,6 +6876,57 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_established_close_linger_timeout() {
+        let mut s = socket_established();
+        s.set_linger(Some(Duration::from_millis(500)));
+        s.send_slice(b"abcdef").unwrap();
+        s.close();
+        assert_eq!(s.state, State::FinWait1);
+        recv!(s, time 100, Ok(TcpRepr {
+            control: TcpControl::Fin,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_millis(600))
+        );
+        recv!(s, time 600, Ok(TcpRepr {
+            control: TcpControl::Rst,
+            seq_number: LOCAL_SEQ + 1 + 6 + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::LingerTimeout));
+    }
+
+    #[test]
+    fn test_established_close_linger_drained() {
+        let mut s = socket_established();
+        s.set_linger(Some(Duration::from_millis(500)));
+        s.send_slice(b"abcdef").unwrap();
+        s.close();
+        recv!(s, time 100, Ok(TcpRepr {
+            control: TcpControl::Fin,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        send!(s, time 200, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 6 + 1),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::FinWait2);
+        recv_nothing!(s, time 1000);
+        assert_eq!(s.state, State::FinWait2);
+    }
+
     #[test]
     fn test_established_rst_bad_seq() {
         let mut s = socket_established();
@@ -4241,7 +6934,7 @@ mod test {
             s,
             TcpRepr {
                 control: TcpControl::Rst,
-                seq_number: REMOTE_SEQ, // Wrong seq
+                seq_number: REMOTE_SEQ + 1 + 5, // Wrong seq
                 ack_number: None,
                 ..SEND_TEMPL
             },
@@ -4273,7 +6966,7 @@ mod test {
             time 2000,
             TcpRepr {
                 control: TcpControl::Rst,
-                seq_number: REMOTE_SEQ, // Wrong seq
+                seq_number: REMOTE_SEQ + 1 + 5, // Wrong seq
                 ack_number: None,
                 ..SEND_TEMPL
             },
@@ -4286,27 +6979,102 @@ mod test {
         );
     }
 
-    // =========================================================================================//
-    // Tests for the FIN-WAIT-1 state.
-    // =========================================================================================//
-
     #[test]
-    fn test_fin_wait_1_fin_ack() {
-        let mut s = socket_fin_wait_1();
-        recv!(
-            s,
-            [TcpRepr {
-                control: TcpControl::Fin,
-                seq_number: LOCAL_SEQ + 1,
-                ack_number: Some(REMOTE_SEQ + 1),
-                ..RECV_TEMPL
-            }]
-        );
+    fn test_established_rst_out_of_window() {
+        let mut s = socket_established();
         send!(
             s,
             TcpRepr {
-                seq_number: REMOTE_SEQ + 1,
-                ack_number: Some(LOCAL_SEQ + 1 + 1),
+                control: TcpControl::Rst,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Rst,
+                seq_number: REMOTE_SEQ + 1 + 64,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+    }
+
+    #[test]
+    fn test_established_rst_challenge_ack_rate_limit() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Rst,
+                seq_number: REMOTE_SEQ + 1 + 5,
+                ack_number: None,
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                ..RECV_TEMPL
+            })
+        );
+        send!(
+            s,
+            time 500,
+            TcpRepr {
+                control: TcpControl::Rst,
+                seq_number: REMOTE_SEQ + 1 + 5,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::Established);
+    }
+
+    #[test]
+    fn test_established_syn() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ + 100,
+                ack_number: None,
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                ..RECV_TEMPL
+            })
+        );
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.remote_seq_no, REMOTE_SEQ + 1);
+    }
+
+    // =========================================================================================//
+    // Tests for the FIN-WAIT-1 state.
+    // =========================================================================================//
+
+    #[test]
+    fn test_fin_wait_1_fin_ack() {
+        let mut s = socket_fin_wait_1();
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Fin,
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1 + 1),
                 ..SEND_TEMPL
             }
         );
@@ -4406,6 +7174,7 @@ mod test {
             ..SEND_TEMPL
         });
         assert_eq!(s.state, State::TimeWait);
+        assert_eq!(s.close_reason(), Some(CloseReason::Graceful));
         sanity!(s, socket_time_wait(false));
     }
 
@@ -4550,6 +7319,151 @@ mod test {
         assert_eq!(s.state, State::Closed);
     }
 
+    #[test]
+    fn test_time_wait_duration() {
+        let mut s = socket_fin_wait_2();
+        s.set_time_wait(Duration::from_millis(500));
+        send!(s, time 1_000, TcpRepr {
+            control: TcpControl::Fin,
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 1),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::TimeWait);
+        assert_eq!(
+            s.timer,
+            Timer::Close {
+                expires_at: Instant::from_millis(1_500)
+            }
+        );
+        recv!(s, time 1_000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 1,
+            ack_number: Some(REMOTE_SEQ + 1 + 1),
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 1_499);
+        assert_eq!(s.state, State::TimeWait);
+        recv_nothing!(s, time 1_500);
+        assert_eq!(s.state, State::Closed);
+    }
+
+    #[test]
+    fn test_time_wait_rst() {
+        let mut s = socket_time_wait(false);
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Rst,
+                seq_number: REMOTE_SEQ + 1 + 1,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::TimeWait);
+    }
+
+    #[test]
+    fn test_time_wait_reopen_syn() {
+        let mut s = socket_time_wait(false);
+        s.listen_endpoint = LISTEN_END;
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ + 1000,
+            ack_number: None,
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::SynReceived);
+        assert_eq!(s.tuple, Some(TUPLE));
+        assert_eq!(s.remote_seq_no, REMOTE_SEQ + 1000 + 1);
+    }
+
+    #[test]
+    fn test_time_wait_reopen_syn_old_seq() {
+        let mut s = socket_time_wait(false);
+        s.listen_endpoint = LISTEN_END;
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: None,
+            ..SEND_TEMPL
+        }, Some(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 1,
+            ack_number: Some(REMOTE_SEQ + 1 + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::TimeWait);
+    }
+
+    #[test]
+    fn test_time_wait_reopen_syn_newer_timestamp() {
+        let mut s = socket_time_wait(false);
+        s.listen_endpoint = LISTEN_END;
+        s.set_timestamps_enabled(true);
+        s.remote_tsval = Some(1_000);
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: None,
+            timestamp: Some(TcpTimestampOption { tsval: 1_001, tsecr: 0 }),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.state, State::SynReceived);
+        assert_eq!(s.remote_seq_no, REMOTE_SEQ + 1);
+        assert_eq!(s.remote_tsval, Some(1_001));
+    }
+
+    #[test]
+    fn test_time_wait_reopen_syn_old_timestamp() {
+        let mut s = socket_time_wait(false);
+        s.listen_endpoint = LISTEN_END;
+        s.set_timestamps_enabled(true);
+        s.remote_tsval = Some(1_000);
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ + 1000,
+            ack_number: None,
+            timestamp: Some(TcpTimestampOption { tsval: 1_000, tsecr: 0 }),
+            ..SEND_TEMPL
+        }, Some(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 1,
+            ack_number: Some(REMOTE_SEQ + 1 + 1),
+            timestamp: Some(TcpTimestampOption { tsval: 2_000, tsecr: 1_000 }),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::TimeWait);
+    }
+
+    #[test]
+    fn test_time_wait_reopen_syn_not_listening() {
+        let mut s = socket_time_wait(false);
+        send!(s, time 2_000, TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ + 1000,
+            ack_number: None,
+            ..SEND_TEMPL
+        }, Some(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 1,
+            ack_number: Some(REMOTE_SEQ + 1 + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::TimeWait);
+    }
+
+    #[test]
+    fn test_time_wait_connect_same_tuple() {
+        let mut s = socket_time_wait(false);
+        assert_eq!(
+            s.socket.connect(&mut s.cx, REMOTE_END, LOCAL_END),
+            Err(ConnectError::InvalidState)
+        );
+        assert_eq!(s.state, State::TimeWait);
+        assert_eq!(
+            s.socket.connect(&mut s.cx, REMOTE_END, LOCAL_END.port + 1),
+            Ok(())
+        );
+        assert_eq!(s.state, State::SynSent);
+    }
+
     // =========================================================================================//
     // Tests for the CLOSE-WAIT state.
     // =========================================================================================//
@@ -4610,6 +7524,7 @@ mod test {
             }
         );
         assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::Graceful));
     }
 
     #[test]
@@ -5962,69 +8877,381 @@ mod test {
     }
 
     // =========================================================================================//
-    // Tests for flow control.
+    // Tests for congestion control.
     // =========================================================================================//
 
     #[test]
-    fn test_psh_transmit() {
-        let mut s = socket_established();
-        s.remote_mss = 6;
-        s.send_slice(b"abcdef").unwrap();
-        s.send_slice(b"123456").unwrap();
-        recv!(s, time 0, Ok(TcpRepr {
-            control:    TcpControl::None,
-            seq_number: LOCAL_SEQ + 1,
-            ack_number: Some(REMOTE_SEQ + 1),
-            payload:    &b"abcdef"[..],
-            ..RECV_TEMPL
-        }), exact);
+    fn test_initial_window() {
+        let mut s = socket_syn_sent_with_buffer_sizes(128, 64);
+        s.set_initial_window(Some(2));
         recv!(s, time 0, Ok(TcpRepr {
-            control:    TcpControl::Psh,
-            seq_number: LOCAL_SEQ + 1 + 6,
-            ack_number: Some(REMOTE_SEQ + 1),
-            payload:    &b"123456"[..],
+            control: TcpControl::Syn,
+            seq_number: LOCAL_SEQ,
+            ack_number: None,
+            max_seg_size: Some(BASE_MSS),
+            window_scale: Some(0),
+            sack_permitted: true,
             ..RECV_TEMPL
-        }), exact);
-    }
-
-    #[test]
-    fn test_psh_receive() {
-        let mut s = socket_established();
+        }));
         send!(
             s,
             TcpRepr {
-                control: TcpControl::Psh,
-                seq_number: REMOTE_SEQ + 1,
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
                 ack_number: Some(LOCAL_SEQ + 1),
-                payload: &b"abcdef"[..],
+                max_seg_size: Some(10),
+                window_scale: Some(0),
                 ..SEND_TEMPL
             }
         );
-        recv!(
-            s,
-            [TcpRepr {
-                seq_number: LOCAL_SEQ + 1,
-                ack_number: Some(REMOTE_SEQ + 1 + 6),
-                window_len: 58,
-                ..RECV_TEMPL
-            }]
-        );
-    }
+        assert_eq!(s.info().congestion_window, Some(20));
 
-    #[test]
-    fn test_zero_window_ack() {
-        let mut s = socket_established();
-        s.rx_buffer = SocketBuffer::new(vec![0; 6]);
-        s.assembler = Assembler::new();
-        send!(
-            s,
-            TcpRepr {
-                seq_number: REMOTE_SEQ + 1,
-                ack_number: Some(LOCAL_SEQ + 1),
-                payload: &b"abcdef"[..],
-                ..SEND_TEMPL
-            }
-        );
+        s.send_slice(&[0; 100]).unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 10],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 10,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 10],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 0);
+
+        // Slow start: every acknowledged segment opens the window by one more segment.
+        send!(s, time 10, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 10),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.info().congestion_window, Some(30));
+        recv!(s, time 10, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 20,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 10],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 10, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 30,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 10],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 10);
+
+        // A retransmission timeout shrinks the window to a single segment.
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 10,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 10],
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.info().congestion_window, Some(10));
+        assert_eq!(s.ssthresh, 20);
+        recv_nothing!(s, time 1000);
+    }
+
+    #[test]
+    fn test_spurious_retransmission() {
+        let mut s = socket_established_with_buffer_sizes(128, 64);
+        s.remote_has_sack = true;
+        s.initial_window = Some(4);
+        s.remote_mss = 10;
+        s.reset_congestion_window();
+
+        s.send_slice(&[0; 40]).unwrap();
+        for i in 0..4 {
+            recv!(s, time 0, Ok(TcpRepr {
+                seq_number: LOCAL_SEQ + 1 + i * 10,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &[0; 10],
+                ..RECV_TEMPL
+            }));
+        }
+
+        // The acknowledgements are delayed past the retransmission timeout.
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 10],
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.info().congestion_window, Some(10));
+        send!(s, time 1010, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 40),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.info().congestion_window, Some(20));
+
+        // The remote endpoint reports that the retransmitted segment was a duplicate.
+        send!(s, time 1020, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 40),
+            sack_ranges: [
+                Some(((LOCAL_SEQ + 1).0 as u32, (LOCAL_SEQ + 1 + 10).0 as u32)),
+                None,
+                None,
+            ],
+            ..SEND_TEMPL
+        });
+        let info = s.info();
+        assert_eq!(info.duplicate_sacks, 1);
+        assert_eq!(info.spurious_retransmissions, 1);
+        assert_eq!(info.congestion_window, Some(40));
+        assert_eq!(s.ssthresh, usize::MAX);
+    }
+
+    #[test]
+    fn test_dsack_not_duplicate_ack() {
+        let mut s = socket_established();
+        s.remote_has_sack = true;
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            }
+        );
+        for _ in 0..3 {
+            send!(
+                s,
+                TcpRepr {
+                    seq_number: REMOTE_SEQ + 1,
+                    ack_number: Some(LOCAL_SEQ + 1),
+                    sack_ranges: [
+                        Some(((LOCAL_SEQ).0 as u32, (LOCAL_SEQ + 1).0 as u32)),
+                        None,
+                        None,
+                    ],
+                    ..SEND_TEMPL
+                }
+            );
+        }
+        let info = s.info();
+        assert_eq!(info.duplicate_sacks, 3);
+        assert_eq!(info.duplicate_acks, 0);
+        assert_eq!(info.spurious_retransmissions, 0);
+        recv_nothing!(s, time 0);
+    }
+
+    #[test]
+    fn test_congestion_avoidance() {
+        let mut s = socket_established_with_buffer_sizes(128, 64);
+        s.initial_window = Some(1);
+        s.remote_mss = 10;
+        s.cwnd = 40;
+        s.ssthresh = 40;
+        s.grow_congestion_window(10);
+        assert_eq!(s.cwnd, 42);
+
+        s.remote_last_seq = s.local_seq_no + 42;
+        s.shrink_congestion_window(false);
+        assert_eq!((s.cwnd, s.ssthresh), (21, 21));
+    }
+
+    #[test]
+    fn test_no_congestion_window() {
+        let mut s = socket_established();
+        s.grow_congestion_window(10);
+        s.shrink_congestion_window(true);
+        assert_eq!(s.info().congestion_window, None);
+        assert_eq!(s.send_window(), s.remote_win_len);
+    }
+
+    #[test]
+    fn test_pacing() {
+        let mut s = socket_established_with_buffer_sizes(128, 64);
+        s.set_pacing_enabled(true);
+        s.remote_mss = 16;
+        assert_eq!(s.remote_win_len, 256);
+        s.send_slice(&[0; 48]).unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 16],
+            ..RECV_TEMPL
+        }));
+        // 300ms RTT * 16 octets / 256 octets window
+        recv_nothing!(s, time 18);
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_micros(18_750))
+        );
+        recv!(s, time 19, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 16,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 16],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 37);
+        recv!(s, time 38, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 32,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 16],
+            ..RECV_TEMPL
+        }));
+
+        s.set_pacing_enabled(false);
+        s.send_slice(&[0; 32]).unwrap();
+        recv!(s, time 39, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 48,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 16],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 39, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 64,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 16],
+            ..RECV_TEMPL
+        }));
+    }
+
+    #[test]
+    fn test_pacing_slow_start() {
+        let mut s = socket_established();
+        s.set_pacing_enabled(true);
+        s.initial_window = Some(2);
+        s.remote_mss = 16;
+        s.reset_congestion_window();
+        // 300ms RTT * 16 octets / (2 * 32 octets window)
+        assert_eq!(
+            s.next_paced_at(Instant::ZERO, 16),
+            Some(Instant::from_millis(75))
+        );
+        s.ssthresh = 32;
+        assert_eq!(
+            s.next_paced_at(Instant::ZERO, 16),
+            Some(Instant::from_millis(150))
+        );
+    }
+
+    #[test]
+    fn test_route_mss() {
+        let mut s = socket_syn_sent_with_buffer_sizes(2048, 64);
+        s.cx.routes_mut().update(|routes| {
+            routes
+                .push(crate::iface::Route {
+                    cidr: crate::wire::IpCidr::new(REMOTE_ADDR.into(), 16),
+                    via_router: REMOTE_ADDR.into(),
+                    preferred_until: None,
+                    expires_at: None,
+                    mss: Some(1000),
+                    src_cidr: None,
+                    dscp: None,
+                    mark: None,
+                })
+                .unwrap();
+        });
+        recv!(
+            s,
+            [TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: LOCAL_SEQ,
+                ack_number: None,
+                max_seg_size: Some(1000),
+                window_scale: Some(0),
+                sack_permitted: true,
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: Some(LOCAL_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                window_scale: Some(0),
+                window_len: 2048,
+                ..SEND_TEMPL
+            }
+        );
+        s.send_slice(&[0; 1500]).unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload: &[0; 1000],
+            ..RECV_TEMPL
+        }));
+    }
+
+    // =========================================================================================//
+    // Tests for flow control.
+    // =========================================================================================//
+
+    #[test]
+    fn test_psh_transmit() {
+        let mut s = socket_established();
+        s.remote_mss = 6;
+        s.send_slice(b"abcdef").unwrap();
+        s.send_slice(b"123456").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::None,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"123456"[..],
+            ..RECV_TEMPL
+        }), exact);
+    }
+
+    #[test]
+    fn test_psh_receive() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Psh,
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 58,
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    #[test]
+    fn test_zero_window_ack() {
+        let mut s = socket_established();
+        s.rx_buffer = SocketBuffer::new(vec![0; 6]);
+        s.assembler = Assembler::new();
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
         recv!(
             s,
             [TcpRepr {
@@ -6203,6 +9430,47 @@ mod test {
         assert!(s.window_to_update());
     }
 
+    #[test]
+    fn test_recv_window_auto_tuning() {
+        let mut s = socket_established_with_buffer_sizes(64, 8192);
+        s.set_recv_window_auto_tuning(true);
+        assert_eq!(s.info().recv_window, 10 * DEFAULT_MSS);
+
+        let data = vec![0; 3536];
+        send!(s, time 0, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            payload:    &data[..536],
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.info().recv_window, 10 * DEFAULT_MSS - 536);
+
+        // More than half of the window delivered within a round-trip time: grow it.
+        send!(s, time 300, TcpRepr {
+            seq_number: REMOTE_SEQ + 1 + 536,
+            ack_number: Some(LOCAL_SEQ + 1),
+            payload:    &data[536..],
+            ..SEND_TEMPL
+        });
+        recv!(s, time 300, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1 + 3536),
+            window_len: 6000 - 3536,
+            ..RECV_TEMPL
+        }));
+
+        // The window does not shrink when the data is read.
+        s.recv(|buffer| (buffer.len(), ())).unwrap();
+        assert_eq!(s.info().recv_window, 6000);
+    }
+
+    #[test]
+    fn test_recv_window_auto_tuning_disabled() {
+        let s = socket_established_with_buffer_sizes(64, 8192);
+        assert!(!s.recv_window_auto_tuning());
+        assert_eq!(s.info().recv_window, 8192);
+    }
+
     // =========================================================================================//
     // Tests for timeouts.
     // =========================================================================================//
@@ -6284,6 +9552,7 @@ mod test {
             ..RECV_TEMPL
         }));
         assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::Timeout));
     }
 
     #[test]
@@ -6387,23 +9656,176 @@ mod test {
         assert_eq!(s.socket.poll_at(&mut s.cx), PollAt::Ingress);
     }
 
-    // =========================================================================================//
-    // Tests for keep-alive.
-    // =========================================================================================//
-
     #[test]
-    fn test_responds_to_keep_alive() {
+    fn test_max_retransmissions() {
         let mut s = socket_established();
-        send!(
-            s,
-            TcpRepr {
-                seq_number: REMOTE_SEQ,
-                ack_number: Some(LOCAL_SEQ + 1),
-                ..SEND_TEMPL
-            },
-            Some(TcpRepr {
-                seq_number: LOCAL_SEQ + 1,
-                ack_number: Some(REMOTE_SEQ + 1),
+        s.set_max_retransmissions(Some(2));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 700, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 1400, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 2100, Ok(TcpRepr {
+            control:    TcpControl::Rst,
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::RetransmissionLimit));
+    }
+
+    #[test]
+    fn test_max_retransmissions_reset_by_ack() {
+        let mut s = socket_established();
+        s.set_max_retransmissions(Some(1));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 700, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        send!(s, time 800, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 3),
+            ..SEND_TEMPL
+        });
+        recv!(s, time 1400, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1 + 3,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"def"[..],
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Established);
+    }
+
+    #[test]
+    fn test_user_timeout() {
+        let mut s = socket_established();
+        s.set_user_timeout(Some(Duration::from_millis(1000)));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv!(s, time 700, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        // A duplicate ACK does not count as progress.
+        send!(s, time 800, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            ..SEND_TEMPL
+        });
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_millis(1000))
+        );
+        recv!(s, time 1000, Ok(TcpRepr {
+            control:    TcpControl::Rst,
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::Timeout));
+    }
+
+    #[test]
+    fn test_user_timeout_reset_by_ack() {
+        let mut s = socket_established();
+        s.set_user_timeout(Some(Duration::from_millis(1000)));
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        send!(s, time 500, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 6),
+            ..SEND_TEMPL
+        });
+        assert_eq!(s.unacked_since, None);
+        recv_nothing!(s, time 1000);
+        assert_eq!(s.state, State::Established);
+    }
+
+    #[test]
+    fn test_rto_bounds() {
+        let mut s = socket_established();
+        s.set_rto_bounds(Duration::from_millis(1000), Duration::from_millis(2000));
+        assert_eq!(
+            s.rto_bounds(),
+            (Duration::from_millis(1000), Duration::from_millis(2000))
+        );
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 700);
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid retransmission timeout bounds")]
+    fn test_rto_bounds_invalid() {
+        let mut s = socket_established();
+        s.set_rto_bounds(Duration::from_millis(2000), Duration::from_millis(1000));
+    }
+
+    // =========================================================================================//
+    // Tests for keep-alive.
+    // =========================================================================================//
+
+    #[test]
+    fn test_responds_to_keep_alive() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
                 ..RECV_TEMPL
             })
         );
@@ -6465,6 +9887,86 @@ mod test {
         }));
     }
 
+    #[test]
+    fn test_keep_alive_probes() {
+        let mut s = socket_established();
+        s.set_keep_alive(Some(Duration::from_millis(100)));
+        s.set_keep_alive_probe_interval(Some(Duration::from_millis(10)));
+        s.set_keep_alive_probes(Some(2));
+
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &[0],
+            ..RECV_TEMPL
+        }));
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_millis(10))
+        );
+
+        // An answer restores the keep-alive interval.
+        send!(s, time 5, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            ..SEND_TEMPL
+        });
+        assert_eq!(
+            s.socket.poll_at(&mut s.cx),
+            PollAt::Time(Instant::from_millis(105))
+        );
+        recv_nothing!(s, time 100);
+
+        recv!(s, time 105, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &[0],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 110);
+        recv!(s, time 115, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &[0],
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.close_reason(), None);
+        recv!(s, time 125, Ok(TcpRepr {
+            control:    TcpControl::Rst,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.state, State::Closed);
+        assert_eq!(s.close_reason(), Some(CloseReason::KeepAliveTimeout));
+    }
+
+    #[test]
+    fn test_keep_alive_probes_default_interval() {
+        let mut s = socket_established();
+        s.set_keep_alive(Some(Duration::from_millis(100)));
+        s.set_keep_alive_probes(Some(1));
+
+        recv!(s, time 0, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &[0],
+            ..RECV_TEMPL
+        }));
+        recv_nothing!(s, time 95);
+        recv!(s, time 100, Ok(TcpRepr {
+            control:    TcpControl::Rst,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            ..RECV_TEMPL
+        }));
+        assert_eq!(s.close_reason(), Some(CloseReason::KeepAliveTimeout));
+
+        // The close reason is kept until the socket is reused.
+        s.listen(LOCAL_PORT).unwrap();
+        assert_eq!(s.close_reason(), None);
+    }
+
     // =========================================================================================//
     // Tests for time-to-live configuration.
     // =========================================================================================//
@@ -6499,6 +10001,61 @@ mod test {
     // Tests for reassembly.
     // =========================================================================================//
 
+    #[test]
+    fn test_reassembly_drops() {
+        use crate::config::ASSEMBLER_MAX_SEGMENT_COUNT;
+
+        let mut s = socket_established_with_buffer_sizes(64, 2 * ASSEMBLER_MAX_SEGMENT_COUNT + 8);
+        for i in 1..=ASSEMBLER_MAX_SEGMENT_COUNT + 1 {
+            let reply = send(
+                &mut s,
+                Instant::ZERO,
+                &TcpRepr {
+                    seq_number: REMOTE_SEQ + 1 + 2 * i,
+                    ack_number: Some(LOCAL_SEQ + 1),
+                    payload: &b"x"[..],
+                    ..SEND_TEMPL
+                },
+            );
+            // Every segment is answered with a duplicate ACK, even when it is dropped.
+            assert_eq!(reply.unwrap().ack_number, Some(REMOTE_SEQ + 1));
+        }
+        assert_eq!(
+            s.info().out_of_order_segments,
+            ASSEMBLER_MAX_SEGMENT_COUNT as u32 + 1
+        );
+        assert_eq!(s.info().reassembly_drops, 1);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_reassembly_capacity() {
+        use crate::config::ASSEMBLER_MAX_SEGMENT_COUNT;
+
+        let mut s = socket_established_with_buffer_sizes(64, 2 * ASSEMBLER_MAX_SEGMENT_COUNT + 8);
+        assert_eq!(s.reassembly_capacity(), ASSEMBLER_MAX_SEGMENT_COUNT);
+        s.set_reassembly_capacity(ASSEMBLER_MAX_SEGMENT_COUNT + 1);
+        assert_eq!(s.reassembly_capacity(), ASSEMBLER_MAX_SEGMENT_COUNT + 1);
+        for i in 1..=ASSEMBLER_MAX_SEGMENT_COUNT + 1 {
+            let reply = send(
+                &mut s,
+                Instant::ZERO,
+                &TcpRepr {
+                    seq_number: REMOTE_SEQ + 1 + 2 * i,
+                    ack_number: Some(LOCAL_SEQ + 1),
+                    payload: &b"x"[..],
+                    ..SEND_TEMPL
+                },
+            );
+            assert_eq!(reply.unwrap().ack_number, Some(REMOTE_SEQ + 1));
+        }
+        assert_eq!(s.info().reassembly_drops, 0);
+
+        // The capacity is kept across connections.
+        s.reset();
+        assert_eq!(s.reassembly_capacity(), ASSEMBLER_MAX_SEGMENT_COUNT + 1);
+    }
+
     #[test]
     fn test_out_of_order() {
         let mut s = socket_established();
@@ -6719,7 +10276,7 @@ mod test {
             s,
             TcpRepr {
                 control: TcpControl::Rst,
-                seq_number: REMOTE_SEQ + 1 + 9,
+                seq_number: REMOTE_SEQ + 1 + 3,
                 ack_number: Some(LOCAL_SEQ + 1),
                 ..SEND_TEMPL
             }
@@ -6789,7 +10346,7 @@ mod test {
             s,
             TcpRepr {
                 control: TcpControl::Rst,
-                seq_number: REMOTE_SEQ + 1 + 9,
+                seq_number: REMOTE_SEQ + 1 + 3,
                 ack_number: Some(LOCAL_SEQ + 1),
                 ..SEND_TEMPL
             }
@@ -7076,31 +10633,440 @@ mod test {
         }), exact);
     }
 
-    // =========================================================================================//
-    // Tests for packet filtering.
-    // =========================================================================================//
-
     #[test]
-    fn test_doesnt_accept_wrong_port() {
+    fn test_cork() {
         let mut s = socket_established();
-        s.rx_buffer = SocketBuffer::new(vec![0; 6]);
-        s.assembler = Assembler::new();
+        s.set_nagle_enabled(false);
+        s.set_cork_enabled(true);
+        s.remote_mss = 6;
 
-        let tcp_repr = TcpRepr {
-            seq_number: REMOTE_SEQ + 1,
-            ack_number: Some(LOCAL_SEQ + 1),
-            dst_port: LOCAL_PORT + 1,
-            ..SEND_TEMPL
-        };
-        assert!(!s.socket.accepts(&mut s.cx, &SEND_IP_TEMPL, &tcp_repr));
+        // Partial segments are held back, even without data in flight.
+        s.send_slice(b"abc").unwrap();
+        recv_nothing!(s);
+        s.send_slice(b"defgh").unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..RECV_TEMPL
+            }]
+        );
 
-        let tcp_repr = TcpRepr {
-            seq_number: REMOTE_SEQ + 1,
-            ack_number: Some(LOCAL_SEQ + 1),
-            src_port: REMOTE_PORT + 1,
-            ..SEND_TEMPL
-        };
-        assert!(!s.socket.accepts(&mut s.cx, &SEND_IP_TEMPL, &tcp_repr));
+        // Releasing the cork sends them.
+        s.set_cork_enabled(false);
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"gh"[..],
+            ..RECV_TEMPL
+        }), exact);
+    }
+
+    #[test]
+    fn test_cork_push() {
+        let mut s = socket_established();
+        s.set_nagle_enabled(false);
+        s.set_cork_enabled(true);
+        s.set_auto_push_enabled(false);
+        s.remote_mss = 6;
+
+        s.send_slice(b"abcdefgh").unwrap();
+        s.push();
+        s.send_slice(b"ij").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::None,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }), exact);
+        // The segment containing the end of the pushed data is sent, with PSH set.
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::Psh,
+            seq_number: LOCAL_SEQ + 1 + 6,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"ghij"[..],
+            ..RECV_TEMPL
+        }), exact);
+        recv_nothing!(s);
+        assert_eq!(s.tx_push_seq, Some(LOCAL_SEQ + 1 + 8));
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1 + 10),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.tx_push_seq, None);
+
+        // Once it is acknowledged, partial segments are held back again.
+        s.send_slice(b"kl").unwrap();
+        recv_nothing!(s);
+    }
+
+    #[test]
+    fn test_auto_push_disabled() {
+        let mut s = socket_established();
+        s.set_auto_push_enabled(false);
+        s.send_slice(b"abc").unwrap();
+        recv!(s, time 0, Ok(TcpRepr {
+            control:    TcpControl::None,
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abc"[..],
+            ..RECV_TEMPL
+        }), exact);
+    }
+
+    // =========================================================================================//
+    // Tests for splicing.
+    // =========================================================================================//
+
+    #[test]
+    fn test_splice_into() {
+        let mut s = socket_established();
+        let mut dst = socket_established_with_buffer_sizes(4, 64);
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.socket.splice_into(&mut dst.socket), 4);
+        assert_eq!(dst.tx_buffer.dequeue_many(4), &b"abcd"[..]);
+        assert_eq!(s.socket.splice_into(&mut dst.socket), 2);
+        assert_eq!(dst.tx_buffer.dequeue_many(2), &b"ef"[..]);
+        assert_eq!(s.socket.splice_into(&mut dst.socket), 0);
+        assert_eq!(dst.state, State::Established);
+
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Fin,
+                seq_number: REMOTE_SEQ + 1 + 6,
+                ack_number: Some(LOCAL_SEQ + 1),
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.socket.splice_into(&mut dst.socket), 0);
+        assert_eq!(dst.state, State::FinWait1);
+    }
+
+    // =========================================================================================//
+    // Tests for SYN cookies.
+    // =========================================================================================//
+
+    fn remote_ip_repr(repr: &TcpRepr) -> IpRepr {
+        IpReprIpvX(IpvXRepr {
+            src_addr: REMOTE_ADDR,
+            dst_addr: LOCAL_ADDR,
+            next_header: IpProtocol::Tcp,
+            payload_len: repr.buffer_len(),
+            hop_limit: 64,
+        })
+    }
+
+    fn syn_cookie(max_seg_size: Option<u16>) -> TcpSeqNumber {
+        let mut s = socket_established();
+        s.listen_endpoint = LISTEN_END;
+        s.set_syn_cookies_enabled(true);
+
+        let syn = TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: None,
+            max_seg_size,
+            ..SEND_TEMPL
+        };
+        let (_, syn_ack) = s
+            .socket
+            .syn_cookie_reply(&mut s.cx, &remote_ip_repr(&syn), &syn)
+            .unwrap();
+        assert_eq!(
+            syn_ack,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: syn_ack.seq_number,
+                ack_number: Some(REMOTE_SEQ + 1),
+                max_seg_size: Some(BASE_MSS),
+                ..RECV_TEMPL
+            }
+        );
+        syn_ack.seq_number
+    }
+
+    #[test]
+    fn test_syn_cookie() {
+        let cookie = syn_cookie(Some(1400));
+
+        let mut s = socket_listen();
+        s.set_syn_cookies_enabled(true);
+        let ack = TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(cookie + 1),
+            payload: &b"abcdef"[..],
+            ..SEND_TEMPL
+        };
+        assert!(!s.socket.accepts(&mut s.cx, &remote_ip_repr(&ack), &ack));
+        assert!(s
+            .socket
+            .accept_syn_cookie(&mut s.cx, &remote_ip_repr(&ack), &ack));
+        assert_eq!(s.state, State::SynReceived);
+        send!(s, ack);
+        assert_eq!(s.state, State::Established);
+        assert_eq!(s.tuple, Some(TUPLE));
+        assert_eq!(s.remote_mss, 1220);
+        assert_eq!(s.remote_win_shift, 0);
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: cookie + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 58,
+                ..RECV_TEMPL
+            }]
+        );
+        assert_eq!(s.rx_buffer.dequeue_many(6), &b"abcdef"[..]);
+    }
+
+    #[test]
+    fn test_syn_cookie_invalid() {
+        let cookie = syn_cookie(None);
+
+        let mut s = socket_listen();
+        s.set_syn_cookies_enabled(true);
+        for ack in [
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 2,
+                ack_number: Some(cookie + 1),
+                ..SEND_TEMPL
+            },
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(cookie + 5),
+                ..SEND_TEMPL
+            },
+        ] {
+            assert!(!s
+                .socket
+                .accept_syn_cookie(&mut s.cx, &remote_ip_repr(&ack), &ack));
+        }
+        assert_eq!(s.state, State::Listen);
+
+        // Cookies expire after a couple of minutes.
+        let ack = TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(cookie + 1),
+            ..SEND_TEMPL
+        };
+        s.cx.set_now(Instant::from_secs(180));
+        assert!(!s
+            .socket
+            .accept_syn_cookie(&mut s.cx, &remote_ip_repr(&ack), &ack));
+        s.cx.set_now(Instant::from_secs(60));
+        assert!(s
+            .socket
+            .accept_syn_cookie(&mut s.cx, &remote_ip_repr(&ack), &ack));
+        assert_eq!(s.remote_mss, DEFAULT_MSS);
+    }
+
+    #[test]
+    fn test_syn_cookie_disabled() {
+        let syn = TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: REMOTE_SEQ,
+            ack_number: None,
+            ..SEND_TEMPL
+        };
+
+        let mut s = socket_established();
+        s.listen_endpoint = LISTEN_END;
+        assert!(s
+            .socket
+            .syn_cookie_reply(&mut s.cx, &remote_ip_repr(&syn), &syn)
+            .is_none());
+
+        // A listening socket handles the SYN itself.
+        let mut s = socket_listen();
+        s.set_syn_cookies_enabled(true);
+        assert!(s
+            .socket
+            .syn_cookie_reply(&mut s.cx, &remote_ip_repr(&syn), &syn)
+            .is_none());
+    }
+
+    // =========================================================================================//
+    // Tests for urgent data.
+    // =========================================================================================//
+
+    #[test]
+    fn test_urgent_recv() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                urgent_pointer: Some(4),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.recv_urgent(), Some(b'd'));
+        assert_eq!(s.recv_urgent(), None);
+
+        let mut buffer = [0; 16];
+        assert_eq!(s.peek(16), Ok(&b"abc"[..]));
+        assert_eq!(s.recv_slice(&mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], b"abc");
+        assert_eq!(s.recv_slice(&mut buffer), Ok(2));
+        assert_eq!(&buffer[..2], b"ef");
+
+        // A retransmission does not signal the urgent data again.
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                urgent_pointer: Some(4),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            },
+            Some(TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1 + 6),
+                window_len: 64,
+                ..RECV_TEMPL
+            })
+        );
+        assert_eq!(s.recv_urgent(), None);
+        assert_eq!(s.recv_slice(&mut buffer), Ok(0));
+    }
+
+    #[test]
+    fn test_urgent_recv_pointer_ahead() {
+        let mut s = socket_established();
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                urgent_pointer: Some(5),
+                payload: &b"abc"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.recv_urgent(), None);
+        let mut buffer = [0; 16];
+        assert_eq!(s.recv_slice(&mut buffer), Ok(3));
+        assert_eq!(&buffer[..3], b"abc");
+
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 3,
+                ack_number: Some(LOCAL_SEQ + 1),
+                urgent_pointer: Some(2),
+                payload: &b"def"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.recv_urgent(), Some(b'e'));
+        assert_eq!(s.recv_slice(&mut buffer), Ok(1));
+        assert_eq!(&buffer[..1], b"d");
+        assert_eq!(s.recv_slice(&mut buffer), Ok(1));
+        assert_eq!(&buffer[..1], b"f");
+    }
+
+    #[test]
+    fn test_urgent_recv_inline() {
+        let mut s = socket_established();
+        s.set_urgent_inline(true);
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1),
+                urgent_pointer: Some(4),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.recv_urgent(), None);
+        let mut buffer = [0; 16];
+        assert_eq!(s.recv_slice(&mut buffer), Ok(6));
+        assert_eq!(&buffer[..6], b"abcdef");
+    }
+
+    #[test]
+    fn test_urgent_send() {
+        let mut s = socket_established();
+        s.send_slice(b"abc").unwrap();
+        assert_eq!(s.send_urgent(b"!"), Ok(1));
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1,
+                ack_number: Some(REMOTE_SEQ + 1),
+                urgent_pointer: Some(4),
+                payload: &b"abc!"[..],
+                ..RECV_TEMPL
+            }]
+        );
+        send!(
+            s,
+            TcpRepr {
+                seq_number: REMOTE_SEQ + 1,
+                ack_number: Some(LOCAL_SEQ + 1 + 4),
+                ..SEND_TEMPL
+            }
+        );
+
+        s.send_slice(b"def").unwrap();
+        recv!(
+            s,
+            [TcpRepr {
+                seq_number: LOCAL_SEQ + 1 + 4,
+                ack_number: Some(REMOTE_SEQ + 1),
+                payload: &b"def"[..],
+                ..RECV_TEMPL
+            }]
+        );
+    }
+
+    // =========================================================================================//
+    // Tests for packet filtering.
+    // =========================================================================================//
+
+    #[test]
+    fn test_doesnt_accept_wrong_port() {
+        let mut s = socket_established();
+        s.rx_buffer = SocketBuffer::new(vec![0; 6]);
+        s.assembler = Assembler::new();
+
+        let tcp_repr = TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            dst_port: LOCAL_PORT + 1,
+            ..SEND_TEMPL
+        };
+        assert!(!s.socket.accepts(&mut s.cx, &SEND_IP_TEMPL, &tcp_repr));
+
+        let tcp_repr = TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            src_port: REMOTE_PORT + 1,
+            ..SEND_TEMPL
+        };
+        assert!(!s.socket.accepts(&mut s.cx, &SEND_IP_TEMPL, &tcp_repr));
     }
 
     #[test]
@@ -7142,6 +11108,335 @@ mod test {
         assert!(!s.socket.accepts(&mut s.cx, &ip_repr_wrong_dst, &tcp_repr));
     }
 
+    // =========================================================================================//
+    // Tests for connection statistics.
+    // =========================================================================================//
+
+    #[test]
+    fn test_info() {
+        let mut s = socket_syn_received();
+        recv!(s, time 50, Ok(TcpRepr {
+            control: TcpControl::Syn,
+            seq_number: LOCAL_SEQ,
+            ack_number: Some(REMOTE_SEQ + 1),
+            max_seg_size: Some(BASE_MSS),
+            ..RECV_TEMPL
+        }));
+        send!(s, time 100, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            ..SEND_TEMPL
+        });
+
+        let info = s.info();
+        assert_eq!(info.state, State::Established);
+        assert_eq!(info.established_at, Some(Instant::from_millis(100)));
+        assert_eq!(info.last_received_at, Some(Instant::from_millis(100)));
+        assert_eq!(info.last_sent_at, Some(Instant::from_millis(50)));
+        assert_eq!(info.send_window, 256);
+        assert_eq!(info.recv_window, 64);
+        assert_eq!(info.bytes_in_flight, 0);
+
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 200, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        let rto = s.info().rto;
+        recv!(s, time 200 + rto.total_millis() as i64, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+
+        let info = s.info();
+        assert_eq!(info.bytes_in_flight, 6);
+        assert_eq!(info.send_queue, 6);
+        assert_eq!(info.retransmissions, 1);
+        assert_eq!(info.consecutive_retransmissions, 1);
+
+        send!(s, time 1000, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 6),
+            ..SEND_TEMPL
+        });
+        let info = s.info();
+        assert_eq!(info.bytes_in_flight, 0);
+        assert_eq!(info.send_queue, 0);
+        assert_eq!(info.retransmissions, 1);
+        assert_eq!(info.consecutive_retransmissions, 0);
+    }
+
+    // =========================================================================================//
+    // Tests for TCP MD5 signatures.
+    // =========================================================================================//
+
+    #[test]
+    #[cfg(feature = "socket-tcp-md5")]
+    fn test_md5_signature() {
+        let mut s = socket_established();
+        s.set_md5_key(Some(b"secret"));
+        s.send_slice(b"abcdef").unwrap();
+
+        let mut bytes = vec![];
+        recv(&mut s, Instant::from_millis(0), |result| {
+            let repr = result.unwrap();
+            assert!(repr.md5_signature.is_some());
+            assert_eq!(repr.payload, b"abcdef");
+            bytes = vec![0; repr.buffer_len()];
+            repr.emit(
+                &mut TcpPacket::new_unchecked(&mut bytes[..]),
+                &LOCAL_ADDR.into(),
+                &REMOTE_ADDR.into(),
+                &crate::phy::ChecksumCapabilities::default(),
+            );
+        });
+
+        let packet = TcpPacket::new_unchecked(&bytes[..]);
+        let repr = TcpRepr::parse(
+            &packet,
+            &LOCAL_ADDR.into(),
+            &REMOTE_ADDR.into(),
+            &crate::phy::ChecksumCapabilities::default(),
+        )
+        .unwrap();
+        let mut ip_repr = SEND_IP_TEMPL;
+        ip_repr.set_payload_len(bytes.len());
+        assert!(s.authenticate(&ip_repr, &packet, &repr));
+
+        s.set_md5_key(Some(b"other"));
+        assert!(!s.authenticate(&ip_repr, &packet, &repr));
+        s.set_md5_key(None);
+        assert!(s.authenticate(&ip_repr, &packet, &repr));
+    }
+
+    #[test]
+    #[cfg(feature = "socket-tcp-md5")]
+    fn test_md5_signature_reply() {
+        let mut s = socket_established();
+        s.set_md5_key(Some(b"secret"));
+        let reply = send(
+            &mut s,
+            Instant::from_millis(0),
+            &TcpRepr {
+                seq_number: REMOTE_SEQ + 1 + 1000,
+                ack_number: Some(LOCAL_SEQ + 1),
+                payload: &b"abcdef"[..],
+                ..SEND_TEMPL
+            },
+        );
+        assert!(reply.unwrap().md5_signature.is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "socket-tcp-md5")]
+    fn test_md5_signature_mss() {
+        let mut s = socket_established_with_buffer_sizes(6000, 64);
+        s.remote_win_len = 6000;
+        s.remote_mss = 6000;
+        s.set_md5_key(Some(b"secret"));
+        s.send_slice(&[0; 6000]).unwrap();
+        recv(&mut s, Instant::from_millis(0), |result| {
+            let repr = result.unwrap();
+            assert_eq!(
+                repr.payload.len(),
+                BASE_MSS as usize - 20,
+                "the MD5 option reduces the space available for data"
+            );
+        });
+    }
+
+    // =========================================================================================//
+    // Tests for the TCP Authentication Option.
+    // =========================================================================================//
+
+    /// Transmit a segment from one socket to another, returning whether it was authenticated.
+    #[cfg(feature = "socket-tcp-ao")]
+    fn ao_deliver(from: &mut TestSocket, to: &mut TestSocket, tamper: bool) -> bool {
+        let mut segment = None;
+        from.socket
+            .dispatch(&mut from.cx, |_, (ip_repr, tcp_repr)| {
+                let mut bytes = vec![0; tcp_repr.buffer_len()];
+                tcp_repr.emit(
+                    &mut TcpPacket::new_unchecked(&mut bytes[..]),
+                    &ip_repr.src_addr(),
+                    &ip_repr.dst_addr(),
+                    &crate::phy::ChecksumCapabilities::ignored(),
+                );
+                segment = Some((ip_repr, bytes));
+                Ok::<_, ()>(())
+            })
+            .unwrap();
+        let (ip_repr, mut bytes) = segment.expect("a segment should be sent");
+        if tamper {
+            // Modify the window field.
+            bytes[15] ^= 1;
+        }
+
+        let packet = TcpPacket::new_checked(&bytes[..]).unwrap();
+        let repr = TcpRepr::parse(
+            &packet,
+            &ip_repr.src_addr(),
+            &ip_repr.dst_addr(),
+            &crate::phy::ChecksumCapabilities::ignored(),
+        )
+        .unwrap();
+        assert!(repr.authentication.is_some());
+        assert!(to.socket.accepts(&mut to.cx, &ip_repr, &repr));
+        if !to.socket.authenticate(&ip_repr, &packet, &repr) {
+            return false;
+        }
+        to.socket.process(&mut to.cx, &ip_repr, &repr);
+        true
+    }
+
+    #[cfg(feature = "socket-tcp-ao")]
+    fn ao_sockets(
+        client_keys: &'static [AoKey<'static>],
+        server_keys: &'static [AoKey<'static>],
+    ) -> (TestSocket, TestSocket) {
+        let mut client = socket();
+        let mut server = socket();
+        client.set_ao_keys(client_keys).unwrap();
+        server.set_ao_keys(server_keys).unwrap();
+        server.listen(REMOTE_PORT).unwrap();
+        client
+            .socket
+            .connect(&mut client.cx, REMOTE_END, LOCAL_END)
+            .unwrap();
+        // Make sure both ISNs are different.
+        client.local_seq_no = LOCAL_SEQ + 1234;
+        client.remote_last_seq = LOCAL_SEQ + 1234;
+        client.ao.set_local_isn(LOCAL_SEQ + 1234);
+        (client, server)
+    }
+
+    #[test]
+    #[cfg(feature = "socket-tcp-ao")]
+    fn test_ao_connection() {
+        const SHA1_KEYS: [[AoKey; 1]; 2] = [
+            [AoKey::new(1, 2, AoAlgorithm::HmacSha1, b"secret")],
+            [AoKey::new(2, 1, AoAlgorithm::HmacSha1, b"secret")],
+        ];
+        const CMAC_KEYS: [[AoKey; 1]; 2] = [
+            [AoKey::new(1, 2, AoAlgorithm::AesCmac, b"secret")],
+            [AoKey::new(2, 1, AoAlgorithm::AesCmac, b"secret")],
+        ];
+        for [client_keys, server_keys] in [&SHA1_KEYS, &CMAC_KEYS] {
+            let (mut client, mut server) = ao_sockets(client_keys, server_keys);
+
+            assert!(ao_deliver(&mut client, &mut server, false));
+            assert_eq!(server.state, State::SynReceived);
+            assert!(ao_deliver(&mut server, &mut client, false));
+            assert_eq!(client.state, State::Established);
+            assert!(ao_deliver(&mut client, &mut server, false));
+            assert_eq!(server.state, State::Established);
+
+            client.send_slice(b"hello").unwrap();
+            assert!(ao_deliver(&mut client, &mut server, false));
+            let mut data = [0; 5];
+            assert_eq!(server.recv_slice(&mut data), Ok(5));
+            assert_eq!(&data, b"hello");
+
+            server.send_slice(b"world").unwrap();
+            assert!(ao_deliver(&mut server, &mut client, false));
+            assert_eq!(client.recv_slice(&mut data), Ok(5));
+            assert_eq!(&data, b"world");
+
+            client.send_slice(b"tampered").unwrap();
+            assert!(!ao_deliver(&mut client, &mut server, true));
+            assert_eq!(server.recv_queue(), 0);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "socket-tcp-ao")]
+    fn test_ao_wrong_key() {
+        const CLIENT_KEYS: [AoKey; 1] = [AoKey::new(1, 2, AoAlgorithm::HmacSha1, b"secret")];
+        const OTHER_KEYS: [AoKey; 1] = [AoKey::new(2, 1, AoAlgorithm::HmacSha1, b"other")];
+        const OTHER_IDS: [AoKey; 1] = [AoKey::new(2, 3, AoAlgorithm::HmacSha1, b"secret")];
+
+        let (mut client, mut server) = ao_sockets(&CLIENT_KEYS, &OTHER_KEYS);
+        assert!(!ao_deliver(&mut client, &mut server, false));
+
+        let (mut client, mut server) = ao_sockets(&CLIENT_KEYS, &OTHER_IDS);
+        assert!(!ao_deliver(&mut client, &mut server, false));
+    }
+
+    #[test]
+    #[cfg(feature = "socket-tcp-ao")]
+    fn test_ao_exclude_options() {
+        const CLIENT_KEYS: [AoKey; 1] = {
+            let mut key = AoKey::new(1, 2, AoAlgorithm::AesCmac, b"secret");
+            key.include_options = false;
+            [key]
+        };
+        const SERVER_KEYS: [AoKey; 1] = {
+            let mut key = AoKey::new(2, 1, AoAlgorithm::AesCmac, b"secret");
+            key.include_options = false;
+            [key]
+        };
+        let (mut client, mut server) = ao_sockets(&CLIENT_KEYS, &SERVER_KEYS);
+        assert!(ao_deliver(&mut client, &mut server, false));
+        assert!(ao_deliver(&mut server, &mut client, false));
+        assert_eq!(client.state, State::Established);
+    }
+
+    #[test]
+    #[cfg(feature = "socket-tcp-ao")]
+    fn test_ao_rnext_key() {
+        const CLIENT_KEYS: [AoKey; 2] = [
+            AoKey::new(1, 2, AoAlgorithm::HmacSha1, b"secret"),
+            AoKey::new(3, 4, AoAlgorithm::AesCmac, b"new secret"),
+        ];
+        const SERVER_KEYS: [AoKey; 2] = [
+            AoKey::new(2, 1, AoAlgorithm::HmacSha1, b"secret"),
+            AoKey::new(4, 3, AoAlgorithm::AesCmac, b"new secret"),
+        ];
+        let (mut client, mut server) = ao_sockets(&CLIENT_KEYS, &SERVER_KEYS);
+        assert!(ao_deliver(&mut client, &mut server, false));
+        assert!(ao_deliver(&mut server, &mut client, false));
+        assert!(ao_deliver(&mut client, &mut server, false));
+
+        // The server asks the client to switch to the new key.
+        server.set_ao_current_key(4).unwrap();
+        server.send_slice(b"abc").unwrap();
+        assert!(ao_deliver(&mut server, &mut client, false));
+        assert_eq!(client.ao_current_key().unwrap().send_id, 3);
+        assert!(ao_deliver(&mut client, &mut server, false));
+    }
+
+    #[test]
+    #[cfg(feature = "socket-tcp-ao")]
+    fn test_ao_unauthenticated() {
+        let mut s = socket_established();
+        const KEYS: [AoKey; 1] = [AoKey::new(1, 2, AoAlgorithm::HmacSha1, b"secret")];
+        s.set_ao_keys(&KEYS).unwrap();
+        let repr = TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1),
+            ..SEND_TEMPL
+        };
+        let mut bytes = vec![0; repr.buffer_len()];
+        repr.emit(
+            &mut TcpPacket::new_unchecked(&mut bytes[..]),
+            &REMOTE_ADDR.into(),
+            &LOCAL_ADDR.into(),
+            &crate::phy::ChecksumCapabilities::ignored(),
+        );
+        let ip_repr = IpReprIpvX(IpvXRepr {
+            src_addr: REMOTE_ADDR,
+            dst_addr: LOCAL_ADDR,
+            next_header: IpProtocol::Tcp,
+            payload_len: bytes.len(),
+            hop_limit: 64,
+        });
+        assert!(!s.authenticate(&ip_repr, &TcpPacket::new_unchecked(&bytes[..]), &repr));
+    }
+
     // =========================================================================================//
     // Timer tests
     // =========================================================================================//
@@ -7184,4 +11479,53 @@ mod test {
             assert_eq!(r.retransmission_timeout(), Duration::from_millis(rto));
         }
     }
+
+    #[test]
+    fn test_rtt_hint_connect() {
+        let mut s = socket();
+        s.cx.set_rtt_hint(&REMOTE_ADDR.into(), Duration::from_millis(40));
+        s.socket.connect(&mut s.cx, REMOTE_END, LOCAL_END).unwrap();
+        assert_eq!(s.rtte.rtt, 40);
+        assert_eq!(s.rtte.deviation, 20);
+        assert_eq!(s.rtte.retransmission_timeout(), Duration::from_millis(120));
+    }
+
+    #[test]
+    fn test_rtt_hint_listen() {
+        let mut s = socket_listen();
+        s.cx.set_rtt_hint(&REMOTE_ADDR.into(), Duration::from_millis(40));
+        send!(
+            s,
+            TcpRepr {
+                control: TcpControl::Syn,
+                seq_number: REMOTE_SEQ,
+                ack_number: None,
+                ..SEND_TEMPL
+            }
+        );
+        assert_eq!(s.state, State::SynReceived);
+        assert_eq!(s.rtte.rtt, 40);
+    }
+
+    #[test]
+    fn test_rtt_hint_learned() {
+        let mut s = socket_established();
+        assert_eq!(s.cx.rtt_hint(&REMOTE_ADDR.into()), None);
+        s.send_slice(b"abcdef").unwrap();
+        recv!(s, time 1000, Ok(TcpRepr {
+            seq_number: LOCAL_SEQ + 1,
+            ack_number: Some(REMOTE_SEQ + 1),
+            payload:    &b"abcdef"[..],
+            ..RECV_TEMPL
+        }));
+        send!(s, time 1100, TcpRepr {
+            seq_number: REMOTE_SEQ + 1,
+            ack_number: Some(LOCAL_SEQ + 1 + 6),
+            ..SEND_TEMPL
+        });
+        assert_eq!(
+            s.cx.rtt_hint(&REMOTE_ADDR.into()),
+            Some(Duration::from_millis(275))
+        );
+    }
 }
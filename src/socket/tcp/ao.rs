@@ -0,0 +1,529 @@
+// TCP Authentication Option, as described in RFC 5925, with the cryptographic algorithms
+// described in RFC 5926.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use core::fmt;
+
+use super::crypto::{Cmac, HmacSha1};
+use crate::phy::ChecksumCapabilities;
+use crate::wire::{
+    IpAddress, IpProtocol, IpRepr, TcpAuthOption, TcpControl, TcpOption, TcpPacket, TcpRepr,
+    TcpSeqNumber, TCP_HEADER_LEN,
+};
+
+/// Maximum length of a TCP-AO master key, in octets.
+pub const MAX_KEY_LEN: usize = 80;
+
+/// Length of the TCP-AO option, in octets.
+pub(super) const OPTION_LEN: usize = 16;
+
+/// Algorithm used to derive traffic keys and compute message authentication codes,
+/// as described in RFC 5926.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AoAlgorithm {
+    /// KDF_HMAC_SHA1 and HMAC-SHA-1-96.
+    HmacSha1,
+    /// KDF_AES_128_CMAC and AES-128-CMAC-96.
+    AesCmac,
+}
+
+impl AoAlgorithm {
+    /// Return the length of a traffic key, in octets.
+    const fn traffic_key_len(self) -> usize {
+        match self {
+            AoAlgorithm::HmacSha1 => 20,
+            AoAlgorithm::AesCmac => 16,
+        }
+    }
+}
+
+/// A TCP-AO master key tuple, as described in RFC 5925 § 3.1.
+///
+/// The connection identifier of the tuple is implicitly the connection of the socket
+/// the key is added to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AoKey<'a> {
+    /// Identifier sent in segments authenticated with this key.
+    pub send_id: u8,
+    /// Identifier of the received segments authenticated with this key.
+    pub recv_id: u8,
+    /// Algorithm used with this key.
+    pub algorithm: AoAlgorithm,
+    /// Whether TCP options other than TCP-AO are covered by the MAC.
+    pub include_options: bool,
+    key: &'a [u8],
+}
+
+impl<'a> AoKey<'a> {
+    /// Create a master key tuple, covering TCP options.
+    ///
+    /// # Panics
+    /// This function panics if the key is longer than 80 octets.
+    pub const fn new(send_id: u8, recv_id: u8, algorithm: AoAlgorithm, key: &'a [u8]) -> AoKey<'a> {
+        assert!(
+            key.len() <= MAX_KEY_LEN,
+            "TCP-AO key must not exceed 80 octets"
+        );
+        AoKey {
+            send_id,
+            recv_id,
+            algorithm,
+            include_options: true,
+            key,
+        }
+    }
+
+    /// Return the master key.
+    pub fn key(&self) -> &'a [u8] {
+        self.key
+    }
+}
+
+/// Error returned by the TCP-AO key management methods of [`Socket`](super::Socket).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AoKeyError {
+    Duplicate,
+    NotFound,
+}
+
+impl fmt::Display for AoKeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AoKeyError::Duplicate => write!(f, "duplicate key identifier"),
+            AoKeyError::NotFound => write!(f, "key not found"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AoKeyError {}
+
+/// A keyed pseudo-random function, used both as KDF and MAC.
+enum Prf {
+    HmacSha1(HmacSha1),
+    AesCmac(Cmac),
+}
+
+impl Prf {
+    fn new(algorithm: AoAlgorithm, key: &[u8]) -> Prf {
+        match algorithm {
+            AoAlgorithm::HmacSha1 => Prf::HmacSha1(HmacSha1::new(key)),
+            AoAlgorithm::AesCmac => {
+                // RFC 5926 § 3.1.1.2: keys that aren't 128 bits long are first
+                // condensed using AES-128-CMAC with a zero key.
+                let key = match key.try_into() {
+                    Ok(key) => key,
+                    Err(_) => {
+                        let mut cmac = Cmac::new(&[0; 16]);
+                        cmac.update(key);
+                        cmac.finalize()
+                    }
+                };
+                Prf::AesCmac(Cmac::new(&key))
+            }
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Prf::HmacSha1(hmac) => hmac.update(data),
+            Prf::AesCmac(cmac) => cmac.update(data),
+        }
+    }
+
+    fn finalize(self) -> [u8; 20] {
+        let mut output = [0; 20];
+        match self {
+            Prf::HmacSha1(hmac) => output = hmac.finalize(),
+            Prf::AesCmac(cmac) => output[..16].copy_from_slice(&cmac.finalize()),
+        }
+        output
+    }
+}
+
+/// Find the offset of the TCP-AO option in a TCP header.
+fn find_option(header: &[u8]) -> Option<usize> {
+    let mut offset = TCP_HEADER_LEN;
+    while offset < header.len() {
+        match TcpOption::parse(&header[offset..]) {
+            Ok((_, TcpOption::Authentication(_))) => return Some(offset),
+            Ok((_, TcpOption::EndOfList)) | Err(_) => return None,
+            Ok((rest, _)) => offset = header.len() - rest.len(),
+        }
+    }
+    None
+}
+
+/// Compute the MAC of a segment, given its full header and payload (RFC 5925 § 5.1).
+fn mac(
+    key: &AoKey,
+    sne: u32,
+    ip_repr: &IpRepr,
+    (src_isn, dst_isn): (TcpSeqNumber, TcpSeqNumber),
+    header: &[u8],
+    payload: &[u8],
+) -> [u8; 12] {
+    let (src_addr, dst_addr) = (ip_repr.src_addr(), ip_repr.dst_addr());
+    let tcp_len = header.len() + payload.len();
+
+    // Derive the traffic key from the master key and the connection (RFC 5926 § 3.1.1).
+    let mut kdf = Prf::new(key.algorithm, key.key());
+    kdf.update(&[1]);
+    kdf.update(b"TCP-AO");
+    kdf.update(src_addr.as_bytes());
+    kdf.update(dst_addr.as_bytes());
+    kdf.update(&header[..4]);
+    kdf.update(&(src_isn.0 as u32).to_be_bytes());
+    kdf.update(&(dst_isn.0 as u32).to_be_bytes());
+    kdf.update(&((key.algorithm.traffic_key_len() * 8) as u16).to_be_bytes());
+    let traffic_key = kdf.finalize();
+
+    let mut prf = Prf::new(
+        key.algorithm,
+        &traffic_key[..key.algorithm.traffic_key_len()],
+    );
+
+    // 1. the sequence number extension
+    prf.update(&sne.to_be_bytes());
+
+    // 2. the IP pseudo-header
+    match (src_addr, dst_addr) {
+        #[cfg(feature = "proto-ipv4")]
+        (IpAddress::Ipv4(_), IpAddress::Ipv4(_)) => {
+            let mut proto_len = [0; 4];
+            proto_len[1] = IpProtocol::Tcp.into();
+            NetworkEndian::write_u16(&mut proto_len[2..4], tcp_len as u16);
+            prf.update(src_addr.as_bytes());
+            prf.update(dst_addr.as_bytes());
+            prf.update(&proto_len);
+        }
+        #[cfg(feature = "proto-ipv6")]
+        (IpAddress::Ipv6(_), IpAddress::Ipv6(_)) => {
+            let mut proto_len = [0; 8];
+            NetworkEndian::write_u32(&mut proto_len[0..4], tcp_len as u32);
+            proto_len[7] = IpProtocol::Tcp.into();
+            prf.update(src_addr.as_bytes());
+            prf.update(dst_addr.as_bytes());
+            prf.update(&proto_len);
+        }
+        #[allow(unreachable_patterns)]
+        _ => unreachable!(),
+    }
+
+    // 3. the TCP header, with the checksum and the MAC set to zero
+    let mut buffer = [0; 60];
+    let buffer = &mut buffer[..header.len()];
+    buffer.copy_from_slice(header);
+    TcpPacket::new_unchecked(&mut buffer[..]).set_checksum(0);
+    let offset = find_option(buffer).unwrap_or(header.len());
+    if offset < header.len() {
+        buffer[offset + 4..offset + OPTION_LEN].fill(0);
+    }
+    if key.include_options {
+        prf.update(buffer);
+    } else {
+        prf.update(&buffer[..TCP_HEADER_LEN]);
+        prf.update(&buffer[offset..(offset + OPTION_LEN).min(header.len())]);
+    }
+
+    // 4. the TCP data
+    prf.update(payload);
+
+    let mut mac = [0; 12];
+    mac.copy_from_slice(&prf.finalize()[..12]);
+    mac
+}
+
+/// Tracking of the sequence number extension of one direction of a connection,
+/// i.e. the number of times the sequence number has wrapped around.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Sne {
+    high: u32,
+    last_seq: u32,
+}
+
+impl Sne {
+    fn new(isn: TcpSeqNumber) -> Sne {
+        Sne {
+            high: 0,
+            last_seq: isn.0 as u32,
+        }
+    }
+
+    /// Return the sequence number extension of a sequence number, along with the updated
+    /// tracking state.
+    fn extend(self, seq: TcpSeqNumber) -> (u32, Sne) {
+        let seq = seq.0 as u32;
+        if seq < self.last_seq && self.last_seq - seq > 1 << 31 {
+            // The sequence number has wrapped around.
+            let high = self.high.wrapping_add(1);
+            (
+                high,
+                Sne {
+                    high,
+                    last_seq: seq,
+                },
+            )
+        } else if seq > self.last_seq && seq - self.last_seq > 1 << 31 {
+            // A sequence number from before the last wrap-around.
+            (self.high.wrapping_sub(1), self)
+        } else {
+            (
+                self.high,
+                Sne {
+                    high: self.high,
+                    last_seq: self.last_seq.max(seq),
+                },
+            )
+        }
+    }
+}
+
+/// The TCP-AO keys and per-connection state of a socket.
+#[derive(Debug)]
+pub(super) struct AoState<'a> {
+    keys: &'a [AoKey<'a>],
+    current_key: usize,
+    local_isn: TcpSeqNumber,
+    remote_isn: TcpSeqNumber,
+    send_sne: Sne,
+    recv_sne: Sne,
+}
+
+impl<'a> AoState<'a> {
+    pub(super) fn new() -> AoState<'a> {
+        AoState {
+            keys: &[],
+            current_key: 0,
+            local_isn: TcpSeqNumber::default(),
+            remote_isn: TcpSeqNumber::default(),
+            send_sne: Sne::default(),
+            recv_sne: Sne::default(),
+        }
+    }
+
+    /// Forget the state of the connection, but keep the keys.
+    pub(super) fn reset(&mut self) {
+        self.local_isn = TcpSeqNumber::default();
+        self.remote_isn = TcpSeqNumber::default();
+        self.send_sne = Sne::default();
+        self.recv_sne = Sne::default();
+    }
+
+    pub(super) fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    pub(super) fn keys(&self) -> &'a [AoKey<'a>] {
+        self.keys
+    }
+
+    pub(super) fn current_key(&self) -> Option<&'a AoKey<'a>> {
+        self.keys.get(self.current_key)
+    }
+
+    /// Replace the keys, keeping the current one if it is among the new keys.
+    pub(super) fn set_keys(&mut self, keys: &'a [AoKey<'a>]) -> Result<(), AoKeyError> {
+        for (index, key) in keys.iter().enumerate() {
+            if keys[..index]
+                .iter()
+                .any(|k| k.send_id == key.send_id || k.recv_id == key.recv_id)
+            {
+                return Err(AoKeyError::Duplicate);
+            }
+        }
+        let current_id = self.current_key().map(|key| key.send_id);
+        self.keys = keys;
+        self.current_key = current_id
+            .and_then(|send_id| self.find_key(send_id).ok())
+            .unwrap_or(0);
+        Ok(())
+    }
+
+    pub(super) fn set_current_key(&mut self, send_id: u8) -> Result<(), AoKeyError> {
+        self.current_key = self.find_key(send_id)?;
+        Ok(())
+    }
+
+    fn find_key(&self, send_id: u8) -> Result<usize, AoKeyError> {
+        self.keys
+            .iter()
+            .position(|k| k.send_id == send_id)
+            .ok_or(AoKeyError::NotFound)
+    }
+
+    pub(super) fn set_local_isn(&mut self, isn: TcpSeqNumber) {
+        self.local_isn = isn;
+        self.send_sne = Sne::new(isn);
+    }
+
+    pub(super) fn set_remote_isn(&mut self, isn: TcpSeqNumber) {
+        self.remote_isn = isn;
+        self.recv_sne = Sne::new(isn);
+    }
+
+    /// Add the TCP-AO option to an outgoing segment, if any key is configured.
+    pub(super) fn sign(&mut self, ip_repr: &mut IpRepr, repr: &mut TcpRepr) {
+        let Some(key) = self.keys.get(self.current_key) else {
+            return;
+        };
+
+        // The option must be present when computing the header length and data offset.
+        repr.authentication = Some(TcpAuthOption {
+            key_id: key.send_id,
+            rnext_key_id: key.recv_id,
+            mac: [0; 12],
+        });
+        ip_repr.set_payload_len(repr.buffer_len());
+
+        let header_len = repr.header_len();
+        let mut header = [0; 60];
+        TcpRepr {
+            payload: &[],
+            ..*repr
+        }
+        .emit(
+            &mut TcpPacket::new_unchecked(&mut header[..header_len]),
+            &ip_repr.src_addr(),
+            &ip_repr.dst_addr(),
+            &ChecksumCapabilities::ignored(),
+        );
+
+        // RFC 5925 § 5.2: the remote ISN is zero in the initial SYN.
+        let remote_isn = match (repr.control, repr.ack_number) {
+            (TcpControl::Syn, None) => TcpSeqNumber(0),
+            _ => self.remote_isn,
+        };
+        let (sne, send_sne) = self.send_sne.extend(repr.seq_number);
+        self.send_sne = send_sne;
+
+        let mac = mac(
+            key,
+            sne,
+            ip_repr,
+            (self.local_isn, remote_isn),
+            &header[..header_len],
+            repr.payload,
+        );
+        if let Some(auth) = &mut repr.authentication {
+            auth.mac = mac;
+        }
+    }
+
+    /// Check the TCP-AO option of an incoming segment.
+    pub(super) fn verify(
+        &mut self,
+        ip_repr: &IpRepr,
+        packet: &TcpPacket<&[u8]>,
+        repr: &TcpRepr,
+    ) -> bool {
+        let Some(auth) = repr.authentication else {
+            net_debug!("TCP-AO option missing");
+            return false;
+        };
+        let Some(key) = self.keys.iter().find(|k| k.recv_id == auth.key_id) else {
+            net_debug!("TCP-AO key {} not found", auth.key_id);
+            return false;
+        };
+
+        // RFC 5925 § 5.2: the ISNs of SYN segments are not known yet; the local one is
+        // zero in the initial SYN.
+        let (sne, recv_sne, isns) = match (repr.control, repr.ack_number) {
+            (TcpControl::Syn, None) => (0, self.recv_sne, (repr.seq_number, TcpSeqNumber(0))),
+            (TcpControl::Syn, Some(_)) => (0, self.recv_sne, (repr.seq_number, self.local_isn)),
+            _ => {
+                let (sne, recv_sne) = self.recv_sne.extend(repr.seq_number);
+                (sne, recv_sne, (self.remote_isn, self.local_isn))
+            }
+        };
+
+        let header_len = packet.header_len() as usize;
+        let expected = mac(
+            key,
+            sne,
+            ip_repr,
+            isns,
+            &packet.as_ref()[..header_len],
+            packet.payload(),
+        );
+
+        // Avoid leaking how much of the MAC matched through timing.
+        let diff = auth
+            .mac
+            .iter()
+            .zip(expected.iter())
+            .fold(0, |diff, (a, b)| diff | (a ^ b));
+        if diff != 0 {
+            net_debug!("TCP-AO MAC mismatch");
+            return false;
+        }
+
+        self.recv_sne = recv_sne;
+
+        // RFC 5925 § 7.5.2: switch to the key requested by the remote, if we have it.
+        if let Ok(index) = self.find_key(auth.rnext_key_id) {
+            self.current_key = index;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sne() {
+        let sne = Sne::new(TcpSeqNumber(-100));
+        let (high, sne) = sne.extend(TcpSeqNumber(-50));
+        assert_eq!(high, 0);
+        let (high, sne) = sne.extend(TcpSeqNumber(5));
+        assert_eq!(high, 1);
+        let (high, sne) = sne.extend(TcpSeqNumber(-5));
+        assert_eq!(high, 0, "retransmission from before the wrap-around");
+        let (high, sne) = sne.extend(TcpSeqNumber(100));
+        assert_eq!(high, 1);
+        let (high, sne) = sne.extend(TcpSeqNumber(i32::MAX));
+        assert_eq!(high, 1);
+        let (high, _) = sne.extend(TcpSeqNumber(i32::MIN + 10));
+        assert_eq!(high, 1);
+    }
+
+    #[test]
+    fn test_keys() {
+        let keys = [
+            AoKey::new(1, 2, AoAlgorithm::HmacSha1, b"key1"),
+            AoKey::new(3, 4, AoAlgorithm::AesCmac, b"key2"),
+            AoKey::new(5, 6, AoAlgorithm::AesCmac, b"key3"),
+        ];
+        let mut ao = AoState::new();
+        assert!(!ao.is_enabled());
+        assert_eq!(ao.current_key(), None);
+        ao.set_keys(&keys[..2]).unwrap();
+        assert!(ao.is_enabled());
+        assert_eq!(ao.current_key().unwrap().send_id, 1);
+
+        ao.set_current_key(3).unwrap();
+        assert_eq!(ao.current_key().unwrap().send_id, 3);
+        assert_eq!(ao.set_current_key(5), Err(AoKeyError::NotFound));
+
+        // The current key is kept if it is among the new keys.
+        ao.set_keys(&keys[1..]).unwrap();
+        assert_eq!(ao.current_key().unwrap().key(), b"key2");
+        ao.set_keys(&keys[2..]).unwrap();
+        assert_eq!(ao.current_key().unwrap().send_id, 5);
+
+        let duplicate = [
+            AoKey::new(1, 2, AoAlgorithm::HmacSha1, b"key1"),
+            AoKey::new(1, 5, AoAlgorithm::HmacSha1, b"key3"),
+        ];
+        assert_eq!(ao.set_keys(&duplicate), Err(AoKeyError::Duplicate));
+        assert_eq!(ao.keys(), &keys[2..]);
+
+        ao.set_keys(&[]).unwrap();
+        assert!(!ao.is_enabled());
+    }
+}
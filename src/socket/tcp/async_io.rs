@@ -0,0 +1,344 @@
+use core::cell::RefCell;
+#[cfg(feature = "futures-io")]
+use core::pin::Pin;
+use core::task::{Context, Poll};
+#[cfg(feature = "futures-io")]
+use std::io;
+
+use super::{RecvError, SendError, Socket, State};
+use crate::iface::{SocketHandle, SocketSet};
+
+/// An asynchronous adapter for a TCP socket.
+///
+/// With the `futures-io` feature, the adapter implements [AsyncRead](futures_io::AsyncRead)
+/// and [AsyncWrite](futures_io::AsyncWrite); with the `embedded-io-async` feature, it
+/// implements [Read](embedded_io_async::Read) and [Write](embedded_io_async::Write).
+///
+/// The adapter refers to a socket in a socket set shared through a [RefCell]; the task
+/// polling the interface must only borrow the socket set for the duration of each
+/// [Interface::poll](crate::iface::Interface::poll) call. Operations that cannot make
+/// progress register the task waker with the socket (see
+/// [register_recv_waker](Socket::register_recv_waker) and
+/// [register_send_waker](Socket::register_send_waker)) and return `Poll::Pending`.
+pub struct AsyncStream<'a, 'b> {
+    sockets: &'a RefCell<SocketSet<'b>>,
+    handle: SocketHandle,
+}
+
+/// Error returned by the operations of an [AsyncStream].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum Error {
+    /// Data was read from a socket that is not connected.
+    NotConnected,
+    /// Data was written to, or flushed into, a socket that is closed.
+    BrokenPipe,
+}
+
+impl<'a, 'b> AsyncStream<'a, 'b> {
+    /// Create an adapter for the TCP socket `handle` in `sockets`.
+    ///
+    /// # Panics
+    /// Reading or writing panics if the handle does not refer to a TCP socket in `sockets`,
+    /// or if the socket set is already borrowed.
+    pub fn new(sockets: &'a RefCell<SocketSet<'b>>, handle: SocketHandle) -> Self {
+        AsyncStream { sockets, handle }
+    }
+
+    /// Return the handle of the underlying socket.
+    pub fn handle(&self) -> SocketHandle {
+        self.handle
+    }
+
+    fn with_socket<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Socket<'b>) -> R,
+    {
+        f(self.sockets.borrow_mut().get_mut::<Socket>(self.handle))
+    }
+
+    fn poll_recv(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<Result<usize, Error>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        self.with_socket(|socket| match socket.recv_slice(buf) {
+            Ok(0) => {
+                socket.register_recv_waker(cx.waker());
+                Poll::Pending
+            }
+            Ok(size) => Poll::Ready(Ok(size)),
+            Err(RecvError::Finished) => Poll::Ready(Ok(0)),
+            Err(RecvError::InvalidState) if is_connecting(socket) => {
+                socket.register_recv_waker(cx.waker());
+                Poll::Pending
+            }
+            Err(RecvError::InvalidState) => Poll::Ready(Err(Error::NotConnected)),
+        })
+    }
+
+    fn poll_send(&self, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize, Error>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        self.with_socket(|socket| match socket.send_slice(buf) {
+            Ok(0) => {
+                socket.register_send_waker(cx.waker());
+                Poll::Pending
+            }
+            Ok(size) => Poll::Ready(Ok(size)),
+            Err(SendError::InvalidState) if is_connecting(socket) => {
+                socket.register_send_waker(cx.waker());
+                Poll::Pending
+            }
+            Err(SendError::InvalidState) => Poll::Ready(Err(Error::BrokenPipe)),
+        })
+    }
+
+    fn poll_sent(&self, cx: &mut Context<'_>) -> Poll<Result<(), Error>> {
+        self.with_socket(|socket| {
+            if socket.send_queue() == 0 {
+                Poll::Ready(Ok(()))
+            } else if !socket.is_active() {
+                Poll::Ready(Err(Error::BrokenPipe))
+            } else {
+                socket.register_send_waker(cx.waker());
+                Poll::Pending
+            }
+        })
+    }
+}
+
+/// Whether the socket may still become connected, in which case the caller should wait for it.
+fn is_connecting(socket: &Socket) -> bool {
+    matches!(
+        socket.state(),
+        State::Listen | State::SynSent | State::SynReceived
+    )
+}
+
+#[cfg(feature = "futures-io")]
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::NotConnected => io::ErrorKind::NotConnected.into(),
+            Error::BrokenPipe => io::ErrorKind::BrokenPipe.into(),
+        }
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<'a, 'b> futures_io::AsyncRead for AsyncStream<'a, 'b> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_recv(cx, buf).map_err(io::Error::from)
+    }
+}
+
+#[cfg(feature = "futures-io")]
+impl<'a, 'b> futures_io::AsyncWrite for AsyncStream<'a, 'b> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_send(cx, buf).map_err(io::Error::from)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.poll_sent(cx).map_err(io::Error::from)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.with_socket(|socket| socket.close());
+        self.poll_flush(cx)
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl From<Error> for embedded_io_async::ErrorKind {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::NotConnected => embedded_io_async::ErrorKind::NotConnected,
+            Error::BrokenPipe => embedded_io_async::ErrorKind::BrokenPipe,
+        }
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<'a, 'b> embedded_io_async::ErrorType for AsyncStream<'a, 'b> {
+    type Error = embedded_io_async::ErrorKind;
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<'a, 'b> embedded_io_async::Read for AsyncStream<'a, 'b> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        core::future::poll_fn(|cx| self.poll_recv(cx, buf))
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<'a, 'b> embedded_io_async::Write for AsyncStream<'a, 'b> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        core::future::poll_fn(|cx| self.poll_send(cx, buf))
+            .await
+            .map_err(Into::into)
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        core::future::poll_fn(|cx| self.poll_sent(cx))
+            .await
+            .map_err(Into::into)
+    }
+}
+
+#[cfg(all(test, feature = "medium-ip", feature = "proto-ipv4"))]
+mod test {
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+    use std::vec;
+
+    use super::*;
+    use crate::iface::{Config, Interface};
+    use crate::phy::{Loopback, Medium};
+    use crate::socket::tcp::SocketBuffer;
+    use crate::time::Instant;
+    use crate::wire::{HardwareAddress, IpAddress, IpCidr};
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Set up a server socket listening on port 1234 and a client socket connecting to it,
+    /// both over a loopback interface.
+    fn setup<'a>() -> (
+        Interface,
+        Loopback,
+        RefCell<SocketSet<'a>>,
+        SocketHandle,
+        SocketHandle,
+    ) {
+        let mut device = Loopback::new(Medium::Ip);
+        let config = Config::new(HardwareAddress::Ip);
+        let mut iface = Interface::new(config, &mut device, Instant::ZERO);
+        iface.update_ip_addrs(|ip_addrs| {
+            ip_addrs
+                .push(IpCidr::new(IpAddress::v4(127, 0, 0, 1), 8))
+                .unwrap();
+        });
+
+        let mut sockets = SocketSet::new(vec![]);
+        let server = sockets.add(Socket::new(
+            SocketBuffer::new(vec![0; 64]),
+            SocketBuffer::new(vec![0; 64]),
+        ));
+        let client = sockets.add(Socket::new(
+            SocketBuffer::new(vec![0; 64]),
+            SocketBuffer::new(vec![0; 64]),
+        ));
+        sockets.get_mut::<Socket>(server).listen(1234).unwrap();
+        sockets
+            .get_mut::<Socket>(client)
+            .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1234), 65000)
+            .unwrap();
+        (iface, device, RefCell::new(sockets), client, server)
+    }
+
+    #[cfg(feature = "futures-io")]
+    #[test]
+    fn test_read_write() {
+        use futures_io::{AsyncRead, AsyncWrite};
+
+        let (mut iface, mut device, sockets, client, server) = setup();
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut client = AsyncStream::new(&sockets, client);
+        let mut server = AsyncStream::new(&sockets, server);
+        let mut buf = [0; 16];
+
+        assert!(Pin::new(&mut client)
+            .poll_write(&mut cx, b"hello")
+            .is_pending());
+        assert!(Pin::new(&mut server)
+            .poll_read(&mut cx, &mut buf)
+            .is_pending());
+
+        let mut written = false;
+        for ms in 0..100 {
+            iface.poll(
+                Instant::from_millis(ms),
+                &mut device,
+                &mut sockets.borrow_mut(),
+            );
+            if !written {
+                match Pin::new(&mut client).poll_write(&mut cx, b"hello") {
+                    Poll::Ready(Ok(size)) => {
+                        assert_eq!(size, 5);
+                        written = true;
+                    }
+                    Poll::Ready(Err(e)) => panic!("{e}"),
+                    Poll::Pending => (),
+                }
+            }
+            if let Poll::Ready(result) = Pin::new(&mut server).poll_read(&mut cx, &mut buf) {
+                assert_eq!(result.unwrap(), 5);
+                assert_eq!(&buf[..5], b"hello");
+                return;
+            }
+        }
+        panic!("data was not received")
+    }
+
+    #[cfg(feature = "embedded-io-async")]
+    #[test]
+    fn test_embedded_read_write() {
+        use core::future::Future;
+        use embedded_io_async::{Read, Write};
+
+        let (mut iface, mut device, sockets, client, server) = setup();
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        let mut client = AsyncStream::new(&sockets, client);
+        let mut server = AsyncStream::new(&sockets, server);
+        let mut buf = [0; 16];
+
+        let mut received = false;
+        {
+            let mut write = core::pin::pin!(client.write(b"hello"));
+            let mut read = core::pin::pin!(server.read(&mut buf));
+            assert!(write.as_mut().poll(&mut cx).is_pending());
+            assert!(read.as_mut().poll(&mut cx).is_pending());
+
+            let mut written = false;
+            for ms in 0..100 {
+                iface.poll(
+                    Instant::from_millis(ms),
+                    &mut device,
+                    &mut sockets.borrow_mut(),
+                );
+                if !written {
+                    if let Poll::Ready(result) = write.as_mut().poll(&mut cx) {
+                        assert_eq!(result, Ok(5));
+                        written = true;
+                    }
+                }
+                if let Poll::Ready(result) = read.as_mut().poll(&mut cx) {
+                    assert_eq!(result, Ok(5));
+                    received = true;
+                    break;
+                }
+            }
+        }
+        assert!(received, "data was not received");
+        assert_eq!(&buf[..5], b"hello");
+    }
+}
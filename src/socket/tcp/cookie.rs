@@ -0,0 +1,182 @@
+// Initial sequence numbers derived from a keyed hash of the connection 4-tuple.
+//
+// Regular initial sequence numbers follow RFC 6528: a timer ticking every 4 microseconds, plus
+// a keyed hash of the 4-tuple, so that successive connections with the same 4-tuple use
+// increasing sequence numbers, while those of other connections cannot be guessed off-path.
+//
+// SYN cookies let a listening socket complete a handshake without keeping any state until
+// the final ACK arrives. The cookie is the initial sequence number of the SYN|ACK:
+//
+//   bits 31..27: a counter incremented every 64 seconds, truncated to 5 bits;
+//   bits 26..2:  a keyed hash of the 4-tuple, the remote initial sequence number and
+//                the counter;
+//   bits 1..0:   the index of the remote MSS in a table of common values.
+//
+// The hash is SipHash-2-4.
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::siphash::siphash;
+use crate::time::Instant;
+use crate::wire::{IpEndpoint, TcpSeqNumber};
+
+/// The remote MSS values that can be encoded in a cookie, in increasing order.
+const MSS_TABLE: [u16; 4] = [536, 1220, 1440, 1460];
+
+/// Period of the cookie counter, in seconds.
+const PERIOD: u64 = 64;
+
+/// Number of counter periods during which a cookie is accepted.
+const LIFETIME: u32 = 2;
+
+fn counter(now: Instant) -> u32 {
+    (now.total_millis() as u64 / 1000 / PERIOD) as u32
+}
+
+/// Hash the 4-tuple of a connection, followed by `extra` words.
+fn hash(key: &[u64; 2], local: IpEndpoint, remote: IpEndpoint, extra: &[u32]) -> u32 {
+    let mut data = [0; 44];
+    let mut len = 0;
+    for addr in [local.addr, remote.addr] {
+        let bytes = addr.as_bytes();
+        data[len..len + bytes.len()].copy_from_slice(bytes);
+        len += bytes.len();
+    }
+    NetworkEndian::write_u16(&mut data[len..], local.port);
+    NetworkEndian::write_u16(&mut data[len + 2..], remote.port);
+    len += 4;
+    for word in extra {
+        NetworkEndian::write_u32(&mut data[len..], *word);
+        len += 4;
+    }
+
+    siphash(key, &data[..len]) as u32
+}
+
+/// Generate the initial sequence number of a connection, as described in RFC 6528.
+pub(super) fn initial_seq_no(
+    key: &[u64; 2],
+    now: Instant,
+    local: IpEndpoint,
+    remote: IpEndpoint,
+) -> TcpSeqNumber {
+    let timer = (now.total_micros() / 4) as u32;
+    TcpSeqNumber(timer.wrapping_add(hash(key, local, remote, &[])) as i32)
+}
+
+/// Generate the cookie to send in the SYN|ACK answering a SYN, given the remote
+/// initial sequence number and MSS.
+pub(super) fn generate(
+    key: &[u64; 2],
+    now: Instant,
+    local: IpEndpoint,
+    remote: IpEndpoint,
+    remote_isn: TcpSeqNumber,
+    remote_mss: u16,
+) -> TcpSeqNumber {
+    let mss_index = MSS_TABLE
+        .iter()
+        .rposition(|&mss| mss <= remote_mss)
+        .unwrap_or(0) as u32;
+    let counter = counter(now);
+    let hash = hash(key, local, remote, &[remote_isn.0 as u32, counter]);
+    TcpSeqNumber(((counter << 27) | (hash & 0x07ff_fffc) | mss_index) as i32)
+}
+
+/// Check the cookie acknowledged by the final ACK of a handshake, given the remote initial
+/// sequence number, and return the remote MSS it encodes if it is valid.
+pub(super) fn validate(
+    key: &[u64; 2],
+    now: Instant,
+    local: IpEndpoint,
+    remote: IpEndpoint,
+    remote_isn: TcpSeqNumber,
+    cookie: TcpSeqNumber,
+) -> Option<u16> {
+    let cookie = cookie.0 as u32;
+    let counter = (0..LIFETIME)
+        .map(|age| counter(now).wrapping_sub(age))
+        .find(|counter| counter & 0x1f == cookie >> 27)?;
+    let hash = hash(key, local, remote, &[remote_isn.0 as u32, counter]);
+    if (hash ^ cookie) & 0x07ff_fffc != 0 {
+        return None;
+    }
+    Some(MSS_TABLE[(cookie & 0x3) as usize])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::time::Duration;
+    use crate::wire::IpAddress;
+
+    const KEY: [u64; 2] = [0x0706050403020100, 0x0f0e0d0c0b0a0908];
+
+    fn endpoints() -> (IpEndpoint, IpEndpoint) {
+        #[cfg(feature = "proto-ipv4")]
+        let (local, remote) = (IpAddress::v4(192, 168, 1, 1), IpAddress::v4(192, 168, 1, 2));
+        #[cfg(not(feature = "proto-ipv4"))]
+        let (local, remote) = (
+            IpAddress::v6(0xfe80, 0, 0, 0, 0, 0, 0, 1),
+            IpAddress::v6(0xfe80, 0, 0, 0, 0, 0, 0, 2),
+        );
+        (IpEndpoint::new(local, 80), IpEndpoint::new(remote, 49500))
+    }
+
+    #[test]
+    fn test_initial_seq_no() {
+        let (local, remote) = endpoints();
+        let now = Instant::from_secs(1000);
+        let isn = initial_seq_no(&KEY, now, local, remote);
+
+        // Later connections with the same 4-tuple use higher sequence numbers.
+        let later = now + Duration::from_millis(1);
+        assert_eq!(initial_seq_no(&KEY, later, local, remote), isn + 250);
+
+        // Other connections use unrelated ones.
+        let other = IpEndpoint::new(remote.addr, remote.port + 1);
+        assert_ne!(initial_seq_no(&KEY, later, local, other), isn + 250);
+        assert_ne!(initial_seq_no(&[0, 0], later, local, remote), isn + 250);
+    }
+
+    #[test]
+    fn test_cookie_roundtrip() {
+        let (local, remote) = endpoints();
+        let now = Instant::from_secs(1000);
+        let isn = TcpSeqNumber(-10000);
+        let cookie = generate(&KEY, now, local, remote, isn, 1460);
+        assert_eq!(validate(&KEY, now, local, remote, isn, cookie), Some(1460));
+
+        let cookie = generate(&KEY, now, local, remote, isn, 1400);
+        assert_eq!(validate(&KEY, now, local, remote, isn, cookie), Some(1220));
+        let cookie = generate(&KEY, now, local, remote, isn, 100);
+        assert_eq!(validate(&KEY, now, local, remote, isn, cookie), Some(536));
+    }
+
+    #[test]
+    fn test_cookie_invalid() {
+        let (local, remote) = endpoints();
+        let now = Instant::from_secs(1000);
+        let isn = TcpSeqNumber(-10000);
+        let cookie = generate(&KEY, now, local, remote, isn, 1460);
+        assert_eq!(validate(&KEY, now, local, remote, isn + 1, cookie), None);
+        assert_eq!(validate(&KEY, now, remote, local, isn, cookie), None);
+        assert_eq!(validate(&[0, 0], now, local, remote, isn, cookie), None);
+        assert_eq!(validate(&KEY, now, local, remote, isn, cookie + 4), None);
+    }
+
+    #[test]
+    fn test_cookie_expiry() {
+        let (local, remote) = endpoints();
+        let now = Instant::from_secs(1000);
+        let isn = TcpSeqNumber(-10000);
+        let cookie = generate(&KEY, now, local, remote, isn, 1460);
+        let later = now + Duration::from_secs(PERIOD);
+        assert_eq!(
+            validate(&KEY, later, local, remote, isn, cookie),
+            Some(1460)
+        );
+        let later = now + Duration::from_secs(PERIOD * LIFETIME as u64);
+        assert_eq!(validate(&KEY, later, local, remote, isn, cookie), None);
+    }
+}
@@ -0,0 +1,399 @@
+// Cryptographic primitives needed by the TCP Authentication Option: HMAC-SHA-1 (RFC 2104,
+// FIPS 180-4) and AES-128-CMAC (RFC 4493, FIPS 197). Only what TCP-AO needs is implemented,
+// favoring small code size over speed.
+
+use byteorder::{BigEndian, ByteOrder};
+
+/// An incremental SHA-1 digest computation.
+#[derive(Clone)]
+pub(super) struct Sha1 {
+    state: [u32; 5],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Sha1 {
+    pub(super) fn new() -> Sha1 {
+        Sha1 {
+            state: [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0],
+            block: [0; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    pub(super) fn update(&mut self, mut data: &[u8]) {
+        self.total_len += data.len() as u64;
+        while !data.is_empty() {
+            let size = data.len().min(64 - self.block_len);
+            self.block[self.block_len..self.block_len + size].copy_from_slice(&data[..size]);
+            self.block_len += size;
+            data = &data[size..];
+            if self.block_len == 64 {
+                self.compress();
+                self.block_len = 0;
+            }
+        }
+    }
+
+    pub(super) fn finalize(mut self) -> [u8; 20] {
+        let bit_len = self.total_len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0]);
+        }
+        let mut len_bytes = [0; 8];
+        BigEndian::write_u64(&mut len_bytes, bit_len);
+        self.update(&len_bytes);
+
+        let mut digest = [0; 20];
+        BigEndian::write_u32_into(&self.state, &mut digest);
+        digest
+    }
+
+    fn compress(&mut self) {
+        let mut words = [0; 80];
+        BigEndian::read_u32_into(&self.block, &mut words[..16]);
+        for i in 16..80 {
+            words[i] = (words[i - 3] ^ words[i - 8] ^ words[i - 14] ^ words[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = self.state;
+        for (i, word) in words.iter().enumerate() {
+            let (f, k) = match i / 20 {
+                0 => ((b & c) | (!b & d), 0x5a827999),
+                1 => (b ^ c ^ d, 0x6ed9eba1),
+                2 => ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+                _ => (b ^ c ^ d, 0xca62c1d6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+
+        for (state, value) in self.state.iter_mut().zip([a, b, c, d, e]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+}
+
+/// An incremental HMAC-SHA-1 computation.
+#[derive(Clone)]
+pub(super) struct HmacSha1 {
+    inner: Sha1,
+    outer: Sha1,
+}
+
+impl HmacSha1 {
+    pub(super) fn new(key: &[u8]) -> HmacSha1 {
+        let mut block = [0; 64];
+        if key.len() > 64 {
+            let mut sha1 = Sha1::new();
+            sha1.update(key);
+            block[..20].copy_from_slice(&sha1.finalize());
+        } else {
+            block[..key.len()].copy_from_slice(key);
+        }
+
+        let mut inner = Sha1::new();
+        let mut outer = Sha1::new();
+        for byte in block.iter_mut() {
+            *byte ^= 0x36;
+        }
+        inner.update(&block);
+        for byte in block.iter_mut() {
+            *byte ^= 0x36 ^ 0x5c;
+        }
+        outer.update(&block);
+
+        HmacSha1 { inner, outer }
+    }
+
+    pub(super) fn update(&mut self, data: &[u8]) {
+        self.inner.update(data)
+    }
+
+    pub(super) fn finalize(self) -> [u8; 20] {
+        let HmacSha1 { inner, mut outer } = self;
+        outer.update(&inner.finalize());
+        outer.finalize()
+    }
+}
+
+const SBOX: [u8; 256] = [
+    0x63, 0x7c, 0x77, 0x7b, 0xf2, 0x6b, 0x6f, 0xc5, 0x30, 0x01, 0x67, 0x2b, 0xfe, 0xd7, 0xab, 0x76,
+    0xca, 0x82, 0xc9, 0x7d, 0xfa, 0x59, 0x47, 0xf0, 0xad, 0xd4, 0xa2, 0xaf, 0x9c, 0xa4, 0x72, 0xc0,
+    0xb7, 0xfd, 0x93, 0x26, 0x36, 0x3f, 0xf7, 0xcc, 0x34, 0xa5, 0xe5, 0xf1, 0x71, 0xd8, 0x31, 0x15,
+    0x04, 0xc7, 0x23, 0xc3, 0x18, 0x96, 0x05, 0x9a, 0x07, 0x12, 0x80, 0xe2, 0xeb, 0x27, 0xb2, 0x75,
+    0x09, 0x83, 0x2c, 0x1a, 0x1b, 0x6e, 0x5a, 0xa0, 0x52, 0x3b, 0xd6, 0xb3, 0x29, 0xe3, 0x2f, 0x84,
+    0x53, 0xd1, 0x00, 0xed, 0x20, 0xfc, 0xb1, 0x5b, 0x6a, 0xcb, 0xbe, 0x39, 0x4a, 0x4c, 0x58, 0xcf,
+    0xd0, 0xef, 0xaa, 0xfb, 0x43, 0x4d, 0x33, 0x85, 0x45, 0xf9, 0x02, 0x7f, 0x50, 0x3c, 0x9f, 0xa8,
+    0x51, 0xa3, 0x40, 0x8f, 0x92, 0x9d, 0x38, 0xf5, 0xbc, 0xb6, 0xda, 0x21, 0x10, 0xff, 0xf3, 0xd2,
+    0xcd, 0x0c, 0x13, 0xec, 0x5f, 0x97, 0x44, 0x17, 0xc4, 0xa7, 0x7e, 0x3d, 0x64, 0x5d, 0x19, 0x73,
+    0x60, 0x81, 0x4f, 0xdc, 0x22, 0x2a, 0x90, 0x88, 0x46, 0xee, 0xb8, 0x14, 0xde, 0x5e, 0x0b, 0xdb,
+    0xe0, 0x32, 0x3a, 0x0a, 0x49, 0x06, 0x24, 0x5c, 0xc2, 0xd3, 0xac, 0x62, 0x91, 0x95, 0xe4, 0x79,
+    0xe7, 0xc8, 0x37, 0x6d, 0x8d, 0xd5, 0x4e, 0xa9, 0x6c, 0x56, 0xf4, 0xea, 0x65, 0x7a, 0xae, 0x08,
+    0xba, 0x78, 0x25, 0x2e, 0x1c, 0xa6, 0xb4, 0xc6, 0xe8, 0xdd, 0x74, 0x1f, 0x4b, 0xbd, 0x8b, 0x8a,
+    0x70, 0x3e, 0xb5, 0x66, 0x48, 0x03, 0xf6, 0x0e, 0x61, 0x35, 0x57, 0xb9, 0x86, 0xc1, 0x1d, 0x9e,
+    0xe1, 0xf8, 0x98, 0x11, 0x69, 0xd9, 0x8e, 0x94, 0x9b, 0x1e, 0x87, 0xe9, 0xce, 0x55, 0x28, 0xdf,
+    0x8c, 0xa1, 0x89, 0x0d, 0xbf, 0xe6, 0x42, 0x68, 0x41, 0x99, 0x2d, 0x0f, 0xb0, 0x54, 0xbb, 0x16,
+];
+
+/// Multiply by x in GF(2^8).
+const fn xtime(value: u8) -> u8 {
+    (value << 1) ^ if value & 0x80 != 0 { 0x1b } else { 0 }
+}
+
+/// An AES-128 block cipher, supporting encryption only.
+#[derive(Clone)]
+pub(super) struct Aes128 {
+    round_keys: [[u8; 16]; 11],
+}
+
+impl Aes128 {
+    pub(super) fn new(key: &[u8; 16]) -> Aes128 {
+        let mut round_keys = [[0; 16]; 11];
+        round_keys[0] = *key;
+        let mut rcon = 1;
+        for round in 1..11 {
+            let prev = round_keys[round - 1];
+            let mut word = [prev[13], prev[14], prev[15], prev[12]];
+            for byte in word.iter_mut() {
+                *byte = SBOX[*byte as usize];
+            }
+            word[0] ^= rcon;
+            rcon = xtime(rcon);
+
+            let next = &mut round_keys[round];
+            for i in 0..16 {
+                let value = if i < 4 { word[i] } else { next[i - 4] };
+                next[i] = prev[i] ^ value;
+            }
+        }
+        Aes128 { round_keys }
+    }
+
+    pub(super) fn encrypt(&self, block: &mut [u8; 16]) {
+        fn add_round_key(block: &mut [u8; 16], round_key: &[u8; 16]) {
+            for (byte, key) in block.iter_mut().zip(round_key) {
+                *byte ^= key;
+            }
+        }
+
+        fn sub_bytes_shift_rows(block: &mut [u8; 16]) {
+            let state = *block;
+            for column in 0..4 {
+                for row in 0..4 {
+                    block[column * 4 + row] = SBOX[state[((column + row) % 4) * 4 + row] as usize];
+                }
+            }
+        }
+
+        fn mix_columns(block: &mut [u8; 16]) {
+            for column in block.chunks_exact_mut(4) {
+                let [a, b, c, d] = [column[0], column[1], column[2], column[3]];
+                let all = a ^ b ^ c ^ d;
+                column[0] ^= all ^ xtime(a ^ b);
+                column[1] ^= all ^ xtime(b ^ c);
+                column[2] ^= all ^ xtime(c ^ d);
+                column[3] ^= all ^ xtime(d ^ a);
+            }
+        }
+
+        add_round_key(block, &self.round_keys[0]);
+        for round_key in &self.round_keys[1..10] {
+            sub_bytes_shift_rows(block);
+            mix_columns(block);
+            add_round_key(block, round_key);
+        }
+        sub_bytes_shift_rows(block);
+        add_round_key(block, &self.round_keys[10]);
+    }
+}
+
+/// An incremental AES-128-CMAC computation.
+#[derive(Clone)]
+pub(super) struct Cmac {
+    cipher: Aes128,
+    state: [u8; 16],
+    block: [u8; 16],
+    block_len: usize,
+}
+
+impl Cmac {
+    pub(super) fn new(key: &[u8; 16]) -> Cmac {
+        Cmac {
+            cipher: Aes128::new(key),
+            state: [0; 16],
+            block: [0; 16],
+            block_len: 0,
+        }
+    }
+
+    pub(super) fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            // Always keep the last block around, since it is processed differently.
+            if self.block_len == 16 {
+                for (state, byte) in self.state.iter_mut().zip(self.block) {
+                    *state ^= byte;
+                }
+                self.cipher.encrypt(&mut self.state);
+                self.block_len = 0;
+            }
+
+            let size = data.len().min(16 - self.block_len);
+            self.block[self.block_len..self.block_len + size].copy_from_slice(&data[..size]);
+            self.block_len += size;
+            data = &data[size..];
+        }
+    }
+
+    pub(super) fn finalize(mut self) -> [u8; 16] {
+        fn double(value: [u8; 16]) -> [u8; 16] {
+            let mut result = [0; 16];
+            for i in 0..16 {
+                let carry = value.get(i + 1).map(|next| next >> 7).unwrap_or(0);
+                result[i] = (value[i] << 1) | carry;
+            }
+            if value[0] & 0x80 != 0 {
+                result[15] ^= 0x87;
+            }
+            result
+        }
+
+        let mut subkey = [0; 16];
+        self.cipher.encrypt(&mut subkey);
+        subkey = double(subkey);
+        if self.block_len < 16 {
+            self.block[self.block_len] = 0x80;
+            self.block[self.block_len + 1..].fill(0);
+            subkey = double(subkey);
+        }
+
+        for ((state, byte), key) in self.state.iter_mut().zip(self.block).zip(subkey) {
+            *state ^= byte ^ key;
+        }
+        self.cipher.encrypt(&mut self.state);
+        self.state
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sha1() {
+        let mut sha1 = Sha1::new();
+        sha1.update(b"abc");
+        assert_eq!(
+            sha1.finalize(),
+            [
+                0xa9, 0x99, 0x3e, 0x36, 0x47, 0x06, 0x81, 0x6a, 0xba, 0x3e, 0x25, 0x71, 0x78, 0x50,
+                0xc2, 0x6c, 0x9c, 0xd0, 0xd8, 0x9d
+            ]
+        );
+
+        let mut sha1 = Sha1::new();
+        sha1.update(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq");
+        assert_eq!(
+            sha1.finalize(),
+            [
+                0x84, 0x98, 0x3e, 0x44, 0x1c, 0x3b, 0xd2, 0x6e, 0xba, 0xae, 0x4a, 0xa1, 0xf9, 0x51,
+                0x29, 0xe5, 0xe5, 0x46, 0x70, 0xf1
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha1_rfc2202() {
+        let mut hmac = HmacSha1::new(&[0x0b; 20]);
+        hmac.update(b"Hi There");
+        assert_eq!(
+            hmac.finalize(),
+            [
+                0xb6, 0x17, 0x31, 0x86, 0x55, 0x05, 0x72, 0x64, 0xe2, 0x8b, 0xc0, 0xb6, 0xfb, 0x37,
+                0x8c, 0x8e, 0xf1, 0x46, 0xbe, 0x00
+            ]
+        );
+
+        let mut hmac = HmacSha1::new(&[0xaa; 80]);
+        hmac.update(b"Test Using Larger Than Block-Size Key - Hash Key First");
+        assert_eq!(
+            hmac.finalize(),
+            [
+                0xaa, 0x4a, 0xe5, 0xe1, 0x52, 0x72, 0xd0, 0x0e, 0x95, 0x70, 0x56, 0x37, 0xce, 0x8a,
+                0x3b, 0x55, 0xed, 0x40, 0x21, 0x12
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aes128_fips197() {
+        let key = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+        let mut block = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+            0xee, 0xff,
+        ];
+        Aes128::new(&key).encrypt(&mut block);
+        assert_eq!(
+            block,
+            [
+                0x69, 0xc4, 0xe0, 0xd8, 0x6a, 0x7b, 0x04, 0x30, 0xd8, 0xcd, 0xb7, 0x80, 0x70, 0xb4,
+                0xc5, 0x5a
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cmac_rfc4493() {
+        const KEY: [u8; 16] = [
+            0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf,
+            0x4f, 0x3c,
+        ];
+        const MESSAGE: [u8; 40] = [
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93,
+            0x17, 0x2a, 0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac,
+            0x45, 0xaf, 0x8e, 0x51, 0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11,
+        ];
+
+        let cmac = |message: &[u8]| {
+            let mut cmac = Cmac::new(&KEY);
+            cmac.update(message);
+            cmac.finalize()
+        };
+        assert_eq!(
+            cmac(&[]),
+            [
+                0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28, 0x7f, 0xa3, 0x7d, 0x12, 0x9b, 0x75,
+                0x67, 0x46
+            ]
+        );
+        assert_eq!(
+            cmac(&MESSAGE[..16]),
+            [
+                0x07, 0x0a, 0x16, 0xb4, 0x6b, 0x4d, 0x41, 0x44, 0xf7, 0x9b, 0xdd, 0x9d, 0xd0, 0x4a,
+                0x28, 0x7c
+            ]
+        );
+        assert_eq!(
+            cmac(&MESSAGE),
+            [
+                0xdf, 0xa6, 0x67, 0x47, 0xde, 0x9a, 0xe6, 0x30, 0x30, 0xca, 0x32, 0x61, 0x14, 0x97,
+                0xc8, 0x27
+            ]
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use std::io;
+
+use super::{RecvError, SendError, Socket, State};
+use crate::iface::{Interface, SocketHandle, SocketSet};
+use crate::phy::Device;
+use crate::time::{Duration, Instant};
+
+/// A blocking [std::io::Read] and [std::io::Write] adapter for a TCP socket.
+///
+/// The adapter borrows the interface, the device and the socket set the socket belongs to,
+/// and drives them itself: every call polls the interface until the operation can make
+/// progress, calling the `wait` function in between with the device and the delay
+/// returned by [Interface::poll_delay]. On a Unix-like host, `wait` would typically
+/// block on the device file descriptor, e.g. using [phy::wait](crate::phy::wait).
+///
+/// This allows running existing blocking libraries (HTTP clients, TLS implementations, etc.)
+/// over a TCP socket with minimal glue.
+pub struct Stream<'a, 'b, D, W>
+where
+    D: Device + ?Sized,
+    W: FnMut(&mut D, Option<Duration>),
+{
+    iface: &'a mut Interface,
+    device: &'a mut D,
+    sockets: &'a mut SocketSet<'b>,
+    handle: SocketHandle,
+    wait: W,
+}
+
+impl<'a, 'b, D, W> Stream<'a, 'b, D, W>
+where
+    D: Device + ?Sized,
+    W: FnMut(&mut D, Option<Duration>),
+{
+    /// Create an adapter for the TCP socket `handle` in `sockets`.
+    ///
+    /// # Panics
+    /// Reading or writing panics if the handle does not refer to a TCP socket in `sockets`.
+    pub fn new(
+        iface: &'a mut Interface,
+        device: &'a mut D,
+        sockets: &'a mut SocketSet<'b>,
+        handle: SocketHandle,
+        wait: W,
+    ) -> Self {
+        Stream {
+            iface,
+            device,
+            sockets,
+            handle,
+            wait,
+        }
+    }
+
+    /// Return the handle of the underlying socket.
+    pub fn handle(&self) -> SocketHandle {
+        self.handle
+    }
+
+    /// Return the underlying socket.
+    pub fn socket(&mut self) -> &mut Socket<'b> {
+        self.sockets.get_mut::<Socket>(self.handle)
+    }
+
+    fn poll(&mut self) {
+        self.iface
+            .poll(Instant::now(), &mut *self.device, &mut *self.sockets);
+    }
+
+    fn wait(&mut self) {
+        let delay = self.iface.poll_delay(Instant::now(), self.sockets);
+        (self.wait)(&mut *self.device, delay)
+    }
+
+    /// Poll the interface, then call `f` with the socket until it returns `Some`,
+    /// waiting for the interface in between.
+    fn poll_until<F, R>(&mut self, mut f: F) -> io::Result<R>
+    where
+        F: FnMut(&mut Socket<'b>) -> Option<io::Result<R>>,
+    {
+        loop {
+            self.poll();
+            if let Some(result) = f(self.socket()) {
+                // Give the interface a chance to transmit what we've just enqueued,
+                // or to update the window after what we've just dequeued.
+                self.poll();
+                return result;
+            }
+            self.wait();
+        }
+    }
+}
+
+/// Return whether reading or writing has to wait for a connection to be established.
+fn is_connecting(socket: &Socket) -> bool {
+    matches!(
+        socket.state(),
+        State::Listen | State::SynSent | State::SynReceived
+    )
+}
+
+impl<'a, 'b, D, W> io::Read for Stream<'a, 'b, D, W>
+where
+    D: Device + ?Sized,
+    W: FnMut(&mut D, Option<Duration>),
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        self.poll_until(|socket| match socket.recv_slice(buf) {
+            Ok(0) => None,
+            Ok(size) => Some(Ok(size)),
+            Err(RecvError::Finished) => Some(Ok(0)),
+            Err(RecvError::InvalidState) if is_connecting(socket) => None,
+            Err(RecvError::InvalidState) => Some(Err(io::ErrorKind::NotConnected.into())),
+        })
+    }
+}
+
+impl<'a, 'b, D, W> io::Write for Stream<'a, 'b, D, W>
+where
+    D: Device + ?Sized,
+    W: FnMut(&mut D, Option<Duration>),
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        self.poll_until(|socket| match socket.send_slice(buf) {
+            Ok(0) => None,
+            Ok(size) => Some(Ok(size)),
+            Err(SendError::InvalidState) if is_connecting(socket) => None,
+            Err(SendError::InvalidState) => Some(Err(io::ErrorKind::BrokenPipe.into())),
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.poll_until(|socket| {
+            if socket.send_queue() == 0 {
+                Some(Ok(()))
+            } else if !socket.is_active() {
+                Some(Err(io::ErrorKind::BrokenPipe.into()))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+#[cfg(all(test, feature = "medium-ip", feature = "proto-ipv4"))]
+mod test {
+    use std::io::{Read, Write};
+    use std::vec;
+
+    use super::*;
+    use crate::iface::Config;
+    use crate::phy::{Loopback, Medium};
+    use crate::socket::tcp::SocketBuffer;
+    use crate::wire::{HardwareAddress, IpAddress, IpCidr};
+
+    fn wait(_device: &mut Loopback, delay: Option<Duration>) {
+        let delay = delay.unwrap_or(Duration::from_millis(1));
+        std::thread::sleep(delay.min(Duration::from_millis(1)).into());
+    }
+
+    #[test]
+    fn test_read_write() {
+        let mut device = Loopback::new(Medium::Ip);
+        let config = Config::new(HardwareAddress::Ip);
+        let mut iface = Interface::new(config, &mut device, Instant::now());
+        iface.update_ip_addrs(|ip_addrs| {
+            ip_addrs
+                .push(IpCidr::new(IpAddress::v4(127, 0, 0, 1), 8))
+                .unwrap();
+        });
+
+        let mut sockets = SocketSet::new(vec![]);
+        let server = sockets.add(Socket::new(
+            SocketBuffer::new(vec![0; 64]),
+            SocketBuffer::new(vec![0; 64]),
+        ));
+        let client = sockets.add(Socket::new(
+            SocketBuffer::new(vec![0; 64]),
+            SocketBuffer::new(vec![0; 64]),
+        ));
+
+        sockets.get_mut::<Socket>(server).listen(1234).unwrap();
+        sockets
+            .get_mut::<Socket>(client)
+            .connect(iface.context(), (IpAddress::v4(127, 0, 0, 1), 1234), 65000)
+            .unwrap();
+
+        let mut stream = Stream::new(&mut iface, &mut device, &mut sockets, client, wait);
+        stream.write_all(b"hello").unwrap();
+        stream.flush().unwrap();
+        stream.socket().close();
+
+        let mut stream = Stream::new(&mut iface, &mut device, &mut sockets, server, wait);
+        let mut data = vec![];
+        stream.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"hello");
+    }
+
+    #[test]
+    fn test_is_connecting() {
+        let mut socket = Socket::new(
+            SocketBuffer::new(vec![0; 64]),
+            SocketBuffer::new(vec![0; 64]),
+        );
+        assert!(!is_connecting(&socket));
+        // A listening socket is waited on until a connection is established in it.
+        socket.listen(1234).unwrap();
+        assert!(is_connecting(&socket));
+    }
+}
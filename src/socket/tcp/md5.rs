@@ -0,0 +1,189 @@
+// TCP MD5 signatures, as described in RFC 2385.
+
+use byteorder::{ByteOrder, NetworkEndian};
+
+use crate::md5::Md5;
+use crate::phy::ChecksumCapabilities;
+use crate::wire::{IpAddress, IpProtocol, IpRepr, TcpPacket, TcpRepr, TCP_HEADER_LEN};
+
+/// Maximum length of a TCP MD5 key, in octets.
+pub const MAX_KEY_LEN: usize = 80;
+
+/// Compute the RFC 2385 signature of a segment, given its fixed header (without options),
+/// its total length (header with options and payload), and its payload.
+fn signature(
+    src_addr: &IpAddress,
+    dst_addr: &IpAddress,
+    header: &[u8],
+    tcp_len: usize,
+    payload: &[u8],
+    key: &[u8],
+) -> [u8; 16] {
+    let mut md5 = Md5::new();
+
+    // 1. the TCP pseudo-header
+    match (src_addr, dst_addr) {
+        #[cfg(feature = "proto-ipv4")]
+        (IpAddress::Ipv4(src_addr), IpAddress::Ipv4(dst_addr)) => {
+            let mut proto_len = [0; 4];
+            proto_len[1] = IpProtocol::Tcp.into();
+            NetworkEndian::write_u16(&mut proto_len[2..4], tcp_len as u16);
+            md5.update(src_addr.as_bytes());
+            md5.update(dst_addr.as_bytes());
+            md5.update(&proto_len);
+        }
+        #[cfg(feature = "proto-ipv6")]
+        (IpAddress::Ipv6(src_addr), IpAddress::Ipv6(dst_addr)) => {
+            let mut proto_len = [0; 8];
+            NetworkEndian::write_u32(&mut proto_len[0..4], tcp_len as u32);
+            proto_len[7] = IpProtocol::Tcp.into();
+            md5.update(src_addr.as_bytes());
+            md5.update(dst_addr.as_bytes());
+            md5.update(&proto_len);
+        }
+        #[allow(unreachable_patterns)]
+        _ => unreachable!(),
+    }
+
+    // 2. the TCP header, excluding options, and assuming a checksum of zero
+    let mut fixed_header = [0; TCP_HEADER_LEN];
+    fixed_header.copy_from_slice(&header[..TCP_HEADER_LEN]);
+    TcpPacket::new_unchecked(&mut fixed_header[..]).set_checksum(0);
+    md5.update(&fixed_header);
+
+    // 3. the TCP segment data
+    md5.update(payload);
+
+    // 4. an independently-specified key known to both TCPs
+    md5.update(key);
+
+    md5.finalize()
+}
+
+/// Fill in the MD5 signature option of an outgoing segment.
+pub(super) fn sign(key: &[u8], ip_repr: &mut IpRepr, repr: &mut TcpRepr) {
+    // The option must be present when computing the header length and data offset.
+    repr.md5_signature = Some([0; 16]);
+    ip_repr.set_payload_len(repr.buffer_len());
+
+    let header_len = repr.header_len();
+    let mut header = [0; 60];
+    TcpRepr {
+        payload: &[],
+        ..*repr
+    }
+    .emit(
+        &mut TcpPacket::new_unchecked(&mut header[..header_len]),
+        &ip_repr.src_addr(),
+        &ip_repr.dst_addr(),
+        &ChecksumCapabilities::ignored(),
+    );
+
+    repr.md5_signature = Some(signature(
+        &ip_repr.src_addr(),
+        &ip_repr.dst_addr(),
+        &header[..header_len],
+        repr.buffer_len(),
+        repr.payload,
+        key,
+    ));
+}
+
+/// Check the MD5 signature option of an incoming segment.
+pub(super) fn verify(
+    key: &[u8],
+    ip_repr: &IpRepr,
+    packet: &TcpPacket<&[u8]>,
+    repr: &TcpRepr,
+) -> bool {
+    let Some(received) = repr.md5_signature else {
+        return false;
+    };
+    let expected = signature(
+        &ip_repr.src_addr(),
+        &ip_repr.dst_addr(),
+        packet.as_ref(),
+        packet.as_ref().len(),
+        packet.payload(),
+        key,
+    );
+
+    // Avoid leaking how much of the signature matched through timing.
+    received
+        .iter()
+        .zip(expected.iter())
+        .fold(0, |diff, (a, b)| diff | (a ^ b))
+        == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_sign_verify() {
+        use crate::wire::{Ipv4Address, Ipv4Repr, TcpControl, TcpSeqNumber};
+
+        let mut ip_repr = IpRepr::Ipv4(Ipv4Repr {
+            src_addr: Ipv4Address([192, 168, 1, 1]),
+            dst_addr: Ipv4Address([192, 168, 1, 2]),
+            next_header: IpProtocol::Tcp,
+            payload_len: 0,
+            hop_limit: 64,
+        });
+        let mut repr = TcpRepr {
+            src_port: 49500,
+            dst_port: 179,
+            control: TcpControl::Psh,
+            seq_number: TcpSeqNumber(0x01234567),
+            ack_number: Some(TcpSeqNumber(0x89abcdefu32 as i32)),
+            window_len: 1024,
+            window_scale: None,
+            max_seg_size: None,
+            sack_permitted: false,
+            sack_ranges: [None, None, None],
+            timestamp: None,
+            md5_signature: None,
+            authentication: None,
+            urgent_pointer: None,
+            payload: b"hello",
+        };
+        sign(b"secret", &mut ip_repr, &mut repr);
+        assert_eq!(ip_repr.payload_len(), 45);
+        assert_eq!(
+            repr.md5_signature,
+            Some([
+                0x25, 0x1a, 0x5e, 0x16, 0x77, 0x54, 0x06, 0x48, 0x19, 0x6c, 0x02, 0x2f, 0xca, 0xff,
+                0xbb, 0x6e
+            ])
+        );
+
+        let mut bytes = vec![0; repr.buffer_len()];
+        let mut packet = TcpPacket::new_unchecked(&mut bytes[..]);
+        repr.emit(
+            &mut packet,
+            &ip_repr.src_addr(),
+            &ip_repr.dst_addr(),
+            &ChecksumCapabilities::default(),
+        );
+        let packet = TcpPacket::new_unchecked(&bytes[..]);
+        assert!(verify(b"secret", &ip_repr, &packet, &repr));
+        assert!(!verify(b"public", &ip_repr, &packet, &repr));
+        assert!(!verify(
+            b"secret",
+            &ip_repr,
+            &packet,
+            &TcpRepr {
+                timestamp: None,
+                md5_signature: None,
+                authentication: None,
+                ..repr
+            }
+        ));
+
+        bytes[40] = b'j';
+        let packet = TcpPacket::new_unchecked(&bytes[..]);
+        assert!(!verify(b"secret", &ip_repr, &packet, &repr));
+    }
+}
@@ -2,19 +2,41 @@ use core::cmp::min;
 #[cfg(feature = "async")]
 use core::task::Waker;
 
+use crate::config::IFACE_MAX_MULTICAST_GROUP_COUNT;
 use crate::iface::Context;
 use crate::phy::PacketMeta;
 use crate::socket::PollAt;
 #[cfg(feature = "async")]
 use crate::socket::WakerRegistration;
 use crate::storage::Empty;
-use crate::wire::{IpEndpoint, IpListenEndpoint, IpProtocol, IpRepr, UdpRepr};
+use crate::time::Instant;
+use crate::wire::{IpAddress, IpEndpoint, IpListenEndpoint, IpProtocol, IpRepr, UdpRepr};
 
 /// Metadata for a sent or received UDP packet.
+///
+/// When sending, only the remote endpoint is required; the other fields let individual
+/// packets override the settings of the socket. When receiving, the local address,
+/// hop limit and timestamp describe how the packet was received.
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct UdpMetadata {
     pub endpoint: IpEndpoint,
+    /// The local address to send the packet from, instead of the bound address or the
+    /// address selected by the interface; or the destination address of a received
+    /// packet, which may be a broadcast or multicast address.
+    pub local_address: Option<IpAddress>,
+    /// The time-to-live (IPv4) or hop limit (IPv6) value to send the packet with,
+    /// instead of the one of the socket; or the one of a received packet.
+    pub hop_limit: Option<u8>,
+    /// The differentiated services code point to send the packet with, in the 6 low bits,
+    /// instead of the one of the socket if not zero. Sending a packet with a code point that
+    /// does not fit in 6 bits panics.
+    pub dscp: u8,
+    /// The explicit congestion notification codepoint to send the packet with,
+    /// in the 2 low bits, instead of the one of the socket if not zero.
+    pub ecn: u8,
+    /// The time at which a packet was received. Ignored when sending.
+    pub timestamp: Option<Instant>,
     pub meta: PacketMeta,
 }
 
@@ -22,6 +44,11 @@ impl<T: Into<IpEndpoint>> From<T> for UdpMetadata {
     fn from(value: T) -> Self {
         Self {
             endpoint: value.into(),
+            local_address: None,
+            hop_limit: None,
+            dscp: 0,
+            ecn: 0,
+            timestamp: None,
             meta: PacketMeta::default(),
         }
     }
@@ -63,6 +90,26 @@ impl core::fmt::Display for BindError {
 #[cfg(feature = "std")]
 impl std::error::Error for BindError {}
 
+/// Error returned by [`Socket::connect`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ConnectError {
+    InvalidState,
+    Unaddressable,
+}
+
+impl core::fmt::Display for ConnectError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ConnectError::InvalidState => write!(f, "invalid state"),
+            ConnectError::Unaddressable => write!(f, "unaddressable"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ConnectError {}
+
 /// Error returned by [`Socket::send`]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -83,6 +130,26 @@ impl core::fmt::Display for SendError {
 #[cfg(feature = "std")]
 impl std::error::Error for SendError {}
 
+/// Error returned by [`Socket::join_multicast_group`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MulticastError {
+    Unaddressable,
+    GroupTableFull,
+}
+
+impl core::fmt::Display for MulticastError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            MulticastError::Unaddressable => write!(f, "unaddressable"),
+            MulticastError::GroupTableFull => write!(f, "group table full"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MulticastError {}
+
 /// Error returned by [`Socket::recv`]
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -104,18 +171,55 @@ impl std::error::Error for RecvError {}
 /// A User Datagram Protocol socket.
 ///
 /// A UDP socket is bound to a specific endpoint, and owns transmit and receive
-/// packet buffers.
+/// packet buffers. It may also be connected to a remote endpoint, in which case
+/// it only receives packets from that endpoint.
 #[derive(Debug)]
 pub struct Socket<'a> {
     endpoint: IpListenEndpoint,
+    /// The remote endpoint the socket is connected to.
+    remote_endpoint: Option<IpEndpoint>,
     rx_buffer: PacketBuffer<'a>,
     tx_buffer: PacketBuffer<'a>,
     /// The time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     hop_limit: Option<u8>,
+    /// The flow label of outgoing IPv6 packets, if set explicitly.
+    flow_label: Option<u32>,
+    /// The DSCP and ECN codepoint of outgoing packets.
+    traffic_class: u8,
+    /// Whether the socket sends and receives UDP-Lite packets instead of UDP packets.
+    lite: bool,
+    /// The number of octets covered by the checksum of sent UDP-Lite packets.
+    send_checksum_coverage: u16,
+    /// The minimum number of octets covered by the checksum of received UDP-Lite packets.
+    recv_checksum_coverage: u16,
+    /// Whether the checksum of sent UDP packets is computed, instead of being left as zero.
+    send_checksum: bool,
+    /// Whether received UDP-over-IPv6 packets with a zero checksum are accepted.
+    recv_zero_checksum: bool,
+    /// Whether IPv4 datagrams larger than the MTU are fragmented, instead of being dropped.
+    fragmentation: bool,
+    /// Whether the don't fragment flag is set in sent IPv4 packets.
+    dont_fragment: bool,
+    /// The last path MTU reported for a destination of the socket, and that destination.
+    path_mtu: Option<(IpAddress, usize)>,
+    /// Whether the socket may send packets to, and receive packets sent to, broadcast addresses.
+    broadcast: bool,
+    /// Whether the multicast packets sent by the socket are delivered to other local sockets.
+    multicast_loop: bool,
+    /// The multicast groups whose packets are delivered to the socket.
+    multicast_groups: heapless::Vec<IpAddress, IFACE_MAX_MULTICAST_GROUP_COUNT>,
+    /// The number of received packets from which the socket is readable.
+    recv_watermark: usize,
+    /// The number of free packet slots from which the socket is writable.
+    send_watermark: usize,
     #[cfg(feature = "async")]
     rx_waker: WakerRegistration,
     #[cfg(feature = "async")]
     tx_waker: WakerRegistration,
+    #[cfg(feature = "async")]
+    readable_waker: WakerRegistration,
+    #[cfg(feature = "async")]
+    writable_waker: WakerRegistration,
 }
 
 impl<'a> Socket<'a> {
@@ -123,13 +227,33 @@ impl<'a> Socket<'a> {
     pub fn new(rx_buffer: PacketBuffer<'a>, tx_buffer: PacketBuffer<'a>) -> Socket<'a> {
         Socket {
             endpoint: IpListenEndpoint::default(),
+            remote_endpoint: None,
             rx_buffer,
             tx_buffer,
             hop_limit: None,
+            flow_label: None,
+            traffic_class: 0,
+            lite: false,
+            send_checksum_coverage: 0,
+            recv_checksum_coverage: 0,
+            send_checksum: true,
+            recv_zero_checksum: false,
+            fragmentation: false,
+            dont_fragment: true,
+            path_mtu: None,
+            broadcast: true,
+            multicast_loop: false,
+            multicast_groups: heapless::Vec::new(),
+            recv_watermark: 1,
+            send_watermark: 1,
             #[cfg(feature = "async")]
             rx_waker: WakerRegistration::new(),
             #[cfg(feature = "async")]
             tx_waker: WakerRegistration::new(),
+            #[cfg(feature = "async")]
+            readable_waker: WakerRegistration::new(),
+            #[cfg(feature = "async")]
+            writable_waker: WakerRegistration::new(),
         }
     }
 
@@ -168,12 +292,59 @@ impl<'a> Socket<'a> {
         self.tx_waker.register(waker)
     }
 
+    /// Register a waker for the socket becoming readable.
+    ///
+    /// Unlike the waker registered with [register_recv_waker](#method.register_recv_waker),
+    /// this waker is only woken once the number of received packets reaches the receive
+    /// watermark (see [set_recv_watermark](#method.set_recv_watermark)), or when the socket
+    /// is bound or closed. It is woken immediately if the socket is already readable.
+    ///
+    /// Notes:
+    ///
+    /// - Only one waker can be registered at a time. If another waker was previously registered,
+    ///   it is overwritten and will no longer be woken.
+    /// - The Waker is woken only once. Once woken, you must register it again to receive more wakes.
+    #[cfg(feature = "async")]
+    pub fn register_readable_waker(&mut self, waker: &Waker) {
+        self.readable_waker.register(waker);
+        if self.is_readable() {
+            self.readable_waker.wake()
+        }
+    }
+
+    /// Register a waker for the socket becoming writable.
+    ///
+    /// Unlike the waker registered with [register_send_waker](#method.register_send_waker),
+    /// this waker is only woken once the number of packets that can be queued for sending
+    /// reaches the send watermark (see [set_send_watermark](#method.set_send_watermark)),
+    /// or when the socket is bound or closed. It is woken immediately if the socket is
+    /// already writable.
+    ///
+    /// Notes:
+    ///
+    /// - Only one waker can be registered at a time. If another waker was previously registered,
+    ///   it is overwritten and will no longer be woken.
+    /// - The Waker is woken only once. Once woken, you must register it again to receive more wakes.
+    #[cfg(feature = "async")]
+    pub fn register_writable_waker(&mut self, waker: &Waker) {
+        self.writable_waker.register(waker);
+        if self.is_writable() {
+            self.writable_waker.wake()
+        }
+    }
+
     /// Return the bound endpoint.
     #[inline]
     pub fn endpoint(&self) -> IpListenEndpoint {
         self.endpoint
     }
 
+    /// Return the remote endpoint the socket is connected to, if any.
+    #[inline]
+    pub fn remote_endpoint(&self) -> Option<IpEndpoint> {
+        self.remote_endpoint
+    }
+
     /// Return the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
     /// See also the [set_hop_limit](#method.set_hop_limit) method
@@ -183,14 +354,14 @@ impl<'a> Socket<'a> {
 
     /// Set the time-to-live (IPv4) or hop limit (IPv6) value used in outgoing packets.
     ///
-    /// A socket without an explicitly set hop limit value uses the default [IANA recommended]
-    /// value (64).
+    /// A socket without an explicitly set hop limit value uses the default of the interface
+    /// it sends the packets through, see [Interface::set_hop_limit].
     ///
     /// # Panics
     ///
     /// This function panics if a hop limit value of 0 is given. See [RFC 1122 § 3.2.1.7].
     ///
-    /// [IANA recommended]: https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml
+    /// [Interface::set_hop_limit]: crate::iface::Interface::set_hop_limit
     /// [RFC 1122 § 3.2.1.7]: https://tools.ietf.org/html/rfc1122#section-3.2.1.7
     pub fn set_hop_limit(&mut self, hop_limit: Option<u8>) {
         // A host MUST NOT send a datagram with a hop limit value of 0
@@ -201,8 +372,368 @@ impl<'a> Socket<'a> {
         self.hop_limit = hop_limit
     }
 
+    /// Return the flow label set in outgoing IPv6 packets, if it was set explicitly.
+    ///
+    /// See also the [set_flow_label](#method.set_flow_label) method
+    pub fn flow_label(&self) -> Option<u32> {
+        self.flow_label
+    }
+
+    /// Set the flow label set in outgoing IPv6 packets.
+    ///
+    /// A socket without an explicitly set flow label uses the one the interface derives from
+    /// the addresses, protocol and ports of the flow, as described in [RFC 6437], if flow
+    /// label generation is enabled, and zero otherwise. A value of zero leaves the packets
+    /// unlabeled.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the flow label does not fit in 20 bits.
+    ///
+    /// [RFC 6437]: https://tools.ietf.org/html/rfc6437
+    pub fn set_flow_label(&mut self, flow_label: Option<u32>) {
+        if let Some(flow_label) = flow_label {
+            assert!(flow_label <= 0xf_ffff, "the flow label must fit in 20 bits");
+        }
+
+        self.flow_label = flow_label
+    }
+
+    /// Return the differentiated services code point of outgoing packets.
+    ///
+    /// See also the [set_dscp](#method.set_dscp) method
+    pub fn dscp(&self) -> u8 {
+        self.traffic_class >> 2
+    }
+
+    /// Set the differentiated services code point of outgoing packets, carried in the IPv4
+    /// type of service or IPv6 traffic class field, as described in [RFC 2474].
+    ///
+    /// A socket sends its packets with the default code point (0, best effort) unless
+    /// another one is set. The DSCP given in the [UdpMetadata] of a packet
+    /// takes precedence, if not zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the code point does not fit in 6 bits.
+    ///
+    /// [RFC 2474]: https://tools.ietf.org/html/rfc2474
+    pub fn set_dscp(&mut self, dscp: u8) {
+        assert!(dscp <= 0x3f, "the DSCP must fit in 6 bits");
+        self.traffic_class = (dscp << 2) | (self.traffic_class & 0x03)
+    }
+
+    /// Return the explicit congestion notification codepoint of outgoing packets.
+    ///
+    /// See also the [set_ecn](#method.set_ecn) method
+    pub fn ecn(&self) -> u8 {
+        self.traffic_class & 0x03
+    }
+
+    /// Set the explicit congestion notification codepoint of outgoing packets, as described
+    /// in [RFC 3168].
+    ///
+    /// A socket sends its packets as not ECN-capable (0) unless another codepoint is set. The ECN codepoint given in the [UdpMetadata] of
+    /// a packet takes precedence, if not zero.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the codepoint does not fit in 2 bits.
+    ///
+    /// [RFC 3168]: https://tools.ietf.org/html/rfc3168
+    pub fn set_ecn(&mut self, ecn: u8) {
+        assert!(ecn <= 0x03, "the ECN codepoint must fit in 2 bits");
+        self.traffic_class = (self.traffic_class & !0x03) | ecn
+    }
+
+    /// Return whether the socket uses UDP-Lite.
+    ///
+    /// See also the [set_udp_lite_enabled](#method.set_udp_lite_enabled) method
+    pub fn udp_lite_enabled(&self) -> bool {
+        self.lite
+    }
+
+    /// Set whether the socket uses the Lightweight User Datagram Protocol described in
+    /// [RFC 3828] instead of UDP.
+    ///
+    /// The checksum of UDP-Lite packets may only cover their beginning, so that packets
+    /// whose payload is damaged can still be delivered. A UDP-Lite socket only receives
+    /// UDP-Lite packets, and other sockets only receive UDP packets.
+    ///
+    /// [RFC 3828]: https://tools.ietf.org/html/rfc3828
+    pub fn set_udp_lite_enabled(&mut self, enabled: bool) {
+        self.lite = enabled
+    }
+
+    /// Return the number of octets covered by the checksum of sent UDP-Lite packets.
+    ///
+    /// See also the [set_send_checksum_coverage](#method.set_send_checksum_coverage) method
+    pub fn send_checksum_coverage(&self) -> u16 {
+        self.send_checksum_coverage
+    }
+
+    /// Set the number of octets, including the 8-octet header, covered by the checksum of
+    /// sent UDP-Lite packets.
+    ///
+    /// A value of 0, which is the default, covers the whole packet. Values lower than
+    /// the length of the header are raised to it.
+    pub fn set_send_checksum_coverage(&mut self, coverage: u16) {
+        self.send_checksum_coverage = coverage
+    }
+
+    /// Return the minimum number of octets covered by the checksum of received UDP-Lite packets.
+    ///
+    /// See also the [set_recv_checksum_coverage](#method.set_recv_checksum_coverage) method
+    pub fn recv_checksum_coverage(&self) -> u16 {
+        self.recv_checksum_coverage
+    }
+
+    /// Set the minimum number of octets, including the 8-octet header, covered by the
+    /// checksum of received UDP-Lite packets.
+    ///
+    /// Packets whose checksum covers fewer octets are dropped; packets whose checksum
+    /// covers the whole packet are always accepted. A value of 0, which is the default,
+    /// accepts any coverage.
+    pub fn set_recv_checksum_coverage(&mut self, coverage: u16) {
+        self.recv_checksum_coverage = coverage
+    }
+
+    /// Return whether the checksum of sent UDP packets is computed.
+    ///
+    /// See also the [set_send_checksum_enabled](#method.set_send_checksum_enabled) method
+    pub fn send_checksum_enabled(&self) -> bool {
+        self.send_checksum
+    }
+
+    /// Set whether the checksum of sent UDP packets is computed.
+    ///
+    /// When disabled, packets are sent with a zero checksum, meaning that it is not used.
+    /// This is allowed over IPv4, but over IPv6 it is only meant for tunnel protocols
+    /// whose payload is protected by checks of its own, as described in [RFC 6936].
+    /// The receivers then have to accept zero checksums, see the
+    /// [set_recv_zero_checksum_enabled](#method.set_recv_zero_checksum_enabled) method.
+    /// UDP-Lite packets always have a checksum. It is enabled by default.
+    ///
+    /// [RFC 6936]: https://tools.ietf.org/html/rfc6936
+    pub fn set_send_checksum_enabled(&mut self, enabled: bool) {
+        self.send_checksum = enabled
+    }
+
+    /// Return whether the socket accepts UDP-over-IPv6 packets with a zero checksum.
+    ///
+    /// See also the [set_recv_zero_checksum_enabled](#method.set_recv_zero_checksum_enabled) method
+    pub fn recv_zero_checksum_enabled(&self) -> bool {
+        self.recv_zero_checksum
+    }
+
+    /// Set whether the socket accepts UDP-over-IPv6 packets with a zero checksum, as
+    /// described in [RFC 6936].
+    ///
+    /// Such packets are dropped by default. UDP-over-IPv4 packets with a zero checksum
+    /// are always accepted.
+    ///
+    /// [RFC 6936]: https://tools.ietf.org/html/rfc6936
+    pub fn set_recv_zero_checksum_enabled(&mut self, enabled: bool) {
+        self.recv_zero_checksum = enabled
+    }
+
+    /// Return the number of received packets from which the socket is readable.
+    ///
+    /// See also the [set_recv_watermark](#method.set_recv_watermark) method
+    pub fn recv_watermark(&self) -> usize {
+        self.recv_watermark
+    }
+
+    /// Set the number of received packets from which the socket is readable.
+    ///
+    /// The readable waker is only woken once this many packets are queued, so that a task
+    /// can process them in a batch. The default, and minimum, is 1 packet.
+    pub fn set_recv_watermark(&mut self, count: usize) {
+        self.recv_watermark = count.max(1);
+        #[cfg(feature = "async")]
+        if self.is_readable() {
+            self.readable_waker.wake()
+        }
+    }
+
+    /// Return the number of free packet slots from which the socket is writable.
+    ///
+    /// See also the [set_send_watermark](#method.set_send_watermark) method
+    pub fn send_watermark(&self) -> usize {
+        self.send_watermark
+    }
+
+    /// Set the number of free packet slots from which the socket is writable.
+    ///
+    /// The writable waker is only woken once this many packets can be queued, so that a
+    /// task can queue them in a batch. Only the packet slots of the transmit buffer are
+    /// counted, not its payload space. The default, and minimum, is 1 packet.
+    pub fn set_send_watermark(&mut self, count: usize) {
+        self.send_watermark = count.max(1);
+        #[cfg(feature = "async")]
+        if self.is_writable() {
+            self.writable_waker.wake()
+        }
+    }
+
+    /// Check whether the number of received packets reached the receive watermark.
+    ///
+    /// See also the [set_recv_watermark](#method.set_recv_watermark) method
+    pub fn is_readable(&self) -> bool {
+        self.rx_buffer.packet_count() >= self.recv_watermark
+    }
+
+    /// Check whether the number of free packet slots reached the send watermark.
+    ///
+    /// See also the [set_send_watermark](#method.set_send_watermark) method
+    pub fn is_writable(&self) -> bool {
+        self.free_send_slots() >= self.send_watermark
+    }
+
+    fn free_send_slots(&self) -> usize {
+        self.tx_buffer.packet_capacity() - self.tx_buffer.packet_count()
+    }
+
+    /// Return whether IPv4 datagrams larger than the MTU are fragmented.
+    ///
+    /// See also the [set_fragmentation_enabled](#method.set_fragmentation_enabled) method
+    pub fn fragmentation_enabled(&self) -> bool {
+        self.fragmentation
+    }
+
+    /// Set whether IPv4 datagrams larger than the MTU are fragmented.
+    ///
+    /// By default, such datagrams are dropped when they are dispatched. When enabled, they
+    /// are split into IP fragments, which requires the `proto-ipv4-fragmentation` feature
+    /// and a fragmentation buffer large enough to hold the whole datagram (see the
+    /// `fragmentation-buffer-size-*` features). IPv6 datagrams are never fragmented.
+    pub fn set_fragmentation_enabled(&mut self, enabled: bool) {
+        self.fragmentation = enabled
+    }
+
+    /// Return the last path MTU reported for a destination of the socket, along with the
+    /// address of that destination.
+    ///
+    /// The path MTU is reported by an ICMPv6 Packet Too Big message, sent by a router that
+    /// dropped a datagram of the socket for being larger than the MTU of its next link.
+    /// Since IPv6 datagrams are not fragmented, later datagrams to that destination have to
+    /// fit in it to get through. The interface keeps track of the path MTUs of all
+    /// destinations for ten minutes.
+    pub fn path_mtu(&self) -> Option<(IpAddress, usize)> {
+        self.path_mtu
+    }
+
+    /// Return whether the don't fragment flag is set in sent IPv4 packets.
+    ///
+    /// See also the [set_dont_fragment](#method.set_dont_fragment) method
+    pub fn dont_fragment(&self) -> bool {
+        self.dont_fragment
+    }
+
+    /// Set whether the don't fragment flag is set in sent IPv4 packets.
+    ///
+    /// The flag is set by default, so that routers drop the packets that do not fit the
+    /// MTU of the next link instead of fragmenting them. It is always cleared in the
+    /// fragments of a datagram, see the
+    /// [set_fragmentation_enabled](#method.set_fragmentation_enabled) method.
+    pub fn set_dont_fragment(&mut self, dont_fragment: bool) {
+        self.dont_fragment = dont_fragment
+    }
+
+    /// Return whether the socket may send and receive broadcast packets.
+    ///
+    /// See also the [set_broadcast_enabled](#method.set_broadcast_enabled) method
+    pub fn broadcast_enabled(&self) -> bool {
+        self.broadcast
+    }
+
+    /// Set whether the socket may send and receive broadcast packets.
+    ///
+    /// When this is disabled, the socket does not send packets to the limited broadcast
+    /// address, or to the directed broadcast address of a subnet of the interface, nor
+    /// receive packets sent to them. It is enabled by default.
+    pub fn set_broadcast_enabled(&mut self, enabled: bool) {
+        self.broadcast = enabled
+    }
+
+    /// Join a multicast group, so that packets sent to it are delivered to the socket.
+    ///
+    /// Packets sent to a multicast group are only delivered to the sockets that joined it,
+    /// except for the all-systems (IPv4) and link-local all-nodes (IPv6) groups, which every
+    /// socket receives. The interface reports the IPv4 groups joined by its sockets via IGMP
+    /// and the IPv6 groups via MLDv2 on the next call to [poll](crate::iface::Interface::poll),
+    /// and leaves them once no socket is a member anymore.
+    ///
+    /// This function returns `Err(Error::Unaddressable)` if the address is not a multicast
+    /// address, and `Err(Error::GroupTableFull)` if the socket already joined as many groups
+    /// as the interface can hold.
+    pub fn join_multicast_group<T: Into<IpAddress>>(
+        &mut self,
+        addr: T,
+    ) -> Result<(), MulticastError> {
+        let addr = addr.into();
+        if !addr.is_multicast() {
+            return Err(MulticastError::Unaddressable);
+        }
+        if self.multicast_groups.contains(&addr) {
+            return Ok(());
+        }
+
+        self.multicast_groups
+            .push(addr)
+            .map_err(|_| MulticastError::GroupTableFull)
+    }
+
+    /// Leave a multicast group.
+    ///
+    /// Returns whether the socket was a member of the group.
+    pub fn leave_multicast_group<T: Into<IpAddress>>(&mut self, addr: T) -> bool {
+        let addr = addr.into();
+        match self
+            .multicast_groups
+            .iter()
+            .position(|group| *group == addr)
+        {
+            Some(index) => {
+                self.multicast_groups.swap_remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return whether the multicast packets sent by the socket are delivered locally.
+    ///
+    /// See also the [set_multicast_loop_enabled](#method.set_multicast_loop_enabled) method
+    pub fn multicast_loop_enabled(&self) -> bool {
+        self.multicast_loop
+    }
+
+    /// Set whether the multicast packets sent by the socket are also delivered to the
+    /// other local sockets that joined their group, like the `IP_MULTICAST_LOOP` option.
+    ///
+    /// This is disabled by default. The packets are never delivered to the sending socket.
+    pub fn set_multicast_loop_enabled(&mut self, enabled: bool) {
+        self.multicast_loop = enabled
+    }
+
+    /// Check whether the socket is a member of the given multicast group.
+    pub fn has_multicast_group<T: Into<IpAddress>>(&self, addr: T) -> bool {
+        self.multicast_groups.contains(&addr.into())
+    }
+
+    /// Return the multicast groups the socket is a member of.
+    pub fn multicast_groups(&self) -> impl Iterator<Item = IpAddress> + '_ {
+        self.multicast_groups.iter().copied()
+    }
+
     /// Bind the socket to the given endpoint.
     ///
+    /// Several sockets may be bound to the same port. Unicast and broadcast packets are
+    /// delivered to the first of them accepting the packet, and multicast packets to every
+    /// one of them that joined the group of the packet
+    /// (see [join_multicast_group](#method.join_multicast_group)).
+    ///
     /// This function returns `Err(Error::Illegal)` if the socket was open
     /// (see [is_open](#method.is_open)), and `Err(Error::Unaddressable)`
     /// if the port in the given endpoint is zero.
@@ -222,15 +753,50 @@ impl<'a> Socket<'a> {
         {
             self.rx_waker.wake();
             self.tx_waker.wake();
+            self.readable_waker.wake();
+            self.writable_waker.wake();
+        }
+
+        Ok(())
+    }
+
+    /// Connect the socket to the given remote endpoint.
+    ///
+    /// Once connected, the socket only receives packets sent from that endpoint, and
+    /// packets can be sent to it with [send_connected](#method.send_connected). Packets
+    /// can still be sent to other endpoints with [send](#method.send). Connecting again
+    /// replaces the remote endpoint.
+    ///
+    /// This function returns `Err(Error::InvalidState)` if the socket is not bound
+    /// (see [bind](#method.bind)), and `Err(Error::Unaddressable)` if the address or port
+    /// of the given endpoint is unspecified.
+    pub fn connect<T: Into<IpEndpoint>>(&mut self, remote_endpoint: T) -> Result<(), ConnectError> {
+        let remote_endpoint = remote_endpoint.into();
+        if remote_endpoint.addr.is_unspecified() || remote_endpoint.port == 0 {
+            return Err(ConnectError::Unaddressable);
         }
 
+        if !self.is_open() {
+            return Err(ConnectError::InvalidState);
+        }
+
+        self.remote_endpoint = Some(remote_endpoint);
         Ok(())
     }
 
+    /// Disconnect the socket from its remote endpoint, so that it receives packets
+    /// from any endpoint again.
+    pub fn disconnect(&mut self) {
+        self.remote_endpoint = None;
+    }
+
     /// Close the socket.
     pub fn close(&mut self) {
-        // Clear the bound endpoint of the socket.
+        // Clear the bound and connected endpoints, and the multicast groups of the socket.
         self.endpoint = IpListenEndpoint::default();
+        self.remote_endpoint = None;
+        self.multicast_groups.clear();
+        self.path_mtu = None;
 
         // Reset the RX and TX buffers of the socket.
         self.tx_buffer.reset();
@@ -240,6 +806,8 @@ impl<'a> Socket<'a> {
         {
             self.rx_waker.wake();
             self.tx_waker.wake();
+            self.readable_waker.wake();
+            self.writable_waker.wake();
         }
     }
 
@@ -285,28 +853,61 @@ impl<'a> Socket<'a> {
         self.tx_buffer.payload_capacity()
     }
 
+    fn send_check(&self, meta: &UdpMetadata) -> Result<(), SendError> {
+        if self.endpoint.port == 0 {
+            return Err(SendError::Unaddressable);
+        }
+        if meta.endpoint.addr.is_unspecified() {
+            return Err(SendError::Unaddressable);
+        }
+        if meta.endpoint.port == 0 {
+            return Err(SendError::Unaddressable);
+        }
+        if meta.endpoint.addr.is_broadcast() && !self.broadcast {
+            return Err(SendError::Unaddressable);
+        }
+        if let Some(local_address) = meta.local_address {
+            if local_address.is_unspecified()
+                || local_address.is_multicast()
+                || local_address.version() != meta.endpoint.addr.version()
+            {
+                return Err(SendError::Unaddressable);
+            }
+        }
+        // A host MUST NOT send a datagram with a hop limit value of 0
+        if let Some(0) = meta.hop_limit {
+            panic!("the time-to-live value of a packet must not be zero")
+        }
+        assert!(meta.dscp <= 0x3f, "the DSCP must fit in 6 bits");
+
+        Ok(())
+    }
+
     /// Enqueue a packet to be sent to a given remote endpoint, and return a pointer
     /// to its payload.
     ///
+    /// Besides the remote endpoint, the metadata may specify the source address,
+    /// hop limit, DSCP and ECN bits of this packet (see [UdpMetadata]).
+    ///
     /// This function returns `Err(Error::Exhausted)` if the transmit buffer is full,
     /// `Err(Error::Unaddressable)` if local or remote port, or remote address are unspecified,
-    /// and `Err(Error::Truncated)` if there is not enough transmit buffer capacity
-    /// to ever send this packet.
+    /// if the remote address is the limited broadcast address and broadcast is not enabled
+    /// (see [set_broadcast_enabled](#method.set_broadcast_enabled)), or if the source
+    /// address is unspecified, multicast, or of another IP version than the remote address,
+    /// and `Err(Error::Truncated)` if there is not enough transmit buffer capacity to ever
+    /// send this packet.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the metadata specifies a hop limit value of 0,
+    /// or a DSCP that does not fit in 6 bits.
     pub fn send(
         &mut self,
         size: usize,
         meta: impl Into<UdpMetadata>,
     ) -> Result<&mut [u8], SendError> {
         let meta = meta.into();
-        if self.endpoint.port == 0 {
-            return Err(SendError::Unaddressable);
-        }
-        if meta.endpoint.addr.is_unspecified() {
-            return Err(SendError::Unaddressable);
-        }
-        if meta.endpoint.port == 0 {
-            return Err(SendError::Unaddressable);
-        }
+        self.send_check(&meta)?;
 
         let payload_buf = self
             .tx_buffer
@@ -337,15 +938,7 @@ impl<'a> Socket<'a> {
         F: FnOnce(&mut [u8]) -> usize,
     {
         let meta = meta.into();
-        if self.endpoint.port == 0 {
-            return Err(SendError::Unaddressable);
-        }
-        if meta.endpoint.addr.is_unspecified() {
-            return Err(SendError::Unaddressable);
-        }
-        if meta.endpoint.port == 0 {
-            return Err(SendError::Unaddressable);
-        }
+        self.send_check(&meta)?;
 
         let size = self
             .tx_buffer
@@ -373,17 +966,62 @@ impl<'a> Socket<'a> {
         Ok(())
     }
 
-    /// Dequeue a packet received from a remote endpoint, and return the endpoint as well
-    /// as a pointer to the payload.
+    /// Enqueue several packets to be sent, each filled from a slice and sent to the remote
+    /// endpoint given with it.
     ///
-    /// This function returns `Err(Error::Exhausted)` if the receive buffer is empty.
-    pub fn recv(&mut self) -> Result<(&[u8], UdpMetadata), RecvError> {
-        let (remote_endpoint, payload_buf) =
-            self.rx_buffer.dequeue().map_err(|_| RecvError::Exhausted)?;
-
-        net_trace!(
-            "udp:{}:{}: receive {} buffered octets",
-            self.endpoint,
+    /// The interface dispatches all the packets queued in a socket at once, as long as the
+    /// device accepts them. This function returns the number of packets enqueued, which is
+    /// lower than the number given if the transmit buffer fills up, or if a packet cannot
+    /// be sent; it only returns an error if not even the first packet could be enqueued.
+    ///
+    /// See also [send_slice](#method.send_slice).
+    pub fn send_batch<'p, I, M>(&mut self, packets: I) -> Result<usize, SendError>
+    where
+        I: IntoIterator<Item = (&'p [u8], M)>,
+        M: Into<UdpMetadata>,
+    {
+        let mut count = 0;
+        for (data, meta) in packets {
+            match self.send_slice(data, meta) {
+                Ok(()) => count += 1,
+                Err(err) if count == 0 => return Err(err),
+                Err(_) => break,
+            }
+        }
+        Ok(count)
+    }
+
+    /// Enqueue a packet to be sent to the remote endpoint the socket is connected to,
+    /// and return a pointer to its payload.
+    ///
+    /// This function returns `Err(Error::Unaddressable)` if the socket is not connected
+    /// (see [connect](#method.connect)), and otherwise behaves identically to
+    /// [send](#method.send).
+    pub fn send_connected(&mut self, size: usize) -> Result<&mut [u8], SendError> {
+        let remote_endpoint = self.remote_endpoint.ok_or(SendError::Unaddressable)?;
+        self.send(size, remote_endpoint)
+    }
+
+    /// Enqueue a packet to be sent to the remote endpoint the socket is connected to,
+    /// and fill it from a slice.
+    ///
+    /// See also [send_connected](#method.send_connected).
+    pub fn send_slice_connected(&mut self, data: &[u8]) -> Result<(), SendError> {
+        self.send_connected(data.len())?.copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Dequeue a packet received from a remote endpoint, and return the endpoint as well
+    /// as a pointer to the payload.
+    ///
+    /// This function returns `Err(Error::Exhausted)` if the receive buffer is empty.
+    pub fn recv(&mut self) -> Result<(&[u8], UdpMetadata), RecvError> {
+        let (remote_endpoint, payload_buf) =
+            self.rx_buffer.dequeue().map_err(|_| RecvError::Exhausted)?;
+
+        net_trace!(
+            "udp:{}:{}: receive {} buffered octets",
+            self.endpoint,
             remote_endpoint.endpoint,
             payload_buf.len()
         );
@@ -401,6 +1039,30 @@ impl<'a> Socket<'a> {
         Ok((length, endpoint))
     }
 
+    /// Dequeue up to `max_count` packets received from remote endpoints, passing each
+    /// payload and its metadata to the provided closure, and return the number of packets
+    /// dequeued.
+    ///
+    /// This function returns `Err(Error::Exhausted)` if the receive buffer is empty.
+    pub fn recv_batch<F>(&mut self, max_count: usize, mut f: F) -> Result<usize, RecvError>
+    where
+        F: FnMut(&[u8], UdpMetadata),
+    {
+        let mut count = 0;
+        while count < max_count {
+            match self.recv() {
+                Ok((payload, meta)) => f(payload, meta),
+                Err(_) => break,
+            }
+            count += 1;
+        }
+
+        if count == 0 && max_count != 0 {
+            return Err(RecvError::Exhausted);
+        }
+        Ok(count)
+    }
+
     /// Peek at a packet received from a remote endpoint, and return the endpoint as well
     /// as a pointer to the payload without removing the packet from the receive buffer.
     /// This function otherwise behaves identically to [recv](#method.recv).
@@ -434,21 +1096,66 @@ impl<'a> Socket<'a> {
         Ok((length, endpoint))
     }
 
+    /// Record the path MTU reported by a Packet Too Big message quoting a datagram sent
+    /// from `local` to `remote`, if it was sent by the socket.
+    #[cfg(feature = "proto-ipv6")]
+    pub(crate) fn path_mtu_reduced(&mut self, local: IpEndpoint, remote: IpEndpoint, mtu: usize) {
+        if self.endpoint.port != local.port
+            || self.endpoint.addr.map_or(false, |addr| addr != local.addr)
+            || self
+                .remote_endpoint
+                .map_or(false, |endpoint| endpoint != remote)
+        {
+            return;
+        }
+        net_debug!(
+            "udp:{}: path mtu to {} is {}",
+            self.endpoint,
+            remote.addr,
+            mtu
+        );
+        self.path_mtu = Some((remote.addr, mtu));
+    }
+
     pub(crate) fn accepts(&self, cx: &mut Context, ip_repr: &IpRepr, repr: &UdpRepr) -> bool {
+        if (ip_repr.next_header() == IpProtocol::UdpLite) != self.lite {
+            return false;
+        }
         if self.endpoint.port != repr.dst_port {
             return false;
         }
+        let is_broadcast = cx.is_broadcast(&ip_repr.dst_addr());
+        if is_broadcast && !self.broadcast {
+            return false;
+        }
         if self.endpoint.addr.is_some()
             && self.endpoint.addr != Some(ip_repr.dst_addr())
-            && !cx.is_broadcast(&ip_repr.dst_addr())
+            && !is_broadcast
             && !ip_repr.dst_addr().is_multicast()
         {
             return false;
         }
+        if let Some(remote_endpoint) = self.remote_endpoint {
+            if remote_endpoint.addr != ip_repr.src_addr() || remote_endpoint.port != repr.src_port {
+                return false;
+            }
+        }
+        if ip_repr.dst_addr().is_multicast()
+            && !is_all_nodes(&ip_repr.dst_addr())
+            && !self.multicast_groups.contains(&ip_repr.dst_addr())
+        {
+            return false;
+        }
 
         true
     }
 
+    /// Check whether a UDP-Lite packet, of the given length and checksum coverage,
+    /// covers enough octets to be delivered to the socket.
+    pub(crate) fn accepts_checksum_coverage(&self, packet_len: usize, coverage: u16) -> bool {
+        coverage == 0 || coverage as usize == packet_len || coverage >= self.recv_checksum_coverage
+    }
+
     pub(crate) fn process(
         &mut self,
         cx: &mut Context,
@@ -474,12 +1181,22 @@ impl<'a> Socket<'a> {
         );
 
         let metadata = UdpMetadata {
-            endpoint: remote_endpoint,
+            local_address: Some(ip_repr.dst_addr()),
+            hop_limit: Some(ip_repr.hop_limit()),
+            timestamp: Some(cx.now()),
             meta,
+            ..remote_endpoint.into()
         };
 
         match self.rx_buffer.enqueue(size, metadata) {
-            Ok(buf) => buf.copy_from_slice(payload),
+            Ok(buf) => {
+                buf.copy_from_slice(payload);
+                // Only wake the readable waker when the watermark is crossed.
+                #[cfg(feature = "async")]
+                if self.rx_buffer.packet_count() == self.recv_watermark {
+                    self.readable_waker.wake();
+                }
+            }
             Err(_) => net_trace!(
                 "udp:{}:{}: buffer full, dropped incoming packet",
                 self.endpoint,
@@ -493,13 +1210,31 @@ impl<'a> Socket<'a> {
 
     pub(crate) fn dispatch<F, E>(&mut self, cx: &mut Context, emit: F) -> Result<(), E>
     where
-        F: FnOnce(&mut Context, PacketMeta, (IpRepr, UdpRepr, &[u8])) -> Result<(), E>,
+        F: FnOnce(&mut Context, PacketMeta, u8, (IpRepr, UdpRepr, &[u8])) -> Result<(), E>,
     {
         let endpoint = self.endpoint;
-        let hop_limit = self.hop_limit.unwrap_or(64);
+        let socket_hop_limit = self.hop_limit;
+        let broadcast = self.broadcast;
+        #[cfg(feature = "proto-ipv4")]
+        let fragmentation = self.fragmentation;
+        let socket_traffic_class = self.traffic_class;
+        let protocol = if self.lite {
+            IpProtocol::UdpLite
+        } else {
+            IpProtocol::Udp
+        };
 
         let res = self.tx_buffer.dequeue_with(|packet_meta, payload_buf| {
-            let src_addr = match endpoint.addr {
+            if !broadcast && cx.is_broadcast(&packet_meta.endpoint.addr) {
+                net_debug!(
+                    "udp:{}:{}: broadcast is not enabled, dropping.",
+                    endpoint,
+                    packet_meta.endpoint
+                );
+                return Ok(());
+            }
+
+            let src_addr = match packet_meta.local_address.or(endpoint.addr) {
                 Some(addr) => addr,
                 None => match cx.get_source_address(packet_meta.endpoint.addr) {
                     Some(addr) => addr,
@@ -525,27 +1260,71 @@ impl<'a> Socket<'a> {
                 src_port: endpoint.port,
                 dst_port: packet_meta.endpoint.port,
             };
+            let hop_limit = packet_meta
+                .hop_limit
+                .or(socket_hop_limit)
+                .unwrap_or_else(|| cx.hop_limit(&packet_meta.endpoint.addr));
             let ip_repr = IpRepr::new(
                 src_addr,
                 packet_meta.endpoint.addr,
-                IpProtocol::Udp,
+                protocol,
                 repr.header_len() + payload_buf.len(),
                 hop_limit,
             );
 
-            emit(cx, packet_meta.meta, (ip_repr, repr, payload_buf))
+            #[cfg(feature = "proto-ipv4")]
+            if !fragmentation
+                && matches!(ip_repr, IpRepr::Ipv4(_))
+                && ip_repr.buffer_len() > cx.ip_mtu()
+            {
+                net_debug!(
+                    "udp:{}:{}: datagram is larger than the MTU, dropping.",
+                    endpoint,
+                    packet_meta.endpoint
+                );
+                return Ok(());
+            }
+
+            let dscp = match packet_meta.dscp {
+                0 => socket_traffic_class >> 2,
+                dscp => dscp,
+            };
+            debug_assert!(dscp <= 0x3f);
+            let ecn = match packet_meta.ecn & 0x03 {
+                0 => socket_traffic_class & 0x03,
+                ecn => ecn,
+            };
+            let traffic_class = (dscp << 2) | ecn;
+
+            emit(
+                cx,
+                packet_meta.meta,
+                traffic_class,
+                (ip_repr, repr, payload_buf),
+            )
         });
         match res {
             Err(Empty) => Ok(()),
             Ok(Err(e)) => Err(e),
             Ok(Ok(())) => {
                 #[cfg(feature = "async")]
-                self.tx_waker.wake();
+                {
+                    self.tx_waker.wake();
+                    // Only wake the writable waker when the watermark is crossed.
+                    if self.free_send_slots() == self.send_watermark {
+                        self.writable_waker.wake();
+                    }
+                }
                 Ok(())
             }
         }
     }
 
+    /// Check whether packets are queued for transmission.
+    pub(crate) fn send_pending(&self) -> bool {
+        !self.tx_buffer.is_empty()
+    }
+
     pub(crate) fn poll_at(&self, _cx: &mut Context) -> PollAt {
         if self.tx_buffer.is_empty() {
             PollAt::Ingress
@@ -555,6 +1334,17 @@ impl<'a> Socket<'a> {
     }
 }
 
+/// Check whether an address is the group of all the hosts on the link, which every host
+/// is a member of.
+fn is_all_nodes(addr: &IpAddress) -> bool {
+    match addr {
+        #[cfg(feature = "proto-ipv4")]
+        IpAddress::Ipv4(addr) => *addr == crate::wire::Ipv4Address::MULTICAST_ALL_SYSTEMS,
+        #[cfg(feature = "proto-ipv6")]
+        IpAddress::Ipv6(addr) => *addr == crate::wire::Ipv6Address::LINK_LOCAL_ALL_NODES,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -588,6 +1378,8 @@ mod test {
             const LOCAL_ADDR: IpvXAddress = IpvXAddress([192, 168, 1, 1]);
             const REMOTE_ADDR: IpvXAddress = IpvXAddress([192, 168, 1, 2]);
             const OTHER_ADDR: IpvXAddress = IpvXAddress([192, 168, 1, 3]);
+            const GROUP_ADDR: IpvXAddress = IpvXAddress([224, 0, 0, 123]);
+            const ALL_NODES_ADDR: IpvXAddress = IpvXAddress::MULTICAST_ALL_SYSTEMS;
         } else {
             use crate::wire::Ipv6Address as IpvXAddress;
             use crate::wire::Ipv6Repr as IpvXRepr;
@@ -602,6 +1394,10 @@ mod test {
             const OTHER_ADDR: IpvXAddress = IpvXAddress([
                 0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3,
             ]);
+            const GROUP_ADDR: IpvXAddress = IpvXAddress([
+                0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x23,
+            ]);
+            const ALL_NODES_ADDR: IpvXAddress = IpvXAddress::LINK_LOCAL_ALL_NODES;
         }
     }
 
@@ -638,6 +1434,30 @@ mod test {
         hop_limit: 64,
     });
 
+    pub const OTHER_IP_REPR: IpRepr = IpReprIpvX(IpvXRepr {
+        src_addr: OTHER_ADDR,
+        dst_addr: LOCAL_ADDR,
+        next_header: IpProtocol::Udp,
+        payload_len: 8 + 6,
+        hop_limit: 64,
+    });
+
+    const GROUP_IP_REPR: IpRepr = IpReprIpvX(IpvXRepr {
+        src_addr: REMOTE_ADDR,
+        dst_addr: GROUP_ADDR,
+        next_header: IpProtocol::Udp,
+        payload_len: 8 + 6,
+        hop_limit: 64,
+    });
+
+    const ALL_NODES_IP_REPR: IpRepr = IpReprIpvX(IpvXRepr {
+        src_addr: REMOTE_ADDR,
+        dst_addr: ALL_NODES_ADDR,
+        next_header: IpProtocol::Udp,
+        payload_len: 8 + 6,
+        hop_limit: 64,
+    });
+
     const LOCAL_UDP_REPR: UdpRepr = UdpRepr {
         src_port: LOCAL_PORT,
         dst_port: REMOTE_PORT,
@@ -650,6 +1470,16 @@ mod test {
 
     const PAYLOAD: &[u8] = b"abcdef";
 
+    /// The metadata of a packet received from `REMOTE_END` at `LOCAL_ADDR`.
+    fn remote_meta() -> UdpMetadata {
+        UdpMetadata {
+            local_address: Some(LOCAL_ADDR.into()),
+            hop_limit: Some(64),
+            timestamp: Some(Instant::ZERO),
+            ..REMOTE_END.into()
+        }
+    }
+
     #[test]
     fn test_bind_unaddressable() {
         let mut socket = socket(buffer(0), buffer(0));
@@ -670,6 +1500,13 @@ mod test {
         s.set_hop_limit(Some(0));
     }
 
+    #[test]
+    #[should_panic(expected = "the flow label must fit in 20 bits")]
+    fn test_set_flow_label_too_large() {
+        let mut s = socket(buffer(0), buffer(1));
+        s.set_flow_label(Some(0x10_0000));
+    }
+
     #[test]
     fn test_send_unaddressable() {
         let mut socket = socket(buffer(0), buffer(1));
@@ -711,7 +1548,7 @@ mod test {
 
         assert!(socket.can_send());
         assert_eq!(
-            socket.dispatch(&mut cx, |_, _, _| unreachable!()),
+            socket.dispatch(&mut cx, |_, _, _, _| unreachable!()),
             Ok::<_, ()>(())
         );
 
@@ -723,7 +1560,7 @@ mod test {
         assert!(!socket.can_send());
 
         assert_eq!(
-            socket.dispatch(&mut cx, |_, _, (ip_repr, udp_repr, payload)| {
+            socket.dispatch(&mut cx, |_, _, _, (ip_repr, udp_repr, payload)| {
                 assert_eq!(ip_repr, LOCAL_IP_REPR);
                 assert_eq!(udp_repr, LOCAL_UDP_REPR);
                 assert_eq!(payload, PAYLOAD);
@@ -734,7 +1571,7 @@ mod test {
         assert!(!socket.can_send());
 
         assert_eq!(
-            socket.dispatch(&mut cx, |_, _, (ip_repr, udp_repr, payload)| {
+            socket.dispatch(&mut cx, |_, _, _, (ip_repr, udp_repr, payload)| {
                 assert_eq!(ip_repr, LOCAL_IP_REPR);
                 assert_eq!(udp_repr, LOCAL_UDP_REPR);
                 assert_eq!(payload, PAYLOAD);
@@ -774,7 +1611,83 @@ mod test {
             PAYLOAD,
         );
 
-        assert_eq!(socket.recv(), Ok((&b"abcdef"[..], REMOTE_END.into())));
+        assert_eq!(socket.recv(), Ok((&b"abcdef"[..], remote_meta())));
+        assert!(!socket.can_recv());
+    }
+
+    #[test]
+    fn test_send_batch() {
+        let mut socket = socket(buffer(0), buffer(2));
+        let mut cx = Context::mock();
+
+        assert_eq!(
+            socket.send_batch([(PAYLOAD, REMOTE_END)]),
+            Err(SendError::Unaddressable)
+        );
+        assert_eq!(socket.bind(LOCAL_END), Ok(()));
+
+        assert_eq!(socket.send_batch([(PAYLOAD, REMOTE_END); 0]), Ok(0));
+        assert_eq!(socket.send_batch([(PAYLOAD, REMOTE_END); 3]), Ok(2));
+        assert_eq!(
+            socket.send_batch([(PAYLOAD, REMOTE_END)]),
+            Err(SendError::BufferFull)
+        );
+
+        for _ in 0..2 {
+            assert_eq!(
+                socket.dispatch(&mut cx, |_, _, _, (ip_repr, udp_repr, payload)| {
+                    assert_eq!(ip_repr, LOCAL_IP_REPR);
+                    assert_eq!(udp_repr, LOCAL_UDP_REPR);
+                    assert_eq!(payload, PAYLOAD);
+                    Ok::<_, ()>(())
+                }),
+                Ok(())
+            );
+        }
+        assert!(!socket.send_pending());
+
+        // Packets after one that cannot be sent are not enqueued.
+        let unspecified = IpEndpoint::new(IpAddress::from(LOCAL_ADDR), 0);
+        assert_eq!(
+            socket.send_batch([
+                (PAYLOAD, REMOTE_END),
+                (PAYLOAD, unspecified),
+                (PAYLOAD, REMOTE_END)
+            ]),
+            Ok(1)
+        );
+    }
+
+    #[test]
+    fn test_recv_batch() {
+        let mut socket = socket(buffer(3), buffer(0));
+        let mut cx = Context::mock();
+
+        assert_eq!(socket.bind(LOCAL_PORT), Ok(()));
+        assert_eq!(
+            socket.recv_batch(2, |_, _| unreachable!()),
+            Err(RecvError::Exhausted)
+        );
+
+        for _ in 0..3 {
+            socket.process(
+                &mut cx,
+                PacketMeta::default(),
+                &REMOTE_IP_REPR,
+                &REMOTE_UDP_REPR,
+                PAYLOAD,
+            );
+        }
+
+        let mut received = 0;
+        let result = socket.recv_batch(2, |payload, meta| {
+            assert_eq!(payload, PAYLOAD);
+            assert_eq!(meta, remote_meta());
+            received += 1;
+        });
+        assert_eq!(result, Ok(2));
+        assert_eq!(received, 2);
+        assert_eq!(socket.recv_batch(2, |_, _| ()), Ok(1));
         assert!(!socket.can_recv());
     }
 
@@ -794,8 +1707,8 @@ mod test {
             &REMOTE_UDP_REPR,
             PAYLOAD,
         );
-        assert_eq!(socket.peek(), Ok((&b"abcdef"[..], &REMOTE_END.into(),)));
-        assert_eq!(socket.recv(), Ok((&b"abcdef"[..], REMOTE_END.into(),)));
+        assert_eq!(socket.peek(), Ok((&b"abcdef"[..], &remote_meta(),)));
+        assert_eq!(socket.recv(), Ok((&b"abcdef"[..], remote_meta(),)));
         assert_eq!(socket.peek(), Err(RecvError::Exhausted));
     }
 
@@ -816,10 +1729,7 @@ mod test {
         );
 
         let mut slice = [0; 4];
-        assert_eq!(
-            socket.recv_slice(&mut slice[..]),
-            Ok((4, REMOTE_END.into()))
-        );
+        assert_eq!(socket.recv_slice(&mut slice[..]), Ok((4, remote_meta())));
         assert_eq!(&slice, b"abcd");
     }
 
@@ -839,15 +1749,9 @@ mod test {
         );
 
         let mut slice = [0; 4];
-        assert_eq!(
-            socket.peek_slice(&mut slice[..]),
-            Ok((4, &REMOTE_END.into()))
-        );
+        assert_eq!(socket.peek_slice(&mut slice[..]), Ok((4, &remote_meta())));
         assert_eq!(&slice, b"abcd");
-        assert_eq!(
-            socket.recv_slice(&mut slice[..]),
-            Ok((4, REMOTE_END.into()))
-        );
+        assert_eq!(socket.recv_slice(&mut slice[..]), Ok((4, remote_meta())));
         assert_eq!(&slice, b"abcd");
         assert_eq!(socket.peek_slice(&mut slice[..]), Err(RecvError::Exhausted));
     }
@@ -862,7 +1766,7 @@ mod test {
         s.set_hop_limit(Some(0x2a));
         assert_eq!(s.send_slice(b"abcdef", REMOTE_END), Ok(()));
         assert_eq!(
-            s.dispatch(&mut cx, |_, _, (ip_repr, _, _)| {
+            s.dispatch(&mut cx, |_, _, _, (ip_repr, _, _)| {
                 assert_eq!(
                     ip_repr,
                     IpReprIpvX(IpvXRepr {
@@ -879,6 +1783,120 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_set_dscp() {
+        let mut s = socket(buffer(0), buffer(2));
+        let mut cx = Context::mock();
+
+        assert_eq!(s.bind(LOCAL_END), Ok(()));
+
+        s.set_dscp(46);
+        s.set_ecn(2);
+        assert_eq!((s.dscp(), s.ecn()), (46, 2));
+        assert_eq!(s.send_slice(b"abcdef", REMOTE_END), Ok(()));
+        assert_eq!(
+            s.dispatch(&mut cx, |_, _, traffic_class, _| {
+                assert_eq!(traffic_class, 0xba);
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+
+        // The DSCP of a packet takes precedence over the one of the socket.
+        let meta = UdpMetadata {
+            dscp: 10,
+            ..REMOTE_END.into()
+        };
+        assert_eq!(s.send_slice(b"abcdef", meta), Ok(()));
+        assert_eq!(
+            s.dispatch(&mut cx, |_, _, traffic_class, _| {
+                assert_eq!(traffic_class, 0x2a);
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the DSCP must fit in 6 bits")]
+    fn test_set_dscp_too_large() {
+        let mut s = socket(buffer(0), buffer(1));
+        s.set_dscp(64);
+    }
+
+    #[test]
+    #[should_panic(expected = "the DSCP must fit in 6 bits")]
+    fn test_send_metadata_dscp_too_large() {
+        let mut s = socket(buffer(0), buffer(1));
+        assert_eq!(s.bind(LOCAL_END), Ok(()));
+
+        let meta = UdpMetadata {
+            dscp: 64,
+            ..REMOTE_END.into()
+        };
+        let _ = s.send_slice(b"abcdef", meta);
+    }
+
+    #[test]
+    fn test_send_metadata() {
+        let mut s = socket(buffer(0), buffer(1));
+        let mut cx = Context::mock();
+
+        assert_eq!(s.bind(LOCAL_END), Ok(()));
+
+        s.set_hop_limit(Some(0x2a));
+        let meta = UdpMetadata {
+            local_address: Some(OTHER_ADDR.into()),
+            hop_limit: Some(1),
+            dscp: 46,
+            ecn: 1,
+            ..REMOTE_END.into()
+        };
+        assert_eq!(s.send_slice(b"abcdef", meta), Ok(()));
+        assert_eq!(
+            s.dispatch(&mut cx, |_, _, traffic_class, (ip_repr, _, _)| {
+                assert_eq!(
+                    ip_repr,
+                    IpReprIpvX(IpvXRepr {
+                        src_addr: OTHER_ADDR,
+                        dst_addr: REMOTE_ADDR,
+                        next_header: IpProtocol::Udp,
+                        payload_len: 8 + 6,
+                        hop_limit: 1,
+                    })
+                );
+                assert_eq!(traffic_class, 0xb9);
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_send_metadata_unaddressable() {
+        let mut s = socket(buffer(0), buffer(1));
+        assert_eq!(s.bind(LOCAL_PORT), Ok(()));
+
+        let meta = UdpMetadata {
+            local_address: Some(IpvXAddress::UNSPECIFIED.into()),
+            ..REMOTE_END.into()
+        };
+        assert_eq!(s.send_slice(b"abcdef", meta), Err(SendError::Unaddressable));
+    }
+
+    #[test]
+    #[should_panic(expected = "the time-to-live value of a packet must not be zero")]
+    fn test_send_metadata_hop_limit_zero() {
+        let mut s = socket(buffer(0), buffer(1));
+        assert_eq!(s.bind(LOCAL_PORT), Ok(()));
+
+        let meta = UdpMetadata {
+            hop_limit: Some(0),
+            ..REMOTE_END.into()
+        };
+        let _ = s.send_slice(b"abcdef", meta);
+    }
+
     #[test]
     fn test_doesnt_accept_wrong_port() {
         let mut socket = socket(buffer(1), buffer(0));
@@ -905,6 +1923,326 @@ mod test {
         assert!(!ip_bound_socket.accepts(&mut cx, &BAD_IP_REPR, &REMOTE_UDP_REPR));
     }
 
+    #[test]
+    fn test_connect() {
+        let mut socket = socket(buffer(1), buffer(1));
+        let mut cx = Context::mock();
+
+        assert_eq!(socket.connect(REMOTE_END), Err(ConnectError::InvalidState));
+        assert_eq!(
+            socket.send_slice_connected(b"abcdef"),
+            Err(SendError::Unaddressable)
+        );
+
+        assert_eq!(socket.bind(LOCAL_END), Ok(()));
+        assert_eq!(
+            socket.connect(IpEndpoint {
+                port: 0,
+                ..REMOTE_END
+            }),
+            Err(ConnectError::Unaddressable)
+        );
+        assert_eq!(socket.connect(REMOTE_END), Ok(()));
+        assert_eq!(socket.remote_endpoint(), Some(REMOTE_END));
+
+        assert_eq!(socket.send_slice_connected(b"abcdef"), Ok(()));
+        assert_eq!(
+            socket.dispatch(&mut cx, |_, _, _, (ip_repr, udp_repr, payload)| {
+                assert_eq!(ip_repr, LOCAL_IP_REPR);
+                assert_eq!(udp_repr, LOCAL_UDP_REPR);
+                assert_eq!(payload, PAYLOAD);
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+
+        // Only packets from the remote endpoint are received.
+        assert!(socket.accepts(&mut cx, &REMOTE_IP_REPR, &REMOTE_UDP_REPR));
+        let mut udp_repr = REMOTE_UDP_REPR;
+        udp_repr.src_port += 1;
+        assert!(!socket.accepts(&mut cx, &REMOTE_IP_REPR, &udp_repr));
+        assert!(!socket.accepts(&mut cx, &OTHER_IP_REPR, &REMOTE_UDP_REPR));
+
+        socket.disconnect();
+        assert_eq!(socket.remote_endpoint(), None);
+        assert!(socket.accepts(&mut cx, &OTHER_IP_REPR, &udp_repr));
+    }
+
+    #[test]
+    fn test_watermarks() {
+        let mut socket = socket(buffer(3), buffer(3));
+        let mut cx = Context::mock();
+        assert_eq!(socket.bind(LOCAL_END), Ok(()));
+
+        socket.set_recv_watermark(2);
+        for _ in 0..2 {
+            assert!(!socket.is_readable());
+            socket.process(
+                &mut cx,
+                PacketMeta::default(),
+                &REMOTE_IP_REPR,
+                &REMOTE_UDP_REPR,
+                PAYLOAD,
+            );
+        }
+        assert!(socket.is_readable());
+
+        socket.set_send_watermark(2);
+        assert!(socket.is_writable());
+        assert_eq!(socket.send_slice(b"abcdef", REMOTE_END), Ok(()));
+        assert_eq!(socket.send_slice(b"abcdef", REMOTE_END), Ok(()));
+        assert!(!socket.is_writable());
+        assert_eq!(
+            socket.dispatch(&mut cx, |_, _, _, _| Ok::<_, ()>(())),
+            Ok(())
+        );
+        assert!(socket.is_writable());
+
+        socket.set_recv_watermark(0);
+        assert_eq!(socket.recv_watermark(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn test_watermark_wakers() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use std::task::Wake;
+
+        #[derive(Default)]
+        struct CountingWaker(AtomicUsize);
+
+        impl Wake for CountingWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let mut socket = socket(buffer(3), buffer(3));
+        let mut cx = Context::mock();
+        assert_eq!(socket.bind(LOCAL_END), Ok(()));
+        socket.set_recv_watermark(2);
+        socket.set_send_watermark(3);
+
+        let readable = Arc::new(CountingWaker::default());
+        let writable = Arc::new(CountingWaker::default());
+        socket.register_readable_waker(&Waker::from(readable.clone()));
+
+        // The readable waker is only woken when the watermark is crossed.
+        for count in [0, 1] {
+            socket.process(
+                &mut cx,
+                PacketMeta::default(),
+                &REMOTE_IP_REPR,
+                &REMOTE_UDP_REPR,
+                PAYLOAD,
+            );
+            assert_eq!(readable.0.load(Ordering::SeqCst), count);
+        }
+        assert!(socket.recv().is_ok());
+        socket.register_readable_waker(&Waker::from(readable.clone()));
+        assert_eq!(readable.0.load(Ordering::SeqCst), 1);
+        socket.process(
+            &mut cx,
+            PacketMeta::default(),
+            &REMOTE_IP_REPR,
+            &REMOTE_UDP_REPR,
+            PAYLOAD,
+        );
+        assert_eq!(readable.0.load(Ordering::SeqCst), 2);
+
+        // The writable waker is woken immediately if the socket is already writable.
+        socket.register_writable_waker(&Waker::from(writable.clone()));
+        assert_eq!(writable.0.load(Ordering::SeqCst), 1);
+
+        assert_eq!(socket.send_slice(b"abcdef", REMOTE_END), Ok(()));
+        assert_eq!(socket.send_slice(b"abcdef", REMOTE_END), Ok(()));
+        socket.register_writable_waker(&Waker::from(writable.clone()));
+        for count in [1, 2] {
+            assert_eq!(
+                socket.dispatch(&mut cx, |_, _, _, _| Ok::<_, ()>(())),
+                Ok(())
+            );
+            assert_eq!(writable.0.load(Ordering::SeqCst), count);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_fragmentation() {
+        let mut socket = socket(
+            buffer(0),
+            PacketBuffer::new(vec![PacketMetadata::EMPTY], vec![0; 2000]),
+        );
+        let mut cx = Context::mock();
+        assert_eq!(socket.bind(LOCAL_END), Ok(()));
+        let mtu = cx.ip_mtu();
+
+        // Datagrams larger than the MTU are dropped by default.
+        assert_eq!(socket.send_slice(&[0x2a; 1500], REMOTE_END), Ok(()));
+        assert_eq!(
+            socket.dispatch(&mut cx, |_, _, _, _| unreachable!()),
+            Ok::<_, ()>(())
+        );
+        assert!(!socket.send_pending());
+
+        socket.set_fragmentation_enabled(true);
+        assert_eq!(socket.send_slice(&[0x2a; 1500], REMOTE_END), Ok(()));
+        assert_eq!(
+            socket.dispatch(&mut cx, |_, _, _, (ip_repr, _, payload)| {
+                assert!(ip_repr.buffer_len() > mtu);
+                assert_eq!(payload.len(), 1500);
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+        assert!(!socket.send_pending());
+    }
+
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_broadcast() {
+        use crate::wire::{Ipv4Address, Ipv4Repr};
+
+        let mut socket = socket(buffer(1), buffer(1));
+        let mut cx = Context::mock();
+        assert_eq!(socket.bind(LOCAL_PORT), Ok(()));
+        assert!(socket.broadcast_enabled());
+
+        let limited = Ipv4Address::BROADCAST;
+        let directed = Ipv4Address::new(192, 168, 1, 255);
+        let broadcast_ip_repr = |dst_addr| {
+            IpRepr::Ipv4(Ipv4Repr {
+                src_addr: REMOTE_ADDR,
+                dst_addr,
+                next_header: IpProtocol::Udp,
+                payload_len: 8 + 6,
+                hop_limit: 64,
+            })
+        };
+
+        // Broadcasts are received and sent by default.
+        assert!(socket.accepts(&mut cx, &broadcast_ip_repr(limited), &REMOTE_UDP_REPR));
+        assert!(socket.accepts(&mut cx, &broadcast_ip_repr(directed), &REMOTE_UDP_REPR));
+        assert_eq!(
+            socket.send_slice(PAYLOAD, IpEndpoint::new(limited.into(), REMOTE_PORT)),
+            Ok(())
+        );
+        assert_eq!(
+            socket.dispatch(&mut cx, |_, _, _, (ip_repr, _, _)| {
+                assert_eq!(ip_repr.dst_addr(), IpAddress::Ipv4(limited));
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+
+        // Broadcasts are neither received nor sent once disabled.
+        socket.set_broadcast_enabled(false);
+        assert!(!socket.accepts(&mut cx, &broadcast_ip_repr(limited), &REMOTE_UDP_REPR));
+        assert!(!socket.accepts(&mut cx, &broadcast_ip_repr(directed), &REMOTE_UDP_REPR));
+        assert_eq!(
+            socket.send_slice(PAYLOAD, IpEndpoint::new(limited.into(), REMOTE_PORT)),
+            Err(SendError::Unaddressable)
+        );
+        assert_eq!(
+            socket.send_slice(PAYLOAD, IpEndpoint::new(directed.into(), REMOTE_PORT)),
+            Ok(())
+        );
+        assert_eq!(
+            socket.dispatch(&mut cx, |_, _, _, _| unreachable!()),
+            Ok::<_, ()>(())
+        );
+        assert!(socket.can_send());
+    }
+
+    #[test]
+    fn test_udp_lite() {
+        let mut socket = socket(buffer(1), buffer(1));
+        let mut cx = Context::mock();
+        assert_eq!(socket.bind(LOCAL_END), Ok(()));
+        socket.set_udp_lite_enabled(true);
+        socket.set_send_checksum_coverage(12);
+        socket.set_recv_checksum_coverage(10);
+
+        // Only UDP-Lite packets are received.
+        let ip_repr = IpRepr::new(
+            REMOTE_ADDR.into(),
+            LOCAL_ADDR.into(),
+            IpProtocol::UdpLite,
+            8 + 6,
+            64,
+        );
+        assert!(!socket.accepts(&mut cx, &REMOTE_IP_REPR, &REMOTE_UDP_REPR));
+        assert!(socket.accepts(&mut cx, &ip_repr, &REMOTE_UDP_REPR));
+
+        // Only if their checksum covers enough of them.
+        assert!(!socket.accepts_checksum_coverage(14, 8));
+        assert!(socket.accepts_checksum_coverage(14, 10));
+        assert!(socket.accepts_checksum_coverage(14, 14));
+        assert!(socket.accepts_checksum_coverage(14, 0));
+
+        assert_eq!(socket.send_slice(b"abcdef", REMOTE_END), Ok(()));
+        assert_eq!(
+            socket.dispatch(&mut cx, |_, _, _, (ip_repr, udp_repr, payload)| {
+                assert_eq!(ip_repr.next_header(), IpProtocol::UdpLite);
+                assert_eq!(ip_repr.payload_len(), 8 + 6);
+                assert_eq!(udp_repr, LOCAL_UDP_REPR);
+                assert_eq!(payload, PAYLOAD);
+                Ok::<_, ()>(())
+            }),
+            Ok(())
+        );
+
+        socket.set_udp_lite_enabled(false);
+        assert!(!socket.accepts(&mut cx, &ip_repr, &REMOTE_UDP_REPR));
+    }
+
+    #[test]
+    fn test_multicast_group() {
+        let mut socket = socket(buffer(1), buffer(1));
+        let mut cx = Context::mock();
+        assert_eq!(socket.bind(LOCAL_PORT), Ok(()));
+
+        // Packets sent to a group are only received once the socket joined it.
+        assert!(!socket.accepts(&mut cx, &GROUP_IP_REPR, &REMOTE_UDP_REPR));
+        assert!(socket.accepts(&mut cx, &ALL_NODES_IP_REPR, &REMOTE_UDP_REPR));
+
+        assert_eq!(
+            socket.join_multicast_group(LOCAL_ADDR),
+            Err(MulticastError::Unaddressable)
+        );
+        assert_eq!(socket.join_multicast_group(GROUP_ADDR), Ok(()));
+        assert_eq!(socket.join_multicast_group(GROUP_ADDR), Ok(()));
+        assert!(socket.has_multicast_group(GROUP_ADDR));
+        assert_eq!(
+            socket.multicast_groups().collect::<Vec<_>>(),
+            vec![IpAddress::from(GROUP_ADDR)]
+        );
+        assert!(socket.accepts(&mut cx, &GROUP_IP_REPR, &REMOTE_UDP_REPR));
+
+        assert!(socket.leave_multicast_group(GROUP_ADDR));
+        assert!(!socket.leave_multicast_group(GROUP_ADDR));
+        assert!(!socket.accepts(&mut cx, &GROUP_IP_REPR, &REMOTE_UDP_REPR));
+
+        assert_eq!(socket.join_multicast_group(GROUP_ADDR), Ok(()));
+        socket.close();
+        assert_eq!(socket.multicast_groups().count(), 0);
+    }
+
+    #[test]
+    fn test_multicast_group_table_full() {
+        let mut socket = socket(buffer(0), buffer(0));
+        for i in 0..IFACE_MAX_MULTICAST_GROUP_COUNT {
+            let mut addr = GROUP_ADDR;
+            addr.0[addr.0.len() - 1] = i as u8;
+            assert_eq!(socket.join_multicast_group(addr), Ok(()));
+        }
+        assert_eq!(
+            socket.join_multicast_group(GROUP_ADDR),
+            Err(MulticastError::GroupTableFull)
+        );
+    }
+
     #[test]
     fn test_send_large_packet() {
         // buffer(4) creates a payload buffer of size 16*4
@@ -933,7 +2271,7 @@ mod test {
             dst_port: LOCAL_PORT,
         };
         socket.process(&mut cx, PacketMeta::default(), &REMOTE_IP_REPR, &repr, &[]);
-        assert_eq!(socket.recv(), Ok((&[][..], REMOTE_END.into())));
+        assert_eq!(socket.recv(), Ok((&[][..], remote_meta())));
     }
 
     #[test]
@@ -943,8 +2281,11 @@ mod test {
         let mut socket = socket(recv_buffer, buffer(0));
         assert_eq!(socket.bind(LOCAL_PORT), Ok(()));
 
+        assert_eq!(socket.connect(REMOTE_END), Ok(()));
+
         assert!(socket.is_open());
         socket.close();
         assert!(!socket.is_open());
+        assert_eq!(socket.remote_endpoint(), None);
     }
 }
@@ -1,4 +1,5 @@
 use core::fmt;
+use core::ops::{Deref, DerefMut};
 
 use crate::config::ASSEMBLER_MAX_SEGMENT_COUNT;
 
@@ -91,12 +92,52 @@ impl Contig {
     }
 }
 
+/// Storage for the contigs of an assembler.
+#[derive(Debug, Clone)]
+enum Contigs {
+    Inline([Contig; ASSEMBLER_MAX_SEGMENT_COUNT]),
+    #[cfg(feature = "alloc")]
+    Heap(alloc::boxed::Box<[Contig]>),
+}
+
+impl Deref for Contigs {
+    type Target = [Contig];
+
+    fn deref(&self) -> &[Contig] {
+        match self {
+            Contigs::Inline(contigs) => contigs,
+            #[cfg(feature = "alloc")]
+            Contigs::Heap(contigs) => contigs,
+        }
+    }
+}
+
+impl DerefMut for Contigs {
+    fn deref_mut(&mut self) -> &mut [Contig] {
+        match self {
+            Contigs::Inline(contigs) => contigs,
+            #[cfg(feature = "alloc")]
+            Contigs::Heap(contigs) => contigs,
+        }
+    }
+}
+
+impl PartialEq for Contigs {
+    fn eq(&self, other: &Contigs) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl Eq for Contigs {}
+
 /// A buffer (re)assembler.
 ///
-/// Currently, up to a hardcoded limit of 4 or 32 holes can be tracked in the buffer.
+/// By default, up to `ASSEMBLER_MAX_SEGMENT_COUNT` holes can be tracked in the buffer.
+/// With the `alloc` feature, [set_capacity](Assembler::set_capacity) moves the holes
+/// to heap-allocated storage of any size.
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Assembler {
-    contigs: [Contig; ASSEMBLER_MAX_SEGMENT_COUNT],
+    contigs: Contigs,
 }
 
 impl fmt::Display for Assembler {
@@ -136,7 +177,7 @@ impl Assembler {
     pub const fn new() -> Assembler {
         const EMPTY: Contig = Contig::empty();
         Assembler {
-            contigs: [EMPTY; ASSEMBLER_MAX_SEGMENT_COUNT],
+            contigs: Contigs::Inline([EMPTY; ASSEMBLER_MAX_SEGMENT_COUNT]),
         }
     }
 
@@ -144,6 +185,29 @@ impl Assembler {
         self.contigs.fill(Contig::empty());
     }
 
+    /// Return the maximum number of holes that can be tracked in the buffer.
+    pub fn capacity(&self) -> usize {
+        self.contigs.len()
+    }
+
+    /// Allocate storage for tracking up to `capacity` holes in the buffer.
+    ///
+    /// The recorded ranges are kept if they fit in the new storage, and discarded otherwise.
+    ///
+    /// # Panics
+    /// This function panics if `capacity` is zero.
+    #[cfg(feature = "alloc")]
+    pub fn set_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "assembler capacity must not be zero");
+
+        let mut contigs = alloc::vec![Contig::empty(); capacity].into_boxed_slice();
+        let used = self.contigs.iter().take_while(|c| c.has_data()).count();
+        if used <= capacity {
+            contigs[..used].copy_from_slice(&self.contigs[..used]);
+        }
+        self.contigs = Contigs::Heap(contigs);
+    }
+
     fn front(&self) -> Contig {
         self.contigs[0]
     }
@@ -179,7 +243,8 @@ impl Assembler {
         }
 
         // Removing the last one.
-        self.contigs[self.contigs.len() - 1] = Contig::empty();
+        let last = self.contigs.len() - 1;
+        self.contigs[last] = Contig::empty();
     }
 
     /// Add a contig at the given index, and return a pointer to it.
@@ -390,7 +455,9 @@ mod test {
                     data_size,
                 };
             }
-            Assembler { contigs }
+            Assembler {
+                contigs: Contigs::Inline(contigs),
+            }
         }
     }
 
@@ -691,6 +758,32 @@ mod test {
         assert_eq!(assr.add_then_remove_front(0, 3), Ok(3));
     }
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_set_capacity() {
+        let mut assr = Assembler::new();
+        assert_eq!(assr.capacity(), ASSEMBLER_MAX_SEGMENT_COUNT);
+        assert_eq!(assr.add(2, 3), Ok(()));
+
+        assr.set_capacity(ASSEMBLER_MAX_SEGMENT_COUNT + 4);
+        assert_eq!(assr.capacity(), ASSEMBLER_MAX_SEGMENT_COUNT + 4);
+        assert_eq!(assr.iter_data(0).collect::<Vec<_>>(), vec![(2, 5)]);
+        for c in 1..ASSEMBLER_MAX_SEGMENT_COUNT + 4 {
+            assert_eq!(assr.add(c * 10, 3), Ok(()));
+        }
+        assert_eq!(assr.add(1000, 3), Err(TooManyHolesError));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_set_capacity_discard() {
+        let mut assr = contigs![(2, 3), (4, 5)];
+        assr.set_capacity(1);
+        assert!(assr.is_empty());
+        assert_eq!(assr.add(0, 3), Ok(()));
+        assert_eq!(assr.add(10, 3), Err(TooManyHolesError));
+    }
+
     // Test against an obviously-correct but inefficient bitmap impl.
     #[test]
     fn test_random() {
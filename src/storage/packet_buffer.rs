@@ -234,6 +234,20 @@ impl<'a, H> PacketBuffer<'a, H> {
         self.metadata_ring.capacity()
     }
 
+    /// Return the number of packets in the buffer.
+    pub fn packet_count(&self) -> usize {
+        let len = self.metadata_ring.len();
+        let first = self.metadata_ring.get_allocated(0, len);
+        let second = self
+            .metadata_ring
+            .get_allocated(first.len(), len - first.len());
+        first
+            .iter()
+            .chain(second)
+            .filter(|metadata| !metadata.is_padding())
+            .count()
+    }
+
     /// Return the maximum number of bytes in the payload ring buffer.
     pub fn payload_capacity(&self) -> usize {
         self.payload_ring.capacity()
@@ -290,6 +304,18 @@ mod test {
         assert_eq!(buffer.metadata_ring.len(), 0);
     }
 
+    #[test]
+    fn test_packet_count() {
+        let mut buffer = buffer();
+        assert_eq!(buffer.packet_count(), 0);
+        assert!(buffer.enqueue(6, ()).is_ok());
+        assert!(buffer.enqueue(8, ()).is_ok());
+        assert!(buffer.dequeue().is_ok());
+        assert!(buffer.enqueue(4, ()).is_ok());
+        assert_eq!(buffer.metadata_ring.len(), 3);
+        assert_eq!(buffer.packet_count(), 2);
+    }
+
     #[test]
     fn test_padding_with_large_payload() {
         let mut buffer = buffer();
@@ -110,6 +110,50 @@ pub struct DhcpOption<'a> {
     pub data: &'a [u8],
 }
 
+impl<'a> DhcpOption<'a> {
+    /// Return an iterator over the sub-options encapsulated in the data of this option,
+    /// such as those of the vendor-specific information option, which are encoded like
+    /// the options of a packet.
+    pub fn sub_options(&self) -> impl Iterator<Item = DhcpOption<'a>> + 'a {
+        parse_options(self.data)
+    }
+}
+
+/// Return an iterator over the options encoded in a buffer.
+fn parse_options(mut buf: &[u8]) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
+    iter::from_fn(move || {
+        loop {
+            match buf.first().copied() {
+                // No more options, return.
+                None => return None,
+                Some(field::OPT_END) => return None,
+
+                // Skip padding.
+                Some(field::OPT_PAD) => buf = &buf[1..],
+                Some(kind) => {
+                    if buf.len() < 2 {
+                        return None;
+                    }
+
+                    let len = buf[1] as usize;
+
+                    if buf.len() < 2 + len {
+                        return None;
+                    }
+
+                    let opt = DhcpOption {
+                        kind,
+                        data: &buf[2..2 + len],
+                    };
+
+                    buf = &buf[2 + len..];
+                    return Some(opt);
+                }
+            }
+        }
+    })
+}
+
 /// A read/write wrapper around a Dynamic Host Configuration Protocol packet buffer.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -229,6 +273,21 @@ pub(crate) mod field {
     pub const OPT_REBINDING_TIME_VALUE: u8 = 59;
     pub const OPT_VENDOR_CLASS_ID: u8 = 60;
     pub const OPT_CLIENT_ID: u8 = 61;
+
+    // Rapid Commit Option (RFC 4039)
+    pub const OPT_RAPID_COMMIT: u8 = 80;
+
+    // Client FQDN Option (RFC 4702)
+    pub const OPT_CLIENT_FQDN: u8 = 81;
+
+    // Relay Agent Information Option (RFC 3046), and its sub-options
+    pub const OPT_RELAY_AGENT_INFORMATION: u8 = 82;
+    pub const RELAY_AGENT_CIRCUIT_ID: u8 = 1;
+    pub const RELAY_AGENT_REMOTE_ID: u8 = 2;
+
+    // Classless Static Route Option (RFC 3442), and its pre-standard code
+    pub const OPT_CLASSLESS_STATIC_ROUTE: u8 = 121;
+    pub const OPT_MS_CLASSLESS_STATIC_ROUTE: u8 = 249;
 }
 
 impl<T: AsRef<[u8]>> Packet<T> {
@@ -361,38 +420,7 @@ impl<T: AsRef<[u8]>> Packet<T> {
     /// Return an iterator over the options.
     #[inline]
     pub fn options(&self) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
-        let mut buf = &self.buffer.as_ref()[field::OPTIONS];
-        iter::from_fn(move || {
-            loop {
-                match buf.first().copied() {
-                    // No more options, return.
-                    None => return None,
-                    Some(field::OPT_END) => return None,
-
-                    // Skip padding.
-                    Some(field::OPT_PAD) => buf = &buf[1..],
-                    Some(kind) => {
-                        if buf.len() < 2 {
-                            return None;
-                        }
-
-                        let len = buf[1] as usize;
-
-                        if buf.len() < 2 + len {
-                            return None;
-                        }
-
-                        let opt = DhcpOption {
-                            kind,
-                            data: &buf[2..2 + len],
-                        };
-
-                        buf = &buf[2 + len..];
-                        return Some(opt);
-                    }
-                }
-            }
-        })
+        parse_options(&self.buffer.as_ref()[field::OPTIONS])
     }
 
     pub fn get_sname(&self) -> Result<&str> {
@@ -639,6 +667,10 @@ pub struct Repr<'a> {
     /// it MUST use that same identifier in all subsequent messages, to ensure that all servers
     /// correctly identify the client.
     pub client_identifier: Option<EthernetAddress>,
+    /// The "client identifier" option, when it is not derived from an Ethernet address: a type
+    /// octet followed by the identifier, for example `255` followed by an IAID and a DUID, as
+    /// described in RFC 4361. It is emitted instead of `client_identifier`.
+    pub raw_client_identifier: Option<&'a [u8]>,
     /// The "server identifier" option. It is used both to identify a DHCP server
     /// in a DHCP message and as a destination address from clients to servers.
     pub server_identifier: Option<Ipv4Address>,
@@ -655,12 +687,35 @@ pub struct Repr<'a> {
     pub renew_duration: Option<u32>,
     /// The DHCP IP rebind duration (T2 interval), in seconds, if specified in the packet.
     pub rebind_duration: Option<u32>,
+    /// The "rapid commit" option. A client includes it in a DHCPDISCOVER message to accept
+    /// a DHCPACK in response, and a server includes it in such a DHCPACK.
+    pub rapid_commit: bool,
+    /// The "host name" option, the name of the client.
+    pub hostname: Option<&'a str>,
+    /// The "client FQDN" option, the fully qualified domain name of the client, which the
+    /// server is asked to register in DNS. When returned from [`Repr::parse`], this field
+    /// will be `None`.
+    pub fqdn: Option<&'a str>,
+    /// The "vendor class identifier" option, which identifies the vendor and configuration
+    /// of the client, so that servers can return vendor-specific information.
+    pub vendor_class_id: Option<&'a [u8]>,
     /// When returned from [`Repr::parse`], this field will be `None`.
     /// However, when calling [`Repr::emit`], this field should contain only
     /// additional DHCP options not known to smoltcp.
     pub additional_options: &'a [DhcpOption<'a>],
 }
 
+/// The server should perform the A record update of a client FQDN.
+const FQDN_FLAG_S: u8 = 0x01;
+/// The domain name of a client FQDN is in the canonical wire format.
+const FQDN_FLAG_E: u8 = 0x04;
+
+/// Return the length of a domain name in the canonical wire format.
+fn fqdn_encoded_len(fqdn: &str) -> usize {
+    // A length octet before each label, in place of the dots, and the root label.
+    fqdn.trim_end_matches('.').len() + 2
+}
+
 impl<'a> Repr<'a> {
     /// Return the length of a packet that will be emitted from this high-level representation.
     pub fn buffer_len(&self) -> usize {
@@ -673,6 +728,9 @@ impl<'a> Repr<'a> {
         if self.client_identifier.is_some() {
             len += 9;
         }
+        if let Some(id) = self.raw_client_identifier {
+            len += id.len() + 2;
+        }
         if self.server_identifier.is_some() {
             len += 6;
         }
@@ -695,6 +753,18 @@ impl<'a> Repr<'a> {
         if let Some(list) = self.parameter_request_list {
             len += list.len() + 2;
         }
+        if self.rapid_commit {
+            len += 2;
+        }
+        if let Some(hostname) = self.hostname {
+            len += hostname.len() + 2;
+        }
+        if let Some(fqdn) = self.fqdn {
+            len += 2 + 3 + fqdn_encoded_len(fqdn);
+        }
+        if let Some(id) = self.vendor_class_id {
+            len += id.len() + 2;
+        }
         for opt in self.additional_options {
             len += 2 + opt.data.len()
         }
@@ -732,6 +802,7 @@ impl<'a> Repr<'a> {
         let mut message_type = Err(Error);
         let mut requested_ip = None;
         let mut client_identifier = None;
+        let mut raw_client_identifier = None;
         let mut server_identifier = None;
         let mut router = None;
         let mut subnet_mask = None;
@@ -741,6 +812,9 @@ impl<'a> Repr<'a> {
         let mut lease_duration = None;
         let mut renew_duration = None;
         let mut rebind_duration = None;
+        let mut rapid_commit = false;
+        let mut hostname = None;
+        let mut vendor_class_id = None;
 
         for option in packet.options() {
             let data = option.data;
@@ -754,13 +828,14 @@ impl<'a> Repr<'a> {
                 (field::OPT_REQUESTED_IP, 4) => {
                     requested_ip = Some(Ipv4Address::from_bytes(data));
                 }
-                (field::OPT_CLIENT_ID, 7) => {
-                    let hardware_type = Hardware::from(u16::from(data[0]));
-                    if hardware_type != Hardware::Ethernet {
-                        return Err(Error);
-                    }
+                (field::OPT_CLIENT_ID, 7)
+                    if Hardware::from(u16::from(data[0])) == Hardware::Ethernet =>
+                {
                     client_identifier = Some(EthernetAddress::from_bytes(&data[1..]));
                 }
+                (field::OPT_CLIENT_ID, _) => {
+                    raw_client_identifier = Some(data);
+                }
                 (field::OPT_SERVER_IDENTIFIER, 4) => {
                     server_identifier = Some(Ipv4Address::from_bytes(data));
                 }
@@ -785,6 +860,15 @@ impl<'a> Repr<'a> {
                 (field::OPT_PARAMETER_REQUEST_LIST, _) => {
                     parameter_request_list = Some(data);
                 }
+                (field::OPT_RAPID_COMMIT, 0) => {
+                    rapid_commit = true;
+                }
+                (field::OPT_HOST_NAME, _) => {
+                    hostname = core::str::from_utf8(data).ok();
+                }
+                (field::OPT_VENDOR_CLASS_ID, _) => {
+                    vendor_class_id = Some(data);
+                }
                 (field::OPT_DOMAIN_NAME_SERVER, _) => {
                     let mut servers = Vec::new();
                     const IP_ADDR_BYTE_LEN: usize = 4;
@@ -816,12 +900,17 @@ impl<'a> Repr<'a> {
             router,
             subnet_mask,
             client_identifier,
+            raw_client_identifier,
             parameter_request_list,
             dns_servers,
             max_size,
             lease_duration,
             renew_duration,
             rebind_duration,
+            rapid_commit,
+            hostname,
+            fqdn: None,
+            vendor_class_id,
             message_type: message_type?,
             additional_options: &[],
         })
@@ -872,6 +961,13 @@ impl<'a> Repr<'a> {
                 })?;
             }
 
+            if let Some(data) = self.raw_client_identifier {
+                options.emit(DhcpOption {
+                    kind: field::OPT_CLIENT_ID,
+                    data,
+                })?;
+            }
+
             if let Some(val) = &self.server_identifier {
                 options.emit(DhcpOption {
                     kind: field::OPT_SERVER_IDENTIFIER,
@@ -934,6 +1030,51 @@ impl<'a> Repr<'a> {
                 })?;
             }
 
+            if self.rapid_commit {
+                options.emit(DhcpOption {
+                    kind: field::OPT_RAPID_COMMIT,
+                    data: &[],
+                })?;
+            }
+
+            if let Some(hostname) = self.hostname {
+                options.emit(DhcpOption {
+                    kind: field::OPT_HOST_NAME,
+                    data: hostname.as_bytes(),
+                })?;
+            }
+
+            if let Some(fqdn) = self.fqdn {
+                // Ask the server to update the A record, with the name in the
+                // canonical wire format.
+                let mut data = [0; u8::MAX as usize];
+                let len = 3 + fqdn_encoded_len(fqdn);
+                if len > data.len() {
+                    return Err(Error);
+                }
+                data[0] = FQDN_FLAG_S | FQDN_FLAG_E;
+                let mut pos = 3;
+                for label in fqdn.trim_end_matches('.').split('.') {
+                    if label.is_empty() || label.len() > 63 {
+                        return Err(Error);
+                    }
+                    data[pos] = label.len() as u8;
+                    data[pos + 1..pos + 1 + label.len()].copy_from_slice(label.as_bytes());
+                    pos += 1 + label.len();
+                }
+                options.emit(DhcpOption {
+                    kind: field::OPT_CLIENT_FQDN,
+                    data: &data[..len],
+                })?;
+            }
+
+            if let Some(data) = self.vendor_class_id {
+                options.emit(DhcpOption {
+                    kind: field::OPT_VENDOR_CLASS_ID,
+                    data,
+                })?;
+            }
+
             for option in self.additional_options {
                 options.emit(*option)?;
             }
@@ -1158,6 +1299,7 @@ mod test {
             broadcast: false,
             requested_ip: None,
             client_identifier: Some(CLIENT_MAC),
+            raw_client_identifier: None,
             server_identifier: None,
             parameter_request_list: None,
             dns_servers: None,
@@ -1165,6 +1307,10 @@ mod test {
             renew_duration: None,
             rebind_duration: None,
             lease_duration: Some(0xffff_ffff), // Infinite lease
+            rapid_commit: false,
+            hostname: None,
+            fqdn: None,
+            vendor_class_id: None,
             additional_options: &[],
         }
     }
@@ -1188,9 +1334,14 @@ mod test {
             lease_duration: None,
             requested_ip: Some(IP_NULL),
             client_identifier: Some(CLIENT_MAC),
+            raw_client_identifier: None,
             server_identifier: None,
             parameter_request_list: Some(&[1, 3, 6, 42]),
             dns_servers: None,
+            rapid_commit: false,
+            hostname: None,
+            fqdn: None,
+            vendor_class_id: None,
             additional_options: &[],
         }
     }
@@ -1224,6 +1375,121 @@ mod test {
         repr.emit(&mut packet).unwrap();
     }
 
+    #[test]
+    fn test_emit_hostname_fqdn() {
+        let repr = Repr {
+            hostname: Some("sensor-12"),
+            fqdn: Some("sensor-12.example.com."),
+            ..discover_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(Repr::parse(&packet).unwrap().hostname, Some("sensor-12"));
+        assert_eq!(
+            packet
+                .options()
+                .find(|option| option.kind == field::OPT_CLIENT_FQDN)
+                .unwrap()
+                .data,
+            b"\x05\x00\x00\x09sensor-12\x07example\x03com\x00"
+        );
+    }
+
+    #[test]
+    fn test_raw_client_identifier() {
+        let repr = Repr {
+            client_identifier: None,
+            raw_client_identifier: Some(b"\x00serial"),
+            ..discover_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(Repr::parse(&packet).unwrap(), repr);
+    }
+
+    #[test]
+    fn test_vendor_class_id() {
+        let repr = Repr {
+            vendor_class_id: Some(b"Cisco AP c2700"),
+            ..discover_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+
+        let packet = Packet::new_unchecked(&bytes);
+        assert_eq!(Repr::parse(&packet).unwrap(), repr);
+    }
+
+    #[test]
+    fn test_sub_options() {
+        let option = DhcpOption {
+            kind: field::OPT_VENDOR_SPECIFIC_INFO,
+            data: &[
+                0xf1, 0x04, 192, 168, 1, 10, 0x00, 0x02, 0x01, 0x07, 0xff, 0x03,
+            ],
+        };
+        let mut sub_options = option.sub_options();
+        assert_eq!(
+            sub_options.next(),
+            Some(DhcpOption {
+                kind: 0xf1,
+                data: &[192, 168, 1, 10]
+            })
+        );
+        assert_eq!(
+            sub_options.next(),
+            Some(DhcpOption {
+                kind: 0x02,
+                data: &[0x07]
+            })
+        );
+        assert_eq!(sub_options.next(), None);
+
+        // Truncated sub-options are ignored.
+        let option = DhcpOption {
+            kind: field::OPT_VENDOR_SPECIFIC_INFO,
+            data: &[0x01, 0x04, 1, 2],
+        };
+        assert_eq!(option.sub_options().next(), None);
+    }
+
+    #[test]
+    fn test_rapid_commit() {
+        let repr = Repr {
+            rapid_commit: true,
+            ..discover_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+
+        let packet = Packet::new_unchecked(&bytes);
+        assert!(packet.options().any(|option| option
+            == DhcpOption {
+                kind: field::OPT_RAPID_COMMIT,
+                data: &[]
+            }));
+        assert_eq!(Repr::parse(&packet).unwrap(), repr);
+    }
+
+    #[test]
+    fn test_emit_fqdn_invalid() {
+        let repr = Repr {
+            fqdn: Some("sensor-12..example.com"),
+            ..discover_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        assert_eq!(repr.emit(&mut packet), Err(Error));
+    }
+
     #[test]
     fn test_emit_offer_dns() {
         let repr = {
@@ -0,0 +1,945 @@
+// See https://tools.ietf.org/html/rfc8415 for the DHCPv6 specification.
+
+use byteorder::{ByteOrder, NetworkEndian};
+use core::iter;
+use heapless::Vec;
+
+use super::{Error, Result};
+use crate::wire::Ipv6Address;
+
+pub const CLIENT_PORT: u16 = 546;
+pub const SERVER_PORT: u16 = 547;
+pub const MAX_DNS_SERVER_COUNT: usize = 3;
+
+/// The maximum length of a DUID, as described in RFC 8415 § 11.1.
+pub const MAX_DUID_LEN: usize = 130;
+
+/// The All_DHCP_Relay_Agents_and_Servers multicast address, to which clients send
+/// their messages.
+pub const ALL_RELAY_AGENTS_AND_SERVERS: Ipv6Address =
+    Ipv6Address([0xff, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 2]);
+
+enum_with_unknown! {
+    /// The possible message types of a DHCPv6 packet.
+    pub enum MessageType(u8) {
+        Solicit = 1,
+        Advertise = 2,
+        Request = 3,
+        Confirm = 4,
+        Renew = 5,
+        Rebind = 6,
+        Reply = 7,
+        Release = 8,
+        Decline = 9,
+        Reconfigure = 10,
+        InformationRequest = 11,
+        RelayForward = 12,
+        RelayReply = 13,
+    }
+}
+
+enum_with_unknown! {
+    /// The status codes of the Status Code option.
+    pub enum StatusCode(u16) {
+        Success = 0,
+        UnspecFail = 1,
+        NoAddrsAvail = 2,
+        NoBinding = 3,
+        NotOnLink = 4,
+        UseMulticast = 5,
+        NoPrefixAvail = 6,
+    }
+}
+
+/// A buffer for DHCPv6 options.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DhcpOptionWriter<'a> {
+    /// The underlying buffer, directly from the DHCPv6 packet representation.
+    buffer: &'a mut [u8],
+}
+
+impl<'a> DhcpOptionWriter<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self { buffer }
+    }
+
+    /// Emit a [`DhcpOption`] into a [`DhcpOptionWriter`].
+    pub fn emit(&mut self, option: DhcpOption<'_>) -> Result<()> {
+        self.emit_with(option.kind, option.data.len(), |buf| {
+            buf.copy_from_slice(option.data)
+        })
+    }
+
+    /// Emit an option of `len` octets, whose data is written by `f`.
+    fn emit_with(&mut self, kind: u16, len: usize, f: impl FnOnce(&mut [u8])) -> Result<()> {
+        if len > u16::MAX as usize {
+            return Err(Error);
+        }
+
+        let total_len = 4 + len;
+        if self.buffer.len() < total_len {
+            return Err(Error);
+        }
+
+        let (buf, rest) = core::mem::take(&mut self.buffer).split_at_mut(total_len);
+        self.buffer = rest;
+
+        NetworkEndian::write_u16(&mut buf[0..2], kind);
+        NetworkEndian::write_u16(&mut buf[2..4], len as u16);
+        f(&mut buf[4..]);
+
+        Ok(())
+    }
+}
+
+/// A representation of a single DHCPv6 option.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DhcpOption<'a> {
+    pub kind: u16,
+    pub data: &'a [u8],
+}
+
+/// Return an iterator over the options encoded in `buf`, stopping at the first
+/// truncated one.
+fn options(mut buf: &[u8]) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
+    iter::from_fn(move || {
+        if buf.len() < 4 {
+            return None;
+        }
+        let kind = NetworkEndian::read_u16(&buf[0..2]);
+        let len = NetworkEndian::read_u16(&buf[2..4]) as usize;
+        let data = buf.get(4..4 + len)?;
+        buf = &buf[4 + len..];
+        Some(DhcpOption { kind, data })
+    })
+}
+
+/// A read/write wrapper around a DHCPv6 client/server message buffer.
+///
+/// Relay agent messages have a different layout, and are not supported.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Packet<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+pub(crate) mod field {
+    #![allow(non_snake_case)]
+    #![allow(unused)]
+
+    use crate::wire::field::*;
+
+    pub const MSG_TYPE: usize = 0;
+    pub const TRANSACTION_ID: Field = 1..4;
+    pub const OPTIONS: Rest = 4..;
+
+    // Option codes, see RFC 8415 § 21 and the IANA registry.
+    pub const OPT_CLIENTID: u16 = 1;
+    pub const OPT_SERVERID: u16 = 2;
+    pub const OPT_IA_NA: u16 = 3;
+    pub const OPT_IA_TA: u16 = 4;
+    pub const OPT_IAADDR: u16 = 5;
+    pub const OPT_ORO: u16 = 6;
+    pub const OPT_PREFERENCE: u16 = 7;
+    pub const OPT_ELAPSED_TIME: u16 = 8;
+    pub const OPT_RELAY_MSG: u16 = 9;
+    pub const OPT_AUTH: u16 = 11;
+    pub const OPT_UNICAST: u16 = 12;
+    pub const OPT_STATUS_CODE: u16 = 13;
+    pub const OPT_RAPID_COMMIT: u16 = 14;
+    pub const OPT_USER_CLASS: u16 = 15;
+    pub const OPT_VENDOR_CLASS: u16 = 16;
+    pub const OPT_VENDOR_OPTS: u16 = 17;
+    pub const OPT_INTERFACE_ID: u16 = 18;
+    pub const OPT_RECONF_MSG: u16 = 19;
+    pub const OPT_RECONF_ACCEPT: u16 = 20;
+    pub const OPT_DNS_SERVERS: u16 = 23;
+    pub const OPT_DOMAIN_LIST: u16 = 24;
+    pub const OPT_IA_PD: u16 = 25;
+    pub const OPT_IAPREFIX: u16 = 26;
+    pub const OPT_SNTP_SERVERS: u16 = 31;
+    pub const OPT_INFORMATION_REFRESH_TIME: u16 = 32;
+    pub const OPT_CLIENT_FQDN: u16 = 39;
+    pub const OPT_NTP_SERVER: u16 = 56;
+    pub const OPT_SOL_MAX_RT: u16 = 82;
+    pub const OPT_INF_MAX_RT: u16 = 83;
+
+    // IA_NA and IA_PD option fields.
+    pub const IA_IAID: Field = 0..4;
+    pub const IA_T1: Field = 4..8;
+    pub const IA_T2: Field = 8..12;
+    pub const IA_OPTIONS: Rest = 12..;
+
+    // IA Address option fields.
+    pub const IAADDR_ADDRESS: Field = 0..16;
+    pub const IAADDR_PREFERRED_LIFETIME: Field = 16..20;
+    pub const IAADDR_VALID_LIFETIME: Field = 20..24;
+    pub const IAADDR_OPTIONS: Rest = 24..;
+
+    // IA Prefix option fields.
+    pub const IAPREFIX_PREFERRED_LIFETIME: Field = 0..4;
+    pub const IAPREFIX_VALID_LIFETIME: Field = 4..8;
+    pub const IAPREFIX_PREFIX_LEN: usize = 8;
+    pub const IAPREFIX_PREFIX: Field = 9..25;
+    pub const IAPREFIX_OPTIONS: Rest = 25..;
+}
+
+impl<T: AsRef<[u8]>> Packet<T> {
+    /// Imbue a raw octet buffer with DHCPv6 packet structure.
+    pub const fn new_unchecked(buffer: T) -> Packet<T> {
+        Packet { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Packet<T>> {
+        let packet = Self::new_unchecked(buffer);
+        packet.check_len()?;
+        Ok(packet)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    pub fn check_len(&self) -> Result<()> {
+        let len = self.buffer.as_ref().len();
+        if len < field::OPTIONS.start {
+            Err(Error)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Consume the packet, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the message type field.
+    pub fn msg_type(&self) -> MessageType {
+        MessageType::from(self.buffer.as_ref()[field::MSG_TYPE])
+    }
+
+    /// Return the transaction ID field, 24 bits long.
+    pub fn transaction_id(&self) -> u32 {
+        let field = &self.buffer.as_ref()[field::TRANSACTION_ID];
+        NetworkEndian::read_u24(field)
+    }
+
+    /// Return an iterator over the options of the message.
+    pub fn options(&self) -> impl Iterator<Item = DhcpOption<'_>> + '_ {
+        options(&self.buffer.as_ref()[field::OPTIONS])
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+    /// Set the message type field.
+    pub fn set_msg_type(&mut self, value: MessageType) {
+        self.buffer.as_mut()[field::MSG_TYPE] = value.into();
+    }
+
+    /// Set the transaction ID field. Only the low 24 bits are used.
+    pub fn set_transaction_id(&mut self, value: u32) {
+        let field = &mut self.buffer.as_mut()[field::TRANSACTION_ID];
+        NetworkEndian::write_u24(field, value & 0xff_ffff)
+    }
+
+    /// Return a writer for the options of the message.
+    pub fn options_mut(&mut self) -> DhcpOptionWriter<'_> {
+        DhcpOptionWriter::new(&mut self.buffer.as_mut()[field::OPTIONS])
+    }
+}
+
+/// An address of an Identity Association for Non-temporary Addresses.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaAddress {
+    pub address: Ipv6Address,
+    /// Preferred lifetime, in seconds.
+    pub preferred_lifetime: u32,
+    /// Valid lifetime, in seconds.
+    pub valid_lifetime: u32,
+}
+
+impl IaAddress {
+    const LEN: usize = field::IAADDR_OPTIONS.start;
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::LEN {
+            return Err(Error);
+        }
+        Ok(IaAddress {
+            address: Ipv6Address::from_bytes(&data[field::IAADDR_ADDRESS]),
+            preferred_lifetime: NetworkEndian::read_u32(&data[field::IAADDR_PREFERRED_LIFETIME]),
+            valid_lifetime: NetworkEndian::read_u32(&data[field::IAADDR_VALID_LIFETIME]),
+        })
+    }
+
+    fn emit(&self, data: &mut [u8]) {
+        data[field::IAADDR_ADDRESS].copy_from_slice(self.address.as_bytes());
+        NetworkEndian::write_u32(
+            &mut data[field::IAADDR_PREFERRED_LIFETIME],
+            self.preferred_lifetime,
+        );
+        NetworkEndian::write_u32(&mut data[field::IAADDR_VALID_LIFETIME], self.valid_lifetime);
+    }
+}
+
+/// An Identity Association for Non-temporary Addresses (IA_NA) option.
+///
+/// Only the first address of the option is represented.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaNa {
+    /// Identity association identifier, chosen by the client.
+    pub iaid: u32,
+    /// Time after which the client contacts the server to extend the lifetimes of the
+    /// addresses, in seconds. Also known as T1.
+    pub renew_time: u32,
+    /// Time after which the client contacts any server to extend the lifetimes of the
+    /// addresses, in seconds. Also known as T2.
+    pub rebind_time: u32,
+    pub address: Option<IaAddress>,
+    /// Status of the identity association, if given by the server.
+    pub status_code: Option<StatusCode>,
+}
+
+impl IaNa {
+    fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < field::IA_OPTIONS.start {
+            return Err(Error);
+        }
+        let mut ia_na = IaNa {
+            iaid: NetworkEndian::read_u32(&data[field::IA_IAID]),
+            renew_time: NetworkEndian::read_u32(&data[field::IA_T1]),
+            rebind_time: NetworkEndian::read_u32(&data[field::IA_T2]),
+            address: None,
+            status_code: None,
+        };
+        for option in options(&data[field::IA_OPTIONS]) {
+            match option.kind {
+                field::OPT_IAADDR if ia_na.address.is_none() => {
+                    ia_na.address = Some(IaAddress::parse(option.data)?);
+                }
+                field::OPT_STATUS_CODE => {
+                    ia_na.status_code = Some(parse_status_code(option.data)?);
+                }
+                _ => {}
+            }
+        }
+        Ok(ia_na)
+    }
+
+    fn buffer_len(&self) -> usize {
+        let mut len = field::IA_OPTIONS.start;
+        if self.address.is_some() {
+            len += 4 + IaAddress::LEN;
+        }
+        if self.status_code.is_some() {
+            len += 4 + 2;
+        }
+        len
+    }
+
+    fn emit(&self, data: &mut [u8]) -> Result<()> {
+        NetworkEndian::write_u32(&mut data[field::IA_IAID], self.iaid);
+        NetworkEndian::write_u32(&mut data[field::IA_T1], self.renew_time);
+        NetworkEndian::write_u32(&mut data[field::IA_T2], self.rebind_time);
+        let mut options = DhcpOptionWriter::new(&mut data[field::IA_OPTIONS]);
+        if let Some(address) = &self.address {
+            options.emit_with(field::OPT_IAADDR, IaAddress::LEN, |buf| address.emit(buf))?;
+        }
+        if let Some(status_code) = self.status_code {
+            options.emit_with(field::OPT_STATUS_CODE, 2, |buf| {
+                NetworkEndian::write_u16(buf, status_code.into())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// A prefix of an Identity Association for Prefix Delegation, as described in RFC 8415 § 21.22.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaPrefix {
+    pub prefix: Ipv6Address,
+    pub prefix_len: u8,
+    /// Preferred lifetime, in seconds.
+    pub preferred_lifetime: u32,
+    /// Valid lifetime, in seconds.
+    pub valid_lifetime: u32,
+}
+
+impl IaPrefix {
+    const LEN: usize = field::IAPREFIX_OPTIONS.start;
+
+    fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::LEN || data[field::IAPREFIX_PREFIX_LEN] > 128 {
+            return Err(Error);
+        }
+        Ok(IaPrefix {
+            prefix: Ipv6Address::from_bytes(&data[field::IAPREFIX_PREFIX]),
+            prefix_len: data[field::IAPREFIX_PREFIX_LEN],
+            preferred_lifetime: NetworkEndian::read_u32(&data[field::IAPREFIX_PREFERRED_LIFETIME]),
+            valid_lifetime: NetworkEndian::read_u32(&data[field::IAPREFIX_VALID_LIFETIME]),
+        })
+    }
+
+    fn emit(&self, data: &mut [u8]) {
+        NetworkEndian::write_u32(
+            &mut data[field::IAPREFIX_PREFERRED_LIFETIME],
+            self.preferred_lifetime,
+        );
+        NetworkEndian::write_u32(
+            &mut data[field::IAPREFIX_VALID_LIFETIME],
+            self.valid_lifetime,
+        );
+        data[field::IAPREFIX_PREFIX_LEN] = self.prefix_len;
+        data[field::IAPREFIX_PREFIX].copy_from_slice(self.prefix.as_bytes());
+    }
+}
+
+/// An Identity Association for Prefix Delegation (IA_PD) option, as described in
+/// RFC 8415 § 21.21.
+///
+/// Only the first prefix of the option is represented.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct IaPd {
+    /// Identity association identifier, chosen by the client.
+    pub iaid: u32,
+    /// Time after which the client contacts the server to extend the lifetimes of the
+    /// prefixes, in seconds. Also known as T1.
+    pub renew_time: u32,
+    /// Time after which the client contacts any server to extend the lifetimes of the
+    /// prefixes, in seconds. Also known as T2.
+    pub rebind_time: u32,
+    pub prefix: Option<IaPrefix>,
+    /// Status of the identity association, if given by the server.
+    pub status_code: Option<StatusCode>,
+}
+
+impl IaPd {
+    fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < field::IA_OPTIONS.start {
+            return Err(Error);
+        }
+        let mut ia_pd = IaPd {
+            iaid: NetworkEndian::read_u32(&data[field::IA_IAID]),
+            renew_time: NetworkEndian::read_u32(&data[field::IA_T1]),
+            rebind_time: NetworkEndian::read_u32(&data[field::IA_T2]),
+            prefix: None,
+            status_code: None,
+        };
+        for option in options(&data[field::IA_OPTIONS]) {
+            match option.kind {
+                field::OPT_IAPREFIX if ia_pd.prefix.is_none() => {
+                    ia_pd.prefix = Some(IaPrefix::parse(option.data)?);
+                }
+                field::OPT_STATUS_CODE => {
+                    ia_pd.status_code = Some(parse_status_code(option.data)?);
+                }
+                _ => {}
+            }
+        }
+        Ok(ia_pd)
+    }
+
+    fn buffer_len(&self) -> usize {
+        let mut len = field::IA_OPTIONS.start;
+        if self.prefix.is_some() {
+            len += 4 + IaPrefix::LEN;
+        }
+        if self.status_code.is_some() {
+            len += 4 + 2;
+        }
+        len
+    }
+
+    fn emit(&self, data: &mut [u8]) -> Result<()> {
+        NetworkEndian::write_u32(&mut data[field::IA_IAID], self.iaid);
+        NetworkEndian::write_u32(&mut data[field::IA_T1], self.renew_time);
+        NetworkEndian::write_u32(&mut data[field::IA_T2], self.rebind_time);
+        let mut options = DhcpOptionWriter::new(&mut data[field::IA_OPTIONS]);
+        if let Some(prefix) = &self.prefix {
+            options.emit_with(field::OPT_IAPREFIX, IaPrefix::LEN, |buf| prefix.emit(buf))?;
+        }
+        if let Some(status_code) = self.status_code {
+            options.emit_with(field::OPT_STATUS_CODE, 2, |buf| {
+                NetworkEndian::write_u16(buf, status_code.into())
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_status_code(data: &[u8]) -> Result<StatusCode> {
+    // The status message that may follow is ignored.
+    if data.len() < 2 {
+        return Err(Error);
+    }
+    Ok(StatusCode::from(NetworkEndian::read_u16(data)))
+}
+
+fn parse_u32(data: &[u8]) -> Result<u32> {
+    if data.len() != 4 {
+        return Err(Error);
+    }
+    Ok(NetworkEndian::read_u32(data))
+}
+
+/// A high-level representation of a DHCPv6 client/server message.
+///
+/// DHCPv6 messages have the following layout (see [RFC 8415](https://tools.ietf.org/html/rfc8415)
+/// for details):
+///
+/// ```no_rust
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    msg-type   |               transaction-id                  |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                                                               |
+/// .                            options                            .
+/// .                 (variable number and length)                  .
+/// |                                                               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Repr<'a> {
+    pub message_type: MessageType,
+    /// Number chosen by the client to match replies with its messages. Only the low 24
+    /// bits are used.
+    pub transaction_id: u32,
+    /// DUID of the client, identifying it to the servers.
+    pub client_id: Option<&'a [u8]>,
+    /// DUID of the server.
+    pub server_id: Option<&'a [u8]>,
+    /// Identity association for non-temporary addresses.
+    pub ia_na: Option<IaNa>,
+    /// Identity association for prefix delegation.
+    pub ia_pd: Option<IaPd>,
+    /// Option codes requested by the client.
+    ///
+    /// This is only emitted: it is always `None` in parsed messages.
+    pub requested_options: Option<&'a [u16]>,
+    /// Time since the client began the exchange, in hundredths of a second.
+    pub elapsed_time: Option<u16>,
+    /// Preference of the server, from 0 to 255. Clients should pick the advertising server
+    /// with the highest preference.
+    pub preference: Option<u8>,
+    /// Status of the whole message, if given by the server.
+    pub status_code: Option<StatusCode>,
+    /// Whether the rapid commit option is present.
+    pub rapid_commit: bool,
+    /// Recursive DNS servers, as described in RFC 3646.
+    pub dns_servers: Option<Vec<Ipv6Address, MAX_DNS_SERVER_COUNT>>,
+    /// Time after which clients should refresh the information obtained without
+    /// acquiring addresses, in seconds.
+    pub information_refresh_time: Option<u32>,
+    /// When returned from [`Repr::parse`], this field will be `&[]`. Use
+    /// [`Packet::options`] to get all the options of the message.
+    pub additional_options: &'a [DhcpOption<'a>],
+}
+
+impl<'a> Repr<'a> {
+    /// Return the length of a packet that will be emitted from this high-level representation.
+    pub fn buffer_len(&self) -> usize {
+        let mut len = field::OPTIONS.start;
+
+        if let Some(client_id) = self.client_id {
+            len += 4 + client_id.len();
+        }
+        if let Some(server_id) = self.server_id {
+            len += 4 + server_id.len();
+        }
+        if let Some(ia_na) = &self.ia_na {
+            len += 4 + ia_na.buffer_len();
+        }
+        if let Some(ia_pd) = &self.ia_pd {
+            len += 4 + ia_pd.buffer_len();
+        }
+        if let Some(requested_options) = self.requested_options {
+            len += 4 + requested_options.len() * 2;
+        }
+        if self.elapsed_time.is_some() {
+            len += 4 + 2;
+        }
+        if self.preference.is_some() {
+            len += 4 + 1;
+        }
+        if self.status_code.is_some() {
+            len += 4 + 2;
+        }
+        if self.rapid_commit {
+            len += 4;
+        }
+        if let Some(dns_servers) = &self.dns_servers {
+            len += 4 + dns_servers.len() * 16;
+        }
+        if self.information_refresh_time.is_some() {
+            len += 4 + 4;
+        }
+        for option in self.additional_options {
+            len += 4 + option.data.len();
+        }
+
+        len
+    }
+
+    /// Parse a DHCPv6 packet and return a high-level representation.
+    pub fn parse<T>(packet: &'a Packet<&'a T>) -> Result<Self>
+    where
+        T: AsRef<[u8]> + ?Sized,
+    {
+        let message_type = packet.msg_type();
+        if matches!(
+            message_type,
+            MessageType::RelayForward | MessageType::RelayReply | MessageType::Unknown(_)
+        ) {
+            return Err(Error);
+        }
+
+        let mut repr = Repr {
+            message_type,
+            transaction_id: packet.transaction_id(),
+            client_id: None,
+            server_id: None,
+            ia_na: None,
+            ia_pd: None,
+            requested_options: None,
+            elapsed_time: None,
+            preference: None,
+            status_code: None,
+            rapid_commit: false,
+            dns_servers: None,
+            information_refresh_time: None,
+            additional_options: &[],
+        };
+
+        for option in packet.options() {
+            let data = option.data;
+            match option.kind {
+                field::OPT_CLIENTID => repr.client_id = Some(data),
+                field::OPT_SERVERID => repr.server_id = Some(data),
+                field::OPT_IA_NA if repr.ia_na.is_none() => repr.ia_na = Some(IaNa::parse(data)?),
+                field::OPT_IA_PD if repr.ia_pd.is_none() => repr.ia_pd = Some(IaPd::parse(data)?),
+                field::OPT_ELAPSED_TIME if data.len() == 2 => {
+                    repr.elapsed_time = Some(NetworkEndian::read_u16(data))
+                }
+                field::OPT_PREFERENCE if data.len() == 1 => repr.preference = Some(data[0]),
+                field::OPT_STATUS_CODE => repr.status_code = Some(parse_status_code(data)?),
+                field::OPT_RAPID_COMMIT => repr.rapid_commit = true,
+                field::OPT_DNS_SERVERS => {
+                    if data.len() % 16 != 0 {
+                        return Err(Error);
+                    }
+                    let mut servers = Vec::new();
+                    for chunk in data.chunks(16).take(MAX_DNS_SERVER_COUNT) {
+                        servers.push(Ipv6Address::from_bytes(chunk)).ok();
+                    }
+                    repr.dns_servers = Some(servers);
+                }
+                field::OPT_INFORMATION_REFRESH_TIME => {
+                    repr.information_refresh_time = Some(parse_u32(data)?)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(repr)
+    }
+
+    /// Emit a high-level representation into a DHCPv6 packet.
+    pub fn emit<T>(&self, packet: &mut Packet<&mut T>) -> Result<()>
+    where
+        T: AsRef<[u8]> + AsMut<[u8]> + ?Sized,
+    {
+        packet.set_msg_type(self.message_type);
+        packet.set_transaction_id(self.transaction_id);
+
+        let mut options = packet.options_mut();
+        if let Some(client_id) = self.client_id {
+            options.emit(DhcpOption {
+                kind: field::OPT_CLIENTID,
+                data: client_id,
+            })?;
+        }
+        if let Some(server_id) = self.server_id {
+            options.emit(DhcpOption {
+                kind: field::OPT_SERVERID,
+                data: server_id,
+            })?;
+        }
+        if let Some(ia_na) = &self.ia_na {
+            let mut result = Ok(());
+            options.emit_with(field::OPT_IA_NA, ia_na.buffer_len(), |buf| {
+                result = ia_na.emit(buf)
+            })?;
+            result?;
+        }
+        if let Some(ia_pd) = &self.ia_pd {
+            let mut result = Ok(());
+            options.emit_with(field::OPT_IA_PD, ia_pd.buffer_len(), |buf| {
+                result = ia_pd.emit(buf)
+            })?;
+            result?;
+        }
+        if let Some(requested_options) = self.requested_options {
+            options.emit_with(field::OPT_ORO, requested_options.len() * 2, |buf| {
+                NetworkEndian::write_u16_into(requested_options, buf)
+            })?;
+        }
+        if let Some(elapsed_time) = self.elapsed_time {
+            options.emit_with(field::OPT_ELAPSED_TIME, 2, |buf| {
+                NetworkEndian::write_u16(buf, elapsed_time)
+            })?;
+        }
+        if let Some(preference) = self.preference {
+            options.emit(DhcpOption {
+                kind: field::OPT_PREFERENCE,
+                data: &[preference],
+            })?;
+        }
+        if let Some(status_code) = self.status_code {
+            options.emit_with(field::OPT_STATUS_CODE, 2, |buf| {
+                NetworkEndian::write_u16(buf, status_code.into())
+            })?;
+        }
+        if self.rapid_commit {
+            options.emit(DhcpOption {
+                kind: field::OPT_RAPID_COMMIT,
+                data: &[],
+            })?;
+        }
+        if let Some(dns_servers) = &self.dns_servers {
+            options.emit_with(field::OPT_DNS_SERVERS, dns_servers.len() * 16, |buf| {
+                for (chunk, server) in buf.chunks_mut(16).zip(dns_servers) {
+                    chunk.copy_from_slice(server.as_bytes());
+                }
+            })?;
+        }
+        if let Some(refresh_time) = self.information_refresh_time {
+            options.emit_with(field::OPT_INFORMATION_REFRESH_TIME, 4, |buf| {
+                NetworkEndian::write_u32(buf, refresh_time)
+            })?;
+        }
+        for option in self.additional_options {
+            options.emit(*option)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CLIENT_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02, 0x02];
+    const SERVER_DUID: &[u8] = &[0x00, 0x03, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+    const ADDRESS: Ipv6Address = Ipv6Address([
+        0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x00,
+    ]);
+    const DNS_SERVER: Ipv6Address = Ipv6Address([
+        0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x53,
+    ]);
+
+    const PREFIX: Ipv6Address = Ipv6Address([
+        0x20, 0x01, 0x0d, 0xb8, 0xff, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ]);
+
+    static SOLICIT_BYTES: [u8; 46] = [
+        0x01, 0x12, 0x34, 0x56, // Solicit, transaction ID
+        0x00, 0x01, 0x00, 0x0a, 0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02,
+        0x02, // client ID
+        0x00, 0x03, 0x00, 0x0c, 0x02, 0x02, 0x02, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, // IA_NA
+        0x00, 0x06, 0x00, 0x02, 0x00, 0x17, // option request: DNS servers
+        0x00, 0x08, 0x00, 0x02, 0x00, 0x00, // elapsed time
+    ];
+
+    static REPLY_BYTES: [u8; 109] = [
+        0x07, 0x12, 0x34, 0x56, // Reply, transaction ID
+        0x00, 0x01, 0x00, 0x0a, 0x00, 0x03, 0x00, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02,
+        0x02, // client ID
+        0x00, 0x02, 0x00, 0x0a, 0x00, 0x03, 0x00, 0x01, 0x02, 0x00, 0x00, 0x00, 0x00,
+        0x01, // server ID
+        0x00, 0x03, 0x00, 0x28, 0x02, 0x02, 0x02, 0x02, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x00, 0x15,
+        0x18, // IA_NA
+        0x00, 0x05, 0x00, 0x18, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x1c, 0x20, 0x00, 0x00, 0x2a,
+        0x30, // IA address
+        0x00, 0x17, 0x00, 0x10, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x53, // DNS servers
+        0x00, 0x0d, 0x00, 0x09, 0x00, 0x00, b's', b'u', b'c', b'c', b'e', b's',
+        b's', // status code
+    ];
+
+    fn solicit_repr() -> Repr<'static> {
+        Repr {
+            message_type: MessageType::Solicit,
+            transaction_id: 0x123456,
+            client_id: Some(CLIENT_DUID),
+            server_id: None,
+            ia_na: Some(IaNa {
+                iaid: 0x02020202,
+                renew_time: 0,
+                rebind_time: 0,
+                address: None,
+                status_code: None,
+            }),
+            ia_pd: None,
+            requested_options: Some(&[field::OPT_DNS_SERVERS]),
+            elapsed_time: Some(0),
+            preference: None,
+            status_code: None,
+            rapid_commit: false,
+            dns_servers: None,
+            information_refresh_time: None,
+            additional_options: &[],
+        }
+    }
+
+    #[test]
+    fn test_deconstruct_reply() {
+        let packet = Packet::new_checked(&REPLY_BYTES[..]).unwrap();
+        assert_eq!(packet.msg_type(), MessageType::Reply);
+        assert_eq!(packet.transaction_id(), 0x123456);
+        assert_eq!(packet.options().count(), 5);
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        let packet = Packet::new_checked(&REPLY_BYTES[..]).unwrap();
+        let repr = Repr::parse(&packet).unwrap();
+        assert_eq!(repr.message_type, MessageType::Reply);
+        assert_eq!(repr.transaction_id, 0x123456);
+        assert_eq!(repr.client_id, Some(CLIENT_DUID));
+        assert_eq!(repr.server_id, Some(SERVER_DUID));
+        assert_eq!(
+            repr.ia_na,
+            Some(IaNa {
+                iaid: 0x02020202,
+                renew_time: 3600,
+                rebind_time: 5400,
+                address: Some(IaAddress {
+                    address: ADDRESS,
+                    preferred_lifetime: 7200,
+                    valid_lifetime: 10800,
+                }),
+                status_code: None,
+            })
+        );
+        assert_eq!(repr.status_code, Some(StatusCode::Success));
+        assert_eq!(
+            repr.dns_servers,
+            Some(Vec::from_slice(&[DNS_SERVER]).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_parse_ia_pd() {
+        static BYTES: [u8; 49] = [
+            0x07, 0x12, 0x34, 0x56, // Reply, transaction ID
+            0x00, 0x19, 0x00, 0x29, 0x02, 0x02, 0x02, 0x02, 0x00, 0x00, 0x0e, 0x10, 0x00, 0x00,
+            0x15, 0x18, // IA_PD
+            0x00, 0x1a, 0x00, 0x19, 0x00, 0x00, 0x1c, 0x20, 0x00, 0x00, 0x2a, 0x30, 0x38, 0x20,
+            0x01, 0x0d, 0xb8, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, // IA prefix
+        ];
+        let packet = Packet::new_checked(&BYTES[..]).unwrap();
+        let repr = Repr::parse(&packet).unwrap();
+        assert_eq!(
+            repr.ia_pd,
+            Some(IaPd {
+                iaid: 0x02020202,
+                renew_time: 3600,
+                rebind_time: 5400,
+                prefix: Some(IaPrefix {
+                    prefix: PREFIX,
+                    prefix_len: 56,
+                    preferred_lifetime: 7200,
+                    valid_lifetime: 10800,
+                }),
+                status_code: None,
+            })
+        );
+
+        // Invalid prefix length.
+        let mut bytes = BYTES;
+        bytes[32] = 129;
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert!(Repr::parse(&packet).is_err());
+    }
+
+    #[test]
+    fn test_emit_solicit() {
+        let repr = solicit_repr();
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet).unwrap();
+        assert_eq!(&bytes[..], &SOLICIT_BYTES[..]);
+    }
+
+    #[test]
+    fn test_emit_parse_roundtrip() {
+        let repr = Repr {
+            message_type: MessageType::Advertise,
+            server_id: Some(SERVER_DUID),
+            ia_na: Some(IaNa {
+                iaid: 1,
+                renew_time: 10,
+                rebind_time: 20,
+                address: Some(IaAddress {
+                    address: ADDRESS,
+                    preferred_lifetime: 30,
+                    valid_lifetime: 40,
+                }),
+                status_code: Some(StatusCode::NoAddrsAvail),
+            }),
+            ia_pd: Some(IaPd {
+                iaid: 2,
+                renew_time: 50,
+                rebind_time: 60,
+                prefix: Some(IaPrefix {
+                    prefix: PREFIX,
+                    prefix_len: 56,
+                    preferred_lifetime: 70,
+                    valid_lifetime: 80,
+                }),
+                status_code: Some(StatusCode::NoPrefixAvail),
+            }),
+            requested_options: None,
+            preference: Some(255),
+            status_code: Some(StatusCode::UnspecFail),
+            rapid_commit: true,
+            dns_servers: Some(Vec::from_slice(&[DNS_SERVER, ADDRESS]).unwrap()),
+            information_refresh_time: Some(86400),
+            ..solicit_repr()
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        repr.emit(&mut Packet::new_unchecked(&mut bytes)).unwrap();
+        let packet = Packet::new_checked(&bytes).unwrap();
+        assert_eq!(Repr::parse(&packet).unwrap(), repr);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Packet::new_checked(&[0x07, 0x12, 0x34][..]).is_err());
+
+        // Relay messages are not supported.
+        let packet = Packet::new_checked(&[0x0c, 0x12, 0x34, 0x56][..]).unwrap();
+        assert!(Repr::parse(&packet).is_err());
+
+        // Truncated IA_NA.
+        let packet =
+            Packet::new_checked(&[0x07, 0x12, 0x34, 0x56, 0x00, 0x03, 0x00, 0x04, 0, 0, 0, 1][..])
+                .unwrap();
+        assert!(Repr::parse(&packet).is_err());
+    }
+}
@@ -8,6 +8,8 @@ enum_with_unknown! {
     pub enum EtherType(u16) {
         Ipv4 = 0x0800,
         Arp  = 0x0806,
+        WakeOnLan = 0x0842,
+        Vlan = 0x8100,
         Ipv6 = 0x86DD
     }
 }
@@ -18,6 +20,8 @@ impl fmt::Display for EtherType {
             EtherType::Ipv4 => write!(f, "IPv4"),
             EtherType::Ipv6 => write!(f, "IPv6"),
             EtherType::Arp => write!(f, "ARP"),
+            EtherType::Vlan => write!(f, "802.1Q"),
+            EtherType::WakeOnLan => write!(f, "WoL"),
             EtherType::Unknown(id) => write!(f, "0x{id:04x}"),
         }
     }
@@ -66,6 +70,13 @@ impl Address {
     pub const fn is_local(&self) -> bool {
         self.0[0] & 0x02 != 0
     }
+
+    /// Convert the address to a modified Extended Unique Identifier (EUI-64), as used for
+    /// IPv6 interface identifiers (RFC 4291 § 2.5.1 and RFC 2464 § 4).
+    pub fn as_eui_64(&self) -> [u8; 8] {
+        let b = self.0;
+        [b[0] ^ 0x02, b[1], b[2], 0xff, 0xfe, b[3], b[4], b[5]]
+    }
 }
 
 impl fmt::Display for Address {
@@ -234,25 +245,42 @@ impl<T: AsRef<[u8]>> PrettyPrint for Frame<T> {
             Ok(frame) => frame,
         };
         write!(f, "{indent}{frame}")?;
+        pretty_print_payload(frame.ethertype(), frame.payload(), f, indent)
+    }
+}
 
-        match frame.ethertype() {
-            #[cfg(feature = "proto-ipv4")]
-            EtherType::Arp => {
-                indent.increase(f)?;
-                super::ArpPacket::<&[u8]>::pretty_print(&frame.payload(), f, indent)
-            }
-            #[cfg(feature = "proto-ipv4")]
-            EtherType::Ipv4 => {
-                indent.increase(f)?;
-                super::Ipv4Packet::<&[u8]>::pretty_print(&frame.payload(), f, indent)
-            }
-            #[cfg(feature = "proto-ipv6")]
-            EtherType::Ipv6 => {
-                indent.increase(f)?;
-                super::Ipv6Packet::<&[u8]>::pretty_print(&frame.payload(), f, indent)
-            }
-            _ => Ok(()),
+/// Pretty print the payload of an Ethernet II frame or of an 802.1Q tag.
+pub(super) fn pretty_print_payload(
+    ethertype: EtherType,
+    payload: &[u8],
+    f: &mut fmt::Formatter,
+    indent: &mut PrettyIndent,
+) -> fmt::Result {
+    match ethertype {
+        #[cfg(feature = "proto-ipv4")]
+        EtherType::Arp => {
+            indent.increase(f)?;
+            super::ArpPacket::<&[u8]>::pretty_print(&payload, f, indent)
+        }
+        #[cfg(feature = "proto-ipv4")]
+        EtherType::Ipv4 => {
+            indent.increase(f)?;
+            super::Ipv4Packet::<&[u8]>::pretty_print(&payload, f, indent)
+        }
+        #[cfg(feature = "proto-ipv6")]
+        EtherType::Ipv6 => {
+            indent.increase(f)?;
+            super::Ipv6Packet::<&[u8]>::pretty_print(&payload, f, indent)
+        }
+        EtherType::Vlan => {
+            indent.increase(f)?;
+            super::VlanPacket::<&[u8]>::pretty_print(&payload, f, indent)
         }
+        EtherType::WakeOnLan => {
+            indent.increase(f)?;
+            super::WolPacket::<&[u8]>::pretty_print(&payload, f, indent)
+        }
+        _ => Ok(()),
     }
 }
 
@@ -302,6 +330,15 @@ mod test {
         assert!(Address::BROADCAST.is_multicast());
         assert!(Address::BROADCAST.is_local());
     }
+
+    #[test]
+    fn test_eui_64() {
+        let addr = Address([0x00, 0x1b, 0x63, 0x84, 0x45, 0xe6]);
+        assert_eq!(
+            addr.as_eui_64(),
+            [0x02, 0x1b, 0x63, 0xff, 0xfe, 0x84, 0x45, 0xe6]
+        );
+    }
 }
 
 #[cfg(test)]
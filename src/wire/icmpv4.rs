@@ -179,6 +179,8 @@ mod field {
     pub const ECHO_IDENT: Field = 4..6;
     pub const ECHO_SEQNO: Field = 6..8;
 
+    pub const NEXT_HOP_MTU: Field = 6..8;
+
     pub const HEADER_END: usize = 8;
 }
 
@@ -259,6 +261,16 @@ impl<T: AsRef<[u8]>> Packet<T> {
         NetworkEndian::read_u16(&data[field::ECHO_SEQNO])
     }
 
+    /// Return the next-hop MTU field (for fragmentation required packets, RFC 1191 § 4).
+    ///
+    /// # Panics
+    /// This function may panic if this packet is not a destination unreachable packet.
+    #[inline]
+    pub fn next_hop_mtu(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::NEXT_HOP_MTU])
+    }
+
     /// Return the header length.
     /// The result depends on the value of the message type field.
     pub fn header_len(&self) -> usize {
@@ -335,6 +347,16 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
         NetworkEndian::write_u16(&mut data[field::ECHO_SEQNO], value)
     }
 
+    /// Set the next-hop MTU field (for fragmentation required packets, RFC 1191 § 4).
+    ///
+    /// # Panics
+    /// This function may panic if this packet is not a destination unreachable packet.
+    #[inline]
+    pub fn set_next_hop_mtu(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::NEXT_HOP_MTU], value)
+    }
+
     /// Compute and fill in the header checksum.
     pub fn fill_checksum(&mut self) {
         self.set_checksum(0);
@@ -382,6 +404,13 @@ pub enum Repr<'a> {
         header: Ipv4Repr,
         data: &'a [u8],
     },
+    /// A destination unreachable message with the fragmentation required code, which
+    /// carries the MTU of the next hop (RFC 1191 § 4).
+    PktTooBig {
+        mtu: u16,
+        header: Ipv4Repr,
+        data: &'a [u8],
+    },
     TimeExceeded {
         reason: TimeExceeded,
         header: Ipv4Repr,
@@ -427,17 +456,25 @@ impl<'a> Repr<'a> {
                     return Err(Error);
                 }
 
-                Ok(Repr::DstUnreachable {
-                    reason: DstUnreachable::from(code),
-                    header: Ipv4Repr {
-                        src_addr: ip_packet.src_addr(),
-                        dst_addr: ip_packet.dst_addr(),
-                        next_header: ip_packet.next_header(),
-                        payload_len: payload.len(),
-                        hop_limit: ip_packet.hop_limit(),
-                    },
-                    data: payload,
-                })
+                let header = Ipv4Repr {
+                    src_addr: ip_packet.src_addr(),
+                    dst_addr: ip_packet.dst_addr(),
+                    next_header: ip_packet.next_header(),
+                    payload_len: payload.len(),
+                    hop_limit: ip_packet.hop_limit(),
+                };
+                match DstUnreachable::from(code) {
+                    DstUnreachable::FragRequired => Ok(Repr::PktTooBig {
+                        mtu: packet.next_hop_mtu(),
+                        header,
+                        data: payload,
+                    }),
+                    reason => Ok(Repr::DstUnreachable {
+                        reason,
+                        header,
+                        data: payload,
+                    }),
+                }
             }
 
             (Message::TimeExceeded, code) => {
@@ -474,6 +511,7 @@ impl<'a> Repr<'a> {
                 field::ECHO_SEQNO.end + data.len()
             }
             &Repr::DstUnreachable { header, data, .. }
+            | &Repr::PktTooBig { header, data, .. }
             | &Repr::TimeExceeded { header, data, .. } => {
                 field::UNUSED.end + header.buffer_len() + data.len()
             }
@@ -528,6 +566,17 @@ impl<'a> Repr<'a> {
                 payload.copy_from_slice(data)
             }
 
+            Repr::PktTooBig { mtu, header, data } => {
+                packet.set_msg_type(Message::DstUnreachable);
+                packet.set_msg_code(DstUnreachable::FragRequired.into());
+                packet.set_next_hop_mtu(mtu);
+
+                let mut ip_packet = Ipv4Packet::new_unchecked(packet.data_mut());
+                header.emit(&mut ip_packet, checksum_caps);
+                let payload = &mut ip_packet.into_inner()[header.buffer_len()..];
+                payload.copy_from_slice(data)
+            }
+
             Repr::TimeExceeded {
                 reason,
                 header,
@@ -602,6 +651,9 @@ impl<'a> fmt::Display for Repr<'a> {
             Repr::DstUnreachable { reason, .. } => {
                 write!(f, "ICMPv4 destination unreachable ({reason})")
             }
+            Repr::PktTooBig { mtu, .. } => {
+                write!(f, "ICMPv4 fragmentation required (mtu={mtu})")
+            }
             Repr::TimeExceeded { reason, .. } => {
                 write!(f, "ICMPv4 time exceeded ({reason})")
             }
@@ -636,6 +688,7 @@ impl<T: AsRef<[u8]>> PrettyPrint for Packet<T> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::wire::{IpProtocol, Ipv4Address};
 
     static ECHO_PACKET_BYTES: [u8; 12] = [
         0x08, 0x00, 0x8e, 0xfe, 0x12, 0x34, 0xab, 0xcd, 0xaa, 0x00, 0x00, 0xff,
@@ -692,6 +745,34 @@ mod test {
         assert_eq!(&packet.into_inner()[..], &ECHO_PACKET_BYTES[..]);
     }
 
+    #[test]
+    fn test_pkt_too_big() {
+        let data = [0x12, 0x34, 0x00, 0x35, 0x00, 0x08, 0x00, 0x00];
+        let repr = Repr::PktTooBig {
+            mtu: 1280,
+            header: Ipv4Repr {
+                src_addr: Ipv4Address::new(192, 168, 1, 1),
+                dst_addr: Ipv4Address::new(10, 0, 0, 1),
+                next_header: IpProtocol::Udp,
+                payload_len: data.len(),
+                hop_limit: 63,
+            },
+            data: &data,
+        };
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(&mut packet, &ChecksumCapabilities::default());
+
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(packet.msg_type(), Message::DstUnreachable);
+        assert_eq!(packet.msg_code(), u8::from(DstUnreachable::FragRequired));
+        assert_eq!(packet.next_hop_mtu(), 1280);
+        assert_eq!(
+            Repr::parse(&packet, &ChecksumCapabilities::default()),
+            Ok(repr)
+        );
+    }
+
     #[test]
     fn test_check_len() {
         let bytes = [0x0b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
@@ -8,7 +8,7 @@ use crate::wire::ip::checksum;
 use crate::wire::Ipv4Address;
 
 enum_with_unknown! {
-    /// Internet Group Management Protocol v1/v2 message version/type.
+    /// Internet Group Management Protocol v1/v2/v3 message version/type.
     pub enum Message(u8) {
         /// Membership Query
         MembershipQuery = 0x11,
@@ -17,11 +17,36 @@ enum_with_unknown! {
         /// Leave Group
         LeaveGroup = 0x17,
         /// Version 1 Membership Report
-        MembershipReportV1 = 0x12
+        MembershipReportV1 = 0x12,
+        /// Version 3 Membership Report
+        MembershipReportV3 = 0x22
     }
 }
 
-/// A read/write wrapper around an Internet Group Management Protocol v1/v2 packet buffer.
+enum_with_unknown! {
+    /// IGMPv3 Membership Report Group Record Type. See [RFC 3376 § 4.2.12] for
+    /// more details.
+    ///
+    /// [RFC 3376 § 4.2.12]: https://tools.ietf.org/html/rfc3376#section-4.2.12
+    pub enum RecordType(u8) {
+        /// Interface has a filter mode of INCLUDE for the specified multicast address.
+        ModeIsInclude   = 0x01,
+        /// Interface has a filter mode of EXCLUDE for the specified multicast address.
+        ModeIsExclude   = 0x02,
+        /// Interface has changed to a filter mode of INCLUDE for the specified
+        /// multicast address.
+        ChangeToInclude = 0x03,
+        /// Interface has changed to a filter mode of EXCLUDE for the specified
+        /// multicast address.
+        ChangeToExclude = 0x04,
+        /// Interface wishes to listen to the sources in the specified list.
+        AllowNewSources = 0x05,
+        /// Interface no longer wishes to listen to the sources in the specified list.
+        BlockOldSources = 0x06
+    }
+}
+
+/// A read/write wrapper around an Internet Group Management Protocol v1/v2/v3 packet buffer.
 #[derive(Debug)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Packet<T: AsRef<[u8]>> {
@@ -35,6 +60,20 @@ mod field {
     pub const MAX_RESP_CODE: usize = 1;
     pub const CHECKSUM: Field = 2..4;
     pub const GROUP_ADDRESS: Field = 4..8;
+
+    // IGMPv3 Membership Query.
+    pub const QUERY_RESV_S_QRV: usize = 8;
+    pub const QUERY_QQIC: usize = 9;
+    pub const QUERY_NUM_SRCS: Field = 10..12;
+
+    // IGMPv3 Membership Report.
+    pub const REPORT_NUM_RECORDS: Field = 6..8;
+
+    // IGMPv3 Membership Report Group Record.
+    pub const RECORD_TYPE: usize = 0;
+    pub const RECORD_AUX_DATA_LEN: usize = 1;
+    pub const RECORD_NUM_SRCS: Field = 2..4;
+    pub const RECORD_MCAST_ADDR: Field = 4..8;
 }
 
 impl fmt::Display for Message {
@@ -44,14 +83,17 @@ impl fmt::Display for Message {
             Message::MembershipReportV2 => write!(f, "version 2 membership report"),
             Message::LeaveGroup => write!(f, "leave group"),
             Message::MembershipReportV1 => write!(f, "version 1 membership report"),
+            Message::MembershipReportV3 => write!(f, "version 3 membership report"),
             Message::Unknown(id) => write!(f, "{id}"),
         }
     }
 }
 
-/// Internet Group Management Protocol v1/v2 defined in [RFC 2236].
+/// Internet Group Management Protocol v1/v2 defined in [RFC 2236], and v3 defined in
+/// [RFC 3376].
 ///
 /// [RFC 2236]: https://tools.ietf.org/html/rfc2236
+/// [RFC 3376]: https://tools.ietf.org/html/rfc3376
 impl<T: AsRef<[u8]>> Packet<T> {
     /// Imbue a raw octet buffer with IGMPv2 packet structure.
     pub const fn new_unchecked(buffer: T) -> Packet<T> {
@@ -115,6 +157,41 @@ impl<T: AsRef<[u8]>> Packet<T> {
         Ipv4Address::from_bytes(&data[field::GROUP_ADDRESS])
     }
 
+    /// Return the S Flag (Suppress Router-Side Processing) of an IGMPv3 query.
+    #[inline]
+    pub fn s_flag(&self) -> bool {
+        let data = self.buffer.as_ref();
+        (data[field::QUERY_RESV_S_QRV] & 0x08) != 0
+    }
+
+    /// Return the Querier's Robustness Variable of an IGMPv3 query.
+    #[inline]
+    pub fn qrv(&self) -> u8 {
+        let data = self.buffer.as_ref();
+        data[field::QUERY_RESV_S_QRV] & 0x07
+    }
+
+    /// Return the Querier's Query Interval Code of an IGMPv3 query.
+    #[inline]
+    pub fn qqic(&self) -> u8 {
+        let data = self.buffer.as_ref();
+        data[field::QUERY_QQIC]
+    }
+
+    /// Return the number of sources of an IGMPv3 query.
+    #[inline]
+    pub fn num_srcs(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::QUERY_NUM_SRCS])
+    }
+
+    /// Return the number of group records of an IGMPv3 report.
+    #[inline]
+    pub fn num_group_records(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::REPORT_NUM_RECORDS])
+    }
+
     /// Validate the header checksum.
     ///
     /// # Fuzzing
@@ -129,6 +206,22 @@ impl<T: AsRef<[u8]>> Packet<T> {
     }
 }
 
+impl<'a, T: AsRef<[u8]> + ?Sized> Packet<&'a T> {
+    /// Return a pointer to the source addresses of an IGMPv3 query, or to the group
+    /// records of an IGMPv3 report.
+    ///
+    /// # Panics
+    /// This function may panic if the buffer of a query is shorter than 12 octets.
+    #[inline]
+    pub fn payload(&self) -> &'a [u8] {
+        let data = self.buffer.as_ref();
+        match self.msg_type() {
+            Message::MembershipQuery => &data[field::QUERY_NUM_SRCS.end..],
+            _ => &data[field::GROUP_ADDRESS.end..],
+        }
+    }
+}
+
 impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
     /// Set the message type field.
     #[inline]
@@ -159,6 +252,49 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
         data[field::GROUP_ADDRESS].copy_from_slice(addr.as_bytes());
     }
 
+    /// Set the S Flag, the Querier's Robustness Variable and the reserved bits of an
+    /// IGMPv3 query.
+    #[inline]
+    pub fn set_s_flag_and_qrv(&mut self, s_flag: bool, qrv: u8) {
+        let data = self.buffer.as_mut();
+        data[field::QUERY_RESV_S_QRV] = ((s_flag as u8) << 3) | (qrv & 0x07);
+    }
+
+    /// Set the Querier's Query Interval Code of an IGMPv3 query.
+    #[inline]
+    pub fn set_qqic(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::QUERY_QQIC] = value;
+    }
+
+    /// Set the number of sources of an IGMPv3 query.
+    #[inline]
+    pub fn set_num_srcs(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::QUERY_NUM_SRCS], value)
+    }
+
+    /// Set the number of group records of an IGMPv3 report, clearing the reserved
+    /// field before it.
+    #[inline]
+    pub fn set_num_group_records(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        data[field::GROUP_ADDRESS.start..field::REPORT_NUM_RECORDS.start].fill(0);
+        NetworkEndian::write_u16(&mut data[field::REPORT_NUM_RECORDS], value)
+    }
+
+    /// Return a mutable pointer to the source addresses of an IGMPv3 query, or to the
+    /// group records of an IGMPv3 report.
+    #[inline]
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        let start = match self.msg_type() {
+            Message::MembershipQuery => field::QUERY_NUM_SRCS.end,
+            _ => field::GROUP_ADDRESS.end,
+        };
+        let data = self.buffer.as_mut();
+        &mut data[start..]
+    }
+
     /// Compute and fill in the header checksum.
     pub fn fill_checksum(&mut self) {
         self.set_checksum(0);
@@ -170,19 +306,161 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
     }
 }
 
-/// A high-level representation of an Internet Group Management Protocol v1/v2 header.
+/// A read/write wrapper around an IGMPv3 Membership Report Group Record.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GroupRecord<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+impl<T: AsRef<[u8]>> GroupRecord<T> {
+    /// Imbue a raw octet buffer with a Group Record structure.
+    pub const fn new_unchecked(buffer: T) -> Self {
+        Self { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Self> {
+        let record = Self::new_unchecked(buffer);
+        record.check_len()?;
+        Ok(record)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    pub fn check_len(&self) -> Result<()> {
+        let len = self.buffer.as_ref().len();
+        if len < field::RECORD_MCAST_ADDR.end || len < self.record_len() {
+            Err(Error)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Consume the record, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the length of the record, including its sources and auxiliary data.
+    pub fn record_len(&self) -> usize {
+        field::RECORD_MCAST_ADDR.end
+            + self.num_srcs() as usize * 4
+            + self.aux_data_len() as usize * 4
+    }
+
+    /// Return the record type.
+    #[inline]
+    pub fn record_type(&self) -> RecordType {
+        let data = self.buffer.as_ref();
+        RecordType::from(data[field::RECORD_TYPE])
+    }
+
+    /// Return the length of the auxiliary data, in units of 32-bit words.
+    #[inline]
+    pub fn aux_data_len(&self) -> u8 {
+        let data = self.buffer.as_ref();
+        data[field::RECORD_AUX_DATA_LEN]
+    }
+
+    /// Return the number of sources field.
+    #[inline]
+    pub fn num_srcs(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::RECORD_NUM_SRCS])
+    }
+
+    /// Return the multicast address field.
+    #[inline]
+    pub fn mcast_addr(&self) -> Ipv4Address {
+        let data = self.buffer.as_ref();
+        Ipv4Address::from_bytes(&data[field::RECORD_MCAST_ADDR])
+    }
+
+    /// Return the source address at `index`.
+    ///
+    /// # Panics
+    /// This function may panic if `index` is not below the number of sources.
+    #[inline]
+    pub fn source(&self, index: usize) -> Ipv4Address {
+        let data = self.buffer.as_ref();
+        let start = field::RECORD_MCAST_ADDR.end + index * 4;
+        Ipv4Address::from_bytes(&data[start..start + 4])
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> GroupRecord<T> {
+    /// Set the record type.
+    #[inline]
+    pub fn set_record_type(&mut self, value: RecordType) {
+        let data = self.buffer.as_mut();
+        data[field::RECORD_TYPE] = value.into();
+    }
+
+    /// Set the length of the auxiliary data, in units of 32-bit words.
+    #[inline]
+    pub fn set_aux_data_len(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::RECORD_AUX_DATA_LEN] = value;
+    }
+
+    /// Set the number of sources field.
+    #[inline]
+    pub fn set_num_srcs(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::RECORD_NUM_SRCS], value);
+    }
+
+    /// Set the multicast address field.
+    #[inline]
+    pub fn set_mcast_addr(&mut self, addr: Ipv4Address) {
+        let data = self.buffer.as_mut();
+        data[field::RECORD_MCAST_ADDR].copy_from_slice(addr.as_bytes());
+    }
+
+    /// Set the source address at `index`.
+    ///
+    /// # Panics
+    /// This function may panic if the buffer is too short.
+    #[inline]
+    pub fn set_source(&mut self, index: usize, addr: Ipv4Address) {
+        let data = self.buffer.as_mut();
+        let start = field::RECORD_MCAST_ADDR.end + index * 4;
+        data[start..start + 4].copy_from_slice(addr.as_bytes());
+    }
+}
+
+/// A high-level representation of an Internet Group Management Protocol v1/v2/v3 header.
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub enum Repr {
+pub enum Repr<'a> {
     MembershipQuery {
         max_resp_time: Duration,
         group_addr: Ipv4Address,
         version: IgmpVersion,
     },
+    /// An IGMPv3 query, with the source addresses in `data`.
+    MembershipQueryV3 {
+        max_resp_time: Duration,
+        group_addr: Ipv4Address,
+        s_flag: bool,
+        qrv: u8,
+        qqic: u8,
+        num_srcs: u16,
+        data: &'a [u8],
+    },
     MembershipReport {
         group_addr: Ipv4Address,
         version: IgmpVersion,
     },
+    /// An IGMPv3 report, with the group records in `data`.
+    MembershipReportV3 {
+        num_group_records: u16,
+        data: &'a [u8],
+    },
     LeaveGroup {
         group_addr: Ipv4Address,
     },
@@ -198,13 +476,20 @@ pub enum IgmpVersion {
     Version2,
 }
 
-impl Repr {
-    /// Parse an Internet Group Management Protocol v1/v2 packet and return
+impl<'a> Repr<'a> {
+    /// Parse an Internet Group Management Protocol v1/v2/v3 packet and return
     /// a high-level representation.
-    pub fn parse<T>(packet: &Packet<&T>) -> Result<Repr>
+    pub fn parse<T>(packet: &Packet<&'a T>) -> Result<Repr<'a>>
     where
         T: AsRef<[u8]> + ?Sized,
     {
+        if packet.msg_type() == Message::MembershipReportV3 {
+            return Ok(Repr::MembershipReportV3 {
+                num_group_records: packet.num_group_records(),
+                data: packet.payload(),
+            });
+        }
+
         // Check if the address is 0.0.0.0 or multicast
         let addr = packet.group_addr();
         if !addr.is_unspecified() && !addr.is_multicast() {
@@ -213,6 +498,25 @@ impl Repr {
 
         // construct a packet based on the Type field
         match packet.msg_type() {
+            // See RFC 3376: 7.1. Query Version Distinctions
+            Message::MembershipQuery
+                if packet.buffer.as_ref().len() >= field::QUERY_NUM_SRCS.end =>
+            {
+                let num_srcs = packet.num_srcs();
+                let data = packet.payload();
+                if data.len() < num_srcs as usize * 4 {
+                    return Err(Error);
+                }
+                Ok(Repr::MembershipQueryV3 {
+                    max_resp_time: max_resp_code_to_duration(packet.max_resp_code()),
+                    group_addr: addr,
+                    s_flag: packet.s_flag(),
+                    qrv: packet.qrv(),
+                    qqic: packet.qqic(),
+                    num_srcs,
+                    data: &data[..num_srcs as usize * 4],
+                })
+            }
             Message::MembershipQuery => {
                 let max_resp_time = max_resp_code_to_duration(packet.max_resp_code());
                 // See RFC 3376: 7.1. Query Version Distinctions
@@ -247,11 +551,15 @@ impl Repr {
 
     /// Return the length of a packet that will be emitted from this high-level representation.
     pub const fn buffer_len(&self) -> usize {
-        // always 8 bytes
-        field::GROUP_ADDRESS.end
+        match self {
+            Repr::MembershipQueryV3 { data, .. } => field::QUERY_NUM_SRCS.end + data.len(),
+            Repr::MembershipReportV3 { data, .. } => field::GROUP_ADDRESS.end + data.len(),
+            // always 8 bytes
+            _ => field::GROUP_ADDRESS.end,
+        }
     }
 
-    /// Emit a high-level representation into an Internet Group Management Protocol v2 packet.
+    /// Emit a high-level representation into an Internet Group Management Protocol packet.
     pub fn emit<T>(&self, packet: &mut Packet<&mut T>)
     where
         T: AsRef<[u8]> + AsMut<[u8]> + ?Sized,
@@ -271,6 +579,23 @@ impl Repr {
                 }
                 packet.set_group_address(group_addr);
             }
+            Repr::MembershipQueryV3 {
+                max_resp_time,
+                group_addr,
+                s_flag,
+                qrv,
+                qqic,
+                num_srcs,
+                data,
+            } => {
+                packet.set_msg_type(Message::MembershipQuery);
+                packet.set_max_resp_code(duration_to_max_resp_code(max_resp_time));
+                packet.set_group_address(group_addr);
+                packet.set_s_flag_and_qrv(s_flag, qrv);
+                packet.set_qqic(qqic);
+                packet.set_num_srcs(num_srcs);
+                packet.payload_mut().copy_from_slice(data);
+            }
             Repr::MembershipReport {
                 group_addr,
                 version,
@@ -282,6 +607,15 @@ impl Repr {
                 packet.set_max_resp_code(0);
                 packet.set_group_address(group_addr);
             }
+            Repr::MembershipReportV3 {
+                num_group_records,
+                data,
+            } => {
+                packet.set_msg_type(Message::MembershipReportV3);
+                packet.set_max_resp_code(0);
+                packet.set_num_group_records(num_group_records);
+                packet.payload_mut().copy_from_slice(data);
+            }
             Repr::LeaveGroup { group_addr } => {
                 packet.set_msg_type(Message::LeaveGroup);
                 packet.set_group_address(group_addr);
@@ -330,7 +664,7 @@ impl<'a, T: AsRef<[u8]> + ?Sized> fmt::Display for Packet<&'a T> {
     }
 }
 
-impl fmt::Display for Repr {
+impl<'a> fmt::Display for Repr<'a> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             Repr::MembershipQuery {
@@ -341,6 +675,15 @@ impl fmt::Display for Repr {
                 f,
                 "IGMP membership query max_resp_time={max_resp_time} group_addr={group_addr} version={version:?}"
             ),
+            Repr::MembershipQueryV3 {
+                max_resp_time,
+                group_addr,
+                num_srcs,
+                ..
+            } => write!(
+                f,
+                "IGMP membership query max_resp_time={max_resp_time} group_addr={group_addr} num_srcs={num_srcs} version=Version3"
+            ),
             Repr::MembershipReport {
                 group_addr,
                 version,
@@ -348,6 +691,13 @@ impl fmt::Display for Repr {
                 f,
                 "IGMP membership report group_addr={group_addr} version={version:?}"
             ),
+            Repr::MembershipReportV3 {
+                num_group_records,
+                ..
+            } => write!(
+                f,
+                "IGMP membership report num_group_records={num_group_records} version=Version3"
+            ),
             Repr::LeaveGroup { group_addr } => {
                 write!(f, "IGMP leave group group_addr={group_addr})")
             }
@@ -377,6 +727,35 @@ mod test {
     static LEAVE_PACKET_BYTES: [u8; 8] = [0x17, 0x00, 0x02, 0x69, 0xe0, 0x00, 0x06, 0x96];
     static REPORT_PACKET_BYTES: [u8; 8] = [0x16, 0x00, 0x08, 0xda, 0xe1, 0x00, 0x00, 0x25];
 
+    static QUERY_V3_PACKET_BYTES: [u8; 16] = [
+        0x11, 0x64, 0xf9, 0x19, 0xe8, 0x01, 0x01, 0x01, 0x02, 0x7d, 0x00, 0x01, 0x0a, 0x00, 0x00,
+        0x01,
+    ];
+
+    static REPORT_V3_PACKET_BYTES: [u8; 20] = [
+        0x22, 0x00, 0xe5, 0xf9, 0x00, 0x00, 0x00, 0x01, 0x05, 0x00, 0x00, 0x01, 0xe8, 0x01, 0x01,
+        0x01, 0x0a, 0x00, 0x00, 0x01,
+    ];
+
+    fn query_v3_repr() -> Repr<'static> {
+        Repr::MembershipQueryV3 {
+            max_resp_time: Duration::from_secs(10),
+            group_addr: Ipv4Address::new(232, 1, 1, 1),
+            s_flag: false,
+            qrv: 2,
+            qqic: 125,
+            num_srcs: 1,
+            data: &QUERY_V3_PACKET_BYTES[12..],
+        }
+    }
+
+    fn report_v3_repr() -> Repr<'static> {
+        Repr::MembershipReportV3 {
+            num_group_records: 1,
+            data: &REPORT_V3_PACKET_BYTES[8..],
+        }
+    }
+
     #[test]
     fn test_leave_group_deconstruct() {
         let packet = Packet::new_unchecked(&LEAVE_PACKET_BYTES[..]);
@@ -425,6 +804,72 @@ mod test {
         assert_eq!(&*packet.into_inner(), &REPORT_PACKET_BYTES[..]);
     }
 
+    #[test]
+    fn test_query_v3_parse() {
+        let packet = Packet::new_checked(&QUERY_V3_PACKET_BYTES[..]).unwrap();
+        assert!(packet.verify_checksum());
+        assert!(!packet.s_flag());
+        assert_eq!(packet.qrv(), 2);
+        assert_eq!(packet.qqic(), 125);
+        assert_eq!(packet.num_srcs(), 1);
+        assert_eq!(Repr::parse(&packet), Ok(query_v3_repr()));
+    }
+
+    #[test]
+    fn test_query_v3_truncated_sources() {
+        let packet = Packet::new_checked(&QUERY_V3_PACKET_BYTES[..14]).unwrap();
+        assert_eq!(Repr::parse(&packet), Err(Error));
+    }
+
+    #[test]
+    fn test_query_v3_emit() {
+        let repr = query_v3_repr();
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        repr.emit(&mut Packet::new_unchecked(&mut bytes));
+        assert_eq!(&bytes[..], &QUERY_V3_PACKET_BYTES[..]);
+    }
+
+    #[test]
+    fn test_report_v3_parse() {
+        let packet = Packet::new_checked(&REPORT_V3_PACKET_BYTES[..]).unwrap();
+        assert!(packet.verify_checksum());
+        assert_eq!(packet.num_group_records(), 1);
+        assert_eq!(Repr::parse(&packet), Ok(report_v3_repr()));
+
+        let record = GroupRecord::new_checked(packet.payload()).unwrap();
+        assert_eq!(record.record_type(), RecordType::AllowNewSources);
+        assert_eq!(record.aux_data_len(), 0);
+        assert_eq!(record.num_srcs(), 1);
+        assert_eq!(record.mcast_addr(), Ipv4Address::new(232, 1, 1, 1));
+        assert_eq!(record.source(0), Ipv4Address::new(10, 0, 0, 1));
+        assert_eq!(record.record_len(), 12);
+    }
+
+    #[test]
+    fn test_report_v3_emit() {
+        let repr = report_v3_repr();
+        let mut bytes = vec![0xa5; repr.buffer_len()];
+        repr.emit(&mut Packet::new_unchecked(&mut bytes));
+        assert_eq!(&bytes[..], &REPORT_V3_PACKET_BYTES[..]);
+    }
+
+    #[test]
+    fn test_group_record_construct() {
+        let mut bytes = vec![0xa5; 12];
+        let mut record = GroupRecord::new_unchecked(&mut bytes);
+        record.set_record_type(RecordType::AllowNewSources);
+        record.set_aux_data_len(0);
+        record.set_num_srcs(1);
+        record.set_mcast_addr(Ipv4Address::new(232, 1, 1, 1));
+        record.set_source(0, Ipv4Address::new(10, 0, 0, 1));
+        assert_eq!(&bytes[..], &REPORT_V3_PACKET_BYTES[8..]);
+    }
+
+    #[test]
+    fn test_group_record_truncated() {
+        assert!(GroupRecord::new_checked(&REPORT_V3_PACKET_BYTES[8..18]).is_err());
+    }
+
     #[test]
     fn max_resp_time_to_duration_and_back() {
         for i in 0..256usize {
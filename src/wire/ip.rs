@@ -57,7 +57,8 @@ enum_with_unknown! {
         Ipv6Frag  = 0x2c,
         Icmpv6    = 0x3a,
         Ipv6NoNxt = 0x3b,
-        Ipv6Opts  = 0x3c
+        Ipv6Opts  = 0x3c,
+        UdpLite   = 0x88
     }
 }
 
@@ -69,6 +70,7 @@ impl fmt::Display for Protocol {
             Protocol::Igmp => write!(f, "IGMP"),
             Protocol::Tcp => write!(f, "TCP"),
             Protocol::Udp => write!(f, "UDP"),
+            Protocol::UdpLite => write!(f, "UDP-Lite"),
             Protocol::Ipv6Route => write!(f, "IPv6-Route"),
             Protocol::Ipv6Frag => write!(f, "IPv6-Frag"),
             Protocol::Icmpv6 => write!(f, "ICMPv6"),
@@ -1,7 +1,7 @@
 use byteorder::{ByteOrder, NetworkEndian};
 use core::fmt;
 
-use super::{Error, Result};
+use super::{Error, Ipv4OptionsIterator, Result};
 use crate::phy::ChecksumCapabilities;
 use crate::wire::ip::{checksum, pretty_print_ip_payload};
 
@@ -30,7 +30,7 @@ pub const ADDR_SIZE: usize = 4;
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct Key {
     id: u16,
-    src_addr: Address,
+    pub(crate) src_addr: Address,
     dst_addr: Address,
     protocol: Protocol,
 }
@@ -52,6 +52,9 @@ impl Address {
     /// All multicast-capable routers
     pub const MULTICAST_ALL_ROUTERS: Address = Address([224, 0, 0, 2]);
 
+    /// All IGMPv3-capable multicast routers
+    pub const MULTICAST_ALL_IGMPV3_ROUTERS: Address = Address([224, 0, 0, 22]);
+
     /// Construct an IPv4 address from parts.
     pub const fn new(a0: u8, a1: u8, a2: u8, a3: u8) -> Address {
         Address([a0, a1, a2, a3])
@@ -102,6 +105,39 @@ impl Address {
         self.0[0] == 127
     }
 
+    /// Return the scope of the address, as that of its IPv4-mapped IPv6 address
+    /// (RFC 6724 § 3.2).
+    #[cfg(any(
+        feature = "medium-ethernet",
+        feature = "medium-ip",
+        feature = "medium-ieee802154"
+    ))]
+    pub(crate) fn scope(&self) -> u8 {
+        if self.is_link_local() || self.is_loopback() {
+            0x2
+        } else {
+            0xe
+        }
+    }
+
+    /// Return the number of leading bits that the address has in common with `other`.
+    #[cfg(any(
+        feature = "medium-ethernet",
+        feature = "medium-ip",
+        feature = "medium-ieee802154"
+    ))]
+    pub(crate) fn common_prefix_len(&self, other: &Address) -> u8 {
+        let mut len = 0;
+        for (a, b) in self.0.iter().zip(other.0.iter()) {
+            let diff = a ^ b;
+            len += diff.leading_zeros() as u8;
+            if diff != 0 {
+                break;
+            }
+        }
+        len
+    }
+
     /// Convert to an `IpAddress`.
     ///
     /// Same as `.into()`, but works in `const`.
@@ -320,8 +356,8 @@ impl<T: AsRef<[u8]>> Packet<T> {
 
     /// Ensure that no accessor method will panic if called.
     /// Returns `Err(Error)` if the buffer is too short.
-    /// Returns `Err(Error)` if the header length is greater
-    /// than total length.
+    /// Returns `Err(Error)` if the header length is smaller than
+    /// the fixed part of the header, or greater than total length.
     ///
     /// The result of this check is invalidated by calling [set_header_len]
     /// and [set_total_len].
@@ -335,6 +371,8 @@ impl<T: AsRef<[u8]>> Packet<T> {
             Err(Error)
         } else if len < self.header_len() as usize {
             Err(Error)
+        } else if (self.header_len() as usize) < field::DST_ADDR.end {
+            Err(Error)
         } else if self.header_len() as u16 > self.total_len() {
             Err(Error)
         } else if len < self.total_len() as usize {
@@ -470,6 +508,13 @@ impl<T: AsRef<[u8]>> Packet<T> {
 }
 
 impl<'a, T: AsRef<[u8]> + ?Sized> Packet<&'a T> {
+    /// Return a pointer to the options, between the fixed part of the header and the payload.
+    #[inline]
+    pub fn options(&self) -> &'a [u8] {
+        let data = self.buffer.as_ref();
+        &data[field::DST_ADDR.end..self.header_len() as usize]
+    }
+
     /// Return a pointer to the payload.
     #[inline]
     pub fn payload(&self) -> &'a [u8] {
@@ -648,6 +693,11 @@ impl Repr {
             return Err(Error);
         }
 
+        // The options are ignored, but malformed ones are not acceptable.
+        for option in Ipv4OptionsIterator::new(packet.options()) {
+            option?;
+        }
+
         let payload_len = packet.total_len() as usize - packet.header_len() as usize;
 
         // All DSCP values are acceptable, since they are of no concern to receiving endpoint.
@@ -664,7 +714,7 @@ impl Repr {
 
     /// Return the length of a header that will be emitted from this high-level representation.
     pub const fn buffer_len(&self) -> usize {
-        // We never emit any options.
+        // We never emit any options here, they are added to the emitted header if needed.
         field::DST_ADDR.end
     }
 
@@ -919,6 +969,54 @@ mod test {
         assert_eq!(Packet::new_checked(&mut bytes), Err(Error));
     }
 
+    #[test]
+    fn test_header_len_less_than_fixed_header() {
+        let mut bytes = vec![0; 24];
+        bytes.copy_from_slice(&REPR_PACKET_BYTES[..]);
+        Packet::new_unchecked(&mut bytes).set_header_len(16);
+        assert_eq!(Packet::new_checked(&bytes), Err(Error));
+    }
+
+    fn packet_with_options(options: &[u8]) -> Vec<u8> {
+        let header_len = 20 + options.len();
+        let mut bytes = vec![0; header_len + REPR_PAYLOAD_BYTES.len()];
+        bytes[..20].copy_from_slice(&REPR_PACKET_BYTES[..20]);
+        bytes[20..header_len].copy_from_slice(options);
+        bytes[header_len..].copy_from_slice(&REPR_PAYLOAD_BYTES);
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        packet.set_header_len(header_len as u8);
+        packet.set_total_len((header_len + REPR_PAYLOAD_BYTES.len()) as u16);
+        packet.fill_checksum();
+        bytes
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let bytes = packet_with_options(&[0x94, 0x04, 0x00, 0x00]);
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(packet.options(), &[0x94, 0x04, 0x00, 0x00]);
+        assert_eq!(packet.payload(), &REPR_PAYLOAD_BYTES[..]);
+        let repr = Repr::parse(&packet, &ChecksumCapabilities::default()).unwrap();
+        assert_eq!(repr, packet_repr());
+
+        // A record route option, with room for one address, and padding.
+        let bytes = packet_with_options(&[0x07, 0x07, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00]);
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        let repr = Repr::parse(&packet, &ChecksumCapabilities::default()).unwrap();
+        assert_eq!(repr, packet_repr());
+    }
+
+    #[test]
+    fn test_parse_bad_options() {
+        // An option overflowing the header.
+        let bytes = packet_with_options(&[0x01, 0x07, 0x07, 0x04]);
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(
+            Repr::parse(&packet, &ChecksumCapabilities::default()),
+            Err(Error)
+        );
+    }
+
     #[test]
     fn test_emit() {
         let repr = packet_repr();
@@ -947,6 +1045,31 @@ mod test {
         assert!(!Address::BROADCAST.is_loopback());
     }
 
+    #[test]
+    #[cfg(any(
+        feature = "medium-ethernet",
+        feature = "medium-ip",
+        feature = "medium-ieee802154"
+    ))]
+    fn test_scope() {
+        assert_eq!(Address::new(127, 0, 0, 1).scope(), 0x2);
+        assert_eq!(Address::new(169, 254, 1, 1).scope(), 0x2);
+        assert_eq!(Address::new(192, 168, 1, 1).scope(), 0xe);
+    }
+
+    #[test]
+    #[cfg(any(
+        feature = "medium-ethernet",
+        feature = "medium-ip",
+        feature = "medium-ieee802154"
+    ))]
+    fn test_common_prefix_len() {
+        let addr = Address::new(192, 168, 1, 1);
+        assert_eq!(addr.common_prefix_len(&addr), 32);
+        assert_eq!(addr.common_prefix_len(&Address::new(192, 168, 1, 2)), 30);
+        assert_eq!(addr.common_prefix_len(&Address::new(10, 0, 0, 1)), 0);
+    }
+
     #[test]
     fn test_cidr() {
         let cidr = Cidr::new(Address::new(192, 168, 1, 10), 24);
@@ -0,0 +1,618 @@
+use super::{Error, Result};
+
+use byteorder::{ByteOrder, NetworkEndian};
+use core::fmt;
+
+enum_with_unknown! {
+    /// IPv4 Option Type
+    pub enum Type(u8) {
+        /// End of the option list
+        EndOfList   = 0,
+        /// 1 byte of padding
+        Nop         = 1,
+        /// Record Route
+        RecordRoute = 7,
+        /// Internet Timestamp
+        Timestamp   = 68,
+        /// Router Alert
+        RouterAlert = 148,
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Type::EndOfList => write!(f, "End of List"),
+            Type::Nop => write!(f, "NOP"),
+            Type::RecordRoute => write!(f, "Record Route"),
+            Type::Timestamp => write!(f, "Timestamp"),
+            Type::RouterAlert => write!(f, "Router Alert"),
+            Type::Unknown(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// A read/write wrapper around an IPv4 Option.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ipv4Option<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+// Format of Option
+//
+// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+- - - - - - - - -
+// |  Option Type  | Option Length |  Option Data
+// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+- - - - - - - - -
+//
+// The length covers the type and length octets. The End of Option List and No Operation
+// options are a single octet, without length.
+//
+// See https://tools.ietf.org/html/rfc791#section-3.1 for details.
+mod field {
+    #![allow(non_snake_case)]
+
+    use crate::wire::field::*;
+
+    // 8-bit identifier of the type of option.
+    pub const TYPE: usize = 0;
+    // 8-bit unsigned integer. Length of the option, in octets.
+    pub const LENGTH: usize = 1;
+    // Variable-length field. Option-Type-specific data.
+    pub const fn DATA(length: u8) -> Field {
+        2..length as usize
+    }
+
+    // 8-bit offset of the next slot in a Record Route or Timestamp option, from the
+    // start of the option.
+    pub const POINTER: usize = 0;
+    // 4-bit overflow count and 4-bit flags of a Timestamp option.
+    pub const OFLW_FLG: usize = 1;
+}
+
+/// The length of a Router Alert option, in octets.
+pub const ROUTER_ALERT_LEN: usize = 4;
+
+impl<T: AsRef<[u8]>> Ipv4Option<T> {
+    /// Create a raw octet buffer with an IPv4 Option structure.
+    pub const fn new_unchecked(buffer: T) -> Ipv4Option<T> {
+        Ipv4Option { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Ipv4Option<T>> {
+        let opt = Self::new_unchecked(buffer);
+        opt.check_len()?;
+        Ok(opt)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short, or if the length
+    /// is smaller than the type and length octets.
+    ///
+    /// The result of this check is invalidated by calling [set_length].
+    ///
+    /// [set_length]: #method.set_length
+    pub fn check_len(&self) -> Result<()> {
+        let data = self.buffer.as_ref();
+        let len = data.len();
+
+        if data.is_empty() {
+            return Err(Error);
+        }
+
+        if matches!(self.option_type(), Type::EndOfList | Type::Nop) {
+            return Ok(());
+        }
+
+        if len == field::LENGTH {
+            return Err(Error);
+        }
+
+        let length = data[field::LENGTH];
+        if (length as usize) < field::DATA(length).start || len < length as usize {
+            return Err(Error);
+        }
+
+        Ok(())
+    }
+
+    /// Consume the ipv4 option, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the option type.
+    #[inline]
+    pub fn option_type(&self) -> Type {
+        let data = self.buffer.as_ref();
+        Type::from(data[field::TYPE])
+    }
+
+    /// Return the length of the option, including the type and length octets.
+    ///
+    /// # Panics
+    /// This function panics if this is a single octet option.
+    #[inline]
+    pub fn length(&self) -> u8 {
+        let data = self.buffer.as_ref();
+        data[field::LENGTH]
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Ipv4Option<&'a T> {
+    /// Return the option data.
+    ///
+    /// # Panics
+    /// This function panics if this is a single octet option.
+    #[inline]
+    pub fn data(&self) -> &'a [u8] {
+        let len = self.length();
+        let data = self.buffer.as_ref();
+        &data[field::DATA(len)]
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Ipv4Option<T> {
+    /// Set the option type.
+    #[inline]
+    pub fn set_option_type(&mut self, value: Type) {
+        let data = self.buffer.as_mut();
+        data[field::TYPE] = value.into();
+    }
+
+    /// Set the length of the option, including the type and length octets.
+    ///
+    /// # Panics
+    /// This function panics if this is a single octet option.
+    #[inline]
+    pub fn set_length(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::LENGTH] = value;
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]> + ?Sized> Ipv4Option<&mut T> {
+    /// Return a mutable pointer to the option data.
+    ///
+    /// # Panics
+    /// This function panics if this is a single octet option.
+    #[inline]
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        let len = self.length();
+        let data = self.buffer.as_mut();
+        &mut data[field::DATA(len)]
+    }
+}
+
+impl<T: AsRef<[u8]> + ?Sized> fmt::Display for Ipv4Option<&T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match Repr::parse(self) {
+            Ok(repr) => write!(f, "{repr}"),
+            Err(err) => {
+                write!(f, "IPv4 Option ({err})")?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A high-level representation of an IPv4 Option.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Repr<'a> {
+    EndOfList,
+    Nop,
+    /// The route recorded so far, made of the addresses before `pointer`, and the room
+    /// left for the next ones.
+    RecordRoute {
+        pointer: u8,
+        data: &'a [u8],
+    },
+    /// The timestamps recorded so far, and the room left for the next ones.
+    Timestamp {
+        pointer: u8,
+        overflow: u8,
+        flags: u8,
+        data: &'a [u8],
+    },
+    /// A Router Alert, with the value telling what routers should do, zero meaning that
+    /// they shall examine the packet (RFC 2113).
+    RouterAlert(u16),
+    Unknown {
+        type_: Type,
+        length: u8,
+        data: &'a [u8],
+    },
+}
+
+impl<'a> Repr<'a> {
+    /// Parse an IPv4 Option and return a high-level representation.
+    pub fn parse<T>(opt: &Ipv4Option<&'a T>) -> Result<Repr<'a>>
+    where
+        T: AsRef<[u8]> + ?Sized,
+    {
+        match opt.option_type() {
+            Type::EndOfList => Ok(Repr::EndOfList),
+            Type::Nop => Ok(Repr::Nop),
+            Type::RecordRoute => {
+                let data = opt.data();
+                // The pointer is relative to the option, and at least 4, past itself.
+                match data.first() {
+                    Some(&pointer) if pointer >= 4 => Ok(Repr::RecordRoute {
+                        pointer,
+                        data: &data[field::POINTER + 1..],
+                    }),
+                    _ => Err(Error),
+                }
+            }
+            Type::Timestamp => {
+                let data = opt.data();
+                // The pointer is relative to the option, and at least 5, past the flags.
+                if data.len() <= field::OFLW_FLG || data[field::POINTER] < 5 {
+                    return Err(Error);
+                }
+                Ok(Repr::Timestamp {
+                    pointer: data[field::POINTER],
+                    overflow: data[field::OFLW_FLG] >> 4,
+                    flags: data[field::OFLW_FLG] & 0x0f,
+                    data: &data[field::OFLW_FLG + 1..],
+                })
+            }
+            Type::RouterAlert => {
+                if opt.length() as usize != ROUTER_ALERT_LEN {
+                    return Err(Error);
+                }
+                Ok(Repr::RouterAlert(NetworkEndian::read_u16(opt.data())))
+            }
+            unknown_type @ Type::Unknown(_) => Ok(Repr::Unknown {
+                type_: unknown_type,
+                length: opt.length(),
+                data: opt.data(),
+            }),
+        }
+    }
+
+    /// Return the length of an option that will be emitted from this high-level representation.
+    pub const fn buffer_len(&self) -> usize {
+        match *self {
+            Repr::EndOfList | Repr::Nop => 1,
+            Repr::RecordRoute { data, .. } => field::DATA(0).start + 1 + data.len(),
+            Repr::Timestamp { data, .. } => field::DATA(0).start + 2 + data.len(),
+            Repr::RouterAlert(_) => ROUTER_ALERT_LEN,
+            Repr::Unknown { length, .. } => length as usize,
+        }
+    }
+
+    /// Emit a high-level representation into an IPv4 Option.
+    pub fn emit<T: AsRef<[u8]> + AsMut<[u8]> + ?Sized>(&self, opt: &mut Ipv4Option<&'a mut T>) {
+        match *self {
+            Repr::EndOfList => opt.set_option_type(Type::EndOfList),
+            Repr::Nop => opt.set_option_type(Type::Nop),
+            Repr::RecordRoute { pointer, data } => {
+                opt.set_option_type(Type::RecordRoute);
+                opt.set_length(self.buffer_len() as u8);
+                let opt_data = opt.data_mut();
+                opt_data[field::POINTER] = pointer;
+                opt_data[field::POINTER + 1..].copy_from_slice(data);
+            }
+            Repr::Timestamp {
+                pointer,
+                overflow,
+                flags,
+                data,
+            } => {
+                opt.set_option_type(Type::Timestamp);
+                opt.set_length(self.buffer_len() as u8);
+                let opt_data = opt.data_mut();
+                opt_data[field::POINTER] = pointer;
+                opt_data[field::OFLW_FLG] = (overflow << 4) | (flags & 0x0f);
+                opt_data[field::OFLW_FLG + 1..].copy_from_slice(data);
+            }
+            Repr::RouterAlert(value) => {
+                opt.set_option_type(Type::RouterAlert);
+                opt.set_length(ROUTER_ALERT_LEN as u8);
+                NetworkEndian::write_u16(opt.data_mut(), value);
+            }
+            Repr::Unknown {
+                type_,
+                length,
+                data,
+            } => {
+                opt.set_option_type(type_);
+                opt.set_length(length);
+                let opt_data = opt.data_mut();
+                let data_len = opt_data.len();
+                opt_data.copy_from_slice(&data[..data_len]);
+            }
+        }
+    }
+}
+
+/// A iterator for IPv4 options.
+///
+/// The iteration stops after the End of Option List option, since what follows it is
+/// padding, or after the first malformed option.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ipv4OptionsIterator<'a> {
+    pos: usize,
+    length: usize,
+    data: &'a [u8],
+    hit_end: bool,
+}
+
+impl<'a> Ipv4OptionsIterator<'a> {
+    /// Create a new `Ipv4OptionsIterator`, used to iterate over the
+    /// options contained in an IPv4 header.
+    pub fn new(data: &'a [u8]) -> Ipv4OptionsIterator<'a> {
+        let length = data.len();
+        Ipv4OptionsIterator {
+            pos: 0,
+            hit_end: false,
+            length,
+            data,
+        }
+    }
+}
+
+impl<'a> Iterator for Ipv4OptionsIterator<'a> {
+    type Item = Result<Repr<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos < self.length && !self.hit_end {
+            // If we still have data to parse and we have neither hit an error
+            // nor the end of the list, attempt to parse the next option.
+            let result = Ipv4Option::new_checked(&self.data[self.pos..])
+                .and_then(|opt| Repr::parse(&opt).map(|repr| (repr, opt.into_inner())));
+            match result {
+                Ok((repr, opt)) => {
+                    // Skip the whole option, even if it has trailing data.
+                    self.pos += match repr {
+                        Repr::EndOfList | Repr::Nop => 1,
+                        _ => opt[field::LENGTH] as usize,
+                    };
+                    self.hit_end = repr == Repr::EndOfList;
+                    Some(Ok(repr))
+                }
+                Err(e) => {
+                    self.hit_end = true;
+                    Some(Err(e))
+                }
+            }
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> fmt::Display for Repr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IPv4 Option ")?;
+        match *self {
+            Repr::EndOfList => write!(f, "{}", Type::EndOfList),
+            Repr::Nop => write!(f, "{}", Type::Nop),
+            Repr::RecordRoute { pointer, data } => write!(
+                f,
+                "{} pointer={} length={}",
+                Type::RecordRoute,
+                pointer,
+                data.len()
+            ),
+            Repr::Timestamp {
+                pointer,
+                overflow,
+                flags,
+                data,
+            } => write!(
+                f,
+                "{} pointer={} overflow={} flags={} length={}",
+                Type::Timestamp,
+                pointer,
+                overflow,
+                flags,
+                data.len()
+            ),
+            Repr::RouterAlert(value) => write!(f, "{} value={}", Type::RouterAlert, value),
+            Repr::Unknown { type_, length, .. } => write!(f, "{type_} length={length}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static IPV4OPTION_BYTES_NOP: [u8; 1] = [0x1];
+    static IPV4OPTION_BYTES_ROUTER_ALERT: [u8; 4] = [0x94, 0x04, 0x00, 0x00];
+    static IPV4OPTION_BYTES_RECORD_ROUTE: [u8; 11] = [
+        0x07, 0x0b, 0x08, 0xc0, 0xa8, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00,
+    ];
+    static IPV4OPTION_BYTES_TIMESTAMP: [u8; 12] = [
+        0x44, 0x0c, 0x09, 0x10, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    static IPV4OPTION_BYTES_UNKNOWN: [u8; 3] = [0xff, 0x03, 0x2a];
+
+    #[test]
+    fn test_check_len() {
+        let bytes = [0u8];
+        // zero byte buffer
+        assert_eq!(
+            Err(Error),
+            Ipv4Option::new_unchecked(&bytes[..0]).check_len()
+        );
+        // end of list
+        assert_eq!(Ok(()), Ipv4Option::new_unchecked(&bytes).check_len());
+        // nop
+        assert_eq!(
+            Ok(()),
+            Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_NOP).check_len()
+        );
+
+        // router alert without length
+        assert_eq!(
+            Err(Error),
+            Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_ROUTER_ALERT[..1]).check_len()
+        );
+        // router alert with truncated data
+        assert_eq!(
+            Err(Error),
+            Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_ROUTER_ALERT[..3]).check_len()
+        );
+        assert_eq!(
+            Ok(()),
+            Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_ROUTER_ALERT).check_len()
+        );
+
+        // length shorter than the type and length octets
+        assert_eq!(
+            Err(Error),
+            Ipv4Option::new_unchecked(&[0xff, 0x01, 0x00]).check_len()
+        );
+    }
+
+    #[test]
+    fn test_option_deconstruct() {
+        let opt = Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_ROUTER_ALERT);
+        assert_eq!(opt.option_type(), Type::RouterAlert);
+        assert_eq!(opt.length(), 4);
+        assert_eq!(opt.data(), &[0, 0]);
+
+        let opt = Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_UNKNOWN);
+        assert_eq!(opt.option_type(), Type::Unknown(0xff));
+        assert_eq!(opt.length(), 3);
+        assert_eq!(opt.data(), &[0x2a]);
+    }
+
+    #[test]
+    fn test_option_parse() {
+        let opt = Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_NOP);
+        assert_eq!(Repr::parse(&opt), Ok(Repr::Nop));
+
+        let opt = Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_ROUTER_ALERT);
+        assert_eq!(Repr::parse(&opt), Ok(Repr::RouterAlert(0)));
+
+        let opt = Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_RECORD_ROUTE);
+        assert_eq!(
+            Repr::parse(&opt),
+            Ok(Repr::RecordRoute {
+                pointer: 8,
+                data: &IPV4OPTION_BYTES_RECORD_ROUTE[3..],
+            })
+        );
+
+        let opt = Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_TIMESTAMP);
+        assert_eq!(
+            Repr::parse(&opt),
+            Ok(Repr::Timestamp {
+                pointer: 9,
+                overflow: 1,
+                flags: 0,
+                data: &IPV4OPTION_BYTES_TIMESTAMP[4..],
+            })
+        );
+
+        let opt = Ipv4Option::new_unchecked(&IPV4OPTION_BYTES_UNKNOWN);
+        assert_eq!(
+            Repr::parse(&opt),
+            Ok(Repr::Unknown {
+                type_: Type::Unknown(0xff),
+                length: 3,
+                data: &[0x2a],
+            })
+        );
+    }
+
+    #[test]
+    fn test_option_parse_malformed() {
+        // router alert of the wrong length
+        let opt = Ipv4Option::new_unchecked(&[0x94, 0x03, 0x00]);
+        assert_eq!(Repr::parse(&opt), Err(Error));
+        // record route with a pointer inside the option header
+        let opt = Ipv4Option::new_unchecked(&[0x07, 0x07, 0x03, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(Repr::parse(&opt), Err(Error));
+        // record route without pointer
+        let opt = Ipv4Option::new_unchecked(&[0x07, 0x02]);
+        assert_eq!(Repr::parse(&opt), Err(Error));
+        // timestamp without flags
+        let opt = Ipv4Option::new_unchecked(&[0x44, 0x03, 0x05]);
+        assert_eq!(Repr::parse(&opt), Err(Error));
+    }
+
+    #[test]
+    fn test_option_emit() {
+        let repr = Repr::RouterAlert(0);
+        let mut bytes = [0xffu8; 4];
+        let mut opt = Ipv4Option::new_unchecked(&mut bytes);
+        repr.emit(&mut opt);
+        assert_eq!(opt.into_inner(), &IPV4OPTION_BYTES_ROUTER_ALERT);
+
+        let repr = Repr::RecordRoute {
+            pointer: 8,
+            data: &IPV4OPTION_BYTES_RECORD_ROUTE[3..],
+        };
+        assert_eq!(repr.buffer_len(), IPV4OPTION_BYTES_RECORD_ROUTE.len());
+        let mut bytes = [0u8; 11];
+        let mut opt = Ipv4Option::new_unchecked(&mut bytes);
+        repr.emit(&mut opt);
+        assert_eq!(opt.into_inner(), &IPV4OPTION_BYTES_RECORD_ROUTE);
+
+        let repr = Repr::Timestamp {
+            pointer: 9,
+            overflow: 1,
+            flags: 0,
+            data: &IPV4OPTION_BYTES_TIMESTAMP[4..],
+        };
+        assert_eq!(repr.buffer_len(), IPV4OPTION_BYTES_TIMESTAMP.len());
+        let mut bytes = [0u8; 12];
+        let mut opt = Ipv4Option::new_unchecked(&mut bytes);
+        repr.emit(&mut opt);
+        assert_eq!(opt.into_inner(), &IPV4OPTION_BYTES_TIMESTAMP);
+
+        let repr = Repr::Unknown {
+            type_: Type::Unknown(0xff),
+            length: 3,
+            data: &[0x2a],
+        };
+        let mut bytes = [0u8; 3];
+        let mut opt = Ipv4Option::new_unchecked(&mut bytes);
+        repr.emit(&mut opt);
+        assert_eq!(opt.into_inner(), &IPV4OPTION_BYTES_UNKNOWN);
+    }
+
+    #[test]
+    fn test_options_iter() {
+        let options = [
+            0x01, 0x94, 0x04, 0x00, 0x00, 0xff, 0x02, 0x00, 0x99, 0x99, 0x99,
+        ];
+
+        let iterator = Ipv4OptionsIterator::new(&options);
+        for (i, opt) in iterator.enumerate() {
+            match (i, opt) {
+                (0, Ok(Repr::Nop)) => continue,
+                (1, Ok(Repr::RouterAlert(0))) => continue,
+                (
+                    2,
+                    Ok(Repr::Unknown {
+                        type_: Type::Unknown(0xff),
+                        length: 2,
+                        ..
+                    }),
+                ) => continue,
+                // The padding after the end of the list is not parsed.
+                (3, Ok(Repr::EndOfList)) => continue,
+                (i, res) => panic!("Unexpected option `{res:?}` at index {i}"),
+            }
+        }
+
+        let options = [0x01, 0x94, 0x05, 0x00];
+        let mut iterator = Ipv4OptionsIterator::new(&options);
+        assert_eq!(iterator.next(), Some(Ok(Repr::Nop)));
+        assert_eq!(iterator.next(), Some(Err(Error)));
+        assert_eq!(iterator.next(), None);
+    }
+}
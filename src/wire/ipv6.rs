@@ -51,6 +51,22 @@ impl Address {
         0x02,
     ]);
 
+    /// The link-local [all RPL nodes multicast address].
+    ///
+    /// [all RPL nodes multicast address]: https://www.rfc-editor.org/rfc/rfc6550#section-20.19
+    pub const LINK_LOCAL_ALL_RPL_NODES: Address = Address([
+        0xff, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x1a,
+    ]);
+
+    /// The link-local [all MLDv2-capable routers multicast address].
+    ///
+    /// [all MLDv2-capable routers multicast address]: https://tools.ietf.org/html/rfc3810#section-11
+    pub const LINK_LOCAL_ALL_MLDV2_ROUTERS: Address = Address([
+        0xff, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x16,
+    ]);
+
     /// The [loopback address].
     ///
     /// [loopback address]: https://tools.ietf.org/html/rfc4291#section-2.5.3
@@ -190,6 +206,35 @@ impl Address {
         }
     }
 
+    /// Return the scope of the address (RFC 6724 § 3.1), wider scopes having larger values:
+    /// the scope field of multicast addresses, or that of the multicast addresses with the
+    /// same reach for unicast ones.
+    pub(crate) fn scope(&self) -> u8 {
+        if self.is_multicast() {
+            self.0[1] & 0x0f
+        } else if self.is_link_local() || self.is_loopback() {
+            0x2
+        } else if self.0[0] == 0xfe && self.0[1] & 0xc0 == 0xc0 {
+            // Deprecated site-local addresses.
+            0x5
+        } else {
+            0xe
+        }
+    }
+
+    /// Return the number of leading bits that the address has in common with `other`.
+    pub(crate) fn common_prefix_len(&self, other: &Address) -> u8 {
+        let mut len = 0;
+        for (a, b) in self.0.iter().zip(other.0.iter()) {
+            let diff = a ^ b;
+            len += diff.leading_zeros() as u8;
+            if diff != 0 {
+                break;
+            }
+        }
+        len
+    }
+
     /// Helper function used to mask an address given a prefix.
     ///
     /// # Panics
@@ -396,6 +441,17 @@ impl Cidr {
         prefix_len: 104,
     };
 
+    /// The [Well-Known Prefix] of the IPv4-embedded addresses used by NAT64.
+    ///
+    /// [Well-Known Prefix]: https://tools.ietf.org/html/rfc6052#section-2.1
+    pub const NAT64_WELL_KNOWN_PREFIX: Cidr = Cidr {
+        address: Address([
+            0x00, 0x64, 0xff, 0x9b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00,
+        ]),
+        prefix_len: 96,
+    };
+
     /// Create an IPv6 CIDR block from the given address and prefix length.
     ///
     /// # Panics
@@ -434,6 +490,72 @@ impl Cidr {
     pub fn contains_subnet(&self, subnet: &Cidr) -> bool {
         self.prefix_len <= subnet.prefix_len && self.contains_addr(&subnet.address)
     }
+
+    /// Return the Subnet-Router anycast address of this IPv6 CIDR block, i.e. its
+    /// prefix followed by zeros (RFC 4291 § 2.6.1).
+    pub fn subnet_router_anycast(&self) -> Address {
+        Address(self.address.mask(self.prefix_len))
+    }
+
+    /// Return the IPv4-embedded IPv6 address of `addr` with this NAT64 prefix
+    /// ([RFC 6052 § 2.2]), or `None` if the prefix length is not one of 32, 40, 48, 56,
+    /// 64 or 96.
+    ///
+    /// [RFC 6052 § 2.2]: https://tools.ietf.org/html/rfc6052#section-2.2
+    #[cfg(feature = "proto-ipv4")]
+    pub fn embed_ipv4(&self, addr: ipv4::Address) -> Option<Address> {
+        let positions = ipv4_embedding(self.prefix_len)?;
+        let mut bytes = self.address.mask(self.prefix_len);
+        for (position, byte) in positions.into_iter().zip(addr.as_bytes()) {
+            bytes[position] = *byte;
+        }
+        Some(Address(bytes))
+    }
+
+    /// Return the IPv4 address embedded in `addr`, if it is an IPv4-embedded IPv6 address
+    /// with this NAT64 prefix.
+    #[cfg(feature = "proto-ipv4")]
+    pub fn extract_ipv4(&self, addr: &Address) -> Option<ipv4::Address> {
+        let positions = ipv4_embedding(self.prefix_len)?;
+        if !self.contains_addr(addr) || addr.0[8] != 0 {
+            return None;
+        }
+        Some(ipv4::Address(positions.map(|position| addr.0[position])))
+    }
+
+    /// Return the NAT64 prefix that a DNS64 server synthesized `addr` with, when it is
+    /// an AAAA record of `ipv4only.arpa` ([RFC 7050 § 3]).
+    ///
+    /// [RFC 7050 § 3]: https://tools.ietf.org/html/rfc7050#section-3
+    pub fn from_ipv4only_arpa(addr: &Address) -> Option<Cidr> {
+        // The well-known IPv4 addresses of `ipv4only.arpa`.
+        const WKAS: [[u8; 4]; 2] = [[192, 0, 0, 170], [192, 0, 0, 171]];
+
+        [96, 64, 56, 48, 40, 32].into_iter().find_map(|prefix_len| {
+            let positions = ipv4_embedding(prefix_len)?;
+            let embedded = positions.map(|position| addr.0[position]);
+            (WKAS.contains(&embedded) && (prefix_len == 96 || addr.0[8] == 0))
+                .then(|| Cidr::new(Address(addr.mask(prefix_len)), prefix_len))
+        })
+    }
+}
+
+/// Return the positions of the octets of an IPv4 address embedded after a NAT64 prefix
+/// of `prefix_len` bits, skipping bits 64 to 71 (RFC 6052 § 2.2).
+fn ipv4_embedding(prefix_len: u8) -> Option<[usize; 4]> {
+    if !matches!(prefix_len, 32 | 40 | 48 | 56 | 64 | 96) {
+        return None;
+    }
+    let mut positions = [0; 4];
+    let mut next = prefix_len as usize / 8;
+    for position in positions.iter_mut() {
+        if next == 8 {
+            next += 1;
+        }
+        *position = next;
+        next += 1;
+    }
+    Some(positions)
 }
 
 impl fmt::Display for Cidr {
@@ -965,6 +1087,32 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_scope() {
+        assert_eq!(Address::LINK_LOCAL_ALL_NODES.scope(), 0x2);
+        assert_eq!(Address::new(0xff05, 0, 0, 0, 0, 0, 0, 1).scope(), 0x5);
+        assert_eq!(Address::LOOPBACK.scope(), 0x2);
+        assert_eq!(LINK_LOCAL_ADDR.scope(), 0x2);
+        assert_eq!(Address::new(0xfec0, 0, 0, 0, 0, 0, 0, 1).scope(), 0x5);
+        assert_eq!(Address::new(0xfd00, 0, 0, 0, 0, 0, 0, 1).scope(), 0xe);
+        assert_eq!(Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).scope(), 0xe);
+    }
+
+    #[test]
+    fn test_common_prefix_len() {
+        let addr = Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        assert_eq!(addr.common_prefix_len(&addr), 128);
+        assert_eq!(
+            addr.common_prefix_len(&Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 3)),
+            126
+        );
+        assert_eq!(
+            addr.common_prefix_len(&Address::new(0x2001, 0xdb9, 0, 0, 0, 0, 0, 1)),
+            31
+        );
+        assert_eq!(addr.common_prefix_len(&LINK_LOCAL_ADDR), 0);
+    }
+
     #[cfg(feature = "proto-ipv4")]
     #[test]
     fn test_is_ipv4_mapped() {
@@ -1129,6 +1277,78 @@ mod test {
         assert!(cidr_without_prefix.contains_addr(&Address::LOOPBACK));
     }
 
+    #[test]
+    fn test_subnet_router_anycast() {
+        let cidr = Cidr::new(Address::new(0x2001, 0xdb8, 1, 2, 3, 4, 5, 6), 64);
+        assert_eq!(
+            cidr.subnet_router_anycast(),
+            Address::new(0x2001, 0xdb8, 1, 2, 0, 0, 0, 0)
+        );
+        let cidr = Cidr::new(Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0x1ff), 120);
+        assert_eq!(
+            cidr.subnet_router_anycast(),
+            Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0x100)
+        );
+    }
+
+    #[cfg(feature = "proto-ipv4")]
+    #[test]
+    fn test_nat64_embedding() {
+        let ipv4 = Ipv4Address::new(192, 0, 2, 33);
+        // Examples from RFC 6052 § 2.4.
+        let cases = [
+            (32, Address::new(0x2001, 0xdb8, 0xc000, 0x221, 0, 0, 0, 0)),
+            (40, Address::new(0x2001, 0xdb8, 0x1c0, 0x2, 0x21, 0, 0, 0)),
+            (
+                48,
+                Address::new(0x2001, 0xdb8, 0x122, 0xc000, 0x2, 0x2100, 0, 0),
+            ),
+            (
+                56,
+                Address::new(0x2001, 0xdb8, 0x122, 0x3c0, 0, 0x221, 0, 0),
+            ),
+            (
+                64,
+                Address::new(0x2001, 0xdb8, 0x122, 0x344, 0xc0, 0x2, 0x2100, 0),
+            ),
+            (
+                96,
+                Address::new(0x2001, 0xdb8, 0x122, 0x344, 0, 0, 0xc000, 0x221),
+            ),
+        ];
+        for (prefix_len, addr) in cases {
+            let prefix = Cidr::new(
+                Address::new(0x2001, 0xdb8, 0x122, 0x344, 0, 0, 0, 0),
+                prefix_len,
+            );
+            assert_eq!(prefix.embed_ipv4(ipv4), Some(addr));
+            assert_eq!(prefix.extract_ipv4(&addr), Some(ipv4));
+        }
+
+        assert_eq!(
+            Cidr::NAT64_WELL_KNOWN_PREFIX.embed_ipv4(ipv4),
+            Some(Address::new(0x64, 0xff9b, 0, 0, 0, 0, 0xc000, 0x221))
+        );
+        assert_eq!(Cidr::new(LINK_LOCAL_ADDR, 72).embed_ipv4(ipv4), None);
+        assert_eq!(
+            Cidr::NAT64_WELL_KNOWN_PREFIX.extract_ipv4(&LINK_LOCAL_ADDR),
+            None
+        );
+    }
+
+    #[test]
+    fn test_from_ipv4only_arpa() {
+        assert_eq!(
+            Cidr::from_ipv4only_arpa(&Address::new(0x64, 0xff9b, 0, 0, 0, 0, 0xc000, 0xaa)),
+            Some(Cidr::NAT64_WELL_KNOWN_PREFIX)
+        );
+        assert_eq!(
+            Cidr::from_ipv4only_arpa(&Address::new(0x2001, 0xdb8, 0xc0, 0, 0xab, 0, 0, 0)),
+            Some(Cidr::new(Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 40))
+        );
+        assert_eq!(Cidr::from_ipv4only_arpa(&LINK_LOCAL_ADDR), None);
+    }
+
     #[test]
     #[should_panic(expected = "length")]
     fn test_from_bytes_too_long() {
@@ -81,6 +81,8 @@ pub mod pretty_print;
 mod arp;
 #[cfg(feature = "proto-dhcpv4")]
 pub(crate) mod dhcpv4;
+#[cfg(feature = "proto-dhcpv6")]
+pub(crate) mod dhcpv6;
 #[cfg(feature = "proto-dns")]
 pub(crate) mod dns;
 #[cfg(feature = "medium-ethernet")]
@@ -98,6 +100,8 @@ mod igmp;
 pub(crate) mod ip;
 #[cfg(feature = "proto-ipv4")]
 mod ipv4;
+#[cfg(feature = "proto-ipv4")]
+mod ipv4option;
 #[cfg(feature = "proto-ipv6")]
 mod ipv6;
 #[cfg(feature = "proto-ipv6")]
@@ -126,6 +130,11 @@ mod rpl;
 mod sixlowpan;
 mod tcp;
 mod udp;
+mod udplite;
+#[cfg(feature = "medium-ethernet")]
+mod vlan;
+#[cfg(feature = "medium-ethernet")]
+mod wol;
 
 use core::fmt;
 
@@ -139,6 +148,14 @@ pub use self::ethernet::{
     Repr as EthernetRepr, HEADER_LEN as ETHERNET_HEADER_LEN,
 };
 
+#[cfg(feature = "medium-ethernet")]
+pub use self::vlan::{Packet as VlanPacket, Repr as VlanRepr, HEADER_LEN as VLAN_HEADER_LEN};
+
+#[cfg(feature = "medium-ethernet")]
+pub use self::wol::{
+    Packet as WolPacket, Repr as WolRepr, HEADER_LEN as WOL_HEADER_LEN, PORT as WOL_PORT,
+};
+
 #[cfg(all(feature = "proto-ipv4", feature = "medium-ethernet"))]
 pub use self::arp::{
     Hardware as ArpHardware, Operation as ArpOperation, Packet as ArpPacket, Repr as ArpRepr,
@@ -147,14 +164,16 @@ pub use self::arp::{
 #[cfg(feature = "proto-rpl")]
 pub use self::rpl::{
     data::HopByHopOption as RplHopByHopRepr, data::Packet as RplHopByHopPacket,
-    options::Packet as RplOptionPacket, options::Repr as RplOptionRepr,
-    InstanceId as RplInstanceId, Repr as RplRepr,
+    options::OptionsIterator as RplOptionsIterator, options::Packet as RplOptionPacket,
+    options::Repr as RplOptionRepr, InstanceId as RplInstanceId,
+    ModeOfOperation as RplModeOfOperation, Repr as RplRepr,
 };
 
 #[cfg(all(feature = "proto-sixlowpan", feature = "medium-ieee802154"))]
 pub use self::sixlowpan::{
     frag::{Key as SixlowpanFragKey, Packet as SixlowpanFragPacket, Repr as SixlowpanFragRepr},
     iphc::{Packet as SixlowpanIphcPacket, Repr as SixlowpanIphcRepr},
+    mesh::{Packet as SixlowpanMeshPacket, Repr as SixlowpanMeshRepr},
     nhc::{
         ExtHeaderPacket as SixlowpanExtHeaderPacket, ExtHeaderRepr as SixlowpanExtHeaderRepr,
         NhcPacket as SixlowpanNhcPacket, UdpNhcPacket as SixlowpanUdpNhcPacket,
@@ -182,6 +201,12 @@ pub use self::ipv4::{
     Repr as Ipv4Repr, HEADER_LEN as IPV4_HEADER_LEN, MIN_MTU as IPV4_MIN_MTU,
 };
 
+#[cfg(feature = "proto-ipv4")]
+pub use self::ipv4option::{
+    Ipv4Option, Ipv4OptionsIterator, Repr as Ipv4OptionRepr, Type as Ipv4OptionType,
+    ROUTER_ALERT_LEN as IPV4_ROUTER_ALERT_LEN,
+};
+
 #[cfg(feature = "proto-ipv6")]
 pub use self::ipv6::{
     Address as Ipv6Address, Cidr as Ipv6Cidr, Packet as Ipv6Packet, Repr as Ipv6Repr,
@@ -220,7 +245,10 @@ pub use self::icmpv4::{
 };
 
 #[cfg(feature = "proto-igmp")]
-pub use self::igmp::{IgmpVersion, Packet as IgmpPacket, Repr as IgmpRepr};
+pub use self::igmp::{
+    GroupRecord as IgmpGroupRecord, IgmpVersion, Packet as IgmpPacket,
+    RecordType as IgmpRecordType, Repr as IgmpRepr,
+};
 
 #[cfg(feature = "proto-ipv6")]
 pub use self::icmpv6::{
@@ -244,19 +272,25 @@ pub use self::ndisc::{
     any(feature = "medium-ethernet", feature = "medium-ieee802154")
 ))]
 pub use self::ndiscoption::{
-    NdiscOption, PrefixInfoFlags as NdiscPrefixInfoFlags,
-    PrefixInformation as NdiscPrefixInformation, RedirectedHeader as NdiscRedirectedHeader,
-    Repr as NdiscOptionRepr, Type as NdiscOptionType,
+    DnsSearchList as NdiscDnsSearchList, NdiscOption, Pref64 as NdiscPref64,
+    PrefixInfoFlags as NdiscPrefixInfoFlags, PrefixInformation as NdiscPrefixInformation,
+    RecursiveDnsServer as NdiscRecursiveDnsServer, RedirectedHeader as NdiscRedirectedHeader,
+    Repr as NdiscOptionRepr, SixlowpanContext as NdiscSixlowpanContext, Type as NdiscOptionType,
 };
 
 #[cfg(feature = "proto-ipv6")]
-pub use self::mld::{AddressRecord as MldAddressRecord, Repr as MldRepr};
+pub use self::mld::{
+    AddressRecord as MldAddressRecord, RecordType as MldRecordType, Repr as MldRepr,
+};
 
 pub use self::udp::{Packet as UdpPacket, Repr as UdpRepr, HEADER_LEN as UDP_HEADER_LEN};
 
+pub use self::udplite::{Packet as UdpLitePacket, Repr as UdpLiteRepr};
+
 pub use self::tcp::{
-    Control as TcpControl, Packet as TcpPacket, Repr as TcpRepr, SeqNumber as TcpSeqNumber,
-    TcpOption, HEADER_LEN as TCP_HEADER_LEN,
+    AuthOption as TcpAuthOption, Control as TcpControl, Packet as TcpPacket, Repr as TcpRepr,
+    SeqNumber as TcpSeqNumber, TcpOption, TimestampOption as TcpTimestampOption,
+    HEADER_LEN as TCP_HEADER_LEN,
 };
 
 #[cfg(feature = "proto-dhcpv4")]
@@ -266,6 +300,17 @@ pub use self::dhcpv4::{
     MAX_DNS_SERVER_COUNT as DHCP_MAX_DNS_SERVER_COUNT, SERVER_PORT as DHCP_SERVER_PORT,
 };
 
+#[cfg(feature = "proto-dhcpv6")]
+pub use self::dhcpv6::{
+    DhcpOption as Dhcpv6Option, DhcpOptionWriter as Dhcpv6OptionWriter,
+    IaAddress as Dhcpv6IaAddress, IaNa as Dhcpv6IaNa, IaPd as Dhcpv6IaPd,
+    IaPrefix as Dhcpv6IaPrefix, MessageType as Dhcpv6MessageType, Packet as Dhcpv6Packet,
+    Repr as Dhcpv6Repr, StatusCode as Dhcpv6StatusCode,
+    ALL_RELAY_AGENTS_AND_SERVERS as DHCPV6_ALL_RELAY_AGENTS_AND_SERVERS,
+    CLIENT_PORT as DHCPV6_CLIENT_PORT, MAX_DNS_SERVER_COUNT as DHCPV6_MAX_DNS_SERVER_COUNT,
+    MAX_DUID_LEN as DHCPV6_MAX_DUID_LEN, SERVER_PORT as DHCPV6_SERVER_PORT,
+};
+
 #[cfg(feature = "proto-dns")]
 pub use self::dns::{
     Flags as DnsFlags, Opcode as DnsOpcode, Packet as DnsPacket, Rcode as DnsRcode,
@@ -6,8 +6,9 @@ use crate::time::Duration;
 use crate::wire::icmpv6::{field, Message, Packet};
 use crate::wire::Ipv6Address;
 use crate::wire::RawHardwareAddress;
-use crate::wire::{NdiscOption, NdiscOptionRepr};
-use crate::wire::{NdiscPrefixInformation, NdiscRedirectedHeader};
+use crate::wire::{NdiscDnsSearchList, NdiscRecursiveDnsServer};
+use crate::wire::{NdiscOption, NdiscOptionRepr, NdiscPref64};
+use crate::wire::{NdiscPrefixInformation, NdiscRedirectedHeader, NdiscSixlowpanContext};
 
 bitflags! {
     #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -204,6 +205,10 @@ pub enum Repr<'a> {
         lladdr: Option<RawHardwareAddress>,
         mtu: Option<u32>,
         prefix_info: Option<NdiscPrefixInformation>,
+        rdnss: Option<NdiscRecursiveDnsServer<'a>>,
+        dnssl: Option<NdiscDnsSearchList<'a>>,
+        sixlowpan_context: Option<NdiscSixlowpanContext>,
+        pref64: Option<NdiscPref64>,
     },
     NeighborSolicit {
         target_addr: Ipv6Address,
@@ -266,11 +271,17 @@ impl<'a> Repr<'a> {
             }
             Message::RouterAdvert => {
                 let (mut lladdr, mut mtu, mut prefix_info) = (None, None, None);
+                let (mut rdnss, mut dnssl, mut sixlowpan_context) = (None, None, None);
+                let mut pref64 = None;
                 foreach_option(packet.payload(), |opt| {
                     match opt {
                         NdiscOptionRepr::SourceLinkLayerAddr(addr) => lladdr = Some(addr),
                         NdiscOptionRepr::Mtu(val) => mtu = Some(val),
                         NdiscOptionRepr::PrefixInformation(info) => prefix_info = Some(info),
+                        NdiscOptionRepr::RecursiveDnsServer(opt) => rdnss = Some(opt),
+                        NdiscOptionRepr::DnsSearchList(opt) => dnssl = Some(opt),
+                        NdiscOptionRepr::SixlowpanContext(opt) => sixlowpan_context = Some(opt),
+                        NdiscOptionRepr::Pref64(opt) => pref64 = Some(opt),
                         _ => {}
                     }
                     Ok(())
@@ -284,6 +295,10 @@ impl<'a> Repr<'a> {
                     lladdr,
                     mtu,
                     prefix_info,
+                    rdnss,
+                    dnssl,
+                    sixlowpan_context,
+                    pref64,
                 })
             }
             Message::NeighborSolicit => {
@@ -320,7 +335,7 @@ impl<'a> Repr<'a> {
 
                 foreach_option(packet.payload(), |opt| {
                     match opt {
-                        NdiscOptionRepr::SourceLinkLayerAddr(addr) => lladdr = Some(addr),
+                        NdiscOptionRepr::TargetLinkLayerAddr(addr) => lladdr = Some(addr),
                         NdiscOptionRepr::RedirectedHeader(rh) => redirected_hdr = Some(rh),
                         _ => {}
                     }
@@ -349,6 +364,10 @@ impl<'a> Repr<'a> {
                 lladdr,
                 mtu,
                 prefix_info,
+                rdnss,
+                dnssl,
+                sixlowpan_context,
+                pref64,
                 ..
             } => {
                 let mut offset = 0;
@@ -361,6 +380,18 @@ impl<'a> Repr<'a> {
                 if let Some(prefix_info) = prefix_info {
                     offset += NdiscOptionRepr::PrefixInformation(prefix_info).buffer_len();
                 }
+                if let Some(rdnss) = rdnss {
+                    offset += NdiscOptionRepr::RecursiveDnsServer(rdnss).buffer_len();
+                }
+                if let Some(dnssl) = dnssl {
+                    offset += NdiscOptionRepr::DnsSearchList(dnssl).buffer_len();
+                }
+                if let Some(context) = sixlowpan_context {
+                    offset += NdiscOptionRepr::SixlowpanContext(context).buffer_len();
+                }
+                if let Some(pref64) = pref64 {
+                    offset += NdiscOptionRepr::Pref64(pref64).buffer_len();
+                }
                 field::RETRANS_TM.end + offset
             }
             &Repr::NeighborSolicit { lladdr, .. } | &Repr::NeighborAdvert { lladdr, .. } => {
@@ -413,6 +444,10 @@ impl<'a> Repr<'a> {
                 lladdr,
                 mtu,
                 prefix_info,
+                rdnss,
+                dnssl,
+                sixlowpan_context,
+                pref64,
             } => {
                 packet.set_msg_type(Message::RouterAdvert);
                 packet.set_msg_code(0);
@@ -437,7 +472,31 @@ impl<'a> Repr<'a> {
                 if let Some(prefix_info) = prefix_info {
                     let mut opt_pkt =
                         NdiscOption::new_unchecked(&mut packet.payload_mut()[offset..]);
-                    NdiscOptionRepr::PrefixInformation(prefix_info).emit(&mut opt_pkt)
+                    NdiscOptionRepr::PrefixInformation(prefix_info).emit(&mut opt_pkt);
+                    offset += NdiscOptionRepr::PrefixInformation(prefix_info).buffer_len();
+                }
+                if let Some(rdnss) = rdnss {
+                    let mut opt_pkt =
+                        NdiscOption::new_unchecked(&mut packet.payload_mut()[offset..]);
+                    NdiscOptionRepr::RecursiveDnsServer(rdnss).emit(&mut opt_pkt);
+                    offset += NdiscOptionRepr::RecursiveDnsServer(rdnss).buffer_len();
+                }
+                if let Some(dnssl) = dnssl {
+                    let mut opt_pkt =
+                        NdiscOption::new_unchecked(&mut packet.payload_mut()[offset..]);
+                    NdiscOptionRepr::DnsSearchList(dnssl).emit(&mut opt_pkt);
+                    offset += NdiscOptionRepr::DnsSearchList(dnssl).buffer_len();
+                }
+                if let Some(context) = sixlowpan_context {
+                    let mut opt_pkt =
+                        NdiscOption::new_unchecked(&mut packet.payload_mut()[offset..]);
+                    NdiscOptionRepr::SixlowpanContext(context).emit(&mut opt_pkt);
+                    offset += NdiscOptionRepr::SixlowpanContext(context).buffer_len();
+                }
+                if let Some(pref64) = pref64 {
+                    let mut opt_pkt =
+                        NdiscOption::new_unchecked(&mut packet.payload_mut()[offset..]);
+                    NdiscOptionRepr::Pref64(pref64).emit(&mut opt_pkt);
                 }
             }
 
@@ -525,6 +584,10 @@ mod test {
             lladdr: Some(EthernetAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]).into()),
             mtu: None,
             prefix_info: None,
+            rdnss: None,
+            dnssl: None,
+            sixlowpan_context: None,
+            pref64: None,
         })
     }
 
@@ -586,4 +649,34 @@ mod test {
         );
         assert_eq!(&*packet.into_inner(), &ROUTER_ADVERT_BYTES[..]);
     }
+
+    #[test]
+    fn test_redirect_repr_roundtrip() {
+        let repr = Icmpv6Repr::Ndisc(Repr::Redirect {
+            target_addr: Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 3),
+            dest_addr: Ipv6Address::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            lladdr: Some(EthernetAddress([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]).into()),
+            redirected_hdr: None,
+        });
+        let mut bytes = vec![0x2a; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        repr.emit(
+            &MOCK_IP_ADDR_1,
+            &MOCK_IP_ADDR_2,
+            &mut packet,
+            &ChecksumCapabilities::default(),
+        );
+        // The link-layer address is carried in a Target Link-Layer Address option.
+        assert_eq!(bytes[field::DEST_ADDR.end], 2);
+        assert_eq!(
+            Icmpv6Repr::parse(
+                &MOCK_IP_ADDR_1,
+                &MOCK_IP_ADDR_2,
+                &Packet::new_unchecked(&bytes[..]),
+                &ChecksumCapabilities::default()
+            )
+            .unwrap(),
+            repr
+        );
+    }
 }
@@ -20,7 +20,15 @@ enum_with_unknown! {
         /// Redirected Header
         RedirectedHeader    = 0x4,
         /// MTU
-        Mtu                 = 0x5
+        Mtu                 = 0x5,
+        /// Recursive DNS Server
+        RecursiveDnsServer  = 0x19,
+        /// DNS Search List
+        DnsSearchList       = 0x1f,
+        /// 6LoWPAN Context
+        SixlowpanContext    = 0x22,
+        /// NAT64 Prefix
+        Pref64              = 0x26
     }
 }
 
@@ -32,6 +40,10 @@ impl fmt::Display for Type {
             Type::PrefixInformation => write!(f, "prefix information"),
             Type::RedirectedHeader => write!(f, "redirected header"),
             Type::Mtu => write!(f, "mtu"),
+            Type::RecursiveDnsServer => write!(f, "recursive dns server"),
+            Type::DnsSearchList => write!(f, "dns search list"),
+            Type::SixlowpanContext => write!(f, "6lowpan context"),
+            Type::Pref64 => write!(f, "pref64"),
             Type::Unknown(id) => write!(f, "{id}"),
         }
     }
@@ -140,6 +152,73 @@ mod field {
 
     //  MTU
     pub const MTU: Field = 4..8;
+
+    // Recursive DNS Server Option fields
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |     Type      |     Length    |           Reserved            |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |                           Lifetime                            |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |                                                               |
+    //  :            Addresses of IPv6 Recursive DNS Servers            :
+    //  |                                                               |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //
+    // DNS Search List Option fields
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |     Type      |     Length    |           Reserved            |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |                           Lifetime                            |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |                                                               |
+    //  :                Domain Names of DNS Search List                :
+    //  |                                                               |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+    // Reserved bits.
+    pub const DNS_RESERVED: Field = 2..4;
+    // Lifetime of the servers or domain names.
+    pub const DNS_LIFETIME: Field = 4..8;
+    // Start of the addresses or domain names.
+    pub const DNS_DATA: usize = 8;
+
+    // 6LoWPAN Context Option fields
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |     Type      |     Length    |Context Length | Res |C|  CID  |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |            Reserved           |         Valid Lifetime        |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  .                                                               .
+    //  .                       Context Prefix                          .
+    //  .                                                               .
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+    // Number of leading bits of the prefix that are valid.
+    pub const CONTEXT_LEN: usize = 2;
+    // Compression flag and Context Identifier.
+    pub const CONTEXT_FLAGS: usize = 3;
+    // Reserved bits.
+    pub const CONTEXT_RESERVED: Field = 4..6;
+    // Valid lifetime, in units of 60 seconds.
+    pub const CONTEXT_LIFETIME: Field = 6..8;
+    // Start of the context prefix.
+    pub const CONTEXT_PREFIX: usize = 8;
+
+    // PREF64 Option fields
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |     Type      |    Length     |     Scaled Lifetime     | PLC |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    //  |                                                               |
+    //  +                                                               +
+    //  |              Highest 96 bits of the Prefix                    |
+    //  +                                                               +
+    //  |                                                               |
+    //  +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+
+    // Lifetime in units of 8 seconds, and Prefix Length Code.
+    pub const PREF64_LIFETIME_PLC: Field = 2..4;
+    // Highest 96 bits of the prefix.
+    pub const PREF64_PREFIX: Field = 4..16;
 }
 
 /// Core getter methods relevant to any type of NDISC option.
@@ -183,9 +262,15 @@ impl<T: AsRef<[u8]>> NdiscOption<T> {
                 Err(Error)
             } else {
                 match self.option_type() {
-                    Type::SourceLinkLayerAddr | Type::TargetLinkLayerAddr | Type::Mtu => Ok(()),
+                    Type::SourceLinkLayerAddr
+                    | Type::TargetLinkLayerAddr
+                    | Type::Mtu
+                    | Type::RecursiveDnsServer
+                    | Type::DnsSearchList => Ok(()),
                     Type::PrefixInformation if data_range.end >= field::PREFIX.end => Ok(()),
                     Type::RedirectedHeader if data_range.end >= field::REDIR_MIN_SZ => Ok(()),
+                    Type::SixlowpanContext if data_range.end >= field::CONTEXT_PREFIX + 8 => Ok(()),
+                    Type::Pref64 if data_range.end >= field::PREF64_PREFIX.end => Ok(()),
                     Type::Unknown(_) => Ok(()),
                     _ => Err(Error),
                 }
@@ -234,6 +319,16 @@ impl<T: AsRef<[u8]>> NdiscOption<T> {
     }
 }
 
+/// Getter methods only relevant for the Recursive DNS Server and DNS Search List options.
+impl<T: AsRef<[u8]>> NdiscOption<T> {
+    /// Return the lifetime of the DNS servers or domain names.
+    #[inline]
+    pub fn dns_lifetime(&self) -> Duration {
+        let data = self.buffer.as_ref();
+        Duration::from_secs(NetworkEndian::read_u32(&data[field::DNS_LIFETIME]) as u64)
+    }
+}
+
 /// Getter methods only relevant for the Prefix Information option.
 impl<T: AsRef<[u8]>> NdiscOption<T> {
     /// Return the prefix length.
@@ -270,6 +365,79 @@ impl<T: AsRef<[u8]>> NdiscOption<T> {
     }
 }
 
+/// Getter methods only relevant for the 6LoWPAN Context option.
+impl<T: AsRef<[u8]>> NdiscOption<T> {
+    /// Return the number of leading bits of the context prefix that are valid.
+    #[inline]
+    pub fn context_len(&self) -> u8 {
+        self.buffer.as_ref()[field::CONTEXT_LEN]
+    }
+
+    /// Return whether the context can be used for compression.
+    #[inline]
+    pub fn context_compression(&self) -> bool {
+        self.buffer.as_ref()[field::CONTEXT_FLAGS] & 0x10 != 0
+    }
+
+    /// Return the Context Identifier.
+    #[inline]
+    pub fn context_id(&self) -> u8 {
+        self.buffer.as_ref()[field::CONTEXT_FLAGS] & 0x0f
+    }
+
+    /// Return the valid lifetime of the context.
+    #[inline]
+    pub fn context_lifetime(&self) -> Duration {
+        let data = self.buffer.as_ref();
+        Duration::from_secs(NetworkEndian::read_u16(&data[field::CONTEXT_LIFETIME]) as u64 * 60)
+    }
+
+    /// Return the context prefix, with the bits past the context length cleared.
+    #[inline]
+    pub fn context_prefix(&self) -> Ipv6Address {
+        let data = self.buffer.as_ref();
+        let len = (self.data_len() as usize * 8 - field::CONTEXT_PREFIX).min(16);
+        let mut bytes = [0; 16];
+        bytes[..len].copy_from_slice(&data[field::CONTEXT_PREFIX..][..len]);
+        Ipv6Address(Ipv6Address(bytes).mask(self.context_len().min(128)))
+    }
+}
+
+/// Getter methods only relevant for the PREF64 option.
+impl<T: AsRef<[u8]>> NdiscOption<T> {
+    /// Return the lifetime of the prefix.
+    #[inline]
+    pub fn pref64_lifetime(&self) -> Duration {
+        let data = self.buffer.as_ref();
+        let scaled = NetworkEndian::read_u16(&data[field::PREF64_LIFETIME_PLC]) >> 3;
+        Duration::from_secs(scaled as u64 * 8)
+    }
+
+    /// Return the length of the prefix, or `None` if the Prefix Length Code is invalid.
+    #[inline]
+    pub fn pref64_prefix_len(&self) -> Option<u8> {
+        let data = self.buffer.as_ref();
+        match data[field::PREF64_LIFETIME_PLC.end - 1] & 0x07 {
+            0 => Some(96),
+            1 => Some(64),
+            2 => Some(56),
+            3 => Some(48),
+            4 => Some(40),
+            5 => Some(32),
+            _ => None,
+        }
+    }
+
+    /// Return the prefix, with the bits past its length cleared.
+    #[inline]
+    pub fn pref64_prefix(&self) -> Ipv6Address {
+        let data = self.buffer.as_ref();
+        let mut bytes = [0; 16];
+        bytes[..12].copy_from_slice(&data[field::PREF64_PREFIX]);
+        Ipv6Address(Ipv6Address(bytes).mask(self.pref64_prefix_len().unwrap_or(96)))
+    }
+}
+
 impl<'a, T: AsRef<[u8]> + ?Sized> NdiscOption<&'a T> {
     /// Return the option data.
     #[inline]
@@ -317,6 +485,23 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> NdiscOption<T> {
     }
 }
 
+/// Setter methods only relevant for the Recursive DNS Server and DNS Search List options.
+impl<T: AsRef<[u8]> + AsMut<[u8]>> NdiscOption<T> {
+    /// Clear the reserved bits.
+    #[inline]
+    pub fn clear_dns_reserved(&mut self) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::DNS_RESERVED], 0);
+    }
+
+    /// Set the lifetime of the DNS servers or domain names.
+    #[inline]
+    pub fn set_dns_lifetime(&mut self, time: Duration) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u32(&mut data[field::DNS_LIFETIME], time.secs() as u32);
+    }
+}
+
 /// Setter methods only relevant for the Prefix Information option.
 impl<T: AsRef<[u8]> + AsMut<[u8]>> NdiscOption<T> {
     /// Set the prefix length.
@@ -360,6 +545,76 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> NdiscOption<T> {
     }
 }
 
+/// Setter methods only relevant for the 6LoWPAN Context option.
+impl<T: AsRef<[u8]> + AsMut<[u8]>> NdiscOption<T> {
+    /// Set the number of leading bits of the context prefix that are valid.
+    #[inline]
+    pub fn set_context_len(&mut self, value: u8) {
+        self.buffer.as_mut()[field::CONTEXT_LEN] = value;
+    }
+
+    /// Set the compression flag and the Context Identifier.
+    #[inline]
+    pub fn set_context_flags(&mut self, compression: bool, context_id: u8) {
+        self.buffer.as_mut()[field::CONTEXT_FLAGS] =
+            if compression { 0x10 } else { 0 } | (context_id & 0x0f);
+    }
+
+    /// Clear the reserved bits.
+    #[inline]
+    pub fn clear_context_reserved(&mut self) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::CONTEXT_RESERVED], 0);
+    }
+
+    /// Set the valid lifetime of the context, rounded down to a whole number of minutes.
+    #[inline]
+    pub fn set_context_lifetime(&mut self, time: Duration) {
+        let data = self.buffer.as_mut();
+        let minutes = (time.secs() / 60).min(0xffff) as u16;
+        NetworkEndian::write_u16(&mut data[field::CONTEXT_LIFETIME], minutes);
+    }
+
+    /// Set the context prefix, filling the option up to its length.
+    #[inline]
+    pub fn set_context_prefix(&mut self, prefix: Ipv6Address) {
+        let len = (self.data_len() as usize * 8 - field::CONTEXT_PREFIX).min(16);
+        let data = self.buffer.as_mut();
+        data[field::CONTEXT_PREFIX..][..len].copy_from_slice(&prefix.as_bytes()[..len]);
+    }
+}
+
+/// Setter methods only relevant for the PREF64 option.
+impl<T: AsRef<[u8]> + AsMut<[u8]>> NdiscOption<T> {
+    /// Set the lifetime and the length of the prefix. The lifetime is rounded up to a
+    /// multiple of 8 seconds, up to 65528 seconds.
+    ///
+    /// # Panics
+    /// This function panics if the prefix length is not 32, 40, 48, 56, 64 or 96.
+    #[inline]
+    pub fn set_pref64_lifetime_and_len(&mut self, time: Duration, prefix_len: u8) {
+        let plc = match prefix_len {
+            96 => 0,
+            64 => 1,
+            56 => 2,
+            48 => 3,
+            40 => 4,
+            32 => 5,
+            _ => panic!("invalid NAT64 prefix length {prefix_len}"),
+        };
+        let scaled = ((time.secs() + 7) / 8).min(0x1fff) as u16;
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::PREF64_LIFETIME_PLC], scaled << 3 | plc);
+    }
+
+    /// Set the prefix.
+    #[inline]
+    pub fn set_pref64_prefix(&mut self, prefix: Ipv6Address) {
+        let data = self.buffer.as_mut();
+        data[field::PREF64_PREFIX].copy_from_slice(&prefix.as_bytes()[..12]);
+    }
+}
+
 /// Setter methods only relevant for the Redirected Header option.
 impl<T: AsRef<[u8]> + AsMut<[u8]>> NdiscOption<T> {
     /// Clear the reserved bits.
@@ -409,6 +664,112 @@ pub struct RedirectedHeader<'a> {
     pub data: &'a [u8],
 }
 
+/// The Recursive DNS Server option, from [RFC 8106 § 5.1].
+///
+/// [RFC 8106 § 5.1]: https://tools.ietf.org/html/rfc8106#section-5.1
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RecursiveDnsServer<'a> {
+    pub lifetime: Duration,
+    /// The addresses of the servers, 16 octets each.
+    pub servers: &'a [u8],
+}
+
+impl<'a> RecursiveDnsServer<'a> {
+    /// Return an iterator over the addresses of the servers.
+    pub fn addresses(&self) -> impl Iterator<Item = Ipv6Address> + 'a {
+        self.servers.chunks_exact(16).map(Ipv6Address::from_bytes)
+    }
+}
+
+/// The DNS Search List option, from [RFC 8106 § 5.2].
+///
+/// [RFC 8106 § 5.2]: https://tools.ietf.org/html/rfc8106#section-5.2
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DnsSearchList<'a> {
+    pub lifetime: Duration,
+    /// The domain names, encoded as in DNS messages without compression.
+    pub domains: &'a [u8],
+}
+
+impl<'a> DnsSearchList<'a> {
+    /// Return an iterator over the domain names, each being an iterator over its labels.
+    pub fn names(&self) -> impl Iterator<Item = impl Iterator<Item = &'a [u8]>> {
+        let mut rest = self.domains;
+        core::iter::from_fn(move || {
+            // The names are followed by zero padding.
+            if rest.iter().all(|&b| b == 0) {
+                return None;
+            }
+            let name = &rest[..name_len(rest)?];
+            rest = &rest[name.len()..];
+
+            let mut labels = name;
+            Some(core::iter::from_fn(move || {
+                let len = *labels.first()? as usize;
+                let label = labels.get(1..1 + len).filter(|l| !l.is_empty())?;
+                labels = &labels[1 + len..];
+                Some(label)
+            }))
+        })
+    }
+}
+
+/// Return the length of the uncompressed domain name at the start of `bytes`,
+/// including the terminating empty label.
+fn name_len(bytes: &[u8]) -> Option<usize> {
+    let mut offset = 0;
+    loop {
+        match *bytes.get(offset)? as usize {
+            0 => return Some(offset + 1),
+            len if len < 64 => offset += 1 + len,
+            _ => return None,
+        }
+    }
+}
+
+/// The 6LoWPAN Context option, from [RFC 6775 § 4.2].
+///
+/// [RFC 6775 § 4.2]: https://tools.ietf.org/html/rfc6775#section-4.2
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SixlowpanContext {
+    /// Number of leading bits of the prefix that are valid, up to 128.
+    pub context_len: u8,
+    /// Whether the context can be used for compression, and not only for decompression.
+    pub compression: bool,
+    /// The Context Identifier, from 0 to 15.
+    pub context_id: u8,
+    /// The valid lifetime, in whole minutes.
+    pub valid_lifetime: Duration,
+    pub prefix: Ipv6Address,
+}
+
+impl SixlowpanContext {
+    /// Return the length of the option, in units of 8 octets.
+    const fn data_len(&self) -> u8 {
+        if self.context_len > 64 {
+            3
+        } else {
+            2
+        }
+    }
+}
+
+/// The PREF64 option, from [RFC 8781 § 4].
+///
+/// [RFC 8781 § 4]: https://tools.ietf.org/html/rfc8781#section-4
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Pref64 {
+    /// The lifetime of the prefix, in multiples of 8 seconds.
+    pub lifetime: Duration,
+    /// The length of the prefix: 32, 40, 48, 56, 64 or 96.
+    pub prefix_len: u8,
+    pub prefix: Ipv6Address,
+}
+
 /// A high-level representation of an NDISC Option.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -418,6 +779,10 @@ pub enum Repr<'a> {
     PrefixInformation(PrefixInformation),
     RedirectedHeader(RedirectedHeader<'a>),
     Mtu(u32),
+    RecursiveDnsServer(RecursiveDnsServer<'a>),
+    DnsSearchList(DnsSearchList<'a>),
+    SixlowpanContext(SixlowpanContext),
+    Pref64(Pref64),
     Unknown {
         type_: u8,
         length: u8,
@@ -484,6 +849,64 @@ impl<'a> Repr<'a> {
                     Err(Error)
                 }
             }
+            Type::RecursiveDnsServer => {
+                // At least one address must be present (RFC 8106 § 5.1).
+                if opt.data_len() >= 3 && opt.data_len() % 2 == 1 {
+                    Ok(Repr::RecursiveDnsServer(RecursiveDnsServer {
+                        lifetime: opt.dns_lifetime(),
+                        servers: &opt.data()[field::DNS_DATA - 2..],
+                    }))
+                } else {
+                    Err(Error)
+                }
+            }
+            Type::DnsSearchList => {
+                if opt.data_len() < 2 {
+                    return Err(Error);
+                }
+                let domains = &opt.data()[field::DNS_DATA - 2..];
+
+                // Check that the names are well-formed, and only followed by padding.
+                let mut rest = domains;
+                while !rest.iter().all(|&b| b == 0) {
+                    let len = name_len(rest).ok_or(Error)?;
+                    if len == 1 {
+                        return Err(Error);
+                    }
+                    rest = &rest[len..];
+                }
+
+                Ok(Repr::DnsSearchList(DnsSearchList {
+                    lifetime: opt.dns_lifetime(),
+                    domains,
+                }))
+            }
+            Type::SixlowpanContext => {
+                // Prefixes longer than 64 bits need the longer option.
+                let context_len = opt.context_len();
+                if context_len <= 128 && (opt.data_len() == 3 || context_len <= 64) {
+                    Ok(Repr::SixlowpanContext(SixlowpanContext {
+                        context_len,
+                        compression: opt.context_compression(),
+                        context_id: opt.context_id(),
+                        valid_lifetime: opt.context_lifetime(),
+                        prefix: opt.context_prefix(),
+                    }))
+                } else {
+                    Err(Error)
+                }
+            }
+            Type::Pref64 => {
+                if opt.data_len() != 2 {
+                    return Err(Error);
+                }
+                let prefix_len = opt.pref64_prefix_len().ok_or(Error)?;
+                Ok(Repr::Pref64(Pref64 {
+                    lifetime: opt.pref64_lifetime(),
+                    prefix_len,
+                    prefix: opt.pref64_prefix(),
+                }))
+            }
             Type::Unknown(id) => {
                 // A length of 0 is invalid.
                 if opt.data_len() != 0 {
@@ -512,6 +935,14 @@ impl<'a> Repr<'a> {
                 (8 + header.buffer_len() + data.len() + 7) / 8 * 8
             }
             &Repr::Mtu(_) => field::MTU.end,
+            &Repr::RecursiveDnsServer(RecursiveDnsServer { servers, .. }) => {
+                field::DNS_DATA + servers.len()
+            }
+            &Repr::DnsSearchList(DnsSearchList { domains, .. }) => {
+                (field::DNS_DATA + domains.len() + 7) / 8 * 8
+            }
+            &Repr::SixlowpanContext(context) => context.data_len() as usize * 8,
+            &Repr::Pref64(_) => field::PREF64_PREFIX.end,
             &Repr::Unknown { length, .. } => field::DATA(length).end,
         }
     }
@@ -566,6 +997,41 @@ impl<'a> Repr<'a> {
                 opt.set_data_len(1);
                 opt.set_mtu(mtu);
             }
+            Repr::RecursiveDnsServer(RecursiveDnsServer { lifetime, servers }) => {
+                opt.set_option_type(Type::RecursiveDnsServer);
+                opt.set_data_len(((field::DNS_DATA + servers.len()) / 8) as u8);
+                opt.clear_dns_reserved();
+                opt.set_dns_lifetime(lifetime);
+                opt.data_mut()[field::DNS_DATA - 2..].copy_from_slice(servers);
+            }
+            Repr::DnsSearchList(DnsSearchList { lifetime, domains }) => {
+                opt.set_option_type(Type::DnsSearchList);
+                opt.set_data_len(((field::DNS_DATA + domains.len() + 7) / 8) as u8);
+                opt.clear_dns_reserved();
+                opt.set_dns_lifetime(lifetime);
+                let data = &mut opt.data_mut()[field::DNS_DATA - 2..];
+                data[..domains.len()].copy_from_slice(domains);
+                data[domains.len()..].fill(0);
+            }
+            Repr::SixlowpanContext(context) => {
+                opt.set_option_type(Type::SixlowpanContext);
+                opt.set_data_len(context.data_len());
+                opt.set_context_len(context.context_len);
+                opt.set_context_flags(context.compression, context.context_id);
+                opt.clear_context_reserved();
+                opt.set_context_lifetime(context.valid_lifetime);
+                opt.set_context_prefix(context.prefix);
+            }
+            Repr::Pref64(Pref64 {
+                lifetime,
+                prefix_len,
+                prefix,
+            }) => {
+                opt.set_option_type(Type::Pref64);
+                opt.set_data_len(2);
+                opt.set_pref64_lifetime_and_len(lifetime, prefix_len);
+                opt.set_pref64_prefix(prefix);
+            }
             Repr::Unknown {
                 type_: id,
                 length,
@@ -600,6 +1066,34 @@ impl<'a> fmt::Display for Repr<'a> {
             Repr::Mtu(mtu) => {
                 write!(f, "MTU mtu={mtu}")
             }
+            Repr::RecursiveDnsServer(RecursiveDnsServer { lifetime, servers }) => {
+                write!(
+                    f,
+                    "RecursiveDnsServer lifetime={lifetime} servers={}",
+                    servers.len() / 16
+                )
+            }
+            Repr::DnsSearchList(DnsSearchList { lifetime, .. }) => {
+                write!(f, "DnsSearchList lifetime={lifetime}")
+            }
+            Repr::SixlowpanContext(SixlowpanContext {
+                context_len,
+                context_id,
+                prefix,
+                ..
+            }) => {
+                write!(
+                    f,
+                    "SixlowpanContext cid={context_id} prefix={prefix}/{context_len}"
+                )
+            }
+            Repr::Pref64(Pref64 {
+                lifetime,
+                prefix_len,
+                prefix,
+            }) => {
+                write!(f, "Pref64 lifetime={lifetime} prefix={prefix}/{prefix_len}")
+            }
             Repr::Unknown {
                 type_: id, length, ..
             } => {
@@ -633,7 +1127,10 @@ impl<T: AsRef<[u8]>> PrettyPrint for NdiscOption<T> {
 #[cfg(test)]
 mod test {
     use super::Error;
-    use super::{NdiscOption, PrefixInfoFlags, PrefixInformation, Repr, Type};
+    use super::{
+        DnsSearchList, NdiscOption, Pref64, PrefixInfoFlags, PrefixInformation, RecursiveDnsServer,
+        Repr, SixlowpanContext, Type,
+    };
     use crate::time::Duration;
     use crate::wire::Ipv6Address;
 
@@ -765,4 +1262,163 @@ mod test {
             Ok(Repr::Mtu(1500))
         );
     }
+
+    static RDNSS_OPT_BYTES: [u8; 24] = [
+        0x19, 0x03, 0x00, 0x00, 0x00, 0x00, 0x0e, 0x10, 0xfd, 0xbe, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x53,
+    ];
+
+    #[test]
+    fn test_repr_parse_rdnss() {
+        let repr = Repr::parse(&NdiscOption::new_unchecked(&RDNSS_OPT_BYTES)).unwrap();
+        let Repr::RecursiveDnsServer(rdnss) = repr else {
+            panic!("unexpected option {repr:?}");
+        };
+        assert_eq!(rdnss.lifetime, Duration::from_secs(3600));
+        assert_eq!(
+            rdnss.addresses().collect::<std::vec::Vec<_>>(),
+            [Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0x53)]
+        );
+
+        // Options without any address are invalid.
+        let bytes = [0x19, 0x01, 0x00, 0x00, 0x00, 0x00, 0x0e, 0x10];
+        assert_eq!(Repr::parse(&NdiscOption::new_unchecked(&bytes)), Err(Error));
+    }
+
+    #[test]
+    fn test_repr_emit_rdnss() {
+        let mut bytes = [0x2a; 24];
+        let server = Ipv6Address::new(0xfdbe, 0, 0, 0, 0, 0, 0, 0x53);
+        let repr = Repr::RecursiveDnsServer(RecursiveDnsServer {
+            lifetime: Duration::from_secs(3600),
+            servers: server.as_bytes(),
+        });
+        assert_eq!(repr.buffer_len(), 24);
+        repr.emit(&mut NdiscOption::new_unchecked(&mut bytes));
+        assert_eq!(bytes, RDNSS_OPT_BYTES);
+    }
+
+    static DNSSL_OPT_BYTES: [u8; 32] = [
+        0x1f, 0x04, 0x00, 0x00, 0x00, 0x00, 0x0e, 0x10, 0x07, b'e', b'x', b'a', b'm', b'p', b'l',
+        b'e', 0x03, b'c', b'o', b'm', 0x00, 0x03, b'l', b'a', b'n', 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00,
+    ];
+
+    #[test]
+    fn test_repr_parse_dnssl() {
+        let repr = Repr::parse(&NdiscOption::new_unchecked(&DNSSL_OPT_BYTES)).unwrap();
+        let Repr::DnsSearchList(dnssl) = repr else {
+            panic!("unexpected option {repr:?}");
+        };
+        assert_eq!(dnssl.lifetime, Duration::from_secs(3600));
+        let names: std::vec::Vec<std::vec::Vec<&[u8]>> =
+            dnssl.names().map(|name| name.collect()).collect();
+        assert_eq!(
+            names,
+            [vec![&b"example"[..], &b"com"[..]], vec![&b"lan"[..]]]
+        );
+
+        // Truncated names are invalid.
+        let mut bytes = DNSSL_OPT_BYTES;
+        bytes[21] = 0x0a;
+        assert_eq!(Repr::parse(&NdiscOption::new_unchecked(&bytes)), Err(Error));
+    }
+
+    #[test]
+    fn test_repr_emit_dnssl() {
+        let mut bytes = [0x2a; 32];
+        let repr = Repr::DnsSearchList(DnsSearchList {
+            lifetime: Duration::from_secs(3600),
+            domains: b"\x07example\x03com\x00\x03lan\x00",
+        });
+        assert_eq!(repr.buffer_len(), 32);
+        repr.emit(&mut NdiscOption::new_unchecked(&mut bytes));
+        assert_eq!(bytes, DNSSL_OPT_BYTES);
+    }
+
+    static CONTEXT_OPT_BYTES: [u8; 16] = [
+        0x22, 0x02, 0x30, 0x11, 0x00, 0x00, 0x00, 0x3c, 0xfd, 0x00, 0x12, 0x34, 0x56, 0x78, 0x00,
+        0x00,
+    ];
+
+    #[test]
+    fn test_repr_parse_sixlowpan_context() {
+        assert_eq!(
+            Repr::parse(&NdiscOption::new_unchecked(&CONTEXT_OPT_BYTES)),
+            Ok(Repr::SixlowpanContext(SixlowpanContext {
+                context_len: 48,
+                compression: true,
+                context_id: 1,
+                valid_lifetime: Duration::from_secs(3600),
+                prefix: Ipv6Address::new(0xfd00, 0x1234, 0x5678, 0, 0, 0, 0, 0),
+            }))
+        );
+
+        // Prefixes longer than 64 bits do not fit in the short option.
+        let mut bytes = CONTEXT_OPT_BYTES;
+        bytes[2] = 72;
+        assert_eq!(Repr::parse(&NdiscOption::new_unchecked(&bytes)), Err(Error));
+    }
+
+    #[test]
+    fn test_repr_emit_sixlowpan_context() {
+        let mut bytes = [0x2a; 16];
+        let repr = Repr::SixlowpanContext(SixlowpanContext {
+            context_len: 48,
+            compression: true,
+            context_id: 1,
+            valid_lifetime: Duration::from_secs(3600),
+            prefix: Ipv6Address::new(0xfd00, 0x1234, 0x5678, 0, 0, 0, 0, 0),
+        });
+        assert_eq!(repr.buffer_len(), 16);
+        repr.emit(&mut NdiscOption::new_unchecked(&mut bytes));
+        assert_eq!(bytes, CONTEXT_OPT_BYTES);
+
+        let mut bytes = [0x2a; 24];
+        let repr = Repr::SixlowpanContext(SixlowpanContext {
+            context_len: 96,
+            compression: false,
+            context_id: 15,
+            valid_lifetime: Duration::from_secs(60),
+            prefix: Ipv6Address::new(0xfd00, 0, 0, 0, 0x1234, 0x5678, 0, 0),
+        });
+        assert_eq!(repr.buffer_len(), 24);
+        repr.emit(&mut NdiscOption::new_unchecked(&mut bytes));
+        assert_eq!(Repr::parse(&NdiscOption::new_unchecked(&bytes)), Ok(repr));
+    }
+
+    static PREF64_OPT_BYTES: [u8; 16] = [
+        0x26, 0x02, 0x07, 0x09, 0x00, 0x64, 0xff, 0x9b, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00,
+    ];
+
+    #[test]
+    fn test_repr_parse_pref64() {
+        assert_eq!(
+            Repr::parse(&NdiscOption::new_unchecked(&PREF64_OPT_BYTES)),
+            Ok(Repr::Pref64(Pref64 {
+                lifetime: Duration::from_secs(1800),
+                prefix_len: 64,
+                prefix: Ipv6Address::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0),
+            }))
+        );
+
+        // Prefix Length Codes past 5 are invalid.
+        let mut bytes = PREF64_OPT_BYTES;
+        bytes[3] = 0x0e;
+        assert_eq!(Repr::parse(&NdiscOption::new_unchecked(&bytes)), Err(Error));
+    }
+
+    #[test]
+    fn test_repr_emit_pref64() {
+        let mut bytes = [0x2a; 16];
+        let repr = Repr::Pref64(Pref64 {
+            lifetime: Duration::from_secs(1800),
+            prefix_len: 64,
+            prefix: Ipv6Address::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0),
+        });
+        assert_eq!(repr.buffer_len(), 16);
+        repr.emit(&mut NdiscOption::new_unchecked(&mut bytes));
+        assert_eq!(bytes, PREF64_OPT_BYTES);
+    }
 }
@@ -915,7 +915,7 @@ pub mod options {
         pub const ROUTE_INFO_PREFIX_LENGTH: usize = 2;
         pub const ROUTE_INFO_RESERVED: usize = 3;
         pub const ROUTE_INFO_PREFERENCE: usize = 3;
-        pub const ROUTE_INFO_LIFETIME: Field = 4..9;
+        pub const ROUTE_INFO_LIFETIME: Field = 4..8;
 
         // DODAG Configuration fields.
         pub const DODAG_CONF_FLAGS: usize = 2;
@@ -976,11 +976,43 @@ pub mod options {
 
         #[inline]
         pub fn new_checked(buffer: T) -> Result<Self> {
-            if buffer.as_ref().is_empty() {
+            let packet = Self::new_unchecked(buffer);
+            packet.check_len()?;
+            Ok(packet)
+        }
+
+        /// Ensure that no accessor method will panic if called.
+        /// Returns `Err(Error)` if the buffer is too short for the option.
+        pub fn check_len(&self) -> Result<()> {
+            let data = self.buffer.as_ref();
+            if data.is_empty() {
+                return Err(Error);
+            }
+            if self.option_type() == OptionType::Pad1 {
+                return Ok(());
+            }
+            if data.len() < field::PADN.start {
                 return Err(Error);
             }
 
-            Ok(Packet { buffer })
+            let len = field::PADN.start + self.option_length() as usize;
+            let min_len = match self.option_type() {
+                OptionType::RouteInformation => field::ROUTE_INFO_LIFETIME.end,
+                OptionType::DodagConfiguration => field::DODAG_CONF_LIFETIME_UNIT.end,
+                OptionType::RplTarget => field::RPL_TARGET_PREFIX_LENGTH + 1,
+                OptionType::TransitInformation if len > field::TRANSIT_INFO_PATH_LIFETIME + 1 => {
+                    field::TRANSIT_INFO_PARENT_ADDRESS.end
+                }
+                OptionType::TransitInformation => field::TRANSIT_INFO_PATH_LIFETIME + 1,
+                OptionType::SolicitedInformation => field::SOLICITED_INFO_VERSION_NUMBER + 1,
+                OptionType::PrefixInformation => field::PREFIX_INFO_PREFIX.end,
+                OptionType::RplTargetDescriptor => field::TARGET_DESCRIPTOR.end,
+                _ => field::PADN.start,
+            };
+            if len < min_len || data.len() < len {
+                return Err(Error);
+            }
+            Ok(())
         }
 
         /// Return the type field.
@@ -1081,7 +1113,7 @@ pub mod options {
         pub fn prefix(&self) -> &'p [u8] {
             let option_len = self.option_length();
             &self.buffer.as_ref()[field::ROUTE_INFO_LIFETIME.end..]
-                [..option_len as usize - field::ROUTE_INFO_LIFETIME.end]
+                [..field::PADN.start + option_len as usize - field::ROUTE_INFO_LIFETIME.end]
         }
     }
 
@@ -2030,7 +2062,7 @@ pub mod options {
             match packet.option_type() {
                 OptionType::Pad1 => Ok(Repr::Pad1),
                 OptionType::PadN => Ok(Repr::PadN(packet.option_length())),
-                OptionType::DagMetricContainer => todo!(),
+                OptionType::DagMetricContainer => Err(Error),
                 OptionType::RouteInformation => Ok(Repr::RouteInformation {
                     prefix_length: packet.prefix_length(),
                     preference: packet.route_preference(),
@@ -2049,10 +2081,17 @@ pub mod options {
                     default_lifetime: packet.default_lifetime(),
                     lifetime_unit: packet.lifetime_unit(),
                 }),
-                OptionType::RplTarget => Ok(Repr::RplTarget {
-                    prefix_length: packet.target_prefix_length(),
-                    prefix: crate::wire::Ipv6Address::from_bytes(packet.target_prefix()),
-                }),
+                OptionType::RplTarget => {
+                    // The prefix is only as long as needed, the rest of it is zero.
+                    let target_prefix = packet.target_prefix();
+                    let mut prefix = crate::wire::Ipv6Address::UNSPECIFIED;
+                    let len = target_prefix.len().min(prefix.0.len());
+                    prefix.0[..len].copy_from_slice(&target_prefix[..len]);
+                    Ok(Repr::RplTarget {
+                        prefix_length: packet.target_prefix_length(),
+                        prefix,
+                    })
+                }
                 OptionType::TransitInformation => Ok(Repr::TransitInformation {
                     external: packet.is_external(),
                     path_control: packet.path_control(),
@@ -2222,6 +2261,56 @@ pub mod options {
             }
         }
     }
+
+    /// An iterator over the options of a RPL control message.
+    ///
+    /// The options that are not supported are skipped, as RFC 6550 § 6.7.1 requires.
+    #[derive(Debug)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct OptionsIterator<'a> {
+        data: &'a [u8],
+        hit_error: bool,
+    }
+
+    impl<'a> OptionsIterator<'a> {
+        /// Create a new `OptionsIterator` over the options of a RPL control message.
+        pub fn new(data: &'a [u8]) -> OptionsIterator<'a> {
+            OptionsIterator {
+                data,
+                hit_error: false,
+            }
+        }
+    }
+
+    impl<'a> Iterator for OptionsIterator<'a> {
+        type Item = Result<Repr<'a>>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            while !self.data.is_empty() && !self.hit_error {
+                let packet = match Packet::new_checked(self.data) {
+                    Ok(packet) => packet,
+                    Err(e) => {
+                        self.hit_error = true;
+                        return Some(Err(e));
+                    }
+                };
+                let len = match packet.option_type() {
+                    OptionType::Pad1 => 1,
+                    _ => field::PADN.start + packet.option_length() as usize,
+                };
+                self.data = &self.data[len..];
+
+                match packet.option_type() {
+                    OptionType::DagMetricContainer | OptionType::Unknown(_) => continue,
+                    _ => {}
+                }
+                let repr = Repr::parse(&Packet::new_unchecked(&packet.buffer[..len]));
+                self.hit_error = repr.is_err();
+                return Some(repr);
+            }
+            None
+        }
+    }
 }
 
 pub mod data {
@@ -2683,4 +2772,44 @@ mod tests {
 
         assert_eq!(&data[..], &buffer[..]);
     }
+
+    #[test]
+    fn options_iterator() {
+        let options = [
+            // Pad1.
+            0x00, //
+            // DAG Metric Container, which is not supported.
+            0x02, 0x02, 0x07, 0x00, //
+            // RPL Target, with a 64-bit prefix.
+            0x05, 0x0a, 0x00, 0x40, 0xfd, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, //
+            // Unknown option.
+            0x42, 0x01, 0x00, //
+            // Transit Information, without a parent address.
+            0x06, 0x04, 0x00, 0x00, 0x01, 0x1e, //
+            // Truncated RPL Target.
+            0x05, 0x12, 0x00, 0x80,
+        ];
+
+        let mut iter = options::OptionsIterator::new(&options);
+        assert_eq!(iter.next(), Some(Ok(OptionRepr::Pad1)));
+        assert_eq!(
+            iter.next(),
+            Some(Ok(OptionRepr::RplTarget {
+                prefix_length: 64,
+                prefix: Address::new(0xfd00, 0, 0, 1, 0, 0, 0, 0),
+            }))
+        );
+        assert_eq!(
+            iter.next(),
+            Some(Ok(OptionRepr::TransitInformation {
+                external: false,
+                path_control: 0,
+                path_sequence: 1,
+                path_lifetime: 30,
+                parent_address: None,
+            }))
+        );
+        assert_eq!(iter.next(), Some(Err(Error)));
+        assert_eq!(iter.next(), None);
+    }
 }
@@ -166,10 +166,12 @@ impl<'a> UnresolvedAddress<'a> {
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum SixlowpanPacket {
+    MeshHeader,
     FragmentHeader,
     IphcHeader,
 }
 
+const DISPATCH_MESH_HEADER: u8 = 0b10;
 const DISPATCH_FIRST_FRAGMENT_HEADER: u8 = 0b11000;
 const DISPATCH_FRAGMENT_HEADER: u8 = 0b11100;
 const DISPATCH_IPHC_HEADER: u8 = 0b011;
@@ -178,11 +180,11 @@ const DISPATCH_EXT_HEADER: u8 = 0b1110;
 
 impl SixlowpanPacket {
     /// Returns the type of the 6LoWPAN header.
-    /// This can either be a fragment header or an IPHC header.
+    /// This can either be a mesh header, a fragment header or an IPHC header.
     ///
     /// # Errors
-    /// Returns `[Error::Unrecognized]` when neither the Fragment Header dispatch or the IPHC
-    /// dispatch is recognized.
+    /// Returns `[Error::Unrecognized]` when neither the Mesh Header dispatch, the Fragment Header
+    /// dispatch or the IPHC dispatch is recognized.
     pub fn dispatch(buffer: impl AsRef<[u8]>) -> Result<Self> {
         let raw = buffer.as_ref();
 
@@ -190,7 +192,10 @@ impl SixlowpanPacket {
             return Err(Error);
         }
 
-        if raw[0] >> 3 == DISPATCH_FIRST_FRAGMENT_HEADER || raw[0] >> 3 == DISPATCH_FRAGMENT_HEADER
+        if raw[0] >> 6 == DISPATCH_MESH_HEADER {
+            Ok(Self::MeshHeader)
+        } else if raw[0] >> 3 == DISPATCH_FIRST_FRAGMENT_HEADER
+            || raw[0] >> 3 == DISPATCH_FRAGMENT_HEADER
         {
             Ok(Self::FragmentHeader)
         } else if raw[0] >> 5 == DISPATCH_IPHC_HEADER {
@@ -201,6 +206,283 @@ impl SixlowpanPacket {
     }
 }
 
+pub mod mesh {
+    //! Implementation of the mesh addressing header from [RFC 4944 § 5.2], with the Deep Hops
+    //! Left extension from [RFC 8025].
+    //!
+    //! [RFC 4944 § 5.2]: https://datatracker.ietf.org/doc/html/rfc4944#section-5.2
+    //! [RFC 8025]: https://datatracker.ietf.org/doc/html/rfc8025
+
+    use super::DISPATCH_MESH_HEADER;
+    use crate::wire::Ieee802154Address;
+    use crate::wire::{Error, Result};
+
+    /// A read/write wrapper around a 6LoWPAN Mesh header.
+    /// [RFC 4944 § 5.2] specifies the format of the header:
+    /// ```txt
+    ///                      1                   2                   3
+    ///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    /// |1 0|V|F|HopsLft| originator address, final address
+    /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    /// ```
+    ///
+    /// The V and F flags are set when the originator and final addresses are short addresses.
+    /// A Hops Left value of 15 means that the actual value follows in the next octet.
+    ///
+    /// [RFC 4944 § 5.2]: https://datatracker.ietf.org/doc/html/rfc4944#section-5.2
+    #[derive(Debug, Clone)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct Packet<T: AsRef<[u8]>> {
+        buffer: T,
+    }
+
+    /// The Hops Left value announcing a Deep Hops Left octet.
+    const DEEP_HOPS_LEFT: u8 = 0x0f;
+
+    mod field {
+        pub const DISPATCH: usize = 0;
+        pub const DEEP_HOPS_LEFT: usize = 1;
+    }
+
+    impl<T: AsRef<[u8]>> Packet<T> {
+        /// Input a raw octet buffer with a 6LoWPAN Mesh header structure.
+        pub const fn new_unchecked(buffer: T) -> Self {
+            Self { buffer }
+        }
+
+        /// Shorthand for a combination of [new_unchecked] and [check_len].
+        ///
+        /// [new_unchecked]: #method.new_unchecked
+        /// [check_len]: #method.check_len
+        pub fn new_checked(buffer: T) -> Result<Self> {
+            let packet = Self::new_unchecked(buffer);
+            packet.check_len()?;
+            Ok(packet)
+        }
+
+        /// Ensure that no accessor method will panic if called.
+        /// Returns `Err(Error)` if the buffer is too short or is not a Mesh header.
+        pub fn check_len(&self) -> Result<()> {
+            let buffer = self.buffer.as_ref();
+            if buffer.is_empty() || buffer[field::DISPATCH] >> 6 != DISPATCH_MESH_HEADER {
+                return Err(Error);
+            }
+
+            if buffer.len() < self.header_len() {
+                return Err(Error);
+            }
+
+            Ok(())
+        }
+
+        /// Consumes the frame, returning the underlying buffer.
+        pub fn into_inner(self) -> T {
+            self.buffer
+        }
+
+        /// Return the number of hops the packet can still be forwarded over.
+        pub fn hops_left(&self) -> u8 {
+            let raw = self.buffer.as_ref();
+            match raw[field::DISPATCH] & 0x0f {
+                DEEP_HOPS_LEFT => raw[field::DEEP_HOPS_LEFT],
+                hops_left => hops_left,
+            }
+        }
+
+        /// Return the link-layer address of the originator of the packet.
+        pub fn originator_addr(&self) -> Ieee802154Address {
+            let start = self.addresses_start();
+            let len = self.originator_addr_len();
+            Ieee802154Address::from_bytes(&self.buffer.as_ref()[start..][..len])
+        }
+
+        /// Return the link-layer address of the final destination of the packet.
+        pub fn final_addr(&self) -> Ieee802154Address {
+            let start = self.addresses_start() + self.originator_addr_len();
+            let len = self.final_addr_len();
+            Ieee802154Address::from_bytes(&self.buffer.as_ref()[start..][..len])
+        }
+
+        /// Return the length of the header.
+        pub fn header_len(&self) -> usize {
+            self.addresses_start() + self.originator_addr_len() + self.final_addr_len()
+        }
+
+        fn addresses_start(&self) -> usize {
+            match self.buffer.as_ref()[field::DISPATCH] & 0x0f {
+                DEEP_HOPS_LEFT => 2,
+                _ => 1,
+            }
+        }
+
+        fn originator_addr_len(&self) -> usize {
+            match self.buffer.as_ref()[field::DISPATCH] & 0b0010_0000 {
+                0 => 8,
+                _ => 2,
+            }
+        }
+
+        fn final_addr_len(&self) -> usize {
+            match self.buffer.as_ref()[field::DISPATCH] & 0b0001_0000 {
+                0 => 8,
+                _ => 2,
+            }
+        }
+    }
+
+    impl<'a, T: AsRef<[u8]> + ?Sized> Packet<&'a T> {
+        /// Return the payload.
+        pub fn payload(&self) -> &'a [u8] {
+            &self.buffer.as_ref()[self.header_len()..]
+        }
+    }
+
+    impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+        /// Return a mutable pointer to the payload.
+        pub fn payload_mut(&mut self) -> &mut [u8] {
+            let len = self.header_len();
+            &mut self.buffer.as_mut()[len..]
+        }
+    }
+
+    /// A high-level representation of a 6LoWPAN Mesh header.
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    pub struct Repr {
+        pub hops_left: u8,
+        pub originator_addr: Ieee802154Address,
+        pub final_addr: Ieee802154Address,
+    }
+
+    impl core::fmt::Display for Repr {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(
+                f,
+                "Mesh orig={} final={} hops-left={}",
+                self.originator_addr, self.final_addr, self.hops_left
+            )
+        }
+    }
+
+    impl Repr {
+        /// Parse a 6LoWPAN Mesh header.
+        pub fn parse<T: AsRef<[u8]> + ?Sized>(packet: &Packet<&T>) -> Result<Self> {
+            packet.check_len()?;
+
+            Ok(Self {
+                hops_left: packet.hops_left(),
+                originator_addr: packet.originator_addr(),
+                final_addr: packet.final_addr(),
+            })
+        }
+
+        /// Returns the length of the Mesh header.
+        pub fn buffer_len(&self) -> usize {
+            let hops_left_len = if self.hops_left >= DEEP_HOPS_LEFT {
+                2
+            } else {
+                1
+            };
+            hops_left_len + self.originator_addr.as_bytes().len() + self.final_addr.as_bytes().len()
+        }
+
+        /// Emit a high-level representation into a 6LoWPAN Mesh header.
+        ///
+        /// # Panics
+        /// This function panics if one of the addresses is absent.
+        pub fn emit<T: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut Packet<T>) {
+            let short = |addr: &Ieee802154Address| match addr {
+                Ieee802154Address::Short(_) => 1,
+                Ieee802154Address::Extended(_) => 0,
+                Ieee802154Address::Absent => panic!("absent mesh address"),
+            };
+
+            let raw = packet.buffer.as_mut();
+            raw[field::DISPATCH] = (DISPATCH_MESH_HEADER << 6)
+                | (short(&self.originator_addr) << 5)
+                | (short(&self.final_addr) << 4);
+
+            let mut idx = 1;
+            if self.hops_left >= DEEP_HOPS_LEFT {
+                raw[field::DISPATCH] |= DEEP_HOPS_LEFT;
+                raw[field::DEEP_HOPS_LEFT] = self.hops_left;
+                idx += 1;
+            } else {
+                raw[field::DISPATCH] |= self.hops_left;
+            }
+
+            let originator = self.originator_addr.as_bytes();
+            raw[idx..][..originator.len()].copy_from_slice(originator);
+            idx += originator.len();
+            let final_addr = self.final_addr.as_bytes();
+            raw[idx..][..final_addr.len()].copy_from_slice(final_addr);
+        }
+    }
+
+    #[cfg(test)]
+    mod test {
+        use super::*;
+
+        #[test]
+        fn mesh_fields() {
+            let bytes = [
+                0xa5, // Mesh dispatch, short originator, extended final, 5 hops left
+                0x12, 0x34, // Originator
+                0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, // Final
+                0x7a, 0x33, // Payload
+            ];
+
+            let packet = Packet::new_checked(&bytes[..]).unwrap();
+            assert_eq!(packet.hops_left(), 5);
+            assert_eq!(
+                packet.originator_addr(),
+                Ieee802154Address::Short([0x12, 0x34])
+            );
+            assert_eq!(
+                packet.final_addr(),
+                Ieee802154Address::Extended([0x02, 0, 0, 0, 0, 0, 0, 0x01])
+            );
+            assert_eq!(packet.header_len(), 11);
+            assert_eq!(packet.payload(), &[0x7a, 0x33]);
+
+            // Truncated addresses are invalid.
+            assert_eq!(Packet::new_checked(&bytes[..10]).unwrap_err(), Error);
+        }
+
+        #[test]
+        fn mesh_emit() {
+            let repr = Repr {
+                hops_left: 5,
+                originator_addr: Ieee802154Address::Short([0x12, 0x34]),
+                final_addr: Ieee802154Address::Extended([0x02, 0, 0, 0, 0, 0, 0, 0x01]),
+            };
+            let mut bytes = [0u8; 11];
+            assert_eq!(repr.buffer_len(), bytes.len());
+            repr.emit(&mut Packet::new_unchecked(&mut bytes[..]));
+            assert_eq!(
+                bytes,
+                [0xa5, 0x12, 0x34, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]
+            );
+            assert_eq!(Repr::parse(&Packet::new_unchecked(&bytes[..])), Ok(repr));
+        }
+
+        #[test]
+        fn mesh_deep_hops_left() {
+            let repr = Repr {
+                hops_left: 42,
+                originator_addr: Ieee802154Address::Short([0x12, 0x34]),
+                final_addr: Ieee802154Address::Short([0x56, 0x78]),
+            };
+            let mut bytes = [0u8; 6];
+            assert_eq!(repr.buffer_len(), bytes.len());
+            repr.emit(&mut Packet::new_unchecked(&mut bytes[..]));
+            assert_eq!(bytes, [0xbf, 42, 0x12, 0x34, 0x56, 0x78]);
+            assert_eq!(Repr::parse(&Packet::new_unchecked(&bytes[..])), Ok(repr));
+        }
+    }
+}
+
 pub mod frag {
     //! Implementation of the fragment headers from [RFC 4944 § 5.3].
     //!
@@ -663,7 +945,8 @@ pub mod iphc {
             }
         }
 
-        /// Return the Source Context Identifier.
+        /// Return the Source Context Identifier, if the CID extension is present.
+        /// Context 0 is used for addresses compressed with a context otherwise.
         pub fn src_context_id(&self) -> Option<u8> {
             if self.cid_field() == 1 {
                 let data = self.buffer.as_ref();
@@ -673,7 +956,8 @@ pub mod iphc {
             }
         }
 
-        /// Return the Destination Context Identifier.
+        /// Return the Destination Context Identifier, if the CID extension is present.
+        /// Context 0 is used for addresses compressed with a context otherwise.
         pub fn dst_context_id(&self) -> Option<u8> {
             if self.cid_field() == 1 {
                 let data = self.buffer.as_ref();
@@ -751,34 +1035,25 @@ pub mod iphc {
                     AddressMode::Unspecified,
                 ))),
                 (1, 0b01) => {
-                    if let Some(id) = self.src_context_id() {
-                        Ok(UnresolvedAddress::WithContext((
-                            id as usize,
-                            AddressMode::InLine64bits(&data[start..][..8]),
-                        )))
-                    } else {
-                        Err(Error)
-                    }
+                    let id = self.src_context_id().unwrap_or(0);
+                    Ok(UnresolvedAddress::WithContext((
+                        id as usize,
+                        AddressMode::InLine64bits(&data[start..][..8]),
+                    )))
                 }
                 (1, 0b10) => {
-                    if let Some(id) = self.src_context_id() {
-                        Ok(UnresolvedAddress::WithContext((
-                            id as usize,
-                            AddressMode::InLine16bits(&data[start..][..2]),
-                        )))
-                    } else {
-                        Err(Error)
-                    }
+                    let id = self.src_context_id().unwrap_or(0);
+                    Ok(UnresolvedAddress::WithContext((
+                        id as usize,
+                        AddressMode::InLine16bits(&data[start..][..2]),
+                    )))
                 }
                 (1, 0b11) => {
-                    if let Some(id) = self.src_context_id() {
-                        Ok(UnresolvedAddress::WithContext((
-                            id as usize,
-                            AddressMode::FullyElided,
-                        )))
-                    } else {
-                        Err(Error)
-                    }
+                    let id = self.src_context_id().unwrap_or(0);
+                    Ok(UnresolvedAddress::WithContext((
+                        id as usize,
+                        AddressMode::FullyElided,
+                    )))
                 }
                 _ => Err(Error),
             }
@@ -806,34 +1081,25 @@ pub mod iphc {
                 (0, 0, 0b11) => Ok(UnresolvedAddress::WithoutContext(AddressMode::FullyElided)),
                 (0, 1, 0b00) => Ok(UnresolvedAddress::Reserved),
                 (0, 1, 0b01) => {
-                    if let Some(id) = self.dst_context_id() {
-                        Ok(UnresolvedAddress::WithContext((
-                            id as usize,
-                            AddressMode::InLine64bits(&data[start..][..8]),
-                        )))
-                    } else {
-                        Err(Error)
-                    }
+                    let id = self.dst_context_id().unwrap_or(0);
+                    Ok(UnresolvedAddress::WithContext((
+                        id as usize,
+                        AddressMode::InLine64bits(&data[start..][..8]),
+                    )))
                 }
                 (0, 1, 0b10) => {
-                    if let Some(id) = self.dst_context_id() {
-                        Ok(UnresolvedAddress::WithContext((
-                            id as usize,
-                            AddressMode::InLine16bits(&data[start..][..2]),
-                        )))
-                    } else {
-                        Err(Error)
-                    }
+                    let id = self.dst_context_id().unwrap_or(0);
+                    Ok(UnresolvedAddress::WithContext((
+                        id as usize,
+                        AddressMode::InLine16bits(&data[start..][..2]),
+                    )))
                 }
                 (0, 1, 0b11) => {
-                    if let Some(id) = self.dst_context_id() {
-                        Ok(UnresolvedAddress::WithContext((
-                            id as usize,
-                            AddressMode::FullyElided,
-                        )))
-                    } else {
-                        Err(Error)
-                    }
+                    let id = self.dst_context_id().unwrap_or(0);
+                    Ok(UnresolvedAddress::WithContext((
+                        id as usize,
+                        AddressMode::FullyElided,
+                    )))
                 }
                 (1, 0, 0b00) => Ok(UnresolvedAddress::WithoutContext(AddressMode::FullInline(
                     &data[start..][..16],
@@ -1025,23 +1291,26 @@ pub mod iphc {
             idx
         }
 
-        /// Set the Source Address based on the IPv6 address and the Link-Local address.
+        /// Set the Source Address based on the IPv6 address, the Link-Local address and the
+        /// context the prefix of the address can be taken from.
         ///
         /// **NOTE**: `idx` is the offset at which the Next Header needs to be written to.
         fn set_src_address(
             &mut self,
             src_addr: ipv6::Address,
             ll_src_addr: Option<LlAddress>,
+            context_id: Option<u8>,
             mut idx: usize,
         ) -> usize {
-            self.set_cid_field(0);
             self.set_sac_field(0);
             let src = src_addr.as_bytes();
             if src_addr == ipv6::Address::UNSPECIFIED {
                 self.set_sac_field(1);
                 self.set_sam_field(0b00);
-            } else if src_addr.is_link_local() {
-                // We have a link local address.
+            } else if src_addr.is_link_local() || context_id.is_some() {
+                // We have a link local address, or the prefix is in a context.
+                self.set_sac_field(context_id.is_some() as u8);
+
                 // The remainder of the address can be elided when the context contains
                 // a 802.15.4 short address or a 802.15.4 extended address which can be
                 // converted to a eui64 address.
@@ -1091,13 +1360,15 @@ pub mod iphc {
             idx
         }
 
-        /// Set the Destination Address based on the IPv6 address and the Link-Local address.
+        /// Set the Destination Address based on the IPv6 address, the Link-Local address and the
+        /// context the prefix of the address can be taken from.
         ///
         /// **NOTE**: `idx` is the offset at which the Next Header needs to be written to.
         fn set_dst_address(
             &mut self,
             dst_addr: ipv6::Address,
             ll_dst_addr: Option<LlAddress>,
+            context_id: Option<u8>,
             mut idx: usize,
         ) -> usize {
             self.set_dac_field(0);
@@ -1132,7 +1403,8 @@ pub mod iphc {
                     self.set_field(idx, dst);
                     idx += 16;
                 }
-            } else if dst_addr.is_link_local() {
+            } else if dst_addr.is_link_local() || context_id.is_some() {
+                self.set_dac_field(context_id.is_some() as u8);
                 let is_eui_64 = ll_dst_addr
                     .map(|addr| {
                         addr.as_eui_64()
@@ -1194,6 +1466,10 @@ pub mod iphc {
         pub ll_src_addr: Option<LlAddress>,
         pub dst_addr: ipv6::Address,
         pub ll_dst_addr: Option<LlAddress>,
+        /// The context holding the prefix of the source address, if it is compressed with one.
+        pub src_context_id: Option<u8>,
+        /// The context holding the prefix of the destination address, if it is compressed with one.
+        pub dst_context_id: Option<u8>,
         pub next_header: NextHeader,
         pub hop_limit: u8,
         // TODO(thvdveld): refactor the following fields into something else
@@ -1245,14 +1521,22 @@ pub mod iphc {
                 return Err(Error);
             }
 
-            let src_addr = packet.src_addr()?.resolve(ll_src_addr, addr_context)?;
-            let dst_addr = packet.dst_addr()?.resolve(ll_dst_addr, addr_context)?;
+            let context_id = |addr| match addr {
+                UnresolvedAddress::WithContext((_, AddressMode::Unspecified)) => None,
+                UnresolvedAddress::WithContext((id, _)) => Some(id as u8),
+                _ => None,
+            };
+
+            let src = packet.src_addr()?;
+            let dst = packet.dst_addr()?;
 
             Ok(Self {
-                src_addr,
+                src_addr: src.resolve(ll_src_addr, addr_context)?,
                 ll_src_addr,
-                dst_addr,
+                dst_addr: dst.resolve(ll_dst_addr, addr_context)?,
                 ll_dst_addr,
+                src_context_id: context_id(src),
+                dst_context_id: context_id(dst),
                 next_header: packet.next_header(),
                 hop_limit: packet.hop_limit(),
                 ecn: packet.ecn_field(),
@@ -1266,6 +1550,11 @@ pub mod iphc {
             let mut len = 0;
             len += 2; // The minimal header length
 
+            // The CID extension is only needed for contexts other than 0.
+            if self.cid() != 0 {
+                len += 1;
+            }
+
             len += match self.next_header {
                 NextHeader::Compressed => 0, // The next header is compressed (we don't need to inline what the next header is)
                 NextHeader::Uncompressed(_) => 1, // The next header field is inlined
@@ -1280,7 +1569,7 @@ pub mod iphc {
             // Add the length of the source address
             len += if self.src_addr == ipv6::Address::UNSPECIFIED {
                 0
-            } else if self.src_addr.is_link_local() {
+            } else if self.src_addr.is_link_local() || self.src_context_id.is_some() {
                 let src = self.src_addr.as_bytes();
                 let ll = [src[14], src[15]];
 
@@ -1320,7 +1609,7 @@ pub mod iphc {
                 } else {
                     16
                 }
-            } else if self.dst_addr.is_link_local() {
+            } else if self.dst_addr.is_link_local() || self.dst_context_id.is_some() {
                 let is_eui_64 = self
                     .ll_dst_addr
                     .map(|addr| {
@@ -1360,17 +1649,32 @@ pub mod iphc {
 
         /// Emit a high-level representation into a 6LoWPAN IPHC header.
         pub fn emit<T: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut Packet<T>) {
-            let idx = 2;
+            let mut idx = 2;
 
             packet.set_dispatch_field();
 
             // FIXME(thvdveld): we don't set anything from the traffic flow.
             packet.set_tf_field(0b11);
 
+            let cid = self.cid();
+            if cid != 0 {
+                packet.set_cid_field(1);
+                packet.set_field(idx, &[cid]);
+                idx += 1;
+            } else {
+                packet.set_cid_field(0);
+            }
+
             let idx = packet.set_next_header(self.next_header, idx);
             let idx = packet.set_hop_limit(self.hop_limit, idx);
-            let idx = packet.set_src_address(self.src_addr, self.ll_src_addr, idx);
-            packet.set_dst_address(self.dst_addr, self.ll_dst_addr, idx);
+            let idx =
+                packet.set_src_address(self.src_addr, self.ll_src_addr, self.src_context_id, idx);
+            packet.set_dst_address(self.dst_addr, self.ll_dst_addr, self.dst_context_id, idx);
+        }
+
+        /// Return the value of the CID extension, holding both context identifiers.
+        fn cid(&self) -> u8 {
+            (self.src_context_id.unwrap_or(0) << 4) | (self.dst_context_id.unwrap_or(0) & 0x0f)
         }
     }
 
@@ -1455,6 +1759,70 @@ pub mod iphc {
                 )))
             );
         }
+
+        #[test]
+        fn iphc_context() {
+            let contexts = [
+                AddressContext([0xfd, 0, 0, 0, 0, 0, 0, 1]),
+                AddressContext([0xfd, 0, 0, 0, 0, 0, 0, 2]),
+            ];
+            let ll_src_addr = LlAddress::Short([0x12, 0x34]);
+            let repr = Repr {
+                src_addr: ipv6::Address::new(0xfd00, 0, 0, 1, 0, 0xff, 0xfe00, 0x1234),
+                ll_src_addr: Some(ll_src_addr),
+                dst_addr: ipv6::Address::new(0xfd00, 0, 0, 2, 0, 0, 0, 0x42),
+                ll_dst_addr: None,
+                src_context_id: Some(0),
+                dst_context_id: Some(1),
+                next_header: NextHeader::Compressed,
+                hop_limit: 64,
+                ecn: None,
+                dscp: None,
+                flow_label: None,
+            };
+
+            // The source address is derived from context 0 and the link-layer address, the
+            // destination from context 1 and the inlined IID.
+            let mut bytes = [0u8; 11];
+            assert_eq!(repr.buffer_len(), bytes.len());
+            repr.emit(&mut Packet::new_unchecked(&mut bytes[..]));
+            assert_eq!(
+                bytes,
+                [0x7e, 0xf5, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42]
+            );
+
+            let packet = Packet::new_unchecked(&bytes[..]);
+            assert_eq!(
+                Repr::parse(&packet, Some(ll_src_addr), None, &contexts),
+                Ok(repr)
+            );
+
+            // Without the CID extension, both addresses come from context 0.
+            let repr = Repr {
+                dst_addr: ipv6::Address::new(0xfd00, 0, 0, 1, 0, 0, 0, 0x42),
+                dst_context_id: Some(0),
+                ..repr
+            };
+            let mut bytes = [0u8; 10];
+            assert_eq!(repr.buffer_len(), bytes.len());
+            repr.emit(&mut Packet::new_unchecked(&mut bytes[..]));
+            assert_eq!(
+                bytes,
+                [0x7e, 0x75, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x42]
+            );
+
+            let packet = Packet::new_unchecked(&bytes[..]);
+            assert_eq!(
+                Repr::parse(&packet, Some(ll_src_addr), None, &contexts),
+                Ok(repr)
+            );
+
+            // Unknown contexts cannot be resolved.
+            assert_eq!(
+                Repr::parse(&packet, Some(ll_src_addr), None, &[]),
+                Err(Error)
+            );
+        }
     }
 }
 
@@ -131,6 +131,9 @@ mod field {
     pub const OPT_WS: u8 = 0x03;
     pub const OPT_SACKPERM: u8 = 0x04;
     pub const OPT_SACKRNG: u8 = 0x05;
+    pub const OPT_TSTAMP: u8 = 0x08;
+    pub const OPT_MD5SIG: u8 = 0x13;
+    pub const OPT_AUTH: u8 = 0x1d;
 }
 
 pub const HEADER_LEN: usize = field::URGENT.end;
@@ -614,6 +617,30 @@ impl<T: AsRef<[u8]>> AsRef<[u8]> for Packet<T> {
     }
 }
 
+/// The contents of a TCP Authentication Option, as described in RFC 5925.
+///
+/// Only 96-bit MACs are supported, which is what both algorithms of RFC 5926 produce.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AuthOption {
+    /// Identifier of the key used to compute the MAC of this segment.
+    pub key_id: u8,
+    /// Identifier of the key that the sender would like to use for received segments.
+    pub rnext_key_id: u8,
+    /// Message authentication code of this segment.
+    pub mac: [u8; 12],
+}
+
+/// The contents of a TCP Timestamps option, as described in RFC 7323.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimestampOption {
+    /// Current value of the timestamp clock of the sender.
+    pub tsval: u32,
+    /// Most recent timestamp value received from the remote, if the ACK flag is set.
+    pub tsecr: u32,
+}
+
 /// A representation of a single TCP option.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -624,6 +651,9 @@ pub enum TcpOption<'a> {
     WindowScale(u8),
     SackPermitted,
     SackRange([Option<(u32, u32)>; 3]),
+    Timestamp(TimestampOption),
+    Md5Signature([u8; 16]),
+    Authentication(AuthOption),
     Unknown { kind: u8, data: &'a [u8] },
 }
 
@@ -687,6 +717,29 @@ impl<'a> TcpOption<'a> {
                         });
                         option = TcpOption::SackRange(sack_ranges);
                     }
+                    (field::OPT_TSTAMP, 10) => {
+                        option = TcpOption::Timestamp(TimestampOption {
+                            tsval: NetworkEndian::read_u32(&data[0..4]),
+                            tsecr: NetworkEndian::read_u32(&data[4..8]),
+                        })
+                    }
+                    (field::OPT_TSTAMP, _) => return Err(Error),
+                    (field::OPT_MD5SIG, 18) => {
+                        let mut signature = [0; 16];
+                        signature.copy_from_slice(data);
+                        option = TcpOption::Md5Signature(signature)
+                    }
+                    (field::OPT_MD5SIG, _) => return Err(Error),
+                    (field::OPT_AUTH, 16) => {
+                        let mut mac = [0; 12];
+                        mac.copy_from_slice(&data[2..]);
+                        option = TcpOption::Authentication(AuthOption {
+                            key_id: data[0],
+                            rnext_key_id: data[1],
+                            mac,
+                        })
+                    }
+                    (field::OPT_AUTH, n) if n < 4 => return Err(Error),
                     (_, _) => option = TcpOption::Unknown { kind, data },
                 }
             }
@@ -702,6 +755,9 @@ impl<'a> TcpOption<'a> {
             TcpOption::WindowScale(_) => 3,
             TcpOption::SackPermitted => 2,
             TcpOption::SackRange(s) => s.iter().filter(|s| s.is_some()).count() * 8 + 2,
+            TcpOption::Timestamp(_) => 10,
+            TcpOption::Md5Signature(_) => 18,
+            TcpOption::Authentication(_) => 16,
             TcpOption::Unknown { data, .. } => 2 + data.len(),
         }
     }
@@ -749,6 +805,21 @@ impl<'a> TcpOption<'a> {
                                 NetworkEndian::write_u32(&mut buffer[pos + 4..], second);
                             });
                     }
+                    &TcpOption::Timestamp(timestamp) => {
+                        buffer[0] = field::OPT_TSTAMP;
+                        NetworkEndian::write_u32(&mut buffer[2..], timestamp.tsval);
+                        NetworkEndian::write_u32(&mut buffer[6..], timestamp.tsecr);
+                    }
+                    &TcpOption::Md5Signature(signature) => {
+                        buffer[0] = field::OPT_MD5SIG;
+                        buffer[2..18].copy_from_slice(&signature)
+                    }
+                    &TcpOption::Authentication(auth) => {
+                        buffer[0] = field::OPT_AUTH;
+                        buffer[2] = auth.key_id;
+                        buffer[3] = auth.rnext_key_id;
+                        buffer[4..16].copy_from_slice(&auth.mac)
+                    }
                     &TcpOption::Unknown {
                         kind,
                         data: provided,
@@ -806,6 +877,12 @@ pub struct Repr<'a> {
     pub max_seg_size: Option<u16>,
     pub sack_permitted: bool,
     pub sack_ranges: [Option<(u32, u32)>; 3],
+    pub timestamp: Option<TimestampOption>,
+    pub md5_signature: Option<[u8; 16]>,
+    pub authentication: Option<AuthOption>,
+    /// The urgent pointer, if the URG flag is set. Per RFC 6093, it is the offset from
+    /// the sequence number of the octet following the urgent data.
+    pub urgent_pointer: Option<u16>,
     pub payload: &'a [u8],
 }
 
@@ -845,15 +922,19 @@ impl<'a> Repr<'a> {
             false => None,
         };
         // The PSH flag is ignored.
-        // The URG flag and the urgent field is ignored. This behavior is standards-compliant,
-        // however, most deployed systems (e.g. Linux) are *not* standards-compliant, and would
-        // cut the byte at the urgent pointer from the stream.
+        let urgent_pointer = match packet.urg() {
+            true => Some(packet.urgent_at()),
+            false => None,
+        };
 
         let mut max_seg_size = None;
         let mut window_scale = None;
         let mut options = packet.options();
         let mut sack_permitted = false;
         let mut sack_ranges = [None, None, None];
+        let mut timestamp = None;
+        let mut md5_signature = None;
+        let mut authentication = None;
         while !options.is_empty() {
             let (next_options, option) = TcpOption::parse(options)?;
             match option {
@@ -880,6 +961,9 @@ impl<'a> Repr<'a> {
                 }
                 TcpOption::SackPermitted => sack_permitted = true,
                 TcpOption::SackRange(slice) => sack_ranges = slice,
+                TcpOption::Timestamp(value) => timestamp = Some(value),
+                TcpOption::Md5Signature(signature) => md5_signature = Some(signature),
+                TcpOption::Authentication(auth) => authentication = Some(auth),
                 _ => (),
             }
             options = next_options;
@@ -896,6 +980,10 @@ impl<'a> Repr<'a> {
             max_seg_size: max_seg_size,
             sack_permitted: sack_permitted,
             sack_ranges: sack_ranges,
+            timestamp: timestamp,
+            md5_signature: md5_signature,
+            authentication: authentication,
+            urgent_pointer: urgent_pointer,
             payload: packet.payload(),
         })
     }
@@ -923,6 +1011,15 @@ impl<'a> Repr<'a> {
         if sack_range_len > 0 {
             length += sack_range_len + 2;
         }
+        if self.timestamp.is_some() {
+            length += 10;
+        }
+        if self.md5_signature.is_some() {
+            length += 18;
+        }
+        if self.authentication.is_some() {
+            length += 16;
+        }
         if length % 4 != 0 {
             length += 4 - length % 4;
         }
@@ -976,12 +1073,25 @@ impl<'a> Repr<'a> {
                 let tmp = options;
                 options = TcpOption::SackRange(self.sack_ranges).emit(tmp);
             }
+            if let Some(timestamp) = self.timestamp {
+                let tmp = options;
+                options = TcpOption::Timestamp(timestamp).emit(tmp);
+            }
+            if let Some(signature) = self.md5_signature {
+                let tmp = options;
+                options = TcpOption::Md5Signature(signature).emit(tmp);
+            }
+            if let Some(auth) = self.authentication {
+                let tmp = options;
+                options = TcpOption::Authentication(auth).emit(tmp);
+            }
 
             if !options.is_empty() {
                 TcpOption::EndOfList.emit(options);
             }
         }
-        packet.set_urgent_at(0);
+        packet.set_urg(self.urgent_pointer.is_some());
+        packet.set_urgent_at(self.urgent_pointer.unwrap_or(0));
         packet.payload_mut()[..self.payload.len()].copy_from_slice(self.payload);
 
         if checksum_caps.tcp.tx() {
@@ -1056,6 +1166,9 @@ impl<'a, T: AsRef<[u8]> + ?Sized> fmt::Display for Packet<&'a T> {
                 TcpOption::WindowScale(value) => write!(f, " ws={value}")?,
                 TcpOption::SackPermitted => write!(f, " sACK")?,
                 TcpOption::SackRange(slice) => write!(f, " sACKr{slice:?}")?, // debug print conveniently includes the []s
+                TcpOption::Timestamp(timestamp) => write!(f, " ts={}", timestamp.tsval)?,
+                TcpOption::Md5Signature(_) => write!(f, " md5")?,
+                TcpOption::Authentication(auth) => write!(f, " ao(key={})", auth.key_id)?,
                 TcpOption::Unknown { kind, .. } => write!(f, " opt({kind})")?,
             }
             options = next_options;
@@ -1083,6 +1196,9 @@ impl<'a> fmt::Display for Repr<'a> {
         if let Some(max_seg_size) = self.max_seg_size {
             write!(f, " mss={max_seg_size}")?;
         }
+        if let Some(urgent_pointer) = self.urgent_pointer {
+            write!(f, " urg={urgent_pointer}")?;
+        }
         Ok(())
     }
 }
@@ -1107,6 +1223,9 @@ impl<'a> defmt::Format for Repr<'a> {
         if let Some(max_seg_size) = self.max_seg_size {
             defmt::write!(fmt, " mss={}", max_seg_size);
         }
+        if let Some(urgent_pointer) = self.urgent_pointer {
+            defmt::write!(fmt, " urg={}", urgent_pointer);
+        }
     }
 }
 
@@ -1231,6 +1350,10 @@ mod test {
             max_seg_size: None,
             sack_permitted: false,
             sack_ranges: [None, None, None],
+            timestamp: None,
+            md5_signature: None,
+            authentication: None,
+            urgent_pointer: None,
             payload: &PAYLOAD_BYTES,
         }
     }
@@ -1264,6 +1387,37 @@ mod test {
         assert_eq!(&*packet.into_inner(), &SYN_PACKET_BYTES[..]);
     }
 
+    #[test]
+    #[cfg(feature = "proto-ipv4")]
+    fn test_urgent_pointer() {
+        let repr = Repr {
+            control: Control::None,
+            ack_number: Some(SeqNumber(0x89abcdefu32 as i32)),
+            urgent_pointer: Some(3),
+            ..packet_repr()
+        };
+        let mut bytes = vec![0; repr.buffer_len()];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        repr.emit(
+            &mut packet,
+            &SRC_ADDR.into(),
+            &DST_ADDR.into(),
+            &ChecksumCapabilities::default(),
+        );
+        assert!(packet.urg());
+        assert_eq!(packet.urgent_at(), 3);
+
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        let parsed = Repr::parse(
+            &packet,
+            &SRC_ADDR.into(),
+            &DST_ADDR.into(),
+            &ChecksumCapabilities::default(),
+        )
+        .unwrap();
+        assert_eq!(parsed, repr);
+    }
+
     #[test]
     #[cfg(feature = "proto-ipv4")]
     fn test_header_len_multiple_of_4() {
@@ -1310,6 +1464,34 @@ mod test {
                 0x00, 0x26, 0x25, 0xa0, 0x34, 0x3e, 0xfc, 0xea, 0x34, 0x40, 0xae, 0xf0
             ]
         );
+        assert_option_parses!(
+            TcpOption::Timestamp(TimestampOption {
+                tsval: 0x01020304,
+                tsecr: 0x05060708
+            }),
+            &[0x08, 0x0a, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]
+        );
+        assert_option_parses!(
+            TcpOption::Md5Signature([
+                0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd,
+                0xee, 0xff
+            ]),
+            &[
+                0x13, 0x12, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb,
+                0xcc, 0xdd, 0xee, 0xff
+            ]
+        );
+        assert_option_parses!(
+            TcpOption::Authentication(AuthOption {
+                key_id: 1,
+                rnext_key_id: 2,
+                mac: [0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb]
+            }),
+            &[
+                0x1d, 0x10, 0x01, 0x02, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99,
+                0xaa, 0xbb
+            ]
+        );
         assert_option_parses!(
             TcpOption::Unknown {
                 kind: 12,
@@ -1327,5 +1509,8 @@ mod test {
         assert_eq!(TcpOption::parse(&[0xc, 0x01]), Err(Error));
         assert_eq!(TcpOption::parse(&[0x2, 0x02]), Err(Error));
         assert_eq!(TcpOption::parse(&[0x3, 0x02]), Err(Error));
+        assert_eq!(TcpOption::parse(&[0x08, 0x04, 0x01, 0x02]), Err(Error));
+        assert_eq!(TcpOption::parse(&[0x13, 0x04, 0x01, 0x02]), Err(Error));
+        assert_eq!(TcpOption::parse(&[0x1d, 0x03, 0x01]), Err(Error));
     }
 }
@@ -0,0 +1,444 @@
+use byteorder::{ByteOrder, NetworkEndian};
+use core::fmt;
+
+use super::{Error, Result};
+use crate::wire::ip::checksum;
+use crate::wire::{IpAddress, IpProtocol};
+
+/// A read/write wrapper around a Lightweight User Datagram Protocol packet buffer.
+///
+/// UDP-Lite, described in [RFC 3828], has the same header as UDP, except that the
+/// length field is replaced with the number of octets covered by the checksum, so that
+/// damaged payloads can still be delivered. The length of the packet is that of the
+/// buffer, which must contain exactly one packet.
+///
+/// [RFC 3828]: https://tools.ietf.org/html/rfc3828
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Packet<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+mod field {
+    #![allow(non_snake_case)]
+
+    use crate::wire::field::*;
+
+    pub const SRC_PORT: Field = 0..2;
+    pub const DST_PORT: Field = 2..4;
+    pub const CHECKSUM_COVERAGE: Field = 4..6;
+    pub const CHECKSUM: Field = 6..8;
+
+    pub const PAYLOAD: Rest = CHECKSUM.end..;
+}
+
+pub const HEADER_LEN: usize = field::CHECKSUM.end;
+
+#[allow(clippy::len_without_is_empty)]
+impl<T: AsRef<[u8]>> Packet<T> {
+    /// Imbue a raw octet buffer with UDP-Lite packet structure.
+    pub const fn new_unchecked(buffer: T) -> Packet<T> {
+        Packet { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Packet<T>> {
+        let packet = Self::new_unchecked(buffer);
+        packet.check_len()?;
+        Ok(packet)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    /// Returns `Err(Error)` if the checksum coverage field is neither zero nor between
+    /// the header length and the buffer length.
+    ///
+    /// The result of this check is invalidated by calling [set_checksum_coverage].
+    ///
+    /// [set_checksum_coverage]: #method.set_checksum_coverage
+    pub fn check_len(&self) -> Result<()> {
+        let buffer_len = self.buffer.as_ref().len();
+        if buffer_len < HEADER_LEN {
+            return Err(Error);
+        }
+        let coverage = self.checksum_coverage() as usize;
+        if coverage != 0 && (coverage < HEADER_LEN || coverage > buffer_len) {
+            return Err(Error);
+        }
+        Ok(())
+    }
+
+    /// Consume the packet, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the length of the packet, which is that of the buffer.
+    pub fn len(&self) -> usize {
+        self.buffer.as_ref().len()
+    }
+
+    /// Return the source port field.
+    #[inline]
+    pub fn src_port(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::SRC_PORT])
+    }
+
+    /// Return the destination port field.
+    #[inline]
+    pub fn dst_port(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::DST_PORT])
+    }
+
+    /// Return the checksum coverage field.
+    ///
+    /// A value of zero means that the checksum covers the whole packet.
+    #[inline]
+    pub fn checksum_coverage(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::CHECKSUM_COVERAGE])
+    }
+
+    /// Return the number of octets covered by the checksum, including the header.
+    pub fn covered_len(&self) -> usize {
+        match self.checksum_coverage() {
+            0 => self.len(),
+            coverage => coverage as usize,
+        }
+    }
+
+    /// Return the checksum field.
+    #[inline]
+    pub fn checksum(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::CHECKSUM])
+    }
+
+    /// Validate the packet checksum.
+    ///
+    /// Unlike with UDP, the checksum cannot be omitted, and a zero checksum is invalid.
+    ///
+    /// # Panics
+    /// This function panics unless `src_addr` and `dst_addr` belong to the same family,
+    /// and that family is IPv4 or IPv6.
+    ///
+    /// # Fuzzing
+    /// This function always returns `true` when fuzzing.
+    #[allow(unexpected_cfgs)]
+    pub fn verify_checksum(&self, src_addr: &IpAddress, dst_addr: &IpAddress) -> bool {
+        if cfg!(fuzzing) {
+            return true;
+        }
+
+        if self.checksum() == 0 {
+            return false;
+        }
+
+        let data = self.buffer.as_ref();
+        checksum::combine(&[
+            checksum::pseudo_header(src_addr, dst_addr, IpProtocol::UdpLite, self.len() as u32),
+            checksum::data(&data[..self.covered_len()]),
+        ]) == !0
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Packet<&'a T> {
+    /// Return a pointer to the payload.
+    #[inline]
+    pub fn payload(&self) -> &'a [u8] {
+        let data = self.buffer.as_ref();
+        &data[field::PAYLOAD]
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+    /// Set the source port field.
+    #[inline]
+    pub fn set_src_port(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::SRC_PORT], value)
+    }
+
+    /// Set the destination port field.
+    #[inline]
+    pub fn set_dst_port(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::DST_PORT], value)
+    }
+
+    /// Set the checksum coverage field.
+    #[inline]
+    pub fn set_checksum_coverage(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::CHECKSUM_COVERAGE], value)
+    }
+
+    /// Set the checksum field.
+    #[inline]
+    pub fn set_checksum(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::CHECKSUM], value)
+    }
+
+    /// Compute and fill in the header checksum.
+    ///
+    /// # Panics
+    /// This function panics unless `src_addr` and `dst_addr` belong to the same family,
+    /// and that family is IPv4 or IPv6.
+    pub fn fill_checksum(&mut self, src_addr: &IpAddress, dst_addr: &IpAddress) {
+        self.set_checksum(0);
+        let checksum = {
+            let data = self.buffer.as_ref();
+            !checksum::combine(&[
+                checksum::pseudo_header(src_addr, dst_addr, IpProtocol::UdpLite, self.len() as u32),
+                checksum::data(&data[..self.covered_len()]),
+            ])
+        };
+        // As with UDP, a computed checksum of zero is transmitted as all-ones.
+        self.set_checksum(if checksum == 0 { 0xffff } else { checksum })
+    }
+
+    /// Return a mutable pointer to the payload.
+    #[inline]
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        let data = self.buffer.as_mut();
+        &mut data[field::PAYLOAD]
+    }
+}
+
+impl<T: AsRef<[u8]>> AsRef<[u8]> for Packet<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+}
+
+/// A high-level representation of a Lightweight User Datagram Protocol packet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Repr {
+    pub src_port: u16,
+    pub dst_port: u16,
+    /// The number of octets covered by the checksum, including the header,
+    /// or zero if it covers the whole packet.
+    pub checksum_coverage: u16,
+}
+
+impl Repr {
+    /// Parse a Lightweight User Datagram Protocol packet and return a high-level
+    /// representation.
+    ///
+    /// The checksum is always verified, since devices do not compute it.
+    pub fn parse<T>(packet: &Packet<&T>, src_addr: &IpAddress, dst_addr: &IpAddress) -> Result<Repr>
+    where
+        T: AsRef<[u8]> + ?Sized,
+    {
+        packet.check_len()?;
+        // Destination port cannot be omitted (but source port can be).
+        if packet.dst_port() == 0 {
+            return Err(Error);
+        }
+        if !packet.verify_checksum(src_addr, dst_addr) {
+            return Err(Error);
+        }
+
+        Ok(Repr {
+            src_port: packet.src_port(),
+            dst_port: packet.dst_port(),
+            checksum_coverage: packet.checksum_coverage(),
+        })
+    }
+
+    /// Return the length of the packet header that will be emitted from this high-level representation.
+    pub const fn header_len(&self) -> usize {
+        HEADER_LEN
+    }
+
+    /// Emit a high-level representation into a Lightweight User Datagram Protocol packet.
+    ///
+    /// The buffer must be exactly as long as the packet. A checksum coverage that is
+    /// shorter than the header, or longer than the packet, is replaced by the length
+    /// of the header or the whole packet respectively.
+    pub fn emit<T>(
+        &self,
+        packet: &mut Packet<&mut T>,
+        src_addr: &IpAddress,
+        dst_addr: &IpAddress,
+        emit_payload: impl FnOnce(&mut [u8]),
+    ) where
+        T: AsRef<[u8]> + AsMut<[u8]> + ?Sized,
+    {
+        let coverage = match self.checksum_coverage as usize {
+            0 => 0,
+            coverage if coverage >= packet.len() => 0,
+            coverage => coverage.max(HEADER_LEN) as u16,
+        };
+
+        packet.set_src_port(self.src_port);
+        packet.set_dst_port(self.dst_port);
+        packet.set_checksum_coverage(coverage);
+        emit_payload(packet.payload_mut());
+        packet.fill_checksum(src_addr, dst_addr)
+    }
+}
+
+impl<T: AsRef<[u8]> + ?Sized> fmt::Display for Packet<&T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "UDP-Lite src={} dst={} cscov={} len={}",
+            self.src_port(),
+            self.dst_port(),
+            self.checksum_coverage(),
+            self.payload().len()
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<T: AsRef<[u8]> + ?Sized> defmt::Format for Packet<&T> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "UDP-Lite src={} dst={} cscov={} len={}",
+            self.src_port(),
+            self.dst_port(),
+            self.checksum_coverage(),
+            self.payload().len()
+        );
+    }
+}
+
+impl fmt::Display for Repr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "UDP-Lite src={} dst={} cscov={}",
+            self.src_port, self.dst_port, self.checksum_coverage
+        )
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Repr {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(
+            fmt,
+            "UDP-Lite src={} dst={} cscov={}",
+            self.src_port,
+            self.dst_port,
+            self.checksum_coverage
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "proto-ipv4")]
+mod test {
+    use super::*;
+    use crate::wire::Ipv4Address;
+
+    const SRC_ADDR: Ipv4Address = Ipv4Address([192, 168, 1, 1]);
+    const DST_ADDR: Ipv4Address = Ipv4Address([192, 168, 1, 2]);
+
+    static PACKET_BYTES: [u8; 12] = [
+        0xbf, 0x00, 0x00, 0x35, 0x00, 0x08, 0xbc, 0xd9, 0xaa, 0x00, 0x00, 0xff,
+    ];
+
+    static PAYLOAD_BYTES: [u8; 4] = [0xaa, 0x00, 0x00, 0xff];
+
+    fn packet_repr() -> Repr {
+        Repr {
+            src_port: 48896,
+            dst_port: 53,
+            checksum_coverage: 8,
+        }
+    }
+
+    #[test]
+    fn test_deconstruct() {
+        let packet = Packet::new_checked(&PACKET_BYTES[..]).unwrap();
+        assert_eq!(packet.src_port(), 48896);
+        assert_eq!(packet.dst_port(), 53);
+        assert_eq!(packet.checksum_coverage(), 8);
+        assert_eq!(packet.checksum(), 0xbcd9);
+        assert_eq!(packet.payload(), &PAYLOAD_BYTES[..]);
+        assert!(packet.verify_checksum(&SRC_ADDR.into(), &DST_ADDR.into()));
+    }
+
+    #[test]
+    fn test_partial_coverage() {
+        // Damage outside of the covered octets goes undetected...
+        let mut bytes = PACKET_BYTES;
+        bytes[9] = 0x55;
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert!(packet.verify_checksum(&SRC_ADDR.into(), &DST_ADDR.into()));
+
+        // ... but not once the whole packet is covered.
+        let mut bytes = PACKET_BYTES;
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        packet.set_checksum_coverage(0);
+        packet.fill_checksum(&SRC_ADDR.into(), &DST_ADDR.into());
+        assert_eq!(packet.checksum(), 0x11e2);
+        packet.payload_mut()[1] = 0x55;
+        assert!(!packet.verify_checksum(&SRC_ADDR.into(), &DST_ADDR.into()));
+    }
+
+    #[test]
+    fn test_invalid_coverage() {
+        let mut bytes = PACKET_BYTES;
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        packet.set_checksum_coverage(7);
+        assert_eq!(packet.check_len(), Err(Error));
+        packet.set_checksum_coverage(13);
+        assert_eq!(packet.check_len(), Err(Error));
+        packet.set_checksum_coverage(12);
+        assert_eq!(packet.check_len(), Ok(()));
+    }
+
+    #[test]
+    fn test_zero_checksum() {
+        let mut bytes = PACKET_BYTES;
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        packet.set_checksum(0);
+        assert!(!packet.verify_checksum(&SRC_ADDR.into(), &DST_ADDR.into()));
+    }
+
+    #[test]
+    fn test_parse() {
+        let packet = Packet::new_unchecked(&PACKET_BYTES[..]);
+        let repr = Repr::parse(&packet, &SRC_ADDR.into(), &DST_ADDR.into()).unwrap();
+        assert_eq!(repr, packet_repr());
+    }
+
+    #[test]
+    fn test_emit() {
+        let mut bytes = [0xa5; 12];
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        packet_repr().emit(&mut packet, &SRC_ADDR.into(), &DST_ADDR.into(), |payload| {
+            payload.copy_from_slice(&PAYLOAD_BYTES)
+        });
+        assert_eq!(&bytes[..], &PACKET_BYTES[..]);
+    }
+
+    #[test]
+    fn test_emit_clamp_coverage() {
+        let mut bytes = [0xa5; 12];
+        let mut packet = Packet::new_unchecked(&mut bytes[..]);
+        let repr = Repr {
+            checksum_coverage: 4,
+            ..packet_repr()
+        };
+        repr.emit(&mut packet, &SRC_ADDR.into(), &DST_ADDR.into(), |_| ());
+        assert_eq!(packet.checksum_coverage(), 8);
+
+        let repr = Repr {
+            checksum_coverage: 100,
+            ..packet_repr()
+        };
+        repr.emit(&mut packet, &SRC_ADDR.into(), &DST_ADDR.into(), |_| ());
+        assert_eq!(packet.checksum_coverage(), 0);
+    }
+}
@@ -0,0 +1,284 @@
+use byteorder::{ByteOrder, NetworkEndian};
+use core::fmt;
+
+use super::{Error, EthernetProtocol as EtherType, Result};
+
+/// A read/write wrapper around an IEEE 802.1Q VLAN tag buffer.
+///
+/// The buffer starts right after the Ethernet II header of a frame whose
+/// EtherType is [EtherType::Vlan], i.e. with the tag control information,
+/// followed by the EtherType of the encapsulated payload.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Packet<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+mod field {
+    use crate::wire::field::*;
+
+    pub const TCI: Field = 0..2;
+    pub const ETHERTYPE: Field = 2..4;
+    pub const PAYLOAD: Rest = 4..;
+}
+
+/// The 802.1Q tag length
+pub const HEADER_LEN: usize = field::PAYLOAD.start;
+
+/// The largest valid VLAN identifier; 0xfff is reserved.
+pub const MAX_VID: u16 = 0xffe;
+
+impl<T: AsRef<[u8]>> Packet<T> {
+    /// Imbue a raw octet buffer with 802.1Q tag structure.
+    pub const fn new_unchecked(buffer: T) -> Packet<T> {
+        Packet { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Packet<T>> {
+        let packet = Self::new_unchecked(buffer);
+        packet.check_len()?;
+        Ok(packet)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    pub fn check_len(&self) -> Result<()> {
+        let len = self.buffer.as_ref().len();
+        if len < HEADER_LEN {
+            Err(Error)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Consumes the packet, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the length of a tag.
+    pub const fn header_len() -> usize {
+        HEADER_LEN
+    }
+
+    /// Return the priority code point field.
+    #[inline]
+    pub fn pcp(&self) -> u8 {
+        let data = self.buffer.as_ref();
+        data[field::TCI.start] >> 5
+    }
+
+    /// Return the drop eligible indicator flag.
+    #[inline]
+    pub fn dei(&self) -> bool {
+        let data = self.buffer.as_ref();
+        data[field::TCI.start] & 0x10 != 0
+    }
+
+    /// Return the VLAN identifier field.
+    #[inline]
+    pub fn vid(&self) -> u16 {
+        let data = self.buffer.as_ref();
+        NetworkEndian::read_u16(&data[field::TCI]) & 0x0fff
+    }
+
+    /// Return the EtherType field of the encapsulated payload.
+    #[inline]
+    pub fn ethertype(&self) -> EtherType {
+        let data = self.buffer.as_ref();
+        let raw = NetworkEndian::read_u16(&data[field::ETHERTYPE]);
+        EtherType::from(raw)
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Packet<&'a T> {
+    /// Return a pointer to the payload.
+    #[inline]
+    pub fn payload(&self) -> &'a [u8] {
+        let data = self.buffer.as_ref();
+        &data[field::PAYLOAD]
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+    /// Set the priority code point field.
+    #[inline]
+    pub fn set_pcp(&mut self, value: u8) {
+        let data = self.buffer.as_mut();
+        data[field::TCI.start] = (data[field::TCI.start] & 0x1f) | (value << 5);
+    }
+
+    /// Set the drop eligible indicator flag.
+    #[inline]
+    pub fn set_dei(&mut self, value: bool) {
+        let data = self.buffer.as_mut();
+        let raw = data[field::TCI.start];
+        data[field::TCI.start] = if value { raw | 0x10 } else { raw & !0x10 };
+    }
+
+    /// Set the VLAN identifier field.
+    #[inline]
+    pub fn set_vid(&mut self, value: u16) {
+        let data = self.buffer.as_mut();
+        let raw = NetworkEndian::read_u16(&data[field::TCI]);
+        let raw = (raw & !0x0fff) | (value & 0x0fff);
+        NetworkEndian::write_u16(&mut data[field::TCI], raw)
+    }
+
+    /// Set the EtherType field of the encapsulated payload.
+    #[inline]
+    pub fn set_ethertype(&mut self, value: EtherType) {
+        let data = self.buffer.as_mut();
+        NetworkEndian::write_u16(&mut data[field::ETHERTYPE], value.into())
+    }
+
+    /// Return a mutable pointer to the payload.
+    #[inline]
+    pub fn payload_mut(&mut self) -> &mut [u8] {
+        let data = self.buffer.as_mut();
+        &mut data[field::PAYLOAD]
+    }
+}
+
+impl<T: AsRef<[u8]>> AsRef<[u8]> for Packet<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+}
+
+impl<T: AsRef<[u8]>> fmt::Display for Packet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "802.1Q vid={} pcp={} type={}",
+            self.vid(),
+            self.pcp(),
+            self.ethertype()
+        )?;
+        if self.dei() {
+            write!(f, " dei")?;
+        }
+        Ok(())
+    }
+}
+
+use crate::wire::pretty_print::{PrettyIndent, PrettyPrint};
+
+impl<T: AsRef<[u8]>> PrettyPrint for Packet<T> {
+    fn pretty_print(
+        buffer: &dyn AsRef<[u8]>,
+        f: &mut fmt::Formatter,
+        indent: &mut PrettyIndent,
+    ) -> fmt::Result {
+        let packet = match Packet::new_checked(buffer) {
+            Err(err) => return write!(f, "{indent}({err})"),
+            Ok(packet) => packet,
+        };
+        write!(f, "{indent}{packet}")?;
+        super::ethernet::pretty_print_payload(packet.ethertype(), packet.payload(), f, indent)
+    }
+}
+
+/// A high-level representation of an IEEE 802.1Q VLAN tag.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Repr {
+    pub pcp: u8,
+    pub dei: bool,
+    pub vid: u16,
+    pub ethertype: EtherType,
+}
+
+impl Repr {
+    /// Parse an 802.1Q tag and return a high-level representation.
+    pub fn parse<T: AsRef<[u8]> + ?Sized>(packet: &Packet<&T>) -> Result<Repr> {
+        packet.check_len()?;
+        if packet.vid() > MAX_VID {
+            return Err(Error);
+        }
+        Ok(Repr {
+            pcp: packet.pcp(),
+            dei: packet.dei(),
+            vid: packet.vid(),
+            ethertype: packet.ethertype(),
+        })
+    }
+
+    /// Return the length of a tag that will be emitted from this high-level representation.
+    pub const fn buffer_len(&self) -> usize {
+        HEADER_LEN
+    }
+
+    /// Emit a high-level representation into an 802.1Q tag.
+    pub fn emit<T: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut Packet<T>) {
+        packet.set_pcp(self.pcp);
+        packet.set_dei(self.dei);
+        packet.set_vid(self.vid);
+        packet.set_ethertype(self.ethertype);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static PACKET_BYTES: [u8; 8] = [0xa0, 0x2a, 0x08, 0x00, 0xaa, 0x00, 0x00, 0xff];
+
+    static PAYLOAD_BYTES: [u8; 4] = [0xaa, 0x00, 0x00, 0xff];
+
+    fn packet_repr() -> Repr {
+        Repr {
+            pcp: 5,
+            dei: false,
+            vid: 42,
+            ethertype: EtherType::Ipv4,
+        }
+    }
+
+    #[test]
+    fn test_deconstruct() {
+        let packet = Packet::new_checked(&PACKET_BYTES[..]).unwrap();
+        assert_eq!(packet.pcp(), 5);
+        assert!(!packet.dei());
+        assert_eq!(packet.vid(), 42);
+        assert_eq!(packet.ethertype(), EtherType::Ipv4);
+        assert_eq!(packet.payload(), &PAYLOAD_BYTES[..]);
+    }
+
+    #[test]
+    fn test_construct() {
+        let mut bytes = vec![0xff; 8];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        packet.set_pcp(5);
+        packet.set_dei(true);
+        packet.set_dei(false);
+        packet.set_vid(42);
+        packet.set_ethertype(EtherType::Ipv4);
+        packet.payload_mut().copy_from_slice(&PAYLOAD_BYTES[..]);
+        assert_eq!(&packet.into_inner()[..], &PACKET_BYTES[..]);
+    }
+
+    #[test]
+    fn test_parse() {
+        let packet = Packet::new_checked(&PACKET_BYTES[..]).unwrap();
+        assert_eq!(Repr::parse(&packet), Ok(packet_repr()));
+        assert!(Packet::new_checked(&PACKET_BYTES[..3]).is_err());
+
+        let bytes = [0x0f, 0xff, 0x08, 0x00];
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(Repr::parse(&packet), Err(Error));
+    }
+
+    #[test]
+    fn test_emit() {
+        let mut bytes = vec![0; 8];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        packet_repr().emit(&mut packet);
+        packet.payload_mut().copy_from_slice(&PAYLOAD_BYTES[..]);
+        assert_eq!(&packet.into_inner()[..], &PACKET_BYTES[..]);
+    }
+}
@@ -0,0 +1,253 @@
+use core::fmt;
+
+use super::{Error, EthernetAddress, Result};
+
+/// A read/write wrapper around a Wake-on-LAN magic packet buffer.
+///
+/// A magic packet is made of six `0xff` octets, followed by sixteen repetitions of the
+/// hardware address of the host to wake up, and optionally by a SecureOn password of four
+/// or six octets. It is sent either in an Ethernet frame of EtherType
+/// [WakeOnLan](super::EthernetProtocol::WakeOnLan), or in a UDP datagram, usually
+/// broadcast to [PORT].
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Packet<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+mod field {
+    use crate::wire::field::*;
+
+    pub const SYNC: Field = 0..6;
+    pub const TARGET: Field = 6..102;
+    pub const PASSWORD: Rest = 102..;
+}
+
+/// The length of a magic packet without a password.
+pub const HEADER_LEN: usize = field::PASSWORD.start;
+
+/// The UDP port magic packets are usually sent to.
+pub const PORT: u16 = 9;
+
+impl<T: AsRef<[u8]>> Packet<T> {
+    /// Imbue a raw octet buffer with magic packet structure.
+    pub const fn new_unchecked(buffer: T) -> Packet<T> {
+        Packet { buffer }
+    }
+
+    /// Shorthand for a combination of [new_unchecked] and [check_len].
+    ///
+    /// [new_unchecked]: #method.new_unchecked
+    /// [check_len]: #method.check_len
+    pub fn new_checked(buffer: T) -> Result<Packet<T>> {
+        let packet = Self::new_unchecked(buffer);
+        packet.check_len()?;
+        Ok(packet)
+    }
+
+    /// Ensure that no accessor method will panic if called.
+    /// Returns `Err(Error)` if the buffer is too short.
+    pub fn check_len(&self) -> Result<()> {
+        let len = self.buffer.as_ref().len();
+        if len < HEADER_LEN {
+            Err(Error)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Consumes the packet, returning the underlying buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return whether the packet starts with the synchronization stream, followed by the
+    /// same hardware address sixteen times.
+    pub fn is_magic(&self) -> bool {
+        let data = self.buffer.as_ref();
+        let target = &data[field::TARGET];
+        data[field::SYNC].iter().all(|&octet| octet == 0xff)
+            && target.chunks(6).all(|chunk| chunk == &target[..6])
+    }
+
+    /// Return the hardware address of the host to wake up.
+    #[inline]
+    pub fn target_addr(&self) -> EthernetAddress {
+        let data = self.buffer.as_ref();
+        EthernetAddress::from_bytes(&data[field::TARGET][..6])
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Packet<&'a T> {
+    /// Return a pointer to the SecureOn password, which is empty if there is none.
+    #[inline]
+    pub fn password(&self) -> &'a [u8] {
+        let data = self.buffer.as_ref();
+        &data[field::PASSWORD]
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Packet<T> {
+    /// Set the synchronization stream, and the hardware address of the host to wake up.
+    #[inline]
+    pub fn set_target_addr(&mut self, value: EthernetAddress) {
+        let data = self.buffer.as_mut();
+        data[field::SYNC].fill(0xff);
+        for chunk in data[field::TARGET].chunks_mut(6) {
+            chunk.copy_from_slice(value.as_bytes());
+        }
+    }
+
+    /// Return a mutable pointer to the SecureOn password.
+    #[inline]
+    pub fn password_mut(&mut self) -> &mut [u8] {
+        let data = self.buffer.as_mut();
+        &mut data[field::PASSWORD]
+    }
+}
+
+impl<T: AsRef<[u8]>> AsRef<[u8]> for Packet<T> {
+    fn as_ref(&self) -> &[u8] {
+        self.buffer.as_ref()
+    }
+}
+
+impl<T: AsRef<[u8]>> fmt::Display for Packet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match Repr::parse(&Packet::new_unchecked(self.buffer.as_ref())) {
+            Ok(repr) => write!(f, "{repr}"),
+            Err(err) => write!(f, "WoL ({err})"),
+        }
+    }
+}
+
+use crate::wire::pretty_print::{PrettyIndent, PrettyPrint};
+
+impl<T: AsRef<[u8]>> PrettyPrint for Packet<T> {
+    fn pretty_print(
+        buffer: &dyn AsRef<[u8]>,
+        f: &mut fmt::Formatter,
+        indent: &mut PrettyIndent,
+    ) -> fmt::Result {
+        match Packet::new_checked(buffer) {
+            Err(err) => write!(f, "{indent}({err})"),
+            Ok(packet) => write!(f, "{indent}{packet}"),
+        }
+    }
+}
+
+/// A high-level representation of a Wake-on-LAN magic packet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Repr<'a> {
+    pub target_addr: EthernetAddress,
+    /// The SecureOn password, of four or six octets, or empty.
+    pub password: &'a [u8],
+}
+
+impl<'a> Repr<'a> {
+    /// Parse a magic packet and return a high-level representation.
+    pub fn parse<T: AsRef<[u8]> + ?Sized>(packet: &Packet<&'a T>) -> Result<Repr<'a>> {
+        packet.check_len()?;
+        if !packet.is_magic() || !matches!(packet.password().len(), 0 | 4 | 6) {
+            return Err(Error);
+        }
+        Ok(Repr {
+            target_addr: packet.target_addr(),
+            password: packet.password(),
+        })
+    }
+
+    /// Return the length of a packet that will be emitted from this high-level representation.
+    pub const fn buffer_len(&self) -> usize {
+        HEADER_LEN + self.password.len()
+    }
+
+    /// Emit a high-level representation into a magic packet.
+    pub fn emit<T: AsRef<[u8]> + AsMut<[u8]>>(&self, packet: &mut Packet<T>) {
+        packet.set_target_addr(self.target_addr);
+        packet.password_mut().copy_from_slice(self.password);
+    }
+}
+
+impl<'a> fmt::Display for Repr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "WoL target={}", self.target_addr)?;
+        if !self.password.is_empty() {
+            write!(f, " password_len={}", self.password.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TARGET: EthernetAddress = EthernetAddress([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+
+    static PASSWORD: [u8; 4] = [0xc0, 0xa8, 0x01, 0x01];
+
+    fn packet_bytes() -> Vec<u8> {
+        let mut bytes = vec![0xff; 6];
+        for _ in 0..16 {
+            bytes.extend_from_slice(TARGET.as_bytes());
+        }
+        bytes.extend_from_slice(&PASSWORD);
+        bytes
+    }
+
+    fn packet_repr() -> Repr<'static> {
+        Repr {
+            target_addr: TARGET,
+            password: &PASSWORD,
+        }
+    }
+
+    #[test]
+    fn test_deconstruct() {
+        let bytes = packet_bytes();
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert!(packet.is_magic());
+        assert_eq!(packet.target_addr(), TARGET);
+        assert_eq!(packet.password(), &PASSWORD[..]);
+    }
+
+    #[test]
+    fn test_construct() {
+        let mut bytes = vec![0xa5; HEADER_LEN + 4];
+        let mut packet = Packet::new_unchecked(&mut bytes);
+        packet.set_target_addr(TARGET);
+        packet.password_mut().copy_from_slice(&PASSWORD);
+        assert_eq!(packet.into_inner(), &packet_bytes());
+    }
+
+    #[test]
+    fn test_parse() {
+        let bytes = packet_bytes();
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert_eq!(Repr::parse(&packet), Ok(packet_repr()));
+        let packet = Packet::new_checked(&bytes[..HEADER_LEN]).unwrap();
+        assert_eq!(Repr::parse(&packet).unwrap().password, &[]);
+        assert!(Packet::new_checked(&bytes[..HEADER_LEN - 1]).is_err());
+
+        // The password is four or six octets long.
+        let packet = Packet::new_checked(&bytes[..HEADER_LEN + 2]).unwrap();
+        assert_eq!(Repr::parse(&packet), Err(Error));
+
+        // The hardware address is repeated sixteen times.
+        let mut bytes = packet_bytes();
+        bytes[HEADER_LEN - 1] ^= 1;
+        let packet = Packet::new_checked(&bytes[..]).unwrap();
+        assert!(!packet.is_magic());
+        assert_eq!(Repr::parse(&packet), Err(Error));
+    }
+
+    #[test]
+    fn test_emit() {
+        let repr = packet_repr();
+        let mut bytes = vec![0; repr.buffer_len()];
+        repr.emit(&mut Packet::new_unchecked(&mut bytes));
+        assert_eq!(bytes, packet_bytes());
+    }
+}